@@ -0,0 +1,76 @@
+//! Pipeline throughput benchmarks
+//!
+//! Sends synthetic tasks through an `AgentPipeline` with a no-op LLM and
+//! measures tasks per second across queue sizes, worker counts and tool
+//! latencies. After the criterion groups a markdown table with latency
+//! percentiles, channel wait and allocations per task is printed, for
+//! pasting into PRs.
+//!
+//! Run with `cargo bench --bench pipeline_throughput`.
+
+use agent2389::testing::loadgen::{markdown_table, run_load, CountingAllocator, LoadConfig};
+use criterion::{criterion_group, BenchmarkId, Criterion, Throughput};
+use std::time::Duration;
+use tokio::runtime::Runtime;
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+const TASKS: usize = 200;
+
+fn configurations() -> Vec<LoadConfig> {
+    let mut configs = Vec::new();
+    for queue_size in [1, 16, 256] {
+        configs.push(LoadConfig::new(TASKS).queue_size(queue_size));
+    }
+    for workers in [1, 4, 16] {
+        for tool_latency in [Duration::from_millis(1), Duration::from_millis(10)] {
+            configs.push(
+                LoadConfig::new(TASKS)
+                    .queue_size(16)
+                    .workers(workers)
+                    .tool_latency(tool_latency),
+            );
+        }
+    }
+    configs
+}
+
+fn runtime() -> Runtime {
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .expect("tokio runtime")
+}
+
+fn pipeline_throughput(c: &mut Criterion) {
+    let runtime = runtime();
+    let mut group = c.benchmark_group("pipeline_throughput");
+    group.throughput(Throughput::Elements(TASKS as u64));
+    group.sample_size(10);
+    for config in configurations() {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(config.label()),
+            &config,
+            |b, config| b.iter(|| runtime.block_on(run_load(config)).expect("load run failed")),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, pipeline_throughput);
+
+fn main() {
+    benches();
+    Criterion::default().configure_from_args().final_summary();
+
+    // `cargo test --benches` only checks the benchmarks run
+    if std::env::args().any(|arg| arg == "--bench") {
+        let runtime = runtime();
+        let reports: Vec<_> = configurations()
+            .iter()
+            .map(|config| runtime.block_on(run_load(config)).expect("load run failed"))
+            .collect();
+        println!("\n{}", markdown_table(&reports));
+    }
+}
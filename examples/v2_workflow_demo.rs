@@ -116,8 +116,18 @@ impl WorkflowType {
                     original_query: "Create an article on Rust async programming".to_string(),
                     steps_completed: vec![],
                     iteration_count: 0,
+                    started_at: None,
+                    budget_secs: None,
+                    state: None,
+                    state_order: Vec::new(),
                 }),
                 routing_trace: None,
+                deadline: None,
+                correlation_id: None,
+                parent_task_id: None,
+                published_at: None,
+                traceparent: None,
+                sender_id: None,
             },
             Self::Iterative => TaskEnvelopeV2 {
                 task_id: Uuid::new_v4(),
@@ -131,8 +141,18 @@ impl WorkflowType {
                     original_query: "Create a high-quality technical article".to_string(),
                     steps_completed: vec![],
                     iteration_count: 0,
+                    started_at: None,
+                    budget_secs: None,
+                    state: None,
+                    state_order: Vec::new(),
                 }),
                 routing_trace: None,
+                deadline: None,
+                correlation_id: None,
+                parent_task_id: None,
+                published_at: None,
+                traceparent: None,
+                sender_id: None,
             },
             Self::PingPong => TaskEnvelopeV2 {
                 task_id: Uuid::new_v4(),
@@ -146,8 +166,18 @@ impl WorkflowType {
                     original_query: "Test max iterations enforcement".to_string(),
                     steps_completed: vec![],
                     iteration_count: 0,
+                    started_at: None,
+                    budget_secs: None,
+                    state: None,
+                    state_order: Vec::new(),
                 }),
                 routing_trace: None,
+                deadline: None,
+                correlation_id: None,
+                parent_task_id: None,
+                published_at: None,
+                traceparent: None,
+                sender_id: None,
             },
         }
     }
@@ -3,6 +3,8 @@
 //! Provides dynamic agent discovery and capability matching through MQTT status messages.
 //! Implements a thread-safe registry with TTL-based cleanup and load-aware agent selection.
 
+use crate::observability::metrics::metrics;
+use crate::protocol::AgentManifest;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -106,6 +108,11 @@ impl AgentInfo {
 pub struct AgentRegistry {
     /// Map of agent_id to AgentInfo
     agents: Arc<RwLock<HashMap<String, AgentInfo>>>,
+    /// Map of agent_id to its latest capability manifest
+    ///
+    /// Manifests are retained messages published once per tool set, so they are
+    /// kept independently of the status TTL.
+    manifests: Arc<RwLock<HashMap<String, AgentManifest>>>,
     /// Last cleanup time for TTL enforcement
     last_cleanup: Arc<RwLock<SystemTime>>,
 }
@@ -121,6 +128,7 @@ impl AgentRegistry {
     pub fn new() -> Self {
         Self {
             agents: Arc::new(RwLock::new(HashMap::new())),
+            manifests: Arc::new(RwLock::new(HashMap::new())),
             last_cleanup: Arc::new(RwLock::new(SystemTime::now())),
         }
     }
@@ -134,6 +142,7 @@ impl AgentRegistry {
             let mut agents = self.agents.write().unwrap();
             let is_new = !agents.contains_key(&agent_id);
             agents.insert(agent_id.clone(), agent_info);
+            metrics().set_registry_agents(agents.len());
 
             if is_new {
                 info!("Registered new agent: {}", agent_id);
@@ -152,6 +161,23 @@ impl AgentRegistry {
         agents.get(agent_id).cloned()
     }
 
+    /// Register or replace an agent's capability manifest
+    pub fn register_manifest(&self, manifest: AgentManifest) {
+        debug!(
+            "Updated manifest for agent {} ({} tools)",
+            manifest.agent_id,
+            manifest.tools.len()
+        );
+        let mut manifests = self.manifests.write().unwrap();
+        manifests.insert(manifest.agent_id.clone(), manifest);
+    }
+
+    /// Get an agent's capability manifest by ID
+    pub fn get_manifest(&self, agent_id: &str) -> Option<AgentManifest> {
+        let manifests = self.manifests.read().unwrap();
+        manifests.get(agent_id).cloned()
+    }
+
     /// Get all healthy agents
     pub fn get_healthy_agents(&self) -> Vec<AgentInfo> {
         let agents = self.agents.read().unwrap();
@@ -230,33 +256,58 @@ impl AgentRegistry {
             return;
         }
 
-        // Perform cleanup with minimal lock time
-        let (initial_count, removed_count) = {
+        self.remove_expired_agents();
+    }
+
+    /// Remove expired agents now, without the cleanup rate limit
+    ///
+    /// Returns how many agents were evicted.
+    pub fn remove_expired_agents(&self) -> usize {
+        let (remaining, removed_count) = {
             let mut agents = self.agents.write().unwrap();
             let initial_count = agents.len();
-            let mut removed_count = 0;
 
             agents.retain(|agent_id, agent_info| {
                 if agent_info.is_expired() {
                     debug!("Removing expired agent: {}", agent_id);
-                    removed_count += 1;
                     false
                 } else {
                     true
                 }
             });
 
-            (initial_count, removed_count)
+            (agents.len(), initial_count - agents.len())
         }; // Release write lock on agents immediately
 
+        metrics().set_registry_agents(remaining);
         if removed_count > 0 {
+            metrics().registry_evictions("expired", removed_count);
             info!(
                 "Cleaned up {} expired agents ({} -> {})",
                 removed_count,
-                initial_count,
-                initial_count - removed_count
+                remaining + removed_count,
+                remaining
             );
         }
+        removed_count
+    }
+
+    /// Remove an agent that announced it is unavailable
+    ///
+    /// Its manifest is kept, as manifests outlive status updates. Returns
+    /// whether the agent was registered.
+    pub fn remove_agent(&self, agent_id: &str) -> bool {
+        let (removed, remaining) = {
+            let mut agents = self.agents.write().unwrap();
+            (agents.remove(agent_id).is_some(), agents.len())
+        };
+
+        metrics().set_registry_agents(remaining);
+        if removed {
+            metrics().registry_evictions("unavailable", 1);
+            info!("Removed unavailable agent: {}", agent_id);
+        }
+        removed
     }
 
     /// Remove all agents (for testing)
@@ -264,6 +315,7 @@ impl AgentRegistry {
     pub fn clear(&self) {
         let mut agents = self.agents.write().unwrap();
         agents.clear();
+        self.manifests.write().unwrap().clear();
     }
 
     /// Register agent without refreshing timestamp (for testing TTL expiration only)
@@ -285,38 +337,20 @@ impl AgentRegistry {
     /// which includes proper rate limiting.
     #[doc(hidden)]
     pub fn force_cleanup_for_test(&self) {
-        let (initial_count, removed_count) = {
-            let mut agents = self.agents.write().unwrap();
-            let initial_count = agents.len();
-            let mut removed_count = 0;
-
-            agents.retain(|agent_id, agent_info| {
-                if agent_info.is_expired() {
-                    debug!("Removing expired agent: {}", agent_id);
-                    removed_count += 1;
-                    false
-                } else {
-                    true
-                }
-            });
-
-            (initial_count, removed_count)
-        };
-
-        if removed_count > 0 {
-            info!(
-                "Cleaned up {} expired agents ({} -> {})",
-                removed_count,
-                initial_count,
-                initial_count - removed_count
-            );
-        }
+        self.remove_expired_agents();
     }
 
-    /// Get all agent IDs (for debugging)
+    /// Get the IDs of all agents that have not expired
+    ///
+    /// Expired agents may linger until the next cleanup, but are never
+    /// offered to routers.
     pub fn get_all_agent_ids(&self) -> Vec<String> {
         let agents = self.agents.read().unwrap();
-        agents.keys().cloned().collect()
+        agents
+            .values()
+            .filter(|agent| !agent.is_expired())
+            .map(|agent| agent.agent_id.clone())
+            .collect()
     }
 }
 
@@ -483,6 +517,44 @@ mod tests {
         assert_eq!(best_agent.agent_id, "healthy");
     }
 
+    #[test]
+    fn test_expired_agents_hidden_and_evicted() {
+        let registry = AgentRegistry::new();
+        let mut stale = AgentInfo::new("stale".to_string(), "ok".to_string(), 0.1);
+        stale.last_updated = (Utc::now() - chrono::Duration::seconds(20)).to_rfc3339();
+        registry.register_agent_without_refresh(stale);
+        registry.register_agent(AgentInfo::new("fresh".to_string(), "ok".to_string(), 0.2));
+
+        // Routers never see the stale entry, even before it is evicted
+        assert_eq!(registry.agent_count(), 2);
+        assert_eq!(registry.get_all_agent_ids(), vec!["fresh".to_string()]);
+
+        assert_eq!(registry.remove_expired_agents(), 1);
+        assert_eq!(registry.remove_expired_agents(), 0);
+        assert_eq!(registry.agent_count(), 1);
+    }
+
+    #[test]
+    fn test_remove_agent_keeps_manifest() {
+        let registry = AgentRegistry::new();
+        registry.register_agent(AgentInfo::new("agent1".to_string(), "ok".to_string(), 0.2));
+        registry.register_manifest(AgentManifest {
+            agent_id: "agent1".to_string(),
+            description: None,
+            capabilities: vec![],
+            envelope_versions: vec!["2.0".to_string()],
+            tools: vec![],
+            model: "test-model".to_string(),
+            max_input_bytes: None,
+            timestamp: Utc::now(),
+        });
+
+        assert!(registry.remove_agent("agent1"));
+        assert!(!registry.remove_agent("agent1"));
+        assert!(registry.get_agent("agent1").is_none());
+        assert!(registry.get_manifest("agent1").is_some());
+    }
+
     #[test]
     fn test_agent_status_message_conversion() {
         let status_msg = AgentStatusMessage {
@@ -525,4 +597,31 @@ mod tests {
         let candidates = registry.find_agents_with_capability("database");
         assert!(candidates.is_empty());
     }
+
+    #[test]
+    fn test_manifest_registration_and_lookup() {
+        let registry = AgentRegistry::new();
+        assert!(registry.get_manifest("agent1").is_none());
+
+        let mut manifest = AgentManifest {
+            agent_id: "agent1".to_string(),
+            description: None,
+            capabilities: vec!["research".to_string()],
+            envelope_versions: vec!["1.0".to_string(), "2.0".to_string()],
+            tools: vec!["web_search".to_string()],
+            model: "test-model".to_string(),
+            max_input_bytes: None,
+            timestamp: Utc::now(),
+        };
+        registry.register_manifest(manifest.clone());
+        assert_eq!(registry.get_manifest("agent1"), Some(manifest.clone()));
+
+        // A refreshed manifest replaces the previous one
+        manifest.tools.push("http_request".to_string());
+        registry.register_manifest(manifest.clone());
+        assert_eq!(registry.get_manifest("agent1").unwrap().tools.len(), 2);
+
+        // Manifests do not depend on a status registration
+        assert!(registry.get_agent("agent1").is_none());
+    }
 }
@@ -1,25 +1,39 @@
 //! MQTT Integration for Agent Discovery
 //!
-//! Provides MQTT-based agent discovery by subscribing to agent status messages
-//! and maintaining a live registry of available agents.
+//! Provides MQTT-based agent discovery by subscribing to agent status and
+//! manifest messages and maintaining a live registry of available agents.
+//!
+//! Entries leave the registry two ways: a background task evicts agents whose
+//! status is older than the TTL, and an `unavailable` status (including the
+//! broker-published last will of a crashed agent) evicts the agent at once.
 
 use super::discovery::{AgentRegistry, AgentStatusMessage};
 use crate::error::{AgentError, AgentResult};
-use crate::protocol::topics::canonicalize_topic;
+use crate::protocol::topics::{agent_id_from_topic, canonicalize_topic};
+use crate::protocol::{AgentManifest, AgentStatus, AgentStatusType};
 use rumqttc::v5::mqttbytes::v5::Packet;
 use rumqttc::v5::{mqttbytes::QoS, AsyncClient, Event};
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
 use tracing::{debug, info, warn};
 
 /// MQTT topic pattern for agent status messages
 const AGENT_STATUS_TOPIC_PATTERN: &str = "/control/agents/+/status";
 
+/// MQTT topic pattern for agent capability manifests
+const AGENT_MANIFEST_TOPIC_PATTERN: &str = "/control/agents/+/manifest";
+
+/// How often expired agents are evicted from the registry
+pub const DEFAULT_PRUNE_INTERVAL: Duration = Duration::from_secs(5);
+
 /// MQTT integration for agent discovery
 #[derive(Debug)]
 pub struct DiscoveryMqttIntegration {
     registry: AgentRegistry,
     client: Option<Arc<tokio::sync::Mutex<AsyncClient>>>,
-    // Removed unused status_receiver field to prevent resource leak
+    /// Background task evicting expired agents
+    pruner: Option<JoinHandle<()>>,
 }
 
 /// Agent status update message from MQTT
@@ -37,6 +51,29 @@ impl DiscoveryMqttIntegration {
         Self {
             registry,
             client: None,
+            pruner: None,
+        }
+    }
+
+    /// Evict expired agents every `interval` until cleanup or drop
+    ///
+    /// Replaces any pruning task already running.
+    pub fn start_pruning(&mut self, interval: Duration) {
+        self.stop_pruning();
+        let registry = self.registry.clone();
+        self.pruner = Some(tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                registry.remove_expired_agents();
+            }
+        }));
+    }
+
+    fn stop_pruning(&mut self) {
+        if let Some(pruner) = self.pruner.take() {
+            pruner.abort();
         }
     }
 
@@ -47,21 +84,24 @@ impl DiscoveryMqttIntegration {
     ) -> AgentResult<()> {
         self.client = Some(mqtt_client.clone());
 
-        // Subscribe to agent status messages
+        // Subscribe to agent status and manifest messages
         {
             let client = mqtt_client.lock().await;
-            client
-                .subscribe(AGENT_STATUS_TOPIC_PATTERN, QoS::AtLeastOnce)
-                .await
-                .map_err(|e| {
-                    AgentError::internal_error(format!("MQTT subscription failed: {e}"))
-                })?;
+            for pattern in [AGENT_STATUS_TOPIC_PATTERN, AGENT_MANIFEST_TOPIC_PATTERN] {
+                client
+                    .subscribe(pattern, QoS::AtLeastOnce)
+                    .await
+                    .map_err(|e| {
+                        AgentError::internal_error(format!("MQTT subscription failed: {e}"))
+                    })?;
+            }
         }
 
         info!(
-            "Subscribed to agent status messages: {}",
-            AGENT_STATUS_TOPIC_PATTERN
+            "Subscribed to agent discovery messages: {}, {}",
+            AGENT_STATUS_TOPIC_PATTERN, AGENT_MANIFEST_TOPIC_PATTERN
         );
+        self.start_pruning(DEFAULT_PRUNE_INTERVAL);
         Ok(())
     }
 
@@ -74,6 +114,8 @@ impl DiscoveryMqttIntegration {
             if self.is_status_message(&topic) {
                 self.handle_status_message(&topic, &publish.payload, publish.retain)
                     .await?;
+            } else if self.is_manifest_message(&topic) {
+                self.handle_manifest_message(&topic, &publish.payload);
             }
         }
         Ok(())
@@ -95,6 +137,14 @@ impl DiscoveryMqttIntegration {
             }
         };
 
+        // An agent going away: an empty payload clears its retained status,
+        // and an unavailable status is its own shutdown or its last will
+        if payload.is_empty() || Self::is_unavailable_status(payload) {
+            debug!("Agent '{}' is unavailable, evicting it", agent_id);
+            self.registry.remove_agent(&agent_id);
+            return Ok(());
+        }
+
         // Parse status message
         let status_message: AgentStatusMessage = match serde_json::from_slice(payload) {
             Ok(msg) => msg,
@@ -126,23 +176,63 @@ impl DiscoveryMqttIntegration {
         Ok(())
     }
 
+    /// Check if payload is a protocol status announcing the agent unavailable
+    fn is_unavailable_status(payload: &[u8]) -> bool {
+        serde_json::from_slice::<AgentStatus>(payload)
+            .is_ok_and(|status| status.status == AgentStatusType::Unavailable)
+    }
+
+    /// Handle agent capability manifest message
+    fn handle_manifest_message(&self, topic: &str, payload: &[u8]) {
+        let Some(agent_id) = Self::extract_agent_id_for_kind(topic, "manifest") else {
+            warn!("Could not extract agent_id from topic: {}", topic);
+            return;
+        };
+
+        let manifest: AgentManifest = match serde_json::from_slice(payload) {
+            Ok(manifest) => manifest,
+            Err(e) => {
+                warn!("Failed to parse agent manifest from {}: {}", agent_id, e);
+                return;
+            }
+        };
+
+        // An agent may only describe itself
+        if manifest.agent_id != agent_id {
+            warn!(
+                "Ignoring manifest for '{}' published on topic of agent '{}'",
+                manifest.agent_id, agent_id
+            );
+            return;
+        }
+
+        self.registry.register_manifest(manifest);
+    }
+
     /// Check if topic is a status message topic
     fn is_status_message(&self, topic: &str) -> bool {
         // Match pattern /control/agents/{agent_id}/status
-        let canonical_topic = canonicalize_topic(topic);
-        let parts: Vec<&str> = canonical_topic.trim_start_matches('/').split('/').collect();
+        Self::extract_agent_id_for_kind(topic, "status").is_some()
+    }
 
-        parts.len() == 4 && parts[0] == "control" && parts[1] == "agents" && parts[3] == "status"
+    /// Check if topic is a manifest message topic
+    fn is_manifest_message(&self, topic: &str) -> bool {
+        // Match pattern /control/agents/{agent_id}/manifest
+        Self::extract_agent_id_for_kind(topic, "manifest").is_some()
     }
 
     /// Extract agent_id from status topic
     fn extract_agent_id_from_topic(&self, topic: &str) -> Option<String> {
+        Self::extract_agent_id_for_kind(topic, "status")
+    }
+
+    /// Extract a valid agent_id from a `/control/agents/{agent_id}/{kind}` topic
+    fn extract_agent_id_for_kind(topic: &str, kind: &str) -> Option<String> {
         let canonical_topic = canonicalize_topic(topic);
         let parts: Vec<&str> = canonical_topic.trim_start_matches('/').split('/').collect();
 
-        if parts.len() == 4 && parts[0] == "control" && parts[1] == "agents" && parts[3] == "status"
-        {
-            Some(parts[2].to_string())
+        if parts.len() == 4 && parts[0] == "control" && parts[1] == "agents" && parts[3] == kind {
+            agent_id_from_topic(&canonical_topic).ok()
         } else {
             None
         }
@@ -164,22 +254,28 @@ impl DiscoveryMqttIntegration {
 
     /// Clean up MQTT resources
     pub async fn cleanup(&mut self) -> AgentResult<()> {
+        self.stop_pruning();
         if let Some(client) = &self.client {
-            // Unsubscribe from agent status messages
+            // Unsubscribe from agent status and manifest messages
             let mqtt_client = client.lock().await;
-            if let Err(e) = mqtt_client.unsubscribe(AGENT_STATUS_TOPIC_PATTERN).await {
-                warn!("Failed to unsubscribe from agent status messages: {}", e);
+            for pattern in [AGENT_STATUS_TOPIC_PATTERN, AGENT_MANIFEST_TOPIC_PATTERN] {
+                if let Err(e) = mqtt_client.unsubscribe(pattern).await {
+                    warn!("Failed to unsubscribe from {}: {}", pattern, e);
+                }
             }
-            info!(
-                "Unsubscribed from agent status messages: {}",
-                AGENT_STATUS_TOPIC_PATTERN
-            );
+            info!("Unsubscribed from agent discovery messages");
         }
         self.client = None;
         Ok(())
     }
 }
 
+impl Drop for DiscoveryMqttIntegration {
+    fn drop(&mut self) {
+        self.stop_pruning();
+    }
+}
+
 /// Statistics about discovered agents
 #[derive(Debug, Clone)]
 pub struct DiscoveryStats {
@@ -231,6 +327,15 @@ mod tests {
             integration.extract_agent_id_from_topic("/invalid/topic"),
             None
         );
+        // Ids that would break topics built from them are not trusted
+        assert_eq!(
+            integration.extract_agent_id_from_topic("/control/agents/bad+id/status"),
+            None
+        );
+        assert_eq!(
+            integration.extract_agent_id_from_topic("/control/agents/agént/status"),
+            None
+        );
     }
 
     #[tokio::test]
@@ -283,6 +388,99 @@ mod tests {
         assert!(integration.registry.get_agent("test-agent").is_none());
     }
 
+    #[tokio::test]
+    async fn test_unavailable_status_evicts_agent() {
+        let registry = AgentRegistry::new();
+        let integration = DiscoveryMqttIntegration::new(registry.clone());
+        for agent_id in ["crashed", "cleared", "other"] {
+            registry.register_agent(AgentInfo::new(agent_id.to_string(), "ok".to_string(), 0.1));
+        }
+
+        // The last will an agent registers with the broker
+        let last_will = AgentStatus {
+            agent_id: "crashed".to_string(),
+            status: AgentStatusType::Unavailable,
+            timestamp: chrono::Utc::now(),
+            capabilities: None,
+            description: None,
+        };
+        let payload = serde_json::to_vec(&last_will).unwrap();
+        integration
+            .handle_status_message("/control/agents/crashed/status", &payload, true)
+            .await
+            .unwrap();
+        // Clearing a retained status publishes an empty payload
+        integration
+            .handle_status_message("/control/agents/cleared/status", b"", true)
+            .await
+            .unwrap();
+
+        assert!(registry.get_agent("crashed").is_none());
+        assert!(registry.get_agent("cleared").is_none());
+        assert_eq!(registry.get_all_agent_ids(), vec!["other".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_pruning_task_evicts_expired_agents() {
+        let registry = AgentRegistry::new();
+        let mut integration = DiscoveryMqttIntegration::new(registry.clone());
+        let mut stale = AgentInfo::new("stale".to_string(), "ok".to_string(), 0.1);
+        stale.last_updated = (chrono::Utc::now() - chrono::Duration::seconds(20)).to_rfc3339();
+        registry.register_agent_without_refresh(stale);
+        registry.register_agent(AgentInfo::new("fresh".to_string(), "ok".to_string(), 0.1));
+        assert_eq!(registry.agent_count(), 2);
+
+        integration.start_pruning(Duration::from_millis(10));
+        for _ in 0..100 {
+            if registry.agent_count() == 1 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        assert!(registry.get_agent("stale").is_none());
+        assert!(registry.get_agent("fresh").is_some());
+        integration.cleanup().await.unwrap();
+        assert!(integration.pruner.is_none());
+    }
+
+    #[test]
+    fn test_manifest_message_processing() {
+        let integration = DiscoveryMqttIntegration::new(AgentRegistry::new());
+        let manifest = AgentManifest {
+            agent_id: "research-agent".to_string(),
+            description: None,
+            capabilities: vec!["research".to_string()],
+            envelope_versions: vec!["2.0".to_string()],
+            tools: vec!["web_search".to_string()],
+            model: "test-model".to_string(),
+            max_input_bytes: Some(1024),
+            timestamp: chrono::Utc::now(),
+        };
+        let payload = serde_json::to_vec(&manifest).unwrap();
+
+        assert!(integration.is_manifest_message("/control/agents/research-agent/manifest"));
+        assert!(!integration.is_status_message("/control/agents/research-agent/manifest"));
+
+        // A manifest published on another agent's topic is ignored
+        integration.handle_manifest_message("/control/agents/impostor/manifest", &payload);
+        assert!(integration
+            .registry
+            .get_manifest("research-agent")
+            .is_none());
+        assert!(integration.registry.get_manifest("impostor").is_none());
+
+        integration.handle_manifest_message("/control/agents/research-agent/manifest", &payload);
+        assert_eq!(
+            integration.registry.get_manifest("research-agent"),
+            Some(manifest)
+        );
+
+        // Invalid payloads are ignored without panicking
+        integration.handle_manifest_message("/control/agents/other/manifest", b"not json");
+        assert!(integration.registry.get_manifest("other").is_none());
+    }
+
     #[tokio::test]
     async fn test_discovery_stats() {
         let registry = AgentRegistry::new();
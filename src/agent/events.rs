@@ -0,0 +1,194 @@
+//! Task and status events for library callers
+//!
+//! The pipeline reports what happens to each task on a broadcast channel, so
+//! an embedding application can follow completions, forwards and status
+//! changes without subscribing to the agent's MQTT topics. Sending never
+//! waits: a receiver that falls more than the channel's capacity behind loses
+//! the oldest events, and its next `recv` returns
+//! [`RecvError::Lagged`](tokio::sync::broadcast::error::RecvError::Lagged)
+//! with the number it missed. Events sent while nobody is subscribed are
+//! dropped.
+//!
+//! # Example
+//!
+//! ```
+//! use agent2389::agent::{AgentEvent, AgentPipeline, AgentProcessor};
+//! use agent2389::config::AgentConfig;
+//! use agent2389::protocol::messages::{TaskEnvelope, TaskEnvelopeWrapper};
+//! use agent2389::testing::mocks::{MockLlmProvider, MockTransport};
+//! use agent2389::tools::ToolSystem;
+//! use std::sync::Arc;
+//! use tokio::sync::mpsc;
+//!
+//! # #[tokio::main(flavor = "current_thread")]
+//! # async fn main() {
+//! let config: AgentConfig = toml::from_str(
+//!     r#"
+//! [agent]
+//! id = "events-agent"
+//! description = "Reports its tasks"
+//!
+//! [mqtt]
+//! broker_url = "mqtt://localhost:1883"
+//!
+//! [llm]
+//! provider = "openai"
+//! model = "gpt-4o"
+//! api_key_env = "OPENAI_API_KEY"
+//! system_prompt = "You are helpful."
+//! "#,
+//! )
+//! .unwrap();
+//! let processor = AgentProcessor::new(
+//!     config,
+//!     Arc::new(MockLlmProvider::single_response("Done")),
+//!     Arc::new(ToolSystem::new()),
+//!     Arc::new(MockTransport::new()),
+//! );
+//! let (tasks, task_receiver) = mpsc::channel(8);
+//! let mut pipeline = AgentPipeline::new(processor, task_receiver, 16);
+//!
+//! // Subscribe before submitting work; earlier events are not replayed
+//! let mut events = pipeline.events();
+//! tokio::spawn(async move { pipeline.run().await });
+//!
+//! let task = TaskEnvelope {
+//!     task_id: uuid::Uuid::new_v4(),
+//!     conversation_id: "conversation-1".to_string(),
+//!     topic: "/control/agents/events-agent/input".to_string(),
+//!     instruction: Some("Do some work".to_string()),
+//!     input: serde_json::json!({}),
+//!     next: None,
+//!     deadline: None,
+//!     correlation_id: None,
+//!     parent_task_id: None,
+//!     published_at: None,
+//!     traceparent: None,
+//! };
+//! let task_id = task.task_id;
+//! tasks.send(TaskEnvelopeWrapper::V1(task)).await.unwrap();
+//!
+//! loop {
+//!     if let AgentEvent::TaskCompleted(result) = events.recv().await.unwrap() {
+//!         assert_eq!(result.task_id, task_id);
+//!         assert_eq!(result.response, "Done");
+//!         break;
+//!     }
+//! }
+//! # }
+//! ```
+
+use crate::processing::nine_step::ProcessingResult;
+use crate::protocol::messages::AgentStatusType;
+use serde_json::Value;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+/// Events buffered per receiver before the oldest are dropped
+pub const DEFAULT_EVENT_CAPACITY: usize = 256;
+
+/// Something that happened to a task or to the agent
+#[derive(Debug, Clone)]
+pub enum AgentEvent {
+    /// A task was taken off the queue and is being processed
+    TaskStarted {
+        task_id: Uuid,
+        conversation_id: String,
+    },
+    /// A task finished; for v2 tasks this follows its routing outcome
+    TaskCompleted(ProcessingResult),
+    /// A task failed, was cancelled or was rejected
+    TaskFailed { task_id: Uuid, error: String },
+    /// A workflow step was handed to another agent
+    Forwarded { task_id: Uuid, target: String },
+    /// A workflow ended here and its final output was published
+    WorkflowCompleted { task_id: Uuid, final_output: Value },
+    /// The agent published a new status
+    StatusChanged(AgentStatusType),
+}
+
+/// Sending side of the event stream, cheap to clone
+#[derive(Debug, Clone)]
+pub struct AgentEvents {
+    sender: broadcast::Sender<AgentEvent>,
+}
+
+impl AgentEvents {
+    /// Event stream buffering up to `capacity` events per receiver
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity.max(1));
+        Self { sender }
+    }
+
+    /// Receive every event sent from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<AgentEvent> {
+        self.sender.subscribe()
+    }
+
+    /// Send `event` to current receivers without waiting for them
+    pub fn emit(&self, event: AgentEvent) {
+        // An error only means nobody is listening
+        let _ = self.sender.send(event);
+    }
+}
+
+impl Default for AgentEvents {
+    fn default() -> Self {
+        Self::new(DEFAULT_EVENT_CAPACITY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::broadcast::error::{RecvError, TryRecvError};
+
+    fn started(n: u128) -> AgentEvent {
+        AgentEvent::TaskStarted {
+            task_id: Uuid::from_u128(n),
+            conversation_id: "conversation".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_emit_without_receivers_is_dropped() {
+        let events = AgentEvents::default();
+        events.emit(started(1));
+
+        let mut receiver = events.subscribe();
+        assert!(matches!(receiver.try_recv(), Err(TryRecvError::Empty)));
+    }
+
+    #[tokio::test]
+    async fn test_every_receiver_gets_each_event() {
+        let events = AgentEvents::default();
+        let mut first = events.subscribe();
+        let mut second = events.clone().subscribe();
+
+        events.emit(AgentEvent::StatusChanged(AgentStatusType::Paused));
+
+        for receiver in [&mut first, &mut second] {
+            assert!(matches!(
+                receiver.recv().await.unwrap(),
+                AgentEvent::StatusChanged(AgentStatusType::Paused)
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_lagging_receiver_is_told_how_many_events_it_missed() {
+        let events = AgentEvents::new(2);
+        let mut receiver = events.subscribe();
+
+        // Never blocks, however far behind the receiver is
+        for n in 0..5 {
+            events.emit(started(n));
+        }
+
+        assert!(matches!(receiver.recv().await, Err(RecvError::Lagged(3))));
+        match receiver.recv().await.unwrap() {
+            AgentEvent::TaskStarted { task_id, .. } => assert_eq!(task_id, Uuid::from_u128(3)),
+            other => panic!("unexpected event {other:?}"),
+        }
+    }
+}
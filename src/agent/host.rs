@@ -0,0 +1,232 @@
+//! Hosting several agents in one process
+//!
+//! An `AgentHost` runs one `AgentLifecycle` per agent, serves each agent's
+//! health under `/agents/<agent_id>/` of a shared health server and shuts them
+//! all down on a single signal. Agents are isolated from each other: one that
+//! fails to start is reported as failed and the others keep running.
+
+use crate::agent::lifecycle::{AgentLifecycle, LifecycleError};
+use crate::observability::health::{HealthCheck, HealthServer};
+use crate::observability::probes::{PipelineState, StartupPhase};
+use crate::transport::Transport;
+use futures::future::join_all;
+use std::future::Future;
+use std::sync::Arc;
+use tracing::{error, info};
+
+/// State of one hosted agent
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HostedAgentState {
+    /// Not started yet, or shut down
+    Stopped,
+    Running,
+    /// Failed to start; the message is the startup error
+    Failed(String),
+}
+
+/// One agent run by the host
+struct HostedAgent<T>
+where
+    T: Transport + 'static,
+{
+    lifecycle: AgentLifecycle<T>,
+    health: Arc<HealthServer>,
+    state: HostedAgentState,
+}
+
+impl<T> HostedAgent<T>
+where
+    T: Transport + 'static,
+{
+    /// Initialize and start the agent, recording a failure instead of returning it
+    async fn start(&mut self) {
+        let result = match self.lifecycle.initialize().await {
+            Ok(()) => self.lifecycle.start().await,
+            Err(e) => Err(e),
+        };
+
+        match result {
+            Ok(()) => {
+                info!(agent_id = %self.lifecycle.agent_id(), "Hosted agent started");
+                self.health.set_mqtt_connected(true).await;
+                self.state = HostedAgentState::Running;
+            }
+            Err(e) => {
+                let message = lifecycle_error_message(&e);
+                error!(
+                    agent_id = %self.lifecycle.agent_id(),
+                    error = %message,
+                    "Hosted agent failed to start; other agents keep running"
+                );
+                self.state = HostedAgentState::Failed(message);
+            }
+        }
+    }
+
+    /// Shut the agent down if it is running
+    async fn shutdown(&mut self) {
+        if self.state != HostedAgentState::Running {
+            return;
+        }
+
+        if let Err(e) = self.lifecycle.shutdown().await {
+            error!(agent_id = %self.lifecycle.agent_id(), error = %e, "Hosted agent shutdown error");
+        }
+        self.health.set_mqtt_connected(false).await;
+        self.state = HostedAgentState::Stopped;
+    }
+
+    /// Health check summarizing the agent's state
+    fn lifecycle_check(&self) -> HealthCheck {
+        let (status, message) = match &self.state {
+            HostedAgentState::Running => ("healthy", "Agent running".to_string()),
+            HostedAgentState::Stopped => ("unhealthy", "Agent stopped".to_string()),
+            HostedAgentState::Failed(error) => ("unhealthy", format!("Agent failed: {error}")),
+        };
+        HealthCheck {
+            status: status.to_string(),
+            message: Some(message),
+            last_check: chrono::Utc::now().timestamp() as u64,
+        }
+    }
+}
+
+/// Runs several agents in one process
+pub struct AgentHost<T>
+where
+    T: Transport + 'static,
+{
+    agents: Vec<HostedAgent<T>>,
+    health_server: Arc<HealthServer>,
+}
+
+impl<T> AgentHost<T>
+where
+    T: Transport + 'static,
+{
+    /// Create a host reporting through `health_server`
+    pub fn new(health_server: Arc<HealthServer>) -> Self {
+        Self {
+            agents: Vec::new(),
+            health_server,
+        }
+    }
+
+    /// Add an agent; its health is served under `/agents/<agent_id>/`
+    pub fn add_agent(&mut self, mut lifecycle: AgentLifecycle<T>) {
+        // Hosted agents share the host's port instead of serving their own
+        let health = Arc::new(
+            HealthServer::new(lifecycle.agent_id().to_string(), 0)
+                .share_progress_stream(&self.health_server),
+        );
+        lifecycle.set_health_server(health.clone());
+        self.health_server.register_agent(health.clone());
+
+        self.agents.push(HostedAgent {
+            lifecycle,
+            health,
+            state: HostedAgentState::Stopped,
+        });
+    }
+
+    /// Start every agent concurrently and return how many are running
+    pub async fn start(&mut self) -> usize {
+        join_all(self.agents.iter_mut().map(HostedAgent::start)).await;
+        self.report_states().await;
+        self.running_count()
+    }
+
+    /// Shut down every running agent
+    pub async fn shutdown(&mut self) {
+        join_all(self.agents.iter_mut().map(HostedAgent::shutdown)).await;
+        self.report_states().await;
+        info!("All hosted agents shut down");
+    }
+
+    /// Start every agent, run until `shutdown` completes, then stop them all
+    ///
+    /// Fails only when no agent could be started.
+    pub async fn run_until(
+        &mut self,
+        shutdown: impl Future<Output = ()>,
+    ) -> Result<(), LifecycleError> {
+        let running = self.start().await;
+        if running == 0 {
+            return Err(LifecycleError::InitializationError(
+                "No hosted agent could be started".to_string(),
+            ));
+        }
+        info!(
+            running,
+            total = self.agents.len(),
+            "Agent host running; waiting for shutdown signal"
+        );
+
+        shutdown.await;
+        self.shutdown().await;
+        Ok(())
+    }
+
+    /// Ids of the hosted agents, in the order they were added
+    pub fn agent_ids(&self) -> Vec<&str> {
+        self.agents
+            .iter()
+            .map(|agent| agent.lifecycle.agent_id())
+            .collect()
+    }
+
+    /// State of a hosted agent
+    pub fn state(&self, agent_id: &str) -> Option<&HostedAgentState> {
+        self.agents
+            .iter()
+            .find(|agent| agent.lifecycle.agent_id() == agent_id)
+            .map(|agent| &agent.state)
+    }
+
+    /// Number of agents currently running
+    pub fn running_count(&self) -> usize {
+        self.agents
+            .iter()
+            .filter(|agent| agent.state == HostedAgentState::Running)
+            .count()
+    }
+
+    /// Get the shared health server
+    pub fn health_server(&self) -> &Arc<HealthServer> {
+        &self.health_server
+    }
+
+    /// Publish each agent's state on its own health and the host's
+    async fn report_states(&self) {
+        for agent in &self.agents {
+            let check = agent.lifecycle_check();
+            agent
+                .health
+                .add_health_check("lifecycle".to_string(), check.clone())
+                .await;
+            self.health_server
+                .add_health_check(format!("agent:{}", agent.lifecycle.agent_id()), check)
+                .await;
+        }
+        let running = self.running_count() > 0;
+        self.health_server.set_mqtt_connected(running).await;
+
+        // The host has no pipeline of its own; it is ready while any agent runs
+        let probes = self.health_server.probes();
+        if running {
+            probes.set_phase(StartupPhase::Ready);
+            probes.set_pipeline(PipelineState::Running);
+        } else {
+            probes.set_phase(StartupPhase::Stopping);
+            probes.set_pipeline(PipelineState::NotStarted);
+        }
+    }
+}
+
+/// Lifecycle error with its source, since the variants only name the category
+fn lifecycle_error_message(error: &LifecycleError) -> String {
+    match std::error::Error::source(error) {
+        Some(source) => format!("{error}: {source}"),
+        None => error.to_string(),
+    }
+}
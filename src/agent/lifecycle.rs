@@ -3,12 +3,21 @@
 //! This module implements ONLY the lifecycle behavior specified in RFC Section 7.
 //! No additional functionality beyond the RFC specification is allowed.
 
-use crate::config::AgentConfig;
+use crate::agent::events::{AgentEvent, AgentEvents};
+use crate::agent::status_publisher::StatusPublisher;
+use crate::clock::{clock_for, Clock};
+use crate::config::{AgentConfig, ConfigError, ConfigReload};
 use crate::health::{HealthCheckManager, LlmProviderHealthCheck, MqttHealthCheck};
-use crate::protocol::{AgentStatus, AgentStatusType};
+use crate::observability::event_log::{event_log, Event, EventCategory, EventSeverity};
+use crate::observability::metrics::metrics;
+use crate::observability::probes::{ComponentStatus, PipelineState, ProbeState, StartupPhase};
+use crate::observability::telemetry::{memory_rss_bytes, TelemetrySampler};
+use crate::protocol::{AgentManifest, AgentStatusType, SUPPORTED_ENVELOPE_VERSIONS};
+use crate::transport::mqtt::{ConnectionState, TopicBuilder};
 use std::sync::Arc;
 use thiserror::Error;
-use tracing::{error, info};
+use tokio::sync::{broadcast, watch};
+use tracing::{debug, error, info, warn};
 
 /// RFC-compliant agent lifecycle management with dependency injection
 pub struct AgentLifecycle<T>
@@ -21,8 +30,22 @@ where
     _pipeline: Option<crate::agent::pipeline::AgentPipeline<T>>,
     _pipeline_handle: Option<tokio::task::JoinHandle<()>>,
     _heartbeat_handle: Option<tokio::task::JoinHandle<()>>,
+    /// Telemetry publication, when `[observability.telemetry]` is set
+    telemetry_handle: Option<tokio::task::JoinHandle<()>>,
+    /// Shared transport once started, kept for republishing the manifest
+    running_transport: Option<Arc<T>>,
+    /// Publishes the agent's status once started, shared with the pipeline
+    status_publisher: Option<Arc<StatusPublisher<T>>>,
+    /// Task, routing and status events, shared with the pipeline once started
+    events: AgentEvents,
+    /// Running processor, kept for swapping in reloaded tools
+    running_processor: Option<Arc<crate::agent::processor::AgentProcessor<T>>>,
+    /// Current configuration, updated by `reload_config`
+    config_updates: watch::Sender<AgentConfig>,
     health_server: Option<std::sync::Arc<crate::observability::health::HealthServer>>,
     health_check_manager: Arc<HealthCheckManager>,
+    /// Time stamped on the manifest, shared with the processor and pipeline
+    clock: Arc<dyn Clock>,
 }
 
 impl<T> AgentLifecycle<T>
@@ -41,6 +64,9 @@ where
         // Convert llm_provider to Arc for sharing
         let llm_arc: Arc<dyn crate::llm::provider::LlmProvider> = Arc::from(llm_provider);
 
+        let (config_updates, _) = watch::channel(config.clone());
+        let clock = clock_for(&config.testing);
+
         Self {
             config,
             transport: Some(transport),
@@ -48,8 +74,15 @@ where
             _pipeline: None, // Will be initialized during start()
             _pipeline_handle: None,
             _heartbeat_handle: None,
+            telemetry_handle: None,
+            running_transport: None,
+            status_publisher: None,
+            events: AgentEvents::default(),
+            running_processor: None,
+            config_updates,
             health_server: None, // Will be set by set_health_server()
             health_check_manager: Arc::new(health_manager),
+            clock,
         }
     }
 
@@ -66,6 +99,14 @@ where
         &self.health_check_manager
     }
 
+    /// Subscribe to the agent's task, routing and status events
+    ///
+    /// May be called before `start`; only events sent after subscribing are
+    /// received.
+    pub fn events(&self) -> broadcast::Receiver<AgentEvent> {
+        self.events.subscribe()
+    }
+
     /// Get the transport instance for testing
     pub fn transport(&self) -> Option<&T> {
         self.transport.as_ref()
@@ -97,21 +138,47 @@ where
 
     // ========== PURE HELPER FUNCTIONS FOR LIFECYCLE START ==========
 
-    /// Create agent status message (pure function)
-    fn create_agent_status(
-        agent_id: String,
-        capabilities: Option<Vec<String>>,
-        description: Option<String>,
-    ) -> AgentStatus {
-        AgentStatus {
-            agent_id,
-            status: AgentStatusType::Available,
-            timestamp: chrono::Utc::now(),
-            capabilities,
-            description,
+    /// Create agent capability manifest (pure function)
+    ///
+    /// Tool names are sorted so republishing an unchanged tool set is stable.
+    fn create_agent_manifest(
+        config: &AgentConfig,
+        mut tools: Vec<String>,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> AgentManifest {
+        tools.sort();
+        AgentManifest {
+            agent_id: config.agent.id.clone(),
+            description: if config.agent.description.is_empty() {
+                None
+            } else {
+                Some(config.agent.description.clone())
+            },
+            capabilities: config.agent.capabilities.clone(),
+            envelope_versions: SUPPORTED_ENVELOPE_VERSIONS.map(str::to_string).to_vec(),
+            tools,
+            model: config.llm.model.clone(),
+            max_input_bytes: config.agent.max_input_bytes,
+            timestamp: now,
         }
     }
 
+    /// Publish the capability manifest, retained so late subscribers receive it
+    async fn publish_manifest(
+        transport: &T,
+        manifest: &AgentManifest,
+    ) -> Result<(), LifecycleError> {
+        let topic = TopicBuilder::build_manifest_topic(&manifest.agent_id);
+        let payload = serde_json::to_vec(manifest).map_err(|e| {
+            LifecycleError::InitializationError(format!("Failed to serialize manifest: {e}"))
+        })?;
+
+        transport
+            .publish(&topic, payload, true)
+            .await
+            .map_err(|e| LifecycleError::TransportError(Box::new(e)))
+    }
+
     /// Create task communication channel (pure function)
     fn create_task_channel() -> (
         tokio::sync::mpsc::Sender<crate::protocol::messages::TaskEnvelopeWrapper>,
@@ -132,49 +199,185 @@ where
     }
 
     /// Create agent processor (pure construction)
+    ///
+    /// Progress goes to every sink enabled under `[progress.sinks]` and, with
+    /// a health server, to its `/progress/stream`. Responses are checked by
+    /// the `[security.moderation]` policy, if configured. Fails if the progress
+    /// file cannot be opened, the webhook URL cannot be resolved or a
+    /// moderation pattern doesn't compile.
     fn create_agent_processor(
         config: AgentConfig,
         llm_provider: Arc<dyn crate::llm::provider::LlmProvider>,
         tool_system: Arc<crate::tools::ToolSystem>,
         transport: Arc<T>,
-    ) -> crate::agent::processor::AgentProcessor<T> {
-        crate::agent::processor::AgentProcessor::new(config, llm_provider, tool_system, transport)
+        health_server: Option<&Arc<crate::observability::health::HealthServer>>,
+    ) -> Result<crate::agent::processor::AgentProcessor<T>, LifecycleError> {
+        use crate::agent::processor::AgentProcessor;
+        use crate::progress::{
+            BroadcastProgress, CompositeProgress, FileProgress, LogProgress, NoOpProgress,
+            Progress, WebhookProgress,
+        };
+
+        let sinks = &config.progress.sinks;
+        let mut children: Vec<Arc<dyn Progress>> = Vec::new();
+        if sinks.mqtt {
+            children.push(AgentProcessor::mqtt_progress(&config, &transport));
+        }
+        if sinks.log {
+            children.push(Arc::new(LogProgress::logging(config.agent.id.clone())));
+        }
+        if let Some(file) = &sinks.file {
+            let file_progress = FileProgress::open(config.agent.id.clone(), file).map_err(|e| {
+                LifecycleError::InitializationError(format!(
+                    "Failed to open progress file {}: {e}",
+                    file.path.display()
+                ))
+            })?;
+            children.push(Arc::new(file_progress));
+        }
+        if let Some(webhook) = &sinks.webhook {
+            let url = webhook.url_env.resolve().map_err(|e| {
+                LifecycleError::InitializationError(format!("Failed to resolve webhook URL: {e}"))
+            })?;
+            children.push(Arc::new(WebhookProgress::spawn(
+                config.agent.id.clone(),
+                url.expose().to_string(),
+                webhook,
+            )));
+        }
+        if let Some(health_server) = health_server {
+            children.push(Arc::new(BroadcastProgress::new(
+                config.agent.id.clone(),
+                health_server.progress_sender(),
+            )));
+        }
+
+        let progress: Arc<dyn Progress> = match children.len() {
+            0 => Arc::new(NoOpProgress),
+            1 => children.remove(0),
+            _ => Arc::new(CompositeProgress::with_queue_capacity(
+                children,
+                sinks.queue_capacity,
+            )),
+        };
+        let moderation =
+            crate::processing::moderation::moderation_policy(&config, llm_provider.clone())
+                .map_err(|e| {
+                    LifecycleError::InitializationError(format!("Invalid moderation pattern: {e}"))
+                })?;
+        let mut processor =
+            AgentProcessor::with_progress(config, llm_provider, tool_system, transport, progress);
+        if let Some(policy) = moderation {
+            processor = processor.with_moderation(policy);
+        }
+        Ok(match health_server {
+            Some(health_server) => processor.with_state_registry(health_server.state().clone()),
+            None => processor,
+        })
     }
 
     /// Create agent pipeline (pure construction)
     fn create_agent_pipeline(
-        processor: crate::agent::processor::AgentProcessor<T>,
+        processor: Arc<dyn crate::agent::task_processor::TaskProcessor<T>>,
         task_receiver: tokio::sync::mpsc::Receiver<crate::protocol::messages::TaskEnvelopeWrapper>,
         max_pipeline_depth: usize,
-        _health_server: Option<Arc<crate::observability::health::HealthServer>>,
+        health_server: Option<Arc<crate::observability::health::HealthServer>>,
     ) -> crate::agent::pipeline::AgentPipeline<T> {
-        crate::agent::pipeline::AgentPipeline::new(processor, task_receiver, max_pipeline_depth)
+        let mut pipeline = crate::agent::pipeline::AgentPipeline::with_processor(
+            processor,
+            task_receiver,
+            max_pipeline_depth,
+        );
+        if let Some(health_server) = health_server {
+            pipeline.set_state_registry(health_server.state().clone());
+        }
+        pipeline
+    }
+
+    /// Open the task journal configured by `[agent.persistence]`, if any
+    fn open_task_journal(
+        config: &AgentConfig,
+    ) -> Result<Option<Arc<crate::processing::TaskJournal>>, LifecycleError> {
+        let Some(persistence) = &config.agent.persistence else {
+            return Ok(None);
+        };
+        let path = persistence
+            .resolve_journal_path(config.agent.state_dir.as_deref())
+            .ok_or_else(|| {
+                LifecycleError::InitializationError(
+                    "agent.persistence requires journal_path or agent.state_dir".to_string(),
+                )
+            })?;
+
+        let journal = crate::processing::TaskJournal::open(
+            &path,
+            persistence.compact_threshold,
+            persistence.sync,
+        )
+        .map_err(|e| {
+            LifecycleError::InitializationError(format!(
+                "Failed to open task journal {}: {e}",
+                path.display()
+            ))
+        })?;
+        info!(path = %path.display(), "Opened task journal");
+        Ok(Some(Arc::new(journal)))
     }
 
-    /// Spawn heartbeat task to republish availability status at configured interval
-    /// This keeps retained status messages fresh and helps with monitoring
+    /// Spawn heartbeat task to republish the current status at configured interval
+    /// This keeps retained status messages fresh and helps with monitoring;
+    /// the status comes from `status_publisher`, so a pause the pipeline announced is kept.
+    /// The interval follows `mqtt.heartbeat_interval_secs` across reloads.
+    /// Ticks while the transport is not connected publish nothing.
     fn spawn_heartbeat_task(
         transport: Arc<T>,
+        status_publisher: Arc<StatusPublisher<T>>,
         agent_id: String,
-        capabilities: Option<Vec<String>>,
-        description: Option<String>,
-        interval_secs: u64,
+        mut config_updates: watch::Receiver<AgentConfig>,
     ) -> tokio::task::JoinHandle<()> {
         tokio::spawn(async move {
-            let mut interval = tokio::time::interval(std::time::Duration::from_secs(interval_secs));
-            interval.tick().await; // First tick completes immediately, skip it
+            let mut interval_secs = config_updates.borrow().mqtt.heartbeat_interval_secs;
+            // The first tick waits a full interval instead of completing immediately
+            let new_interval = |secs: u64| {
+                let period = std::time::Duration::from_secs(secs);
+                tokio::time::interval_at(tokio::time::Instant::now() + period, period)
+            };
+            let mut interval = new_interval(interval_secs);
+            let mut reloads_open = true;
 
             loop {
-                interval.tick().await;
+                tokio::select! {
+                    _ = interval.tick() => {}
+                    changed = config_updates.changed(), if reloads_open => {
+                        if changed.is_err() {
+                            reloads_open = false;
+                            continue;
+                        }
+                        let reloaded_secs = config_updates.borrow_and_update().mqtt.heartbeat_interval_secs;
+                        if reloaded_secs != interval_secs {
+                            interval_secs = reloaded_secs;
+                            interval = new_interval(interval_secs);
+                            info!(interval_secs, "Heartbeat interval reloaded");
+                        }
+                        continue;
+                    }
+                }
 
-                let status = Self::create_agent_status(
-                    agent_id.clone(),
-                    capabilities.clone(),
-                    description.clone(),
-                );
+                // A reconnecting transport would only fail the publish
+                if !transport.is_connected() {
+                    debug!(
+                        agent_id = %agent_id,
+                        state = ?transport.connection_state(),
+                        "Heartbeat: Skipping status publish while disconnected"
+                    );
+                    continue;
+                }
 
-                match transport.publish_status(&status).await {
-                    Ok(_) => {
+                match status_publisher.republish().await {
+                    Ok(false) => {
+                        debug!(agent_id = %agent_id, "Heartbeat: Agent stopped, not publishing");
+                    }
+                    Ok(true) => {
                         info!(
                             agent_id = %agent_id,
                             interval_secs = %interval_secs,
@@ -194,23 +397,71 @@ where
         })
     }
 
+    /// Spawn the task publishing a `TelemetrySnapshot` every `interval_secs`
+    ///
+    /// Snapshots are published non-retained at QoS 0: a missed one is
+    /// superseded by the next, and a stale one should not outlive the agent.
+    fn spawn_telemetry_task(
+        transport: Arc<T>,
+        agent_id: String,
+        interval_secs: u64,
+    ) -> tokio::task::JoinHandle<()> {
+        // Sampled here so the first interval starts now, not when the task first runs
+        let mut sampler = TelemetrySampler::new(agent_id.clone(), &metrics().get_metrics());
+        let period = std::time::Duration::from_secs(interval_secs);
+        let start = tokio::time::Instant::now() + period;
+        tokio::spawn(async move {
+            let topic = TopicBuilder::build_telemetry_topic(&agent_id);
+            let mut interval = tokio::time::interval_at(start, period);
+
+            loop {
+                interval.tick().await;
+
+                let snapshot = sampler.sample(
+                    &metrics().get_metrics(),
+                    &transport.health_metrics(),
+                    memory_rss_bytes(),
+                );
+                let payload = match serde_json::to_vec(&snapshot) {
+                    Ok(payload) => payload,
+                    Err(e) => {
+                        error!(agent_id = %agent_id, error = %e, "Telemetry: Failed to serialize snapshot");
+                        continue;
+                    }
+                };
+                if let Err(e) = transport.publish(&topic, payload, false).await {
+                    warn!(agent_id = %agent_id, error = %e, "Telemetry: Failed to publish snapshot");
+                }
+            }
+        })
+    }
+
     /// RFC Section 7.1: Start the agent and begin processing
     pub async fn start(&mut self) -> Result<(), LifecycleError> {
         info!("Starting agent lifecycle: {}", self.config.agent.id);
 
+        // Start with any configuration reloaded before the agent started
+        self.config = self.config_updates.borrow().clone();
+        let probes = self.probes().cloned();
+        self.set_phase(StartupPhase::Initializing);
+
         if let (Some(transport), Some(llm_provider)) =
             (self.transport.take(), self.llm_provider.take())
         {
             // Initialize tool system from config
-            let mut tool_system = crate::tools::ToolSystem::new();
-            tool_system
-                .initialize(&self.config.tools)
+            let tool_system = crate::testing::recording::build_tool_system(&self.config)
                 .await
                 .map_err(|e| {
+                    if let Some(probes) = &probes {
+                        probes.set_component("tools", ComponentStatus::failing(e.to_string()));
+                    }
                     LifecycleError::ConfigurationError(crate::config::ConfigError::InvalidAgentId(
                         format!("Tool initialization failed: {e}"),
                     ))
                 })?;
+            if let Some(probes) = &probes {
+                probes.set_component("tools", ComponentStatus::ok(tools_summary(&tool_system)));
+            }
 
             // RFC Section 7.1: Agent MUST establish connection to MQTT broker
             let mut transport = transport;
@@ -225,12 +476,21 @@ where
                 .subscribe_to_tasks()
                 .await
                 .map_err(|e| LifecycleError::TransportError(Box::new(e)))?;
+            self.set_phase(StartupPhase::Subscribed);
+            if let Some(health_server) = &self.health_server {
+                health_server.set_mqtt_connected(true).await;
+            }
 
             // Create the RFC-compliant AgentPipeline
             info!("Initializing RFC-compliant agent pipeline...");
 
             let llm_provider_arc = llm_provider;
+            let tool_names = tool_system.list_tools();
             let tool_system_arc = std::sync::Arc::new(tool_system);
+            if let Some(health_server) = &self.health_server {
+                health_server.state().set_tools(&tool_system_arc);
+                health_server.state().set_config(&self.config);
+            }
 
             // Convert to Arc for shared ownership
             let transport_arc = std::sync::Arc::new(transport);
@@ -242,6 +502,9 @@ where
             // RFC Section 7.1: Agent MUST verify LLM adapter connectivity
             // Perform initial health checks on all components now that manager is populated
             let health_results = self.health_check_manager.run_health_checks().await;
+            if let Some(probes) = &probes {
+                probes.record_health_checks(&health_results);
+            }
 
             for result in &health_results {
                 if result.healthy {
@@ -276,12 +539,18 @@ where
             info!("All components passed initial health checks");
 
             // Create processor using extracted function
-            let processor = Self::create_agent_processor(
-                self.config.clone(),
-                llm_provider_arc,
-                tool_system_arc,
-                transport_arc.clone(),
+            let processor = Arc::new(
+                Self::create_agent_processor(
+                    self.config.clone(),
+                    llm_provider_arc,
+                    tool_system_arc,
+                    transport_arc.clone(),
+                    self.health_server.as_ref(),
+                )?
+                .with_config_updates(self.config_updates.subscribe())
+                .with_clock(self.clock.clone()),
             );
+            self.running_processor = Some(processor.clone());
 
             // Create task channel using extracted function
             let (task_sender, task_receiver) = Self::create_task_channel();
@@ -290,15 +559,47 @@ where
             let mut pipeline = Self::create_agent_pipeline(
                 processor,
                 task_receiver,
-                16, // max_pipeline_depth
+                self.config.processing.max_pipeline_depth as usize,
                 self.health_server.clone(),
             );
 
+            // Route cancel requests from the transport into the pipeline
+            let (cancel_sender, cancel_receiver) = tokio::sync::mpsc::channel(100);
+            pipeline.set_cancel_receiver(cancel_receiver);
+
+            // Route task batches from the transport into the pipeline
+            let (batch_sender, batch_receiver) = tokio::sync::mpsc::channel(100);
+            pipeline.set_batch_receiver(batch_receiver);
+
+            // Route pause/resume requests from the transport into the pipeline
+            let (admin_sender, admin_receiver) = tokio::sync::mpsc::channel(16);
+            pipeline.set_admin_receiver(admin_receiver);
+
+            // Journal received tasks so a crash between the broker ack and
+            // processing does not lose them
+            if let Some(journal) = Self::open_task_journal(&self.config)? {
+                pipeline.set_task_journal(journal.clone());
+                transport_arc.set_task_journal(journal);
+            }
+
             // Set the task_sender on the transport using interior mutability
             tracing::debug!("Setting task sender on MQTT transport...");
             transport_arc.set_task_sender(task_sender);
+            transport_arc.set_cancel_sender(cancel_sender);
+            transport_arc.set_batch_sender(batch_sender);
+            transport_arc.set_admin_sender(admin_sender);
             tracing::debug!("Task sender configured on transport successfully");
 
+            // One publisher for every status the agent reports, so the
+            // heartbeat follows the pause state the pipeline announces
+            let status_publisher = Arc::new(
+                StatusPublisher::new(transport_arc.clone(), &self.config.agent)
+                    .with_events(self.events.clone()),
+            );
+            pipeline.set_events(self.events.clone());
+            pipeline.set_status_publisher(status_publisher.clone());
+            self.status_publisher = Some(status_publisher.clone());
+
             // Start the pipeline
             tracing::debug!("Starting agent pipeline...");
             pipeline
@@ -306,65 +607,115 @@ where
                 .await
                 .map_err(|e| LifecycleError::TransportError(Box::new(e)))?;
 
-            // Run the pipeline processing
+            // Run the pipeline processing; liveness fails if the loop ends
+            if let Some(probes) = &probes {
+                probes.set_pipeline(PipelineState::Running);
+            }
+            let pipeline_probes = probes.clone();
+            let agent_id = self.config.agent.id.clone();
             let pipeline_handle = tokio::spawn(async move {
-                if let Err(e) = pipeline.run().await {
-                    error!("Agent pipeline error: {}", e);
+                use futures::FutureExt;
+
+                let (state, event) = match std::panic::AssertUnwindSafe(pipeline.run())
+                    .catch_unwind()
+                    .await
+                {
+                    Ok(Ok(())) => (
+                        PipelineState::Exited,
+                        Event::new(
+                            EventSeverity::Warning,
+                            EventCategory::Lifecycle,
+                            "Pipeline exited",
+                        ),
+                    ),
+                    Ok(Err(e)) => {
+                        error!("Agent pipeline error: {}", e);
+                        (
+                            PipelineState::Exited,
+                            Event::new(
+                                EventSeverity::Error,
+                                EventCategory::Lifecycle,
+                                "Pipeline exited with an error",
+                            )
+                            .with_field("error", e.to_string()),
+                        )
+                    }
+                    Err(_) => {
+                        error!("Agent pipeline panicked");
+                        (
+                            PipelineState::Panicked,
+                            Event::new(
+                                EventSeverity::Error,
+                                EventCategory::Lifecycle,
+                                "Pipeline panicked",
+                            ),
+                        )
+                    }
+                };
+                event_log().record(event.with_field("agent_id", agent_id));
+                if let Some(probes) = pipeline_probes {
+                    probes.set_pipeline(state);
                 }
             });
 
             // Store the handle for lifecycle management
             self._pipeline_handle = Some(pipeline_handle);
 
-            // RFC Section 7.1: Agent MUST publish availability status using extracted function
-            let status = Self::create_agent_status(
-                self.config.agent.id.clone(),
-                if self.config.agent.capabilities.is_empty() {
-                    None
-                } else {
-                    Some(self.config.agent.capabilities.clone())
-                },
-                if self.config.agent.description.is_empty() {
-                    None
-                } else {
-                    Some(self.config.agent.description.clone())
-                },
-            );
-
-            // Publish initial status using our configured transport
+            // RFC Section 7.1: Agent MUST publish availability status
             info!("Publishing initial 'available' status to MQTT...");
-            transport_arc
-                .publish_status(&status)
+            status_publisher
+                .publish(AgentStatusType::Available)
                 .await
                 .map_err(|e| LifecycleError::TransportError(Box::new(e)))?;
             info!("Initial status published successfully");
 
+            // Publish the capability manifest so routers can see tools and model
+            let manifest = Self::create_agent_manifest(&self.config, tool_names, self.clock.now());
+            Self::publish_manifest(&transport_arc, &manifest).await?;
+            info!(
+                tools = manifest.tools.len(),
+                "Capability manifest published"
+            );
+
             // Spawn heartbeat task to republish availability at configured interval
             // This keeps retained messages fresh and prevents stale status
             let heartbeat_interval = self.config.mqtt.heartbeat_interval_secs;
             let heartbeat_handle = Self::spawn_heartbeat_task(
                 transport_arc.clone(),
+                status_publisher,
                 self.config.agent.id.clone(),
-                if self.config.agent.capabilities.is_empty() {
-                    None
-                } else {
-                    Some(self.config.agent.capabilities.clone())
-                },
-                if self.config.agent.description.is_empty() {
-                    None
-                } else {
-                    Some(self.config.agent.description.clone())
-                },
-                heartbeat_interval,
+                self.config_updates.subscribe(),
             );
             self._heartbeat_handle = Some(heartbeat_handle);
             info!(interval_secs = heartbeat_interval, "Heartbeat task started");
 
+            if let Some(telemetry) = &self.config.observability.telemetry {
+                self.telemetry_handle = Some(Self::spawn_telemetry_task(
+                    transport_arc.clone(),
+                    self.config.agent.id.clone(),
+                    telemetry.interval_secs,
+                ));
+                info!(
+                    interval_secs = telemetry.interval_secs,
+                    "Telemetry task started"
+                );
+            }
+            self.set_phase(StartupPhase::Ready);
+            event_log().record(
+                Event::new(
+                    EventSeverity::Info,
+                    EventCategory::Lifecycle,
+                    "Agent started",
+                )
+                .with_field("agent_id", self.config.agent.id.as_str()),
+            );
+
             info!("Agent pipeline started successfully");
 
             // Keep the transport arc - we can't extract it back to owned
             // The transport is now managed by the Arc and the pipeline
             self.transport = None;
+            self.running_transport = Some(transport_arc);
         } else {
             return Err(LifecycleError::ConfigurationError(
                 crate::config::ConfigError::InvalidAgentId(
@@ -381,6 +732,15 @@ where
     /// RFC Section 7.2: Gracefully shut down the agent
     pub async fn shutdown(&mut self) -> Result<(), LifecycleError> {
         info!("Shutting down agent: {}", self.config.agent.id);
+        self.set_phase(StartupPhase::Stopping);
+        event_log().record(
+            Event::new(
+                EventSeverity::Info,
+                EventCategory::Lifecycle,
+                "Agent shutting down",
+            )
+            .with_field("agent_id", self.config.agent.id.as_str()),
+        );
 
         // Shut down heartbeat task if running
         if let Some(handle) = self._heartbeat_handle.take() {
@@ -392,6 +752,15 @@ where
             }
         }
 
+        if let Some(handle) = self.telemetry_handle.take() {
+            handle.abort();
+            if let Err(e) = handle.await {
+                if !e.is_cancelled() {
+                    error!("Telemetry shutdown error: {}", e);
+                }
+            }
+        }
+
         // Shut down pipeline if running
         if let Some(handle) = self._pipeline_handle.take() {
             handle.abort();
@@ -402,13 +771,109 @@ where
             }
         }
 
-        // Note: Transport shutdown is now handled by the pipeline
-        // RFC Section 7.2 compliance is maintained through pipeline shutdown sequence
+        // RFC Section 7.2: publish unavailability once nothing else can publish
+        // status, so a late heartbeat cannot leave a retained Available behind
+        if let Some(status_publisher) = &self.status_publisher {
+            if let Err(e) = status_publisher.publish_final().await {
+                warn!(error = %e, "Failed to publish unavailable status during shutdown");
+            }
+        }
 
         info!("Agent shutdown complete");
         Ok(())
     }
 
+    /// Republish the capability manifest after the tool set changed
+    ///
+    /// Call this whenever tools are reloaded so routers see the current tool list.
+    pub async fn refresh_manifest(
+        &self,
+        tool_system: &crate::tools::ToolSystem,
+    ) -> Result<(), LifecycleError> {
+        let transport = self.running_transport.as_ref().ok_or_else(|| {
+            LifecycleError::InitializationError(
+                "Cannot refresh manifest before the agent is started".to_string(),
+            )
+        })?;
+
+        let manifest = Self::create_agent_manifest(
+            &self.config_updates.borrow(),
+            tool_system.list_tools(),
+            self.clock.now(),
+        );
+        Self::publish_manifest(transport, &manifest).await?;
+        info!(
+            tools = manifest.tools.len(),
+            "Capability manifest refreshed"
+        );
+        Ok(())
+    }
+
+    /// Apply a re-read configuration file to the running agent
+    ///
+    /// Reloadable changes (LLM prompt, model, temperature, max tokens,
+    /// allowed override models and prices, the heartbeat interval, tool configs
+    /// and authorization rules) take effect for tasks that start afterwards.
+    /// Changes that need a restart are logged and ignored. If the new tools
+    /// fail to initialize, the running configuration is kept.
+    pub async fn reload_config(
+        &self,
+        candidate: AgentConfig,
+    ) -> Result<ConfigReload, LifecycleError> {
+        let reload = self.config_updates.borrow().plan_reload(&candidate);
+        for field in &reload.rejected {
+            warn!(
+                field,
+                "Configuration change requires a restart; keeping the running value"
+            );
+        }
+        if reload.applied.is_empty() {
+            return Ok(reload);
+        }
+
+        let tool_system = match &self.running_processor {
+            Some(processor) if reload.tools_changed() => {
+                let tool_system = crate::testing::recording::build_tool_system(&reload.config)
+                    .await
+                    .map_err(|e| {
+                        LifecycleError::ConfigurationError(ConfigError::InvalidConfig(format!(
+                            "Tool initialization failed: {e}"
+                        )))
+                    })?;
+                let tool_system = Arc::new(tool_system);
+                processor
+                    .nine_step_processor()
+                    .replace_tool_system(tool_system.clone());
+                Some(tool_system)
+            }
+            Some(processor) => Some(processor.nine_step_processor().tool_system()),
+            None => None,
+        };
+
+        self.config_updates.send_replace(reload.config.clone());
+        info!(fields = ?reload.applied, "Configuration reloaded");
+
+        if let Some(health_server) = &self.health_server {
+            health_server.state().set_config(&reload.config);
+            if let Some(tool_system) = tool_system.as_ref().filter(|_| reload.tools_changed()) {
+                health_server.state().set_tools(tool_system);
+            }
+        }
+
+        // The manifest advertises the tools and model
+        if let Some(tool_system) = tool_system {
+            if reload.tools_changed() || reload.applied.contains(&"llm.model") {
+                self.refresh_manifest(&tool_system).await?;
+            }
+        }
+        Ok(reload)
+    }
+
+    /// Get the current configuration, including reloaded changes
+    pub fn current_config(&self) -> AgentConfig {
+        self.config_updates.borrow().clone()
+    }
+
     /// Get agent ID
     pub fn agent_id(&self) -> &str {
         &self.config.agent.id
@@ -423,17 +888,90 @@ where
 
     /// Check if the transport connection is permanently disconnected
     pub fn is_permanently_disconnected(&self) -> bool {
-        // If transport still exists (before start), check it directly
-        if let Some(transport) = &self.transport {
-            transport.is_permanently_disconnected()
-        } else {
-            // After start(), transport is moved to pipeline
-            // Return false as we can't determine status without async call
-            false
+        // Before start() the transport is owned, afterwards it is shared
+        match (&self.transport, &self.running_transport) {
+            (Some(transport), _) => transport.is_permanently_disconnected(),
+            (None, Some(transport)) => transport.is_permanently_disconnected(),
+            (None, None) => false,
+        }
+    }
+
+    /// Wait until the transport is permanently disconnected, then report it
+    ///
+    /// Follows the transport's connection events, mirroring whether it is
+    /// connected on the health server, and marks the agent disconnected and
+    /// not ready once it gives up reconnecting. Never returns while there is
+    /// no transport.
+    pub async fn wait_for_permanent_disconnect(&self) {
+        // Before start() the transport is owned, afterwards it is shared
+        let mut events = match (&self.transport, &self.running_transport) {
+            (Some(transport), _) => transport.connection_events(),
+            (None, Some(transport)) => transport.connection_events(),
+            (None, None) => return std::future::pending().await,
+        };
+        loop {
+            let state = events.borrow_and_update().clone();
+            if let Some(health_server) = &self.health_server {
+                health_server
+                    .set_mqtt_connected(state == ConnectionState::Connected)
+                    .await;
+            }
+            if matches!(state, ConnectionState::PermanentlyDisconnected(_)) {
+                break;
+            }
+            if events.changed().await.is_err() {
+                // The transport is gone, so its state can no longer change
+                return std::future::pending().await;
+            }
+        }
+        self.set_phase(StartupPhase::Disconnected);
+    }
+
+    /// Probe state of the health server, if one is set
+    fn probes(&self) -> Option<&Arc<ProbeState>> {
+        self.health_server
+            .as_ref()
+            .map(|health_server| health_server.probes())
+    }
+
+    /// Report a lifecycle phase on `/readyz`
+    fn set_phase(&self, phase: StartupPhase) {
+        if let Some(probes) = self.probes() {
+            probes.set_phase(phase);
         }
     }
 }
 
+impl<T> Drop for AgentLifecycle<T>
+where
+    T: crate::transport::Transport + 'static,
+{
+    fn drop(&mut self) {
+        // Without shutdown() the spawned tasks would keep the agent running
+        for handle in [
+            self._heartbeat_handle.take(),
+            self.telemetry_handle.take(),
+            self._pipeline_handle.take(),
+        ]
+        .into_iter()
+        .flatten()
+        {
+            handle.abort();
+        }
+    }
+}
+
+/// Readiness message for the tools component, naming skipped optional tools
+fn tools_summary(tool_system: &crate::tools::ToolSystem) -> String {
+    let initialized = format!("{} tools initialized", tool_system.list_tools().len());
+    let degraded = tool_system.degraded_tools();
+    if degraded.is_empty() {
+        return initialized;
+    }
+    let names: Vec<&str> = degraded.iter().map(|tool| tool.name.as_str()).collect();
+    format!("{initialized}, optional skipped: {}", names.join(", "))
+}
+
 /// RFC-compliant agent lifecycle errors
 #[derive(Debug, Error)]
 pub enum LifecycleError {
@@ -458,56 +996,25 @@ impl From<crate::config::ConfigError> for LifecycleError {
 #[cfg(test)]
 mod helper_tests {
     use super::*;
-    use crate::protocol::AgentStatusType;
     use crate::testing::mocks::{MockLlmProvider, MockTransport};
 
     #[test]
-    fn test_create_agent_status() {
-        let agent_id = "test-agent-123".to_string();
-        let capabilities = Some(vec!["test".to_string()]);
-        let description = Some("Test agent".to_string());
-        let status = AgentLifecycle::<MockTransport>::create_agent_status(
-            agent_id.clone(),
-            capabilities.clone(),
-            description.clone(),
+    fn test_create_agent_manifest() {
+        let mut config = crate::config::AgentConfig::test_config();
+        config.agent.max_input_bytes = Some(4096);
+
+        let manifest = AgentLifecycle::<MockTransport>::create_agent_manifest(
+            &config,
+            vec!["web_search".to_string(), "http_request".to_string()],
+            chrono::Utc::now(),
         );
 
-        assert_eq!(status.agent_id, agent_id);
-        assert_eq!(status.status, AgentStatusType::Available);
-        assert_eq!(status.capabilities, capabilities);
-        assert_eq!(status.description, description);
-        assert!(status.timestamp <= chrono::Utc::now());
-    }
-
-    #[test]
-    fn test_create_agent_status_with_special_chars() {
-        let agent_id = "agent.with-special_chars".to_string();
-        let status =
-            AgentLifecycle::<MockTransport>::create_agent_status(agent_id.clone(), None, None);
-
-        assert_eq!(status.agent_id, agent_id);
-        assert_eq!(status.status, AgentStatusType::Available);
-    }
-
-    #[test]
-    fn test_create_agent_status_with_empty_id() {
-        let agent_id = "".to_string();
-        let status =
-            AgentLifecycle::<MockTransport>::create_agent_status(agent_id.clone(), None, None);
-
-        assert_eq!(status.agent_id, "");
-        assert_eq!(status.status, AgentStatusType::Available);
-    }
-
-    #[test]
-    fn test_create_agent_status_timestamp_ordering() {
-        let before = chrono::Utc::now();
-        let status =
-            AgentLifecycle::<MockTransport>::create_agent_status("test".to_string(), None, None);
-        let after = chrono::Utc::now();
-
-        assert!(status.timestamp >= before);
-        assert!(status.timestamp <= after);
+        assert_eq!(manifest.agent_id, config.agent.id);
+        assert_eq!(manifest.capabilities, config.agent.capabilities);
+        assert_eq!(manifest.model, config.llm.model);
+        assert_eq!(manifest.envelope_versions, vec!["1.0", "2.0"]);
+        assert_eq!(manifest.tools, vec!["http_request", "web_search"]);
+        assert_eq!(manifest.max_input_bytes, Some(4096));
     }
 
     #[tokio::test]
@@ -523,6 +1030,12 @@ mod helper_tests {
                 instruction: Some("test instruction".to_string()),
                 input: serde_json::json!({"test": "data"}),
                 next: None,
+                deadline: None,
+                correlation_id: None,
+                parent_task_id: None,
+                published_at: None,
+                traceparent: None,
+                sender_id: None,
             },
         );
 
@@ -547,6 +1060,12 @@ mod helper_tests {
                     instruction: Some(format!("instruction-{i}")),
                     input: serde_json::json!({"index": i}),
                     next: None,
+                    deadline: None,
+                    correlation_id: None,
+                    parent_task_id: None,
+                    published_at: None,
+                    traceparent: None,
+                    sender_id: None,
                 },
             );
             sender.send(envelope).await.unwrap();
@@ -569,6 +1088,12 @@ mod helper_tests {
                     instruction: None,
                     input: serde_json::json!({}),
                     next: None,
+                    deadline: None,
+                    correlation_id: None,
+                    parent_task_id: None,
+                    published_at: None,
+                    traceparent: None,
+                    sender_id: None,
                 },
             );
             sender.send(envelope).await.unwrap();
@@ -639,12 +1164,169 @@ mod helper_tests {
             llm_provider,
             tool_system,
             transport,
-        );
+            None,
+        )
+        .unwrap();
 
         // Verify processor was created (construction test - if it doesn't panic, it passed)
         drop(processor);
     }
 
+    #[tokio::test]
+    async fn test_processor_progress_feeds_health_server_stream() {
+        let config = crate::config::AgentConfig::test_config();
+        let llm_provider: Arc<dyn crate::llm::provider::LlmProvider> =
+            Arc::new(MockLlmProvider::single_response("test"));
+        let health_server = Arc::new(crate::observability::health::HealthServer::new(
+            config.agent.id.clone(),
+            0,
+        ));
+        let mut progress = health_server.progress_sender().subscribe();
+
+        let processor = AgentLifecycle::<MockTransport>::create_agent_processor(
+            config.clone(),
+            llm_provider,
+            Arc::new(crate::tools::ToolSystem::new()),
+            Arc::new(MockTransport::new()),
+            Some(&health_server),
+        )
+        .unwrap();
+        let task = crate::protocol::messages::TaskEnvelope {
+            task_id: uuid::Uuid::new_v4(),
+            conversation_id: "stream-conversation".to_string(),
+            topic: format!("/control/agents/{}/input", config.agent.id),
+            instruction: Some("Say hi".to_string()),
+            input: serde_json::json!({}),
+            next: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
+        };
+        let topic = task.topic.clone();
+        processor
+            .process_task(
+                crate::protocol::messages::TaskEnvelopeWrapper::V1(task),
+                &topic,
+                false,
+            )
+            .await
+            .unwrap();
+
+        let first = progress.recv().await.unwrap();
+        assert_eq!(
+            first.event_type,
+            crate::progress::ProgressEventType::TaskStart
+        );
+        assert_eq!(
+            first.conversation_id.as_deref(),
+            Some("stream-conversation")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_processor_progress_goes_to_configured_sinks() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = crate::config::AgentConfig::test_config();
+        config.progress.sinks.mqtt = false;
+        config.progress.sinks.file = Some(crate::config::FileSinkConfig {
+            path: dir.path().join("progress.jsonl"),
+            max_bytes: 1 << 20,
+            max_files: 1,
+        });
+        let health_server = Arc::new(crate::observability::health::HealthServer::new(
+            config.agent.id.clone(),
+            0,
+        ));
+        let mut stream = health_server.progress_sender().subscribe();
+        let transport = Arc::new(MockTransport::new());
+
+        let processor = AgentLifecycle::<MockTransport>::create_agent_processor(
+            config.clone(),
+            Arc::new(MockLlmProvider::single_response("test")),
+            Arc::new(crate::tools::ToolSystem::new()),
+            transport.clone(),
+            Some(&health_server),
+        )
+        .unwrap();
+        let task = crate::protocol::messages::TaskEnvelope {
+            task_id: uuid::Uuid::new_v4(),
+            conversation_id: "sink-conversation".to_string(),
+            topic: format!("/control/agents/{}/input", config.agent.id),
+            instruction: Some("Say hi".to_string()),
+            input: serde_json::json!({}),
+            next: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
+        };
+        let topic = task.topic.clone();
+        processor
+            .process_task(
+                crate::protocol::messages::TaskEnvelopeWrapper::V1(task),
+                &topic,
+                false,
+            )
+            .await
+            .unwrap();
+
+        // Both enabled sinks see the whole task, in order
+        loop {
+            let event = stream.recv().await.unwrap();
+            if event.event_type == crate::progress::ProgressEventType::TaskComplete {
+                break;
+            }
+        }
+        let file_events = tokio::time::timeout(std::time::Duration::from_secs(5), async {
+            loop {
+                let contents = std::fs::read_to_string(dir.path().join("progress.jsonl")).unwrap();
+                if contents.contains("TaskComplete") {
+                    return contents;
+                }
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+        assert!(file_events.lines().next().unwrap().contains("TaskStart"));
+        // The MQTT sink is disabled
+        assert!(!transport
+            .get_published_messages()
+            .await
+            .iter()
+            .any(|(topic, _)| topic.contains("/progress")));
+    }
+
+    #[test]
+    fn test_create_agent_processor_fails_on_unwritable_progress_file() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let mut config = crate::config::AgentConfig::test_config();
+        config.progress.sinks.file = Some(crate::config::FileSinkConfig {
+            // A regular file cannot be a directory
+            path: file.path().join("progress.jsonl"),
+            max_bytes: 1 << 20,
+            max_files: 1,
+        });
+
+        let result = AgentLifecycle::<MockTransport>::create_agent_processor(
+            config,
+            Arc::new(MockLlmProvider::single_response("test")),
+            Arc::new(crate::tools::ToolSystem::new()),
+            Arc::new(MockTransport::new()),
+            None,
+        );
+
+        assert!(matches!(
+            result,
+            Err(LifecycleError::InitializationError(_))
+        ));
+    }
+
     #[test]
     fn test_create_agent_pipeline_basic() {
         let config = crate::config::AgentConfig::test_config();
@@ -662,8 +1344,12 @@ mod helper_tests {
 
         let (_sender, receiver) = tokio::sync::mpsc::channel(100);
 
-        let pipeline =
-            AgentLifecycle::<MockTransport>::create_agent_pipeline(processor, receiver, 16, None);
+        let pipeline = AgentLifecycle::<MockTransport>::create_agent_pipeline(
+            Arc::new(processor),
+            receiver,
+            16,
+            None,
+        );
 
         // Verify pipeline was created
         drop(pipeline);
@@ -691,7 +1377,7 @@ mod helper_tests {
         ));
 
         let pipeline = AgentLifecycle::<MockTransport>::create_agent_pipeline(
-            processor,
+            Arc::new(processor),
             receiver,
             16,
             Some(health_server),
@@ -721,7 +1407,10 @@ mod helper_tests {
             let (_sender, receiver) = tokio::sync::mpsc::channel(100);
 
             let pipeline = AgentLifecycle::<MockTransport>::create_agent_pipeline(
-                processor, receiver, depth, None,
+                Arc::new(processor),
+                receiver,
+                depth,
+                None,
             );
             drop(pipeline);
         }
@@ -745,8 +1434,12 @@ mod helper_tests {
         let (_sender, receiver) = tokio::sync::mpsc::channel(100);
 
         // Test edge case: zero depth
-        let pipeline =
-            AgentLifecycle::<MockTransport>::create_agent_pipeline(processor, receiver, 0, None);
+        let pipeline = AgentLifecycle::<MockTransport>::create_agent_pipeline(
+            Arc::new(processor),
+            receiver,
+            0,
+            None,
+        );
         drop(pipeline);
     }
 }
@@ -755,7 +1448,8 @@ mod helper_tests {
 mod tests {
     use super::*;
     use crate::config::AgentConfig;
-    use crate::testing::mocks::{MockLlmProvider, MockTransport};
+    use crate::testing::mocks::{MockLlmProvider, MockTransport, TransportCall};
+    use crate::transport::mqtt::ConnectionState;
 
     fn create_test_lifecycle() -> AgentLifecycle<MockTransport> {
         let config = AgentConfig::test_config();
@@ -866,4 +1560,365 @@ mod tests {
         let transport = lifecycle.transport();
         assert!(transport.is_none()); // Transport moved to pipeline
     }
+
+    #[tokio::test]
+    async fn test_permanent_disconnect_reported_after_start() {
+        let transport = MockTransport::new();
+        // Shares the induced connection state with the transport moved into the pipeline
+        let handle = MockTransport {
+            induced_state: transport.induced_state.clone(),
+            ..MockTransport::default()
+        };
+        let mut lifecycle = AgentLifecycle::new(
+            AgentConfig::test_config(),
+            transport,
+            Box::new(MockLlmProvider::single_response("ok")),
+        );
+        lifecycle.initialize().await.unwrap();
+        lifecycle.start().await.unwrap();
+        assert!(lifecycle.transport().is_none());
+        assert!(!lifecycle.is_permanently_disconnected());
+
+        handle.set_connection_state(ConnectionState::PermanentlyDisconnected(
+            "gave up".to_string(),
+        ));
+
+        assert!(lifecycle.is_permanently_disconnected());
+        tokio::time::timeout(
+            std::time::Duration::from_secs(5),
+            lifecycle.wait_for_permanent_disconnect(),
+        )
+        .await
+        .expect("disconnect should be noticed");
+        lifecycle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_connection_events_drive_health_server_mqtt_flag() {
+        let transport = MockTransport::new();
+        let handle = transport.clone();
+        let mut lifecycle = AgentLifecycle::new(
+            AgentConfig::test_config(),
+            transport,
+            Box::new(MockLlmProvider::single_response("ok")),
+        );
+        let health_server = Arc::new(crate::observability::health::HealthServer::new(
+            "test-agent".to_string(),
+            0,
+        ));
+        lifecycle.set_health_server(health_server.clone());
+        lifecycle.initialize().await.unwrap();
+        lifecycle.start().await.unwrap();
+
+        let until_connected = |connected: bool| {
+            let health_server = health_server.clone();
+            async move {
+                while health_server.is_mqtt_connected() != connected {
+                    tokio::task::yield_now().await;
+                }
+            }
+        };
+        let drive = async {
+            until_connected(true).await;
+            handle.set_connection_state(ConnectionState::Reconnecting(1));
+            until_connected(false).await;
+            handle.set_connection_state(ConnectionState::Connected);
+            until_connected(true).await;
+            handle.set_connection_state(ConnectionState::PermanentlyDisconnected(
+                "gave up".to_string(),
+            ));
+        };
+        tokio::time::timeout(std::time::Duration::from_secs(5), async {
+            tokio::join!(lifecycle.wait_for_permanent_disconnect(), drive)
+        })
+        .await
+        .expect("permanent disconnect should end the wait");
+
+        assert!(!health_server.is_mqtt_connected());
+        lifecycle.shutdown().await.unwrap();
+    }
+
+    fn published_statuses(transport: &MockTransport) -> Vec<AgentStatusType> {
+        transport
+            .calls()
+            .into_iter()
+            .filter_map(|call| match call {
+                TransportCall::PublishStatus(status) => Some(status.status),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_heartbeat_skips_publishing_while_disconnected() {
+        let transport = MockTransport::new();
+        transport.set_connection_state(ConnectionState::Reconnecting(1));
+        let mut config = AgentConfig::test_config();
+        config.mqtt.heartbeat_interval_secs = 1;
+        let status_publisher = Arc::new(StatusPublisher::new(
+            Arc::new(transport.clone()),
+            &config.agent,
+        ));
+        let (_config_tx, config_updates) = watch::channel(config);
+        let handle = AgentLifecycle::<MockTransport>::spawn_heartbeat_task(
+            Arc::new(transport.clone()),
+            status_publisher,
+            "test-agent".to_string(),
+            config_updates,
+        );
+
+        tokio::time::sleep(std::time::Duration::from_millis(3500)).await;
+        assert!(published_statuses(&transport).is_empty());
+
+        transport.set_connection_state(ConnectionState::Connected);
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        assert_eq!(
+            published_statuses(&transport),
+            vec![AgentStatusType::Available]
+        );
+        handle.abort();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_shutdown_publishes_unavailable_last() {
+        let transport = MockTransport::new();
+        let handle = transport.clone();
+        let mut config = AgentConfig::test_config();
+        config.mqtt.heartbeat_interval_secs = 1;
+        let mut lifecycle = AgentLifecycle::new(
+            config,
+            transport,
+            Box::new(MockLlmProvider::single_response("ok")),
+        );
+        lifecycle.initialize().await.unwrap();
+        lifecycle.start().await.unwrap();
+
+        // Let a few heartbeats go out before shutting down
+        tokio::time::sleep(std::time::Duration::from_millis(2500)).await;
+        lifecycle.shutdown().await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_secs(3)).await;
+
+        let statuses = published_statuses(&handle);
+        let heartbeats = statuses
+            .iter()
+            .filter(|status| **status == AgentStatusType::Available)
+            .count();
+        assert!(heartbeats >= 3, "statuses: {statuses:?}");
+        assert_eq!(statuses.last(), Some(&AgentStatusType::Unavailable));
+    }
+
+    #[tokio::test]
+    async fn test_status_sequence_across_start_run_shutdown() {
+        use crate::protocol::messages::{AdminMessage, PauseAgent, TaskEnvelopeWrapper};
+
+        let transport = MockTransport::new();
+        let handle = transport.clone();
+        let config = AgentConfig::test_config();
+        let mut lifecycle = AgentLifecycle::new(
+            config.clone(),
+            transport,
+            Box::new(MockLlmProvider::single_response("ok")),
+        );
+        lifecycle.initialize().await.unwrap();
+        lifecycle.start().await.unwrap();
+
+        // Run a task, then pause and resume twice each
+        let task = crate::protocol::messages::TaskEnvelope {
+            task_id: uuid::Uuid::new_v4(),
+            conversation_id: "status-conversation".to_string(),
+            topic: format!("/control/agents/{}/input", config.agent.id),
+            instruction: Some("Say hi".to_string()),
+            input: serde_json::json!({}),
+            next: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
+        };
+        handle
+            .deliver_task(TaskEnvelopeWrapper::V1(task))
+            .await
+            .unwrap();
+        tokio::time::timeout(std::time::Duration::from_secs(5), async {
+            while handle.published_responses().is_empty() {
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("task should be answered");
+        let admin = handle.admin_sender.lock().await.clone().unwrap();
+        let wait_for_statuses = |count: usize| {
+            let handle = handle.clone();
+            async move {
+                tokio::time::timeout(std::time::Duration::from_secs(5), async {
+                    while published_statuses(&handle).len() < count {
+                        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+                    }
+                })
+                .await
+                .expect("status should be published");
+            }
+        };
+        for _ in 0..2 {
+            admin
+                .send(AdminMessage::PauseAgent(PauseAgent::default()))
+                .await
+                .unwrap();
+        }
+        wait_for_statuses(2).await;
+        for _ in 0..2 {
+            admin.send(AdminMessage::ResumeAgent).await.unwrap();
+        }
+        wait_for_statuses(3).await;
+        // Let the second resume be handled before stopping
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+        lifecycle.shutdown().await.unwrap();
+
+        assert_eq!(
+            published_statuses(&handle),
+            vec![
+                AgentStatusType::Available,
+                AgentStatusType::Paused,
+                AgentStatusType::Available,
+                AgentStatusType::Unavailable,
+            ]
+        );
+        for status in handle.get_published_statuses().await {
+            assert_eq!(
+                status.capabilities.as_ref(),
+                Some(&config.agent.capabilities)
+            );
+            assert_eq!(status.description.as_ref(), Some(&config.agent.description));
+        }
+        assert_eq!(handle.published_responses().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_events_report_completions_and_status_changes() {
+        use crate::protocol::messages::TaskEnvelopeWrapper;
+
+        let transport = MockTransport::new();
+        let handle = transport.clone();
+        let config = AgentConfig::test_config();
+        let mut lifecycle = AgentLifecycle::new(
+            config.clone(),
+            transport,
+            Box::new(MockLlmProvider::single_response("ok")),
+        );
+        // Subscribing before start sees everything from the first status on
+        let mut events = lifecycle.events();
+        lifecycle.initialize().await.unwrap();
+        lifecycle.start().await.unwrap();
+
+        let mut submitted = Vec::new();
+        for n in 0..2 {
+            let task = crate::protocol::messages::TaskEnvelope {
+                task_id: uuid::Uuid::new_v4(),
+                conversation_id: format!("events-conversation-{n}"),
+                topic: format!("/control/agents/{}/input", config.agent.id),
+                instruction: Some("Say hi".to_string()),
+                input: serde_json::json!({}),
+                next: None,
+                deadline: None,
+                correlation_id: None,
+                parent_task_id: None,
+                published_at: None,
+                traceparent: None,
+                sender_id: None,
+            };
+            submitted.push(task.task_id);
+            handle
+                .deliver_task(TaskEnvelopeWrapper::V1(task))
+                .await
+                .unwrap();
+        }
+
+        let mut started = Vec::new();
+        let mut completed = Vec::new();
+        let mut statuses = Vec::new();
+        tokio::time::timeout(std::time::Duration::from_secs(5), async {
+            while completed.len() < submitted.len() {
+                match events.recv().await.unwrap() {
+                    AgentEvent::TaskStarted { task_id, .. } => started.push(task_id),
+                    AgentEvent::TaskCompleted(result) => {
+                        assert_eq!(result.response, "ok");
+                        completed.push(result.task_id);
+                    }
+                    AgentEvent::StatusChanged(status) => statuses.push(status),
+                    other => panic!("unexpected event {other:?}"),
+                }
+            }
+        })
+        .await
+        .expect("both tasks should complete");
+        lifecycle.shutdown().await.unwrap();
+        while let Ok(event) = events.try_recv() {
+            if let AgentEvent::StatusChanged(status) = event {
+                statuses.push(status);
+            }
+        }
+
+        // Tasks of different conversations may run concurrently
+        submitted.sort();
+        started.sort();
+        completed.sort();
+        assert_eq!(started, submitted);
+        assert_eq!(completed, submitted);
+        assert_eq!(
+            statuses,
+            vec![AgentStatusType::Available, AgentStatusType::Unavailable]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reload_config_applies_reloadable_fields_only() {
+        let mut lifecycle = create_test_lifecycle();
+        lifecycle.start().await.unwrap();
+
+        let mut candidate = AgentConfig::test_config();
+        candidate.llm.system_prompt = "You are terse.".to_string();
+        candidate.agent.id = "renamed-agent".to_string();
+        candidate.tools.insert(
+            "file_read".to_string(),
+            crate::config::ToolConfig::Simple("builtin".to_string()),
+        );
+
+        let reload = lifecycle.reload_config(candidate).await.unwrap();
+
+        assert_eq!(reload.applied, vec!["llm.system_prompt", "tools"]);
+        assert_eq!(reload.rejected, vec!["agent.id"]);
+        let current = lifecycle.current_config();
+        assert_eq!(current.llm.system_prompt, "You are terse.");
+        assert_eq!(current.agent.id, "test-agent");
+        let processor = lifecycle.running_processor.as_ref().unwrap();
+        assert_eq!(
+            processor.nine_step_processor().tool_system().list_tools(),
+            vec!["file_read".to_string()]
+        );
+
+        lifecycle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_reload_config_keeps_running_config_when_tools_fail() {
+        let mut lifecycle = create_test_lifecycle();
+        lifecycle.start().await.unwrap();
+
+        let mut candidate = AgentConfig::test_config();
+        candidate.llm.system_prompt = "You are terse.".to_string();
+        candidate.tools.insert(
+            "unknown_tool".to_string(),
+            crate::config::ToolConfig::Simple("builtin".to_string()),
+        );
+
+        let result = lifecycle.reload_config(candidate).await;
+
+        assert!(matches!(result, Err(LifecycleError::ConfigurationError(_))));
+        assert_eq!(lifecycle.current_config(), AgentConfig::test_config());
+
+        lifecycle.shutdown().await.unwrap();
+    }
 }
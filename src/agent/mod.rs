@@ -5,16 +5,26 @@
 
 pub mod discovery;
 pub mod discovery_integration;
+pub mod events;
+pub mod host;
 pub mod lifecycle;
 pub mod pipeline;
 pub mod processor;
 pub mod response;
 pub mod route_decision;
+pub mod status_publisher;
+pub mod task_processor;
+pub mod workflow;
 
 pub use discovery::*;
 pub use discovery_integration::*;
+pub use events::*;
+pub use host::*;
 pub use lifecycle::*;
 pub use pipeline::*;
 pub use processor::*;
 pub use response::*;
 pub use route_decision::*;
+pub use status_publisher::*;
+pub use task_processor::*;
+pub use workflow::*;
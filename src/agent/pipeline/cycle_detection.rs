@@ -0,0 +1,185 @@
+//! Workflow cycle detection
+//!
+//! Routers can send a workflow around in circles (A→B→A→B…) until
+//! `max_iterations` stops it, paying for an LLM call on every hop. These pure
+//! functions spot a repeating tail in the workflow history early so the
+//! pipeline can complete the workflow instead of forwarding again.
+
+use crate::protocol::messages::WorkflowStep;
+use serde_json::{json, Value};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+/// Default number of back-to-back repetitions a pattern may make
+pub const DEFAULT_CYCLE_REPEAT_THRESHOLD: usize = 2;
+
+/// A repeating pattern found at the end of the workflow history
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DetectedCycle {
+    /// Agents in one turn of the cycle, oldest first
+    pub agents: Vec<String>,
+    /// Number of consecutive repetitions observed
+    pub repeats: usize,
+}
+
+/// Hash an instruction, ignoring case and surrounding whitespace - pure function
+fn instruction_hash(instruction: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    instruction.trim().to_lowercase().hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Count back-to-back repetitions of the trailing block of length `period` - pure function
+fn trailing_repeats<K: PartialEq>(sequence: &[K], period: usize) -> usize {
+    let len = sequence.len();
+    let block = &sequence[len - period..];
+    let mut repeats = 1;
+    while (repeats + 1) * period <= len {
+        let start = len - (repeats + 1) * period;
+        if &sequence[start..start + period] != block {
+            break;
+        }
+        repeats += 1;
+    }
+    repeats
+}
+
+/// Find the shortest period whose trailing block repeats more than `threshold` times
+fn find_repeating_period<K: PartialEq>(
+    sequence: &[K],
+    max_period: usize,
+    threshold: usize,
+) -> Option<(usize, usize)> {
+    (1..=max_period.min(sequence.len() / (threshold + 1))).find_map(|period| {
+        let repeats = trailing_repeats(sequence, period);
+        (repeats > threshold).then_some((period, repeats))
+    })
+}
+
+/// Detect a cycle at the end of the workflow history - pure function
+///
+/// Two patterns are recognised, each once it repeats more than `threshold`
+/// times back to back:
+/// - any sequence of (agent, instruction) hops, e.g. A→B→C→A→B→C with the
+///   same instructions each turn
+/// - agents ping-ponging (or an agent forwarding to itself), even when the
+///   router words each instruction differently
+pub fn detect_cycle(steps: &[WorkflowStep], threshold: usize) -> Option<DetectedCycle> {
+    let threshold = threshold.max(1);
+    let hops: Vec<(&str, u64)> = steps
+        .iter()
+        .map(|step| (step.agent_id.as_str(), instruction_hash(&step.action)))
+        .collect();
+    let agents: Vec<&str> = hops.iter().map(|(agent, _)| *agent).collect();
+
+    let (period, repeats) = find_repeating_period(&hops, hops.len(), threshold)
+        .or_else(|| find_repeating_period(&agents, 2, threshold))?;
+
+    Some(DetectedCycle {
+        agents: agents[agents.len() - period..]
+            .iter()
+            .map(|agent| agent.to_string())
+            .collect(),
+        repeats,
+    })
+}
+
+/// Fields of an object output, or any other output wrapped under `output` - pure function
+pub(super) fn output_fields(output: Value) -> serde_json::Map<String, Value> {
+    match output {
+        Value::Object(map) => map,
+        other => {
+            let mut map = serde_json::Map::new();
+            map.insert("output".to_string(), other);
+            map
+        }
+    }
+}
+
+/// Mark a final output as ended by cycle detection - pure function
+///
+/// Object outputs gain `cycle_detected` and `cycle_agents` fields; any other
+/// output is wrapped under `output`.
+pub fn annotate_cycle(output: Value, cycle: &DetectedCycle) -> Value {
+    let mut annotated = output_fields(output);
+    annotated.insert("cycle_detected".to_string(), json!(true));
+    annotated.insert("cycle_agents".to_string(), json!(cycle.agents));
+    Value::Object(annotated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn steps(hops: &[(&str, &str)]) -> Vec<WorkflowStep> {
+        hops.iter()
+            .map(|(agent, action)| WorkflowStep {
+                agent_id: agent.to_string(),
+                action: action.to_string(),
+                timestamp: "2024-01-01T00:00:00Z".to_string(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_ping_pong_detected_despite_reworded_instructions() {
+        let history = steps(&[
+            ("writer", "Review the draft"),
+            ("editor", "Revise paragraph two"),
+            ("writer", "Please review again"),
+            ("editor", "Revise the intro"),
+        ]);
+
+        assert_eq!(
+            detect_cycle(&history, 1),
+            Some(DetectedCycle {
+                agents: vec!["writer".to_string(), "editor".to_string()],
+                repeats: 2,
+            })
+        );
+        // One more turn is allowed at the default threshold
+        assert_eq!(detect_cycle(&history, DEFAULT_CYCLE_REPEAT_THRESHOLD), None);
+    }
+
+    #[test]
+    fn test_three_agent_cycle_detected() {
+        let turn = [("a", "Research"), ("b", "Write"), ("c", "Edit")];
+        let history = steps(&[turn, turn, turn].concat());
+
+        let cycle = detect_cycle(&history, 2).expect("cycle should be detected");
+        assert_eq!(cycle.agents, vec!["a", "b", "c"]);
+        assert_eq!(cycle.repeats, 3);
+        assert_eq!(detect_cycle(&history[..6], 2), None);
+    }
+
+    #[test]
+    fn test_progressing_workflow_is_not_a_cycle() {
+        let history = steps(&[
+            ("research", "Find sources"),
+            ("writer", "Write draft"),
+            ("editor", "Polish"),
+            ("writer", "Apply edits"),
+            ("judge", "Score"),
+        ]);
+
+        assert_eq!(detect_cycle(&history, 1), None);
+        assert_eq!(detect_cycle(&[], 1), None);
+    }
+
+    #[test]
+    fn test_annotate_cycle() {
+        let cycle = DetectedCycle {
+            agents: vec!["a".to_string(), "b".to_string()],
+            repeats: 2,
+        };
+
+        let annotated = annotate_cycle(json!({"draft": "text"}), &cycle);
+        assert_eq!(annotated["draft"], "text");
+        assert_eq!(annotated["cycle_detected"], true);
+        assert_eq!(annotated["cycle_agents"], json!(["a", "b"]));
+
+        let wrapped = annotate_cycle(json!("plain"), &cycle);
+        assert_eq!(wrapped["output"], "plain");
+        assert_eq!(wrapped["cycle_detected"], true);
+    }
+}
@@ -0,0 +1,634 @@
+//! Fan-out and fan-in of parallel workflow branches
+//!
+//! A router's `FanOut` decision sends each branch to its agent at once. Every
+//! branch runs in a conversation of its own, derived from the workflow's
+//! conversation and the fan-out's id, so the fanning-out agent can tell the
+//! branches' results apart by the conversation topic they are published on,
+//! however many agents a branch passes through.
+//!
+//! The [`FanInTracker`] holds a [`FanOutJoin`] per fan-out until it settles.
+//! A join task follows the branch conversations, records each branch's
+//! response or error, and once the [`JoinPolicy`] is met - or its timeout
+//! passes - sends the branches' outputs to the aggregator agent, or publishes
+//! an error to the workflow's conversation when too few branches succeeded.
+
+use crate::protocol::messages::{
+    ErrorCode, ErrorDetails, ErrorMessage, ResponseMessage, TaskEnvelopeV2, TaskEnvelopeWrapper,
+};
+use crate::routing::JoinPolicy;
+use crate::transport::mqtt::codec::PayloadCodec;
+use crate::transport::{TopicMessage, Transport};
+use futures::stream::{self, StreamExt};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+/// Conversation a branch of a fan-out runs in (pure function)
+pub fn branch_conversation_id(conversation_id: &str, fanout_id: Uuid, branch: usize) -> String {
+    format!("{conversation_id}.fanout.{fanout_id}.{branch}")
+}
+
+/// Topic filter matching the responses and errors of a branch conversation
+pub fn branch_topic_filter(branch_conversation_id: &str) -> String {
+    format!("/conversations/{branch_conversation_id}/+")
+}
+
+/// A branch of a fan-out, as published
+#[derive(Debug, Clone, PartialEq)]
+pub struct FanOutBranch {
+    /// Agent the branch was sent to
+    pub agent_id: String,
+    pub instruction: String,
+    /// Conversation the branch runs in
+    pub conversation_id: String,
+}
+
+/// How a branch ended
+#[derive(Debug, Clone, PartialEq)]
+pub enum BranchOutcome {
+    /// The branch's workflow published a response
+    Succeeded(Box<ResponseMessage>),
+    /// The branch's workflow published an error, or its task was never sent
+    Failed(ErrorDetails),
+}
+
+impl BranchOutcome {
+    /// Outcome carried by a message on a branch conversation topic
+    ///
+    /// Progress updates and anything that is neither a response nor an error
+    /// carry none.
+    pub fn from_message(topic: &str, payload: &[u8]) -> Option<Self> {
+        if topic.ends_with("/progress") {
+            return None;
+        }
+        let value = PayloadCodec::decode_value(payload, None).ok()?;
+        if let Ok(error) = serde_json::from_value::<ErrorMessage>(value.clone()) {
+            return Some(Self::Failed(error.error));
+        }
+        serde_json::from_value::<ResponseMessage>(value)
+            .ok()
+            .map(|response| Self::Succeeded(Box::new(response)))
+    }
+}
+
+/// What a join does next
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JoinStatus {
+    /// Waiting for more branches
+    Waiting,
+    /// Send the successful branches to the aggregator; `complete` when the
+    /// quorum was met
+    Aggregate { complete: bool },
+    /// Too few branches succeeded
+    Failed,
+}
+
+/// Join state of one fan-out
+#[derive(Debug, Clone)]
+pub struct FanOutJoin {
+    pub fanout_id: Uuid,
+    pub branches: Vec<FanOutBranch>,
+    outcomes: Vec<Option<BranchOutcome>>,
+    policy: JoinPolicy,
+}
+
+impl FanOutJoin {
+    pub fn new(fanout_id: Uuid, branches: Vec<FanOutBranch>, policy: JoinPolicy) -> Self {
+        Self {
+            fanout_id,
+            outcomes: vec![None; branches.len()],
+            branches,
+            policy,
+        }
+    }
+
+    /// Record how a branch ended, returning false if it had already ended
+    pub fn record(&mut self, branch: usize, outcome: BranchOutcome) -> bool {
+        match self.outcomes.get_mut(branch) {
+            Some(slot @ None) => {
+                *slot = Some(outcome);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Branch whose conversation `topic` belongs to
+    pub fn branch_for_topic(&self, topic: &str) -> Option<usize> {
+        self.branches.iter().position(|branch| {
+            topic
+                .strip_prefix("/conversations/")
+                .and_then(|rest| rest.strip_prefix(branch.conversation_id.as_str()))
+                .is_some_and(|rest| rest.starts_with('/'))
+        })
+    }
+
+    pub fn succeeded(&self) -> usize {
+        self.outcomes
+            .iter()
+            .filter(|outcome| matches!(outcome, Some(BranchOutcome::Succeeded(_))))
+            .count()
+    }
+
+    /// Branches that have not ended yet
+    pub fn pending(&self) -> usize {
+        self.outcomes
+            .iter()
+            .filter(|outcome| outcome.is_none())
+            .count()
+    }
+
+    /// Where the join stands (pure function)
+    pub fn status(&self) -> JoinStatus {
+        let required = self.policy.required(self.branches.len());
+        let succeeded = self.succeeded();
+        if succeeded >= required {
+            JoinStatus::Aggregate { complete: true }
+        } else if self.pending() == 0 {
+            self.incomplete()
+        } else if succeeded + self.pending() < required && !self.policy.allow_partial {
+            // The quorum can no longer be met and nothing less will do
+            JoinStatus::Failed
+        } else {
+            JoinStatus::Waiting
+        }
+    }
+
+    /// Where the join stands once its timeout has passed (pure function)
+    pub fn status_at_timeout(&self) -> JoinStatus {
+        match self.status() {
+            JoinStatus::Waiting => self.incomplete(),
+            settled => settled,
+        }
+    }
+
+    fn incomplete(&self) -> JoinStatus {
+        if self.policy.allow_partial && self.succeeded() > 0 {
+            JoinStatus::Aggregate { complete: false }
+        } else {
+            JoinStatus::Failed
+        }
+    }
+
+    /// Input of the aggregation task: every branch with its status and output
+    ///
+    /// Branches that had not ended are `timed_out` when the join timed out,
+    /// and `pending` when the quorum was met without them.
+    pub fn aggregation_input(&self, timed_out: bool) -> Value {
+        let branches: Vec<Value> = self
+            .branches
+            .iter()
+            .zip(&self.outcomes)
+            .enumerate()
+            .map(|(index, (branch, outcome))| {
+                let mut entry = json!({
+                    "branch": index,
+                    "agent_id": branch.agent_id,
+                    "instruction": branch.instruction,
+                });
+                match outcome {
+                    Some(BranchOutcome::Succeeded(response)) => {
+                        entry["status"] = json!("succeeded");
+                        entry["output"] = Self::output(&response.response);
+                        if !response.artifacts.is_empty() {
+                            entry["artifacts"] = json!(response.artifacts);
+                        }
+                    }
+                    Some(BranchOutcome::Failed(error)) => {
+                        entry["status"] = json!("failed");
+                        entry["error"] = json!({
+                            "code": error.code.as_str(),
+                            "message": error.message,
+                        });
+                    }
+                    None if timed_out => entry["status"] = json!("timed_out"),
+                    None => entry["status"] = json!("pending"),
+                }
+                entry
+            })
+            .collect();
+        json!({
+            "fanout_id": self.fanout_id,
+            "complete": self.status() == JoinStatus::Aggregate { complete: true },
+            "branches": branches,
+        })
+    }
+
+    /// A branch's response text, as JSON when it holds an object or array
+    fn output(response: &str) -> Value {
+        serde_json::from_str::<Value>(response)
+            .ok()
+            .filter(|value| value.is_object() || value.is_array())
+            .unwrap_or_else(|| Value::String(response.to_string()))
+    }
+
+    /// Error published to the workflow's conversation when the join fails
+    pub fn failure(&self, timed_out: bool) -> ErrorDetails {
+        let required = self.policy.required(self.branches.len());
+        let summary = format!(
+            "Fan-out {} failed: {} of {} branches succeeded, {required} required",
+            self.fanout_id,
+            self.succeeded(),
+            self.branches.len(),
+        );
+        if timed_out {
+            return ErrorDetails::new(
+                ErrorCode::Timeout,
+                format!(
+                    "{summary}; timed out after {}s waiting for {}",
+                    self.policy.timeout_secs,
+                    self.pending()
+                ),
+            );
+        }
+        let last_error = self
+            .outcomes
+            .iter()
+            .rev()
+            .find_map(|outcome| match outcome {
+                Some(BranchOutcome::Failed(error)) => Some(error),
+                _ => None,
+            });
+        match last_error {
+            Some(error) => ErrorDetails::new(
+                error.code.clone(),
+                format!("{summary}; last error: {}", error.message),
+            ),
+            None => ErrorDetails::new(ErrorCode::InternalError, summary),
+        }
+    }
+}
+
+/// Fan-outs waiting for their branches, shared by the pipeline and the join
+/// tasks
+#[derive(Debug, Default)]
+pub struct FanInTracker {
+    joins: Mutex<HashMap<Uuid, FanOutJoin>>,
+}
+
+impl FanInTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(&self, join: FanOutJoin) {
+        self.joins.lock().unwrap().insert(join.fanout_id, join);
+    }
+
+    /// Record how a branch ended, returning where its join now stands, or
+    /// None if the fan-out is not waiting or the branch had already ended
+    pub fn record(
+        &self,
+        fanout_id: Uuid,
+        branch: usize,
+        outcome: BranchOutcome,
+    ) -> Option<JoinStatus> {
+        let mut joins = self.joins.lock().unwrap();
+        let join = joins.get_mut(&fanout_id)?;
+        join.record(branch, outcome).then(|| join.status())
+    }
+
+    /// Branch of a waiting fan-out whose conversation `topic` belongs to
+    pub fn branch_for_topic(&self, fanout_id: Uuid, topic: &str) -> Option<usize> {
+        self.joins
+            .lock()
+            .unwrap()
+            .get(&fanout_id)
+            .and_then(|join| join.branch_for_topic(topic))
+    }
+
+    /// Stop waiting for a fan-out, returning its join
+    pub fn take(&self, fanout_id: Uuid) -> Option<FanOutJoin> {
+        self.joins.lock().unwrap().remove(&fanout_id)
+    }
+
+    /// Ids of the fan-outs still waiting for branches
+    pub fn pending(&self) -> Vec<Uuid> {
+        self.joins.lock().unwrap().keys().copied().collect()
+    }
+}
+
+/// A fan-out whose branches were published, waiting to be joined
+pub(crate) struct JoinTask<T: Transport> {
+    pub transport: Arc<T>,
+    pub tracker: Arc<FanInTracker>,
+    pub fanout_id: Uuid,
+    /// Subscriptions to the branch conversations, with their filters
+    pub subscriptions: Vec<(String, mpsc::Receiver<TopicMessage>)>,
+    pub timeout: Duration,
+    /// Task for the aggregator, sent with the joined outputs as its input
+    pub aggregation_task: TaskEnvelopeV2,
+    pub aggregator_agent: String,
+    /// Task whose routing fanned out, which a failed join is reported against
+    pub origin: TaskEnvelopeV2,
+}
+
+impl<T: Transport + 'static> JoinTask<T> {
+    /// Follow the branches until the join settles or times out, then
+    /// aggregate or report the failure
+    pub async fn run(mut self) {
+        let subscriptions = std::mem::take(&mut self.subscriptions);
+        let filters: Vec<String> = subscriptions
+            .iter()
+            .map(|(filter, _)| filter.clone())
+            .collect();
+        let mut messages = stream::select_all(subscriptions.into_iter().map(|(_, receiver)| {
+            stream::unfold(receiver, |mut receiver| async move {
+                receiver.recv().await.map(|message| (message, receiver))
+            })
+            .boxed()
+        }));
+
+        // Branches that failed to publish may already have settled the join
+        let mut status = self
+            .tracker
+            .joins
+            .lock()
+            .unwrap()
+            .get(&self.fanout_id)
+            .map_or(JoinStatus::Failed, FanOutJoin::status);
+        let deadline = tokio::time::Instant::now() + self.timeout;
+        let mut timed_out = false;
+        while status == JoinStatus::Waiting {
+            let message = tokio::time::timeout_at(deadline, messages.next()).await;
+            let Ok(Some((topic, payload))) = message else {
+                // Timed out, or every subscription closed
+                timed_out = true;
+                break;
+            };
+            let Some(branch) = self.tracker.branch_for_topic(self.fanout_id, &topic) else {
+                continue;
+            };
+            let Some(outcome) = BranchOutcome::from_message(&topic, &payload) else {
+                continue;
+            };
+            debug!(
+                fanout_id = %self.fanout_id,
+                branch,
+                succeeded = matches!(outcome, BranchOutcome::Succeeded(_)),
+                "Fan-out branch ended"
+            );
+            if let Some(updated) = self.tracker.record(self.fanout_id, branch, outcome) {
+                status = updated;
+            }
+        }
+        drop(messages);
+
+        for filter in &filters {
+            if let Err(e) = self.transport.unsubscribe(filter).await {
+                warn!(topic = %filter, error = %e, "Failed to unsubscribe from fan-out branch");
+            }
+        }
+        let Some(join) = self.tracker.take(self.fanout_id) else {
+            return;
+        };
+        let status = if timed_out {
+            join.status_at_timeout()
+        } else {
+            status
+        };
+        match status {
+            JoinStatus::Aggregate { complete } => {
+                self.aggregate(&join, complete, timed_out).await;
+            }
+            JoinStatus::Failed | JoinStatus::Waiting => self.fail(&join, timed_out).await,
+        }
+    }
+
+    async fn aggregate(&self, join: &FanOutJoin, complete: bool, timed_out: bool) {
+        let mut task = self.aggregation_task.clone();
+        task.input = join.aggregation_input(timed_out);
+        info!(
+            fanout_id = %self.fanout_id,
+            aggregator = %self.aggregator_agent,
+            succeeded = join.succeeded(),
+            branches = join.branches.len(),
+            complete,
+            "Fan-out joined, sending branch outputs to aggregator"
+        );
+        if let Err(e) = self
+            .transport
+            .publish_task(&self.aggregator_agent, &TaskEnvelopeWrapper::V2(task))
+            .await
+        {
+            warn!(
+                fanout_id = %self.fanout_id,
+                aggregator = %self.aggregator_agent,
+                error = %e,
+                "Failed to send fan-out to aggregator"
+            );
+            let error = ErrorDetails::new(
+                ErrorCode::InternalError,
+                format!(
+                    "Fan-out {} joined but could not be sent to {}: {e}",
+                    self.fanout_id, self.aggregator_agent
+                ),
+            );
+            self.publish_error(error).await;
+        }
+    }
+
+    async fn fail(&self, join: &FanOutJoin, timed_out: bool) {
+        let error = join.failure(timed_out);
+        warn!(fanout_id = %self.fanout_id, error = %error.message, "Fan-out failed");
+        self.publish_error(error).await;
+    }
+
+    async fn publish_error(&self, error: ErrorDetails) {
+        let message = ErrorMessage {
+            error,
+            task_id: self.origin.task_id,
+            correlation_id: self.origin.correlation_id.clone(),
+            parent_task_id: self.origin.parent_task_id,
+        };
+        if let Err(e) = self
+            .transport
+            .publish_error(&self.origin.conversation_id, &message)
+            .await
+        {
+            warn!(fanout_id = %self.fanout_id, error = %e, "Failed to publish fan-out error");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn branches(count: usize) -> Vec<FanOutBranch> {
+        (0..count)
+            .map(|index| FanOutBranch {
+                agent_id: format!("agent-{index}"),
+                instruction: format!("Review part {index}"),
+                conversation_id: branch_conversation_id("conv", Uuid::nil(), index),
+            })
+            .collect()
+    }
+
+    fn join(count: usize, quorum: Option<usize>, allow_partial: bool) -> FanOutJoin {
+        FanOutJoin::new(
+            Uuid::nil(),
+            branches(count),
+            JoinPolicy {
+                quorum,
+                allow_partial,
+                ..JoinPolicy::default()
+            },
+        )
+    }
+
+    fn succeeded(text: &str) -> BranchOutcome {
+        BranchOutcome::Succeeded(Box::new(ResponseMessage {
+            response: text.to_string(),
+            task_id: Uuid::new_v4(),
+            routing_trace: None,
+            correlation_id: None,
+            parent_task_id: None,
+            conversation_id: None,
+            agent_id: None,
+            completed_at: None,
+            oversized: None,
+            artifacts: Vec::new(),
+        }))
+    }
+
+    fn failed(message: &str) -> BranchOutcome {
+        BranchOutcome::Failed(ErrorDetails::new(ErrorCode::LlmError, message))
+    }
+
+    #[test]
+    fn test_all_branches_complete_the_join() {
+        let mut join = join(2, None, true);
+
+        assert!(join.record(0, succeeded("first")));
+        assert_eq!(join.status(), JoinStatus::Waiting);
+        assert!(join.record(1, succeeded(r#"{"score": 7}"#)));
+        assert_eq!(join.status(), JoinStatus::Aggregate { complete: true });
+
+        let input = join.aggregation_input(false);
+        assert_eq!(input["complete"], true);
+        assert_eq!(input["branches"][0]["output"], "first");
+        assert_eq!(input["branches"][1]["output"], json!({"score": 7}));
+        assert_eq!(input["branches"][1]["agent_id"], "agent-1");
+    }
+
+    #[test]
+    fn test_branch_outcome_is_recorded_once() {
+        let mut join = join(2, None, true);
+
+        assert!(join.record(0, failed("boom")));
+        assert!(!join.record(0, succeeded("late")));
+        assert!(!join.record(5, succeeded("unknown")));
+        assert_eq!(join.succeeded(), 0);
+    }
+
+    #[test]
+    fn test_quorum_completes_the_join_early() {
+        let mut join = join(3, Some(2), true);
+        join.record(0, succeeded("a"));
+        join.record(2, succeeded("c"));
+
+        assert_eq!(join.status(), JoinStatus::Aggregate { complete: true });
+        assert_eq!(
+            join.aggregation_input(false)["branches"][1]["status"],
+            "pending"
+        );
+    }
+
+    #[test]
+    fn test_failed_branch_leaves_a_partial_join() {
+        let mut join = join(2, None, true);
+        join.record(0, failed("model unavailable"));
+        assert_eq!(join.status(), JoinStatus::Waiting);
+        join.record(1, succeeded("b"));
+
+        assert_eq!(join.status(), JoinStatus::Aggregate { complete: false });
+        let input = join.aggregation_input(false);
+        assert_eq!(input["complete"], false);
+        assert_eq!(input["branches"][0]["status"], "failed");
+        assert_eq!(input["branches"][0]["error"]["code"], "llm_error");
+    }
+
+    #[test]
+    fn test_join_fails_once_the_quorum_is_out_of_reach() {
+        let mut join = join(3, None, false);
+        join.record(1, failed("model unavailable"));
+
+        assert_eq!(join.status(), JoinStatus::Failed);
+        let error = join.failure(false);
+        assert_eq!(error.code, ErrorCode::LlmError);
+        assert!(error
+            .message
+            .contains("0 of 3 branches succeeded, 3 required"));
+        assert!(error.message.contains("model unavailable"));
+    }
+
+    #[test]
+    fn test_timeout_aggregates_what_arrived() {
+        let mut join = join(3, None, true);
+        join.record(0, succeeded("a"));
+
+        assert_eq!(join.status(), JoinStatus::Waiting);
+        assert_eq!(
+            join.status_at_timeout(),
+            JoinStatus::Aggregate { complete: false }
+        );
+        assert_eq!(
+            join.aggregation_input(true)["branches"][2]["status"],
+            "timed_out"
+        );
+    }
+
+    #[test]
+    fn test_timeout_without_successes_fails() {
+        let join = join(2, None, true);
+
+        assert_eq!(join.status_at_timeout(), JoinStatus::Failed);
+        assert_eq!(join.failure(true).code, ErrorCode::Timeout);
+    }
+
+    #[test]
+    fn test_branch_for_topic() {
+        let join = join(2, None, true);
+        let conversation = branch_conversation_id("conv", Uuid::nil(), 1);
+
+        assert_eq!(
+            join.branch_for_topic(&format!("/conversations/{conversation}/agent-1")),
+            Some(1)
+        );
+        assert_eq!(join.branch_for_topic("/conversations/conv/agent-1"), None);
+        assert_eq!(
+            join.branch_for_topic(&format!("/conversations/{conversation}0/agent-1")),
+            None
+        );
+    }
+
+    #[test]
+    fn test_branch_outcome_from_message() {
+        let error = serde_json::to_vec(&json!({
+            "error": {"code": "llm_error", "message": "boom"},
+            "task_id": Uuid::nil(),
+        }))
+        .unwrap();
+        let response = serde_json::to_vec(&json!({
+            "response": "done",
+            "task_id": Uuid::nil(),
+        }))
+        .unwrap();
+
+        assert!(matches!(
+            BranchOutcome::from_message("/conversations/c/agent", &error),
+            Some(BranchOutcome::Failed(_))
+        ));
+        assert!(matches!(
+            BranchOutcome::from_message("/conversations/c/agent", &response),
+            Some(BranchOutcome::Succeeded(_))
+        ));
+        assert!(BranchOutcome::from_message("/conversations/c/progress", &response).is_none());
+        assert!(BranchOutcome::from_message("/conversations/c/agent", b"{}").is_none());
+    }
+}
@@ -0,0 +1,89 @@
+//! Idle Tracking
+//!
+//! Most agents in a fleet sit idle for long stretches while holding open LLM
+//! connection pools and tool state. The run loop goes idle once no work has
+//! been in flight for `agent.idle_after_secs`, releasing those resources;
+//! the next task wakes it and re-acquires them lazily.
+
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Idle state of the run loop
+#[derive(Debug)]
+pub(crate) struct IdleState {
+    /// Quiet period before going idle; `None` never goes idle
+    idle_after: Option<Duration>,
+    /// When work last started or finished
+    last_active: Instant,
+    idle: bool,
+}
+
+impl IdleState {
+    pub fn new(idle_after: Option<Duration>, now: Instant) -> Self {
+        Self {
+            idle_after,
+            last_active: now,
+            idle: false,
+        }
+    }
+
+    /// When the loop goes idle if nothing happens first
+    ///
+    /// `None` while work is in flight, when already idle, or when disabled.
+    pub fn idle_at(&self, in_flight: usize) -> Option<Instant> {
+        if self.idle || in_flight > 0 {
+            return None;
+        }
+        self.idle_after
+            .map(|idle_after| self.last_active + idle_after)
+    }
+
+    /// Enter the idle state
+    pub fn go_idle(&mut self) {
+        self.idle = true;
+    }
+
+    /// Record work starting or finishing; returns whether this ended an idle period
+    pub fn touch(&mut self, now: Instant) -> bool {
+        self.last_active = now;
+        std::mem::replace(&mut self.idle, false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_idle_deadline_follows_last_activity() {
+        let start = Instant::now();
+        let mut state = IdleState::new(Some(Duration::from_secs(60)), start);
+        assert_eq!(state.idle_at(0), Some(start + Duration::from_secs(60)));
+        // Work in flight keeps the loop active however long it takes
+        assert_eq!(state.idle_at(1), None);
+
+        let later = start + Duration::from_secs(30);
+        assert!(!state.touch(later));
+        assert_eq!(state.idle_at(0), Some(later + Duration::from_secs(60)));
+    }
+
+    #[test]
+    fn test_touch_ends_idle_period() {
+        let start = Instant::now();
+        let mut state = IdleState::new(Some(Duration::from_secs(60)), start);
+
+        state.go_idle();
+        assert_eq!(state.idle_at(0), None);
+
+        let woken = start + Duration::from_secs(90);
+        assert!(state.touch(woken));
+        assert_eq!(state.idle_at(0), Some(woken + Duration::from_secs(60)));
+        assert!(!state.touch(woken));
+    }
+
+    #[test]
+    fn test_disabled_never_goes_idle() {
+        let state = IdleState::new(None, Instant::now());
+        assert_eq!(state.idle_at(0), None);
+    }
+}
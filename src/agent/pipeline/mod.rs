@@ -3,12 +3,17 @@
 //! This module provides focused components for agent task processing,
 //! separating pure business logic from I/O operations.
 
+pub mod cycle_detection;
+pub mod fan_out;
+mod idle;
 pub mod nine_step_executor;
+mod pause;
 pub mod pipeline_orchestrator;
+pub mod workflow_budget;
 
 // Re-export public types for convenience
+pub use fan_out::FanInTracker;
 pub use nine_step_executor::NineStepExecutor;
-// TaskProcessor is internal implementation detail, not exported
 pub use pipeline_orchestrator::AgentPipeline;
 
 // Re-export error types
@@ -71,11 +71,13 @@ impl NineStepExecutor {
         max_depth: usize,
     ) -> ErrorMessage {
         ErrorMessage {
-            error: crate::protocol::messages::ErrorDetails {
-                code: crate::protocol::messages::ErrorCode::PipelineDepthExceeded,
-                message: format!("Pipeline depth {depth} exceeds maximum {max_depth}"),
-            },
+            error: crate::protocol::messages::ErrorDetails::new(
+                crate::protocol::messages::ErrorCode::PipelineDepthExceeded,
+                format!("Pipeline depth {depth} exceeds maximum {max_depth}"),
+            ),
             task_id,
+            correlation_id: None,
+            parent_task_id: None,
         }
     }
 
@@ -87,6 +89,14 @@ impl NineStepExecutor {
         crate::protocol::ResponseMessage {
             response: response.to_string(),
             task_id,
+            routing_trace: None,
+            correlation_id: None,
+            parent_task_id: None,
+            conversation_id: None,
+            agent_id: None,
+            completed_at: None,
+            oversized: None,
+            artifacts: Vec::new(),
         }
     }
 
@@ -118,6 +128,12 @@ impl NineStepExecutor {
                     next: nested.next.clone(),
                 })
             }),
+            deadline: original_task.deadline,
+            correlation_id: original_task.correlation_id.clone(),
+            parent_task_id: Some(original_task.task_id),
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
         }
     }
 
@@ -178,10 +194,6 @@ impl NineStepExecutor {
     }
 }
 
-// Note: AgentProcessor handles all task processing for the v1.0 protocol.
-// TaskProcessor was considered during refactoring but determined unnecessary.
-// The pipeline orchestrator calls AgentProcessor directly for clean separation of concerns.
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -210,6 +222,12 @@ mod tests {
             instruction: Some("Test".to_string()),
             input: serde_json::json!("Test"),
             next: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
         };
 
         assert_eq!(NineStepExecutor::calculate_pipeline_depth(&task), 0);
@@ -234,6 +252,12 @@ mod tests {
             instruction: Some("Test".to_string()),
             input: serde_json::json!("Test"),
             next: Some(next_task),
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
         };
 
         // Should be 2 nested next tasks
@@ -273,6 +297,12 @@ mod tests {
             instruction: None,
             input: serde_json::Value::Null,
             next: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
         };
         assert!(NineStepExecutor::is_final_task(&task));
 
@@ -289,6 +319,12 @@ mod tests {
                 input: None,
                 next: None,
             })),
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
         };
         assert!(!NineStepExecutor::is_final_task(&task_with_next));
     }
@@ -0,0 +1,180 @@
+//! Pausing Task Intake
+//!
+//! A `PauseAgent` admin request stops the run loop from starting new work
+//! until a `ResumeAgent` request arrives or the optional auto-resume deadline
+//! passes. The run loop keeps reading its channels while paused, because the
+//! transport blocks on a full channel and would otherwise never deliver the
+//! resume request; work read while paused is held here in arrival order.
+
+use crate::protocol::messages::{PauseAgent, TaskBatchEnvelope, TaskEnvelopeWrapper};
+use std::collections::VecDeque;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Work waiting to start ahead of the channels: received while paused, or
+/// recovered from the task journal
+#[derive(Debug)]
+pub(crate) enum HeldWork {
+    Task(Box<TaskEnvelopeWrapper>),
+    Batch(TaskBatchEnvelope),
+}
+
+/// Pause state of the run loop
+#[derive(Debug, Default)]
+pub(crate) struct PauseState {
+    paused: bool,
+    /// Accepted work is still finishing; the paused status waits for it
+    draining: bool,
+    resume_at: Option<Instant>,
+    held: VecDeque<HeldWork>,
+}
+
+impl PauseState {
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// When the pause ends on its own, if ever
+    pub fn resume_at(&self) -> Option<Instant> {
+        self.resume_at
+    }
+
+    pub fn held_len(&self) -> usize {
+        self.held.len()
+    }
+
+    /// Enter (or re-enter) the paused state
+    ///
+    /// Returns whether the agent is paused right away; with `drain` and work
+    /// in flight the paused state is only reached once [`Self::finish_draining`]
+    /// reports it. A repeated pause replaces the auto-resume deadline.
+    pub fn pause(&mut self, request: &PauseAgent, in_flight: usize, now: Instant) -> bool {
+        self.paused = true;
+        self.draining = request.drain && in_flight > 0;
+        self.resume_at = request
+            .auto_resume_secs
+            .map(|secs| now + Duration::from_secs(secs));
+        !self.draining
+    }
+
+    /// Leave the paused state; returns false if the agent was not paused
+    pub fn resume(&mut self) -> bool {
+        let was_paused = self.paused;
+        self.paused = false;
+        self.draining = false;
+        self.resume_at = None;
+        was_paused
+    }
+
+    /// Report, once, that a draining pause has no work left in flight
+    pub fn finish_draining(&mut self, in_flight: usize) -> bool {
+        if self.draining && in_flight == 0 {
+            self.draining = false;
+            return true;
+        }
+        false
+    }
+
+    /// Hold work to start before anything read from the channels
+    pub fn hold(&mut self, work: HeldWork) {
+        self.held.push_back(work);
+    }
+
+    /// Hold tasks that were accepted before the pause, ahead of newer work
+    pub fn hold_accepted(&mut self, tasks: Vec<TaskEnvelopeWrapper>) {
+        for task in tasks.into_iter().rev() {
+            self.held.push_front(HeldWork::Task(Box::new(task)));
+        }
+    }
+
+    /// Oldest held work, once resumed
+    pub fn next_held(&mut self) -> Option<HeldWork> {
+        if self.paused {
+            return None;
+        }
+        self.held.pop_front()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::messages::TaskEnvelope;
+    use serde_json::json;
+    use uuid::Uuid;
+
+    fn task() -> TaskEnvelopeWrapper {
+        TaskEnvelopeWrapper::V1(TaskEnvelope {
+            task_id: Uuid::new_v4(),
+            conversation_id: "conv-1".to_string(),
+            topic: "/control/agents/test-agent/input".to_string(),
+            instruction: None,
+            input: json!({}),
+            next: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
+        })
+    }
+
+    fn held_task_id(work: Option<HeldWork>) -> Uuid {
+        match work {
+            Some(HeldWork::Task(task)) => task.task_id(),
+            other => panic!("expected a held task, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_held_work_starts_in_order_after_resume() {
+        let mut state = PauseState::default();
+        assert!(state.pause(&PauseAgent::default(), 0, Instant::now()));
+
+        let (accepted, newer) = (task(), task());
+        let (accepted_id, newer_id) = (accepted.task_id(), newer.task_id());
+        state.hold(HeldWork::Task(Box::new(newer)));
+        state.hold_accepted(vec![accepted]);
+        assert!(state.next_held().is_none());
+
+        assert!(state.resume());
+        assert_eq!(held_task_id(state.next_held()), accepted_id);
+        assert_eq!(held_task_id(state.next_held()), newer_id);
+        assert!(!state.resume());
+    }
+
+    #[test]
+    fn test_drain_waits_for_in_flight_work() {
+        let mut state = PauseState::default();
+        let drain = PauseAgent {
+            drain: true,
+            ..Default::default()
+        };
+        assert!(!state.pause(&drain, 2, Instant::now()));
+        assert!(!state.finish_draining(1));
+        assert!(state.finish_draining(0));
+        assert!(!state.finish_draining(0));
+
+        // Nothing in flight: paused immediately
+        assert!(state.pause(&drain, 0, Instant::now()));
+    }
+
+    #[test]
+    fn test_auto_resume_deadline() {
+        let mut state = PauseState::default();
+        let now = Instant::now();
+        state.pause(
+            &PauseAgent {
+                auto_resume_secs: Some(30),
+                ..Default::default()
+            },
+            0,
+            now,
+        );
+        assert_eq!(state.resume_at(), Some(now + Duration::from_secs(30)));
+
+        state.pause(&PauseAgent::default(), 0, now);
+        assert_eq!(state.resume_at(), None);
+    }
+}
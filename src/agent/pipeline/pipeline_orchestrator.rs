@@ -3,24 +3,73 @@
 //! This module contains the primary AgentPipeline struct that coordinates
 //! task processing using the 9-step algorithm with clean separation of concerns.
 
-// TaskProcessor not needed - using AgentProcessor directly
 use crate::agent::discovery::AgentRegistry;
+use crate::agent::events::{AgentEvent, AgentEvents};
+use crate::agent::pipeline::cycle_detection::{
+    annotate_cycle, detect_cycle, DEFAULT_CYCLE_REPEAT_THRESHOLD,
+};
+use crate::agent::pipeline::fan_out::{
+    branch_conversation_id, branch_topic_filter, BranchOutcome, FanInTracker, FanOutBranch,
+    FanOutJoin, JoinTask,
+};
+use crate::agent::pipeline::idle::IdleState;
+use crate::agent::pipeline::pause::{HeldWork, PauseState};
+use crate::agent::pipeline::workflow_budget::annotate_budget_exhausted;
 use crate::agent::processor::AgentProcessor;
+use crate::agent::status_publisher::StatusPublisher;
+use crate::agent::task_processor::TaskProcessor;
+use crate::clock::derived_task_id;
+use crate::config::PauseMode;
+use crate::error::AgentError;
+use crate::observability::agent_state::{AgentStateRegistry, TaskOutcome};
+use crate::observability::event_log::{event_log, Event, EventCategory, EventSeverity};
+use crate::observability::metrics::metrics;
+use crate::processing::artifacts::{apply_artifact_limits, split_artifacts};
+use crate::processing::cancellation::{CancelOutcome, CancellationRegistry};
+use crate::processing::moderation::moderate;
 use crate::processing::nine_step::ProcessingResult;
+use crate::processing::response_limit::apply_response_limit;
+use crate::processing::TaskJournal;
+use crate::progress::{ProgressCategory, ProgressEventType};
 use crate::protocol::messages::{
-    TaskEnvelopeV2, TaskEnvelopeWrapper, WorkflowContext, WorkflowStep,
+    AdminMessage, AgentStatusType, BatchItemResult, BatchItemStatus, BatchSummary, CancelMessage,
+    ErrorCode, ErrorDetails, ResponseMessage, RoutingExplanation, RoutingStep, TaskBatchEnvelope,
+    TaskEnvelope, TaskEnvelopeV2, TaskEnvelopeWrapper, WorkflowContext, WorkflowStep,
+    MAX_WORKFLOW_HISTORY_STEPS,
+};
+use crate::protocol::workflow_state::state_updates;
+use crate::routing::agent_matcher::{available_agents, describe_unknown_agent};
+use crate::routing::agent_selector::{
+    AgentSelectionDecision, RoutingHelper, CAPABILITY_TARGET_PREFIX,
+};
+use crate::routing::{
+    match_agent_id, record_routing_decision, AgentMatch, DecisionCache, ForwardBranch, JoinPolicy,
+    Router, RoutingAuditEntry, RoutingAuditLog, RoutingDecision, RoutingOutcome, StickyRoutes,
 };
-use crate::routing::{Router, RoutingDecision};
+use crate::transport::mqtt::TopicBuilder;
 use crate::transport::Transport;
-use chrono::Utc;
-use serde_json::Value;
+use chrono::{DateTime, Utc};
+use futures::future::{BoxFuture, FutureExt};
+use futures::stream::{self, FuturesUnordered, StreamExt};
+use serde_json::{json, Map, Value};
+use std::any::Any;
+use std::collections::{HashMap, VecDeque};
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use tokio::sync::mpsc;
-use tracing::{debug, error, info, warn};
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, mpsc, OwnedSemaphorePermit, Semaphore};
+use tracing::{debug, error, info, warn, Instrument};
 use uuid::Uuid;
 
-/// Maximum number of workflow steps to keep in history to prevent unbounded memory growth
-const MAX_WORKFLOW_HISTORY_STEPS: usize = 100;
+/// Default number of batch items processed concurrently
+const DEFAULT_BATCH_CONCURRENCY: usize = 4;
+
+/// Longest backoff between task retries
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// Most tasks that may wait behind busy conversations before intake stops
+const MAX_QUEUED_TASKS: usize = 100;
 
 /// Agent pipeline that orchestrates the complete agent lifecycle
 /// Supports both v1.0 and v2.0 TaskEnvelope formats
@@ -28,8 +77,34 @@ const MAX_WORKFLOW_HISTORY_STEPS: usize = 100;
 /// With V2 routing, the pipeline can optionally use a Router to make
 /// intelligent workflow decisions after agent work completes.
 pub struct AgentPipeline<T: Transport> {
-    processor: AgentProcessor<T>,
+    processor: Arc<dyn TaskProcessor<T>>,
+    /// Resolves `capability:` targets when the processor has no routing helper
+    fallback_routing_helper: RoutingHelper,
     task_receiver: Option<mpsc::Receiver<TaskEnvelopeWrapper>>,
+    /// Optional receiver for cancel requests routed from the transport
+    cancel_receiver: Option<mpsc::Receiver<CancelMessage>>,
+    /// Optional receiver for task batches routed from the transport
+    batch_receiver: Option<mpsc::Receiver<TaskBatchEnvelope>>,
+    /// Optional receiver for pause/resume requests routed from the transport
+    admin_receiver: Option<mpsc::Receiver<AdminMessage>>,
+    /// Whether tasks received while paused are held or rejected
+    pause_mode: PauseMode,
+    /// Set while the agent reports itself paused
+    paused: Arc<AtomicBool>,
+    /// Optional journal of received tasks, completed as tasks settle
+    task_journal: Option<Arc<TaskJournal>>,
+    /// Maximum number of batch items processed at once
+    batch_concurrency: usize,
+    /// Maximum number of tasks (or batches) the run loop holds at once
+    max_concurrent_tasks: usize,
+    /// Extra attempts for a task that fails with a retryable error
+    max_task_retries: u32,
+    /// Delay before the first retry, doubled for each further attempt
+    retry_base_delay: Duration,
+    /// Tasks published longer ago than this are rejected instead of processed
+    max_task_age: Option<Duration>,
+    /// Quiet period after which idle resources are released
+    idle_after: Option<Duration>,
     max_pipeline_depth: usize,
     /// Optional V2 router for workflow decisions
     router: Option<Arc<dyn Router>>,
@@ -37,14 +112,135 @@ pub struct AgentPipeline<T: Transport> {
     agent_registry: Arc<AgentRegistry>,
     /// Maximum iterations before forced workflow completion
     max_iterations: usize,
+    /// Repetitions of a hop pattern tolerated before completing as a cycle
+    cycle_repeat_threshold: usize,
+    /// Wall-clock budget stamped into the workflows this agent starts
+    workflow_budget_secs: Option<u64>,
+    /// Optional cache consulted before invoking the router
+    decision_cache: Option<Arc<DecisionCache>>,
+    /// Publish router decisions without acting on them
+    dry_run: bool,
+    /// Replace an unknown target with its single close match
+    auto_correct_agent_ids: bool,
+    /// Optional per-conversation pins for `capability:` targets
+    sticky_routes: Option<Arc<StickyRoutes>>,
+    /// Optional JSONL log of every routing decision
+    routing_audit_log: Option<Arc<RoutingAuditLog>>,
+    /// Optional registry of in-flight and recent tasks for the admin endpoints
+    state_registry: Option<Arc<AgentStateRegistry>>,
+    /// Publishes the agent's status; shared with the lifecycle when it owns one
+    status_publisher: Arc<StatusPublisher<T>>,
+    /// Task, routing and status events for library callers
+    events: AgentEvents,
+    /// Fan-outs waiting for their branches to be joined
+    fan_in: Arc<FanInTracker>,
+}
+
+/// Outcome of one unit of work in the run loop's worker set
+enum WorkerOutcome {
+    /// A single task finished; its conversation may have queued successors
+    Task {
+        conversation_id: String,
+        task_id: Uuid,
+        result: Result<ProcessingResult, PipelineError>,
+        /// Execution slot, handed to the conversation's next task if any
+        slot: OwnedSemaphorePermit,
+    },
+    /// A batch finished; item failures are reported in its summary
+    Batch,
+}
+
+/// Whether a workflow may take another hop after counting the current one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum IterationCheck {
+    Continue,
+    /// The workflow reached `max_iterations`; the current output is final
+    BudgetExhausted,
+}
+
+/// Conversations with a task in progress and the tasks queued behind each
+#[derive(Default)]
+struct ConversationQueues {
+    queues: HashMap<String, VecDeque<TaskEnvelopeWrapper>>,
+    /// Tasks waiting across all conversations
+    queued: usize,
+}
+
+impl ConversationQueues {
+    fn is_busy(&self, conversation_id: &str) -> bool {
+        self.queues.contains_key(conversation_id)
+    }
+
+    /// Mark a conversation as having a task in progress
+    fn start(&mut self, conversation_id: &str) {
+        self.queues
+            .insert(conversation_id.to_string(), VecDeque::new());
+    }
+
+    /// Queue a task behind its busy conversation
+    fn queue(&mut self, conversation_id: &str, task: TaskEnvelopeWrapper) {
+        if let Some(queue) = self.queues.get_mut(conversation_id) {
+            queue.push_back(task);
+            self.queued += 1;
+        }
+    }
+
+    /// A conversation's task finished: its next queued task, if any, which
+    /// is now in progress
+    fn finish(&mut self, conversation_id: &str) -> Option<TaskEnvelopeWrapper> {
+        let next = self
+            .queues
+            .get_mut(conversation_id)
+            .and_then(VecDeque::pop_front);
+        match next {
+            Some(_) => self.queued -= 1,
+            None => {
+                self.queues.remove(conversation_id);
+            }
+        }
+        next
+    }
+
+    /// Remove every queued task, leaving the tasks in progress
+    fn take_queued(&mut self) -> Vec<TaskEnvelopeWrapper> {
+        self.queued = 0;
+        self.queues
+            .values_mut()
+            .flat_map(|queue| queue.drain(..))
+            .collect()
+    }
+}
+
+/// A failed attempt at processing a task
+struct AttemptFailure {
+    error: PipelineError,
+    /// Whether a later attempt may succeed
+    retryable: bool,
+    /// Minimum delay requested by the failure, such as a rate limit's retry-after
+    retry_after: Option<Duration>,
+}
+
+impl From<PipelineError> for AttemptFailure {
+    /// Routing failures are only retryable when publishing hit the transport
+    fn from(error: PipelineError) -> Self {
+        let retryable = matches!(error, PipelineError::TransportError(_));
+        Self {
+            error,
+            retryable,
+            retry_after: None,
+        }
+    }
 }
 
 /// Synthesize a default workflow context from a task envelope
 ///
 /// Uses the task's instruction field as the original_query if available,
 /// falling back to "Unknown" if the instruction is None or empty/whitespace.
+/// The workflow's wall-clock budget starts `now`.
 fn synthesize_context_from_task(
     task: &TaskEnvelopeV2,
+    budget_secs: Option<u64>,
+    now: DateTime<Utc>,
 ) -> crate::protocol::messages::WorkflowContext {
     let original_query = task
         .instruction
@@ -58,9 +254,40 @@ fn synthesize_context_from_task(
         original_query,
         steps_completed: vec![],
         iteration_count: 0,
+        started_at: Some(now),
+        budget_secs,
+        state: None,
+        state_order: Vec::new(),
     }
 }
 
+/// Event log entry for a task that did not complete (pure function)
+fn task_event(
+    outcome: TaskOutcome,
+    error: &PipelineError,
+    task_id: Uuid,
+    conversation_id: &str,
+) -> Event {
+    let (severity, message) = match outcome {
+        TaskOutcome::Rejected => (EventSeverity::Warning, "Task rejected"),
+        TaskOutcome::Cancelled => (EventSeverity::Info, "Task cancelled"),
+        TaskOutcome::Completed | TaskOutcome::Failed => (EventSeverity::Error, "Task failed"),
+    };
+    Event::new(severity, EventCategory::Task, message)
+        .with_field("task_id", task_id.to_string())
+        .with_field("conversation_id", conversation_id)
+        .with_field("error", error.to_string())
+}
+
+/// Text of a panic payload; `panic!` produces a `&str` or a `String`
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic payload".to_string())
+}
+
 /// Cap workflow history to a maximum number of steps using FIFO
 ///
 /// Removes oldest steps when the vector exceeds the specified maximum,
@@ -79,13 +306,75 @@ impl<T: Transport + 'static> AgentPipeline<T> {
         task_receiver: mpsc::Receiver<TaskEnvelopeWrapper>,
         max_pipeline_depth: usize,
     ) -> Self {
+        Self::with_processor(Arc::new(processor), task_receiver, max_pipeline_depth)
+    }
+
+    /// Create new agent pipeline around any task processor, without V2 routing
+    ///
+    /// Agent and `[routing]` settings are taken from the processor's config;
+    /// the routing ones apply once a router is attached.
+    pub fn with_processor(
+        processor: Arc<dyn TaskProcessor<T>>,
+        task_receiver: mpsc::Receiver<TaskEnvelopeWrapper>,
+        max_pipeline_depth: usize,
+    ) -> Self {
+        let agent = &processor.config().agent;
+        let max_concurrent_tasks = agent.max_concurrent_tasks.max(1);
+        let max_task_retries = agent.max_task_retries;
+        let retry_base_delay = Duration::from_millis(agent.retry_base_delay_ms);
+        let max_task_age = agent.max_task_age_secs.map(Duration::from_secs);
+        let idle_after = agent.idle_after_secs.map(Duration::from_secs);
+        let pause_mode = agent.pause_mode;
+        let routing = processor.config().routing.as_ref();
+        let cycle_repeat_threshold = routing.map_or(DEFAULT_CYCLE_REPEAT_THRESHOLD, |routing| {
+            routing.cycle_repeat_threshold
+        });
+        let workflow_budget_secs = routing.and_then(|routing| routing.workflow_budget_secs);
+        let dry_run = routing.is_some_and(|routing| routing.dry_run);
+        let auto_correct_agent_ids = routing.is_some_and(|routing| routing.auto_correct_agent_ids);
+        let decision_cache = routing
+            .and_then(|routing| routing.cache.clone())
+            .map(|cache| Arc::new(DecisionCache::new(cache)));
+        let sticky_routes = routing
+            .and_then(|routing| routing.sticky.clone())
+            .map(|sticky| Arc::new(StickyRoutes::new(sticky)));
+        let routing_audit_log = processor.routing_audit_log().cloned();
+        let events = AgentEvents::default();
+        let status_publisher = Arc::new(
+            StatusPublisher::new(processor.transport().clone(), &processor.config().agent)
+                .with_events(events.clone()),
+        );
         Self {
             processor,
+            fallback_routing_helper: RoutingHelper::new(),
             task_receiver: Some(task_receiver),
+            cancel_receiver: None,
+            batch_receiver: None,
+            admin_receiver: None,
+            pause_mode,
+            paused: Arc::new(AtomicBool::new(false)),
+            task_journal: None,
+            batch_concurrency: DEFAULT_BATCH_CONCURRENCY,
+            max_concurrent_tasks,
+            max_task_retries,
+            retry_base_delay,
+            max_task_age,
+            idle_after,
             max_pipeline_depth,
             router: None,
             agent_registry: Arc::new(AgentRegistry::new()),
             max_iterations: 10,
+            cycle_repeat_threshold,
+            workflow_budget_secs,
+            decision_cache,
+            dry_run,
+            auto_correct_agent_ids,
+            sticky_routes,
+            routing_audit_log,
+            state_registry: None,
+            status_publisher,
+            events,
+            fan_in: Arc::new(FanInTracker::new()),
         }
     }
 
@@ -98,19 +387,247 @@ impl<T: Transport + 'static> AgentPipeline<T> {
         agent_registry: Arc<AgentRegistry>,
         max_iterations: usize,
     ) -> Self {
-        Self {
-            processor,
-            task_receiver: Some(task_receiver),
+        Self::with_processor_and_router(
+            Arc::new(processor),
+            task_receiver,
             max_pipeline_depth,
-            router: Some(router),
+            router,
             agent_registry,
             max_iterations,
-        }
+        )
+    }
+
+    /// Create new agent pipeline around any task processor, with V2 routing support
+    pub fn with_processor_and_router(
+        processor: Arc<dyn TaskProcessor<T>>,
+        task_receiver: mpsc::Receiver<TaskEnvelopeWrapper>,
+        max_pipeline_depth: usize,
+        router: Arc<dyn Router>,
+        agent_registry: Arc<AgentRegistry>,
+        max_iterations: usize,
+    ) -> Self {
+        let mut pipeline = Self::with_processor(processor, task_receiver, max_pipeline_depth);
+        pipeline.router = Some(router);
+        pipeline.agent_registry = agent_registry;
+        pipeline.max_iterations = max_iterations;
+        pipeline
     }
 
     /// Get reference to the processor
-    pub fn processor(&self) -> &AgentProcessor<T> {
-        &self.processor
+    pub fn processor(&self) -> &dyn TaskProcessor<T> {
+        self.processor.as_ref()
+    }
+
+    /// Attach a receiver for cancel requests
+    ///
+    /// Cancel requests are applied to the processor's shared cancellation
+    /// registry while tasks are running, so they take effect mid-task.
+    pub fn set_cancel_receiver(&mut self, cancel_receiver: mpsc::Receiver<CancelMessage>) {
+        self.cancel_receiver = Some(cancel_receiver);
+    }
+
+    /// Attach a receiver for pause/resume requests
+    ///
+    /// While paused, no new tasks or batches are started. Tasks received in
+    /// the meantime are held or rejected according to `agent.pause_mode`;
+    /// batches are always held.
+    pub fn set_admin_receiver(&mut self, admin_receiver: mpsc::Receiver<AdminMessage>) {
+        self.admin_receiver = Some(admin_receiver);
+    }
+
+    /// Set whether tasks received while paused are held or rejected
+    ///
+    /// Defaults to `agent.pause_mode` from the configuration.
+    pub fn set_pause_mode(&mut self, pause_mode: PauseMode) {
+        self.pause_mode = pause_mode;
+    }
+
+    /// Flag that is set while the agent reports itself paused
+    ///
+    /// A draining pause only sets it once accepted work has finished, so
+    /// status published outside the pipeline, such as the heartbeat, matches
+    /// the status the pipeline announced.
+    pub fn paused_flag(&self) -> Arc<AtomicBool> {
+        self.paused.clone()
+    }
+
+    /// Attach the journal the transport records received tasks in
+    ///
+    /// Tasks it recovered from before a crash are replayed ahead of new work
+    /// when the pipeline runs, and every task is marked complete once settled.
+    pub fn set_task_journal(&mut self, task_journal: Arc<TaskJournal>) {
+        self.task_journal = Some(task_journal);
+    }
+
+    /// Publish status through `status_publisher` instead of the pipeline's own
+    ///
+    /// The lifecycle shares its publisher so the pipeline's pause and resume
+    /// announcements, the heartbeat and shutdown never contradict each other.
+    pub fn set_status_publisher(&mut self, status_publisher: Arc<StatusPublisher<T>>) {
+        self.status_publisher = status_publisher;
+    }
+
+    /// Report events on `events` instead of the pipeline's own stream
+    ///
+    /// Status changes are reported by the status publisher, so one passed to
+    /// [`Self::set_status_publisher`] should be built with the same stream.
+    pub fn set_events(&mut self, events: AgentEvents) {
+        self.events = events;
+    }
+
+    /// Subscribe to the pipeline's task, routing and status events
+    ///
+    /// Only events sent after subscribing are received; see
+    /// [`crate::agent::events`] for how slow receivers are handled.
+    pub fn events(&self) -> broadcast::Receiver<AgentEvent> {
+        self.events.subscribe()
+    }
+
+    /// Ids of the fan-outs still waiting for their branches to be joined
+    pub fn pending_fan_outs(&self) -> Vec<Uuid> {
+        self.fan_in.pending()
+    }
+
+    /// Record every task's start and outcome in `state_registry`
+    pub fn set_state_registry(&mut self, state_registry: Arc<AgentStateRegistry>) {
+        self.state_registry = Some(state_registry);
+    }
+
+    /// Attach a receiver for task batches
+    ///
+    /// Batches are expanded into individual tasks and processed between
+    /// single tasks, with up to `batch_concurrency` items in flight.
+    pub fn set_batch_receiver(&mut self, batch_receiver: mpsc::Receiver<TaskBatchEnvelope>) {
+        self.batch_receiver = Some(batch_receiver);
+    }
+
+    /// Cache router decisions keyed by workflow state
+    ///
+    /// Cached Forward decisions are only reused while their target agent is
+    /// still healthy in the registry.
+    pub fn set_decision_cache(&mut self, decision_cache: Arc<DecisionCache>) {
+        self.decision_cache = Some(decision_cache);
+    }
+
+    /// Enable sticky routing for `capability:` targets
+    pub fn set_sticky_routes(&mut self, sticky_routes: Arc<StickyRoutes>) {
+        self.sticky_routes = Some(sticky_routes);
+    }
+
+    /// Append every routing decision to an audit log
+    pub fn set_routing_audit_log(&mut self, routing_audit_log: Arc<RoutingAuditLog>) {
+        self.routing_audit_log = Some(routing_audit_log);
+    }
+
+    /// Enable routing dry-run mode
+    ///
+    /// The router is still consulted, but its decision is only published to
+    /// the routing explain topic; every workflow completes locally.
+    pub fn set_dry_run(&mut self, dry_run: bool) {
+        self.dry_run = dry_run;
+    }
+
+    /// Forward to the single healthy agent closely matching an unknown target
+    ///
+    /// Matches are case-insensitive or by prefix; ambiguous or missing
+    /// matches still fail with the list of available agents.
+    pub fn set_auto_correct_agent_ids(&mut self, auto_correct_agent_ids: bool) {
+        self.auto_correct_agent_ids = auto_correct_agent_ids;
+    }
+
+    /// Set how many back-to-back repetitions of a hop pattern are tolerated
+    /// before the workflow is completed as a cycle
+    pub fn set_cycle_repeat_threshold(&mut self, cycle_repeat_threshold: usize) {
+        self.cycle_repeat_threshold = cycle_repeat_threshold;
+    }
+
+    /// Set the wall-clock budget of workflows this agent starts (`None` for unlimited)
+    pub fn set_workflow_budget_secs(&mut self, workflow_budget_secs: Option<u64>) {
+        self.workflow_budget_secs = workflow_budget_secs;
+    }
+
+    /// Set the maximum number of batch items processed concurrently
+    pub fn set_batch_concurrency(&mut self, batch_concurrency: usize) {
+        self.batch_concurrency = batch_concurrency.max(1);
+    }
+
+    /// Set the maximum number of tasks processed concurrently
+    ///
+    /// Defaults to `agent.max_concurrent_tasks` from the configuration.
+    /// Tasks in the same conversation are always processed one at a time, in
+    /// the order they were received.
+    pub fn set_max_concurrent_tasks(&mut self, max_concurrent_tasks: usize) {
+        self.max_concurrent_tasks = max_concurrent_tasks.max(1);
+    }
+
+    /// Set how often a task failing with a retryable error is retried
+    ///
+    /// Defaults to `agent.max_task_retries` from the configuration.
+    pub fn set_max_task_retries(&mut self, max_task_retries: u32) {
+        self.max_task_retries = max_task_retries;
+    }
+
+    /// Set the delay before the first retry; later retries double it
+    pub fn set_retry_base_delay(&mut self, retry_base_delay: Duration) {
+        self.retry_base_delay = retry_base_delay;
+    }
+
+    /// Set the age beyond which tasks are rejected as stale, or `None` to accept any age
+    ///
+    /// Defaults to `agent.max_task_age_secs` from the configuration.
+    pub fn set_max_task_age(&mut self, max_task_age: Option<Duration>) {
+        self.max_task_age = max_task_age;
+    }
+
+    /// Set how long the pipeline waits without work before releasing idle
+    /// resources, or `None` to never go idle
+    ///
+    /// Defaults to `agent.idle_after_secs` from the configuration.
+    pub fn set_idle_after(&mut self, idle_after: Option<Duration>) {
+        self.idle_after = idle_after;
+    }
+
+    /// Receive the next batch, or wait forever when no batch receiver is attached
+    async fn recv_batch(
+        batch_receiver: &mut Option<mpsc::Receiver<TaskBatchEnvelope>>,
+    ) -> Option<TaskBatchEnvelope> {
+        match batch_receiver {
+            Some(receiver) => receiver.recv().await,
+            None => std::future::pending().await,
+        }
+    }
+
+    /// Receive the next admin request, or wait forever when no admin receiver is attached
+    async fn recv_admin(
+        admin_receiver: &mut Option<mpsc::Receiver<AdminMessage>>,
+    ) -> Option<AdminMessage> {
+        match admin_receiver {
+            Some(receiver) => receiver.recv().await,
+            None => std::future::pending().await,
+        }
+    }
+
+    /// Apply a cancel request to the cancellation registry
+    fn apply_cancel(registry: &CancellationRegistry, cancel: CancelMessage) -> CancelOutcome {
+        let outcome = registry.cancel(cancel.task_id, &cancel.conversation_id, cancel.reason);
+        match outcome {
+            CancelOutcome::Flagged => info!(
+                task_id = %cancel.task_id,
+                conversation_id = %cancel.conversation_id,
+                "Cancellation requested for in-flight task"
+            ),
+            CancelOutcome::Pending => info!(
+                task_id = %cancel.task_id,
+                conversation_id = %cancel.conversation_id,
+                "Cancellation recorded for task that has not started"
+            ),
+            CancelOutcome::Ignored => debug!(
+                task_id = %cancel.task_id,
+                conversation_id = %cancel.conversation_id,
+                "Ignoring cancel request for completed task or mismatched conversation"
+            ),
+        }
+        outcome
     }
 
     /// Start the pipeline - set up transport connections and subscriptions
@@ -121,21 +638,468 @@ impl<T: Transport + 'static> AgentPipeline<T> {
     }
 
     /// Main processing loop - runs until shutdown is requested
+    ///
+    /// Up to `max_concurrent_tasks` tasks are processed at once. A task whose
+    /// conversation already has a task in progress waits behind it, still
+    /// holding a slot, so a conversation's tasks never interleave. Once the
+    /// task channel closes or a task fails fatally, no new work is accepted
+    /// and the loop returns after all accepted work has finished.
+    ///
+    /// Admin requests pause and resume task intake; see
+    /// [`Self::set_admin_receiver`].
     pub async fn run(&mut self) -> Result<(), PipelineError> {
-        info!("Agent pipeline running, waiting for tasks");
+        info!(
+            max_concurrent_tasks = self.max_concurrent_tasks,
+            "Agent pipeline running, waiting for tasks"
+        );
 
         let mut task_receiver = self.task_receiver.take().ok_or_else(|| {
             PipelineError::ProcessingFailed("Task receiver not available".to_string())
         })?;
 
-        while let Some(task) = task_receiver.recv().await {
-            self.process_single_task(task).await?;
+        // Cancel requests must be applied while a task is processing, so drain
+        // them on a separate task rather than in the task loop
+        let cancel_handle = self.cancel_receiver.take().map(|mut cancel_receiver| {
+            let registry = self.processor.cancellation().cloned();
+            tokio::spawn(async move {
+                while let Some(cancel) = cancel_receiver.recv().await {
+                    match &registry {
+                        Some(registry) => {
+                            Self::apply_cancel(registry, cancel);
+                        }
+                        // Still drained, so the transport never blocks on the channel
+                        None => debug!(
+                            task_id = %cancel.task_id,
+                            "Ignoring cancel request, processor does not support cancellation"
+                        ),
+                    }
+                }
+            })
+        });
+
+        let mut batch_receiver = self.batch_receiver.take();
+        let mut admin_receiver = self.admin_receiver.take();
+
+        // Workers borrow the pipeline, so they are polled by this loop as a
+        // future set instead of being spawned onto the runtime
+        let this = &*self;
+        let mut workers: FuturesUnordered<BoxFuture<'_, WorkerOutcome>> = FuturesUnordered::new();
+        // Execution slots shared by tasks and batch items
+        let slots = Arc::new(Semaphore::new(this.max_concurrent_tasks));
+        // Conversations with a task in progress, and the tasks queued behind it
+        let mut conversations = ConversationQueues::default();
+        let mut accepting = true;
+        let mut pause = PauseState::default();
+        let mut idle = IdleState::new(this.idle_after, tokio::time::Instant::now());
+        // Held work starts before anything new, so recovered tasks go first
+        for task in this.recover_journaled_tasks().await {
+            pause.hold(HeldWork::Task(Box::new(task)));
         }
 
+        let mut result = Ok(());
+        loop {
+            // Work held during a pause starts first, in arrival order
+            while accepting && conversations.queued < MAX_QUEUED_TASKS {
+                let Ok(slot) = slots.clone().try_acquire_owned() else {
+                    break;
+                };
+                let Some(work) = pause.next_held() else {
+                    break;
+                };
+                match work {
+                    HeldWork::Task(task) => {
+                        this.start_task(&mut conversations, &mut workers, &slots, *task, Some(slot))
+                    }
+                    HeldWork::Batch(batch) => workers.push(this.batch_worker(batch, &slots)),
+                }
+            }
+            if pause.finish_draining(workers.len()) {
+                info!("Accepted work drained, agent paused");
+                this.announce_paused().await;
+            }
+
+            if !accepting && workers.is_empty() {
+                break;
+            }
+            // While paused, channels are still read so the transport never blocks
+            // on a full channel; what arrives is held or rejected, not started.
+            // Tasks queued behind a busy conversation don't take a slot.
+            let has_capacity =
+                slots.available_permits() > 0 && conversations.queued < MAX_QUEUED_TASKS;
+            let can_receive = accepting && (pause.is_paused() || has_capacity);
+            let resume_at = pause.resume_at();
+            let idle_at = idle.idle_at(workers.len());
+
+            tokio::select! {
+                biased;
+                Some(outcome) = workers.next(), if !workers.is_empty() => {
+                    idle.touch(tokio::time::Instant::now());
+                    let WorkerOutcome::Task { conversation_id, task_id, result: task_result, slot } = outcome else {
+                        continue;
+                    };
+                    if let Err(e) = Self::settle_task(task_id, task_result) {
+                        // Stop accepting work; the first fatal error is returned once drained
+                        accepting = false;
+                        if result.is_ok() {
+                            result = Err(e);
+                        } else {
+                            error!(task_id = %task_id, error = %e, "Task failed while pipeline was stopping");
+                        }
+                    }
+
+                    if let Some(next) = conversations.finish(&conversation_id) {
+                        workers.push(this.task_worker(conversation_id, next, &slots, Some(slot)));
+                    }
+                }
+                Some(admin) = Self::recv_admin(&mut admin_receiver) => match admin {
+                    AdminMessage::PauseAgent(request) => {
+                        if !request.drain {
+                            // Tasks waiting behind a busy conversation have not started yet
+                            pause.hold_accepted(conversations.take_queued());
+                        }
+
+                        let paused_now = pause.pause(&request, workers.len(), tokio::time::Instant::now());
+                        info!(
+                            drain = request.drain,
+                            auto_resume_secs = ?request.auto_resume_secs,
+                            reason = ?request.reason,
+                            in_flight = workers.len(),
+                            "Pausing task intake"
+                        );
+                        if paused_now {
+                            this.announce_paused().await;
+                        }
+                    }
+                    AdminMessage::ResumeAgent => this.resume(&mut pause).await,
+                },
+                _ = tokio::time::sleep_until(resume_at.unwrap_or_else(tokio::time::Instant::now)), if resume_at.is_some() => {
+                    info!("Auto-resume timeout elapsed");
+                    this.resume(&mut pause).await;
+                }
+                _ = tokio::time::sleep_until(idle_at.unwrap_or_else(tokio::time::Instant::now)), if idle_at.is_some() => {
+                    idle.go_idle();
+                    this.go_idle().await;
+                }
+                // Drain queued batches before tasks so they are not lost when the task channel closes
+                Some(batch) = Self::recv_batch(&mut batch_receiver), if can_receive => {
+                    this.wake(&mut idle);
+                    if pause.is_paused() {
+                        pause.hold(HeldWork::Batch(batch));
+                        continue;
+                    }
+                    workers.push(this.batch_worker(batch, &slots));
+                }
+                task = task_receiver.recv(), if can_receive => {
+                    let Some(task) = task else {
+                        accepting = false;
+                        continue;
+                    };
+                    this.wake(&mut idle);
+                    if pause.is_paused() {
+                        match this.pause_mode {
+                            PauseMode::Buffer => {
+                                debug!(task_id = %task.task_id(), "Agent paused, holding task");
+                                pause.hold(HeldWork::Task(Box::new(task)));
+                            }
+                            PauseMode::Reject => this.reject_paused_task(&task).await,
+                        }
+                        continue;
+                    }
+                    // Batch items may have taken the last slot while the
+                    // workers were polled; the task then waits for one
+                    let slot = slots.clone().try_acquire_owned().ok();
+                    this.start_task(&mut conversations, &mut workers, &slots, task, slot);
+                }
+            }
+        }
+
+        if pause.held_len() > 0 {
+            // Journaled tasks among them are replayed on the next start
+            warn!(
+                held = pause.held_len(),
+                "Pipeline stopped with held work, dropping it"
+            );
+        }
+        if let Some(handle) = cancel_handle {
+            handle.abort();
+        }
+        result?;
+
         info!("Pipeline processing loop ended");
         Ok(())
     }
 
+    /// Start a task, or queue it behind its conversation's task in progress
+    ///
+    /// A queued task gives up `slot`; it takes over its predecessor's instead.
+    fn start_task<'a>(
+        &'a self,
+        conversations: &mut ConversationQueues,
+        workers: &mut FuturesUnordered<BoxFuture<'a, WorkerOutcome>>,
+        slots: &Arc<Semaphore>,
+        task: TaskEnvelopeWrapper,
+        slot: Option<OwnedSemaphorePermit>,
+    ) {
+        let conversation_id = task.conversation_id().to_string();
+        if conversations.is_busy(&conversation_id) {
+            debug!(
+                task_id = %task.task_id(),
+                conversation_id = %conversation_id,
+                "Conversation busy, queueing task"
+            );
+            conversations.queue(&conversation_id, task);
+        } else {
+            conversations.start(&conversation_id);
+            workers.push(self.task_worker(conversation_id, task, slots, slot));
+        }
+    }
+
+    /// Process a task as a worker future for the run loop
+    ///
+    /// Without a `slot` the worker waits for one before processing.
+    fn task_worker(
+        &self,
+        conversation_id: String,
+        task: TaskEnvelopeWrapper,
+        slots: &Arc<Semaphore>,
+        slot: Option<OwnedSemaphorePermit>,
+    ) -> BoxFuture<'_, WorkerOutcome> {
+        let slots = slots.clone();
+        Box::pin(async move {
+            let slot = match slot {
+                Some(slot) => slot,
+                None => slots
+                    .acquire_owned()
+                    .await
+                    .expect("task slots are never closed"),
+            };
+            let task_id = task.task_id();
+            metrics().task_in_flight();
+            let result = self.process_isolated(task).await;
+            metrics().task_settled();
+            // Settled either way: failures were already reported to the conversation
+            self.complete_journaled(task_id).await;
+            WorkerOutcome::Task {
+                conversation_id,
+                task_id,
+                result,
+                slot,
+            }
+        })
+    }
+
+    /// Tasks the journal recovered from before a crash, minus those the
+    /// idempotency store shows were already processed
+    async fn recover_journaled_tasks(&self) -> Vec<TaskEnvelopeWrapper> {
+        let Some(journal) = &self.task_journal else {
+            return Vec::new();
+        };
+
+        let mut recovered = Vec::new();
+        for task in journal.take_recovered() {
+            let task_id = task.task_id();
+            // Step 4 would reject it as a duplicate
+            if self.processor.has_processed(&task_id).await {
+                debug!(task_id = %task_id, "Journaled task was already processed, skipping");
+                self.complete_journaled(task_id).await;
+                continue;
+            }
+            recovered.push(task);
+        }
+        if !recovered.is_empty() {
+            info!(
+                count = recovered.len(),
+                "Replaying unfinished tasks from the task journal"
+            );
+        }
+        recovered
+    }
+
+    /// Mark a task complete in the journal, if one is attached
+    async fn complete_journaled(&self, task_id: Uuid) {
+        if let Some(journal) = &self.task_journal {
+            if let Err(e) = journal.complete_async(task_id).await {
+                warn!(task_id = %task_id, error = %e, "Failed to mark task complete in journal");
+            }
+        }
+    }
+
+    /// Process a batch as a worker future for the run loop
+    ///
+    /// Each item takes one of the run loop's execution slots while it runs.
+    fn batch_worker(
+        &self,
+        batch: TaskBatchEnvelope,
+        slots: &Arc<Semaphore>,
+    ) -> BoxFuture<'_, WorkerOutcome> {
+        let slots = slots.clone();
+        Box::pin(async move {
+            // Item failures are reported in the summary and never stop the pipeline
+            self.process_batch_in_slots(batch, Some(&slots)).await;
+            WorkerOutcome::Batch
+        })
+    }
+
+    /// Leave the paused state and announce availability
+    async fn resume(&self, pause: &mut PauseState) {
+        if !pause.resume() {
+            debug!("Resume requested while not paused");
+            return;
+        }
+        self.paused.store(false, Ordering::Relaxed);
+        metrics().set_paused(false);
+        info!(held = pause.held_len(), "Resuming task intake");
+        self.announce_status(AgentStatusType::Available).await;
+    }
+
+    /// Report the paused state, once no accepted work is left to drain
+    async fn announce_paused(&self) {
+        self.paused.store(true, Ordering::Relaxed);
+        metrics().set_paused(true);
+        self.announce_status(AgentStatusType::Paused).await;
+    }
+
+    /// Publish a pause state change; a failed publish does not stop the pipeline
+    async fn announce_status(&self, status: AgentStatusType) {
+        if let Err(e) = self.update_status(status).await {
+            warn!(error = %e, "Failed to announce agent status");
+        }
+    }
+
+    /// Log expected task failures, returning only errors that stop the pipeline
+    fn settle_task(
+        task_id: Uuid,
+        result: Result<ProcessingResult, PipelineError>,
+    ) -> Result<(), PipelineError> {
+        match result {
+            Ok(_) => Ok(()),
+            // A cancelled task is an expected outcome and must not stop the pipeline
+            Err(PipelineError::TaskCancelled(message)) => {
+                warn!(task_id = %task_id, reason = %message, "Task cancelled");
+                Ok(())
+            }
+            // Expired tasks are likewise expected and already reported
+            Err(PipelineError::DeadlineExceeded(deadline)) => {
+                warn!(task_id = %task_id, deadline = %deadline, "Task deadline exceeded");
+                Ok(())
+            }
+            // A panic only fails its own task; it was reported to the conversation
+            Err(PipelineError::TaskPanicked(_)) => Ok(()),
+            // Stale redeliveries are expected after a reconnect and already reported
+            Err(PipelineError::StaleTask(task_id)) => {
+                debug!(task_id = %task_id, "Stale task rejected");
+                Ok(())
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Process a task, recording its outcome in the task metrics
+    async fn process_isolated(
+        &self,
+        wrapper: TaskEnvelopeWrapper,
+    ) -> Result<ProcessingResult, PipelineError> {
+        metrics().task_received();
+        metrics().task_processing_started();
+        let started = Instant::now();
+        let task_id = wrapper.task_id();
+        let conversation_id = wrapper.conversation_id().to_string();
+        let tracked = self
+            .state_registry
+            .as_ref()
+            .map(|registry| registry.task_started(wrapper.task_id(), wrapper.conversation_id()));
+        self.events.emit(AgentEvent::TaskStarted {
+            task_id,
+            conversation_id: conversation_id.clone(),
+        });
+
+        let result = self.process_contained(wrapper).await;
+        let outcome = match &result {
+            Ok(_) => {
+                metrics().task_processing_completed(started.elapsed());
+                TaskOutcome::Completed
+            }
+            Err(PipelineError::StaleTask(_) | PipelineError::PipelineDepthExceeded(_)) => {
+                metrics().task_processing_rejected();
+                TaskOutcome::Rejected
+            }
+            Err(PipelineError::TaskCancelled(_)) => {
+                metrics().task_processing_failed(started.elapsed());
+                TaskOutcome::Cancelled
+            }
+            Err(_) => {
+                metrics().task_processing_failed(started.elapsed());
+                TaskOutcome::Failed
+            }
+        };
+        if let Err(e) = &result {
+            event_log().record(task_event(outcome, e, task_id, &conversation_id));
+            // Failures are reported to progress sinks such as webhook notifications
+            if let (TaskOutcome::Failed, Some(progress)) = (outcome, self.processor.progress()) {
+                progress
+                    .report_task_error(
+                        Some(&task_id.to_string()),
+                        Some(&conversation_id),
+                        &e.to_string(),
+                    )
+                    .await;
+            }
+        }
+        if let Some(tracked) = tracked {
+            tracked.finish(outcome, result.as_ref().err().map(ToString::to_string));
+        }
+        self.events.emit(match &result {
+            Ok(processed) => AgentEvent::TaskCompleted(processed.clone()),
+            Err(e) => AgentEvent::TaskFailed {
+                task_id,
+                error: e.to_string(),
+            },
+        });
+        result
+    }
+
+    /// Process a task, containing a panic to that task
+    ///
+    /// Workers are polled by the run loop, so a panicking tool or provider
+    /// would otherwise unwind through it and stop the agent consuming work.
+    /// The panic is published to the conversation as an internal error.
+    async fn process_contained(
+        &self,
+        wrapper: TaskEnvelopeWrapper,
+    ) -> Result<ProcessingResult, PipelineError> {
+        let task_id = wrapper.task_id();
+        let conversation_id = wrapper.conversation_id().to_string();
+        let correlation_id = wrapper.correlation_id().map(str::to_string);
+        let parent_task_id = wrapper.parent_task_id();
+
+        let payload = match AssertUnwindSafe(self.process_single_task(wrapper))
+            .catch_unwind()
+            .await
+        {
+            Ok(result) => return result,
+            Err(payload) => payload,
+        };
+
+        let message = panic_message(payload.as_ref());
+        error!(task_id = %task_id, panic = %message, "Task processing panicked");
+        metrics().task_panicked();
+
+        let error_message =
+            AgentError::internal_error(format!("Task processing panicked: {message}"))
+                .to_error_message(task_id)
+                .with_correlation(correlation_id, parent_task_id);
+        if let Err(e) = self
+            .processor
+            .transport()
+            .publish_error(&conversation_id, &error_message)
+            .await
+        {
+            error!(error = %e, "Failed to publish task panic error");
+        }
+
+        Err(PipelineError::TaskPanicked(message))
+    }
+
     /// Calculate topic depth by counting non-empty segments
     fn calculate_topic_depth(topic: &str) -> usize {
         topic.split('/').filter(|s| !s.is_empty()).count()
@@ -148,16 +1112,22 @@ impl<T: Transport + 'static> AgentPipeline<T> {
     /// 1. Process the task (agent does work)
     /// 2. Invoke router to decide next step
     /// 3. Either complete workflow or forward to next agent
+    ///
+    /// An attempt that fails with a retryable error is retried up to
+    /// `max_task_retries` times with exponential backoff. The error is only
+    /// published to the conversation once no attempts remain.
     pub async fn process_single_task(
         &self,
-        wrapper: TaskEnvelopeWrapper,
+        mut wrapper: TaskEnvelopeWrapper,
     ) -> Result<ProcessingResult, PipelineError> {
+        // Fix the correlation id up front so the router forwards the same one
+        wrapper.ensure_correlation_id_with(self.processor.config().testing.deterministic);
+
         // Extract topic from wrapper
         let topic = match &wrapper {
             TaskEnvelopeWrapper::V1(env) => env.topic.clone(),
             TaskEnvelopeWrapper::V2(env) => env.topic.clone(),
         };
-        let is_retained = false; // Assume not retained
 
         // VALIDATE TOPIC DEPTH: Prevent DoS attacks via deep topic nesting
         let topic_depth = Self::calculate_topic_depth(&topic);
@@ -171,14 +1141,81 @@ impl<T: Transport + 'static> AgentPipeline<T> {
             return Err(PipelineError::PipelineDepthExceeded(topic_depth));
         }
 
+        // Queued QoS 1 tasks redelivered after a reconnect may be long out of date
+        if let Some(max_task_age) = self.max_task_age {
+            if let Some(age) = wrapper.age_at(self.now()) {
+                if age.to_std().unwrap_or_default() > max_task_age {
+                    return Err(self.reject_stale_task(&wrapper, age, max_task_age).await);
+                }
+            }
+        }
+
+        let task_id = wrapper.task_id();
+        let mut attempt = 0;
+        loop {
+            // Tasks may sit in the channel, or back off, long enough for their deadline to pass
+            if wrapper.is_expired_at(self.now()) {
+                return Err(self.reject_expired_task(&wrapper).await);
+            }
+
+            let will_retry = attempt < self.max_task_retries;
+            match self.attempt_task(wrapper.clone(), &topic, will_retry).await {
+                Ok(result) => return Ok(result),
+                Err(failure) if will_retry && failure.retryable => {
+                    attempt += 1;
+                    let delay = self.retry_delay(attempt, failure.retry_after);
+                    warn!(
+                        task_id = %task_id,
+                        attempt,
+                        max_task_retries = self.max_task_retries,
+                        delay_ms = delay.as_millis() as u64,
+                        error = %failure.error,
+                        "Retrying task after transient failure"
+                    );
+                    metrics().task_retried();
+                    tokio::time::sleep(delay).await;
+
+                    // An attempt that failed after the agent's work completed,
+                    // in routing, has recorded the task id as processed
+                    self.processor.allow_retry(&task_id).await;
+                }
+                Err(failure) => return Err(failure.error),
+            }
+        }
+    }
+
+    /// Run one attempt of a task: agent work followed by V2 routing
+    async fn attempt_task(
+        &self,
+        wrapper: TaskEnvelopeWrapper,
+        topic: &str,
+        will_retry: bool,
+    ) -> Result<ProcessingResult, AttemptFailure> {
+        let is_retained = false; // Assume not retained
+
         // Process the task (agent does its work)
         let result = self
             .processor
-            .process_task(wrapper.clone(), &topic, is_retained)
+            .process_task_attempt(wrapper.clone(), topic, is_retained, will_retry)
             .await
             .map_err(|e| {
-                error!("Task processing failed: {}", e);
-                PipelineError::ProcessingFailed(e.to_string())
+                let retryable = e.is_retryable();
+                let retry_after = e.retry_after_ms().map(Duration::from_millis);
+                let error = match e {
+                    AgentError::Cancelled { message } => PipelineError::TaskCancelled(message),
+                    AgentError::DeadlineExceeded { deadline } => {
+                        PipelineError::DeadlineExceeded(deadline)
+                    }
+                    e => {
+                        error!("Task processing failed: {}", e);
+                        PipelineError::ProcessingFailed(e.to_string())
+                    }
+                };
+                AttemptFailure {
+                    error,
+                    retryable,
+                    retry_after,
+                }
             })?;
 
         // V2 ROUTING: Check if we should invoke the router
@@ -202,8 +1239,15 @@ impl<T: Transport + 'static> AgentPipeline<T> {
                     ))
                 })?;
 
-                // Invoke V2 routing workflow
-                self.process_with_routing(task, work_output).await?;
+                // Invoke V2 routing workflow, in the incoming task's trace so
+                // forwarded envelopes carry it on
+                let span = tracing::info_span!("task_routing", task_id = %task.task_id);
+                if let Some(traceparent) = &task.traceparent {
+                    crate::observability::otel::set_remote_parent(&span, traceparent);
+                }
+                self.process_with_routing(task, work_output)
+                    .instrument(span)
+                    .await?;
 
                 info!(
                     task_id = %result.task_id,
@@ -216,103 +1260,745 @@ impl<T: Transport + 'static> AgentPipeline<T> {
                 );
             }
         }
-
-        Ok(result)
+
+        Ok(result)
+    }
+
+    /// Backoff before the given retry, honouring a longer delay requested by the error
+    fn retry_delay(&self, attempt: u32, retry_after: Option<Duration>) -> Duration {
+        let backoff = self
+            .retry_base_delay
+            .saturating_mul(1 << attempt.saturating_sub(1).min(16))
+            .min(MAX_RETRY_DELAY);
+        retry_after.map_or(backoff, |retry_after| retry_after.max(backoff))
+    }
+
+    /// Process a task batch and publish its summary
+    ///
+    /// Items run through [`Self::process_single_task`] with bounded concurrency.
+    /// Item task ids are derived from the batch id, so items already seen by the
+    /// idempotency cache (for example after a re-delivered batch) are skipped and
+    /// reported as duplicates. Failed items never abort the rest of the batch.
+    pub async fn process_batch(&self, batch: TaskBatchEnvelope) -> BatchSummary {
+        self.process_batch_in_slots(batch, None).await
+    }
+
+    /// Process a batch, running each item in one of `slots` when given
+    async fn process_batch_in_slots(
+        &self,
+        batch: TaskBatchEnvelope,
+        slots: Option<&Arc<Semaphore>>,
+    ) -> BatchSummary {
+        info!(
+            batch_id = %batch.batch_id,
+            conversation_id = %batch.conversation_id,
+            items = batch.items.len(),
+            concurrency = self.batch_concurrency,
+            "Processing task batch"
+        );
+
+        let topic = format!("/control/agents/{}/input", self.processor.config().agent.id);
+        let results = stream::iter(batch.expand(&topic).into_iter().enumerate())
+            .map(|(index, task)| self.process_batch_item(index, task, slots))
+            .buffer_unordered(self.batch_concurrency)
+            .collect::<Vec<_>>()
+            .await;
+
+        let summary = BatchSummary::from_results(&batch, results);
+        info!(
+            batch_id = %summary.batch_id,
+            succeeded = summary.succeeded,
+            failed = summary.failed,
+            duplicates = summary.duplicates,
+            "Task batch finished"
+        );
+
+        if let Err(e) = self
+            .processor
+            .transport()
+            .publish_batch_summary(&summary.conversation_id, &summary)
+            .await
+        {
+            error!(error = %e, batch_id = %summary.batch_id, "Failed to publish batch summary");
+        }
+
+        summary
+    }
+
+    /// Process a single expanded batch item
+    async fn process_batch_item(
+        &self,
+        index: usize,
+        task: TaskEnvelope,
+        slots: Option<&Arc<Semaphore>>,
+    ) -> BatchItemResult {
+        let slot = match slots {
+            Some(slots) => Some(
+                slots
+                    .clone()
+                    .acquire_owned()
+                    .await
+                    .expect("task slots are never closed"),
+            ),
+            None => None,
+        };
+        if slot.is_some() {
+            metrics().task_in_flight();
+        }
+        let task_id = task.task_id;
+        let (status, error) = if self.processor.has_processed(&task_id).await {
+            debug!(task_id = %task_id, index, "Skipping already processed batch item");
+            (BatchItemStatus::Duplicate, None)
+        } else {
+            match self.process_isolated(TaskEnvelopeWrapper::V1(task)).await {
+                Ok(_) => (BatchItemStatus::Succeeded, None),
+                Err(e) => {
+                    warn!(task_id = %task_id, index, error = %e, "Batch item failed");
+                    (BatchItemStatus::Failed, Some(e.to_string()))
+                }
+            }
+        };
+
+        if slot.is_some() {
+            metrics().task_settled();
+        }
+        BatchItemResult {
+            index,
+            task_id,
+            status,
+            error,
+        }
+    }
+
+    /// Publish a deadline error for a task that expired while queued
+    async fn reject_expired_task(&self, wrapper: &TaskEnvelopeWrapper) -> PipelineError {
+        let deadline = wrapper
+            .deadline()
+            .map(|deadline| deadline.to_rfc3339())
+            .unwrap_or_default();
+        warn!(
+            task_id = %wrapper.task_id(),
+            deadline = %deadline,
+            "Task deadline passed while queued, skipping processing"
+        );
+
+        let error_message = AgentError::deadline_exceeded(deadline.clone())
+            .to_error_message(wrapper.task_id())
+            .with_correlation(
+                wrapper.correlation_id().map(str::to_string),
+                wrapper.parent_task_id(),
+            );
+        if let Err(e) = self
+            .processor
+            .transport()
+            .publish_error(wrapper.conversation_id(), &error_message)
+            .await
+        {
+            error!(error = %e, "Failed to publish deadline exceeded error");
+        }
+
+        PipelineError::DeadlineExceeded(deadline)
+    }
+
+    /// Publish a stale task error and return the pipeline error to settle with
+    async fn reject_stale_task(
+        &self,
+        wrapper: &TaskEnvelopeWrapper,
+        age: chrono::Duration,
+        max_task_age: Duration,
+    ) -> PipelineError {
+        let age_secs = age.num_seconds().max(0) as u64;
+        warn!(
+            task_id = %wrapper.task_id(),
+            age_secs,
+            max_task_age_secs = max_task_age.as_secs(),
+            "Task is older than the maximum task age, skipping processing"
+        );
+        metrics().task_stale();
+
+        let error_message = AgentError::stale_task(age_secs, max_task_age.as_secs())
+            .to_error_message(wrapper.task_id())
+            .with_correlation(
+                wrapper.correlation_id().map(str::to_string),
+                wrapper.parent_task_id(),
+            );
+        if let Err(e) = self
+            .processor
+            .transport()
+            .publish_error(wrapper.conversation_id(), &error_message)
+            .await
+        {
+            error!(error = %e, "Failed to publish stale task error");
+        }
+
+        PipelineError::StaleTask(wrapper.task_id().to_string())
+    }
+
+    /// Release resources held between tasks after the idle period passed
+    async fn go_idle(&self) {
+        info!(
+            idle_after_secs = self.idle_after.map(|idle_after| idle_after.as_secs()),
+            "No tasks received recently, agent idle; releasing resources"
+        );
+        metrics().set_idle(true);
+        self.processor.on_idle().await;
+    }
+
+    /// Record new work arriving, leaving the idle state if the agent was idle
+    fn wake(&self, idle: &mut IdleState) {
+        if idle.touch(tokio::time::Instant::now()) {
+            info!("Work received, agent active again");
+            metrics().set_idle(false);
+        }
+    }
+
+    /// Publish a retryable error for a task received while paused
+    async fn reject_paused_task(&self, wrapper: &TaskEnvelopeWrapper) {
+        info!(task_id = %wrapper.task_id(), "Agent paused, rejecting task");
+
+        let error_message = AgentError::Overloaded {
+            message: "Agent is paused; resubmit the task later".to_string(),
+        }
+        .to_error_message(wrapper.task_id())
+        .with_correlation(
+            wrapper.correlation_id().map(str::to_string),
+            wrapper.parent_task_id(),
+        );
+        if let Err(e) = self
+            .processor
+            .transport()
+            .publish_error(wrapper.conversation_id(), &error_message)
+            .await
+        {
+            error!(error = %e, "Failed to publish paused rejection");
+        }
+        self.complete_journaled(wrapper.task_id()).await;
+    }
+
+    /// Update agent status; a status equal to the last one published is not resent
+    pub async fn update_status(
+        &self,
+        status: crate::protocol::messages::AgentStatusType,
+    ) -> Result<(), PipelineError> {
+        let published = self
+            .status_publisher
+            .publish(status.clone())
+            .await
+            .map_err(|e| {
+                error!("Failed to publish status: {}", e);
+                PipelineError::TransportError(e.to_string())
+            })?;
+
+        if published {
+            debug!("Published status: {:?}", status);
+        }
+        Ok(())
+    }
+
+    // ===== V2 Routing Methods =====
+
+    /// Process task with V2 routing support
+    ///
+    /// This method:
+    /// 1. Processes the task with the agent (does work)
+    /// 2. Invokes the router to decide next step
+    /// 3. Either completes workflow or forwards to next agent
+    pub async fn process_with_routing(
+        &self,
+        task: TaskEnvelopeV2,
+        work_output: Value,
+    ) -> Result<(), PipelineError> {
+        // Check if we have a router configured
+        let router = self
+            .router
+            .as_ref()
+            .ok_or_else(|| PipelineError::ProcessingFailed("No router configured".to_string()))?;
+
+        info!(
+            task_id = %task.task_id,
+            iteration_count = task.context.as_ref().map(|c| c.iteration_count).unwrap_or(0),
+            "Invoking router for workflow decision"
+        );
+
+        if self.dry_run {
+            return self
+                .explain_routing(router.as_ref(), task, work_output)
+                .await;
+        }
+
+        // Consult the decision cache before invoking the router
+        let agent_id = &self.processor.config().agent.id;
+        let cache_key = self
+            .decision_cache
+            .as_ref()
+            .map(|cache| cache.key(&task, agent_id, &work_output));
+        let cached = self
+            .decision_cache
+            .as_ref()
+            .zip(cache_key.as_deref())
+            .and_then(|(cache, key)| cache.get(key, &self.agent_registry));
+        let cache_hit = cached.is_some();
+
+        let started = Instant::now();
+        let (decision, reasoning) = match cached {
+            Some(decision) => {
+                info!(task_id = %task.task_id, "Using cached routing decision");
+                (decision, None)
+            }
+            None => {
+                // Router decides next step
+                let explained = match router
+                    .explain_next_step(&task, &work_output, &self.agent_registry)
+                    .await
+                {
+                    Ok(explained) => explained,
+                    Err(e) => {
+                        self.record_routing(
+                            RoutingAuditEntry::new(
+                                task.task_id,
+                                &task.conversation_id,
+                                agent_id,
+                                RoutingOutcome::Error,
+                                started.elapsed(),
+                            )
+                            .with_error(e.to_string()),
+                        );
+                        return Err(PipelineError::ProcessingFailed(format!(
+                            "Routing failed: {e}"
+                        )));
+                    }
+                };
+                // A fan-out's join outlives the decision, so it is never replayed
+                if let (Some(cache), Some(key), false) = (
+                    &self.decision_cache,
+                    cache_key,
+                    explained.decision.is_fan_out(),
+                ) {
+                    cache.insert(key, explained.decision.clone());
+                }
+                (explained.decision, explained.reasoning)
+            }
+        };
+        let audit_entry = RoutingAuditEntry::new(
+            task.task_id,
+            &task.conversation_id,
+            agent_id,
+            RoutingOutcome::Complete,
+            started.elapsed(),
+        )
+        .with_reasoning(reasoning)
+        .with_cached(cache_hit);
+
+        match decision {
+            RoutingDecision::Complete { final_output } => {
+                self.record_routing(audit_entry);
+                info!(
+                    task_id = %task.task_id,
+                    conversation_id = %task.conversation_id,
+                    "Workflow complete, publishing final result"
+                );
+
+                // Publish final result to conversation topic
+                self.publish_final_result(&task, &final_output).await?;
+            }
+            RoutingDecision::Forward {
+                next_agent,
+                next_instruction,
+                forwarded_data,
+                sticky,
+            } => {
+                let next_agent =
+                    match self.resolve_forward_target(&task.conversation_id, next_agent, sticky) {
+                        Ok(next_agent) => next_agent,
+                        Err(e) => {
+                            self.record_routing(
+                                RoutingAuditEntry {
+                                    decision: RoutingOutcome::Error,
+                                    ..audit_entry
+                                }
+                                .with_error(e.to_string()),
+                            );
+                            return Err(e);
+                        }
+                    };
+                self.record_routing(
+                    RoutingAuditEntry {
+                        decision: RoutingOutcome::Forward,
+                        ..audit_entry
+                    }
+                    .with_next_agent(next_agent.as_str()),
+                );
+                info!(
+                    task_id = %task.task_id,
+                    next_agent = %next_agent,
+                    next_instruction = %next_instruction,
+                    "Forwarding to next agent"
+                );
+
+                // Forward to next agent with iteration enforcement
+                self.forward_to_agent(
+                    &task,
+                    next_agent,
+                    next_instruction,
+                    forwarded_data,
+                    state_updates(&work_output),
+                    cache_hit,
+                )
+                .await?;
+            }
+            RoutingDecision::FanOut {
+                branches,
+                aggregator_agent,
+                aggregator_instruction,
+                join_policy,
+            } => {
+                let resolved =
+                    self.resolve_fan_out_targets(&task.conversation_id, branches, aggregator_agent);
+                let (branches, aggregator_agent) = match resolved {
+                    Ok(resolved) => resolved,
+                    Err(e) => {
+                        self.record_routing(
+                            RoutingAuditEntry {
+                                decision: RoutingOutcome::Error,
+                                ..audit_entry
+                            }
+                            .with_error(e.to_string()),
+                        );
+                        return Err(e);
+                    }
+                };
+                self.record_routing(
+                    RoutingAuditEntry {
+                        decision: RoutingOutcome::FanOut,
+                        ..audit_entry
+                    }
+                    .with_next_agent(aggregator_agent.as_str()),
+                );
+                info!(
+                    task_id = %task.task_id,
+                    branches = branches.len(),
+                    aggregator = %aggregator_agent,
+                    "Fanning out to parallel agents"
+                );
+
+                self.fan_out_to_agents(
+                    &task,
+                    work_output,
+                    branches,
+                    aggregator_agent,
+                    aggregator_instruction,
+                    join_policy,
+                    cache_hit,
+                )
+                .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Resolve the branch and aggregator targets of a fan-out
+    ///
+    /// Branches are resolved without sticky pins, so a capability shared by
+    /// two branches may go to two agents; the aggregator is pinned like any
+    /// forward target.
+    fn resolve_fan_out_targets(
+        &self,
+        conversation_id: &str,
+        branches: Vec<ForwardBranch>,
+        aggregator_agent: String,
+    ) -> Result<(Vec<ForwardBranch>, String), PipelineError> {
+        if branches.is_empty() {
+            return Err(PipelineError::ProcessingFailed(
+                "Fan-out decision has no branches".to_string(),
+            ));
+        }
+        let branches = branches
+            .into_iter()
+            .map(|branch| {
+                Ok(ForwardBranch {
+                    next_agent: self.resolve_forward_target(
+                        conversation_id,
+                        branch.next_agent,
+                        false,
+                    )?,
+                    ..branch
+                })
+            })
+            .collect::<Result<Vec<_>, PipelineError>>()?;
+        let aggregator_agent =
+            self.resolve_forward_target(conversation_id, aggregator_agent, true)?;
+        Ok((branches, aggregator_agent))
+    }
+
+    /// Report a routing decision to the routing metrics and audit log
+    fn record_routing(&self, entry: RoutingAuditEntry) {
+        record_routing_decision(self.routing_audit_log.as_deref(), entry);
+    }
+
+    /// Resolve a router's target to a concrete agent, honouring sticky pins
+    ///
+    /// Plain agent ids are checked against the registry (see
+    /// [`Self::validate_agent_id`]). With sticky routing enabled and not opted
+    /// out of by the decision, a `capability:` target or a logical agent name
+    /// (a prefix of its replicas' ids) keeps going to the agent the
+    /// conversation was pinned to while that agent stays healthy; otherwise the
+    /// routing helper's strategy, or for a logical name the least loaded
+    /// replica, picks an agent, which is then pinned.
+    fn resolve_forward_target(
+        &self,
+        conversation_id: &str,
+        target: String,
+        sticky: bool,
+    ) -> Result<String, PipelineError> {
+        let capability = target.strip_prefix(CAPABILITY_TARGET_PREFIX).map(str::trim);
+        let sticky_routes = self.sticky_routes.as_ref().filter(|_| sticky);
+        if capability.is_none()
+            && (sticky_routes.is_none() || self.agent_registry.get_agent(&target).is_some())
+        {
+            return self.validate_agent_id(conversation_id, target);
+        }
+
+        if let Some(agent) = sticky_routes
+            .and_then(|routes| routes.pinned(conversation_id, &target, &self.agent_registry))
+        {
+            debug!(
+                conversation_id = %conversation_id,
+                agent_id = %agent.agent_id,
+                target = %target,
+                "Using sticky route"
+            );
+            return Ok(agent.agent_id);
+        }
+
+        let agent_id = match capability {
+            Some(_) => match self
+                .routing_helper()
+                .resolve_target(&target, &self.agent_registry)
+            {
+                AgentSelectionDecision::RouteToAgent { agent, .. } => agent.agent_id,
+                AgentSelectionDecision::NoRoute { reason } => {
+                    return Err(PipelineError::ProcessingFailed(format!(
+                        "Cannot forward to {target}: {reason}"
+                    )))
+                }
+            },
+            None => match self.least_loaded_replica(&target) {
+                Some(agent_id) => agent_id,
+                None => return self.validate_agent_id(conversation_id, target),
+            },
+        };
+        if let Some(routes) = sticky_routes {
+            routes.pin(conversation_id, &target, &agent_id);
+        }
+        Ok(agent_id)
     }
 
-    /// Update agent status
-    pub async fn update_status(
-        &self,
-        status: crate::protocol::messages::AgentStatusType,
-    ) -> Result<(), PipelineError> {
-        let status_msg = crate::protocol::messages::AgentStatus {
-            agent_id: self.processor.config().agent.id.clone(),
-            status: status.clone(),
-            timestamp: chrono::Utc::now(),
-            capabilities: None,
-            description: None,
-        };
+    /// The least loaded healthy replica of a logical agent name, ties broken
+    /// by agent id; replicas are the agents whose id the name is a prefix of
+    fn least_loaded_replica(&self, name: &str) -> Option<String> {
+        let name = name.trim().to_lowercase();
+        if name.is_empty() {
+            return None;
+        }
+        available_agents(&self.agent_registry)
+            .into_iter()
+            .filter(|agent_id| {
+                let agent_id = agent_id.to_lowercase();
+                agent_id.len() > name.len() && agent_id.starts_with(&name)
+            })
+            .filter_map(|agent_id| self.agent_registry.get_agent(&agent_id))
+            .min_by(|a, b| {
+                a.load
+                    .total_cmp(&b.load)
+                    .then_with(|| a.agent_id.cmp(&b.agent_id))
+            })
+            .map(|agent| agent.agent_id)
+    }
 
+    /// Routing helper shared with the processor, or the pipeline's own
+    fn routing_helper(&self) -> &RoutingHelper {
         self.processor
-            .transport()
-            .publish_status(&status_msg)
-            .await
-            .map_err(|e| {
-                error!("Failed to publish status: {}", e);
-                PipelineError::TransportError(e.to_string())
-            })?;
-
-        debug!("Published status: {:?}", status);
-        Ok(())
+            .routing_helper()
+            .unwrap_or(&self.fallback_routing_helper)
     }
 
-    // ===== V2 Routing Methods =====
+    /// Check a router's target against the registry
+    ///
+    /// An unknown id is replaced by its single case-insensitive or prefix match
+    /// when auto-correction is enabled; otherwise the error lists suggestions
+    /// and the healthy agents that could have been chosen.
+    fn validate_agent_id(
+        &self,
+        conversation_id: &str,
+        target: String,
+    ) -> Result<String, PipelineError> {
+        let agent_match = match_agent_id(&self.agent_registry, &target);
+        match agent_match {
+            AgentMatch::Exact(agent_id) => Ok(agent_id),
+            AgentMatch::Suggested(agent_id) if self.auto_correct_agent_ids => {
+                info!(
+                    requested = %target,
+                    agent_id = %agent_id,
+                    conversation_id = %conversation_id,
+                    "Auto-corrected router target to registered agent"
+                );
+                Ok(agent_id)
+            }
+            agent_match => {
+                warn!(
+                    next_agent = %target,
+                    conversation_id = %conversation_id,
+                    "Router selected non-existent agent"
+                );
+                Err(PipelineError::ProcessingFailed(format!(
+                    "Cannot forward to {}",
+                    describe_unknown_agent(&self.agent_registry, &target, &agent_match)
+                )))
+            }
+        }
+    }
 
-    /// Process task with V2 routing support
+    /// Dry-run routing: publish the router's decision, then complete locally
     ///
-    /// This method:
-    /// 1. Processes the task with the agent (does work)
-    /// 2. Invokes the router to decide next step
-    /// 3. Either completes workflow or forwards to next agent
-    pub async fn process_with_routing(
+    /// The decision cache is bypassed so every explanation reflects a fresh
+    /// router call.
+    async fn explain_routing(
         &self,
+        router: &dyn Router,
         task: TaskEnvelopeV2,
         work_output: Value,
     ) -> Result<(), PipelineError> {
-        // Check if we have a router configured
-        let router = self
-            .router
-            .as_ref()
-            .ok_or_else(|| PipelineError::ProcessingFailed("No router configured".to_string()))?;
+        let agent_id = &self.processor.config().agent.id;
+        let started = Instant::now();
+        let explained = match router
+            .explain_next_step(&task, &work_output, &self.agent_registry)
+            .await
+        {
+            Ok(explained) => explained,
+            Err(e) => {
+                self.record_routing(
+                    RoutingAuditEntry::new(
+                        task.task_id,
+                        &task.conversation_id,
+                        agent_id,
+                        RoutingOutcome::Error,
+                        started.elapsed(),
+                    )
+                    .with_error(e.to_string()),
+                );
+                return Err(PipelineError::ProcessingFailed(format!(
+                    "Routing failed: {e}"
+                )));
+            }
+        };
+        let audit_entry = RoutingAuditEntry::new(
+            task.task_id,
+            &task.conversation_id,
+            agent_id,
+            RoutingOutcome::Complete,
+            started.elapsed(),
+        )
+        .with_reasoning(explained.reasoning.clone());
+        // A fan-out is explained by its aggregator, which would receive the
+        // joined branches
+        let (outcome, next_agent, next_instruction, final_output) = match explained.decision {
+            RoutingDecision::Complete { final_output } => {
+                (RoutingOutcome::Complete, None, None, final_output)
+            }
+            RoutingDecision::Forward {
+                next_agent,
+                next_instruction,
+                ..
+            } => (
+                RoutingOutcome::Forward,
+                Some(next_agent),
+                Some(next_instruction),
+                work_output,
+            ),
+            RoutingDecision::FanOut {
+                aggregator_agent,
+                aggregator_instruction,
+                ..
+            } => (
+                RoutingOutcome::FanOut,
+                Some(aggregator_agent),
+                Some(aggregator_instruction),
+                work_output,
+            ),
+        };
+        // Nothing is forwarded in a dry run, so the router's raw target is recorded
+        self.record_routing(match &next_agent {
+            Some(next_agent) => RoutingAuditEntry {
+                decision: outcome,
+                ..audit_entry
+            }
+            .with_next_agent(next_agent.as_str()),
+            None => audit_entry,
+        });
+        let explanation = RoutingExplanation {
+            task_id: task.task_id,
+            conversation_id: task.conversation_id.clone(),
+            agent_id: agent_id.clone(),
+            workflow_complete: next_agent.is_none(),
+            next_agent,
+            next_instruction,
+            reasoning: explained.reasoning,
+            iteration_count: task.context.as_ref().map_or(0, |c| c.iteration_count),
+            timestamp: self.now(),
+        };
 
         info!(
             task_id = %task.task_id,
-            iteration_count = task.context.as_ref().map(|c| c.iteration_count).unwrap_or(0),
-            "Invoking router for workflow decision"
+            workflow_complete = explanation.workflow_complete,
+            next_agent = explanation.next_agent.as_deref().unwrap_or("-"),
+            next_instruction = explanation.next_instruction.as_deref().unwrap_or("-"),
+            reasoning = explanation.reasoning.as_deref().unwrap_or("-"),
+            "Routing dry run: completing workflow locally"
         );
 
-        // Router decides next step
-        let decision = router
-            .decide_next_step(&task, &work_output, &self.agent_registry)
+        let payload = serde_json::to_vec(&explanation).map_err(|e| {
+            PipelineError::ProcessingFailed(format!("Failed to serialize explanation: {e}"))
+        })?;
+        self.processor
+            .transport()
+            .publish(
+                &TopicBuilder::build_routing_explain_topic(agent_id),
+                payload,
+                false,
+            )
             .await
-            .map_err(|e| PipelineError::ProcessingFailed(format!("Routing failed: {e}")))?;
+            .map_err(|e| PipelineError::TransportError(e.to_string()))?;
 
-        match decision {
-            RoutingDecision::Complete { final_output } => {
-                info!(
-                    task_id = %task.task_id,
-                    conversation_id = %task.conversation_id,
-                    "Workflow complete, publishing final result"
-                );
+        self.publish_final_result(&task, &final_output).await
+    }
 
-                // Publish final result to conversation topic
-                self.publish_final_result(&task.conversation_id, &final_output)
-                    .await?;
-            }
-            RoutingDecision::Forward {
-                next_agent,
-                next_instruction,
-                forwarded_data,
-            } => {
-                info!(
-                    task_id = %task.task_id,
-                    next_agent = %next_agent,
-                    next_instruction = %next_instruction,
-                    "Forwarding to next agent"
-                );
+    /// The current time, from the processor's clock when it has one
+    fn now(&self) -> DateTime<Utc> {
+        self.processor
+            .clock()
+            .map_or_else(Utc::now, |clock| clock.now())
+    }
 
-                // Forward to next agent with iteration enforcement
-                self.forward_to_agent(&task, next_agent, next_instruction, forwarded_data)
-                    .await?;
-            }
+    /// Id of a task forwarded from `parent` at `hop`
+    ///
+    /// Random, except in `[testing] deterministic` mode, where it is derived
+    /// from the parent's task id and the hop so a rerun forwards the same ids.
+    fn forwarded_task_id(&self, parent: &TaskEnvelopeV2, hop: &str) -> Uuid {
+        if self.processor.config().testing.deterministic {
+            derived_task_id(parent.task_id, hop)
+        } else {
+            Uuid::new_v4()
         }
-
-        Ok(())
     }
 
     /// Prepare workflow context - clone existing or synthesize default
     /// Pure function extracted for testability
-    fn prepare_workflow_context(original_task: &TaskEnvelopeV2) -> WorkflowContext {
+    fn prepare_workflow_context(
+        original_task: &TaskEnvelopeV2,
+        workflow_budget_secs: Option<u64>,
+        now: DateTime<Utc>,
+    ) -> WorkflowContext {
         match original_task.context.clone() {
             Some(ctx) => ctx,
             None => {
@@ -322,32 +2008,31 @@ impl<T: Transport + 'static> AgentPipeline<T> {
                     conversation_id = %original_task.conversation_id,
                     "Missing workflow context on forward; synthesizing default context"
                 );
-                synthesize_context_from_task(original_task)
+                synthesize_context_from_task(original_task, workflow_budget_secs, now)
             }
         }
     }
 
     /// Increment iteration count and validate against max limit
-    /// Returns Err if max iterations exceeded (with suggested final data)
+    /// The count saturates instead of overflowing
     fn increment_and_validate_iterations(
         context: &mut WorkflowContext,
         max_iterations: usize,
         conversation_id: &str,
-    ) -> Result<(), Value> {
-        context.iteration_count += 1;
+    ) -> IterationCheck {
+        context.iteration_count = context.iteration_count.saturating_add(1);
 
-        if context.iteration_count >= max_iterations {
+        if usize::try_from(context.iteration_count).map_or(true, |count| count >= max_iterations) {
             warn!(
                 conversation_id = %conversation_id,
                 iteration_count = context.iteration_count,
                 max_iterations = max_iterations,
                 "Max iterations reached, completing workflow"
             );
-            // Return Err with placeholder - caller should publish final result
-            return Err(Value::Null);
+            return IterationCheck::BudgetExhausted;
         }
 
-        Ok(())
+        IterationCheck::Continue
     }
 
     /// Add current workflow step to history and cap if needed
@@ -357,11 +2042,12 @@ impl<T: Transport + 'static> AgentPipeline<T> {
         agent_id: String,
         action: String,
         conversation_id: &str,
+        now: DateTime<Utc>,
     ) {
         context.steps_completed.push(WorkflowStep {
             agent_id,
             action,
-            timestamp: Utc::now().to_rfc3339(),
+            timestamp: now.to_rfc3339(),
         });
 
         // Cap workflow history to prevent unbounded growth
@@ -379,15 +2065,36 @@ impl<T: Transport + 'static> AgentPipeline<T> {
 
     /// Create next task envelope for forwarding
     /// Pure function for task construction
+    ///
+    /// The router's decision is appended to the original routing trace, marked
+    /// when it was served from the decision cache.
+    #[allow(clippy::too_many_arguments)]
     fn create_next_task_envelope(
         original_task: &TaskEnvelopeV2,
+        task_id: Uuid,
+        from_agent: &str,
         next_agent: &str,
         next_instruction: String,
         forwarded_data: Value,
         new_context: WorkflowContext,
+        cache_hit: bool,
+        now: DateTime<Utc>,
     ) -> TaskEnvelopeV2 {
-        TaskEnvelopeV2 {
-            task_id: Uuid::new_v4(),
+        let reason = if cache_hit {
+            format!("Router decision (cached): {next_instruction}")
+        } else {
+            format!("Router decision: {next_instruction}")
+        };
+        let routing_step = RoutingStep {
+            from_agent: from_agent.to_string(),
+            to_agent: next_agent.to_string(),
+            reason,
+            timestamp: now.to_rfc3339(),
+            step_number: original_task.next_routing_step_number(),
+        };
+
+        let mut next_task = TaskEnvelopeV2 {
+            task_id,
             conversation_id: original_task.conversation_id.clone(),
             topic: format!("/control/agents/{next_agent}/input"),
             instruction: Some(next_instruction),
@@ -396,42 +2103,56 @@ impl<T: Transport + 'static> AgentPipeline<T> {
             version: "2.0".to_string(),
             context: Some(new_context),
             routing_trace: original_task.routing_trace.clone(),
-        }
+            deadline: original_task.deadline,
+            correlation_id: original_task.correlation_id.clone(),
+            parent_task_id: Some(original_task.task_id),
+            published_at: None,
+            traceparent: None,
+            sender_id: Some(from_agent.to_string()),
+        };
+        next_task.push_routing_step(routing_step);
+        next_task
     }
 
     /// Forward task to next agent with iteration limit enforcement
+    ///
+    /// `next_agent` has already been checked by [`Self::resolve_forward_target`].
+    /// The agent's `state_updates` are merged into the forwarded context.
     async fn forward_to_agent(
         &self,
         original_task: &TaskEnvelopeV2,
         next_agent: String,
         next_instruction: String,
         forwarded_data: Value,
+        state_updates: Option<&Map<String, Value>>,
+        cache_hit: bool,
     ) -> Result<(), PipelineError> {
-        // Validate that the target agent exists in registry
-        if self.agent_registry.get_agent(&next_agent).is_none() {
+        // Prepare workflow context
+        let mut new_context =
+            Self::prepare_workflow_context(original_task, self.workflow_budget_secs, self.now());
+
+        // Past the wall-clock budget, the current output is the final result
+        if new_context.budget_exhausted(self.now()) {
             warn!(
-                next_agent = %next_agent,
                 conversation_id = %original_task.conversation_id,
-                "Router selected non-existent agent"
+                budget_secs = ?new_context.budget_secs,
+                next_agent = %next_agent,
+                "Workflow budget exhausted, completing workflow"
             );
-            return Err(PipelineError::ProcessingFailed(format!(
-                "Cannot forward to unknown agent: {next_agent}"
-            )));
+            return self
+                .publish_final_result(original_task, &annotate_budget_exhausted(forwarded_data))
+                .await;
         }
 
-        // Prepare workflow context
-        let mut new_context = Self::prepare_workflow_context(original_task);
-
         // Increment and validate iteration count
         if Self::increment_and_validate_iterations(
             &mut new_context,
             self.max_iterations,
             &original_task.conversation_id,
-        )
-        .is_err()
+        ) == IterationCheck::BudgetExhausted
         {
             return self
-                .publish_final_result(&original_task.conversation_id, &forwarded_data)
+                .publish_final_result(original_task, &forwarded_data)
                 .await;
         }
 
@@ -441,78 +2162,366 @@ impl<T: Transport + 'static> AgentPipeline<T> {
             self.processor.config().agent.id.clone(),
             next_instruction.clone(),
             &original_task.conversation_id,
+            self.now(),
         );
+        if let Some(updates) = state_updates {
+            new_context.merge_state(updates);
+        }
+
+        // Stop routers that keep sending the workflow around in circles
+        if let Some(cycle) = detect_cycle(&new_context.steps_completed, self.cycle_repeat_threshold)
+        {
+            warn!(
+                conversation_id = %original_task.conversation_id,
+                cycle_agents = ?cycle.agents,
+                repeats = cycle.repeats,
+                "Workflow cycle detected, completing workflow"
+            );
+            metrics().workflow_cycle_detected();
+            if let Some(progress) = self.processor.progress() {
+                progress
+                    .report_custom(
+                        ProgressCategory::General,
+                        ProgressEventType::Warning,
+                        Some(&original_task.task_id.to_string()),
+                        Some(&original_task.conversation_id),
+                        "Workflow cycle detected, completing early",
+                        Some(json!({
+                            "cycle_agents": cycle.agents,
+                            "repeats": cycle.repeats,
+                            "next_agent": next_agent,
+                        })),
+                    )
+                    .await;
+            }
+            return self
+                .publish_final_result(original_task, &annotate_cycle(forwarded_data, &cycle))
+                .await;
+        }
 
         // Create task for next agent
+        let task_id =
+            self.forwarded_task_id(original_task, &new_context.iteration_count.to_string());
         let next_task = Self::create_next_task_envelope(
             original_task,
+            task_id,
+            &self.processor.config().agent.id,
             &next_agent,
             next_instruction,
             forwarded_data,
             new_context,
+            cache_hit,
+            self.now(),
         );
 
-        // Publish to next agent's input topic
-        let topic = next_task.topic.clone();
-        let payload = serde_json::to_vec(&next_task).map_err(|e| {
-            PipelineError::ProcessingFailed(format!("Failed to serialize task: {e}"))
-        })?;
-
-        self.processor
+        // Publish to next agent's input topic, encoded, encrypted and signed
+        // like any other task
+        let iteration_count = next_task.context.as_ref().map_or(0, |c| c.iteration_count);
+        let published = self
+            .processor
             .transport()
-            .publish(&topic, payload, false)
-            .await
-            .map_err(|e| PipelineError::TransportError(e.to_string()))?;
+            .publish_task(&next_agent, &TaskEnvelopeWrapper::V2(next_task))
+            .await;
+
+        // Feed the outcome into load-aware selection's error rate
+        let selector = self.routing_helper().load_aware_selector();
+        match &published {
+            Ok(()) => selector.record_success(&next_agent),
+            Err(_) => selector.record_failure(&next_agent),
+        }
+        published.map_err(|e| PipelineError::TransportError(e.to_string()))?;
 
         info!(
             next_agent = %next_agent,
-            iteration_count = next_task.context.as_ref().map(|c| c.iteration_count).unwrap_or(0),
+            iteration_count = iteration_count,
             "Forwarded task to next agent"
         );
+        self.events.emit(AgentEvent::Forwarded {
+            task_id: original_task.task_id,
+            target: next_agent,
+        });
+
+        Ok(())
+    }
+
+    /// Publish each branch of a fan-out to its agent, then join the branches
+    /// in the background for the aggregator
+    ///
+    /// The fan-out is one workflow step: the budget and iteration limits are
+    /// checked once, and every branch and the aggregator continue from the
+    /// same context. Each branch runs in its own conversation (see
+    /// [`branch_conversation_id`]), subscribed to before the branch is
+    /// published so its result cannot be missed. A branch that cannot be
+    /// published counts as failed. Branch results are read as published, so
+    /// fan-in needs branch agents whose responses are not encrypted.
+    #[allow(clippy::too_many_arguments)]
+    async fn fan_out_to_agents(
+        &self,
+        original_task: &TaskEnvelopeV2,
+        work_output: Value,
+        branches: Vec<ForwardBranch>,
+        aggregator_agent: String,
+        aggregator_instruction: String,
+        join_policy: JoinPolicy,
+        cache_hit: bool,
+    ) -> Result<(), PipelineError> {
+        let agent_id = &self.processor.config().agent.id;
+        let mut new_context =
+            Self::prepare_workflow_context(original_task, self.workflow_budget_secs, self.now());
+
+        // Past the wall-clock budget, the current output is the final result
+        if new_context.budget_exhausted(self.now()) {
+            warn!(
+                conversation_id = %original_task.conversation_id,
+                budget_secs = ?new_context.budget_secs,
+                "Workflow budget exhausted, completing workflow instead of fanning out"
+            );
+            return self
+                .publish_final_result(original_task, &annotate_budget_exhausted(work_output))
+                .await;
+        }
+
+        if Self::increment_and_validate_iterations(
+            &mut new_context,
+            self.max_iterations,
+            &original_task.conversation_id,
+        ) == IterationCheck::BudgetExhausted
+        {
+            return self.publish_final_result(original_task, &work_output).await;
+        }
+
+        Self::add_workflow_step(
+            &mut new_context,
+            agent_id.clone(),
+            format!(
+                "Fan out to {} agents, joined by {aggregator_agent}",
+                branches.len()
+            ),
+            &original_task.conversation_id,
+            self.now(),
+        );
+        if let Some(updates) = state_updates(&work_output) {
+            new_context.merge_state(updates);
+        }
+
+        // Subscribe to every branch before publishing any of them
+        let hop = new_context.iteration_count;
+        let fanout_id = self.forwarded_task_id(original_task, &format!("{hop}.fanout"));
+        let transport = self.processor.transport().clone();
+        let mut subscriptions = Vec::with_capacity(branches.len());
+        let mut fan_out_branches = Vec::with_capacity(branches.len());
+        for (index, branch) in branches.iter().enumerate() {
+            let conversation_id =
+                branch_conversation_id(&original_task.conversation_id, fanout_id, index);
+            let filter = branch_topic_filter(&conversation_id);
+            match transport.subscribe_topic(&filter).await {
+                Ok(receiver) => subscriptions.push((filter, receiver)),
+                Err(e) => {
+                    for (filter, _) in &subscriptions {
+                        let _ = transport.unsubscribe(filter).await;
+                    }
+                    return Err(PipelineError::TransportError(format!(
+                        "Failed to subscribe to fan-out branch {index}: {e}"
+                    )));
+                }
+            }
+            fan_out_branches.push(FanOutBranch {
+                agent_id: branch.next_agent.clone(),
+                instruction: branch.next_instruction.clone(),
+                conversation_id,
+            });
+        }
+        self.fan_in.register(FanOutJoin::new(
+            fanout_id,
+            fan_out_branches.clone(),
+            join_policy.clone(),
+        ));
+
+        let selector = self.routing_helper().load_aware_selector();
+        for (index, (branch, fan_out_branch)) in
+            branches.into_iter().zip(&fan_out_branches).enumerate()
+        {
+            let mut branch_task = Self::create_next_task_envelope(
+                original_task,
+                self.forwarded_task_id(original_task, &format!("{hop}.{index}")),
+                agent_id,
+                &branch.next_agent,
+                branch.next_instruction,
+                branch.forwarded_data,
+                new_context.clone(),
+                cache_hit,
+                self.now(),
+            );
+            branch_task.conversation_id = fan_out_branch.conversation_id.clone();
+            let published = transport
+                .publish_task(&branch.next_agent, &TaskEnvelopeWrapper::V2(branch_task))
+                .await;
+            match published {
+                Ok(()) => {
+                    selector.record_success(&branch.next_agent);
+                    self.events.emit(AgentEvent::Forwarded {
+                        task_id: original_task.task_id,
+                        target: branch.next_agent,
+                    });
+                }
+                Err(e) => {
+                    selector.record_failure(&branch.next_agent);
+                    warn!(
+                        fanout_id = %fanout_id,
+                        branch = index,
+                        next_agent = %branch.next_agent,
+                        error = %e,
+                        "Failed to publish fan-out branch"
+                    );
+                    let error = ErrorDetails::new(
+                        ErrorCode::InternalError,
+                        format!("Failed to publish branch to {}: {e}", branch.next_agent),
+                    );
+                    self.fan_in
+                        .record(fanout_id, index, BranchOutcome::Failed(error));
+                }
+            }
+        }
+
+        let aggregation_task = Self::create_next_task_envelope(
+            original_task,
+            self.forwarded_task_id(original_task, &format!("{hop}.aggregate")),
+            agent_id,
+            &aggregator_agent,
+            aggregator_instruction,
+            Value::Null,
+            new_context,
+            cache_hit,
+            self.now(),
+        );
+        info!(
+            fanout_id = %fanout_id,
+            branches = fan_out_branches.len(),
+            aggregator = %aggregator_agent,
+            timeout_secs = join_policy.timeout_secs,
+            "Fanned out task, waiting for branches"
+        );
+        let join = JoinTask {
+            transport,
+            tracker: self.fan_in.clone(),
+            fanout_id,
+            subscriptions,
+            timeout: Duration::from_secs(join_policy.timeout_secs),
+            aggregation_task,
+            aggregator_agent,
+            origin: original_task.clone(),
+        };
+        tokio::spawn(join.run());
 
         Ok(())
     }
 
     /// Publish final workflow result to conversation topic
+    ///
+    /// Like the responses of the 9-step processor, the result carries the
+    /// task's correlation, the routing trace of the whole workflow, and the
+    /// conversation and agent it came from, and is cut down to the agent's
+    /// `[agent.response_limit]`. An `artifacts` array in an object output is
+    /// published in the response's `artifacts` instead of its text. The text
+    /// is checked by the processor's moderation policy first; a blocked result
+    /// is published as a `policy_violation` error.
     async fn publish_final_result(
         &self,
-        conversation_id: &str,
+        task: &TaskEnvelopeV2,
         final_output: &Value,
     ) -> Result<(), PipelineError> {
-        let topic = format!(
-            "/conversations/{}/{}",
-            conversation_id,
-            self.processor.config().agent.id
-        );
-
-        let payload = serde_json::to_vec(final_output).map_err(|e| {
-            PipelineError::ProcessingFailed(format!("Failed to serialize output: {e}"))
-        })?;
+        let agent_config = &self.processor.config().agent;
+        let (output, artifacts) = split_artifacts(final_output);
+        let mut text = Self::final_response_text(&output);
+        if let Some(policy) = self.processor.moderation() {
+            text = match moderate(policy.as_ref(), text).await {
+                Ok(text) => text,
+                Err(e) => return Err(self.publish_final_result_error(task, e).await),
+            };
+        }
+        let mut response = ResponseMessage {
+            response: text,
+            task_id: task.task_id,
+            routing_trace: task.routing_trace.clone(),
+            correlation_id: task.correlation_id.clone(),
+            parent_task_id: task.parent_task_id,
+            conversation_id: Some(task.conversation_id.clone()),
+            agent_id: Some(self.processor.config().agent.id.clone()),
+            completed_at: Some(self.now()),
+            oversized: None,
+            artifacts: apply_artifact_limits(
+                artifacts,
+                &agent_config.artifact_limits,
+                task.task_id,
+            ),
+        };
+        if let Some(ref limit) = agent_config.response_limit {
+            if let Err(e) =
+                apply_response_limit(limit, agent_config.state_dir.as_deref(), &mut response).await
+            {
+                return Err(self.publish_final_result_error(task, e).await);
+            }
+        }
 
         self.processor
             .transport()
-            .publish(&topic, payload, false)
+            .publish_response(&task.conversation_id, &response)
             .await
             .map_err(|e| PipelineError::TransportError(e.to_string()))?;
 
         info!(
-            conversation_id = %conversation_id,
-            topic = %topic,
+            conversation_id = %task.conversation_id,
+            task_id = %task.task_id,
             "Published final workflow result"
         );
+        self.events.emit(AgentEvent::WorkflowCompleted {
+            task_id: task.task_id,
+            final_output: final_output.clone(),
+        });
 
         Ok(())
     }
 
+    /// Publish the error that stopped the final result of `task` from being
+    /// published, in its place
+    async fn publish_final_result_error(
+        &self,
+        task: &TaskEnvelopeV2,
+        e: AgentError,
+    ) -> PipelineError {
+        let error_message = e
+            .to_error_message(task.task_id)
+            .with_correlation(task.correlation_id.clone(), task.parent_task_id);
+        if let Err(publish_error) = self
+            .processor
+            .transport()
+            .publish_error(&task.conversation_id, &error_message)
+            .await
+        {
+            error!(error = %publish_error, "Failed to publish final result error");
+        }
+        PipelineError::ProcessingFailed(e.to_string())
+    }
+
+    /// Response text for a final workflow output: strings are sent as-is,
+    /// anything else as JSON
+    /// Pure function extracted for testability
+    fn final_response_text(final_output: &Value) -> String {
+        match final_output {
+            Value::String(text) => text.clone(),
+            other => other.to_string(),
+        }
+    }
+
     /// Shutdown the pipeline gracefully
     pub async fn shutdown(self) -> Result<(), PipelineError> {
         info!("Shutting down agent pipeline");
 
-        // Update status to unavailable
-        let pipeline_ref = &self;
-        pipeline_ref
-            .update_status(crate::protocol::messages::AgentStatusType::Unavailable)
-            .await?;
+        // The agent's one final Unavailable, unless the lifecycle already sent it
+        self.status_publisher
+            .publish_final()
+            .await
+            .map_err(|e| PipelineError::TransportError(e.to_string()))?;
 
         // Note: AgentProcessor doesn't need explicit shutdown - cleanup is automatic
         info!("Agent pipeline shutdown complete");
@@ -537,12 +2546,24 @@ pub enum PipelineError {
 
     #[error("Shutdown error: {0}")]
     ShutdownError(String),
+
+    #[error("Task cancelled: {0}")]
+    TaskCancelled(String),
+
+    #[error("Task deadline {0} exceeded")]
+    DeadlineExceeded(String),
+
+    #[error("Task processing panicked: {0}")]
+    TaskPanicked(String),
+
+    #[error("Task {0} is older than the maximum task age")]
+    StaleTask(String),
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::protocol::messages::{TaskEnvelopeV2, WorkflowStep};
+    use crate::protocol::messages::{TaskEnvelopeV2, WorkflowStep, MAX_ROUTING_TRACE_STEPS};
     use serde_json::json;
     use uuid::Uuid;
 
@@ -560,12 +2581,20 @@ mod tests {
             version: "2.0".to_string(),
             context: None,
             routing_trace: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
         };
 
-        let context = synthesize_context_from_task(&task);
+        let context = synthesize_context_from_task(&task, Some(120), Utc::now());
         assert_eq!(context.original_query, "Research Herodotus");
         assert_eq!(context.steps_completed.len(), 0);
         assert_eq!(context.iteration_count, 0);
+        assert!(context.started_at.is_some());
+        assert_eq!(context.budget_secs, Some(120));
     }
 
     #[test]
@@ -580,9 +2609,15 @@ mod tests {
             version: "2.0".to_string(),
             context: None,
             routing_trace: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
         };
 
-        let context = synthesize_context_from_task(&task);
+        let context = synthesize_context_from_task(&task, None, Utc::now());
         assert_eq!(context.original_query, "Unknown");
     }
 
@@ -598,9 +2633,15 @@ mod tests {
             version: "2.0".to_string(),
             context: None,
             routing_trace: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
         };
 
-        let context = synthesize_context_from_task(&task);
+        let context = synthesize_context_from_task(&task, None, Utc::now());
         assert_eq!(context.original_query, "Unknown");
     }
 
@@ -690,6 +2731,10 @@ mod tests {
             original_query: "Test query".to_string(),
             steps_completed: vec![],
             iteration_count: 5,
+            started_at: None,
+            budget_secs: None,
+            state: None,
+            state_order: Vec::new(),
         };
 
         let task = TaskEnvelopeV2 {
@@ -702,10 +2747,20 @@ mod tests {
             version: "2.0".to_string(),
             context: Some(existing_context.clone()),
             routing_trace: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
         };
 
         let result =
-            AgentPipeline::<crate::testing::mocks::MockTransport>::prepare_workflow_context(&task);
+            AgentPipeline::<crate::testing::mocks::MockTransport>::prepare_workflow_context(
+                &task,
+                None,
+                Utc::now(),
+            );
         assert_eq!(result.original_query, "Test query");
         assert_eq!(result.iteration_count, 5);
     }
@@ -722,20 +2777,45 @@ mod tests {
             version: "2.0".to_string(),
             context: None,
             routing_trace: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
         };
 
         let result =
-            AgentPipeline::<crate::testing::mocks::MockTransport>::prepare_workflow_context(&task);
+            AgentPipeline::<crate::testing::mocks::MockTransport>::prepare_workflow_context(
+                &task,
+                None,
+                Utc::now(),
+            );
         assert_eq!(result.original_query, "Synthesized");
         assert_eq!(result.iteration_count, 0);
     }
 
+    #[test]
+    fn test_final_response_text_keeps_strings_and_serializes_json() {
+        type Pipeline = AgentPipeline<crate::testing::mocks::MockTransport>;
+
+        assert_eq!(Pipeline::final_response_text(&json!("done")), "done");
+        assert_eq!(
+            Pipeline::final_response_text(&json!({"summary": "done"})),
+            r#"{"summary":"done"}"#
+        );
+    }
+
     #[test]
     fn test_increment_and_validate_iterations_below_limit() {
         let mut context = WorkflowContext {
             original_query: "Test".to_string(),
             steps_completed: vec![],
             iteration_count: 3,
+            started_at: None,
+            budget_secs: None,
+            state: None,
+            state_order: Vec::new(),
         };
 
         let result = AgentPipeline::<crate::testing::mocks::MockTransport>::increment_and_validate_iterations(
@@ -744,7 +2824,11 @@ mod tests {
             "conv1",
         );
 
-        assert!(result.is_ok(), "Should succeed when below limit");
+        assert_eq!(
+            result,
+            IterationCheck::Continue,
+            "Should succeed when below limit"
+        );
         assert_eq!(context.iteration_count, 4, "Should increment count");
     }
 
@@ -754,6 +2838,10 @@ mod tests {
             original_query: "Test".to_string(),
             steps_completed: vec![],
             iteration_count: 9,
+            started_at: None,
+            budget_secs: None,
+            state: None,
+            state_order: Vec::new(),
         };
 
         let result = AgentPipeline::<crate::testing::mocks::MockTransport>::increment_and_validate_iterations(
@@ -762,7 +2850,11 @@ mod tests {
             "conv1",
         );
 
-        assert!(result.is_err(), "Should fail when at limit");
+        assert_eq!(
+            result,
+            IterationCheck::BudgetExhausted,
+            "Should fail when at limit"
+        );
         assert_eq!(
             context.iteration_count, 10,
             "Should still increment before failing"
@@ -775,6 +2867,10 @@ mod tests {
             original_query: "Test".to_string(),
             steps_completed: vec![],
             iteration_count: 15,
+            started_at: None,
+            budget_secs: None,
+            state: None,
+            state_order: Vec::new(),
         };
 
         let result = AgentPipeline::<crate::testing::mocks::MockTransport>::increment_and_validate_iterations(
@@ -783,10 +2879,36 @@ mod tests {
             "conv1",
         );
 
-        assert!(result.is_err(), "Should fail when exceeding limit");
+        assert_eq!(
+            result,
+            IterationCheck::BudgetExhausted,
+            "Should fail when exceeding limit"
+        );
         assert_eq!(context.iteration_count, 16);
     }
 
+    #[test]
+    fn test_increment_and_validate_iterations_saturates() {
+        let mut context = WorkflowContext {
+            original_query: "Test".to_string(),
+            steps_completed: vec![],
+            iteration_count: u32::MAX,
+            started_at: None,
+            budget_secs: None,
+            state: None,
+            state_order: Vec::new(),
+        };
+
+        let result = AgentPipeline::<crate::testing::mocks::MockTransport>::increment_and_validate_iterations(
+            &mut context,
+            10,
+            "conv1",
+        );
+
+        assert_eq!(result, IterationCheck::BudgetExhausted);
+        assert_eq!(context.iteration_count, u32::MAX);
+    }
+
     #[test]
     fn test_add_workflow_step_below_cap() {
         let mut context = WorkflowContext {
@@ -797,6 +2919,10 @@ mod tests {
                 timestamp: "2024-01-01T00:00:00Z".to_string(),
             }],
             iteration_count: 1,
+            started_at: None,
+            budget_secs: None,
+            state: None,
+            state_order: Vec::new(),
         };
 
         AgentPipeline::<crate::testing::mocks::MockTransport>::add_workflow_step(
@@ -804,6 +2930,7 @@ mod tests {
             "agent2".to_string(),
             "action2".to_string(),
             "conv1",
+            Utc::now(),
         );
 
         assert_eq!(context.steps_completed.len(), 2);
@@ -822,7 +2949,11 @@ mod tests {
                     timestamp: "2024-01-01T00:00:00Z".to_string(),
                 })
                 .collect(),
-            iteration_count: MAX_WORKFLOW_HISTORY_STEPS,
+            iteration_count: MAX_WORKFLOW_HISTORY_STEPS as u32,
+            started_at: None,
+            budget_secs: None,
+            state: None,
+            state_order: Vec::new(),
         };
 
         let _initial_len = context.steps_completed.len();
@@ -832,6 +2963,7 @@ mod tests {
             "new_agent".to_string(),
             "new_action".to_string(),
             "conv1",
+            Utc::now(),
         );
 
         // Should be capped at max after adding
@@ -849,6 +2981,10 @@ mod tests {
             original_query: "Original query".to_string(),
             steps_completed: vec![],
             iteration_count: 3,
+            started_at: None,
+            budget_secs: None,
+            state: None,
+            state_order: Vec::new(),
         };
 
         let original_task = TaskEnvelopeV2 {
@@ -861,6 +2997,12 @@ mod tests {
             version: "2.0".to_string(),
             context: Some(original_context.clone()),
             routing_trace: Some(vec![]),
+            deadline: None,
+            correlation_id: Some("workflow-1".to_string()),
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
         };
 
         let new_context = WorkflowContext {
@@ -871,15 +3013,23 @@ mod tests {
                 timestamp: "2024-01-01T00:00:00Z".to_string(),
             }],
             iteration_count: 4,
+            started_at: None,
+            budget_secs: None,
+            state: None,
+            state_order: Vec::new(),
         };
 
         let result =
             AgentPipeline::<crate::testing::mocks::MockTransport>::create_next_task_envelope(
                 &original_task,
+                Uuid::new_v4(),
+                "agent1",
                 "agent2",
                 "Next instruction".to_string(),
                 json!({"forwarded": "data"}),
                 new_context.clone(),
+                false,
+                Utc::now(),
             );
 
         assert_eq!(result.conversation_id, "conv123");
@@ -888,5 +3038,69 @@ mod tests {
         assert_eq!(result.input, json!({"forwarded": "data"}));
         assert_eq!(result.version, "2.0");
         assert_eq!(result.context.unwrap().iteration_count, 4);
+        assert_eq!(result.correlation_id.as_deref(), Some("workflow-1"));
+        assert_eq!(result.parent_task_id, Some(original_task.task_id));
+        assert_ne!(result.task_id, original_task.task_id);
+
+        let trace = result.routing_trace.unwrap();
+        assert_eq!(trace.len(), 1);
+        assert_eq!(trace[0].from_agent, "agent1");
+        assert_eq!(trace[0].to_agent, "agent2");
+        assert_eq!(trace[0].step_number, 1);
+    }
+
+    #[test]
+    fn test_routing_trace_is_capped() {
+        let mut task = TaskEnvelopeV2 {
+            task_id: Uuid::new_v4(),
+            conversation_id: "conv123".to_string(),
+            topic: "/control/agents/agent1/input".to_string(),
+            instruction: None,
+            input: json!({}),
+            next: None,
+            version: "2.0".to_string(),
+            context: None,
+            routing_trace: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
+        };
+        for _ in 0..MAX_ROUTING_TRACE_STEPS {
+            task = AgentPipeline::<crate::testing::mocks::MockTransport>::create_next_task_envelope(
+                &task,
+                Uuid::new_v4(),
+                "agent1",
+                "agent1",
+                "Loop".to_string(),
+                json!({}),
+                synthesize_context_from_task(&task, None, Utc::now()),
+                false,
+                Utc::now(),
+            );
+        }
+        let next = AgentPipeline::<crate::testing::mocks::MockTransport>::create_next_task_envelope(
+            &task,
+            Uuid::new_v4(),
+            "agent1",
+            "agent2",
+            "Last".to_string(),
+            json!({}),
+            synthesize_context_from_task(&task, None, Utc::now()),
+            false,
+            Utc::now(),
+        );
+
+        // Oldest steps are dropped but numbering keeps counting
+        let trace = next.routing_trace.unwrap();
+        assert_eq!(trace.len(), MAX_ROUTING_TRACE_STEPS);
+        assert_eq!(trace[0].step_number, 2);
+        assert_eq!(
+            trace.last().unwrap().step_number,
+            MAX_ROUTING_TRACE_STEPS as u32 + 1
+        );
+        assert_eq!(trace.last().unwrap().to_agent, "agent2");
     }
 }
@@ -0,0 +1,36 @@
+//! Workflow wall-clock budget
+//!
+//! `max_iterations` caps the number of hops but not how long they take. The
+//! agent that starts a workflow stamps `started_at` and `budget_secs` into its
+//! context; an agent about to forward after the budget has run out completes
+//! the workflow with its current output instead.
+
+use super::cycle_detection::output_fields;
+use serde_json::{json, Value};
+
+/// Mark a final output as ended by the workflow budget - pure function
+///
+/// Object outputs gain a `budget_exhausted` field; any other output is
+/// wrapped under `output`.
+pub fn annotate_budget_exhausted(output: Value) -> Value {
+    let mut annotated = output_fields(output);
+    annotated.insert("budget_exhausted".to_string(), json!(true));
+    Value::Object(annotated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_annotate_budget_exhausted() {
+        assert_eq!(
+            annotate_budget_exhausted(json!({"draft": "text"})),
+            json!({"draft": "text", "budget_exhausted": true})
+        );
+        assert_eq!(
+            annotate_budget_exhausted(json!("plain")),
+            json!({"output": "plain", "budget_exhausted": true})
+        );
+    }
+}
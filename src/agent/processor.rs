@@ -4,17 +4,19 @@
 //! 9-step processor, maintaining backward compatibility while ensuring
 //! strict protocol compliance.
 
+use crate::clock::Clock;
 use crate::config::AgentConfig;
 use crate::error::{AgentError, AgentResult};
 use crate::llm::provider::LlmProvider;
+use crate::processing::hooks::ProcessingHook;
+use crate::processing::moderation::ModerationPolicy;
 use crate::processing::nine_step::{NineStepProcessor, ProcessingResult};
-use crate::progress::{MqttProgressReporter, ProgressConfig};
-use crate::protocol::messages::TaskEnvelopeWrapper;
+use crate::progress::{MqttProgressReporter, Progress, ProgressConfig};
+use crate::protocol::messages::{ErrorMessage, TaskEnvelopeWrapper};
 use crate::tools::ToolSystem;
 use crate::transport::Transport;
 use std::sync::Arc;
-use tracing::{error, info};
-use uuid::Uuid;
+use tracing::{error, info, warn};
 
 /// Simplified agent processor that enforces RFC compliance
 pub struct AgentProcessor<T: Transport> {
@@ -30,20 +32,24 @@ impl<T: Transport + 'static> AgentProcessor<T> {
         tool_system: Arc<ToolSystem>,
         transport: Arc<T>,
     ) -> Self {
-        // Create progress reporter
-        let progress_config = ProgressConfig::default();
-        let progress_reporter = Arc::new(MqttProgressReporter::new(
-            config.agent.id.clone(),
-            transport.clone(),
-            progress_config,
-        ));
+        let progress = Self::mqtt_progress(&config, &transport);
+        Self::with_progress(config, llm_provider, tool_system, transport, progress)
+    }
 
+    /// Create a processor reporting progress through `progress`
+    pub fn with_progress(
+        config: AgentConfig,
+        llm_provider: Arc<dyn LlmProvider>,
+        tool_system: Arc<ToolSystem>,
+        transport: Arc<T>,
+        progress: Arc<dyn Progress>,
+    ) -> Self {
         let nine_step_processor = NineStepProcessor::with_progress(
             config.clone(),
             llm_provider,
             tool_system,
             transport,
-            progress_reporter,
+            progress,
         );
 
         Self {
@@ -52,6 +58,57 @@ impl<T: Transport + 'static> AgentProcessor<T> {
         }
     }
 
+    /// The default progress reporter, publishing on the agent's MQTT progress
+    /// topics and, if `[progress]` enables them, on conversation progress topics
+    pub fn mqtt_progress(config: &AgentConfig, transport: &Arc<T>) -> Arc<dyn Progress> {
+        Arc::new(MqttProgressReporter::new(
+            config.agent.id.clone(),
+            transport.clone(),
+            ProgressConfig {
+                conversation_topics: config.progress.conversation_topics,
+                max_metadata_bytes: config.progress.max_metadata_bytes,
+                ..Default::default()
+            },
+        ))
+    }
+
+    /// Run hooks around the LLM and publish steps, in the given order
+    pub fn with_hooks(mut self, hooks: Vec<Arc<dyn ProcessingHook>>) -> Self {
+        self.nine_step_processor = self.nine_step_processor.with_hooks(hooks);
+        self
+    }
+
+    /// Read timestamps from `clock`, shared with the pipeline
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.nine_step_processor = self.nine_step_processor.with_clock(clock);
+        self
+    }
+
+    /// Check every response with `policy` before it is published
+    pub fn with_moderation(mut self, policy: Arc<dyn ModerationPolicy>) -> Self {
+        self.nine_step_processor = self.nine_step_processor.with_moderation(policy);
+        self
+    }
+
+    /// Read LLM settings and authorization rules from reloaded configuration
+    /// instead of the startup config
+    pub fn with_config_updates(
+        mut self,
+        config_updates: tokio::sync::watch::Receiver<AgentConfig>,
+    ) -> Self {
+        self.nine_step_processor = self.nine_step_processor.with_config_updates(config_updates);
+        self
+    }
+
+    /// Report the step each task is in to the admin endpoints' state registry
+    pub fn with_state_registry(
+        mut self,
+        state_registry: Arc<crate::observability::AgentStateRegistry>,
+    ) -> Self {
+        self.nine_step_processor = self.nine_step_processor.with_state_registry(state_registry);
+        self
+    }
+
     /// Get the agent configuration
     pub fn config(&self) -> &AgentConfig {
         &self.config
@@ -72,13 +129,37 @@ impl<T: Transport + 'static> AgentProcessor<T> {
     ///
     /// This is the ONLY way to process tasks. All budget tracking,
     /// conversation management, and other non-RFC features have been removed.
-    #[tracing::instrument(name = "process_task", skip(self, wrapper))]
     pub async fn process_task(
         &self,
         wrapper: TaskEnvelopeWrapper,
         received_topic: &str,
         is_retained: bool,
     ) -> AgentResult<ProcessingResult> {
+        self.process_task_attempt(wrapper, received_topic, is_retained, false)
+            .await
+    }
+
+    /// Process one attempt of a task that may be retried
+    ///
+    /// With `will_retry` set, a retryable failure is returned without
+    /// publishing its error to the conversation, since a later attempt may
+    /// still succeed.
+    #[tracing::instrument(
+        name = "process_task",
+        skip(self, wrapper),
+        fields(correlation_id = tracing::field::Empty)
+    )]
+    pub async fn process_task_attempt(
+        &self,
+        mut wrapper: TaskEnvelopeWrapper,
+        received_topic: &str,
+        is_retained: bool,
+        will_retry: bool,
+    ) -> AgentResult<ProcessingResult> {
+        // Workflows without a correlation id start here; every outbound message carries it
+        let correlation_id = wrapper.ensure_correlation_id_with(self.config.testing.deterministic);
+        tracing::Span::current().record("correlation_id", correlation_id.as_str());
+        let parent_task_id = wrapper.parent_task_id();
         let task_id = wrapper.task_id();
         let conversation_id = wrapper.conversation_id().to_string();
 
@@ -107,6 +188,14 @@ impl<T: Transport + 'static> AgentProcessor<T> {
                 );
                 Ok(result)
             }
+            Err(e) if will_retry && e.is_retryable() => {
+                warn!(
+                    error = %e,
+                    task_id = %task_id,
+                    "Task attempt failed with a retryable error"
+                );
+                Err(e)
+            }
             Err(e) => {
                 error!(
                     error = %e,
@@ -116,7 +205,11 @@ impl<T: Transport + 'static> AgentProcessor<T> {
                 );
 
                 // Publish error to conversation topic
-                if let Err(publish_error) = self.publish_error(&task_id, &conversation_id, &e).await
+                let error_message = e
+                    .to_error_message(task_id)
+                    .with_correlation(Some(correlation_id), parent_task_id);
+                if let Err(publish_error) =
+                    self.publish_error(&conversation_id, &error_message).await
                 {
                     error!(
                         error = %publish_error,
@@ -133,14 +226,11 @@ impl<T: Transport + 'static> AgentProcessor<T> {
     /// Publish error message to conversation topic per RFC requirements
     async fn publish_error(
         &self,
-        task_id: &Uuid,
         conversation_id: &str,
-        error: &AgentError,
+        error_message: &ErrorMessage,
     ) -> AgentResult<()> {
-        let error_message = error.to_error_message(*task_id);
-
         self.transport()
-            .publish_error(conversation_id, &error_message)
+            .publish_error(conversation_id, error_message)
             .await
             .map_err(|e| AgentError::internal_error(format!("Failed to publish error: {e}")))?;
 
@@ -164,6 +254,7 @@ mod processor_tests {
     use crate::protocol::messages::{TaskEnvelope, TaskEnvelopeWrapper};
     use crate::testing::mocks::{MockLlmProvider, MockTransport};
     use serde_json::json;
+    use uuid::Uuid;
 
     fn create_test_processor() -> AgentProcessor<MockTransport> {
         let config = AgentConfig::test_config();
@@ -183,6 +274,12 @@ mod processor_tests {
             instruction: Some("test instruction".to_string()),
             input: json!({"test": "data"}),
             next: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
         })
     }
 
@@ -248,6 +345,12 @@ mod processor_tests {
             next: None,
             context: None,
             routing_trace: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
         });
 
         let result = processor
@@ -286,6 +389,12 @@ mod processor_tests {
             instruction: Some("test instruction".to_string()),
             input: json!({"test": "data"}),
             next: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
         });
 
         let result = processor
@@ -307,6 +416,12 @@ mod processor_tests {
             instruction: None, // Empty instruction
             input: json!({"test": "data"}),
             next: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
         });
 
         let result = processor
@@ -330,6 +445,12 @@ mod processor_tests {
                 instruction: Some(format!("instruction-{i}")),
                 input: json!({"index": i}),
                 next: None,
+                deadline: None,
+                correlation_id: None,
+                parent_task_id: None,
+                published_at: None,
+                traceparent: None,
+                sender_id: None,
             });
 
             let _ = processor
@@ -2,8 +2,12 @@
 //!
 //! Provides structures and utilities for parsing agent decisions about routing.
 
+use crate::agent::route_decision::check_schema_version;
+use crate::protocol::messages::{deserialize_artifacts, Artifact};
+use crate::protocol::workflow_state::deserialize_state_updates;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use thiserror::Error;
 
 /// Agent's routing decision from LLM response
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -26,81 +30,170 @@ pub struct AgentDecision {
     /// True if workflow is complete
     #[serde(default)]
     pub workflow_complete: bool,
+
+    /// Structured outputs published alongside the result
+    #[serde(
+        default,
+        deserialize_with = "deserialize_artifacts",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub artifacts: Vec<Artifact>,
+
+    /// Keys to set in the workflow state passed to later agents; `null`
+    /// removes a key
+    #[serde(
+        default,
+        deserialize_with = "deserialize_state_updates",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub state_updates: Option<serde_json::Map<String, Value>>,
+}
+
+/// Fields an object needs to be read as an `AgentDecision`
+///
+/// `RouteDecision` also requires `schema_version` and `workflow_complete`;
+/// both default here so decisions from older prompts still parse.
+const REQUIRED_DECISION_FIELDS: [&str; 1] = ["result"];
+
+/// How many times a JSON string holding the decision is unwrapped
+const MAX_DECODE_DEPTH: usize = 2;
+
+/// Why a response could not be read as an agent decision
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum DecisionParseError {
+    #[error("no JSON object found in response")]
+    NoJsonObject,
+    #[error("response ends inside a JSON object, it may have been truncated")]
+    Truncated,
+    #[error("decision is missing required field(s): {}", .0.join(", "))]
+    MissingFields(Vec<&'static str>),
+    #[error("decision has an invalid field: {0}")]
+    InvalidField(String),
+    #[error(
+        "decision schema_version '{0}' is not supported; this agent supports {major}.x",
+        major = crate::agent::route_decision::SUPPORTED_SCHEMA_MAJOR
+    )]
+    UnsupportedSchemaVersion(String),
 }
 
 /// Parse agent decision from response string
-pub fn parse_agent_decision(response: &str) -> Result<AgentDecision, String> {
-    // First try to parse as raw JSON
-    if let Ok(decision) = serde_json::from_str::<AgentDecision>(response) {
-        return Ok(decision);
-    }
+///
+/// LLMs often wrap the decision in markdown fences, put prose before or after
+/// it, or encode it as a JSON string. The decision is the first complete JSON
+/// object in the response, or in its first fenced block if it has one.
+pub fn parse_agent_decision(response: &str) -> Result<AgentDecision, DecisionParseError> {
+    parse_decision_text(response, 0)
+}
+
+fn parse_decision_text(text: &str, depth: usize) -> Result<AgentDecision, DecisionParseError> {
+    let text = strip_code_fence(text);
 
-    // Try to extract JSON from markdown blocks
-    if let Some(json_str) = extract_json_from_markdown(response) {
-        if let Ok(decision) = serde_json::from_str::<AgentDecision>(&json_str) {
-            return Ok(decision);
+    // Double-encoded: the whole response is a JSON string holding the decision
+    if depth < MAX_DECODE_DEPTH {
+        if let Ok(Value::String(inner)) = serde_json::from_str::<Value>(text) {
+            return parse_decision_text(&inner, depth + 1);
         }
     }
 
-    // Try to find JSON object in the response
-    if let Some(json_str) = find_json_object(response) {
-        if let Ok(decision) = serde_json::from_str::<AgentDecision>(&json_str) {
-            return Ok(decision);
+    validate_decision(find_json_object(text)?)
+}
+
+/// Check an extracted object against the decision schema (pure function)
+fn validate_decision(
+    object: serde_json::Map<String, Value>,
+) -> Result<AgentDecision, DecisionParseError> {
+    let missing: Vec<&'static str> = REQUIRED_DECISION_FIELDS
+        .into_iter()
+        .filter(|field| !object.contains_key(*field))
+        .collect();
+    if !missing.is_empty() {
+        return Err(DecisionParseError::MissingFields(missing));
+    }
+    // Decisions without a version predate versioning and are read as 1.0
+    match object.get("schema_version") {
+        None | Some(Value::Null) => {}
+        Some(Value::String(version)) => check_schema_version(version)?,
+        Some(other) => {
+            return Err(DecisionParseError::InvalidField(format!(
+                "schema_version must be a string, got {other}"
+            )))
         }
     }
 
-    Err("Failed to parse agent decision from response".to_string())
+    let decision: AgentDecision = serde_json::from_value(Value::Object(object))
+        .map_err(|e| DecisionParseError::InvalidField(e.to_string()))?;
+    if decision.next_agent.as_deref() == Some("") {
+        return Err(DecisionParseError::InvalidField(
+            "next_agent must not be empty".to_string(),
+        ));
+    }
+    Ok(decision)
 }
 
-/// Extract JSON from markdown code blocks
-fn extract_json_from_markdown(text: &str) -> Option<String> {
-    // Look for ```json blocks
-    if let Some(start) = text.find("```json") {
-        let content = &text[start + 7..];
-        if let Some(end) = content.find("```") {
-            return Some(content[..end].trim().to_string());
-        }
+/// Content of the first markdown code block, or the whole text without one
+///
+/// The language tag (```json, ```JSON, ...) is skipped, and a block the LLM
+/// never closed runs to the end of the text.
+pub(crate) fn strip_code_fence(text: &str) -> &str {
+    let trimmed = text.trim();
+    let Some(start) = trimmed.find("```") else {
+        return trimmed;
+    };
+    let content = trimmed[start + 3..].trim_start_matches(|c: char| c.is_ascii_alphanumeric());
+    match content.find("```") {
+        Some(end) => content[..end].trim(),
+        None => content.trim(),
     }
+}
 
-    // Look for ``` blocks without language specifier
-    if let Some(start) = text.find("```") {
-        let content = &text[start + 3..];
-        if let Some(end) = content.find("```") {
-            let potential_json = content[..end].trim();
-            // Check if it looks like JSON
-            if potential_json.starts_with('{') && potential_json.ends_with('}') {
-                return Some(potential_json.to_string());
+/// First balanced top-level JSON object in `text`
+///
+/// Braces inside JSON strings don't count, and anything after the object is
+/// ignored. A candidate that isn't valid JSON is skipped for the next `{`.
+fn find_json_object(text: &str) -> Result<serde_json::Map<String, Value>, DecisionParseError> {
+    let mut truncated = false;
+
+    for (start, _) in text.match_indices('{') {
+        match balanced_object_end(&text[start..]) {
+            Some(len) => {
+                if let Ok(Value::Object(object)) = serde_json::from_str(&text[start..start + len]) {
+                    return Ok(object);
+                }
             }
+            None => truncated = true,
         }
     }
 
-    None
+    Err(if truncated {
+        DecisionParseError::Truncated
+    } else {
+        DecisionParseError::NoJsonObject
+    })
 }
 
-/// Find JSON object in text
-fn find_json_object(text: &str) -> Option<String> {
-    // Find the first { and try to match it with }
-    let mut brace_count = 0;
-    let mut start_pos = None;
+/// Length of the object `text` starts with, or None if it never closes
+fn balanced_object_end(text: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
 
     for (i, ch) in text.char_indices() {
-        match ch {
-            '{' => {
-                if start_pos.is_none() {
-                    start_pos = Some(i);
-                }
-                brace_count += 1;
+        if in_string {
+            match ch {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
             }
+            continue;
+        }
+        match ch {
+            '"' => in_string = true,
+            '{' => depth += 1,
             '}' => {
-                brace_count -= 1;
-                if brace_count == 0 && start_pos.is_some() {
-                    let json_str = &text[start_pos.unwrap()..=i];
-                    // Verify it's valid JSON
-                    if serde_json::from_str::<Value>(json_str).is_ok() {
-                        return Some(json_str.to_string());
-                    }
-                    // Reset and continue looking
-                    start_pos = None;
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i + 1);
                 }
             }
             _ => {}
@@ -118,6 +211,8 @@ impl Default for AgentDecision {
             next_agent: None,
             next_instruction: None,
             workflow_complete: false,
+            artifacts: Vec::new(),
+            state_updates: None,
         }
     }
 }
@@ -200,7 +295,76 @@ mod tests {
     fn test_invalid_json() {
         let response = "This is not JSON at all";
         let result = parse_agent_decision(response);
-        assert!(result.is_err());
+        assert_eq!(result.unwrap_err(), DecisionParseError::NoJsonObject);
+    }
+
+    /// Messy real-world outputs from `tests/fixtures/decisions`, with the
+    /// `next_agent` each parses to or the start of the error it produces
+    #[test]
+    fn test_messy_decision_fixtures() {
+        let cases: [(&str, Result<&str, &str>); 13] = [
+            ("fenced-json.txt", Ok("writer")),
+            ("fenced-untagged.txt", Ok("writer")),
+            ("unclosed-fence.txt", Ok("writer")),
+            ("prefixed.txt", Ok("writer")),
+            ("trailing-commentary.txt", Ok("writer")),
+            ("braces-in-strings.txt", Ok("writer")),
+            ("prose-with-braces-before.txt", Ok("writer")),
+            ("double-encoded.txt", Ok("writer")),
+            ("truncated.txt", Err("response ends inside a JSON object")),
+            (
+                "missing-result.txt",
+                Err("decision is missing required field(s): result"),
+            ),
+            ("wrong-type.txt", Err("decision has an invalid field")),
+            (
+                "empty-next-agent.txt",
+                Err("decision has an invalid field: next_agent must not be empty"),
+            ),
+            ("prose-only.txt", Err("no JSON object found in response")),
+        ];
+        let fixtures =
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/decisions");
+        assert_eq!(
+            std::fs::read_dir(&fixtures).unwrap().count(),
+            cases.len(),
+            "every fixture has an expected outcome"
+        );
+
+        for (file, expected) in cases {
+            let response = std::fs::read_to_string(fixtures.join(file)).unwrap();
+            match (parse_agent_decision(&response), expected) {
+                (Ok(decision), Ok(next_agent)) => {
+                    assert_eq!(decision.next_agent.as_deref(), Some(next_agent), "{file}");
+                    assert!(!decision.workflow_complete, "{file}");
+                }
+                (Err(e), Err(message)) => {
+                    assert!(e.to_string().starts_with(message), "{file}: {e}");
+                }
+                (outcome, expected) => {
+                    panic!("{file}: expected {expected:?}, got {outcome:?}")
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_braces_in_strings_are_kept() {
+        let response = r#"{"result": "a } b { c \" }", "workflow_complete": true}"#;
+
+        let decision = parse_agent_decision(response).unwrap();
+        assert_eq!(
+            decision.result,
+            Value::String(r#"a } b { c " }"#.to_string())
+        );
+    }
+
+    #[test]
+    fn test_fence_without_newline() {
+        let response = "```json {\"result\": \"done\"}``` and more text\nover lines";
+
+        let decision = parse_agent_decision(response).unwrap();
+        assert_eq!(decision.result, Value::String("done".to_string()));
     }
 
     #[test]
@@ -224,4 +388,62 @@ mod tests {
             panic!("Expected result to be a string");
         }
     }
+
+    #[test]
+    fn test_parse_decision_with_state_updates() {
+        let response = r#"{"result": "Noted", "state_updates": {"language": "fr", "tone": null}}"#;
+        let decision = parse_agent_decision(response).unwrap();
+        let updates = decision.state_updates.unwrap();
+        assert_eq!(updates["language"], "fr");
+        assert!(updates["tone"].is_null());
+
+        // A malformed update is dropped, not the decision
+        let response = r#"{"result": "Noted", "state_updates": ["language"]}"#;
+        assert!(parse_agent_decision(response)
+            .unwrap()
+            .state_updates
+            .is_none());
+    }
+
+    #[test]
+    fn test_parse_decision_with_artifacts() {
+        let response = r#"{"result": "Two found", "workflow_complete": true, "artifacts": [{"name": "people", "data": ["Ada", "Grace"]}, {"name": "broken"}]}"#;
+
+        let decision = parse_agent_decision(response).unwrap();
+        assert_eq!(decision.artifacts.len(), 1);
+        assert_eq!(decision.artifacts[0].name, "people");
+        assert_eq!(decision.artifacts[0].content_type, "application/json");
+        assert!(parse_agent_decision(r#"{"result": "x"}"#)
+            .unwrap()
+            .artifacts
+            .is_empty());
+    }
+
+    #[test]
+    fn test_later_minor_version_with_unknown_fields_is_read() {
+        let response = r#"{"schema_version": "1.3", "result": "done", "next_agent": "editor", "priority": "high", "workflow_complete": false}"#;
+
+        let decision = parse_agent_decision(response).unwrap();
+        assert_eq!(decision.schema_version, Some("1.3".to_string()));
+        assert_eq!(decision.next_agent.as_deref(), Some("editor"));
+    }
+
+    #[test]
+    fn test_unknown_major_version_is_rejected() {
+        let response = r#"{"schema_version": "2.0", "result": "done", "route_to": "editor"}"#;
+
+        let error = parse_agent_decision(response).unwrap_err();
+        assert_eq!(
+            error,
+            DecisionParseError::UnsupportedSchemaVersion("2.0".to_string())
+        );
+        assert_eq!(
+            error.to_string(),
+            "decision schema_version '2.0' is not supported; this agent supports 1.x"
+        );
+        assert!(matches!(
+            parse_agent_decision(r#"{"schema_version": 2, "result": "done"}"#),
+            Err(DecisionParseError::InvalidField(_))
+        ));
+    }
 }
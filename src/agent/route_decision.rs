@@ -3,8 +3,35 @@
 //! Defines the structured output format for agent routing decisions
 //! and provides the JSON schema for LLM structured outputs.
 
+use crate::agent::response::DecisionParseError;
+use crate::protocol::messages::{deserialize_artifacts, Artifact};
+use crate::protocol::workflow_state::deserialize_state_updates;
 use serde::{Deserialize, Serialize};
-use serde_json::{json, Value};
+use serde_json::{json, Map, Value};
+
+/// Decision schema version this agent produces
+pub const ROUTE_DECISION_SCHEMA_VERSION: &str = "1.0";
+
+/// Major schema version this agent understands; any 1.x decision is read as 1.0
+pub const SUPPORTED_SCHEMA_MAJOR: u64 = 1;
+
+/// Check that a decision's `schema_version` is one this agent can read
+///
+/// Minor versions only add fields, which are ignored, so every `1.x` is
+/// accepted. Another major version may rename or repurpose fields and is
+/// rejected rather than misread.
+pub fn check_schema_version(version: &str) -> Result<(), DecisionParseError> {
+    let major = version
+        .split('.')
+        .next()
+        .and_then(|major| major.trim().parse::<u64>().ok());
+    match major {
+        Some(SUPPORTED_SCHEMA_MAJOR) => Ok(()),
+        _ => Err(DecisionParseError::UnsupportedSchemaVersion(
+            version.to_string(),
+        )),
+    }
+}
 
 /// Agent routing decision from LLM response (v2 workflow)
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -25,6 +52,23 @@ pub struct RouteDecision {
 
     /// True if workflow is complete
     pub workflow_complete: bool,
+
+    /// Structured outputs published alongside the result
+    #[serde(
+        default,
+        deserialize_with = "deserialize_artifacts",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub artifacts: Vec<Artifact>,
+
+    /// Keys to set in the workflow state passed to later agents; `null`
+    /// removes a key
+    #[serde(
+        default,
+        deserialize_with = "deserialize_state_updates",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub state_updates: Option<Map<String, Value>>,
 }
 
 impl RouteDecision {
@@ -39,7 +83,7 @@ impl RouteDecision {
             "properties": {
                 "schema_version": {
                     "type": "string",
-                    "const": "1.0",
+                    "const": ROUTE_DECISION_SCHEMA_VERSION,
                     "description": "Schema version for evolution tracking"
                 },
                 "result": {
@@ -58,19 +102,95 @@ impl RouteDecision {
                 "workflow_complete": {
                     "type": "boolean",
                     "description": "Whether the workflow is complete"
+                },
+                "artifacts": {
+                    "type": "array",
+                    "description": "Structured outputs, such as tables or extracted entities, published alongside the result",
+                    "items": {
+                        "type": "object",
+                        "additionalProperties": false,
+                        "required": ["name", "data"],
+                        "properties": {
+                            "name": {"type": "string", "minLength": 1},
+                            "content_type": {
+                                "type": "string",
+                                "description": "Media type of data (default: application/json)"
+                            },
+                            "data": {"description": "The artifact itself"}
+                        }
+                    }
+                },
+                "state_updates": {
+                    "type": "object",
+                    "description": "Keys to set in the workflow state shared with later agents; null removes a key"
                 }
             }
         })
     }
 
+    /// Read a decision object, accepting any 1.x schema version
+    ///
+    /// Fields added by later minor versions are ignored. A missing
+    /// `workflow_complete` reads as false.
+    pub fn from_json(value: &Value) -> Result<Self, DecisionParseError> {
+        let Value::Object(object) = value else {
+            return Err(DecisionParseError::NoJsonObject);
+        };
+        let missing: Vec<&'static str> = ["schema_version", "result"]
+            .into_iter()
+            .filter(|field| !object.contains_key(*field))
+            .collect();
+        if !missing.is_empty() {
+            return Err(DecisionParseError::MissingFields(missing));
+        }
+        match &object["schema_version"] {
+            Value::String(version) => check_schema_version(version)?,
+            other => {
+                return Err(DecisionParseError::InvalidField(format!(
+                    "schema_version must be a string, got {other}"
+                )))
+            }
+        }
+
+        #[derive(Deserialize)]
+        struct Known {
+            schema_version: String,
+            result: Value,
+            next_agent: Option<String>,
+            next_instruction: Option<String>,
+            #[serde(default)]
+            workflow_complete: bool,
+            #[serde(default, deserialize_with = "deserialize_artifacts")]
+            artifacts: Vec<Artifact>,
+            #[serde(default, deserialize_with = "deserialize_state_updates")]
+            state_updates: Option<Map<String, Value>>,
+        }
+        let known: Known = serde_json::from_value(value.clone())
+            .map_err(|e| DecisionParseError::InvalidField(e.to_string()))?;
+        Ok(Self {
+            schema_version: known.schema_version,
+            result: match known.result {
+                Value::String(text) => text,
+                other => other.to_string(),
+            },
+            next_agent: known.next_agent,
+            next_instruction: known.next_instruction,
+            workflow_complete: known.workflow_complete,
+            artifacts: known.artifacts,
+            state_updates: known.state_updates,
+        })
+    }
+
     /// Create a RouteDecision from AgentDecision (fallback compatibility)
     pub fn from_agent_decision(decision: &crate::agent::response::AgentDecision) -> Self {
         Self {
-            schema_version: "1.0".to_string(),
+            schema_version: ROUTE_DECISION_SCHEMA_VERSION.to_string(),
             result: decision.result.to_string(),
             next_agent: decision.next_agent.clone(),
             next_instruction: decision.next_instruction.clone(),
             workflow_complete: decision.workflow_complete,
+            artifacts: decision.artifacts.clone(),
+            state_updates: decision.state_updates.clone(),
         }
     }
 }
@@ -78,11 +198,13 @@ impl RouteDecision {
 impl Default for RouteDecision {
     fn default() -> Self {
         Self {
-            schema_version: "1.0".to_string(),
+            schema_version: ROUTE_DECISION_SCHEMA_VERSION.to_string(),
             result: String::new(),
             next_agent: None,
             next_instruction: None,
             workflow_complete: false,
+            artifacts: Vec::new(),
+            state_updates: None,
         }
     }
 }
@@ -99,6 +221,8 @@ mod tests {
             next_agent: Some("writer-agent".to_string()),
             next_instruction: Some("Write article based on research".to_string()),
             workflow_complete: false,
+            artifacts: Vec::new(),
+            state_updates: None,
         };
 
         let json = serde_json::to_string(&decision).unwrap();
@@ -137,6 +261,8 @@ mod tests {
             next_agent: None,
             next_instruction: None,
             workflow_complete: true,
+            artifacts: Vec::new(),
+            state_updates: None,
         };
 
         let json = serde_json::to_string(&decision).unwrap();
@@ -172,4 +298,103 @@ mod tests {
         assert!(decision.next_instruction.is_none());
         assert!(!decision.workflow_complete);
     }
+
+    #[test]
+    fn test_schema_version_acceptance() {
+        for version in ["1.0", "1", "1.1", "1.12"] {
+            assert_eq!(check_schema_version(version), Ok(()), "{version}");
+        }
+        for version in ["2.0", "0.9", "v1", "", "one"] {
+            assert_eq!(
+                check_schema_version(version),
+                Err(DecisionParseError::UnsupportedSchemaVersion(
+                    version.to_string()
+                )),
+                "{version}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_from_json_ignores_fields_of_later_minor_versions() {
+        let decision = RouteDecision::from_json(&json!({
+            "schema_version": "1.1",
+            "result": "Draft written",
+            "next_agent": "editor",
+            "confidence": 0.9,
+            "workflow_complete": false
+        }))
+        .unwrap();
+
+        assert_eq!(decision.schema_version, "1.1");
+        assert_eq!(decision.result, "Draft written");
+        assert_eq!(decision.next_agent.as_deref(), Some("editor"));
+    }
+
+    #[test]
+    fn test_from_json_reads_artifacts() {
+        let decision = RouteDecision::from_json(&json!({
+            "schema_version": "1.0",
+            "result": "Two found",
+            "workflow_complete": true,
+            "artifacts": [{"name": "table", "content_type": "text/csv", "data": "a,b"}]
+        }))
+        .unwrap();
+
+        assert_eq!(decision.artifacts.len(), 1);
+        assert_eq!(decision.artifacts[0].content_type, "text/csv");
+        assert_eq!(decision.artifacts[0].data, json!("a,b"));
+        assert!(RouteDecision::json_schema()["properties"]["artifacts"].is_object());
+    }
+
+    #[test]
+    fn test_from_json_reads_state_updates() {
+        let decision = RouteDecision::from_json(&json!({
+            "schema_version": "1.0",
+            "result": "Noted",
+            "state_updates": {"citations": ["RFC 2389"]}
+        }))
+        .unwrap();
+
+        assert_eq!(
+            decision.state_updates.unwrap()["citations"],
+            json!(["RFC 2389"])
+        );
+        assert!(RouteDecision::json_schema()["properties"]["state_updates"].is_object());
+    }
+
+    #[test]
+    fn test_from_json_rejects_other_major_versions() {
+        let error = RouteDecision::from_json(&json!({
+            "schema_version": "2.0",
+            "output": "Draft written",
+            "result": "ignored",
+            "workflow_complete": true
+        }))
+        .unwrap_err();
+
+        assert_eq!(
+            error,
+            DecisionParseError::UnsupportedSchemaVersion("2.0".to_string())
+        );
+        assert!(error.to_string().contains("supports 1.x"));
+    }
+
+    #[test]
+    fn test_from_json_requires_version_and_result() {
+        assert_eq!(
+            RouteDecision::from_json(&json!({"workflow_complete": true})).unwrap_err(),
+            DecisionParseError::MissingFields(vec!["schema_version", "result"])
+        );
+        assert!(matches!(
+            RouteDecision::from_json(&json!({"schema_version": 1.0, "result": "x"})),
+            Err(DecisionParseError::InvalidField(_))
+        ));
+        // Structured results are kept as JSON text
+        let decision =
+            RouteDecision::from_json(&json!({"schema_version": "1.0", "result": {"a": 1}}))
+                .unwrap();
+        assert_eq!(decision.result, r#"{"a":1}"#);
+        assert!(!decision.workflow_complete);
+    }
 }
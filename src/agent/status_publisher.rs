@@ -0,0 +1,296 @@
+//! Single owner of the agent's status publications
+//!
+//! The lifecycle, the heartbeat and the pipeline all report the agent's
+//! status. Sending every report through one `StatusPublisher` keeps the
+//! retained status consistent: each one carries the agent's capabilities and
+//! description, a status equal to the last one published is not sent again,
+//! and exactly one Unavailable goes out at shutdown, after which nothing is
+//! published. Each change of status is also reported on the agent's event
+//! stream when one is attached.
+
+use crate::agent::events::{AgentEvent, AgentEvents};
+use crate::config::AgentSection;
+use crate::protocol::messages::{AgentStatus, AgentStatusType};
+use crate::transport::Transport;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing::debug;
+
+/// Publishes the agent's status on its transport
+pub struct StatusPublisher<T: Transport> {
+    transport: Arc<T>,
+    agent_id: String,
+    capabilities: Option<Vec<String>>,
+    description: Option<String>,
+    /// Where status changes are reported, if anywhere
+    events: Option<AgentEvents>,
+    /// Held across each publish so reports from different tasks don't interleave
+    state: Mutex<PublisherState>,
+}
+
+#[derive(Debug, Default)]
+struct PublisherState {
+    /// Last status the transport accepted
+    last: Option<AgentStatusType>,
+    /// Set once the final Unavailable went out
+    finished: bool,
+}
+
+impl<T: Transport> StatusPublisher<T> {
+    /// Publisher for the agent described by `agent`
+    pub fn new(transport: Arc<T>, agent: &AgentSection) -> Self {
+        Self {
+            transport,
+            agent_id: agent.id.clone(),
+            capabilities: (!agent.capabilities.is_empty()).then(|| agent.capabilities.clone()),
+            description: (!agent.description.is_empty()).then(|| agent.description.clone()),
+            events: None,
+            state: Mutex::new(PublisherState::default()),
+        }
+    }
+
+    /// Report every change of status on `events`
+    pub fn with_events(mut self, events: AgentEvents) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Status message for `status`, with the agent's capabilities and description
+    pub fn build_status(&self, status: AgentStatusType) -> AgentStatus {
+        AgentStatus {
+            agent_id: self.agent_id.clone(),
+            status,
+            timestamp: chrono::Utc::now(),
+            capabilities: self.capabilities.clone(),
+            description: self.description.clone(),
+        }
+    }
+
+    /// Publish `status` unless it is the last one published
+    ///
+    /// Returns whether anything was sent. Nothing is sent after `publish_final`.
+    pub async fn publish(&self, status: AgentStatusType) -> Result<bool, T::Error> {
+        let mut state = self.state.lock().await;
+        if state.finished || state.last.as_ref() == Some(&status) {
+            debug!(status = ?status, "Status unchanged, not republishing");
+            return Ok(false);
+        }
+        self.send(&mut state, status).await?;
+        Ok(true)
+    }
+
+    /// Publish the current status again to keep the retained one fresh
+    ///
+    /// The status is Available until another one is published.
+    pub async fn republish(&self) -> Result<bool, T::Error> {
+        let mut state = self.state.lock().await;
+        if state.finished {
+            return Ok(false);
+        }
+        let status = state.last.clone().unwrap_or(AgentStatusType::Available);
+        self.send(&mut state, status).await?;
+        Ok(true)
+    }
+
+    /// Publish the final Unavailable; later calls and publications send nothing
+    ///
+    /// Skipped if Unavailable was already the last status published.
+    pub async fn publish_final(&self) -> Result<bool, T::Error> {
+        let mut state = self.state.lock().await;
+        if state.finished {
+            return Ok(false);
+        }
+        state.finished = true;
+        if state.last == Some(AgentStatusType::Unavailable) {
+            return Ok(false);
+        }
+        self.send(&mut state, AgentStatusType::Unavailable).await?;
+        Ok(true)
+    }
+
+    /// Last status published, if any
+    pub async fn current(&self) -> Option<AgentStatusType> {
+        self.state.lock().await.last.clone()
+    }
+
+    async fn send(
+        &self,
+        state: &mut PublisherState,
+        status: AgentStatusType,
+    ) -> Result<(), T::Error> {
+        self.transport
+            .publish_status(&self.build_status(status.clone()))
+            .await?;
+        if state.last.as_ref() != Some(&status) {
+            if let Some(events) = &self.events {
+                events.emit(AgentEvent::StatusChanged(status.clone()));
+            }
+        }
+        state.last = Some(status);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AgentConfig;
+    use crate::testing::mocks::MockTransport;
+    use crate::transport::mqtt::MqttError;
+
+    fn publisher() -> (StatusPublisher<MockTransport>, MockTransport) {
+        let transport = MockTransport::new();
+        let agent = AgentConfig::test_config().agent;
+        (
+            StatusPublisher::new(Arc::new(transport.clone()), &agent),
+            transport,
+        )
+    }
+
+    async fn published(transport: &MockTransport) -> Vec<AgentStatusType> {
+        transport
+            .get_published_statuses()
+            .await
+            .into_iter()
+            .map(|status| status.status)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_identical_consecutive_statuses_are_debounced() {
+        let (publisher, transport) = publisher();
+
+        assert!(publisher.publish(AgentStatusType::Available).await.unwrap());
+        assert!(!publisher.publish(AgentStatusType::Available).await.unwrap());
+        assert!(publisher.publish(AgentStatusType::Paused).await.unwrap());
+        assert!(publisher.publish(AgentStatusType::Available).await.unwrap());
+
+        assert_eq!(
+            published(&transport).await,
+            vec![
+                AgentStatusType::Available,
+                AgentStatusType::Paused,
+                AgentStatusType::Available
+            ]
+        );
+    }
+
+    #[test]
+    fn test_build_status_omits_empty_details() {
+        let mut agent = AgentConfig::test_config().agent;
+        agent.capabilities.clear();
+        agent.description.clear();
+        let publisher = StatusPublisher::new(Arc::new(MockTransport::new()), &agent);
+
+        let before = chrono::Utc::now();
+        let status = publisher.build_status(AgentStatusType::Paused);
+
+        assert_eq!(status.agent_id, agent.id);
+        assert_eq!(status.status, AgentStatusType::Paused);
+        assert_eq!(status.capabilities, None);
+        assert_eq!(status.description, None);
+        assert!(status.timestamp >= before && status.timestamp <= chrono::Utc::now());
+    }
+
+    #[tokio::test]
+    async fn test_every_status_carries_agent_details() {
+        let (publisher, transport) = publisher();
+        let agent = AgentConfig::test_config().agent;
+
+        publisher.publish(AgentStatusType::Available).await.unwrap();
+        publisher.publish_final().await.unwrap();
+
+        let statuses = transport.get_published_statuses().await;
+        assert_eq!(statuses.len(), 2);
+        for status in statuses {
+            assert_eq!(status.agent_id, agent.id);
+            assert_eq!(status.capabilities.as_ref(), Some(&agent.capabilities));
+            assert_eq!(status.description.as_ref(), Some(&agent.description));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_republish_repeats_current_status() {
+        let (publisher, transport) = publisher();
+
+        publisher.republish().await.unwrap();
+        publisher.publish(AgentStatusType::Paused).await.unwrap();
+        publisher.republish().await.unwrap();
+
+        assert_eq!(
+            published(&transport).await,
+            vec![
+                AgentStatusType::Available,
+                AgentStatusType::Paused,
+                AgentStatusType::Paused
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_final_unavailable_is_published_once_and_last() {
+        let (publisher, transport) = publisher();
+
+        publisher.publish(AgentStatusType::Available).await.unwrap();
+        assert!(publisher.publish_final().await.unwrap());
+        assert!(!publisher.publish_final().await.unwrap());
+        assert!(!publisher.publish(AgentStatusType::Available).await.unwrap());
+        assert!(!publisher.republish().await.unwrap());
+
+        assert_eq!(
+            published(&transport).await,
+            vec![AgentStatusType::Available, AgentStatusType::Unavailable]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_final_after_unavailable_sends_nothing() {
+        let (publisher, transport) = publisher();
+
+        publisher
+            .publish(AgentStatusType::Unavailable)
+            .await
+            .unwrap();
+        assert!(!publisher.publish_final().await.unwrap());
+
+        assert_eq!(
+            published(&transport).await,
+            vec![AgentStatusType::Unavailable]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_status_changes_are_reported_as_events() {
+        let transport = MockTransport::new();
+        let events = AgentEvents::default();
+        let mut receiver = events.subscribe();
+        let publisher =
+            StatusPublisher::new(Arc::new(transport), &AgentConfig::test_config().agent)
+                .with_events(events);
+
+        publisher.publish(AgentStatusType::Available).await.unwrap();
+        publisher.republish().await.unwrap();
+        publisher.publish_final().await.unwrap();
+
+        let mut reported = Vec::new();
+        while let Ok(AgentEvent::StatusChanged(status)) = receiver.try_recv() {
+            reported.push(status);
+        }
+        assert_eq!(
+            reported,
+            vec![AgentStatusType::Available, AgentStatusType::Unavailable]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_publish_is_retried() {
+        let (publisher, transport) = publisher();
+        transport.fail_next_publish(MqttError::ConnectionFailedStr("broker gone".to_string()));
+
+        assert!(publisher.publish(AgentStatusType::Available).await.is_err());
+        assert_eq!(publisher.current().await, None);
+
+        assert!(publisher.publish(AgentStatusType::Available).await.unwrap());
+        assert_eq!(publisher.current().await, Some(AgentStatusType::Available));
+    }
+}
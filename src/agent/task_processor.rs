@@ -0,0 +1,186 @@
+//! Task processor abstraction used by the agent pipeline
+//!
+//! `AgentPipeline` drives tasks through any [`TaskProcessor`], so an embedding
+//! application can replace the RFC 9-step algorithm with its own processing
+//! while keeping the pipeline's concurrency, retries, routing and lifecycle
+//! handling. [`AgentProcessor`] is the implementation used by the agent binary.
+//!
+//! Beyond processing, the pipeline only needs the agent configuration and the
+//! transport, exposed through the narrower [`ConfigAccess`] and
+//! [`TransportAccess`] traits. Services specific to the 9-step processor, such
+//! as the idempotency cache and the cancellation registry, have defaults that
+//! turn the related pipeline features off.
+
+use crate::agent::processor::AgentProcessor;
+use crate::clock::Clock;
+use crate::config::AgentConfig;
+use crate::error::AgentResult;
+use crate::processing::cancellation::CancellationRegistry;
+use crate::processing::moderation::ModerationPolicy;
+use crate::processing::nine_step::ProcessingResult;
+use crate::progress::Progress;
+use crate::protocol::messages::TaskEnvelopeWrapper;
+use crate::routing::agent_selector::RoutingHelper;
+use crate::routing::RoutingAuditLog;
+use crate::transport::Transport;
+use async_trait::async_trait;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Access to the configuration of the agent a processor runs for
+pub trait ConfigAccess {
+    /// Get the agent configuration
+    fn config(&self) -> &AgentConfig;
+}
+
+/// Access to the transport a processor publishes through
+pub trait TransportAccess<T: Transport> {
+    /// Get the transport instance
+    fn transport(&self) -> &Arc<T>;
+}
+
+/// Processes tasks handed over by the agent pipeline
+#[async_trait]
+pub trait TaskProcessor<T: Transport>: ConfigAccess + TransportAccess<T> + Send + Sync {
+    /// Process a task, publishing its response or error
+    async fn process_task(
+        &self,
+        wrapper: TaskEnvelopeWrapper,
+        received_topic: &str,
+        is_retained: bool,
+    ) -> AgentResult<ProcessingResult>;
+
+    /// Process one attempt of a task that may be retried
+    ///
+    /// With `will_retry` set, a retryable failure should be returned without
+    /// publishing its error, since a later attempt may still succeed. The
+    /// default processes the attempt like any other task.
+    async fn process_task_attempt(
+        &self,
+        wrapper: TaskEnvelopeWrapper,
+        received_topic: &str,
+        is_retained: bool,
+        _will_retry: bool,
+    ) -> AgentResult<ProcessingResult> {
+        self.process_task(wrapper, received_topic, is_retained)
+            .await
+    }
+
+    /// Whether the task id was already processed; defaults to never
+    ///
+    /// The pipeline skips such tasks when replaying its journal or a batch.
+    async fn has_processed(&self, _task_id: &Uuid) -> bool {
+        false
+    }
+
+    /// Let a task that was already processed be processed again, for retries
+    async fn allow_retry(&self, _task_id: &Uuid) {}
+
+    /// Registry that cancel requests are applied to, if the processor
+    /// supports cancelling in-flight tasks
+    fn cancellation(&self) -> Option<&CancellationRegistry> {
+        None
+    }
+
+    /// Routing helper whose state `capability:` targets share with the
+    /// processor; the pipeline uses its own when none is provided
+    fn routing_helper(&self) -> Option<&RoutingHelper> {
+        None
+    }
+
+    /// Progress reporter for pipeline-level events, if any
+    fn progress(&self) -> Option<&Arc<dyn Progress>> {
+        None
+    }
+
+    /// Audit log the pipeline shares for its own routing decisions, if any
+    fn routing_audit_log(&self) -> Option<&Arc<RoutingAuditLog>> {
+        None
+    }
+
+    /// Clock the pipeline reads timestamps from; it uses the system clock
+    /// when none is provided
+    fn clock(&self) -> Option<&Arc<dyn Clock>> {
+        None
+    }
+
+    /// Moderation policy the pipeline checks final workflow results with, if any
+    fn moderation(&self) -> Option<&Arc<dyn ModerationPolicy>> {
+        None
+    }
+
+    /// Release resources held between tasks once the agent has gone idle;
+    /// defaults to holding nothing
+    async fn on_idle(&self) {}
+}
+
+impl<T: Transport + 'static> ConfigAccess for AgentProcessor<T> {
+    fn config(&self) -> &AgentConfig {
+        AgentProcessor::config(self)
+    }
+}
+
+impl<T: Transport + 'static> TransportAccess<T> for AgentProcessor<T> {
+    fn transport(&self) -> &Arc<T> {
+        AgentProcessor::transport(self)
+    }
+}
+
+#[async_trait]
+impl<T: Transport + 'static> TaskProcessor<T> for AgentProcessor<T> {
+    async fn process_task(
+        &self,
+        wrapper: TaskEnvelopeWrapper,
+        received_topic: &str,
+        is_retained: bool,
+    ) -> AgentResult<ProcessingResult> {
+        AgentProcessor::process_task(self, wrapper, received_topic, is_retained).await
+    }
+
+    async fn process_task_attempt(
+        &self,
+        wrapper: TaskEnvelopeWrapper,
+        received_topic: &str,
+        is_retained: bool,
+        will_retry: bool,
+    ) -> AgentResult<ProcessingResult> {
+        AgentProcessor::process_task_attempt(self, wrapper, received_topic, is_retained, will_retry)
+            .await
+    }
+
+    async fn has_processed(&self, task_id: &Uuid) -> bool {
+        self.nine_step_processor().has_processed(task_id).await
+    }
+
+    async fn allow_retry(&self, task_id: &Uuid) {
+        self.nine_step_processor().allow_retry(task_id).await
+    }
+
+    fn cancellation(&self) -> Option<&CancellationRegistry> {
+        Some(self.nine_step_processor().cancellation())
+    }
+
+    fn routing_helper(&self) -> Option<&RoutingHelper> {
+        Some(self.nine_step_processor().routing_helper())
+    }
+
+    fn progress(&self) -> Option<&Arc<dyn Progress>> {
+        Some(self.nine_step_processor().progress())
+    }
+
+    fn routing_audit_log(&self) -> Option<&Arc<RoutingAuditLog>> {
+        self.nine_step_processor().routing_audit_log()
+    }
+
+    fn clock(&self) -> Option<&Arc<dyn Clock>> {
+        Some(self.nine_step_processor().clock())
+    }
+
+    fn moderation(&self) -> Option<&Arc<dyn ModerationPolicy>> {
+        self.nine_step_processor().moderation()
+    }
+
+    async fn on_idle(&self) {
+        self.nine_step_processor().release_idle_resources().await
+    }
+}
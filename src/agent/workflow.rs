@@ -0,0 +1,325 @@
+//! Starting v2.0 workflows from library code
+//!
+//! `WorkflowBuilder` produces the first `TaskEnvelopeV2` of a workflow, with
+//! the version, input topic and workflow context an agent expects, and can
+//! publish it to the target agent:
+//!
+//! ```
+//! use agent2389::agent::workflow::WorkflowBuilder;
+//! use serde_json::json;
+//! use std::time::Duration;
+//!
+//! let task = WorkflowBuilder::new("Summarize this week's incidents")
+//!     .for_agent("researcher")
+//!     .with_input(json!({"week": 42}))
+//!     .with_budget(Duration::from_secs(600))
+//!     .build()
+//!     .unwrap();
+//!
+//! assert_eq!(task.topic, "/control/agents/researcher/input");
+//! assert_eq!(task.version, "2.0");
+//! ```
+
+use crate::protocol::messages::{TaskEnvelopeV2, TaskEnvelopeWrapper, WorkflowContext};
+use crate::protocol::topics::{canonicalize_topic, validate_agent_id, ValidationError};
+use crate::protocol::validation::validate_envelope;
+use crate::transport::Transport;
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::time::Duration;
+use thiserror::Error;
+use uuid::Uuid;
+
+/// Errors building or submitting a workflow's first task
+#[derive(Debug, Error)]
+pub enum WorkflowError {
+    #[error("Workflow query cannot be empty")]
+    EmptyQuery,
+    #[error("Workflow has no target agent; call for_agent() or to_topic()")]
+    MissingTarget,
+    #[error("Invalid target agent id: {0}")]
+    InvalidAgentId(#[from] ValidationError),
+    #[error("Malformed target topic '{0}'; expected /control/agents/<agent_id>/input")]
+    MalformedTopic(String),
+    #[error("Workflow budget must be at least one second")]
+    ZeroBudget,
+    #[error("Built envelope failed validation: {0}")]
+    InvalidEnvelope(String),
+    #[error("Failed to publish workflow task: {0}")]
+    PublishFailed(String),
+}
+
+/// Where the first task is sent
+#[derive(Debug, Clone)]
+enum Target {
+    Agent(String),
+    Topic(String),
+}
+
+/// Fluent builder for the first task of a v2.0 workflow
+#[derive(Debug, Clone)]
+pub struct WorkflowBuilder {
+    original_query: String,
+    target: Option<Target>,
+    instruction: Option<String>,
+    input: Value,
+    budget: Option<Duration>,
+    conversation_id: Option<String>,
+    correlation_id: Option<String>,
+    deadline: Option<DateTime<Utc>>,
+}
+
+impl WorkflowBuilder {
+    /// Start a workflow answering `original_query`
+    ///
+    /// The query is also the first agent's instruction unless
+    /// [`Self::with_instruction`] sets another.
+    pub fn new(original_query: impl Into<String>) -> Self {
+        Self {
+            original_query: original_query.into(),
+            target: None,
+            instruction: None,
+            input: Value::Null,
+            budget: None,
+            conversation_id: None,
+            correlation_id: None,
+            deadline: None,
+        }
+    }
+
+    /// Send the first task to the agent `agent_id`
+    pub fn for_agent(mut self, agent_id: impl Into<String>) -> Self {
+        self.target = Some(Target::Agent(agent_id.into()));
+        self
+    }
+
+    /// Send the first task to an agent input topic, `/control/agents/<agent_id>/input`
+    pub fn to_topic(mut self, topic: impl Into<String>) -> Self {
+        self.target = Some(Target::Topic(topic.into()));
+        self
+    }
+
+    /// Instruction for the first agent, instead of the original query
+    pub fn with_instruction(mut self, instruction: impl Into<String>) -> Self {
+        self.instruction = Some(instruction.into());
+        self
+    }
+
+    /// Input data for the first agent
+    pub fn with_input(mut self, input: Value) -> Self {
+        self.input = input;
+        self
+    }
+
+    /// Wall-clock budget for the whole workflow, counted from `build()`
+    pub fn with_budget(mut self, budget: Duration) -> Self {
+        self.budget = Some(budget);
+        self
+    }
+
+    /// Conversation the workflow's responses and errors go to (default: a new UUID)
+    pub fn with_conversation_id(mut self, conversation_id: impl Into<String>) -> Self {
+        self.conversation_id = Some(conversation_id.into());
+        self
+    }
+
+    /// Correlation id shared by every task of the workflow (default: a new UUID)
+    pub fn with_correlation_id(mut self, correlation_id: impl Into<String>) -> Self {
+        self.correlation_id = Some(correlation_id.into());
+        self
+    }
+
+    /// Deadline after which the first task should not be processed
+    pub fn with_deadline(mut self, deadline: DateTime<Utc>) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Build and validate the workflow's first task
+    pub fn build(self) -> Result<TaskEnvelopeV2, WorkflowError> {
+        if self.original_query.trim().is_empty() {
+            return Err(WorkflowError::EmptyQuery);
+        }
+        let agent_id = match self.target {
+            Some(Target::Agent(agent_id)) => {
+                validate_agent_id(&agent_id)?;
+                agent_id
+            }
+            Some(Target::Topic(topic)) => input_topic_agent_id(&topic)?,
+            None => return Err(WorkflowError::MissingTarget),
+        };
+        let budget_secs = match self.budget {
+            Some(budget) if budget.as_secs() == 0 => return Err(WorkflowError::ZeroBudget),
+            budget => budget.map(|budget| budget.as_secs()),
+        };
+
+        let task = TaskEnvelopeV2 {
+            task_id: Uuid::new_v4(),
+            conversation_id: self
+                .conversation_id
+                .unwrap_or_else(|| Uuid::new_v4().to_string()),
+            topic: canonicalize_topic(&format!("/control/agents/{agent_id}/input")),
+            instruction: Some(
+                self.instruction
+                    .unwrap_or_else(|| self.original_query.clone()),
+            ),
+            input: self.input,
+            next: None,
+            version: "2.0".to_string(),
+            context: Some(WorkflowContext {
+                original_query: self.original_query,
+                steps_completed: Vec::new(),
+                iteration_count: 0,
+                started_at: Some(Utc::now()),
+                budget_secs,
+                state: None,
+                state_order: Vec::new(),
+            }),
+            routing_trace: None,
+            deadline: self.deadline,
+            correlation_id: Some(
+                self.correlation_id
+                    .unwrap_or_else(|| Uuid::new_v4().to_string()),
+            ),
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
+        };
+
+        // Hold the envelope to the same rules agents apply on receipt
+        let value = serde_json::to_value(&task)
+            .map_err(|e| WorkflowError::InvalidEnvelope(e.to_string()))?;
+        validate_envelope(&value).map_err(|e| WorkflowError::InvalidEnvelope(e.to_string()))?;
+        Ok(task)
+    }
+
+    /// Build the first task and publish it to the target agent's input topic
+    ///
+    /// Returns the published task, whose ids identify the workflow.
+    pub async fn submit<T: Transport>(
+        self,
+        transport: &T,
+    ) -> Result<TaskEnvelopeV2, WorkflowError> {
+        let task = self.build()?;
+        let agent_id = input_topic_agent_id(&task.topic)?;
+        transport
+            .publish_task(&agent_id, &TaskEnvelopeWrapper::V2(task.clone()))
+            .await
+            .map_err(|e| WorkflowError::PublishFailed(e.to_string()))?;
+        Ok(task)
+    }
+}
+
+/// Agent id of an agent input topic (pure function)
+pub(crate) fn input_topic_agent_id(topic: &str) -> Result<String, WorkflowError> {
+    let malformed = || WorkflowError::MalformedTopic(topic.to_string());
+    let canonical = canonicalize_topic(topic);
+    let parts: Vec<&str> = canonical.trim_start_matches('/').split('/').collect();
+    match parts.as_slice() {
+        ["control", "agents", agent_id, "input"] => {
+            validate_agent_id(agent_id).map_err(|_| malformed())?;
+            Ok(agent_id.to_string())
+        }
+        _ => Err(malformed()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_build_fills_in_workflow_defaults() {
+        let task = WorkflowBuilder::new("Find recent papers")
+            .for_agent("researcher")
+            .with_input(json!({"topic": "rust"}))
+            .build()
+            .unwrap();
+
+        assert_eq!(task.topic, "/control/agents/researcher/input");
+        assert_eq!(task.version, "2.0");
+        assert_eq!(task.instruction.as_deref(), Some("Find recent papers"));
+        assert_eq!(task.input, json!({"topic": "rust"}));
+        assert!(Uuid::parse_str(&task.conversation_id).is_ok());
+        assert!(task.correlation_id.is_some());
+        let context = task.context.unwrap();
+        assert_eq!(context.original_query, "Find recent papers");
+        assert_eq!(context.iteration_count, 0);
+        assert!(context.steps_completed.is_empty());
+        assert!(context.started_at.is_some());
+        assert_eq!(context.budget_secs, None);
+    }
+
+    #[test]
+    fn test_build_applies_overrides() {
+        let deadline = Utc::now() + chrono::Duration::minutes(5);
+        let task = WorkflowBuilder::new("Write a report")
+            .to_topic("control/agents/writer/input/")
+            .with_instruction("Draft the introduction")
+            .with_budget(Duration::from_secs(600))
+            .with_conversation_id("conv-1")
+            .with_correlation_id("workflow-1")
+            .with_deadline(deadline)
+            .build()
+            .unwrap();
+
+        assert_eq!(task.topic, "/control/agents/writer/input");
+        assert_eq!(task.instruction.as_deref(), Some("Draft the introduction"));
+        assert_eq!(task.conversation_id, "conv-1");
+        assert_eq!(task.correlation_id.as_deref(), Some("workflow-1"));
+        assert_eq!(task.deadline, Some(deadline));
+        let context = task.context.unwrap();
+        assert_eq!(context.original_query, "Write a report");
+        assert_eq!(context.budget_secs, Some(600));
+    }
+
+    #[test]
+    fn test_build_rejects_invalid_targets() {
+        let build = |builder: WorkflowBuilder| builder.build().unwrap_err();
+
+        assert!(matches!(
+            build(WorkflowBuilder::new("q")),
+            WorkflowError::MissingTarget
+        ));
+        assert!(matches!(
+            build(WorkflowBuilder::new("q").for_agent("")),
+            WorkflowError::InvalidAgentId(ValidationError::EmptyAgentId)
+        ));
+        assert!(matches!(
+            build(WorkflowBuilder::new("q").for_agent("bad agent")),
+            WorkflowError::InvalidAgentId(ValidationError::InvalidAgentIdChar(' '))
+        ));
+        for topic in [
+            "/control/agents/writer/status",
+            "/control/agents//input",
+            "/control/agents/+/input",
+            "/conversations/writer/input",
+            "",
+        ] {
+            assert!(
+                matches!(
+                    build(WorkflowBuilder::new("q").to_topic(topic)),
+                    WorkflowError::MalformedTopic(_)
+                ),
+                "{topic:?} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn test_build_rejects_empty_query_and_zero_budget() {
+        assert!(matches!(
+            WorkflowBuilder::new("  ").for_agent("a").build(),
+            Err(WorkflowError::EmptyQuery)
+        ));
+        assert!(matches!(
+            WorkflowBuilder::new("q")
+                .for_agent("a")
+                .with_budget(Duration::from_millis(500))
+                .build(),
+            Err(WorkflowError::ZeroBudget)
+        ));
+    }
+}
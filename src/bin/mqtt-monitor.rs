@@ -235,14 +235,10 @@ fn highlight_json(json_str: &str) -> String {
                 }
                 result.push(ch);
             }
-            '{' | '[' | '}' | ']' | ':' | ',' => {
-                if !in_string {
-                    result.push_str(JSON_PUNCT_COLOR);
-                    result.push(ch);
-                    result.push_str(RESET);
-                } else {
-                    result.push(ch);
-                }
+            '{' | '[' | '}' | ']' | ':' | ',' if !in_string => {
+                result.push_str(JSON_PUNCT_COLOR);
+                result.push(ch);
+                result.push_str(RESET);
             }
             _ if !in_string => {
                 // Handle numbers, booleans, null
@@ -0,0 +1,483 @@
+//! `agent2389 doctor`: diagnose why an agent can't reach its broker, LLM or tools
+//!
+//! Each check returns a [`CheckResult`] instead of failing, so every problem
+//! is reported in one run. Checks that do I/O take a timeout and report a
+//! failure when it expires, so doctor never hangs on an unresponsive service.
+
+use crate::config::{AgentConfig, ConfigWarning, ToolConfig};
+use crate::llm::provider::{LlmError, LlmProvider};
+use crate::secrets::SecretRef;
+use crate::tools::ToolSystem;
+use crate::transport::Transport;
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use tokio::net::{lookup_host, TcpStream};
+use url::Url;
+
+/// How long each check may take by default
+pub const DEFAULT_CHECK_TIMEOUT_SECS: u64 = 10;
+
+/// Outcome of a single check
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+impl fmt::Display for CheckStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CheckStatus::Pass => write!(f, "PASS"),
+            CheckStatus::Warn => write!(f, "WARN"),
+            CheckStatus::Fail => write!(f, "FAIL"),
+        }
+    }
+}
+
+/// One line of the doctor report
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckResult {
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: String,
+    /// What to try next, for warnings and failures
+    pub hint: Option<String>,
+}
+
+impl CheckResult {
+    pub fn pass(name: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            status: CheckStatus::Pass,
+            detail: detail.into(),
+            hint: None,
+        }
+    }
+
+    pub fn warn(
+        name: impl Into<String>,
+        detail: impl Into<String>,
+        hint: impl Into<String>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            status: CheckStatus::Warn,
+            detail: detail.into(),
+            hint: Some(hint.into()),
+        }
+    }
+
+    pub fn fail(
+        name: impl Into<String>,
+        detail: impl Into<String>,
+        hint: impl Into<String>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            status: CheckStatus::Fail,
+            detail: detail.into(),
+            hint: Some(hint.into()),
+        }
+    }
+}
+
+impl fmt::Display for CheckResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {}: {}", self.status, self.name, self.detail)?;
+        if let Some(hint) = &self.hint {
+            write!(f, "\n       hint: {hint}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Exit status for a finished report: non-zero when any check failed (pure function)
+pub fn exit_code(results: &[CheckResult]) -> i32 {
+    i32::from(
+        results
+            .iter()
+            .any(|result| result.status == CheckStatus::Fail),
+    )
+}
+
+/// Run `check`, failing as `name` if it takes longer than `timeout`
+pub async fn with_timeout(
+    name: &str,
+    timeout: Duration,
+    check: impl Future<Output = CheckResult>,
+) -> CheckResult {
+    tokio::time::timeout(timeout, check)
+        .await
+        .unwrap_or_else(|_| {
+            CheckResult::fail(
+                name,
+                format!("No answer within {}s", timeout.as_secs_f64()),
+                "The service is unreachable or overloaded; raise --timeout if it is just slow",
+            )
+        })
+}
+
+/// Load and validate the configuration
+///
+/// The configuration is returned whenever it could be read, even with
+/// validation errors, so the remaining checks still run.
+pub fn check_config(path: &Path, profile: Option<&str>) -> (CheckResult, Option<AgentConfig>) {
+    const NAME: &str = "configuration";
+    let (config, mut warnings) = match AgentConfig::read_with_warnings(path, profile) {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            let result = CheckResult::fail(
+                NAME,
+                format!("Cannot load {}: {e}", path.display()),
+                "Fix the file or pass another one with -c/--config",
+            );
+            return (result, None);
+        }
+    };
+    warnings.extend(config.warnings());
+
+    let result = match config.validate() {
+        Err(errors) => CheckResult::fail(
+            NAME,
+            errors
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join("; "),
+            format!(
+                "Run `agent2389 -c {} config --check` for every problem",
+                path.display()
+            ),
+        ),
+        Ok(()) if !warnings.is_empty() => CheckResult::warn(
+            NAME,
+            warnings
+                .iter()
+                .map(ConfigWarning::to_string)
+                .collect::<Vec<_>>()
+                .join("; "),
+            "The agent starts, but these settings may not do what you expect",
+        ),
+        Ok(()) => CheckResult::pass(NAME, format!("{} is valid", path.display())),
+    };
+    (result, Some(config))
+}
+
+/// Resolve the broker's host name and open a TCP connection to it
+pub async fn check_broker_reachable(broker_url: &str, timeout: Duration) -> CheckResult {
+    const NAME: &str = "broker reachable";
+    let Some((host, port)) = broker_address(broker_url) else {
+        return CheckResult::fail(
+            NAME,
+            format!("Invalid broker URL '{broker_url}'"),
+            "Set mqtt.broker_url to mqtt://host:port or mqtts://host:port",
+        );
+    };
+
+    with_timeout(NAME, timeout, async {
+        let started = Instant::now();
+        let addresses: Vec<_> = match lookup_host((host.as_str(), port)).await {
+            Ok(addresses) => addresses.collect(),
+            Err(e) => {
+                return CheckResult::fail(
+                    NAME,
+                    format!("Cannot resolve {host}: {e}"),
+                    "Check the host name in mqtt.broker_url and your DNS settings",
+                )
+            }
+        };
+        match TcpStream::connect(addresses.as_slice()).await {
+            Ok(_) => CheckResult::pass(
+                NAME,
+                format!(
+                    "Connected to {host}:{port} in {} ms",
+                    started.elapsed().as_millis()
+                ),
+            ),
+            Err(e) => CheckResult::fail(
+                NAME,
+                format!("Cannot connect to {host}:{port}: {e}"),
+                "Is the broker running and listening on that port? Check firewalls in between",
+            ),
+        }
+    })
+    .await
+}
+
+/// Host and port of an `mqtt://` or `mqtts://` URL (pure function)
+fn broker_address(broker_url: &str) -> Option<(String, u16)> {
+    let url = Url::parse(broker_url).ok()?;
+    let default_port = match url.scheme() {
+        "mqtt" => 1883,
+        "mqtts" => 8883,
+        _ => return None,
+    };
+    Some((
+        url.host_str()?.to_string(),
+        url.port().unwrap_or(default_port),
+    ))
+}
+
+/// Scratch topic for the round-trip check, unique per run
+pub fn scratch_topic(agent_id: &str) -> String {
+    format!("/doctor/{agent_id}/{}", uuid::Uuid::new_v4())
+}
+
+/// Connect, subscribe to `topic`, publish to it and wait for the message to come back
+pub async fn check_mqtt_round_trip<T: Transport>(
+    transport: &mut T,
+    topic: &str,
+    timeout: Duration,
+) -> CheckResult {
+    const NAME: &str = "mqtt round trip";
+    let result = with_timeout(NAME, timeout, async {
+        let started = Instant::now();
+        if let Err(e) = transport.connect().await {
+            return CheckResult::fail(
+                NAME,
+                format!("MQTT connect failed: {e}"),
+                "Check the credentials in mqtt.username_env / mqtt.password_env",
+            );
+        }
+        let mut receiver = match transport.subscribe_topic(topic).await {
+            Ok(receiver) => receiver,
+            Err(e) => {
+                return CheckResult::fail(
+                    NAME,
+                    format!("Subscribe to {topic} failed: {e}"),
+                    "The broker's ACL may not allow this client to subscribe",
+                )
+            }
+        };
+        let nonce = uuid::Uuid::new_v4().to_string().into_bytes();
+        if let Err(e) = transport.publish(topic, nonce.clone(), false).await {
+            return CheckResult::fail(
+                NAME,
+                format!("Publish to {topic} failed: {e}"),
+                "The broker's ACL may not allow this client to publish",
+            );
+        }
+        while let Some((_, payload)) = receiver.recv().await {
+            if payload == nonce {
+                return CheckResult::pass(
+                    NAME,
+                    format!(
+                        "Published and received on {topic} in {} ms",
+                        started.elapsed().as_millis()
+                    ),
+                );
+            }
+        }
+        CheckResult::fail(
+            NAME,
+            format!("Subscription to {topic} ended before the message arrived"),
+            "The connection dropped; check the broker logs",
+        )
+    })
+    .await;
+    let _ = transport.disconnect().await;
+    result
+}
+
+/// Ask the LLM provider whether it is configured and answering
+///
+/// `provider` is the error message when the provider couldn't even be created.
+pub async fn check_llm(
+    provider: Result<&dyn LlmProvider, String>,
+    api_key_env: &SecretRef,
+    timeout: Duration,
+) -> CheckResult {
+    const NAME: &str = "llm provider";
+    let key_hint = match api_key_env {
+        SecretRef::Env(name) => format!("Export {name} with a valid API key"),
+        other => format!("Check that {other} holds a valid API key"),
+    };
+    let provider = match provider {
+        Ok(provider) => provider,
+        Err(e) => return CheckResult::fail(NAME, e, key_hint),
+    };
+
+    with_timeout(NAME, timeout, async {
+        match provider.health_check().await {
+            Ok(()) => CheckResult::pass(NAME, format!("{} is answering", provider.name())),
+            Err(e @ LlmError::AuthenticationFailed(_)) | Err(e @ LlmError::NotConfigured(_)) => {
+                CheckResult::fail(NAME, e.to_string(), key_hint)
+            }
+            Err(e @ LlmError::RateLimitExceeded(_)) => CheckResult::warn(
+                NAME,
+                e.to_string(),
+                "The key works but is rate limited; tasks may be slow",
+            ),
+            Err(e) => CheckResult::fail(
+                NAME,
+                e.to_string(),
+                "Check network access to the provider's API",
+            ),
+        }
+    })
+    .await
+}
+
+/// Initialize and shut down each configured tool on its own
+pub async fn check_tools(
+    tool_configs: &HashMap<String, ToolConfig>,
+    timeout: Duration,
+) -> Vec<CheckResult> {
+    let mut names: Vec<&String> = tool_configs.keys().collect();
+    names.sort();
+
+    let mut results = Vec::new();
+    for name in names {
+        let check_name = format!("tool {name}");
+        let tool_config = HashMap::from([(name.clone(), tool_configs[name].clone())]);
+        let result = with_timeout(&check_name, timeout, async {
+            let mut tool_system = ToolSystem::new();
+            let outcome = match tool_system.initialize(&tool_config).await {
+                Ok(()) => tool_system.shutdown().await,
+                Err(e) => Err(e),
+            };
+            if let Some(degraded) = tool_system.degraded_tools().first() {
+                return CheckResult::warn(
+                    &check_name,
+                    format!("Optional tool will be skipped: {}", degraded.error),
+                    format!("Check [tools.{name}] and any credentials it needs"),
+                );
+            }
+            match outcome {
+                Ok(()) => CheckResult::pass(&check_name, "Initialized"),
+                Err(e) => CheckResult::fail(
+                    &check_name,
+                    e.to_string(),
+                    format!("Check [tools.{name}] and any credentials it needs"),
+                ),
+            }
+        })
+        .await;
+        results.push(result);
+    }
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::mocks::{MockLlmProvider, MockTransport};
+    use tokio::net::TcpListener;
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    #[test]
+    fn test_exit_code_fails_only_on_failures() {
+        let pass = CheckResult::pass("a", "ok");
+        let warn = CheckResult::warn("b", "meh", "try this");
+        let fail = CheckResult::fail("c", "broken", "fix it");
+        assert_eq!(exit_code(&[pass.clone(), warn.clone()]), 0);
+        assert_eq!(exit_code(&[pass, warn, fail.clone()]), 1);
+        assert_eq!(fail.to_string(), "[FAIL] c: broken\n       hint: fix it");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_checks_time_out() {
+        let result = with_timeout("slow", Duration::from_secs(3), std::future::pending()).await;
+        assert_eq!(result.status, CheckStatus::Fail);
+        assert_eq!(result.detail, "No answer within 3s");
+    }
+
+    #[test]
+    fn test_check_config_reports_validation_errors_and_warnings() {
+        let dir = tempfile::tempdir().unwrap();
+        let fixture = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/config/agent.toml"
+        );
+        let (result, config) = check_config(Path::new(fixture), None);
+        assert_ne!(result.status, CheckStatus::Fail, "{result}");
+        assert!(config.is_some());
+
+        let invalid = dir.path().join("agent.toml");
+        let content = std::fs::read_to_string(fixture)
+            .unwrap()
+            .replace("provider = \"openai\"", "provider = \"nonexistent\"")
+            .replace("provider = \"anthropic\"", "provider = \"nonexistent\"");
+        std::fs::write(&invalid, content).unwrap();
+        let (result, config) = check_config(&invalid, None);
+        assert_eq!(result.status, CheckStatus::Fail);
+        assert!(result.detail.contains("llm.provider"), "{result}");
+        assert!(config.is_some());
+
+        let (result, config) = check_config(&dir.path().join("missing.toml"), None);
+        assert_eq!(result.status, CheckStatus::Fail);
+        assert!(config.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_broker_reachable() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let result = check_broker_reachable(&format!("mqtt://127.0.0.1:{port}"), TIMEOUT).await;
+        assert_eq!(result.status, CheckStatus::Pass, "{result}");
+
+        drop(listener);
+        let result = check_broker_reachable(&format!("mqtt://127.0.0.1:{port}"), TIMEOUT).await;
+        assert_eq!(result.status, CheckStatus::Fail);
+        assert!(result.detail.starts_with("Cannot connect"), "{result}");
+
+        let result = check_broker_reachable("http://localhost", TIMEOUT).await;
+        assert_eq!(result.status, CheckStatus::Fail);
+        assert!(result.hint.unwrap().contains("mqtt.broker_url"));
+    }
+
+    #[tokio::test]
+    async fn test_mqtt_round_trip() {
+        let mut transport = MockTransport::new();
+        let result =
+            check_mqtt_round_trip(&mut transport, &scratch_topic("doctor-test"), TIMEOUT).await;
+        assert_eq!(result.status, CheckStatus::Pass, "{result}");
+
+        let mut transport = MockTransport::with_failure();
+        let result =
+            check_mqtt_round_trip(&mut transport, &scratch_topic("doctor-test"), TIMEOUT).await;
+        assert_eq!(result.status, CheckStatus::Fail);
+        assert!(result.detail.starts_with("MQTT connect failed"));
+    }
+
+    #[tokio::test]
+    async fn test_llm_health() {
+        let key = SecretRef::Env("OPENAI_API_KEY".to_string());
+        let healthy = MockLlmProvider::single_response("hi");
+        let result = check_llm(Ok(&healthy), &key, TIMEOUT).await;
+        assert_eq!(result.status, CheckStatus::Pass, "{result}");
+
+        let failing = MockLlmProvider::with_failure();
+        let result = check_llm(Ok(&failing), &key, TIMEOUT).await;
+        assert_eq!(result.status, CheckStatus::Fail);
+
+        let result = check_llm(Err("API key missing".to_string()), &key, TIMEOUT).await;
+        assert_eq!(result.status, CheckStatus::Fail);
+        assert!(result.hint.unwrap().contains("OPENAI_API_KEY"));
+    }
+
+    #[tokio::test]
+    async fn test_tools_are_checked_one_by_one() {
+        let tool_configs = HashMap::from([
+            (
+                "file_read".to_string(),
+                ToolConfig::Simple("builtin".to_string()),
+            ),
+            (
+                "calculator".to_string(),
+                ToolConfig::Simple("builtin".to_string()),
+            ),
+        ]);
+        let results = check_tools(&tool_configs, TIMEOUT).await;
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].name, "tool calculator");
+        assert_eq!(results[0].status, CheckStatus::Fail);
+        assert_eq!(results[1].name, "tool file_read");
+        assert_eq!(results[1].status, CheckStatus::Pass, "{}", results[1]);
+    }
+}
@@ -0,0 +1,182 @@
+//! `agent2389 validate-envelope`: check a task envelope the way an agent would
+
+use crate::processing::{NineStepProcessor, ProcessorConfig};
+use crate::protocol::validation::SchemaViolation;
+use crate::protocol::{validate_envelope, TaskEnvelopeWrapper};
+use crate::transport::MqttTransport;
+use serde_json::Value;
+use std::path::Path;
+
+/// Exit status when the file can't be read or isn't JSON
+pub const EXIT_UNREADABLE: i32 = 1;
+/// Exit status when the envelope has violations
+pub const EXIT_INVALID: i32 = 2;
+
+/// Outcome of validating one envelope
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvelopeReport {
+    /// Envelope version the agent would detect: `1.0` when `version` is absent
+    pub version: String,
+    /// Every violation found, in check order
+    pub violations: Vec<SchemaViolation>,
+}
+
+impl EnvelopeReport {
+    pub fn is_valid(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+/// Read and validate the envelope stored in `path`
+///
+/// Fails only when the file can't be read or parsed as JSON.
+pub fn validate_file(path: &Path, topic: Option<&str>) -> Result<EnvelopeReport, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Cannot read {}: {e}", path.display()))?;
+    let value: Value = serde_json::from_str(&content)
+        .map_err(|e| format!("{} is not valid JSON: {e}", path.display()))?;
+    Ok(validate(&value, topic))
+}
+
+/// Validate an envelope without I/O (pure function)
+///
+/// Runs the protocol JSON Schema, then the nine-step checks that need no
+/// state: topic canonicalization against `topic` when given (step 3) and
+/// pipeline depth (step 5).
+pub fn validate(value: &Value, topic: Option<&str>) -> EnvelopeReport {
+    let version = detect_version(value);
+    let mut violations = validate_envelope(value)
+        .err()
+        .map(|errors| errors.violations)
+        .unwrap_or_default();
+
+    let task = match serde_json::from_value::<TaskEnvelopeWrapper>(value.clone()) {
+        Ok(wrapper) => wrapper.into_parts().0,
+        Err(e) => {
+            // The schema already explains why an envelope doesn't parse
+            if violations.is_empty() {
+                violations.push(violation("", e.to_string()));
+            }
+            return EnvelopeReport {
+                version,
+                violations,
+            };
+        }
+    };
+
+    if let Some(received_topic) = topic {
+        let step3 = NineStepProcessor::<MqttTransport>::step_3_validate_topic(
+            received_topic,
+            &task.topic,
+            false,
+        );
+        if let Some(message) = step3.error_message {
+            violations.push(violation("/topic", message));
+        }
+    }
+    let step5 = NineStepProcessor::<MqttTransport>::step_5_check_pipeline_depth(
+        &task,
+        ProcessorConfig::default().max_pipeline_depth,
+    );
+    if let Some(message) = step5.error_message {
+        violations.push(violation("/next", message));
+    }
+
+    EnvelopeReport {
+        version,
+        violations,
+    }
+}
+
+/// Version an agent reads the envelope as (pure function)
+///
+/// The v2.0 schema is selected by the presence of `version`, whatever its value.
+pub fn detect_version(value: &Value) -> String {
+    match value.get("version") {
+        None => "1.0".to_string(),
+        Some(Value::String(version)) => version.clone(),
+        Some(other) => other.to_string(),
+    }
+}
+
+fn violation(path: &str, message: String) -> SchemaViolation {
+    SchemaViolation {
+        path: path.to_string(),
+        message,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn fixture(name: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/envelopes")
+            .join(name)
+    }
+
+    fn paths(report: &EnvelopeReport) -> Vec<&str> {
+        report
+            .violations
+            .iter()
+            .map(|violation| violation.path.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_valid_envelopes_and_detected_versions() {
+        let v1 = validate_file(&fixture("valid-v1.json"), None).unwrap();
+        assert!(v1.is_valid(), "{:?}", v1.violations);
+        assert_eq!(v1.version, "1.0");
+
+        let v2 = validate_file(
+            &fixture("valid-v2.json"),
+            Some("control/agents/editor//input/"),
+        )
+        .unwrap();
+        assert!(v2.is_valid(), "{:?}", v2.violations);
+        assert_eq!(v2.version, "2.0");
+    }
+
+    #[test]
+    fn test_every_schema_violation_is_reported() {
+        let report = validate_file(&fixture("invalid-fields.json"), None).unwrap();
+        assert_eq!(report.version, "banana");
+        let mut found = paths(&report);
+        found.sort();
+        assert_eq!(
+            found,
+            vec!["/conversation_id", "/next/topic", "/task_id", "/version"]
+        );
+    }
+
+    #[test]
+    fn test_topic_mismatch_reported() {
+        let report = validate_file(
+            &fixture("valid-v1.json"),
+            Some("/control/agents/someone-else/input"),
+        )
+        .unwrap();
+        assert_eq!(paths(&report), vec!["/topic"]);
+        assert!(report.violations[0].message.contains("Topic mismatch"));
+    }
+
+    #[test]
+    fn test_pipeline_too_deep_reported() {
+        let report = validate_file(&fixture("too-deep.json"), None).unwrap();
+        assert_eq!(paths(&report), vec!["/next"]);
+        assert!(report.violations[0]
+            .message
+            .contains("Pipeline depth 17 exceeds maximum 16"));
+    }
+
+    #[test]
+    fn test_unreadable_files_are_errors() {
+        assert!(validate_file(&fixture("missing.json"), None).is_err());
+        assert!(validate_file(&fixture("not-json.txt"), None)
+            .unwrap_err()
+            .contains("is not valid JSON"));
+    }
+}
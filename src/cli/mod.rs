@@ -0,0 +1,10 @@
+//! Library side of the `agent2389` subcommands
+//!
+//! `main.rs` parses arguments and prints; the work each subcommand does lives
+//! here so it can be tested without spawning the binary.
+
+pub mod doctor;
+pub mod envelope;
+pub mod replay;
+pub mod status;
+pub mod tools;
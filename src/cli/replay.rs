@@ -0,0 +1,199 @@
+//! `agent2389 replay`: publish a directory of recorded envelopes again
+
+use crate::agent::workflow::input_topic_agent_id;
+use crate::protocol::TaskEnvelopeWrapper;
+use crate::transport::Transport;
+use chrono::{DateTime, Utc};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// One envelope read from a recording directory
+#[derive(Debug, Clone)]
+pub struct RecordedEnvelope {
+    pub path: PathBuf,
+    pub envelope: TaskEnvelopeWrapper,
+}
+
+impl RecordedEnvelope {
+    /// When the envelope was originally published, if it was recorded
+    pub fn timestamp(&self) -> Option<DateTime<Utc>> {
+        self.envelope.published_at()
+    }
+}
+
+/// Read every `*.json` envelope in `dir`, in original publish order
+///
+/// Envelopes are ordered by `published_at`; those without one come first, in
+/// file name order. Other files are skipped.
+pub fn load_recording(dir: &Path) -> Result<Vec<RecordedEnvelope>, String> {
+    let entries =
+        std::fs::read_dir(dir).map_err(|e| format!("Cannot read {}: {e}", dir.display()))?;
+    let mut recording = Vec::new();
+    for entry in entries {
+        let path = entry
+            .map_err(|e| format!("Cannot read {}: {e}", dir.display()))?
+            .path();
+        if path.extension().and_then(|extension| extension.to_str()) != Some("json") {
+            continue;
+        }
+        let content = std::fs::read_to_string(&path)
+            .map_err(|e| format!("Cannot read {}: {e}", path.display()))?;
+        let envelope = serde_json::from_str(&content)
+            .map_err(|e| format!("{} is not a task envelope: {e}", path.display()))?;
+        recording.push(RecordedEnvelope { path, envelope });
+    }
+    recording.sort_by(|a, b| (a.timestamp(), &a.path).cmp(&(b.timestamp(), &b.path)));
+    Ok(recording)
+}
+
+/// Wait before publishing an envelope recorded at `next` after one recorded at `previous` (pure function)
+///
+/// The recorded gap is divided by `speed`; without both timestamps there is no wait.
+pub fn replay_delay(
+    previous: Option<DateTime<Utc>>,
+    next: Option<DateTime<Utc>>,
+    speed: f64,
+) -> Duration {
+    match (previous, next) {
+        (Some(previous), Some(next)) => (next - previous)
+            .to_std()
+            .map(|gap| gap.div_f64(speed))
+            .unwrap_or_default(),
+        _ => Duration::ZERO,
+    }
+}
+
+/// Publish `recording` to each envelope's target agent, keeping the recorded
+/// gaps divided by `speed`
+///
+/// Envelopes go through [`Transport::publish_task`], so they are signed,
+/// encrypted and stamped with a new `published_at` like any other task.
+/// `on_published` is called after each one. Returns how many were published.
+pub async fn replay<T: Transport>(
+    transport: &T,
+    recording: &[RecordedEnvelope],
+    speed: f64,
+    mut on_published: impl FnMut(&RecordedEnvelope),
+) -> Result<usize, String> {
+    if !speed.is_finite() || speed <= 0.0 {
+        return Err(format!(
+            "Speed factor must be a positive number, got {speed}"
+        ));
+    }
+
+    let mut previous = None;
+    for recorded in recording {
+        tokio::time::sleep(replay_delay(previous, recorded.timestamp(), speed)).await;
+        let agent_id = input_topic_agent_id(recorded.envelope.topic())
+            .map_err(|e| format!("{}: {e}", recorded.path.display()))?;
+        transport
+            .publish_task(&agent_id, &recorded.envelope)
+            .await
+            .map_err(|e| format!("Failed to publish {}: {e}", recorded.path.display()))?;
+        on_published(recorded);
+        previous = recorded.timestamp().or(previous);
+    }
+    Ok(recording.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::mocks::MockTransport;
+
+    fn incident() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/recordings/incident")
+    }
+
+    fn at(seconds: u32) -> Option<DateTime<Utc>> {
+        Some(format!("2026-10-01T12:00:{seconds:02}Z").parse().unwrap())
+    }
+
+    #[test]
+    fn test_recording_is_ordered_by_timestamp() {
+        let recording = load_recording(&incident()).unwrap();
+        let topics: Vec<&str> = recording
+            .iter()
+            .map(|recorded| recorded.envelope.topic())
+            .collect();
+        assert_eq!(
+            topics,
+            vec![
+                "/control/agents/researcher/input",
+                "/control/agents/writer/input",
+                "/control/agents/editor/input",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_replay_delay_scales_recorded_gaps() {
+        assert_eq!(replay_delay(at(0), at(6), 1.0), Duration::from_secs(6));
+        assert_eq!(replay_delay(at(0), at(6), 2.0), Duration::from_secs(3));
+        assert_eq!(replay_delay(at(6), at(0), 1.0), Duration::ZERO);
+        assert_eq!(replay_delay(None, at(6), 1.0), Duration::ZERO);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_replay_publishes_in_order_with_scaled_gaps() {
+        let transport = MockTransport::new();
+        let recording = load_recording(&incident()).unwrap();
+        let started = tokio::time::Instant::now();
+        let mut published_at = Vec::new();
+
+        let published = replay(&transport, &recording, 2.0, |_| {
+            published_at.push(started.elapsed())
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(published, 3);
+        assert_eq!(
+            published_at,
+            vec![
+                Duration::ZERO,
+                Duration::from_secs(1),
+                Duration::from_secs(3)
+            ]
+        );
+        let targets: Vec<String> = transport
+            .published_task_envelopes
+            .lock()
+            .await
+            .iter()
+            .map(|(topic, _)| topic.clone())
+            .collect();
+        assert_eq!(
+            targets,
+            vec![
+                "/control/agents/researcher/input",
+                "/control/agents/writer/input",
+                "/control/agents/editor/input",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_replay_rejects_bad_speed_and_topics() {
+        let transport = MockTransport::new();
+        let recording = load_recording(&incident()).unwrap();
+        assert!(replay(&transport, &recording, 0.0, |_| {}).await.is_err());
+
+        let mut recorded = recording[0].clone();
+        if let TaskEnvelopeWrapper::V1(envelope) = &mut recorded.envelope {
+            envelope.topic = "/conversations/incident-42/progress".to_string();
+        }
+        let error = replay(&transport, &[recorded], 1.0, |_| {})
+            .await
+            .unwrap_err();
+        assert!(error.contains("Malformed target topic"));
+    }
+
+    #[test]
+    fn test_load_recording_reports_bad_files() {
+        let envelopes = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/envelopes");
+        let error = load_recording(&envelopes).unwrap_err();
+        assert!(error.contains("is not a task envelope"));
+        assert!(load_recording(&incident().join("missing")).is_err());
+    }
+}
@@ -0,0 +1,346 @@
+//! `agent2389 status`: the fleet as seen through retained status and manifest messages
+
+use crate::protocol::{AgentManifest, AgentStatus, AgentStatusType};
+use crate::transport::{TopicMessage, Transport};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// Filter matching every agent's retained status
+pub const STATUS_TOPIC_FILTER: &str = "/control/agents/+/status";
+/// Filter matching every agent's retained capability manifest
+pub const MANIFEST_TOPIC_FILTER: &str = "/control/agents/+/manifest";
+/// How long to collect retained messages before printing, by default
+pub const DEFAULT_COLLECT_SECS: u64 = 3;
+
+/// One agent's row in the fleet table
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct FleetEntry {
+    pub agent_id: String,
+    /// Last status the agent published, if any
+    pub status: Option<AgentStatusType>,
+    /// Capabilities from the status, or from the manifest when the status has none
+    pub capabilities: Vec<String>,
+    /// Newest timestamp seen in the agent's status or manifest
+    pub last_updated: Option<DateTime<Utc>>,
+    /// Payloads on the agent's topics that couldn't be parsed
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub problems: Vec<String>,
+    #[serde(skip)]
+    status_capabilities: Option<Vec<String>>,
+    #[serde(skip)]
+    manifest_capabilities: Vec<String>,
+}
+
+/// Fleet state built from status and manifest messages, keyed by agent id
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FleetStatus {
+    agents: BTreeMap<String, FleetEntry>,
+}
+
+impl FleetStatus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Apply a message received on a status or manifest topic
+    ///
+    /// Returns the agent the message was about, or `None` for topics outside
+    /// `/control/agents/<id>/{status,manifest}` and cleared retained messages.
+    pub fn apply(&mut self, topic: &str, payload: &[u8]) -> Option<&FleetEntry> {
+        let (agent_id, kind) = parse_agent_topic(topic)?;
+        if payload.is_empty() {
+            return None;
+        }
+        let entry = self
+            .agents
+            .entry(agent_id.to_string())
+            .or_insert_with(|| FleetEntry {
+                agent_id: agent_id.to_string(),
+                ..FleetEntry::default()
+            });
+
+        match kind {
+            "status" => match serde_json::from_slice::<AgentStatus>(payload) {
+                Ok(status) => {
+                    entry.status = Some(status.status);
+                    entry.status_capabilities = status.capabilities;
+                    entry.touch(status.timestamp);
+                }
+                Err(e) => entry
+                    .problems
+                    .push(format!("malformed status payload: {e}")),
+            },
+            _ => match serde_json::from_slice::<AgentManifest>(payload) {
+                Ok(manifest) => {
+                    entry.manifest_capabilities = manifest.capabilities;
+                    entry.touch(manifest.timestamp);
+                }
+                Err(e) => entry
+                    .problems
+                    .push(format!("malformed manifest payload: {e}")),
+            },
+        }
+        entry.capabilities = entry
+            .status_capabilities
+            .clone()
+            .unwrap_or_else(|| entry.manifest_capabilities.clone());
+        Some(entry)
+    }
+
+    /// Agents sorted by id
+    pub fn agents(&self) -> impl Iterator<Item = &FleetEntry> {
+        self.agents.values()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.agents.is_empty()
+    }
+
+    /// Fixed-width table with one row per agent (pure function)
+    pub fn render_table(&self) -> String {
+        let id_width = self
+            .agents
+            .keys()
+            .map(String::len)
+            .chain([AGENT_HEADER.len()])
+            .max()
+            .unwrap_or_default();
+        let mut table = format!(
+            "{AGENT_HEADER:<id_width$}  {:<11}  {:<24}  CAPABILITIES\n",
+            "STATUS", "LAST UPDATED"
+        );
+        for entry in self.agents() {
+            table.push_str(&entry.render_row(id_width));
+            table.push('\n');
+        }
+        table
+    }
+
+    /// Agents as a JSON array (pure function)
+    pub fn render_json(&self) -> String {
+        let agents: Vec<&FleetEntry> = self.agents().collect();
+        serde_json::to_string_pretty(&agents).unwrap_or_default()
+    }
+}
+
+const AGENT_HEADER: &str = "AGENT";
+
+impl FleetEntry {
+    fn touch(&mut self, timestamp: DateTime<Utc>) {
+        if self.last_updated.map_or(true, |last| timestamp > last) {
+            self.last_updated = Some(timestamp);
+        }
+    }
+
+    /// A table row, with malformed payloads flagged after the capabilities
+    pub fn render_row(&self, id_width: usize) -> String {
+        let status = match &self.status {
+            Some(AgentStatusType::Available) => "available",
+            Some(AgentStatusType::Unavailable) => "unavailable",
+            Some(AgentStatusType::Paused) => "paused",
+            None => "unknown",
+        };
+        let last_updated = self
+            .last_updated
+            .map(|timestamp| timestamp.to_rfc3339_opts(chrono::SecondsFormat::Secs, true))
+            .unwrap_or_else(|| "-".to_string());
+        let capabilities = if self.capabilities.is_empty() {
+            "-".to_string()
+        } else {
+            self.capabilities.join(",")
+        };
+        let mut row = format!(
+            "{:<id_width$}  {status:<11}  {last_updated:<24}  {capabilities}",
+            self.agent_id
+        );
+        for problem in &self.problems {
+            row.push_str(&format!("  [{problem}]"));
+        }
+        row
+    }
+}
+
+/// Agent id and topic kind of `/control/agents/<id>/{status,manifest}` (pure function)
+fn parse_agent_topic(topic: &str) -> Option<(&str, &str)> {
+    let rest = topic.strip_prefix("/control/agents/")?;
+    let (agent_id, kind) = rest.split_once('/')?;
+    (!agent_id.is_empty() && matches!(kind, "status" | "manifest")).then_some((agent_id, kind))
+}
+
+/// Status and manifest messages from every agent, as one stream
+pub struct FleetSubscription {
+    status: mpsc::Receiver<TopicMessage>,
+    manifest: mpsc::Receiver<TopicMessage>,
+}
+
+impl FleetSubscription {
+    /// Subscribe to every agent's status and manifest topics
+    ///
+    /// A broker delivers the retained messages right after subscribing.
+    pub async fn subscribe<T: Transport>(transport: &T) -> Result<Self, T::Error> {
+        Ok(Self {
+            status: transport.subscribe_topic(STATUS_TOPIC_FILTER).await?,
+            manifest: transport.subscribe_topic(MANIFEST_TOPIC_FILTER).await?,
+        })
+    }
+
+    /// Next message on either topic, or `None` once both subscriptions ended
+    pub async fn next(&mut self) -> Option<TopicMessage> {
+        tokio::select! {
+            Some(message) = self.status.recv() => Some(message),
+            Some(message) = self.manifest.recv() => Some(message),
+            else => None,
+        }
+    }
+
+    /// Apply every message received within `wait`
+    pub async fn collect(&mut self, fleet: &mut FleetStatus, wait: Duration) {
+        let deadline = tokio::time::Instant::now() + wait;
+        while let Ok(Some((topic, payload))) = tokio::time::timeout_at(deadline, self.next()).await
+        {
+            fleet.apply(&topic, &payload);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::mocks::MockTransport;
+    use serde_json::json;
+
+    fn status_payload(agent_id: &str, status: &str, capabilities: Option<&[&str]>) -> Vec<u8> {
+        let mut payload = json!({
+            "agent_id": agent_id,
+            "status": status,
+            "timestamp": "2026-10-01T12:00:00Z",
+        });
+        if let Some(capabilities) = capabilities {
+            payload["capabilities"] = json!(capabilities);
+        }
+        payload.to_string().into_bytes()
+    }
+
+    fn manifest_payload(agent_id: &str, capabilities: &[&str]) -> Vec<u8> {
+        json!({
+            "agent_id": agent_id,
+            "capabilities": capabilities,
+            "envelope_versions": ["1.0", "2.0"],
+            "tools": [],
+            "model": "gpt-4o",
+            "timestamp": "2026-10-01T12:05:00Z",
+        })
+        .to_string()
+        .into_bytes()
+    }
+
+    #[tokio::test]
+    async fn test_collects_retained_status_and_manifests() {
+        let transport = MockTransport::new();
+        transport
+            .publish(
+                "/control/agents/writer/status",
+                status_payload("writer", "available", None),
+                true,
+            )
+            .await
+            .unwrap();
+        transport
+            .publish(
+                "/control/agents/writer/manifest",
+                manifest_payload("writer", &["writing"]),
+                true,
+            )
+            .await
+            .unwrap();
+        transport
+            .publish(
+                "/control/agents/reviewer/status",
+                status_payload("reviewer", "paused", Some(&["review", "editing"])),
+                true,
+            )
+            .await
+            .unwrap();
+        // Not retained, so a new subscriber never sees it
+        transport
+            .publish(
+                "/control/agents/ghost/status",
+                status_payload("ghost", "available", None),
+                false,
+            )
+            .await
+            .unwrap();
+
+        let mut subscription = FleetSubscription::subscribe(&transport).await.unwrap();
+        let mut fleet = FleetStatus::new();
+        subscription
+            .collect(&mut fleet, Duration::from_millis(50))
+            .await;
+
+        let agents: Vec<&FleetEntry> = fleet.agents().collect();
+        assert_eq!(agents.len(), 2);
+        assert_eq!(agents[0].agent_id, "reviewer");
+        assert_eq!(agents[0].status, Some(AgentStatusType::Paused));
+        assert_eq!(agents[0].capabilities, vec!["review", "editing"]);
+        assert_eq!(agents[1].agent_id, "writer");
+        assert_eq!(agents[1].capabilities, vec!["writing"]);
+        assert_eq!(
+            agents[1].last_updated,
+            Some("2026-10-01T12:05:00Z".parse().unwrap())
+        );
+
+        let table = fleet.render_table();
+        assert!(table.starts_with("AGENT     STATUS"));
+        assert!(table.contains("reviewer  paused       2026-10-01T12:00:00Z      review,editing"));
+        let json: serde_json::Value = serde_json::from_str(&fleet.render_json()).unwrap();
+        assert_eq!(json[1]["status"], "available");
+        assert!(json[1].get("problems").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_streams_updates_after_collecting() {
+        let transport = MockTransport::new();
+        let mut subscription = FleetSubscription::subscribe(&transport).await.unwrap();
+        let mut fleet = FleetStatus::new();
+
+        transport
+            .publish(
+                "/control/agents/writer/status",
+                status_payload("writer", "unavailable", None),
+                false,
+            )
+            .await
+            .unwrap();
+        let (topic, payload) = subscription.next().await.unwrap();
+        let entry = fleet.apply(&topic, &payload).unwrap();
+        assert_eq!(entry.status, Some(AgentStatusType::Unavailable));
+    }
+
+    #[test]
+    fn test_malformed_payloads_are_flagged() {
+        let mut fleet = FleetStatus::new();
+        fleet.apply("/control/agents/broken/status", b"not json");
+        fleet.apply("/control/agents/broken/manifest", br#"{"agent_id": 1}"#);
+
+        let entry = fleet.agents().next().unwrap();
+        assert_eq!(entry.status, None);
+        assert_eq!(entry.problems.len(), 2);
+        assert!(entry.problems[0].starts_with("malformed status payload"));
+        assert!(entry.problems[1].starts_with("malformed manifest payload"));
+        assert!(fleet.render_table().contains(
+            "broken  unknown      -                         -  [malformed status payload"
+        ));
+    }
+
+    #[test]
+    fn test_ignores_other_topics_and_cleared_messages() {
+        let mut fleet = FleetStatus::new();
+        assert!(fleet.apply("/control/agents/a/input", b"{}").is_none());
+        assert!(fleet.apply("/control/agents//status", b"{}").is_none());
+        assert!(fleet.apply("/control/agents/a/status", b"").is_none());
+        assert!(fleet.is_empty());
+    }
+}
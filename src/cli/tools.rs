@@ -0,0 +1,172 @@
+//! `agent2389 tools`: inspect and run configured tools without an LLM
+
+use crate::config::AgentConfig;
+use crate::tools::{ToolDescription, ToolError, ToolSystem};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Exit status when the tool can't be found or its parameters can't be read
+pub const EXIT_USAGE: i32 = 1;
+/// Exit status when the parameters don't match the tool's schema
+pub const EXIT_INVALID_PARAMETERS: i32 = 2;
+/// Exit status when the tool fails to initialize or execute
+pub const EXIT_TOOL_FAILED: i32 = 3;
+
+/// Every configured tool with its description, sorted by name
+///
+/// Tools aren't initialized, so credentials they need may be missing.
+pub fn list(config: &AgentConfig) -> Result<Vec<(String, ToolDescription)>, ToolError> {
+    ToolSystem::describe_configured(&config.tools)
+}
+
+/// Description of the configured tool `name`
+pub fn describe(config: &AgentConfig, name: &str) -> Result<ToolDescription, ToolError> {
+    list(config)?
+        .into_iter()
+        .find(|(tool_name, _)| tool_name == name)
+        .map(|(_, description)| description)
+        .ok_or_else(|| ToolError::UnknownTool(name.to_string()))
+}
+
+/// Initialize only the configured tool `name`, validate `parameters` and execute it
+pub async fn exec(
+    config: &AgentConfig,
+    name: &str,
+    parameters: &Value,
+) -> Result<Value, ToolError> {
+    let tool_config = config
+        .tools
+        .get(name)
+        .ok_or_else(|| ToolError::UnknownTool(name.to_string()))?;
+
+    // The tool was asked for by name, so it can't be skipped
+    let mut tool_system = ToolSystem::new();
+    tool_system
+        .initialize(&HashMap::from([(name.to_string(), tool_config.required())]))
+        .await?;
+    tool_system.validate_parameters(name, parameters)?;
+    let result = tool_system.execute_tool(name, parameters).await;
+    tool_system.shutdown().await?;
+    result
+}
+
+/// Parameters given as inline JSON, or as `@path` to a JSON file
+pub fn parse_parameters(argument: &str) -> Result<Value, String> {
+    let (source, json) = match argument.strip_prefix('@') {
+        Some(path) => (
+            path,
+            std::fs::read_to_string(path).map_err(|e| format!("Cannot read {path}: {e}"))?,
+        ),
+        None => ("--params", argument.to_string()),
+    };
+    serde_json::from_str(&json).map_err(|e| format!("{source} is not valid JSON: {e}"))
+}
+
+/// Exit status for a failed `tools exec` (pure function)
+pub fn exit_code(error: &ToolError) -> i32 {
+    match error {
+        ToolError::ValidationError(_) | ToolError::SchemaError(_) => EXIT_INVALID_PARAMETERS,
+        ToolError::UnknownTool(_) | ToolError::UnknownImplementation(_) => EXIT_USAGE,
+        ToolError::InitializationError(_)
+        | ToolError::ExecutionError(_)
+        | ToolError::ShutdownError(_)
+        | ToolError::RequiredToolsFailed(_) => EXIT_TOOL_FAILED,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ToolConfig;
+    use serde_json::json;
+    use std::io::Write;
+
+    fn config_with_file_read() -> AgentConfig {
+        let mut config = AgentConfig::test_config();
+        config.tools = HashMap::from([
+            (
+                "file_read".to_string(),
+                ToolConfig::Simple("builtin".to_string()),
+            ),
+            (
+                "web_search".to_string(),
+                ToolConfig::Simple("builtin".to_string()),
+            ),
+        ]);
+        config
+    }
+
+    #[test]
+    fn test_list_describes_tools_without_initializing_them() {
+        let tools = list(&config_with_file_read()).unwrap();
+
+        let names: Vec<_> = tools.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["file_read", "web_search"]);
+        assert_eq!(tools[0].1.description, "Read file contents");
+    }
+
+    #[test]
+    fn test_describe_unknown_tool() {
+        let config = config_with_file_read();
+
+        assert_eq!(
+            describe(&config, "file_read").unwrap().parameters["required"],
+            json!(["path"])
+        );
+        assert!(matches!(
+            describe(&config, "file_write"),
+            Err(ToolError::UnknownTool(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_exec_runs_builtin_tool() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        write!(file, "hello from a tool").unwrap();
+        let parameters = json!({ "path": file.path() });
+
+        let result = exec(&config_with_file_read(), "file_read", &parameters)
+            .await
+            .unwrap();
+
+        assert_eq!(result["content"], "hello from a tool");
+    }
+
+    #[tokio::test]
+    async fn test_exec_exit_codes_distinguish_validation_and_execution() {
+        let config = config_with_file_read();
+
+        let invalid = exec(&config, "file_read", &json!({ "file": "x" }))
+            .await
+            .unwrap_err();
+        assert!(matches!(invalid, ToolError::ValidationError(_)));
+        assert_eq!(exit_code(&invalid), EXIT_INVALID_PARAMETERS);
+
+        let missing = json!({ "path": "/nonexistent/agent2389/file.txt" });
+        let failed = exec(&config, "file_read", &missing).await.unwrap_err();
+        assert!(matches!(failed, ToolError::ExecutionError(_)));
+        assert_eq!(exit_code(&failed), EXIT_TOOL_FAILED);
+
+        let unknown = exec(&config, "file_write", &json!({})).await.unwrap_err();
+        assert_eq!(exit_code(&unknown), EXIT_USAGE);
+    }
+
+    #[test]
+    fn test_parse_parameters_inline_and_from_file() {
+        assert_eq!(
+            parse_parameters(r#"{"path": "a.txt"}"#).unwrap(),
+            json!({ "path": "a.txt" })
+        );
+
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        write!(file, r#"{{"path": "b.txt"}}"#).unwrap();
+        let argument = format!("@{}", file.path().display());
+        assert_eq!(
+            parse_parameters(&argument).unwrap(),
+            json!({ "path": "b.txt" })
+        );
+
+        assert!(parse_parameters("{not json").is_err());
+        assert!(parse_parameters("@/nonexistent/params.json").is_err());
+    }
+}
@@ -0,0 +1,156 @@
+//! Clock abstraction for timestamps on envelopes and workflows
+//!
+//! The nine-step processor, the pipeline and the lifecycle read the time
+//! through a [`Clock`] instead of calling `Utc::now()` directly. Agents use
+//! the [`SystemClock`]; with `[testing] deterministic = true` they use a
+//! [`SteppingClock`] that starts at [`deterministic_epoch`] and advances by a
+//! fixed step on every read, so two runs of the same scripted workflow stamp
+//! the same times on everything they publish.
+
+use crate::config::TestingSection;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// First time read from the clock of a deterministic run (2025-01-01T00:00:00Z)
+pub const DETERMINISTIC_EPOCH_SECS: i64 = 1_735_689_600;
+
+/// Milliseconds a deterministic clock advances on every read
+pub const DETERMINISTIC_STEP_MILLIS: i64 = 1;
+
+/// First time read from the clock of a deterministic run
+pub fn deterministic_epoch() -> DateTime<Utc> {
+    Utc.timestamp_opt(DETERMINISTIC_EPOCH_SECS, 0)
+        .single()
+        .unwrap_or_default()
+}
+
+/// Source of the current time
+pub trait Clock: Send + Sync + std::fmt::Debug {
+    /// The current time
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// The wall clock
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// Clock that returns `start`, then advances by `step` on every read
+///
+/// Reads are strictly increasing, even from several tasks at once.
+#[derive(Debug)]
+pub struct SteppingClock {
+    start: DateTime<Utc>,
+    step: Duration,
+    reads: AtomicI64,
+}
+
+impl SteppingClock {
+    /// Clock starting at `start` and advancing by `step` per read
+    pub fn new(start: DateTime<Utc>, step: Duration) -> Self {
+        Self {
+            start,
+            step,
+            reads: AtomicI64::new(0),
+        }
+    }
+
+    /// Clock of a deterministic run
+    pub fn deterministic() -> Self {
+        Self::new(
+            deterministic_epoch(),
+            Duration::milliseconds(DETERMINISTIC_STEP_MILLIS),
+        )
+    }
+}
+
+impl Clock for SteppingClock {
+    fn now(&self) -> DateTime<Utc> {
+        let reads = self.reads.fetch_add(1, Ordering::SeqCst);
+        let elapsed = self
+            .step
+            .checked_mul(reads.try_into().unwrap_or(i32::MAX))
+            .unwrap_or(Duration::MAX);
+        self.start
+            .checked_add_signed(elapsed)
+            .unwrap_or(DateTime::<Utc>::MAX_UTC)
+    }
+}
+
+/// Clock for an agent with the given `[testing]` section
+pub fn clock_for(testing: &TestingSection) -> Arc<dyn Clock> {
+    if testing.deterministic {
+        Arc::new(SteppingClock::deterministic())
+    } else {
+        Arc::new(SystemClock)
+    }
+}
+
+/// Task id of a task forwarded from `parent_task_id`, in deterministic mode
+///
+/// Derived as a UUID v5 in the parent's namespace from the hop index, so a
+/// rerun forwards the same ids. `hop` must tell apart the tasks a parent
+/// forwards, e.g. `"3"` for the third hop and `"3.1"` for its second fan-out
+/// branch.
+pub fn derived_task_id(parent_task_id: Uuid, hop: &str) -> Uuid {
+    Uuid::new_v5(&parent_task_id, hop.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stepping_clock_advances_on_every_read() {
+        let clock = SteppingClock::deterministic();
+
+        let first = clock.now();
+        let second = clock.now();
+
+        assert_eq!(first, deterministic_epoch());
+        assert_eq!(second - first, Duration::milliseconds(1));
+    }
+
+    #[test]
+    fn test_two_deterministic_clocks_read_the_same_times() {
+        let (a, b) = (
+            SteppingClock::deterministic(),
+            SteppingClock::deterministic(),
+        );
+
+        let a_reads: Vec<_> = (0..5).map(|_| a.now()).collect();
+        let b_reads: Vec<_> = (0..5).map(|_| b.now()).collect();
+
+        assert_eq!(a_reads, b_reads);
+    }
+
+    #[test]
+    fn test_clock_for_follows_the_testing_section() {
+        let deterministic = TestingSection {
+            deterministic: true,
+            ..TestingSection::default()
+        };
+
+        assert_eq!(clock_for(&deterministic).now(), deterministic_epoch());
+        assert!(clock_for(&TestingSection::default()).now() > deterministic_epoch());
+    }
+
+    #[test]
+    fn test_derived_task_ids_depend_on_parent_and_hop() {
+        let parent = Uuid::new_v4();
+
+        assert_eq!(derived_task_id(parent, "1"), derived_task_id(parent, "1"));
+        assert_ne!(derived_task_id(parent, "1"), derived_task_id(parent, "2"));
+        assert_ne!(
+            derived_task_id(parent, "1"),
+            derived_task_id(Uuid::new_v4(), "1")
+        );
+    }
+}
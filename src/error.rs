@@ -3,7 +3,9 @@
 //! This module implements ONLY the error types and codes specified in the RFC.
 //! Maps internal errors to protocol-defined error codes for MQTT publishing.
 
+use crate::llm::provider::LlmError;
 use crate::protocol::messages::{ErrorCode, ErrorDetails, ErrorMessage};
+use crate::tools::ToolError;
 use thiserror::Error;
 use uuid::Uuid;
 
@@ -14,7 +16,7 @@ pub enum AgentError {
     ToolExecutionFailed { message: String },
 
     #[error("LLM provider error: {message}")]
-    LlmError { message: String },
+    LlmError { message: String, retryable: bool },
 
     #[error("Invalid input: {message}")]
     InvalidInput { message: String },
@@ -32,10 +34,95 @@ pub enum AgentError {
     ConfigError(#[from] crate::config::ConfigError),
 
     #[error("Tool error: {0}")]
-    ToolError(#[from] crate::tools::ToolError),
+    ToolError(#[from] ToolError),
 
     #[error("Routing error: {message}")]
     RoutingError { message: String },
+
+    #[error("Task cancelled: {message}")]
+    Cancelled { message: String },
+
+    #[error("Task deadline exceeded: deadline was {deadline}")]
+    DeadlineExceeded { deadline: String },
+
+    #[error("Stale task: published {age_secs}s ago, max age is {max_age_secs}s")]
+    StaleTask { age_secs: u64, max_age_secs: u64 },
+
+    #[error("Rate limited: {message}")]
+    RateLimited {
+        message: String,
+        retry_after_ms: Option<u64>,
+    },
+
+    #[error("Timed out: {message}")]
+    Timeout { message: String },
+
+    #[error("Provider overloaded: {message}")]
+    Overloaded { message: String },
+
+    #[error("Policy violation: {message}")]
+    PolicyViolation { message: String },
+
+    #[error("Unauthorized: {message}")]
+    Unauthorized { message: String },
+}
+
+impl From<LlmError> for AgentError {
+    /// Classify provider failures so consumers know whether to retry
+    fn from(error: LlmError) -> Self {
+        let message = error.to_string();
+        match &error {
+            LlmError::RateLimitExceeded(_) => Self::RateLimited {
+                message,
+                retry_after_ms: None,
+            },
+            LlmError::NetworkError(details) if is_timeout_message(details) => {
+                Self::Timeout { message }
+            }
+            LlmError::NetworkError(_) => Self::LlmError {
+                message,
+                retryable: true,
+            },
+            LlmError::ApiError(details) if is_rate_limit_message(details) => Self::RateLimited {
+                message,
+                retry_after_ms: None,
+            },
+            LlmError::ApiError(details) if is_overloaded_message(details) => {
+                Self::Overloaded { message }
+            }
+            LlmError::ApiError(details) => Self::LlmError {
+                message,
+                retryable: details.contains("server error"),
+            },
+            LlmError::NotConfigured(_)
+            | LlmError::AuthenticationFailed(_)
+            | LlmError::ModelNotFound(_)
+            | LlmError::RequestFailed(_)
+            | LlmError::InvalidRequest(_)
+            | LlmError::InvalidResponse(_) => Self::LlmError {
+                message,
+                retryable: false,
+            },
+        }
+    }
+}
+
+/// Detect timeouts reported by the HTTP client (pure function)
+fn is_timeout_message(details: &str) -> bool {
+    let details = details.to_lowercase();
+    details.contains("timed out") || details.contains("is_timeout: true")
+}
+
+/// Detect HTTP 429 responses surfaced as generic API errors (pure function)
+fn is_rate_limit_message(details: &str) -> bool {
+    details.contains("429 Too Many Requests")
+}
+
+/// Detect provider capacity errors: HTTP 503/529 or an `overloaded` error type (pure function)
+fn is_overloaded_message(details: &str) -> bool {
+    details.to_lowercase().contains("overloaded")
+        || details.contains(" 503 ")
+        || details.contains(" 529 ")
 }
 
 impl AgentError {
@@ -45,7 +132,7 @@ impl AgentError {
             AgentError::ToolExecutionFailed { message } => {
                 (ErrorCode::ToolExecutionFailed, message.clone())
             }
-            AgentError::LlmError { message } => (ErrorCode::LlmError, message.clone()),
+            AgentError::LlmError { message, .. } => (ErrorCode::LlmError, message.clone()),
             AgentError::InvalidInput { message } => (ErrorCode::InvalidInput, message.clone()),
             AgentError::PipelineDepthExceeded { current, max } => (
                 ErrorCode::PipelineDepthExceeded,
@@ -59,18 +146,65 @@ impl AgentError {
                 ErrorCode::InternalError,
                 format!("Configuration error: {e}"),
             ),
+            AgentError::ToolError(
+                e @ (ToolError::ValidationError(_) | ToolError::SchemaError(_)),
+            ) => (ErrorCode::InvalidInput, format!("Tool error: {e}")),
             AgentError::ToolError(e) => {
                 (ErrorCode::ToolExecutionFailed, format!("Tool error: {e}"))
             }
             AgentError::RoutingError { message } => (ErrorCode::InternalError, message.clone()),
+            AgentError::Cancelled { message } => (ErrorCode::Cancelled, message.clone()),
+            AgentError::DeadlineExceeded { deadline } => (
+                ErrorCode::DeadlineExceeded,
+                format!("Task deadline {deadline} has passed"),
+            ),
+            AgentError::StaleTask {
+                age_secs,
+                max_age_secs,
+            } => (
+                ErrorCode::StaleTask,
+                format!("Task was published {age_secs}s ago, older than the {max_age_secs}s limit"),
+            ),
+            AgentError::RateLimited { message, .. } => (ErrorCode::RateLimited, message.clone()),
+            AgentError::Timeout { message } => (ErrorCode::Timeout, message.clone()),
+            AgentError::Overloaded { message } => (ErrorCode::Overloaded, message.clone()),
+            AgentError::PolicyViolation { message } => {
+                (ErrorCode::PolicyViolation, message.clone())
+            }
+            AgentError::Unauthorized { message } => (ErrorCode::Unauthorized, message.clone()),
         };
 
         ErrorMessage {
             error: ErrorDetails {
                 code,
                 message: sanitize_error_message(&message),
+                retryable: self.is_retryable(),
+                retry_after_ms: self.retry_after_ms(),
             },
             task_id,
+            correlation_id: None,
+            parent_task_id: None,
+        }
+    }
+
+    /// Whether resubmitting the same task may succeed
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            AgentError::RateLimited { .. }
+            | AgentError::Timeout { .. }
+            | AgentError::Overloaded { .. }
+            | AgentError::TransportError(_) => true,
+            AgentError::LlmError { retryable, .. } => *retryable,
+            AgentError::ToolError(ToolError::ExecutionError(_)) => true,
+            _ => false,
+        }
+    }
+
+    /// Suggested delay before retrying, if the error carries one
+    pub fn retry_after_ms(&self) -> Option<u64> {
+        match self {
+            AgentError::RateLimited { retry_after_ms, .. } => *retry_after_ms,
+            _ => None,
         }
     }
 
@@ -81,10 +215,19 @@ impl AgentError {
         }
     }
 
-    /// Create LLM error
+    /// Create non-retryable LLM error
     pub fn llm_error<S: Into<String>>(message: S) -> Self {
         Self::LlmError {
             message: message.into(),
+            retryable: false,
+        }
+    }
+
+    /// Create rate limited error
+    pub fn rate_limited<S: Into<String>>(message: S, retry_after_ms: Option<u64>) -> Self {
+        Self::RateLimited {
+            message: message.into(),
+            retry_after_ms,
         }
     }
 
@@ -106,6 +249,47 @@ impl AgentError {
             message: message.into(),
         }
     }
+
+    /// Create retryable transport error from a message
+    pub fn transport_error<S: Into<String>>(message: S) -> Self {
+        Self::TransportError(message.into().into())
+    }
+
+    /// Create deadline exceeded error
+    pub fn deadline_exceeded<S: Into<String>>(deadline: S) -> Self {
+        Self::DeadlineExceeded {
+            deadline: deadline.into(),
+        }
+    }
+
+    /// Create stale task error
+    pub fn stale_task(age_secs: u64, max_age_secs: u64) -> Self {
+        Self::StaleTask {
+            age_secs,
+            max_age_secs,
+        }
+    }
+
+    /// Create task cancelled error
+    pub fn cancelled<S: Into<String>>(message: S) -> Self {
+        Self::Cancelled {
+            message: message.into(),
+        }
+    }
+
+    /// Create policy violation error for a response blocked by moderation
+    pub fn policy_violation<S: Into<String>>(message: S) -> Self {
+        Self::PolicyViolation {
+            message: message.into(),
+        }
+    }
+
+    /// Create unauthorized error for a task denied by the authorization rules
+    pub fn unauthorized<S: Into<String>>(message: S) -> Self {
+        Self::Unauthorized {
+            message: message.into(),
+        }
+    }
 }
 
 /// Sanitize error messages to prevent sensitive data leakage per RFC requirements
@@ -238,6 +422,14 @@ mod tests {
         assert_eq!(error.to_string(), "Internal error: unexpected state");
     }
 
+    #[test]
+    fn test_transport_error_constructor() {
+        let error = AgentError::transport_error("broker unavailable");
+        assert!(matches!(error, AgentError::TransportError(_)));
+        assert!(error.is_retryable());
+        assert_eq!(error.to_string(), "Transport error: broker unavailable");
+    }
+
     // ========== Tests for Error Code Mapping ==========
 
     #[test]
@@ -344,4 +536,160 @@ mod tests {
         assert_eq!(error_msg.error.code, ErrorCode::InternalError);
         assert_eq!(error_msg.error.message, "No route found");
     }
+
+    #[test]
+    fn test_cancelled_maps_to_cancelled_code() {
+        let task_id = Uuid::new_v4();
+        let error = AgentError::cancelled("Task cancelled: user abort");
+
+        let error_msg = error.to_error_message(task_id);
+        assert_eq!(error_msg.error.code, ErrorCode::Cancelled);
+        assert_eq!(error_msg.error.message, "Task cancelled: user abort");
+    }
+
+    #[test]
+    fn test_deadline_exceeded_maps_to_deadline_code() {
+        let task_id = Uuid::new_v4();
+        let error = AgentError::deadline_exceeded("2024-01-01T00:00:00+00:00");
+
+        let error_msg = error.to_error_message(task_id);
+        assert_eq!(error_msg.error.code, ErrorCode::DeadlineExceeded);
+        assert!(error_msg
+            .error
+            .message
+            .contains("2024-01-01T00:00:00+00:00"));
+    }
+
+    #[test]
+    fn test_stale_task_maps_to_stale_task_code() {
+        let error_msg = AgentError::stale_task(900, 300).to_error_message(Uuid::new_v4());
+
+        assert_eq!(error_msg.error.code, ErrorCode::StaleTask);
+        assert!(!error_msg.error.retryable);
+        assert!(error_msg.error.message.contains("900s"));
+    }
+
+    #[test]
+    fn test_policy_violation_maps_to_policy_violation_code() {
+        let error_msg = AgentError::policy_violation("response mentions an internal codename")
+            .to_error_message(Uuid::new_v4());
+
+        assert_eq!(error_msg.error.code, ErrorCode::PolicyViolation);
+        assert!(!error_msg.error.retryable);
+        assert!(error_msg.error.message.contains("internal codename"));
+    }
+
+    #[test]
+    fn test_unauthorized_maps_to_unauthorized_code() {
+        let error_msg = AgentError::unauthorized("denied by authorization rule 2")
+            .to_error_message(Uuid::new_v4());
+
+        assert_eq!(error_msg.error.code, ErrorCode::Unauthorized);
+        assert!(!error_msg.error.retryable);
+    }
+
+    #[test]
+    fn test_llm_errors_map_to_wire_codes() {
+        let task_id = Uuid::new_v4();
+        let cases = [
+            (
+                LlmError::RateLimitExceeded("429".to_string()),
+                ErrorCode::RateLimited,
+                true,
+            ),
+            (
+                LlmError::ApiError(
+                    "Anthropic API error: 429 Too Many Requests - Rate limit exceeded".to_string(),
+                ),
+                ErrorCode::RateLimited,
+                true,
+            ),
+            (
+                LlmError::NetworkError("operation timed out".to_string()),
+                ErrorCode::Timeout,
+                true,
+            ),
+            (
+                LlmError::NetworkError("connection refused".to_string()),
+                ErrorCode::LlmError,
+                true,
+            ),
+            (
+                LlmError::ApiError("Anthropic API error: 529 - overloaded_error".to_string()),
+                ErrorCode::Overloaded,
+                true,
+            ),
+            (
+                LlmError::ApiError("OpenAI API server error: 500 - oops".to_string()),
+                ErrorCode::LlmError,
+                true,
+            ),
+            (
+                LlmError::ApiError("OpenAI API error: 400 - bad request".to_string()),
+                ErrorCode::LlmError,
+                false,
+            ),
+            (
+                LlmError::AuthenticationFailed("bad key".to_string()),
+                ErrorCode::LlmError,
+                false,
+            ),
+        ];
+
+        for (llm_error, code, retryable) in cases {
+            let error_msg = AgentError::from(llm_error).to_error_message(task_id);
+            assert_eq!(error_msg.error.code, code);
+            assert_eq!(error_msg.error.retryable, retryable, "{code}");
+        }
+    }
+
+    #[test]
+    fn test_tool_errors_map_to_wire_codes() {
+        let task_id = Uuid::new_v4();
+        let cases = [
+            (
+                ToolError::ExecutionError("HTTP 502".to_string()),
+                ErrorCode::ToolExecutionFailed,
+                true,
+            ),
+            (
+                ToolError::ValidationError("missing url".to_string()),
+                ErrorCode::InvalidInput,
+                false,
+            ),
+            (
+                ToolError::UnknownTool("nope".to_string()),
+                ErrorCode::ToolExecutionFailed,
+                false,
+            ),
+        ];
+
+        for (tool_error, code, retryable) in cases {
+            let error_msg = AgentError::from(tool_error).to_error_message(task_id);
+            assert_eq!(error_msg.error.code, code);
+            assert_eq!(error_msg.error.retryable, retryable, "{code}");
+        }
+    }
+
+    #[test]
+    fn test_agent_error_retry_hints() {
+        let task_id = Uuid::new_v4();
+
+        let error_msg = AgentError::rate_limited("slow down", Some(1500)).to_error_message(task_id);
+        assert_eq!(error_msg.error.code, ErrorCode::RateLimited);
+        assert!(error_msg.error.retryable);
+        assert_eq!(error_msg.error.retry_after_ms, Some(1500));
+
+        for error in [
+            AgentError::invalid_input("bad"),
+            AgentError::pipeline_depth_exceeded(17, 16),
+            AgentError::cancelled("stop"),
+            AgentError::deadline_exceeded("2024-01-01T00:00:00+00:00"),
+            AgentError::llm_error("no content"),
+        ] {
+            let error_msg = error.to_error_message(task_id);
+            assert!(!error_msg.error.retryable);
+            assert_eq!(error_msg.error.retry_after_ms, None);
+        }
+    }
 }
@@ -26,6 +26,11 @@
 //!     instruction: Some("Process this data".to_string()),
 //!     input: json!({"key": "value"}),
 //!     next: None,
+//!     deadline: None,
+//!     correlation_id: None,
+//!     parent_task_id: None,
+//!     published_at: None,
+//!     traceparent: None,
 //! };
 //!
 //! // Create a v2.0 task envelope with workflow context
@@ -36,6 +41,7 @@
 //!     instruction: Some("Process this data".to_string()),
 //!     input: json!({"urgency_score": 0.9}),
 //!     next: None,
+//!     deadline: None,
 //!     version: "2.0".to_string(),
 //!     context: Some(WorkflowContext {
 //!         original_query: "Process urgent request".to_string(),
@@ -47,8 +53,16 @@
 //!             }
 //!         ],
 //!         iteration_count: 1,
+//!         started_at: None,
+//!         budget_secs: None,
+//!         state: None,
+//!         state_order: Vec::new(),
 //!     }),
 //!     routing_trace: None,
+//!     correlation_id: None,
+//!     parent_task_id: None,
+//!     published_at: None,
+//!     traceparent: None,
 //! };
 //!
 //! // Both serialize to JSON for MQTT transport
@@ -57,6 +71,8 @@
 //! ```
 
 pub mod agent;
+pub mod cli;
+pub mod clock;
 pub mod config;
 pub mod error;
 pub mod health;
@@ -66,6 +82,7 @@ pub mod processing;
 pub mod progress;
 pub mod protocol;
 pub mod routing;
+pub mod secrets;
 pub mod testing;
 pub mod tools;
 pub mod transport;
@@ -25,13 +25,16 @@ pub enum MessageRole {
 }
 
 /// LLM completion request parameters
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct CompletionRequest {
     pub messages: Vec<Message>,
     pub model: String,
     pub max_tokens: Option<u32>,
     pub temperature: Option<f32>,
     pub top_p: Option<f32>,
+    // Left out when unset, so requests recorded before it existed hash the same
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
     pub stop_sequences: Option<Vec<String>>,
     pub tools: Option<Vec<crate::tools::ToolDescription>>,
     pub tool_choice: Option<String>,
@@ -64,6 +67,16 @@ pub struct TokenUsage {
     pub prompt_tokens: u32,
     pub completion_tokens: u32,
     pub total_tokens: u32,
+    /// Prompt tokens read from the provider's prompt cache, when it reports them
+    ///
+    /// Not included in `prompt_tokens`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_read_tokens: Option<u32>,
+    /// Prompt tokens written to the provider's prompt cache, when it reports them
+    ///
+    /// Not included in `prompt_tokens`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_creation_tokens: Option<u32>,
 }
 
 /// Reason why completion finished
@@ -76,10 +89,11 @@ pub enum FinishReason {
 }
 
 /// Response format for structured outputs
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum ResponseFormat {
     /// Plain text response
+    #[default]
     Text,
     /// JSON object without schema validation
     Json,
@@ -99,12 +113,6 @@ pub struct JsonSchemaDefinition {
     pub schema: serde_json::Value,
 }
 
-impl Default for ResponseFormat {
-    fn default() -> Self {
-        Self::Text
-    }
-}
-
 /// LLM provider trait for dependency injection and testing
 #[async_trait]
 pub trait LlmProvider: Send + Sync {
@@ -119,10 +127,16 @@ pub trait LlmProvider: Send + Sync {
 
     /// Check if the provider is configured and ready
     async fn health_check(&self) -> Result<(), LlmError>;
+
+    /// Release resources held between requests, such as pooled HTTP connections
+    ///
+    /// Called when the agent goes idle. The provider must re-acquire them
+    /// lazily on the next request. The default holds nothing to release.
+    fn release_resources(&self) {}
 }
 
 /// LLM provider errors
-#[derive(Debug, Clone, Error)]
+#[derive(Debug, Clone, Error, Serialize, Deserialize)]
 pub enum LlmError {
     #[error("Provider not configured: {0}")]
     NotConfigured(String),
@@ -178,6 +192,7 @@ mod tests {
             max_tokens: Some(100),
             temperature: Some(0.7),
             top_p: None,
+            seed: None,
             stop_sequences: None,
             tools: None,
             tool_choice: None,
@@ -197,6 +212,8 @@ mod tests {
         assert_eq!(usage.prompt_tokens, 0);
         assert_eq!(usage.completion_tokens, 0);
         assert_eq!(usage.total_tokens, 0);
+        assert_eq!(usage.cache_read_tokens, None);
+        assert_eq!(usage.cache_creation_tokens, None);
     }
 
     #[test]
@@ -1,13 +1,17 @@
 //! Anthropic provider implementation
 //!
 //! This module provides Anthropic API integration for the LLM provider system.
+//! With prompt caching enabled, the tool schemas and the system prompt are
+//! marked with `cache_control` breakpoints so that the stable prefix of every
+//! request is billed at the cache read rate after the first.
 
+use super::http_pool::HttpPool;
 use crate::llm::provider::{
     CompletionRequest, CompletionResponse, FinishReason, LlmError, LlmProvider, Message,
-    MessageRole, TokenUsage,
+    MessageRole, TokenUsage, ToolCall,
 };
+use crate::tools::ToolDescription;
 use async_trait::async_trait;
-use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
 
@@ -18,6 +22,8 @@ pub struct AnthropicConfig {
     pub base_url: String,
     pub timeout: Duration,
     pub version: String,
+    /// Mark the tools and system prompt as cacheable (`[llm] prompt_caching`)
+    pub prompt_caching: bool,
 }
 
 impl Default for AnthropicConfig {
@@ -27,6 +33,7 @@ impl Default for AnthropicConfig {
             base_url: "https://api.anthropic.com/v1".to_string(),
             timeout: Duration::from_secs(60),
             version: "2023-06-01".to_string(),
+            prompt_caching: false,
         }
     }
 }
@@ -34,7 +41,7 @@ impl Default for AnthropicConfig {
 /// Anthropic provider implementation
 pub struct AnthropicProvider {
     config: AnthropicConfig,
-    client: Client,
+    http: HttpPool,
 }
 
 impl AnthropicProvider {
@@ -46,12 +53,9 @@ impl AnthropicProvider {
             ));
         }
 
-        let client = Client::builder()
-            .timeout(config.timeout)
-            .build()
-            .map_err(|e| LlmError::NetworkError(e.to_string()))?;
+        let http = HttpPool::new(config.timeout)?;
 
-        Ok(Self { config, client })
+        Ok(Self { config, http })
     }
 
     /// Convert internal messages to Anthropic format
@@ -80,10 +84,43 @@ impl AnthropicProvider {
         (system_message, anthropic_messages)
     }
 
+    /// Anthropic system prompt, with a cache breakpoint when caching is enabled
+    fn convert_system(&self, system_message: String) -> AnthropicSystem {
+        if self.config.prompt_caching {
+            AnthropicSystem::Blocks(vec![AnthropicTextBlock {
+                block_type: "text".to_string(),
+                text: system_message,
+                cache_control: Some(CacheControl::ephemeral()),
+            }])
+        } else {
+            AnthropicSystem::Text(system_message)
+        }
+    }
+
+    /// Convert tool descriptions to Anthropic tools
+    ///
+    /// With caching enabled the last tool carries the cache breakpoint, which
+    /// caches every tool before it as well.
+    fn convert_tools(&self, tools: &[ToolDescription]) -> Vec<AnthropicTool> {
+        let last = tools.len().saturating_sub(1);
+        tools
+            .iter()
+            .enumerate()
+            .map(|(index, tool)| AnthropicTool {
+                name: tool.name.clone(),
+                description: tool.description.clone(),
+                input_schema: tool.parameters.clone(),
+                cache_control: (self.config.prompt_caching && index == last)
+                    .then(CacheControl::ephemeral),
+            })
+            .collect()
+    }
+
     /// Convert Anthropic finish reason to internal format
     fn convert_finish_reason(&self, reason: Option<String>) -> FinishReason {
         match reason.as_deref() {
             Some("end_turn") => FinishReason::Stop,
+            Some("tool_use") => FinishReason::Stop,
             Some("max_tokens") => FinishReason::Length,
             Some("stop_sequence") => FinishReason::Stop,
             _ => FinishReason::Error,
@@ -123,19 +160,27 @@ impl LlmProvider for AnthropicProvider {
             ResponseFormat::Text => None,
         });
 
+        let tools = request
+            .tools
+            .as_deref()
+            .filter(|tools| !tools.is_empty())
+            .map(|tools| self.convert_tools(tools));
+
         let anthropic_request = AnthropicCompletionRequest {
             model: request.model.clone(),
             max_tokens: request.max_tokens.unwrap_or(4096),
             messages,
-            system: system_message,
+            system: system_message.map(|system| self.convert_system(system)),
             temperature: request.temperature,
             top_p: request.top_p,
             stop_sequences: request.stop_sequences,
+            tools,
             response_format,
         };
 
         let response = self
-            .client
+            .http
+            .client()?
             .post(format!("{}/messages", self.config.base_url))
             .header("x-api-key", &self.config.api_key)
             .header("anthropic-version", &self.config.version)
@@ -164,21 +209,28 @@ impl LlmProvider for AnthropicProvider {
             ));
         }
 
-        let content = anthropic_response
-            .content
-            .into_iter()
-            .filter_map(|c| match c.content_type.as_str() {
-                "text" => Some(c.text),
-                _ => None,
-            })
-            .collect::<Vec<_>>()
-            .join("");
+        let mut content = String::new();
+        let mut tool_calls = Vec::new();
+        for block in anthropic_response.content {
+            match block.content_type.as_str() {
+                "text" => content.push_str(&block.text),
+                "tool_use" => tool_calls.push(ToolCall {
+                    id: block.id.unwrap_or_default(),
+                    name: block.name.unwrap_or_default(),
+                    arguments: block.input.unwrap_or_default(),
+                }),
+                _ => {}
+            }
+        }
 
+        // Cached prompt tokens are reported apart from `input_tokens`
         let usage = TokenUsage {
             prompt_tokens: anthropic_response.usage.input_tokens,
             completion_tokens: anthropic_response.usage.output_tokens,
             total_tokens: anthropic_response.usage.input_tokens
                 + anthropic_response.usage.output_tokens,
+            cache_read_tokens: anthropic_response.usage.cache_read_input_tokens,
+            cache_creation_tokens: anthropic_response.usage.cache_creation_input_tokens,
         };
 
         Ok(CompletionResponse {
@@ -186,11 +238,15 @@ impl LlmProvider for AnthropicProvider {
             model: anthropic_response.model,
             usage,
             finish_reason: self.convert_finish_reason(anthropic_response.stop_reason),
-            tool_calls: None, // Anthropic doesn't support structured tool calling yet
+            tool_calls: (!tool_calls.is_empty()).then_some(tool_calls),
             metadata: request.metadata,
         })
     }
 
+    fn release_resources(&self) {
+        self.http.release();
+    }
+
     async fn health_check(&self) -> Result<(), LlmError> {
         // Anthropic doesn't have a simple health check endpoint, so we make a minimal request
         let test_request = AnthropicCompletionRequest {
@@ -204,11 +260,13 @@ impl LlmProvider for AnthropicProvider {
             temperature: None,
             top_p: None,
             stop_sequences: None,
+            tools: None,
             response_format: None,
         };
 
         let response = self
-            .client
+            .http
+            .client()?
             .post(format!("{}/messages", self.config.base_url))
             .header("x-api-key", &self.config.api_key)
             .header("anthropic-version", &self.config.version)
@@ -234,7 +292,7 @@ struct AnthropicCompletionRequest {
     max_tokens: u32,
     messages: Vec<AnthropicMessage>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    system: Option<String>,
+    system: Option<AnthropicSystem>,
     #[serde(skip_serializing_if = "Option::is_none")]
     temperature: Option<f32>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -242,9 +300,52 @@ struct AnthropicCompletionRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     stop_sequences: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    tools: Option<Vec<AnthropicTool>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     response_format: Option<AnthropicResponseFormat>,
 }
 
+/// System prompt: plain text, or text blocks when one carries a cache breakpoint
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+enum AnthropicSystem {
+    Text(String),
+    Blocks(Vec<AnthropicTextBlock>),
+}
+
+#[derive(Debug, Serialize)]
+struct AnthropicTextBlock {
+    #[serde(rename = "type")]
+    block_type: String,
+    text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cache_control: Option<CacheControl>,
+}
+
+#[derive(Debug, Serialize)]
+struct AnthropicTool {
+    name: String,
+    description: String,
+    input_schema: serde_json::Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cache_control: Option<CacheControl>,
+}
+
+/// Prompt cache breakpoint: the request prefix up to this block is cached
+#[derive(Debug, Serialize)]
+struct CacheControl {
+    #[serde(rename = "type")]
+    control_type: String,
+}
+
+impl CacheControl {
+    fn ephemeral() -> Self {
+        Self {
+            control_type: "ephemeral".to_string(),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 struct AnthropicMessage {
     role: String,
@@ -263,13 +364,25 @@ struct AnthropicCompletionResponse {
 struct AnthropicContent {
     #[serde(rename = "type")]
     content_type: String,
+    #[serde(default)]
     text: String,
+    /// Tool use id, name and input of a `tool_use` block
+    #[serde(default)]
+    id: Option<String>,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    input: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Deserialize)]
 struct AnthropicUsage {
     input_tokens: u32,
     output_tokens: u32,
+    #[serde(default)]
+    cache_creation_input_tokens: Option<u32>,
+    #[serde(default)]
+    cache_read_input_tokens: Option<u32>,
 }
 
 /// Anthropic response format
@@ -395,10 +508,11 @@ mod tests {
                 role: "user".to_string(),
                 content: "Hello".to_string(),
             }],
-            system: Some("You are helpful".to_string()),
+            system: Some(AnthropicSystem::Text("You are helpful".to_string())),
             temperature: Some(0.7),
             top_p: None,
             stop_sequences: None,
+            tools: None,
             response_format: None,
         };
 
@@ -409,5 +523,53 @@ mod tests {
         assert!(json.contains("\"temperature\":0.7"));
         assert!(!json.contains("top_p"));
         assert!(!json.contains("stop_sequences"));
+        assert!(!json.contains("tools"));
+    }
+
+    fn tool(name: &str) -> ToolDescription {
+        ToolDescription {
+            name: name.to_string(),
+            description: format!("The {name} tool"),
+            parameters: serde_json::json!({"type": "object"}),
+        }
+    }
+
+    #[test]
+    fn test_prompt_caching_marks_system_and_last_tool() {
+        let config = AnthropicConfig {
+            api_key: "test-key".to_string(),
+            prompt_caching: true,
+            ..Default::default()
+        };
+        let provider = AnthropicProvider::new(config).unwrap();
+
+        let system = serde_json::to_value(provider.convert_system("Be brief".to_string())).unwrap();
+        let tools =
+            serde_json::to_value(provider.convert_tools(&[tool("search"), tool("fetch")])).unwrap();
+
+        assert_eq!(
+            system,
+            serde_json::json!([
+                {"type": "text", "text": "Be brief", "cache_control": {"type": "ephemeral"}}
+            ])
+        );
+        assert!(tools[0].get("cache_control").is_none());
+        assert_eq!(tools[1]["cache_control"]["type"], "ephemeral");
+        assert_eq!(tools[1]["input_schema"]["type"], "object");
+    }
+
+    #[test]
+    fn test_without_prompt_caching_nothing_is_marked() {
+        let config = AnthropicConfig {
+            api_key: "test-key".to_string(),
+            ..Default::default()
+        };
+        let provider = AnthropicProvider::new(config).unwrap();
+
+        let system = serde_json::to_value(provider.convert_system("Be brief".to_string())).unwrap();
+        let tools = serde_json::to_value(provider.convert_tools(&[tool("search")])).unwrap();
+
+        assert_eq!(system, "Be brief");
+        assert!(tools[0].get("cache_control").is_none());
     }
 }
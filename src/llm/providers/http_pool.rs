@@ -0,0 +1,76 @@
+//! Lazily built HTTP client shared by the providers
+//!
+//! The client owns the provider's connection pool. Releasing it closes the
+//! idle connections once in-flight requests finish; the next request builds
+//! a fresh client.
+
+use crate::llm::provider::LlmError;
+use reqwest::Client;
+use std::sync::Mutex;
+use std::time::Duration;
+use tracing::debug;
+
+/// HTTP client that can be dropped while idle and rebuilt on demand
+pub(crate) struct HttpPool {
+    timeout: Duration,
+    client: Mutex<Option<Client>>,
+}
+
+impl HttpPool {
+    /// Build the client up front, so a bad setup fails at provider creation
+    pub(crate) fn new(timeout: Duration) -> Result<Self, LlmError> {
+        let client = Self::build(timeout)?;
+        Ok(Self {
+            timeout,
+            client: Mutex::new(Some(client)),
+        })
+    }
+
+    /// Current client, rebuilding it if it was released
+    pub(crate) fn client(&self) -> Result<Client, LlmError> {
+        let mut client = self.client.lock().unwrap();
+        if let Some(client) = client.as_ref() {
+            return Ok(client.clone());
+        }
+
+        debug!("Rebuilding released LLM HTTP client");
+        let rebuilt = Self::build(self.timeout)?;
+        *client = Some(rebuilt.clone());
+        Ok(rebuilt)
+    }
+
+    /// Drop the client and its pooled connections
+    pub(crate) fn release(&self) {
+        let released = self.client.lock().unwrap().take();
+        if released.is_some() {
+            debug!("Released LLM HTTP client");
+        }
+    }
+
+    fn build(timeout: Duration) -> Result<Client, LlmError> {
+        Client::builder()
+            .timeout(timeout)
+            .build()
+            .map_err(|e| LlmError::NetworkError(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_released_client_is_rebuilt_on_demand() {
+        let pool = HttpPool::new(Duration::from_secs(5)).unwrap();
+        let is_warm = |pool: &HttpPool| pool.client.lock().unwrap().is_some();
+        assert!(is_warm(&pool));
+
+        pool.release();
+        assert!(!is_warm(&pool));
+        // Releasing twice is harmless
+        pool.release();
+
+        assert!(pool.client().is_ok());
+        assert!(is_warm(&pool));
+    }
+}
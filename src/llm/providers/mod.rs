@@ -4,6 +4,7 @@
 //! for different LLM services.
 
 pub mod anthropic;
+mod http_pool;
 pub mod openai;
 
 pub use anthropic::*;
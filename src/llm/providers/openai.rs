@@ -2,13 +2,13 @@
 //!
 //! This module provides OpenAI API integration for the LLM provider system.
 
+use super::http_pool::HttpPool;
 use crate::llm::provider::{
     CompletionRequest, CompletionResponse, FinishReason, LlmError, LlmProvider, Message,
     MessageRole, TokenUsage, ToolCall as ProviderToolCall,
 };
 use crate::tools::ToolDescription;
 use async_trait::async_trait;
-use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
 use tracing::{debug, error, warn};
@@ -34,7 +34,7 @@ impl Default for OpenAiConfig {
 /// OpenAI provider implementation
 pub struct OpenAiProvider {
     config: OpenAiConfig,
-    client: Client,
+    http: HttpPool,
 }
 
 impl OpenAiProvider {
@@ -46,12 +46,9 @@ impl OpenAiProvider {
             ));
         }
 
-        let client = Client::builder()
-            .timeout(config.timeout)
-            .build()
-            .map_err(|e| LlmError::NetworkError(e.to_string()))?;
+        let http = HttpPool::new(config.timeout)?;
 
-        Ok(Self { config, client })
+        Ok(Self { config, http })
     }
 
     /// Estimate token count for messages (pure function)
@@ -94,6 +91,7 @@ impl OpenAiProvider {
             max_tokens: request.max_tokens,
             temperature: request.temperature,
             top_p: request.top_p,
+            seed: request.seed,
             stop: request.stop_sequences.clone(),
             tools,
             tool_choice: request.tool_choice.clone(),
@@ -117,6 +115,8 @@ impl OpenAiProvider {
             prompt_tokens: openai_response.usage.prompt_tokens,
             completion_tokens: openai_response.usage.completion_tokens,
             total_tokens: openai_response.usage.total_tokens,
+            cache_read_tokens: None,
+            cache_creation_tokens: None,
         };
 
         let tool_calls = choice
@@ -235,9 +235,14 @@ impl LlmProvider for OpenAiProvider {
             .await
     }
 
+    fn release_resources(&self) {
+        self.http.release();
+    }
+
     async fn health_check(&self) -> Result<(), LlmError> {
         let response = self
-            .client
+            .http
+            .client()?
             .get(format!("{}/models", self.config.base_url))
             .header("Authorization", format!("Bearer {}", self.config.api_key))
             .send()
@@ -327,7 +332,8 @@ impl OpenAiProvider {
         openai_request: &OpenAiCompletionRequest,
     ) -> Result<OpenAiCompletionResponse, LlmError> {
         let response = self
-            .client
+            .http
+            .client()?
             .post(format!("{}/chat/completions", self.config.base_url))
             .header("Authorization", format!("Bearer {}", self.config.api_key))
             .header("Content-Type", "application/json")
@@ -412,6 +418,8 @@ struct OpenAiCompletionRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     top_p: Option<f32>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     stop: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     tools: Option<Vec<OpenAiTool>>,
@@ -607,6 +615,7 @@ mod tests {
             max_tokens: Some(100),
             temperature: Some(0.7),
             top_p: None,
+            seed: None,
             stop: None,
             tools: None,
             tool_choice: None,
@@ -3,17 +3,25 @@
 //! This implements ONLY the functionality specified in the RFC.
 //! No additional features beyond the RFC specification are allowed.
 
-use agent2389::config::AgentConfig;
+use agent2389::agent::AgentHost;
+use agent2389::cli::doctor::{self, CheckResult, CheckStatus};
+use agent2389::cli::envelope as envelope_cli;
+use agent2389::cli::replay as replay_cli;
+use agent2389::cli::status::{FleetStatus, FleetSubscription, DEFAULT_COLLECT_SECS};
+use agent2389::cli::tools as tools_cli;
+use agent2389::config::{AgentConfig, ConfigFormat, HostConfig, TestingSection};
+use agent2389::observability::event_log::{event_log, DEFAULT_EVENT_LOG_CAPACITY};
+use agent2389::observability::otel::shutdown_trace_export;
 use agent2389::observability::{health::HealthServer, init_default_logging, metrics::metrics};
+use agent2389::testing::recording::{Recorder, Recording, RecordingLlmProvider, ReplayLlmProvider};
+use agent2389::transport::mqtt::MqttClient;
 use clap::{Parser, Subcommand};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process;
 use std::sync::Arc;
-use tokio::{
-    signal,
-    time::{sleep, Duration},
-};
-use tracing::{error, info};
+use std::time::Duration;
+use tokio::signal;
+use tracing::{error, info, warn};
 
 /// RFC-compliant 2389 Agent Protocol Implementation
 #[derive(Parser)]
@@ -25,6 +33,10 @@ struct Cli {
     #[arg(short, long, value_name = "FILE")]
     config: Option<PathBuf>,
 
+    /// Merge `[profiles.<NAME>]` of the configuration over the rest of it
+    #[arg(short, long, value_name = "NAME", global = true)]
+    profile: Option<String>,
+
     /// Verbose logging
     #[arg(short, long, action = clap::ArgAction::Count)]
     verbose: u8,
@@ -36,12 +48,95 @@ struct Cli {
 #[derive(Subcommand)]
 enum Commands {
     /// Run the agent per RFC Section 7
-    Run,
+    Run {
+        /// Record every LLM and tool call into DIR, overriding [testing]
+        #[arg(long, value_name = "DIR")]
+        record: Option<PathBuf>,
+        /// Answer LLM and tool calls from the recording in DIR, overriding [testing]
+        #[arg(long, value_name = "DIR", conflicts_with = "record")]
+        replay: Option<PathBuf>,
+    },
+    /// Run every agent declared in `[agents.<name>]` sections in this process
+    Host,
     /// Validate configuration per RFC Section 9
     Config {
         /// Show current configuration
         #[arg(long)]
         show: bool,
+        /// Format for --show: toml, yaml or json
+        #[arg(long, default_value = "toml", requires = "show")]
+        format: ConfigFormat,
+        /// Report every problem and warning; exit non-zero only on problems
+        #[arg(long)]
+        check: bool,
+        /// Print the JSON Schema of the configuration file; needs no configuration
+        #[arg(long, conflicts_with_all = ["show", "check"])]
+        schema: bool,
+    },
+    /// Inspect and run configured tools without an LLM
+    Tools {
+        #[command(subcommand)]
+        command: ToolsCommand,
+    },
+    /// Show every agent's retained status and capabilities from the broker
+    Status {
+        /// Print the agents as a JSON array (one object per update with --watch)
+        #[arg(long)]
+        json: bool,
+        /// Keep printing updates after the initial table until interrupted
+        #[arg(long)]
+        watch: bool,
+        /// Seconds to collect retained messages before printing
+        #[arg(long, value_name = "SECS", default_value_t = DEFAULT_COLLECT_SECS)]
+        wait: u64,
+    },
+    /// Check a task envelope file against the protocol schema and the pure processing checks
+    ///
+    /// Exits with 2 when the envelope has violations; needs no configuration.
+    ValidateEnvelope {
+        /// Envelope JSON file
+        file: PathBuf,
+        /// Topic the envelope would be received on, checked against its `topic`
+        #[arg(long)]
+        topic: Option<String>,
+    },
+    /// Check the configuration, broker, LLM provider and tools, with a hint for each problem
+    ///
+    /// Exits non-zero when any check fails.
+    Doctor {
+        /// Seconds each check may take before it fails
+        #[arg(long, value_name = "SECS", default_value_t = doctor::DEFAULT_CHECK_TIMEOUT_SECS)]
+        timeout: u64,
+    },
+    /// Publish recorded envelopes from a directory in their original order
+    Replay {
+        /// Directory of envelope JSON files
+        dir: PathBuf,
+        /// Replay this many times faster than recorded
+        #[arg(long, default_value_t = 1.0)]
+        speed: f64,
+    },
+}
+
+#[derive(Subcommand)]
+enum ToolsCommand {
+    /// List configured tools with their descriptions
+    List,
+    /// Print a tool's description and parameter schema
+    Describe {
+        /// Configured tool name
+        name: String,
+    },
+    /// Initialize one tool, validate the parameters and execute it
+    ///
+    /// Exits with 2 when the parameters fail validation and 3 when the tool
+    /// fails to initialize or execute.
+    Exec {
+        /// Configured tool name
+        name: String,
+        /// Parameters as JSON, or @FILE to read them from a file
+        #[arg(long, value_name = "JSON|@FILE")]
+        params: String,
     },
 }
 
@@ -49,28 +144,98 @@ enum Commands {
 async fn main() {
     let cli = Cli::parse();
 
+    // Printed before logging starts so the output is only the schema
+    if let Commands::Config { schema: true, .. } = cli.command {
+        match serde_json::to_string_pretty(&AgentConfig::json_schema()) {
+            Ok(schema) => println!("{schema}"),
+            Err(e) => {
+                eprintln!("Failed to render configuration schema: {e}");
+                process::exit(1);
+            }
+        }
+        return;
+    }
+
+    // Tool output is JSON for scripts, so these also run before logging starts
+    if let Commands::Tools { command } = &cli.command {
+        process::exit(run_tools_command(&cli, command).await);
+    }
+    if let Commands::Status { json, watch, wait } = cli.command {
+        process::exit(run_status_command(&cli, json, watch, Duration::from_secs(wait)).await);
+    }
+    if let Commands::ValidateEnvelope { file, topic } = &cli.command {
+        process::exit(run_validate_envelope(file, topic.as_deref()));
+    }
+    if let Commands::Replay { dir, speed } = &cli.command {
+        process::exit(run_replay_command(&cli, dir, *speed).await);
+    }
+    if let Commands::Doctor { timeout } = cli.command {
+        process::exit(run_doctor(&cli, Duration::from_secs(timeout)).await);
+    }
+
     // Initialize observability system
-    init_default_logging();
+    let logged_agent = match cli.command {
+        Commands::Run { .. } => peek_configuration(&cli.config, cli.profile.as_deref()),
+        Commands::Host
+        | Commands::Config { .. }
+        | Commands::Tools { .. }
+        | Commands::Status { .. }
+        | Commands::ValidateEnvelope { .. }
+        | Commands::Replay { .. }
+        | Commands::Doctor { .. } => None,
+    };
+    init_default_logging(logged_agent.as_ref());
 
     info!(
         "Starting RFC-compliant 2389 Agent Protocol v{}",
         env!("CARGO_PKG_VERSION")
     );
 
-    // Load configuration
-    let config = match load_configuration(&cli.config).await {
-        Ok(config) => config,
-        Err(e) => {
-            error!("Failed to load configuration: {}", e);
-            process::exit(1);
+    // A host config declares several agents, so the host command loads its own
+    let config_path = find_configuration(&cli.config);
+    let profile = cli.profile.as_deref();
+    let result = match cli.command {
+        Commands::Run { record, replay } => {
+            let mut config = load_configuration_or_exit(&config_path, profile).await;
+            // The flags replace the recording directories; deterministic mode stays
+            let testing = (record.is_some() || replay.is_some()).then(|| TestingSection {
+                record_dir: record,
+                replay_dir: replay,
+                ..config.testing.clone()
+            });
+            if let Some(testing) = &testing {
+                config.testing = testing.clone();
+            }
+            run_agent(config, config_path, cli.profile.clone(), testing).await
+        }
+        Commands::Host => {
+            if profile.is_some() {
+                warn!("--profile applies to single-agent configurations; the host ignores it");
+            }
+            run_host(&config_path).await
+        }
+        Commands::Config { check: true, .. } => check_configuration(&config_path, profile),
+        Commands::Tools { .. }
+        | Commands::Status { .. }
+        | Commands::ValidateEnvelope { .. }
+        | Commands::Replay { .. }
+        | Commands::Doctor { .. } => {
+            unreachable!("command-line utilities return before logging starts")
+        }
+        Commands::Config { show, format, .. } => {
+            handle_config_command(
+                load_configuration_or_exit(&config_path, profile).await,
+                show,
+                format,
+            )
+            .await
         }
     };
 
-    // Execute command
-    let result = match cli.command {
-        Commands::Run => run_agent(config).await,
-        Commands::Config { show } => handle_config_command(config, show).await,
-    };
+    if logged_agent.is_some_and(|config| config.observability.otel.enabled) {
+        // Flushing blocks on the batch exporter
+        let _ = tokio::task::spawn_blocking(shutdown_trace_export).await;
+    }
 
     if let Err(e) = result {
         error!("Command failed: {}", e);
@@ -80,40 +245,134 @@ async fn main() {
     info!("Application shutdown complete");
 }
 
+/// The agent configuration, read for its `[observability]` settings before
+/// logging starts
+///
+/// Problems with the file are ignored here; they are reported when the
+/// configuration is loaded for the command.
+fn peek_configuration(config_path: &Option<PathBuf>, profile: Option<&str>) -> Option<AgentConfig> {
+    let path = config_path.clone().or_else(default_configuration)?;
+    AgentConfig::load_with_warnings(&path, profile)
+        .ok()
+        .map(|(config, _)| config)
+}
+
+/// Resolve the configuration file from the CLI or the default locations
+fn find_configuration(config_path: &Option<PathBuf>) -> PathBuf {
+    if let Some(path) = config_path.clone().or_else(default_configuration) {
+        return path;
+    }
+
+    error!("No configuration file found. Please provide one with -c/--config or create agent.toml, agent.yaml or agent.json");
+    process::exit(1);
+}
+
+/// The first default configuration location that exists
+fn default_configuration() -> Option<PathBuf> {
+    [
+        "agent.toml",
+        "agent.yaml",
+        "agent.yml",
+        "agent.json",
+        "config/agent.toml",
+        "agent-rfc.toml",
+    ]
+    .into_iter()
+    .map(PathBuf::from)
+    .find(|path| path.exists())
+}
+
 async fn load_configuration(
-    config_path: &Option<PathBuf>,
+    config_path: &Path,
+    profile: Option<&str>,
 ) -> Result<AgentConfig, Box<dyn std::error::Error>> {
-    match config_path {
-        Some(path) => {
-            info!("Loading configuration from: {}", path.display());
-            Ok(AgentConfig::load_from_file(path)?)
+    match profile {
+        Some(profile) => info!(
+            "Loading configuration from: {} (profile {})",
+            config_path.display(),
+            profile
+        ),
+        None => info!("Loading configuration from: {}", config_path.display()),
+    }
+    let (config, warnings) = AgentConfig::load_with_warnings(config_path, profile)?;
+    for warning in warnings {
+        warn!("Configuration warning: {}", warning);
+    }
+    Ok(config)
+}
+
+async fn load_configuration_or_exit(config_path: &Path, profile: Option<&str>) -> AgentConfig {
+    match load_configuration(config_path, profile).await {
+        Ok(config) => config,
+        Err(e) => {
+            error!("Failed to load configuration: {}", e);
+            process::exit(1);
         }
-        None => {
-            // Try default locations
-            let default_paths = vec!["agent.toml", "config/agent.toml", "agent-rfc.toml"];
-
-            for path_str in default_paths {
-                let path = PathBuf::from(path_str);
-                if path.exists() {
-                    info!("Loading configuration from: {}", path.display());
-                    return Ok(AgentConfig::load_from_file(&path)?);
-                }
+    }
+}
+
+/// Re-read the configuration file and apply it to the running agent
+///
+/// An invalid file, or tools that fail to initialize, leave the running
+/// configuration in place. `testing` replaces the file's `[testing]` section
+/// when given on the command line.
+async fn reload_configuration<T>(
+    agent: &agent2389::agent::AgentLifecycle<T>,
+    config_path: &Path,
+    profile: Option<&str>,
+    testing: Option<&TestingSection>,
+) where
+    T: agent2389::transport::Transport,
+{
+    let candidate = match load_configuration(config_path, profile).await {
+        Ok(mut candidate) => {
+            if let Some(testing) = testing {
+                candidate.testing = testing.clone();
             }
+            candidate
+        }
+        Err(e) => {
+            error!(
+                "Configuration reload failed, keeping running configuration: {}",
+                e
+            );
+            return;
+        }
+    };
 
+    match agent.reload_config(candidate).await {
+        Ok(reload) if reload.applied.is_empty() => {
+            info!("Configuration reloaded with no applicable changes");
+        }
+        Ok(reload) => {
+            info!(fields = ?reload.applied, "Configuration reload applied");
+        }
+        Err(e) => {
             error!(
-                "No configuration file found. Please provide one with -c/--config or create agent.toml"
+                "Configuration reload failed, keeping running configuration: {}",
+                e
             );
-            process::exit(1);
         }
     }
 }
 
-async fn run_agent(config: AgentConfig) -> Result<(), Box<dyn std::error::Error>> {
+async fn run_agent(
+    config: AgentConfig,
+    config_path: PathBuf,
+    profile: Option<String>,
+    testing: Option<TestingSection>,
+) -> Result<(), Box<dyn std::error::Error>> {
     info!("Application starting with agent ID: {}", config.agent.id);
 
     // Initialize metrics
     let collector = metrics();
     collector.set_agent_state("initializing");
+    event_log().set_capacity(
+        config
+            .observability
+            .event_log_capacity
+            .unwrap_or(DEFAULT_EVENT_LOG_CAPACITY),
+    );
 
     // Bootstrap: Build agent with injected dependencies (Zen pattern)
     let mut agent = build_agent(config.clone()).await?;
@@ -144,26 +403,33 @@ async fn run_agent(config: AgentConfig) -> Result<(), Box<dyn std::error::Error>
     agent.start().await?;
     collector.set_agent_state("running");
 
-    // Update health server with MQTT status
-    health_server.set_mqtt_connected(true).await;
-
     // Set up signal handling for graceful shutdown per RFC Section 7.2
     let mut sigint = signal::unix::signal(signal::unix::SignalKind::interrupt())?;
     let mut sigterm = signal::unix::signal(signal::unix::SignalKind::terminate())?;
+    let mut sighup = signal::unix::signal(signal::unix::SignalKind::hangup())?;
 
     info!("Agent is running and waiting for tasks on MQTT...");
 
-    // Wait for shutdown signals or permanent disconnection
-    tokio::select! {
-        _ = sigint.recv() => {
-            info!("Received SIGINT, shutting down gracefully...");
-        }
-        _ = sigterm.recv() => {
-            info!("Received SIGTERM, shutting down gracefully...");
-        }
-        _ = monitor_connection_health(&agent) => {
-            error!("MQTT connection permanently lost, shutting down agent...");
-            health_server.set_mqtt_connected(false).await;
+    // Wait for shutdown signals or permanent disconnection; SIGHUP reloads the config
+    loop {
+        tokio::select! {
+            _ = sighup.recv() => {
+                info!("Received SIGHUP, reloading configuration...");
+                reload_configuration(&agent, &config_path, profile.as_deref(), testing.as_ref())
+                    .await;
+            }
+            _ = sigint.recv() => {
+                info!("Received SIGINT, shutting down gracefully...");
+                break;
+            }
+            _ = sigterm.recv() => {
+                info!("Received SIGTERM, shutting down gracefully...");
+                break;
+            }
+            _ = agent.wait_for_permanent_disconnect() => {
+                error!("MQTT connection permanently lost, shutting down agent...");
+                break;
+            }
         }
     }
 
@@ -180,6 +446,59 @@ async fn run_agent(config: AgentConfig) -> Result<(), Box<dyn std::error::Error>
     Ok(())
 }
 
+/// Run every agent of a multi-agent configuration until SIGINT or SIGTERM
+///
+/// An agent that can't be built or started is logged and skipped; the host
+/// fails only when no agent runs.
+async fn run_host(config_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    info!("Loading host configuration from: {}", config_path.display());
+    let host_config = HostConfig::load_from_file(config_path)?;
+
+    let collector = metrics();
+    collector.set_agent_state("initializing");
+
+    let health_port = std::env::var("HEALTH_PORT")
+        .ok()
+        .and_then(|p| p.parse().ok())
+        .unwrap_or(8080);
+    let health_server = Arc::new(HealthServer::new("agent-host".to_string(), health_port));
+    let health_server_clone = health_server.clone();
+    tokio::spawn(async move {
+        if let Err(e) = health_server_clone.start().await {
+            error!("Health server error: {}", e);
+        }
+    });
+
+    let mut host = AgentHost::new(health_server);
+    for (name, config) in host_config.agents {
+        if config.observability != Default::default() {
+            warn!(
+                agent = %name,
+                "[observability] is ignored in host mode; logging follows the LOG_* environment variables"
+            );
+        }
+        match build_agent(config).await {
+            Ok(agent) => host.add_agent(agent),
+            Err(e) => error!(agent = %name, "Failed to build hosted agent: {}", e),
+        }
+    }
+
+    let mut sigint = signal::unix::signal(signal::unix::SignalKind::interrupt())?;
+    let mut sigterm = signal::unix::signal(signal::unix::SignalKind::terminate())?;
+    let shutdown = async {
+        tokio::select! {
+            _ = sigint.recv() => info!("Received SIGINT, shutting down all agents..."),
+            _ = sigterm.recv() => info!("Received SIGTERM, shutting down all agents..."),
+        }
+        collector.set_agent_state("stopping");
+    };
+
+    collector.set_agent_state("running");
+    host.run_until(shutdown).await?;
+    collector.set_agent_state("stopped");
+    Ok(())
+}
+
 /// Provider factory for creating LLM providers from configuration
 struct LlmProviderFactory;
 
@@ -193,7 +512,9 @@ impl LlmProviderFactory {
 
         match config.llm.provider.as_str() {
             "openai" => {
-                let api_key = config.get_llm_api_key()?;
+                // OpenAI caches long prompt prefixes by itself, so
+                // `llm.prompt_caching` has nothing to mark there
+                let api_key = config.get_llm_api_key()?.expose().to_string();
                 let openai_config = OpenAiConfig {
                     api_key,
                     ..Default::default()
@@ -202,9 +523,10 @@ impl LlmProviderFactory {
                 Ok(Box::new(provider))
             }
             "anthropic" => {
-                let api_key = config.get_llm_api_key()?;
+                let api_key = config.get_llm_api_key()?.expose().to_string();
                 let anthropic_config = AnthropicConfig {
                     api_key,
+                    prompt_caching: config.llm.prompt_caching,
                     ..Default::default()
                 };
                 let provider = AnthropicProvider::new(anthropic_config)?;
@@ -235,12 +557,44 @@ async fn build_agent(
     agent2389::agent::AgentLifecycle<agent2389::transport::mqtt::MqttClient>,
     Box<dyn std::error::Error>,
 > {
+    // Resolve every credential once, failing fast on the first unavailable one
+    config
+        .resolve_secrets(&agent2389::secrets::SecretResolver::from_environment()?)
+        .await?;
+
+    // Redact log lines now that the secret values are known
+    agent2389::observability::install_log_redactor(
+        &agent2389::observability::Redactor::from_config(&config)?,
+    );
+
     // Create transport (injected dependency) - now using factory
-    let transport =
+    let mut transport =
         TransportFactory::create_mqtt_transport(&config.agent.id, config.mqtt.clone()).await?;
 
-    // Create LLM provider (injected dependency) - now using factory
-    let llm_provider = LlmProviderFactory::create_provider(&config)?;
+    // Enable message signing when [security] is configured
+    if let Some(signer) = config.get_message_signer()? {
+        transport.set_message_signer(signer);
+    }
+
+    // Enable payload encryption when [security.encryption] is enabled
+    if let Some(encryptor) = config.get_payload_encryptor()? {
+        transport.set_payload_encryptor(encryptor);
+    }
+
+    // Stamp task publish times from a stepping clock in [testing] deterministic mode
+    transport.set_clock(agent2389::clock::clock_for(&config.testing));
+
+    // Create LLM provider (injected dependency) - now using factory, unless
+    // [testing] replays a recording or records the provider's answers
+    let llm_provider: Box<dyn agent2389::llm::provider::LlmProvider> =
+        match (&config.testing.replay_dir, &config.testing.record_dir) {
+            (Some(dir), _) => Box::new(ReplayLlmProvider::new(Arc::new(Recording::load(dir)?))),
+            (None, Some(dir)) => Box::new(RecordingLlmProvider::new(
+                LlmProviderFactory::create_provider(&config)?.into(),
+                Recorder::create(dir)?,
+            )),
+            (None, None) => LlmProviderFactory::create_provider(&config)?,
+        };
 
     // Inject dependencies into AgentLifecycle (no factory logic in business logic)
     Ok(agent2389::agent::AgentLifecycle::new(
@@ -250,28 +604,342 @@ async fn build_agent(
     ))
 }
 
+/// Print every problem and warning in the configuration file
+///
+/// Exits with status 1 when the file can't be parsed or has problems;
+/// warnings alone leave the status at 0.
+fn check_configuration(
+    config_path: &Path,
+    profile: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (config, mut warnings) = AgentConfig::read_with_warnings(config_path, profile)?;
+    warnings.extend(config.warnings());
+    let errors = config.validate().err().unwrap_or_default();
+
+    for error in &errors {
+        println!("error: {error}");
+    }
+    for warning in &warnings {
+        println!("warning: {warning}");
+    }
+    println!(
+        "{}: {} error(s), {} warning(s)",
+        config_path.display(),
+        errors.len(),
+        warnings.len()
+    );
+
+    if !errors.is_empty() {
+        process::exit(1);
+    }
+    Ok(())
+}
+
 async fn handle_config_command(
     config: AgentConfig,
     show: bool,
+    format: ConfigFormat,
 ) -> Result<(), Box<dyn std::error::Error>> {
     if show {
         println!("Current RFC-compliant configuration:");
-        println!("{}", toml::to_string_pretty(&config)?);
+        println!("{}", format.render(&config)?);
     }
 
     info!("Configuration validation complete");
     Ok(())
 }
 
-/// Monitor MQTT connection health and signal when permanently disconnected
-async fn monitor_connection_health<T>(agent: &agent2389::agent::AgentLifecycle<T>)
-where
-    T: agent2389::transport::Transport,
-{
-    loop {
-        if agent.is_permanently_disconnected() {
-            break;
+/// Load the configuration for a command that runs before logging starts
+///
+/// Problems are printed to stderr; `None` means the command should exit.
+fn load_command_configuration(cli: &Cli) -> Option<AgentConfig> {
+    let Some(config_path) = cli.config.clone().or_else(default_configuration) else {
+        eprintln!("No configuration file found. Please provide one with -c/--config");
+        return None;
+    };
+    match AgentConfig::load_with_warnings(&config_path, cli.profile.as_deref()) {
+        Ok((config, _)) => Some(config),
+        Err(e) => {
+            eprintln!("Failed to load configuration: {e}");
+            None
+        }
+    }
+}
+
+/// Print the fleet's status from retained broker messages, returning the exit status
+///
+/// Connects with the configuration's broker URL and credentials. The client
+/// disconnects cleanly on Ctrl-C, so its last will never reaches the broker.
+async fn run_status_command(cli: &Cli, json: bool, watch: bool, wait: Duration) -> i32 {
+    let Some(config) = load_command_configuration(cli) else {
+        return 1;
+    };
+
+    let observer_id = format!("{}-status", config.agent.id);
+    let mut client = match MqttClient::new(&observer_id, config.mqtt.clone()).await {
+        Ok(client) => client,
+        Err(e) => {
+            eprintln!("Invalid broker configuration: {e}");
+            return 1;
+        }
+    };
+    if let Err(e) = client.connect().await {
+        eprintln!("Failed to connect to {}: {e}", config.mqtt.broker_url);
+        return 1;
+    }
+    let mut subscription = match FleetSubscription::subscribe(&client).await {
+        Ok(subscription) => subscription,
+        Err(e) => {
+            eprintln!("Failed to subscribe to agent status topics: {e}");
+            let _ = client.disconnect().await;
+            return 1;
+        }
+    };
+
+    let mut fleet = FleetStatus::new();
+    subscription.collect(&mut fleet, wait).await;
+    if json {
+        println!("{}", fleet.render_json());
+    } else if fleet.is_empty() {
+        println!("No agents found on {}", config.mqtt.broker_url);
+    } else {
+        print!("{}", fleet.render_table());
+    }
+
+    if watch {
+        loop {
+            let message = tokio::select! {
+                message = subscription.next() => message,
+                _ = signal::ctrl_c() => None,
+            };
+            let Some((topic, payload)) = message else {
+                break;
+            };
+            if let Some(entry) = fleet.apply(&topic, &payload) {
+                if json {
+                    println!("{}", serde_json::to_string(entry).unwrap_or_default());
+                } else {
+                    println!("{}", entry.render_row(entry.agent_id.len()));
+                }
+            }
+        }
+    }
+
+    let _ = client.disconnect().await;
+    0
+}
+
+/// Run every doctor check in order, printing each result as it completes
+///
+/// Returns the exit status: non-zero when any check failed.
+async fn run_doctor(cli: &Cli, timeout: Duration) -> i32 {
+    let mut results: Vec<CheckResult> = Vec::new();
+    let mut report = |result: CheckResult| {
+        println!("{result}");
+        results.push(result);
+    };
+
+    let Some(config_path) = cli.config.clone().or_else(default_configuration) else {
+        report(CheckResult::fail(
+            "configuration",
+            "No configuration file found",
+            "Pass one with -c/--config or create agent.toml",
+        ));
+        return 1;
+    };
+    let (config_result, config) = doctor::check_config(&config_path, cli.profile.as_deref());
+    report(config_result);
+    let Some(config) = config else {
+        return 1;
+    };
+
+    let reachable = doctor::check_broker_reachable(&config.mqtt.broker_url, timeout).await;
+    let broker_up = reachable.status == CheckStatus::Pass;
+    report(reachable);
+    if broker_up {
+        let doctor_id = format!("{}-doctor", config.agent.id);
+        report(
+            match MqttClient::new(&doctor_id, config.mqtt.clone()).await {
+                Ok(mut client) => {
+                    let topic = doctor::scratch_topic(&config.agent.id);
+                    doctor::check_mqtt_round_trip(&mut client, &topic, timeout).await
+                }
+                Err(e) => CheckResult::fail(
+                    "mqtt round trip",
+                    e.to_string(),
+                    "Fix mqtt.broker_url in the configuration",
+                ),
+            },
+        );
+    } else {
+        report(CheckResult::warn(
+            "mqtt round trip",
+            "Skipped",
+            "Fix broker reachability first",
+        ));
+    }
+
+    let provider = LlmProviderFactory::create_provider(&config).map_err(|e| e.to_string());
+    report(
+        doctor::check_llm(
+            provider
+                .as_ref()
+                .map(|provider| provider.as_ref())
+                .map_err(Clone::clone),
+            &config.llm.api_key_env,
+            timeout,
+        )
+        .await,
+    );
+
+    for result in doctor::check_tools(&config.tools, timeout).await {
+        report(result);
+    }
+
+    doctor::exit_code(&results)
+}
+
+/// Print every violation in an envelope file, returning the exit status
+fn run_validate_envelope(file: &Path, topic: Option<&str>) -> i32 {
+    let report = match envelope_cli::validate_file(file, topic) {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("error: {e}");
+            return envelope_cli::EXIT_UNREADABLE;
+        }
+    };
+    if report.is_valid() {
+        println!(
+            "{}: valid envelope (version {})",
+            file.display(),
+            report.version
+        );
+        return 0;
+    }
+    println!(
+        "{}: envelope (version {}) with {} violation(s)",
+        file.display(),
+        report.version,
+        report.violations.len()
+    );
+    for violation in &report.violations {
+        let path = if violation.path.is_empty() {
+            "/"
+        } else {
+            &violation.path
+        };
+        println!("  {path}: {}", violation.message);
+    }
+    envelope_cli::EXIT_INVALID
+}
+
+/// Publish a recorded conversation to the configured broker, returning the exit status
+///
+/// Tasks are signed and encrypted when the configuration enables it, like the
+/// agent's own.
+async fn run_replay_command(cli: &Cli, dir: &Path, speed: f64) -> i32 {
+    let recording = match replay_cli::load_recording(dir) {
+        Ok(recording) => recording,
+        Err(e) => {
+            eprintln!("error: {e}");
+            return 1;
+        }
+    };
+    let Some(config) = load_command_configuration(cli) else {
+        return 1;
+    };
+
+    let replayer_id = format!("{}-replay", config.agent.id);
+    let mut client = match MqttClient::new(&replayer_id, config.mqtt.clone()).await {
+        Ok(client) => client,
+        Err(e) => {
+            eprintln!("Invalid broker configuration: {e}");
+            return 1;
+        }
+    };
+    match (config.get_message_signer(), config.get_payload_encryptor()) {
+        (Ok(signer), Ok(encryptor)) => {
+            if let Some(signer) = signer {
+                client.set_message_signer(signer);
+            }
+            if let Some(encryptor) = encryptor {
+                client.set_payload_encryptor(encryptor);
+            }
+        }
+        (Err(e), _) | (_, Err(e)) => {
+            eprintln!("Invalid security configuration: {e}");
+            return 1;
+        }
+    }
+    if let Err(e) = client.connect().await {
+        eprintln!("Failed to connect to {}: {e}", config.mqtt.broker_url);
+        return 1;
+    }
+
+    let result = replay_cli::replay(&client, &recording, speed, |recorded| {
+        println!(
+            "{} -> {}",
+            recorded.path.display(),
+            recorded.envelope.topic()
+        );
+    })
+    .await;
+    let _ = client.disconnect().await;
+    match result {
+        Ok(published) => {
+            println!("Replayed {published} envelope(s)");
+            0
+        }
+        Err(e) => {
+            eprintln!("error: {e}");
+            1
+        }
+    }
+}
+
+/// Run a `tools` subcommand, returning the process exit status
+async fn run_tools_command(cli: &Cli, command: &ToolsCommand) -> i32 {
+    let Some(config) = load_command_configuration(cli) else {
+        return tools_cli::EXIT_USAGE;
+    };
+
+    let printed = match command {
+        ToolsCommand::List => tools_cli::list(&config).map(|tools| {
+            for (name, description) in tools {
+                let summary = description.description.lines().next().unwrap_or_default();
+                println!("{name:<20} {summary}");
+            }
+        }),
+        ToolsCommand::Describe { name } => tools_cli::describe(&config, name).map(|description| {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&description).unwrap_or_default()
+            );
+        }),
+        ToolsCommand::Exec { name, params } => {
+            let parameters = match tools_cli::parse_parameters(params) {
+                Ok(parameters) => parameters,
+                Err(e) => {
+                    eprintln!("error: {e}");
+                    return tools_cli::EXIT_USAGE;
+                }
+            };
+            tools_cli::exec(&config, name, &parameters)
+                .await
+                .map(|result| {
+                    println!(
+                        "{}",
+                        serde_json::to_string_pretty(&result).unwrap_or_default()
+                    );
+                })
+        }
+    };
+
+    match printed {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("error: {e}");
+            tools_cli::exit_code(&e)
         }
-        sleep(Duration::from_millis(100)).await;
     }
 }
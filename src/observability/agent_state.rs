@@ -0,0 +1,344 @@
+//! Runtime state behind the admin endpoints
+//!
+//! The pipeline registers every task it starts and records how it ended,
+//! and the processor records which step each task is in. The health server
+//! reads the registry to serve `/tasks/active` and `/tasks/recent`, along
+//! with the tools and (redacted) configuration the agent is running with,
+//! served on `/tools` and `/config`.
+
+use crate::config::AgentConfig;
+use crate::tools::{DegradedTool, ToolDescription, ToolSystem};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// Finished tasks kept for `/tasks/recent`
+pub const DEFAULT_RECENT_TASKS: usize = 100;
+
+/// How a task ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskOutcome {
+    Completed,
+    Failed,
+    /// Turned away before processing, e.g. stale or too deep in a pipeline
+    Rejected,
+    Cancelled,
+}
+
+/// A task being processed, as served on `/tasks/active`
+#[derive(Debug, Clone, Serialize)]
+pub struct ActiveTask {
+    pub task_id: Uuid,
+    pub conversation_id: String,
+    pub started_at: DateTime<Utc>,
+    pub elapsed_ms: u64,
+    /// Step the task is in, if the processor reported one yet
+    pub step: Option<u8>,
+    pub step_description: Option<String>,
+}
+
+/// Time spent in one processing step
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StepTiming {
+    pub step: u8,
+    pub description: String,
+    pub duration_ms: u64,
+}
+
+/// A finished task, as served on `/tasks/recent`
+#[derive(Debug, Clone, Serialize)]
+pub struct RecentTask {
+    pub task_id: Uuid,
+    pub conversation_id: String,
+    pub outcome: TaskOutcome,
+    pub error: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub duration_ms: u64,
+    /// Steps in the order the task went through them
+    pub steps: Vec<StepTiming>,
+}
+
+struct CurrentStep {
+    step: u8,
+    description: String,
+    entered: Instant,
+}
+
+struct ActiveEntry {
+    task_id: Uuid,
+    conversation_id: String,
+    started_at: DateTime<Utc>,
+    started: Instant,
+    current: Option<CurrentStep>,
+    steps: Vec<StepTiming>,
+}
+
+impl ActiveEntry {
+    /// Close the current step, if any, at `now`
+    fn close_step(&mut self, now: Instant) {
+        if let Some(current) = self.current.take() {
+            self.steps.push(StepTiming {
+                step: current.step,
+                description: current.description,
+                duration_ms: millis(now - current.entered),
+            });
+        }
+    }
+}
+
+/// Shared record of in-flight and recently finished tasks
+pub struct AgentStateRegistry {
+    /// Keyed by start order, so a redelivered task id never clobbers the
+    /// attempt already in flight
+    active: Mutex<BTreeMap<u64, ActiveEntry>>,
+    next_entry: AtomicU64,
+    recent: Mutex<VecDeque<RecentTask>>,
+    recent_capacity: usize,
+    tools: RwLock<Vec<ToolDescription>>,
+    degraded_tools: RwLock<Vec<DegradedTool>>,
+    config: RwLock<Option<serde_json::Value>>,
+}
+
+impl Default for AgentStateRegistry {
+    fn default() -> Self {
+        Self::new(DEFAULT_RECENT_TASKS)
+    }
+}
+
+impl AgentStateRegistry {
+    /// Create a registry keeping the last `recent_capacity` finished tasks
+    pub fn new(recent_capacity: usize) -> Self {
+        Self {
+            active: Mutex::new(BTreeMap::new()),
+            next_entry: AtomicU64::new(0),
+            recent: Mutex::new(VecDeque::with_capacity(recent_capacity)),
+            recent_capacity,
+            tools: RwLock::new(Vec::new()),
+            degraded_tools: RwLock::new(Vec::new()),
+            config: RwLock::new(None),
+        }
+    }
+
+    /// Register a task as in flight until the returned handle is finished
+    ///
+    /// Dropping the handle without finishing it, as when the task's future is
+    /// dropped at shutdown, removes the task without recording it as recent.
+    pub fn task_started(self: &Arc<Self>, task_id: Uuid, conversation_id: &str) -> TrackedTask {
+        let entry = self.next_entry.fetch_add(1, Ordering::Relaxed);
+        self.active.lock().unwrap().insert(
+            entry,
+            ActiveEntry {
+                task_id,
+                conversation_id: conversation_id.to_string(),
+                started_at: Utc::now(),
+                started: Instant::now(),
+                current: None,
+                steps: Vec::new(),
+            },
+        );
+        TrackedTask {
+            registry: self.clone(),
+            entry: Some(entry),
+        }
+    }
+
+    /// Record that an in-flight task entered `step`, closing its previous step
+    pub fn task_step(&self, task_id: Uuid, step: u8, description: &str) {
+        let now = Instant::now();
+        let mut active = self.active.lock().unwrap();
+        for entry in active.values_mut().filter(|e| e.task_id == task_id) {
+            entry.close_step(now);
+            entry.current = Some(CurrentStep {
+                step,
+                description: description.to_string(),
+                entered: now,
+            });
+        }
+    }
+
+    fn finish(&self, entry: u64, outcome: TaskOutcome, error: Option<String>) {
+        let Some(mut entry) = self.active.lock().unwrap().remove(&entry) else {
+            return;
+        };
+        let now = Instant::now();
+        entry.close_step(now);
+        let finished = RecentTask {
+            task_id: entry.task_id,
+            conversation_id: entry.conversation_id,
+            outcome,
+            error,
+            started_at: entry.started_at,
+            finished_at: Utc::now(),
+            duration_ms: millis(now - entry.started),
+            steps: entry.steps,
+        };
+
+        if self.recent_capacity == 0 {
+            return;
+        }
+        let mut recent = self.recent.lock().unwrap();
+        if recent.len() == self.recent_capacity {
+            recent.pop_front();
+        }
+        recent.push_back(finished);
+    }
+
+    /// Tasks in flight, oldest first
+    pub fn active_tasks(&self) -> Vec<ActiveTask> {
+        let now = Instant::now();
+        self.active
+            .lock()
+            .unwrap()
+            .values()
+            .map(|entry| ActiveTask {
+                task_id: entry.task_id,
+                conversation_id: entry.conversation_id.clone(),
+                started_at: entry.started_at,
+                elapsed_ms: millis(now - entry.started),
+                step: entry.current.as_ref().map(|current| current.step),
+                step_description: entry
+                    .current
+                    .as_ref()
+                    .map(|current| current.description.clone()),
+            })
+            .collect()
+    }
+
+    /// Recently finished tasks, newest first
+    pub fn recent_tasks(&self) -> Vec<RecentTask> {
+        self.recent.lock().unwrap().iter().rev().cloned().collect()
+    }
+
+    /// Record the tools the agent currently offers, and the optional ones it skipped
+    pub fn set_tools(&self, tool_system: &ToolSystem) {
+        *self.tools.write().unwrap() = tool_system.describe_tools();
+        *self.degraded_tools.write().unwrap() = tool_system.degraded_tools().to_vec();
+    }
+
+    /// Tools the agent currently offers, by name
+    pub fn tools(&self) -> Vec<ToolDescription> {
+        self.tools.read().unwrap().clone()
+    }
+
+    /// Optional tools skipped because they failed to initialize, by name
+    pub fn degraded_tools(&self) -> Vec<DegradedTool> {
+        self.degraded_tools.read().unwrap().clone()
+    }
+
+    /// Record the effective configuration, with secrets redacted
+    pub fn set_config(&self, config: &AgentConfig) {
+        *self.config.write().unwrap() = Some(config.redacted());
+    }
+
+    /// Effective configuration with secrets redacted, once one was recorded
+    pub fn config(&self) -> Option<serde_json::Value> {
+        self.config.read().unwrap().clone()
+    }
+}
+
+/// Handle for a task registered with [`AgentStateRegistry::task_started`]
+pub struct TrackedTask {
+    registry: Arc<AgentStateRegistry>,
+    entry: Option<u64>,
+}
+
+impl TrackedTask {
+    /// Move the task to the recent tasks with how it ended
+    pub fn finish(mut self, outcome: TaskOutcome, error: Option<String>) {
+        if let Some(entry) = self.entry.take() {
+            self.registry.finish(entry, outcome, error);
+        }
+    }
+}
+
+impl Drop for TrackedTask {
+    fn drop(&mut self) {
+        if let Some(entry) = self.entry.take() {
+            self.registry.active.lock().unwrap().remove(&entry);
+        }
+    }
+}
+
+fn millis(duration: Duration) -> u64 {
+    duration.as_millis().try_into().unwrap_or(u64::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_task_moves_from_active_to_recent_with_step_timings() {
+        let registry = Arc::new(AgentStateRegistry::new(10));
+        let task_id = Uuid::new_v4();
+
+        let tracked = registry.task_started(task_id, "conv-1");
+        assert_eq!(registry.active_tasks()[0].step, None);
+
+        registry.task_step(task_id, 1, "Validating task");
+        registry.task_step(task_id, 7, "Processing with LLM");
+        let active = registry.active_tasks();
+        assert_eq!(active.len(), 1);
+        assert_eq!(active[0].step, Some(7));
+        assert_eq!(
+            active[0].step_description.as_deref(),
+            Some("Processing with LLM")
+        );
+
+        tracked.finish(TaskOutcome::Completed, None);
+        assert!(registry.active_tasks().is_empty());
+        let recent = registry.recent_tasks();
+        assert_eq!(recent.len(), 1);
+        assert_eq!(recent[0].outcome, TaskOutcome::Completed);
+        assert_eq!(recent[0].conversation_id, "conv-1");
+        let steps: Vec<u8> = recent[0].steps.iter().map(|s| s.step).collect();
+        assert_eq!(steps, vec![1, 7]);
+    }
+
+    #[test]
+    fn test_recent_tasks_keep_only_the_newest() {
+        let registry = Arc::new(AgentStateRegistry::new(2));
+        let ids: Vec<Uuid> = (0..3).map(|_| Uuid::new_v4()).collect();
+
+        for id in &ids {
+            registry
+                .task_started(*id, "conv-1")
+                .finish(TaskOutcome::Failed, Some("boom".to_string()));
+        }
+
+        let recent: Vec<Uuid> = registry.recent_tasks().iter().map(|t| t.task_id).collect();
+        assert_eq!(recent, vec![ids[2], ids[1]]);
+    }
+
+    #[test]
+    fn test_dropped_handle_is_not_recorded_as_recent() {
+        let registry = Arc::new(AgentStateRegistry::default());
+
+        drop(registry.task_started(Uuid::new_v4(), "conv-1"));
+
+        assert!(registry.active_tasks().is_empty());
+        assert!(registry.recent_tasks().is_empty());
+    }
+
+    #[test]
+    fn test_redelivered_task_keeps_both_attempts() {
+        let registry = Arc::new(AgentStateRegistry::default());
+        let task_id = Uuid::new_v4();
+
+        let first = registry.task_started(task_id, "conv-1");
+        let second = registry.task_started(task_id, "conv-1");
+        second.finish(TaskOutcome::Failed, Some("duplicate".to_string()));
+
+        assert_eq!(registry.active_tasks().len(), 1);
+        first.finish(TaskOutcome::Completed, None);
+        let outcomes: Vec<TaskOutcome> =
+            registry.recent_tasks().iter().map(|t| t.outcome).collect();
+        assert_eq!(outcomes, vec![TaskOutcome::Completed, TaskOutcome::Failed]);
+    }
+}
@@ -0,0 +1,315 @@
+//! Recent significant events, served on `/events`
+//!
+//! Connection changes, task failures, routing decisions and tool errors are
+//! recorded into a bounded ring buffer so an operator can see what happened
+//! a few minutes ago without access to the logs. Writers never wait on a
+//! lock: events go into a channel that readers drain into the buffer under
+//! a short lock, and writers only drain themselves (with `try_lock`) once a
+//! buffer's worth of events is pending.
+
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Mutex};
+
+/// Events kept when `observability.event_log_capacity` is not set
+pub const DEFAULT_EVENT_LOG_CAPACITY: usize = 1000;
+
+/// Process-wide event log
+pub static EVENT_LOG: Lazy<EventLog> = Lazy::new(|| EventLog::new(DEFAULT_EVENT_LOG_CAPACITY));
+
+/// Get the global event log
+pub fn event_log() -> &'static EventLog {
+    &EVENT_LOG
+}
+
+/// How significant an event is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventSeverity {
+    Info,
+    Warning,
+    Error,
+}
+
+/// What an event is about, for filtering `/events`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventCategory {
+    /// Broker connections, disconnections and reconnects
+    Connection,
+    /// Agent start, shutdown and pipeline exits
+    Lifecycle,
+    /// Tasks that failed, were rejected or were cancelled
+    Task,
+    /// Routing decisions and routing errors
+    Routing,
+    /// Tool execution errors
+    Tool,
+}
+
+/// One recorded event
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Event {
+    /// Position in the log, increasing in the order events were stored
+    pub sequence: u64,
+    pub timestamp: DateTime<Utc>,
+    pub severity: EventSeverity,
+    pub category: EventCategory,
+    pub message: String,
+    /// Structured details such as task or agent ids
+    pub fields: Map<String, Value>,
+}
+
+impl Event {
+    /// Create an event stamped with the current time
+    pub fn new(
+        severity: EventSeverity,
+        category: EventCategory,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            sequence: 0,
+            timestamp: Utc::now(),
+            severity,
+            category,
+            message: message.into(),
+            fields: Map::new(),
+        }
+    }
+
+    /// Add a structured field
+    pub fn with_field(mut self, name: &str, value: impl Into<Value>) -> Self {
+        self.fields.insert(name.to_string(), value.into());
+        self
+    }
+}
+
+/// Which events to return; every given criterion must match
+#[derive(Debug, Clone, Default)]
+pub struct EventFilter {
+    /// Only events at or after this time
+    pub since: Option<DateTime<Utc>>,
+    pub category: Option<EventCategory>,
+}
+
+impl EventFilter {
+    /// Whether `event` passes the filter (pure function)
+    pub fn matches(&self, event: &Event) -> bool {
+        self.since.map_or(true, |since| event.timestamp >= since)
+            && self
+                .category
+                .map_or(true, |category| event.category == category)
+    }
+}
+
+struct EventBuffer {
+    receiver: mpsc::Receiver<Event>,
+    events: VecDeque<Event>,
+    next_sequence: u64,
+}
+
+/// Bounded ring buffer of recent events
+pub struct EventLog {
+    sender: mpsc::Sender<Event>,
+    /// Events sent but not drained into the buffer yet
+    pending: AtomicUsize,
+    capacity: AtomicUsize,
+    buffer: Mutex<EventBuffer>,
+}
+
+impl EventLog {
+    /// Create a log keeping the latest `capacity` events
+    pub fn new(capacity: usize) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self {
+            sender,
+            pending: AtomicUsize::new(0),
+            capacity: AtomicUsize::new(capacity.max(1)),
+            buffer: Mutex::new(EventBuffer {
+                receiver,
+                events: VecDeque::new(),
+                next_sequence: 1,
+            }),
+        }
+    }
+
+    /// Change how many events are kept, dropping the oldest beyond it
+    pub fn set_capacity(&self, capacity: usize) {
+        self.capacity.store(capacity.max(1), Ordering::Relaxed);
+        let mut buffer = self.buffer.lock().unwrap();
+        self.drain(&mut buffer);
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity.load(Ordering::Relaxed)
+    }
+
+    /// Record an event without waiting on readers
+    pub fn record(&self, event: Event) {
+        // Counted before sending so a concurrent drain never sees it negative
+        let pending = self.pending.fetch_add(1, Ordering::Relaxed) + 1;
+        if self.sender.send(event).is_err() {
+            self.pending.fetch_sub(1, Ordering::Relaxed);
+            return;
+        }
+        // Bound the channel when nobody reads; a busy reader drains it anyway
+        if pending > self.capacity() {
+            if let Ok(mut buffer) = self.buffer.try_lock() {
+                self.drain(&mut buffer);
+            }
+        }
+    }
+
+    /// Events passing `filter`, oldest first
+    pub fn events(&self, filter: &EventFilter) -> Vec<Event> {
+        let mut buffer = self.buffer.lock().unwrap();
+        self.drain(&mut buffer);
+        buffer
+            .events
+            .iter()
+            .filter(|event| filter.matches(event))
+            .cloned()
+            .collect()
+    }
+
+    /// Move pending events into the ring, evicting the oldest over capacity
+    fn drain(&self, buffer: &mut EventBuffer) {
+        let capacity = self.capacity();
+        while let Ok(mut event) = buffer.receiver.try_recv() {
+            self.pending.fetch_sub(1, Ordering::Relaxed);
+            event.sequence = buffer.next_sequence;
+            buffer.next_sequence += 1;
+            buffer.events.push_back(event);
+        }
+        while buffer.events.len() > capacity {
+            buffer.events.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    fn event(category: EventCategory, message: &str) -> Event {
+        Event::new(EventSeverity::Info, category, message)
+    }
+
+    fn messages(events: &[Event]) -> Vec<&str> {
+        events.iter().map(|event| event.message.as_str()).collect()
+    }
+
+    #[test]
+    fn test_events_are_returned_oldest_first() {
+        let log = EventLog::new(10);
+        log.record(event(EventCategory::Connection, "first"));
+        log.record(event(EventCategory::Task, "second"));
+        log.record(event(EventCategory::Routing, "third"));
+
+        let events = log.events(&EventFilter::default());
+
+        assert_eq!(messages(&events), vec!["first", "second", "third"]);
+        let sequences: Vec<u64> = events.iter().map(|event| event.sequence).collect();
+        assert_eq!(sequences, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_oldest_events_are_evicted_over_capacity() {
+        let log = EventLog::new(3);
+        for i in 0..5 {
+            log.record(event(EventCategory::Task, &format!("event-{i}")));
+        }
+
+        let events = log.events(&EventFilter::default());
+
+        assert_eq!(messages(&events), vec!["event-2", "event-3", "event-4"]);
+        assert_eq!(events[0].sequence, 3);
+    }
+
+    #[test]
+    fn test_shrinking_capacity_keeps_the_latest_events() {
+        let log = EventLog::new(10);
+        for i in 0..4 {
+            log.record(event(EventCategory::Task, &format!("event-{i}")));
+        }
+
+        log.set_capacity(2);
+
+        assert_eq!(
+            messages(&log.events(&EventFilter::default())),
+            vec!["event-2", "event-3"]
+        );
+    }
+
+    #[test]
+    fn test_filter_by_category_and_since() {
+        let log = EventLog::new(10);
+        let mut old = event(EventCategory::Tool, "old tool error");
+        old.timestamp = Utc::now() - chrono::Duration::minutes(10);
+        log.record(old);
+        log.record(event(EventCategory::Tool, "tool error"));
+        log.record(event(EventCategory::Connection, "reconnected"));
+
+        let tools = log.events(&EventFilter {
+            since: None,
+            category: Some(EventCategory::Tool),
+        });
+        let recent = log.events(&EventFilter {
+            since: Some(Utc::now() - chrono::Duration::minutes(5)),
+            category: None,
+        });
+
+        assert_eq!(messages(&tools), vec!["old tool error", "tool error"]);
+        assert_eq!(messages(&recent), vec!["tool error", "reconnected"]);
+    }
+
+    #[test]
+    fn test_unread_log_stays_bounded() {
+        let log = EventLog::new(5);
+        for i in 0..100 {
+            log.record(event(EventCategory::Task, &format!("event-{i}")));
+        }
+
+        assert!(log.pending.load(Ordering::Relaxed) <= 5);
+        assert_eq!(log.events(&EventFilter::default()).len(), 5);
+    }
+
+    #[test]
+    fn test_concurrent_writers_keep_each_writers_order() {
+        let log = Arc::new(EventLog::new(1000));
+        let writers: Vec<_> = (0..4)
+            .map(|writer| {
+                let log = log.clone();
+                std::thread::spawn(move || {
+                    for i in 0..100 {
+                        log.record(
+                            event(EventCategory::Task, "write")
+                                .with_field("writer", writer)
+                                .with_field("i", i),
+                        );
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        let events = log.events(&EventFilter::default());
+
+        assert_eq!(events.len(), 400);
+        for writer in 0..4 {
+            let order: Vec<u64> = events
+                .iter()
+                .filter(|event| event.fields["writer"] == writer)
+                .map(|event| event.fields["i"].as_u64().unwrap())
+                .collect();
+            assert_eq!(order, (0..100).collect::<Vec<_>>());
+        }
+    }
+}
@@ -3,16 +3,33 @@
 //! Provides HTTP endpoints for monitoring agent status, supporting both
 //! human operators and container orchestration platforms.
 
+use crate::agent::route_decision::RouteDecision;
+use crate::observability::agent_state::AgentStateRegistry;
+use crate::observability::event_log::{event_log, Event, EventCategory, EventFilter};
 use crate::observability::metrics::metrics;
-use serde::Serialize;
+use crate::observability::probes::ProbeState;
+use crate::observability::prometheus;
+use crate::progress::ProgressMessage;
+use futures::{Stream, StreamExt};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::convert::Infallible;
+use std::future::Future;
+use std::net::SocketAddr;
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::broadcast::{self, error::RecvError};
 use tokio::sync::RwLock;
+use tracing::warn;
 use warp::Filter;
 
+/// Progress messages a `/progress/stream` client may fall behind by before it is dropped
+const PROGRESS_STREAM_CAPACITY: usize = 256;
+
+/// Interval of the SSE keep-alive comments that stop proxies closing idle streams
+const PROGRESS_STREAM_HEARTBEAT: Duration = Duration::from_secs(15);
+
 /// HTTP health check server
 pub struct HealthServer {
     agent_id: String,
@@ -20,6 +37,14 @@ pub struct HealthServer {
     mqtt_connected: Arc<AtomicBool>,
     last_task_processed: Arc<AtomicU64>,
     additional_checks: Arc<RwLock<HashMap<String, HealthCheck>>>,
+    /// Agents hosted in this process, served under `/agents/<agent_id>/`
+    agents: Arc<std::sync::RwLock<HashMap<String, Arc<HealthServer>>>>,
+    /// Live progress served on `/progress/stream`
+    progress: broadcast::Sender<ProgressMessage>,
+    /// Task, tool and config state served on the admin endpoints
+    state: Arc<AgentStateRegistry>,
+    /// Startup phase, pipeline state and components served on `/livez` and `/readyz`
+    probes: Arc<ProbeState>,
 }
 
 impl HealthServer {
@@ -31,14 +56,64 @@ impl HealthServer {
             mqtt_connected: Arc::new(AtomicBool::new(false)),
             last_task_processed: Arc::new(AtomicU64::new(0)),
             additional_checks: Arc::new(RwLock::new(HashMap::new())),
+            agents: Arc::new(std::sync::RwLock::new(HashMap::new())),
+            progress: broadcast::channel(PROGRESS_STREAM_CAPACITY).0,
+            state: Arc::new(AgentStateRegistry::default()),
+            probes: Arc::new(ProbeState::default()),
         }
     }
 
+    /// Registry the pipeline and processor report task state into
+    pub fn state(&self) -> &Arc<AgentStateRegistry> {
+        &self.state
+    }
+
+    /// Probe state the lifecycle reports startup phases and components into
+    pub fn probes(&self) -> &Arc<ProbeState> {
+        &self.probes
+    }
+
+    /// Stream this server's progress on `host`'s `/progress/stream`
+    ///
+    /// Used for hosted agents, whose own server is never started.
+    pub fn share_progress_stream(mut self, host: &HealthServer) -> Self {
+        self.progress = host.progress.clone();
+        self
+    }
+
+    /// Sender feeding `/progress/stream`, for a progress sink to write into
+    pub fn progress_sender(&self) -> broadcast::Sender<ProgressMessage> {
+        self.progress.clone()
+    }
+
+    /// Get the agent id this server reports for
+    pub fn agent_id(&self) -> &str {
+        &self.agent_id
+    }
+
+    /// Serve a hosted agent's health under `/agents/<agent_id>/`
+    pub fn register_agent(&self, agent: Arc<HealthServer>) {
+        self.agents
+            .write()
+            .unwrap()
+            .insert(agent.agent_id.clone(), agent);
+    }
+
+    /// Get a hosted agent's health server
+    pub fn agent(&self, agent_id: &str) -> Option<Arc<HealthServer>> {
+        self.agents.read().unwrap().get(agent_id).cloned()
+    }
+
     /// Update MQTT connection status
     pub async fn set_mqtt_connected(&self, connected: bool) {
         self.mqtt_connected.store(connected, Ordering::Relaxed);
     }
 
+    /// Whether MQTT is reported connected
+    pub fn is_mqtt_connected(&self) -> bool {
+        self.mqtt_connected.load(Ordering::Relaxed)
+    }
+
     /// Update last task processed timestamp
     pub async fn set_last_task_processed(&self, timestamp: u64) {
         self.last_task_processed.store(timestamp, Ordering::Relaxed);
@@ -58,63 +133,124 @@ impl HealthServer {
 
     /// Start the HTTP health server
     pub async fn start(self: Arc<Self>) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        tracing::info!("Starting health server on port {}", self.port);
+        let port = self.port;
+        let (_, server) = self.serve_on(([0, 0, 0, 0], port));
+        server.await;
+
+        Ok(())
+    }
+
+    /// Bind the endpoints to `addr`, which may use port 0
+    ///
+    /// Returns the bound address and the future that serves requests.
+    pub fn serve_on(
+        self: Arc<Self>,
+        addr: impl Into<SocketAddr>,
+    ) -> (SocketAddr, impl Future<Output = ()> + 'static) {
         let health_server = self.clone();
         let metrics_server = self.clone();
         let ready_server = self.clone();
         let live_server = self.clone();
+        let livez_server = self.clone();
+        let readyz_server = self.clone();
         let root_server = self.clone();
+        let agents_server = self.clone();
+        let progress_server = self.clone();
+        let tasks_server = self.clone();
+        let agent_tasks_server = self.clone();
+        let tools_server = self.clone();
+        let config_server = self.clone();
+        let events_server = self.clone();
 
         // GET /health - comprehensive health status
-        let health_route = warp::path("health").and(warp::get()).and_then(move || {
-            let server = health_server.clone();
-            async move {
-                match server.get_health_status().await {
-                    Ok(status) => {
-                        let status_code = if status.status == "healthy" { 200 } else { 503 };
-                        Ok::<_, Infallible>(warp::reply::with_status(
-                            warp::reply::json(&status),
-                            warp::http::StatusCode::from_u16(status_code).unwrap(),
-                        ))
-                    }
-                    Err(e) => {
-                        let error_response = ErrorResponse {
-                            error: format!("Health check failed: {e}"),
-                            timestamp: current_timestamp(),
-                        };
-                        Ok::<_, Infallible>(warp::reply::with_status(
-                            warp::reply::json(&error_response),
-                            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
-                        ))
-                    }
-                }
-            }
-        });
-
-        // GET /metrics - complete metrics export
-        let metrics_route = warp::path("metrics").and(warp::get()).and_then(move || {
-            let _server = metrics_server.clone();
-            async move {
-                let metrics_snapshot = metrics().get_metrics();
-                Ok::<_, Infallible>(warp::reply::json(&metrics_snapshot))
-            }
-        });
+        let health_route = warp::path("health")
+            .and(warp::get())
+            .and_then(move || health_reply(health_server.clone()));
+
+        // GET /metrics - complete metrics export, JSON or Prometheus text
+        let metrics_route = warp::path("metrics")
+            .and(warp::get())
+            .and(warp::header::optional::<String>("accept"))
+            .map(move |accept: Option<String>| {
+                metrics_reply(&metrics_server.agent_id, accept.as_deref())
+            });
 
         // GET /ready - Kubernetes readiness probe
-        let ready_route = warp::path("ready").and(warp::get()).and_then(move || {
-            let server = ready_server.clone();
-            async move {
-                let ready = server.mqtt_connected.load(Ordering::Relaxed);
-                let response = ReadinessResponse {
-                    ready,
-                    timestamp: current_timestamp(),
-                };
-                let status_code = if ready { 200 } else { 503 };
-                Ok::<_, Infallible>(warp::reply::with_status(
-                    warp::reply::json(&response),
-                    warp::http::StatusCode::from_u16(status_code).unwrap(),
-                ))
-            }
-        });
+        let ready_route = warp::path("ready")
+            .and(warp::get())
+            .and_then(move || ready_reply(ready_server.clone()));
+
+        // GET /livez - liveness: fails only once the pipeline loop stopped
+        let livez_route = warp::path("livez")
+            .and(warp::path::end())
+            .and(warp::get())
+            .map(move || livez_reply(&livez_server));
+
+        // GET /readyz - readiness with per-component status
+        let readyz_route = warp::path("readyz")
+            .and(warp::path::end())
+            .and(warp::get())
+            .map(move || readyz_reply(&readyz_server));
+
+        // GET /agents/<agent_id>/{health,ready,livez,readyz,metrics} - per hosted agent
+        let agents_route = warp::path!("agents" / String / String)
+            .and(warp::get())
+            .and_then(move |agent_id: String, endpoint: String| {
+                agent_reply(agents_server.agent(&agent_id), endpoint)
+            });
+
+        // GET /progress/stream - live progress as Server-Sent Events
+        let progress_route = warp::path!("progress" / "stream")
+            .and(warp::get())
+            .and(warp::query::<ProgressStreamFilter>())
+            .map(move |filter: ProgressStreamFilter| {
+                let events = progress_events(progress_server.progress.subscribe(), filter);
+                warp::sse::reply(
+                    warp::sse::keep_alive()
+                        .interval(PROGRESS_STREAM_HEARTBEAT)
+                        .stream(events),
+                )
+            });
+
+        // GET /tasks/{active,recent} - in-flight and recently finished tasks
+        let tasks_route = warp::path!("tasks" / String)
+            .and(warp::get())
+            .map(move |which: String| state_reply(&tasks_server, &format!("tasks/{which}")));
+
+        // GET /agents/<agent_id>/tasks/{active,recent} - per hosted agent
+        let agent_tasks_route = warp::path!("agents" / String / "tasks" / String)
+            .and(warp::get())
+            .map(move |agent_id: String, which: String| {
+                match agent_tasks_server.agent(&agent_id) {
+                    Some(agent) => state_reply(&agent, &format!("tasks/{which}")),
+                    None => not_found_reply(),
+                }
+            });
+
+        // GET /tools - names and parameter schemas of the available tools
+        let tools_route = warp::path("tools")
+            .and(warp::path::end())
+            .and(warp::get())
+            .map(move || state_reply(&tools_server, "tools"));
+
+        // GET /config - effective configuration with secrets redacted
+        let config_route = warp::path("config")
+            .and(warp::path::end())
+            .and(warp::get())
+            .map(move || state_reply(&config_server, "config"));
+
+        // GET /schemas/route-decision - JSON Schema routing decisions must follow
+        let schema_route = warp::path!("schemas" / "route-decision")
+            .and(warp::get())
+            .map(|| warp::reply::json(&RouteDecision::json_schema()));
+
+        // GET /events - recent significant events, filtered by since and category
+        let events_route = warp::path("events")
+            .and(warp::path::end())
+            .and(warp::get())
+            .and(warp::query::<EventsQuery>())
+            .map(move |query: EventsQuery| events_reply(&events_server, &query));
 
         // GET /live - Kubernetes liveness probe
         let live_route = warp::path("live").and(warp::get()).and_then(move || {
@@ -139,7 +275,7 @@ impl HealthServer {
                 );
                 endpoints.insert(
                     "/metrics".to_string(),
-                    "Comprehensive metrics and statistics".to_string(),
+                    "Comprehensive metrics and statistics; Prometheus text with Accept: text/plain".to_string(),
                 );
                 endpoints.insert(
                     "/ready".to_string(),
@@ -149,6 +285,47 @@ impl HealthServer {
                     "/live".to_string(),
                     "Liveness probe for Kubernetes".to_string(),
                 );
+                endpoints.insert(
+                    "/livez".to_string(),
+                    "Liveness; 503 only once the pipeline loop has exited or panicked".to_string(),
+                );
+                endpoints.insert(
+                    "/readyz".to_string(),
+                    "Readiness with per-component status: broker, subscription, LLM, tools".to_string(),
+                );
+                endpoints.insert(
+                    "/agents/<agent_id>/{health,ready,livez,readyz,metrics,tools,config,tasks/active,tasks/recent}".to_string(),
+                    "Per-agent status when hosting several agents".to_string(),
+                );
+                endpoints.insert(
+                    "/progress/stream".to_string(),
+                    "Live progress as Server-Sent Events; filter with task_id, conversation_id or agent_id".to_string(),
+                );
+
+                endpoints.insert(
+                    "/tasks/active".to_string(),
+                    "In-flight tasks with their current step and elapsed time".to_string(),
+                );
+                endpoints.insert(
+                    "/tasks/recent".to_string(),
+                    "Recently finished tasks with outcome and step durations".to_string(),
+                );
+                endpoints.insert(
+                    "/tools".to_string(),
+                    "Available tools with their parameter schemas".to_string(),
+                );
+                endpoints.insert(
+                    "/config".to_string(),
+                    "Effective configuration with secrets redacted".to_string(),
+                );
+                endpoints.insert(
+                    "/schemas/route-decision".to_string(),
+                    "JSON Schema of the routing decisions v2 agents and gatekeepers produce".to_string(),
+                );
+                endpoints.insert(
+                    "/events".to_string(),
+                    "Recent connection, task, routing and tool events; filter with since and category".to_string(),
+                );
 
                 let response = ApiDocumentationResponse { endpoints };
                 Ok::<_, Infallible>(warp::reply::json(&response))
@@ -159,20 +336,28 @@ impl HealthServer {
             .or(metrics_route)
             .or(ready_route)
             .or(live_route)
+            .or(livez_route)
+            .or(readyz_route)
+            .or(agents_route)
+            .or(progress_route)
+            .or(tasks_route)
+            .or(agent_tasks_route)
+            .or(tools_route)
+            .or(config_route)
+            .or(schema_route)
+            .or(events_route)
             .or(root_route)
             .with(warp::cors().allow_any_origin());
 
-        tracing::info!("Starting health server on port {}", self.port);
-
-        warp::serve(routes).run(([0, 0, 0, 0], self.port)).await;
-
-        Ok(())
+        warp::serve(routes).bind_ephemeral(addr)
     }
 
     async fn get_health_status(
         &self,
     ) -> Result<HealthStatus, Box<dyn std::error::Error + Send + Sync>> {
         let now = current_timestamp();
+        let paused = metrics().is_paused();
+        let idle = metrics().is_idle();
 
         // Perform individual health checks
         let mut checks = HashMap::new();
@@ -182,7 +367,22 @@ impl HealthServer {
         checks.insert("mqtt".to_string(), mqtt_check);
 
         // Task processing health check
-        let task_check = self.check_task_processing_health().await;
+        let task_check = if paused {
+            HealthCheck {
+                status: "paused".to_string(),
+                message: Some("Task intake paused by admin request".to_string()),
+                last_check: now,
+            }
+        } else if idle {
+            // Going idle is configured and expected, so a quiet agent isn't stale
+            HealthCheck {
+                status: "healthy".to_string(),
+                message: Some("Agent idle, resources released until the next task".to_string()),
+                last_check: now,
+            }
+        } else {
+            self.check_task_processing_health().await
+        };
         checks.insert("task_processing".to_string(), task_check);
 
         // Add any additional health checks
@@ -191,13 +391,7 @@ impl HealthServer {
             checks.insert(name.clone(), check.clone());
         }
 
-        // Determine overall health status
-        let overall_healthy = checks.values().all(|check| check.status == "healthy");
-        let overall_status = if overall_healthy {
-            "healthy".to_string()
-        } else {
-            "degraded".to_string()
-        };
+        let overall_status = overall_status(&checks, paused).to_string();
 
         let uptime_seconds = now - metrics().get_metrics().lifecycle.uptime_seconds;
 
@@ -206,6 +400,9 @@ impl HealthServer {
             timestamp: now,
             agent_id: self.agent_id.clone(),
             uptime_seconds,
+            paused,
+            idle,
+            task_panics: metrics().task_panics(),
             checks,
         })
     }
@@ -274,6 +471,11 @@ struct HealthStatus {
     timestamp: u64,
     agent_id: String,
     uptime_seconds: u64,
+    paused: bool,
+    /// No task has run for a while and pooled resources were released
+    idle: bool,
+    /// Tasks whose processing panicked; the agent kept running after each
+    task_panics: u64,
     checks: HashMap<String, HealthCheck>,
 }
 
@@ -294,12 +496,339 @@ struct ApiDocumentationResponse {
     endpoints: HashMap<String, String>,
 }
 
+#[derive(Debug, Serialize)]
+struct AgentMetricsResponse {
+    agent_id: String,
+    metrics: crate::observability::metrics::MetricsSnapshot,
+}
+
+#[derive(Debug, Serialize)]
+struct ActiveTasksResponse {
+    agent_id: String,
+    tasks: Vec<crate::observability::agent_state::ActiveTask>,
+}
+
+#[derive(Debug, Serialize)]
+struct RecentTasksResponse {
+    agent_id: String,
+    tasks: Vec<crate::observability::agent_state::RecentTask>,
+}
+
+#[derive(Debug, Serialize)]
+struct ToolsResponse {
+    agent_id: String,
+    tools: Vec<crate::tools::ToolDescription>,
+    /// Optional tools skipped at startup or reload
+    degraded: Vec<crate::tools::DegradedTool>,
+}
+
+#[derive(Debug, Serialize)]
+struct EventsResponse {
+    agent_id: String,
+    events: Vec<Event>,
+}
+
 #[derive(Debug, Serialize)]
 struct ErrorResponse {
     error: String,
     timestamp: u64,
 }
 
+type StatusReply = warp::reply::WithStatus<warp::reply::Json>;
+
+/// Query of `/progress/stream`; every given field must match
+#[derive(Debug, Default, Deserialize)]
+pub struct ProgressStreamFilter {
+    pub task_id: Option<String>,
+    pub conversation_id: Option<String>,
+    pub agent_id: Option<String>,
+}
+
+impl ProgressStreamFilter {
+    /// Whether a progress message passes the filter (pure function)
+    pub fn matches(&self, message: &ProgressMessage) -> bool {
+        let field_matches = |wanted: &Option<String>, actual: Option<&str>| {
+            wanted
+                .as_deref()
+                .map_or(true, |wanted| actual == Some(wanted))
+        };
+        field_matches(&self.task_id, message.task_id.as_deref())
+            && field_matches(&self.conversation_id, message.conversation_id.as_deref())
+            && field_matches(&self.agent_id, Some(message.agent_id.as_str()))
+    }
+}
+
+/// SSE events for the progress messages passing `filter`
+///
+/// The stream ends when the client falls too far behind, so a slow client
+/// never holds back the agents reporting progress.
+fn progress_events(
+    receiver: broadcast::Receiver<ProgressMessage>,
+    filter: ProgressStreamFilter,
+) -> impl Stream<Item = Result<warp::sse::Event, Infallible>> {
+    futures::stream::unfold(receiver, |mut receiver| async move {
+        match receiver.recv().await {
+            Ok(message) => Some((message, receiver)),
+            Err(RecvError::Lagged(missed)) => {
+                warn!(missed, "Dropping progress stream client that fell behind");
+                None
+            }
+            Err(RecvError::Closed) => None,
+        }
+    })
+    .filter_map(move |message| {
+        let event = filter
+            .matches(&message)
+            .then(|| warp::sse::Event::default().json_data(&message).ok())
+            .flatten();
+        futures::future::ready(event.map(Ok))
+    })
+}
+
+/// Query of `/events`
+#[derive(Debug, Default, Deserialize)]
+pub struct EventsQuery {
+    /// RFC 3339 timestamp or Unix seconds; only events at or after it
+    pub since: Option<String>,
+    /// Event category, e.g. `connection` or `task`
+    pub category: Option<String>,
+}
+
+impl EventsQuery {
+    /// Parse the query into an event filter (pure function)
+    pub fn to_filter(&self) -> Result<EventFilter, String> {
+        let since = match self.since.as_deref() {
+            None => None,
+            Some(since) => Some(
+                chrono::DateTime::parse_from_rfc3339(since)
+                    .map(|since| since.with_timezone(&chrono::Utc))
+                    .ok()
+                    .or_else(|| {
+                        since
+                            .parse::<i64>()
+                            .ok()
+                            .and_then(|secs| chrono::DateTime::from_timestamp(secs, 0))
+                    })
+                    .ok_or_else(|| {
+                        format!("Invalid since '{since}': expected RFC 3339 or Unix seconds")
+                    })?,
+            ),
+        };
+        let category = match self.category.as_deref() {
+            None => None,
+            Some(category) => Some(
+                serde_json::from_value::<EventCategory>(serde_json::Value::from(category))
+                    .map_err(|_| format!("Unknown event category '{category}'"))?,
+            ),
+        };
+        Ok(EventFilter { since, category })
+    }
+}
+
+/// Reply for `/events`: 400 for an unparseable query
+fn events_reply(server: &HealthServer, query: &EventsQuery) -> StatusReply {
+    match query.to_filter() {
+        Ok(filter) => warp::reply::with_status(
+            warp::reply::json(&EventsResponse {
+                agent_id: server.agent_id.clone(),
+                events: event_log().events(&filter),
+            }),
+            warp::http::StatusCode::OK,
+        ),
+        Err(error) => warp::reply::with_status(
+            warp::reply::json(&ErrorResponse {
+                error,
+                timestamp: current_timestamp(),
+            }),
+            warp::http::StatusCode::BAD_REQUEST,
+        ),
+    }
+}
+
+/// Reply for `/health`: 503 while degraded
+async fn health_reply(server: Arc<HealthServer>) -> Result<StatusReply, Infallible> {
+    match server.get_health_status().await {
+        Ok(status) => {
+            // A paused agent is deliberately idle, not failing
+            let status_code = if status.status == "degraded" {
+                503
+            } else {
+                200
+            };
+            Ok(warp::reply::with_status(
+                warp::reply::json(&status),
+                warp::http::StatusCode::from_u16(status_code).unwrap(),
+            ))
+        }
+        Err(e) => {
+            let error_response = ErrorResponse {
+                error: format!("Health check failed: {e}"),
+                timestamp: current_timestamp(),
+            };
+            Ok(warp::reply::with_status(
+                warp::reply::json(&error_response),
+                warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+            ))
+        }
+    }
+}
+
+/// Reply for `/metrics`: Prometheus text if the client asks for it, else JSON
+fn metrics_reply(agent_id: &str, accept: Option<&str>) -> warp::reply::Response {
+    use warp::Reply;
+
+    if accept.is_some_and(wants_prometheus) {
+        warp::reply::with_header(
+            metrics().render_prometheus(agent_id),
+            "content-type",
+            prometheus::CONTENT_TYPE,
+        )
+        .into_response()
+    } else {
+        warp::reply::json(&metrics().get_metrics()).into_response()
+    }
+}
+
+/// Whether an `Accept` header asks for the Prometheus text format (pure function)
+///
+/// Prometheus scrapers accept `text/plain` or `application/openmetrics-text`;
+/// clients such as curl send `*/*` and keep getting JSON.
+pub fn wants_prometheus(accept: &str) -> bool {
+    accept.split(',').any(|media_range| {
+        let media_type = media_range.split(';').next().unwrap_or_default().trim();
+        media_type.eq_ignore_ascii_case("text/plain")
+            || media_type.eq_ignore_ascii_case("application/openmetrics-text")
+    })
+}
+
+/// Reply for `/ready`: 503 unless connected and not paused
+async fn ready_reply(server: Arc<HealthServer>) -> Result<StatusReply, Infallible> {
+    let ready = server.mqtt_connected.load(Ordering::Relaxed) && !metrics().is_paused();
+    let response = ReadinessResponse {
+        ready,
+        timestamp: current_timestamp(),
+    };
+    let status_code = if ready { 200 } else { 503 };
+    Ok(warp::reply::with_status(
+        warp::reply::json(&response),
+        warp::http::StatusCode::from_u16(status_code).unwrap(),
+    ))
+}
+
+/// Reply for `/livez`: 503 once the pipeline loop has exited or panicked
+fn livez_reply(server: &HealthServer) -> StatusReply {
+    let report = server.probes.liveness(current_timestamp());
+    let status_code = if report.live {
+        warp::http::StatusCode::OK
+    } else {
+        warp::http::StatusCode::SERVICE_UNAVAILABLE
+    };
+    warp::reply::with_status(warp::reply::json(&report), status_code)
+}
+
+/// Reply for `/readyz`: 503 unless started with every component ok
+fn readyz_reply(server: &HealthServer) -> StatusReply {
+    let report = server.probes.readiness(
+        server.mqtt_connected.load(Ordering::Relaxed),
+        metrics().is_paused(),
+        current_timestamp(),
+    );
+    let status_code = if report.ready {
+        warp::http::StatusCode::OK
+    } else {
+        warp::http::StatusCode::SERVICE_UNAVAILABLE
+    };
+    warp::reply::with_status(warp::reply::json(&report), status_code)
+}
+
+/// Reply for `/agents/<agent_id>/<endpoint>`: 404 for unknown agents or endpoints
+async fn agent_reply(
+    agent: Option<Arc<HealthServer>>,
+    endpoint: String,
+) -> Result<StatusReply, Infallible> {
+    match (agent, endpoint.as_str()) {
+        (Some(agent), "health") => health_reply(agent).await,
+        (Some(agent), "ready") => ready_reply(agent).await,
+        (Some(agent), "livez") => Ok(livez_reply(&agent)),
+        (Some(agent), "readyz") => Ok(readyz_reply(&agent)),
+        (Some(agent), "metrics") => {
+            // Counters are process-wide; the snapshot is labelled with the agent
+            let response = AgentMetricsResponse {
+                agent_id: agent.agent_id.clone(),
+                metrics: metrics().get_metrics(),
+            };
+            Ok(warp::reply::with_status(
+                warp::reply::json(&response),
+                warp::http::StatusCode::OK,
+            ))
+        }
+        (Some(agent), endpoint) => Ok(state_reply(&agent, endpoint)),
+        (None, _) => Ok(not_found_reply()),
+    }
+}
+
+/// Reply for the admin endpoints read from the state registry
+///
+/// `endpoint` is the path below the server root: `tasks/active`,
+/// `tasks/recent`, `tools` or `config`; anything else is a 404.
+fn state_reply(server: &HealthServer, endpoint: &str) -> StatusReply {
+    let agent_id = server.agent_id.clone();
+    let state = &server.state;
+    let reply = match endpoint {
+        "tasks/active" => warp::reply::json(&ActiveTasksResponse {
+            agent_id,
+            tasks: state.active_tasks(),
+        }),
+        "tasks/recent" => warp::reply::json(&RecentTasksResponse {
+            agent_id,
+            tasks: state.recent_tasks(),
+        }),
+        "tools" => warp::reply::json(&ToolsResponse {
+            agent_id,
+            tools: state.tools(),
+            degraded: state.degraded_tools(),
+        }),
+        "config" => match state.config() {
+            Some(config) => warp::reply::json(&config),
+            None => {
+                let error_response = ErrorResponse {
+                    error: "Configuration not recorded yet".to_string(),
+                    timestamp: current_timestamp(),
+                };
+                return warp::reply::with_status(
+                    warp::reply::json(&error_response),
+                    warp::http::StatusCode::SERVICE_UNAVAILABLE,
+                );
+            }
+        },
+        _ => return not_found_reply(),
+    };
+    warp::reply::with_status(reply, warp::http::StatusCode::OK)
+}
+
+fn not_found_reply() -> StatusReply {
+    let error_response = ErrorResponse {
+        error: "Unknown agent or endpoint".to_string(),
+        timestamp: current_timestamp(),
+    };
+    warp::reply::with_status(
+        warp::reply::json(&error_response),
+        warp::http::StatusCode::NOT_FOUND,
+    )
+}
+
+/// Overall status: "degraded" if any check fails, else "paused" or "healthy"
+fn overall_status(checks: &HashMap<String, HealthCheck>, paused: bool) -> &'static str {
+    let healthy = checks
+        .values()
+        .all(|check| check.status == "healthy" || check.status == "paused");
+    match (healthy, paused) {
+        (false, _) => "degraded",
+        (true, true) => "paused",
+        (true, false) => "healthy",
+    }
+}
+
 fn current_timestamp() -> u64 {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
@@ -377,6 +906,110 @@ mod tests {
         assert_eq!(task_check.status, "stale");
     }
 
+    #[tokio::test]
+    async fn test_agent_routes_resolve_registered_agents() {
+        use warp::Reply;
+
+        let host = HealthServer::new("agent-host".to_string(), 0);
+        host.register_agent(Arc::new(HealthServer::new("writer".to_string(), 0)));
+
+        let reply = agent_reply(host.agent("writer"), "metrics".to_string())
+            .await
+            .unwrap();
+        assert_eq!(reply.into_response().status(), warp::http::StatusCode::OK);
+
+        let reply = agent_reply(host.agent("missing"), "health".to_string())
+            .await
+            .unwrap();
+        assert_eq!(
+            reply.into_response().status(),
+            warp::http::StatusCode::NOT_FOUND
+        );
+
+        let reply = agent_reply(host.agent("writer"), "unknown".to_string())
+            .await
+            .unwrap();
+        assert_eq!(
+            reply.into_response().status(),
+            warp::http::StatusCode::NOT_FOUND
+        );
+    }
+
+    #[test]
+    fn test_wants_prometheus() {
+        assert!(wants_prometheus("text/plain;version=0.0.4;q=0.5,*/*;q=0.1"));
+        assert!(wants_prometheus(
+            "application/openmetrics-text; version=1.0.0, */*"
+        ));
+        assert!(!wants_prometheus("*/*"));
+        assert!(!wants_prometheus("application/json"));
+    }
+
+    fn progress(agent_id: &str, task_id: &str, conversation_id: &str) -> ProgressMessage {
+        ProgressMessage::new(
+            agent_id.to_string(),
+            crate::progress::ProgressCategory::General,
+            crate::progress::ProgressEventType::Processing,
+            "working".to_string(),
+        )
+        .with_task_context(Some(task_id.to_string()), Some(conversation_id.to_string()))
+    }
+
+    #[test]
+    fn test_progress_stream_filter() {
+        let message = progress("agent-1", "task-1", "conv-1");
+        assert!(ProgressStreamFilter::default().matches(&message));
+
+        let by_task = ProgressStreamFilter {
+            task_id: Some("task-1".to_string()),
+            ..Default::default()
+        };
+        assert!(by_task.matches(&message));
+        assert!(!by_task.matches(&progress("agent-1", "task-2", "conv-1")));
+
+        let by_conversation_and_agent = ProgressStreamFilter {
+            conversation_id: Some("conv-1".to_string()),
+            agent_id: Some("agent-2".to_string()),
+            ..Default::default()
+        };
+        assert!(!by_conversation_and_agent.matches(&message));
+        assert!(by_conversation_and_agent.matches(&progress("agent-2", "task-9", "conv-1")));
+    }
+
+    #[tokio::test]
+    async fn test_lagging_progress_client_is_dropped() {
+        let (sender, receiver) = broadcast::channel(2);
+        let events = progress_events(receiver, ProgressStreamFilter::default());
+
+        // The client reads nothing while more events arrive than the channel holds
+        for i in 0..5 {
+            sender
+                .send(progress("agent-1", &format!("task-{i}"), "conv-1"))
+                .unwrap();
+        }
+
+        // Sending never blocked, and the stream ends instead of catching up
+        assert_eq!(events.collect::<Vec<_>>().await.len(), 0);
+    }
+
+    #[test]
+    fn test_overall_status_while_paused() {
+        let check = |status: &str| HealthCheck {
+            status: status.to_string(),
+            message: None,
+            last_check: 0,
+        };
+        let mut checks = HashMap::new();
+        checks.insert("mqtt".to_string(), check("healthy"));
+        checks.insert("task_processing".to_string(), check("paused"));
+
+        assert_eq!(overall_status(&checks, true), "paused");
+        assert_eq!(overall_status(&checks, false), "healthy");
+
+        checks.insert("mqtt".to_string(), check("unhealthy"));
+        assert_eq!(overall_status(&checks, true), "degraded");
+    }
+
     #[tokio::test]
     async fn test_additional_health_checks() {
         let health_server = HealthServer::new("test-agent".to_string(), 8080);
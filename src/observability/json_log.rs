@@ -0,0 +1,99 @@
+//! One-JSON-object-per-line log format with span fields flattened in
+//!
+//! tracing-subscriber's JSON format nests span fields under `span` and
+//! `spans`, so a line logged inside a tool span would only carry
+//! `tool_name` at the top level. `FlatJson` instead merges the fields of
+//! every enclosing span, outermost first, into the line itself, followed
+//! by the event's own fields. A log line inside a tool execution of a task
+//! therefore carries `agent_id`, `task_id`, `conversation_id` and
+//! `tool_name` next to `message`, whichever code emitted it.
+//!
+//! ```json
+//! {"timestamp":"2024-01-01T12:00:00.000000Z","level":"INFO","target":"agent2389::tools",
+//!  "agent_id":"researcher","task_id":"…","conversation_id":"…","tool_name":"web_search",
+//!  "message":"Search returned 5 results","spans":["task_processing","tool_execution"]}
+//! ```
+
+use serde_json::{Map, Value};
+use std::fmt;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::fmt::format::{JsonFields, Writer};
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormattedFields};
+use tracing_subscriber::registry::LookupSpan;
+
+/// Event format writing flat JSON lines; span fields must be recorded with
+/// [`JsonFields`]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FlatJson;
+
+impl<S> FormatEvent<S, JsonFields> for FlatJson
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, JsonFields>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let mut line = Map::new();
+
+        if let Some(scope) = ctx.event_scope() {
+            let mut spans = Vec::new();
+            for span in scope.from_root() {
+                if let Some(fields) = span.extensions().get::<FormattedFields<JsonFields>>() {
+                    if let Ok(Value::Object(fields)) = serde_json::from_str(fields) {
+                        line.extend(fields);
+                    }
+                }
+                spans.push(Value::from(span.name()));
+            }
+            line.insert("spans".to_string(), Value::Array(spans));
+        }
+        event.record(&mut JsonVisitor(&mut line));
+
+        let metadata = event.metadata();
+        line.insert(
+            "timestamp".to_string(),
+            chrono::Utc::now()
+                .to_rfc3339_opts(chrono::SecondsFormat::Micros, true)
+                .into(),
+        );
+        line.insert("level".to_string(), metadata.level().as_str().into());
+        line.insert("target".to_string(), metadata.target().into());
+
+        let line = serde_json::to_string(&line).map_err(|_| fmt::Error)?;
+        writeln!(writer, "{line}")
+    }
+}
+
+/// Records event fields as JSON values, keeping numbers and booleans typed
+struct JsonVisitor<'a>(&'a mut Map<String, Value>);
+
+impl Visit for JsonVisitor<'_> {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0
+            .insert(field.name().to_string(), format!("{value:?}").into());
+    }
+}
@@ -5,9 +5,11 @@
 //!
 //! ## Log Format Options
 //!
-//! The logging system supports three output formats controlled by the `LOG_FORMAT` environment variable:
+//! The logging system supports three output formats controlled by the `LOG_FORMAT` environment variable
+//! or `[observability] log_format` in the agent configuration:
 //!
-//! - `json` - Structured JSON format for production and log aggregation systems
+//! - `json` - One JSON object per line for production and log aggregation systems,
+//!   carrying the fields of every enclosing span (see [`super::json_log`])
 //! - `pretty` - Human-readable format with colors and indentation for development
 //! - `compact` - Terminal-friendly format with colors but minimal spacing
 //!
@@ -18,6 +20,12 @@
 //! - `LOG_SPANS`: Include span events (true/false) - defaults to false
 //! - `RUST_LOG`: Override log filtering (follows env_logger format)
 //!
+//! `[observability.log_file]` sends logs to a size-rotated file instead of
+//! stdout. Spans are additionally exported over OTLP when `[observability.otel]` is
+//! enabled in the agent configuration (see [`super::otel`]). Once an agent's
+//! secrets are resolved, `[observability.redaction]` redacts every log line
+//! (see [`super::redaction`]).
+//!
 //! ## Examples
 //!
 //! ```bash
@@ -31,12 +39,24 @@
 //! LOG_FORMAT=compact LOG_LEVEL=INFO ./agent2389
 //! ```
 
+use super::json_log::FlatJson;
+use super::otel::TraceExport;
+use super::redaction::RedactingMakeWriter;
+use super::rotating_file::RotatingFile;
+use crate::config::{AgentConfig, LogFileConfig};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use std::env;
-use tracing::Level;
+use std::sync::Arc;
+use tracing::{warn, Level, Subscriber};
+use tracing_subscriber::fmt::format::JsonFields;
+use tracing_subscriber::fmt::writer::{BoxMakeWriter, MakeWriter};
+use tracing_subscriber::registry::LookupSpan;
 use tracing_subscriber::{fmt, prelude::*, EnvFilter};
 
 /// Log output format options
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
 pub enum LogFormat {
     /// JSON format for structured logging (machine-readable)
     Json,
@@ -59,7 +79,18 @@ impl LogFormat {
 }
 
 /// Initialize logging with manual configuration
-pub fn init_logging(level: Level, format: LogFormat, include_spans: bool) {
+///
+/// Logs go to stdout, or to the rotating `log_file` if one is given. With
+/// `trace_export`, spans are also exported over OTLP. A log file that
+/// cannot be opened or an exporter that cannot be built is reported once
+/// logging is up, and the agent runs without it.
+pub fn init_logging(
+    level: Level,
+    format: LogFormat,
+    include_spans: bool,
+    log_file: Option<&LogFileConfig>,
+    trace_export: Option<&TraceExport>,
+) {
     let mut filter = EnvFilter::new(level.to_string())
         // Reduce noise from dependencies
         .add_directive("rumqttc=warn".parse().unwrap())
@@ -72,46 +103,86 @@ pub fn init_logging(level: Level, format: LogFormat, include_spans: bool) {
         filter = EnvFilter::new(rust_log);
     }
 
-    let subscriber = tracing_subscriber::registry().with(filter);
+    let (otel_layer, export_error) = match trace_export.map(TraceExport::install) {
+        Some(Ok(tracer)) => (
+            Some(tracing_opentelemetry::layer().with_tracer(tracer)),
+            None,
+        ),
+        Some(Err(e)) => (None, Some(e)),
+        None => (None, None),
+    };
+    let subscriber = tracing_subscriber::registry().with(filter).with(otel_layer);
+
+    let (writer, to_file, file_error) =
+        match log_file.map(|f| RotatingFile::open(&f.path, f.max_bytes, f.max_files)) {
+            Some(Ok(file)) => (BoxMakeWriter::new(Arc::new(file)), true, None),
+            Some(Err(e)) => (BoxMakeWriter::new(std::io::stdout), false, Some(e)),
+            None => (BoxMakeWriter::new(std::io::stdout), false, None),
+        };
+    let writer = RedactingMakeWriter::new(writer);
+    let span_events = if include_spans {
+        fmt::format::FmtSpan::NEW | fmt::format::FmtSpan::CLOSE
+    } else {
+        fmt::format::FmtSpan::NONE
+    };
 
     match format {
         LogFormat::Json => {
-            let fmt_layer = fmt::layer().json().with_span_events(if include_spans {
-                fmt::format::FmtSpan::NEW | fmt::format::FmtSpan::CLOSE
-            } else {
-                fmt::format::FmtSpan::NONE
-            });
+            let fmt_layer = json_layer(writer, span_events);
             subscriber.with(fmt_layer).init();
         }
         LogFormat::Pretty => {
-            let fmt_layer =
-                fmt::layer()
-                    .pretty()
-                    .with_ansi(true)
-                    .with_span_events(if include_spans {
-                        fmt::format::FmtSpan::NEW | fmt::format::FmtSpan::CLOSE
-                    } else {
-                        fmt::format::FmtSpan::NONE
-                    });
+            let fmt_layer = fmt::layer()
+                .pretty()
+                .with_ansi(!to_file)
+                .with_writer(writer)
+                .with_span_events(span_events);
             subscriber.with(fmt_layer).init();
         }
         LogFormat::Compact => {
             let fmt_layer = fmt::layer()
                 .compact()
-                .with_ansi(true)
+                .with_ansi(!to_file)
                 .with_target(false)
-                .with_span_events(if include_spans {
-                    fmt::format::FmtSpan::NEW | fmt::format::FmtSpan::CLOSE
-                } else {
-                    fmt::format::FmtSpan::NONE
-                });
+                .with_writer(writer)
+                .with_span_events(span_events);
             subscriber.with(fmt_layer).init();
         }
     }
+
+    if let (Some(file), Some(e)) = (log_file, file_error) {
+        warn!(
+            path = %file.path.display(),
+            "Cannot open log file, logging to stdout: {}", e
+        );
+    }
+    if let Some(e) = export_error {
+        warn!("OpenTelemetry trace export disabled: {}", e);
+    }
 }
 
-/// Initialize logging from environment variables
-pub fn init_default_logging() {
+/// The JSON log layer: one object per line with span fields flattened in
+/// (see [`FlatJson`])
+pub fn json_layer<S, W>(
+    make_writer: W,
+    span_events: fmt::format::FmtSpan,
+) -> fmt::Layer<S, JsonFields, FlatJson, W>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'writer> MakeWriter<'writer> + 'static,
+{
+    fmt::layer()
+        .with_span_events(span_events)
+        .fmt_fields(JsonFields::new())
+        .event_format(FlatJson)
+        .with_writer(make_writer)
+}
+
+/// Initialize logging from environment variables and, when running an
+/// agent, its `[observability]` section
+///
+/// `LOG_FORMAT` takes precedence over `observability.log_format`.
+pub fn init_default_logging(agent: Option<&AgentConfig>) {
     let log_level = env::var("LOG_LEVEL").unwrap_or_else(|_| "INFO".to_string());
 
     let level = match log_level.to_uppercase().as_str() {
@@ -123,38 +194,72 @@ pub fn init_default_logging() {
         _ => Level::INFO,
     };
 
-    let format = env::var("LOG_FORMAT").unwrap_or_else(|_| "json".to_string());
-    let log_format = LogFormat::parse(&format);
+    let observability = agent.map(|config| &config.observability);
+    let log_format = match env::var("LOG_FORMAT") {
+        Ok(format) => LogFormat::parse(&format),
+        Err(_) => observability
+            .and_then(|o| o.log_format)
+            .unwrap_or(LogFormat::Json),
+    };
 
     let include_spans = env::var("LOG_SPANS")
         .unwrap_or_else(|_| "false".to_string())
         .to_lowercase()
         == "true";
 
-    init_logging(level, log_format, include_spans);
+    let trace_export = agent
+        .and_then(|config| TraceExport::for_agent(&config.agent.id, &config.observability.otel));
+
+    init_logging(
+        level,
+        log_format,
+        include_spans,
+        observability.and_then(|o| o.log_file.as_ref()),
+        trace_export.as_ref(),
+    );
 }
 
-/// Create a task processing span with contextual information
+/// Create a task processing span
+///
+/// `task_id`, `conversation_id` and `agent_id` are required, in this order,
+/// and recorded with `Display`, so every log line inside the task carries
+/// them. Further fields follow as in `tracing::info_span!`.
 #[macro_export]
 macro_rules! task_span {
-    ($($field:tt)*) => {
-        tracing::info_span!("task_processing", $($field)*)
+    (
+        task_id = $task_id:expr,
+        conversation_id = $conversation_id:expr,
+        agent_id = $agent_id:expr
+        $(, $($field:tt)*)?
+    ) => {
+        tracing::info_span!(
+            "task_processing",
+            task_id = %$task_id,
+            conversation_id = %$conversation_id,
+            agent_id = %$agent_id
+            $(, $($field)*)?
+        )
     };
 }
 
 /// Create a tool execution span
+///
+/// `tool_name` is required; inside a task span, log lines also inherit the
+/// task's ids.
 #[macro_export]
 macro_rules! tool_span {
-    ($($field:tt)*) => {
-        tracing::info_span!("tool_execution", $($field)*)
+    (tool_name = $tool_name:expr $(, $($field:tt)*)?) => {
+        tracing::info_span!("tool_execution", tool_name = %$tool_name $(, $($field)*)?)
     };
 }
 
 /// Create an MQTT operation span
+///
+/// `agent_id` is required.
 #[macro_export]
 macro_rules! mqtt_span {
-    ($($field:tt)*) => {
-        tracing::info_span!("mqtt_operation", $($field)*)
+    (agent_id = $agent_id:expr $(, $($field:tt)*)?) => {
+        tracing::info_span!("mqtt_operation", agent_id = %$agent_id $(, $($field)*)?)
     };
 }
 
@@ -1,11 +1,13 @@
 //! Thread-safe metrics collection system
 //!
 //! Provides atomic counters and mutex-protected collections for tracking
-//! operational statistics across task processing, MQTT transport, and tools.
+//! operational statistics across task processing, the nine processing
+//! steps, MQTT transport, tools, and routing.
 
+use super::prometheus::{HistogramSample, MetricType, PrometheusWriter};
 use once_cell::sync::Lazy;
 use serde::Serialize;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Mutex;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
@@ -28,6 +30,15 @@ pub struct MetricsCollector {
     tasks_rejected: AtomicU64,
     current_pipeline_depth: AtomicU64,
     max_pipeline_depth_reached: AtomicU64,
+    workflow_cycles_detected: AtomicU64,
+    sticky_routes: AtomicU64,
+    tasks_in_flight: AtomicU64,
+    tasks_retried: AtomicU64,
+    task_panics: AtomicU64,
+    tasks_stale: AtomicU64,
+    tasks_unauthorized: AtomicU64,
+    idempotency_cache_size: AtomicU64,
+    idempotency_evictions: AtomicU64,
 
     // MQTT metrics (atomic for high frequency)
     mqtt_connected: AtomicBool,
@@ -37,8 +48,10 @@ pub struct MetricsCollector {
     messages_published: AtomicU64,
     publish_failures: AtomicU64,
     messages_received: AtomicU64,
+    signature_failures: AtomicU64,
     last_heartbeat: AtomicU64,
     connection_start_time: AtomicU64,
+    mqtt_reconnects: AtomicU64,
 
     // Processing times (mutex protected for complex operations)
     processing_times: Mutex<Vec<u64>>, // in milliseconds
@@ -46,6 +59,15 @@ pub struct MetricsCollector {
     // Tool statistics (mutex protected for complex data)
     tool_stats: Mutex<HashMap<String, ToolExecutionStats>>,
 
+    // Routing decision statistics (mutex protected for complex data)
+    routing_stats: Mutex<RoutingStats>,
+
+    // LLM request, token, cost and latency statistics by model
+    llm_stats: Mutex<HashMap<String, LlmModelRecord>>,
+
+    // Nine-step algorithm latency by step number and success
+    step_stats: Mutex<BTreeMap<(u8, bool), StepLatencyRecord>>,
+
     // Lifecycle metrics
     agent_state: Mutex<String>,
     uptime_start: AtomicU64,
@@ -53,6 +75,9 @@ pub struct MetricsCollector {
     restarts: AtomicU64,
     health_status: AtomicBool,
     last_health_check: AtomicU64,
+    paused: AtomicBool,
+    idle: AtomicBool,
+    idle_transitions: AtomicU64,
 }
 
 impl MetricsCollector {
@@ -65,6 +90,11 @@ impl MetricsCollector {
         AtomicU64,
         AtomicU64,
         AtomicU64,
+        AtomicU64,
+        AtomicU64,
+        AtomicU64,
+        AtomicU64,
+        AtomicU64,
     ) {
         (
             AtomicU64::new(0), // tasks_received
@@ -74,6 +104,11 @@ impl MetricsCollector {
             AtomicU64::new(0), // tasks_rejected
             AtomicU64::new(0), // current_pipeline_depth
             AtomicU64::new(0), // max_pipeline_depth_reached
+            AtomicU64::new(0), // workflow_cycles_detected
+            AtomicU64::new(0), // sticky_routes
+            AtomicU64::new(0), // tasks_in_flight
+            AtomicU64::new(0), // tasks_retried
+            AtomicU64::new(0), // task_panics
         )
     }
 
@@ -88,6 +123,7 @@ impl MetricsCollector {
         AtomicU64,
         AtomicU64,
         AtomicU64,
+        AtomicU64,
     ) {
         (
             AtomicBool::new(false), // mqtt_connected
@@ -97,6 +133,7 @@ impl MetricsCollector {
             AtomicU64::new(0),      // messages_published
             AtomicU64::new(0),      // publish_failures
             AtomicU64::new(0),      // messages_received
+            AtomicU64::new(0),      // signature_failures
             AtomicU64::new(0),      // last_heartbeat
             AtomicU64::new(0),      // connection_start_time
         )
@@ -112,6 +149,7 @@ impl MetricsCollector {
         AtomicU64,
         AtomicBool,
         AtomicU64,
+        AtomicBool,
     ) {
         (
             Mutex::new("initializing".to_string()), // agent_state
@@ -120,6 +158,7 @@ impl MetricsCollector {
             AtomicU64::new(0),                      // restarts
             AtomicBool::new(true),                  // health_status
             AtomicU64::new(now),                    // last_health_check
+            AtomicBool::new(false),                 // paused
         )
     }
 
@@ -134,6 +173,11 @@ impl MetricsCollector {
             tasks_rejected,
             current_pipeline_depth,
             max_pipeline_depth_reached,
+            workflow_cycles_detected,
+            sticky_routes,
+            tasks_in_flight,
+            tasks_retried,
+            task_panics,
         ) = Self::init_task_metrics();
         let (
             mqtt_connected,
@@ -143,6 +187,7 @@ impl MetricsCollector {
             messages_published,
             publish_failures,
             messages_received,
+            signature_failures,
             last_heartbeat,
             connection_start_time,
         ) = Self::init_mqtt_metrics();
@@ -153,6 +198,7 @@ impl MetricsCollector {
             restarts,
             health_status,
             last_health_check,
+            paused,
         ) = Self::init_lifecycle_metrics(now);
 
         Self {
@@ -163,6 +209,15 @@ impl MetricsCollector {
             tasks_rejected,
             current_pipeline_depth,
             max_pipeline_depth_reached,
+            workflow_cycles_detected,
+            sticky_routes,
+            tasks_in_flight,
+            tasks_retried,
+            task_panics,
+            tasks_stale: AtomicU64::new(0),
+            tasks_unauthorized: AtomicU64::new(0),
+            idempotency_cache_size: AtomicU64::new(0),
+            idempotency_evictions: AtomicU64::new(0),
             mqtt_connected,
             connection_attempts,
             connections_established,
@@ -170,16 +225,24 @@ impl MetricsCollector {
             messages_published,
             publish_failures,
             messages_received,
+            signature_failures,
             last_heartbeat,
             connection_start_time,
+            mqtt_reconnects: AtomicU64::new(0),
             processing_times: Mutex::new(Vec::new()),
             tool_stats: Mutex::new(HashMap::new()),
+            routing_stats: Mutex::new(RoutingStats::default()),
+            llm_stats: Mutex::new(HashMap::new()),
+            step_stats: Mutex::new(BTreeMap::new()),
             agent_state,
             uptime_start,
             state_transitions,
             restarts,
             health_status,
             last_health_check,
+            paused,
+            idle: AtomicBool::new(false),
+            idle_transitions: AtomicU64::new(0),
         }
     }
 
@@ -226,6 +289,79 @@ impl MetricsCollector {
         self.tasks_rejected.fetch_add(1, Ordering::Relaxed);
     }
 
+    /// A task whose processing started was rejected before any work was done
+    pub fn task_processing_rejected(&self) {
+        self.tasks_rejected.fetch_add(1, Ordering::Relaxed);
+        self.tasks_processing.fetch_sub(1, Ordering::Relaxed);
+        self.current_pipeline_depth.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    pub fn workflow_cycle_detected(&self) {
+        self.workflow_cycles_detected
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn set_sticky_routes(&self, count: usize) {
+        self.sticky_routes.store(count as u64, Ordering::Relaxed);
+    }
+
+    /// A task was accepted by a pipeline worker pool
+    pub fn task_in_flight(&self) {
+        self.tasks_in_flight.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// A task accepted by a pipeline worker pool finished
+    pub fn task_settled(&self) {
+        self.tasks_in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    /// A failed task attempt is being retried
+    pub fn task_retried(&self) {
+        self.tasks_retried.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Processing a task panicked; the panic was contained to that task
+    pub fn task_panicked(&self) {
+        self.task_panics.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Tasks whose processing panicked since startup
+    pub fn task_panics(&self) -> u64 {
+        self.task_panics.load(Ordering::Relaxed)
+    }
+
+    /// A task older than `agent.max_task_age_secs` was rejected at intake
+    pub fn task_stale(&self) {
+        self.tasks_stale.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Tasks rejected as stale since startup
+    pub fn tasks_stale(&self) -> u64 {
+        self.tasks_stale.load(Ordering::Relaxed)
+    }
+
+    /// A task was denied by the `[authorization]` rules
+    pub fn task_unauthorized(&self) {
+        self.tasks_unauthorized.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Tasks denied by the authorization rules since startup
+    pub fn tasks_unauthorized(&self) -> u64 {
+        self.tasks_unauthorized.load(Ordering::Relaxed)
+    }
+
+    /// Task ids currently held by the in-memory idempotency cache
+    pub fn set_idempotency_cache_size(&self, size: usize) {
+        self.idempotency_cache_size
+            .store(size as u64, Ordering::Relaxed);
+    }
+
+    /// Task ids dropped from the idempotency cache, expired or over capacity
+    pub fn idempotency_evictions(&self, count: usize) {
+        self.idempotency_evictions
+            .fetch_add(count as u64, Ordering::Relaxed);
+    }
+
     fn record_processing_time(&self, duration: Duration) {
         if let Ok(mut times) = self.processing_times.lock() {
             times.push(duration.as_millis() as u64);
@@ -242,8 +378,11 @@ impl MetricsCollector {
         self.connection_attempts.fetch_add(1, Ordering::Relaxed);
     }
 
+    /// The broker acknowledged a connection; every one after the first is a reconnect
     pub fn mqtt_connection_established(&self) {
-        self.connections_established.fetch_add(1, Ordering::Relaxed);
+        if self.connections_established.fetch_add(1, Ordering::Relaxed) > 0 {
+            self.mqtt_reconnects.fetch_add(1, Ordering::Relaxed);
+        }
         self.mqtt_connected.store(true, Ordering::Relaxed);
         self.connection_start_time
             .store(current_timestamp(), Ordering::Relaxed);
@@ -271,6 +410,10 @@ impl MetricsCollector {
         self.messages_received.fetch_add(1, Ordering::Relaxed);
     }
 
+    pub fn mqtt_signature_failed(&self) {
+        self.signature_failures.fetch_add(1, Ordering::Relaxed);
+    }
+
     pub fn mqtt_heartbeat(&self) {
         self.last_heartbeat
             .store(current_timestamp(), Ordering::Relaxed);
@@ -290,6 +433,7 @@ impl MetricsCollector {
                 timeouts: 0,
                 execution_times: Vec::new(),
                 last_execution: 0,
+                latency: LatencyHistogram::default(),
             })
     }
 
@@ -302,6 +446,9 @@ impl MetricsCollector {
         tool_stats.executions += 1;
         tool_stats.last_execution = current_timestamp();
         tool_stats.execution_times.push(duration.as_millis() as u64);
+        tool_stats
+            .latency
+            .record(&TOOL_LATENCY_BUCKETS_MS, duration);
 
         // Limit execution times to prevent unbounded growth
         if tool_stats.execution_times.len() > 1000 {
@@ -329,6 +476,110 @@ impl MetricsCollector {
         }
     }
 
+    // LLM metrics
+    /// An LLM request to `model` succeeded after `latency`, using the given
+    /// tokens; `cost_usd` is `None` when the model has no configured price
+    pub fn llm_request_completed(
+        &self,
+        model: &str,
+        prompt_tokens: u64,
+        completion_tokens: u64,
+        latency: Duration,
+        cost_usd: Option<f64>,
+    ) {
+        if let Ok(mut stats) = self.llm_stats.lock() {
+            let record = stats.entry(model.to_string()).or_default();
+            record.requests += 1;
+            record.prompt_tokens += prompt_tokens;
+            record.completion_tokens += completion_tokens;
+            match cost_usd {
+                Some(cost_usd) => record.estimated_cost_usd += cost_usd,
+                None => record.unpriced_requests += 1,
+            }
+            record.latency.record(&LLM_LATENCY_BUCKETS_MS, latency);
+        }
+    }
+
+    /// A successful LLM request to `model` read and wrote the given prompt
+    /// tokens from the provider's prompt cache
+    pub fn llm_prompt_cache_used(
+        &self,
+        model: &str,
+        cache_read_tokens: u64,
+        cache_creation_tokens: u64,
+    ) {
+        if let Ok(mut stats) = self.llm_stats.lock() {
+            let record = stats.entry(model.to_string()).or_default();
+            record.cache_read_tokens += cache_read_tokens;
+            record.cache_creation_tokens += cache_creation_tokens;
+        }
+    }
+
+    /// An LLM request to `model` failed after `latency`
+    pub fn llm_request_failed(&self, model: &str, latency: Duration) {
+        if let Ok(mut stats) = self.llm_stats.lock() {
+            let record = stats.entry(model.to_string()).or_default();
+            record.requests += 1;
+            record.errors += 1;
+            record.latency.record(&LLM_LATENCY_BUCKETS_MS, latency);
+        }
+    }
+
+    // Step metrics
+    /// Step `step` (1-9) of the nine-step algorithm finished or failed after `duration`
+    pub fn step_completed(&self, step: u8, duration: Duration, success: bool) {
+        if let Ok(mut stats) = self.step_stats.lock() {
+            stats.entry((step, success)).or_default().record(duration);
+        }
+    }
+
+    // Routing metrics
+    pub fn routing_decision(
+        &self,
+        decision: &str,
+        next_agent: Option<&str>,
+        latency: Option<Duration>,
+    ) {
+        if let Ok(mut stats) = self.routing_stats.lock() {
+            *stats.decisions.entry(decision.to_string()).or_insert(0) += 1;
+            if let Some(next_agent) = next_agent {
+                *stats
+                    .forwards_by_agent
+                    .entry(next_agent.to_string())
+                    .or_insert(0) += 1;
+            }
+            if let Some(latency) = latency {
+                stats.record_latency(latency);
+            }
+        }
+    }
+
+    /// Agents currently in the discovery registry
+    pub fn set_registry_agents(&self, count: usize) {
+        if let Ok(mut stats) = self.routing_stats.lock() {
+            stats.registry_agents = count as u64;
+        }
+    }
+
+    /// Agents evicted from the discovery registry: `expired` or `unavailable`
+    pub fn registry_evictions(&self, reason: &str, count: usize) {
+        if let Ok(mut stats) = self.routing_stats.lock() {
+            *stats
+                .registry_evictions
+                .entry(reason.to_string())
+                .or_insert(0) += count as u64;
+        }
+    }
+
+    pub fn routing_error(&self, latency: Option<Duration>) {
+        if let Ok(mut stats) = self.routing_stats.lock() {
+            stats.router_errors += 1;
+            if let Some(latency) = latency {
+                stats.record_latency(latency);
+            }
+        }
+    }
+
     // Lifecycle metrics
     pub fn set_agent_state(&self, state: &str) {
         if let Ok(mut current_state) = self.agent_state.lock() {
@@ -345,6 +596,26 @@ impl MetricsCollector {
             .store(current_timestamp(), Ordering::Relaxed);
     }
 
+    /// Record whether task intake is paused by an admin request
+    pub fn set_paused(&self, paused: bool) {
+        self.paused.store(paused, Ordering::Relaxed);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    /// Record whether the agent is idle, counting each change between idle and active
+    pub fn set_idle(&self, idle: bool) {
+        if self.idle.swap(idle, Ordering::Relaxed) != idle {
+            self.idle_transitions.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn is_idle(&self) -> bool {
+        self.idle.load(Ordering::Relaxed)
+    }
+
     // Health status metrics
     pub fn update_health_status(&self, healthy: bool) {
         self.health_status.store(healthy, Ordering::Relaxed);
@@ -361,6 +632,15 @@ impl MetricsCollector {
         self.tasks_rejected.store(0, Ordering::Relaxed);
         self.current_pipeline_depth.store(0, Ordering::Relaxed);
         self.max_pipeline_depth_reached.store(0, Ordering::Relaxed);
+        self.workflow_cycles_detected.store(0, Ordering::Relaxed);
+        self.sticky_routes.store(0, Ordering::Relaxed);
+        self.tasks_in_flight.store(0, Ordering::Relaxed);
+        self.tasks_retried.store(0, Ordering::Relaxed);
+        self.task_panics.store(0, Ordering::Relaxed);
+        self.tasks_stale.store(0, Ordering::Relaxed);
+        self.tasks_unauthorized.store(0, Ordering::Relaxed);
+        self.idempotency_cache_size.store(0, Ordering::Relaxed);
+        self.idempotency_evictions.store(0, Ordering::Relaxed);
     }
 
     /// Reset MQTT metrics (pure function)
@@ -372,8 +652,10 @@ impl MetricsCollector {
         self.messages_published.store(0, Ordering::Relaxed);
         self.publish_failures.store(0, Ordering::Relaxed);
         self.messages_received.store(0, Ordering::Relaxed);
+        self.signature_failures.store(0, Ordering::Relaxed);
         self.last_heartbeat.store(0, Ordering::Relaxed);
         self.connection_start_time.store(0, Ordering::Relaxed);
+        self.mqtt_reconnects.store(0, Ordering::Relaxed);
     }
 
     /// Reset lifecycle metrics (pure function)
@@ -384,6 +666,9 @@ impl MetricsCollector {
         self.uptime_start.store(now, Ordering::Relaxed);
         self.health_status.store(true, Ordering::Relaxed);
         self.last_health_check.store(now, Ordering::Relaxed);
+        self.paused.store(false, Ordering::Relaxed);
+        self.idle.store(false, Ordering::Relaxed);
+        self.idle_transitions.store(0, Ordering::Relaxed);
     }
 
     /// Reset mutex-protected collections (pure function)
@@ -394,6 +679,15 @@ impl MetricsCollector {
         if let Ok(mut stats) = self.tool_stats.lock() {
             stats.clear();
         }
+        if let Ok(mut stats) = self.routing_stats.lock() {
+            *stats = RoutingStats::default();
+        }
+        if let Ok(mut stats) = self.llm_stats.lock() {
+            stats.clear();
+        }
+        if let Ok(mut stats) = self.step_stats.lock() {
+            stats.clear();
+        }
         if let Ok(mut state) = self.agent_state.lock() {
             *state = "initializing".to_string();
         }
@@ -500,6 +794,76 @@ impl MetricsCollector {
         }
     }
 
+    /// Build routing statistics summary (pure function)
+    fn build_routing_metrics(&self) -> RoutingMetrics {
+        let Ok(stats) = self.routing_stats.lock() else {
+            return RoutingMetrics::default();
+        };
+
+        let mut sorted_times = stats.latency_times.clone();
+        sorted_times.sort_unstable();
+        let avg_router_latency_ms = if sorted_times.is_empty() {
+            0.0
+        } else {
+            sorted_times.iter().sum::<u64>() as f64 / sorted_times.len() as f64
+        };
+
+        let router_latency_histogram = stats.latency.to_buckets(&ROUTER_LATENCY_BUCKETS_MS);
+
+        RoutingMetrics {
+            decisions: stats.decisions.clone(),
+            forwards_by_agent: stats.forwards_by_agent.clone(),
+            router_errors: stats.router_errors,
+            avg_router_latency_ms,
+            router_latency_p50_ms: percentile(&sorted_times, 50.0),
+            router_latency_p95_ms: percentile(&sorted_times, 95.0),
+            router_latency_p99_ms: percentile(&sorted_times, 99.0),
+            router_latency_histogram,
+            registry_agents: stats.registry_agents,
+            registry_evictions: stats.registry_evictions.clone(),
+        }
+    }
+
+    /// Build LLM statistics summary (pure function)
+    fn build_llm_metrics(&self) -> LlmMetrics {
+        let Ok(stats) = self.llm_stats.lock() else {
+            return LlmMetrics::default();
+        };
+        let models: HashMap<String, LlmModelStats> = stats
+            .iter()
+            .map(|(model, record)| (model.clone(), record.snapshot()))
+            .collect();
+        let total_prompt_tokens = models.values().map(|s| s.prompt_tokens).sum();
+        let total_cache_read_tokens = models.values().map(|s| s.cache_read_tokens).sum();
+        let total_cache_creation_tokens = models.values().map(|s| s.cache_creation_tokens).sum();
+        LlmMetrics {
+            total_requests: models.values().map(|s| s.requests).sum(),
+            total_errors: models.values().map(|s| s.errors).sum(),
+            total_prompt_tokens,
+            total_completion_tokens: models.values().map(|s| s.completion_tokens).sum(),
+            total_cache_read_tokens,
+            total_cache_creation_tokens,
+            cache_hit_ratio: cache_hit_ratio(
+                total_prompt_tokens,
+                total_cache_read_tokens,
+                total_cache_creation_tokens,
+            ),
+            total_estimated_cost_usd: models.values().map(|s| s.estimated_cost_usd).sum(),
+            models,
+        }
+    }
+
+    /// Build per-step latency statistics, ordered by step (pure function)
+    fn build_step_metrics(&self) -> Vec<StepLatencyStats> {
+        let Ok(stats) = self.step_stats.lock() else {
+            return Vec::new();
+        };
+        stats
+            .iter()
+            .map(|(&(step, success), record)| record.snapshot(step, success))
+            .collect()
+    }
+
     /// Calculate connection duration (pure function)
     fn calculate_connection_duration(&self, now: u64) -> u64 {
         if self.mqtt_connected.load(Ordering::Relaxed) {
@@ -561,6 +925,15 @@ impl MetricsCollector {
                 current_pipeline_depth: self.current_pipeline_depth.load(Ordering::Relaxed) as u32,
                 max_pipeline_depth_reached: self.max_pipeline_depth_reached.load(Ordering::Relaxed)
                     as u32,
+                workflow_cycles_detected: self.workflow_cycles_detected.load(Ordering::Relaxed),
+                sticky_routes: self.sticky_routes.load(Ordering::Relaxed),
+                tasks_in_flight: self.tasks_in_flight.load(Ordering::Relaxed),
+                tasks_retried: self.tasks_retried.load(Ordering::Relaxed),
+                task_panics_total: self.task_panics.load(Ordering::Relaxed),
+                tasks_stale: self.tasks_stale.load(Ordering::Relaxed),
+                tasks_unauthorized: self.tasks_unauthorized.load(Ordering::Relaxed),
+                idempotency_cache_size: self.idempotency_cache_size.load(Ordering::Relaxed),
+                idempotency_evictions: self.idempotency_evictions.load(Ordering::Relaxed),
             },
             mqtt: MqttMetrics {
                 connected: self.mqtt_connected.load(Ordering::Relaxed),
@@ -570,8 +943,10 @@ impl MetricsCollector {
                 messages_published: self.messages_published.load(Ordering::Relaxed),
                 publish_failures: self.publish_failures.load(Ordering::Relaxed),
                 messages_received: self.messages_received.load(Ordering::Relaxed),
+                signature_failures: self.signature_failures.load(Ordering::Relaxed),
                 last_heartbeat: self.last_heartbeat.load(Ordering::Relaxed),
                 connection_duration_seconds,
+                reconnects: self.mqtt_reconnects.load(Ordering::Relaxed),
             },
             tools: ToolMetrics {
                 tool_stats: tool_stats_map,
@@ -580,6 +955,9 @@ impl MetricsCollector {
                 total_timeouts: total_tool_timeouts,
                 avg_execution_time_ms: avg_tool_time,
             },
+            routing: self.build_routing_metrics(),
+            llm: self.build_llm_metrics(),
+            steps: self.build_step_metrics(),
             lifecycle: LifecycleMetrics {
                 current_state,
                 uptime_seconds,
@@ -587,6 +965,9 @@ impl MetricsCollector {
                 restarts: self.restarts.load(Ordering::Relaxed),
                 healthy: self.health_status.load(Ordering::Relaxed),
                 last_health_check: self.last_health_check.load(Ordering::Relaxed),
+                paused: self.paused.load(Ordering::Relaxed),
+                idle: self.idle.load(Ordering::Relaxed),
+                idle_transitions: self.idle_transitions.load(Ordering::Relaxed),
             },
             timestamp,
         }
@@ -611,6 +992,464 @@ impl MetricsCollector {
             now,
         )
     }
+
+    /// Render all metrics in the Prometheus text exposition format
+    ///
+    /// Metric names are prefixed `agent2389_` and every sample carries an
+    /// `agent_id` label. Counters are totals since startup (or `reset`);
+    /// latency histograms are cumulative and in seconds.
+    pub fn render_prometheus(&self, agent_id: &str) -> String {
+        let snapshot = self.get_metrics();
+        let mut w = PrometheusWriter::new(agent_id);
+
+        Self::render_task_metrics(&mut w, &snapshot.tasks);
+        self.render_step_metrics(&mut w);
+        Self::render_lifecycle_metrics(&mut w, &snapshot.lifecycle);
+        Self::render_mqtt_metrics(&mut w, &snapshot.mqtt);
+        self.render_llm_metrics(&mut w, &snapshot.llm);
+        self.render_tool_metrics(&mut w);
+        self.render_routing_metrics(&mut w, &snapshot.routing);
+
+        w.finish()
+    }
+
+    fn render_task_metrics(w: &mut PrometheusWriter, tasks: &TaskMetrics) {
+        w.family(
+            "tasks_total",
+            MetricType::Counter,
+            "Tasks that finished processing, by outcome",
+        );
+        for (outcome, count) in [
+            ("completed", tasks.tasks_completed),
+            ("failed", tasks.tasks_failed),
+            ("rejected", tasks.tasks_rejected),
+        ] {
+            w.sample("tasks_total", &[("outcome", outcome)], count as f64);
+        }
+
+        let counters = [
+            (
+                "tasks_received_total",
+                "Tasks received",
+                tasks.tasks_received,
+            ),
+            (
+                "tasks_retried_total",
+                "Task attempts retried after a retryable failure",
+                tasks.tasks_retried,
+            ),
+            (
+                "tasks_stale_total",
+                "Tasks rejected at intake as too old",
+                tasks.tasks_stale,
+            ),
+            (
+                "tasks_unauthorized_total",
+                "Tasks denied by the authorization rules",
+                tasks.tasks_unauthorized,
+            ),
+            (
+                "task_panics_total",
+                "Tasks whose processing panicked",
+                tasks.task_panics_total,
+            ),
+            (
+                "workflow_cycles_detected_total",
+                "Workflow cycles detected",
+                tasks.workflow_cycles_detected,
+            ),
+            (
+                "idempotency_evictions_total",
+                "Processed task ids dropped from the idempotency cache",
+                tasks.idempotency_evictions,
+            ),
+        ];
+        for (name, help, value) in counters {
+            w.family(name, MetricType::Counter, help);
+            w.sample(name, &[], value as f64);
+        }
+
+        let gauges = [
+            (
+                "tasks_processing",
+                "Tasks currently being processed",
+                tasks.tasks_processing,
+            ),
+            (
+                "task_queue_depth",
+                "Tasks accepted by the pipeline that have not finished, including queued ones",
+                tasks.tasks_in_flight,
+            ),
+            (
+                "sticky_routes",
+                "Conversations currently pinned by sticky routing",
+                tasks.sticky_routes,
+            ),
+            (
+                "idempotency_cache_size",
+                "Processed task ids held in memory for duplicate detection",
+                tasks.idempotency_cache_size,
+            ),
+        ];
+        for (name, help, value) in gauges {
+            w.family(name, MetricType::Gauge, help);
+            w.sample(name, &[], value as f64);
+        }
+    }
+
+    fn render_step_metrics(&self, w: &mut PrometheusWriter) {
+        let Ok(stats) = self.step_stats.lock() else {
+            return;
+        };
+        w.family(
+            "task_step_duration_seconds",
+            MetricType::Histogram,
+            "Nine-step algorithm step duration, by step and outcome",
+        );
+        for (&(step, success), record) in stats.iter() {
+            w.histogram(
+                "task_step_duration_seconds",
+                &[("step", &step.to_string()), ("outcome", outcome(success))],
+                &record.latency.to_sample(&STEP_LATENCY_BUCKETS_MS),
+            );
+        }
+    }
+
+    fn render_lifecycle_metrics(w: &mut PrometheusWriter, lifecycle: &LifecycleMetrics) {
+        w.family(
+            "agent_state",
+            MetricType::Gauge,
+            "Current lifecycle state of the agent; 1 for the current state",
+        );
+        let current = lifecycle.current_state.as_str();
+        for state in AGENT_STATES {
+            w.sample(
+                "agent_state",
+                &[("state", state)],
+                f64::from(state == current),
+            );
+        }
+        if !AGENT_STATES.contains(&current) {
+            w.sample("agent_state", &[("state", current)], 1.0);
+        }
+
+        let gauges = [
+            (
+                "agent_healthy",
+                "Whether the last health check passed",
+                f64::from(lifecycle.healthy),
+            ),
+            (
+                "agent_paused",
+                "Whether task intake is paused",
+                f64::from(lifecycle.paused),
+            ),
+            (
+                "agent_idle",
+                "Whether the agent is idle",
+                f64::from(lifecycle.idle),
+            ),
+            (
+                "agent_uptime_seconds",
+                "Seconds since the agent started",
+                lifecycle.uptime_seconds as f64,
+            ),
+        ];
+        for (name, help, value) in gauges {
+            w.family(name, MetricType::Gauge, help);
+            w.sample(name, &[], value);
+        }
+
+        let counters = [
+            (
+                "agent_state_transitions_total",
+                "Lifecycle state changes",
+                lifecycle.state_transitions,
+            ),
+            ("agent_restarts_total", "Agent restarts", lifecycle.restarts),
+        ];
+        for (name, help, value) in counters {
+            w.family(name, MetricType::Counter, help);
+            w.sample(name, &[], value as f64);
+        }
+    }
+
+    fn render_mqtt_metrics(w: &mut PrometheusWriter, mqtt: &MqttMetrics) {
+        w.family(
+            "mqtt_connected",
+            MetricType::Gauge,
+            "Whether the agent is connected to the MQTT broker",
+        );
+        w.sample("mqtt_connected", &[], f64::from(mqtt.connected));
+
+        let counters = [
+            (
+                "mqtt_reconnects_total",
+                "Connections to the broker after the first",
+                mqtt.reconnects,
+            ),
+            (
+                "mqtt_connection_failures_total",
+                "Failed connection attempts",
+                mqtt.connection_failures,
+            ),
+            (
+                "mqtt_messages_published_total",
+                "Messages published",
+                mqtt.messages_published,
+            ),
+            (
+                "mqtt_publish_failures_total",
+                "Failed publishes",
+                mqtt.publish_failures,
+            ),
+            (
+                "mqtt_messages_received_total",
+                "Messages received",
+                mqtt.messages_received,
+            ),
+            (
+                "mqtt_signature_failures_total",
+                "Messages rejected for a bad signature",
+                mqtt.signature_failures,
+            ),
+        ];
+        for (name, help, value) in counters {
+            w.family(name, MetricType::Counter, help);
+            w.sample(name, &[], value as f64);
+        }
+    }
+
+    fn render_llm_metrics(&self, w: &mut PrometheusWriter, llm: &LlmMetrics) {
+        let mut models: Vec<_> = llm.models.iter().collect();
+        models.sort_by(|a, b| a.0.cmp(b.0));
+
+        w.family(
+            "llm_requests_total",
+            MetricType::Counter,
+            "LLM requests by model and outcome",
+        );
+        for (model, stats) in &models {
+            let succeeded = stats.requests - stats.errors;
+            w.sample(
+                "llm_requests_total",
+                &[("model", model), ("outcome", "success")],
+                succeeded as f64,
+            );
+            w.sample(
+                "llm_requests_total",
+                &[("model", model), ("outcome", "error")],
+                stats.errors as f64,
+            );
+        }
+
+        w.family(
+            "llm_tokens_total",
+            MetricType::Counter,
+            "LLM tokens used, by model and kind",
+        );
+        for (model, stats) in &models {
+            w.sample(
+                "llm_tokens_total",
+                &[("model", model), ("kind", "prompt")],
+                stats.prompt_tokens as f64,
+            );
+            w.sample(
+                "llm_tokens_total",
+                &[("model", model), ("kind", "completion")],
+                stats.completion_tokens as f64,
+            );
+            if stats.cache_hit_ratio.is_some() {
+                w.sample(
+                    "llm_tokens_total",
+                    &[("model", model), ("kind", "cache_read")],
+                    stats.cache_read_tokens as f64,
+                );
+                w.sample(
+                    "llm_tokens_total",
+                    &[("model", model), ("kind", "cache_creation")],
+                    stats.cache_creation_tokens as f64,
+                );
+            }
+        }
+
+        let cache_hit_ratios: Vec<_> = models
+            .iter()
+            .filter_map(|(model, stats)| stats.cache_hit_ratio.map(|ratio| (model, ratio)))
+            .collect();
+        if !cache_hit_ratios.is_empty() {
+            w.family(
+                "llm_cache_hit_ratio",
+                MetricType::Gauge,
+                "Share of LLM prompt tokens read from the prompt cache, by model",
+            );
+            for (model, ratio) in cache_hit_ratios {
+                w.sample("llm_cache_hit_ratio", &[("model", model)], ratio);
+            }
+        }
+
+        w.family(
+            "llm_estimated_cost_usd_total",
+            MetricType::Counter,
+            "Estimated LLM spend in USD from llm.prices, by model",
+        );
+        for (model, stats) in &models {
+            w.sample(
+                "llm_estimated_cost_usd_total",
+                &[("model", model)],
+                stats.estimated_cost_usd,
+            );
+        }
+
+        let Ok(records) = self.llm_stats.lock() else {
+            return;
+        };
+        w.family(
+            "llm_request_duration_seconds",
+            MetricType::Histogram,
+            "LLM request latency, by model",
+        );
+        for (model, _) in &models {
+            if let Some(record) = records.get(*model) {
+                w.histogram(
+                    "llm_request_duration_seconds",
+                    &[("model", model)],
+                    &record.latency.to_sample(&LLM_LATENCY_BUCKETS_MS),
+                );
+            }
+        }
+    }
+
+    fn render_tool_metrics(&self, w: &mut PrometheusWriter) {
+        let Ok(stats) = self.tool_stats.lock() else {
+            return;
+        };
+        let mut tools: Vec<_> = stats.values().collect();
+        tools.sort_by(|a, b| a.name.cmp(&b.name));
+
+        w.family(
+            "tool_executions_total",
+            MetricType::Counter,
+            "Tool executions by tool and outcome",
+        );
+        for tool in &tools {
+            let succeeded = tool.executions - tool.failures;
+            w.sample(
+                "tool_executions_total",
+                &[("tool", &tool.name), ("outcome", "success")],
+                succeeded as f64,
+            );
+            w.sample(
+                "tool_executions_total",
+                &[("tool", &tool.name), ("outcome", "failure")],
+                tool.failures as f64,
+            );
+        }
+
+        w.family(
+            "tool_timeouts_total",
+            MetricType::Counter,
+            "Tool executions that timed out",
+        );
+        for tool in &tools {
+            w.sample(
+                "tool_timeouts_total",
+                &[("tool", &tool.name)],
+                tool.timeouts as f64,
+            );
+        }
+
+        w.family(
+            "tool_duration_seconds",
+            MetricType::Histogram,
+            "Tool execution latency",
+        );
+        for tool in &tools {
+            w.histogram(
+                "tool_duration_seconds",
+                &[("tool", &tool.name)],
+                &tool.latency.to_sample(&TOOL_LATENCY_BUCKETS_MS),
+            );
+        }
+    }
+
+    fn render_routing_metrics(&self, w: &mut PrometheusWriter, routing: &RoutingMetrics) {
+        let mut decisions: Vec<_> = routing.decisions.iter().collect();
+        decisions.sort();
+        w.family(
+            "routing_decisions_total",
+            MetricType::Counter,
+            "Routing decisions by type",
+        );
+        for (decision, count) in decisions {
+            w.sample(
+                "routing_decisions_total",
+                &[("decision", decision)],
+                *count as f64,
+            );
+        }
+
+        let mut forwards: Vec<_> = routing.forwards_by_agent.iter().collect();
+        forwards.sort();
+        w.family(
+            "routing_forwards_total",
+            MetricType::Counter,
+            "Tasks forwarded, by target agent",
+        );
+        for (target, count) in forwards {
+            w.sample(
+                "routing_forwards_total",
+                &[("target_agent", target)],
+                *count as f64,
+            );
+        }
+
+        w.family(
+            "router_errors_total",
+            MetricType::Counter,
+            "Failed router calls and unresolvable forward targets",
+        );
+        w.sample("router_errors_total", &[], routing.router_errors as f64);
+
+        w.family(
+            "discovery_registry_agents",
+            MetricType::Gauge,
+            "Agents currently in the discovery registry",
+        );
+        w.sample(
+            "discovery_registry_agents",
+            &[],
+            routing.registry_agents as f64,
+        );
+
+        let mut evictions: Vec<_> = routing.registry_evictions.iter().collect();
+        evictions.sort();
+        w.family(
+            "discovery_registry_evictions_total",
+            MetricType::Counter,
+            "Agents evicted from the discovery registry, by reason",
+        );
+        for (reason, count) in evictions {
+            w.sample(
+                "discovery_registry_evictions_total",
+                &[("reason", reason)],
+                *count as f64,
+            );
+        }
+
+        if let Ok(stats) = self.routing_stats.lock() {
+            w.family(
+                "router_duration_seconds",
+                MetricType::Histogram,
+                "Router call latency",
+            );
+            w.histogram(
+                "router_duration_seconds",
+                &[],
+                &stats.latency.to_sample(&ROUTER_LATENCY_BUCKETS_MS),
+            );
+        }
+    }
 }
 
 impl Default for MetricsCollector {
@@ -628,6 +1467,259 @@ struct ToolExecutionStats {
     timeouts: u64,
     execution_times: Vec<u64>, // milliseconds
     last_execution: u64,
+    latency: LatencyHistogram,
+}
+
+/// Upper bounds (inclusive, milliseconds) of the router latency histogram
+/// buckets; slower calls land in a final overflow bucket
+const ROUTER_LATENCY_BUCKETS_MS: [u64; 8] = [10, 50, 100, 250, 500, 1000, 2500, 5000];
+
+/// Upper bounds (inclusive, milliseconds) of the tool latency histogram buckets
+const TOOL_LATENCY_BUCKETS_MS: [u64; 10] = [10, 50, 100, 250, 500, 1000, 2500, 5000, 10000, 30000];
+
+/// Upper bounds (inclusive, milliseconds) of the LLM request latency histogram buckets
+const LLM_LATENCY_BUCKETS_MS: [u64; 10] = [
+    250, 500, 1000, 2500, 5000, 10000, 20000, 30000, 60000, 120000,
+];
+
+/// Upper bounds (inclusive, milliseconds) of the processing step latency
+/// histogram buckets; validation steps take well under the first bound
+const STEP_LATENCY_BUCKETS_MS: [u64; 13] = [
+    1, 5, 10, 50, 100, 250, 500, 1000, 2500, 5000, 10000, 30000, 60000,
+];
+
+/// Known agent lifecycle states, exported even while not current
+const AGENT_STATES: [&str; 6] = [
+    "initializing",
+    "initialized",
+    "running",
+    "stopping",
+    "stopped",
+    "error",
+];
+
+/// Latency counts since startup, per bucket (not cumulative) plus the total
+#[derive(Debug, Default)]
+struct LatencyHistogram {
+    buckets: Vec<u64>,
+    sum_ms: u64,
+    count: u64,
+}
+
+impl LatencyHistogram {
+    fn record(&mut self, bounds_ms: &[u64], latency: Duration) {
+        let latency_ms = latency.as_millis() as u64;
+        if self.buckets.is_empty() {
+            self.buckets = vec![0; bounds_ms.len() + 1];
+        }
+        let bucket = bounds_ms
+            .iter()
+            .position(|&le_ms| latency_ms <= le_ms)
+            .unwrap_or(bounds_ms.len());
+        self.buckets[bucket] += 1;
+        self.sum_ms += latency_ms;
+        self.count += 1;
+    }
+
+    /// Count per bucket, including the overflow bucket (pure function)
+    fn bucket_counts(&self, bounds_ms: &[u64]) -> Vec<u64> {
+        if self.buckets.is_empty() {
+            vec![0; bounds_ms.len() + 1]
+        } else {
+            self.buckets.clone()
+        }
+    }
+
+    /// Count per bucket with its upper bound, for JSON snapshots (pure function)
+    fn to_buckets(&self, bounds_ms: &[u64]) -> Vec<LatencyBucket> {
+        bounds_ms
+            .iter()
+            .map(|&le_ms| Some(le_ms))
+            .chain(std::iter::once(None))
+            .zip(self.bucket_counts(bounds_ms))
+            .map(|(le_ms, count)| LatencyBucket { le_ms, count })
+            .collect()
+    }
+
+    /// Mean latency in milliseconds, 0 before the first record (pure function)
+    fn mean_ms(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            self.sum_ms as f64 / self.count as f64
+        }
+    }
+
+    /// Cumulative histogram in seconds (pure function)
+    fn to_sample(&self, bounds_ms: &[u64]) -> HistogramSample {
+        let buckets = bounds_ms
+            .iter()
+            .zip(self.bucket_counts(bounds_ms))
+            .scan(0, |cumulative, (&le_ms, count)| {
+                *cumulative += count;
+                Some((le_ms as f64 / 1000.0, *cumulative))
+            })
+            .collect();
+        HistogramSample {
+            buckets,
+            sum: self.sum_ms as f64 / 1000.0,
+            count: self.count,
+        }
+    }
+}
+
+/// Share of prompt tokens read from the prompt cache (pure function)
+///
+/// Cached tokens are reported apart from the uncached `prompt_tokens`, so
+/// the ratio is over all three; `None` when no cache usage was reported.
+fn cache_hit_ratio(
+    prompt_tokens: u64,
+    cache_read_tokens: u64,
+    cache_creation_tokens: u64,
+) -> Option<f64> {
+    if cache_read_tokens == 0 && cache_creation_tokens == 0 {
+        return None;
+    }
+    let total = prompt_tokens + cache_read_tokens + cache_creation_tokens;
+    Some(cache_read_tokens as f64 / total as f64)
+}
+
+/// LLM requests, tokens, cost and latency of one model since startup
+#[derive(Debug, Default)]
+struct LlmModelRecord {
+    requests: u64,
+    errors: u64,
+    prompt_tokens: u64,
+    completion_tokens: u64,
+    cache_read_tokens: u64,
+    cache_creation_tokens: u64,
+    estimated_cost_usd: f64,
+    unpriced_requests: u64,
+    latency: LatencyHistogram,
+}
+
+impl LlmModelRecord {
+    /// Public form of the record (pure function)
+    fn snapshot(&self) -> LlmModelStats {
+        LlmModelStats {
+            requests: self.requests,
+            errors: self.errors,
+            prompt_tokens: self.prompt_tokens,
+            completion_tokens: self.completion_tokens,
+            cache_read_tokens: self.cache_read_tokens,
+            cache_creation_tokens: self.cache_creation_tokens,
+            cache_hit_ratio: cache_hit_ratio(
+                self.prompt_tokens,
+                self.cache_read_tokens,
+                self.cache_creation_tokens,
+            ),
+            estimated_cost_usd: self.estimated_cost_usd,
+            unpriced_requests: self.unpriced_requests,
+            avg_latency_ms: self.latency.mean_ms(),
+            latency_histogram: self.latency.to_buckets(&LLM_LATENCY_BUCKETS_MS),
+        }
+    }
+}
+
+/// LLM requests, token usage, cost and latency of one model
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct LlmModelStats {
+    pub requests: u64,
+    pub errors: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    /// Prompt tokens read from the prompt cache, not included in `prompt_tokens`
+    pub cache_read_tokens: u64,
+    /// Prompt tokens written to the prompt cache, not included in `prompt_tokens`
+    pub cache_creation_tokens: u64,
+    /// Share of all prompt tokens read from the prompt cache; `None` until
+    /// the provider reports prompt cache usage
+    pub cache_hit_ratio: Option<f64>,
+    /// Estimated spend in USD of the successful requests with a configured price
+    pub estimated_cost_usd: f64,
+    /// Successful requests not included in the cost, as the model has no price
+    pub unpriced_requests: u64,
+    /// Mean latency of all requests, failed ones included
+    pub avg_latency_ms: f64,
+    /// Request counts per latency bucket (not cumulative)
+    pub latency_histogram: Vec<LatencyBucket>,
+}
+
+/// Latency of one processing step with one outcome since startup
+#[derive(Debug, Default)]
+struct StepLatencyRecord {
+    latency_times: Vec<u64>, // milliseconds
+    latency: LatencyHistogram,
+}
+
+impl StepLatencyRecord {
+    fn record(&mut self, duration: Duration) {
+        self.latency.record(&STEP_LATENCY_BUCKETS_MS, duration);
+
+        self.latency_times.push(duration.as_millis() as u64);
+        // Limit to last 1000 measurements to prevent unbounded growth
+        if self.latency_times.len() > 1000 {
+            self.latency_times.remove(0);
+        }
+    }
+
+    /// Public form of the record (pure function)
+    fn snapshot(&self, step: u8, success: bool) -> StepLatencyStats {
+        let mut sorted_times = self.latency_times.clone();
+        sorted_times.sort_unstable();
+        StepLatencyStats {
+            step,
+            outcome: outcome(success).to_string(),
+            count: self.latency.count,
+            avg_ms: self.latency.mean_ms(),
+            p50_ms: percentile(&sorted_times, 50.0),
+            p95_ms: percentile(&sorted_times, 95.0),
+            p99_ms: percentile(&sorted_times, 99.0),
+            latency_histogram: self.latency.to_buckets(&STEP_LATENCY_BUCKETS_MS),
+        }
+    }
+}
+
+/// Latency of one nine-step algorithm step with one outcome
+#[derive(Debug, Clone, Serialize)]
+pub struct StepLatencyStats {
+    /// Step number, 1-9
+    pub step: u8,
+    /// `success` or `failure`
+    pub outcome: String,
+    pub count: u64,
+    pub avg_ms: f64,
+    /// Percentiles over the last 1000 runs of the step
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub p99_ms: f64,
+    /// Run counts per latency bucket (not cumulative)
+    pub latency_histogram: Vec<LatencyBucket>,
+}
+
+// Internal routing statistics (with timing data)
+#[derive(Debug, Default)]
+struct RoutingStats {
+    decisions: HashMap<String, u64>,
+    forwards_by_agent: HashMap<String, u64>,
+    router_errors: u64,
+    latency_times: Vec<u64>, // milliseconds
+    latency: LatencyHistogram,
+    registry_agents: u64,
+    registry_evictions: HashMap<String, u64>,
+}
+
+impl RoutingStats {
+    fn record_latency(&mut self, latency: Duration) {
+        let latency_ms = latency.as_millis() as u64;
+        self.latency.record(&ROUTER_LATENCY_BUCKETS_MS, latency);
+
+        self.latency_times.push(latency_ms);
+        // Limit to last 1000 measurements to prevent unbounded growth
+        if self.latency_times.len() > 1000 {
+            self.latency_times.remove(0);
+        }
+    }
 }
 
 // Public metrics structures
@@ -636,6 +1728,10 @@ pub struct MetricsSnapshot {
     pub tasks: TaskMetrics,
     pub mqtt: MqttMetrics,
     pub tools: ToolMetrics,
+    pub routing: RoutingMetrics,
+    pub llm: LlmMetrics,
+    /// Nine-step algorithm latency by step and outcome, ordered by step
+    pub steps: Vec<StepLatencyStats>,
     pub lifecycle: LifecycleMetrics,
     pub timestamp: u64,
 }
@@ -653,6 +1749,24 @@ pub struct TaskMetrics {
     pub processing_time_p99_ms: f64,
     pub current_pipeline_depth: u32,
     pub max_pipeline_depth_reached: u32,
+    pub workflow_cycles_detected: u64,
+    /// Conversations currently pinned by sticky routing
+    pub sticky_routes: u64,
+    /// Tasks accepted by the pipeline that have not finished, including
+    /// tasks waiting behind another task in the same conversation
+    pub tasks_in_flight: u64,
+    /// Task attempts retried after a retryable failure
+    pub tasks_retried: u64,
+    /// Tasks whose processing panicked; each one failed with an internal error
+    pub task_panics_total: u64,
+    /// Tasks rejected at intake as older than `agent.max_task_age_secs`
+    pub tasks_stale: u64,
+    /// Tasks denied by the `[authorization]` rules
+    pub tasks_unauthorized: u64,
+    /// Processed task ids held in memory for duplicate detection
+    pub idempotency_cache_size: u64,
+    /// Processed task ids dropped from memory, expired or over capacity
+    pub idempotency_evictions: u64,
 }
 
 #[derive(Debug, Serialize)]
@@ -664,8 +1778,26 @@ pub struct MqttMetrics {
     pub messages_published: u64,
     pub publish_failures: u64,
     pub messages_received: u64,
+    pub signature_failures: u64,
     pub last_heartbeat: u64,
     pub connection_duration_seconds: u64,
+    /// Connections acknowledged by the broker after the first
+    pub reconnects: u64,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct LlmMetrics {
+    /// Requests, token usage, cost and latency by model
+    pub models: HashMap<String, LlmModelStats>,
+    pub total_requests: u64,
+    pub total_errors: u64,
+    pub total_prompt_tokens: u64,
+    pub total_completion_tokens: u64,
+    pub total_cache_read_tokens: u64,
+    pub total_cache_creation_tokens: u64,
+    /// Share of all prompt tokens read from the prompt cache, across models
+    pub cache_hit_ratio: Option<f64>,
+    pub total_estimated_cost_usd: f64,
 }
 
 #[derive(Debug, Serialize)]
@@ -688,6 +1820,33 @@ pub struct ToolExecutionStatsSnapshot {
     pub success_rate: f64,
 }
 
+#[derive(Debug, Default, Serialize)]
+pub struct RoutingMetrics {
+    /// Decisions by type: `complete` or `forward`
+    pub decisions: HashMap<String, u64>,
+    /// Forwards by the concrete agent they were sent to
+    pub forwards_by_agent: HashMap<String, u64>,
+    /// Failed router calls and unresolvable forward targets
+    pub router_errors: u64,
+    pub avg_router_latency_ms: f64,
+    pub router_latency_p50_ms: f64,
+    pub router_latency_p95_ms: f64,
+    pub router_latency_p99_ms: f64,
+    /// Router call counts per latency bucket (not cumulative)
+    pub router_latency_histogram: Vec<LatencyBucket>,
+    /// Agents currently in the discovery registry
+    pub registry_agents: u64,
+    /// Agents evicted from the discovery registry, by reason
+    pub registry_evictions: HashMap<String, u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LatencyBucket {
+    /// Inclusive upper bound in milliseconds; `None` for the overflow bucket
+    pub le_ms: Option<u64>,
+    pub count: u64,
+}
+
 #[derive(Debug, Serialize)]
 pub struct LifecycleMetrics {
     pub current_state: String,
@@ -696,6 +1855,11 @@ pub struct LifecycleMetrics {
     pub restarts: u64,
     pub healthy: bool,
     pub last_health_check: u64,
+    pub paused: bool,
+    /// No task has run for `agent.idle_after_secs`; pooled resources are released
+    pub idle: bool,
+    /// Changes between idle and active since startup
+    pub idle_transitions: u64,
 }
 
 // Helper functions
@@ -706,6 +1870,15 @@ fn current_timestamp() -> u64 {
         .as_secs()
 }
 
+/// Label of a step outcome (pure function)
+fn outcome(success: bool) -> &'static str {
+    if success {
+        "success"
+    } else {
+        "failure"
+    }
+}
+
 fn percentile(sorted_data: &[u64], percentile: f64) -> f64 {
     if sorted_data.is_empty() {
         return 0.0;
@@ -748,6 +1921,33 @@ mod tests {
         assert!(metrics.tasks.avg_processing_time_ms > 1400.0);
     }
 
+    #[test]
+    fn test_tasks_in_flight_gauge() {
+        let collector = MetricsCollector::new();
+
+        collector.task_in_flight();
+        collector.task_in_flight();
+        collector.task_settled();
+        assert_eq!(collector.get_metrics().tasks.tasks_in_flight, 1);
+
+        collector.task_settled();
+        assert_eq!(collector.get_metrics().tasks.tasks_in_flight, 0);
+    }
+
+    #[test]
+    fn test_idle_transitions_count_changes_only() {
+        let collector = MetricsCollector::new();
+
+        collector.set_idle(true);
+        collector.set_idle(true);
+        assert!(collector.is_idle());
+        collector.set_idle(false);
+
+        let lifecycle = collector.get_metrics().lifecycle;
+        assert!(!lifecycle.idle);
+        assert_eq!(lifecycle.idle_transitions, 2);
+    }
+
     #[test]
     fn test_mqtt_metrics() {
         let collector = MetricsCollector::new();
@@ -755,9 +1955,11 @@ mod tests {
         collector.mqtt_connection_attempt();
         collector.mqtt_connection_established();
         collector.mqtt_message_published();
+        collector.mqtt_signature_failed();
 
         let metrics = collector.get_metrics();
         assert_eq!(metrics.mqtt.connection_attempts, 1);
+        assert_eq!(metrics.mqtt.signature_failures, 1);
         assert_eq!(metrics.mqtt.connections_established, 1);
         assert_eq!(metrics.mqtt.messages_published, 1);
         assert!(metrics.mqtt.connected);
@@ -779,6 +1981,142 @@ mod tests {
         assert!(tool_stats.avg_execution_time_ms > 350.0);
     }
 
+    #[test]
+    fn test_llm_metrics_by_model() {
+        let collector = MetricsCollector::new();
+
+        collector.llm_request_completed("gpt-4", 100, 20, Duration::from_millis(400), Some(0.25));
+        collector.llm_request_completed("gpt-4", 50, 10, Duration::from_millis(3000), None);
+        collector.llm_request_failed("claude", Duration::from_millis(100));
+
+        let llm = collector.get_metrics().llm;
+        let gpt4 = &llm.models["gpt-4"];
+        assert_eq!(gpt4.prompt_tokens, 150);
+        assert_eq!(gpt4.estimated_cost_usd, 0.25);
+        assert_eq!(gpt4.unpriced_requests, 1);
+        assert_eq!(gpt4.avg_latency_ms, 1700.0);
+        let counts: Vec<u64> = gpt4.latency_histogram.iter().map(|b| b.count).collect();
+        assert_eq!(counts, vec![0, 1, 0, 0, 1, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(llm.models["claude"].errors, 1);
+        assert_eq!(llm.models["claude"].latency_histogram[0].count, 1);
+        assert_eq!(llm.total_requests, 3);
+        assert_eq!(llm.total_completion_tokens, 30);
+        assert_eq!(llm.total_estimated_cost_usd, 0.25);
+        assert_eq!(llm.cache_hit_ratio, None);
+    }
+
+    #[test]
+    fn test_llm_cache_hit_ratio() {
+        let collector = MetricsCollector::new();
+
+        collector.llm_request_completed("claude", 100, 20, Duration::from_millis(400), None);
+        collector.llm_prompt_cache_used("claude", 0, 6000);
+        collector.llm_request_completed("claude", 100, 20, Duration::from_millis(400), None);
+        collector.llm_prompt_cache_used("claude", 6000, 0);
+        collector.llm_request_completed("gpt-4", 800, 20, Duration::from_millis(400), None);
+
+        let llm = collector.get_metrics().llm;
+        let claude = &llm.models["claude"];
+        assert_eq!(claude.cache_read_tokens, 6000);
+        assert_eq!(claude.cache_creation_tokens, 6000);
+        assert_eq!(claude.cache_hit_ratio, Some(6000.0 / 12200.0));
+        assert_eq!(llm.models["gpt-4"].cache_hit_ratio, None);
+        assert_eq!(llm.total_cache_read_tokens, 6000);
+        assert_eq!(llm.cache_hit_ratio, Some(6000.0 / 13000.0));
+    }
+
+    #[test]
+    fn test_step_metrics_by_step_and_outcome() {
+        let collector = MetricsCollector::new();
+
+        collector.step_completed(7, Duration::from_millis(2000), true);
+        collector.step_completed(7, Duration::from_millis(4000), true);
+        collector.step_completed(1, Duration::from_micros(200), true);
+        collector.step_completed(4, Duration::from_millis(3), false);
+
+        let steps = collector.get_metrics().steps;
+        let keys: Vec<(u8, &str)> = steps
+            .iter()
+            .map(|stats| (stats.step, stats.outcome.as_str()))
+            .collect();
+        assert_eq!(keys, vec![(1, "success"), (4, "failure"), (7, "success")]);
+        let step7 = &steps[2];
+        assert_eq!(step7.count, 2);
+        assert_eq!(step7.avg_ms, 3000.0);
+        assert_eq!(step7.p50_ms, 3000.0);
+        assert_eq!(step7.latency_histogram[8].count, 1); // le 2500
+        assert_eq!(step7.latency_histogram[9].count, 1); // le 5000
+        assert_eq!(steps[0].latency_histogram[0].count, 1);
+
+        let rendered = collector.render_prometheus("agent-1");
+        assert!(rendered.contains(
+            "agent2389_task_step_duration_seconds_count{agent_id=\"agent-1\",step=\"7\",outcome=\"success\"} 2"
+        ));
+
+        collector.reset();
+        assert!(collector.get_metrics().steps.is_empty());
+    }
+
+    #[test]
+    fn test_reconnects_count_connections_after_the_first() {
+        let collector = MetricsCollector::new();
+
+        collector.mqtt_connection_established();
+        assert_eq!(collector.get_metrics().mqtt.reconnects, 0);
+        collector.mqtt_connection_lost();
+        collector.mqtt_connection_established();
+        assert_eq!(collector.get_metrics().mqtt.reconnects, 1);
+    }
+
+    #[test]
+    fn test_routing_metrics() {
+        let collector = MetricsCollector::new();
+
+        collector.routing_decision("forward", Some("writer"), Some(Duration::from_millis(40)));
+        collector.routing_decision("forward", Some("writer"), None);
+        collector.routing_decision("complete", None, Some(Duration::from_millis(3000)));
+        collector.routing_error(Some(Duration::from_millis(5)));
+
+        let routing = collector.get_metrics().routing;
+        assert_eq!(routing.decisions.get("forward"), Some(&2));
+        assert_eq!(routing.decisions.get("complete"), Some(&1));
+        assert_eq!(routing.forwards_by_agent.get("writer"), Some(&2));
+        assert_eq!(routing.router_errors, 1);
+        assert!((routing.avg_router_latency_ms - 1015.0).abs() < 0.1);
+
+        let counts: Vec<u64> = routing
+            .router_latency_histogram
+            .iter()
+            .map(|bucket| bucket.count)
+            .collect();
+        assert_eq!(counts, vec![1, 1, 0, 0, 0, 0, 0, 1, 0]);
+        assert_eq!(routing.router_latency_histogram.last().unwrap().le_ms, None);
+
+        collector.reset();
+        assert!(collector.get_metrics().routing.decisions.is_empty());
+    }
+
+    #[test]
+    fn test_registry_metrics() {
+        let collector = MetricsCollector::new();
+
+        collector.set_registry_agents(3);
+        collector.registry_evictions("expired", 2);
+        collector.registry_evictions("unavailable", 1);
+        collector.registry_evictions("expired", 1);
+
+        let routing = collector.get_metrics().routing;
+        assert_eq!(routing.registry_agents, 3);
+        assert_eq!(routing.registry_evictions.get("expired"), Some(&3));
+        assert_eq!(routing.registry_evictions.get("unavailable"), Some(&1));
+
+        let rendered = collector.render_prometheus("agent-1");
+        assert!(rendered.contains("agent2389_discovery_registry_agents{agent_id=\"agent-1\"} 3"));
+        assert!(rendered.contains(
+            "agent2389_discovery_registry_evictions_total{agent_id=\"agent-1\",reason=\"expired\"} 3"
+        ));
+    }
+
     #[test]
     fn test_thread_safety() {
         let collector = Arc::new(MetricsCollector::new());
@@ -3,14 +3,28 @@
 //! This module implements comprehensive monitoring with structured logging,
 //! metrics collection, and health check endpoints per the observability specification.
 
+pub mod agent_state;
+pub mod event_log;
 pub mod health;
+pub mod json_log;
 pub mod logging;
 pub mod metrics;
+pub mod otel;
+pub mod probes;
+pub mod prometheus;
+pub mod redaction;
+pub mod rotating_file;
+pub mod telemetry;
 
 // Re-export for convenience
+pub use agent_state::AgentStateRegistry;
+pub use event_log::{event_log, Event, EventCategory, EventLog, EventSeverity};
 pub use health::HealthServer;
 pub use logging::{init_default_logging, init_logging, LogFormat};
 pub use metrics::{metrics, MetricsCollector, MetricsSnapshot};
+pub use probes::ProbeState;
+pub use redaction::{install_log_redactor, Redactor};
+pub use telemetry::{TelemetrySampler, TelemetrySnapshot};
 
 // Span macros for structured logging
 pub use logging::{lifecycle_span, mqtt_span, task_span, tool_span};
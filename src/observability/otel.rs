@@ -0,0 +1,188 @@
+//! OpenTelemetry trace export and cross-agent trace context
+//!
+//! With `[observability.otel]` enabled, `init_logging` adds a
+//! `tracing-opentelemetry` layer exporting the agent's spans over OTLP/HTTP.
+//!
+//! Envelopes published to another agent carry the publishing span's W3C
+//! `traceparent`, and the receiving agent parents its processing span on
+//! it, so a workflow spanning several agents is a single trace. Without the
+//! layer spans have no trace context: nothing is injected and extraction
+//! does nothing.
+
+use crate::config::OtelConfig;
+use opentelemetry::propagation::TextMapPropagator;
+use opentelemetry::trace::{TraceContextExt, TraceError, TracerProvider as _};
+use opentelemetry::KeyValue;
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::{Sampler, Tracer, TracerProvider};
+use opentelemetry_sdk::Resource;
+use std::collections::HashMap;
+use tracing::Span;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+/// Header name of the W3C trace context
+pub const TRACEPARENT: &str = "traceparent";
+
+/// Trace export settings for one process
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceExport {
+    pub config: OtelConfig,
+    /// `service.name` of exported spans
+    pub service_name: String,
+}
+
+impl TraceExport {
+    /// Export settings of an agent, or `None` when export is disabled
+    pub fn for_agent(agent_id: &str, config: &OtelConfig) -> Option<Self> {
+        config.enabled.then(|| Self {
+            config: config.clone(),
+            service_name: config
+                .service_name
+                .clone()
+                .unwrap_or_else(|| agent_id.to_string()),
+        })
+    }
+
+    /// Build the batch exporting tracer and install its provider globally
+    ///
+    /// New traces are sampled at `sampling_ratio`; traces continued from
+    /// another agent keep that agent's sampling decision.
+    pub fn install(&self) -> Result<Tracer, TraceError> {
+        let exporter = opentelemetry_otlp::SpanExporter::builder()
+            .with_http()
+            .with_endpoint(&self.config.endpoint)
+            .build()?;
+        let provider = TracerProvider::builder()
+            .with_batch_exporter(exporter, opentelemetry_sdk::runtime::Tokio)
+            .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+                self.config.sampling_ratio,
+            ))))
+            .with_resource(Resource::new([KeyValue::new(
+                "service.name",
+                self.service_name.clone(),
+            )]))
+            .build();
+        let tracer = provider.tracer("agent2389");
+        opentelemetry::global::set_tracer_provider(provider);
+        Ok(tracer)
+    }
+}
+
+/// Flush buffered spans and stop exporting; does nothing if export is off
+pub fn shutdown_trace_export() {
+    opentelemetry::global::shutdown_tracer_provider();
+}
+
+/// W3C `traceparent` of the current span, if it has a trace context
+pub fn current_traceparent() -> Option<String> {
+    traceparent_of(&Span::current())
+}
+
+/// W3C `traceparent` of `span`, if it has a trace context
+pub fn traceparent_of(span: &Span) -> Option<String> {
+    let context = span.context();
+    if !context.span().span_context().is_valid() {
+        return None;
+    }
+    let mut carrier = HashMap::new();
+    TraceContextPropagator::new().inject_context(&context, &mut carrier);
+    carrier.remove(TRACEPARENT)
+}
+
+/// Continue the trace of a `traceparent` received from another agent
+///
+/// Must be called before `span` has children, since children take their
+/// trace id from the span when they are created. Returns whether the
+/// header was a valid trace context.
+pub fn set_remote_parent(span: &Span, traceparent: &str) -> bool {
+    let carrier = HashMap::from([(TRACEPARENT.to_string(), traceparent.to_string())]);
+    let context = TraceContextPropagator::new().extract(&carrier);
+    if !context.span().span_context().is_valid() {
+        return false;
+    }
+    span.set_parent(context);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_subscriber::prelude::*;
+
+    fn traced<R>(f: impl FnOnce() -> R) -> R {
+        let tracer = TracerProvider::builder().build().tracer("test");
+        let subscriber =
+            tracing_subscriber::registry().with(tracing_opentelemetry::layer().with_tracer(tracer));
+        tracing::subscriber::with_default(subscriber, f)
+    }
+
+    fn trace_id(traceparent: &str) -> &str {
+        traceparent.split('-').nth(1).unwrap()
+    }
+
+    #[test]
+    fn test_traceparent_round_trips_between_agents() {
+        traced(|| {
+            let publisher = tracing::info_span!("publish");
+            let traceparent = traceparent_of(&publisher).expect("span has a trace context");
+            assert!(traceparent.starts_with("00-") && traceparent.ends_with("-01"));
+
+            let receiver = tracing::info_span!("nine_step_process");
+            assert!(set_remote_parent(&receiver, &traceparent));
+            let child = receiver.in_scope(|| tracing::info_span!("task_processing"));
+
+            let continued = traceparent_of(&child).unwrap();
+            assert_eq!(trace_id(&continued), trace_id(&traceparent));
+            assert_ne!(continued, traceparent);
+        });
+    }
+
+    #[test]
+    fn test_invalid_traceparent_is_ignored() {
+        traced(|| {
+            let span = tracing::info_span!("nine_step_process");
+            let own = traceparent_of(&span).unwrap();
+
+            assert!(!set_remote_parent(&span, "not-a-traceparent"));
+            assert!(!set_remote_parent(
+                &span,
+                "00-00000000000000000000000000000000-0000000000000000-01"
+            ));
+            assert_eq!(traceparent_of(&span).unwrap(), own);
+        });
+    }
+
+    #[test]
+    fn test_no_trace_context_without_layer() {
+        let span = tracing::info_span!("untraced");
+        assert_eq!(traceparent_of(&span), None);
+
+        set_remote_parent(
+            &span,
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+        );
+        assert_eq!(traceparent_of(&span), None);
+    }
+
+    #[test]
+    fn test_export_only_when_enabled() {
+        let mut config = OtelConfig::default();
+        assert_eq!(TraceExport::for_agent("agent-1", &config), None);
+
+        config.enabled = true;
+        assert_eq!(
+            TraceExport::for_agent("agent-1", &config)
+                .unwrap()
+                .service_name,
+            "agent-1"
+        );
+        config.service_name = Some("research-pipeline".to_string());
+        assert_eq!(
+            TraceExport::for_agent("agent-1", &config)
+                .unwrap()
+                .service_name,
+            "research-pipeline"
+        );
+    }
+}
@@ -0,0 +1,317 @@
+//! Liveness and readiness state behind `/livez` and `/readyz`
+//!
+//! Liveness only fails when the agent is wedged: its pipeline loop has
+//! exited or panicked, so no task will ever be processed again. Readiness
+//! follows the lifecycle through its startup phases and aggregates the
+//! components the agent needs to take work: the broker connection and task
+//! subscription, the startup health checks (MQTT transport, LLM provider)
+//! and the tools.
+
+use crate::health::HealthCheckResult;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+/// Where the agent is in its lifecycle, as far as readiness is concerned
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StartupPhase {
+    /// Starting up: connecting, initializing tools and checking components
+    Initializing,
+    /// Connected and subscribed to the task topic, still publishing status
+    Subscribed,
+    /// Taking tasks
+    Ready,
+    /// The broker connection was lost for good
+    Disconnected,
+    /// Shutting down
+    Stopping,
+}
+
+/// State of the pipeline loop that takes tasks off the queue
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PipelineState {
+    NotStarted,
+    Running,
+    /// The loop returned; tasks are no longer taken off the queue
+    Exited,
+    Panicked,
+}
+
+/// Whether one component is usable
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ComponentHealth {
+    Ok,
+    Failing,
+}
+
+/// Status of one component, as served on `/readyz`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ComponentStatus {
+    pub status: ComponentHealth,
+    pub message: Option<String>,
+}
+
+impl ComponentStatus {
+    pub fn ok(message: impl Into<String>) -> Self {
+        Self {
+            status: ComponentHealth::Ok,
+            message: Some(message.into()),
+        }
+    }
+
+    pub fn failing(message: impl Into<String>) -> Self {
+        Self {
+            status: ComponentHealth::Failing,
+            message: Some(message.into()),
+        }
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.status == ComponentHealth::Ok
+    }
+}
+
+/// Body of `/livez`
+#[derive(Debug, Clone, Serialize)]
+pub struct LivenessReport {
+    pub live: bool,
+    pub pipeline: PipelineState,
+    pub timestamp: u64,
+}
+
+/// Body of `/readyz`
+#[derive(Debug, Clone, Serialize)]
+pub struct ReadinessReport {
+    pub ready: bool,
+    pub phase: StartupPhase,
+    pub components: BTreeMap<String, ComponentStatus>,
+    pub timestamp: u64,
+}
+
+struct ProbeInner {
+    phase: StartupPhase,
+    pipeline: PipelineState,
+    /// Components recorded by the lifecycle, by name
+    components: BTreeMap<String, ComponentStatus>,
+}
+
+/// Startup phase, pipeline state and component statuses of one agent
+pub struct ProbeState {
+    inner: Mutex<ProbeInner>,
+}
+
+impl Default for ProbeState {
+    fn default() -> Self {
+        Self {
+            inner: Mutex::new(ProbeInner {
+                phase: StartupPhase::Initializing,
+                pipeline: PipelineState::NotStarted,
+                components: BTreeMap::new(),
+            }),
+        }
+    }
+}
+
+impl ProbeState {
+    pub fn set_phase(&self, phase: StartupPhase) {
+        self.inner.lock().unwrap().phase = phase;
+    }
+
+    pub fn phase(&self) -> StartupPhase {
+        self.inner.lock().unwrap().phase
+    }
+
+    pub fn set_pipeline(&self, pipeline: PipelineState) {
+        self.inner.lock().unwrap().pipeline = pipeline;
+    }
+
+    pub fn pipeline(&self) -> PipelineState {
+        self.inner.lock().unwrap().pipeline
+    }
+
+    /// Record the status of a component, replacing any earlier one
+    pub fn set_component(&self, name: impl Into<String>, status: ComponentStatus) {
+        self.inner
+            .lock()
+            .unwrap()
+            .components
+            .insert(name.into(), status);
+    }
+
+    /// Record the results of a `HealthCheckManager` run, one component each
+    pub fn record_health_checks(&self, results: &[HealthCheckResult]) {
+        for result in results {
+            let message = result.message.clone().unwrap_or_default();
+            let status = if result.healthy {
+                ComponentStatus::ok(message)
+            } else {
+                ComponentStatus::failing(message)
+            };
+            self.set_component(result.component.clone(), status);
+        }
+    }
+
+    /// Liveness: false only once the pipeline loop has exited or panicked
+    pub fn liveness(&self, timestamp: u64) -> LivenessReport {
+        let pipeline = self.pipeline();
+        LivenessReport {
+            live: !matches!(pipeline, PipelineState::Exited | PipelineState::Panicked),
+            pipeline,
+            timestamp,
+        }
+    }
+
+    /// Readiness: ready once started, with every component ok and intake open
+    ///
+    /// Besides the recorded components, reports the broker connection, the
+    /// task subscription, the pipeline loop and whether intake is paused.
+    pub fn readiness(&self, mqtt_connected: bool, paused: bool, timestamp: u64) -> ReadinessReport {
+        let inner = self.inner.lock().unwrap();
+        let mut components = inner.components.clone();
+        components.insert(
+            "mqtt_connection".to_string(),
+            if mqtt_connected {
+                ComponentStatus::ok("Connected to broker")
+            } else {
+                ComponentStatus::failing("Not connected to broker")
+            },
+        );
+        components.insert(
+            "mqtt_subscription".to_string(),
+            match inner.phase {
+                StartupPhase::Subscribed | StartupPhase::Ready => {
+                    ComponentStatus::ok("Subscribed to task topic")
+                }
+                StartupPhase::Initializing => {
+                    ComponentStatus::failing("Not subscribed to task topic yet")
+                }
+                StartupPhase::Disconnected | StartupPhase::Stopping => {
+                    ComponentStatus::failing("No longer subscribed to task topic")
+                }
+            },
+        );
+        components.insert(
+            "pipeline".to_string(),
+            match inner.pipeline {
+                PipelineState::Running => ComponentStatus::ok("Pipeline running"),
+                PipelineState::NotStarted => ComponentStatus::failing("Pipeline not started"),
+                PipelineState::Exited => ComponentStatus::failing("Pipeline exited"),
+                PipelineState::Panicked => ComponentStatus::failing("Pipeline panicked"),
+            },
+        );
+        components.insert(
+            "task_intake".to_string(),
+            if paused {
+                ComponentStatus::failing("Task intake paused")
+            } else {
+                ComponentStatus::ok("Accepting tasks")
+            },
+        );
+
+        let ready =
+            inner.phase == StartupPhase::Ready && components.values().all(ComponentStatus::is_ok);
+        ReadinessReport {
+            ready,
+            phase: inner.phase,
+            components,
+            timestamp,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn started() -> ProbeState {
+        let probes = ProbeState::default();
+        probes.set_pipeline(PipelineState::Running);
+        probes.set_phase(StartupPhase::Ready);
+        probes
+    }
+
+    #[test]
+    fn test_liveness_fails_only_once_pipeline_stops() {
+        let probes = ProbeState::default();
+        assert!(probes.liveness(0).live);
+
+        probes.set_pipeline(PipelineState::Running);
+        assert!(probes.liveness(0).live);
+
+        probes.set_pipeline(PipelineState::Exited);
+        assert!(!probes.liveness(0).live);
+
+        probes.set_pipeline(PipelineState::Panicked);
+        let report = probes.liveness(0);
+        assert!(!report.live);
+        assert_eq!(report.pipeline, PipelineState::Panicked);
+    }
+
+    #[test]
+    fn test_readiness_follows_startup_phases() {
+        let probes = ProbeState::default();
+        probes.set_pipeline(PipelineState::Running);
+
+        let report = probes.readiness(true, false, 0);
+        assert!(!report.ready);
+        assert!(!report.components["mqtt_subscription"].is_ok());
+
+        probes.set_phase(StartupPhase::Subscribed);
+        let report = probes.readiness(true, false, 0);
+        assert!(
+            !report.ready,
+            "subscribed is not ready until startup finishes"
+        );
+        assert!(report.components["mqtt_subscription"].is_ok());
+
+        probes.set_phase(StartupPhase::Ready);
+        assert!(probes.readiness(true, false, 0).ready);
+
+        probes.set_phase(StartupPhase::Disconnected);
+        let report = probes.readiness(false, false, 0);
+        assert!(!report.ready);
+        assert!(!report.components["mqtt_connection"].is_ok());
+    }
+
+    #[test]
+    fn test_failing_health_check_makes_agent_unready() {
+        let probes = started();
+        probes.record_health_checks(&[
+            HealthCheckResult {
+                component: "mqtt_transport".to_string(),
+                healthy: true,
+                message: Some("MQTT connected".to_string()),
+                response_time_ms: Some(1),
+            },
+            HealthCheckResult {
+                component: "llm_provider".to_string(),
+                healthy: false,
+                message: Some("LLM unreachable".to_string()),
+                response_time_ms: None,
+            },
+        ]);
+
+        let report = probes.readiness(true, false, 0);
+
+        assert!(!report.ready);
+        assert!(report.components["mqtt_transport"].is_ok());
+        assert_eq!(
+            report.components["llm_provider"],
+            ComponentStatus::failing("LLM unreachable")
+        );
+    }
+
+    #[test]
+    fn test_paused_intake_and_stopped_pipeline_make_agent_unready() {
+        let probes = started();
+        assert!(probes.readiness(true, false, 0).ready);
+
+        assert!(!probes.readiness(true, true, 0).ready);
+
+        probes.set_pipeline(PipelineState::Exited);
+        assert!(!probes.readiness(true, false, 0).ready);
+    }
+}
@@ -0,0 +1,190 @@
+//! Prometheus text exposition format
+//!
+//! A small writer for the text format (version 0.0.4) scraped by
+//! Prometheus: each metric family gets one `# HELP` and one `# TYPE` line
+//! followed by its samples. Label values and help text are escaped as the
+//! format requires, so agent ids, tool names and states can contain any
+//! characters.
+
+use std::fmt::Write;
+
+/// Content type of the text exposition format
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Prefix of every metric name
+pub const METRIC_PREFIX: &str = "agent2389_";
+
+/// Kind of a metric family, as written on its `# TYPE` line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricType {
+    Counter,
+    Gauge,
+    Histogram,
+}
+
+impl MetricType {
+    fn as_str(self) -> &'static str {
+        match self {
+            MetricType::Counter => "counter",
+            MetricType::Gauge => "gauge",
+            MetricType::Histogram => "histogram",
+        }
+    }
+}
+
+/// Cumulative histogram sample: counts per upper bound plus sum and count
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistogramSample {
+    /// `(le, cumulative count)` pairs in increasing order of `le`, excluding `+Inf`
+    pub buckets: Vec<(f64, u64)>,
+    pub sum: f64,
+    pub count: u64,
+}
+
+/// Writer for one exposition, labelling every sample with the agent id
+pub struct PrometheusWriter {
+    agent_id: String,
+    output: String,
+}
+
+impl PrometheusWriter {
+    pub fn new(agent_id: &str) -> Self {
+        Self {
+            agent_id: agent_id.to_string(),
+            output: String::new(),
+        }
+    }
+
+    /// Start a metric family; `name` is given without the `agent2389_` prefix
+    pub fn family(&mut self, name: &str, metric_type: MetricType, help: &str) {
+        let _ = writeln!(
+            self.output,
+            "# HELP {METRIC_PREFIX}{name} {}",
+            escape_help(help)
+        );
+        let _ = writeln!(
+            self.output,
+            "# TYPE {METRIC_PREFIX}{name} {}",
+            metric_type.as_str()
+        );
+    }
+
+    /// Write one sample of the current family
+    pub fn sample(&mut self, name: &str, labels: &[(&str, &str)], value: f64) {
+        let _ = writeln!(
+            self.output,
+            "{METRIC_PREFIX}{name}{} {}",
+            self.labels(labels),
+            format_value(value)
+        );
+    }
+
+    /// Write the `_bucket`, `_sum` and `_count` samples of a histogram
+    pub fn histogram(&mut self, name: &str, labels: &[(&str, &str)], histogram: &HistogramSample) {
+        for (le, count) in &histogram.buckets {
+            let le = format_value(*le);
+            let mut bucket_labels = labels.to_vec();
+            bucket_labels.push(("le", &le));
+            self.sample(&format!("{name}_bucket"), &bucket_labels, *count as f64);
+        }
+        let mut inf_labels = labels.to_vec();
+        inf_labels.push(("le", "+Inf"));
+        self.sample(
+            &format!("{name}_bucket"),
+            &inf_labels,
+            histogram.count as f64,
+        );
+        self.sample(&format!("{name}_sum"), labels, histogram.sum);
+        self.sample(&format!("{name}_count"), labels, histogram.count as f64);
+    }
+
+    pub fn finish(self) -> String {
+        self.output
+    }
+
+    /// `{agent_id="...",k="v"}` with escaped values (pure function)
+    fn labels(&self, labels: &[(&str, &str)]) -> String {
+        let pairs: Vec<String> = std::iter::once(("agent_id", self.agent_id.as_str()))
+            .chain(labels.iter().copied())
+            .map(|(key, value)| format!("{key}=\"{}\"", escape_label_value(value)))
+            .collect();
+        format!("{{{}}}", pairs.join(","))
+    }
+}
+
+/// Escape a label value: backslash, double quote and line feed (pure function)
+pub fn escape_label_value(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Escape help text: backslash and line feed (pure function)
+pub fn escape_help(help: &str) -> String {
+    help.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+/// Sample value as Prometheus expects it, including `+Inf`/`NaN` (pure function)
+fn format_value(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".to_string()
+    } else if value.is_infinite() {
+        if value > 0.0 { "+Inf" } else { "-Inf" }.to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escaping() {
+        assert_eq!(escape_label_value(r#"a"b\c"#), r#"a\"b\\c"#);
+        assert_eq!(escape_label_value("line\nbreak"), "line\\nbreak");
+        assert_eq!(escape_help("50% \\ done\nnext"), "50% \\\\ done\\nnext");
+    }
+
+    #[test]
+    fn test_writer_output() {
+        let mut writer = PrometheusWriter::new("agent \"1\"");
+        writer.family("tasks_total", MetricType::Counter, "Tasks by outcome");
+        writer.sample("tasks_total", &[("outcome", "completed")], 3.0);
+        writer.family(
+            "tool_duration_seconds",
+            MetricType::Histogram,
+            "Tool latency",
+        );
+        writer.histogram(
+            "tool_duration_seconds",
+            &[],
+            &HistogramSample {
+                buckets: vec![(0.1, 1), (1.0, 2)],
+                sum: 0.75,
+                count: 3,
+            },
+        );
+
+        assert_eq!(
+            writer.finish(),
+            "# HELP agent2389_tasks_total Tasks by outcome\n\
+             # TYPE agent2389_tasks_total counter\n\
+             agent2389_tasks_total{agent_id=\"agent \\\"1\\\"\",outcome=\"completed\"} 3\n\
+             # HELP agent2389_tool_duration_seconds Tool latency\n\
+             # TYPE agent2389_tool_duration_seconds histogram\n\
+             agent2389_tool_duration_seconds_bucket{agent_id=\"agent \\\"1\\\"\",le=\"0.1\"} 1\n\
+             agent2389_tool_duration_seconds_bucket{agent_id=\"agent \\\"1\\\"\",le=\"1\"} 2\n\
+             agent2389_tool_duration_seconds_bucket{agent_id=\"agent \\\"1\\\"\",le=\"+Inf\"} 3\n\
+             agent2389_tool_duration_seconds_sum{agent_id=\"agent \\\"1\\\"\"} 0.75\n\
+             agent2389_tool_duration_seconds_count{agent_id=\"agent \\\"1\\\"\"} 3\n"
+        );
+    }
+}
@@ -0,0 +1,471 @@
+//! Secret redaction
+//!
+//! Tool arguments and results regularly carry credentials: the LLM echoes
+//! configured values back, and a failing tool quotes the request it made.
+//! A [`Redactor`] replaces every match of the configured patterns, and every
+//! occurrence of a configured secret's value, with [`REDACTED`]. It is applied
+//! to progress messages and their metadata, to log lines through
+//! [`RedactingMakeWriter`], and, with `redact_tool_io = true`, to tool results
+//! before they are added to the LLM conversation.
+//!
+//! Text without a match costs one pass of a combined [`RegexSet`] and is
+//! returned borrowed; only text that needs redacting is copied. Matches of
+//! different patterns may overlap, and their union is redacted, so no part of
+//! either match survives.
+
+use crate::config::AgentConfig;
+use regex::{Regex, RegexSet};
+use std::borrow::Cow;
+use std::fmt;
+use std::io;
+use std::ops::Range;
+use std::sync::{Arc, RwLock};
+use tracing_subscriber::fmt::writer::MakeWriter;
+
+/// Replacement for redacted text
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Secret values shorter than this are not redacted
+///
+/// Redacting a very short value would mangle unrelated text that happens to
+/// contain it; real credentials are much longer.
+pub const MIN_SECRET_LEN: usize = 8;
+
+/// Replaces matches of patterns and known secret values with [`REDACTED`]
+#[derive(Clone, Default)]
+pub struct Redactor {
+    /// Source of each matcher, kept to merge redactors
+    expressions: Vec<String>,
+    /// One matcher per pattern or secret, in `expressions` order
+    matchers: Vec<Regex>,
+    /// Every expression, checked in one pass before any matcher runs
+    set: Option<RegexSet>,
+}
+
+impl Redactor {
+    /// Redactor for the given regular expressions and secret values
+    ///
+    /// Secrets are matched literally; empty ones and ones shorter than
+    /// [`MIN_SECRET_LEN`] are skipped.
+    pub fn new<P, S>(
+        patterns: impl IntoIterator<Item = P>,
+        secrets: impl IntoIterator<Item = S>,
+    ) -> Result<Self, regex::Error>
+    where
+        P: AsRef<str>,
+        S: AsRef<str>,
+    {
+        let expressions = patterns
+            .into_iter()
+            .map(|pattern| pattern.as_ref().to_string())
+            .chain(
+                secrets
+                    .into_iter()
+                    .filter(|secret| secret.as_ref().len() >= MIN_SECRET_LEN)
+                    .map(|secret| regex::escape(secret.as_ref())),
+            )
+            .collect();
+        Self::from_expressions(expressions)
+    }
+
+    /// Redactor configured by `[observability.redaction]`
+    ///
+    /// Empty without the section. With `redact_secrets`, covers the secrets
+    /// that resolve when it is built, so build it after
+    /// [`AgentConfig::resolve_secrets`].
+    pub fn from_config(config: &AgentConfig) -> Result<Self, regex::Error> {
+        let Some(redaction) = &config.observability.redaction else {
+            return Ok(Self::default());
+        };
+        let secrets = if redaction.redact_secrets {
+            config.secret_values()
+        } else {
+            Vec::new()
+        };
+        Self::new(
+            &redaction.patterns,
+            secrets.iter().map(|secret| secret.expose()),
+        )
+    }
+
+    fn from_expressions(mut expressions: Vec<String>) -> Result<Self, regex::Error> {
+        let mut seen = std::collections::HashSet::new();
+        expressions.retain(|expression| seen.insert(expression.clone()));
+        if expressions.is_empty() {
+            return Ok(Self::default());
+        }
+        let matchers = expressions
+            .iter()
+            .map(|expression| Regex::new(expression))
+            .collect::<Result<_, _>>()?;
+        let set = RegexSet::new(&expressions)?;
+        Ok(Self {
+            expressions,
+            matchers,
+            set: Some(set),
+        })
+    }
+
+    /// Redactor covering both this one's and `other`'s patterns and secrets
+    pub fn merged(&self, other: &Redactor) -> Self {
+        let expressions = self
+            .expressions
+            .iter()
+            .chain(&other.expressions)
+            .cloned()
+            .collect();
+        // Both sets of expressions already compiled on their own
+        Self::from_expressions(expressions).expect("merged expressions compile")
+    }
+
+    /// Whether the redactor has nothing to redact
+    pub fn is_empty(&self) -> bool {
+        self.matchers.is_empty()
+    }
+
+    /// `text` with every match replaced by [`REDACTED`]
+    ///
+    /// Borrows `text` when nothing matches.
+    pub fn redact<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let Some(set) = &self.set else {
+            return Cow::Borrowed(text);
+        };
+        let matched = set.matches(text);
+        if !matched.matched_any() {
+            return Cow::Borrowed(text);
+        }
+
+        let mut ranges: Vec<Range<usize>> = matched
+            .iter()
+            .flat_map(|index| self.matchers[index].find_iter(text))
+            .map(|m| m.range())
+            .filter(|range| !range.is_empty())
+            .collect();
+        if ranges.is_empty() {
+            return Cow::Borrowed(text);
+        }
+        ranges.sort_unstable_by_key(|range| range.start);
+        Cow::Owned(replace_ranges(text, &ranges))
+    }
+
+    /// Redact every string in `value`, in place
+    ///
+    /// Object keys are left alone.
+    pub fn redact_json(&self, value: &mut serde_json::Value) {
+        if self.is_empty() {
+            return;
+        }
+        match value {
+            serde_json::Value::String(text) => {
+                let redacted = match self.redact(text) {
+                    Cow::Owned(redacted) => Some(redacted),
+                    Cow::Borrowed(_) => None,
+                };
+                if let Some(redacted) = redacted {
+                    *text = redacted;
+                }
+            }
+            serde_json::Value::Array(items) => {
+                items.iter_mut().for_each(|item| self.redact_json(item))
+            }
+            serde_json::Value::Object(fields) => fields
+                .values_mut()
+                .for_each(|field| self.redact_json(field)),
+            _ => {}
+        }
+    }
+}
+
+impl fmt::Debug for Redactor {
+    // Expressions include the secrets themselves, so only count them
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Redactor")
+            .field("expressions", &self.expressions.len())
+            .finish()
+    }
+}
+
+/// `text` with each run of overlapping or adjacent `ranges` replaced by one
+/// [`REDACTED`]; `ranges` must be sorted by start
+/// Pure function extracted for testability
+fn replace_ranges(text: &str, ranges: &[Range<usize>]) -> String {
+    let mut redacted = String::with_capacity(text.len());
+    let mut copied = 0;
+    let mut current: Option<Range<usize>> = None;
+    for range in ranges {
+        match &mut current {
+            Some(run) if range.start <= run.end => run.end = run.end.max(range.end),
+            _ => {
+                if let Some(run) = current.replace(range.clone()) {
+                    redacted.push_str(&text[copied..run.start]);
+                    redacted.push_str(REDACTED);
+                    copied = run.end;
+                }
+            }
+        }
+    }
+    if let Some(run) = current {
+        redacted.push_str(&text[copied..run.start]);
+        redacted.push_str(REDACTED);
+        copied = run.end;
+    }
+    redacted.push_str(&text[copied..]);
+    redacted
+}
+
+/// Redactor applied to log output, installed once secrets are resolved
+static LOG_REDACTOR: RwLock<Option<Arc<Redactor>>> = RwLock::new(None);
+
+/// Redact log lines written from now on with `redactor`
+///
+/// Logging starts before secrets are resolved, so the redactor is installed
+/// afterwards. Installing another, as each agent of a host does, redacts
+/// both sets of patterns and secrets.
+pub fn install_log_redactor(redactor: &Redactor) {
+    if redactor.is_empty() {
+        return;
+    }
+    if let Ok(mut installed) = LOG_REDACTOR.write() {
+        let merged = match installed.as_deref() {
+            Some(current) => current.merged(redactor),
+            None => redactor.clone(),
+        };
+        *installed = Some(Arc::new(merged));
+    }
+}
+
+fn log_redactor() -> Option<Arc<Redactor>> {
+    LOG_REDACTOR
+        .read()
+        .ok()
+        .and_then(|installed| installed.clone())
+}
+
+/// Log writer factory redacting each line with the installed log redactor
+///
+/// The formatting layers write each event with a single `write_all`, so a
+/// secret is never split across two writes.
+pub struct RedactingMakeWriter<M> {
+    inner: M,
+}
+
+impl<M> RedactingMakeWriter<M> {
+    pub fn new(inner: M) -> Self {
+        Self { inner }
+    }
+}
+
+impl<'a, M: MakeWriter<'a>> MakeWriter<'a> for RedactingMakeWriter<M> {
+    type Writer = RedactingWriter<M::Writer>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        RedactingWriter::new(self.inner.make_writer(), log_redactor())
+    }
+
+    fn make_writer_for(&'a self, meta: &tracing::Metadata<'_>) -> Self::Writer {
+        RedactingWriter::new(self.inner.make_writer_for(meta), log_redactor())
+    }
+}
+
+/// Writer passing text through a [`Redactor`]
+///
+/// Bytes that are not UTF-8 are written unchanged.
+pub struct RedactingWriter<W> {
+    inner: W,
+    redactor: Option<Arc<Redactor>>,
+}
+
+impl<W> RedactingWriter<W> {
+    pub fn new(inner: W, redactor: Option<Arc<Redactor>>) -> Self {
+        Self { inner, redactor }
+    }
+}
+
+impl<W: io::Write> io::Write for RedactingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let Some(redactor) = &self.redactor else {
+            return self.inner.write(buf);
+        };
+        match std::str::from_utf8(buf).map(|text| redactor.redact(text)) {
+            Ok(Cow::Owned(redacted)) => self.inner.write_all(redacted.as_bytes())?,
+            Ok(Cow::Borrowed(_)) | Err(_) => self.inner.write_all(buf)?,
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::RedactionConfig;
+    use std::io::Write;
+
+    const SECRET: &str = "sk-test-0123456789abcdef";
+
+    fn redactor(patterns: &[&str], secrets: &[&str]) -> Redactor {
+        Redactor::new(patterns, secrets).unwrap()
+    }
+
+    #[test]
+    fn test_text_without_matches_is_borrowed() {
+        let redactor = redactor(&[r"ghp_[A-Za-z0-9]{8,}"], &[SECRET]);
+
+        let redacted = redactor.redact("nothing to see here");
+
+        assert!(matches!(redacted, Cow::Borrowed("nothing to see here")));
+        assert!(matches!(
+            Redactor::default().redact("anything"),
+            Cow::Borrowed(_)
+        ));
+    }
+
+    #[test]
+    fn test_patterns_and_secrets_are_replaced() {
+        let redactor = redactor(&[r"ghp_[A-Za-z0-9]{8,}"], &[SECRET]);
+
+        let text = format!("calling with token ghp_abcdefgh12 and key {SECRET}, twice: {SECRET}");
+
+        assert_eq!(
+            redactor.redact(&text),
+            "calling with token [REDACTED] and key [REDACTED], twice: [REDACTED]"
+        );
+    }
+
+    #[test]
+    fn test_secrets_are_matched_literally() {
+        let redactor = redactor(&[], &["pa$$w0rd.(x)"]);
+
+        assert_eq!(redactor.redact("pw=pa$$w0rd.(x)"), "pw=[REDACTED]");
+        assert_eq!(redactor.redact("pw=pa$$w0rdX(x)"), "pw=pa$$w0rdX(x)");
+    }
+
+    #[test]
+    fn test_short_and_empty_secrets_are_skipped() {
+        let redactor = redactor(&[], &["", "abc"]);
+
+        assert!(redactor.is_empty());
+        assert_eq!(redactor.redact("abc"), "abc");
+    }
+
+    #[test]
+    fn test_overlapping_matches_are_redacted_as_one() {
+        // The two secrets overlap in "efgh"
+        let redactor = redactor(&[], &["abcdefgh", "efghijkl"]);
+
+        assert_eq!(redactor.redact("<abcdefghijkl>"), "<[REDACTED]>");
+    }
+
+    #[test]
+    fn test_contained_and_adjacent_matches_are_redacted_as_one() {
+        let redactor = redactor(&[r"token=\S+", r"\d{4}"], &[]);
+
+        // "1234" lies inside the token match, "5678" directly follows "1234"
+        assert_eq!(redactor.redact("token=ab1234cd x"), "[REDACTED] x");
+        assert_eq!(redactor.redact("pin 12345678!"), "pin [REDACTED]!");
+    }
+
+    #[test]
+    fn test_prefix_secret_does_not_leave_the_longer_one_partly_visible() {
+        let redactor = redactor(&[], &["secret-value", "secret-value-long"]);
+
+        assert_eq!(
+            redactor.redact("a secret-value-long b secret-value c"),
+            "a [REDACTED] b [REDACTED] c"
+        );
+    }
+
+    #[test]
+    fn test_non_ascii_text_is_redacted_on_char_boundaries() {
+        let redactor = redactor(&["clé-[0-9]+"], &[]);
+
+        assert_eq!(redactor.redact("é clé-42 ü"), "é [REDACTED] ü");
+    }
+
+    #[test]
+    fn test_redact_json_redacts_nested_strings_but_not_keys() {
+        let redactor = redactor(&[], &[SECRET]);
+        let mut value = serde_json::json!({
+            "tool": "http",
+            "arguments": {"headers": [format!("Bearer {SECRET}")], SECRET: 1},
+            "count": 2
+        });
+
+        redactor.redact_json(&mut value);
+
+        assert_eq!(value["arguments"]["headers"][0], "Bearer [REDACTED]");
+        assert_eq!(value["tool"], "http");
+        assert_eq!(value["count"], 2);
+        assert!(value["arguments"].get(SECRET).is_some());
+    }
+
+    #[test]
+    fn test_merged_redactor_covers_both() {
+        let first = redactor(&["first-[0-9]+"], &[]);
+        let second = redactor(&[], &[SECRET]);
+
+        let merged = first.merged(&second);
+
+        assert_eq!(
+            merged.redact(&format!("first-1 {SECRET}")),
+            "[REDACTED] [REDACTED]"
+        );
+    }
+
+    #[test]
+    fn test_invalid_pattern_is_an_error() {
+        assert!(Redactor::new(["("], [] as [&str; 0]).is_err());
+    }
+
+    #[test]
+    fn test_debug_does_not_show_secrets() {
+        let redactor = redactor(&[], &[SECRET]);
+
+        assert!(!format!("{redactor:?}").contains("0123456789"));
+    }
+
+    #[test]
+    fn test_from_config_redacts_resolved_secrets() {
+        std::env::set_var("TEST_REDACTION_API_KEY", SECRET);
+        let mut config = AgentConfig::test_config();
+        config.llm.api_key_env = crate::secrets::SecretRef::env("TEST_REDACTION_API_KEY");
+
+        // Nothing is redacted without the section
+        assert!(Redactor::from_config(&config).unwrap().is_empty());
+
+        config.observability.redaction = Some(RedactionConfig {
+            patterns: vec!["internal-[a-z]+".to_string()],
+            ..RedactionConfig::default()
+        });
+        let redactor = Redactor::from_config(&config).unwrap();
+        assert_eq!(
+            redactor.redact(&format!("key {SECRET} on internal-host")),
+            "key [REDACTED] on [REDACTED]"
+        );
+
+        config.observability.redaction = Some(RedactionConfig {
+            redact_secrets: false,
+            ..RedactionConfig::default()
+        });
+        assert!(Redactor::from_config(&config).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_redacting_writer_redacts_whole_writes() {
+        let redactor = Arc::new(redactor(&[], &[SECRET]));
+        let mut writer = RedactingWriter::new(Vec::new(), Some(redactor));
+
+        writer
+            .write_all(format!("{{\"args\":\"{SECRET}\"}}\n").as_bytes())
+            .unwrap();
+        writer.write_all(&[0xff, 0xfe]).unwrap();
+
+        assert_eq!(
+            writer.inner,
+            b"{\"args\":\"[REDACTED]\"}\n\xff\xfe".to_vec()
+        );
+    }
+}
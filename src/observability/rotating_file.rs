@@ -0,0 +1,142 @@
+//! Size-rotated append-only file
+//!
+//! Backs the progress file sink and the log file. Lines are appended
+//! whole; when the next line would take the file past `max_bytes`, the file
+//! is rotated: `agent.log` becomes `agent.log.1`, older files move up by
+//! one, and only `max_files` rotated files are kept.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Append-only file of lines, rotated by size
+pub struct RotatingFile {
+    path: PathBuf,
+    max_bytes: u64,
+    max_files: usize,
+    current: Mutex<OpenFile>,
+}
+
+struct OpenFile {
+    file: File,
+    len: u64,
+}
+
+impl RotatingFile {
+    /// Open (or create) `path` for appending, creating its directory
+    pub fn open(path: &Path, max_bytes: u64, max_files: usize) -> io::Result<Self> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        Ok(Self {
+            path: path.to_path_buf(),
+            max_bytes,
+            max_files,
+            current: Mutex::new(Self::open_current(path)?),
+        })
+    }
+
+    fn open_current(path: &Path) -> io::Result<OpenFile> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let len = file.metadata()?.len();
+        Ok(OpenFile { file, len })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// `path.N`, the Nth most recent rotated file
+    pub fn rotated(&self, n: usize) -> PathBuf {
+        let mut name = self.path.clone().into_os_string();
+        name.push(format!(".{n}"));
+        PathBuf::from(name)
+    }
+
+    /// Shift rotated files up by one and start an empty current file
+    fn rotate(&self, current: &mut OpenFile) -> io::Result<()> {
+        current.file.flush()?;
+        if self.max_files == 0 {
+            std::fs::remove_file(&self.path)?;
+        } else {
+            let _ = std::fs::remove_file(self.rotated(self.max_files));
+            for n in (1..self.max_files).rev() {
+                let from = self.rotated(n);
+                if from.exists() {
+                    std::fs::rename(&from, self.rotated(n + 1))?;
+                }
+            }
+            std::fs::rename(&self.path, self.rotated(1))?;
+        }
+        *current = Self::open_current(&self.path)?;
+        Ok(())
+    }
+
+    /// Append `line`, which includes its line ending, rotating first if needed
+    pub fn append_line(&self, line: &[u8]) -> io::Result<()> {
+        let mut current = self.current.lock().unwrap();
+        // A line larger than the limit still gets a file of its own
+        if current.len > 0 && current.len + line.len() as u64 > self.max_bytes {
+            self.rotate(&mut current)?;
+        }
+        current.file.write_all(line)?;
+        current.len += line.len() as u64;
+        Ok(())
+    }
+}
+
+/// Each write is appended as one unit, so it is never split across files
+impl Write for &RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.append_line(buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.current.lock().unwrap().file.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(path: &Path) -> Vec<String> {
+        std::fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .map(str::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn test_writes_are_never_split_across_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("logs/agent.log");
+        let file = RotatingFile::open(&path, 10, 1).unwrap();
+
+        (&file).write_all(b"short\n").unwrap();
+        (&file).write_all(b"longer than the limit\n").unwrap();
+
+        assert_eq!(lines(&file.rotated(1)), vec!["short"]);
+        assert_eq!(lines(&path), vec!["longer than the limit"]);
+    }
+
+    #[test]
+    fn test_rotates_by_size_and_keeps_max_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("agent.log");
+        // Two lines fit in a file
+        let file = RotatingFile::open(&path, 16, 2).unwrap();
+
+        for i in 0..7 {
+            file.append_line(format!("event-{i}\n").as_bytes()).unwrap();
+        }
+
+        assert_eq!(lines(&path), vec!["event-6"]);
+        assert_eq!(lines(&file.rotated(1)), vec!["event-4", "event-5"]);
+        assert_eq!(lines(&file.rotated(2)), vec!["event-2", "event-3"]);
+        assert!(!file.rotated(3).exists());
+    }
+}
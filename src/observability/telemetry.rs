@@ -0,0 +1,165 @@
+//! Periodic telemetry published on `/control/agents/{id}/telemetry`
+//!
+//! The heartbeat only republishes the agent's status; fleet dashboards also
+//! want task counts, queue depth, memory use and LLM token spend. When
+//! `[observability.telemetry]` is set, the lifecycle publishes a
+//! `TelemetrySnapshot` every `interval_secs`, non-retained at QoS 0, built
+//! from the `MetricsCollector` snapshot and the transport's `HealthMetrics`.
+
+use crate::observability::metrics::MetricsSnapshot;
+use crate::transport::mqtt::HealthMetrics;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// One telemetry publication
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TelemetrySnapshot {
+    pub agent_id: String,
+    pub timestamp: DateTime<Utc>,
+    /// Seconds since the agent process started
+    pub uptime_seconds: u64,
+    /// Tasks completed since startup
+    pub tasks_processed: u64,
+    /// Tasks that failed since startup
+    pub tasks_failed: u64,
+    /// Tasks accepted by the pipeline that have not finished
+    pub queue_depth: u64,
+    /// Resident set size of the process; `None` where it cannot be read
+    pub memory_rss_bytes: Option<u64>,
+    /// Prompt and completion tokens used since the previous publication
+    pub llm_tokens_last_interval: u64,
+    /// Broker connections after the first
+    pub mqtt_reconnects: u64,
+    /// Whether the broker connection is up and recently active
+    pub mqtt_healthy: bool,
+    /// Seconds since the current broker connection was established
+    pub mqtt_connection_uptime_seconds: Option<u64>,
+}
+
+/// Builds successive snapshots, tracking what changed between them
+#[derive(Debug)]
+pub struct TelemetrySampler {
+    agent_id: String,
+    /// Total LLM tokens at the previous sample
+    last_llm_tokens: u64,
+}
+
+impl TelemetrySampler {
+    /// Start sampling; tokens used before `metrics` are not counted
+    pub fn new(agent_id: impl Into<String>, metrics: &MetricsSnapshot) -> Self {
+        Self {
+            agent_id: agent_id.into(),
+            last_llm_tokens: total_llm_tokens(metrics),
+        }
+    }
+
+    /// Snapshot of the agent now, given its metrics and connection health
+    pub fn sample(
+        &mut self,
+        metrics: &MetricsSnapshot,
+        health: &HealthMetrics,
+        memory_rss_bytes: Option<u64>,
+    ) -> TelemetrySnapshot {
+        let llm_tokens = total_llm_tokens(metrics);
+        // Metrics reset in tests start the count over
+        let llm_tokens_last_interval = llm_tokens.saturating_sub(self.last_llm_tokens);
+        self.last_llm_tokens = llm_tokens;
+
+        TelemetrySnapshot {
+            agent_id: self.agent_id.clone(),
+            timestamp: Utc::now(),
+            uptime_seconds: metrics.lifecycle.uptime_seconds,
+            tasks_processed: metrics.tasks.tasks_completed,
+            tasks_failed: metrics.tasks.tasks_failed,
+            queue_depth: metrics.tasks.tasks_in_flight,
+            memory_rss_bytes,
+            llm_tokens_last_interval,
+            mqtt_reconnects: metrics
+                .mqtt
+                .reconnects
+                .max(u64::from(health.reconnect_count)),
+            mqtt_healthy: health.is_healthy,
+            mqtt_connection_uptime_seconds: health.uptime.map(|uptime| uptime.as_secs()),
+        }
+    }
+}
+
+/// Prompt and completion tokens across all models (pure function)
+fn total_llm_tokens(metrics: &MetricsSnapshot) -> u64 {
+    metrics.llm.total_prompt_tokens + metrics.llm.total_completion_tokens
+}
+
+/// Resident set size of this process, from `/proc/self/status` on Linux
+pub fn memory_rss_bytes() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    parse_vm_rss(&status)
+}
+
+/// `VmRSS` of a `/proc/<pid>/status` file in bytes (pure function)
+fn parse_vm_rss(status: &str) -> Option<u64> {
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    let kib: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kib * 1024)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::observability::metrics::MetricsCollector;
+    use std::time::Duration;
+
+    fn health(reconnect_count: u32) -> HealthMetrics {
+        HealthMetrics {
+            uptime: Some(Duration::from_secs(90)),
+            time_since_last_message: None,
+            reconnect_count,
+            is_healthy: true,
+        }
+    }
+
+    #[test]
+    fn test_sample_counts_tokens_per_interval() {
+        let collector = MetricsCollector::new();
+        collector.llm_request_completed("gpt-4", 100, 20, Duration::ZERO, None);
+        let mut sampler = TelemetrySampler::new("agent-1", &collector.get_metrics());
+
+        collector.llm_request_completed("gpt-4", 300, 50, Duration::ZERO, None);
+        let first = sampler.sample(&collector.get_metrics(), &health(0), Some(1024));
+        let second = sampler.sample(&collector.get_metrics(), &health(0), Some(1024));
+
+        assert_eq!(first.llm_tokens_last_interval, 350);
+        assert_eq!(second.llm_tokens_last_interval, 0);
+        assert_eq!(first.agent_id, "agent-1");
+        assert_eq!(first.memory_rss_bytes, Some(1024));
+        assert_eq!(first.mqtt_connection_uptime_seconds, Some(90));
+    }
+
+    #[test]
+    fn test_sample_reports_task_counters() {
+        let collector = MetricsCollector::new();
+        collector.task_processing_completed(Duration::from_millis(10));
+        collector.task_processing_completed(Duration::from_millis(10));
+        collector.task_processing_failed(Duration::from_millis(10));
+        collector.task_in_flight();
+        collector.mqtt_connection_established();
+        collector.mqtt_connection_lost();
+        collector.mqtt_connection_established();
+        let mut sampler = TelemetrySampler::new("agent-1", &collector.get_metrics());
+
+        let snapshot = sampler.sample(&collector.get_metrics(), &health(0), None);
+
+        assert_eq!(snapshot.tasks_processed, 2);
+        assert_eq!(snapshot.tasks_failed, 1);
+        assert_eq!(snapshot.queue_depth, 1);
+        assert_eq!(snapshot.mqtt_reconnects, 1);
+        assert!(snapshot.mqtt_healthy);
+    }
+
+    #[test]
+    fn test_parse_vm_rss() {
+        let status = "Name:\tagent2389\nVmPeak:\t  20000 kB\nVmRSS:\t   12345 kB\nThreads:\t8\n";
+
+        assert_eq!(parse_vm_rss(status), Some(12345 * 1024));
+        assert_eq!(parse_vm_rss("Name:\tagent2389\n"), None);
+    }
+}
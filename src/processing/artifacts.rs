@@ -0,0 +1,178 @@
+//! Response artifacts
+//!
+//! An agent attaches structured outputs to its response with an `artifacts`
+//! array next to `result` in its decision, or, for a v2 workflow, in the final
+//! output object. The artifacts are taken out of the response text and
+//! published in the response message's `artifacts` field, capped by
+//! `[agent.artifact_limits]`.
+
+use crate::config::ArtifactLimitsConfig;
+use crate::protocol::messages::Artifact;
+use serde_json::Value;
+use tracing::warn;
+use uuid::Uuid;
+
+/// Key of the artifacts array in a decision or final output
+pub const ARTIFACTS_KEY: &str = "artifacts";
+
+/// `output` without its `artifacts` array, and the artifacts it held
+///
+/// Anything but an object with an `artifacts` key is returned unchanged,
+/// with no artifacts.
+/// Pure function extracted for testability
+pub fn split_artifacts(output: &Value) -> (Value, Vec<Artifact>) {
+    match output {
+        Value::Object(object) if object.contains_key(ARTIFACTS_KEY) => {
+            let mut rest = object.clone();
+            let artifacts = rest
+                .remove(ARTIFACTS_KEY)
+                .map(|artifacts| Artifact::from_json_list(&artifacts))
+                .unwrap_or_default();
+            (Value::Object(rest), artifacts)
+        }
+        other => (other.clone(), Vec::new()),
+    }
+}
+
+/// The artifacts that fit within `limits`, in order
+///
+/// An artifact larger than `max_artifact_bytes`, or one that would take the
+/// total past `max_total_bytes`, is dropped with a warning; later, smaller
+/// artifacts may still fit.
+pub fn apply_artifact_limits(
+    artifacts: Vec<Artifact>,
+    limits: &ArtifactLimitsConfig,
+    task_id: Uuid,
+) -> Vec<Artifact> {
+    let mut total_bytes = 0usize;
+    artifacts
+        .into_iter()
+        .filter(|artifact| {
+            let bytes = artifact.data_bytes();
+            let reason = if bytes > limits.max_artifact_bytes {
+                "exceeds max_artifact_bytes"
+            } else if total_bytes + bytes > limits.max_total_bytes {
+                "would exceed max_total_bytes"
+            } else {
+                total_bytes += bytes;
+                return true;
+            };
+            warn!(
+                task_id = %task_id,
+                artifact = %artifact.name,
+                bytes,
+                "Dropping artifact that {reason}"
+            );
+            false
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn artifact(name: &str, data: Value) -> Artifact {
+        Artifact {
+            name: name.to_string(),
+            content_type: "application/json".to_string(),
+            data,
+        }
+    }
+
+    fn limits(max_artifact_bytes: usize, max_total_bytes: usize) -> ArtifactLimitsConfig {
+        ArtifactLimitsConfig {
+            max_artifact_bytes,
+            max_total_bytes,
+        }
+    }
+
+    fn names(artifacts: &[Artifact]) -> Vec<&str> {
+        artifacts.iter().map(|a| a.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_split_artifacts_removes_them_from_the_output() {
+        let output = json!({
+            "summary": "Two people found",
+            "artifacts": [
+                {"name": "people", "data": ["Ada", "Grace"]},
+                {"name": "table", "content_type": "text/csv", "data": "name\nAda\nGrace"}
+            ]
+        });
+
+        let (rest, artifacts) = split_artifacts(&output);
+
+        assert_eq!(rest, json!({"summary": "Two people found"}));
+        assert_eq!(
+            artifacts,
+            vec![
+                artifact("people", json!(["Ada", "Grace"])),
+                Artifact {
+                    content_type: "text/csv".to_string(),
+                    ..artifact("table", json!("name\nAda\nGrace"))
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_split_artifacts_leaves_other_output_alone() {
+        for output in [
+            json!("plain text"),
+            json!({"result": "done"}),
+            json!([1, 2]),
+        ] {
+            let (rest, artifacts) = split_artifacts(&output);
+            assert_eq!(rest, output);
+            assert!(artifacts.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_malformed_artifacts_are_skipped() {
+        let output = json!({
+            "artifacts": [
+                {"name": "kept", "data": 1},
+                {"name": "no data"},
+                {"name": " ", "data": 2},
+                "not an object"
+            ]
+        });
+
+        let (_, artifacts) = split_artifacts(&output);
+
+        assert_eq!(names(&artifacts), vec!["kept"]);
+        assert!(split_artifacts(&json!({"artifacts": "nope"})).1.is_empty());
+    }
+
+    #[test]
+    fn test_artifact_over_its_cap_is_dropped() {
+        let artifacts = vec![
+            artifact("small", json!("abc")),
+            artifact("big", json!("x".repeat(100))),
+            artifact("also-small", json!(1)),
+        ];
+
+        let kept = apply_artifact_limits(artifacts, &limits(50, 1000), Uuid::nil());
+
+        assert_eq!(names(&kept), vec!["small", "also-small"]);
+    }
+
+    #[test]
+    fn test_artifacts_past_the_total_cap_are_dropped() {
+        // Each serializes to 32 bytes: 30 characters and two quotes
+        let data = json!("y".repeat(30));
+        let artifacts = vec![
+            artifact("first", data.clone()),
+            artifact("second", data.clone()),
+            artifact("third", data),
+            artifact("tiny", json!(7)),
+        ];
+
+        let kept = apply_artifact_limits(artifacts, &limits(100, 70), Uuid::nil());
+
+        assert_eq!(names(&kept), vec!["first", "second", "tiny"]);
+    }
+}
@@ -0,0 +1,237 @@
+//! Authorization of inbound tasks
+//!
+//! A [`TaskAuthorizer`] decides whether the agent accepts a task, from the
+//! `[authorization]` rules: they are checked in order and the first rule
+//! matching the task decides, and a task no rule matches gets the default.
+//! A denied task fails with [`AgentError::Unauthorized`] before the LLM is
+//! called, and an `ErrorMessage` with the `unauthorized` code is published
+//! to its conversation. The message names the deciding rule by position
+//! only, so producers don't learn what the rules match on.
+
+use crate::config::{AuthorizationEffect, AuthorizationRule, AuthorizationSection};
+use crate::error::{AgentError, AgentResult};
+use crate::protocol::messages::TaskEnvelope;
+use regex::Regex;
+
+/// Rules from `[authorization]`, with their instruction patterns compiled
+#[derive(Debug)]
+pub struct TaskAuthorizer {
+    default: AuthorizationEffect,
+    rules: Vec<CompiledRule>,
+}
+
+#[derive(Debug)]
+struct CompiledRule {
+    effect: AuthorizationEffect,
+    conversation_prefixes: Vec<String>,
+    sender_ids: Vec<String>,
+    instruction_pattern: Option<Regex>,
+}
+
+impl CompiledRule {
+    fn new(rule: &AuthorizationRule) -> Result<Self, regex::Error> {
+        Ok(Self {
+            effect: rule.effect,
+            conversation_prefixes: rule.conversation_prefixes.clone(),
+            sender_ids: rule.sender_ids.clone(),
+            instruction_pattern: rule
+                .instruction_pattern
+                .as_deref()
+                .map(Regex::new)
+                .transpose()?,
+        })
+    }
+
+    /// Whether every condition the rule sets matches `task`
+    fn matches(&self, task: &TaskEnvelope) -> bool {
+        let conversation_matches = self.conversation_prefixes.is_empty()
+            || self
+                .conversation_prefixes
+                .iter()
+                .any(|prefix| task.conversation_id.starts_with(prefix.as_str()));
+        let sender_matches = self.sender_ids.is_empty()
+            || task
+                .sender_id
+                .as_ref()
+                .is_some_and(|sender| self.sender_ids.contains(sender));
+        let instruction_matches = match &self.instruction_pattern {
+            Some(pattern) => task
+                .instruction
+                .as_deref()
+                .is_some_and(|instruction| pattern.is_match(instruction)),
+            None => true,
+        };
+        conversation_matches && sender_matches && instruction_matches
+    }
+}
+
+impl TaskAuthorizer {
+    /// Compile the rules of `section`
+    pub fn new(section: &AuthorizationSection) -> Result<Self, regex::Error> {
+        Ok(Self {
+            default: section.default,
+            rules: section
+                .rules
+                .iter()
+                .map(CompiledRule::new)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    /// Accept `task`, or fail with [`AgentError::Unauthorized`]
+    pub fn authorize(&self, task: &TaskEnvelope) -> AgentResult<()> {
+        let decision = self
+            .rules
+            .iter()
+            .enumerate()
+            .find(|(_, rule)| rule.matches(task));
+        match decision {
+            Some((_, rule)) if rule.effect == AuthorizationEffect::Allow => Ok(()),
+            Some((index, _)) => Err(AgentError::unauthorized(format!(
+                "Task denied by authorization rule {}",
+                index + 1
+            ))),
+            None if self.default == AuthorizationEffect::Allow => Ok(()),
+            None => Err(AgentError::unauthorized(
+                "Task denied by the default authorization policy",
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use uuid::Uuid;
+
+    fn task(conversation_id: &str, sender_id: Option<&str>, instruction: &str) -> TaskEnvelope {
+        TaskEnvelope {
+            task_id: Uuid::new_v4(),
+            conversation_id: conversation_id.to_string(),
+            topic: "/control/agents/test-agent/input".to_string(),
+            instruction: Some(instruction.to_string()),
+            input: json!({}),
+            next: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: sender_id.map(str::to_string),
+        }
+    }
+
+    fn authorizer(toml: &str) -> TaskAuthorizer {
+        let section: AuthorizationSection = toml::from_str(toml).unwrap();
+        TaskAuthorizer::new(&section).unwrap()
+    }
+
+    #[test]
+    fn test_no_rules_allow_every_task() {
+        let authorizer = authorizer("");
+
+        assert!(authorizer.authorize(&task("any", None, "Do it")).is_ok());
+    }
+
+    #[test]
+    fn test_default_deny_rejects_unmatched_tasks() {
+        let authorizer = authorizer(
+            r#"
+            default = "deny"
+
+            [[rules]]
+            effect = "allow"
+            conversation_prefixes = ["team-a/"]
+            "#,
+        );
+
+        assert!(authorizer
+            .authorize(&task("team-a/1", None, "Do it"))
+            .is_ok());
+        let error = authorizer
+            .authorize(&task("team-b/1", None, "Do it"))
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Unauthorized: Task denied by the default authorization policy"
+        );
+    }
+
+    #[test]
+    fn test_first_matching_rule_decides() {
+        let authorizer = authorizer(
+            r#"
+            [[rules]]
+            effect = "allow"
+            sender_ids = ["trusted-agent"]
+
+            [[rules]]
+            effect = "deny"
+            instruction_pattern = "(?i)delete"
+            "#,
+        );
+
+        assert!(authorizer
+            .authorize(&task("conv", Some("trusted-agent"), "Delete the logs"))
+            .is_ok());
+        let error = authorizer
+            .authorize(&task("conv", Some("other-agent"), "Delete the logs"))
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Unauthorized: Task denied by authorization rule 2"
+        );
+    }
+
+    #[test]
+    fn test_rule_matches_only_when_every_condition_matches() {
+        let authorizer = authorizer(
+            r#"
+            [[rules]]
+            effect = "deny"
+            conversation_prefixes = ["public/"]
+            instruction_pattern = "^Export"
+            "#,
+        );
+
+        assert!(authorizer
+            .authorize(&task("public/1", None, "Export the table"))
+            .is_err());
+        assert!(authorizer
+            .authorize(&task("public/1", None, "Summarize the table"))
+            .is_ok());
+        assert!(authorizer
+            .authorize(&task("private/1", None, "Export the table"))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_sender_rule_never_matches_tasks_without_a_sender() {
+        let authorizer = authorizer(
+            r#"
+            default = "deny"
+
+            [[rules]]
+            effect = "allow"
+            sender_ids = ["trusted-agent"]
+            "#,
+        );
+
+        assert!(authorizer.authorize(&task("conv", None, "Do it")).is_err());
+    }
+
+    #[test]
+    fn test_invalid_instruction_pattern_is_rejected() {
+        let section: AuthorizationSection = toml::from_str(
+            r#"
+            [[rules]]
+            effect = "deny"
+            instruction_pattern = "("
+            "#,
+        )
+        .unwrap();
+
+        assert!(TaskAuthorizer::new(&section).is_err());
+    }
+}
@@ -0,0 +1,252 @@
+//! Shared task cancellation registry
+//!
+//! Tracks which tasks are currently in flight and which of those have been
+//! asked to cancel. The registry is cheap to clone and shared between the
+//! transport-facing pipeline (which records cancellations) and the 9-step
+//! processor (which checks for them between steps and tool iterations).
+//!
+//! Cancel requests for tasks that have not started yet (for example tasks still
+//! queued in the pipeline channel) are held as pending and applied when the
+//! task begins. Both pending cancellations and recently finished task ids are
+//! bounded so unknown task ids cannot grow memory without limit.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+/// Maximum number of pending cancellations for tasks that have not started
+const MAX_PENDING_CANCELLATIONS: usize = 1024;
+
+/// Maximum number of finished task ids remembered for no-op detection
+const MAX_FINISHED_TASKS: usize = 1024;
+
+/// Outcome of a cancel request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CancelOutcome {
+    /// Task is in flight and is now flagged for cancellation
+    Flagged,
+    /// Task has not started yet; cancellation applies when it begins
+    Pending,
+    /// Task already finished, or the conversation did not match (no-op)
+    Ignored,
+}
+
+/// Shared cancellation state for in-flight tasks
+#[derive(Debug, Clone, Default)]
+pub struct CancellationRegistry {
+    inner: Arc<Mutex<CancellationState>>,
+}
+
+#[derive(Debug, Default)]
+struct CancellationState {
+    /// Tasks currently being processed, with their conversation id
+    active: HashMap<Uuid, String>,
+    /// Active tasks that have been asked to cancel, with optional reason
+    cancelled: HashMap<Uuid, Option<String>>,
+    /// Cancellations received before the task started: (conversation_id, reason)
+    pending: HashMap<Uuid, (String, Option<String>)>,
+    pending_order: VecDeque<Uuid>,
+    /// Recently finished tasks, so late cancellations are no-ops
+    finished: HashSet<Uuid>,
+    finished_order: VecDeque<Uuid>,
+}
+
+impl CancellationRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Mark a task as in flight so that it can be cancelled
+    ///
+    /// Applies any pending cancellation recorded for the same task and conversation.
+    pub fn begin(&self, task_id: Uuid, conversation_id: &str) {
+        let mut state = self.inner.lock().unwrap();
+        state.active.insert(task_id, conversation_id.to_string());
+        state.cancelled.remove(&task_id);
+
+        if let Some((pending_conversation, reason)) = state.pending.remove(&task_id) {
+            state.pending_order.retain(|id| *id != task_id);
+            if pending_conversation == conversation_id {
+                state.cancelled.insert(task_id, reason);
+            }
+        }
+    }
+
+    /// Mark a task as finished, discarding any pending cancellation
+    pub fn finish(&self, task_id: Uuid) {
+        let mut state = self.inner.lock().unwrap();
+        state.active.remove(&task_id);
+        state.cancelled.remove(&task_id);
+
+        if state.finished.insert(task_id) {
+            state.finished_order.push_back(task_id);
+            if state.finished_order.len() > MAX_FINISHED_TASKS {
+                if let Some(oldest) = state.finished_order.pop_front() {
+                    state.finished.remove(&oldest);
+                }
+            }
+        }
+    }
+
+    /// Request cancellation of a task in the given conversation
+    ///
+    /// In-flight tasks are flagged immediately; tasks not yet started are recorded
+    /// as pending. Cancelling a completed task, or using a conversation id that does
+    /// not match the in-flight task, is a no-op.
+    pub fn cancel(
+        &self,
+        task_id: Uuid,
+        conversation_id: &str,
+        reason: Option<String>,
+    ) -> CancelOutcome {
+        let mut state = self.inner.lock().unwrap();
+
+        if let Some(active_conversation) = state.active.get(&task_id) {
+            if active_conversation != conversation_id {
+                return CancelOutcome::Ignored;
+            }
+            state.cancelled.entry(task_id).or_insert(reason);
+            return CancelOutcome::Flagged;
+        }
+
+        if state.finished.contains(&task_id) {
+            return CancelOutcome::Ignored;
+        }
+
+        if !state.pending.contains_key(&task_id) {
+            state.pending_order.push_back(task_id);
+            if state.pending_order.len() > MAX_PENDING_CANCELLATIONS {
+                if let Some(oldest) = state.pending_order.pop_front() {
+                    state.pending.remove(&oldest);
+                }
+            }
+        }
+        state
+            .pending
+            .entry(task_id)
+            .or_insert((conversation_id.to_string(), reason));
+        CancelOutcome::Pending
+    }
+
+    /// Check whether a task has been asked to cancel
+    pub fn is_cancelled(&self, task_id: &Uuid) -> bool {
+        self.inner.lock().unwrap().cancelled.contains_key(task_id)
+    }
+
+    /// Get the cancellation reason if the task has been cancelled
+    pub fn cancellation_reason(&self, task_id: &Uuid) -> Option<Option<String>> {
+        self.inner.lock().unwrap().cancelled.get(task_id).cloned()
+    }
+
+    /// Check whether a task is currently in flight
+    pub fn is_active(&self, task_id: &Uuid) -> bool {
+        self.inner.lock().unwrap().active.contains_key(task_id)
+    }
+
+    /// Number of tasks currently in flight
+    pub fn active_count(&self) -> usize {
+        self.inner.lock().unwrap().active.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cancel_active_task() {
+        let registry = CancellationRegistry::new();
+        let task_id = Uuid::new_v4();
+
+        registry.begin(task_id, "conv");
+        assert_eq!(
+            registry.cancel(task_id, "conv", Some("user abort".to_string())),
+            CancelOutcome::Flagged
+        );
+        assert!(registry.is_cancelled(&task_id));
+        assert_eq!(
+            registry.cancellation_reason(&task_id),
+            Some(Some("user abort".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_cancel_before_begin_is_applied_on_begin() {
+        let registry = CancellationRegistry::new();
+        let task_id = Uuid::new_v4();
+
+        assert_eq!(
+            registry.cancel(task_id, "conv", Some("queued".to_string())),
+            CancelOutcome::Pending
+        );
+        assert!(!registry.is_cancelled(&task_id));
+
+        registry.begin(task_id, "conv");
+        assert!(registry.is_cancelled(&task_id));
+    }
+
+    #[test]
+    fn test_cancel_with_wrong_conversation_is_ignored() {
+        let registry = CancellationRegistry::new();
+        let task_id = Uuid::new_v4();
+
+        registry.begin(task_id, "conv-a");
+        assert_eq!(
+            registry.cancel(task_id, "conv-b", None),
+            CancelOutcome::Ignored
+        );
+        assert!(!registry.is_cancelled(&task_id));
+
+        // Pending cancellation for the wrong conversation is dropped at begin
+        let queued = Uuid::new_v4();
+        registry.cancel(queued, "conv-b", None);
+        registry.begin(queued, "conv-a");
+        assert!(!registry.is_cancelled(&queued));
+    }
+
+    #[test]
+    fn test_cancel_completed_task_is_noop() {
+        let registry = CancellationRegistry::new();
+        let task_id = Uuid::new_v4();
+
+        registry.begin(task_id, "conv");
+        registry.finish(task_id);
+
+        assert_eq!(
+            registry.cancel(task_id, "conv", None),
+            CancelOutcome::Ignored
+        );
+        assert!(!registry.is_cancelled(&task_id));
+        assert!(!registry.is_active(&task_id));
+    }
+
+    #[test]
+    fn test_pending_cancellations_are_bounded() {
+        let registry = CancellationRegistry::new();
+        let first = Uuid::new_v4();
+        registry.cancel(first, "conv", None);
+
+        for _ in 0..MAX_PENDING_CANCELLATIONS {
+            registry.cancel(Uuid::new_v4(), "conv", None);
+        }
+
+        // Oldest pending entry was evicted
+        registry.begin(first, "conv");
+        assert!(!registry.is_cancelled(&first));
+    }
+
+    #[test]
+    fn test_repeated_cancel_keeps_first_reason() {
+        let registry = CancellationRegistry::new();
+        let task_id = Uuid::new_v4();
+
+        registry.begin(task_id, "conv");
+        registry.cancel(task_id, "conv", Some("first".to_string()));
+        registry.cancel(task_id, "conv", Some("second".to_string()));
+        assert_eq!(
+            registry.cancellation_reason(&task_id),
+            Some(Some("first".to_string()))
+        );
+    }
+}
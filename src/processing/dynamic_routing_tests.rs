@@ -14,8 +14,9 @@ mod tests {
     use crate::agent::pipeline::AgentPipeline;
     use crate::agent::processor::AgentProcessor;
     use crate::config::{AgentConfig, AgentSection, BudgetConfig, LlmSection, MqttSection};
-    use crate::protocol::{TaskEnvelopeV2, WorkflowContext, WorkflowStep};
+    use crate::protocol::{ResponseMessage, TaskEnvelopeV2, WorkflowContext, WorkflowStep};
     use crate::routing::{Router, RoutingDecision};
+    use crate::secrets::SecretRef;
     use crate::testing::mocks::{MockAgentRegistry, MockLlmProvider, MockTransport};
     use serde_json::{json, Value};
     use std::collections::HashMap;
@@ -23,6 +24,30 @@ mod tests {
     use tokio::sync::mpsc;
     use uuid::Uuid;
 
+    /// Tasks forwarded to other agents, with the input topic each went to
+    async fn forwarded_tasks(transport: &MockTransport) -> Vec<(String, TaskEnvelopeV2)> {
+        transport
+            .get_published_task_envelopes()
+            .await
+            .into_iter()
+            .map(|(topic, envelope)| (topic, envelope.to_v2()))
+            .collect()
+    }
+
+    /// Final workflow results published to a conversation
+    async fn final_results(
+        transport: &MockTransport,
+        conversation_id: &str,
+    ) -> Vec<ResponseMessage> {
+        transport
+            .get_published_responses()
+            .await
+            .into_iter()
+            .filter(|(conversation, _)| conversation == conversation_id)
+            .map(|(_, response)| response)
+            .collect()
+    }
+
     /// Create a basic test configuration
     fn create_test_config() -> AgentConfig {
         AgentConfig {
@@ -30,24 +55,48 @@ mod tests {
                 id: "test-agent".to_string(),
                 description: "Test agent".to_string(),
                 capabilities: vec!["test".to_string()],
+                max_input_bytes: None,
+                publish_acks: false,
+                max_concurrent_tasks: 1,
+                state_dir: None,
+                idempotency_ttl_secs: 86400,
+                max_task_retries: 0,
+                retry_base_delay_ms: 500,
+                pause_mode: Default::default(),
+                persistence: None,
+                max_task_age_secs: None,
+                idle_after_secs: None,
+                response_limit: None,
+                artifact_limits: Default::default(),
             },
             mqtt: MqttSection {
                 broker_url: "mqtt://localhost:1883".to_string(),
                 username_env: None,
                 password_env: None,
                 heartbeat_interval_secs: 900,
+                payload_format: Default::default(),
             },
             llm: LlmSection {
                 provider: "mock".to_string(),
                 model: "mock-model".to_string(),
-                api_key_env: "MOCK_API_KEY".to_string(),
+                api_key_env: SecretRef::env("MOCK_API_KEY"),
                 system_prompt: "You are a test agent".to_string(),
                 temperature: Some(0.7),
                 max_tokens: Some(1000),
+                allowed_override_models: Vec::new(),
+                prices: Default::default(),
+                prompt_caching: false,
             },
             tools: HashMap::new(),
             budget: BudgetConfig::default(),
+            processing: Default::default(),
             routing: None,
+            security: Default::default(),
+            authorization: Default::default(),
+            progress: Default::default(),
+            observability: Default::default(),
+            testing: Default::default(),
+            protocol: Default::default(),
         }
     }
 
@@ -68,6 +117,12 @@ mod tests {
             version: "2.0".to_string(),
             context,
             routing_trace: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
         }
     }
 
@@ -106,6 +161,7 @@ mod tests {
                 next_agent: self.next_agent.clone(),
                 next_instruction: self.next_instruction.clone(),
                 forwarded_data: work_output.clone(),
+                sticky: true,
             })
         }
     }
@@ -168,21 +224,51 @@ mod tests {
         assert!(result.is_ok(), "Workflow should complete successfully");
 
         // Verify final result was published
-        let published_messages = transport.get_published_messages().await;
-        assert!(
-            !published_messages.is_empty(),
-            "Should publish final result"
-        );
+        let results = final_results(&transport, &task.conversation_id).await;
 
-        // Find the conversation topic message (format: /conversations/{conversation_id}/{agent_id})
-        let final_result = published_messages.iter().find(|(topic, _)| {
-            topic.starts_with(&format!("/conversations/{}", task.conversation_id))
-        });
+        assert!(!results.is_empty(), "Should publish to conversation topic");
+    }
 
-        assert!(
-            final_result.is_some(),
-            "Should publish to conversation topic"
+    #[tokio::test]
+    async fn test_final_result_carries_correlation_and_trace() {
+        // Arrange: a forwarded task that arrives with correlation and a trace
+        let registry = MockAgentRegistry::new();
+        let (pipeline, transport) = create_test_pipeline(
+            Arc::new(AlwaysCompleteRouter),
+            Arc::new(registry.registry().clone()),
+            10,
         );
+        let parent_task_id = Uuid::new_v4();
+        let trace = vec![crate::protocol::RoutingStep {
+            from_agent: "writer-agent".to_string(),
+            to_agent: "test-agent".to_string(),
+            reason: "Needs review".to_string(),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            step_number: 1,
+        }];
+        let task = TaskEnvelopeV2 {
+            correlation_id: Some("request-42".to_string()),
+            parent_task_id: Some(parent_task_id),
+            routing_trace: Some(trace.clone()),
+            ..create_test_task(Uuid::new_v4(), "correlated-conversation", None, None)
+        };
+
+        // Act
+        pipeline
+            .process_with_routing(task.clone(), json!("All done"))
+            .await
+            .unwrap();
+
+        // Assert
+        let result = final_results(&transport, "correlated-conversation")
+            .await
+            .pop()
+            .expect("Should publish final result");
+        assert_eq!(result.task_id, task.task_id);
+        assert_eq!(result.response, "All done");
+        assert_eq!(result.correlation_id.as_deref(), Some("request-42"));
+        assert_eq!(result.parent_task_id, Some(parent_task_id));
+        assert_eq!(result.routing_trace, Some(trace));
     }
 
     #[tokio::test]
@@ -217,15 +303,12 @@ mod tests {
         assert!(result.is_ok(), "Workflow should forward successfully");
 
         // Verify task was forwarded
-        let published_messages = transport.get_published_messages().await;
-        assert!(
-            !published_messages.is_empty(),
-            "Should publish forward task"
-        );
+        let forwarded = forwarded_tasks(&transport).await;
+        assert!(!forwarded.is_empty(), "Should publish forward task");
 
         // Find the forwarded task
-        let forwarded_task_msg = published_messages
-            .iter()
+        let forwarded_task_msg = forwarded
+            .into_iter()
             .find(|(topic, _)| topic.contains("processor-agent"));
 
         assert!(
@@ -234,8 +317,7 @@ mod tests {
         );
 
         // Parse and verify the forwarded task
-        let (_, payload) = forwarded_task_msg.unwrap();
-        let forwarded_task: TaskEnvelopeV2 = serde_json::from_slice(payload).unwrap();
+        let (_, forwarded_task) = forwarded_task_msg.unwrap();
 
         // Note: Task ID is NOT preserved - new UUID generated for forwarded task
         assert_ne!(
@@ -298,15 +380,14 @@ mod tests {
         assert!(result.is_ok(), "First forward should succeed");
 
         // Verify task was forwarded to analyzer
-        let published_messages = transport.get_published_messages().await;
-        let analyzer_msg = published_messages
-            .iter()
+        let analyzer_msg = forwarded_tasks(&transport)
+            .await
+            .into_iter()
             .find(|(topic, _)| topic.contains("analyzer"));
 
         assert!(analyzer_msg.is_some(), "Should forward to analyzer");
 
-        let (_, payload) = analyzer_msg.unwrap();
-        let forwarded_task: TaskEnvelopeV2 = serde_json::from_slice(payload).unwrap();
+        let (_, forwarded_task) = analyzer_msg.unwrap();
 
         // Verify workflow context
         let context = forwarded_task.context.unwrap();
@@ -357,6 +438,10 @@ mod tests {
                     },
                 ],
                 iteration_count: 2, // Already at limit
+                started_at: None,
+                budget_secs: None,
+                state: None,
+                state_order: Vec::new(),
             }),
         );
 
@@ -371,19 +456,17 @@ mod tests {
         assert!(result.is_ok(), "Should handle max iterations gracefully");
 
         // Verify final result was published (not forwarded)
-        let published_messages = transport.get_published_messages().await;
-        let final_result = published_messages.iter().find(|(topic, _)| {
-            topic.starts_with(&format!("/conversations/{}", task.conversation_id))
-        });
+        let results = final_results(&transport, &task.conversation_id).await;
 
         assert!(
-            final_result.is_some(),
+            !results.is_empty(),
             "Should publish final result when hitting iteration limit"
         );
 
         // Verify NO forwarding occurred
-        let forwarded = published_messages
-            .iter()
+        let forwarded = forwarded_tasks(&transport)
+            .await
+            .into_iter()
             .find(|(topic, _)| topic.contains("next-agent"));
         assert!(
             forwarded.is_none(),
@@ -422,15 +505,12 @@ mod tests {
         assert!(result.is_ok(), "Should forward successfully");
 
         // Verify iteration count incremented
-        let published_messages = transport.get_published_messages().await;
-        let forwarded_msg = published_messages
-            .iter()
+        let (_, forwarded_task) = forwarded_tasks(&transport)
+            .await
+            .into_iter()
             .find(|(topic, _)| topic.contains("next-agent"))
             .expect("Should forward task");
 
-        let (_, payload) = forwarded_msg;
-        let forwarded_task: TaskEnvelopeV2 = serde_json::from_slice(payload).unwrap();
-
         assert!(forwarded_task.context.is_some(), "Context should exist");
         assert_eq!(
             forwarded_task.context.unwrap().iteration_count,
@@ -506,6 +586,10 @@ mod tests {
                     timestamp: chrono::Utc::now().to_rfc3339(),
                 }],
                 iteration_count: 1,
+                started_at: None,
+                budget_secs: None,
+                state: None,
+                state_order: Vec::new(),
             }),
         );
 
@@ -520,15 +604,12 @@ mod tests {
         assert!(result.is_ok(), "Should forward successfully");
 
         // Verify history preserved and extended
-        let published_messages = transport.get_published_messages().await;
-        let forwarded_msg = published_messages
-            .iter()
+        let (_, forwarded_task) = forwarded_tasks(&transport)
+            .await
+            .into_iter()
             .find(|(topic, _)| topic.contains("agent2"))
             .expect("Should forward to agent2");
 
-        let (_, payload) = forwarded_msg;
-        let forwarded_task: TaskEnvelopeV2 = serde_json::from_slice(payload).unwrap();
-
         let context = forwarded_task.context.expect("Context should exist");
 
         // Original query preserved
@@ -619,13 +700,10 @@ mod tests {
         assert!(result.is_ok(), "LlmRouter should complete workflow");
 
         // Verify final result was published
-        let published_messages = transport.get_published_messages().await;
-        let final_result = published_messages.iter().find(|(topic, _)| {
-            topic.starts_with(&format!("/conversations/{}", task.conversation_id))
-        });
+        let results = final_results(&transport, &task.conversation_id).await;
 
         assert!(
-            final_result.is_some(),
+            !results.is_empty(),
             "Should publish final result via LlmRouter"
         );
     }
@@ -688,9 +766,9 @@ mod tests {
         assert!(result.is_ok(), "LlmRouter should forward to next agent");
 
         // Verify forwarding occurred
-        let published_messages = transport.get_published_messages().await;
-        let forwarded_task = published_messages
-            .iter()
+        let forwarded_task = forwarded_tasks(&transport)
+            .await
+            .into_iter()
             .find(|(topic, _)| topic.contains("processor-agent"));
 
         assert!(
@@ -768,13 +846,10 @@ mod tests {
         assert!(result.is_ok(), "GatekeeperRouter should complete workflow");
 
         // Verify final result was published
-        let published_messages = transport.get_published_messages().await;
-        let final_result = published_messages.iter().find(|(topic, _)| {
-            topic.starts_with(&format!("/conversations/{}", task.conversation_id))
-        });
+        let results = final_results(&transport, &task.conversation_id).await;
 
         assert!(
-            final_result.is_some(),
+            !results.is_empty(),
             "Should publish final result via GatekeeperRouter"
         );
     }
@@ -847,9 +922,9 @@ mod tests {
         );
 
         // Verify forwarding occurred
-        let published_messages = transport.get_published_messages().await;
-        let forwarded_task = published_messages
-            .iter()
+        let forwarded_task = forwarded_tasks(&transport)
+            .await
+            .into_iter()
             .find(|(topic, _)| topic.contains("editor-agent"));
 
         assert!(
@@ -857,4 +932,1009 @@ mod tests {
             "Should forward to editor-agent via GatekeeperRouter"
         );
     }
+
+    // ========== DECISION CACHE TESTS ==========
+
+    /// Forwarding router that counts how often it is invoked
+    struct CountingRouter {
+        next_agent: String,
+        calls: std::sync::atomic::AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl Router for CountingRouter {
+        async fn decide_next_step(
+            &self,
+            _task: &TaskEnvelopeV2,
+            work_output: &Value,
+            _agent_registry: &crate::agent::discovery::AgentRegistry,
+        ) -> Result<RoutingDecision, crate::error::AgentError> {
+            self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok(RoutingDecision::Forward {
+                next_agent: self.next_agent.clone(),
+                next_instruction: "Edit the draft".to_string(),
+                forwarded_data: work_output.clone(),
+                sticky: true,
+            })
+        }
+    }
+
+    async fn forwarded_reasons(transport: &MockTransport) -> Vec<String> {
+        forwarded_tasks(transport)
+            .await
+            .into_iter()
+            .filter(|(topic, _)| topic.contains("editor-agent"))
+            .map(|(_, task)| task.routing_trace.unwrap().last().unwrap().reason.clone())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_decision_cache_hit_skips_router_and_marks_trace() {
+        // Arrange: same decision point reached twice (e.g. a retried hop)
+        let registry = MockAgentRegistry::new();
+        registry.register_agent("editor-agent", vec!["editing"]);
+        let router = Arc::new(CountingRouter {
+            next_agent: "editor-agent".to_string(),
+            calls: Default::default(),
+        });
+        let (mut pipeline, transport) =
+            create_test_pipeline(router.clone(), Arc::new(registry.registry().clone()), 10);
+        pipeline.set_decision_cache(Arc::new(crate::routing::DecisionCache::new(
+            crate::config::DecisionCacheConfig::default(),
+        )));
+        let task = create_test_task(Uuid::new_v4(), "cache-conversation", None, None);
+        let work_output = json!({"draft": "v1"});
+
+        // Act
+        for _ in 0..2 {
+            pipeline
+                .process_with_routing(task.clone(), work_output.clone())
+                .await
+                .unwrap();
+        }
+
+        // Assert: router consulted once, second hop marked as cached
+        assert_eq!(router.calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+        assert_eq!(
+            forwarded_reasons(&transport).await,
+            vec![
+                "Router decision: Edit the draft".to_string(),
+                "Router decision (cached): Edit the draft".to_string(),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_decision_cache_revalidates_unhealthy_target() {
+        // Arrange: cached forward to an agent that later reports an error
+        let registry = MockAgentRegistry::new();
+        registry.register_agent("editor-agent", vec!["editing"]);
+        let router = Arc::new(CountingRouter {
+            next_agent: "editor-agent".to_string(),
+            calls: Default::default(),
+        });
+        let (mut pipeline, transport) =
+            create_test_pipeline(router.clone(), Arc::new(registry.registry().clone()), 10);
+        pipeline.set_decision_cache(Arc::new(crate::routing::DecisionCache::new(
+            crate::config::DecisionCacheConfig::default(),
+        )));
+        let task = create_test_task(Uuid::new_v4(), "cache-conversation", None, None);
+        let work_output = json!({"draft": "v1"});
+
+        pipeline
+            .process_with_routing(task.clone(), work_output.clone())
+            .await
+            .unwrap();
+        let mut unhealthy = registry.registry().get_agent("editor-agent").unwrap();
+        unhealthy.health = "error".to_string();
+        registry.registry().register_agent(unhealthy);
+
+        // Act
+        pipeline
+            .process_with_routing(task, work_output)
+            .await
+            .unwrap();
+
+        // Assert: the stale entry was not reused, so the router decided again
+        assert_eq!(router.calls.load(std::sync::atomic::Ordering::SeqCst), 2);
+        assert!(forwarded_reasons(&transport)
+            .await
+            .iter()
+            .all(|reason| !reason.contains("cached")));
+    }
+
+    // ========== CYCLE DETECTION TESTS ==========
+
+    fn history(hops: &[(&str, &str)]) -> Vec<WorkflowStep> {
+        hops.iter()
+            .map(|(agent, action)| WorkflowStep {
+                agent_id: agent.to_string(),
+                action: action.to_string(),
+                timestamp: chrono::Utc::now().to_rfc3339(),
+            })
+            .collect()
+    }
+
+    /// Assert the workflow was completed as a cycle rather than forwarded
+    async fn assert_completed_as_cycle(
+        transport: &MockTransport,
+        conversation_id: &str,
+        next_agent: &str,
+        expected_agents: Value,
+    ) {
+        assert!(
+            !forwarded_tasks(transport)
+                .await
+                .iter()
+                .any(|(topic, _)| topic == &format!("/control/agents/{next_agent}/input")),
+            "Should NOT forward once a cycle is detected"
+        );
+
+        let result = final_results(transport, conversation_id)
+            .await
+            .pop()
+            .expect("Should publish final result");
+        let output: Value = serde_json::from_str(&result.response).unwrap();
+
+        let published = transport.get_published_messages().await;
+        assert_eq!(output["cycle_detected"], true);
+        assert_eq!(output["cycle_agents"], expected_agents);
+
+        let warning = published
+            .iter()
+            .filter(|(topic, _)| topic == "/control/agents/test-agent/progress")
+            .map(|(_, payload)| serde_json::from_slice::<Value>(payload).unwrap())
+            .find(|event| event["event_type"] == "Warning")
+            .expect("Should report a warning progress event");
+        assert_eq!(warning["metadata"]["cycle_agents"], expected_agents);
+    }
+
+    #[tokio::test]
+    async fn test_ping_pong_cycle_completes_workflow() {
+        // Arrange: test-agent and editor-agent have bounced the draft back and forth
+        let registry = MockAgentRegistry::new();
+        registry.register_agent("editor-agent", vec!["editing"]);
+        let router = ForwardToAgentRouter {
+            next_agent: "editor-agent".to_string(),
+            next_instruction: "Please edit once more".to_string(),
+        };
+        let (pipeline, transport) =
+            create_test_pipeline(Arc::new(router), Arc::new(registry.registry().clone()), 20);
+
+        let task = create_test_task(
+            Uuid::new_v4(),
+            "ping-pong-conversation",
+            Some("Revise".to_string()),
+            Some(WorkflowContext {
+                original_query: "Write an article".to_string(),
+                steps_completed: history(&[
+                    ("editor-agent", "Revise the intro"),
+                    ("test-agent", "Edit the draft"),
+                    ("editor-agent", "Tighten paragraph two"),
+                    ("test-agent", "Edit again"),
+                    ("editor-agent", "Fix the conclusion"),
+                ]),
+                iteration_count: 5,
+                started_at: None,
+                budget_secs: None,
+                state: None,
+                state_order: Vec::new(),
+            }),
+        );
+        let detected_before = crate::observability::metrics::metrics()
+            .get_metrics()
+            .tasks
+            .workflow_cycles_detected;
+
+        // Act
+        pipeline
+            .process_with_routing(task, json!({"draft": "v6"}))
+            .await
+            .unwrap();
+
+        // Assert
+        assert_completed_as_cycle(
+            &transport,
+            "ping-pong-conversation",
+            "editor-agent",
+            json!(["editor-agent", "test-agent"]),
+        )
+        .await;
+        assert!(
+            crate::observability::metrics::metrics()
+                .get_metrics()
+                .tasks
+                .workflow_cycles_detected
+                > detected_before
+        );
+    }
+
+    #[tokio::test]
+    async fn test_three_agent_cycle_completes_workflow() {
+        // Arrange: A -> B -> C -> A with identical instructions each turn
+        let registry = MockAgentRegistry::new();
+        registry.register_agent("writer-agent", vec!["writing"]);
+        let router = ForwardToAgentRouter {
+            next_agent: "writer-agent".to_string(),
+            next_instruction: "Write".to_string(),
+        };
+        let (mut pipeline, transport) =
+            create_test_pipeline(Arc::new(router), Arc::new(registry.registry().clone()), 20);
+        pipeline.set_cycle_repeat_threshold(1);
+
+        let task = create_test_task(
+            Uuid::new_v4(),
+            "three-agent-conversation",
+            Some("Research".to_string()),
+            Some(WorkflowContext {
+                original_query: "Write an article".to_string(),
+                steps_completed: history(&[
+                    ("test-agent", "Write"),
+                    ("writer-agent", "Edit"),
+                    ("editor-agent", "Research"),
+                ]),
+                iteration_count: 3,
+                started_at: None,
+                budget_secs: None,
+                state: None,
+                state_order: Vec::new(),
+            }),
+        );
+
+        // Act: one hop short of a full second turn still forwards
+        pipeline
+            .process_with_routing(task, json!({"notes": "sources"}))
+            .await
+            .unwrap();
+        assert!(forwarded_tasks(&transport)
+            .await
+            .iter()
+            .any(|(topic, _)| topic == "/control/agents/writer-agent/input"));
+        transport.clear_history().await;
+
+        let looped = create_test_task(
+            Uuid::new_v4(),
+            "three-agent-conversation",
+            Some("Research".to_string()),
+            Some(WorkflowContext {
+                original_query: "Write an article".to_string(),
+                steps_completed: history(&[
+                    ("writer-agent", "Edit"),
+                    ("editor-agent", "Research"),
+                    ("test-agent", "Write"),
+                    ("writer-agent", "Edit"),
+                    ("editor-agent", "Research"),
+                ]),
+                iteration_count: 6,
+                started_at: None,
+                budget_secs: None,
+                state: None,
+                state_order: Vec::new(),
+            }),
+        );
+        pipeline
+            .process_with_routing(looped, json!({"notes": "sources"}))
+            .await
+            .unwrap();
+
+        // Assert
+        assert_completed_as_cycle(
+            &transport,
+            "three-agent-conversation",
+            "writer-agent",
+            json!(["writer-agent", "editor-agent", "test-agent"]),
+        )
+        .await;
+    }
+
+    // ========== WORKFLOW BUDGET TESTS ==========
+
+    #[tokio::test]
+    async fn test_started_workflow_carries_budget_to_next_agent() {
+        let registry = MockAgentRegistry::new();
+        registry.register_agent("editor-agent", vec!["editing"]);
+        let router = ForwardToAgentRouter {
+            next_agent: "editor-agent".to_string(),
+            next_instruction: "Polish the draft".to_string(),
+        };
+        let (mut pipeline, transport) =
+            create_test_pipeline(Arc::new(router), Arc::new(registry.registry().clone()), 10);
+        pipeline.set_workflow_budget_secs(Some(300));
+        let task = create_test_task(Uuid::new_v4(), "budget-conversation", None, None);
+
+        let before = chrono::Utc::now();
+        pipeline
+            .process_with_routing(task, json!({"draft": "v1"}))
+            .await
+            .unwrap();
+
+        let (_, forwarded) = forwarded_tasks(&transport).await.pop().expect("forwarded");
+        let context = forwarded.context.expect("context synthesized");
+        assert!(context
+            .started_at
+            .is_some_and(|started_at| started_at >= before));
+        assert_eq!(context.budget_secs, Some(300));
+    }
+
+    #[tokio::test]
+    async fn test_exhausted_budget_completes_workflow() {
+        let registry = MockAgentRegistry::new();
+        registry.register_agent("editor-agent", vec!["editing"]);
+        let router = ForwardToAgentRouter {
+            next_agent: "editor-agent".to_string(),
+            next_instruction: "Polish the draft".to_string(),
+        };
+        let (pipeline, transport) =
+            create_test_pipeline(Arc::new(router), Arc::new(registry.registry().clone()), 10);
+        let task = create_test_task(
+            Uuid::new_v4(),
+            "slow-conversation",
+            Some("Polish".to_string()),
+            Some(WorkflowContext {
+                original_query: "Write an article".to_string(),
+                steps_completed: Vec::new(),
+                iteration_count: 1,
+                started_at: Some(chrono::Utc::now() - chrono::Duration::seconds(3600)),
+                budget_secs: Some(60),
+                state: None,
+                state_order: Vec::new(),
+            }),
+        );
+
+        pipeline
+            .process_with_routing(task, json!({"draft": "v1"}))
+            .await
+            .unwrap();
+
+        assert!(forwarded_tasks(&transport).await.is_empty());
+        let result = final_results(&transport, "slow-conversation")
+            .await
+            .pop()
+            .expect("Should publish final result");
+        let output: Value = serde_json::from_str(&result.response).unwrap();
+        assert_eq!(output, json!({"draft": "v1", "budget_exhausted": true}));
+    }
+
+    // ========== DRY-RUN TESTS ==========
+
+    fn published_explanations(
+        published: &[(String, Vec<u8>)],
+    ) -> Vec<crate::protocol::RoutingExplanation> {
+        published
+            .iter()
+            .filter(|(topic, _)| topic == "/control/agents/test-agent/routing/explain")
+            .map(|(_, payload)| serde_json::from_slice(payload).unwrap())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_forward_outcomes_feed_load_aware_error_rate() {
+        let registry = MockAgentRegistry::new();
+        registry.register_agent("editor-agent", vec!["editing"]);
+        let router = Arc::new(ForwardToAgentRouter {
+            next_agent: "editor-agent".to_string(),
+            next_instruction: "Polish the draft".to_string(),
+        });
+        let registry = Arc::new(registry.registry().clone());
+
+        // A failed publish counts against the target agent
+        let failing_transport = Arc::new(MockTransport::with_failure());
+        let processor = AgentProcessor::new(
+            create_test_config(),
+            Arc::new(MockLlmProvider::single_response("Test response")),
+            Arc::new(crate::tools::ToolSystem::new()),
+            failing_transport,
+        );
+        let (_tx, rx) = mpsc::channel(10);
+        let pipeline =
+            AgentPipeline::with_router(processor, rx, 16, router.clone(), registry.clone(), 10);
+        let task = create_test_task(Uuid::new_v4(), "selector-conversation", None, None);
+        assert!(pipeline
+            .process_with_routing(task.clone(), json!({"draft": "v1"}))
+            .await
+            .is_err());
+        let selector = pipeline
+            .processor()
+            .routing_helper()
+            .unwrap()
+            .load_aware_selector();
+        assert_eq!(selector.error_rate("editor-agent"), 1.0);
+
+        // A successful forward counts for it
+        let (pipeline, _transport) = create_test_pipeline(router, registry, 10);
+        let selector = pipeline
+            .processor()
+            .routing_helper()
+            .unwrap()
+            .load_aware_selector()
+            .clone();
+        selector.record_failure("editor-agent");
+        pipeline
+            .process_with_routing(task, json!({"draft": "v1"}))
+            .await
+            .unwrap();
+        assert_eq!(selector.error_rate("editor-agent"), 0.5);
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_explains_forward_without_forwarding() {
+        // Arrange
+        let registry = MockAgentRegistry::new();
+        registry.register_agent("editor-agent", vec!["editing"]);
+        let router = ForwardToAgentRouter {
+            next_agent: "editor-agent".to_string(),
+            next_instruction: "Polish the draft".to_string(),
+        };
+        let (mut pipeline, transport) =
+            create_test_pipeline(Arc::new(router), Arc::new(registry.registry().clone()), 10);
+        pipeline.set_dry_run(true);
+        let task = create_test_task(Uuid::new_v4(), "dry-run-conversation", None, None);
+        let work_output = json!({"draft": "v1"});
+
+        // Act
+        pipeline
+            .process_with_routing(task.clone(), work_output.clone())
+            .await
+            .unwrap();
+
+        // Assert: the would-be forward is explained, not performed
+        let published = transport.get_published_messages().await;
+        assert!(transport.get_published_tasks().await.is_empty());
+
+        let explanations = published_explanations(&published);
+        assert_eq!(explanations.len(), 1);
+        assert_eq!(explanations[0].task_id, task.task_id);
+        assert!(!explanations[0].workflow_complete);
+        assert_eq!(explanations[0].next_agent.as_deref(), Some("editor-agent"));
+        assert_eq!(
+            explanations[0].next_instruction.as_deref(),
+            Some("Polish the draft")
+        );
+        assert_eq!(explanations[0].reasoning, None);
+
+        // The workflow completes locally with the agent's own output
+        let result = final_results(&transport, "dry-run-conversation")
+            .await
+            .pop()
+            .expect("Should publish final result");
+        assert_eq!(
+            serde_json::from_str::<Value>(&result.response).unwrap(),
+            work_output
+        );
+    }
+
+    #[tokio::test]
+    async fn test_routing_config_applies_to_pipeline() {
+        // Arrange: dry run and the audit log come from `[routing]`
+        let audit_path =
+            std::env::temp_dir().join(format!("routing-audit-{}.jsonl", Uuid::new_v4()));
+        let mut config = create_test_config();
+        config.routing = Some(
+            toml::from_str(&format!(
+                "strategy = \"llm\"\ndry_run = true\naudit_log = {:?}\n[llm]\nprovider = \"openai\"\nmodel = \"gpt-4o-mini\"\n",
+                audit_path
+            ))
+            .unwrap(),
+        );
+        let registry = MockAgentRegistry::new();
+        registry.register_agent("editor-agent", vec!["editing"]);
+        let transport = Arc::new(MockTransport::new());
+        let processor = AgentProcessor::new(
+            config,
+            Arc::new(MockLlmProvider::single_response("Test response")),
+            Arc::new(crate::tools::ToolSystem::new()),
+            transport.clone(),
+        );
+        let (_tx, rx) = mpsc::channel(10);
+        let router = ForwardToAgentRouter {
+            next_agent: "editor-agent".to_string(),
+            next_instruction: "Polish the draft".to_string(),
+        };
+        let pipeline = AgentPipeline::with_router(
+            processor,
+            rx,
+            16,
+            Arc::new(router),
+            Arc::new(registry.registry().clone()),
+            10,
+        );
+        let task = create_test_task(Uuid::new_v4(), "configured-conversation", None, None);
+
+        // Act
+        pipeline
+            .process_with_routing(task, json!({"draft": "v1"}))
+            .await
+            .unwrap();
+
+        // Assert: nothing is forwarded and the decision is audited
+        assert!(forwarded_tasks(&transport).await.is_empty());
+        assert_eq!(
+            final_results(&transport, "configured-conversation")
+                .await
+                .len(),
+            1
+        );
+        let entries = read_audit_log(&audit_path);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].next_agent.as_deref(), Some("editor-agent"));
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_includes_llm_router_reasoning() {
+        use crate::routing::llm_router::LlmRouter;
+        use crate::routing::schema::RoutingDecisionOutput;
+
+        // Arrange
+        let decision_json = serde_json::to_string(&RoutingDecisionOutput {
+            workflow_complete: false,
+            reasoning: "Draft needs a copy edit".to_string(),
+            next_agent: Some("editor-agent".to_string()),
+            next_instruction: Some("Copy edit the draft".to_string()),
+        })
+        .unwrap();
+        let router = LlmRouter::new(
+            Arc::new(MockLlmProvider::single_response(&decision_json)),
+            "mock-model".to_string(),
+        );
+        let registry = MockAgentRegistry::new();
+        registry.register_agent("editor-agent", vec!["editing"]);
+        let (mut pipeline, transport) =
+            create_test_pipeline(Arc::new(router), Arc::new(registry.registry().clone()), 10);
+        pipeline.set_dry_run(true);
+        let task = create_test_task(Uuid::new_v4(), "dry-run-conversation", None, None);
+
+        // Act
+        pipeline
+            .process_with_routing(task, json!({"draft": "v1"}))
+            .await
+            .unwrap();
+
+        // Assert
+        let published = transport.get_published_messages().await;
+        let explanations = published_explanations(&published);
+        assert_eq!(explanations.len(), 1);
+        assert_eq!(
+            explanations[0].reasoning.as_deref(),
+            Some("Draft needs a copy edit")
+        );
+        assert!(forwarded_tasks(&transport).await.is_empty());
+    }
+
+    // ========== STICKY ROUTING TESTS ==========
+
+    /// Router that forwards to `next_agent`: any agent with the writing
+    /// capability, or a logical name shared by the writer replicas
+    struct StickyTestRouter {
+        next_agent: &'static str,
+        sticky: bool,
+    }
+
+    #[async_trait::async_trait]
+    impl Router for StickyTestRouter {
+        async fn decide_next_step(
+            &self,
+            _task: &TaskEnvelopeV2,
+            work_output: &Value,
+            _agent_registry: &crate::agent::discovery::AgentRegistry,
+        ) -> Result<RoutingDecision, crate::error::AgentError> {
+            Ok(RoutingDecision::Forward {
+                next_agent: self.next_agent.to_string(),
+                next_instruction: "Continue the draft".to_string(),
+                forwarded_data: work_output.clone(),
+                sticky: self.sticky,
+            })
+        }
+    }
+
+    fn register_writer(registry: &AgentRegistry, agent_id: &str, health: &str, load: f64) {
+        registry.register_agent(
+            crate::agent::discovery::AgentInfo::new(agent_id.to_string(), health.to_string(), load)
+                .with_capabilities(vec!["writing".to_string()]),
+        );
+    }
+
+    fn create_sticky_pipeline(
+        sticky: bool,
+        config: crate::config::StickyRoutingConfig,
+    ) -> (
+        AgentPipeline<MockTransport>,
+        Arc<MockTransport>,
+        Arc<AgentRegistry>,
+    ) {
+        create_sticky_pipeline_to("capability:writing", sticky, config)
+    }
+
+    fn create_sticky_pipeline_to(
+        next_agent: &'static str,
+        sticky: bool,
+        config: crate::config::StickyRoutingConfig,
+    ) -> (
+        AgentPipeline<MockTransport>,
+        Arc<MockTransport>,
+        Arc<AgentRegistry>,
+    ) {
+        let registry = Arc::new(AgentRegistry::new());
+        register_writer(&registry, "writer-1", "ok", 0.2);
+        register_writer(&registry, "writer-2", "ok", 0.5);
+        let router = StickyTestRouter { next_agent, sticky };
+        let (mut pipeline, transport) =
+            create_test_pipeline(Arc::new(router), registry.clone(), 10);
+        pipeline.set_sticky_routes(Arc::new(crate::routing::StickyRoutes::new(config)));
+        (pipeline, transport, registry)
+    }
+
+    /// Route one task for a conversation and return the agent it was forwarded to
+    async fn route_conversation(
+        pipeline: &AgentPipeline<MockTransport>,
+        transport: &MockTransport,
+        conversation_id: &str,
+    ) -> String {
+        transport.clear_history().await;
+        let task = create_test_task(Uuid::new_v4(), conversation_id, None, None);
+        pipeline
+            .process_with_routing(task, json!({"draft": "v1"}))
+            .await
+            .unwrap();
+
+        let (_, forwarded) = forwarded_tasks(transport)
+            .await
+            .into_iter()
+            .find(|(topic, _)| topic.ends_with("/input"))
+            .expect("Should forward the task");
+        forwarded
+            .routing_trace
+            .unwrap()
+            .last()
+            .unwrap()
+            .to_agent
+            .clone()
+    }
+
+    #[tokio::test]
+    async fn test_sticky_routing_pins_conversation_to_agent() {
+        let (pipeline, transport, registry) =
+            create_sticky_pipeline(true, crate::config::StickyRoutingConfig::default());
+
+        assert_eq!(
+            route_conversation(&pipeline, &transport, "conv-a").await,
+            "writer-1"
+        );
+
+        // writer-2 becomes the better pick, but conv-a stays on writer-1
+        register_writer(&registry, "writer-1", "ok", 0.9);
+        assert_eq!(
+            route_conversation(&pipeline, &transport, "conv-a").await,
+            "writer-1"
+        );
+        assert_eq!(
+            route_conversation(&pipeline, &transport, "conv-b").await,
+            "writer-2"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sticky_routing_can_be_disabled_per_decision() {
+        let (pipeline, transport, registry) =
+            create_sticky_pipeline(false, crate::config::StickyRoutingConfig::default());
+
+        assert_eq!(
+            route_conversation(&pipeline, &transport, "conv-a").await,
+            "writer-1"
+        );
+        register_writer(&registry, "writer-1", "ok", 0.9);
+        assert_eq!(
+            route_conversation(&pipeline, &transport, "conv-a").await,
+            "writer-2"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sticky_routing_fails_over_from_unhealthy_agent() {
+        let (pipeline, transport, registry) =
+            create_sticky_pipeline(true, crate::config::StickyRoutingConfig::default());
+
+        assert_eq!(
+            route_conversation(&pipeline, &transport, "conv-a").await,
+            "writer-1"
+        );
+        register_writer(&registry, "writer-1", "error", 0.2);
+        assert_eq!(
+            route_conversation(&pipeline, &transport, "conv-a").await,
+            "writer-2"
+        );
+
+        // The conversation is re-pinned to the failover agent
+        register_writer(&registry, "writer-1", "ok", 0.1);
+        assert_eq!(
+            route_conversation(&pipeline, &transport, "conv-a").await,
+            "writer-2"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sticky_routing_pin_expires() {
+        let (pipeline, transport, registry) = create_sticky_pipeline(
+            true,
+            crate::config::StickyRoutingConfig {
+                ttl_secs: 0,
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(
+            route_conversation(&pipeline, &transport, "conv-a").await,
+            "writer-1"
+        );
+        register_writer(&registry, "writer-1", "ok", 0.9);
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        assert_eq!(
+            route_conversation(&pipeline, &transport, "conv-a").await,
+            "writer-2"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sticky_routing_pins_logical_agent_name() {
+        let (pipeline, transport, registry) = create_sticky_pipeline_to(
+            "writer",
+            true,
+            crate::config::StickyRoutingConfig::default(),
+        );
+
+        // The least loaded replica is chosen and pinned
+        assert_eq!(
+            route_conversation(&pipeline, &transport, "conv-a").await,
+            "writer-1"
+        );
+        register_writer(&registry, "writer-1", "ok", 0.9);
+        assert_eq!(
+            route_conversation(&pipeline, &transport, "conv-a").await,
+            "writer-1"
+        );
+        assert_eq!(
+            route_conversation(&pipeline, &transport, "conv-b").await,
+            "writer-2"
+        );
+
+        // An unhealthy pinned replica fails over to another one
+        register_writer(&registry, "writer-1", "error", 0.1);
+        assert_eq!(
+            route_conversation(&pipeline, &transport, "conv-a").await,
+            "writer-2"
+        );
+    }
+
+    // ========== ROUTING METRICS TESTS ==========
+
+    fn create_audited_pipeline(
+        router: Arc<dyn Router>,
+        registry: &MockAgentRegistry,
+    ) -> (AgentPipeline<MockTransport>, std::path::PathBuf) {
+        let audit_path =
+            std::env::temp_dir().join(format!("routing-audit-{}.jsonl", Uuid::new_v4()));
+        let (mut pipeline, _transport) =
+            create_test_pipeline(router, Arc::new(registry.registry().clone()), 10);
+        pipeline.set_routing_audit_log(Arc::new(
+            crate::routing::RoutingAuditLog::open(&audit_path).unwrap(),
+        ));
+        (pipeline, audit_path)
+    }
+
+    fn read_audit_log(path: &std::path::Path) -> Vec<crate::routing::RoutingAuditEntry> {
+        let content = std::fs::read_to_string(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        content
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    fn forwards_to(agent_id: &str) -> u64 {
+        crate::observability::metrics::metrics()
+            .get_metrics()
+            .routing
+            .forwards_by_agent
+            .get(agent_id)
+            .copied()
+            .unwrap_or(0)
+    }
+
+    #[tokio::test]
+    async fn test_llm_router_decisions_feed_routing_metrics() {
+        use crate::routing::llm_router::LlmRouter;
+        use crate::routing::schema::RoutingDecisionOutput;
+        use crate::routing::RoutingOutcome;
+
+        let decision = RoutingDecisionOutput {
+            workflow_complete: false,
+            reasoning: "Needs a metrics pass".to_string(),
+            next_agent: Some("metrics-llm-agent".to_string()),
+            next_instruction: Some("Collect metrics".to_string()),
+        };
+        let llm_provider = Arc::new(MockLlmProvider::single_response(
+            serde_json::to_string(&decision).unwrap(),
+        ));
+        let router = Arc::new(LlmRouter::new(llm_provider, "mock-model".to_string()));
+        let registry = MockAgentRegistry::new();
+        registry.register_agent("metrics-llm-agent", vec!["metrics"]);
+        let (pipeline, audit_path) = create_audited_pipeline(router, &registry);
+
+        let forwards_before = crate::observability::metrics::metrics()
+            .get_metrics()
+            .routing
+            .decisions
+            .get("forward")
+            .copied()
+            .unwrap_or(0);
+        let task = create_test_task(Uuid::new_v4(), "conv-metrics-llm", None, None);
+        pipeline
+            .process_with_routing(task.clone(), json!({"draft": "v1"}))
+            .await
+            .unwrap();
+
+        let routing = crate::observability::metrics::metrics()
+            .get_metrics()
+            .routing;
+        assert!(routing.decisions.get("forward").copied().unwrap_or(0) > forwards_before);
+        assert_eq!(forwards_to("metrics-llm-agent"), 1);
+        assert!(routing
+            .router_latency_histogram
+            .iter()
+            .any(|bucket| bucket.count > 0));
+
+        let entries = read_audit_log(&audit_path);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].task_id, task.task_id);
+        assert_eq!(entries[0].conversation_id, "conv-metrics-llm");
+        assert_eq!(entries[0].decision, RoutingOutcome::Forward);
+        assert_eq!(entries[0].next_agent.as_deref(), Some("metrics-llm-agent"));
+        assert_eq!(
+            entries[0].reasoning.as_deref(),
+            Some("Needs a metrics pass")
+        );
+        assert!(!entries[0].cached);
+    }
+
+    #[tokio::test]
+    async fn test_gatekeeper_router_decisions_feed_routing_metrics() {
+        use crate::routing::gatekeeper_router::GatekeeperRouter;
+        use crate::routing::RoutingOutcome;
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/route"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "workflow_complete": false,
+                "next_agent": "metrics-gatekeeper-agent",
+                "next_instruction": "Review the metrics",
+                "reasoning": "Metrics need review"
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/broken"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&mock_server)
+            .await;
+
+        let registry = MockAgentRegistry::new();
+        registry.register_agent("metrics-gatekeeper-agent", vec!["review"]);
+        let router = Arc::new(GatekeeperRouter::from_url(
+            format!("{}/route", mock_server.uri()),
+            5000,
+            1,
+        ));
+        let (pipeline, audit_path) = create_audited_pipeline(router, &registry);
+
+        let task = create_test_task(Uuid::new_v4(), "conv-metrics-gatekeeper", None, None);
+        pipeline
+            .process_with_routing(task, json!({"draft": "v1"}))
+            .await
+            .unwrap();
+        assert_eq!(forwards_to("metrics-gatekeeper-agent"), 1);
+
+        let entries = read_audit_log(&audit_path);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].decision, RoutingOutcome::Forward);
+        assert_eq!(entries[0].reasoning.as_deref(), Some("Metrics need review"));
+
+        // A failing gatekeeper is counted as a router error
+        let broken_router = Arc::new(GatekeeperRouter::from_url(
+            format!("{}/broken", mock_server.uri()),
+            5000,
+            1,
+        ));
+        let (pipeline, audit_path) = create_audited_pipeline(broken_router, &registry);
+        let errors_before = crate::observability::metrics::metrics()
+            .get_metrics()
+            .routing
+            .router_errors;
+
+        let task = create_test_task(Uuid::new_v4(), "conv-metrics-gatekeeper", None, None);
+        assert!(pipeline
+            .process_with_routing(task, json!({"draft": "v1"}))
+            .await
+            .is_err());
+
+        assert!(
+            crate::observability::metrics::metrics()
+                .get_metrics()
+                .routing
+                .router_errors
+                > errors_before
+        );
+        let entries = read_audit_log(&audit_path);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].decision, RoutingOutcome::Error);
+        assert!(entries[0].error.is_some());
+    }
+
+    // ========== FORWARD TARGET VALIDATION TESTS ==========
+
+    async fn forward_to_misspelled_agent(
+        next_agent: &str,
+        auto_correct: bool,
+    ) -> (
+        Result<(), crate::agent::pipeline::PipelineError>,
+        Vec<String>,
+    ) {
+        let registry = MockAgentRegistry::new();
+        registry.register_agent("editor-agent", vec!["editing"]);
+        registry.register_agent("writer-a", vec!["writing"]);
+        registry.register_agent("writer-b", vec!["writing"]);
+
+        let router = ForwardToAgentRouter {
+            next_agent: next_agent.to_string(),
+            next_instruction: "Continue".to_string(),
+        };
+        let (mut pipeline, transport) =
+            create_test_pipeline(Arc::new(router), Arc::new(registry.registry().clone()), 10);
+        pipeline.set_auto_correct_agent_ids(auto_correct);
+
+        let task = create_test_task(Uuid::new_v4(), "conv-validate", None, None);
+        let result = pipeline
+            .process_with_routing(task, json!({"draft": "v1"}))
+            .await;
+        let topics = forwarded_tasks(&transport)
+            .await
+            .into_iter()
+            .map(|(topic, _)| topic)
+            .collect();
+        (result, topics)
+    }
+
+    #[tokio::test]
+    async fn test_unknown_agent_is_auto_corrected_when_unambiguous() {
+        let (result, topics) = forward_to_misspelled_agent("Editor", true).await;
+
+        assert!(result.is_ok(), "{result:?}");
+        assert!(topics.contains(&"/control/agents/editor-agent/input".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_unknown_agent_error_suggests_and_lists_agents() {
+        let (result, topics) = forward_to_misspelled_agent("Editor", false).await;
+
+        let error = result.unwrap_err().to_string();
+        assert!(error.contains("did you mean: editor-agent?"), "{error}");
+        assert!(
+            error.contains("available agents: editor-agent, writer-a, writer-b"),
+            "{error}"
+        );
+        assert!(topics.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_ambiguous_agent_is_not_auto_corrected() {
+        let (result, topics) = forward_to_misspelled_agent("writer", true).await;
+
+        let error = result.unwrap_err().to_string();
+        assert!(
+            error.contains("did you mean: writer-a, writer-b?"),
+            "{error}"
+        );
+        assert!(topics.is_empty());
+    }
 }
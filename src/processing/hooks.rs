@@ -0,0 +1,189 @@
+//! Processing hooks around the LLM and publish steps
+//!
+//! Hooks inject behavior into step 7 and step 9 of the nine-step algorithm
+//! (input redaction, output moderation, ...) without changing the processor.
+//! Hooks run in registration order and each one receives the previous hook's
+//! output. A hook error fails the task with that error.
+
+use crate::error::AgentResult;
+use crate::llm::provider::Message;
+use crate::protocol::messages::TaskEnvelope;
+use async_trait::async_trait;
+use std::sync::Arc;
+
+/// Behavior injected around task processing
+///
+/// Every method defaults to passing its input through unchanged, so a hook
+/// only implements the points it cares about.
+#[async_trait]
+pub trait ProcessingHook: Send + Sync {
+    /// Rewrite the initial conversation before the first LLM request of step 7
+    async fn before_llm(
+        &self,
+        _task: &TaskEnvelope,
+        messages: Vec<Message>,
+    ) -> AgentResult<Vec<Message>> {
+        Ok(messages)
+    }
+
+    /// Rewrite the final LLM response before step 8 routes on it
+    async fn after_llm(&self, _task: &TaskEnvelope, response: String) -> AgentResult<String> {
+        Ok(response)
+    }
+
+    /// Rewrite the response content before step 9 publishes it to the conversation
+    async fn before_publish(&self, _task: &TaskEnvelope, response: String) -> AgentResult<String> {
+        Ok(response)
+    }
+}
+
+/// Run every hook's `before_llm` in order, stopping at the first error
+pub async fn run_before_llm(
+    hooks: &[Arc<dyn ProcessingHook>],
+    task: &TaskEnvelope,
+    mut messages: Vec<Message>,
+) -> AgentResult<Vec<Message>> {
+    for hook in hooks {
+        messages = hook.before_llm(task, messages).await?;
+    }
+    Ok(messages)
+}
+
+/// Run every hook's `after_llm` in order, stopping at the first error
+pub async fn run_after_llm(
+    hooks: &[Arc<dyn ProcessingHook>],
+    task: &TaskEnvelope,
+    mut response: String,
+) -> AgentResult<String> {
+    for hook in hooks {
+        response = hook.after_llm(task, response).await?;
+    }
+    Ok(response)
+}
+
+/// Run every hook's `before_publish` in order, stopping at the first error
+pub async fn run_before_publish(
+    hooks: &[Arc<dyn ProcessingHook>],
+    task: &TaskEnvelope,
+    mut response: String,
+) -> AgentResult<String> {
+    for hook in hooks {
+        response = hook.before_publish(task, response).await?;
+    }
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::AgentError;
+    use crate::llm::provider::MessageRole;
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use uuid::Uuid;
+
+    /// Appends its tag at every hook point
+    struct TagHook(&'static str);
+
+    #[async_trait]
+    impl ProcessingHook for TagHook {
+        async fn before_llm(
+            &self,
+            _task: &TaskEnvelope,
+            mut messages: Vec<Message>,
+        ) -> AgentResult<Vec<Message>> {
+            messages.push(Message {
+                role: MessageRole::User,
+                content: self.0.to_string(),
+            });
+            Ok(messages)
+        }
+
+        async fn after_llm(&self, _task: &TaskEnvelope, response: String) -> AgentResult<String> {
+            Ok(format!("{response}+{}", self.0))
+        }
+
+        async fn before_publish(
+            &self,
+            _task: &TaskEnvelope,
+            response: String,
+        ) -> AgentResult<String> {
+            Ok(format!("{response}+{}", self.0))
+        }
+    }
+
+    /// Rejects every response and counts how often it was asked
+    struct RejectHook(AtomicUsize);
+
+    #[async_trait]
+    impl ProcessingHook for RejectHook {
+        async fn after_llm(&self, _task: &TaskEnvelope, _response: String) -> AgentResult<String> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Err(AgentError::invalid_input("response rejected by moderation"))
+        }
+    }
+
+    fn task() -> TaskEnvelope {
+        TaskEnvelope {
+            task_id: Uuid::new_v4(),
+            conversation_id: "hooks".to_string(),
+            topic: "/control/agents/test/input".to_string(),
+            instruction: Some("hello".to_string()),
+            input: json!(null),
+            next: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hooks_run_in_registration_order() {
+        let hooks: Vec<Arc<dyn ProcessingHook>> =
+            vec![Arc::new(TagHook("a")), Arc::new(TagHook("b"))];
+        let task = task();
+
+        let messages = run_before_llm(&hooks, &task, Vec::new()).await.unwrap();
+        let contents: Vec<_> = messages.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents, vec!["a", "b"]);
+
+        let response = run_after_llm(&hooks, &task, "r".to_string()).await.unwrap();
+        assert_eq!(response, "r+a+b");
+
+        let published = run_before_publish(&hooks, &task, "p".to_string())
+            .await
+            .unwrap();
+        assert_eq!(published, "p+a+b");
+    }
+
+    #[tokio::test]
+    async fn test_hook_error_stops_later_hooks() {
+        let reject = Arc::new(RejectHook(AtomicUsize::new(0)));
+        let later = Arc::new(RejectHook(AtomicUsize::new(0)));
+        let hooks: Vec<Arc<dyn ProcessingHook>> =
+            vec![Arc::new(TagHook("a")), reject.clone(), later.clone()];
+
+        let error = run_after_llm(&hooks, &task(), "r".to_string())
+            .await
+            .unwrap_err();
+
+        assert!(matches!(error, AgentError::InvalidInput { .. }));
+        assert_eq!(reject.0.load(Ordering::SeqCst), 1);
+        assert_eq!(later.0.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_default_hook_methods_pass_through() {
+        struct NoOpHook;
+        impl ProcessingHook for NoOpHook {}
+
+        let hooks: Vec<Arc<dyn ProcessingHook>> = vec![Arc::new(NoOpHook)];
+        let response = run_before_publish(&hooks, &task(), "unchanged".to_string())
+            .await
+            .unwrap();
+        assert_eq!(response, "unchanged");
+    }
+}
@@ -0,0 +1,625 @@
+//! Idempotency Stores for Processed Task IDs
+//!
+//! RFC step 4 rejects a task whose id has already been processed. The
+//! in-memory store forgets every id on restart, so QoS 1 messages redelivered
+//! by the broker would run again; the SQLite store keeps ids in a database
+//! under the agent's `state_dir` so they survive restarts. Both stores forget
+//! ids once they are older than the configured TTL, unless the TTL is zero.
+//!
+//! Ids held in memory are also bounded in number: once full, the store drops
+//! the id recorded longest ago, so recently processed tasks are the last to
+//! lose duplicate protection.
+
+use crate::observability::metrics::metrics;
+use async_trait::async_trait;
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::{HashMap, VecDeque};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// How long processed task ids are remembered by default
+pub const DEFAULT_IDEMPOTENCY_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// File name of the SQLite database inside the agent's state directory
+pub const IDEMPOTENCY_DB_FILE: &str = "idempotency.sqlite3";
+
+/// Inserts between pruning passes over the SQLite database
+const PRUNE_INTERVAL_INSERTS: u64 = 256;
+
+/// Storage for the ids of tasks that have already been processed
+#[async_trait]
+pub trait IdempotencyStore: Send + Sync {
+    /// Short name of the backend, for logs
+    fn name(&self) -> &str;
+
+    /// Whether the task id was recorded within the TTL
+    async fn contains(&self, task_id: &Uuid) -> bool;
+
+    /// Record a task id, returning false if it was already recorded
+    async fn insert(&self, task_id: Uuid) -> bool;
+
+    /// Forget a task id so the task may be processed again
+    async fn remove(&self, task_id: &Uuid);
+
+    /// Forget ids older than the TTL, returning how many were removed
+    async fn prune(&self) -> usize;
+}
+
+/// Errors opening a persistent idempotency store
+#[derive(thiserror::Error, Debug)]
+pub enum IdempotencyStoreError {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("SQLite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+
+    #[error("Idempotency database is corrupt: {0}")]
+    Corrupt(String),
+}
+
+fn now_millis() -> i64 {
+    Utc::now().timestamp_millis()
+}
+
+fn ttl_millis(ttl: Duration) -> i64 {
+    i64::try_from(ttl.as_millis()).unwrap_or(i64::MAX)
+}
+
+/// Ids recorded before this time have expired; a zero TTL never expires
+fn expiry_cutoff(ttl: Duration) -> i64 {
+    if ttl.is_zero() {
+        i64::MIN
+    } else {
+        now_millis().saturating_sub(ttl_millis(ttl))
+    }
+}
+
+/// Recorded ids, in the order they were recorded
+#[derive(Debug, Default)]
+struct Entries {
+    /// When each id was recorded, in epoch milliseconds, and its position
+    recorded: HashMap<Uuid, (i64, u64)>,
+    /// Ids by position, oldest first; an id removed or re-recorded since
+    /// leaves a stale entry here, skipped once it reaches the front
+    order: VecDeque<(Uuid, u64)>,
+    next_position: u64,
+}
+
+impl Entries {
+    fn len(&self) -> usize {
+        self.recorded.len()
+    }
+
+    fn get(&self, task_id: &Uuid) -> Option<i64> {
+        self.recorded
+            .get(task_id)
+            .map(|(recorded_at, _)| *recorded_at)
+    }
+
+    fn insert(&mut self, task_id: Uuid, recorded_at: i64) {
+        let position = self.next_position;
+        self.next_position += 1;
+        self.recorded.insert(task_id, (recorded_at, position));
+        self.order.push_back((task_id, position));
+    }
+
+    fn remove(&mut self, task_id: &Uuid) {
+        self.recorded.remove(task_id);
+    }
+
+    /// When the id at this order entry was recorded, unless the entry is stale
+    fn current(&self, (task_id, position): &(Uuid, u64)) -> Option<i64> {
+        self.recorded
+            .get(task_id)
+            .filter(|(_, current)| current == position)
+            .map(|(recorded_at, _)| *recorded_at)
+    }
+
+    /// Drop the id recorded longest ago, returning false if there is none
+    fn pop_oldest(&mut self) -> bool {
+        while let Some(entry) = self.order.pop_front() {
+            if self.current(&entry).is_some() {
+                self.recorded.remove(&entry.0);
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Drop expired ids from the front of the order, where the oldest are
+    fn pop_expired(&mut self, cutoff: i64) -> usize {
+        let mut removed = 0;
+        while let Some(entry) = self.order.front().copied() {
+            match self.current(&entry) {
+                Some(recorded_at) if recorded_at >= cutoff => break,
+                Some(_) => {
+                    self.recorded.remove(&entry.0);
+                    removed += 1;
+                }
+                None => {}
+            }
+            self.order.pop_front();
+        }
+        removed
+    }
+
+    /// Drop every expired id, wherever it is in the order
+    fn retain_unexpired(&mut self, cutoff: i64) -> usize {
+        let before = self.recorded.len();
+        self.recorded
+            .retain(|_, (recorded_at, _)| *recorded_at >= cutoff);
+        self.compact();
+        before - self.recorded.len()
+    }
+
+    /// Forget stale order entries
+    fn compact(&mut self) {
+        let recorded = &self.recorded;
+        self.order.retain(|(task_id, position)| {
+            recorded
+                .get(task_id)
+                .is_some_and(|(_, current)| current == position)
+        });
+    }
+}
+
+/// In-memory idempotency store, lost on restart
+///
+/// Besides the TTL, at most `max_entries` ids are kept; once full, the id
+/// recorded longest ago is evicted first.
+pub struct InMemoryIdempotencyStore {
+    entries: Mutex<Entries>,
+    ttl: Duration,
+    max_entries: usize,
+}
+
+impl InMemoryIdempotencyStore {
+    pub fn new(ttl: Duration, max_entries: usize) -> Self {
+        Self {
+            entries: Mutex::new(Entries::default()),
+            ttl,
+            max_entries: max_entries.max(1),
+        }
+    }
+
+    /// Number of ids currently held, expired ones not yet pruned included
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn contains_id(&self, task_id: &Uuid) -> bool {
+        let cutoff = expiry_cutoff(self.ttl);
+        self.entries
+            .lock()
+            .unwrap()
+            .get(task_id)
+            .is_some_and(|recorded_at| recorded_at >= cutoff)
+    }
+
+    /// Record an id with the time it was processed
+    fn insert_at(&self, task_id: Uuid, recorded_at: i64) -> bool {
+        let cutoff = expiry_cutoff(self.ttl);
+        let mut entries = self.entries.lock().unwrap();
+        if entries
+            .get(&task_id)
+            .is_some_and(|existing| existing >= cutoff)
+        {
+            return false;
+        }
+        entries.insert(task_id, recorded_at);
+
+        let mut evicted = 0;
+        if entries.len() > self.max_entries {
+            // Expired ids go first; the oldest of them are at the front
+            evicted += entries.pop_expired(cutoff);
+        }
+        while entries.len() > self.max_entries && entries.pop_oldest() {
+            evicted += 1;
+        }
+        // Re-recorded and removed ids leave stale order entries behind
+        if entries.order.len() > self.max_entries * 2 {
+            entries.compact();
+        }
+        Self::report(&entries, evicted);
+        true
+    }
+
+    fn remove_id(&self, task_id: &Uuid) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.remove(task_id);
+        Self::report(&entries, 0);
+    }
+
+    fn prune_expired(&self) -> usize {
+        let mut entries = self.entries.lock().unwrap();
+        let removed = entries.retain_unexpired(expiry_cutoff(self.ttl));
+        Self::report(&entries, removed);
+        removed
+    }
+
+    fn report(entries: &Entries, evicted: usize) {
+        metrics().set_idempotency_cache_size(entries.len());
+        if evicted > 0 {
+            metrics().idempotency_evictions(evicted);
+        }
+    }
+}
+
+#[async_trait]
+impl IdempotencyStore for InMemoryIdempotencyStore {
+    fn name(&self) -> &str {
+        "memory"
+    }
+
+    async fn contains(&self, task_id: &Uuid) -> bool {
+        self.contains_id(task_id)
+    }
+
+    async fn insert(&self, task_id: Uuid) -> bool {
+        self.insert_at(task_id, now_millis())
+    }
+
+    async fn remove(&self, task_id: &Uuid) {
+        self.remove_id(task_id);
+    }
+
+    async fn prune(&self) -> usize {
+        self.prune_expired()
+    }
+}
+
+/// SQLite-backed idempotency store that survives restarts
+///
+/// Ids are not loaded at startup: lookups that miss the in-memory cache fall
+/// through to the database and cache what they find. Database errors after
+/// opening are logged and the in-memory cache keeps deduplicating.
+pub struct SqliteIdempotencyStore {
+    connection: Mutex<Connection>,
+    /// Ids recently seen in or written to the database
+    cache: InMemoryIdempotencyStore,
+    ttl: Duration,
+    inserts: AtomicU64,
+}
+
+impl SqliteIdempotencyStore {
+    /// Open (or create) the database, pruning expired ids
+    pub fn open(
+        path: &Path,
+        ttl: Duration,
+        max_cached: usize,
+    ) -> Result<Self, IdempotencyStoreError> {
+        let connection = Connection::open(path)?;
+        let check: String = connection.query_row("PRAGMA quick_check", [], |row| row.get(0))?;
+        if check != "ok" {
+            return Err(IdempotencyStoreError::Corrupt(check));
+        }
+        connection.execute_batch(
+            "CREATE TABLE IF NOT EXISTS processed_tasks (
+                 task_id TEXT PRIMARY KEY,
+                 processed_at INTEGER NOT NULL
+             );
+             CREATE INDEX IF NOT EXISTS processed_tasks_processed_at
+                 ON processed_tasks (processed_at);",
+        )?;
+
+        let store = Self {
+            connection: Mutex::new(connection),
+            cache: InMemoryIdempotencyStore::new(ttl, max_cached),
+            ttl,
+            inserts: AtomicU64::new(0),
+        };
+        store.prune_expired()?;
+        Ok(store)
+    }
+
+    fn cutoff(&self) -> i64 {
+        expiry_cutoff(self.ttl)
+    }
+
+    /// When the id was processed, if it is in the database and not expired
+    fn lookup(
+        connection: &Connection,
+        task_id: &Uuid,
+        cutoff: i64,
+    ) -> rusqlite::Result<Option<i64>> {
+        connection
+            .query_row(
+                "SELECT processed_at FROM processed_tasks WHERE task_id = ?1 AND processed_at >= ?2",
+                params![task_id.to_string(), cutoff],
+                |row| row.get(0),
+            )
+            .optional()
+    }
+
+    /// Consult the cache, then the database, caching database hits
+    fn contains_locked(&self, connection: &Connection, task_id: &Uuid) -> bool {
+        if self.cache.contains_id(task_id) {
+            return true;
+        }
+        match Self::lookup(connection, task_id, self.cutoff()) {
+            Ok(Some(processed_at)) => {
+                self.cache.insert_at(*task_id, processed_at);
+                true
+            }
+            Ok(None) => false,
+            Err(e) => {
+                warn!(task_id = %task_id, error = %e, "Failed to query idempotency database");
+                false
+            }
+        }
+    }
+
+    fn prune_expired(&self) -> rusqlite::Result<usize> {
+        let removed = self.connection.lock().unwrap().execute(
+            "DELETE FROM processed_tasks WHERE processed_at < ?1",
+            params![self.cutoff()],
+        )?;
+        self.cache.prune_expired();
+        Ok(removed)
+    }
+}
+
+#[async_trait]
+impl IdempotencyStore for SqliteIdempotencyStore {
+    fn name(&self) -> &str {
+        "sqlite"
+    }
+
+    async fn contains(&self, task_id: &Uuid) -> bool {
+        let connection = self.connection.lock().unwrap();
+        self.contains_locked(&connection, task_id)
+    }
+
+    async fn insert(&self, task_id: Uuid) -> bool {
+        {
+            // Hold the connection so concurrent inserts of one id cannot both succeed
+            let connection = self.connection.lock().unwrap();
+            if self.contains_locked(&connection, &task_id) {
+                return false;
+            }
+
+            let processed_at = now_millis();
+            // Replace rows that expired but have not been pruned yet
+            if let Err(e) = connection.execute(
+                "INSERT OR REPLACE INTO processed_tasks (task_id, processed_at) VALUES (?1, ?2)",
+                params![task_id.to_string(), processed_at],
+            ) {
+                warn!(task_id = %task_id, error = %e, "Failed to persist processed task id");
+            }
+            self.cache.insert_at(task_id, processed_at);
+        }
+
+        if self.inserts.fetch_add(1, Ordering::Relaxed) % PRUNE_INTERVAL_INSERTS
+            == PRUNE_INTERVAL_INSERTS - 1
+        {
+            self.prune().await;
+        }
+        true
+    }
+
+    async fn remove(&self, task_id: &Uuid) {
+        let connection = self.connection.lock().unwrap();
+        if let Err(e) = connection.execute(
+            "DELETE FROM processed_tasks WHERE task_id = ?1",
+            params![task_id.to_string()],
+        ) {
+            warn!(task_id = %task_id, error = %e, "Failed to remove processed task id");
+        }
+        self.cache.remove_id(task_id);
+    }
+
+    async fn prune(&self) -> usize {
+        match self.prune_expired() {
+            Ok(removed) => removed,
+            Err(e) => {
+                warn!(error = %e, "Failed to prune idempotency database");
+                0
+            }
+        }
+    }
+}
+
+/// Open the idempotency store for an agent
+///
+/// Without a state directory ids are only kept in memory. A state directory
+/// or database that cannot be opened - for example a corrupt file - degrades
+/// to the in-memory store with a warning instead of preventing startup.
+pub fn open_idempotency_store(
+    state_dir: Option<&Path>,
+    ttl: Duration,
+    max_entries: usize,
+) -> Arc<dyn IdempotencyStore> {
+    let Some(state_dir) = state_dir else {
+        return Arc::new(InMemoryIdempotencyStore::new(ttl, max_entries));
+    };
+
+    let path = state_dir.join(IDEMPOTENCY_DB_FILE);
+    let opened = std::fs::create_dir_all(state_dir)
+        .map_err(IdempotencyStoreError::from)
+        .and_then(|_| SqliteIdempotencyStore::open(&path, ttl, max_entries));
+    match opened {
+        Ok(store) => {
+            info!(path = %path.display(), "Opened persistent idempotency store");
+            Arc::new(store)
+        }
+        Err(e) => {
+            warn!(
+                path = %path.display(),
+                error = %e,
+                "Failed to open idempotency database, falling back to in-memory store"
+            );
+            Arc::new(InMemoryIdempotencyStore::new(ttl, max_entries))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_in_memory_store_rejects_duplicates() {
+        let store = InMemoryIdempotencyStore::new(DEFAULT_IDEMPOTENCY_TTL, 10);
+        let task_id = Uuid::new_v4();
+
+        assert!(!store.contains(&task_id).await);
+        assert!(store.insert(task_id).await);
+        assert!(store.contains(&task_id).await);
+        assert!(!store.insert(task_id).await);
+
+        store.remove(&task_id).await;
+        assert!(store.insert(task_id).await);
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_store_evicts_oldest_over_capacity() {
+        let store = InMemoryIdempotencyStore::new(DEFAULT_IDEMPOTENCY_TTL, 2);
+        let ids: Vec<Uuid> = (0..3).map(|_| Uuid::new_v4()).collect();
+        let now = now_millis();
+        for (age, id) in ids.iter().enumerate() {
+            store.insert_at(*id, now - 1000 + age as i64);
+        }
+
+        assert!(!store.contains(&ids[0]).await);
+        assert!(store.contains(&ids[1]).await);
+        assert!(store.contains(&ids[2]).await);
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_store_evicts_in_insertion_order() {
+        let store = InMemoryIdempotencyStore::new(DEFAULT_IDEMPOTENCY_TTL, 3);
+        let ids: Vec<Uuid> = (0..5).map(|_| Uuid::new_v4()).collect();
+        let evictions_before = metrics().get_metrics().tasks.idempotency_evictions;
+        for id in &ids {
+            assert!(store.insert(*id).await);
+        }
+
+        assert_eq!(store.len(), 3);
+        for oldest in &ids[..2] {
+            assert!(!store.contains(oldest).await);
+        }
+        for newest in &ids[2..] {
+            assert!(store.contains(newest).await);
+        }
+        assert!(metrics().get_metrics().tasks.idempotency_evictions >= evictions_before + 2);
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_store_rerecorded_id_counts_as_newest() {
+        let store = InMemoryIdempotencyStore::new(DEFAULT_IDEMPOTENCY_TTL, 2);
+        let (first, second, third) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        store.insert(first).await;
+        store.insert(second).await;
+        // A retried task is forgotten and recorded again
+        store.remove(&first).await;
+        store.insert(first).await;
+        store.insert(third).await;
+
+        assert!(!store.contains(&second).await);
+        assert!(store.contains(&first).await);
+        assert!(store.contains(&third).await);
+        assert!(store.entries.lock().unwrap().order.len() <= 4);
+    }
+
+    #[tokio::test]
+    async fn test_zero_ttl_never_expires() {
+        let store = InMemoryIdempotencyStore::new(Duration::ZERO, 10);
+        let ancient = Uuid::new_v4();
+        store.insert_at(ancient, 0);
+
+        assert!(store.contains(&ancient).await);
+        assert_eq!(store.prune().await, 0);
+        assert!(!store.insert(ancient).await);
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_store_prunes_expired_ids() {
+        let store = InMemoryIdempotencyStore::new(Duration::from_secs(60), 10);
+        let expired = Uuid::new_v4();
+        let fresh = Uuid::new_v4();
+        store.insert_at(expired, now_millis() - 120_000);
+        store.insert_at(fresh, now_millis());
+
+        assert!(!store.contains(&expired).await);
+        assert_eq!(store.prune().await, 1);
+        assert!(store.contains(&fresh).await);
+        // An expired id may be processed again
+        assert!(store.insert(expired).await);
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_store_survives_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let task_id = Uuid::new_v4();
+
+        let store = open_idempotency_store(Some(dir.path()), DEFAULT_IDEMPOTENCY_TTL, 10);
+        assert_eq!(store.name(), "sqlite");
+        assert!(store.insert(task_id).await);
+        drop(store);
+
+        let reopened = open_idempotency_store(Some(dir.path()), DEFAULT_IDEMPOTENCY_TTL, 10);
+        assert!(reopened.contains(&task_id).await);
+        assert!(!reopened.insert(task_id).await);
+        assert!(!reopened.contains(&Uuid::new_v4()).await);
+
+        reopened.remove(&task_id).await;
+        assert!(!reopened.contains(&task_id).await);
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_store_prunes_expired_ids() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(IDEMPOTENCY_DB_FILE);
+        let ttl = Duration::from_secs(60);
+        let expired = Uuid::new_v4();
+        let fresh = Uuid::new_v4();
+
+        let store = SqliteIdempotencyStore::open(&path, ttl, 10).unwrap();
+        store
+            .connection
+            .lock()
+            .unwrap()
+            .execute(
+                "INSERT INTO processed_tasks (task_id, processed_at) VALUES (?1, ?2)",
+                params![expired.to_string(), now_millis() - 120_000],
+            )
+            .unwrap();
+        assert!(store.insert(fresh).await);
+
+        assert!(!store.contains(&expired).await);
+        assert_eq!(store.prune().await, 1);
+        drop(store);
+
+        // Opening prunes too, and the fresh id is still remembered
+        let reopened = SqliteIdempotencyStore::open(&path, ttl, 10).unwrap();
+        assert!(reopened.contains(&fresh).await);
+        assert_eq!(reopened.prune().await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_corrupt_database_falls_back_to_memory() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join(IDEMPOTENCY_DB_FILE),
+            b"this is not a sqlite database, just some garbage bytes",
+        )
+        .unwrap();
+
+        let store = open_idempotency_store(Some(dir.path()), DEFAULT_IDEMPOTENCY_TTL, 10);
+        assert_eq!(store.name(), "memory");
+
+        let task_id = Uuid::new_v4();
+        assert!(store.insert(task_id).await);
+        assert!(!store.insert(task_id).await);
+    }
+}
@@ -0,0 +1,173 @@
+//! Per-task LLM parameter overrides
+//!
+//! A task can tune its own completion through a reserved `_llm` object in the
+//! envelope input, for example `{"_llm": {"temperature": 0.0}}`. Overrides are
+//! merged over the `[llm]` config. A task may only pick the configured model or
+//! one listed in `llm.allowed_override_models`, so a prompt-injected task can't
+//! switch the agent to an expensive model.
+
+use crate::config::LlmSection;
+use crate::error::{AgentError, AgentResult};
+use serde::Deserialize;
+use serde_json::Value;
+
+/// Reserved input key holding the overrides
+pub const LLM_OVERRIDES_KEY: &str = "_llm";
+
+/// LLM parameters a task sets for its own completions
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LlmOverrides {
+    pub model: Option<String>,
+    pub temperature: Option<f32>,
+    pub max_tokens: Option<u32>,
+    pub top_p: Option<f32>,
+}
+
+impl LlmOverrides {
+    /// Read and validate the overrides in a task input (pure function)
+    ///
+    /// Inputs without an `_llm` object have no overrides.
+    pub fn from_input(input: &Value, llm: &LlmSection) -> AgentResult<Self> {
+        let Some(raw) = input.get(LLM_OVERRIDES_KEY) else {
+            return Ok(Self::default());
+        };
+
+        let overrides: Self = serde_json::from_value(raw.clone()).map_err(|e| {
+            AgentError::invalid_input(format!("Invalid {LLM_OVERRIDES_KEY} overrides: {e}"))
+        })?;
+        overrides.validate(llm)?;
+        Ok(overrides)
+    }
+
+    /// Check each override against the config and the provider ranges (pure function)
+    fn validate(&self, llm: &LlmSection) -> AgentResult<()> {
+        if let Some(model) = &self.model {
+            if *model != llm.model && !llm.allowed_override_models.contains(model) {
+                return Err(AgentError::invalid_input(format!(
+                    "Model override '{model}' is not in llm.allowed_override_models"
+                )));
+            }
+        }
+        if let Some(temperature) = self.temperature {
+            if !(0.0..=2.0).contains(&temperature) {
+                return Err(AgentError::invalid_input(format!(
+                    "Temperature override {temperature} must be between 0.0 and 2.0"
+                )));
+            }
+        }
+        if let Some(top_p) = self.top_p {
+            if !(0.0..=1.0).contains(&top_p) {
+                return Err(AgentError::invalid_input(format!(
+                    "top_p override {top_p} must be between 0.0 and 1.0"
+                )));
+            }
+        }
+        if self.max_tokens == Some(0) {
+            return Err(AgentError::invalid_input(
+                "max_tokens override must be at least 1",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Task input as shown to the LLM, without the reserved overrides (pure function)
+///
+/// Returns `None` when there is no input left to show.
+pub fn input_without_overrides(input: &Value) -> Option<Value> {
+    match input {
+        Value::Null => None,
+        Value::Object(fields) if fields.contains_key(LLM_OVERRIDES_KEY) => {
+            let mut fields = fields.clone();
+            fields.remove(LLM_OVERRIDES_KEY);
+            (!fields.is_empty()).then_some(Value::Object(fields))
+        }
+        other => Some(other.clone()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AgentConfig;
+    use serde_json::json;
+
+    fn llm_section() -> LlmSection {
+        let mut llm = AgentConfig::test_config().llm;
+        llm.allowed_override_models = vec!["claude-haiku".to_string()];
+        llm
+    }
+
+    #[test]
+    fn test_input_without_overrides_has_none() {
+        let overrides = LlmOverrides::from_input(&json!({"text": "hi"}), &llm_section()).unwrap();
+        assert_eq!(overrides, LlmOverrides::default());
+
+        let overrides = LlmOverrides::from_input(&json!("plain text"), &llm_section()).unwrap();
+        assert_eq!(overrides, LlmOverrides::default());
+    }
+
+    #[test]
+    fn test_valid_overrides_are_parsed() {
+        let input = json!({"_llm": {"model": "claude-haiku", "temperature": 0.0, "top_p": 0.5}});
+
+        let overrides = LlmOverrides::from_input(&input, &llm_section()).unwrap();
+
+        assert_eq!(overrides.model.as_deref(), Some("claude-haiku"));
+        assert_eq!(overrides.temperature, Some(0.0));
+        assert_eq!(overrides.top_p, Some(0.5));
+        assert_eq!(overrides.max_tokens, None);
+    }
+
+    #[test]
+    fn test_configured_model_needs_no_allowlist_entry() {
+        let llm = llm_section();
+        let input = json!({"_llm": {"model": llm.model}});
+        assert!(LlmOverrides::from_input(&input, &llm).is_ok());
+    }
+
+    #[test]
+    fn test_model_outside_allowlist_is_rejected() {
+        let input = json!({"_llm": {"model": "claude-opus-4-20250514"}});
+
+        let error = LlmOverrides::from_input(&input, &llm_section()).unwrap_err();
+
+        assert!(matches!(error, AgentError::InvalidInput { .. }));
+        assert!(error.to_string().contains("allowed_override_models"));
+    }
+
+    #[test]
+    fn test_out_of_range_and_unknown_overrides_are_rejected() {
+        for overrides in [
+            json!({"temperature": 2.5}),
+            json!({"top_p": -0.1}),
+            json!({"max_tokens": 0}),
+            json!({"stop": ["\n"]}),
+            json!("cheap"),
+        ] {
+            let input = json!({ "_llm": overrides });
+            assert!(
+                matches!(
+                    LlmOverrides::from_input(&input, &llm_section()),
+                    Err(AgentError::InvalidInput { .. })
+                ),
+                "{input} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn test_input_without_overrides_strips_reserved_key() {
+        assert_eq!(
+            input_without_overrides(&json!({"text": "hi", "_llm": {"temperature": 0.0}})),
+            Some(json!({"text": "hi"}))
+        );
+        assert_eq!(
+            input_without_overrides(&json!({"_llm": {"temperature": 0.0}})),
+            None
+        );
+        assert_eq!(input_without_overrides(&json!(null)), None);
+        assert_eq!(input_without_overrides(&json!([1, 2])), Some(json!([1, 2])));
+    }
+}
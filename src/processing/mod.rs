@@ -3,9 +3,29 @@
 //! This module implements ONLY the exact 9-step processing algorithm
 //! specified in the 2389 Agent Protocol RFC Section 5.
 
+pub mod artifacts;
+pub mod authorization;
+pub mod cancellation;
+pub mod hooks;
+pub mod idempotency;
+pub mod llm_overrides;
+pub mod moderation;
 pub mod nine_step;
+pub mod response_limit;
+pub mod task_claims;
+pub mod task_journal;
 
 #[cfg(test)]
 mod dynamic_routing_tests;
 
+pub use authorization::TaskAuthorizer;
+pub use cancellation::{CancelOutcome, CancellationRegistry};
+pub use hooks::ProcessingHook;
+pub use idempotency::{
+    open_idempotency_store, IdempotencyStore, InMemoryIdempotencyStore, SqliteIdempotencyStore,
+};
+pub use llm_overrides::LlmOverrides;
+pub use moderation::{ModerationPolicy, ModerationVerdict};
 pub use nine_step::{NineStepProcessor, ProcessingResult, ProcessorConfig};
+pub use response_limit::apply_response_limit;
+pub use task_journal::TaskJournal;
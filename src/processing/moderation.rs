@@ -0,0 +1,338 @@
+//! Moderation of responses before they leave the agent
+//!
+//! A [`ModerationPolicy`] checks the text of every response right before it
+//! is published to its conversation: at step 9 of the nine-step algorithm and
+//! for the final result of a routed workflow. It lets the text through,
+//! replaces it with a redacted copy, or blocks it, in which case an
+//! `ErrorMessage` with the `policy_violation` code is published instead.
+//!
+//! [`RegexModerationPolicy`] blocks and redacts on regular expressions, and
+//! [`LlmModerationPolicy`] asks an LLM provider for a verdict. A policy that
+//! cannot reach a verdict fails the task with its error, so no response is
+//! published unchecked.
+
+use crate::agent::response::strip_code_fence;
+use crate::config::{AgentConfig, ModerationPolicyKind};
+use crate::error::{AgentError, AgentResult};
+use crate::llm::provider::{CompletionRequest, LlmProvider, Message, MessageRole, ResponseFormat};
+use crate::observability::Redactor;
+use async_trait::async_trait;
+use regex::Regex;
+use serde::Deserialize;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// Instructions of the LLM policy unless `[security.moderation] prompt` replaces them
+pub const DEFAULT_MODERATION_PROMPT: &str = "You are a content moderator. \
+The user message is a response an AI agent is about to publish. Decide whether \
+it may be published as-is, must have parts removed first (personal data, \
+credentials, confidential material), or must not be published at all (harmful, \
+abusive or illegal content). Reply with JSON only: {\"verdict\": \"allow\"}, \
+{\"verdict\": \"redact\", \"text\": \"<the response with the offending parts \
+replaced by [REDACTED]>\"} or {\"verdict\": \"block\", \"reason\": \"<why>\"}.";
+
+/// Outcome of checking a response
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModerationVerdict {
+    /// Publish the response unchanged
+    Allow,
+    /// Publish this redacted text instead
+    Redact(String),
+    /// Publish a policy violation error, for the given reason
+    Block(String),
+}
+
+/// Check applied to every response before it is published
+#[async_trait]
+pub trait ModerationPolicy: Send + Sync {
+    /// Verdict on `text`, the response about to be published
+    async fn check(&self, text: &str) -> AgentResult<ModerationVerdict>;
+}
+
+/// Check `text` against `policy`, returning the text to publish
+///
+/// Blocked text fails with [`AgentError::PolicyViolation`].
+pub async fn moderate(policy: &dyn ModerationPolicy, text: String) -> AgentResult<String> {
+    match policy.check(&text).await? {
+        ModerationVerdict::Allow => Ok(text),
+        ModerationVerdict::Redact(redacted) => {
+            info!("Response redacted by moderation");
+            Ok(redacted)
+        }
+        ModerationVerdict::Block(reason) => {
+            warn!(reason = %reason, "Response blocked by moderation");
+            Err(AgentError::policy_violation(format!(
+                "Response blocked by moderation: {reason}"
+            )))
+        }
+    }
+}
+
+/// The policy configured by `[security.moderation]`, if any
+///
+/// The LLM policy checks responses with `provider`, the agent's own provider.
+pub fn moderation_policy(
+    config: &AgentConfig,
+    provider: Arc<dyn LlmProvider>,
+) -> Result<Option<Arc<dyn ModerationPolicy>>, regex::Error> {
+    let Some(moderation) = &config.security.moderation else {
+        return Ok(None);
+    };
+    let policy: Arc<dyn ModerationPolicy> = match moderation.policy {
+        ModerationPolicyKind::Regex => Arc::new(RegexModerationPolicy::new(
+            &moderation.block_patterns,
+            &moderation.redact_patterns,
+        )?),
+        ModerationPolicyKind::Llm => {
+            let model = moderation.model.as_ref().unwrap_or(&config.llm.model);
+            let mut policy = LlmModerationPolicy::new(provider, model.clone());
+            if let Some(prompt) = &moderation.prompt {
+                policy = policy.with_prompt(prompt.clone());
+            }
+            Arc::new(policy)
+        }
+    };
+    Ok(Some(policy))
+}
+
+/// Blocks responses matching any block pattern and redacts matches of the
+/// redact patterns with `[REDACTED]`
+pub struct RegexModerationPolicy {
+    block: Vec<Regex>,
+    redactor: Redactor,
+}
+
+impl RegexModerationPolicy {
+    /// Policy for the given block and redact patterns
+    pub fn new<P: AsRef<str>>(
+        block_patterns: impl IntoIterator<Item = P>,
+        redact_patterns: impl IntoIterator<Item = P>,
+    ) -> Result<Self, regex::Error> {
+        let block = block_patterns
+            .into_iter()
+            .map(|pattern| Regex::new(pattern.as_ref()))
+            .collect::<Result<_, _>>()?;
+        let redactor = Redactor::new(redact_patterns, std::iter::empty::<&str>())?;
+        Ok(Self { block, redactor })
+    }
+}
+
+#[async_trait]
+impl ModerationPolicy for RegexModerationPolicy {
+    async fn check(&self, text: &str) -> AgentResult<ModerationVerdict> {
+        // The pattern is logged, not published, so it can't be probed for
+        if let Some(pattern) = self.block.iter().find(|pattern| pattern.is_match(text)) {
+            warn!(pattern = %pattern.as_str(), "Response matches a blocked pattern");
+            return Ok(ModerationVerdict::Block(
+                "response matches a blocked pattern".to_string(),
+            ));
+        }
+        Ok(match self.redactor.redact(text) {
+            Cow::Borrowed(_) => ModerationVerdict::Allow,
+            Cow::Owned(redacted) => ModerationVerdict::Redact(redacted),
+        })
+    }
+}
+
+/// Asks an LLM provider for a verdict on each response
+pub struct LlmModerationPolicy {
+    provider: Arc<dyn LlmProvider>,
+    model: String,
+    prompt: String,
+}
+
+/// Verdict as the moderation prompt asks the LLM to reply
+#[derive(Debug, Deserialize)]
+#[serde(tag = "verdict", rename_all = "lowercase")]
+enum LlmVerdict {
+    Allow,
+    Redact {
+        text: String,
+    },
+    Block {
+        #[serde(default)]
+        reason: Option<String>,
+    },
+}
+
+impl LlmModerationPolicy {
+    /// Policy asking `model` with the built-in moderation prompt
+    pub fn new(provider: Arc<dyn LlmProvider>, model: impl Into<String>) -> Self {
+        Self {
+            provider,
+            model: model.into(),
+            prompt: DEFAULT_MODERATION_PROMPT.to_string(),
+        }
+    }
+
+    /// Use `prompt` as the moderation instructions
+    pub fn with_prompt(mut self, prompt: impl Into<String>) -> Self {
+        self.prompt = prompt.into();
+        self
+    }
+
+    /// Verdict from the LLM's reply (pure function)
+    fn parse_verdict(content: &str) -> AgentResult<ModerationVerdict> {
+        let verdict: LlmVerdict = serde_json::from_str(strip_code_fence(content))
+            .map_err(|e| AgentError::llm_error(format!("Invalid moderation verdict: {e}")))?;
+        Ok(match verdict {
+            LlmVerdict::Allow => ModerationVerdict::Allow,
+            LlmVerdict::Redact { text } => ModerationVerdict::Redact(text),
+            LlmVerdict::Block { reason } => ModerationVerdict::Block(
+                reason.unwrap_or_else(|| "rejected by the moderation model".to_string()),
+            ),
+        })
+    }
+}
+
+#[async_trait]
+impl ModerationPolicy for LlmModerationPolicy {
+    async fn check(&self, text: &str) -> AgentResult<ModerationVerdict> {
+        let request = CompletionRequest {
+            messages: vec![
+                Message {
+                    role: MessageRole::System,
+                    content: self.prompt.clone(),
+                },
+                Message {
+                    role: MessageRole::User,
+                    content: text.to_string(),
+                },
+            ],
+            model: self.model.clone(),
+            max_tokens: None,
+            temperature: Some(0.0),
+            top_p: None,
+            seed: None,
+            stop_sequences: None,
+            tools: None,
+            tool_choice: None,
+            response_format: Some(ResponseFormat::Json),
+            metadata: HashMap::new(),
+        };
+        let response = self.provider.complete(request).await?;
+        Self::parse_verdict(response.content.as_deref().unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::mocks::MockLlmProvider;
+
+    fn regex_policy() -> RegexModerationPolicy {
+        RegexModerationPolicy::new(["(?i)project nightingale"], [r"\b\d{3}-\d{2}-\d{4}\b"]).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_regex_policy_allows_clean_text() {
+        let verdict = regex_policy().check("The report is ready").await.unwrap();
+
+        assert_eq!(verdict, ModerationVerdict::Allow);
+    }
+
+    #[tokio::test]
+    async fn test_regex_policy_redacts_matches() {
+        let verdict = regex_policy()
+            .check("Customer SSN is 123-45-6789.")
+            .await
+            .unwrap();
+
+        assert_eq!(
+            verdict,
+            ModerationVerdict::Redact("Customer SSN is [REDACTED].".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_regex_policy_blocks_before_redacting() {
+        let verdict = regex_policy()
+            .check("Project Nightingale launches for 123-45-6789")
+            .await
+            .unwrap();
+
+        // The reason doesn't reveal the pattern
+        assert_eq!(
+            verdict,
+            ModerationVerdict::Block("response matches a blocked pattern".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_moderate_turns_a_block_into_a_policy_violation() {
+        let policy = regex_policy();
+
+        let allowed = moderate(&policy, "fine".to_string()).await.unwrap();
+        let redacted = moderate(&policy, "id 123-45-6789".to_string())
+            .await
+            .unwrap();
+        let blocked = moderate(&policy, "project nightingale".to_string()).await;
+
+        assert_eq!(allowed, "fine");
+        assert_eq!(redacted, "id [REDACTED]");
+        assert!(matches!(
+            blocked,
+            Err(AgentError::PolicyViolation { message }) if message.contains("blocked pattern")
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_llm_policy_reads_each_verdict() {
+        let llm = Arc::new(MockLlmProvider::new(vec![
+            r#"{"verdict": "allow"}"#.to_string(),
+            "```json\n{\"verdict\": \"redact\", \"text\": \"Call [REDACTED]\"}\n```".to_string(),
+            r#"{"verdict": "block", "reason": "threatening language"}"#.to_string(),
+        ]));
+        let policy = LlmModerationPolicy::new(llm.clone(), "moderation-model")
+            .with_prompt("Only allow polite text");
+
+        let allow = policy.check("Hello").await.unwrap();
+        let redact = policy.check("Call 555-0100").await.unwrap();
+        let block = policy.check("Or else").await.unwrap();
+
+        assert_eq!(allow, ModerationVerdict::Allow);
+        assert_eq!(
+            redact,
+            ModerationVerdict::Redact("Call [REDACTED]".to_string())
+        );
+        assert_eq!(
+            block,
+            ModerationVerdict::Block("threatening language".to_string())
+        );
+        let request = &llm.requests()[1];
+        assert_eq!(request.model, "moderation-model");
+        assert_eq!(request.messages[0].content, "Only allow polite text");
+        assert_eq!(request.messages[1].content, "Call 555-0100");
+    }
+
+    #[tokio::test]
+    async fn test_llm_policy_without_a_verdict_fails() {
+        let llm = Arc::new(MockLlmProvider::single_response("Looks fine to me"));
+        let policy = LlmModerationPolicy::new(llm, "moderation-model");
+
+        let result = policy.check("Hello").await;
+
+        assert!(matches!(result, Err(AgentError::LlmError { .. })));
+    }
+
+    #[test]
+    fn test_policy_from_config() {
+        let llm: Arc<dyn LlmProvider> = Arc::new(MockLlmProvider::single_response("unused"));
+        let mut config = AgentConfig::test_config();
+        assert!(moderation_policy(&config, llm.clone()).unwrap().is_none());
+
+        config.security.moderation = Some(crate::config::ModerationConfig {
+            policy: ModerationPolicyKind::Regex,
+            block_patterns: vec!["(unclosed".to_string()],
+            redact_patterns: Vec::new(),
+            model: None,
+            prompt: None,
+        });
+        assert!(moderation_policy(&config, llm.clone()).is_err());
+
+        config.security.moderation.as_mut().unwrap().policy = ModerationPolicyKind::Llm;
+        assert!(moderation_policy(&config, llm).unwrap().is_some());
+    }
+}
@@ -12,36 +12,88 @@
 //! 9. Mark task as completed
 
 use crate::agent::discovery::AgentRegistry;
-use crate::agent::response::parse_agent_decision;
-use crate::config::AgentConfig;
+use crate::agent::pipeline::workflow_budget::annotate_budget_exhausted;
+use crate::agent::response::{parse_agent_decision, DecisionParseError};
+use crate::clock::{clock_for, Clock};
+use crate::config::{
+    AgentConfig, AuthorizationSection, DuplicateInFlight, LlmSection, ProcessingSection,
+};
 use crate::error::{AgentError, AgentResult};
 use crate::llm::provider::{
     CompletionRequest, CompletionResponse, LlmProvider, Message, MessageRole, ToolCall,
 };
-use crate::progress::{NoOpProgress, Progress};
-use crate::protocol::messages::{ResponseMessage, RoutingStep, TaskEnvelope, TaskEnvelopeWrapper};
-use crate::protocol::topics::canonicalize_topic;
+use crate::observability::agent_state::AgentStateRegistry;
+use crate::observability::event_log::{event_log, Event, EventCategory, EventSeverity};
+use crate::observability::metrics::metrics;
+use crate::observability::redaction::Redactor;
+use crate::processing::artifacts::apply_artifact_limits;
+use crate::processing::authorization::TaskAuthorizer;
+use crate::processing::cancellation::CancellationRegistry;
+use crate::processing::hooks::{run_after_llm, run_before_llm, run_before_publish, ProcessingHook};
+use crate::processing::idempotency::{open_idempotency_store, IdempotencyStore};
+use crate::processing::llm_overrides::{input_without_overrides, LlmOverrides};
+use crate::processing::moderation::{moderate, ModerationPolicy};
+use crate::processing::response_limit::apply_response_limit;
+use crate::processing::task_claims::{settled, TaskClaim, TaskClaims};
+use crate::progress::{
+    metadata, NoOpProgress, Progress, ProgressCategory, ProgressEventType, RedactingProgress,
+};
+use crate::protocol::messages::{
+    Artifact, DroppedV2Fields, ResponseMessage, RoutingStep, TaskAck, TaskEnvelope,
+    TaskEnvelopeWrapper, WorkflowContext,
+};
+use crate::protocol::topics::{
+    agent_id_from_topic, agent_input_topic, canonicalize_topic_folded, describe_topic_difference,
+};
+use crate::protocol::workflow_state::STATE_UPDATES_KEY;
 use crate::routing::agent_selector::{AgentSelectionDecision, RoutingHelper};
+use crate::routing::audit_log::{
+    record_routing_decision, RoutingAuditEntry, RoutingAuditLog, RoutingOutcome,
+};
 use crate::tools::ToolSystem;
 use crate::transport::Transport;
 use chrono;
-use std::collections::HashSet;
-use std::sync::Arc;
-use tokio::sync::Mutex;
-use tracing::{debug, info, warn};
+use serde_json::{Map, Value};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+use tracing::{debug, info, warn, Instrument};
 use uuid::Uuid;
 
+/// Closing request to a v2 agent that used tools, answered under the RouteDecision schema
+const ROUTE_DECISION_PROMPT: &str = "Tool use is complete. Respond with your result and \
+     routing decision as a RouteDecision JSON object.";
+
 /// RFC-compliant task processor implementing exact 9-step algorithm
 pub struct NineStepProcessor<T: Transport> {
     config: AgentConfig,
     llm_provider: Arc<dyn LlmProvider>,
-    tool_system: Arc<ToolSystem>,
+    /// Current tool system, replaced when the tool configs are reloaded
+    tool_system: RwLock<Arc<ToolSystem>>,
     pub transport: Arc<T>,
     progress: Arc<dyn Progress>,
-    processed_tasks: Arc<Mutex<HashSet<Uuid>>>,
+    idempotency_store: Arc<dyn IdempotencyStore>,
+    /// Tasks past step 4 that are not yet recorded in the idempotency store
+    claims: TaskClaims,
     processor_config: ProcessorConfig,
     routing_helper: RoutingHelper,
     agent_registry: AgentRegistry,
+    cancellation: CancellationRegistry,
+    routing_audit_log: Option<Arc<RoutingAuditLog>>,
+    /// Hooks around the LLM and publish steps, in registration order
+    hooks: Vec<Arc<dyn ProcessingHook>>,
+    /// Check on every response before it is published
+    moderation: Option<Arc<dyn ModerationPolicy>>,
+    /// Reloaded configuration; LLM settings and authorization rules are read from here when set
+    config_updates: Option<watch::Receiver<AgentConfig>>,
+    /// Authorization rules last compiled, rebuilt when the rules are reloaded
+    authorizer: RwLock<Option<(AuthorizationSection, Arc<TaskAuthorizer>)>>,
+    /// Registry the step each task is in is reported to, for `/tasks/active`
+    state_registry: Option<Arc<AgentStateRegistry>>,
+    /// Redaction from `[observability.redaction]`, already applied to `progress`
+    redactor: Arc<Redactor>,
+    /// Time stamped on workflows, routing steps and responses
+    clock: Arc<dyn Clock>,
 }
 
 /// Configuration for the 9-step processor
@@ -49,15 +101,30 @@ pub struct NineStepProcessor<T: Transport> {
 pub struct ProcessorConfig {
     /// Maximum pipeline depth per RFC FR-013
     pub max_pipeline_depth: u32,
-    /// Maximum processed task IDs to keep in memory
+    /// Maximum processed task IDs to keep in memory; older ids are evicted first
     pub max_task_cache: usize,
+    /// LLM rounds that may request tools before the task fails
+    pub max_tool_iterations: usize,
+    /// How long one task may take; no limit when None
+    pub task_timeout: Option<Duration>,
+    /// Handling of a copy of a task that arrives while the task is in flight
+    pub duplicate_in_flight: DuplicateInFlight,
 }
 
 impl Default for ProcessorConfig {
     fn default() -> Self {
+        Self::from(&ProcessingSection::default())
+    }
+}
+
+impl From<&ProcessingSection> for ProcessorConfig {
+    fn from(section: &ProcessingSection) -> Self {
         Self {
-            max_pipeline_depth: 16, // RFC FR-013 requirement
-            max_task_cache: 10000,
+            max_pipeline_depth: section.max_pipeline_depth,
+            max_task_cache: section.max_task_cache,
+            max_tool_iterations: section.max_tool_iterations,
+            task_timeout: section.task_timeout_secs.map(Duration::from_secs),
+            duplicate_in_flight: section.duplicate_in_flight,
         }
     }
 }
@@ -79,6 +146,32 @@ pub struct ProcessingState {
     pub error_message: Option<String>,
 }
 
+/// Wall-clock duration of each step a task went through, in order
+type StepTimings = Vec<(u8, Duration)>;
+
+/// Registration of an in-flight task for cancel requests; finished on drop,
+/// so a task that panics does not stay registered as active
+struct ActiveTask<'a> {
+    cancellation: &'a CancellationRegistry,
+    task_id: Uuid,
+}
+
+impl<'a> ActiveTask<'a> {
+    fn begin(cancellation: &'a CancellationRegistry, task_id: Uuid, conversation_id: &str) -> Self {
+        cancellation.begin(task_id, conversation_id);
+        Self {
+            cancellation,
+            task_id,
+        }
+    }
+}
+
+impl Drop for ActiveTask<'_> {
+    fn drop(&mut self) {
+        self.cancellation.finish(self.task_id);
+    }
+}
+
 impl<T: Transport + 'static> NineStepProcessor<T> {
     /// Create a new RFC-compliant processor (backward compatibility with defaults)
     pub fn new(
@@ -87,16 +180,32 @@ impl<T: Transport + 'static> NineStepProcessor<T> {
         tool_system: Arc<ToolSystem>,
         transport: Arc<T>,
     ) -> Self {
+        let processor_config = ProcessorConfig::from(&config.processing);
+        let idempotency_store = Self::open_idempotency_store(&config, &processor_config);
+        let routing_helper = RoutingHelper::from_config(config.routing.as_ref());
+        let routing_audit_log = Self::open_routing_audit_log(&config);
+        let redactor = Self::open_redactor(&config);
+        let clock = clock_for(&config.testing);
         Self {
             config,
             llm_provider,
-            tool_system,
+            tool_system: RwLock::new(tool_system),
             transport,
             progress: Arc::new(NoOpProgress),
-            processed_tasks: Arc::new(Mutex::new(HashSet::new())),
-            processor_config: ProcessorConfig::default(),
-            routing_helper: RoutingHelper::new(),
+            idempotency_store,
+            claims: TaskClaims::new(),
+            processor_config,
+            routing_helper,
             agent_registry: AgentRegistry::new(),
+            cancellation: CancellationRegistry::new(),
+            routing_audit_log,
+            hooks: Vec::new(),
+            moderation: None,
+            config_updates: None,
+            authorizer: RwLock::new(None),
+            state_registry: None,
+            redactor,
+            clock,
         }
     }
 
@@ -109,19 +218,73 @@ impl<T: Transport + 'static> NineStepProcessor<T> {
         routing_helper: RoutingHelper,
         agent_registry: AgentRegistry,
     ) -> Self {
+        let processor_config = ProcessorConfig::from(&config.processing);
+        let idempotency_store = Self::open_idempotency_store(&config, &processor_config);
+        let routing_audit_log = Self::open_routing_audit_log(&config);
+        let redactor = Self::open_redactor(&config);
+        let clock = clock_for(&config.testing);
         Self {
             config,
             llm_provider,
-            tool_system,
+            tool_system: RwLock::new(tool_system),
             transport,
             progress: Arc::new(NoOpProgress),
-            processed_tasks: Arc::new(Mutex::new(HashSet::new())),
-            processor_config: ProcessorConfig::default(),
+            idempotency_store,
+            claims: TaskClaims::new(),
+            processor_config,
             routing_helper,
             agent_registry,
+            cancellation: CancellationRegistry::new(),
+            routing_audit_log,
+            hooks: Vec::new(),
+            moderation: None,
+            config_updates: None,
+            authorizer: RwLock::new(None),
+            state_registry: None,
+            redactor,
+            clock,
         }
     }
 
+    /// Open the idempotency store configured by `agent.state_dir`
+    fn open_idempotency_store(
+        config: &AgentConfig,
+        processor_config: &ProcessorConfig,
+    ) -> Arc<dyn IdempotencyStore> {
+        open_idempotency_store(
+            config.agent.state_dir.as_deref(),
+            Duration::from_secs(config.agent.idempotency_ttl_secs),
+            processor_config.max_task_cache,
+        )
+    }
+
+    /// Open the audit log configured by `[routing] audit_log`
+    ///
+    /// A log that can't be opened is reported and routing continues unaudited.
+    fn open_routing_audit_log(config: &AgentConfig) -> Option<Arc<RoutingAuditLog>> {
+        let path = config.routing.as_ref()?.audit_log.as_ref()?;
+        match RoutingAuditLog::open(path) {
+            Ok(audit_log) => Some(Arc::new(audit_log)),
+            Err(e) => {
+                warn!(path = %path.display(), error = %e, "Failed to open routing audit log");
+                None
+            }
+        }
+    }
+
+    /// Build the redactor configured by `[observability.redaction]`
+    ///
+    /// Validation rejects invalid patterns; should one get through anyway,
+    /// it is reported and only the secrets are redacted.
+    fn open_redactor(config: &AgentConfig) -> Arc<Redactor> {
+        let redactor = Redactor::from_config(config).unwrap_or_else(|e| {
+            warn!(error = %e, "Invalid redaction pattern, redacting secrets only");
+            let secrets = config.secret_values();
+            Redactor::new([] as [&str; 0], secrets.iter().map(|s| s.expose())).unwrap_or_default()
+        });
+        Arc::new(redactor)
+    }
+
     // ========== PURE RFC STEP FUNCTIONS ==========
     // Each step is pure and testable independently
 
@@ -155,19 +318,24 @@ impl<T: Transport + 'static> NineStepProcessor<T> {
     }
 
     /// Step 3: Validate topic canonicalization (pure function)
-    fn step_3_validate_topic(received_topic: &str, task_topic: &str) -> ProcessingState {
-        let canonical_received = canonicalize_topic(received_topic);
-        let canonical_task = canonicalize_topic(task_topic);
+    ///
+    /// With `case_insensitive`, topics differing only in case match.
+    pub fn step_3_validate_topic(
+        received_topic: &str,
+        task_topic: &str,
+        case_insensitive: bool,
+    ) -> ProcessingState {
+        let canonical_received = canonicalize_topic_folded(received_topic, case_insensitive);
+        let canonical_task = canonicalize_topic_folded(task_topic, case_insensitive);
 
         if canonical_received != canonical_task {
+            let difference = describe_topic_difference(&canonical_received, &canonical_task);
             ProcessingState {
                 step: 3,
-                description: format!(
-                    "Topic mismatch - received: '{canonical_received}', task: '{canonical_task}'"
-                ),
+                description: format!("Topic mismatch - received vs task: {difference}"),
                 success: false,
                 error_message: Some(format!(
-                    "Topic mismatch - received: '{received_topic}' (canonical: '{canonical_received}'), task: '{task_topic}' (canonical: '{canonical_task}')"
+                    "Topic mismatch - received: '{received_topic}', task: '{task_topic}'; canonical received vs task: {difference}"
                 )),
             }
         } else {
@@ -181,37 +349,74 @@ impl<T: Transport + 'static> NineStepProcessor<T> {
     }
 
     /// Step 4: Check task idempotency (impure - requires state check)
-    async fn step_4_check_idempotency(&self, task_id: Uuid) -> ProcessingState {
-        let mut processed = self.processed_tasks.lock().await;
-        if processed.contains(&task_id) {
-            return ProcessingState {
+    ///
+    /// The task id is claimed before the store is consulted, so of two copies
+    /// arriving together only one gets past this step. A copy arriving while
+    /// the claim is held is rejected, or waits for the claim to settle, per
+    /// `duplicate_in_flight`. The id is only recorded in the idempotency store
+    /// once the task completes (see [`Self::record_processed`]), so a task that
+    /// fails, or is interrupted by a crash, may be processed again.
+    async fn step_4_check_idempotency(
+        &self,
+        task_id: Uuid,
+    ) -> (ProcessingState, Option<TaskClaim<'_>>) {
+        let claim = loop {
+            match self.claims.try_claim(task_id) {
+                Ok(claim) => break claim,
+                Err(in_flight) => match self.processor_config.duplicate_in_flight {
+                    DuplicateInFlight::Reject => {
+                        let state = ProcessingState {
+                            step: 4,
+                            description: format!(
+                                "Duplicate task ID {task_id} rejected, the task is in flight"
+                            ),
+                            success: false,
+                            error_message: Some(
+                                "Task already being processed (idempotency)".to_string(),
+                            ),
+                        };
+                        return (state, None);
+                    }
+                    DuplicateInFlight::Wait => {
+                        debug!(task_id = %task_id, "Waiting for in-flight copy of task to settle");
+                        let outcome = settled(in_flight).await;
+                        debug!(task_id = %task_id, ?outcome, "In-flight copy of task settled");
+                    }
+                },
+            }
+        };
+
+        // A claim is only released after its task is recorded, so this sees
+        // every copy that completed before the claim was taken
+        if self.idempotency_store.contains(&task_id).await {
+            let state = ProcessingState {
                 step: 4,
                 description: format!("Duplicate task ID {task_id} rejected for idempotency"),
                 success: false,
                 error_message: Some("Task already processed (idempotency)".to_string()),
             };
+            return (state, None);
         }
 
-        // Add to processed set with memory management
-        processed.insert(task_id);
-        if processed.len() > self.processor_config.max_task_cache {
-            let excess = processed.len() - self.processor_config.max_task_cache;
-            let to_remove: Vec<_> = processed.iter().take(excess).copied().collect();
-            for id in to_remove {
-                processed.remove(&id);
-            }
-        }
-
-        ProcessingState {
+        let state = ProcessingState {
             step: 4,
-            description: format!("Task ID {task_id} is unique, added to idempotency cache"),
+            description: format!("Task ID {task_id} is unique, claimed until processed"),
             success: true,
             error_message: None,
-        }
+        };
+        (state, Some(claim))
+    }
+
+    /// Record a completed task as processed, then release its claim
+    ///
+    /// The store prunes ids older than its TTL as it grows.
+    async fn record_processed(&self, claim: TaskClaim<'_>) {
+        self.idempotency_store.insert(claim.task_id()).await;
+        claim.complete();
     }
 
     /// Step 5: Check pipeline depth (pure function)
-    fn step_5_check_pipeline_depth(task: &TaskEnvelope, max_depth: u32) -> ProcessingState {
+    pub fn step_5_check_pipeline_depth(task: &TaskEnvelope, max_depth: u32) -> ProcessingState {
         let pipeline_depth = Self::calculate_pipeline_depth(task);
         if pipeline_depth > max_depth {
             ProcessingState {
@@ -242,6 +447,16 @@ impl<T: Transport + 'static> NineStepProcessor<T> {
         }
     }
 
+    /// Reject tasks whose deadline has already passed (pure validation)
+    fn check_deadline(task: &TaskEnvelope, now: chrono::DateTime<chrono::Utc>) -> AgentResult<()> {
+        match task.deadline {
+            Some(deadline) if now > deadline => {
+                Err(AgentError::deadline_exceeded(deadline.to_rfc3339()))
+            }
+            _ => Ok(()),
+        }
+    }
+
     /// Calculate pipeline depth (pure function)
     fn calculate_pipeline_depth(task: &TaskEnvelope) -> u32 {
         let mut depth = 1;
@@ -263,16 +478,74 @@ impl<T: Transport + 'static> NineStepProcessor<T> {
 
     /// Step 8 Enhanced: Simplified routing for TaskEnvelope v2.0
     /// Handles both static (v1.0) and agent decision-based (v2.0) routing
+    ///
+    /// Every decision is reported to the routing metrics and audit log.
     #[cfg_attr(test, allow(dead_code))]
     pub async fn step_8_enhanced_routing(
         &self,
-        _wrapper: &TaskEnvelopeWrapper,
+        v2_fields: Option<&DroppedV2Fields>,
+        task: &TaskEnvelope,
+        response: &str,
+    ) -> AgentResult<(bool, Vec<RoutingStep>)> {
+        let started = Instant::now();
+        let routed = self.route_task(v2_fields, task, response).await;
+
+        let entry = |decision| {
+            RoutingAuditEntry::new(
+                task.task_id,
+                &task.conversation_id,
+                &self.config.agent.id,
+                decision,
+                started.elapsed(),
+            )
+        };
+        let entry = match &routed {
+            Ok((true, trace)) => match trace.last() {
+                Some(step) => entry(RoutingOutcome::Forward)
+                    .with_next_agent(step.to_agent.as_str())
+                    .with_reasoning(Some(step.reason.clone())),
+                None => entry(RoutingOutcome::Forward),
+            },
+            Ok((false, _)) => entry(RoutingOutcome::Complete),
+            Err(e) => entry(RoutingOutcome::Error).with_error(e.to_string()),
+        };
+        record_routing_decision(self.routing_audit_log.as_deref(), entry);
+
+        routed
+    }
+
+    /// Decide and perform step 8 routing
+    async fn route_task(
+        &self,
+        v2_fields: Option<&DroppedV2Fields>,
         task: &TaskEnvelope,
         response: &str,
     ) -> AgentResult<(bool, Vec<RoutingStep>)> {
+        if let Some(context) = v2_fields.and_then(|fields| fields.context.as_ref()) {
+            debug!(
+                task_id = %task.task_id,
+                original_query = %context.original_query,
+                iteration_count = context.iteration_count,
+                steps_completed = context.steps_completed.len(),
+                "Routing with v2 workflow context"
+            );
+        }
+
+        // A workflow past its wall-clock budget completes here instead of forwarding
+        if Self::workflow_budget_exhausted(v2_fields, self.clock.now()) {
+            warn!(
+                task_id = %task.task_id,
+                conversation_id = %task.conversation_id,
+                "Workflow budget exhausted, completing workflow"
+            );
+            return Ok((false, Vec::new()));
+        }
+
         // Check for static v1.0 routing first
         if let Some(next_task) = &task.next {
-            return self.handle_static_routing(task, next_task, response).await;
+            return self
+                .handle_static_routing(v2_fields, task, next_task, response)
+                .await;
         }
 
         // No static routing, try dynamic agent decision routing
@@ -293,7 +566,10 @@ impl<T: Transport + 'static> NineStepProcessor<T> {
                 }
 
                 // Try dynamic routing
-                if let Some(routing_step) = self.handle_dynamic_routing(task, &decision).await? {
+                if let Some(routing_step) = self
+                    .handle_dynamic_routing(v2_fields, task, &decision)
+                    .await?
+                {
                     return Ok((true, vec![routing_step]));
                 }
 
@@ -303,6 +579,21 @@ impl<T: Transport + 'static> NineStepProcessor<T> {
                     "Agent decision does not include next agent"
                 );
             }
+            Err(e @ DecisionParseError::UnsupportedSchemaVersion(_)) => {
+                warn!(
+                    task_id = %task.task_id,
+                    error = %e,
+                    "Agent decision uses an unsupported schema version, not forwarding"
+                );
+            }
+            // v2 agents are asked for a decision, so a malformed one is worth a warning
+            Err(e) if v2_fields.is_some() && e != DecisionParseError::NoJsonObject => {
+                warn!(
+                    task_id = %task.task_id,
+                    error = %e,
+                    "Malformed agent decision, not forwarding"
+                );
+            }
             Err(e) => {
                 debug!(
                     task_id = %task.task_id,
@@ -320,18 +611,91 @@ impl<T: Transport + 'static> NineStepProcessor<T> {
         Ok((false, Vec::new()))
     }
 
+    /// Step number for the next routing decision - pure function
+    ///
+    /// Continues the incoming v2.0 trace; v1.0 tasks start a new trace.
+    fn next_routing_step_number(v2_fields: Option<&DroppedV2Fields>) -> u32 {
+        v2_fields
+            .and_then(|fields| fields.routing_trace.as_ref())
+            .and_then(|trace| trace.last())
+            .map_or(1, |step| step.step_number + 1)
+    }
+
+    /// Wrap a forwarded task for publishing
+    ///
+    /// v2.0 tasks get the routing step appended to their trace and carry
+    /// their workflow context forward with the hop counted, so the receiving
+    /// agent's iteration limit sees the whole workflow. A v2.0 task without a
+    /// context starts one from the original instruction, under this agent's
+    /// workflow budget. The deciding agent's `state_updates` are merged into
+    /// the context's state. v1.0 tasks have no trace and are forwarded
+    /// unchanged.
+    fn build_forwarded_envelope(
+        &self,
+        original_task: &TaskEnvelope,
+        forwarded_task: TaskEnvelope,
+        v2_fields: Option<&DroppedV2Fields>,
+        routing_step: &RoutingStep,
+        state_updates: Option<&Map<String, Value>>,
+    ) -> TaskEnvelopeWrapper {
+        let Some(fields) = v2_fields else {
+            return TaskEnvelopeWrapper::V1(forwarded_task);
+        };
+
+        let mut fields = fields.clone();
+        let context = fields.context.get_or_insert_with(|| WorkflowContext {
+            original_query: original_task
+                .instruction
+                .clone()
+                .unwrap_or_else(|| "Unknown".to_string()),
+            steps_completed: Vec::new(),
+            iteration_count: 0,
+            started_at: Some(self.clock.now()),
+            budget_secs: self
+                .config
+                .routing
+                .as_ref()
+                .and_then(|routing| routing.workflow_budget_secs),
+            state: None,
+            state_order: Vec::new(),
+        });
+        let action = forwarded_task
+            .instruction
+            .clone()
+            .unwrap_or_else(|| routing_step.reason.clone());
+        context.record_forward(&routing_step.from_agent, action, self.clock.now());
+        if let Some(updates) = state_updates {
+            context.merge_state(updates);
+        }
+
+        let mut envelope = fields.reattach(forwarded_task);
+        envelope.push_routing_step(routing_step.clone());
+        TaskEnvelopeWrapper::V2(envelope)
+    }
+
+    /// Whether the task's workflow has run for its whole wall-clock budget - pure function
+    fn workflow_budget_exhausted(
+        v2_fields: Option<&DroppedV2Fields>,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> bool {
+        v2_fields
+            .and_then(|fields| fields.context.as_ref())
+            .is_some_and(|context| context.budget_exhausted(now))
+    }
+
     /// Create a routing trace step - pure function
     fn create_routing_step(
         from_agent: &str,
         to_agent: &str,
         reason: String,
         step_number: u32,
+        now: chrono::DateTime<chrono::Utc>,
     ) -> RoutingStep {
         RoutingStep {
             from_agent: from_agent.to_string(),
             to_agent: to_agent.to_string(),
             reason,
-            timestamp: chrono::Utc::now().to_rfc3339(),
+            timestamp: now.to_rfc3339(),
             step_number,
         }
     }
@@ -339,6 +703,7 @@ impl<T: Transport + 'static> NineStepProcessor<T> {
     /// Handle static v1.0 routing from TaskEnvelope.next field
     async fn handle_static_routing(
         &self,
+        v2_fields: Option<&DroppedV2Fields>,
         task: &TaskEnvelope,
         next_task: &crate::protocol::messages::NextTask,
         response: &str,
@@ -358,17 +723,22 @@ impl<T: Transport + 'static> NineStepProcessor<T> {
             &self.config.agent.id,
             &agent_id,
             "Static routing from TaskEnvelope.next field".to_string(),
-            1,
+            Self::next_routing_step_number(v2_fields),
+            self.clock.now(),
         );
 
-        self.forward_to_next_agent(task, next_task, response)
+        self.forward_to_next_agent(task, next_task, response, v2_fields, &routing_step)
             .await?;
         Ok((true, vec![routing_step]))
     }
 
     /// Handle dynamic agent decision-based routing
+    ///
+    /// `next_agent` is either an agent id or `capability:<name>`, which is
+    /// resolved to a healthy agent using the routing helper's strategy.
     async fn handle_dynamic_routing(
         &self,
+        v2_fields: Option<&DroppedV2Fields>,
         task: &TaskEnvelope,
         decision: &crate::agent::response::AgentDecision,
     ) -> AgentResult<Option<RoutingStep>> {
@@ -381,7 +751,7 @@ impl<T: Transport + 'static> NineStepProcessor<T> {
 
             let routing_decision = self
                 .routing_helper
-                .find_agent_by_id(next_agent_id, &self.agent_registry);
+                .resolve_target(next_agent_id, &self.agent_registry);
 
             match routing_decision {
                 AgentSelectionDecision::RouteToAgent { agent, reason } => {
@@ -402,16 +772,29 @@ impl<T: Transport + 'static> NineStepProcessor<T> {
                                 .as_ref()
                                 .unwrap_or(&"Continue processing".to_string())
                         ),
-                        1,
+                        Self::next_routing_step_number(v2_fields),
+                        self.clock.now(),
                     );
 
-                    self.forward_to_agent(
-                        task,
-                        &agent.agent_id,
-                        decision.next_instruction.as_deref(),
-                        &decision.result,
-                    )
-                    .await?;
+                    let forwarded = self
+                        .forward_to_agent(
+                            task,
+                            &agent.agent_id,
+                            decision.next_instruction.as_deref(),
+                            &decision.result,
+                            v2_fields,
+                            &routing_step,
+                            decision.state_updates.as_ref(),
+                        )
+                        .await;
+
+                    // Feed the outcome into load-aware selection's error rate
+                    let selector = self.routing_helper.load_aware_selector();
+                    match &forwarded {
+                        Ok(()) => selector.record_success(&agent.agent_id),
+                        Err(_) => selector.record_failure(&agent.agent_id),
+                    }
+                    forwarded?;
 
                     return Ok(Some(routing_step));
                 }
@@ -429,21 +812,13 @@ impl<T: Transport + 'static> NineStepProcessor<T> {
     }
 
     /// Extract agent ID from control topic: /control/agents/{agent_id}/input
+    ///
+    /// `None` unless the topic is an agent topic with a valid agent id.
     pub fn extract_agent_id_from_topic(&self, topic: &str) -> Option<String> {
-        use crate::protocol::topics::canonicalize_topic;
-
-        let canonical_topic = canonicalize_topic(topic);
-        let parts: Vec<&str> = canonical_topic.trim_start_matches('/').split('/').collect();
-
-        if parts.len() >= 3 && parts[0] == "control" && parts[1] == "agents" {
-            Some(parts[2].to_string())
-        } else {
-            None
-        }
+        agent_id_from_topic(topic).ok()
     }
 
-    /// Get reference to the routing helper for testing
-    #[cfg(test)]
+    /// Get the routing helper used to resolve `capability:` targets
     pub fn routing_helper(&self) -> &RoutingHelper {
         &self.routing_helper
     }
@@ -454,6 +829,166 @@ impl<T: Transport + 'static> NineStepProcessor<T> {
         &self.agent_registry
     }
 
+    /// Check whether a task id is already in the idempotency cache or being processed
+    pub async fn has_processed(&self, task_id: &Uuid) -> bool {
+        self.claims.contains(task_id) || self.idempotency_store.contains(task_id).await
+    }
+
+    /// Let a task that was recorded as processed be processed again
+    ///
+    /// Used for internal retries, which reuse the task id of the failed
+    /// attempt. Tasks that fail in the 9 steps are never recorded, but a v2
+    /// task can fail in routing after its work completed.
+    pub async fn allow_retry(&self, task_id: &Uuid) {
+        self.idempotency_store.remove(task_id).await;
+    }
+
+    /// Replace the idempotency store, for example with a shared or custom backend
+    pub fn set_idempotency_store(&mut self, idempotency_store: Arc<dyn IdempotencyStore>) {
+        self.idempotency_store = idempotency_store;
+    }
+
+    /// Get the shared cancellation registry for in-flight tasks
+    pub fn cancellation(&self) -> &CancellationRegistry {
+        &self.cancellation
+    }
+
+    /// Append every step 8 routing decision to an audit log
+    pub fn set_routing_audit_log(&mut self, routing_audit_log: Arc<RoutingAuditLog>) {
+        self.routing_audit_log = Some(routing_audit_log);
+    }
+
+    /// Get the routing audit log, if any
+    pub fn routing_audit_log(&self) -> Option<&Arc<RoutingAuditLog>> {
+        self.routing_audit_log.as_ref()
+    }
+
+    /// Run hooks around the LLM and publish steps, in the given order
+    pub fn with_hooks(mut self, hooks: Vec<Arc<dyn ProcessingHook>>) -> Self {
+        self.hooks = hooks;
+        self
+    }
+
+    /// Check every response with `policy` before it is published
+    pub fn with_moderation(mut self, policy: Arc<dyn ModerationPolicy>) -> Self {
+        self.moderation = Some(policy);
+        self
+    }
+
+    /// Read the time from `clock`, shared with the pipeline and lifecycle
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// The clock timestamps are read from
+    pub fn clock(&self) -> &Arc<dyn Clock> {
+        &self.clock
+    }
+
+    /// The moderation policy responses are checked with, if any
+    pub fn moderation(&self) -> Option<&Arc<dyn ModerationPolicy>> {
+        self.moderation.as_ref()
+    }
+
+    /// Read LLM settings and authorization rules from reloaded configuration
+    /// instead of the startup config
+    pub fn with_config_updates(mut self, config_updates: watch::Receiver<AgentConfig>) -> Self {
+        self.config_updates = Some(config_updates);
+        self
+    }
+
+    /// Report the step each task is in to `state_registry`
+    pub fn with_state_registry(mut self, state_registry: Arc<AgentStateRegistry>) -> Self {
+        self.state_registry = Some(state_registry);
+        self
+    }
+
+    /// Record that a task entered `step` in the state registry, if any
+    fn enter_step(&self, task_id: Uuid, step: u8, description: &str) {
+        if let Some(state_registry) = &self.state_registry {
+            state_registry.task_step(task_id, step, description);
+        }
+    }
+
+    /// Current LLM settings, following configuration reloads
+    fn llm_settings(&self) -> LlmSection {
+        match &self.config_updates {
+            Some(config_updates) => config_updates.borrow().llm.clone(),
+            None => self.config.llm.clone(),
+        }
+    }
+
+    /// Current authorization rules, following configuration reloads
+    fn authorization_section(&self) -> AuthorizationSection {
+        match &self.config_updates {
+            Some(config_updates) => config_updates.borrow().authorization.clone(),
+            None => self.config.authorization.clone(),
+        }
+    }
+
+    /// Check `task` against the current authorization rules
+    ///
+    /// The rules are compiled once and again only after they are reloaded.
+    fn authorize(&self, task: &TaskEnvelope) -> AgentResult<()> {
+        let section = self.authorization_section();
+        let cached = self
+            .authorizer
+            .read()
+            .unwrap()
+            .as_ref()
+            .filter(|(compiled, _)| *compiled == section)
+            .map(|(_, authorizer)| authorizer.clone());
+        let authorizer = match cached {
+            Some(authorizer) => authorizer,
+            None => {
+                let authorizer = Arc::new(TaskAuthorizer::new(&section).map_err(|e| {
+                    AgentError::internal_error(format!("Invalid authorization rules: {e}"))
+                })?);
+                *self.authorizer.write().unwrap() = Some((section, authorizer.clone()));
+                authorizer
+            }
+        };
+
+        if let Err(e) = authorizer.authorize(task) {
+            metrics().task_unauthorized();
+            warn!(
+                task_id = %task.task_id,
+                conversation_id = %task.conversation_id,
+                sender_id = task.sender_id.as_deref().unwrap_or("unknown"),
+                error = %e,
+                "Task rejected by authorization rules"
+            );
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Get the current tool system
+    pub fn tool_system(&self) -> Arc<ToolSystem> {
+        self.tool_system.read().unwrap().clone()
+    }
+
+    /// Use a new tool system for tasks that start from now on
+    ///
+    /// Tasks already running keep the tool system they started with.
+    pub fn replace_tool_system(&self, tool_system: Arc<ToolSystem>) {
+        *self.tool_system.write().unwrap() = tool_system;
+    }
+
+    /// Release the LLM provider's connections and notify tools that the agent is idle
+    ///
+    /// Both are re-acquired lazily by the next task.
+    pub async fn release_idle_resources(&self) {
+        self.llm_provider.release_resources();
+        self.tool_system().on_idle().await;
+    }
+
+    /// Get the progress reporter
+    pub fn progress(&self) -> &Arc<dyn Progress> {
+        &self.progress
+    }
+
     // ========== STEP ORCHESTRATOR ==========
 
     /// Create a new processor with progress reporting (backward compatibility)
@@ -464,16 +999,32 @@ impl<T: Transport + 'static> NineStepProcessor<T> {
         transport: Arc<T>,
         progress: Arc<dyn Progress>,
     ) -> Self {
+        let processor_config = ProcessorConfig::from(&config.processing);
+        let idempotency_store = Self::open_idempotency_store(&config, &processor_config);
+        let routing_helper = RoutingHelper::from_config(config.routing.as_ref());
+        let routing_audit_log = Self::open_routing_audit_log(&config);
+        let redactor = Self::open_redactor(&config);
+        let clock = clock_for(&config.testing);
         Self {
             config,
             llm_provider,
-            tool_system,
+            tool_system: RwLock::new(tool_system),
             transport,
-            progress,
-            processed_tasks: Arc::new(Mutex::new(HashSet::new())),
-            processor_config: ProcessorConfig::default(),
-            routing_helper: RoutingHelper::new(),
+            progress: RedactingProgress::wrap(progress, &redactor),
+            idempotency_store,
+            claims: TaskClaims::new(),
+            processor_config,
+            routing_helper,
             agent_registry: AgentRegistry::new(),
+            cancellation: CancellationRegistry::new(),
+            routing_audit_log,
+            hooks: Vec::new(),
+            moderation: None,
+            config_updates: None,
+            authorizer: RwLock::new(None),
+            state_registry: None,
+            redactor,
+            clock,
         }
     }
 
@@ -487,16 +1038,31 @@ impl<T: Transport + 'static> NineStepProcessor<T> {
         routing_helper: RoutingHelper,
         agent_registry: AgentRegistry,
     ) -> Self {
+        let processor_config = ProcessorConfig::from(&config.processing);
+        let idempotency_store = Self::open_idempotency_store(&config, &processor_config);
+        let routing_audit_log = Self::open_routing_audit_log(&config);
+        let redactor = Self::open_redactor(&config);
+        let clock = clock_for(&config.testing);
         Self {
             config,
             llm_provider,
-            tool_system,
+            tool_system: RwLock::new(tool_system),
             transport,
-            progress,
-            processed_tasks: Arc::new(Mutex::new(HashSet::new())),
-            processor_config: ProcessorConfig::default(),
+            progress: RedactingProgress::wrap(progress, &redactor),
+            idempotency_store,
+            claims: TaskClaims::new(),
+            processor_config,
             routing_helper,
             agent_registry,
+            cancellation: CancellationRegistry::new(),
+            routing_audit_log,
+            hooks: Vec::new(),
+            moderation: None,
+            config_updates: None,
+            authorizer: RwLock::new(None),
+            state_registry: None,
+            redactor,
+            clock,
         }
     }
 
@@ -508,16 +1074,31 @@ impl<T: Transport + 'static> NineStepProcessor<T> {
         transport: Arc<T>,
         processor_config: ProcessorConfig,
     ) -> Self {
+        let idempotency_store = Self::open_idempotency_store(&config, &processor_config);
+        let routing_helper = RoutingHelper::from_config(config.routing.as_ref());
+        let routing_audit_log = Self::open_routing_audit_log(&config);
+        let redactor = Self::open_redactor(&config);
+        let clock = clock_for(&config.testing);
         Self {
             config,
             llm_provider,
-            tool_system,
+            tool_system: RwLock::new(tool_system),
             transport,
             progress: Arc::new(NoOpProgress),
-            processed_tasks: Arc::new(Mutex::new(HashSet::new())),
+            idempotency_store,
+            claims: TaskClaims::new(),
             processor_config,
-            routing_helper: RoutingHelper::new(),
+            routing_helper,
             agent_registry: AgentRegistry::new(),
+            cancellation: CancellationRegistry::new(),
+            routing_audit_log,
+            hooks: Vec::new(),
+            moderation: None,
+            config_updates: None,
+            authorizer: RwLock::new(None),
+            state_registry: None,
+            redactor,
+            clock,
         }
     }
 
@@ -530,16 +1111,31 @@ impl<T: Transport + 'static> NineStepProcessor<T> {
         progress: Arc<dyn Progress>,
         processor_config: ProcessorConfig,
     ) -> Self {
+        let idempotency_store = Self::open_idempotency_store(&config, &processor_config);
+        let routing_helper = RoutingHelper::from_config(config.routing.as_ref());
+        let routing_audit_log = Self::open_routing_audit_log(&config);
+        let redactor = Self::open_redactor(&config);
+        let clock = clock_for(&config.testing);
         Self {
             config,
             llm_provider,
-            tool_system,
+            tool_system: RwLock::new(tool_system),
             transport,
-            progress,
-            processed_tasks: Arc::new(Mutex::new(HashSet::new())),
+            progress: RedactingProgress::wrap(progress, &redactor),
+            idempotency_store,
+            claims: TaskClaims::new(),
             processor_config,
-            routing_helper: RoutingHelper::new(),
+            routing_helper,
             agent_registry: AgentRegistry::new(),
+            cancellation: CancellationRegistry::new(),
+            routing_audit_log,
+            hooks: Vec::new(),
+            moderation: None,
+            config_updates: None,
+            authorizer: RwLock::new(None),
+            state_registry: None,
+            redactor,
+            clock,
         }
     }
 
@@ -551,10 +1147,16 @@ impl<T: Transport + 'static> NineStepProcessor<T> {
     )]
     pub async fn process_task(
         &self,
-        wrapper: TaskEnvelopeWrapper,
+        mut wrapper: TaskEnvelopeWrapper,
         received_topic: &str,
         is_retained: bool,
     ) -> AgentResult<ProcessingResult> {
+        // Join the publishing agent's trace before any child span exists
+        if let Some(traceparent) = wrapper.traceparent() {
+            crate::observability::otel::set_remote_parent(&tracing::Span::current(), traceparent);
+        }
+        let correlation_id = wrapper.ensure_correlation_id_with(self.config.testing.deterministic);
+        let parent_task_id = wrapper.parent_task_id().map(|id| id.to_string());
         let task_id = wrapper.task_id();
         let conversation_id = wrapper.conversation_id();
         let topic = match &wrapper {
@@ -565,6 +1167,7 @@ impl<T: Transport + 'static> NineStepProcessor<T> {
         info!(
             task_id = %task_id,
             conversation_id = %conversation_id,
+            correlation_id = %correlation_id,
             topic = %topic,
             envelope_version = match &wrapper {
                 TaskEnvelopeWrapper::V1(_) => "v1.0",
@@ -573,6 +1176,13 @@ impl<T: Transport + 'static> NineStepProcessor<T> {
             "Starting RFC-compliant 9-step processing"
         );
 
+        self.progress
+            .register_correlation(
+                &task_id.to_string(),
+                &correlation_id,
+                parent_task_id.as_deref(),
+            )
+            .await;
         self.progress
             .report_task_start(
                 &task_id.to_string(),
@@ -581,9 +1191,34 @@ impl<T: Transport + 'static> NineStepProcessor<T> {
             )
             .await;
 
+        // Track the task as in flight so cancel requests can reach it
+        let active = ActiveTask::begin(&self.cancellation, task_id, conversation_id);
+
         // Execute all 9 steps using pure functions where possible
-        self.execute_nine_step_algorithm(wrapper, received_topic, is_retained)
-            .await
+        let span = crate::task_span!(
+            task_id = task_id,
+            conversation_id = conversation_id,
+            agent_id = self.config.agent.id,
+            correlation_id = %correlation_id
+        );
+        let algorithm = self
+            .execute_nine_step_algorithm(wrapper, received_topic, is_retained)
+            .instrument(span);
+        let result = match self.processor_config.task_timeout {
+            Some(limit) => tokio::time::timeout(limit, algorithm)
+                .await
+                .unwrap_or_else(|_| {
+                    warn!(task_id = %task_id, timeout_secs = limit.as_secs(), "Task timed out");
+                    Err(AgentError::Timeout {
+                        message: format!("Task exceeded {}s time limit", limit.as_secs()),
+                    })
+                }),
+            None => algorithm.await,
+        };
+
+        drop(active);
+        self.progress.clear_correlation(&task_id.to_string()).await;
+        result
     }
 
     /// Execute the 9-step algorithm using composed pure functions
@@ -601,47 +1236,90 @@ impl<T: Transport + 'static> NineStepProcessor<T> {
             TaskEnvelopeWrapper::V2(env) => env.topic.clone(),
         };
 
-        // Convert to v1 for processing (v2 routing config will be extracted separately)
-        let task = wrapper.clone().to_v1();
-
-        // Steps 1-3 are pure validation functions
-        let step1 = Self::step_1_receive_message(received_topic);
-        self.report_and_handle_step(&task, &step1).await?;
-
-        let step2 = Self::step_2_check_retained(is_retained);
-        self.report_and_handle_step(&task, &step2).await?;
-
-        let step3 = Self::step_3_validate_topic(received_topic, &task_topic);
-        self.report_and_handle_step(&task, &step3).await?;
-
-        // Step 4 requires state mutation (idempotency cache)
-        let step4 = self.step_4_check_idempotency(task_id).await;
-        self.report_and_handle_step(&task, &step4).await?;
-
-        // Step 5 is pure validation
-        let step5 =
-            Self::step_5_check_pipeline_depth(&task, self.processor_config.max_pipeline_depth);
-        self.report_and_handle_step(&task, &step5).await?;
+        // Split into the v1 core and any v2 fields so v2 context survives to step 8
+        let (task, v2_fields) = wrapper.into_parts();
+
+        // Steps 1-6 decide whether the task is accepted; the producer is told either way
+        let mut timings = StepTimings::new();
+        self.enter_step(task_id, 1, "Validating task");
+        let claim = match self
+            .execute_validation_steps(
+                &task,
+                task_id,
+                received_topic,
+                &task_topic,
+                is_retained,
+                &mut timings,
+            )
+            .await
+        {
+            Ok(claim) => claim,
+            Err(e) => {
+                let error = e.to_error_message(task_id).error;
+                self.publish_ack(
+                    &task,
+                    TaskAck::rejected(task_id, &self.config.agent.id, error),
+                )
+                .await;
+                return Err(e);
+            }
+        };
+        let queue_position = self.cancellation.active_count().saturating_sub(1);
+        self.publish_ack(
+            &task,
+            TaskAck::accepted(task_id, &self.config.agent.id, queue_position),
+        )
+        .await;
 
-        // Step 6 is pure validation (envelope already parsed)
-        let step6 = Self::step_6_parse_envelope();
-        self.report_and_handle_step(&task, &step6).await?;
+        let result = self.execute_work_steps(&task, v2_fields, timings).await;
+        // A failed task's claim settles as Failed on drop, leaving it retryable
+        if result.is_ok() {
+            self.record_processed(claim).await;
+        }
+        result
+    }
 
+    /// Run steps 7-9 for a task that passed validation
+    async fn execute_work_steps(
+        &self,
+        task: &TaskEnvelope,
+        v2_fields: Option<DroppedV2Fields>,
+        mut timings: StepTimings,
+    ) -> AgentResult<ProcessingResult> {
         // Step 7 requires LLM I/O - get the response
-        let is_v2 = wrapper.is_v2();
-        let response = self.execute_task_processing(&task, is_v2).await?;
+        let is_v2 = v2_fields.is_some();
+        self.enter_step(task.task_id, 7, "Processing with LLM and tools");
+        let started = Instant::now();
+        let response = async {
+            let state = v2_fields
+                .as_ref()
+                .and_then(|fields| fields.context.as_ref())
+                .and_then(|context| context.state.as_ref());
+            let response = self.execute_task_processing(task, is_v2, state).await?;
+            run_after_llm(&self.hooks, task, response).await
+        }
+        .await
+        .map_err(|e| Self::step_failed(7, started, e))?;
         let step7 = ProcessingState {
             step: 7,
             description: "LLM and tool processing completed".to_string(),
             success: true,
             error_message: None,
         };
-        self.report_and_handle_step(&task, &step7).await?;
-
-        // Step 8 requires transport I/O for forwarding (enhanced with dynamic routing)
-        let (forwarded, routing_trace) = self
-            .step_8_enhanced_routing(&wrapper, &task, &response)
+        self.report_and_handle_step(task, &step7, started, &mut timings)
             .await?;
+        self.check_cancelled(&task.task_id)?;
+
+        // Step 8 requires transport I/O for forwarding (enhanced with dynamic routing);
+        // it never forwards once the workflow budget is spent
+        let budget_exhausted =
+            Self::workflow_budget_exhausted(v2_fields.as_ref(), self.clock.now());
+        self.enter_step(task.task_id, 8, "Routing");
+        let started = Instant::now();
+        let (forwarded, routing_trace) = self
+            .step_8_enhanced_routing(v2_fields.as_ref(), task, &response)
+            .await
+            .map_err(|e| Self::step_failed(8, started, e))?;
         let step8 = ProcessingState {
             step: 8,
             description: format!(
@@ -651,12 +1329,23 @@ impl<T: Transport + 'static> NineStepProcessor<T> {
             success: true,
             error_message: None,
         };
-        self.report_and_handle_step(&task, &step8).await?;
+        self.report_and_handle_step(task, &step8, started, &mut timings)
+            .await?;
+
+        // Once forwarded, the workflow continues downstream and can no longer be cancelled here
+        if !forwarded {
+            self.check_cancelled(&task.task_id)?;
+        }
 
         // Step 9 requires transport I/O for response publishing
         // ONLY publish to conversation if we did NOT forward to another agent
+        let started = Instant::now();
         if !forwarded {
-            self.publish_response(&task, &response).await?;
+            self.enter_step(task.task_id, 9, "Publishing response");
+            let routing_trace = v2_fields.and_then(|fields| fields.routing_trace);
+            self.publish_response(task, &response, routing_trace, budget_exhausted)
+                .await
+                .map_err(|e| Self::step_failed(9, started, e))?;
         }
         let step9 = ProcessingState {
             step: 9,
@@ -668,7 +1357,8 @@ impl<T: Transport + 'static> NineStepProcessor<T> {
             success: true,
             error_message: None,
         };
-        self.report_and_handle_step(&task, &step9).await?;
+        self.report_and_handle_step(task, &step9, started, &mut timings)
+            .await?;
 
         self.progress
             .report_task_complete(
@@ -678,6 +1368,7 @@ impl<T: Transport + 'static> NineStepProcessor<T> {
                     "9-step processing completed successfully for task {} (forwarded: {})",
                     task.task_id, forwarded
                 ),
+                Some(metadata::task_complete_metadata(&timings, forwarded)),
             )
             .await;
 
@@ -695,12 +1386,105 @@ impl<T: Transport + 'static> NineStepProcessor<T> {
         })
     }
 
+    /// Run validation steps 1-6 and the intake authorization and deadline checks
+    async fn execute_validation_steps(
+        &self,
+        task: &TaskEnvelope,
+        task_id: Uuid,
+        received_topic: &str,
+        task_topic: &str,
+        is_retained: bool,
+        timings: &mut StepTimings,
+    ) -> AgentResult<TaskClaim<'_>> {
+        // Steps 1-3 are pure validation functions
+        let started = Instant::now();
+        let step1 = Self::step_1_receive_message(received_topic);
+        self.report_and_handle_step(task, &step1, started, timings)
+            .await?;
+        self.check_cancelled(&task.task_id)?;
+
+        let started = Instant::now();
+        let step2 = Self::step_2_check_retained(is_retained);
+        self.report_and_handle_step(task, &step2, started, timings)
+            .await?;
+        self.check_cancelled(&task.task_id)?;
+
+        let started = Instant::now();
+        let step3 = Self::step_3_validate_topic(
+            received_topic,
+            task_topic,
+            self.config.protocol.case_insensitive_topics,
+        );
+        self.report_and_handle_step(task, &step3, started, timings)
+            .await?;
+        self.check_cancelled(&task.task_id)?;
+
+        // Step 4 requires state mutation (idempotency cache)
+        let started = Instant::now();
+        let (step4, claim) = self.step_4_check_idempotency(task_id).await;
+        self.report_and_handle_step(task, &step4, started, timings)
+            .await?;
+        self.check_cancelled(&task.task_id)?;
+        let claim = claim.expect("step 4 succeeded, so the task id is claimed");
+
+        // Step 5 is pure validation
+        let started = Instant::now();
+        let step5 =
+            Self::step_5_check_pipeline_depth(task, self.processor_config.max_pipeline_depth);
+        self.report_and_handle_step(task, &step5, started, timings)
+            .await?;
+        self.check_cancelled(&task.task_id)?;
+
+        // Step 6 is pure validation (envelope already parsed)
+        let started = Instant::now();
+        let step6 = Self::step_6_parse_envelope();
+        self.report_and_handle_step(task, &step6, started, timings)
+            .await?;
+        self.check_cancelled(&task.task_id)?;
+
+        // Unauthorized and expired tasks are rejected before spending any LLM time on them
+        self.authorize(task)?;
+        Self::check_deadline(task, self.clock.now())?;
+        Ok(claim)
+    }
+
+    /// Publish a task ack or nack when enabled (best effort, never fails the task)
+    async fn publish_ack(&self, task: &TaskEnvelope, ack: TaskAck) {
+        if !self.config.agent.publish_acks {
+            return;
+        }
+
+        let ack = ack.with_correlation_id(task.correlation_id.clone());
+        if let Err(e) = self
+            .transport
+            .publish_ack(&task.conversation_id, &ack)
+            .await
+        {
+            warn!(task_id = %task.task_id, error = %e, "Failed to publish task ack");
+        }
+    }
+
+    /// Record a step that failed before it could be reported, passing its error on
+    fn step_failed(step: u8, started: Instant, error: AgentError) -> AgentError {
+        metrics().step_completed(step, started.elapsed(), false);
+        error
+    }
+
     /// Report step progress and handle errors (impure logging/progress)
+    ///
+    /// Also records how long the step took since `started`, in the step
+    /// metrics and in the task's `timings`.
     async fn report_and_handle_step(
         &self,
         task: &TaskEnvelope,
         state: &ProcessingState,
+        started: Instant,
+        timings: &mut StepTimings,
     ) -> AgentResult<()> {
+        let elapsed = started.elapsed();
+        metrics().step_completed(state.step, elapsed, state.success);
+        timings.push((state.step, elapsed));
+
         self.progress
             .report_step_start(
                 &task.task_id.to_string(),
@@ -720,6 +1504,14 @@ impl<T: Transport + 'static> NineStepProcessor<T> {
                     &state.description,
                 )
                 .await;
+            self.progress
+                .report_progress_percent(
+                    &task.task_id.to_string(),
+                    &task.conversation_id,
+                    Self::step_percent(state.step),
+                    &format!("Step {} of 9 completed", state.step),
+                )
+                .await;
             Ok(())
         } else {
             warn!("Step {}: {}", state.step, state.description);
@@ -739,24 +1531,44 @@ impl<T: Transport + 'static> NineStepProcessor<T> {
         }
     }
 
+    /// Abort processing if the task has been cancelled
+    fn check_cancelled(&self, task_id: &Uuid) -> AgentResult<()> {
+        match self.cancellation.cancellation_reason(task_id) {
+            Some(reason) => {
+                info!(task_id = %task_id, reason = ?reason, "Task cancelled, aborting processing");
+                Err(AgentError::cancelled(match reason {
+                    Some(reason) => format!("Task {task_id} cancelled: {reason}"),
+                    None => format!("Task {task_id} cancelled"),
+                }))
+            }
+            None => Ok(()),
+        }
+    }
+
     /// Build available tool descriptions (pure function)
-    fn build_available_tools(&self) -> Vec<crate::tools::ToolDescription> {
-        self.tool_system
-            .list_tools()
-            .into_iter()
-            .filter_map(|tool_name| self.tool_system.describe_tool(&tool_name))
-            .collect()
+    fn build_available_tools(tool_system: &ToolSystem) -> Vec<crate::tools::ToolDescription> {
+        tool_system.describe_tools()
     }
 
     /// Build initial conversation messages (pure function)
-    fn build_initial_messages(&self, task: &TaskEnvelope) -> Vec<Message> {
+    ///
+    /// A v2 workflow's shared state, if it has any, gets its own section of
+    /// the system message.
+    fn build_initial_messages(
+        llm: &LlmSection,
+        task: &TaskEnvelope,
+        state: Option<&Map<String, Value>>,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Vec<Message> {
         // Append current date to system prompt for temporal context
-        let now = chrono::Utc::now();
         let date_info = format!(
             "\n\nCurrent date and time: {} UTC",
             now.format("%Y-%m-%d %H:%M:%S")
         );
-        let system_prompt_with_date = format!("{}{}", self.config.llm.system_prompt, date_info);
+        let mut system_prompt_with_date = format!("{}{}", llm.system_prompt, date_info);
+        if let Some(state) = state.filter(|state| !state.is_empty()) {
+            system_prompt_with_date.push_str(&Self::workflow_state_section(state));
+        }
 
         let mut messages = vec![Message {
             role: MessageRole::System,
@@ -770,29 +1582,56 @@ impl<T: Transport + 'static> NineStepProcessor<T> {
             });
         }
 
-        if !task.input.is_null() {
+        // The LLM sees the input without the reserved `_llm` overrides
+        if let Some(input) = input_without_overrides(&task.input) {
             messages.push(Message {
                 role: MessageRole::User,
-                content: format!("Input data: {}", task.input),
+                content: format!("Input data: {input}"),
             });
         }
 
         messages
     }
 
-    /// Create completion request (pure function)
+    /// System prompt section presenting the workflow state (pure function)
+    fn workflow_state_section(state: &Map<String, Value>) -> String {
+        let state = serde_json::to_string_pretty(state).unwrap_or_default();
+        format!(
+            "\n\nWorkflow state shared by the agents of this workflow:\n{state}\n\
+             To change it for the agents after you, return `{STATE_UPDATES_KEY}` in your \
+             decision: an object of keys to set, with null to remove a key."
+        )
+    }
+
+    /// Sample at temperature 0 with `seed` in deterministic mode (pure function)
+    ///
+    /// Providers without a seed parameter ignore it.
+    fn apply_deterministic_sampling(
+        mut request: CompletionRequest,
+        seed: Option<u64>,
+    ) -> CompletionRequest {
+        if seed.is_some() {
+            request.temperature = Some(0.0);
+            request.seed = seed;
+        }
+        request
+    }
+
+    /// Create completion request with task overrides merged over the config (pure function)
     /// For v2 workflows, adds structured output format for routing decisions
     fn create_completion_request(
-        &self,
+        llm: &LlmSection,
+        overrides: &LlmOverrides,
         messages: Vec<Message>,
         available_tools: &[crate::tools::ToolDescription],
     ) -> CompletionRequest {
         CompletionRequest {
             messages,
-            model: self.config.llm.model.clone(),
-            max_tokens: self.config.llm.max_tokens,
-            temperature: self.config.llm.temperature,
-            top_p: None,
+            model: overrides.model.clone().unwrap_or_else(|| llm.model.clone()),
+            max_tokens: overrides.max_tokens.or(llm.max_tokens),
+            temperature: overrides.temperature.or(llm.temperature),
+            top_p: overrides.top_p,
+            seed: None,
             stop_sequences: None,
             tools: if available_tools.is_empty() {
                 None
@@ -807,7 +1646,8 @@ impl<T: Transport + 'static> NineStepProcessor<T> {
 
     /// Create completion request with structured output for v2 routing (pure function)
     fn create_completion_request_v2(
-        &self,
+        llm: &LlmSection,
+        overrides: &LlmOverrides,
         messages: Vec<Message>,
         available_tools: &[crate::tools::ToolDescription],
     ) -> CompletionRequest {
@@ -818,10 +1658,11 @@ impl<T: Transport + 'static> NineStepProcessor<T> {
 
         CompletionRequest {
             messages,
-            model: self.config.llm.model.clone(),
-            max_tokens: self.config.llm.max_tokens,
-            temperature: self.config.llm.temperature,
-            top_p: None,
+            model: overrides.model.clone().unwrap_or_else(|| llm.model.clone()),
+            max_tokens: overrides.max_tokens.or(llm.max_tokens),
+            temperature: overrides.temperature.or(llm.temperature),
+            top_p: overrides.top_p,
+            seed: None,
             stop_sequences: None,
             tools: if available_tools.is_empty() {
                 None
@@ -846,73 +1687,97 @@ impl<T: Transport + 'static> NineStepProcessor<T> {
         request: CompletionRequest,
         task: &TaskEnvelope,
     ) -> AgentResult<CompletionResponse> {
-        let request_summary = self.format_request_summary(&request);
+        let request = Self::apply_deterministic_sampling(request, self.config.testing.llm_seed());
+        let task_id = task.task_id.to_string();
+        let model = request.model.clone();
         self.progress
-            .report_llm_request(
-                &task.task_id.to_string(),
-                &task.conversation_id,
-                &request_summary,
+            .report_custom(
+                ProgressCategory::LLM,
+                ProgressEventType::LlmRequest,
+                Some(&task_id),
+                Some(&task.conversation_id),
+                &format!("LLM request to {model}"),
+                Some(metadata::llm_request_metadata(&request)),
             )
             .await;
 
+        let started = Instant::now();
         match self.llm_provider.complete(request).await {
             Ok(response) => {
-                let response_summary = self.format_response_summary(&response);
+                let elapsed = started.elapsed();
+                let prompt_tokens = u64::from(response.usage.prompt_tokens);
+                let completion_tokens = u64::from(response.usage.completion_tokens);
+                let cost_usd = self
+                    .llm_settings()
+                    .price_for(&response.model)
+                    .map(|price| price.cost_usd(prompt_tokens, completion_tokens));
+                metrics().llm_request_completed(
+                    &response.model,
+                    prompt_tokens,
+                    completion_tokens,
+                    elapsed,
+                    cost_usd,
+                );
+                let usage = &response.usage;
+                if usage.cache_read_tokens.is_some() || usage.cache_creation_tokens.is_some() {
+                    metrics().llm_prompt_cache_used(
+                        &response.model,
+                        u64::from(usage.cache_read_tokens.unwrap_or(0)),
+                        u64::from(usage.cache_creation_tokens.unwrap_or(0)),
+                    );
+                }
                 self.progress
-                    .report_llm_response(
-                        &task.task_id.to_string(),
-                        &task.conversation_id,
-                        &response_summary,
+                    .report_custom(
+                        ProgressCategory::LLM,
+                        ProgressEventType::LlmResponse,
+                        Some(&task_id),
+                        Some(&task.conversation_id),
+                        &format!(
+                            "LLM response from {} in {}ms",
+                            response.model,
+                            elapsed.as_millis()
+                        ),
+                        Some(metadata::llm_response_metadata(&response, elapsed)),
                     )
                     .await;
                 Ok(response)
             }
             Err(e) => {
+                let error = e.to_string();
+                metrics().llm_request_failed(&model, started.elapsed());
                 self.progress
-                    .report_llm_error(
-                        &task.task_id.to_string(),
-                        &task.conversation_id,
-                        &format!("LLM request failed: {e}"),
+                    .report_custom(
+                        ProgressCategory::LLM,
+                        ProgressEventType::LlmError,
+                        Some(&task_id),
+                        Some(&task.conversation_id),
+                        &format!("LLM request failed: {error}"),
+                        Some(metadata::llm_error_metadata(
+                            &model,
+                            started.elapsed(),
+                            &error,
+                        )),
                     )
                     .await;
-                Err(AgentError::llm_error(e.to_string()))
+                Err(e.into())
             }
         }
     }
 
-    /// Format LLM request summary (pure function)
-    fn format_request_summary(&self, request: &CompletionRequest) -> String {
-        format!(
-            "LLM Request to {}: {} messages, max_tokens={:?}, temperature={:?}, tools={}",
-            request.model,
-            request.messages.len(),
-            request.max_tokens,
-            request.temperature,
-            request.tools.as_ref().map(|t| t.len()).unwrap_or(0)
-        )
-    }
-
-    /// Format LLM response summary (pure function)
-    fn format_response_summary(&self, response: &CompletionResponse) -> String {
-        format!(
-            "LLM Response: content_length={}, tool_calls={}, finish_reason={:?}, tokens_used={:?}",
-            response.content.as_ref().map(|c| c.len()).unwrap_or(0),
-            response.tool_calls.as_ref().map(|t| t.len()).unwrap_or(0),
-            response.finish_reason,
-            response.usage.total_tokens
-        )
-    }
-
     /// Execute all tool calls with progress reporting
     async fn execute_tool_calls(
         &self,
+        tool_system: &ToolSystem,
         tool_calls: &[ToolCall],
         task: &TaskEnvelope,
     ) -> Vec<String> {
         let mut tool_results = Vec::new();
 
         for tool_call in tool_calls {
-            let result = self.execute_single_tool_call(tool_call, task).await;
+            let result = self
+                .execute_single_tool_call(tool_system, tool_call, task)
+                .instrument(crate::tool_span!(tool_name = tool_call.name))
+                .await;
             tool_results.push(result);
         }
 
@@ -920,57 +1785,108 @@ impl<T: Transport + 'static> NineStepProcessor<T> {
     }
 
     /// Execute single tool call with progress reporting
-    async fn execute_single_tool_call(&self, tool_call: &ToolCall, task: &TaskEnvelope) -> String {
+    async fn execute_single_tool_call(
+        &self,
+        tool_system: &ToolSystem,
+        tool_call: &ToolCall,
+        task: &TaskEnvelope,
+    ) -> String {
         debug!(
             "Executing tool: {} with args: {}",
             tool_call.name, tool_call.arguments
         );
 
+        let task_id = task.task_id.to_string();
         self.progress
-            .report_tool_call(
-                &task.task_id.to_string(),
-                &task.conversation_id,
-                &tool_call.name,
-                &format!(
-                    "Executing tool '{}' with parameters: {}",
-                    tool_call.name, tool_call.arguments
-                ),
+            .report_custom(
+                ProgressCategory::Tool,
+                ProgressEventType::ToolCall,
+                Some(&task_id),
+                Some(&task.conversation_id),
+                &format!("Executing tool '{}'", tool_call.name),
+                Some(metadata::tool_call_metadata(
+                    &tool_call.name,
+                    &tool_call.arguments,
+                )),
             )
             .await;
 
-        match self
-            .tool_system
+        let started = Instant::now();
+        match tool_system
             .execute_tool(&tool_call.name, &tool_call.arguments)
             .await
         {
             Ok(result) => {
+                let elapsed = started.elapsed();
+                metrics().tool_executed(&tool_call.name, elapsed, true);
                 self.progress
-                    .report_tool_complete(
-                        &task.task_id.to_string(),
-                        &task.conversation_id,
-                        &tool_call.name,
+                    .report_custom(
+                        ProgressCategory::Tool,
+                        ProgressEventType::ToolComplete,
+                        Some(&task_id),
+                        Some(&task.conversation_id),
                         &format!(
-                            "Tool '{}' completed successfully. Result: {}",
-                            tool_call.name, result
+                            "Tool '{}' completed in {}ms",
+                            tool_call.name,
+                            elapsed.as_millis()
                         ),
+                        Some(metadata::tool_complete_metadata(
+                            &tool_call.name,
+                            elapsed,
+                            &result,
+                        )),
                     )
                     .await;
-                format!("Tool {} returned: {}", tool_call.name, result)
+                self.tool_output_for_llm(format!("Tool {} returned: {}", tool_call.name, result))
             }
             Err(e) => {
+                let error = e.to_string();
+                metrics().tool_executed(&tool_call.name, started.elapsed(), false);
+                event_log().record(
+                    Event::new(EventSeverity::Warning, EventCategory::Tool, "Tool failed")
+                        .with_field("tool", tool_call.name.as_str())
+                        .with_field("task_id", task_id.as_str())
+                        .with_field("conversation_id", task.conversation_id.as_str())
+                        .with_field("error", self.redactor.redact(&error).as_ref()),
+                );
                 self.progress
-                    .report_tool_error(
-                        &task.task_id.to_string(),
-                        &task.conversation_id,
-                        &tool_call.name,
-                        &format!("Tool '{}' failed with error: {}", tool_call.name, e),
+                    .report_custom(
+                        ProgressCategory::Tool,
+                        ProgressEventType::ToolError,
+                        Some(&task_id),
+                        Some(&task.conversation_id),
+                        &format!("Tool '{}' failed: {error}", tool_call.name),
+                        Some(metadata::tool_error_metadata(
+                            &tool_call.name,
+                            started.elapsed(),
+                            &error,
+                        )),
                     )
                     .await;
-                format!("Tool {} failed: {}", tool_call.name, e)
+                self.tool_output_for_llm(format!("Tool {} failed: {}", tool_call.name, e))
             }
         }
     }
 
+    /// Tool output as added to the LLM conversation: redacted when
+    /// `redact_tool_io` is set, unchanged otherwise
+    fn tool_output_for_llm(&self, output: String) -> String {
+        let redact_tool_io = self
+            .config
+            .observability
+            .redaction
+            .as_ref()
+            .is_some_and(|redaction| redaction.redact_tool_io);
+        if !redact_tool_io {
+            return output;
+        }
+        let redacted = match self.redactor.redact(&output) {
+            std::borrow::Cow::Owned(redacted) => Some(redacted),
+            std::borrow::Cow::Borrowed(_) => None,
+        };
+        redacted.unwrap_or(output)
+    }
+
     /// Add assistant response to messages (pure function)
     fn add_assistant_response(messages: &mut Vec<Message>, response: &CompletionResponse) {
         if let Some(content) = &response.content {
@@ -1001,7 +1917,7 @@ impl<T: Transport + 'static> NineStepProcessor<T> {
         _task_id: &Uuid,
     ) -> AgentResult<()> {
         if iteration > max_iterations {
-            return Err(AgentError::internal_error(format!(
+            return Err(AgentError::tool_execution_failed(format!(
                 "Tool execution exceeded maximum iterations ({max_iterations})"
             )));
         }
@@ -1014,6 +1930,20 @@ impl<T: Transport + 'static> NineStepProcessor<T> {
         response.tool_calls.is_some()
     }
 
+    /// Share of the task done once `step` of the 9 steps completes (pure function)
+    fn step_percent(step: u8) -> f32 {
+        f32::from(step.min(9)) * 100.0 / 9.0
+    }
+
+    /// Share of the task done after `iteration` tool rounds of step 7 (pure function)
+    ///
+    /// Step 7 usually dominates the task's run time, so its share is spread
+    /// over the tool rounds it may take.
+    fn tool_loop_percent(iteration: usize, max_iterations: usize) -> f32 {
+        let done = iteration.min(max_iterations) as f32 / max_iterations.max(1) as f32;
+        Self::step_percent(6) + done * (Self::step_percent(7) - Self::step_percent(6))
+    }
+
     /// Extract final content from LLM response (pure extraction)
     /// Returns the content string or empty default
     fn extract_final_content(response: &CompletionResponse) -> String {
@@ -1025,27 +1955,63 @@ impl<T: Transport + 'static> NineStepProcessor<T> {
         &self,
         task: &TaskEnvelope,
         is_v2: bool,
+        state: Option<&Map<String, Value>>,
     ) -> AgentResult<String> {
-        let available_tools = self.build_available_tools();
-        let mut messages = self.build_initial_messages(task);
+        // Settings and tools are fixed for the task even if a reload happens meanwhile
+        let llm = self.llm_settings();
+        // Invalid task overrides fail the task before any LLM call
+        let overrides = LlmOverrides::from_input(&task.input, &llm)?;
+        let tool_system = self.tool_system();
+        let available_tools = Self::build_available_tools(&tool_system);
+        let mut messages = run_before_llm(
+            &self.hooks,
+            task,
+            Self::build_initial_messages(&llm, task, state, self.clock.now()),
+        )
+        .await?;
 
-        // BUG FIX: Prevent infinite loops when LLM keeps requesting tools
-        const MAX_TOOL_ITERATIONS: usize = 10;
+        // Prevent infinite loops when the LLM keeps requesting tools
+        let max_tool_iterations = self.processor_config.max_tool_iterations;
         let mut iteration = 0;
+        // v2 agents with tools ask for the RouteDecision once the tool phase is over
+        let needs_route_decision = is_v2 && !available_tools.is_empty();
 
         loop {
             iteration += 1;
 
+            // A v2 task out of tool rounds still gets its routing decision
+            if needs_route_decision && iteration > max_tool_iterations {
+                warn!(
+                    task_id = %task.task_id,
+                    max_iterations = max_tool_iterations,
+                    "Tool rounds exhausted, requesting routing decision"
+                );
+                break;
+            }
+
             // Check iteration limit using pure function
-            Self::check_iteration_limit(iteration, MAX_TOOL_ITERATIONS, &task.task_id)?;
+            Self::check_iteration_limit(iteration, max_tool_iterations, &task.task_id)?;
+
+            // Stop between tool iterations if a cancel request arrived
+            self.check_cancelled(&task.task_id)?;
 
-            // For v2 envelopes on the final iteration (no tools pending), use structured output
+            // Without tools the first answer is final, so v2 envelopes ask for structured output
             let use_structured_output = is_v2 && available_tools.is_empty();
 
             let request = if use_structured_output {
-                self.create_completion_request_v2(messages.clone(), &available_tools)
+                Self::create_completion_request_v2(
+                    &llm,
+                    &overrides,
+                    messages.clone(),
+                    &available_tools,
+                )
             } else {
-                self.create_completion_request(messages.clone(), &available_tools)
+                Self::create_completion_request(
+                    &llm,
+                    &overrides,
+                    messages.clone(),
+                    &available_tools,
+                )
             };
 
             let response = self.execute_llm_request(request, task).await?;
@@ -1062,12 +2028,26 @@ impl<T: Transport + 'static> NineStepProcessor<T> {
                         "Processing tool calls"
                     );
 
-                    let tool_results = self.execute_tool_calls(tool_calls, task).await;
+                    let tool_results = self
+                        .execute_tool_calls(&tool_system, tool_calls, task)
+                        .await;
                     Self::add_tool_results(&mut messages, &tool_results);
+                    self.progress
+                        .report_progress_percent(
+                            &task.task_id.to_string(),
+                            &task.conversation_id,
+                            Self::tool_loop_percent(iteration, max_tool_iterations),
+                            &format!("Tool round {iteration} completed"),
+                        )
+                        .await;
                     continue;
                 }
             }
 
+            if needs_route_decision {
+                break;
+            }
+
             // Extract final content using pure function
             info!(
                 task_id = %task.task_id,
@@ -1077,6 +2057,22 @@ impl<T: Transport + 'static> NineStepProcessor<T> {
             );
             return Ok(Self::extract_final_content(&response));
         }
+
+        // Final v2 turn: no tools offered, the answer must follow the RouteDecision schema
+        self.check_cancelled(&task.task_id)?;
+        messages.push(Message {
+            role: MessageRole::User,
+            content: ROUTE_DECISION_PROMPT.to_string(),
+        });
+        let request = Self::create_completion_request_v2(&llm, &overrides, messages, &[]);
+        let response = self.execute_llm_request(request, task).await?;
+        info!(
+            task_id = %task.task_id,
+            iterations = iteration,
+            v2_structured_output = true,
+            "LLM processing completed"
+        );
+        Ok(Self::extract_final_content(&response))
     }
 
     /// Forward task to next agent in pipeline
@@ -1085,16 +2081,16 @@ impl<T: Transport + 'static> NineStepProcessor<T> {
         original_task: &TaskEnvelope,
         next_task: &crate::protocol::messages::NextTask,
         response: &str,
+        v2_fields: Option<&DroppedV2Fields>,
+        routing_step: &RoutingStep,
     ) -> AgentResult<()> {
         // Extract agent ID from the topic
-        let target_agent = self
-            .extract_agent_id_from_topic(&next_task.topic)
-            .ok_or_else(|| {
-                AgentError::internal_error(format!(
-                    "Cannot extract agent ID from topic: {}",
-                    next_task.topic
-                ))
-            })?;
+        let target_agent = agent_id_from_topic(&next_task.topic).map_err(|e| {
+            AgentError::invalid_input(format!(
+                "Cannot forward to topic '{}': {e}",
+                next_task.topic
+            ))
+        })?;
 
         // Create new task envelope for forwarding
         let forwarded_task = TaskEnvelope {
@@ -1107,14 +2103,32 @@ impl<T: Transport + 'static> NineStepProcessor<T> {
                 serde_json::Value::String(response.to_string())
             }),
             next: next_task.next.clone(),
+            deadline: original_task.deadline, // Deadline covers the whole workflow
+            correlation_id: original_task.correlation_id.clone(),
+            parent_task_id: Some(original_task.task_id),
+            published_at: None,
+            traceparent: None,
+            sender_id: Some(self.config.agent.id.clone()),
         };
 
+        // A statically routed agent may still answer with a decision
+        let state_updates = parse_agent_decision(response)
+            .ok()
+            .and_then(|decision| decision.state_updates);
+        let envelope = self.build_forwarded_envelope(
+            original_task,
+            forwarded_task,
+            v2_fields,
+            routing_step,
+            state_updates.as_ref(),
+        );
+
         // Publish to next agent's input topic using agent ID
         // (Transport layer will build the full topic path)
         self.transport
-            .publish_task(&target_agent, &forwarded_task)
+            .publish_task(&target_agent, &envelope)
             .await
-            .map_err(|e| AgentError::internal_error(format!("Failed to forward task: {e}")))?;
+            .map_err(|e| AgentError::transport_error(format!("Failed to forward task: {e}")))?;
 
         info!(
             task_id = %original_task.task_id,
@@ -1126,15 +2140,21 @@ impl<T: Transport + 'static> NineStepProcessor<T> {
     }
 
     /// Forward task to a specific agent based on agent decision
+    #[allow(clippy::too_many_arguments)]
     async fn forward_to_agent(
         &self,
         original_task: &TaskEnvelope,
         agent_id: &str,
         instruction: Option<&str>,
         result: &serde_json::Value,
+        v2_fields: Option<&DroppedV2Fields>,
+        routing_step: &RoutingStep,
+        state_updates: Option<&Map<String, Value>>,
     ) -> AgentResult<()> {
         // Construct the topic for the target agent
-        let target_topic = format!("/control/agents/{agent_id}/input");
+        let target_topic = agent_input_topic(agent_id).map_err(|e| {
+            AgentError::invalid_input(format!("Cannot forward to agent '{agent_id}': {e}"))
+        })?;
 
         // Create new task envelope for forwarding
         let forwarded_task = TaskEnvelope {
@@ -1143,14 +2163,29 @@ impl<T: Transport + 'static> NineStepProcessor<T> {
             topic: target_topic.clone(),
             instruction: instruction.map(String::from),
             input: result.clone(),
-            next: None, // Agent will decide next step
+            next: None,                       // Agent will decide next step
+            deadline: original_task.deadline, // Deadline covers the whole workflow
+            correlation_id: original_task.correlation_id.clone(),
+            parent_task_id: Some(original_task.task_id),
+            published_at: None,
+            traceparent: None,
+            sender_id: Some(self.config.agent.id.clone()),
         };
 
-        // Publish to target agent's input topic
+        let envelope = self.build_forwarded_envelope(
+            original_task,
+            forwarded_task,
+            v2_fields,
+            routing_step,
+            state_updates,
+        );
+
+        // Publish to target agent's input topic using agent ID
+        // (Transport layer will build the full topic path)
         self.transport
-            .publish_task(&forwarded_task.topic, &forwarded_task)
+            .publish_task(agent_id, &envelope)
             .await
-            .map_err(|e| AgentError::internal_error(format!("Failed to forward task: {e}")))?;
+            .map_err(|e| AgentError::transport_error(format!("Failed to forward task: {e}")))?;
 
         info!(
             task_id = %original_task.task_id,
@@ -1162,17 +2197,18 @@ impl<T: Transport + 'static> NineStepProcessor<T> {
         Ok(())
     }
 
-    /// Extract the result to publish from response string
-    /// If response contains AgentDecision JSON, extract the result field
-    /// Otherwise, return the response as-is
-    fn extract_publishable_result(response: &str) -> String {
+    /// Extract the result and artifacts to publish from response string
+    /// If response contains AgentDecision JSON, extract the result field and
+    /// its artifacts
+    /// Otherwise, return the response as-is, without artifacts
+    fn extract_publishable_result(response: &str) -> (String, Vec<Artifact>) {
         match parse_agent_decision(response) {
             Ok(decision) => {
                 debug!("Parsed AgentDecision, extracting result field");
                 // Extract just the result field
                 // If result is a string, return the string value directly
                 // Otherwise, serialize the value to JSON
-                match &decision.result {
+                let result = match &decision.result {
                     serde_json::Value::String(s) => {
                         debug!(
                             "Result is a string, returning directly (length: {})",
@@ -1184,31 +2220,81 @@ impl<T: Transport + 'static> NineStepProcessor<T> {
                         debug!("Result is not a string, serializing to JSON");
                         serde_json::to_string(other).unwrap_or_else(|_| response.to_string())
                     }
-                }
+                };
+                (result, decision.artifacts)
             }
             Err(e) => {
                 debug!("Not an AgentDecision ({}), publishing response as-is", e);
                 // Not an AgentDecision, publish the response as-is
-                response.to_string()
+                (response.to_string(), Vec::new())
             }
         }
     }
 
+    /// Mark publishable content as ended by the workflow budget - pure function
+    ///
+    /// JSON object content gains a `budget_exhausted` field; anything else is
+    /// wrapped under `output`.
+    fn annotate_budget_exhausted(content: String) -> String {
+        let output = match serde_json::from_str(&content) {
+            Ok(object @ serde_json::Value::Object(_)) => object,
+            _ => serde_json::Value::String(content),
+        };
+        annotate_budget_exhausted(output).to_string()
+    }
+
     /// Publish response to conversation topic
-    async fn publish_response(&self, task: &TaskEnvelope, response: &str) -> AgentResult<()> {
+    ///
+    /// The incoming v2.0 routing trace is attached so the full audit of the
+    /// workflow arrives with the final response.
+    async fn publish_response(
+        &self,
+        task: &TaskEnvelope,
+        response: &str,
+        routing_trace: Option<Vec<RoutingStep>>,
+        budget_exhausted: bool,
+    ) -> AgentResult<()> {
         // Extract the publishable result (strips routing metadata if present)
-        let publishable_content = Self::extract_publishable_result(response);
+        let (mut publishable_content, artifacts) = Self::extract_publishable_result(response);
+        if budget_exhausted {
+            publishable_content = Self::annotate_budget_exhausted(publishable_content);
+        }
+        let mut publishable_content =
+            run_before_publish(&self.hooks, task, publishable_content).await?;
+        if let Some(policy) = &self.moderation {
+            publishable_content = moderate(policy.as_ref(), publishable_content).await?;
+        }
 
-        let response_message = ResponseMessage {
+        let mut response_message = ResponseMessage {
             response: publishable_content,
             task_id: task.task_id,
+            routing_trace,
+            correlation_id: task.correlation_id.clone(),
+            parent_task_id: task.parent_task_id,
+            conversation_id: Some(task.conversation_id.clone()),
+            agent_id: Some(self.config.agent.id.clone()),
+            completed_at: Some(self.clock.now()),
+            oversized: None,
+            artifacts: apply_artifact_limits(
+                artifacts,
+                &self.config.agent.artifact_limits,
+                task.task_id,
+            ),
         };
+        if let Some(ref limit) = self.config.agent.response_limit {
+            apply_response_limit(
+                limit,
+                self.config.agent.state_dir.as_deref(),
+                &mut response_message,
+            )
+            .await?;
+        }
 
         // Pass just the conversation_id - transport will build the full topic
         self.transport
             .publish_response(&task.conversation_id, &response_message)
             .await
-            .map_err(|e| AgentError::internal_error(format!("Failed to publish response: {e}")))?;
+            .map_err(|e| AgentError::transport_error(format!("Failed to publish response: {e}")))?;
 
         Ok(())
     }
@@ -1245,6 +2331,12 @@ mod tests {
             instruction: None,
             input: json!({}),
             next: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
         };
         assert_eq!(
             NineStepProcessor::<MockTransport>::calculate_pipeline_depth(&simple_task),
@@ -1265,6 +2357,12 @@ mod tests {
             instruction: None,
             input: json!({}),
             next: Some(Box::new(next_task)),
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
         };
         assert_eq!(
             NineStepProcessor::<MockTransport>::calculate_pipeline_depth(&task_with_next),
@@ -1290,6 +2388,12 @@ mod tests {
             instruction: None,
             input: json!({}),
             next: Some(Box::new(nested_next)),
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
         };
         assert_eq!(
             NineStepProcessor::<MockTransport>::calculate_pipeline_depth(&nested_task),
@@ -1308,6 +2412,12 @@ mod tests {
             instruction: Some("Process this task".to_string()),
             input: json!({"test": "data"}),
             next: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
         };
 
         let result = processor
@@ -1325,6 +2435,264 @@ mod tests {
         assert!(!processing_result.forwarded);
     }
 
+    /// Redacts "secret" from the LLM input and the published response
+    struct RedactHook;
+
+    #[async_trait::async_trait]
+    impl ProcessingHook for RedactHook {
+        async fn before_llm(
+            &self,
+            _task: &TaskEnvelope,
+            messages: Vec<Message>,
+        ) -> AgentResult<Vec<Message>> {
+            Ok(messages
+                .into_iter()
+                .map(|m| Message {
+                    content: m.content.replace("secret", "[redacted]"),
+                    ..m
+                })
+                .collect())
+        }
+
+        async fn before_publish(
+            &self,
+            _task: &TaskEnvelope,
+            response: String,
+        ) -> AgentResult<String> {
+            Ok(response.replace("secret", "[redacted]"))
+        }
+    }
+
+    /// Fails every task after the LLM responds
+    struct RejectHook;
+
+    #[async_trait::async_trait]
+    impl ProcessingHook for RejectHook {
+        async fn after_llm(&self, _task: &TaskEnvelope, _response: String) -> AgentResult<String> {
+            Err(AgentError::invalid_input("response rejected by moderation"))
+        }
+    }
+
+    fn create_hooked_processor(
+        llm_provider: Arc<MockLlmProvider>,
+        hooks: Vec<Arc<dyn ProcessingHook>>,
+    ) -> NineStepProcessor<MockTransport> {
+        NineStepProcessor::new(
+            AgentConfig::test_config(),
+            llm_provider,
+            Arc::new(ToolSystem::new()),
+            Arc::new(MockTransport::new()),
+        )
+        .with_hooks(hooks)
+    }
+
+    fn hook_task(instruction: &str) -> TaskEnvelope {
+        TaskEnvelope {
+            task_id: Uuid::new_v4(),
+            conversation_id: "hooks".to_string(),
+            topic: "/control/agents/test-agent/input".to_string(),
+            instruction: Some(instruction.to_string()),
+            input: json!(null),
+            next: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hooks_rewrite_llm_input_and_published_response() {
+        let llm = Arc::new(MockLlmProvider::single_response("the secret answer"));
+        let processor = create_hooked_processor(llm.clone(), vec![Arc::new(RedactHook)]);
+
+        processor
+            .process_task(
+                TaskEnvelopeWrapper::V1(hook_task("tell me the secret")),
+                "/control/agents/test-agent/input",
+                false,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(llm.events()[0], "start:tell me the [redacted]");
+        let responses = processor.transport.get_published_responses().await;
+        assert_eq!(responses[0].1.response, "the [redacted] answer");
+    }
+
+    #[tokio::test]
+    async fn test_hook_error_fails_task_without_publishing() {
+        let llm = Arc::new(MockLlmProvider::single_response("answer"));
+        let processor = create_hooked_processor(llm, vec![Arc::new(RejectHook)]);
+
+        let error = processor
+            .process_task(
+                TaskEnvelopeWrapper::V1(hook_task("question")),
+                "/control/agents/test-agent/input",
+                false,
+            )
+            .await
+            .unwrap_err();
+
+        assert!(matches!(error, AgentError::InvalidInput { .. }));
+        assert!(processor
+            .transport
+            .get_published_responses()
+            .await
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_reloaded_llm_settings_apply_to_new_tasks() {
+        // The mock rejects any prompt containing the reloaded system prompt
+        let llm = Arc::new(MockLlmProvider::single_response("answer").failing_on("Be terse."));
+        let (config_updates, config_receiver) = watch::channel(AgentConfig::test_config());
+        let processor =
+            create_hooked_processor(llm, Vec::new()).with_config_updates(config_receiver);
+
+        let wrapper = || TaskEnvelopeWrapper::V1(hook_task("question"));
+        let topic = "/control/agents/test-agent/input";
+        assert!(processor
+            .process_task(wrapper(), topic, false)
+            .await
+            .is_ok());
+
+        let mut reloaded = AgentConfig::test_config();
+        reloaded.llm.system_prompt = "Be terse.".to_string();
+        config_updates.send_replace(reloaded);
+        assert!(processor
+            .process_task(wrapper(), topic, false)
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn test_task_overrides_take_precedence_over_config() {
+        let llm = AgentConfig::test_config().llm;
+        let overrides = LlmOverrides {
+            temperature: Some(0.0),
+            top_p: Some(0.9),
+            ..LlmOverrides::default()
+        };
+
+        for request in [
+            NineStepProcessor::<MockTransport>::create_completion_request(
+                &llm,
+                &overrides,
+                Vec::new(),
+                &[],
+            ),
+            NineStepProcessor::<MockTransport>::create_completion_request_v2(
+                &llm,
+                &overrides,
+                Vec::new(),
+                &[],
+            ),
+        ] {
+            assert_eq!(request.temperature, Some(0.0));
+            assert_eq!(request.top_p, Some(0.9));
+            // Fields without an override keep the config defaults
+            assert_eq!(request.model, llm.model);
+            assert_eq!(request.max_tokens, llm.max_tokens);
+        }
+    }
+
+    #[test]
+    fn test_deterministic_sampling_pins_temperature_and_seed() {
+        let llm = AgentConfig::test_config().llm;
+        let request = || {
+            NineStepProcessor::<MockTransport>::create_completion_request(
+                &llm,
+                &LlmOverrides {
+                    temperature: Some(0.9),
+                    ..LlmOverrides::default()
+                },
+                Vec::new(),
+                &[],
+            )
+        };
+
+        let seeded =
+            NineStepProcessor::<MockTransport>::apply_deterministic_sampling(request(), Some(7));
+        let unseeded =
+            NineStepProcessor::<MockTransport>::apply_deterministic_sampling(request(), None);
+
+        assert_eq!(seeded.temperature, Some(0.0));
+        assert_eq!(seeded.seed, Some(7));
+        assert_eq!(unseeded.temperature, Some(0.9));
+        assert_eq!(unseeded.seed, None);
+    }
+
+    #[tokio::test]
+    async fn test_disallowed_model_override_fails_before_llm_call() {
+        let llm = Arc::new(MockLlmProvider::single_response("answer"));
+        let processor = create_hooked_processor(llm.clone(), Vec::new());
+        let mut task = hook_task("question");
+        task.input = json!({"_llm": {"model": "claude-opus-4-20250514"}});
+
+        let error = processor
+            .process_task(
+                TaskEnvelopeWrapper::V1(task),
+                "/control/agents/test-agent/input",
+                false,
+            )
+            .await
+            .unwrap_err();
+
+        assert!(matches!(error, AgentError::InvalidInput { .. }));
+        assert_eq!(llm.calls(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_step_8_routing_feeds_metrics_and_audit_log() {
+        let audit_path =
+            std::env::temp_dir().join(format!("routing-audit-{}.jsonl", Uuid::new_v4()));
+        let mut processor = create_test_processor();
+        processor.set_routing_audit_log(Arc::new(RoutingAuditLog::open(&audit_path).unwrap()));
+
+        let task = TaskEnvelope {
+            task_id: Uuid::new_v4(),
+            conversation_id: "test".to_string(),
+            topic: "/control/agents/test-agent/input".to_string(),
+            instruction: Some("Process this task".to_string()),
+            input: json!({}),
+            next: Some(Box::new(NextTask {
+                topic: "/control/agents/metrics-static-agent/input".to_string(),
+                instruction: None,
+                input: None,
+                next: None,
+            })),
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
+        };
+        let (forwarded, _) = processor
+            .step_8_enhanced_routing(None, &task, "done")
+            .await
+            .unwrap();
+        assert!(forwarded);
+
+        let routing = crate::observability::metrics::metrics()
+            .get_metrics()
+            .routing;
+        assert_eq!(
+            routing.forwards_by_agent.get("metrics-static-agent"),
+            Some(&1)
+        );
+
+        let content = std::fs::read_to_string(&audit_path).unwrap();
+        std::fs::remove_file(&audit_path).unwrap();
+        let entry: RoutingAuditEntry = serde_json::from_str(content.trim()).unwrap();
+        assert_eq!(entry.task_id, task.task_id);
+        assert_eq!(entry.decision, RoutingOutcome::Forward);
+        assert_eq!(entry.next_agent.as_deref(), Some("metrics-static-agent"));
+    }
+
     #[tokio::test]
     async fn test_nine_step_retained_message_rejection() {
         let processor = create_test_processor();
@@ -1336,6 +2704,12 @@ mod tests {
             instruction: Some("Process this task".to_string()),
             input: json!({"test": "data"}),
             next: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
         };
 
         let result = processor
@@ -1365,6 +2739,12 @@ mod tests {
             instruction: Some("Process this task".to_string()),
             input: json!({"test": "data"}),
             next: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
         };
 
         // First processing should succeed
@@ -1392,6 +2772,42 @@ mod tests {
             .contains("already processed"));
     }
 
+    #[tokio::test]
+    async fn test_publish_failure_is_retryable_transport_error() {
+        let processor = NineStepProcessor::new(
+            AgentConfig::test_config(),
+            Arc::new(MockLlmProvider::single_response("test response")),
+            Arc::new(ToolSystem::new()),
+            Arc::new(MockTransport::with_failure()),
+        );
+
+        let error = processor
+            .process_task(
+                TaskEnvelopeWrapper::V1(TaskEnvelope {
+                    task_id: Uuid::new_v4(),
+                    conversation_id: "test".to_string(),
+                    topic: "/control/agents/test-agent/input".to_string(),
+                    instruction: Some("Process this task".to_string()),
+                    input: json!({}),
+                    next: None,
+                    deadline: None,
+                    correlation_id: None,
+                    parent_task_id: None,
+                    published_at: None,
+                    traceparent: None,
+                    sender_id: None,
+                }),
+                "/control/agents/test-agent/input",
+                false,
+            )
+            .await
+            .unwrap_err();
+
+        assert!(matches!(error, AgentError::TransportError(_)));
+        assert!(error.is_retryable());
+        assert!(error.to_string().contains("Failed to publish response"));
+    }
+
     #[test]
     fn test_processor_config_defaults() {
         let config = ProcessorConfig::default();
@@ -1415,6 +2831,7 @@ mod tests {
             to_agent,
             reason.clone(),
             step_number,
+            chrono::Utc::now(),
         );
 
         // Assert
@@ -1437,6 +2854,7 @@ mod tests {
             to_agent,
             reason.clone(),
             42,
+            chrono::Utc::now(),
         );
 
         assert_eq!(step.from_agent, from_agent);
@@ -1454,6 +2872,7 @@ mod tests {
                 "to",
                 "reason".to_string(),
                 step_num,
+                chrono::Utc::now(),
             );
             assert_eq!(step.step_number, step_num);
         }
@@ -1461,6 +2880,19 @@ mod tests {
 
     // ========== Tests for Task Processing Pure Functions ==========
 
+    #[test]
+    fn test_percent_grows_through_steps_and_tool_rounds() {
+        type P = NineStepProcessor<MockTransport>;
+        assert_eq!(P::step_percent(9), 100.0);
+        assert_eq!(P::tool_loop_percent(0, 10), P::step_percent(6));
+        assert_eq!(P::tool_loop_percent(10, 10), P::step_percent(7));
+
+        let mut percents: Vec<f32> = (1..=6).map(P::step_percent).collect();
+        percents.extend((1..=10).map(|i| P::tool_loop_percent(i, 10)));
+        percents.extend((7..=9).map(P::step_percent));
+        assert!(percents.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+
     #[test]
     fn test_check_iteration_limit_within_limit() {
         // Arrange
@@ -1550,6 +2982,8 @@ mod tests {
                 prompt_tokens: 10,
                 completion_tokens: 5,
                 total_tokens: 15,
+                cache_read_tokens: None,
+                cache_creation_tokens: None,
             },
             metadata: HashMap::new(),
         };
@@ -1577,6 +3011,8 @@ mod tests {
                 prompt_tokens: 10,
                 completion_tokens: 5,
                 total_tokens: 15,
+                cache_read_tokens: None,
+                cache_creation_tokens: None,
             },
             metadata: HashMap::new(),
         };
@@ -1604,6 +3040,8 @@ mod tests {
                 prompt_tokens: 10,
                 completion_tokens: 5,
                 total_tokens: 15,
+                cache_read_tokens: None,
+                cache_creation_tokens: None,
             },
             metadata: HashMap::new(),
         };
@@ -1632,6 +3070,8 @@ mod tests {
                 prompt_tokens: 10,
                 completion_tokens: 20,
                 total_tokens: 30,
+                cache_read_tokens: None,
+                cache_creation_tokens: None,
             },
             metadata: HashMap::new(),
         };
@@ -1658,6 +3098,8 @@ mod tests {
                 prompt_tokens: 10,
                 completion_tokens: 0,
                 total_tokens: 10,
+                cache_read_tokens: None,
+                cache_creation_tokens: None,
             },
             metadata: HashMap::new(),
         };
@@ -1687,6 +3129,8 @@ mod tests {
                 prompt_tokens: 10,
                 completion_tokens: 0,
                 total_tokens: 10,
+                cache_read_tokens: None,
+                cache_creation_tokens: None,
             },
             metadata: HashMap::new(),
         };
@@ -1709,10 +3153,39 @@ mod tests {
         }"#;
 
         // Act
-        let publishable = NineStepProcessor::<MockTransport>::extract_publishable_result(response);
+        let (publishable, artifacts) =
+            NineStepProcessor::<MockTransport>::extract_publishable_result(response);
 
         // Assert - should extract just the result string directly (no JSON encoding)
         assert_eq!(publishable, "This is the actual result to publish");
+        assert!(artifacts.is_empty());
+    }
+
+    #[test]
+    fn test_extract_publishable_result_with_artifacts() {
+        // Arrange - decision carrying structured outputs next to its prose result
+        let response = r#"{
+            "result": "Found two people",
+            "workflow_complete": true,
+            "artifacts": [
+                {"name": "people", "data": [{"name": "Ada"}, {"name": "Grace"}]},
+                {"name": "broken"}
+            ]
+        }"#;
+
+        // Act
+        let (publishable, artifacts) =
+            NineStepProcessor::<MockTransport>::extract_publishable_result(response);
+
+        // Assert - the prose stays clean and the malformed artifact is skipped
+        assert_eq!(publishable, "Found two people");
+        assert_eq!(artifacts.len(), 1);
+        assert_eq!(artifacts[0].name, "people");
+        assert_eq!(artifacts[0].content_type, "application/json");
+        assert_eq!(
+            artifacts[0].data,
+            json!([{"name": "Ada"}, {"name": "Grace"}])
+        );
     }
 
     #[test]
@@ -1726,7 +3199,8 @@ mod tests {
         }"#;
 
         // Act
-        let publishable = NineStepProcessor::<MockTransport>::extract_publishable_result(response);
+        let (publishable, _) =
+            NineStepProcessor::<MockTransport>::extract_publishable_result(response);
 
         // Assert - should extract just the result field as JSON
         let parsed: serde_json::Value = serde_json::from_str(&publishable).unwrap();
@@ -1743,10 +3217,12 @@ mod tests {
         let response = "This is just plain text from the LLM";
 
         // Act
-        let publishable = NineStepProcessor::<MockTransport>::extract_publishable_result(response);
+        let (publishable, artifacts) =
+            NineStepProcessor::<MockTransport>::extract_publishable_result(response);
 
         // Assert - should return the response as-is
         assert_eq!(publishable, response);
+        assert!(artifacts.is_empty());
     }
 
     #[test]
@@ -1755,10 +3231,12 @@ mod tests {
         let response = r#"{"result": "incomplete"#;
 
         // Act
-        let publishable = NineStepProcessor::<MockTransport>::extract_publishable_result(response);
+        let (publishable, artifacts) =
+            NineStepProcessor::<MockTransport>::extract_publishable_result(response);
 
         // Assert - should fall back to returning the response as-is
         assert_eq!(publishable, response);
+        assert!(artifacts.is_empty());
     }
 
     #[test]
@@ -1770,7 +3248,8 @@ mod tests {
         }"#;
 
         // Act
-        let publishable = NineStepProcessor::<MockTransport>::extract_publishable_result(response);
+        let (publishable, _) =
+            NineStepProcessor::<MockTransport>::extract_publishable_result(response);
 
         // Assert - should extract the string result directly (no JSON encoding)
         assert_eq!(publishable, "Simple string result");
@@ -1783,7 +3262,8 @@ mod tests {
         let response = "{\"schema_version\":\"1.0\",\"result\":\"# Exploring Rust\\n\\nRust has been making strides.\",\"workflow_complete\":true}";
 
         // Act
-        let publishable = NineStepProcessor::<MockTransport>::extract_publishable_result(response);
+        let (publishable, _) =
+            NineStepProcessor::<MockTransport>::extract_publishable_result(response);
 
         // Assert - should publish ONLY the article content, not the JSON structure
         assert_eq!(
@@ -1835,7 +3315,7 @@ mod workflow_routing_tests {
 
         for (response, expected, description) in test_cases {
             // Act
-            let publishable =
+            let (publishable, _) =
                 NineStepProcessor::<MockTransport>::extract_publishable_result(response);
 
             // Assert
@@ -1931,6 +3411,7 @@ mod rfc_step_tests {
             NineStepProcessor::<crate::testing::mocks::MockTransport>::step_3_validate_topic(
                 "/control/agents/test/input",
                 "/control/agents/test/input",
+                false,
             );
 
         assert!(result.success);
@@ -1944,6 +3425,7 @@ mod rfc_step_tests {
             NineStepProcessor::<crate::testing::mocks::MockTransport>::step_3_validate_topic(
                 "/control/agents/test/input",
                 "/control/agents/other/input",
+                false,
             );
 
         // Topic mismatch should fail
@@ -1960,6 +3442,7 @@ mod rfc_step_tests {
             NineStepProcessor::<crate::testing::mocks::MockTransport>::step_3_validate_topic(
                 "//control/agents/test/input/",
                 "/control/agents/test/input",
+                false,
             );
 
         // After canonicalization, these should match
@@ -1976,6 +3459,12 @@ mod rfc_step_tests {
             instruction: None,
             input: serde_json::json!({"pipeline_step": 5}),
             next: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
         };
 
         let result =
@@ -1997,6 +3486,12 @@ mod rfc_step_tests {
             instruction: None,
             input: serde_json::json!({"pipeline_step": 16}),
             next: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
         };
 
         let result =
@@ -2031,6 +3526,12 @@ mod rfc_step_tests {
             instruction: None,
             input: serde_json::json!({}),
             next: next_chain,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
         };
 
         let result =
@@ -2054,6 +3555,12 @@ mod rfc_step_tests {
             instruction: None,
             input: serde_json::json!({"pipeline_step": 0}),
             next: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
         };
 
         let result =
@@ -2084,6 +3591,7 @@ mod rfc_step_tests {
             NineStepProcessor::<crate::testing::mocks::MockTransport>::step_3_validate_topic(
                 "/control/agents/test/input/",
                 "/control/agents/test/input",
+                false,
             );
 
         // Trailing slash should be canonicalized away
@@ -2096,12 +3604,46 @@ mod rfc_step_tests {
             NineStepProcessor::<crate::testing::mocks::MockTransport>::step_3_validate_topic(
                 "//control//agents//test//input",
                 "/control/agents/test/input",
+                false,
             );
 
         // Double slashes should be canonicalized
         assert!(result.success);
     }
 
+    #[test]
+    fn test_step_3_case_folding_is_configurable() {
+        let validate =
+            NineStepProcessor::<crate::testing::mocks::MockTransport>::step_3_validate_topic;
+
+        let strict = validate(
+            "/control/agents/agent-1/input",
+            "/control/agents/Agent-1/input",
+            false,
+        );
+        assert!(!strict.success);
+        let error = strict.error_message.unwrap();
+        assert!(error.contains(
+            "'/control/agents/[a]gent-1/input' vs '/control/agents/[A]gent-1/input' \
+             (first difference at character 17); the topics differ only in case"
+        ));
+
+        let folded = validate(
+            "/control/agents/agent-1/input",
+            "/control/agents/Agent-1/input",
+            true,
+        );
+        assert!(folded.success);
+        assert!(
+            !validate(
+                "/control/agents/agent-1/input",
+                "/control/agents/agent-2/input",
+                true
+            )
+            .success
+        );
+    }
+
     #[test]
     fn test_step_1_receive_message_various_topics() {
         let topics = vec![
@@ -2153,6 +3695,12 @@ mod rfc_step_tests {
                 instruction: None,
                 input: serde_json::json!({}),
                 next: next_chain,
+                deadline: None,
+                correlation_id: None,
+                parent_task_id: None,
+                published_at: None,
+                traceparent: None,
+                sender_id: None,
             };
 
             let actual_depth = 1 + next_chain_length;
@@ -0,0 +1,199 @@
+//! Oversized response handling
+//!
+//! A response larger than the broker's maximum packet size fails to publish
+//! after all of the task's work is done, losing its result. With
+//! `[agent.response_limit]` configured, a response whose text exceeds
+//! `max_response_bytes` is cut down before it is published: either truncated
+//! with a marker, or written in full to a file the published message refers
+//! to. Either way the message's `oversized` field records the original size.
+
+use crate::config::{OversizedResponseStrategy, ResponseLimitConfig};
+use crate::error::{AgentError, AgentResult};
+use crate::protocol::messages::{OversizedResponse, ResponseMessage};
+use std::path::Path;
+use tracing::warn;
+
+/// Directory under `agent.state_dir` that full responses are written to
+pub const RESPONSE_ARTIFACT_DIR: &str = "responses";
+
+/// Fit `message` within `limit`, leaving smaller responses untouched
+///
+/// Fails if the `reference` strategy cannot write the full response, so the
+/// task reports an error rather than publishing a response nobody can read.
+pub async fn apply_response_limit(
+    limit: &ResponseLimitConfig,
+    state_dir: Option<&Path>,
+    message: &mut ResponseMessage,
+) -> AgentResult<()> {
+    let original_bytes = message.response.len();
+    if original_bytes <= limit.max_response_bytes {
+        return Ok(());
+    }
+
+    let reference = match limit.strategy {
+        OversizedResponseStrategy::Truncate => {
+            message.response = truncate_response(&message.response, limit.max_response_bytes);
+            None
+        }
+        OversizedResponseStrategy::Reference => {
+            let dir = limit.resolve_artifact_dir(state_dir).ok_or_else(|| {
+                AgentError::internal_error(
+                    "Response too large to publish and no artifact directory is configured",
+                )
+            })?;
+            let path = dir.join(format!("{}.txt", message.task_id));
+            write_artifact(&dir, &path, &message.response).await?;
+            let reference = path.display().to_string();
+            message.response =
+                format!("[Response of {original_bytes} bytes written to {reference}]");
+            Some(reference)
+        }
+    };
+
+    warn!(
+        task_id = %message.task_id,
+        original_bytes,
+        max_response_bytes = limit.max_response_bytes,
+        strategy = ?limit.strategy,
+        "Response exceeds max_response_bytes"
+    );
+    message.oversized = Some(OversizedResponse {
+        original_bytes,
+        reference,
+    });
+    Ok(())
+}
+
+/// The start of `response` and a marker, together at most `max_bytes` long
+/// Pure function extracted for testability
+fn truncate_response(response: &str, max_bytes: usize) -> String {
+    let marker = format!("\n\n[Response truncated from {} bytes]", response.len());
+    let mut end = max_bytes.saturating_sub(marker.len()).min(response.len());
+    while !response.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}{marker}", &response[..end])
+}
+
+async fn write_artifact(dir: &Path, path: &Path, response: &str) -> AgentResult<()> {
+    tokio::fs::create_dir_all(dir).await.map_err(|e| {
+        AgentError::internal_error(format!(
+            "Failed to create response directory {}: {e}",
+            dir.display()
+        ))
+    })?;
+    tokio::fs::write(path, response).await.map_err(|e| {
+        AgentError::internal_error(format!(
+            "Failed to write oversized response to {}: {e}",
+            path.display()
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn message(response: &str) -> ResponseMessage {
+        ResponseMessage {
+            response: response.to_string(),
+            task_id: Uuid::new_v4(),
+            routing_trace: None,
+            correlation_id: None,
+            parent_task_id: None,
+            conversation_id: None,
+            agent_id: None,
+            completed_at: None,
+            oversized: None,
+            artifacts: Vec::new(),
+        }
+    }
+
+    fn limit(
+        max_response_bytes: usize,
+        strategy: OversizedResponseStrategy,
+        artifact_dir: Option<&Path>,
+    ) -> ResponseLimitConfig {
+        ResponseLimitConfig {
+            max_response_bytes,
+            strategy,
+            artifact_dir: artifact_dir.map(Path::to_path_buf),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_response_within_limit_is_untouched() {
+        let mut response = message("short");
+        let limit = limit(256, OversizedResponseStrategy::Truncate, None);
+
+        apply_response_limit(&limit, None, &mut response)
+            .await
+            .unwrap();
+
+        assert_eq!(response.response, "short");
+        assert_eq!(response.oversized, None);
+    }
+
+    #[tokio::test]
+    async fn test_truncated_response_fits_and_is_marked() {
+        let text = "é".repeat(1000);
+        let mut response = message(&text);
+        let limit = limit(256, OversizedResponseStrategy::Truncate, None);
+
+        apply_response_limit(&limit, None, &mut response)
+            .await
+            .unwrap();
+
+        assert!(response.response.len() <= 256);
+        assert!(response
+            .response
+            .ends_with("[Response truncated from 2000 bytes]"));
+        assert!(text.starts_with(response.response.split("\n\n").next().unwrap()));
+        assert_eq!(
+            response.oversized,
+            Some(OversizedResponse {
+                original_bytes: 2000,
+                reference: None
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reference_writes_full_response_under_state_dir() {
+        let state_dir = tempfile::tempdir().unwrap();
+        let text = "x".repeat(1000);
+        let mut response = message(&text);
+        let limit = limit(256, OversizedResponseStrategy::Reference, None);
+
+        apply_response_limit(&limit, Some(state_dir.path()), &mut response)
+            .await
+            .unwrap();
+
+        let expected = state_dir
+            .path()
+            .join(RESPONSE_ARTIFACT_DIR)
+            .join(format!("{}.txt", response.task_id));
+        let oversized = response.oversized.clone().unwrap();
+        assert_eq!(oversized.original_bytes, 1000);
+        assert_eq!(oversized.reference, Some(expected.display().to_string()));
+        assert_eq!(std::fs::read_to_string(&expected).unwrap(), text);
+        assert!(response.response.contains(&expected.display().to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_unwritable_artifact_dir_is_an_error() {
+        let state_dir = tempfile::tempdir().unwrap();
+        let blocker = state_dir.path().join("file");
+        std::fs::write(&blocker, "not a directory").unwrap();
+        let mut response = message(&"x".repeat(1000));
+        let limit = limit(256, OversizedResponseStrategy::Reference, Some(&blocker));
+
+        let error = apply_response_limit(&limit, None, &mut response)
+            .await
+            .unwrap_err();
+
+        assert!(matches!(error, AgentError::InternalError { .. }));
+        assert_eq!(response.oversized, None);
+    }
+}
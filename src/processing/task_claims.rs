@@ -0,0 +1,164 @@
+//! In-flight task claims
+//!
+//! Step 4 reserves a task id before any work starts, so two copies of one
+//! task delivered at the same time - by concurrent workers or a fast
+//! redelivery - cannot both pass the idempotency check. A claim is Pending
+//! while its task runs and settles as Completed or Failed when released.
+//! Only completed tasks are recorded in the idempotency store; a failed
+//! task's id is freed so a retry of it is processed.
+//!
+//! A copy arriving while its task is Pending is rejected, or waits for the
+//! claim to settle, per `[processing] duplicate_in_flight`.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tokio::sync::watch;
+use uuid::Uuid;
+
+/// State of a claimed task id
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClaimState {
+    /// The task is being processed
+    Pending,
+    /// The task finished and was recorded as processed
+    Completed,
+    /// The task failed, or was abandoned, and may be processed again
+    Failed,
+}
+
+/// Task ids claimed by tasks in flight
+#[derive(Debug, Default)]
+pub struct TaskClaims {
+    pending: Mutex<HashMap<Uuid, watch::Sender<ClaimState>>>,
+}
+
+impl TaskClaims {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Claim a task id, or watch the claim already held on it
+    ///
+    /// The returned receiver sees the other claim settle.
+    pub fn try_claim(&self, task_id: Uuid) -> Result<TaskClaim<'_>, watch::Receiver<ClaimState>> {
+        let mut pending = self.pending.lock().unwrap();
+        if let Some(claim) = pending.get(&task_id) {
+            return Err(claim.subscribe());
+        }
+        pending.insert(task_id, watch::channel(ClaimState::Pending).0);
+        Ok(TaskClaim {
+            claims: self,
+            task_id,
+            outcome: ClaimState::Failed,
+        })
+    }
+
+    /// Whether a task with this id is in flight
+    pub fn contains(&self, task_id: &Uuid) -> bool {
+        self.pending.lock().unwrap().contains_key(task_id)
+    }
+
+    /// Number of task ids claimed
+    pub fn len(&self) -> usize {
+        self.pending.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn release(&self, task_id: &Uuid, outcome: ClaimState) {
+        if let Some(claim) = self.pending.lock().unwrap().remove(task_id) {
+            claim.send_replace(outcome);
+        }
+    }
+}
+
+/// Claim on a task id, held from step 4 until the task settles
+///
+/// Released on drop, including when validation fails after step 4, a task
+/// times out or panics; unless marked completed it settles as Failed.
+#[derive(Debug)]
+pub struct TaskClaim<'a> {
+    claims: &'a TaskClaims,
+    task_id: Uuid,
+    outcome: ClaimState,
+}
+
+impl TaskClaim<'_> {
+    pub fn task_id(&self) -> Uuid {
+        self.task_id
+    }
+
+    /// Release the claim, the task having been recorded as processed
+    pub fn complete(mut self) {
+        self.outcome = ClaimState::Completed;
+    }
+}
+
+impl Drop for TaskClaim<'_> {
+    fn drop(&mut self) {
+        self.claims.release(&self.task_id, self.outcome);
+    }
+}
+
+/// Wait for a claim watched through [`TaskClaims::try_claim`] to settle
+pub async fn settled(mut claim: watch::Receiver<ClaimState>) -> ClaimState {
+    let outcome = claim
+        .wait_for(|state| *state != ClaimState::Pending)
+        .await
+        .map(|state| *state);
+    // Claims settle before their sender is dropped, so the last value is final
+    outcome.unwrap_or_else(|_| *claim.borrow())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_second_claim_is_refused_while_the_first_is_held() {
+        let claims = TaskClaims::new();
+        let task_id = Uuid::new_v4();
+
+        let first = claims.try_claim(task_id).unwrap();
+        let second = claims.try_claim(task_id).unwrap_err();
+
+        assert_eq!(*second.borrow(), ClaimState::Pending);
+        assert!(claims.contains(&task_id));
+        assert!(claims.try_claim(Uuid::new_v4()).is_ok());
+        drop(first);
+        assert!(claims.is_empty());
+        assert!(claims.try_claim(task_id).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_waiters_see_the_claim_complete() {
+        let claims = TaskClaims::new();
+        let task_id = Uuid::new_v4();
+        let claim = claims.try_claim(task_id).unwrap();
+        let watched = claims.try_claim(task_id).unwrap_err();
+
+        let (state, ()) = tokio::join!(settled(watched), async {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            claim.complete();
+        });
+
+        assert_eq!(state, ClaimState::Completed);
+        assert!(!claims.contains(&task_id));
+    }
+
+    #[tokio::test]
+    async fn test_dropped_claim_settles_as_failed() {
+        let claims = TaskClaims::new();
+        let task_id = Uuid::new_v4();
+        let claim = claims.try_claim(task_id).unwrap();
+        let watched = claims.try_claim(task_id).unwrap_err();
+
+        drop(claim);
+
+        assert_eq!(settled(watched).await, ClaimState::Failed);
+        assert!(claims.try_claim(task_id).is_ok());
+    }
+}
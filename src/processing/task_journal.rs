@@ -0,0 +1,322 @@
+//! Write-Ahead Journal for Received Tasks
+//!
+//! A QoS 1 task is acknowledged to the broker as soon as the transport
+//! receives it, but the task channel into the pipeline lives in memory: a
+//! crash before the pipeline settles the task loses it. With
+//! `[agent.persistence]` configured, the transport appends every task to this
+//! journal before handing it to the pipeline, and the pipeline records its
+//! completion once the task is settled. On startup the tasks without a
+//! completion record are replayed ahead of new work, except those the
+//! idempotency store shows were already processed.
+//!
+//! The journal is an append-only JSONL file. Completion records accumulate
+//! until `compact_threshold` of them have been written; the file is then
+//! rewritten to hold only the pending tasks.
+
+use crate::protocol::messages::TaskEnvelopeWrapper;
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// File name of the journal inside the agent's state directory
+pub const TASK_JOURNAL_FILE: &str = "task_journal.jsonl";
+
+/// One line of the journal
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum JournalRecord {
+    /// A task was received
+    Enqueue { task: Box<TaskEnvelopeWrapper> },
+    /// A received task was settled
+    Complete { task_id: Uuid },
+}
+
+#[derive(Debug)]
+struct JournalState {
+    file: File,
+    /// Tasks without a completion record, in the order they were received
+    pending: Vec<TaskEnvelopeWrapper>,
+    /// Completion records written since the last compaction
+    completed_since_compaction: usize,
+}
+
+/// Append-only journal of received tasks that have not been settled
+#[derive(Debug)]
+pub struct TaskJournal {
+    path: PathBuf,
+    compact_threshold: usize,
+    sync: bool,
+    state: Mutex<JournalState>,
+    /// Tasks found pending when the journal was opened, until taken for replay
+    recovered: Mutex<Vec<TaskEnvelopeWrapper>>,
+}
+
+impl TaskJournal {
+    /// Open (or create) the journal, recovering the tasks it left pending
+    ///
+    /// A line that cannot be parsed, such as one torn by a crash mid-write,
+    /// is skipped. The recovered journal is compacted before new records are
+    /// appended.
+    pub fn open(path: &Path, compact_threshold: usize, sync: bool) -> std::io::Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let pending = match File::open(path) {
+            Ok(file) => Self::replay(path, file)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e),
+        };
+        if !pending.is_empty() {
+            info!(
+                path = %path.display(),
+                pending = pending.len(),
+                "Recovered unfinished tasks from journal"
+            );
+        }
+
+        let file = Self::rewrite(path, &pending, sync)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            compact_threshold: compact_threshold.max(1),
+            sync,
+            recovered: Mutex::new(pending.clone()),
+            state: Mutex::new(JournalState {
+                file,
+                pending,
+                completed_since_compaction: 0,
+            }),
+        })
+    }
+
+    /// Tasks received but not yet settled, oldest first
+    pub fn pending(&self) -> Vec<TaskEnvelopeWrapper> {
+        self.state.lock().unwrap().pending.clone()
+    }
+
+    /// Tasks that were pending when the journal was opened, returned once
+    ///
+    /// Tasks appended since opening are not included, as they are already on
+    /// their way to the pipeline.
+    pub fn take_recovered(&self) -> Vec<TaskEnvelopeWrapper> {
+        std::mem::take(&mut *self.recovered.lock().unwrap())
+    }
+
+    /// Number of tasks received but not yet settled
+    pub fn pending_len(&self) -> usize {
+        self.state.lock().unwrap().pending.len()
+    }
+
+    /// Record a received task before it is handed to the pipeline
+    pub fn append(&self, task: &TaskEnvelopeWrapper) -> std::io::Result<()> {
+        let mut state = self.state.lock().unwrap();
+        self.write_record(
+            &mut state.file,
+            &JournalRecord::Enqueue {
+                task: Box::new(task.clone()),
+            },
+        )?;
+        state.pending.push(task.clone());
+        Ok(())
+    }
+
+    /// Record that a task was settled, compacting the journal when due
+    ///
+    /// Completing a task the journal does not hold is a no-op.
+    pub fn complete(&self, task_id: Uuid) -> std::io::Result<()> {
+        let mut state = self.state.lock().unwrap();
+        if !state.pending.iter().any(|task| task.task_id() == task_id) {
+            return Ok(());
+        }
+
+        self.write_record(&mut state.file, &JournalRecord::Complete { task_id })?;
+        state.pending.retain(|task| task.task_id() != task_id);
+        state.completed_since_compaction += 1;
+
+        if state.completed_since_compaction >= self.compact_threshold {
+            state.file = Self::rewrite(&self.path, &state.pending, self.sync)?;
+            state.completed_since_compaction = 0;
+        }
+        Ok(())
+    }
+
+    /// [`Self::append`] on the blocking thread pool, so the file write and
+    /// sync do not stall an async caller
+    pub async fn append_async(self: &Arc<Self>, task: &TaskEnvelopeWrapper) -> std::io::Result<()> {
+        let journal = self.clone();
+        let task = task.clone();
+        tokio::task::spawn_blocking(move || journal.append(&task))
+            .await
+            .map_err(std::io::Error::other)?
+    }
+
+    /// [`Self::complete`] on the blocking thread pool, for async callers
+    pub async fn complete_async(self: &Arc<Self>, task_id: Uuid) -> std::io::Result<()> {
+        let journal = self.clone();
+        tokio::task::spawn_blocking(move || journal.complete(task_id))
+            .await
+            .map_err(std::io::Error::other)?
+    }
+
+    /// Rebuild the pending tasks from a journal file
+    fn replay(path: &Path, file: File) -> std::io::Result<Vec<TaskEnvelopeWrapper>> {
+        let mut pending: Vec<TaskEnvelopeWrapper> = Vec::new();
+        for (index, line) in BufReader::new(file).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str::<JournalRecord>(&line) {
+                Ok(JournalRecord::Enqueue { task }) => pending.push(*task),
+                Ok(JournalRecord::Complete { task_id }) => {
+                    pending.retain(|task| task.task_id() != task_id)
+                }
+                Err(e) => warn!(
+                    path = %path.display(),
+                    line = index + 1,
+                    error = %e,
+                    "Skipping unreadable task journal record"
+                ),
+            }
+        }
+        Ok(pending)
+    }
+
+    /// Atomically replace the journal with the pending tasks, returning an
+    /// append handle to the new file
+    fn rewrite(path: &Path, pending: &[TaskEnvelopeWrapper], sync: bool) -> std::io::Result<File> {
+        let temp_path = path.with_extension("compacting");
+        {
+            let mut temp = File::create(&temp_path)?;
+            for task in pending {
+                let record = JournalRecord::Enqueue {
+                    task: Box::new(task.clone()),
+                };
+                temp.write_all(&Self::encode(&record)?)?;
+            }
+            if sync {
+                temp.sync_all()?;
+            }
+        }
+        std::fs::rename(&temp_path, path)?;
+        OpenOptions::new().append(true).open(path)
+    }
+
+    fn write_record(&self, file: &mut File, record: &JournalRecord) -> std::io::Result<()> {
+        file.write_all(&Self::encode(record)?)?;
+        if self.sync {
+            file.sync_data()?;
+        }
+        Ok(())
+    }
+
+    fn encode(record: &JournalRecord) -> std::io::Result<Vec<u8>> {
+        let mut line = serde_json::to_vec(record)?;
+        line.push(b'\n');
+        Ok(line)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::messages::TaskEnvelope;
+    use serde_json::json;
+
+    fn task() -> TaskEnvelopeWrapper {
+        TaskEnvelopeWrapper::V1(TaskEnvelope {
+            task_id: Uuid::new_v4(),
+            conversation_id: "conv-1".to_string(),
+            topic: "/control/agents/test-agent/input".to_string(),
+            instruction: Some("Journal me".to_string()),
+            input: json!({"n": 1}),
+            next: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
+        })
+    }
+
+    #[test]
+    fn test_pending_tasks_survive_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(TASK_JOURNAL_FILE);
+        let (first, second, third) = (task(), task(), task());
+
+        let journal = TaskJournal::open(&path, 100, false).unwrap();
+        journal.append(&first).unwrap();
+        journal.append(&second).unwrap();
+        journal.append(&third).unwrap();
+        journal.complete(second.task_id()).unwrap();
+        drop(journal);
+
+        let journal = TaskJournal::open(&path, 100, false).unwrap();
+        journal.append(&second).unwrap();
+        assert_eq!(journal.take_recovered(), vec![first, third]);
+        assert!(journal.take_recovered().is_empty());
+        assert_eq!(journal.pending_len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_async_writes_reach_the_journal() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(TASK_JOURNAL_FILE);
+        let (first, second) = (task(), task());
+
+        let journal = Arc::new(TaskJournal::open(&path, 100, true).unwrap());
+        journal.append_async(&first).await.unwrap();
+        journal.append_async(&second).await.unwrap();
+        journal.complete_async(first.task_id()).await.unwrap();
+        drop(journal);
+
+        let journal = TaskJournal::open(&path, 100, true).unwrap();
+        assert_eq!(journal.take_recovered(), vec![second]);
+    }
+
+    #[test]
+    fn test_torn_record_is_skipped() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(TASK_JOURNAL_FILE);
+        let pending = task();
+
+        let journal = TaskJournal::open(&path, 100, false).unwrap();
+        journal.append(&pending).unwrap();
+        drop(journal);
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(br#"{"op":"enqueue","task":{"task_id""#)
+            .unwrap();
+        drop(file);
+
+        let journal = TaskJournal::open(&path, 100, false).unwrap();
+        assert_eq!(journal.pending(), vec![pending]);
+    }
+
+    #[test]
+    fn test_compaction_bounds_journal_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(TASK_JOURNAL_FILE);
+        let journal = TaskJournal::open(&path, 3, false).unwrap();
+        let pending = task();
+        journal.append(&pending).unwrap();
+
+        for _ in 0..10 {
+            let done = task();
+            journal.append(&done).unwrap();
+            journal.complete(done.task_id()).unwrap();
+        }
+        // Completing an unknown task writes nothing
+        journal.complete(Uuid::new_v4()).unwrap();
+
+        // Since the last compaction: the pending task, then one task enqueued and completed
+        let lines = std::fs::read_to_string(&path).unwrap().lines().count();
+        assert_eq!(lines, 3);
+        assert_eq!(journal.pending(), vec![pending]);
+    }
+}
@@ -0,0 +1,362 @@
+//! Progress Throttling and Batching
+//!
+//! Tool-heavy tasks emit progress events far faster than anyone watches
+//! them. Events are grouped per (task, category): the first event of a
+//! group goes out immediately, and later ones wait until `throttle_ms` has
+//! passed since the group last published. With `batch_size > 1` the waiting
+//! events are published together, early once the batch is full; otherwise
+//! only the latest waiting event is kept.
+//!
+//! Milestones (task start/complete, errors and warnings) are never delayed
+//! or coalesced: they flush their group and go out on their own. Task
+//! completion and task errors flush every group of the task, so nothing is
+//! left behind. The batcher only decides what to publish and when; the
+//! reporter does the publishing.
+
+use super::{ProgressCategory, ProgressConfig, ProgressEventType, ProgressMessage};
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Events throttled together: task id and category
+pub(crate) type BatchKey = (Option<String>, ProgressCategory);
+
+/// Delayed flush the reporter must run at `at`
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ScheduledFlush {
+    pub key: BatchKey,
+    pub generation: u64,
+    pub at: Instant,
+}
+
+/// What to do after admitting an event
+#[derive(Debug, Default)]
+pub(crate) struct Admitted {
+    /// Publishes to make now, in order; each is one MQTT message
+    pub publish: Vec<Vec<ProgressMessage>>,
+    /// Flush to schedule for events left waiting
+    pub schedule: Option<ScheduledFlush>,
+}
+
+#[derive(Debug)]
+struct Pending {
+    /// Distinguishes this batch from later ones under the same key
+    generation: u64,
+    messages: Vec<ProgressMessage>,
+    /// Events replaced by a later one while coalescing
+    coalesced: usize,
+}
+
+/// Per-(task, category) throttle and batch state
+#[derive(Debug, Default)]
+pub(crate) struct ProgressBatcher {
+    pending: HashMap<BatchKey, Pending>,
+    last_published: HashMap<BatchKey, Instant>,
+    next_generation: u64,
+}
+
+/// Events published immediately and on their own
+fn is_milestone(event_type: &ProgressEventType) -> bool {
+    matches!(
+        event_type,
+        ProgressEventType::TaskStart
+            | ProgressEventType::TaskComplete
+            | ProgressEventType::TaskError
+            | ProgressEventType::ToolError
+            | ProgressEventType::LlmError
+            | ProgressEventType::ValidationError
+            | ProgressEventType::Warning
+    )
+}
+
+impl ProgressBatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Admit an event that passed the verbosity filter
+    pub fn admit(
+        &mut self,
+        message: ProgressMessage,
+        config: &ProgressConfig,
+        now: Instant,
+    ) -> Admitted {
+        let key = (message.task_id.clone(), message.category.clone());
+
+        if is_milestone(&message.event_type) {
+            let finishes_task = matches!(
+                message.event_type,
+                ProgressEventType::TaskComplete | ProgressEventType::TaskError
+            );
+            let mut publish = if finishes_task {
+                self.finish_task(&key.0)
+            } else {
+                self.take(&key, now).into_iter().collect()
+            };
+            publish.push(vec![message]);
+            return Admitted {
+                publish,
+                schedule: None,
+            };
+        }
+
+        let throttle = Duration::from_millis(config.throttle_ms);
+        let batch_size = config.batch_size.max(1);
+        let generation = self.next_generation;
+        let pending = self.pending.entry(key.clone()).or_insert_with(|| Pending {
+            generation,
+            messages: Vec::new(),
+            coalesced: 0,
+        });
+        let is_new = pending.generation == generation && pending.messages.is_empty();
+        if is_new {
+            self.next_generation += 1;
+        }
+        if batch_size == 1 && pending.messages.pop().is_some() {
+            pending.coalesced += 1;
+        }
+        pending.messages.push(message);
+        let batch_full = batch_size > 1 && pending.messages.len() >= batch_size;
+        let generation = pending.generation;
+
+        let window_opens = self.last_published.get(&key).map(|at| *at + throttle);
+        if batch_full || window_opens.map_or(true, |at| now >= at) {
+            return Admitted {
+                publish: self.take(&key, now).into_iter().collect(),
+                schedule: None,
+            };
+        }
+
+        Admitted {
+            publish: Vec::new(),
+            // One flush per batch; later events join the one already scheduled
+            schedule: window_opens.filter(|_| is_new).map(|at| ScheduledFlush {
+                key,
+                generation,
+                at,
+            }),
+        }
+    }
+
+    /// Run a scheduled flush; empty if its batch was already published
+    pub fn flush_scheduled(
+        &mut self,
+        flush: &ScheduledFlush,
+        now: Instant,
+    ) -> Vec<ProgressMessage> {
+        match self.pending.get(&flush.key) {
+            Some(pending) if pending.generation == flush.generation => {
+                self.take(&flush.key, now).unwrap_or_default()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Take every waiting batch
+    pub fn flush_all(&mut self, now: Instant) -> Vec<Vec<ProgressMessage>> {
+        let keys: Vec<BatchKey> = self.pending.keys().cloned().collect();
+        keys.iter().filter_map(|key| self.take(key, now)).collect()
+    }
+
+    /// Take the waiting batches of a task and forget its throttle windows
+    fn finish_task(&mut self, task_id: &Option<String>) -> Vec<Vec<ProgressMessage>> {
+        let keys: Vec<BatchKey> = self
+            .pending
+            .keys()
+            .filter(|(task, _)| task == task_id)
+            .cloned()
+            .collect();
+        let batches = keys
+            .iter()
+            .filter_map(|key| self.pending.remove(key).map(finish_batch))
+            .collect();
+        self.last_published.retain(|(task, _), _| task != task_id);
+        batches
+    }
+
+    /// Take the waiting batch of a group, starting its next throttle window
+    fn take(&mut self, key: &BatchKey, now: Instant) -> Option<Vec<ProgressMessage>> {
+        let pending = self.pending.remove(key)?;
+        self.last_published.insert(key.clone(), now);
+        Some(finish_batch(pending))
+    }
+}
+
+/// Messages of a batch, noting how many events the last one replaced
+fn finish_batch(pending: Pending) -> Vec<ProgressMessage> {
+    let mut messages = pending.messages;
+    if pending.coalesced > 0 {
+        if let Some(message) = messages.last_mut() {
+            let mut metadata = match message.metadata.take() {
+                Some(serde_json::Value::Object(map)) => map,
+                _ => serde_json::Map::new(),
+            };
+            metadata.insert("coalesced_events".to_string(), pending.coalesced.into());
+            message.metadata = Some(serde_json::Value::Object(metadata));
+        }
+    }
+    messages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(task_id: &str, event_type: ProgressEventType, text: &str) -> ProgressMessage {
+        let category = match event_type {
+            ProgressEventType::ToolCall | ProgressEventType::ToolComplete => ProgressCategory::Tool,
+            _ => ProgressCategory::General,
+        };
+        ProgressMessage::new("agent".to_string(), category, event_type, text.to_string())
+            .with_task_context(Some(task_id.to_string()), Some("conv".to_string()))
+    }
+
+    fn config(throttle_ms: u64, batch_size: usize) -> ProgressConfig {
+        ProgressConfig {
+            throttle_ms,
+            batch_size,
+            ..Default::default()
+        }
+    }
+
+    fn texts(batch: &[ProgressMessage]) -> Vec<&str> {
+        batch.iter().map(|m| m.message.as_str()).collect()
+    }
+
+    #[test]
+    fn test_first_event_leads_and_rest_wait_for_window() {
+        let mut batcher = ProgressBatcher::new();
+        let config = config(100, 10);
+        let start = Instant::now();
+
+        let first = batcher.admit(event("t", ProgressEventType::ToolCall, "a"), &config, start);
+        assert_eq!(first.publish.len(), 1);
+        assert!(first.schedule.is_none());
+
+        let second = batcher.admit(event("t", ProgressEventType::ToolCall, "b"), &config, start);
+        assert!(second.publish.is_empty());
+        let flush = second.schedule.expect("waiting event needs a flush");
+        assert_eq!(flush.at, start + Duration::from_millis(100));
+
+        // Later events join the scheduled flush
+        let third = batcher.admit(event("t", ProgressEventType::ToolCall, "c"), &config, start);
+        assert!(third.publish.is_empty() && third.schedule.is_none());
+
+        let batch = batcher.flush_scheduled(&flush, flush.at);
+        assert_eq!(texts(&batch), vec!["b", "c"]);
+        assert!(batcher.flush_scheduled(&flush, flush.at).is_empty());
+    }
+
+    #[test]
+    fn test_full_batch_publishes_early() {
+        let mut batcher = ProgressBatcher::new();
+        let config = config(1_000, 2);
+        let start = Instant::now();
+        batcher.admit(event("t", ProgressEventType::ToolCall, "a"), &config, start);
+
+        let waiting = batcher.admit(event("t", ProgressEventType::ToolCall, "b"), &config, start);
+        let stale_flush = waiting.schedule.unwrap();
+        let full = batcher.admit(event("t", ProgressEventType::ToolCall, "c"), &config, start);
+        assert_eq!(full.publish.len(), 1);
+        assert_eq!(texts(&full.publish[0]), vec!["b", "c"]);
+
+        // A new batch starts; the earlier flush must not publish it
+        let next = batcher.admit(event("t", ProgressEventType::ToolCall, "d"), &config, start);
+        assert!(next.schedule.is_some());
+        assert!(batcher
+            .flush_scheduled(&stale_flush, stale_flush.at)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_single_event_batches_coalesce_to_latest() {
+        let mut batcher = ProgressBatcher::new();
+        let config = config(100, 1);
+        let start = Instant::now();
+        batcher.admit(
+            event("t", ProgressEventType::StepStart, "1"),
+            &config,
+            start,
+        );
+        let flush = batcher
+            .admit(
+                event("t", ProgressEventType::StepStart, "2"),
+                &config,
+                start,
+            )
+            .schedule
+            .unwrap();
+        batcher.admit(
+            event("t", ProgressEventType::StepStart, "3"),
+            &config,
+            start,
+        );
+
+        let batch = batcher.flush_scheduled(&flush, flush.at);
+        assert_eq!(texts(&batch), vec!["3"]);
+        assert_eq!(batch[0].metadata.as_ref().unwrap()["coalesced_events"], 1);
+    }
+
+    #[test]
+    fn test_task_completion_flushes_every_group_of_the_task() {
+        let mut batcher = ProgressBatcher::new();
+        let config = config(100, 10);
+        let start = Instant::now();
+        for text in ["s1", "s2"] {
+            batcher.admit(
+                event("t", ProgressEventType::StepStart, text),
+                &config,
+                start,
+            );
+        }
+        for text in ["tool1", "tool2"] {
+            batcher.admit(
+                event("t", ProgressEventType::ToolCall, text),
+                &config,
+                start,
+            );
+        }
+        batcher.admit(
+            event("other", ProgressEventType::StepStart, "o1"),
+            &config,
+            start,
+        );
+        batcher.admit(
+            event("other", ProgressEventType::StepStart, "o2"),
+            &config,
+            start,
+        );
+
+        let done = batcher.admit(
+            event("t", ProgressEventType::TaskComplete, "done"),
+            &config,
+            start,
+        );
+
+        let mut published: Vec<Vec<&str>> = done.publish.iter().map(|b| texts(b)).collect();
+        assert_eq!(published.pop(), Some(vec!["done"]));
+        published.sort();
+        assert_eq!(published, vec![vec!["s2"], vec!["tool2"]]);
+        // The other task's events still wait
+        assert_eq!(batcher.flush_all(start).len(), 1);
+        assert!(!batcher
+            .last_published
+            .keys()
+            .any(|(task, _)| task.as_deref() == Some("t")));
+    }
+
+    #[test]
+    fn test_zero_throttle_publishes_every_event() {
+        let mut batcher = ProgressBatcher::new();
+        let config = config(0, 10);
+        let start = Instant::now();
+        for text in ["a", "b", "c"] {
+            let admitted = batcher.admit(
+                event("t", ProgressEventType::ToolCall, text),
+                &config,
+                start,
+            );
+            assert_eq!(admitted.publish.len(), 1);
+        }
+    }
+}
@@ -0,0 +1,55 @@
+//! In-process progress feed
+//!
+//! `BroadcastProgress` puts every progress message on a tokio broadcast
+//! channel, for local consumers such as the health server's
+//! `/progress/stream` endpoint. Sending never waits: a consumer that falls
+//! more than the channel's capacity behind misses messages and is told so
+//! by the channel, instead of slowing down task processing.
+
+use super::sink::{ProgressSink, SinkProgress};
+use super::ProgressMessage;
+use tokio::sync::broadcast;
+
+/// Progress reporter publishing every event on a broadcast channel
+pub type BroadcastProgress = SinkProgress<broadcast::Sender<ProgressMessage>>;
+
+impl ProgressSink for broadcast::Sender<ProgressMessage> {
+    fn send(&self, message: ProgressMessage) {
+        // No subscribers is not an error; the event is simply not observed
+        let _ = broadcast::Sender::send(self, message);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::progress::{Progress, ProgressEventType};
+
+    #[tokio::test]
+    async fn test_reports_reach_subscribers() {
+        let (sender, mut receiver) = broadcast::channel(16);
+        let progress = BroadcastProgress::new("agent-1".to_string(), sender);
+
+        progress
+            .report_tool_call("task-1", "conv-1", "web_search", "Searching")
+            .await;
+
+        let tool_call = receiver.recv().await.unwrap();
+        assert_eq!(tool_call.event_type, ProgressEventType::ToolCall);
+        assert_eq!(tool_call.task_id.as_deref(), Some("task-1"));
+    }
+
+    #[tokio::test]
+    async fn test_reporting_without_subscribers_is_harmless() {
+        let (sender, receiver) = broadcast::channel(1);
+        drop(receiver);
+        let progress = BroadcastProgress::new("agent-1".to_string(), sender);
+
+        progress
+            .report_task_start("task-1", "conv-1", "Start")
+            .await;
+        progress
+            .report_task_complete("task-1", "conv-1", "Done", None)
+            .await;
+    }
+}
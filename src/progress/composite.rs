@@ -0,0 +1,738 @@
+//! Fan-out progress reporting
+//!
+//! `CompositeProgress` forwards every report to several sinks, so a task's
+//! progress can go to MQTT, a file and the health server's live stream at
+//! once. Each child gets its own bounded queue and worker task: reporting
+//! never waits on a child, a slow child only loses its own events once its
+//! queue is full, and a child that panics skips that event and carries on.
+//! Each child still sees its events in order.
+
+use super::{Progress, ProgressCategory, ProgressEventType};
+use async_trait::async_trait;
+use futures::FutureExt;
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tracing::{error, warn};
+
+/// Reports a child may fall behind by before its events are dropped
+pub const DEFAULT_QUEUE_CAPACITY: usize = 1024;
+
+/// Progress reporter forwarding every call to each child
+pub struct CompositeProgress {
+    children: Vec<ChildQueue>,
+}
+
+struct ChildQueue {
+    calls: mpsc::Sender<ProgressCall>,
+    dropped: AtomicU64,
+}
+
+impl CompositeProgress {
+    /// Must be called within a Tokio runtime; spawns one worker per child
+    pub fn new(children: Vec<Arc<dyn Progress>>) -> Self {
+        Self::with_queue_capacity(children, DEFAULT_QUEUE_CAPACITY)
+    }
+
+    pub fn with_queue_capacity(children: Vec<Arc<dyn Progress>>, capacity: usize) -> Self {
+        let children = children
+            .into_iter()
+            .enumerate()
+            .map(|(index, child)| {
+                let (calls, receiver) = mpsc::channel(capacity.max(1));
+                tokio::spawn(run_child(index, child, receiver));
+                ChildQueue {
+                    calls,
+                    dropped: AtomicU64::new(0),
+                }
+            })
+            .collect();
+        Self { children }
+    }
+
+    /// Events each child lost because its queue was full, in child order
+    pub fn dropped_events(&self) -> Vec<u64> {
+        self.children
+            .iter()
+            .map(|child| child.dropped.load(Ordering::Relaxed))
+            .collect()
+    }
+
+    fn dispatch(&self, call: ProgressCall) {
+        for (index, child) in self.children.iter().enumerate() {
+            if let Err(mpsc::error::TrySendError::Full(_)) = child.calls.try_send(call.clone()) {
+                let dropped = child.dropped.fetch_add(1, Ordering::Relaxed) + 1;
+                // Warn on the 1st, 2nd, 4th, 8th... drop rather than every one
+                if dropped.is_power_of_two() {
+                    warn!(
+                        child = index,
+                        dropped, "Progress sink is falling behind; dropping events"
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Make each queued report on `child` until the composite is dropped
+async fn run_child(
+    index: usize,
+    child: Arc<dyn Progress>,
+    mut calls: mpsc::Receiver<ProgressCall>,
+) {
+    while let Some(call) = calls.recv().await {
+        if AssertUnwindSafe(call.apply(child.as_ref()))
+            .catch_unwind()
+            .await
+            .is_err()
+        {
+            error!(child = index, "Progress sink panicked; event skipped");
+        }
+    }
+}
+
+/// A progress report, owned so it can wait in a child's queue
+#[derive(Clone)]
+enum ProgressCall {
+    RegisterCorrelation {
+        task_id: String,
+        correlation_id: String,
+        parent_task_id: Option<String>,
+    },
+    ClearCorrelation {
+        task_id: String,
+    },
+    TaskStart {
+        task_id: String,
+        conversation_id: String,
+        message: String,
+    },
+    TaskComplete {
+        task_id: String,
+        conversation_id: String,
+        message: String,
+        metadata: Option<serde_json::Value>,
+    },
+    TaskError {
+        task_id: Option<String>,
+        conversation_id: Option<String>,
+        message: String,
+    },
+    StepStart {
+        task_id: String,
+        conversation_id: String,
+        step: u8,
+        message: String,
+    },
+    StepComplete {
+        task_id: String,
+        conversation_id: String,
+        step: u8,
+        message: String,
+    },
+    ToolCall {
+        task_id: String,
+        conversation_id: String,
+        tool_name: String,
+        message: String,
+    },
+    ToolComplete {
+        task_id: String,
+        conversation_id: String,
+        tool_name: String,
+        message: String,
+    },
+    ToolError {
+        task_id: String,
+        conversation_id: String,
+        tool_name: String,
+        message: String,
+    },
+    LlmRequest {
+        task_id: String,
+        conversation_id: String,
+        message: String,
+    },
+    LlmResponse {
+        task_id: String,
+        conversation_id: String,
+        message: String,
+    },
+    LlmError {
+        task_id: String,
+        conversation_id: String,
+        message: String,
+    },
+    ValidationStart {
+        task_id: String,
+        conversation_id: String,
+        message: String,
+    },
+    ValidationComplete {
+        task_id: String,
+        conversation_id: String,
+        message: String,
+    },
+    ValidationError {
+        task_id: String,
+        conversation_id: String,
+        message: String,
+    },
+    Processing {
+        task_id: String,
+        conversation_id: String,
+        message: String,
+    },
+    ProgressPercent {
+        task_id: String,
+        conversation_id: String,
+        percent: f32,
+        message: String,
+    },
+    Custom {
+        category: ProgressCategory,
+        event_type: ProgressEventType,
+        task_id: Option<String>,
+        conversation_id: Option<String>,
+        message: String,
+        metadata: Option<serde_json::Value>,
+    },
+}
+
+impl ProgressCall {
+    /// Make this report on `child`
+    async fn apply(self, child: &dyn Progress) {
+        match self {
+            ProgressCall::RegisterCorrelation {
+                task_id,
+                correlation_id,
+                parent_task_id,
+            } => {
+                child
+                    .register_correlation(&task_id, &correlation_id, parent_task_id.as_deref())
+                    .await
+            }
+            ProgressCall::ClearCorrelation { task_id } => child.clear_correlation(&task_id).await,
+            ProgressCall::TaskStart {
+                task_id,
+                conversation_id,
+                message,
+            } => {
+                child
+                    .report_task_start(&task_id, &conversation_id, &message)
+                    .await
+            }
+            ProgressCall::TaskComplete {
+                task_id,
+                conversation_id,
+                message,
+                metadata,
+            } => {
+                child
+                    .report_task_complete(&task_id, &conversation_id, &message, metadata)
+                    .await
+            }
+            ProgressCall::TaskError {
+                task_id,
+                conversation_id,
+                message,
+            } => {
+                child
+                    .report_task_error(task_id.as_deref(), conversation_id.as_deref(), &message)
+                    .await
+            }
+            ProgressCall::StepStart {
+                task_id,
+                conversation_id,
+                step,
+                message,
+            } => {
+                child
+                    .report_step_start(&task_id, &conversation_id, step, &message)
+                    .await
+            }
+            ProgressCall::StepComplete {
+                task_id,
+                conversation_id,
+                step,
+                message,
+            } => {
+                child
+                    .report_step_complete(&task_id, &conversation_id, step, &message)
+                    .await
+            }
+            ProgressCall::ToolCall {
+                task_id,
+                conversation_id,
+                tool_name,
+                message,
+            } => {
+                child
+                    .report_tool_call(&task_id, &conversation_id, &tool_name, &message)
+                    .await
+            }
+            ProgressCall::ToolComplete {
+                task_id,
+                conversation_id,
+                tool_name,
+                message,
+            } => {
+                child
+                    .report_tool_complete(&task_id, &conversation_id, &tool_name, &message)
+                    .await
+            }
+            ProgressCall::ToolError {
+                task_id,
+                conversation_id,
+                tool_name,
+                message,
+            } => {
+                child
+                    .report_tool_error(&task_id, &conversation_id, &tool_name, &message)
+                    .await
+            }
+            ProgressCall::LlmRequest {
+                task_id,
+                conversation_id,
+                message,
+            } => {
+                child
+                    .report_llm_request(&task_id, &conversation_id, &message)
+                    .await
+            }
+            ProgressCall::LlmResponse {
+                task_id,
+                conversation_id,
+                message,
+            } => {
+                child
+                    .report_llm_response(&task_id, &conversation_id, &message)
+                    .await
+            }
+            ProgressCall::LlmError {
+                task_id,
+                conversation_id,
+                message,
+            } => {
+                child
+                    .report_llm_error(&task_id, &conversation_id, &message)
+                    .await
+            }
+            ProgressCall::ValidationStart {
+                task_id,
+                conversation_id,
+                message,
+            } => {
+                child
+                    .report_validation_start(&task_id, &conversation_id, &message)
+                    .await
+            }
+            ProgressCall::ValidationComplete {
+                task_id,
+                conversation_id,
+                message,
+            } => {
+                child
+                    .report_validation_complete(&task_id, &conversation_id, &message)
+                    .await
+            }
+            ProgressCall::ValidationError {
+                task_id,
+                conversation_id,
+                message,
+            } => {
+                child
+                    .report_validation_error(&task_id, &conversation_id, &message)
+                    .await
+            }
+            ProgressCall::Processing {
+                task_id,
+                conversation_id,
+                message,
+            } => {
+                child
+                    .report_processing(&task_id, &conversation_id, &message)
+                    .await
+            }
+            ProgressCall::ProgressPercent {
+                task_id,
+                conversation_id,
+                percent,
+                message,
+            } => {
+                child
+                    .report_progress_percent(&task_id, &conversation_id, percent, &message)
+                    .await
+            }
+            ProgressCall::Custom {
+                category,
+                event_type,
+                task_id,
+                conversation_id,
+                message,
+                metadata,
+            } => {
+                child
+                    .report_custom(
+                        category,
+                        event_type,
+                        task_id.as_deref(),
+                        conversation_id.as_deref(),
+                        &message,
+                        metadata,
+                    )
+                    .await
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Progress for CompositeProgress {
+    async fn register_correlation(
+        &self,
+        task_id: &str,
+        correlation_id: &str,
+        parent_task_id: Option<&str>,
+    ) {
+        self.dispatch(ProgressCall::RegisterCorrelation {
+            task_id: task_id.to_string(),
+            correlation_id: correlation_id.to_string(),
+            parent_task_id: parent_task_id.map(str::to_string),
+        });
+    }
+
+    async fn clear_correlation(&self, task_id: &str) {
+        self.dispatch(ProgressCall::ClearCorrelation {
+            task_id: task_id.to_string(),
+        });
+    }
+
+    async fn report_task_start(&self, task_id: &str, conversation_id: &str, message: &str) {
+        self.dispatch(ProgressCall::TaskStart {
+            task_id: task_id.to_string(),
+            conversation_id: conversation_id.to_string(),
+            message: message.to_string(),
+        });
+    }
+
+    async fn report_task_complete(
+        &self,
+        task_id: &str,
+        conversation_id: &str,
+        message: &str,
+        metadata: Option<serde_json::Value>,
+    ) {
+        self.dispatch(ProgressCall::TaskComplete {
+            task_id: task_id.to_string(),
+            conversation_id: conversation_id.to_string(),
+            message: message.to_string(),
+            metadata,
+        });
+    }
+
+    async fn report_task_error(
+        &self,
+        task_id: Option<&str>,
+        conversation_id: Option<&str>,
+        message: &str,
+    ) {
+        self.dispatch(ProgressCall::TaskError {
+            task_id: task_id.map(str::to_string),
+            conversation_id: conversation_id.map(str::to_string),
+            message: message.to_string(),
+        });
+    }
+
+    async fn report_step_start(
+        &self,
+        task_id: &str,
+        conversation_id: &str,
+        step: u8,
+        message: &str,
+    ) {
+        self.dispatch(ProgressCall::StepStart {
+            task_id: task_id.to_string(),
+            conversation_id: conversation_id.to_string(),
+            step,
+            message: message.to_string(),
+        });
+    }
+
+    async fn report_step_complete(
+        &self,
+        task_id: &str,
+        conversation_id: &str,
+        step: u8,
+        message: &str,
+    ) {
+        self.dispatch(ProgressCall::StepComplete {
+            task_id: task_id.to_string(),
+            conversation_id: conversation_id.to_string(),
+            step,
+            message: message.to_string(),
+        });
+    }
+
+    async fn report_tool_call(
+        &self,
+        task_id: &str,
+        conversation_id: &str,
+        tool_name: &str,
+        message: &str,
+    ) {
+        self.dispatch(ProgressCall::ToolCall {
+            task_id: task_id.to_string(),
+            conversation_id: conversation_id.to_string(),
+            tool_name: tool_name.to_string(),
+            message: message.to_string(),
+        });
+    }
+
+    async fn report_tool_complete(
+        &self,
+        task_id: &str,
+        conversation_id: &str,
+        tool_name: &str,
+        message: &str,
+    ) {
+        self.dispatch(ProgressCall::ToolComplete {
+            task_id: task_id.to_string(),
+            conversation_id: conversation_id.to_string(),
+            tool_name: tool_name.to_string(),
+            message: message.to_string(),
+        });
+    }
+
+    async fn report_tool_error(
+        &self,
+        task_id: &str,
+        conversation_id: &str,
+        tool_name: &str,
+        message: &str,
+    ) {
+        self.dispatch(ProgressCall::ToolError {
+            task_id: task_id.to_string(),
+            conversation_id: conversation_id.to_string(),
+            tool_name: tool_name.to_string(),
+            message: message.to_string(),
+        });
+    }
+
+    async fn report_llm_request(&self, task_id: &str, conversation_id: &str, message: &str) {
+        self.dispatch(ProgressCall::LlmRequest {
+            task_id: task_id.to_string(),
+            conversation_id: conversation_id.to_string(),
+            message: message.to_string(),
+        });
+    }
+
+    async fn report_llm_response(&self, task_id: &str, conversation_id: &str, message: &str) {
+        self.dispatch(ProgressCall::LlmResponse {
+            task_id: task_id.to_string(),
+            conversation_id: conversation_id.to_string(),
+            message: message.to_string(),
+        });
+    }
+
+    async fn report_llm_error(&self, task_id: &str, conversation_id: &str, message: &str) {
+        self.dispatch(ProgressCall::LlmError {
+            task_id: task_id.to_string(),
+            conversation_id: conversation_id.to_string(),
+            message: message.to_string(),
+        });
+    }
+
+    async fn report_validation_start(&self, task_id: &str, conversation_id: &str, message: &str) {
+        self.dispatch(ProgressCall::ValidationStart {
+            task_id: task_id.to_string(),
+            conversation_id: conversation_id.to_string(),
+            message: message.to_string(),
+        });
+    }
+
+    async fn report_validation_complete(
+        &self,
+        task_id: &str,
+        conversation_id: &str,
+        message: &str,
+    ) {
+        self.dispatch(ProgressCall::ValidationComplete {
+            task_id: task_id.to_string(),
+            conversation_id: conversation_id.to_string(),
+            message: message.to_string(),
+        });
+    }
+
+    async fn report_validation_error(&self, task_id: &str, conversation_id: &str, message: &str) {
+        self.dispatch(ProgressCall::ValidationError {
+            task_id: task_id.to_string(),
+            conversation_id: conversation_id.to_string(),
+            message: message.to_string(),
+        });
+    }
+
+    async fn report_processing(&self, task_id: &str, conversation_id: &str, message: &str) {
+        self.dispatch(ProgressCall::Processing {
+            task_id: task_id.to_string(),
+            conversation_id: conversation_id.to_string(),
+            message: message.to_string(),
+        });
+    }
+
+    async fn report_progress_percent(
+        &self,
+        task_id: &str,
+        conversation_id: &str,
+        percent: f32,
+        message: &str,
+    ) {
+        self.dispatch(ProgressCall::ProgressPercent {
+            task_id: task_id.to_string(),
+            conversation_id: conversation_id.to_string(),
+            percent,
+            message: message.to_string(),
+        });
+    }
+
+    async fn report_custom(
+        &self,
+        category: ProgressCategory,
+        event_type: ProgressEventType,
+        task_id: Option<&str>,
+        conversation_id: Option<&str>,
+        message: &str,
+        metadata: Option<serde_json::Value>,
+    ) {
+        self.dispatch(ProgressCall::Custom {
+            category,
+            event_type,
+            task_id: task_id.map(str::to_string),
+            conversation_id: conversation_id.map(str::to_string),
+            message: message.to_string(),
+            metadata,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::progress::sink::{ProgressSink, SinkProgress};
+    use crate::progress::ProgressMessage;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    /// Sink forwarding messages to a channel, panicking on "boom"
+    struct Forward(mpsc::UnboundedSender<ProgressMessage>);
+
+    impl ProgressSink for Forward {
+        fn send(&self, message: ProgressMessage) {
+            assert_ne!(message.message, "boom", "sink failure");
+            let _ = self.0.send(message);
+        }
+    }
+
+    /// Sink that never returns until the gate is dropped
+    struct Blocked(Mutex<std::sync::mpsc::Receiver<()>>);
+
+    impl ProgressSink for Blocked {
+        fn send(&self, _message: ProgressMessage) {
+            let _ = self.0.lock().unwrap().recv();
+        }
+    }
+
+    fn sink(agent_id: &str) -> (Arc<dyn Progress>, mpsc::UnboundedReceiver<ProgressMessage>) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        (
+            Arc::new(SinkProgress::new(agent_id.to_string(), Forward(sender))),
+            receiver,
+        )
+    }
+
+    async fn next(events: &mut mpsc::UnboundedReceiver<ProgressMessage>) -> ProgressMessage {
+        tokio::time::timeout(Duration::from_secs(5), events.recv())
+            .await
+            .expect("event should arrive")
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_every_child_receives_every_report() {
+        let (first, mut first_events) = sink("first");
+        let (second, mut second_events) = sink("second");
+        let composite = CompositeProgress::new(vec![first, second]);
+
+        composite
+            .register_correlation("task-1", "corr-1", None)
+            .await;
+        composite
+            .report_task_start("task-1", "conv-1", "Start")
+            .await;
+        composite
+            .report_custom(
+                ProgressCategory::General,
+                ProgressEventType::Warning,
+                Some("task-1"),
+                Some("conv-1"),
+                "Careful",
+                None,
+            )
+            .await;
+
+        for events in [&mut first_events, &mut second_events] {
+            let start = next(events).await;
+            assert_eq!(start.event_type, ProgressEventType::TaskStart);
+            assert_eq!(start.correlation_id.as_deref(), Some("corr-1"));
+            assert_eq!(next(events).await.event_type, ProgressEventType::Warning);
+        }
+        assert_eq!(composite.dropped_events(), vec![0, 0]);
+    }
+
+    #[tokio::test]
+    async fn test_panicking_child_keeps_receiving_later_reports() {
+        let (flaky, mut events) = sink("flaky");
+        let composite = CompositeProgress::new(vec![flaky]);
+
+        composite
+            .report_processing("task-1", "conv-1", "boom")
+            .await;
+        composite
+            .report_processing("task-1", "conv-1", "after")
+            .await;
+
+        assert_eq!(next(&mut events).await.message, "after");
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_blocked_child_does_not_stall_the_others() {
+        let (gate, gate_receiver) = std::sync::mpsc::channel();
+        let blocked: Arc<dyn Progress> = Arc::new(SinkProgress::new(
+            "blocked".to_string(),
+            Blocked(Mutex::new(gate_receiver)),
+        ));
+        let (healthy, mut healthy_events) = sink("healthy");
+        let composite = CompositeProgress::with_queue_capacity(vec![blocked, healthy], 4);
+
+        for i in 0..10 {
+            // Reporting returns at once even though one child never finishes
+            tokio::time::timeout(
+                Duration::from_secs(1),
+                composite.report_processing("task-1", "conv-1", &format!("event-{i}")),
+            )
+            .await
+            .expect("reporting must not wait on a blocked child");
+            assert_eq!(
+                next(&mut healthy_events).await.message,
+                format!("event-{i}")
+            );
+        }
+
+        // One event in flight and four queued; the rest were dropped
+        let dropped = composite.dropped_events();
+        assert!((5..=6).contains(&dropped[0]), "dropped: {dropped:?}");
+        assert_eq!(dropped[1], 0);
+        drop(gate);
+    }
+}
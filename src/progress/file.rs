@@ -0,0 +1,107 @@
+//! JSONL progress log
+//!
+//! `FileProgress` appends every progress message to a file, one JSON object
+//! per line. When the next line would take the file past `max_bytes`, the
+//! file is rotated: `progress.jsonl` becomes `progress.jsonl.1`, older files
+//! move up by one, and only `max_files` rotated files are kept (see
+//! [`RotatingFile`]).
+
+use super::sink::{ProgressSink, SinkProgress};
+use super::ProgressMessage;
+use crate::config::FileSinkConfig;
+use crate::observability::rotating_file::RotatingFile;
+use std::io;
+use tracing::warn;
+
+/// Progress reporter appending every event to a rotating JSONL file
+pub type FileProgress = SinkProgress<RotatingFile>;
+
+impl FileProgress {
+    /// Open (or create) the progress file described by `config`
+    pub fn open(agent_id: String, config: &FileSinkConfig) -> io::Result<Self> {
+        Ok(Self::new(
+            agent_id,
+            RotatingFile::open(&config.path, config.max_bytes, config.max_files)?,
+        ))
+    }
+}
+
+impl ProgressSink for RotatingFile {
+    fn send(&self, message: ProgressMessage) {
+        let appended = serde_json::to_vec(&message)
+            .map_err(std::io::Error::from)
+            .and_then(|mut line| {
+                line.push(b'\n');
+                self.append_line(&line)
+            });
+        if let Err(e) = appended {
+            warn!(
+                path = %self.path().display(),
+                error = %e,
+                "Failed to write progress event"
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::progress::{ProgressCategory, ProgressEventType};
+    use std::path::{Path, PathBuf};
+
+    fn message(text: &str) -> ProgressMessage {
+        ProgressMessage::new(
+            "agent-1".to_string(),
+            ProgressCategory::General,
+            ProgressEventType::Processing,
+            text.to_string(),
+        )
+    }
+
+    fn lines(path: &Path) -> Vec<ProgressMessage> {
+        std::fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_appends_one_json_object_per_line() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("logs/progress.jsonl");
+        let file = RotatingFile::open(&path, 1 << 20, 3).unwrap();
+
+        file.send(message("first"));
+        file.send(message("second"));
+        // Reopening appends instead of truncating
+        RotatingFile::open(&path, 1 << 20, 3)
+            .unwrap()
+            .send(message("third"));
+
+        let texts: Vec<String> = lines(&path).into_iter().map(|m| m.message).collect();
+        assert_eq!(texts, vec!["first", "second", "third"]);
+    }
+
+    #[test]
+    fn test_rotates_by_size_and_keeps_max_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("progress.jsonl");
+        let line_len = serde_json::to_vec(&message("event-0")).unwrap().len() as u64 + 1;
+        // Two lines fit in a file
+        let file = RotatingFile::open(&path, line_len * 2, 2).unwrap();
+
+        for i in 0..7 {
+            file.send(message(&format!("event-{i}")));
+        }
+
+        let texts = |path: PathBuf| -> Vec<String> {
+            lines(&path).into_iter().map(|m| m.message).collect()
+        };
+        assert_eq!(texts(path.clone()), vec!["event-6"]);
+        assert_eq!(texts(file.rotated(1)), vec!["event-4", "event-5"]);
+        assert_eq!(texts(file.rotated(2)), vec!["event-2", "event-3"]);
+        assert!(!file.rotated(3).exists());
+    }
+}
@@ -0,0 +1,59 @@
+//! Progress in the agent's own log
+//!
+//! `LogProgress` writes each progress event as a structured tracing event
+//! under the `agent2389::progress` target, so progress can be followed
+//! without an MQTT client. Error events are logged as warnings.
+
+use super::sink::{ProgressSink, SinkProgress};
+use super::{ProgressEventType, ProgressMessage};
+use tracing::{info, warn};
+
+/// Progress reporter logging every event through `tracing`
+pub type LogProgress = SinkProgress<TracingSink>;
+
+impl LogProgress {
+    pub fn logging(agent_id: String) -> Self {
+        Self::new(agent_id, TracingSink)
+    }
+}
+
+/// Sink writing progress messages to the log
+pub struct TracingSink;
+
+impl ProgressSink for TracingSink {
+    fn send(&self, message: ProgressMessage) {
+        let event_type = format!("{:?}", message.event_type);
+        let task_id = message.task_id.as_deref().unwrap_or("-");
+        let conversation_id = message.conversation_id.as_deref().unwrap_or("-");
+        let is_error = matches!(
+            message.event_type,
+            ProgressEventType::TaskError
+                | ProgressEventType::ToolError
+                | ProgressEventType::LlmError
+                | ProgressEventType::ValidationError
+                | ProgressEventType::Warning
+        );
+        if is_error {
+            warn!(
+                target: "agent2389::progress",
+                agent_id = %message.agent_id,
+                task_id,
+                conversation_id,
+                event_type,
+                "{}",
+                message.message
+            );
+        } else {
+            info!(
+                target: "agent2389::progress",
+                agent_id = %message.agent_id,
+                task_id,
+                conversation_id,
+                event_type,
+                percent = message.percent,
+                "{}",
+                message.message
+            );
+        }
+    }
+}
@@ -0,0 +1,227 @@
+//! Structured metadata for task, tool and LLM progress events
+//!
+//! Progress messages stay short and human-readable; the details a client
+//! may want to parse (durations, sizes, token usage) go in `metadata`.
+//! Tool results are never sent whole, only a preview of at most
+//! `RESULT_PREVIEW_CHARS` characters. The reporter caps the serialized
+//! metadata at `ProgressConfig::max_metadata_bytes` (see `cap_metadata`).
+
+use crate::llm::provider::{CompletionRequest, CompletionResponse};
+use serde_json::{json, Map, Value};
+use std::time::Duration;
+
+/// Characters of a tool result included as its preview
+pub const RESULT_PREVIEW_CHARS: usize = 200;
+
+/// Metadata of a completed task, with how long each step it ran took
+pub fn task_complete_metadata(steps: &[(u8, Duration)], forwarded: bool) -> Value {
+    let steps: Vec<Value> = steps
+        .iter()
+        .map(|(step, duration)| {
+            json!({
+                "step": step,
+                "duration_ms": duration.as_millis() as u64,
+            })
+        })
+        .collect();
+    json!({
+        "forwarded": forwarded,
+        "steps": steps,
+    })
+}
+
+/// Metadata of a tool call about to run
+pub fn tool_call_metadata(tool_name: &str, arguments: &Value) -> Value {
+    json!({
+        "tool_name": tool_name,
+        "arguments_size_bytes": arguments.to_string().len(),
+    })
+}
+
+/// Metadata of a finished tool call, with a preview of its result
+pub fn tool_complete_metadata(tool_name: &str, duration: Duration, result: &Value) -> Value {
+    let result = result.to_string();
+    let (preview, truncated) = preview(&result, RESULT_PREVIEW_CHARS);
+    json!({
+        "tool_name": tool_name,
+        "duration_ms": duration.as_millis() as u64,
+        "result_size_bytes": result.len(),
+        "result_preview": preview,
+        "result_truncated": truncated,
+    })
+}
+
+/// Metadata of a failed tool call
+pub fn tool_error_metadata(tool_name: &str, duration: Duration, error: &str) -> Value {
+    json!({
+        "tool_name": tool_name,
+        "duration_ms": duration.as_millis() as u64,
+        "error": error,
+    })
+}
+
+/// Metadata of an LLM request about to be sent
+pub fn llm_request_metadata(request: &CompletionRequest) -> Value {
+    json!({
+        "model": request.model,
+        "message_count": request.messages.len(),
+        "tool_count": request.tools.as_ref().map_or(0, Vec::len),
+        "max_tokens": request.max_tokens,
+        "temperature": request.temperature,
+    })
+}
+
+/// Metadata of an LLM response, with its token usage
+pub fn llm_response_metadata(response: &CompletionResponse, duration: Duration) -> Value {
+    json!({
+        "model": response.model,
+        "duration_ms": duration.as_millis() as u64,
+        "finish_reason": response.finish_reason,
+        "prompt_tokens": response.usage.prompt_tokens,
+        "completion_tokens": response.usage.completion_tokens,
+        "total_tokens": response.usage.total_tokens,
+        "tool_calls": response.tool_calls.as_ref().map_or(0, Vec::len),
+        "content_length": response.content.as_ref().map_or(0, String::len),
+    })
+}
+
+/// Metadata of a failed LLM request
+pub fn llm_error_metadata(model: &str, duration: Duration, error: &str) -> Value {
+    json!({
+        "model": model,
+        "duration_ms": duration.as_millis() as u64,
+        "error": error,
+    })
+}
+
+/// The first `max_chars` characters of `text`, and whether any were cut
+fn preview(text: &str, max_chars: usize) -> (&str, bool) {
+    match text.char_indices().nth(max_chars) {
+        Some((end, _)) => (&text[..end], true),
+        None => (text, false),
+    }
+}
+
+/// Fit metadata into `max_bytes` of JSON (pure function)
+///
+/// Metadata that fits is returned unchanged. Otherwise the smallest fields
+/// of an object are kept while they fit, and `metadata_truncated` is set,
+/// so short fields such as `tool_name` survive a huge preview or error.
+/// Metadata that is not an object and does not fit is dropped.
+pub fn cap_metadata(metadata: Value, max_bytes: usize) -> Option<Value> {
+    if serialized_len(&metadata) <= max_bytes {
+        return Some(metadata);
+    }
+    let Value::Object(fields) = metadata else {
+        return None;
+    };
+
+    let mut fields: Vec<(String, Value, usize)> = fields
+        .into_iter()
+        .map(|(key, value)| {
+            let len = serialized_len(&json!({ &key: &value }));
+            (key, value, len)
+        })
+        .collect();
+    fields.sort_by(|a, b| a.2.cmp(&b.2).then_with(|| a.0.cmp(&b.0)));
+
+    let mut capped = Map::new();
+    capped.insert("metadata_truncated".to_string(), Value::Bool(true));
+    if serialized_len(&Value::Object(capped.clone())) > max_bytes {
+        return None;
+    }
+    for (key, value, _) in fields {
+        capped.insert(key.clone(), value);
+        if serialized_len(&Value::Object(capped.clone())) > max_bytes {
+            capped.remove(&key);
+        }
+    }
+    Some(Value::Object(capped))
+}
+
+fn serialized_len(value: &Value) -> usize {
+    serde_json::to_vec(value).map_or(usize::MAX, |bytes| bytes.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::provider::{FinishReason, TokenUsage};
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_tool_complete_metadata_previews_result() {
+        let result = json!({ "body": "x".repeat(1000) });
+        let metadata = tool_complete_metadata("http_request", Duration::from_millis(42), &result);
+
+        assert_eq!(metadata["tool_name"], "http_request");
+        assert_eq!(metadata["duration_ms"], 42);
+        assert_eq!(metadata["result_size_bytes"], result.to_string().len());
+        assert_eq!(
+            metadata["result_preview"].as_str().unwrap().chars().count(),
+            RESULT_PREVIEW_CHARS
+        );
+        assert_eq!(metadata["result_truncated"], true);
+
+        let small = tool_complete_metadata("echo", Duration::ZERO, &json!("hi"));
+        assert_eq!(small["result_preview"], "\"hi\"");
+        assert_eq!(small["result_truncated"], false);
+    }
+
+    #[test]
+    fn test_preview_cuts_on_char_boundaries() {
+        assert_eq!(preview("héllo wörld", 4), ("héll", true));
+        assert_eq!(preview("héllo", 5), ("héllo", false));
+    }
+
+    #[test]
+    fn test_llm_response_metadata() {
+        let response = CompletionResponse {
+            content: Some("Hello".to_string()),
+            model: "gpt-4".to_string(),
+            usage: TokenUsage {
+                prompt_tokens: 10,
+                completion_tokens: 5,
+                total_tokens: 15,
+                cache_read_tokens: None,
+                cache_creation_tokens: None,
+            },
+            finish_reason: FinishReason::Stop,
+            tool_calls: None,
+            metadata: HashMap::new(),
+        };
+
+        let metadata = llm_response_metadata(&response, Duration::from_millis(300));
+
+        assert_eq!(
+            metadata,
+            json!({
+                "model": "gpt-4",
+                "duration_ms": 300,
+                "finish_reason": "Stop",
+                "prompt_tokens": 10,
+                "completion_tokens": 5,
+                "total_tokens": 15,
+                "tool_calls": 0,
+                "content_length": 5,
+            })
+        );
+    }
+
+    #[test]
+    fn test_cap_keeps_small_fields_of_large_metadata() {
+        let metadata =
+            tool_error_metadata("web_search", Duration::from_millis(7), &"e".repeat(500));
+        assert_eq!(cap_metadata(metadata.clone(), 4096), Some(metadata.clone()));
+
+        let capped = cap_metadata(metadata, 100).unwrap();
+        assert!(serde_json::to_vec(&capped).unwrap().len() <= 100);
+        assert_eq!(capped["tool_name"], "web_search");
+        assert_eq!(capped["duration_ms"], 7);
+        assert_eq!(capped["metadata_truncated"], true);
+        assert!(capped.get("error").is_none());
+
+        assert_eq!(cap_metadata(json!({"a": "a long value"}), 10), None);
+        assert_eq!(cap_metadata(json!("a long string"), 5), None);
+    }
+}
@@ -1,11 +1,31 @@
+use crate::transport::mqtt::TopicBuilder;
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+mod batcher;
+pub mod broadcast;
+pub mod composite;
+pub mod file;
+pub mod log;
+pub mod metadata;
 pub mod mqtt_reporter;
+pub mod redacting;
+pub mod sink;
+pub mod subscriber;
+pub mod webhook;
+pub use broadcast::BroadcastProgress;
+pub use composite::CompositeProgress;
+pub use file::FileProgress;
+pub use log::LogProgress;
 pub use mqtt_reporter::MqttProgressReporter;
+pub use redacting::RedactingProgress;
+pub use sink::{ProgressSink, SinkProgress};
+pub use subscriber::ProgressSubscriber;
+pub use webhook::{NotificationSeverity, WebhookProgress};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct ProgressMessage {
     pub agent_id: String,
     pub task_id: Option<String>,
@@ -15,16 +35,28 @@ pub struct ProgressMessage {
     pub event_type: ProgressEventType,
     pub message: String,
     pub metadata: Option<serde_json::Value>,
+    /// Correlation id of the workflow the task belongs to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<String>,
+    /// Parent of the task, if it was forwarded from another task
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_task_id: Option<String>,
+    /// Estimated task completion, 0-100
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub percent: Option<f32>,
+    /// Estimated seconds until the task completes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub eta_seconds: Option<u64>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq, Eq, Hash)]
 pub enum ProgressCategory {
     General,
     Tool,
     LLM,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub enum ProgressEventType {
     TaskStart,
     TaskComplete,
@@ -41,6 +73,10 @@ pub enum ProgressEventType {
     ValidationComplete,
     ValidationError,
     Processing,
+    /// Non-fatal problem worth surfacing, e.g. a detected workflow cycle
+    Warning,
+    /// Completion estimate carrying `percent` and `eta_seconds`
+    PercentComplete,
     Custom,
 }
 
@@ -60,6 +96,10 @@ impl ProgressMessage {
             event_type,
             message,
             metadata: None,
+            correlation_id: None,
+            parent_task_id: None,
+            percent: None,
+            eta_seconds: None,
         }
     }
 
@@ -73,6 +113,22 @@ impl ProgressMessage {
         self
     }
 
+    pub fn with_correlation(
+        mut self,
+        correlation_id: Option<String>,
+        parent_task_id: Option<String>,
+    ) -> Self {
+        self.correlation_id = correlation_id;
+        self.parent_task_id = parent_task_id;
+        self
+    }
+
+    pub fn with_percent(mut self, percent: f32, eta_seconds: Option<u64>) -> Self {
+        self.percent = Some(percent);
+        self.eta_seconds = eta_seconds;
+        self
+    }
+
     pub fn with_metadata(mut self, metadata: serde_json::Value) -> Self {
         self.metadata = Some(metadata);
         self
@@ -85,15 +141,37 @@ impl ProgressMessage {
             ProgressCategory::LLM => format!("/control/agents/{}/progress/llm", self.agent_id),
         }
     }
+
+    /// Progress topic of the message's conversation, if it has one
+    pub fn conversation_topic(&self) -> Option<String> {
+        self.conversation_id
+            .as_deref()
+            .map(TopicBuilder::build_conversation_progress_topic)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProgressConfig {
     pub enabled: bool,
     pub verbosity: ProgressVerbosity,
+    /// Minimum time between publishes for one task and category; events in
+    /// between wait and are coalesced or batched
     pub throttle_ms: u64,
+    /// Events published together as a JSON array; 1 keeps only the latest
+    /// waiting event instead
     pub batch_size: usize,
     pub categories: Vec<ProgressCategory>,
+    /// Also publish each event on `/conversations/{conversation_id}/progress`
+    #[serde(default)]
+    pub conversation_topics: bool,
+    /// Largest serialized `metadata` of one event; larger metadata keeps its
+    /// smallest fields (see `metadata::cap_metadata`)
+    #[serde(default = "default_max_metadata_bytes")]
+    pub max_metadata_bytes: usize,
+}
+
+fn default_max_metadata_bytes() -> usize {
+    4096
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -103,6 +181,38 @@ pub enum ProgressVerbosity {
     Verbose,
 }
 
+impl ProgressVerbosity {
+    /// Whether events of this type are reported at this verbosity
+    ///
+    /// Minimal reports task start/complete and errors, Normal adds steps and
+    /// tool calls, Verbose reports everything.
+    pub fn includes(&self, event_type: &ProgressEventType) -> bool {
+        let minimal = matches!(
+            event_type,
+            ProgressEventType::TaskStart
+                | ProgressEventType::TaskComplete
+                | ProgressEventType::TaskError
+                | ProgressEventType::ToolError
+                | ProgressEventType::LlmError
+                | ProgressEventType::ValidationError
+                | ProgressEventType::Warning
+        );
+        let normal = matches!(
+            event_type,
+            ProgressEventType::StepStart
+                | ProgressEventType::StepComplete
+                | ProgressEventType::ToolCall
+                | ProgressEventType::ToolComplete
+                | ProgressEventType::PercentComplete
+        );
+        match self {
+            ProgressVerbosity::Minimal => minimal,
+            ProgressVerbosity::Normal => minimal || normal,
+            ProgressVerbosity::Verbose => true,
+        }
+    }
+}
+
 impl Default for ProgressConfig {
     fn default() -> Self {
         Self {
@@ -115,14 +225,42 @@ impl Default for ProgressConfig {
                 ProgressCategory::Tool,
                 ProgressCategory::LLM,
             ],
+            conversation_topics: false,
+            max_metadata_bytes: default_max_metadata_bytes(),
         }
     }
 }
 
+/// Seconds left if the rest of the task goes as fast as the part done (pure function)
+pub(crate) fn estimate_eta(elapsed: std::time::Duration, percent: f32) -> Option<u64> {
+    if percent <= 0.0 {
+        return None;
+    }
+    let remaining = elapsed.as_secs_f32() * (100.0 - percent) / percent;
+    Some(remaining.max(0.0).round() as u64)
+}
+
 #[async_trait]
 pub trait Progress: Send + Sync {
+    /// Attach workflow correlation ids to every later message for the task
+    async fn register_correlation(
+        &self,
+        task_id: &str,
+        correlation_id: &str,
+        parent_task_id: Option<&str>,
+    );
+    /// Forget the correlation ids of a finished task
+    async fn clear_correlation(&self, task_id: &str);
+
     async fn report_task_start(&self, task_id: &str, conversation_id: &str, message: &str);
-    async fn report_task_complete(&self, task_id: &str, conversation_id: &str, message: &str);
+    /// Report a finished task; `metadata` carries details such as step timings
+    async fn report_task_complete(
+        &self,
+        task_id: &str,
+        conversation_id: &str,
+        message: &str,
+        metadata: Option<serde_json::Value>,
+    );
     async fn report_task_error(
         &self,
         task_id: Option<&str>,
@@ -177,6 +315,15 @@ pub trait Progress: Send + Sync {
 
     async fn report_processing(&self, task_id: &str, conversation_id: &str, message: &str);
 
+    /// Report how far along the task is, as a percentage from 0 to 100
+    async fn report_progress_percent(
+        &self,
+        task_id: &str,
+        conversation_id: &str,
+        percent: f32,
+        message: &str,
+    );
+
     async fn report_custom(
         &self,
         category: ProgressCategory,
@@ -192,8 +339,24 @@ pub struct NoOpProgress;
 
 #[async_trait]
 impl Progress for NoOpProgress {
+    async fn register_correlation(
+        &self,
+        _task_id: &str,
+        _correlation_id: &str,
+        _parent_task_id: Option<&str>,
+    ) {
+    }
+    async fn clear_correlation(&self, _task_id: &str) {}
+
     async fn report_task_start(&self, _task_id: &str, _conversation_id: &str, _message: &str) {}
-    async fn report_task_complete(&self, _task_id: &str, _conversation_id: &str, _message: &str) {}
+    async fn report_task_complete(
+        &self,
+        _task_id: &str,
+        _conversation_id: &str,
+        _message: &str,
+        _metadata: Option<serde_json::Value>,
+    ) {
+    }
     async fn report_task_error(
         &self,
         _task_id: Option<&str>,
@@ -272,6 +435,15 @@ impl Progress for NoOpProgress {
 
     async fn report_processing(&self, _task_id: &str, _conversation_id: &str, _message: &str) {}
 
+    async fn report_progress_percent(
+        &self,
+        _task_id: &str,
+        _conversation_id: &str,
+        _percent: f32,
+        _message: &str,
+    ) {
+    }
+
     async fn report_custom(
         &self,
         _category: ProgressCategory,
@@ -346,6 +518,36 @@ mod tests {
         assert_eq!(llm_msg.topic(), "/control/agents/agent-1/progress/llm");
     }
 
+    #[test]
+    fn test_percent_fields_are_optional_on_the_wire() {
+        let msg = ProgressMessage::new(
+            "agent-1".to_string(),
+            ProgressCategory::General,
+            ProgressEventType::StepComplete,
+            "Step 1".to_string(),
+        );
+        let json = serde_json::to_value(&msg).unwrap();
+        assert!(json.get("percent").is_none());
+        assert!(json.get("eta_seconds").is_none());
+
+        // Messages from agents without percent support still parse
+        let legacy: ProgressMessage = serde_json::from_value(json).unwrap();
+        assert!(legacy.percent.is_none());
+
+        let with_percent = msg.with_percent(42.5, Some(12));
+        let json = serde_json::to_value(&with_percent).unwrap();
+        assert_eq!(json["percent"], 42.5);
+        assert_eq!(json["eta_seconds"], 12);
+    }
+
+    #[test]
+    fn test_eta_extrapolates_from_elapsed_time() {
+        use std::time::Duration;
+        assert_eq!(estimate_eta(Duration::from_secs(10), 25.0), Some(30));
+        assert_eq!(estimate_eta(Duration::from_secs(10), 100.0), Some(0));
+        assert_eq!(estimate_eta(Duration::from_secs(10), 0.0), None);
+    }
+
     #[test]
     fn test_progress_config_default() {
         let config = ProgressConfig::default();
@@ -355,4 +557,28 @@ mod tests {
         assert_eq!(config.batch_size, 10);
         assert_eq!(config.categories.len(), 3);
     }
+
+    #[test]
+    fn test_verbosity_levels() {
+        use ProgressEventType::*;
+        let minimal = ProgressVerbosity::Minimal;
+        let normal = ProgressVerbosity::Normal;
+        let verbose = ProgressVerbosity::Verbose;
+
+        for event in [TaskStart, TaskComplete, TaskError, ToolError, Warning] {
+            assert!(minimal.includes(&event) && normal.includes(&event));
+        }
+        for event in [
+            StepStart,
+            StepComplete,
+            ToolCall,
+            ToolComplete,
+            PercentComplete,
+        ] {
+            assert!(!minimal.includes(&event) && normal.includes(&event));
+        }
+        for event in [LlmRequest, LlmResponse, ValidationStart, Processing, Custom] {
+            assert!(!normal.includes(&event) && verbose.includes(&event));
+        }
+    }
 }
@@ -1,20 +1,30 @@
+use super::batcher::{ProgressBatcher, ScheduledFlush};
+use super::metadata::cap_metadata;
 use super::{
-    Progress, ProgressCategory, ProgressConfig, ProgressEventType, ProgressMessage,
-    ProgressVerbosity,
+    estimate_eta, Progress, ProgressCategory, ProgressConfig, ProgressEventType, ProgressMessage,
 };
 use crate::transport::Transport;
 use async_trait::async_trait;
-use std::collections::VecDeque;
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::{Mutex, RwLock};
+use tokio::time::Instant;
 use tracing::{debug, error, trace};
 
+/// Correlation id and optional parent task id of a task
+type TaskCorrelation = (String, Option<String>);
+
 pub struct MqttProgressReporter<T: Transport + 'static> {
     agent_id: String,
     transport: Arc<T>,
     config: Arc<RwLock<ProgressConfig>>,
-    message_buffer: Arc<Mutex<VecDeque<ProgressMessage>>>,
+    /// Events waiting out their throttle window
+    batcher: Arc<Mutex<ProgressBatcher>>,
+    /// Correlation and parent task ids keyed by task id
+    correlations: Arc<RwLock<HashMap<String, TaskCorrelation>>>,
+    /// Start time of each running task, for ETA estimates
+    task_started: Arc<RwLock<HashMap<String, Instant>>>,
 }
 
 impl<T: Transport + 'static> MqttProgressReporter<T> {
@@ -23,7 +33,9 @@ impl<T: Transport + 'static> MqttProgressReporter<T> {
             agent_id,
             transport,
             config: Arc::new(RwLock::new(config)),
-            message_buffer: Arc::new(Mutex::new(VecDeque::new())),
+            batcher: Arc::new(Mutex::new(ProgressBatcher::new())),
+            correlations: Arc::new(RwLock::new(HashMap::new())),
+            task_started: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 
@@ -33,84 +45,91 @@ impl<T: Transport + 'static> MqttProgressReporter<T> {
     }
 
     async fn buffer_message(&self, mut message: ProgressMessage) {
-        let config = self.config.read().await;
-
-        // Filter by verbosity
-        match config.verbosity {
-            ProgressVerbosity::Minimal => {
-                // Only task start/complete/error and critical events
-                if !matches!(
-                    message.event_type,
-                    ProgressEventType::TaskStart
-                        | ProgressEventType::TaskComplete
-                        | ProgressEventType::TaskError
-                        | ProgressEventType::ToolError
-                        | ProgressEventType::LlmError
-                        | ProgressEventType::ValidationError
-                ) {
-                    return;
-                }
-            }
-            ProgressVerbosity::Normal => {
-                // Skip verbose step-by-step details
-                if matches!(
-                    message.event_type,
-                    ProgressEventType::StepStart
-                        | ProgressEventType::StepComplete
-                        | ProgressEventType::ValidationStart
-                        | ProgressEventType::ValidationComplete
-                ) {
-                    return;
-                }
-            }
-            ProgressVerbosity::Verbose => {
-                // Report everything
-            }
+        let config = self.config.read().await.clone();
+        if !config.verbosity.includes(&message.event_type) {
+            return;
         }
 
         // Set agent_id if not already set
         if message.agent_id.is_empty() {
             message.agent_id = self.agent_id.clone();
         }
+        message.metadata = message
+            .metadata
+            .take()
+            .and_then(|metadata| cap_metadata(metadata, config.max_metadata_bytes));
+
+        let admitted = self
+            .batcher
+            .lock()
+            .await
+            .admit(message, &config, Instant::now());
+        for batch in &admitted.publish {
+            Self::publish_batch(&self.transport, batch, config.conversation_topics).await;
+        }
+        if let Some(flush) = admitted.schedule {
+            self.schedule_flush(flush);
+        }
+    }
 
-        let mut buffer = self.message_buffer.lock().await;
-        buffer.push_back(message);
-
-        // Flush immediately for real-time progress updates
-        drop(buffer);
-        drop(config);
-        self.flush_buffer().await;
+    /// Publish events left waiting once their throttle window ends
+    fn schedule_flush(&self, flush: ScheduledFlush) {
+        let batcher = Arc::clone(&self.batcher);
+        let transport = Arc::clone(&self.transport);
+        let config = Arc::clone(&self.config);
+        tokio::spawn(async move {
+            tokio::time::sleep_until(flush.at).await;
+            let batch = batcher.lock().await.flush_scheduled(&flush, Instant::now());
+            let conversation_topics = config.read().await.conversation_topics;
+            Self::publish_batch(&transport, &batch, conversation_topics).await;
+        });
     }
 
     async fn flush_buffer(&self) {
-        let mut buffer = self.message_buffer.lock().await;
-        if buffer.is_empty() {
-            return;
+        let conversation_topics = self.config.read().await.conversation_topics;
+        let batches = self.batcher.lock().await.flush_all(Instant::now());
+        for batch in &batches {
+            Self::publish_batch(&self.transport, batch, conversation_topics).await;
         }
+    }
 
-        let messages: Vec<ProgressMessage> = buffer.drain(..).collect();
-        drop(buffer);
+    /// Publish one batch: a single event as an object, several as a JSON array
+    ///
+    /// With `conversation_topics`, the same payload also goes to the
+    /// conversation's progress topic; a batch never spans conversations.
+    async fn publish_batch(transport: &T, batch: &[ProgressMessage], conversation_topics: bool) {
+        let Some(first) = batch.first() else {
+            return;
+        };
+        let payload = match batch {
+            [message] => serde_json::to_vec(message),
+            messages => serde_json::to_vec(messages),
+        };
+        let payload = match payload {
+            Ok(payload) => payload,
+            Err(e) => {
+                error!("Failed to serialize progress message: {}", e);
+                return;
+            }
+        };
 
-        for message in messages {
-            if let Err(e) = self.publish_message(&message).await {
-                error!("Failed to publish progress message: {}", e);
+        let mut topics = vec![first.topic()];
+        if conversation_topics {
+            topics.extend(first.conversation_topic());
+        }
+        for topic in topics {
+            trace!(
+                "Publishing progress: {} -> {} ({} events)",
+                topic,
+                first.message,
+                batch.len()
+            );
+            if let Err(e) = transport.publish(&topic, payload.clone(), false).await {
+                error!("Failed to publish progress message to {}: {}", topic, e);
             }
         }
     }
 
-    async fn publish_message(
-        &self,
-        message: &ProgressMessage,
-    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        let topic = message.topic();
-        let payload = serde_json::to_vec(message)?;
-
-        trace!("Publishing progress: {} -> {}", topic, message.message);
-
-        self.transport.publish(&topic, payload, false).await?;
-        Ok(())
-    }
-
     pub async fn update_config(&self, config: ProgressConfig) {
         let mut current_config = self.config.write().await;
         *current_config = config;
@@ -130,6 +149,19 @@ impl<T: Transport + 'static> MqttProgressReporter<T> {
         message: &str,
         metadata: Option<serde_json::Value>,
     ) -> ProgressMessage {
+        let (correlation_id, parent_task_id) = match task_id {
+            Some(task_id) => self
+                .correlations
+                .read()
+                .await
+                .get(task_id)
+                .cloned()
+                .map_or((None, None), |(correlation_id, parent_task_id)| {
+                    (Some(correlation_id), parent_task_id)
+                }),
+            None => (None, None),
+        };
+
         ProgressMessage::new(
             self.agent_id.clone(),
             category,
@@ -140,6 +172,7 @@ impl<T: Transport + 'static> MqttProgressReporter<T> {
             task_id.map(|s| s.to_string()),
             conversation_id.map(|s| s.to_string()),
         )
+        .with_correlation(correlation_id, parent_task_id)
         .with_metadata(metadata.unwrap_or_default())
     }
 
@@ -158,7 +191,30 @@ impl<T: Transport + 'static> MqttProgressReporter<T> {
 
 #[async_trait]
 impl<T: Transport + 'static> Progress for MqttProgressReporter<T> {
+    async fn register_correlation(
+        &self,
+        task_id: &str,
+        correlation_id: &str,
+        parent_task_id: Option<&str>,
+    ) {
+        self.correlations.write().await.insert(
+            task_id.to_string(),
+            (
+                correlation_id.to_string(),
+                parent_task_id.map(str::to_string),
+            ),
+        );
+    }
+
+    async fn clear_correlation(&self, task_id: &str) {
+        self.correlations.write().await.remove(task_id);
+    }
+
     async fn report_task_start(&self, task_id: &str, conversation_id: &str, message: &str) {
+        self.task_started
+            .write()
+            .await
+            .insert(task_id.to_string(), Instant::now());
         if !self.should_report(&ProgressCategory::General).await {
             return;
         }
@@ -177,7 +233,14 @@ impl<T: Transport + 'static> Progress for MqttProgressReporter<T> {
         self.buffer_message(progress_msg).await;
     }
 
-    async fn report_task_complete(&self, task_id: &str, conversation_id: &str, message: &str) {
+    async fn report_task_complete(
+        &self,
+        task_id: &str,
+        conversation_id: &str,
+        message: &str,
+        metadata: Option<serde_json::Value>,
+    ) {
+        self.task_started.write().await.remove(task_id);
         if !self.should_report(&ProgressCategory::General).await {
             return;
         }
@@ -189,7 +252,7 @@ impl<T: Transport + 'static> Progress for MqttProgressReporter<T> {
                 Some(task_id),
                 Some(conversation_id),
                 message,
-                None,
+                metadata,
             )
             .await;
 
@@ -202,6 +265,9 @@ impl<T: Transport + 'static> Progress for MqttProgressReporter<T> {
         conversation_id: Option<&str>,
         message: &str,
     ) {
+        if let Some(task_id) = task_id {
+            self.task_started.write().await.remove(task_id);
+        }
         if !self.should_report(&ProgressCategory::General).await {
             return;
         }
@@ -488,6 +554,39 @@ impl<T: Transport + 'static> Progress for MqttProgressReporter<T> {
         self.buffer_message(progress_msg).await;
     }
 
+    async fn report_progress_percent(
+        &self,
+        task_id: &str,
+        conversation_id: &str,
+        percent: f32,
+        message: &str,
+    ) {
+        if !self.should_report(&ProgressCategory::General).await {
+            return;
+        }
+
+        let percent = percent.clamp(0.0, 100.0);
+        let eta_seconds = self
+            .task_started
+            .read()
+            .await
+            .get(task_id)
+            .and_then(|started| estimate_eta(started.elapsed(), percent));
+        let progress_msg = self
+            .create_message(
+                ProgressCategory::General,
+                ProgressEventType::PercentComplete,
+                Some(task_id),
+                Some(conversation_id),
+                message,
+                None,
+            )
+            .await
+            .with_percent(percent, eta_seconds);
+
+        self.buffer_message(progress_msg).await;
+    }
+
     async fn report_custom(
         &self,
         category: ProgressCategory,
@@ -519,6 +618,7 @@ impl<T: Transport + 'static> Progress for MqttProgressReporter<T> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::progress::ProgressVerbosity;
     use crate::testing::MockTransport;
 
     #[tokio::test]
@@ -531,6 +631,35 @@ mod tests {
         assert_eq!(reporter.agent_id, "test-agent");
     }
 
+    #[tokio::test]
+    async fn test_registered_correlation_is_attached() {
+        let transport = Arc::new(MockTransport::new());
+        let reporter = MqttProgressReporter::new(
+            "test-agent".to_string(),
+            transport.clone(),
+            ProgressConfig::default(),
+        );
+
+        reporter
+            .register_correlation("task-1", "corr-1", Some("parent-1"))
+            .await;
+        reporter
+            .report_task_start("task-1", "conv-1", "Starting task")
+            .await;
+        reporter.clear_correlation("task-1").await;
+        reporter
+            .report_task_complete("task-1", "conv-1", "Done", None)
+            .await;
+
+        let messages = transport.get_published_messages().await;
+        assert_eq!(messages.len(), 2);
+        let started: ProgressMessage = serde_json::from_slice(&messages[0].1).unwrap();
+        assert_eq!(started.correlation_id.as_deref(), Some("corr-1"));
+        assert_eq!(started.parent_task_id.as_deref(), Some("parent-1"));
+        let completed: ProgressMessage = serde_json::from_slice(&messages[1].1).unwrap();
+        assert!(completed.correlation_id.is_none());
+    }
+
     #[tokio::test]
     async fn test_progress_reporting_disabled() {
         let transport = Arc::new(MockTransport::new());
@@ -612,7 +741,10 @@ mod tests {
     #[tokio::test]
     async fn test_topic_routing() {
         let transport = Arc::new(MockTransport::new());
-        let config = ProgressConfig::default();
+        let config = ProgressConfig {
+            verbosity: ProgressVerbosity::Verbose,
+            ..Default::default()
+        };
 
         let reporter =
             MqttProgressReporter::new("test-agent".to_string(), transport.clone(), config);
@@ -637,4 +769,212 @@ mod tests {
         assert!(topics.contains(&&"/control/agents/test-agent/progress/tools".to_string()));
         assert!(topics.contains(&&"/control/agents/test-agent/progress/llm".to_string()));
     }
+
+    /// Events in a published payload, which is an object or an array
+    fn decode(payload: &[u8]) -> Vec<ProgressMessage> {
+        match serde_json::from_slice::<serde_json::Value>(payload).unwrap() {
+            serde_json::Value::Array(events) => events
+                .into_iter()
+                .map(|event| serde_json::from_value(event).unwrap())
+                .collect(),
+            event => vec![serde_json::from_value(event).unwrap()],
+        }
+    }
+
+    fn throttled_reporter(
+        batch_size: usize,
+    ) -> (MqttProgressReporter<MockTransport>, Arc<MockTransport>) {
+        let transport = Arc::new(MockTransport::new());
+        let config = ProgressConfig {
+            throttle_ms: 100,
+            batch_size,
+            ..Default::default()
+        };
+        let reporter =
+            MqttProgressReporter::new("test-agent".to_string(), transport.clone(), config);
+        (reporter, transport)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_events_within_window_are_batched() {
+        let (reporter, transport) = throttled_reporter(10);
+
+        for i in 0..4 {
+            reporter
+                .report_tool_call("task-1", "conv-1", "web_search", &format!("call {i}"))
+                .await;
+        }
+
+        // The first call goes out at once, the rest wait for the window
+        let messages = transport.get_published_messages().await;
+        assert_eq!(messages.len(), 1);
+        assert_eq!(decode(&messages[0].1)[0].message, "call 0");
+
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        let messages = transport.get_published_messages().await;
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[1].0, "/control/agents/test-agent/progress/tools");
+        let payload: serde_json::Value = serde_json::from_slice(&messages[1].1).unwrap();
+        assert!(payload.is_array(), "batches are published as an array");
+        let batch: Vec<String> = decode(&messages[1].1)
+            .into_iter()
+            .map(|m| m.message)
+            .collect();
+        assert_eq!(batch, vec!["call 1", "call 2", "call 3"]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_events_within_window_coalesce_without_batching() {
+        let (reporter, transport) = throttled_reporter(1);
+
+        for step in 1..=5 {
+            reporter
+                .report_step_start("task-1", "conv-1", step, &format!("step {step}"))
+                .await;
+        }
+        tokio::time::sleep(Duration::from_millis(150)).await;
+
+        // Leading event, then only the latest of the ones that waited
+        let messages = transport.get_published_messages().await;
+        assert_eq!(messages.len(), 2);
+        let latest = decode(&messages[1].1).remove(0);
+        assert_eq!(latest.message, "step 5");
+        let metadata = latest.metadata.unwrap();
+        assert_eq!(metadata["step"], 5);
+        assert_eq!(metadata["coalesced_events"], 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_task_completion_flushes_waiting_events() {
+        let (reporter, transport) = throttled_reporter(10);
+
+        reporter
+            .report_task_start("task-1", "conv-1", "Starting")
+            .await;
+        for step in 1..=3 {
+            reporter
+                .report_step_start("task-1", "conv-1", step, &format!("step {step}"))
+                .await;
+        }
+        reporter
+            .report_tool_call("task-1", "conv-1", "web_search", "first call")
+            .await;
+        reporter
+            .report_tool_call("task-1", "conv-1", "web_search", "second call")
+            .await;
+        reporter
+            .report_task_complete("task-1", "conv-1", "Done", None)
+            .await;
+
+        // Everything is out before the window ends, with completion last
+        let published = transport.get_published_messages().await;
+        let events: Vec<ProgressMessage> = published
+            .iter()
+            .flat_map(|(_, payload)| decode(payload))
+            .collect();
+        assert_eq!(events.len(), 7);
+        assert_eq!(events[0].event_type, ProgressEventType::TaskStart);
+        assert_eq!(events[6].event_type, ProgressEventType::TaskComplete);
+
+        // No scheduled flush publishes anything twice
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        assert_eq!(
+            transport.get_published_messages().await.len(),
+            published.len()
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_task_error_flushes_waiting_events() {
+        let (reporter, transport) = throttled_reporter(1);
+
+        reporter
+            .report_step_start("task-1", "conv-1", 1, "step 1")
+            .await;
+        reporter
+            .report_step_start("task-1", "conv-1", 2, "step 2")
+            .await;
+        reporter
+            .report_task_error(Some("task-1"), Some("conv-1"), "Failed")
+            .await;
+
+        let messages = transport.get_published_messages().await;
+        let events: Vec<String> = messages
+            .iter()
+            .flat_map(|(_, payload)| decode(payload))
+            .map(|m| m.message)
+            .collect();
+        assert_eq!(events, vec!["step 1", "step 2", "Failed"]);
+    }
+
+    #[tokio::test]
+    async fn test_normal_verbosity_skips_llm_chatter() {
+        let transport = Arc::new(MockTransport::new());
+        let reporter = MqttProgressReporter::new(
+            "test-agent".to_string(),
+            transport.clone(),
+            ProgressConfig::default(),
+        );
+
+        reporter
+            .report_step_start("task-1", "conv-1", 1, "Starting step 1")
+            .await;
+        reporter
+            .report_llm_request("task-1", "conv-1", "Requesting LLM")
+            .await;
+        reporter
+            .report_llm_error("task-1", "conv-1", "LLM unavailable")
+            .await;
+
+        let topics: Vec<String> = transport
+            .get_published_messages()
+            .await
+            .into_iter()
+            .map(|(topic, _)| topic)
+            .collect();
+        assert_eq!(
+            topics,
+            vec![
+                "/control/agents/test-agent/progress",
+                "/control/agents/test-agent/progress/llm"
+            ]
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_percent_report_carries_eta() {
+        let transport = Arc::new(MockTransport::new());
+        let reporter = MqttProgressReporter::new(
+            "test-agent".to_string(),
+            transport.clone(),
+            ProgressConfig::default(),
+        );
+
+        reporter
+            .report_task_start("task-1", "conv-1", "Starting")
+            .await;
+        tokio::time::sleep(Duration::from_secs(20)).await;
+        reporter
+            .report_progress_percent("task-1", "conv-1", 40.0, "Step 4 of 9")
+            .await;
+        // Out of range estimates are clamped
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        reporter
+            .report_progress_percent("task-1", "conv-1", 140.0, "Done")
+            .await;
+        tokio::time::sleep(Duration::from_secs(1)).await;
+
+        let updates: Vec<ProgressMessage> = transport
+            .get_published_messages()
+            .await
+            .iter()
+            .flat_map(|(_, payload)| decode(payload))
+            .filter(|m| m.event_type == ProgressEventType::PercentComplete)
+            .collect();
+        assert_eq!(updates.len(), 2);
+        assert_eq!(updates[0].percent, Some(40.0));
+        assert_eq!(updates[0].eta_seconds, Some(30));
+        assert_eq!(updates[1].percent, Some(100.0));
+        assert_eq!(updates[1].eta_seconds, Some(0));
+    }
 }
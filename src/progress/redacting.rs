@@ -0,0 +1,305 @@
+//! Redacted progress reporting
+//!
+//! `RedactingProgress` passes every message and metadata string through a
+//! [`Redactor`] before handing the report to the reporter it wraps, so no
+//! sink sees a secret a tool argument or error carried.
+
+use super::{Progress, ProgressCategory, ProgressEventType};
+use crate::observability::Redactor;
+use async_trait::async_trait;
+use std::sync::Arc;
+
+/// Progress reporter redacting each report before forwarding it
+pub struct RedactingProgress {
+    inner: Arc<dyn Progress>,
+    redactor: Arc<Redactor>,
+}
+
+impl RedactingProgress {
+    pub fn new(inner: Arc<dyn Progress>, redactor: Arc<Redactor>) -> Self {
+        Self { inner, redactor }
+    }
+
+    /// `inner` wrapped in a redacting reporter, or unchanged when `redactor`
+    /// has nothing to redact
+    pub fn wrap(inner: Arc<dyn Progress>, redactor: &Arc<Redactor>) -> Arc<dyn Progress> {
+        if redactor.is_empty() {
+            inner
+        } else {
+            Arc::new(Self::new(inner, redactor.clone()))
+        }
+    }
+
+    fn metadata(&self, metadata: Option<serde_json::Value>) -> Option<serde_json::Value> {
+        metadata.map(|mut metadata| {
+            self.redactor.redact_json(&mut metadata);
+            metadata
+        })
+    }
+}
+
+#[async_trait]
+impl Progress for RedactingProgress {
+    async fn register_correlation(
+        &self,
+        task_id: &str,
+        correlation_id: &str,
+        parent_task_id: Option<&str>,
+    ) {
+        self.inner
+            .register_correlation(task_id, correlation_id, parent_task_id)
+            .await;
+    }
+
+    async fn clear_correlation(&self, task_id: &str) {
+        self.inner.clear_correlation(task_id).await;
+    }
+
+    async fn report_task_start(&self, task_id: &str, conversation_id: &str, message: &str) {
+        let message = self.redactor.redact(message);
+        self.inner
+            .report_task_start(task_id, conversation_id, &message)
+            .await;
+    }
+
+    async fn report_task_complete(
+        &self,
+        task_id: &str,
+        conversation_id: &str,
+        message: &str,
+        metadata: Option<serde_json::Value>,
+    ) {
+        let message = self.redactor.redact(message);
+        self.inner
+            .report_task_complete(task_id, conversation_id, &message, self.metadata(metadata))
+            .await;
+    }
+
+    async fn report_task_error(
+        &self,
+        task_id: Option<&str>,
+        conversation_id: Option<&str>,
+        message: &str,
+    ) {
+        let message = self.redactor.redact(message);
+        self.inner
+            .report_task_error(task_id, conversation_id, &message)
+            .await;
+    }
+
+    async fn report_step_start(
+        &self,
+        task_id: &str,
+        conversation_id: &str,
+        step: u8,
+        message: &str,
+    ) {
+        let message = self.redactor.redact(message);
+        self.inner
+            .report_step_start(task_id, conversation_id, step, &message)
+            .await;
+    }
+
+    async fn report_step_complete(
+        &self,
+        task_id: &str,
+        conversation_id: &str,
+        step: u8,
+        message: &str,
+    ) {
+        let message = self.redactor.redact(message);
+        self.inner
+            .report_step_complete(task_id, conversation_id, step, &message)
+            .await;
+    }
+
+    async fn report_tool_call(
+        &self,
+        task_id: &str,
+        conversation_id: &str,
+        tool_name: &str,
+        message: &str,
+    ) {
+        let message = self.redactor.redact(message);
+        self.inner
+            .report_tool_call(task_id, conversation_id, tool_name, &message)
+            .await;
+    }
+
+    async fn report_tool_complete(
+        &self,
+        task_id: &str,
+        conversation_id: &str,
+        tool_name: &str,
+        message: &str,
+    ) {
+        let message = self.redactor.redact(message);
+        self.inner
+            .report_tool_complete(task_id, conversation_id, tool_name, &message)
+            .await;
+    }
+
+    async fn report_tool_error(
+        &self,
+        task_id: &str,
+        conversation_id: &str,
+        tool_name: &str,
+        message: &str,
+    ) {
+        let message = self.redactor.redact(message);
+        self.inner
+            .report_tool_error(task_id, conversation_id, tool_name, &message)
+            .await;
+    }
+
+    async fn report_llm_request(&self, task_id: &str, conversation_id: &str, message: &str) {
+        let message = self.redactor.redact(message);
+        self.inner
+            .report_llm_request(task_id, conversation_id, &message)
+            .await;
+    }
+
+    async fn report_llm_response(&self, task_id: &str, conversation_id: &str, message: &str) {
+        let message = self.redactor.redact(message);
+        self.inner
+            .report_llm_response(task_id, conversation_id, &message)
+            .await;
+    }
+
+    async fn report_llm_error(&self, task_id: &str, conversation_id: &str, message: &str) {
+        let message = self.redactor.redact(message);
+        self.inner
+            .report_llm_error(task_id, conversation_id, &message)
+            .await;
+    }
+
+    async fn report_validation_start(&self, task_id: &str, conversation_id: &str, message: &str) {
+        let message = self.redactor.redact(message);
+        self.inner
+            .report_validation_start(task_id, conversation_id, &message)
+            .await;
+    }
+
+    async fn report_validation_complete(
+        &self,
+        task_id: &str,
+        conversation_id: &str,
+        message: &str,
+    ) {
+        let message = self.redactor.redact(message);
+        self.inner
+            .report_validation_complete(task_id, conversation_id, &message)
+            .await;
+    }
+
+    async fn report_validation_error(&self, task_id: &str, conversation_id: &str, message: &str) {
+        let message = self.redactor.redact(message);
+        self.inner
+            .report_validation_error(task_id, conversation_id, &message)
+            .await;
+    }
+
+    async fn report_processing(&self, task_id: &str, conversation_id: &str, message: &str) {
+        let message = self.redactor.redact(message);
+        self.inner
+            .report_processing(task_id, conversation_id, &message)
+            .await;
+    }
+
+    async fn report_progress_percent(
+        &self,
+        task_id: &str,
+        conversation_id: &str,
+        percent: f32,
+        message: &str,
+    ) {
+        let message = self.redactor.redact(message);
+        self.inner
+            .report_progress_percent(task_id, conversation_id, percent, &message)
+            .await;
+    }
+
+    async fn report_custom(
+        &self,
+        category: ProgressCategory,
+        event_type: ProgressEventType,
+        task_id: Option<&str>,
+        conversation_id: Option<&str>,
+        message: &str,
+        metadata: Option<serde_json::Value>,
+    ) {
+        let message = self.redactor.redact(message);
+        self.inner
+            .report_custom(
+                category,
+                event_type,
+                task_id,
+                conversation_id,
+                &message,
+                self.metadata(metadata),
+            )
+            .await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::progress::sink::{ProgressSink, SinkProgress};
+    use crate::progress::{metadata, NoOpProgress, ProgressMessage};
+    use std::sync::Mutex;
+
+    const SECRET: &str = "sk-test-0123456789abcdef";
+
+    /// Sink keeping every message it is sent
+    #[derive(Clone, Default)]
+    struct Collect(Arc<Mutex<Vec<ProgressMessage>>>);
+
+    impl ProgressSink for Collect {
+        fn send(&self, message: ProgressMessage) {
+            self.0.lock().unwrap().push(message);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_messages_and_metadata_are_redacted() {
+        let collected = Collect::default();
+        let inner = Arc::new(SinkProgress::new("agent".to_string(), collected.clone()));
+        let redactor = Arc::new(Redactor::new([] as [&str; 0], [SECRET]).unwrap());
+        let progress = RedactingProgress::wrap(inner, &redactor);
+
+        progress
+            .report_custom(
+                ProgressCategory::Tool,
+                ProgressEventType::ToolComplete,
+                Some("task-1"),
+                Some("conv-1"),
+                &format!("Tool called with key {SECRET}"),
+                Some(metadata::tool_complete_metadata(
+                    "http",
+                    std::time::Duration::from_millis(5),
+                    &serde_json::json!({"echo": {"authorization": format!("Bearer {SECRET}")}}),
+                )),
+            )
+            .await;
+        progress
+            .report_task_error(Some("task-1"), None, &format!("401 for {SECRET}"))
+            .await;
+
+        let messages = collected.0.lock().unwrap();
+        let text = serde_json::to_string(&*messages).unwrap();
+        assert!(!text.contains(SECRET), "secret leaked: {text}");
+        assert_eq!(messages[0].message, "Tool called with key [REDACTED]");
+        assert!(text.contains("Bearer [REDACTED]"));
+        assert_eq!(messages[1].message, "401 for [REDACTED]");
+    }
+
+    #[test]
+    fn test_empty_redactor_does_not_wrap() {
+        let inner: Arc<dyn Progress> = Arc::new(NoOpProgress);
+
+        let wrapped = RedactingProgress::wrap(inner.clone(), &Arc::new(Redactor::default()));
+
+        assert!(Arc::ptr_eq(&inner, &wrapped));
+    }
+}
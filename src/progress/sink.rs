@@ -0,0 +1,443 @@
+//! Progress reporting onto a message sink
+//!
+//! `SinkProgress` does the bookkeeping every message-based reporter needs:
+//! it turns each report into a `ProgressMessage`, attaching the task's
+//! correlation ids and, for percent reports, an ETA. Where the message goes
+//! is up to the `ProgressSink`: a broadcast channel, a JSONL file, the log.
+
+use super::{estimate_eta, Progress, ProgressCategory, ProgressEventType, ProgressMessage};
+use async_trait::async_trait;
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tokio::time::Instant;
+
+/// What is known about a running task
+#[derive(Debug, Default)]
+struct TaskState {
+    correlation_id: Option<String>,
+    parent_task_id: Option<String>,
+    started: Option<Instant>,
+}
+
+/// Destination for complete progress messages
+pub trait ProgressSink: Send + Sync {
+    /// Deliver one message; must not wait on slow consumers
+    fn send(&self, message: ProgressMessage);
+}
+
+/// Progress reporter building a `ProgressMessage` for every report and
+/// handing it to a sink
+pub struct SinkProgress<S> {
+    agent_id: String,
+    sink: S,
+    tasks: Mutex<HashMap<String, TaskState>>,
+}
+
+impl<S: ProgressSink> SinkProgress<S> {
+    pub fn new(agent_id: String, sink: S) -> Self {
+        Self {
+            agent_id,
+            sink,
+            tasks: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Build the message for an event and hand it to the sink
+    fn emit(
+        &self,
+        category: ProgressCategory,
+        event_type: ProgressEventType,
+        task_id: Option<&str>,
+        conversation_id: Option<&str>,
+        message: &str,
+        metadata: Option<serde_json::Value>,
+    ) {
+        let (correlation_id, parent_task_id) = task_id
+            .and_then(|task_id| {
+                let tasks = self.tasks.lock().unwrap();
+                let task = tasks.get(task_id)?;
+                Some((task.correlation_id.clone(), task.parent_task_id.clone()))
+            })
+            .unwrap_or_default();
+        let mut progress = ProgressMessage::new(
+            self.agent_id.clone(),
+            category,
+            event_type,
+            message.to_string(),
+        )
+        .with_task_context(
+            task_id.map(str::to_string),
+            conversation_id.map(str::to_string),
+        )
+        .with_correlation(correlation_id, parent_task_id);
+        progress.metadata = metadata;
+
+        self.sink.send(progress);
+    }
+
+    fn task_started(&self, task_id: &str) {
+        self.tasks
+            .lock()
+            .unwrap()
+            .entry(task_id.to_string())
+            .or_default()
+            .started = Some(Instant::now());
+    }
+
+    fn task_finished(&self, task_id: &str) {
+        if let Some(task) = self.tasks.lock().unwrap().get_mut(task_id) {
+            task.started = None;
+        }
+    }
+}
+
+#[async_trait]
+impl<S: ProgressSink> Progress for SinkProgress<S> {
+    async fn register_correlation(
+        &self,
+        task_id: &str,
+        correlation_id: &str,
+        parent_task_id: Option<&str>,
+    ) {
+        let mut tasks = self.tasks.lock().unwrap();
+        let task = tasks.entry(task_id.to_string()).or_default();
+        task.correlation_id = Some(correlation_id.to_string());
+        task.parent_task_id = parent_task_id.map(str::to_string);
+    }
+
+    async fn clear_correlation(&self, task_id: &str) {
+        self.tasks.lock().unwrap().remove(task_id);
+    }
+
+    async fn report_task_start(&self, task_id: &str, conversation_id: &str, message: &str) {
+        self.task_started(task_id);
+        self.emit(
+            ProgressCategory::General,
+            ProgressEventType::TaskStart,
+            Some(task_id),
+            Some(conversation_id),
+            message,
+            None,
+        );
+    }
+
+    async fn report_task_complete(
+        &self,
+        task_id: &str,
+        conversation_id: &str,
+        message: &str,
+        metadata: Option<serde_json::Value>,
+    ) {
+        self.task_finished(task_id);
+        self.emit(
+            ProgressCategory::General,
+            ProgressEventType::TaskComplete,
+            Some(task_id),
+            Some(conversation_id),
+            message,
+            metadata,
+        );
+    }
+
+    async fn report_task_error(
+        &self,
+        task_id: Option<&str>,
+        conversation_id: Option<&str>,
+        message: &str,
+    ) {
+        if let Some(task_id) = task_id {
+            self.task_finished(task_id);
+        }
+        self.emit(
+            ProgressCategory::General,
+            ProgressEventType::TaskError,
+            task_id,
+            conversation_id,
+            message,
+            None,
+        );
+    }
+
+    async fn report_step_start(
+        &self,
+        task_id: &str,
+        conversation_id: &str,
+        step: u8,
+        message: &str,
+    ) {
+        self.emit(
+            ProgressCategory::General,
+            ProgressEventType::StepStart,
+            Some(task_id),
+            Some(conversation_id),
+            message,
+            Some(json!({ "step": step })),
+        );
+    }
+
+    async fn report_step_complete(
+        &self,
+        task_id: &str,
+        conversation_id: &str,
+        step: u8,
+        message: &str,
+    ) {
+        self.emit(
+            ProgressCategory::General,
+            ProgressEventType::StepComplete,
+            Some(task_id),
+            Some(conversation_id),
+            message,
+            Some(json!({ "step": step })),
+        );
+    }
+
+    async fn report_tool_call(
+        &self,
+        task_id: &str,
+        conversation_id: &str,
+        tool_name: &str,
+        message: &str,
+    ) {
+        self.emit(
+            ProgressCategory::Tool,
+            ProgressEventType::ToolCall,
+            Some(task_id),
+            Some(conversation_id),
+            message,
+            Some(json!({ "tool_name": tool_name })),
+        );
+    }
+
+    async fn report_tool_complete(
+        &self,
+        task_id: &str,
+        conversation_id: &str,
+        tool_name: &str,
+        message: &str,
+    ) {
+        self.emit(
+            ProgressCategory::Tool,
+            ProgressEventType::ToolComplete,
+            Some(task_id),
+            Some(conversation_id),
+            message,
+            Some(json!({ "tool_name": tool_name })),
+        );
+    }
+
+    async fn report_tool_error(
+        &self,
+        task_id: &str,
+        conversation_id: &str,
+        tool_name: &str,
+        message: &str,
+    ) {
+        self.emit(
+            ProgressCategory::Tool,
+            ProgressEventType::ToolError,
+            Some(task_id),
+            Some(conversation_id),
+            message,
+            Some(json!({ "tool_name": tool_name })),
+        );
+    }
+
+    async fn report_llm_request(&self, task_id: &str, conversation_id: &str, message: &str) {
+        self.emit(
+            ProgressCategory::LLM,
+            ProgressEventType::LlmRequest,
+            Some(task_id),
+            Some(conversation_id),
+            message,
+            None,
+        );
+    }
+
+    async fn report_llm_response(&self, task_id: &str, conversation_id: &str, message: &str) {
+        self.emit(
+            ProgressCategory::LLM,
+            ProgressEventType::LlmResponse,
+            Some(task_id),
+            Some(conversation_id),
+            message,
+            None,
+        );
+    }
+
+    async fn report_llm_error(&self, task_id: &str, conversation_id: &str, message: &str) {
+        self.emit(
+            ProgressCategory::LLM,
+            ProgressEventType::LlmError,
+            Some(task_id),
+            Some(conversation_id),
+            message,
+            None,
+        );
+    }
+
+    async fn report_validation_start(&self, task_id: &str, conversation_id: &str, message: &str) {
+        self.emit(
+            ProgressCategory::General,
+            ProgressEventType::ValidationStart,
+            Some(task_id),
+            Some(conversation_id),
+            message,
+            None,
+        );
+    }
+
+    async fn report_validation_complete(
+        &self,
+        task_id: &str,
+        conversation_id: &str,
+        message: &str,
+    ) {
+        self.emit(
+            ProgressCategory::General,
+            ProgressEventType::ValidationComplete,
+            Some(task_id),
+            Some(conversation_id),
+            message,
+            None,
+        );
+    }
+
+    async fn report_validation_error(&self, task_id: &str, conversation_id: &str, message: &str) {
+        self.emit(
+            ProgressCategory::General,
+            ProgressEventType::ValidationError,
+            Some(task_id),
+            Some(conversation_id),
+            message,
+            None,
+        );
+    }
+
+    async fn report_processing(&self, task_id: &str, conversation_id: &str, message: &str) {
+        self.emit(
+            ProgressCategory::General,
+            ProgressEventType::Processing,
+            Some(task_id),
+            Some(conversation_id),
+            message,
+            None,
+        );
+    }
+
+    async fn report_progress_percent(
+        &self,
+        task_id: &str,
+        conversation_id: &str,
+        percent: f32,
+        message: &str,
+    ) {
+        let percent = percent.clamp(0.0, 100.0);
+        let eta_seconds = self
+            .tasks
+            .lock()
+            .unwrap()
+            .get(task_id)
+            .and_then(|task| task.started)
+            .and_then(|started| estimate_eta(started.elapsed(), percent));
+        let mut progress = ProgressMessage::new(
+            self.agent_id.clone(),
+            ProgressCategory::General,
+            ProgressEventType::PercentComplete,
+            message.to_string(),
+        )
+        .with_task_context(Some(task_id.to_string()), Some(conversation_id.to_string()))
+        .with_percent(percent, eta_seconds);
+        if let Some(task) = self.tasks.lock().unwrap().get(task_id) {
+            progress =
+                progress.with_correlation(task.correlation_id.clone(), task.parent_task_id.clone());
+        }
+        self.sink.send(progress);
+    }
+
+    async fn report_custom(
+        &self,
+        category: ProgressCategory,
+        event_type: ProgressEventType,
+        task_id: Option<&str>,
+        conversation_id: Option<&str>,
+        message: &str,
+        metadata: Option<serde_json::Value>,
+    ) {
+        self.emit(
+            category,
+            event_type,
+            task_id,
+            conversation_id,
+            message,
+            metadata,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sink keeping every message it receives
+    #[derive(Default)]
+    struct Collect(Mutex<Vec<ProgressMessage>>);
+
+    impl ProgressSink for Collect {
+        fn send(&self, message: ProgressMessage) {
+            self.0.lock().unwrap().push(message);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_messages_carry_task_correlation() {
+        let progress = SinkProgress::new("agent-1".to_string(), Collect::default());
+
+        progress
+            .register_correlation("task-1", "corr-1", Some("parent-1"))
+            .await;
+        progress
+            .report_tool_call("task-1", "conv-1", "web_search", "Searching")
+            .await;
+        progress
+            .report_progress_percent("task-1", "conv-1", 150.0, "Past the end")
+            .await;
+        progress.clear_correlation("task-1").await;
+        progress
+            .report_processing("task-1", "conv-1", "Afterwards")
+            .await;
+
+        let messages = progress.sink.0.lock().unwrap();
+        assert_eq!(messages[0].agent_id, "agent-1");
+        assert_eq!(messages[0].event_type, ProgressEventType::ToolCall);
+        assert_eq!(messages[0].correlation_id.as_deref(), Some("corr-1"));
+        assert_eq!(
+            messages[0].metadata.as_ref().unwrap()["tool_name"],
+            "web_search"
+        );
+        assert_eq!(messages[1].percent, Some(100.0));
+        assert_eq!(messages[1].parent_task_id.as_deref(), Some("parent-1"));
+        assert_eq!(messages[2].correlation_id, None);
+        assert!(progress.tasks.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_percent_has_eta_once_task_started() {
+        let progress = SinkProgress::new("agent-1".to_string(), Collect::default());
+
+        progress
+            .report_progress_percent("task-1", "conv-1", 10.0, "Unknown start")
+            .await;
+        progress
+            .report_task_start("task-1", "conv-1", "Start")
+            .await;
+        progress
+            .report_progress_percent("task-1", "conv-1", 50.0, "Halfway")
+            .await;
+
+        let messages = progress.sink.0.lock().unwrap();
+        assert_eq!(messages[0].eta_seconds, None);
+        assert_eq!(messages[2].eta_seconds, Some(0));
+    }
+}
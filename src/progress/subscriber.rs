@@ -0,0 +1,121 @@
+//! Following one conversation's progress
+//!
+//! With `ProgressConfig::conversation_topics` enabled, every agent also
+//! publishes progress on `/conversations/{conversation_id}/progress`.
+//! `ProgressSubscriber` subscribes to that topic through a `Transport` and
+//! yields the decoded `ProgressMessage`s as a stream, optionally narrowed to
+//! one task, so clients need not subscribe to every agent and filter.
+
+use super::ProgressMessage;
+use crate::transport::mqtt::TopicBuilder;
+use crate::transport::Transport;
+use futures::stream::{self, BoxStream, StreamExt};
+use tracing::debug;
+
+/// Stream of progress messages from a subscription
+pub type ProgressStream = BoxStream<'static, ProgressMessage>;
+
+/// Subscription to the progress of one conversation
+#[derive(Debug, Clone)]
+pub struct ProgressSubscriber {
+    conversation_id: String,
+    task_id: Option<String>,
+}
+
+impl ProgressSubscriber {
+    pub fn new(conversation_id: impl Into<String>) -> Self {
+        Self {
+            conversation_id: conversation_id.into(),
+            task_id: None,
+        }
+    }
+
+    /// Only yield events of this task
+    pub fn with_task_id(mut self, task_id: impl Into<String>) -> Self {
+        self.task_id = Some(task_id.into());
+        self
+    }
+
+    /// Topic the subscription listens on
+    pub fn topic(&self) -> String {
+        TopicBuilder::build_conversation_progress_topic(&self.conversation_id)
+    }
+
+    /// Subscribe and stream matching progress messages
+    ///
+    /// Batched payloads are split into their events. Payloads that are not
+    /// progress messages are skipped. The stream ends when the transport
+    /// drops the subscription.
+    pub async fn subscribe<T: Transport + ?Sized>(
+        self,
+        transport: &T,
+    ) -> Result<ProgressStream, T::Error> {
+        let receiver = transport.subscribe_topic(&self.topic()).await?;
+        let task_id = self.task_id;
+
+        let payloads = stream::unfold(receiver, |mut receiver| async move {
+            receiver.recv().await.map(|message| (message, receiver))
+        });
+        Ok(payloads
+            .flat_map(move |(topic, payload)| {
+                let messages = decode_progress_payload(&payload).unwrap_or_else(|e| {
+                    debug!("Ignoring malformed progress payload on {}: {}", topic, e);
+                    Vec::new()
+                });
+                let task_id = task_id.clone();
+                stream::iter(
+                    messages
+                        .into_iter()
+                        .filter(move |message| task_id.is_none() || message.task_id == task_id),
+                )
+            })
+            .boxed())
+    }
+}
+
+/// Events in a progress payload: one JSON object, or an array of a batch
+pub fn decode_progress_payload(payload: &[u8]) -> Result<Vec<ProgressMessage>, serde_json::Error> {
+    match serde_json::from_slice::<serde_json::Value>(payload)? {
+        serde_json::Value::Array(events) => {
+            events.into_iter().map(serde_json::from_value).collect()
+        }
+        event => Ok(vec![serde_json::from_value(event)?]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::progress::{ProgressCategory, ProgressEventType};
+
+    fn message(task_id: &str) -> ProgressMessage {
+        ProgressMessage::new(
+            "agent-1".to_string(),
+            ProgressCategory::General,
+            ProgressEventType::Processing,
+            "working".to_string(),
+        )
+        .with_task_context(Some(task_id.to_string()), Some("conv-1".to_string()))
+    }
+
+    #[test]
+    fn test_decode_single_and_batched_payloads() {
+        let single = serde_json::to_vec(&message("task-1")).unwrap();
+        assert_eq!(decode_progress_payload(&single).unwrap().len(), 1);
+
+        let batch = serde_json::to_vec(&vec![message("task-1"), message("task-2")]).unwrap();
+        let decoded = decode_progress_payload(&batch).unwrap();
+        assert_eq!(decoded[1].task_id.as_deref(), Some("task-2"));
+
+        assert!(decode_progress_payload(b"{\"not\": \"progress\"}").is_err());
+        assert!(decode_progress_payload(b"not json").is_err());
+    }
+
+    #[test]
+    fn test_topic_goes_through_topic_builder() {
+        assert_eq!(
+            ProgressSubscriber::new("//conv-1//").topic(),
+            "/conversations/conv-1/progress"
+        );
+    }
+}
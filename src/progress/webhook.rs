@@ -0,0 +1,402 @@
+//! Webhook notifications for progress events
+//!
+//! `WebhookNotifier` POSTs a JSON payload for each progress event that passes
+//! its filter, so operators get a Slack ping when a workflow fails without
+//! running MQTT-to-Slack glue. The payload is rendered from a template with
+//! `{{field}}` placeholders; the default is a Slack incoming-webhook message.
+//!
+//! Notifications never hold up a task: filtering and rate limiting happen
+//! when the event is reported, and delivery runs on a worker of its own that
+//! retries 5xx answers and network errors, then logs and drops the
+//! notification.
+
+use super::sink::{ProgressSink, SinkProgress};
+use super::{ProgressEventType, ProgressMessage};
+use crate::config::WebhookSinkConfig;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tracing::{debug, warn};
+
+/// Payload sent unless `[progress.sinks.webhook] template` replaces it
+pub const DEFAULT_WEBHOOK_TEMPLATE: &str = r#"{"text": "[{{severity}}] {{agent_id}} {{event_type}}: {{message}} (conversation {{conversation_id}}, task {{task_id}})"}"#;
+
+/// Notifications waiting for delivery before new ones are dropped
+const WEBHOOK_QUEUE_CAPACITY: usize = 64;
+
+/// Time one delivery attempt may take
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Progress reporter POSTing matching events to a webhook
+pub type WebhookProgress = SinkProgress<WebhookNotifier>;
+
+impl WebhookProgress {
+    /// Must be called within a Tokio runtime; spawns the delivery worker
+    pub fn spawn(agent_id: String, url: String, config: &WebhookSinkConfig) -> Self {
+        Self::new(agent_id, WebhookNotifier::spawn(url, config))
+    }
+}
+
+/// How serious a progress event is, for filtering notifications
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum NotificationSeverity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl NotificationSeverity {
+    /// Severity of an event type (pure function)
+    pub fn of(event_type: &ProgressEventType) -> Self {
+        match event_type {
+            ProgressEventType::TaskError
+            | ProgressEventType::ToolError
+            | ProgressEventType::LlmError
+            | ProgressEventType::ValidationError => Self::Error,
+            ProgressEventType::Warning => Self::Warning,
+            _ => Self::Info,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Info => "info",
+            Self::Warning => "warning",
+            Self::Error => "error",
+        }
+    }
+}
+
+/// Allows at most `max` events in any window of `window`
+#[derive(Debug)]
+pub struct RateLimiter {
+    max: usize,
+    window: Duration,
+    allowed: VecDeque<Instant>,
+}
+
+impl RateLimiter {
+    pub fn new(max: usize, window: Duration) -> Self {
+        Self {
+            max,
+            window,
+            allowed: VecDeque::with_capacity(max),
+        }
+    }
+
+    /// Whether an event at `now` is within the limit, counting it if so
+    pub fn try_acquire(&mut self, now: Instant) -> bool {
+        while self
+            .allowed
+            .front()
+            .is_some_and(|&allowed| now.duration_since(allowed) >= self.window)
+        {
+            self.allowed.pop_front();
+        }
+        if self.allowed.len() >= self.max {
+            return false;
+        }
+        self.allowed.push_back(now);
+        true
+    }
+}
+
+/// Render `template` for `message`, JSON-escaping every substituted value (pure function)
+///
+/// Placeholders are `{{agent_id}}`, `{{task_id}}`, `{{conversation_id}}`,
+/// `{{correlation_id}}`, `{{category}}`, `{{event_type}}`, `{{severity}}`,
+/// `{{message}}` and `{{timestamp}}`; a field the event lacks renders as
+/// `-`. Unknown placeholders are left as they are.
+pub fn render_template(template: &str, message: &ProgressMessage) -> String {
+    let mut rendered = String::with_capacity(template.len() + message.message.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        rendered.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            rest = &rest[start..];
+            break;
+        };
+        match placeholder_value(after[..end].trim(), message) {
+            Some(value) => rendered.push_str(&json_escape(&value)),
+            None => rendered.push_str(&rest[start..start + 2 + end + 2]),
+        }
+        rest = &after[end + 2..];
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+/// Check that `template` renders to valid JSON (pure function)
+pub fn validate_template(template: &str) -> Result<(), serde_json::Error> {
+    let sample = ProgressMessage::new(
+        "agent".to_string(),
+        super::ProgressCategory::General,
+        ProgressEventType::TaskError,
+        "message with \"quotes\"\nand a newline".to_string(),
+    );
+    serde_json::from_str::<serde_json::Value>(&render_template(template, &sample)).map(|_| ())
+}
+
+fn placeholder_value(name: &str, message: &ProgressMessage) -> Option<String> {
+    let optional = |value: &Option<String>| value.clone().unwrap_or_else(|| "-".to_string());
+    Some(match name {
+        "agent_id" => message.agent_id.clone(),
+        "task_id" => optional(&message.task_id),
+        "conversation_id" => optional(&message.conversation_id),
+        "correlation_id" => optional(&message.correlation_id),
+        "category" => format!("{:?}", message.category),
+        "event_type" => format!("{:?}", message.event_type),
+        "severity" => NotificationSeverity::of(&message.event_type)
+            .as_str()
+            .to_string(),
+        "message" => message.message.clone(),
+        "timestamp" => message.timestamp.to_rfc3339(),
+        _ => return None,
+    })
+}
+
+/// `value` as the inside of a JSON string
+fn json_escape(value: &str) -> String {
+    let quoted = serde_json::Value::String(value.to_string()).to_string();
+    quoted[1..quoted.len() - 1].to_string()
+}
+
+/// Sink POSTing a rendered payload for each event passing the filter
+pub struct WebhookNotifier {
+    config: WebhookSinkConfig,
+    template: String,
+    limiter: Mutex<RateLimiter>,
+    deliveries: mpsc::Sender<String>,
+}
+
+impl WebhookNotifier {
+    /// Must be called within a Tokio runtime; spawns the delivery worker
+    pub fn spawn(url: String, config: &WebhookSinkConfig) -> Self {
+        let (deliveries, receiver) = mpsc::channel(WEBHOOK_QUEUE_CAPACITY);
+        tokio::spawn(run_deliveries(
+            url,
+            config.max_retries,
+            Duration::from_millis(config.retry_delay_ms),
+            receiver,
+        ));
+        Self {
+            template: config
+                .template
+                .clone()
+                .unwrap_or_else(|| DEFAULT_WEBHOOK_TEMPLATE.to_string()),
+            limiter: Mutex::new(RateLimiter::new(
+                config.max_per_minute as usize,
+                Duration::from_secs(60),
+            )),
+            config: config.clone(),
+            deliveries,
+        }
+    }
+
+    /// Whether the event passes the category, event type and severity filters
+    pub fn accepts(&self, message: &ProgressMessage) -> bool {
+        let config = &self.config;
+        (config.categories.is_empty() || config.categories.contains(&message.category))
+            && (config.event_types.is_empty() || config.event_types.contains(&message.event_type))
+            && NotificationSeverity::of(&message.event_type) >= config.min_severity
+    }
+}
+
+impl ProgressSink for WebhookNotifier {
+    fn send(&self, message: ProgressMessage) {
+        if !self.accepts(&message) {
+            return;
+        }
+        if !self.limiter.lock().unwrap().try_acquire(Instant::now()) {
+            debug!(
+                event_type = ?message.event_type,
+                "Webhook notification dropped by the rate limit"
+            );
+            return;
+        }
+        let payload = render_template(&self.template, &message);
+        if self.deliveries.try_send(payload).is_err() {
+            warn!("Webhook notification queue full, dropping notification");
+        }
+    }
+}
+
+/// Deliver queued payloads one at a time until the notifier is dropped
+async fn run_deliveries(
+    url: String,
+    max_retries: u32,
+    retry_delay: Duration,
+    mut payloads: mpsc::Receiver<String>,
+) {
+    let client = reqwest::Client::builder()
+        .timeout(WEBHOOK_TIMEOUT)
+        .build()
+        .unwrap_or_default();
+    while let Some(payload) = payloads.recv().await {
+        deliver(&client, &url, payload, max_retries, retry_delay).await;
+    }
+}
+
+/// POST one payload, retrying 5xx answers and network errors with backoff
+async fn deliver(
+    client: &reqwest::Client,
+    url: &str,
+    payload: String,
+    max_retries: u32,
+    retry_delay: Duration,
+) {
+    for attempt in 0..=max_retries {
+        let error = match client
+            .post(url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(payload.clone())
+            .send()
+            .await
+        {
+            Ok(response) if response.status().is_success() => return,
+            Ok(response) if response.status().is_server_error() => {
+                format!("server error: {}", response.status())
+            }
+            Ok(response) => {
+                // The webhook rejected the payload; sending it again won't help
+                warn!(status = %response.status(), "Webhook rejected notification");
+                return;
+            }
+            // The URL is a secret, so reqwest's error text (which includes it) isn't logged
+            Err(e) if e.is_timeout() => "timed out".to_string(),
+            Err(_) => "network error".to_string(),
+        };
+        if attempt == max_retries {
+            warn!(
+                error = %error,
+                attempts = attempt + 1,
+                "Webhook notification failed, dropping it"
+            );
+            return;
+        }
+        debug!(error = %error, attempt = attempt + 1, "Webhook notification failed, retrying");
+        tokio::time::sleep(retry_delay.saturating_mul(2_u32.saturating_pow(attempt))).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::progress::ProgressCategory;
+
+    fn message(event_type: ProgressEventType, text: &str) -> ProgressMessage {
+        ProgressMessage::new(
+            "agent-1".to_string(),
+            ProgressCategory::General,
+            event_type,
+            text.to_string(),
+        )
+        .with_task_context(Some("task-1".to_string()), Some("conv-1".to_string()))
+    }
+
+    fn config() -> WebhookSinkConfig {
+        toml::from_str(r#"url_env = "WEBHOOK_URL""#).unwrap()
+    }
+
+    #[test]
+    fn test_severity_of_event_types() {
+        use ProgressEventType::*;
+        for event in [TaskError, ToolError, LlmError, ValidationError] {
+            assert_eq!(
+                NotificationSeverity::of(&event),
+                NotificationSeverity::Error
+            );
+        }
+        assert_eq!(
+            NotificationSeverity::of(&Warning),
+            NotificationSeverity::Warning
+        );
+        for event in [TaskStart, TaskComplete, ToolCall, Custom] {
+            assert_eq!(NotificationSeverity::of(&event), NotificationSeverity::Info);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_filter_by_category_event_type_and_severity() {
+        let notifier = WebhookNotifier::spawn("http://127.0.0.1:9".to_string(), &config());
+        // The default notifies about errors of every kind only
+        assert!(notifier.accepts(&message(ProgressEventType::TaskError, "failed")));
+        assert!(notifier.accepts(&message(ProgressEventType::ToolError, "failed")));
+        assert!(!notifier.accepts(&message(ProgressEventType::Warning, "careful")));
+        assert!(!notifier.accepts(&message(ProgressEventType::TaskComplete, "done")));
+
+        let mut completions = config();
+        completions.event_types = vec![ProgressEventType::TaskComplete];
+        completions.min_severity = NotificationSeverity::Info;
+        let notifier = WebhookNotifier::spawn("http://127.0.0.1:9".to_string(), &completions);
+        assert!(notifier.accepts(&message(ProgressEventType::TaskComplete, "done")));
+        assert!(!notifier.accepts(&message(ProgressEventType::TaskError, "failed")));
+
+        let mut tools = config();
+        tools.categories = vec![ProgressCategory::Tool];
+        let notifier = WebhookNotifier::spawn("http://127.0.0.1:9".to_string(), &tools);
+        let mut tool_error = message(ProgressEventType::ToolError, "failed");
+        tool_error.category = ProgressCategory::Tool;
+        assert!(notifier.accepts(&tool_error));
+        assert!(!notifier.accepts(&message(ProgressEventType::TaskError, "failed")));
+    }
+
+    #[test]
+    fn test_default_template_renders_a_slack_message() {
+        let rendered = render_template(
+            DEFAULT_WEBHOOK_TEMPLATE,
+            &message(ProgressEventType::TaskError, "LLM unavailable"),
+        );
+
+        let payload: serde_json::Value = serde_json::from_str(&rendered).unwrap();
+        assert_eq!(
+            payload["text"],
+            "[error] agent-1 TaskError: LLM unavailable (conversation conv-1, task task-1)"
+        );
+    }
+
+    #[test]
+    fn test_template_escapes_values_and_keeps_unknown_placeholders() {
+        let mut event = message(ProgressEventType::TaskError, "said \"no\"\n{{agent_id}}");
+        event.task_id = None;
+
+        let rendered = render_template(
+            r#"{"m": "{{ message }}", "t": "{{task_id}}", "x": "{{unknown}}", "o": "{{open"}"#,
+            &event,
+        );
+
+        let payload: serde_json::Value = serde_json::from_str(&rendered).unwrap();
+        // Placeholders inside values are not expanded again
+        assert_eq!(payload["m"], "said \"no\"\n{{agent_id}}");
+        assert_eq!(payload["t"], "-");
+        assert_eq!(payload["x"], "{{unknown}}");
+        assert_eq!(payload["o"], "{{open");
+    }
+
+    #[test]
+    fn test_validate_template() {
+        assert!(validate_template(DEFAULT_WEBHOOK_TEMPLATE).is_ok());
+        assert!(validate_template(r#"{"text": {{message}}}"#).is_err());
+    }
+
+    #[test]
+    fn test_rate_limiter_allows_max_per_window() {
+        let start = Instant::now();
+        let mut limiter = RateLimiter::new(2, Duration::from_secs(60));
+
+        assert!(limiter.try_acquire(start));
+        assert!(limiter.try_acquire(start + Duration::from_secs(10)));
+        assert!(!limiter.try_acquire(start + Duration::from_secs(20)));
+        // The first event leaves the window, the second doesn't yet
+        assert!(limiter.try_acquire(start + Duration::from_secs(60)));
+        assert!(!limiter.try_acquire(start + Duration::from_secs(65)));
+        assert!(limiter.try_acquire(start + Duration::from_secs(70)));
+    }
+}
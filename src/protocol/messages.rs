@@ -3,9 +3,11 @@
 //! This module defines all message structures used for agent communication,
 //! including task envelopes, agent status, and error messages.
 
+use crate::protocol::workflow_state::{merge_state, MAX_WORKFLOW_STATE_BYTES};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{Map, Value};
+use tracing::{debug, warn};
 use uuid::Uuid;
 
 /// Task envelope containing all task information
@@ -26,6 +28,12 @@ use uuid::Uuid;
 ///     instruction: Some("Process this data".to_string()),
 ///     input: json!({"key": "value"}),
 ///     next: None,
+///     deadline: None,
+///     correlation_id: None,
+///     parent_task_id: None,
+///     published_at: None,
+///     traceparent: None,
+///     sender_id: None,
 /// };
 /// ```
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -42,6 +50,24 @@ pub struct TaskEnvelope {
     pub input: Value,
     /// Next agent in pipeline (optional)
     pub next: Option<Box<NextTask>>,
+    /// RFC 3339 deadline after which the task should not be processed (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deadline: Option<DateTime<Utc>>,
+    /// Workflow identifier set once at workflow start and propagated verbatim (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<String>,
+    /// Task that caused this one to be created (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_task_id: Option<Uuid>,
+    /// RFC 3339 time the envelope was published, used to reject stale redeliveries (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub published_at: Option<DateTime<Utc>>,
+    /// W3C `traceparent` of the span that published the envelope, joining agents' traces (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub traceparent: Option<String>,
+    /// Client or agent that published the envelope, checked by `[authorization]` rules (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sender_id: Option<String>,
 }
 
 /// TaskEnvelope v2.0 with workflow context and simplified routing
@@ -72,8 +98,18 @@ pub struct TaskEnvelope {
 ///             }
 ///         ],
 ///         iteration_count: 1,
+///         started_at: None,
+///         budget_secs: None,
+///         state: None,
+///         state_order: Vec::new(),
 ///     }),
 ///     routing_trace: None,
+///     deadline: None,
+///     correlation_id: None,
+///     parent_task_id: None,
+///     published_at: None,
+///     traceparent: None,
+///     sender_id: None,
 /// };
 /// ```
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -97,6 +133,143 @@ pub struct TaskEnvelopeV2 {
     pub context: Option<WorkflowContext>,
     /// Trace of routing decisions for debugging and observability
     pub routing_trace: Option<Vec<RoutingStep>>,
+    /// RFC 3339 deadline after which the task should not be processed (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deadline: Option<DateTime<Utc>>,
+    /// Workflow identifier set once at workflow start and propagated verbatim (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<String>,
+    /// Task that caused this one to be created (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_task_id: Option<Uuid>,
+    /// RFC 3339 time the envelope was published, used to reject stale redeliveries (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub published_at: Option<DateTime<Utc>>,
+    /// W3C `traceparent` of the span that published the envelope, joining agents' traces (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub traceparent: Option<String>,
+    /// Client or agent that published the envelope, checked by `[authorization]` rules (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sender_id: Option<String>,
+}
+
+impl TaskEnvelope {
+    /// Upgrade to a v2.0 envelope with a freshly synthesized workflow context
+    ///
+    /// The context's `original_query` is taken from `original_query`, falling back to
+    /// the task instruction; if neither is available no context is attached.
+    pub fn upgrade(self, original_query: Option<String>) -> TaskEnvelopeV2 {
+        let context = original_query
+            .or_else(|| self.instruction.clone())
+            .map(|original_query| WorkflowContext {
+                original_query,
+                steps_completed: Vec::new(),
+                iteration_count: 0,
+                started_at: None,
+                budget_secs: None,
+                state: None,
+                state_order: Vec::new(),
+            });
+
+        DroppedV2Fields {
+            version: "2.0".to_string(),
+            context,
+            routing_trace: None,
+        }
+        .reattach(self)
+    }
+}
+
+impl TaskEnvelopeV2 {
+    /// Downgrade to a v1.0 envelope, returning the v2.0-only fields separately
+    ///
+    /// Nothing is lost: the returned fields can be logged or re-attached with
+    /// [`DroppedV2Fields::reattach`] to recover the original envelope.
+    pub fn downgrade(self) -> (TaskEnvelope, DroppedV2Fields) {
+        let dropped = DroppedV2Fields {
+            version: self.version,
+            context: self.context,
+            routing_trace: self.routing_trace,
+        };
+        let task = TaskEnvelope {
+            task_id: self.task_id,
+            conversation_id: self.conversation_id,
+            topic: self.topic,
+            instruction: self.instruction,
+            input: self.input,
+            next: self.next,
+            deadline: self.deadline,
+            correlation_id: self.correlation_id,
+            parent_task_id: self.parent_task_id,
+            published_at: self.published_at,
+            traceparent: self.traceparent,
+            sender_id: self.sender_id,
+        };
+        (task, dropped)
+    }
+
+    /// Append a routing decision to the trace, keeping only the most recent steps
+    ///
+    /// The trace is capped at [`MAX_ROUTING_TRACE_STEPS`] so long-running
+    /// workflows cannot grow envelopes without bound.
+    pub fn push_routing_step(&mut self, step: RoutingStep) {
+        let trace = self.routing_trace.get_or_insert_with(Vec::new);
+        trace.push(step);
+        if trace.len() > MAX_ROUTING_TRACE_STEPS {
+            let overflow = trace.len() - MAX_ROUTING_TRACE_STEPS;
+            trace.drain(0..overflow);
+        }
+    }
+
+    /// Step number for the next routing decision appended to the trace
+    pub fn next_routing_step_number(&self) -> u32 {
+        self.routing_trace
+            .as_ref()
+            .and_then(|trace| trace.last())
+            .map_or(1, |step| step.step_number + 1)
+    }
+}
+
+/// Maximum number of routing steps kept in a TaskEnvelopeV2 routing trace
+pub const MAX_ROUTING_TRACE_STEPS: usize = 100;
+
+/// v2.0-only fields removed by [`TaskEnvelopeV2::downgrade`]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DroppedV2Fields {
+    /// Protocol version of the original envelope
+    pub version: String,
+    /// Workflow context, if any
+    pub context: Option<WorkflowContext>,
+    /// Routing trace, if any
+    pub routing_trace: Option<Vec<RoutingStep>>,
+}
+
+impl DroppedV2Fields {
+    /// Check whether downgrading dropped any workflow data
+    pub fn is_empty(&self) -> bool {
+        self.context.is_none() && self.routing_trace.is_none()
+    }
+
+    /// Re-attach the v2.0 fields to a v1.0 envelope
+    pub fn reattach(self, task: TaskEnvelope) -> TaskEnvelopeV2 {
+        TaskEnvelopeV2 {
+            task_id: task.task_id,
+            conversation_id: task.conversation_id,
+            topic: task.topic,
+            instruction: task.instruction,
+            input: task.input,
+            next: task.next,
+            version: self.version,
+            context: self.context,
+            routing_trace: self.routing_trace,
+            deadline: task.deadline,
+            correlation_id: task.correlation_id,
+            parent_task_id: task.parent_task_id,
+            published_at: task.published_at,
+            traceparent: task.traceparent,
+            sender_id: task.sender_id,
+        }
+    }
 }
 
 /// Context accumulated across multi-agent workflow
@@ -107,8 +280,109 @@ pub struct WorkflowContext {
     /// Steps completed so far
     pub steps_completed: Vec<WorkflowStep>,
     /// Current iteration count (safety counter to prevent infinite loops)
-    #[serde(default)]
-    pub iteration_count: usize,
+    ///
+    /// A missing count reads as 0; negative or oversized counts are clamped.
+    #[serde(default, deserialize_with = "deserialize_iteration_count")]
+    pub iteration_count: u32,
+    /// When the workflow started, set by the agent that synthesized the context
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<DateTime<Utc>>,
+    /// Wall-clock seconds the workflow may run from `started_at`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub budget_secs: Option<u64>,
+    /// Key-value state shared by the workflow's agents (see
+    /// [`crate::protocol::workflow_state`])
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state: Option<Map<String, Value>>,
+    /// Keys of `state`, least recently written first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub state_order: Vec<String>,
+}
+
+impl WorkflowContext {
+    /// Whether the workflow has run for its whole wall-clock budget (pure function)
+    ///
+    /// A context without a start time or a budget never runs out.
+    pub fn budget_exhausted(&self, now: DateTime<Utc>) -> bool {
+        match (self.started_at, self.budget_secs) {
+            (Some(started_at), Some(budget_secs)) => {
+                u64::try_from((now - started_at).num_seconds())
+                    .is_ok_and(|elapsed| elapsed >= budget_secs)
+            }
+            _ => false,
+        }
+    }
+
+    /// Count a forward to the next agent and record the step that led to it
+    ///
+    /// History is capped at [`MAX_WORKFLOW_HISTORY_STEPS`], dropping the
+    /// oldest steps first.
+    pub fn record_forward(&mut self, agent_id: &str, action: String, now: DateTime<Utc>) {
+        self.iteration_count = self.iteration_count.saturating_add(1);
+        self.steps_completed.push(WorkflowStep {
+            agent_id: agent_id.to_string(),
+            action,
+            timestamp: now.to_rfc3339(),
+        });
+        if self.steps_completed.len() > MAX_WORKFLOW_HISTORY_STEPS {
+            let overflow = self.steps_completed.len() - MAX_WORKFLOW_HISTORY_STEPS;
+            self.steps_completed.drain(0..overflow);
+        }
+    }
+
+    /// Merge an agent's `state_updates` into the workflow state
+    ///
+    /// The state is capped at [`MAX_WORKFLOW_STATE_BYTES`], evicting the
+    /// least recently written keys; an emptied state is dropped. Returns the
+    /// evicted keys.
+    pub fn merge_state(&mut self, updates: &Map<String, Value>) -> Vec<String> {
+        let state = self.state.get_or_insert_with(Map::new);
+        let evicted = merge_state(
+            state,
+            &mut self.state_order,
+            updates,
+            MAX_WORKFLOW_STATE_BYTES,
+        );
+        if state.is_empty() {
+            self.state = None;
+        }
+        evicted
+    }
+}
+
+/// Maximum number of workflow steps kept in a WorkflowContext to prevent unbounded growth
+pub const MAX_WORKFLOW_HISTORY_STEPS: usize = 100;
+
+/// Iteration count as another implementation may have sent it
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum WireIterationCount {
+    Unsigned(u64),
+    Signed(i64),
+}
+
+/// Read an iteration count, clamping values outside `u32` instead of failing
+///
+/// A bad counter should not cost the whole envelope: negative counts become 0
+/// and counts past `u32::MAX` saturate.
+fn deserialize_iteration_count<'de, D>(deserializer: D) -> Result<u32, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    match Option::<WireIterationCount>::deserialize(deserializer)? {
+        None => Ok(0),
+        Some(WireIterationCount::Unsigned(count)) => {
+            Ok(u32::try_from(count).unwrap_or_else(|_| {
+                warn!(count, "Workflow iteration_count above u32::MAX, clamping");
+                u32::MAX
+            }))
+        }
+        // Only negative counts fail to read as unsigned
+        Some(WireIterationCount::Signed(count)) => {
+            warn!(count, "Negative workflow iteration_count, clamping to 0");
+            Ok(0)
+        }
+    }
 }
 
 /// Single step in workflow history
@@ -171,6 +445,100 @@ impl TaskEnvelopeWrapper {
         }
     }
 
+    /// Get the deadline regardless of envelope version
+    pub fn deadline(&self) -> Option<DateTime<Utc>> {
+        match self {
+            TaskEnvelopeWrapper::V1(envelope) => envelope.deadline,
+            TaskEnvelopeWrapper::V2(envelope) => envelope.deadline,
+        }
+    }
+
+    /// Get the correlation_id regardless of envelope version
+    pub fn correlation_id(&self) -> Option<&str> {
+        match self {
+            TaskEnvelopeWrapper::V1(envelope) => envelope.correlation_id.as_deref(),
+            TaskEnvelopeWrapper::V2(envelope) => envelope.correlation_id.as_deref(),
+        }
+    }
+
+    /// Get the parent_task_id regardless of envelope version
+    pub fn parent_task_id(&self) -> Option<Uuid> {
+        match self {
+            TaskEnvelopeWrapper::V1(envelope) => envelope.parent_task_id,
+            TaskEnvelopeWrapper::V2(envelope) => envelope.parent_task_id,
+        }
+    }
+
+    /// Get the publish time regardless of envelope version
+    pub fn published_at(&self) -> Option<DateTime<Utc>> {
+        match self {
+            TaskEnvelopeWrapper::V1(envelope) => envelope.published_at,
+            TaskEnvelopeWrapper::V2(envelope) => envelope.published_at,
+        }
+    }
+
+    /// Stamp the time the envelope is published, replacing any earlier hop's
+    pub fn set_published_at(&mut self, published_at: DateTime<Utc>) {
+        match self {
+            TaskEnvelopeWrapper::V1(envelope) => envelope.published_at = Some(published_at),
+            TaskEnvelopeWrapper::V2(envelope) => envelope.published_at = Some(published_at),
+        }
+    }
+
+    /// Get the W3C traceparent regardless of envelope version
+    pub fn traceparent(&self) -> Option<&str> {
+        match self {
+            TaskEnvelopeWrapper::V1(envelope) => envelope.traceparent.as_deref(),
+            TaskEnvelopeWrapper::V2(envelope) => envelope.traceparent.as_deref(),
+        }
+    }
+
+    /// Record the trace context of the span publishing the envelope
+    pub fn set_traceparent(&mut self, traceparent: String) {
+        match self {
+            TaskEnvelopeWrapper::V1(envelope) => envelope.traceparent = Some(traceparent),
+            TaskEnvelopeWrapper::V2(envelope) => envelope.traceparent = Some(traceparent),
+        }
+    }
+
+    /// Return the correlation_id, generating one if the workflow starts here
+    pub fn ensure_correlation_id(&mut self) -> String {
+        self.ensure_correlation_id_with(false)
+    }
+
+    /// Like [`Self::ensure_correlation_id`], but with `deterministic` set a new
+    /// id is derived from the task id, so a rerun of the workflow gets the same one
+    pub fn ensure_correlation_id_with(&mut self, deterministic: bool) -> String {
+        let task_id = self.task_id();
+        let correlation_id = match self {
+            TaskEnvelopeWrapper::V1(envelope) => &mut envelope.correlation_id,
+            TaskEnvelopeWrapper::V2(envelope) => &mut envelope.correlation_id,
+        };
+        correlation_id
+            .get_or_insert_with(|| {
+                if deterministic {
+                    Uuid::new_v5(&task_id, b"correlation").to_string()
+                } else {
+                    Uuid::new_v4().to_string()
+                }
+            })
+            .clone()
+    }
+
+    /// Check whether the task deadline has passed at the given time (pure function)
+    /// Tasks without a deadline never expire
+    pub fn is_expired_at(&self, now: DateTime<Utc>) -> bool {
+        self.deadline().is_some_and(|deadline| now > deadline)
+    }
+
+    /// Age of the task at the given time, if the envelope carries a publish time (pure function)
+    ///
+    /// Publish times in the future, e.g. from clock skew, count as age zero.
+    pub fn age_at(&self, now: DateTime<Utc>) -> Option<chrono::Duration> {
+        self.published_at()
+            .map(|published_at| (now - published_at).max(chrono::Duration::zero()))
+    }
+
     /// Check if this is a v2.0 envelope
     pub fn is_v2(&self) -> bool {
         matches!(self, TaskEnvelopeWrapper::V2(_))
@@ -190,22 +558,41 @@ impl TaskEnvelopeWrapper {
                 version: "2.0".to_string(),
                 context: None,
                 routing_trace: None,
+                deadline: envelope.deadline,
+                correlation_id: envelope.correlation_id,
+                parent_task_id: envelope.parent_task_id,
+                published_at: envelope.published_at,
+                traceparent: envelope.traceparent,
+                sender_id: envelope.sender_id,
             },
         }
     }
 
     /// Convert to v1.0 envelope (loses v2.0-specific fields)
+    ///
+    /// Prefer [`TaskEnvelopeWrapper::into_parts`] when the v2.0 fields are still needed.
     pub fn to_v1(self) -> TaskEnvelope {
+        let (task, dropped) = self.into_parts();
+        if let Some(dropped) = dropped.filter(|d| !d.is_empty()) {
+            debug!(
+                task_id = %task.task_id,
+                dropped = ?dropped,
+                "Dropping v2.0 fields while converting envelope to v1.0"
+            );
+        }
+        task
+    }
+
+    /// Split into a v1.0 envelope plus the v2.0-only fields (lossless)
+    ///
+    /// The second element is `None` for v1.0 envelopes.
+    pub fn into_parts(self) -> (TaskEnvelope, Option<DroppedV2Fields>) {
         match self {
-            TaskEnvelopeWrapper::V1(envelope) => envelope,
-            TaskEnvelopeWrapper::V2(envelope) => TaskEnvelope {
-                task_id: envelope.task_id,
-                conversation_id: envelope.conversation_id,
-                topic: envelope.topic,
-                instruction: envelope.instruction,
-                input: envelope.input,
-                next: envelope.next,
-            },
+            TaskEnvelopeWrapper::V1(envelope) => (envelope, None),
+            TaskEnvelopeWrapper::V2(envelope) => {
+                let (task, dropped) = envelope.downgrade();
+                (task, Some(dropped))
+            }
         }
     }
 }
@@ -256,7 +643,7 @@ pub struct NextTask {
 ///     description: Some("AI research and writing agent".to_string()),
 /// };
 /// ```
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct AgentStatus {
     pub agent_id: String,
     pub status: AgentStatusType,
@@ -276,6 +663,55 @@ pub struct AgentStatus {
 pub enum AgentStatusType {
     Available,
     Unavailable,
+    /// Connected but not taking new tasks until resumed
+    Paused,
+}
+
+/// Envelope versions this implementation accepts
+pub const SUPPORTED_ENVELOPE_VERSIONS: [&str; 2] = ["1.0", "2.0"];
+
+/// Agent capability manifest (retained)
+///
+/// Published to `/control/agents/{agent_id}/manifest` at startup and whenever
+/// the agent's tool set changes. Carries the richer data routers need beyond
+/// the flat capability list in [`AgentStatus`].
+///
+/// # Examples
+/// ```
+/// use agent2389::protocol::{AgentManifest, SUPPORTED_ENVELOPE_VERSIONS};
+/// use chrono::Utc;
+///
+/// let manifest = AgentManifest {
+///     agent_id: "my-agent".to_string(),
+///     description: Some("AI research agent".to_string()),
+///     capabilities: vec!["research".to_string()],
+///     envelope_versions: SUPPORTED_ENVELOPE_VERSIONS.map(str::to_string).to_vec(),
+///     tools: vec!["web_search".to_string(), "http_request".to_string()],
+///     model: "claude-sonnet-4-20250514".to_string(),
+///     max_input_bytes: Some(65536),
+///     timestamp: Utc::now(),
+/// };
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AgentManifest {
+    pub agent_id: String,
+    /// Agent description (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+    /// TaskEnvelope versions the agent accepts, e.g. `["1.0", "2.0"]`
+    pub envelope_versions: Vec<String>,
+    /// Names of the tools available to the agent
+    #[serde(default)]
+    pub tools: Vec<String>,
+    /// LLM model in use
+    pub model: String,
+    /// Largest task input in bytes the agent accepts (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_input_bytes: Option<usize>,
+    /// RFC 3339 format with Z suffix
+    pub timestamp: DateTime<Utc>,
 }
 
 /// Error message format
@@ -291,14 +727,24 @@ pub enum AgentStatusType {
 ///     error: ErrorDetails {
 ///         code: ErrorCode::ToolExecutionFailed,
 ///         message: "HTTP request timeout".to_string(),
+///         retryable: false,
+///         retry_after_ms: None,
 ///     },
 ///     task_id: Uuid::new_v4(),
+///     correlation_id: None,
+///     parent_task_id: None,
 /// };
 /// ```
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct ErrorMessage {
     pub error: ErrorDetails,
     pub task_id: Uuid,
+    /// Correlation id of the workflow the failed task belongs to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<String>,
+    /// Parent of the failed task, if it was forwarded from another task
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_task_id: Option<Uuid>,
 }
 
 /// Agent response message format
@@ -314,33 +760,562 @@ pub struct ErrorMessage {
 /// let response = ResponseMessage {
 ///     response: "Hello! I processed your request successfully.".to_string(),
 ///     task_id: Uuid::new_v4(),
+///     routing_trace: None,
+///     correlation_id: None,
+///     parent_task_id: None,
+///     conversation_id: None,
+///     agent_id: None,
+///     completed_at: None,
+///     oversized: None,
+///     artifacts: Vec::new(),
 /// };
 /// ```
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct ResponseMessage {
     pub response: String,
     pub task_id: Uuid,
+    /// Routing decisions that led to this response (v2.0 workflows only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub routing_trace: Option<Vec<RoutingStep>>,
+    /// Correlation id of the workflow this response completes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<String>,
+    /// Parent of the completed task, if it was forwarded from another task
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_task_id: Option<Uuid>,
+    /// Conversation the response belongs to, so it is understood off its topic
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub conversation_id: Option<String>,
+    /// Agent that produced the response
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent_id: Option<String>,
+    /// When the agent finished the task
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completed_at: Option<DateTime<Utc>>,
+    /// Present when the response was too large to publish in full
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub oversized: Option<OversizedResponse>,
+    /// Structured outputs published alongside the prose `response`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub artifacts: Vec<Artifact>,
 }
 
-/// Error details structure
+/// Structured output of a task, such as a table or extracted entities
+///
+/// An agent attaches artifacts by adding an `artifacts` array to its decision,
+/// so downstream systems get machine-readable data without parsing it out of
+/// the prose response.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Artifact {
+    /// Name identifying the artifact within the response
+    pub name: String,
+    /// Media type of `data` (default: `application/json`)
+    #[serde(default = "default_artifact_content_type")]
+    pub content_type: String,
+    /// The artifact itself
+    pub data: Value,
+}
+
+fn default_artifact_content_type() -> String {
+    "application/json".to_string()
+}
+
+impl Artifact {
+    /// Size of the artifact's serialized `data` in bytes
+    pub fn data_bytes(&self) -> usize {
+        self.data.to_string().len()
+    }
+
+    /// The artifacts in a decision's `artifacts` array
+    ///
+    /// Entries that are not valid artifacts, or have an empty name, are
+    /// skipped with a warning rather than failing the whole decision; anything
+    /// but an array yields none.
+    pub fn from_json_list(value: &Value) -> Vec<Artifact> {
+        let Value::Array(entries) = value else {
+            if !value.is_null() {
+                warn!("Ignoring artifacts that are not an array");
+            }
+            return Vec::new();
+        };
+        entries
+            .iter()
+            .filter_map(
+                |entry| match serde_json::from_value::<Artifact>(entry.clone()) {
+                    Ok(artifact) if !artifact.name.trim().is_empty() => Some(artifact),
+                    Ok(_) => {
+                        warn!("Ignoring artifact without a name");
+                        None
+                    }
+                    Err(e) => {
+                        warn!(error = %e, "Ignoring malformed artifact");
+                        None
+                    }
+                },
+            )
+            .collect()
+    }
+}
+
+/// Deserialize an `artifacts` field with [`Artifact::from_json_list`]
+pub(crate) fn deserialize_artifacts<'de, D>(deserializer: D) -> Result<Vec<Artifact>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let value = Value::deserialize(deserializer)?;
+    Ok(Artifact::from_json_list(&value))
+}
+
+/// How a response larger than the agent's `max_response_bytes` was sent
+///
+/// `response` then holds either the truncated text, ending in a marker, or a
+/// note pointing at `reference`, where the full text was written.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct OversizedResponse {
+    /// Size of the full response text in bytes
+    pub original_bytes: usize,
+    /// File holding the full response; absent when it was truncated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reference: Option<String>,
+}
+
+/// Whether an agent took on a task
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AckStatus {
+    Accepted,
+    Rejected,
+}
+
+/// Task acknowledgement
+///
+/// Published to `/conversations/{conversation_id}/{agent_id}/ack` once a task
+/// passes validation (steps 1-6), or as a nack carrying the error when it is
+/// rejected, so producers can tell a lost task from a slow one.
+///
+/// # Examples
+/// ```
+/// use agent2389::protocol::{AckStatus, TaskAck};
+/// use uuid::Uuid;
+///
+/// let ack = TaskAck::accepted(Uuid::new_v4(), "research-agent", 0);
+/// assert_eq!(ack.status, AckStatus::Accepted);
+/// assert!(ack.error.is_none());
+/// ```
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskAck {
+    pub task_id: Uuid,
+    pub agent_id: String,
+    pub status: AckStatus,
+    /// When the agent accepted or rejected the task
+    pub accepted_at: DateTime<Utc>,
+    /// Number of other tasks the agent was already working on
+    pub queue_position: usize,
+    /// Reason for a rejection
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<ErrorDetails>,
+    /// Correlation id of the workflow the task belongs to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<String>,
+}
+
+impl TaskAck {
+    /// Acknowledge a task the agent has started working on
+    pub fn accepted(task_id: Uuid, agent_id: impl Into<String>, queue_position: usize) -> Self {
+        Self {
+            task_id,
+            agent_id: agent_id.into(),
+            status: AckStatus::Accepted,
+            accepted_at: Utc::now(),
+            queue_position,
+            error: None,
+            correlation_id: None,
+        }
+    }
+
+    /// Reject a task that failed validation
+    pub fn rejected(task_id: Uuid, agent_id: impl Into<String>, error: ErrorDetails) -> Self {
+        Self {
+            task_id,
+            agent_id: agent_id.into(),
+            status: AckStatus::Rejected,
+            accepted_at: Utc::now(),
+            queue_position: 0,
+            error: Some(error),
+            correlation_id: None,
+        }
+    }
+
+    /// Attach the workflow correlation id
+    pub fn with_correlation_id(mut self, correlation_id: Option<String>) -> Self {
+        self.correlation_id = correlation_id;
+        self
+    }
+}
+
+/// Would-be routing decision published in routing dry-run mode
+///
+/// Published to `/control/agents/{agent_id}/routing/explain` instead of acting
+/// on the decision, so a router can be evaluated against live traffic before it
+/// is allowed to forward tasks.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RoutingExplanation {
+    pub task_id: Uuid,
+    pub conversation_id: String,
+    /// Agent whose router made the decision
+    pub agent_id: String,
+    /// Whether the router would have completed the workflow
+    pub workflow_complete: bool,
+    /// Agent the router would have forwarded to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_agent: Option<String>,
+    /// Instruction the router would have sent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_instruction: Option<String>,
+    /// Router's own explanation, when it provides one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reasoning: Option<String>,
+    /// Workflow iterations completed before this decision
+    pub iteration_count: u32,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Task cancellation request
+///
+/// Published to `/control/agents/{agent_id}/cancel` to abort an in-flight task.
+/// Cancelling a task that is unknown or already completed is a no-op.
+///
+/// # Examples
+/// ```
+/// use agent2389::protocol::CancelMessage;
+/// use uuid::Uuid;
+///
+/// let cancel = CancelMessage {
+///     task_id: Uuid::new_v4(),
+///     conversation_id: "test-conversation".to_string(),
+///     reason: Some("User aborted workflow".to_string()),
+/// };
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CancelMessage {
+    pub task_id: Uuid,
+    pub conversation_id: String,
+    /// Human-readable reason for cancellation (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// Administrative control message
+///
+/// Published to `/control/agents/{agent_id}/admin` to pause or resume task
+/// intake without disconnecting the agent, e.g. during broker maintenance.
+///
+/// # Examples
+/// ```
+/// use agent2389::protocol::{AdminMessage, PauseAgent};
+///
+/// let pause: AdminMessage = serde_json::from_str(
+///     r#"{"type": "pause_agent", "drain": true, "auto_resume_secs": 600}"#,
+/// )
+/// .unwrap();
+/// assert_eq!(
+///     pause,
+///     AdminMessage::PauseAgent(PauseAgent {
+///         drain: true,
+///         auto_resume_secs: Some(600),
+///         reason: None,
+///     })
+/// );
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AdminMessage {
+    /// Stop taking new tasks
+    PauseAgent(PauseAgent),
+    /// Take new tasks again
+    ResumeAgent,
+}
+
+/// Parameters of a pause request
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct PauseAgent {
+    /// Let accepted tasks, including those queued behind a busy
+    /// conversation, finish before the agent reports itself paused
+    #[serde(default)]
+    pub drain: bool,
+    /// Resume automatically after this many seconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_resume_secs: Option<u64>,
+    /// Human-readable reason for pausing (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl ErrorMessage {
+    /// Attach the correlation ids of the task this error belongs to
+    pub fn with_correlation(
+        mut self,
+        correlation_id: Option<String>,
+        parent_task_id: Option<Uuid>,
+    ) -> Self {
+        self.correlation_id = correlation_id;
+        self.parent_task_id = parent_task_id;
+        self
+    }
+}
+
+/// Batch of near-identical tasks sharing one instruction
+///
+/// Published to `/control/agents/{agent_id}/input/batch` so that fan-out work
+/// (for example one task per document) travels as a single message. The agent
+/// expands the batch into one [`TaskEnvelope`] per item and publishes a
+/// [`BatchSummary`] once every item has finished.
+///
+/// # Examples
+/// ```
+/// use agent2389::protocol::TaskBatchEnvelope;
+/// use uuid::Uuid;
+/// use serde_json::json;
+///
+/// let batch = TaskBatchEnvelope {
+///     batch_id: Uuid::new_v4(),
+///     conversation_id: "test-conversation".to_string(),
+///     shared_instruction: Some("Summarize this document".to_string()),
+///     items: vec![json!({"doc": "a.txt"}), json!({"doc": "b.txt"})],
+///     next: None,
+/// };
+///
+/// let tasks = batch.expand("/control/agents/my-agent/input");
+/// assert_eq!(tasks.len(), 2);
+/// assert_eq!(tasks[1].task_id, batch.item_task_id(1));
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TaskBatchEnvelope {
+    pub batch_id: Uuid,
+    pub conversation_id: String,
+    /// Instruction applied to every item (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shared_instruction: Option<String>,
+    /// One input per task
+    pub items: Vec<Value>,
+    /// Pipeline continuation applied to every item (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next: Option<Box<NextTask>>,
+}
+
+impl TaskBatchEnvelope {
+    /// Deterministic task id of the item at `index`
+    ///
+    /// Derived as a UUID v5 in the `batch_id` namespace, so a re-delivered batch
+    /// produces the same task ids and is caught by the idempotency check.
+    pub fn item_task_id(&self, index: usize) -> Uuid {
+        Uuid::new_v5(&self.batch_id, index.to_string().as_bytes())
+    }
+
+    /// Expand the batch into one task envelope per item (pure function)
+    ///
+    /// Every task carries the batch id as its correlation id.
+    pub fn expand(&self, topic: &str) -> Vec<TaskEnvelope> {
+        self.items
+            .iter()
+            .enumerate()
+            .map(|(index, input)| TaskEnvelope {
+                task_id: self.item_task_id(index),
+                conversation_id: self.conversation_id.clone(),
+                topic: topic.to_string(),
+                instruction: self.shared_instruction.clone(),
+                input: input.clone(),
+                next: self.next.clone(),
+                deadline: None,
+                correlation_id: Some(self.batch_id.to_string()),
+                parent_task_id: None,
+                published_at: None,
+                traceparent: None,
+                sender_id: None,
+            })
+            .collect()
+    }
+}
+
+/// Outcome of a single batch item
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchItemStatus {
+    Succeeded,
+    Failed,
+    /// Already processed by an earlier delivery of the batch, skipped
+    Duplicate,
+}
+
+/// Result of a single batch item
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BatchItemResult {
+    pub index: usize,
+    pub task_id: Uuid,
+    pub status: BatchItemStatus,
+    /// Failure description for failed items
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Summary published to the conversation topic once every batch item finished
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BatchSummary {
+    pub batch_id: Uuid,
+    pub conversation_id: String,
+    pub total: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub duplicates: usize,
+    /// Per-item results ordered by item index
+    pub items: Vec<BatchItemResult>,
+}
+
+impl BatchSummary {
+    /// Build a summary from per-item results, in any order (pure function)
+    pub fn from_results(batch: &TaskBatchEnvelope, mut items: Vec<BatchItemResult>) -> Self {
+        items.sort_by_key(|item| item.index);
+        let count = |status| items.iter().filter(|item| item.status == status).count();
+
+        Self {
+            batch_id: batch.batch_id,
+            conversation_id: batch.conversation_id.clone(),
+            total: items.len(),
+            succeeded: count(BatchItemStatus::Succeeded),
+            failed: count(BatchItemStatus::Failed),
+            duplicates: count(BatchItemStatus::Duplicate),
+            items,
+        }
+    }
+}
+
+/// Error details structure
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct ErrorDetails {
     pub code: ErrorCode,
     /// Human-readable description (no sensitive data)
     pub message: String,
+    /// Whether resubmitting the same task may succeed
+    #[serde(default)]
+    pub retryable: bool,
+    /// Suggested delay before retrying, if known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_after_ms: Option<u64>,
+}
+
+impl ErrorDetails {
+    /// Create error details using the code's default retryability
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            retryable: code.is_retryable(),
+            code,
+            message: message.into(),
+            retry_after_ms: None,
+        }
+    }
 }
 
 /// Protocol error codes
 ///
-/// Maps to specific error conditions in the 2389 Agent Protocol.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-#[serde(rename_all = "snake_case")]
+/// Maps to specific error conditions in the 2389 Agent Protocol. Codes are
+/// serialized in snake_case; codes this agent does not know (for example from
+/// newer peers) deserialize to [`ErrorCode::Other`] instead of failing.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ErrorCode {
     ToolExecutionFailed,
     LlmError,
     InvalidInput,
     PipelineDepthExceeded,
     InternalError,
+    Cancelled,
+    DeadlineExceeded,
+    /// Task was published longer ago than the agent's `max_task_age_secs`
+    StaleTask,
+    /// Upstream provider rejected the request due to rate limits
+    RateLimited,
+    /// An operation did not complete in time
+    Timeout,
+    /// Upstream provider is temporarily over capacity
+    Overloaded,
+    /// The response was blocked by the agent's moderation policy
+    PolicyViolation,
+    /// The task was denied by the agent's authorization rules
+    Unauthorized,
+    /// Any code not known to this agent
+    Other(String),
+}
+
+impl ErrorCode {
+    /// Wire representation of the code
+    pub fn as_str(&self) -> &str {
+        match self {
+            ErrorCode::ToolExecutionFailed => "tool_execution_failed",
+            ErrorCode::LlmError => "llm_error",
+            ErrorCode::InvalidInput => "invalid_input",
+            ErrorCode::PipelineDepthExceeded => "pipeline_depth_exceeded",
+            ErrorCode::InternalError => "internal_error",
+            ErrorCode::Cancelled => "cancelled",
+            ErrorCode::DeadlineExceeded => "deadline_exceeded",
+            ErrorCode::StaleTask => "stale_task",
+            ErrorCode::RateLimited => "rate_limited",
+            ErrorCode::Timeout => "timeout",
+            ErrorCode::Overloaded => "overloaded",
+            ErrorCode::PolicyViolation => "policy_violation",
+            ErrorCode::Unauthorized => "unauthorized",
+            ErrorCode::Other(code) => code,
+        }
+    }
+
+    /// Whether errors with this code are transient by default
+    ///
+    /// Individual errors may override this, e.g. an `llm_error` caused by a
+    /// network failure is retryable while one caused by bad credentials is not.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            ErrorCode::RateLimited | ErrorCode::Timeout | ErrorCode::Overloaded
+        )
+    }
+}
+
+impl From<&str> for ErrorCode {
+    fn from(code: &str) -> Self {
+        match code {
+            "tool_execution_failed" => ErrorCode::ToolExecutionFailed,
+            "llm_error" => ErrorCode::LlmError,
+            "invalid_input" => ErrorCode::InvalidInput,
+            "pipeline_depth_exceeded" => ErrorCode::PipelineDepthExceeded,
+            "internal_error" => ErrorCode::InternalError,
+            "cancelled" => ErrorCode::Cancelled,
+            "deadline_exceeded" => ErrorCode::DeadlineExceeded,
+            "stale_task" => ErrorCode::StaleTask,
+            "rate_limited" => ErrorCode::RateLimited,
+            "timeout" => ErrorCode::Timeout,
+            "overloaded" => ErrorCode::Overloaded,
+            "policy_violation" => ErrorCode::PolicyViolation,
+            "unauthorized" => ErrorCode::Unauthorized,
+            other => ErrorCode::Other(other.to_string()),
+        }
+    }
+}
+
+impl std::fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for ErrorCode {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for ErrorCode {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let code = String::deserialize(deserializer)?;
+        Ok(ErrorCode::from(code.as_str()))
+    }
 }
 
 #[cfg(test)]
@@ -368,8 +1343,18 @@ mod v2_tests {
                     timestamp: "2024-01-01T12:00:00Z".to_string(),
                 }],
                 iteration_count: 1,
+                started_at: None,
+                budget_secs: None,
+                state: None,
+                state_order: Vec::new(),
             }),
             routing_trace: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
         };
 
         // Should serialize and deserialize correctly
@@ -413,6 +1398,12 @@ mod v2_tests {
                     step_number: 2,
                 },
             ]),
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
         };
 
         let json = serde_json::to_string(&task).unwrap();
@@ -494,6 +1485,12 @@ mod v2_tests {
             instruction: Some("test instruction".to_string()),
             input: json!({"key": "value"}),
             next: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
         };
 
         let wrapper = TaskEnvelopeWrapper::V1(v1_envelope.clone());
@@ -525,6 +1522,12 @@ mod v2_tests {
             version: "2.0".to_string(),
             context: None,
             routing_trace: Some(vec![]),
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
         };
 
         let wrapper = TaskEnvelopeWrapper::V2(v2_envelope.clone());
@@ -538,7 +1541,223 @@ mod v2_tests {
         assert_eq!(v1_envelope.input, v2_envelope.input);
         assert_eq!(v1_envelope.next, v2_envelope.next);
 
-        // v2-specific fields are lost (expected)
+        // v2-specific fields are dropped by to_v1; use into_parts to keep them
+    }
+
+    #[test]
+    fn test_upgrade_synthesizes_context() {
+        let v1 = TaskEnvelope {
+            task_id: Uuid::new_v4(),
+            conversation_id: "test-conv".to_string(),
+            topic: "/control/agents/test/input".to_string(),
+            instruction: Some("Summarize the logs".to_string()),
+            input: json!({}),
+            next: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
+        };
+
+        // Explicit original query wins
+        let v2 = v1
+            .clone()
+            .upgrade(Some("What broke last night?".to_string()));
+        let context = v2.context.expect("context should be synthesized");
+        assert_eq!(context.original_query, "What broke last night?");
+        assert!(context.steps_completed.is_empty());
+        assert_eq!(context.iteration_count, 0);
+
+        // Falls back to the instruction, then to no context at all
+        let v2 = v1.clone().upgrade(None);
+        assert_eq!(v2.context.unwrap().original_query, "Summarize the logs");
+        let v2 = TaskEnvelope {
+            instruction: None,
+            ..v1
+        }
+        .upgrade(None);
+        assert!(v2.context.is_none());
+        assert_eq!(v2.version, "2.0");
+    }
+
+    #[test]
+    fn test_workflow_context_iteration_count_round_trip() {
+        let context = WorkflowContext {
+            original_query: "q".to_string(),
+            steps_completed: Vec::new(),
+            iteration_count: u32::MAX,
+            started_at: None,
+            budget_secs: None,
+            state: None,
+            state_order: Vec::new(),
+        };
+
+        let value = serde_json::to_value(&context).unwrap();
+        assert_eq!(value["iteration_count"], json!(u32::MAX));
+        let parsed: WorkflowContext = serde_json::from_value(value).unwrap();
+        assert_eq!(parsed, context);
+
+        // Missing or null counts read as 0
+        for value in [
+            json!({"original_query": "q", "steps_completed": []}),
+            json!({"original_query": "q", "steps_completed": [], "iteration_count": null}),
+        ] {
+            let parsed: WorkflowContext = serde_json::from_value(value).unwrap();
+            assert_eq!(parsed.iteration_count, 0);
+        }
+    }
+
+    #[test]
+    fn test_workflow_context_iteration_count_out_of_range_is_clamped() {
+        let parse = |count: Value| {
+            serde_json::from_value::<WorkflowContext>(json!({
+                "original_query": "q",
+                "steps_completed": [],
+                "iteration_count": count
+            }))
+            .map(|context| context.iteration_count)
+        };
+
+        assert_eq!(parse(json!(-3)).unwrap(), 0);
+        assert_eq!(parse(json!(i64::MIN)).unwrap(), 0);
+        assert_eq!(parse(json!(u64::from(u32::MAX) + 1)).unwrap(), u32::MAX);
+        assert_eq!(parse(json!(u64::MAX)).unwrap(), u32::MAX);
+        // Values that are not integers still fail
+        assert!(parse(json!("three")).is_err());
+    }
+
+    #[test]
+    fn test_workflow_context_budget_fields_are_optional() {
+        // Contexts from agents that predate the budget still parse
+        let context: WorkflowContext = serde_json::from_value(json!({
+            "original_query": "q",
+            "steps_completed": [],
+            "iteration_count": 1
+        }))
+        .unwrap();
+        assert_eq!(context.started_at, None);
+        assert_eq!(context.budget_secs, None);
+        assert!(!context.budget_exhausted(Utc::now()));
+
+        // and unset fields are left out on the wire
+        let value = serde_json::to_value(&context).unwrap();
+        assert!(value.get("started_at").is_none());
+        assert!(value.get("budget_secs").is_none());
+
+        let started_at = Utc::now();
+        let context = WorkflowContext {
+            started_at: Some(started_at),
+            budget_secs: Some(60),
+            ..context
+        };
+        let parsed: WorkflowContext =
+            serde_json::from_value(serde_json::to_value(&context).unwrap()).unwrap();
+        assert_eq!(parsed, context);
+    }
+
+    #[test]
+    fn test_workflow_context_budget_exhausted() {
+        let started_at = Utc::now() - chrono::Duration::seconds(120);
+        let context = WorkflowContext {
+            original_query: "q".to_string(),
+            steps_completed: Vec::new(),
+            iteration_count: 0,
+            started_at: Some(started_at),
+            budget_secs: Some(60),
+            state: None,
+            state_order: Vec::new(),
+        };
+
+        assert!(context.budget_exhausted(Utc::now()));
+        assert!(!context.budget_exhausted(started_at + chrono::Duration::seconds(59)));
+        assert!(context.budget_exhausted(started_at + chrono::Duration::seconds(60)));
+        // A start time in the future (clock skew) never counts as exhausted
+        assert!(!context.budget_exhausted(started_at - chrono::Duration::seconds(5)));
+
+        let unbounded = WorkflowContext {
+            budget_secs: None,
+            ..context
+        };
+        assert!(!unbounded.budget_exhausted(Utc::now()));
+    }
+
+    #[test]
+    fn test_workflow_context_record_forward_caps_history() {
+        let mut context = WorkflowContext {
+            original_query: "q".to_string(),
+            steps_completed: Vec::new(),
+            iteration_count: 0,
+            started_at: None,
+            budget_secs: None,
+            state: None,
+            state_order: Vec::new(),
+        };
+
+        for i in 0..=MAX_WORKFLOW_HISTORY_STEPS {
+            context.record_forward("agent", format!("step {i}"), Utc::now());
+        }
+
+        assert_eq!(
+            context.iteration_count as usize,
+            MAX_WORKFLOW_HISTORY_STEPS + 1
+        );
+        assert_eq!(context.steps_completed.len(), MAX_WORKFLOW_HISTORY_STEPS);
+        assert_eq!(context.steps_completed[0].action, "step 1");
+        assert_eq!(context.original_query, "q");
+    }
+
+    #[test]
+    fn test_upgrade_downgrade_round_trip_is_lossless() {
+        let v1 = TaskEnvelope {
+            task_id: Uuid::new_v4(),
+            conversation_id: "test-conv".to_string(),
+            topic: "/control/agents/test/input".to_string(),
+            instruction: Some("test instruction".to_string()),
+            input: json!({"nested": {"values": [1, 2, 3]}}),
+            next: Some(Box::new(NextTask {
+                topic: "/control/agents/next/input".to_string(),
+                instruction: None,
+                input: None,
+                next: None,
+            })),
+            deadline: Some(Utc::now()),
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
+        };
+
+        // upgrade -> downgrade returns the original v1 envelope
+        let mut v2 = v1.clone().upgrade(Some("query".to_string()));
+        v2.routing_trace = Some(vec![RoutingStep {
+            from_agent: "a".to_string(),
+            to_agent: "b".to_string(),
+            reason: "because".to_string(),
+            timestamp: "2024-01-01T12:00:00Z".to_string(),
+            step_number: 1,
+        }]);
+        v2.version = "2.1".to_string();
+        let (downgraded, dropped) = v2.clone().downgrade();
+        assert_eq!(downgraded, v1);
+        assert!(!dropped.is_empty());
+
+        // downgrade -> reattach returns the original v2 envelope
+        let reattached = dropped.clone().reattach(downgraded.clone());
+        assert_eq!(reattached, v2);
+
+        // and the wrapper path keeps the same v2 fields
+        let (task, fields) = TaskEnvelopeWrapper::V2(reattached).into_parts();
+        assert_eq!(task, v1);
+        assert_eq!(fields, Some(dropped));
+
+        // upgrading the downgraded envelope again matches the first upgrade
+        assert_eq!(
+            downgraded.upgrade(Some("query".to_string())),
+            v1.upgrade(Some("query".to_string()))
+        );
     }
 
     #[test]
@@ -551,6 +1770,12 @@ mod v2_tests {
             instruction: None,
             input: json!({}),
             next: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
         });
 
         let v1_json = serde_json::to_string(&v1_wrapper).unwrap();
@@ -567,6 +1792,12 @@ mod v2_tests {
             version: "2.0".to_string(),
             context: None,
             routing_trace: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
         });
 
         let v2_json = serde_json::to_string(&v2_wrapper).unwrap();
@@ -587,6 +1818,12 @@ mod v2_tests {
             version: "2.0".to_string(),
             context: None,
             routing_trace: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
         };
 
         let json = serde_json::to_string(&minimal).unwrap();
@@ -613,6 +1850,12 @@ mod tests {
             instruction: Some("Process this test".to_string()),
             input: json!({"test": "data"}),
             next: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
         };
 
         // Should serialize and deserialize correctly
@@ -641,6 +1884,12 @@ mod tests {
             instruction: Some("Start processing".to_string()),
             input: json!({"start": "data"}),
             next: Some(Box::new(next_task)),
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
         };
 
         // Should handle nested structure
@@ -679,6 +1928,12 @@ mod tests {
             instruction: Some("First step".to_string()),
             input: json!({"pipeline": "test"}),
             next: Some(Box::new(middle_next)),
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
         };
 
         // Should handle deep nesting
@@ -733,14 +1988,106 @@ mod tests {
         assert_eq!(parsed.status, AgentStatusType::Unavailable);
     }
 
+    #[test]
+    fn test_task_ack_serialization() {
+        let task_id = Uuid::new_v4();
+        let ack = TaskAck::accepted(task_id, "agent-a", 2)
+            .with_correlation_id(Some("workflow".to_string()));
+        let json = serde_json::to_value(&ack).unwrap();
+        assert_eq!(json["status"], "accepted");
+        assert_eq!(json["queue_position"], 2);
+        assert_eq!(json["correlation_id"], "workflow");
+        assert!(json.get("error").is_none());
+
+        let nack = TaskAck::rejected(
+            task_id,
+            "agent-a",
+            ErrorDetails::new(ErrorCode::InvalidInput, "Retained message"),
+        );
+        let json = serde_json::to_value(&nack).unwrap();
+        assert_eq!(json["status"], "rejected");
+        assert_eq!(json["error"]["code"], "invalid_input");
+
+        let parsed: TaskAck = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed.status, AckStatus::Rejected);
+        assert_eq!(parsed.task_id, task_id);
+    }
+
+    #[test]
+    fn test_agent_manifest_serialization() {
+        let manifest = AgentManifest {
+            agent_id: "test-agent".to_string(),
+            description: None,
+            capabilities: vec!["research".to_string()],
+            envelope_versions: SUPPORTED_ENVELOPE_VERSIONS.map(str::to_string).to_vec(),
+            tools: vec!["web_search".to_string()],
+            model: "test-model".to_string(),
+            max_input_bytes: None,
+            timestamp: DateTime::from_timestamp(1609459200, 0).unwrap(),
+        };
+
+        let json = serde_json::to_value(&manifest).unwrap();
+        assert_eq!(json["envelope_versions"], json!(["1.0", "2.0"]));
+        assert_eq!(json["tools"], json!(["web_search"]));
+        assert!(json.get("description").is_none());
+        assert!(json.get("max_input_bytes").is_none());
+
+        let parsed: AgentManifest = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed, manifest);
+    }
+
+    #[test]
+    fn test_response_message_serialization_is_self_describing() {
+        let response = ResponseMessage {
+            response: "done".to_string(),
+            task_id: Uuid::new_v4(),
+            routing_trace: None,
+            correlation_id: None,
+            parent_task_id: None,
+            conversation_id: Some("conversation-1".to_string()),
+            agent_id: Some("test-agent".to_string()),
+            completed_at: Some(DateTime::from_timestamp(1609459200, 0).unwrap()),
+            oversized: None,
+            artifacts: Vec::new(),
+        };
+
+        let json = serde_json::to_value(&response).unwrap();
+        assert_eq!(json["conversation_id"], "conversation-1");
+        assert_eq!(json["agent_id"], "test-agent");
+        assert_eq!(json["completed_at"], "2021-01-01T00:00:00Z");
+
+        let parsed: ResponseMessage = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed, response);
+    }
+
+    #[test]
+    fn test_response_message_without_origin_fields_still_parses() {
+        let task_id = Uuid::new_v4();
+        let parsed: ResponseMessage =
+            serde_json::from_value(json!({"response": "done", "task_id": task_id})).unwrap();
+
+        assert_eq!(parsed.task_id, task_id);
+        assert_eq!(parsed.conversation_id, None);
+        assert_eq!(parsed.agent_id, None);
+        assert_eq!(parsed.completed_at, None);
+
+        // Absent fields stay absent on the wire
+        let json = serde_json::to_value(&parsed).unwrap();
+        assert_eq!(json, json!({"response": "done", "task_id": task_id}));
+    }
+
     #[test]
     fn test_error_message_serialization() {
         let error = ErrorMessage {
             error: ErrorDetails {
                 code: ErrorCode::ToolExecutionFailed,
                 message: "HTTP request failed".to_string(),
+                retryable: false,
+                retry_after_ms: None,
             },
             task_id: Uuid::new_v4(),
+            correlation_id: None,
+            parent_task_id: None,
         };
 
         let json = serde_json::to_string(&error).unwrap();
@@ -761,6 +2108,14 @@ mod tests {
             ErrorCode::InvalidInput,
             ErrorCode::PipelineDepthExceeded,
             ErrorCode::InternalError,
+            ErrorCode::Cancelled,
+            ErrorCode::DeadlineExceeded,
+            ErrorCode::StaleTask,
+            ErrorCode::RateLimited,
+            ErrorCode::Timeout,
+            ErrorCode::Overloaded,
+            ErrorCode::PolicyViolation,
+            ErrorCode::Unauthorized,
         ];
 
         for code in error_codes {
@@ -768,8 +2123,12 @@ mod tests {
                 error: ErrorDetails {
                     code: code.clone(),
                     message: "Test error".to_string(),
+                    retryable: false,
+                    retry_after_ms: None,
                 },
                 task_id: Uuid::new_v4(),
+                correlation_id: None,
+                parent_task_id: None,
             };
 
             // Should serialize and deserialize correctly
@@ -780,6 +2139,33 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_unknown_error_code_deserializes_to_other() {
+        // Older agents omit retry fields; newer agents may send unknown codes
+        let json = r#"{"error":{"code":"quota_exhausted","message":"x"},"task_id":"550e8400-e29b-41d4-a716-446655440000"}"#;
+        let parsed: ErrorMessage = serde_json::from_str(json).unwrap();
+
+        assert_eq!(
+            parsed.error.code,
+            ErrorCode::Other("quota_exhausted".to_string())
+        );
+        assert!(!parsed.error.retryable);
+        assert_eq!(parsed.error.retry_after_ms, None);
+
+        // Unknown codes are re-serialized unchanged
+        let json = serde_json::to_string(&parsed).unwrap();
+        assert!(json.contains("\"quota_exhausted\""));
+        assert!(!json.contains("retry_after_ms"));
+    }
+
+    #[test]
+    fn test_error_details_default_retryability() {
+        assert!(ErrorDetails::new(ErrorCode::RateLimited, "slow down").retryable);
+        assert!(ErrorDetails::new(ErrorCode::Overloaded, "busy").retryable);
+        assert!(!ErrorDetails::new(ErrorCode::InvalidInput, "bad").retryable);
+        assert!(!ErrorDetails::new(ErrorCode::Other("x".to_string()), "?").retryable);
+    }
+
     #[test]
     fn test_protocol_compliance_json_format() {
         // Test exact JSON structure matches protocol specification
@@ -790,6 +2176,12 @@ mod tests {
             instruction: Some("test instruction".to_string()),
             input: json!({"key": "value"}),
             next: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
         };
 
         let json = serde_json::to_string_pretty(&task).unwrap();
@@ -802,4 +2194,186 @@ mod tests {
         assert!(json.contains("\"input\""));
         assert!(json.contains("\"next\""));
     }
+
+    #[test]
+    fn test_admin_message_serialization() {
+        let resume: AdminMessage = serde_json::from_str(r#"{"type": "resume_agent"}"#).unwrap();
+        assert_eq!(resume, AdminMessage::ResumeAgent);
+
+        let pause: AdminMessage = serde_json::from_str(r#"{"type": "pause_agent"}"#).unwrap();
+        assert_eq!(pause, AdminMessage::PauseAgent(PauseAgent::default()));
+
+        let json = serde_json::to_string(&AdminMessage::PauseAgent(PauseAgent {
+            drain: false,
+            auto_resume_secs: None,
+            reason: Some("broker maintenance".to_string()),
+        }))
+        .unwrap();
+        assert_eq!(
+            json,
+            r#"{"type":"pause_agent","drain":false,"reason":"broker maintenance"}"#
+        );
+        assert_eq!(
+            serde_json::to_string(&AgentStatusType::Paused).unwrap(),
+            r#""paused""#
+        );
+    }
+
+    #[test]
+    fn test_cancel_message_serialization() {
+        let cancel = CancelMessage {
+            task_id: Uuid::new_v4(),
+            conversation_id: "conv-123".to_string(),
+            reason: Some("no longer needed".to_string()),
+        };
+
+        let json = serde_json::to_string(&cancel).unwrap();
+        let parsed: CancelMessage = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, cancel);
+
+        // Reason is optional on the wire
+        let minimal = json!({
+            "task_id": cancel.task_id,
+            "conversation_id": "conv-123"
+        });
+        let parsed: CancelMessage = serde_json::from_value(minimal).unwrap();
+        assert!(parsed.reason.is_none());
+    }
+
+    #[test]
+    fn test_batch_expansion_uses_deterministic_task_ids() {
+        let batch: TaskBatchEnvelope = serde_json::from_value(json!({
+            "batch_id": Uuid::new_v4(),
+            "conversation_id": "conv-batch",
+            "shared_instruction": "Summarize",
+            "items": [{"doc": "a"}, {"doc": "b"}, {"doc": "c"}]
+        }))
+        .unwrap();
+
+        let tasks = batch.expand("/control/agents/worker/input");
+        assert_eq!(tasks.len(), 3);
+        for (index, task) in tasks.iter().enumerate() {
+            assert_eq!(task.task_id, batch.item_task_id(index));
+            assert_eq!(task.input, batch.items[index]);
+            assert_eq!(task.instruction.as_deref(), Some("Summarize"));
+            assert_eq!(task.topic, "/control/agents/worker/input");
+            assert_eq!(task.correlation_id, Some(batch.batch_id.to_string()));
+        }
+
+        // Re-expanding yields the same ids; distinct items get distinct ids
+        let again = batch.expand("/control/agents/worker/input");
+        assert_eq!(again, tasks);
+        assert_ne!(tasks[0].task_id, tasks[1].task_id);
+    }
+
+    #[test]
+    fn test_batch_summary_counts_and_orders_items() {
+        let batch = TaskBatchEnvelope {
+            batch_id: Uuid::new_v4(),
+            conversation_id: "conv-batch".to_string(),
+            shared_instruction: None,
+            items: vec![json!(1), json!(2), json!(3)],
+            next: None,
+        };
+        let result = |index, status, error: Option<&str>| BatchItemResult {
+            index,
+            task_id: batch.item_task_id(index),
+            status,
+            error: error.map(str::to_string),
+        };
+
+        let summary = BatchSummary::from_results(
+            &batch,
+            vec![
+                result(2, BatchItemStatus::Duplicate, None),
+                result(0, BatchItemStatus::Succeeded, None),
+                result(1, BatchItemStatus::Failed, Some("boom")),
+            ],
+        );
+
+        assert_eq!(summary.total, 3);
+        assert_eq!(summary.succeeded, 1);
+        assert_eq!(summary.failed, 1);
+        assert_eq!(summary.duplicates, 1);
+        let indexes: Vec<_> = summary.items.iter().map(|item| item.index).collect();
+        assert_eq!(indexes, vec![0, 1, 2]);
+
+        let json = serde_json::to_value(&summary).unwrap();
+        assert_eq!(json["items"][2]["status"], "duplicate");
+        assert!(json["items"][0].get("error").is_none());
+    }
+
+    #[test]
+    fn test_deadline_is_optional_and_rfc3339() {
+        let task_id = Uuid::new_v4();
+
+        // Old producers omit the field entirely
+        let legacy = json!({
+            "task_id": task_id,
+            "conversation_id": "conv",
+            "topic": "/control/agents/a/input",
+            "instruction": null,
+            "input": {},
+            "next": null
+        });
+        let parsed: TaskEnvelope = serde_json::from_value(legacy).unwrap();
+        assert!(parsed.deadline.is_none());
+        assert!(!serde_json::to_string(&parsed).unwrap().contains("deadline"));
+
+        let with_deadline = json!({
+            "task_id": task_id,
+            "conversation_id": "conv",
+            "topic": "/control/agents/a/input",
+            "instruction": null,
+            "input": {},
+            "next": null,
+            "deadline": "2030-01-01T00:00:00Z"
+        });
+        let wrapper: TaskEnvelopeWrapper = serde_json::from_value(with_deadline).unwrap();
+        let deadline = wrapper.deadline().unwrap();
+        assert_eq!(deadline.to_rfc3339(), "2030-01-01T00:00:00+00:00");
+        assert!(!wrapper.is_expired_at(deadline));
+        assert!(wrapper.is_expired_at(deadline + chrono::Duration::seconds(1)));
+
+        // Deadline survives v1 <-> v2 conversion
+        let v2 = wrapper.clone().to_v2();
+        assert_eq!(v2.deadline, Some(deadline));
+        assert_eq!(TaskEnvelopeWrapper::V2(v2).to_v1().deadline, Some(deadline));
+    }
+
+    #[test]
+    fn test_published_at_gives_task_age() {
+        let envelope = json!({
+            "task_id": Uuid::new_v4(),
+            "conversation_id": "conv",
+            "topic": "/control/agents/a/input",
+            "instruction": null,
+            "input": {},
+            "next": null
+        });
+        let mut wrapper: TaskEnvelopeWrapper = serde_json::from_value(envelope).unwrap();
+        let now = Utc::now();
+        assert_eq!(wrapper.published_at(), None);
+        assert_eq!(wrapper.age_at(now), None);
+        assert!(!serde_json::to_string(&wrapper)
+            .unwrap()
+            .contains("published_at"));
+
+        wrapper.set_published_at(now - chrono::Duration::seconds(90));
+        assert_eq!(wrapper.age_at(now), Some(chrono::Duration::seconds(90)));
+        // A publisher clock ahead of ours doesn't make the age negative
+        assert_eq!(
+            wrapper.age_at(now - chrono::Duration::seconds(120)),
+            Some(chrono::Duration::zero())
+        );
+
+        // Publish time survives v1 <-> v2 conversion
+        let published_at = wrapper.published_at();
+        let v2 = wrapper.to_v2();
+        assert_eq!(v2.published_at, published_at);
+        assert_eq!(
+            TaskEnvelopeWrapper::V2(v2).to_v1().published_at,
+            published_at
+        );
+    }
 }
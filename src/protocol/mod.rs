@@ -5,6 +5,10 @@
 
 pub mod messages;
 pub mod topics;
+pub mod validation;
+pub mod workflow_state;
 
 pub use messages::*;
 pub use topics::*;
+pub use validation::{validate_envelope, ValidationErrors};
+pub use workflow_state::{MAX_WORKFLOW_STATE_BYTES, STATE_UPDATES_KEY};
@@ -30,10 +30,68 @@ pub fn canonicalize_topic(topic: &str) -> String {
     result
 }
 
+/// Canonical form of `topic`, lowercased when topics compare case-insensitively
+pub fn canonicalize_topic_folded(topic: &str, case_insensitive: bool) -> String {
+    let canonical = canonicalize_topic(topic);
+    if case_insensitive {
+        canonical.to_lowercase()
+    } else {
+        canonical
+    }
+}
+
+/// Where two canonical topics differ, with the differing part of each in brackets
+///
+/// For example `'/control/agents/[A]gent-1/input' vs
+/// '/control/agents/[a]gent-1/input' (first difference at character 17)`.
+/// Characters are counted from 1.
+pub fn describe_topic_difference(left: &str, right: &str) -> String {
+    let left_chars: Vec<char> = left.chars().collect();
+    let right_chars: Vec<char> = right.chars().collect();
+    let prefix = left_chars
+        .iter()
+        .zip(&right_chars)
+        .take_while(|(l, r)| l == r)
+        .count();
+    let suffix = left_chars[prefix..]
+        .iter()
+        .rev()
+        .zip(right_chars[prefix..].iter().rev())
+        .take_while(|(l, r)| l == r)
+        .count();
+
+    let mark = |chars: &[char]| -> String {
+        let (head, rest) = chars.split_at(prefix);
+        let (middle, tail) = rest.split_at(rest.len() - suffix);
+        format!(
+            "{}[{}]{}",
+            head.iter().collect::<String>(),
+            middle.iter().collect::<String>(),
+            tail.iter().collect::<String>()
+        )
+    };
+    let mut description = format!(
+        "'{}' vs '{}' (first difference at character {})",
+        mark(&left_chars),
+        mark(&right_chars),
+        prefix + 1
+    );
+    if left != right && left.to_lowercase() == right.to_lowercase() {
+        description.push_str("; the topics differ only in case");
+    }
+    description
+}
+
+/// Longest agent id accepted, keeping topics well within MQTT's limits
+pub const MAX_AGENT_ID_LEN: usize = 128;
+
 pub fn validate_agent_id(agent_id: &str) -> Result<(), ValidationError> {
     if agent_id.is_empty() {
         return Err(ValidationError::EmptyAgentId);
     }
+    if agent_id.len() > MAX_AGENT_ID_LEN {
+        return Err(ValidationError::AgentIdTooLong(agent_id.len()));
+    }
 
     for ch in agent_id.chars() {
         if !ch.is_ascii_alphanumeric() && ch != '.' && ch != '_' && ch != '-' {
@@ -44,6 +102,25 @@ pub fn validate_agent_id(agent_id: &str) -> Result<(), ValidationError> {
     Ok(())
 }
 
+/// Input topic of `agent_id`, once the id is known to be valid
+pub fn agent_input_topic(agent_id: &str) -> Result<String, ValidationError> {
+    validate_agent_id(agent_id)?;
+    Ok(format!("/control/agents/{agent_id}/input"))
+}
+
+/// Agent id of a `/control/agents/{agent_id}/...` topic, once validated
+pub fn agent_id_from_topic(topic: &str) -> Result<String, ValidationError> {
+    let canonical = canonicalize_topic(topic);
+    let mut segments = canonical.trim_start_matches('/').split('/');
+    match (segments.next(), segments.next(), segments.next()) {
+        (Some("control"), Some("agents"), Some(agent_id)) => {
+            validate_agent_id(agent_id)?;
+            Ok(agent_id.to_string())
+        }
+        _ => Err(ValidationError::NotAnAgentTopic(topic.to_string())),
+    }
+}
+
 /// Validation errors for agent protocol
 #[derive(Debug, Error, PartialEq)]
 pub enum ValidationError {
@@ -51,6 +128,10 @@ pub enum ValidationError {
     EmptyAgentId,
     #[error("Agent ID contains invalid character: '{0}'")]
     InvalidAgentIdChar(char),
+    #[error("Agent ID is {0} bytes long, longer than the maximum of {MAX_AGENT_ID_LEN}")]
+    AgentIdTooLong(usize),
+    #[error("Topic '{0}' is not under /control/agents/{{agent_id}}")]
+    NotAnAgentTopic(String),
 }
 
 #[cfg(test)]
@@ -211,4 +292,119 @@ mod tests {
             panic!("Expected InvalidAgentIdChar error");
         }
     }
+
+    #[test]
+    fn test_agent_id_length_limit() {
+        assert!(validate_agent_id(&"a".repeat(MAX_AGENT_ID_LEN)).is_ok());
+        assert_eq!(
+            validate_agent_id(&"a".repeat(MAX_AGENT_ID_LEN + 1)),
+            Err(ValidationError::AgentIdTooLong(MAX_AGENT_ID_LEN + 1))
+        );
+    }
+
+    #[test]
+    fn test_unicode_agent_ids_rejected() {
+        assert_eq!(
+            validate_agent_id("agént"),
+            Err(ValidationError::InvalidAgentIdChar('é'))
+        );
+        assert_eq!(
+            validate_agent_id("агент"),
+            Err(ValidationError::InvalidAgentIdChar('а'))
+        );
+        // A fullwidth slash would look like a topic level to a reader
+        assert_eq!(
+            validate_agent_id("a\u{FF0F}b"),
+            Err(ValidationError::InvalidAgentIdChar('\u{FF0F}'))
+        );
+    }
+
+    #[test]
+    fn test_agent_input_topic_validates_id() {
+        assert_eq!(
+            agent_input_topic("writer-1").unwrap(),
+            "/control/agents/writer-1/input"
+        );
+        assert_eq!(
+            agent_input_topic("writer/1"),
+            Err(ValidationError::InvalidAgentIdChar('/'))
+        );
+        assert_eq!(
+            agent_input_topic("writer+"),
+            Err(ValidationError::InvalidAgentIdChar('+'))
+        );
+        assert_eq!(agent_input_topic(""), Err(ValidationError::EmptyAgentId));
+    }
+
+    #[test]
+    fn test_agent_id_from_topic() {
+        assert_eq!(
+            agent_id_from_topic("//control//agents/writer-1/input/").unwrap(),
+            "writer-1"
+        );
+        assert_eq!(
+            agent_id_from_topic("/control/agents/Agent-1/status").unwrap(),
+            "Agent-1"
+        );
+        assert_eq!(
+            agent_id_from_topic("/control/agents/wr#ter/input"),
+            Err(ValidationError::InvalidAgentIdChar('#'))
+        );
+        assert_eq!(
+            agent_id_from_topic("/control/agents/rédacteur/input"),
+            Err(ValidationError::InvalidAgentIdChar('é'))
+        );
+        assert_eq!(
+            agent_id_from_topic("/conversations/c1/agent"),
+            Err(ValidationError::NotAnAgentTopic(
+                "/conversations/c1/agent".to_string()
+            ))
+        );
+        assert!(matches!(
+            agent_id_from_topic("/control/agents"),
+            Err(ValidationError::NotAnAgentTopic(_))
+        ));
+    }
+
+    #[test]
+    fn test_case_folding_toggle() {
+        assert_eq!(
+            canonicalize_topic_folded("/control/agents/Agent-1/input/", false),
+            "/control/agents/Agent-1/input"
+        );
+        assert_eq!(
+            canonicalize_topic_folded("/control/agents/Agent-1/input/", true),
+            "/control/agents/agent-1/input"
+        );
+    }
+
+    #[test]
+    fn test_describe_topic_difference() {
+        assert_eq!(
+            describe_topic_difference(
+                "/control/agents/Agent-1/input",
+                "/control/agents/agent-1/input"
+            ),
+            "'/control/agents/[A]gent-1/input' vs '/control/agents/[a]gent-1/input' \
+             (first difference at character 17); the topics differ only in case"
+        );
+        assert_eq!(
+            describe_topic_difference(
+                "/control/agents/writer/input",
+                "/control/agents/editor/input"
+            ),
+            "'/control/agents/[write]r/input' vs '/control/agents/[edito]r/input' \
+             (first difference at character 17)"
+        );
+        // One topic extending the other leaves an empty bracket on the shorter side
+        assert_eq!(
+            describe_topic_difference("/a/b", "/a/b/input"),
+            "'/a/b[]' vs '/a/b[/input]' (first difference at character 5)"
+        );
+        // Counted in characters, not bytes
+        assert_eq!(
+            describe_topic_difference("/é/x", "/é/y"),
+            "'/é/[x]' vs '/é/[y]' (first difference at character 4)"
+        );
+    }
 }
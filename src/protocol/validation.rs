@@ -0,0 +1,519 @@
+//! JSON Schema validation of inbound protocol messages
+//!
+//! Serde only checks that a payload has the right shape. These schemas also
+//! enforce the protocol's semantic rules (non-empty conversation ids, topic
+//! grammar, non-negative iteration counts) so bad envelopes are rejected at the
+//! transport boundary with precise error paths instead of failing deep inside
+//! processing.
+
+use jsonschema::Validator;
+use once_cell::sync::Lazy;
+use serde_json::Value;
+use std::fmt;
+
+/// Shared definitions referenced by the envelope schemas
+macro_rules! envelope_defs {
+    () => {
+        r##""$defs": {
+    "uuid": {
+      "type": "string",
+      "pattern": "^[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}$"
+    },
+    "topic": {
+      "type": "string",
+      "pattern": "^/[^#+\\s]+$"
+    },
+    "deadline": {
+      "type": ["string", "null"],
+      "pattern": "^\\d{4}-\\d{2}-\\d{2}T\\d{2}:\\d{2}:\\d{2}(\\.\\d+)?(Z|[+-]\\d{2}:\\d{2})$"
+    },
+    "next_task": {
+      "type": ["object", "null"],
+      "required": ["topic"],
+      "properties": {
+        "topic": { "$ref": "#/$defs/topic" },
+        "instruction": { "type": ["string", "null"] },
+        "next": { "$ref": "#/$defs/next_task" }
+      }
+    }
+  }"##
+    };
+}
+
+/// JSON Schema for v1.0 TaskEnvelope
+pub const TASK_ENVELOPE_V1_SCHEMA: &str = concat!(
+    r##"{
+  "$schema": "https://json-schema.org/draft/2020-12/schema",
+  "title": "TaskEnvelope",
+  "type": "object",
+  "required": ["task_id", "conversation_id", "topic", "input"],
+  "properties": {
+    "task_id": { "$ref": "#/$defs/uuid" },
+    "conversation_id": { "type": "string", "minLength": 1 },
+    "topic": { "$ref": "#/$defs/topic" },
+    "instruction": { "type": ["string", "null"] },
+    "input": true,
+    "next": { "$ref": "#/$defs/next_task" },
+    "deadline": { "$ref": "#/$defs/deadline" },
+    "correlation_id": { "type": ["string", "null"], "minLength": 1 },
+    "parent_task_id": { "anyOf": [{ "$ref": "#/$defs/uuid" }, { "type": "null" }] }
+  },
+  "##,
+    envelope_defs!(),
+    "\n}"
+);
+
+/// JSON Schema for v2.0 TaskEnvelope
+pub const TASK_ENVELOPE_V2_SCHEMA: &str = concat!(
+    r##"{
+  "$schema": "https://json-schema.org/draft/2020-12/schema",
+  "title": "TaskEnvelopeV2",
+  "type": "object",
+  "required": ["task_id", "conversation_id", "topic", "input", "version"],
+  "properties": {
+    "task_id": { "$ref": "#/$defs/uuid" },
+    "conversation_id": { "type": "string", "minLength": 1 },
+    "topic": { "$ref": "#/$defs/topic" },
+    "instruction": { "type": ["string", "null"] },
+    "input": true,
+    "next": { "$ref": "#/$defs/next_task" },
+    "deadline": { "$ref": "#/$defs/deadline" },
+    "correlation_id": { "type": ["string", "null"], "minLength": 1 },
+    "parent_task_id": { "anyOf": [{ "$ref": "#/$defs/uuid" }, { "type": "null" }] },
+    "version": { "type": "string", "pattern": "^2\\.[0-9]+$" },
+    "context": {
+      "type": ["object", "null"],
+      "required": ["original_query", "steps_completed"],
+      "properties": {
+        "original_query": { "type": "string" },
+        "steps_completed": {
+          "type": "array",
+          "items": {
+            "type": "object",
+            "required": ["agent_id", "action", "timestamp"],
+            "properties": {
+              "agent_id": { "type": "string", "minLength": 1 },
+              "action": { "type": "string" },
+              "timestamp": { "type": "string" }
+            }
+          }
+        },
+        "iteration_count": { "type": "integer", "minimum": 0 }
+      }
+    },
+    "routing_trace": {
+      "type": ["array", "null"],
+      "items": {
+        "type": "object",
+        "required": ["from_agent", "to_agent", "reason", "timestamp", "step_number"],
+        "properties": {
+          "from_agent": { "type": "string" },
+          "to_agent": { "type": "string" },
+          "reason": { "type": "string" },
+          "timestamp": { "type": "string" },
+          "step_number": { "type": "integer", "minimum": 0 }
+        }
+      }
+    }
+  },
+  "##,
+    envelope_defs!(),
+    "\n}"
+);
+
+/// JSON Schema for AgentStatus
+pub const AGENT_STATUS_SCHEMA: &str = r##"{
+  "$schema": "https://json-schema.org/draft/2020-12/schema",
+  "title": "AgentStatus",
+  "type": "object",
+  "required": ["agent_id", "status", "timestamp"],
+  "properties": {
+    "agent_id": { "type": "string", "pattern": "^[a-zA-Z0-9._-]+$" },
+    "status": { "enum": ["available", "unavailable", "paused"] },
+    "timestamp": { "type": "string", "minLength": 1 },
+    "capabilities": { "type": ["array", "null"], "items": { "type": "string" } },
+    "description": { "type": ["string", "null"] }
+  }
+}"##;
+
+/// JSON Schema for ErrorMessage
+pub const ERROR_MESSAGE_SCHEMA: &str = r##"{
+  "$schema": "https://json-schema.org/draft/2020-12/schema",
+  "title": "ErrorMessage",
+  "type": "object",
+  "required": ["error", "task_id"],
+  "properties": {
+    "task_id": { "type": "string", "minLength": 1 },
+    "correlation_id": { "type": ["string", "null"], "minLength": 1 },
+    "parent_task_id": { "type": ["string", "null"] },
+    "error": {
+      "type": "object",
+      "required": ["code", "message"],
+      "properties": {
+        "code": { "type": "string", "pattern": "^[a-z_]+$" },
+        "message": { "type": "string" },
+        "retryable": { "type": "boolean" },
+        "retry_after_ms": { "type": ["integer", "null"], "minimum": 0 }
+      }
+    }
+  }
+}"##;
+
+/// JSON Schema for ResponseMessage
+pub const RESPONSE_MESSAGE_SCHEMA: &str = r##"{
+  "$schema": "https://json-schema.org/draft/2020-12/schema",
+  "title": "ResponseMessage",
+  "type": "object",
+  "required": ["response", "task_id"],
+  "properties": {
+    "task_id": { "type": "string", "minLength": 1 },
+    "correlation_id": { "type": ["string", "null"], "minLength": 1 },
+    "parent_task_id": { "type": ["string", "null"] },
+    "conversation_id": { "type": ["string", "null"], "minLength": 1 },
+    "agent_id": { "type": ["string", "null"], "pattern": "^[a-zA-Z0-9._-]+$" },
+    "completed_at": { "type": ["string", "null"], "minLength": 1 },
+    "oversized": {
+      "type": ["object", "null"],
+      "required": ["original_bytes"],
+      "properties": {
+        "original_bytes": { "type": "integer", "minimum": 0 },
+        "reference": { "type": ["string", "null"] }
+      }
+    },
+    "response": { "type": "string" },
+    "artifacts": {
+      "type": "array",
+      "items": {
+        "type": "object",
+        "required": ["name", "data"],
+        "properties": {
+          "name": { "type": "string", "minLength": 1 },
+          "content_type": { "type": "string", "minLength": 1 }
+        }
+      }
+    },
+    "routing_trace": {
+      "type": ["array", "null"],
+      "items": {
+        "type": "object",
+        "required": ["from_agent", "to_agent", "step_number"]
+      }
+    }
+  }
+}"##;
+
+/// JSON Schema for responses from an external gatekeeper routing service
+///
+/// Forward decisions (`workflow_complete: false`) must name the next agent and
+/// instruction. Unknown fields are allowed so newer gatekeepers stay compatible.
+pub const GATEKEEPER_RESPONSE_SCHEMA: &str = r##"{
+  "$schema": "https://json-schema.org/draft/2020-12/schema",
+  "title": "GatekeeperResponse",
+  "type": "object",
+  "required": ["workflow_complete"],
+  "properties": {
+    "workflow_complete": { "type": "boolean" },
+    "next_agent": { "type": ["string", "null"], "minLength": 1 },
+    "next_instruction": { "type": ["string", "null"] },
+    "reasoning": { "type": ["string", "null"] },
+    "sticky": { "type": ["boolean", "null"] },
+    "version": { "type": ["string", "null"], "pattern": "^\\d+(\\.\\d+)*$" }
+  },
+  "if": {
+    "required": ["workflow_complete"],
+    "properties": { "workflow_complete": { "const": false } }
+  },
+  "then": {
+    "required": ["next_agent", "next_instruction"],
+    "properties": {
+      "next_agent": { "type": "string" },
+      "next_instruction": { "type": "string" }
+    }
+  }
+}"##;
+
+static TASK_ENVELOPE_V1: Lazy<Validator> = Lazy::new(|| compile(TASK_ENVELOPE_V1_SCHEMA));
+static TASK_ENVELOPE_V2: Lazy<Validator> = Lazy::new(|| compile(TASK_ENVELOPE_V2_SCHEMA));
+static AGENT_STATUS: Lazy<Validator> = Lazy::new(|| compile(AGENT_STATUS_SCHEMA));
+static ERROR_MESSAGE: Lazy<Validator> = Lazy::new(|| compile(ERROR_MESSAGE_SCHEMA));
+static RESPONSE_MESSAGE: Lazy<Validator> = Lazy::new(|| compile(RESPONSE_MESSAGE_SCHEMA));
+static GATEKEEPER_RESPONSE: Lazy<Validator> = Lazy::new(|| compile(GATEKEEPER_RESPONSE_SCHEMA));
+
+fn compile(schema: &str) -> Validator {
+    let schema: Value = serde_json::from_str(schema).expect("built-in schema is valid JSON");
+    jsonschema::validator_for(&schema).expect("built-in schema compiles")
+}
+
+/// A single schema violation with the JSON pointer of the offending value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaViolation {
+    /// JSON pointer to the invalid value (empty for the document root)
+    pub path: String,
+    /// Human-readable description of the violation
+    pub message: String,
+}
+
+/// All schema violations found in a message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationErrors {
+    pub violations: Vec<SchemaViolation>,
+}
+
+impl fmt::Display for ValidationErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let messages: Vec<String> = self
+            .violations
+            .iter()
+            .map(|v| format!("At '{}': {}", v.path, v.message))
+            .collect();
+        write!(f, "{}", messages.join("; "))
+    }
+}
+
+impl std::error::Error for ValidationErrors {}
+
+fn validate_with(validator: &Validator, value: &Value) -> Result<(), ValidationErrors> {
+    validator
+        .validate(value)
+        .map_err(|errors| ValidationErrors {
+            violations: errors
+                .map(|e| SchemaViolation {
+                    path: e.instance_path.to_string(),
+                    message: e.to_string(),
+                })
+                .collect(),
+        })
+}
+
+/// Validate a TaskEnvelope, selecting the v2.0 schema when a `version` field is present
+pub fn validate_envelope(value: &Value) -> Result<(), ValidationErrors> {
+    if value.get("version").is_some() {
+        validate_with(&TASK_ENVELOPE_V2, value)
+    } else {
+        validate_with(&TASK_ENVELOPE_V1, value)
+    }
+}
+
+/// Validate an AgentStatus message
+pub fn validate_agent_status(value: &Value) -> Result<(), ValidationErrors> {
+    validate_with(&AGENT_STATUS, value)
+}
+
+/// Validate an ErrorMessage
+pub fn validate_error_message(value: &Value) -> Result<(), ValidationErrors> {
+    validate_with(&ERROR_MESSAGE, value)
+}
+
+/// Validate a ResponseMessage
+pub fn validate_response_message(value: &Value) -> Result<(), ValidationErrors> {
+    validate_with(&RESPONSE_MESSAGE, value)
+}
+
+/// Validate a gatekeeper routing response
+pub fn validate_gatekeeper_response(value: &Value) -> Result<(), ValidationErrors> {
+    validate_with(&GATEKEEPER_RESPONSE, value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::{
+        AgentStatus, AgentStatusType, Artifact, ErrorCode, ErrorDetails, ErrorMessage,
+        OversizedResponse, ResponseMessage, TaskEnvelope,
+    };
+    use serde_json::json;
+    use uuid::Uuid;
+
+    fn valid_v1() -> Value {
+        json!({
+            "task_id": Uuid::new_v4(),
+            "conversation_id": "conv-1",
+            "topic": "/control/agents/writer/input",
+            "instruction": "Write",
+            "input": {"text": "hello"},
+            "next": {"topic": "/control/agents/editor/input", "instruction": null}
+        })
+    }
+
+    fn valid_v2() -> Value {
+        let mut value = valid_v1();
+        value["version"] = json!("2.0");
+        value["context"] = json!({
+            "original_query": "Write and edit",
+            "steps_completed": [],
+            "iteration_count": 1
+        });
+        value
+    }
+
+    fn paths(result: Result<(), ValidationErrors>) -> Vec<String> {
+        result
+            .unwrap_err()
+            .violations
+            .into_iter()
+            .map(|v| v.path)
+            .collect()
+    }
+
+    #[test]
+    fn test_valid_envelopes_pass() {
+        assert!(validate_envelope(&valid_v1()).is_ok());
+        assert!(validate_envelope(&valid_v2()).is_ok());
+
+        let task = TaskEnvelope {
+            task_id: Uuid::new_v4(),
+            conversation_id: "conv".to_string(),
+            topic: "/control/agents/a/input".to_string(),
+            instruction: None,
+            input: json!(null),
+            next: None,
+            deadline: Some(chrono::Utc::now()),
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
+        };
+        assert!(validate_envelope(&serde_json::to_value(&task).unwrap()).is_ok());
+    }
+
+    #[test]
+    fn test_empty_conversation_id_rejected() {
+        let mut value = valid_v1();
+        value["conversation_id"] = json!("");
+        assert_eq!(paths(validate_envelope(&value)), vec!["/conversation_id"]);
+    }
+
+    #[test]
+    fn test_invalid_topics_rejected() {
+        let mut value = valid_v1();
+        value["topic"] = json!("control/agents/writer/input");
+        assert_eq!(paths(validate_envelope(&value)), vec!["/topic"]);
+
+        let mut value = valid_v1();
+        value["next"]["topic"] = json!("/control/agents/+/input");
+        assert_eq!(paths(validate_envelope(&value)), vec!["/next/topic"]);
+    }
+
+    #[test]
+    fn test_negative_iteration_count_rejected() {
+        let mut value = valid_v2();
+        value["context"]["iteration_count"] = json!(-1);
+        assert_eq!(
+            paths(validate_envelope(&value)),
+            vec!["/context/iteration_count"]
+        );
+    }
+
+    #[test]
+    fn test_bad_task_id_and_version_rejected() {
+        let mut value = valid_v2();
+        value["task_id"] = json!("not-a-uuid");
+        value["version"] = json!("banana");
+        let mut found = paths(validate_envelope(&value));
+        found.sort();
+        assert_eq!(found, vec!["/task_id", "/version"]);
+    }
+
+    #[test]
+    fn test_missing_required_field_reported_at_root() {
+        let mut value = valid_v1();
+        value.as_object_mut().unwrap().remove("input");
+        let errors = validate_envelope(&value).unwrap_err();
+        assert_eq!(errors.violations[0].path, "");
+        assert!(errors.to_string().contains("input"));
+    }
+
+    #[test]
+    fn test_status_error_and_response_schemas() {
+        let status = AgentStatus {
+            agent_id: "agent-1".to_string(),
+            status: AgentStatusType::Available,
+            timestamp: chrono::Utc::now(),
+            capabilities: None,
+            description: None,
+        };
+        assert!(validate_agent_status(&serde_json::to_value(&status).unwrap()).is_ok());
+        assert!(validate_agent_status(&json!({"agent_id": "bad id!", "status": "busy"})).is_err());
+
+        let error = ErrorMessage {
+            error: ErrorDetails {
+                code: ErrorCode::InvalidInput,
+                message: "bad".to_string(),
+                retryable: false,
+                retry_after_ms: None,
+            },
+            task_id: Uuid::new_v4(),
+            correlation_id: None,
+            parent_task_id: None,
+        };
+        assert!(validate_error_message(&serde_json::to_value(&error).unwrap()).is_ok());
+        assert!(validate_error_message(&json!({"task_id": "x", "error": {}})).is_err());
+
+        let response = ResponseMessage {
+            response: "done".to_string(),
+            task_id: Uuid::new_v4(),
+            routing_trace: None,
+            correlation_id: None,
+            parent_task_id: None,
+            conversation_id: None,
+            agent_id: None,
+            completed_at: None,
+            oversized: None,
+            artifacts: Vec::new(),
+        };
+        assert!(validate_response_message(&serde_json::to_value(&response).unwrap()).is_ok());
+        assert!(validate_response_message(&json!({"response": 42})).is_err());
+
+        let populated = ResponseMessage {
+            conversation_id: Some("conversation-1".to_string()),
+            agent_id: Some("agent-1".to_string()),
+            completed_at: Some(chrono::Utc::now()),
+            oversized: Some(OversizedResponse {
+                original_bytes: 2_000_000,
+                reference: Some("/var/lib/agent/responses/task.txt".to_string()),
+            }),
+            artifacts: vec![Artifact {
+                name: "entities".to_string(),
+                content_type: "application/json".to_string(),
+                data: json!([{"name": "Ada Lovelace", "type": "person"}]),
+            }],
+            ..response
+        };
+        assert!(validate_response_message(&serde_json::to_value(&populated).unwrap()).is_ok());
+        let mut bad_agent = serde_json::to_value(&populated).unwrap();
+        bad_agent["agent_id"] = json!("bad id!");
+        assert!(validate_response_message(&bad_agent).is_err());
+        let mut bad_oversized = serde_json::to_value(&populated).unwrap();
+        bad_oversized["oversized"] = json!({"reference": "/tmp/task.txt"});
+        assert!(validate_response_message(&bad_oversized).is_err());
+        let mut bad_artifact = serde_json::to_value(&populated).unwrap();
+        bad_artifact["artifacts"] = json!([{"name": "", "data": 1}]);
+        assert!(validate_response_message(&bad_artifact).is_err());
+    }
+
+    #[test]
+    fn test_gatekeeper_response_schema() {
+        assert!(validate_gatekeeper_response(&json!({"workflow_complete": true})).is_ok());
+        assert!(validate_gatekeeper_response(&json!({
+            "workflow_complete": false,
+            "next_agent": "editor",
+            "next_instruction": "Edit",
+            "version": "1.0"
+        }))
+        .is_ok());
+
+        assert_eq!(
+            paths(validate_gatekeeper_response(&json!({"complete": true}))),
+            vec![""]
+        );
+        let mut found = paths(validate_gatekeeper_response(&json!({
+            "workflow_complete": false,
+            "next_agent": null,
+            "next_instruction": "Edit",
+            "version": "v2"
+        })));
+        found.sort();
+        assert_eq!(found, vec!["/next_agent", "/version"]);
+    }
+}
@@ -0,0 +1,193 @@
+//! Key-value state shared across the hops of a workflow
+//!
+//! Agents pass small structured values - user preferences, accumulated
+//! citations - to the agents after them by returning a `state_updates` object
+//! in their decision. The updates are merged into the `state` of the workflow
+//! context carried by the forwarded task, and every later agent sees the
+//! state in its system prompt.
+//!
+//! JSON objects don't keep their key order through every decoder, so the
+//! context lists the keys in `state_order`, least recently written first.
+//! Once the state outgrows [`MAX_WORKFLOW_STATE_BYTES`] the least recently
+//! written keys are evicted; keys missing from `state_order` count as oldest.
+
+use serde::{Deserialize, Deserializer};
+use serde_json::{Map, Value};
+use tracing::warn;
+
+/// Key of the updates object in a decision or work output
+pub const STATE_UPDATES_KEY: &str = "state_updates";
+
+/// Largest serialized workflow state carried from hop to hop, in bytes
+pub const MAX_WORKFLOW_STATE_BYTES: usize = 16 * 1024;
+
+/// Apply `updates` to `state`, then evict the least recently written keys
+/// until it serializes to at most `max_bytes`
+///
+/// Updated keys become the most recently written, in the order given; a
+/// `null` value removes its key. Returns the evicted keys.
+pub fn merge_state(
+    state: &mut Map<String, Value>,
+    order: &mut Vec<String>,
+    updates: &Map<String, Value>,
+    max_bytes: usize,
+) -> Vec<String> {
+    // Keys another writer left out of the order are the oldest
+    order.retain(|key| state.contains_key(key));
+    let unordered: Vec<String> = state
+        .keys()
+        .filter(|key| !order.contains(key))
+        .cloned()
+        .collect();
+    order.splice(0..0, unordered);
+
+    for (key, value) in updates {
+        order.retain(|ordered| ordered != key);
+        if value.is_null() {
+            state.remove(key);
+        } else {
+            state.insert(key.clone(), value.clone());
+            order.push(key.clone());
+        }
+    }
+
+    let mut evicted = Vec::new();
+    while !order.is_empty() && state_bytes(state) > max_bytes {
+        let key = order.remove(0);
+        state.remove(&key);
+        evicted.push(key);
+    }
+    if !evicted.is_empty() {
+        warn!(
+            evicted = ?evicted,
+            max_bytes,
+            "Workflow state over its size cap, evicted oldest keys"
+        );
+    }
+    evicted
+}
+
+/// Size of the state serialized as a JSON object (pure function)
+pub fn state_bytes(state: &Map<String, Value>) -> usize {
+    serde_json::to_vec(state).map_or(0, |bytes| bytes.len())
+}
+
+/// The `state_updates` object of a decision or work output, if it has one
+pub fn state_updates(output: &Value) -> Option<&Map<String, Value>> {
+    output.get(STATE_UPDATES_KEY).and_then(Value::as_object)
+}
+
+/// Deserialize a `state_updates` field, ignoring anything but an object
+///
+/// A malformed update should not cost the agent its whole decision.
+pub(crate) fn deserialize_state_updates<'de, D>(
+    deserializer: D,
+) -> Result<Option<Map<String, Value>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Value::deserialize(deserializer)? {
+        Value::Object(updates) => Ok(Some(updates)),
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn object(value: Value) -> Map<String, Value> {
+        value.as_object().unwrap().clone()
+    }
+
+    fn merge(
+        state: &mut Map<String, Value>,
+        order: &mut Vec<String>,
+        updates: Value,
+        max_bytes: usize,
+    ) -> Vec<String> {
+        merge_state(state, order, &object(updates), max_bytes)
+    }
+
+    #[test]
+    fn test_merge_sets_overwrites_and_removes_keys() {
+        let (mut state, mut order) = (Map::new(), Vec::new());
+        merge(
+            &mut state,
+            &mut order,
+            json!({"language": "en", "citations": [1]}),
+            MAX_WORKFLOW_STATE_BYTES,
+        );
+
+        merge(
+            &mut state,
+            &mut order,
+            json!({"citations": [1, 2], "language": null}),
+            MAX_WORKFLOW_STATE_BYTES,
+        );
+
+        assert_eq!(state, object(json!({"citations": [1, 2]})));
+        assert_eq!(order, vec!["citations"]);
+    }
+
+    #[test]
+    fn test_rewritten_key_becomes_the_newest() {
+        let (mut state, mut order) = (Map::new(), Vec::new());
+        merge(&mut state, &mut order, json!({"a": 1}), 1024);
+        merge(&mut state, &mut order, json!({"b": 2}), 1024);
+
+        merge(&mut state, &mut order, json!({"a": 3}), 1024);
+
+        assert_eq!(order, vec!["b", "a"]);
+    }
+
+    #[test]
+    fn test_oldest_keys_are_evicted_over_the_cap() {
+        let (mut state, mut order) = (Map::new(), Vec::new());
+        for key in ["zz", "aa", "mm"] {
+            merge(
+                &mut state,
+                &mut order,
+                json!({ key: "x".repeat(20) }),
+                MAX_WORKFLOW_STATE_BYTES,
+            );
+        }
+        let cap = state_bytes(&state);
+
+        let evicted = merge(&mut state, &mut order, json!({"oo": "y".repeat(20)}), cap);
+
+        assert_eq!(evicted, vec!["zz"]);
+        assert_eq!(order, vec!["aa", "mm", "oo"]);
+        assert!(state_bytes(&state) <= cap);
+    }
+
+    #[test]
+    fn test_value_larger_than_the_cap_empties_the_state() {
+        let (mut state, mut order) = (Map::new(), Vec::new());
+        merge(&mut state, &mut order, json!({"small": 1}), 50);
+
+        let evicted = merge(&mut state, &mut order, json!({"huge": "z".repeat(100)}), 50);
+
+        assert_eq!(evicted, vec!["small", "huge"]);
+        assert!(state.is_empty());
+        assert!(order.is_empty());
+    }
+
+    #[test]
+    fn test_unordered_keys_count_as_oldest() {
+        let mut state = object(json!({"legacy": 1, "known": 2}));
+        let mut order = vec!["known".to_string(), "gone".to_string()];
+
+        merge(&mut state, &mut order, json!({"new": 3}), 1024);
+
+        assert_eq!(order, vec!["legacy", "known", "new"]);
+    }
+
+    #[test]
+    fn test_state_updates_must_be_an_object() {
+        assert!(state_updates(&json!({"state_updates": {"a": 1}})).is_some());
+        assert!(state_updates(&json!({"state_updates": "a=1"})).is_none());
+        assert!(state_updates(&json!("text")).is_none());
+    }
+}
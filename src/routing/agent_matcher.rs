@@ -0,0 +1,162 @@
+//! Registry-backed Validation of Forward Targets
+//!
+//! Routers (especially LLM-based ones) sometimes name an agent slightly
+//! wrong: `Writer-Agent` instead of `writer-agent`, or `editor` instead of
+//! `editor-v2`. `match_agent_id` checks a requested id against the registry,
+//! falling back to case-insensitive and prefix matching over healthy agents,
+//! so callers can auto-correct an unambiguous miss or report an actionable
+//! error listing the agents that are actually available.
+
+use crate::agent::discovery::AgentRegistry;
+
+/// Result of matching a requested agent id against the registry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AgentMatch {
+    /// The id names a registered agent
+    Exact(String),
+    /// Exactly one healthy agent matched case-insensitively or by prefix
+    Suggested(String),
+    /// Several healthy agents matched, sorted by id
+    Ambiguous(Vec<String>),
+    /// No registered agent resembles the id
+    Unknown,
+}
+
+impl AgentMatch {
+    /// Candidate ids for a miss, for "did you mean" hints
+    pub fn suggestions(&self) -> Vec<String> {
+        match self {
+            Self::Suggested(agent_id) => vec![agent_id.clone()],
+            Self::Ambiguous(agent_ids) => agent_ids.clone(),
+            Self::Exact(_) | Self::Unknown => Vec::new(),
+        }
+    }
+}
+
+/// Match a requested agent id against the registry
+///
+/// Exact ids always match. Otherwise healthy agents are compared
+/// case-insensitively, then by the requested id being a prefix of theirs.
+pub fn match_agent_id(registry: &AgentRegistry, requested: &str) -> AgentMatch {
+    if registry.get_agent(requested).is_some() {
+        return AgentMatch::Exact(requested.to_string());
+    }
+
+    let requested = requested.trim().to_lowercase();
+    if requested.is_empty() {
+        return AgentMatch::Unknown;
+    }
+    let available = available_agents(registry);
+
+    let mut candidates: Vec<String> = available
+        .iter()
+        .filter(|agent_id| agent_id.to_lowercase() == requested)
+        .cloned()
+        .collect();
+    if candidates.is_empty() {
+        candidates = available
+            .into_iter()
+            .filter(|agent_id| agent_id.to_lowercase().starts_with(&requested))
+            .collect();
+    }
+
+    match candidates.len() {
+        0 => AgentMatch::Unknown,
+        1 => AgentMatch::Suggested(candidates.remove(0)),
+        _ => AgentMatch::Ambiguous(candidates),
+    }
+}
+
+/// Ids of healthy, non-expired agents, sorted
+pub fn available_agents(registry: &AgentRegistry) -> Vec<String> {
+    let mut agent_ids: Vec<String> = registry
+        .get_all_agent_ids()
+        .iter()
+        .filter_map(|id| registry.get_agent(id))
+        .filter(|agent| agent.is_healthy() && !agent.is_expired())
+        .map(|agent| agent.agent_id)
+        .collect();
+    agent_ids.sort();
+    agent_ids
+}
+
+/// Describe a miss with its suggestions and the currently available agents
+pub fn describe_unknown_agent(
+    registry: &AgentRegistry,
+    requested: &str,
+    agent_match: &AgentMatch,
+) -> String {
+    let mut message = format!("unknown agent: {requested}");
+    let suggestions = agent_match.suggestions();
+    if !suggestions.is_empty() {
+        message.push_str(&format!(" (did you mean: {}?)", suggestions.join(", ")));
+    }
+
+    let available = available_agents(registry);
+    if available.is_empty() {
+        message.push_str("; no healthy agents are available");
+    } else {
+        message.push_str(&format!("; available agents: {}", available.join(", ")));
+    }
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::discovery::AgentInfo;
+
+    fn registry(agents: &[(&str, &str)]) -> AgentRegistry {
+        let registry = AgentRegistry::new();
+        for (agent_id, health) in agents {
+            registry.register_agent(AgentInfo::new(
+                agent_id.to_string(),
+                health.to_string(),
+                0.1,
+            ));
+        }
+        registry
+    }
+
+    #[test]
+    fn test_exact_match_includes_unhealthy_agents() {
+        let registry = registry(&[("writer", "error")]);
+        assert_eq!(
+            match_agent_id(&registry, "writer"),
+            AgentMatch::Exact("writer".to_string())
+        );
+    }
+
+    #[test]
+    fn test_case_insensitive_match_beats_prefix() {
+        let registry = registry(&[("Writer", "ok"), ("writer-v2", "ok")]);
+        assert_eq!(
+            match_agent_id(&registry, "WRITER"),
+            AgentMatch::Suggested("Writer".to_string())
+        );
+    }
+
+    #[test]
+    fn test_prefix_match_and_ambiguity() {
+        let registry = registry(&[("editor-v2", "ok"), ("writer-a", "ok"), ("writer-b", "ok")]);
+        assert_eq!(
+            match_agent_id(&registry, "editor"),
+            AgentMatch::Suggested("editor-v2".to_string())
+        );
+        assert_eq!(
+            match_agent_id(&registry, "Writer"),
+            AgentMatch::Ambiguous(vec!["writer-a".to_string(), "writer-b".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_unhealthy_agents_are_not_suggested() {
+        let registry = registry(&[("editor-v2", "error"), ("writer", "ok")]);
+        let agent_match = match_agent_id(&registry, "editor");
+        assert_eq!(agent_match, AgentMatch::Unknown);
+        assert_eq!(
+            describe_unknown_agent(&registry, "editor", &agent_match),
+            "unknown agent: editor; available agents: writer"
+        );
+    }
+}
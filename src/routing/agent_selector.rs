@@ -5,8 +5,30 @@
 //! routing decisions (see router.rs for V2 routing architecture).
 
 use crate::agent::discovery::{AgentInfo, AgentRegistry};
+use crate::config::{LoadAwareSelectionConfig, RoutingConfig};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
 use tracing::{debug, info, warn};
 
+/// Prefix marking a routing target as a capability rather than an agent id
+///
+/// `capability:summarize` routes to any healthy agent advertising `summarize`.
+pub const CAPABILITY_TARGET_PREFIX: &str = "capability:";
+
+/// Strategy for choosing between agents that share a capability
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SelectionStrategy {
+    /// Least loaded agent, ties broken by agent_id
+    #[default]
+    LowestLoad,
+    /// Rotate through candidates (ordered by agent_id) per capability
+    RoundRobin,
+    /// Uniformly random candidate
+    Random,
+    /// Lowest combined load, recent error rate and cost; see [`LoadAwareSelector`]
+    LoadAware,
+}
+
 /// Agent selection decision result
 ///
 /// Note: This is for agent DISCOVERY, not workflow routing.
@@ -25,7 +47,12 @@ pub enum AgentSelectionDecision {
 /// Simple routing helper
 #[derive(Debug, Clone)]
 pub struct RoutingHelper {
-    // No state needed for now
+    /// Strategy used when resolving `capability:` targets
+    strategy: SelectionStrategy,
+    /// Next round-robin index per capability
+    round_robin_cursors: Arc<Mutex<HashMap<String, usize>>>,
+    /// Scorer for the LoadAware strategy, also tracking routing failures
+    load_aware: Arc<LoadAwareSelector>,
 }
 
 impl Default for RoutingHelper {
@@ -37,7 +64,131 @@ impl Default for RoutingHelper {
 impl RoutingHelper {
     /// Create a new routing helper
     pub fn new() -> Self {
-        Self {}
+        Self::with_strategy(SelectionStrategy::default())
+    }
+
+    /// Create a routing helper that resolves capability targets with `strategy`
+    pub fn with_strategy(strategy: SelectionStrategy) -> Self {
+        Self {
+            strategy,
+            round_robin_cursors: Arc::new(Mutex::new(HashMap::new())),
+            load_aware: Arc::new(LoadAwareSelector::default()),
+        }
+    }
+
+    /// Resolve capability targets with a load-aware selector
+    ///
+    /// The selector can be shared with other routers (e.g. the LlmRouter) so
+    /// they see the same per-agent error rates.
+    pub fn with_load_aware_selector(selector: Arc<LoadAwareSelector>) -> Self {
+        Self {
+            load_aware: selector,
+            ..Self::with_strategy(SelectionStrategy::LoadAware)
+        }
+    }
+
+    /// Create the routing helper described by `[routing]`
+    ///
+    /// A `[routing.selection]` section selects the LoadAware strategy with
+    /// its weights; otherwise the default strategy is used.
+    pub fn from_config(routing: Option<&RoutingConfig>) -> Self {
+        match routing.and_then(|routing| routing.selection.clone()) {
+            Some(selection) => {
+                Self::with_load_aware_selector(Arc::new(LoadAwareSelector::new(selection)))
+            }
+            None => Self::new(),
+        }
+    }
+
+    /// Selector used by the LoadAware strategy
+    pub fn load_aware_selector(&self) -> &Arc<LoadAwareSelector> {
+        &self.load_aware
+    }
+
+    /// Strategy used when resolving `capability:` targets
+    pub fn strategy(&self) -> SelectionStrategy {
+        self.strategy
+    }
+
+    /// Find healthy, unexpired agents with a capability, ordered by agent_id
+    pub fn find_agents_by_capability(
+        &self,
+        capability: &str,
+        registry: &AgentRegistry,
+    ) -> Vec<AgentInfo> {
+        let mut candidates = registry.find_agents_with_capability(capability);
+        candidates.retain(|agent| agent.is_healthy() && !agent.is_expired());
+        candidates.sort_by(|a, b| a.agent_id.cmp(&b.agent_id));
+        candidates
+    }
+
+    /// Select one agent with a capability using the given strategy
+    pub fn select_best_agent(
+        &self,
+        capability: &str,
+        strategy: SelectionStrategy,
+        registry: &AgentRegistry,
+    ) -> AgentSelectionDecision {
+        let mut candidates = self.find_agents_by_capability(capability, registry);
+        if candidates.is_empty() {
+            let reason = format!("No healthy agents found for capability '{capability}'");
+            warn!("{}", reason);
+            return AgentSelectionDecision::NoRoute { reason };
+        }
+
+        let index = match strategy {
+            SelectionStrategy::LowestLoad => candidates
+                .iter()
+                .enumerate()
+                .min_by(|(_, a), (_, b)| {
+                    a.load
+                        .partial_cmp(&b.load)
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                })
+                .map_or(0, |(index, _)| index),
+            SelectionStrategy::RoundRobin => {
+                let mut cursors = self.round_robin_cursors.lock().unwrap();
+                let cursor = cursors.entry(capability.to_lowercase()).or_insert(0);
+                let index = *cursor % candidates.len();
+                *cursor = cursor.wrapping_add(1);
+                index
+            }
+            SelectionStrategy::Random => {
+                (uuid::Uuid::new_v4().as_u128() % candidates.len() as u128) as usize
+            }
+            SelectionStrategy::LoadAware => self
+                .load_aware
+                .select(&candidates)
+                .and_then(|selected| {
+                    candidates
+                        .iter()
+                        .position(|agent| agent.agent_id == selected.agent_id)
+                })
+                .unwrap_or(0),
+        };
+
+        let agent = candidates.swap_remove(index);
+        let reason = format!(
+            "Selected agent for capability '{}' using {:?} (load: {:.3})",
+            capability, strategy, agent.load
+        );
+        info!(
+            "Selected agent '{}' for capability '{}' using {:?}",
+            agent.agent_id, capability, strategy
+        );
+
+        AgentSelectionDecision::RouteToAgent {
+            agent: Box::new(agent),
+            reason,
+        }
+    }
+
+    /// Resolve a routing target that is either an agent id or `capability:<name>`
+    pub fn resolve_target(&self, target: &str, registry: &AgentRegistry) -> AgentSelectionDecision {
+        match target.strip_prefix(CAPABILITY_TARGET_PREFIX) {
+            Some(capability) => self.select_best_agent(capability.trim(), self.strategy, registry),
+            None => self.find_agent_by_id(target, registry),
+        }
     }
 
     /// Find best agent with a specific capability
@@ -103,6 +254,121 @@ impl RoutingHelper {
     }
 }
 
+/// Scores capability-matching agents by load, recent error rate and cost
+///
+/// Each candidate scores `load + error_weight * error_rate + cost`, where the
+/// error rate covers the agent's last `error_window` routing outcomes and the
+/// cost comes from `[routing.selection.costs]`. Agents at or above the
+/// overload threshold are skipped unless every candidate is overloaded, in
+/// which case the least loaded one is used. Ties go to the lowest agent_id.
+#[derive(Debug, Default)]
+pub struct LoadAwareSelector {
+    config: LoadAwareSelectionConfig,
+    /// Recent routing outcomes per agent, `true` for a failure
+    outcomes: Mutex<HashMap<String, VecDeque<bool>>>,
+}
+
+impl LoadAwareSelector {
+    /// Create a selector with the given weights
+    pub fn new(config: LoadAwareSelectionConfig) -> Self {
+        Self {
+            config,
+            outcomes: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Record that routing to an agent succeeded
+    pub fn record_success(&self, agent_id: &str) {
+        self.record(agent_id, false);
+    }
+
+    /// Record that routing to an agent failed
+    pub fn record_failure(&self, agent_id: &str) {
+        self.record(agent_id, true);
+    }
+
+    /// Fraction of the agent's recent routing outcomes that failed
+    pub fn error_rate(&self, agent_id: &str) -> f64 {
+        let outcomes = self.outcomes.lock().unwrap();
+        match outcomes.get(agent_id) {
+            Some(recent) if !recent.is_empty() => {
+                recent.iter().filter(|failed| **failed).count() as f64 / recent.len() as f64
+            }
+            _ => 0.0,
+        }
+    }
+
+    /// Score a candidate; lower is better
+    pub fn score(&self, agent: &AgentInfo) -> f64 {
+        agent.load
+            + self.config.error_weight * self.error_rate(&agent.agent_id)
+            + self
+                .config
+                .costs
+                .get(&agent.agent_id)
+                .copied()
+                .unwrap_or(0.0)
+    }
+
+    /// Order candidates best first, dropping overloaded ones when possible
+    ///
+    /// When every candidate is overloaded all of them are kept, ordered by
+    /// load alone.
+    pub fn rank(&self, candidates: &[AgentInfo]) -> Vec<AgentInfo> {
+        let (available, mut overloaded): (Vec<_>, Vec<_>) = candidates
+            .iter()
+            .cloned()
+            .partition(|agent| agent.load < self.config.overload_threshold);
+
+        if available.is_empty() {
+            if !overloaded.is_empty() {
+                warn!(
+                    candidates = overloaded.len(),
+                    "All candidate agents are overloaded, falling back to the least loaded"
+                );
+            }
+            overloaded.sort_by(|a, b| {
+                a.load
+                    .total_cmp(&b.load)
+                    .then_with(|| a.agent_id.cmp(&b.agent_id))
+            });
+            return overloaded;
+        }
+
+        let mut scored: Vec<_> = available
+            .into_iter()
+            .map(|agent| (self.score(&agent), agent))
+            .collect();
+        scored.sort_by(|(a_score, a), (b_score, b)| {
+            a_score
+                .total_cmp(b_score)
+                .then_with(|| a.agent_id.cmp(&b.agent_id))
+        });
+        scored.into_iter().map(|(_, agent)| agent).collect()
+    }
+
+    /// Pick the best candidate, or `None` when there are none
+    pub fn select(&self, candidates: &[AgentInfo]) -> Option<AgentInfo> {
+        let selected = self.rank(candidates).into_iter().next()?;
+        debug!(
+            agent_id = %selected.agent_id,
+            score = self.score(&selected),
+            "Load-aware selection"
+        );
+        Some(selected)
+    }
+
+    fn record(&self, agent_id: &str, failed: bool) {
+        let window = self.config.error_window.max(1);
+        let mut outcomes = self.outcomes.lock().unwrap();
+        let recent = outcomes.entry(agent_id.to_string()).or_default();
+        recent.push_back(failed);
+        while recent.len() > window {
+            recent.pop_front();
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -169,6 +435,129 @@ mod tests {
         }
     }
 
+    fn create_summarizer_registry() -> AgentRegistry {
+        let registry = AgentRegistry::new();
+        for (agent_id, load) in [
+            ("summarizer-b", 0.3),
+            ("summarizer-a", 0.3),
+            ("summarizer-c", 0.7),
+        ] {
+            let agent = AgentInfo::new(agent_id.to_string(), "ok".to_string(), load)
+                .with_capabilities(vec!["summarize".to_string()]);
+            registry.register_agent(agent);
+        }
+        let unhealthy = AgentInfo::new("summarizer-down".to_string(), "error".to_string(), 0.0)
+            .with_capabilities(vec!["summarize".to_string()]);
+        registry.register_agent(unhealthy);
+        registry
+    }
+
+    fn selected_id(decision: AgentSelectionDecision) -> String {
+        match decision {
+            AgentSelectionDecision::RouteToAgent { agent, .. } => agent.agent_id,
+            AgentSelectionDecision::NoRoute { reason } => panic!("Expected route: {reason}"),
+        }
+    }
+
+    #[test]
+    fn test_find_agents_by_capability_filters_unhealthy() {
+        let helper = RoutingHelper::new();
+        let registry = create_summarizer_registry();
+
+        let ids: Vec<_> = helper
+            .find_agents_by_capability("summarize", &registry)
+            .into_iter()
+            .map(|agent| agent.agent_id)
+            .collect();
+        assert_eq!(ids, vec!["summarizer-a", "summarizer-b", "summarizer-c"]);
+    }
+
+    #[test]
+    fn test_lowest_load_breaks_ties_by_agent_id() {
+        let helper = RoutingHelper::new();
+        let registry = create_summarizer_registry();
+
+        for _ in 0..3 {
+            let decision =
+                helper.select_best_agent("summarize", SelectionStrategy::LowestLoad, &registry);
+            assert_eq!(selected_id(decision), "summarizer-a");
+        }
+    }
+
+    #[test]
+    fn test_round_robin_rotates_through_candidates() {
+        let helper = RoutingHelper::new();
+        let registry = create_summarizer_registry();
+
+        let picks: Vec<_> = (0..4)
+            .map(|_| {
+                selected_id(helper.select_best_agent(
+                    "summarize",
+                    SelectionStrategy::RoundRobin,
+                    &registry,
+                ))
+            })
+            .collect();
+        assert_eq!(
+            picks,
+            vec![
+                "summarizer-a",
+                "summarizer-b",
+                "summarizer-c",
+                "summarizer-a"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_random_selects_healthy_candidate() {
+        let helper = RoutingHelper::new();
+        let registry = create_summarizer_registry();
+
+        for _ in 0..20 {
+            let id = selected_id(helper.select_best_agent(
+                "summarize",
+                SelectionStrategy::Random,
+                &registry,
+            ));
+            assert_ne!(id, "summarizer-down");
+        }
+    }
+
+    #[test]
+    fn test_select_with_no_candidates_returns_no_route() {
+        let helper = RoutingHelper::new();
+        let registry = create_summarizer_registry();
+
+        for strategy in [
+            SelectionStrategy::LowestLoad,
+            SelectionStrategy::RoundRobin,
+            SelectionStrategy::Random,
+        ] {
+            let decision = helper.select_best_agent("translate", strategy, &registry);
+            assert!(matches!(decision, AgentSelectionDecision::NoRoute { .. }));
+        }
+    }
+
+    #[test]
+    fn test_resolve_target_by_capability_or_id() {
+        let helper = RoutingHelper::with_strategy(SelectionStrategy::LowestLoad);
+        let registry = create_summarizer_registry();
+
+        assert_eq!(
+            selected_id(helper.resolve_target("capability:summarize", &registry)),
+            "summarizer-a"
+        );
+        assert_eq!(
+            selected_id(helper.resolve_target("summarizer-c", &registry)),
+            "summarizer-c"
+        );
+        assert!(matches!(
+            helper.resolve_target("capability:translate", &registry),
+            AgentSelectionDecision::NoRoute { .. }
+        ));
+    }
+
     #[test]
     fn test_unhealthy_agent_not_selected() {
         let helper = RoutingHelper::new();
@@ -177,4 +566,143 @@ mod tests {
         let decision = helper.find_agent_by_id("unhealthy-agent", &registry);
         assert!(matches!(decision, AgentSelectionDecision::NoRoute { .. }));
     }
+
+    fn candidates(agents: &[(&str, f64)]) -> Vec<AgentInfo> {
+        agents
+            .iter()
+            .map(|(agent_id, load)| AgentInfo::new(agent_id.to_string(), "ok".to_string(), *load))
+            .collect()
+    }
+
+    fn selected(selector: &LoadAwareSelector, agents: &[AgentInfo]) -> String {
+        selector.select(agents).expect("a candidate").agent_id
+    }
+
+    #[test]
+    fn test_load_aware_weights_load_errors_and_cost() {
+        let agents = candidates(&[("agent-a", 0.2), ("agent-b", 0.3), ("agent-c", 0.5)]);
+        assert_eq!(selected(&LoadAwareSelector::default(), &agents), "agent-a");
+
+        // A static cost outweighs agent-a's lower load
+        let selector = LoadAwareSelector::new(LoadAwareSelectionConfig {
+            costs: HashMap::from([("agent-a".to_string(), 0.5)]),
+            ..Default::default()
+        });
+        assert_eq!(selected(&selector, &agents), "agent-b");
+
+        // Recent routing failures push agent-b behind agent-c
+        selector.record_failure("agent-b");
+        selector.record_success("agent-b");
+        assert_eq!(selector.error_rate("agent-b"), 0.5);
+        assert_eq!(selected(&selector, &agents), "agent-c");
+        assert_eq!(
+            selector
+                .rank(&agents)
+                .into_iter()
+                .map(|agent| agent.agent_id)
+                .collect::<Vec<_>>(),
+            vec!["agent-c", "agent-a", "agent-b"]
+        );
+    }
+
+    #[test]
+    fn test_load_aware_error_rate_uses_recent_window() {
+        let selector = LoadAwareSelector::new(LoadAwareSelectionConfig {
+            error_window: 2,
+            ..Default::default()
+        });
+        assert_eq!(selector.error_rate("agent-a"), 0.0);
+
+        selector.record_failure("agent-a");
+        selector.record_success("agent-a");
+        selector.record_success("agent-a");
+        assert_eq!(selector.error_rate("agent-a"), 0.0);
+    }
+
+    #[test]
+    fn test_load_aware_ties_break_by_agent_id() {
+        let agents = candidates(&[("agent-b", 0.4), ("agent-a", 0.4)]);
+        let selector = LoadAwareSelector::default();
+
+        for _ in 0..3 {
+            assert_eq!(selected(&selector, &agents), "agent-a");
+        }
+        assert!(selector.select(&[]).is_none());
+    }
+
+    #[test]
+    fn test_load_aware_skips_overloaded_agents() {
+        // A cheap agent is still skipped while it is overloaded
+        let agents = candidates(&[("agent-a", 0.95), ("agent-b", 0.6)]);
+        let selector = LoadAwareSelector::new(LoadAwareSelectionConfig {
+            costs: HashMap::from([("agent-a".to_string(), -1.0)]),
+            ..Default::default()
+        });
+
+        assert_eq!(selected(&selector, &agents), "agent-b");
+        assert_eq!(selector.rank(&agents).len(), 1);
+    }
+
+    #[test]
+    fn test_load_aware_all_overloaded_falls_back_to_least_loaded() {
+        let agents = candidates(&[("agent-a", 0.97), ("agent-b", 0.92), ("agent-c", 0.99)]);
+        let selector = LoadAwareSelector::new(LoadAwareSelectionConfig {
+            costs: HashMap::from([("agent-b".to_string(), 5.0)]),
+            ..Default::default()
+        });
+        selector.record_failure("agent-b");
+
+        // Costs and error rates are ignored once every candidate is saturated
+        assert_eq!(selected(&selector, &agents), "agent-b");
+        assert_eq!(
+            selector
+                .rank(&agents)
+                .into_iter()
+                .map(|agent| agent.agent_id)
+                .collect::<Vec<_>>(),
+            vec!["agent-b", "agent-a", "agent-c"]
+        );
+    }
+
+    #[test]
+    fn test_resolve_target_with_load_aware_selector() {
+        let selector = Arc::new(LoadAwareSelector::new(LoadAwareSelectionConfig {
+            costs: HashMap::from([("summarizer-a".to_string(), 1.0)]),
+            ..Default::default()
+        }));
+        let helper = RoutingHelper::with_load_aware_selector(selector.clone());
+        let registry = create_summarizer_registry();
+
+        assert_eq!(helper.strategy(), SelectionStrategy::LoadAware);
+        assert_eq!(
+            selected_id(helper.resolve_target("capability:summarize", &registry)),
+            "summarizer-b"
+        );
+
+        // Failures recorded through the shared selector affect the next pick
+        selector.record_failure("summarizer-b");
+        assert_eq!(
+            selected_id(helper.resolve_target("capability:summarize", &registry)),
+            "summarizer-c"
+        );
+    }
+
+    #[test]
+    fn test_from_config_uses_load_aware_selection_when_configured() {
+        assert_eq!(
+            RoutingHelper::from_config(None).strategy(),
+            SelectionStrategy::default()
+        );
+
+        let mut routing: RoutingConfig =
+            toml::from_str("strategy = \"rules\"\n[selection]\nerror_weight = 2.0\n").unwrap();
+        let helper = RoutingHelper::from_config(Some(&routing));
+        assert_eq!(helper.strategy(), SelectionStrategy::LoadAware);
+
+        routing.selection = None;
+        assert_eq!(
+            RoutingHelper::from_config(Some(&routing)).strategy(),
+            SelectionStrategy::default()
+        );
+    }
 }
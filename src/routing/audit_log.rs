@@ -0,0 +1,271 @@
+//! Routing Decision Instrumentation
+//!
+//! Every routing decision - from the V2 router in the pipeline or from an
+//! agent decision in step 8 - is reported through [`record_routing_decision`].
+//! It updates the global routing metrics and, when an audit log is
+//! configured, appends the decision as one JSON line to that file.
+
+use crate::observability::event_log::{event_log, Event, EventCategory, EventSeverity};
+use crate::observability::metrics::metrics;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+use tracing::warn;
+use uuid::Uuid;
+
+/// Outcome of a routing decision
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RoutingOutcome {
+    /// The workflow was completed
+    Complete,
+    /// The task was forwarded to another agent
+    Forward,
+    /// The task was sent to several agents in parallel
+    FanOut,
+    /// The router failed or its target could not be resolved
+    Error,
+}
+
+impl RoutingOutcome {
+    /// Label used for metrics and logs
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Complete => "complete",
+            Self::Forward => "forward",
+            Self::FanOut => "fan_out",
+            Self::Error => "error",
+        }
+    }
+}
+
+/// One line of the routing audit log
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RoutingAuditEntry {
+    pub timestamp: DateTime<Utc>,
+    pub task_id: Uuid,
+    pub conversation_id: String,
+    /// Agent that made the decision
+    pub agent_id: String,
+    pub decision: RoutingOutcome,
+    /// Concrete agent the task was forwarded to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_agent: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reasoning: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Time spent deciding, in milliseconds
+    pub latency_ms: u64,
+    /// Whether the decision came from the decision cache instead of the router
+    #[serde(default)]
+    pub cached: bool,
+}
+
+impl RoutingAuditEntry {
+    /// Create an entry stamped with the current time
+    pub fn new(
+        task_id: Uuid,
+        conversation_id: &str,
+        agent_id: &str,
+        decision: RoutingOutcome,
+        latency: Duration,
+    ) -> Self {
+        Self {
+            timestamp: Utc::now(),
+            task_id,
+            conversation_id: conversation_id.to_string(),
+            agent_id: agent_id.to_string(),
+            decision,
+            next_agent: None,
+            reasoning: None,
+            error: None,
+            latency_ms: latency.as_millis() as u64,
+            cached: false,
+        }
+    }
+
+    /// Set the agent the task was forwarded to
+    pub fn with_next_agent(mut self, next_agent: impl Into<String>) -> Self {
+        self.next_agent = Some(next_agent.into());
+        self
+    }
+
+    /// Set the router's reasoning
+    pub fn with_reasoning(mut self, reasoning: Option<String>) -> Self {
+        self.reasoning = reasoning;
+        self
+    }
+
+    /// Set the error that prevented routing
+    pub fn with_error(mut self, error: impl Into<String>) -> Self {
+        self.error = Some(error.into());
+        self
+    }
+
+    /// Mark the decision as served from the decision cache
+    pub fn with_cached(mut self, cached: bool) -> Self {
+        self.cached = cached;
+        self
+    }
+}
+
+/// Append-only JSONL file of routing decisions
+#[derive(Debug)]
+pub struct RoutingAuditLog {
+    file: Mutex<File>,
+}
+
+impl RoutingAuditLog {
+    /// Open (or create) the audit log for appending
+    pub fn open(path: &Path) -> std::io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            file: Mutex::new(file),
+        })
+    }
+
+    /// Append one entry; failures are logged rather than failing the routing
+    pub fn append(&self, entry: &RoutingAuditEntry) {
+        let mut line = match serde_json::to_vec(entry) {
+            Ok(line) => line,
+            Err(e) => {
+                warn!(error = %e, "Failed to serialize routing audit entry");
+                return;
+            }
+        };
+        line.push(b'\n');
+
+        let mut file = self.file.lock().unwrap();
+        if let Err(e) = file.write_all(&line) {
+            warn!(error = %e, "Failed to write routing audit entry");
+        }
+    }
+}
+
+/// Record a routing decision in the global metrics and the audit log, if any
+///
+/// Router latency is only sampled for decisions that were not served from
+/// the decision cache.
+pub fn record_routing_decision(audit_log: Option<&RoutingAuditLog>, entry: RoutingAuditEntry) {
+    let latency = (!entry.cached).then(|| Duration::from_millis(entry.latency_ms));
+    event_log().record(routing_event(&entry));
+    match entry.decision {
+        RoutingOutcome::Error => metrics().routing_error(latency),
+        outcome => {
+            metrics().routing_decision(outcome.as_str(), entry.next_agent.as_deref(), latency)
+        }
+    }
+
+    if let Some(audit_log) = audit_log {
+        audit_log.append(&entry);
+    }
+}
+
+/// Event log entry for a routing decision (pure function)
+fn routing_event(entry: &RoutingAuditEntry) -> Event {
+    let (severity, message) = match entry.decision {
+        RoutingOutcome::Complete => (EventSeverity::Info, "Workflow completed"),
+        RoutingOutcome::Forward => (EventSeverity::Info, "Task forwarded"),
+        RoutingOutcome::FanOut => (EventSeverity::Info, "Task fanned out"),
+        RoutingOutcome::Error => (EventSeverity::Error, "Routing failed"),
+    };
+    let mut event = Event::new(severity, EventCategory::Routing, message)
+        .with_field("task_id", entry.task_id.to_string())
+        .with_field("conversation_id", entry.conversation_id.as_str())
+        .with_field("agent_id", entry.agent_id.as_str())
+        .with_field("cached", entry.cached);
+    if let Some(next_agent) = &entry.next_agent {
+        event = event.with_field("next_agent", next_agent.as_str());
+    }
+    if let Some(error) = &entry.error {
+        event = event.with_field("error", error.as_str());
+    }
+    event
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::observability::event_log::EventFilter;
+
+    #[test]
+    fn test_routing_decisions_are_recorded_as_events() {
+        let task_id = Uuid::new_v4();
+
+        record_routing_decision(
+            None,
+            RoutingAuditEntry::new(
+                task_id,
+                "conv-events",
+                "router-agent",
+                RoutingOutcome::Forward,
+                Duration::from_millis(5),
+            )
+            .with_next_agent("events-writer"),
+        );
+
+        let events: Vec<Event> = event_log()
+            .events(&EventFilter {
+                since: None,
+                category: Some(EventCategory::Routing),
+            })
+            .into_iter()
+            .filter(|event| event.fields["task_id"] == task_id.to_string())
+            .collect();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].message, "Task forwarded");
+        assert_eq!(events[0].fields["next_agent"], "events-writer");
+    }
+
+    #[test]
+    fn test_audit_log_appends_json_lines() {
+        let path = std::env::temp_dir().join(format!("routing-audit-{}.jsonl", Uuid::new_v4()));
+        let audit_log = RoutingAuditLog::open(&path).unwrap();
+        let task_id = Uuid::new_v4();
+
+        record_routing_decision(
+            Some(&audit_log),
+            RoutingAuditEntry::new(
+                task_id,
+                "conv-1",
+                "router-agent",
+                RoutingOutcome::Forward,
+                Duration::from_millis(42),
+            )
+            .with_next_agent("audit-writer")
+            .with_reasoning(Some("Needs a draft".to_string())),
+        );
+        record_routing_decision(
+            Some(&audit_log),
+            RoutingAuditEntry::new(
+                task_id,
+                "conv-1",
+                "router-agent",
+                RoutingOutcome::Error,
+                Duration::from_millis(7),
+            )
+            .with_error("gatekeeper unavailable"),
+        );
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let entries: Vec<RoutingAuditEntry> = content
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].decision, RoutingOutcome::Forward);
+        assert_eq!(entries[0].next_agent.as_deref(), Some("audit-writer"));
+        assert_eq!(entries[0].reasoning.as_deref(), Some("Needs a draft"));
+        assert_eq!(entries[0].latency_ms, 42);
+        assert_eq!(entries[1].decision, RoutingOutcome::Error);
+        assert_eq!(entries[1].error.as_deref(), Some("gatekeeper unavailable"));
+        assert!(content.contains("\"decision\":\"forward\""));
+    }
+}
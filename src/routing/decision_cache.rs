@@ -0,0 +1,344 @@
+//! Routing Decision Cache
+//!
+//! Caches router decisions so a workflow that reaches the same decision point
+//! again (retries, iterative loops) doesn't pay for another LLM or HTTP call.
+//!
+//! Entries are keyed on a SHA-256 hash of the original query, the last few
+//! workflow steps, the deciding agent and a digest of the work output. Cached
+//! `Forward` decisions are revalidated against the registry before reuse, so a
+//! target agent that went unhealthy or expired, or a `capability:` target no
+//! healthy agent advertises any more, forces a fresh decision.
+
+use crate::agent::discovery::AgentRegistry;
+use crate::config::DecisionCacheConfig;
+use crate::protocol::messages::TaskEnvelopeV2;
+use crate::routing::agent_selector::CAPABILITY_TARGET_PREFIX;
+use crate::routing::router::RoutingDecision;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+#[derive(Debug, Clone)]
+struct CachedDecision {
+    decision: RoutingDecision,
+    inserted_at: Instant,
+}
+
+/// Thread-safe TTL cache of routing decisions
+#[derive(Debug)]
+pub struct DecisionCache {
+    config: DecisionCacheConfig,
+    entries: Mutex<HashMap<String, CachedDecision>>,
+}
+
+impl DecisionCache {
+    /// Create an empty cache
+    pub fn new(config: DecisionCacheConfig) -> Self {
+        Self {
+            config,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Compute the cache key for a decision point - pure function
+    ///
+    /// Step timestamps are excluded so a repeated loop maps to the same key.
+    pub fn key(&self, task: &TaskEnvelopeV2, current_agent: &str, work_output: &Value) -> String {
+        let mut hasher = Sha256::new();
+        let mut field = |bytes: &[u8]| {
+            // Length-prefix every field so adjacent values can't run together
+            hasher.update((bytes.len() as u64).to_be_bytes());
+            hasher.update(bytes);
+        };
+
+        let context = task.context.as_ref();
+        field(context.map_or("", |c| c.original_query.as_str()).as_bytes());
+        let steps = context.map_or(&[][..], |c| c.steps_completed.as_slice());
+        for step in &steps[steps.len().saturating_sub(self.config.history_steps)..] {
+            field(step.agent_id.as_bytes());
+            field(step.action.as_bytes());
+        }
+        field(current_agent.as_bytes());
+        field(&Sha256::digest(work_output.to_string().as_bytes()));
+
+        hex::encode(hasher.finalize())
+    }
+
+    /// Look up a live decision, revalidating Forward targets against the registry
+    ///
+    /// Expired entries and forwards to agents that are no longer healthy are
+    /// evicted and reported as misses.
+    pub fn get(&self, key: &str, registry: &AgentRegistry) -> Option<RoutingDecision> {
+        let mut entries = self.entries.lock().unwrap();
+        let entry = entries.get(key)?;
+
+        if entry.inserted_at.elapsed() > self.ttl() {
+            debug!(key = %key, "Cached routing decision expired");
+            entries.remove(key);
+            return None;
+        }
+
+        if let Some(next_agent) = entry.decision.next_agent() {
+            if !Self::target_available(next_agent, registry) {
+                debug!(
+                    key = %key,
+                    next_agent = %next_agent,
+                    "Cached routing target is no longer available"
+                );
+                entries.remove(key);
+                return None;
+            }
+        }
+
+        Some(entry.decision.clone())
+    }
+
+    /// Store a decision, evicting expired entries and then the oldest if full
+    pub fn insert(&self, key: String, decision: RoutingDecision) {
+        if self.config.max_entries == 0 {
+            return;
+        }
+        let ttl = self.ttl();
+        let mut entries = self.entries.lock().unwrap();
+
+        if !entries.contains_key(&key) && entries.len() >= self.config.max_entries {
+            entries.retain(|_, entry| entry.inserted_at.elapsed() <= ttl);
+            if entries.len() >= self.config.max_entries {
+                if let Some(oldest) = entries
+                    .iter()
+                    .min_by_key(|(_, entry)| entry.inserted_at)
+                    .map(|(key, _)| key.clone())
+                {
+                    entries.remove(&oldest);
+                }
+            }
+        }
+
+        entries.insert(
+            key,
+            CachedDecision {
+                decision,
+                inserted_at: Instant::now(),
+            },
+        );
+    }
+
+    /// Number of cached decisions, including any not yet evicted as expired
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    /// Whether the cache is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Whether a Forward target can still be reached: the agent is healthy, or
+    /// for `capability:` targets, any healthy agent advertises the capability
+    fn target_available(next_agent: &str, registry: &AgentRegistry) -> bool {
+        match next_agent.strip_prefix(CAPABILITY_TARGET_PREFIX) {
+            Some(capability) => !registry
+                .find_agents_with_capability(capability.trim())
+                .is_empty(),
+            None => registry
+                .get_agent(next_agent)
+                .is_some_and(|agent| agent.is_healthy() && !agent.is_expired()),
+        }
+    }
+
+    fn ttl(&self) -> Duration {
+        Duration::from_secs(self.config.ttl_secs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::discovery::AgentInfo;
+    use crate::protocol::messages::{WorkflowContext, WorkflowStep};
+    use chrono::Utc;
+    use serde_json::json;
+    use uuid::Uuid;
+
+    fn create_task(steps: &[&str]) -> TaskEnvelopeV2 {
+        TaskEnvelopeV2 {
+            task_id: Uuid::new_v4(),
+            conversation_id: "cache-conversation".to_string(),
+            topic: "/control/agents/writer-agent/input".to_string(),
+            instruction: None,
+            input: json!({}),
+            next: None,
+            version: "2.0".to_string(),
+            context: Some(WorkflowContext {
+                original_query: "Write an article".to_string(),
+                steps_completed: steps
+                    .iter()
+                    .map(|agent| WorkflowStep {
+                        agent_id: agent.to_string(),
+                        action: "work".to_string(),
+                        timestamp: Utc::now().to_rfc3339(),
+                    })
+                    .collect(),
+                iteration_count: steps.len() as u32,
+                started_at: None,
+                budget_secs: None,
+                state: None,
+                state_order: Vec::new(),
+            }),
+            routing_trace: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
+        }
+    }
+
+    fn forward(agent: &str) -> RoutingDecision {
+        RoutingDecision::Forward {
+            next_agent: agent.to_string(),
+            next_instruction: "Edit".to_string(),
+            forwarded_data: json!({}),
+            sticky: true,
+        }
+    }
+
+    #[test]
+    fn test_key_depends_on_recent_state_only() {
+        let cache = DecisionCache::new(DecisionCacheConfig {
+            history_steps: 2,
+            ..Default::default()
+        });
+        let output = json!({"draft": "v1"});
+        let key = cache.key(&create_task(&["a", "b", "c"]), "writer", &output);
+
+        // Fresh timestamps and steps beyond the window don't change the key
+        assert_eq!(
+            key,
+            cache.key(&create_task(&["x", "b", "c"]), "writer", &output)
+        );
+        assert_ne!(
+            key,
+            cache.key(&create_task(&["a", "c", "b"]), "writer", &output)
+        );
+        assert_ne!(
+            key,
+            cache.key(&create_task(&["a", "b", "c"]), "editor", &output)
+        );
+        assert_ne!(
+            key,
+            cache.key(
+                &create_task(&["a", "b", "c"]),
+                "writer",
+                &json!({"draft": "v2"})
+            )
+        );
+    }
+
+    #[test]
+    fn test_hit_returns_cached_decision() {
+        let cache = DecisionCache::new(DecisionCacheConfig::default());
+        let registry = AgentRegistry::new();
+        registry.register_agent(AgentInfo::new("editor".to_string(), "ok".to_string(), 0.1));
+
+        assert!(cache.get("key", &registry).is_none());
+        cache.insert("key".to_string(), forward("editor"));
+        assert_eq!(cache.get("key", &registry), Some(forward("editor")));
+
+        let complete = RoutingDecision::Complete {
+            final_output: json!("done"),
+        };
+        cache.insert("done".to_string(), complete.clone());
+        assert_eq!(cache.get("done", &registry), Some(complete));
+    }
+
+    #[test]
+    fn test_expired_entries_are_evicted() {
+        let cache = DecisionCache::new(DecisionCacheConfig {
+            ttl_secs: 0,
+            ..Default::default()
+        });
+        let registry = AgentRegistry::new();
+        cache.insert(
+            "key".to_string(),
+            RoutingDecision::Complete {
+                final_output: json!(null),
+            },
+        );
+
+        std::thread::sleep(Duration::from_millis(5));
+        assert!(cache.get("key", &registry).is_none());
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_stale_forward_target_is_revalidated() {
+        let cache = DecisionCache::new(DecisionCacheConfig::default());
+        let registry = AgentRegistry::new();
+        registry.register_agent(AgentInfo::new("editor".to_string(), "ok".to_string(), 0.1));
+        cache.insert("key".to_string(), forward("editor"));
+
+        // Target goes unhealthy: cached decision must not be reused
+        registry.register_agent(AgentInfo::new(
+            "editor".to_string(),
+            "error".to_string(),
+            0.1,
+        ));
+        assert!(cache.get("key", &registry).is_none());
+        assert!(cache.is_empty());
+
+        // Unknown targets are treated the same way
+        cache.insert("missing".to_string(), forward("ghost"));
+        assert!(cache.get("missing", &registry).is_none());
+    }
+
+    #[test]
+    fn test_capability_forward_is_revalidated_by_capability() {
+        let cache = DecisionCache::new(DecisionCacheConfig::default());
+        let registry = AgentRegistry::new();
+        registry.register_agent(
+            AgentInfo::new("editor".to_string(), "ok".to_string(), 0.1)
+                .with_capabilities(vec!["editing".to_string()]),
+        );
+        cache.insert("key".to_string(), forward("capability:editing"));
+
+        // Any healthy agent with the capability keeps the entry live
+        assert_eq!(
+            cache.get("key", &registry),
+            Some(forward("capability:editing"))
+        );
+
+        // Once no healthy agent advertises it, the entry is evicted
+        registry.register_agent(
+            AgentInfo::new("editor".to_string(), "error".to_string(), 0.1)
+                .with_capabilities(vec!["editing".to_string()]),
+        );
+        assert!(cache.get("key", &registry).is_none());
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_max_entries_evicts_oldest() {
+        let cache = DecisionCache::new(DecisionCacheConfig {
+            max_entries: 2,
+            ..Default::default()
+        });
+        let registry = AgentRegistry::new();
+        let complete = RoutingDecision::Complete {
+            final_output: json!(null),
+        };
+
+        for key in ["first", "second", "third"] {
+            cache.insert(key.to_string(), complete.clone());
+            std::thread::sleep(Duration::from_millis(2));
+        }
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get("first", &registry).is_none());
+        assert!(cache.get("third", &registry).is_some());
+    }
+}
@@ -45,10 +45,24 @@
 //!   "workflow_complete": false,
 //!   "next_agent": "agent-id",
 //!   "next_instruction": "What to do next",
-//!   "reasoning": "Why this decision was made"
+//!   "reasoning": "Why this decision was made",
+//!   "sticky": true,
+//!   "version": "1.0"
 //! }
 //! ```
 //!
+//! Responses are validated against
+//! [`GATEKEEPER_RESPONSE_SCHEMA`](crate::protocol::validation::GATEKEEPER_RESPONSE_SCHEMA)
+//! before conversion, so a malformed body is rejected with every missing or
+//! mistyped field listed.
+//!
+//! # Version Negotiation
+//!
+//! With a protocol version configured, each request carries an
+//! `X-Protocol-Version` header. A gatekeeper may report the version it
+//! answered with in the optional `version` field; a response whose major
+//! version differs from the requested one is rejected.
+//!
 //! # Example - Using Builder Pattern
 //!
 //! ```no_run
@@ -89,16 +103,53 @@
 //! # Ok(())
 //! # }
 //! ```
+//!
+//! # Example - Authenticated Gateway
+//!
+//! ```no_run
+//! use agent2389::config::{GatekeeperAuthConfig, GatekeeperSigningConfig};
+//! use agent2389::routing::gatekeeper_router::{GatekeeperRouter, GatekeeperConfig};
+//! use agent2389::secrets::SecretRef;
+//!
+//! # fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! // Bearer token and HMAC key are resolved at construction
+//! let auth = GatekeeperAuthConfig {
+//!     bearer_token_env: Some(SecretRef::env("GATEKEEPER_TOKEN")),
+//!     signing: Some(GatekeeperSigningConfig {
+//!         key_env: "file:/run/secrets/gatekeeper_hmac_key".parse()?,
+//!         header: "X-Signature".to_string(),
+//!     }),
+//!     ..Default::default()
+//! };
+//! let config = GatekeeperConfig::new()
+//!     .with_host("gatekeeper.example.com")
+//!     .with_scheme("https")
+//!     .with_auth(auth);
+//!
+//! let router = GatekeeperRouter::try_new(config)?;
+//! # Ok(())
+//! # }
+//! ```
 
 use crate::agent::discovery::AgentRegistry;
+use crate::config::{ConfigError, GatekeeperAuthConfig, GatekeeperRouterConfig};
 use crate::error::AgentError;
 use crate::protocol::messages::{TaskEnvelopeV2, WorkflowStep};
-use crate::routing::router::{Router, RoutingDecision};
+use crate::protocol::validation::validate_gatekeeper_response;
+use crate::routing::router::{ExplainedDecision, Router, RoutingDecision};
+use crate::transport::mqtt::MessageSigner;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION, CONTENT_TYPE};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::time::Duration;
 use tracing::{debug, info, warn};
 
+/// Gatekeeper API version spoken by this router
+pub const GATEKEEPER_PROTOCOL_VERSION: &str = "1.0";
+
+/// Request header announcing the gatekeeper API version
+pub const PROTOCOL_VERSION_HEADER: &str = "x-protocol-version";
+
 /// HTTP-based router that delegates routing decisions to an external service
 ///
 /// The GatekeeperRouter allows users to implement custom routing logic in their
@@ -112,6 +163,69 @@ pub struct GatekeeperRouter {
     config: GatekeeperConfig,
     /// HTTP client for making requests
     client: reqwest::Client,
+    /// Authentication resolved from the environment at construction
+    auth: ResolvedAuth,
+}
+
+/// Gatekeeper credentials resolved from environment variables
+#[derive(Default)]
+struct ResolvedAuth {
+    /// Bearer token and static headers, applied to every attempt
+    headers: HeaderMap,
+    /// Signature header name and signer for the request body
+    signing: Option<(HeaderName, MessageSigner)>,
+}
+
+impl std::fmt::Debug for ResolvedAuth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Never print tokens or key material
+        f.debug_struct("ResolvedAuth")
+            .field("headers", &self.headers.keys().collect::<Vec<_>>())
+            .field("signing", &self.signing.as_ref().map(|(header, _)| header))
+            .finish()
+    }
+}
+
+impl ResolvedAuth {
+    /// Resolve secrets and validate header names and values
+    fn resolve(config: Option<&GatekeeperAuthConfig>) -> Result<Self, ConfigError> {
+        let Some(config) = config else {
+            return Ok(Self::default());
+        };
+        let invalid = |what: &str, name: &str| {
+            ConfigError::InvalidConfig(format!("Invalid gatekeeper {what} '{name}'"))
+        };
+        let mut headers = HeaderMap::new();
+        for (name, value) in &config.headers {
+            let header_name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|_| invalid("header name", name))?;
+            let mut header_value =
+                HeaderValue::from_str(value).map_err(|_| invalid("header value for", name))?;
+            header_value.set_sensitive(true);
+            headers.insert(header_name, header_value);
+        }
+        if let Some(token_env) = &config.bearer_token_env {
+            let token = token_env.resolve()?;
+            let mut value = HeaderValue::from_str(&format!("Bearer {}", token.expose()))
+                .map_err(|_| invalid("bearer token in", &token_env.to_string()))?;
+            value.set_sensitive(true);
+            headers.insert(AUTHORIZATION, value);
+        }
+
+        let signing = match &config.signing {
+            Some(signing) => {
+                let header = HeaderName::from_bytes(signing.header.as_bytes())
+                    .map_err(|_| invalid("signature header", &signing.header))?;
+                Some((
+                    header,
+                    MessageSigner::new(signing.key_env.resolve()?.expose()),
+                ))
+            }
+            None => None,
+        };
+
+        Ok(Self { headers, signing })
+    }
 }
 
 /// Configuration for the Gatekeeper HTTP service
@@ -132,6 +246,10 @@ pub struct GatekeeperConfig {
     pub timeout_ms: u64,
     /// Number of retry attempts for transient failures (5xx errors)
     pub retry_attempts: usize,
+    /// Optional bearer token, static headers and request signing
+    pub auth: Option<GatekeeperAuthConfig>,
+    /// Gatekeeper API version sent as `X-Protocol-Version` (header omitted when unset)
+    pub protocol_version: Option<String>,
 }
 
 impl Default for GatekeeperConfig {
@@ -143,6 +261,8 @@ impl Default for GatekeeperConfig {
             path: "/should_agents_respond".to_string(),
             timeout_ms: 5000,
             retry_attempts: 3,
+            auth: None,
+            protocol_version: None,
         }
     }
 }
@@ -189,6 +309,18 @@ impl GatekeeperConfig {
         self
     }
 
+    /// Set request authentication
+    pub fn with_auth(mut self, auth: GatekeeperAuthConfig) -> Self {
+        self.auth = Some(auth);
+        self
+    }
+
+    /// Announce a gatekeeper API version with every request
+    pub fn with_protocol_version(mut self, protocol_version: impl Into<String>) -> Self {
+        self.protocol_version = Some(protocol_version.into());
+        self
+    }
+
     /// Build the full URL from configuration
     pub fn build_url(&self) -> String {
         format!("{}://{}:{}{}", self.scheme, self.host, self.port, self.path)
@@ -223,11 +355,35 @@ impl GatekeeperRouter {
     ///
     /// let router = GatekeeperRouter::new(config);
     /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `config.auth` references an unset environment variable; use
+    /// [`GatekeeperRouter::try_new`] when authentication is configured.
     pub fn new(config: GatekeeperConfig) -> Self {
-        Self {
+        Self::try_new(config).expect("Invalid gatekeeper authentication configuration")
+    }
+
+    /// Create a new GatekeeperRouter, resolving authentication secrets
+    ///
+    /// Fails if a configured environment variable is missing or a header is invalid.
+    pub fn try_new(config: GatekeeperConfig) -> Result<Self, ConfigError> {
+        let auth = ResolvedAuth::resolve(config.auth.as_ref())?;
+        Ok(Self {
             config,
             client: reqwest::Client::new(),
-        }
+            auth,
+        })
+    }
+
+    /// Create a GatekeeperRouter from the `[routing.gatekeeper]` config section
+    pub fn from_config(config: &GatekeeperRouterConfig) -> Result<Self, ConfigError> {
+        let mut router =
+            Self::from_url(config.url.clone(), config.timeout_ms, config.retry_attempts);
+        router.auth = ResolvedAuth::resolve(config.auth.as_ref())?;
+        router.config.auth = config.auth.clone();
+        router.config.protocol_version = config.protocol_version.clone();
+        Ok(router)
     }
 
     /// Create a new GatekeeperRouter from a full URL (legacy convenience method)
@@ -259,11 +415,14 @@ impl GatekeeperRouter {
             path: String::new(),
             timeout_ms,
             retry_attempts,
+            auth: None,
+            protocol_version: None,
         };
 
         Self {
             config,
             client: reqwest::Client::new(),
+            auth: ResolvedAuth::default(),
         }
     }
 
@@ -290,7 +449,7 @@ struct GatekeeperRequest {
     /// List of available agents with their capabilities
     available_agents: Vec<AgentSummary>,
     /// Current iteration count in the workflow
-    iteration_count: usize,
+    iteration_count: u32,
 }
 
 /// Simplified agent information for routing decisions
@@ -302,6 +461,9 @@ struct AgentSummary {
     capabilities: Vec<String>,
     /// Current load (0.0 = idle, 1.0 = fully loaded)
     load: f32,
+    /// Tool names from the agent's manifest, if one was published
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tools: Vec<String>,
 }
 
 /// Response from external routing service
@@ -315,6 +477,10 @@ struct GatekeeperResponse {
     next_instruction: Option<String>,
     /// Reasoning for the routing decision (optional)
     reasoning: Option<String>,
+    /// Set to false to opt this forward out of sticky routing (optional)
+    sticky: Option<bool>,
+    /// Gatekeeper API version the response was produced with (optional)
+    version: Option<String>,
 }
 
 #[async_trait::async_trait]
@@ -325,6 +491,17 @@ impl Router for GatekeeperRouter {
         work_output: &Value,
         registry: &AgentRegistry,
     ) -> Result<RoutingDecision, AgentError> {
+        self.explain_next_step(original_task, work_output, registry)
+            .await
+            .map(|explained| explained.decision)
+    }
+
+    async fn explain_next_step(
+        &self,
+        original_task: &TaskEnvelopeV2,
+        work_output: &Value,
+        registry: &AgentRegistry,
+    ) -> Result<ExplainedDecision, AgentError> {
         info!("GatekeeperRouter making routing decision");
 
         // Build request payload
@@ -334,7 +511,10 @@ impl Router for GatekeeperRouter {
         let response = self.call_external_api(&request).await?;
 
         // Convert response to RoutingDecision
-        self.parse_response(&response, work_output)
+        Ok(ExplainedDecision {
+            decision: self.parse_response(&response, work_output)?,
+            reasoning: response.reasoning,
+        })
     }
 }
 
@@ -368,6 +548,10 @@ impl GatekeeperRouter {
                 agent_id: agent.agent_id.clone(),
                 capabilities: agent.capabilities.clone().unwrap_or_default(),
                 load: agent.load as f32,
+                tools: registry
+                    .get_manifest(&agent.agent_id)
+                    .map(|manifest| manifest.tools)
+                    .unwrap_or_default(),
             })
             .collect();
 
@@ -390,6 +574,12 @@ impl GatekeeperRouter {
         let timeout = self.config.timeout();
         let retry_attempts = self.config.retry_attempts;
 
+        // Serialize once so every attempt sends (and signs) identical bytes
+        let body = serde_json::to_vec(request).map_err(|e| AgentError::InternalError {
+            message: format!("Failed to serialize gatekeeper request: {e}"),
+        })?;
+        let headers = self.request_headers(&body);
+
         for attempt in 0..=retry_attempts {
             debug!(
                 attempt = attempt + 1,
@@ -401,7 +591,8 @@ impl GatekeeperRouter {
             match self
                 .client
                 .post(&url)
-                .json(request)
+                .headers(headers.clone())
+                .body(body.clone())
                 .timeout(timeout)
                 .send()
                 .await
@@ -419,10 +610,7 @@ impl GatekeeperRouter {
                                     message: format!("Failed to read response body: {e}"),
                                 })?;
 
-                        let parsed: GatekeeperResponse =
-                            serde_json::from_str(&body).map_err(|e| AgentError::InvalidInput {
-                                message: format!("Invalid JSON response from gatekeeper: {e}"),
-                            })?;
+                        let parsed = self.validate_response(&body)?;
 
                         info!(
                             workflow_complete = parsed.workflow_complete,
@@ -489,6 +677,59 @@ impl GatekeeperRouter {
         })
     }
 
+    /// Parse a response body, checking it against the gatekeeper schema and
+    /// the negotiated protocol version
+    fn validate_response(&self, body: &str) -> Result<GatekeeperResponse, AgentError> {
+        let value: Value = serde_json::from_str(body).map_err(|e| AgentError::InvalidInput {
+            message: format!("Invalid JSON response from gatekeeper: {e}"),
+        })?;
+        validate_gatekeeper_response(&value).map_err(|errors| AgentError::InvalidInput {
+            message: format!("Gatekeeper response does not match schema: {errors}"),
+        })?;
+        let parsed: GatekeeperResponse =
+            serde_json::from_value(value).map_err(|e| AgentError::InvalidInput {
+                message: format!("Invalid gatekeeper response: {e}"),
+            })?;
+
+        if let Some(version) = &parsed.version {
+            let requested = self
+                .config
+                .protocol_version
+                .as_deref()
+                .unwrap_or(GATEKEEPER_PROTOCOL_VERSION);
+            if major_version(version) != major_version(requested) {
+                return Err(AgentError::InvalidInput {
+                    message: format!(
+                        "Gatekeeper responded with protocol version {version}, expected {requested}"
+                    ),
+                });
+            }
+            debug!(version = %version, "Gatekeeper protocol version");
+        }
+
+        Ok(parsed)
+    }
+
+    /// Build headers for a request body, including auth and signature
+    fn request_headers(&self, body: &[u8]) -> HeaderMap {
+        let mut headers = self.auth.headers.clone();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        if let Some(version) = &self.config.protocol_version {
+            match HeaderValue::from_str(version) {
+                Ok(value) => {
+                    headers.insert(HeaderName::from_static(PROTOCOL_VERSION_HEADER), value);
+                }
+                Err(_) => warn!(version = %version, "Invalid gatekeeper protocol version"),
+            }
+        }
+        if let Some((header, signer)) = &self.auth.signing {
+            let signature = HeaderValue::from_str(&signer.sign(body))
+                .expect("hex signatures are valid header values");
+            headers.insert(header.clone(), signature);
+        }
+        headers
+    }
+
     /// Parse gatekeeper response into RoutingDecision
     fn parse_response(
         &self,
@@ -524,19 +765,26 @@ impl GatekeeperRouter {
                 next_agent: next_agent.clone(),
                 next_instruction: next_instruction.clone(),
                 forwarded_data: work_output.clone(),
+                sticky: response.sticky.unwrap_or(true),
             })
         }
     }
 }
 
+/// Major component of a dotted version string
+fn major_version(version: &str) -> &str {
+    version.split('.').next().unwrap_or(version)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::agent::discovery::AgentInfo;
     use crate::protocol::messages::WorkflowContext;
+    use crate::secrets::SecretRef;
     use serde_json::json;
     use uuid::Uuid;
-    use wiremock::matchers::{method, path};
+    use wiremock::matchers::{header, method, path};
     use wiremock::{Mock, MockServer, ResponseTemplate};
 
     #[tokio::test]
@@ -572,8 +820,18 @@ mod tests {
                 original_query: "Write a blog post".to_string(),
                 steps_completed: vec![],
                 iteration_count: 1,
+                started_at: None,
+                budget_secs: None,
+                state: None,
+                state_order: Vec::new(),
             }),
             routing_trace: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
         };
 
         let work_output = json!({"draft": "This is my blog post..."});
@@ -596,6 +854,54 @@ mod tests {
         assert_eq!(decision.next_agent(), Some("editor-agent"));
     }
 
+    #[test]
+    fn test_build_request_includes_manifest_tools() {
+        let router = GatekeeperRouter::from_url("http://localhost/route".to_string(), 5000, 3);
+        let task = TaskEnvelopeV2 {
+            task_id: Uuid::new_v4(),
+            conversation_id: "test-conv".to_string(),
+            topic: "/test".to_string(),
+            instruction: None,
+            input: json!({}),
+            next: None,
+            version: "2.0".to_string(),
+            context: None,
+            routing_trace: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
+        };
+
+        let registry = AgentRegistry::new();
+        registry.register_agent(AgentInfo::new(
+            "researcher".to_string(),
+            "ok".to_string(),
+            0.1,
+        ));
+        registry.register_agent(AgentInfo::new("writer".to_string(), "ok".to_string(), 0.2));
+        registry.register_manifest(crate::protocol::AgentManifest {
+            agent_id: "researcher".to_string(),
+            description: None,
+            capabilities: vec![],
+            envelope_versions: vec!["2.0".to_string()],
+            tools: vec!["web_search".to_string()],
+            model: "test-model".to_string(),
+            max_input_bytes: None,
+            timestamp: chrono::Utc::now(),
+        });
+
+        let request = router.build_request(&task, &json!({}), &registry);
+        let request = serde_json::to_value(&request).unwrap();
+        let agents = request["available_agents"].as_array().unwrap();
+        let agent = |id: &str| agents.iter().find(|a| a["agent_id"] == id).unwrap().clone();
+
+        assert_eq!(agent("researcher")["tools"], json!(["web_search"]));
+        assert!(agent("writer").get("tools").is_none());
+    }
+
     #[tokio::test]
     async fn test_gatekeeper_successful_complete() {
         // Setup: Start mock HTTP server
@@ -625,8 +931,18 @@ mod tests {
                 original_query: "Complete task".to_string(),
                 steps_completed: vec![],
                 iteration_count: 1,
+                started_at: None,
+                budget_secs: None,
+                state: None,
+                state_order: Vec::new(),
             }),
             routing_trace: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
         };
 
         let work_output = json!({"result": "Task completed successfully"});
@@ -681,8 +997,18 @@ mod tests {
                 original_query: "Test".to_string(),
                 steps_completed: vec![],
                 iteration_count: 0,
+                started_at: None,
+                budget_secs: None,
+                state: None,
+                state_order: Vec::new(),
             }),
             routing_trace: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
         };
 
         let work_output = json!({});
@@ -725,6 +1051,12 @@ mod tests {
             version: "2.0".to_string(),
             context: None,
             routing_trace: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
         };
 
         let work_output = json!({});
@@ -762,6 +1094,12 @@ mod tests {
             version: "2.0".to_string(),
             context: None,
             routing_trace: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
         };
 
         let work_output = json!({});
@@ -798,6 +1136,12 @@ mod tests {
             version: "2.0".to_string(),
             context: None,
             routing_trace: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
         };
 
         let work_output = json!({});
@@ -828,6 +1172,12 @@ mod tests {
             version: "2.0".to_string(),
             context: None,
             routing_trace: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
         };
 
         let work_output = json!({});
@@ -841,6 +1191,128 @@ mod tests {
         assert!(decision.is_err());
     }
 
+    fn auth_config(token_env: &str, key_env: &str) -> GatekeeperAuthConfig {
+        GatekeeperAuthConfig {
+            bearer_token_env: Some(SecretRef::env(token_env)),
+            headers: [("X-Gateway-Key".to_string(), "static-key".to_string())].into(),
+            signing: Some(crate::config::GatekeeperSigningConfig {
+                key_env: SecretRef::env(key_env),
+                header: "X-Signature".to_string(),
+            }),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_gatekeeper_auth_applied_on_every_attempt() {
+        std::env::set_var("TEST_GATEKEEPER_TOKEN", "secret-token");
+        std::env::set_var("TEST_GATEKEEPER_HMAC_KEY", "hmac-key");
+        let mock_server = MockServer::start().await;
+
+        // First attempt fails so the retry must carry the same auth
+        Mock::given(method("POST"))
+            .and(path("/route"))
+            .respond_with(ResponseTemplate::new(500))
+            .up_to_n_times(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/route"))
+            .and(header("Authorization", "Bearer secret-token"))
+            .and(header("X-Gateway-Key", "static-key"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "workflow_complete": true
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let mut router =
+            GatekeeperRouter::from_url(format!("{}/route", mock_server.uri()), 5000, 3);
+        router.auth = ResolvedAuth::resolve(Some(&auth_config(
+            "TEST_GATEKEEPER_TOKEN",
+            "TEST_GATEKEEPER_HMAC_KEY",
+        )))
+        .unwrap();
+
+        let task = TaskEnvelopeV2 {
+            task_id: Uuid::new_v4(),
+            conversation_id: "test-conv".to_string(),
+            topic: "/test".to_string(),
+            instruction: None,
+            input: json!({}),
+            next: None,
+            version: "2.0".to_string(),
+            context: None,
+            routing_trace: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
+        };
+        let decision = router
+            .decide_next_step(&task, &json!({"draft": "text"}), &AgentRegistry::new())
+            .await
+            .unwrap();
+        assert!(decision.is_complete());
+
+        // Both attempts are authenticated and carry a valid body signature
+        let requests = mock_server.received_requests().await.unwrap();
+        assert_eq!(requests.len(), 2);
+        let signer = MessageSigner::new("hmac-key");
+        for request in &requests {
+            assert_eq!(
+                request.headers.get("authorization").unwrap(),
+                "Bearer secret-token"
+            );
+            assert_eq!(request.headers.get("x-gateway-key").unwrap(), "static-key");
+            let signature = request
+                .headers
+                .get("x-signature")
+                .unwrap()
+                .to_str()
+                .unwrap();
+            assert!(signer.verify(&request.body, Some(signature)).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_gatekeeper_auth_missing_env_fails_construction() {
+        std::env::remove_var("TEST_GATEKEEPER_MISSING_TOKEN");
+        let config = GatekeeperConfig::new().with_auth(GatekeeperAuthConfig {
+            bearer_token_env: Some(SecretRef::env("TEST_GATEKEEPER_MISSING_TOKEN")),
+            ..Default::default()
+        });
+
+        assert!(matches!(
+            GatekeeperRouter::try_new(config),
+            Err(ConfigError::EnvVarNotFound(name)) if name == "TEST_GATEKEEPER_MISSING_TOKEN"
+        ));
+
+        let invalid_header = GatekeeperConfig::new().with_auth(GatekeeperAuthConfig {
+            headers: [("bad header".to_string(), "value".to_string())].into(),
+            ..Default::default()
+        });
+        assert!(matches!(
+            GatekeeperRouter::try_new(invalid_header),
+            Err(ConfigError::InvalidConfig(_))
+        ));
+    }
+
+    #[test]
+    fn test_gatekeeper_auth_debug_hides_secrets() {
+        std::env::set_var("TEST_GATEKEEPER_DEBUG_TOKEN", "debug-secret-token");
+        std::env::set_var("TEST_GATEKEEPER_DEBUG_KEY", "debug-hmac-key");
+        let auth = auth_config("TEST_GATEKEEPER_DEBUG_TOKEN", "TEST_GATEKEEPER_DEBUG_KEY");
+        let router = GatekeeperRouter::try_new(GatekeeperConfig::new().with_auth(auth)).unwrap();
+
+        let printed = format!("{:?} {:?}", router.auth, router.config);
+        assert!(!printed.contains("debug-secret-token"));
+        assert!(!printed.contains("debug-hmac-key"));
+        assert!(!printed.contains("static-key"));
+        assert!(printed.contains("x-gateway-key"));
+    }
+
     #[tokio::test]
     async fn test_gatekeeper_config_builder() {
         // Setup: Start mock HTTP server
@@ -882,6 +1354,12 @@ mod tests {
             version: "2.0".to_string(),
             context: None,
             routing_trace: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
         };
 
         let work_output = json!({"result": "Test using config builder"});
@@ -896,4 +1374,128 @@ mod tests {
         let decision = decision.unwrap();
         assert!(decision.is_complete());
     }
+
+    fn schema_test_task() -> TaskEnvelopeV2 {
+        TaskEnvelopeV2 {
+            task_id: Uuid::new_v4(),
+            conversation_id: "test-conv".to_string(),
+            topic: "/test".to_string(),
+            instruction: None,
+            input: json!({}),
+            next: None,
+            version: "2.0".to_string(),
+            context: None,
+            routing_trace: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
+        }
+    }
+
+    /// Route once against a gatekeeper that answers with `body`
+    async fn route_with_response(body: Value) -> Result<RoutingDecision, AgentError> {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/route"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(body))
+            .mount(&mock_server)
+            .await;
+
+        let router = GatekeeperRouter::from_url(format!("{}/route", mock_server.uri()), 5000, 0);
+        router
+            .decide_next_step(&schema_test_task(), &json!({}), &AgentRegistry::new())
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_gatekeeper_response_with_wrong_field_name_rejected() {
+        let err = route_with_response(json!({"complete": true}))
+            .await
+            .unwrap_err();
+
+        assert!(matches!(err, AgentError::InvalidInput { .. }));
+        let message = err.to_string();
+        assert!(message.contains("does not match schema"), "{message}");
+        assert!(message.contains("workflow_complete"), "{message}");
+    }
+
+    #[tokio::test]
+    async fn test_gatekeeper_response_lists_every_bad_field() {
+        let err = route_with_response(json!({
+            "workflow_complete": false,
+            "next_agent": 42,
+            "sticky": "yes"
+        }))
+        .await
+        .unwrap_err();
+
+        assert!(matches!(err, AgentError::InvalidInput { .. }));
+        let message = err.to_string();
+        assert!(message.contains("'/next_agent'"), "{message}");
+        assert!(message.contains("'/sticky'"), "{message}");
+        assert!(message.contains("next_instruction"), "{message}");
+    }
+
+    #[tokio::test]
+    async fn test_gatekeeper_response_accepts_unknown_fields_and_version() {
+        let decision = route_with_response(json!({
+            "workflow_complete": false,
+            "next_agent": "editor",
+            "next_instruction": "Edit",
+            "version": "1.3",
+            "confidence": 0.9
+        }))
+        .await
+        .unwrap();
+
+        assert_eq!(decision.next_agent(), Some("editor"));
+    }
+
+    #[tokio::test]
+    async fn test_gatekeeper_response_with_other_major_version_rejected() {
+        let err = route_with_response(json!({
+            "workflow_complete": true,
+            "version": "2.0"
+        }))
+        .await
+        .unwrap_err();
+
+        assert!(matches!(err, AgentError::InvalidInput { .. }));
+        assert!(err.to_string().contains("protocol version 2.0"));
+    }
+
+    #[tokio::test]
+    async fn test_gatekeeper_sends_protocol_version_header() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/route"))
+            .and(header("X-Protocol-Version", "2.1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "workflow_complete": true,
+                "version": "2.0"
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let mut router =
+            GatekeeperRouter::from_url(format!("{}/route", mock_server.uri()), 5000, 0);
+        router.config.protocol_version = Some("2.1".to_string());
+        let decision = router
+            .decide_next_step(&schema_test_task(), &json!({}), &AgentRegistry::new())
+            .await
+            .unwrap();
+        assert!(decision.is_complete());
+
+        // Without a configured version the header is omitted
+        let router = GatekeeperRouter::from_url(format!("{}/route", mock_server.uri()), 5000, 0);
+        assert!(router
+            .decide_next_step(&schema_test_task(), &json!({}), &AgentRegistry::new())
+            .await
+            .is_err());
+        let requests = mock_server.received_requests().await.unwrap();
+        assert!(requests[1].headers.get(PROTOCOL_VERSION_HEADER).is_none());
+    }
 }
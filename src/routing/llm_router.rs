@@ -6,12 +6,17 @@
 //! The LlmRouter uses structured output to guarantee valid JSON responses:
 //! - OpenAI: JSON Schema with `response_format`
 //! - Anthropic: Tool schema with `tool_choice: required`
+//!
+//! When the LLM forwards to an agent that is not registered, it gets one
+//! corrective retry listing the ids it may choose from.
 
-use crate::agent::discovery::AgentRegistry;
+use crate::agent::discovery::{AgentInfo, AgentRegistry};
 use crate::error::AgentError;
 use crate::llm::provider::{CompletionRequest, LlmProvider, Message, MessageRole};
 use crate::protocol::messages::TaskEnvelopeV2;
-use crate::routing::router::{Router, RoutingDecision};
+use crate::routing::agent_matcher::{describe_unknown_agent, match_agent_id, AgentMatch};
+use crate::routing::agent_selector::{LoadAwareSelector, CAPABILITY_TARGET_PREFIX};
+use crate::routing::router::{ExplainedDecision, Router, RoutingDecision};
 use crate::routing::schema::RoutingDecisionOutput;
 use serde_json::Value;
 use std::sync::Arc;
@@ -33,6 +38,10 @@ pub struct LlmRouter {
     model: String,
     /// Temperature for routing decisions (default: 0.1 for consistency)
     temperature: f32,
+    /// Sampling seed for providers that support one, for reproducible routing
+    seed: Option<u64>,
+    /// Optional selector used to pre-filter and order the agent catalog
+    selector: Option<Arc<LoadAwareSelector>>,
 }
 
 impl LlmRouter {
@@ -42,6 +51,8 @@ impl LlmRouter {
             provider,
             model,
             temperature: 0.1, // Low temperature for consistent routing
+            seed: None,
+            selector: None,
         }
     }
 
@@ -51,6 +62,22 @@ impl LlmRouter {
         self
     }
 
+    /// Route at temperature 0 with `seed`, as agents do in `[testing] deterministic` mode
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.temperature = 0.0;
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Pre-filter the agent catalog with a load-aware selector
+    ///
+    /// Overloaded agents are hidden from the LLM when another agent offers the
+    /// same capabilities, and the remaining agents are listed best first.
+    pub fn with_load_aware_selector(mut self, selector: Arc<LoadAwareSelector>) -> Self {
+        self.selector = Some(selector);
+        self
+    }
+
     /// Check if the provider is OpenAI (case-insensitive)
     fn is_openai_provider(&self) -> bool {
         self.provider.name().eq_ignore_ascii_case("openai")
@@ -71,7 +98,8 @@ impl LlmRouter {
         use crate::llm::provider::{JsonSchemaDefinition, ResponseFormat};
         use crate::routing::schema::RoutingDecisionOutput;
 
-        let prompt = Self::build_routing_prompt(task, work_output, registry);
+        let prompt =
+            Self::build_routing_prompt(task, work_output, registry, self.selector.as_deref());
 
         let mut request = CompletionRequest {
             model: self.model.clone(),
@@ -88,6 +116,7 @@ impl LlmRouter {
             temperature: Some(self.temperature),
             max_tokens: Some(500),
             top_p: None,
+            seed: self.seed,
             stop_sequences: None,
             tools: None,
             tool_choice: None,
@@ -160,13 +189,64 @@ impl LlmRouter {
         output
     }
 
-    /// Format available agents catalog for the LLM prompt
-    fn format_agent_catalog(registry: &AgentRegistry) -> String {
-        let agent_ids = registry.get_all_agent_ids();
-        let agents: Vec<_> = agent_ids
+    /// Healthy agents to offer the LLM, pre-filtered when a selector is set
+    ///
+    /// An agent is kept if the selector would still rank it among the agents
+    /// sharing one of its capabilities, so a capability is never hidden just
+    /// because every agent offering it is overloaded.
+    fn catalog_candidates(
+        registry: &AgentRegistry,
+        selector: Option<&LoadAwareSelector>,
+    ) -> Vec<AgentInfo> {
+        let agents: Vec<_> = registry
+            .get_all_agent_ids()
             .iter()
             .filter_map(|id| registry.get_agent(id))
+            .filter(|agent| agent.is_healthy() && !agent.is_expired())
             .collect();
+        let Some(selector) = selector else {
+            return agents;
+        };
+
+        let shares_capability = |a: &AgentInfo, b: &AgentInfo| {
+            a.agent_id == b.agent_id
+                || a.capabilities.iter().flatten().any(|capability| {
+                    b.capabilities
+                        .iter()
+                        .flatten()
+                        .any(|other| other.eq_ignore_ascii_case(capability))
+                })
+        };
+        let mut kept: Vec<_> = agents
+            .iter()
+            .filter(|agent| {
+                let peers: Vec<_> = agents
+                    .iter()
+                    .filter(|other| shares_capability(agent, other))
+                    .cloned()
+                    .collect();
+                selector
+                    .rank(&peers)
+                    .iter()
+                    .any(|ranked| ranked.agent_id == agent.agent_id)
+            })
+            .cloned()
+            .collect();
+        kept.sort_by(|a, b| {
+            selector
+                .score(a)
+                .total_cmp(&selector.score(b))
+                .then_with(|| a.agent_id.cmp(&b.agent_id))
+        });
+        kept
+    }
+
+    /// Format available agents catalog for the LLM prompt
+    fn format_agent_catalog(
+        registry: &AgentRegistry,
+        selector: Option<&LoadAwareSelector>,
+    ) -> String {
+        let agents = Self::catalog_candidates(registry, selector);
 
         if agents.is_empty() {
             return "No agents currently available.".to_string();
@@ -174,18 +254,23 @@ impl LlmRouter {
 
         let mut output = String::from("AVAILABLE AGENTS:\n");
         for agent in agents {
-            if agent.is_healthy() && !agent.is_expired() {
-                let capabilities = agent
-                    .capabilities
-                    .as_ref()
-                    .map(|c| c.join(", "))
-                    .unwrap_or_else(|| "none".to_string());
-
-                output.push_str(&format!(
-                    "- {} (capabilities: {}, load: {:.3})\n",
-                    agent.agent_id, capabilities, agent.load
-                ));
-            }
+            let capabilities = agent
+                .capabilities
+                .as_ref()
+                .map(|c| c.join(", "))
+                .unwrap_or_else(|| "none".to_string());
+
+            // Tool names come from the agent's manifest, when it published one
+            let tools = registry
+                .get_manifest(&agent.agent_id)
+                .filter(|manifest| !manifest.tools.is_empty())
+                .map(|manifest| format!(", tools: {}", manifest.tools.join(", ")))
+                .unwrap_or_default();
+
+            output.push_str(&format!(
+                "- {} (capabilities: {}{}, load: {:.3})\n",
+                agent.agent_id, capabilities, tools, agent.load
+            ));
         }
 
         output
@@ -196,6 +281,7 @@ impl LlmRouter {
         task: &TaskEnvelopeV2,
         work_output: &Value,
         registry: &AgentRegistry,
+        selector: Option<&LoadAwareSelector>,
     ) -> String {
         let original_query = task
             .context
@@ -204,7 +290,7 @@ impl LlmRouter {
             .unwrap_or("Unknown");
 
         let workflow_history = Self::format_workflow_history(task);
-        let agent_catalog = Self::format_agent_catalog(registry);
+        let agent_catalog = Self::format_agent_catalog(registry, selector);
 
         format!(
             r#"You are a workflow router. Your job is to decide what happens next after an agent completes work.
@@ -245,6 +331,58 @@ Make your routing decision:"#,
         )
     }
 
+    /// Call the LLM and parse its structured routing decision
+    async fn request_decision(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<(String, RoutingDecisionOutput), AgentError> {
+        let response = self
+            .provider
+            .complete(request)
+            .await
+            .map_err(AgentError::from)?;
+
+        let content = response
+            .content
+            .ok_or_else(|| AgentError::llm_error("No content in LLM response"))?;
+
+        let routing_output: RoutingDecisionOutput =
+            serde_json::from_str(&content).map_err(|e| {
+                warn!(
+                    error = %e,
+                    response = %content,
+                    "Failed to parse LLM routing decision"
+                );
+                AgentError::InvalidInput {
+                    message: format!("Failed to parse routing decision: {e}"),
+                }
+            })?;
+
+        Ok((content, routing_output))
+    }
+
+    /// Corrective feedback when a forward names an agent that isn't registered
+    ///
+    /// `capability:` targets are resolved later by the pipeline and are not checked.
+    fn unknown_target_feedback(
+        output: &RoutingDecisionOutput,
+        registry: &AgentRegistry,
+    ) -> Option<String> {
+        let next_agent = output.next_agent.as_deref().filter(|next_agent| {
+            !output.workflow_complete && !next_agent.starts_with(CAPABILITY_TARGET_PREFIX)
+        })?;
+        let agent_match = match_agent_id(registry, next_agent);
+        if matches!(agent_match, AgentMatch::Exact(_)) {
+            return None;
+        }
+
+        Some(format!(
+            "Your decision cannot be carried out: {}. Make the decision again, using an \
+             agent id exactly as listed in AVAILABLE AGENTS for next_agent.",
+            describe_unknown_agent(registry, next_agent, &agent_match)
+        ))
+    }
+
     /// Parse LLM response into RoutingDecision
     fn parse_routing_decision(
         output: &RoutingDecisionOutput,
@@ -292,6 +430,7 @@ Make your routing decision:"#,
                 next_agent: next_agent.clone(),
                 next_instruction: next_instruction.clone(),
                 forwarded_data: work_output.clone(),
+                sticky: true,
             })
         }
     }
@@ -305,6 +444,17 @@ impl Router for LlmRouter {
         work_output: &Value,
         registry: &AgentRegistry,
     ) -> Result<RoutingDecision, AgentError> {
+        self.explain_next_step(original_task, work_output, registry)
+            .await
+            .map(|explained| explained.decision)
+    }
+
+    async fn explain_next_step(
+        &self,
+        original_task: &TaskEnvelopeV2,
+        work_output: &Value,
+        registry: &AgentRegistry,
+    ) -> Result<ExplainedDecision, AgentError> {
         info!("LlmRouter making routing decision");
 
         // Build completion request with provider-specific structured output
@@ -320,30 +470,26 @@ impl Router for LlmRouter {
         );
 
         // Call LLM provider
-        let response = self
-            .provider
-            .complete(request)
-            .await
-            .map_err(|e| AgentError::LlmError {
-                message: e.to_string(),
-            })?;
-
-        // Parse the response as RoutingDecisionOutput
-        let content = response.content.ok_or_else(|| AgentError::LlmError {
-            message: "No content in LLM response".to_string(),
-        })?;
+        let (content, mut routing_output) = self.request_decision(request.clone()).await?;
 
-        let routing_output: RoutingDecisionOutput =
-            serde_json::from_str(&content).map_err(|e| {
-                warn!(
-                    error = %e,
-                    response = %content,
-                    "Failed to parse LLM routing decision"
-                );
-                AgentError::InvalidInput {
-                    message: format!("Failed to parse routing decision: {e}"),
-                }
-            })?;
+        // Give the LLM one chance to fix a forward to an unregistered agent;
+        // a second miss is left for the pipeline to report
+        if let Some(feedback) = Self::unknown_target_feedback(&routing_output, registry) {
+            warn!(
+                next_agent = routing_output.next_agent.as_deref().unwrap_or("-"),
+                "LLM router chose an unknown agent, retrying with correction"
+            );
+            let mut retry = request;
+            retry.messages.push(Message {
+                role: MessageRole::Assistant,
+                content,
+            });
+            retry.messages.push(Message {
+                role: MessageRole::User,
+                content: feedback,
+            });
+            routing_output = self.request_decision(retry).await?.1;
+        }
 
         info!(
             workflow_complete = routing_output.workflow_complete,
@@ -352,7 +498,10 @@ impl Router for LlmRouter {
         );
 
         // Convert to RoutingDecision
-        Self::parse_routing_decision(&routing_output, work_output)
+        Ok(ExplainedDecision {
+            decision: Self::parse_routing_decision(&routing_output, work_output)?,
+            reasoning: Some(routing_output.reasoning),
+        })
     }
 }
 
@@ -378,8 +527,18 @@ mod tests {
                 original_query: "Test".to_string(),
                 steps_completed: vec![],
                 iteration_count: 0,
+                started_at: None,
+                budget_secs: None,
+                state: None,
+                state_order: Vec::new(),
             }),
             routing_trace: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
         };
 
         let history = LlmRouter::format_workflow_history(&task);
@@ -412,8 +571,18 @@ mod tests {
                     },
                 ],
                 iteration_count: 2,
+                started_at: None,
+                budget_secs: None,
+                state: None,
+                state_order: Vec::new(),
             }),
             routing_trace: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
         };
 
         let history = LlmRouter::format_workflow_history(&task);
@@ -436,11 +605,65 @@ mod tests {
         registry.register_agent(agent1);
         registry.register_agent(agent2);
 
-        let catalog = LlmRouter::format_agent_catalog(&registry);
+        let catalog = LlmRouter::format_agent_catalog(&registry, None);
         assert!(catalog.contains("researcher"));
         assert!(catalog.contains("research, analysis"));
         assert!(catalog.contains("writer"));
         assert!(catalog.contains("writing"));
+        assert!(!catalog.contains("tools:"));
+    }
+
+    #[test]
+    fn test_format_agent_catalog_prefilters_with_load_aware_selector() {
+        let registry = AgentRegistry::new();
+        for (agent_id, load, capability) in [
+            ("writer-busy", 0.95, "writing"),
+            ("writer-idle", 0.4, "writing"),
+            ("writer-cheap", 0.5, "writing"),
+            ("translator", 0.98, "translation"),
+        ] {
+            registry.register_agent(
+                AgentInfo::new(agent_id.to_string(), "ok".to_string(), load)
+                    .with_capabilities(vec![capability.to_string()]),
+            );
+        }
+        let selector = LoadAwareSelector::new(crate::config::LoadAwareSelectionConfig {
+            costs: [("writer-idle".to_string(), 0.3)].into(),
+            ..Default::default()
+        });
+
+        let catalog = LlmRouter::format_agent_catalog(&registry, Some(&selector));
+        let listed: Vec<_> = catalog
+            .lines()
+            .filter_map(|line| line.strip_prefix("- "))
+            .map(|line| line.split_whitespace().next().unwrap())
+            .collect();
+
+        // The overloaded writer is hidden, the only translator is kept, best first
+        assert_eq!(listed, vec!["writer-cheap", "writer-idle", "translator"]);
+    }
+
+    #[test]
+    fn test_format_agent_catalog_includes_manifest_tools() {
+        let registry = AgentRegistry::new();
+        registry.register_agent(AgentInfo::new(
+            "researcher".to_string(),
+            "ok".to_string(),
+            0.3,
+        ));
+        registry.register_manifest(crate::protocol::AgentManifest {
+            agent_id: "researcher".to_string(),
+            description: None,
+            capabilities: vec![],
+            envelope_versions: vec!["2.0".to_string()],
+            tools: vec!["web_search".to_string(), "http_request".to_string()],
+            model: "test-model".to_string(),
+            max_input_bytes: None,
+            timestamp: chrono::Utc::now(),
+        });
+
+        let catalog = LlmRouter::format_agent_catalog(&registry, None);
+        assert!(catalog.contains("tools: web_search, http_request"));
     }
 
     #[test]
@@ -650,8 +873,18 @@ mod tests {
                 original_query: "Test query".to_string(),
                 steps_completed: vec![],
                 iteration_count: 0,
+                started_at: None,
+                budget_secs: None,
+                state: None,
+                state_order: Vec::new(),
             }),
             routing_trace: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
         };
 
         let work_output = json!({"result": "test"});
@@ -734,8 +967,18 @@ mod tests {
                 original_query: "Test query".to_string(),
                 steps_completed: vec![],
                 iteration_count: 0,
+                started_at: None,
+                budget_secs: None,
+                state: None,
+                state_order: Vec::new(),
             }),
             routing_trace: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
         };
 
         let work_output = json!({"result": "test"});
@@ -808,8 +1051,18 @@ mod tests {
                 original_query: "Test query".to_string(),
                 steps_completed: vec![],
                 iteration_count: 0,
+                started_at: None,
+                budget_secs: None,
+                state: None,
+                state_order: Vec::new(),
             }),
             routing_trace: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
         };
 
         let work_output = json!({"result": "test"});
@@ -835,4 +1088,163 @@ mod tests {
             "Anthropic should require tool usage"
         );
     }
+
+    /// Provider that replays scripted decisions and records every request
+    struct ScriptedProvider {
+        decisions: Vec<Value>,
+        requests: std::sync::Mutex<Vec<CompletionRequest>>,
+    }
+
+    impl ScriptedProvider {
+        fn new(decisions: Vec<Value>) -> Self {
+            Self {
+                decisions,
+                requests: std::sync::Mutex::new(Vec::new()),
+            }
+        }
+
+        fn requests(&self) -> Vec<CompletionRequest> {
+            self.requests.lock().unwrap().clone()
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl LlmProvider for ScriptedProvider {
+        fn name(&self) -> &str {
+            "mock"
+        }
+        fn available_models(&self) -> Vec<String> {
+            vec!["mock-model".to_string()]
+        }
+        async fn complete(
+            &self,
+            request: CompletionRequest,
+        ) -> Result<crate::llm::provider::CompletionResponse, crate::llm::provider::LlmError>
+        {
+            let mut requests = self.requests.lock().unwrap();
+            let decision = &self.decisions[requests.len().min(self.decisions.len() - 1)];
+            requests.push(request);
+            Ok(crate::llm::provider::CompletionResponse {
+                content: Some(decision.to_string()),
+                model: "mock-model".to_string(),
+                usage: crate::llm::provider::TokenUsage {
+                    prompt_tokens: 10,
+                    completion_tokens: 5,
+                    total_tokens: 15,
+                    cache_read_tokens: None,
+                    cache_creation_tokens: None,
+                },
+                finish_reason: crate::llm::provider::FinishReason::Stop,
+                tool_calls: None,
+                metadata: Default::default(),
+            })
+        }
+        async fn health_check(&self) -> Result<(), crate::llm::provider::LlmError> {
+            Ok(())
+        }
+    }
+
+    fn forward_to(next_agent: &str) -> Value {
+        json!({
+            "workflow_complete": false,
+            "reasoning": "Needs editing",
+            "next_agent": next_agent,
+            "next_instruction": "Edit the draft"
+        })
+    }
+
+    fn retry_test_task() -> TaskEnvelopeV2 {
+        TaskEnvelopeV2 {
+            task_id: Uuid::new_v4(),
+            conversation_id: "conv1".to_string(),
+            topic: "/test".to_string(),
+            instruction: None,
+            input: json!({}),
+            next: None,
+            version: "2.0".to_string(),
+            context: None,
+            routing_trace: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
+        }
+    }
+
+    fn editor_registry() -> AgentRegistry {
+        let registry = AgentRegistry::new();
+        registry.register_agent(AgentInfo::new(
+            "editor-agent".to_string(),
+            "ok".to_string(),
+            0.1,
+        ));
+        registry.register_agent(AgentInfo::new(
+            "writer-agent".to_string(),
+            "ok".to_string(),
+            0.1,
+        ));
+        registry
+    }
+
+    #[tokio::test]
+    async fn test_unknown_agent_gets_one_corrective_retry() {
+        let provider = Arc::new(ScriptedProvider::new(vec![
+            forward_to("editr"),
+            forward_to("editor-agent"),
+        ]));
+        let router = LlmRouter::new(provider.clone(), "mock-model".to_string());
+
+        let decision = router
+            .decide_next_step(&retry_test_task(), &json!({}), &editor_registry())
+            .await
+            .unwrap();
+        assert_eq!(decision.next_agent(), Some("editor-agent"));
+
+        let requests = provider.requests();
+        assert_eq!(requests.len(), 2);
+        let retry = &requests[1].messages;
+        assert_eq!(retry.len(), requests[0].messages.len() + 2);
+        assert_eq!(retry[retry.len() - 2].role, MessageRole::Assistant);
+        let feedback = &retry.last().unwrap().content;
+        assert!(feedback.contains("unknown agent: editr"), "{feedback}");
+        assert!(
+            feedback.contains("available agents: editor-agent, writer-agent"),
+            "{feedback}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_corrective_retry_is_attempted_once() {
+        let provider = Arc::new(ScriptedProvider::new(vec![
+            forward_to("editr"),
+            forward_to("still-wrong"),
+            forward_to("editor-agent"),
+        ]));
+        let router = LlmRouter::new(provider.clone(), "mock-model".to_string());
+
+        let decision = router
+            .decide_next_step(&retry_test_task(), &json!({}), &editor_registry())
+            .await
+            .unwrap();
+
+        // The second miss is returned for the pipeline to report
+        assert_eq!(decision.next_agent(), Some("still-wrong"));
+        assert_eq!(provider.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_known_agent_and_capability_targets_are_not_retried() {
+        for next_agent in ["editor-agent", "capability:editing"] {
+            let provider = Arc::new(ScriptedProvider::new(vec![forward_to(next_agent)]));
+            let router = LlmRouter::new(provider.clone(), "mock-model".to_string());
+
+            router
+                .decide_next_step(&retry_test_task(), &json!({}), &editor_registry())
+                .await
+                .unwrap();
+            assert_eq!(provider.requests().len(), 1, "{next_agent}");
+        }
+    }
 }
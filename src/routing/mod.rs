@@ -13,14 +13,24 @@
 //! Simple agent discovery and selection helpers for finding agents by capability
 //! or ID. Note: This is for agent DISCOVERY, not workflow routing decisions.
 
+pub mod agent_matcher;
 pub mod agent_selector;
+pub mod audit_log;
+pub mod decision_cache;
 pub mod gatekeeper_router;
 pub mod llm_router;
 pub mod router;
+pub mod rule_router;
 pub mod schema;
+pub mod sticky_routes;
 
+pub use agent_matcher::{match_agent_id, AgentMatch};
 pub use agent_selector::*;
+pub use audit_log::{record_routing_decision, RoutingAuditEntry, RoutingAuditLog, RoutingOutcome};
+pub use decision_cache::DecisionCache;
 pub use gatekeeper_router::{GatekeeperConfig, GatekeeperRouter};
 pub use llm_router::LlmRouter;
-pub use router::{Router, RoutingDecision};
+pub use router::{ExplainedDecision, ForwardBranch, JoinPolicy, Router, RoutingDecision};
+pub use rule_router::RuleRouter;
 pub use schema::RoutingDecisionOutput;
+pub use sticky_routes::StickyRoutes;
@@ -36,9 +36,12 @@
 //!         RoutingDecision::Complete { final_output } => {
 //!             println!("Workflow complete: {:?}", final_output);
 //!         }
-//!         RoutingDecision::Forward { next_agent, next_instruction, forwarded_data } => {
+//!         RoutingDecision::Forward { next_agent, next_instruction, .. } => {
 //!             println!("Forwarding to: {} with instruction: {}", next_agent, next_instruction);
 //!         }
+//!         RoutingDecision::FanOut { branches, aggregator_agent, .. } => {
+//!             println!("Fanning out to {} agents, joined by {}", branches.len(), aggregator_agent);
+//!         }
 //!     }
 //!     Ok(())
 //! }
@@ -98,13 +101,42 @@ pub trait Router: Send + Sync {
         work_output: &Value,
         registry: &AgentRegistry,
     ) -> Result<RoutingDecision, AgentError>;
+
+    /// Decide the next step and report why, for routing dry-run mode
+    ///
+    /// Routers that receive an explanation from their backend (an LLM or an
+    /// external service) override this to return it; the default makes the
+    /// decision without one.
+    async fn explain_next_step(
+        &self,
+        original_task: &TaskEnvelopeV2,
+        work_output: &Value,
+        registry: &AgentRegistry,
+    ) -> Result<ExplainedDecision, AgentError> {
+        Ok(ExplainedDecision {
+            decision: self
+                .decide_next_step(original_task, work_output, registry)
+                .await?,
+            reasoning: None,
+        })
+    }
+}
+
+/// Routing decision together with the router's reasoning
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExplainedDecision {
+    pub decision: RoutingDecision,
+    /// Why the router decided this way, when it says
+    pub reasoning: Option<String>,
 }
 
 /// Routing decision made by a Router
 ///
-/// This enum represents the two possible outcomes after an agent completes work:
+/// This enum represents the possible outcomes after an agent completes work:
 /// 1. The workflow is complete (user's request satisfied)
 /// 2. The workflow continues (forward to another agent)
+/// 3. The workflow continues in parallel (fan out to several agents, then
+///    hand their outputs to an aggregator agent)
 #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum RoutingDecision {
@@ -115,15 +147,98 @@ pub enum RoutingDecision {
     },
     /// Workflow continues - forward to next agent
     Forward {
-        /// Agent ID to forward to (must exist in registry)
+        /// Agent ID to forward to (must exist in registry), or `capability:<name>`
         next_agent: String,
         /// Instruction for the next agent (what to do)
         next_instruction: String,
         /// Data to forward to next agent
         forwarded_data: Value,
+        /// Whether sticky routing may pin a `capability:` target or logical
+        /// agent name to the agent that served this conversation before
+        /// (default: true)
+        #[serde(default = "default_sticky")]
+        sticky: bool,
+    },
+    /// Workflow continues in parallel - send each branch to its agent, then
+    /// send the branches' outputs to the aggregator once they are joined
+    FanOut {
+        /// Branches published at once, each to its own agent
+        branches: Vec<ForwardBranch>,
+        /// Agent ID (or `capability:<name>`) that receives the joined outputs
+        aggregator_agent: String,
+        /// Instruction for the aggregator agent
+        #[serde(default = "default_aggregator_instruction")]
+        aggregator_instruction: String,
+        /// When the branches are joined
+        #[serde(default)]
+        join_policy: JoinPolicy,
     },
 }
 
+fn default_sticky() -> bool {
+    true
+}
+
+fn default_aggregator_instruction() -> String {
+    "Combine the outputs of the parallel branches into one result".to_string()
+}
+
+/// One branch of a fan-out
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ForwardBranch {
+    /// Agent ID to send the branch to, or `capability:<name>`
+    pub next_agent: String,
+    /// Instruction for the branch's agent
+    pub next_instruction: String,
+    /// Data sent to the branch's agent
+    #[serde(default)]
+    pub forwarded_data: Value,
+}
+
+/// When the branches of a fan-out are joined
+///
+/// The join waits for `quorum` branches to succeed, all of them by default.
+/// If every branch settles, or `timeout_secs` passes, with fewer successes,
+/// the branches that did succeed are aggregated when `allow_partial` is set
+/// and at least one did; otherwise the fan-out fails.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct JoinPolicy {
+    /// Successful branches that complete the join (all branches when absent)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quorum: Option<usize>,
+    /// Seconds to wait for the branches (default: 300)
+    #[serde(default = "default_join_timeout_secs")]
+    pub timeout_secs: u64,
+    /// Aggregate the successful branches when the quorum is missed (default: true)
+    #[serde(default = "default_allow_partial")]
+    pub allow_partial: bool,
+}
+
+fn default_join_timeout_secs() -> u64 {
+    300
+}
+
+fn default_allow_partial() -> bool {
+    true
+}
+
+impl Default for JoinPolicy {
+    fn default() -> Self {
+        Self {
+            quorum: None,
+            timeout_secs: default_join_timeout_secs(),
+            allow_partial: default_allow_partial(),
+        }
+    }
+}
+
+impl JoinPolicy {
+    /// Successful branches needed out of `branches`, at least one
+    pub fn required(&self, branches: usize) -> usize {
+        self.quorum.unwrap_or(branches).clamp(1, branches.max(1))
+    }
+}
+
 impl RoutingDecision {
     /// Check if this decision completes the workflow
     pub fn is_complete(&self) -> bool {
@@ -135,6 +250,11 @@ impl RoutingDecision {
         matches!(self, RoutingDecision::Forward { .. })
     }
 
+    /// Check if this decision fans out to several agents
+    pub fn is_fan_out(&self) -> bool {
+        matches!(self, RoutingDecision::FanOut { .. })
+    }
+
     /// Extract next agent ID if this is a Forward decision
     pub fn next_agent(&self) -> Option<&str> {
         match self {
@@ -166,10 +286,50 @@ mod tests {
             next_agent: "editor-agent".to_string(),
             next_instruction: "Polish the document".to_string(),
             forwarded_data: json!({"document": "..."}),
+            sticky: true,
         };
 
         assert!(!decision.is_complete());
         assert!(decision.is_forward());
         assert_eq!(decision.next_agent(), Some("editor-agent"));
     }
+
+    #[test]
+    fn test_fan_out_decision_deserializes_with_defaults() {
+        let decision: RoutingDecision = serde_json::from_value(json!({
+            "type": "fan_out",
+            "branches": [
+                {"next_agent": "legal-agent", "next_instruction": "Review the terms"},
+                {"next_agent": "capability:finance", "next_instruction": "Check the numbers"}
+            ],
+            "aggregator_agent": "editor-agent"
+        }))
+        .unwrap();
+
+        assert!(decision.is_fan_out());
+        assert!(decision.next_agent().is_none());
+        let RoutingDecision::FanOut {
+            branches,
+            join_policy,
+            ..
+        } = decision
+        else {
+            panic!("expected a fan-out");
+        };
+        assert_eq!(branches.len(), 2);
+        assert_eq!(branches[0].forwarded_data, Value::Null);
+        assert_eq!(join_policy, JoinPolicy::default());
+    }
+
+    #[test]
+    fn test_join_policy_required_branches() {
+        assert_eq!(JoinPolicy::default().required(3), 3);
+        let quorum = |quorum| JoinPolicy {
+            quorum: Some(quorum),
+            ..JoinPolicy::default()
+        };
+        assert_eq!(quorum(2).required(3), 2);
+        assert_eq!(quorum(5).required(3), 3);
+        assert_eq!(quorum(0).required(3), 1);
+    }
 }
@@ -0,0 +1,435 @@
+//! Static Rule-Based Router Implementation
+//!
+//! This module implements the Router trait with an ordered list of rules from
+//! `agent.toml`, for deployments that don't want an LLM or external service in
+//! the routing path.
+//!
+//! # Evaluation
+//!
+//! Each rule's conditions are JSON pointers evaluated against a document built
+//! from the work output and workflow context:
+//!
+//! ```json
+//! {
+//!   "output": { ... },
+//!   "context": {
+//!     "original_query": "...",
+//!     "iteration_count": 2,
+//!     "last_agent": "writer-agent"
+//!   }
+//! }
+//! ```
+//!
+//! The first rule whose conditions all match decides. Pointers that don't
+//! resolve never match (except `exists = false`), so unknown fields fall
+//! through to the configured default decision.
+//!
+//! # Example
+//!
+//! ```toml
+//! [routing]
+//! strategy = "rules"
+//!
+//! [routing.rules]
+//! default = { action = "complete" }
+//!
+//! [[routing.rules.rule]]
+//! name = "iteration-limit"
+//! when = [{ pointer = "/context/iteration_count", gte = 3 }]
+//! action = "complete"
+//!
+//! [[routing.rules.rule]]
+//! name = "needs-review"
+//! when = [{ pointer = "/output/needs_review", equals = true }]
+//! action = "forward"
+//! agent = "reviewer-agent"
+//! instruction = "Review the draft titled {{/output/title}}"
+//! ```
+
+use crate::agent::discovery::AgentRegistry;
+use crate::config::{RuleAction, RuleCondition, RuleRouterConfig};
+use crate::error::AgentError;
+use crate::protocol::messages::TaskEnvelopeV2;
+use crate::routing::router::{Router, RoutingDecision};
+use serde_json::{json, Value};
+use tracing::{debug, info};
+
+/// Router that evaluates ordered, statically configured rules
+#[derive(Debug, Clone)]
+pub struct RuleRouter {
+    config: RuleRouterConfig,
+}
+
+impl RuleRouter {
+    /// Create a rule router from `[routing.rules]` configuration
+    pub fn new(config: RuleRouterConfig) -> Self {
+        Self { config }
+    }
+
+    /// Build the document rule pointers are evaluated against - pure function
+    fn build_evaluation_document(task: &TaskEnvelopeV2, work_output: &Value) -> Value {
+        let context = task.context.as_ref();
+        json!({
+            "output": work_output,
+            "context": {
+                "original_query": context.map(|c| c.original_query.as_str()),
+                "iteration_count": context.map_or(0, |c| c.iteration_count),
+                "last_agent": context
+                    .and_then(|c| c.steps_completed.last())
+                    .map(|step| step.agent_id.as_str()),
+            },
+        })
+    }
+
+    /// Check a single condition against the evaluation document - pure function
+    fn condition_matches(condition: &RuleCondition, document: &Value) -> bool {
+        let value = document
+            .pointer(&condition.pointer)
+            .filter(|v| !v.is_null());
+
+        if let Some(exists) = condition.exists {
+            if value.is_some() != exists {
+                return false;
+            }
+        }
+        let Some(value) = value else {
+            // Unresolved pointers only satisfy `exists = false`
+            return condition.exists == Some(false);
+        };
+
+        if let Some(expected) = &condition.equals {
+            if !Self::values_equal(value, expected) {
+                return false;
+            }
+        }
+        if let Some(unexpected) = &condition.not_equals {
+            if Self::values_equal(value, unexpected) {
+                return false;
+            }
+        }
+        if condition.gte.is_some() || condition.lte.is_some() {
+            let Some(number) = value.as_f64() else {
+                return false;
+            };
+            if condition.gte.is_some_and(|min| number < min)
+                || condition.lte.is_some_and(|max| number > max)
+            {
+                return false;
+            }
+        }
+        if let Some(needle) = &condition.contains {
+            let found = match value {
+                Value::String(text) => text.contains(needle.as_str()),
+                Value::Array(items) => items.iter().any(|item| item.as_str() == Some(needle)),
+                _ => false,
+            };
+            if !found {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Compare values, treating numbers by value so `3` equals `3.0` - pure function
+    fn values_equal(actual: &Value, expected: &Value) -> bool {
+        match (actual.as_f64(), expected.as_f64()) {
+            (Some(a), Some(b)) => a == b,
+            _ => actual == expected,
+        }
+    }
+
+    /// Substitute `{{/json/pointer}}` placeholders in an instruction - pure function
+    ///
+    /// Strings are inserted verbatim, other values as JSON; unresolved pointers
+    /// render as an empty string.
+    fn render_instruction(template: &str, document: &Value) -> String {
+        let mut rendered = String::with_capacity(template.len());
+        let mut rest = template;
+
+        while let Some(start) = rest.find("{{") {
+            let Some(len) = rest[start + 2..].find("}}") else {
+                break;
+            };
+            rendered.push_str(&rest[..start]);
+            let pointer = rest[start + 2..start + 2 + len].trim();
+            match document.pointer(pointer) {
+                Some(Value::String(text)) => rendered.push_str(text),
+                Some(Value::Null) | None => {}
+                Some(other) => rendered.push_str(&other.to_string()),
+            }
+            rest = &rest[start + 2 + len + 2..];
+        }
+        rendered.push_str(rest);
+        rendered
+    }
+
+    /// Turn a configured action into a routing decision - pure function
+    fn to_decision(action: &RuleAction, work_output: &Value, document: &Value) -> RoutingDecision {
+        match action {
+            RuleAction::Complete => RoutingDecision::Complete {
+                final_output: work_output.clone(),
+            },
+            RuleAction::Forward { agent, instruction } => RoutingDecision::Forward {
+                next_agent: agent.clone(),
+                next_instruction: Self::render_instruction(instruction, document),
+                forwarded_data: work_output.clone(),
+                sticky: true,
+            },
+        }
+    }
+
+    /// Evaluate rules in order and return the decision - pure function
+    pub fn evaluate(&self, task: &TaskEnvelopeV2, work_output: &Value) -> RoutingDecision {
+        let document = Self::build_evaluation_document(task, work_output);
+
+        for rule in &self.config.rules {
+            if rule
+                .when
+                .iter()
+                .all(|condition| Self::condition_matches(condition, &document))
+            {
+                let decision = Self::to_decision(&rule.action, work_output, &document);
+                info!(
+                    task_id = %task.task_id,
+                    rule = %rule.name,
+                    next_agent = decision.next_agent().unwrap_or("-"),
+                    "Routing rule matched"
+                );
+                return decision;
+            }
+            debug!(task_id = %task.task_id, rule = %rule.name, "Routing rule did not match");
+        }
+
+        let decision = Self::to_decision(&self.config.default, work_output, &document);
+        info!(
+            task_id = %task.task_id,
+            rule = "default",
+            next_agent = decision.next_agent().unwrap_or("-"),
+            "No routing rule matched, using default decision"
+        );
+        decision
+    }
+}
+
+#[async_trait::async_trait]
+impl Router for RuleRouter {
+    async fn decide_next_step(
+        &self,
+        original_task: &TaskEnvelopeV2,
+        work_output: &Value,
+        _registry: &AgentRegistry,
+    ) -> Result<RoutingDecision, AgentError> {
+        Ok(self.evaluate(original_task, work_output))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::RoutingRule;
+    use crate::protocol::messages::{WorkflowContext, WorkflowStep};
+    use uuid::Uuid;
+
+    fn create_task(iteration_count: u32, last_agent: Option<&str>) -> TaskEnvelopeV2 {
+        TaskEnvelopeV2 {
+            task_id: Uuid::new_v4(),
+            conversation_id: "rules-conversation".to_string(),
+            topic: "/control/agents/writer-agent/input".to_string(),
+            instruction: Some("Write a draft".to_string()),
+            input: json!({}),
+            next: None,
+            version: "2.0".to_string(),
+            context: Some(WorkflowContext {
+                original_query: "Write and review an article".to_string(),
+                steps_completed: last_agent
+                    .map(|agent| {
+                        vec![WorkflowStep {
+                            agent_id: agent.to_string(),
+                            action: "work".to_string(),
+                            timestamp: "2024-01-01T00:00:00Z".to_string(),
+                        }]
+                    })
+                    .unwrap_or_default(),
+                iteration_count,
+                started_at: None,
+                budget_secs: None,
+                state: None,
+                state_order: Vec::new(),
+            }),
+            routing_trace: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
+        }
+    }
+
+    fn condition(pointer: &str) -> RuleCondition {
+        RuleCondition {
+            pointer: pointer.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn forward(agent: &str, instruction: &str) -> RuleAction {
+        RuleAction::Forward {
+            agent: agent.to_string(),
+            instruction: instruction.to_string(),
+        }
+    }
+
+    fn create_router() -> RuleRouter {
+        RuleRouter::new(RuleRouterConfig {
+            rules: vec![
+                RoutingRule {
+                    name: "iteration-limit".to_string(),
+                    when: vec![RuleCondition {
+                        gte: Some(3.0),
+                        ..condition("/context/iteration_count")
+                    }],
+                    action: RuleAction::Complete,
+                },
+                RoutingRule {
+                    name: "needs-review".to_string(),
+                    when: vec![
+                        RuleCondition {
+                            equals: Some(json!(true)),
+                            ..condition("/output/needs_review")
+                        },
+                        RuleCondition {
+                            not_equals: Some(json!("reviewer-agent")),
+                            ..condition("/context/last_agent")
+                        },
+                    ],
+                    action: forward(
+                        "reviewer-agent",
+                        "Review '{{/output/title}}' ({{ /output/words }} words)",
+                    ),
+                },
+                RoutingRule {
+                    name: "has-draft".to_string(),
+                    when: vec![condition("/output/draft")],
+                    action: forward("editor-agent", "Polish the draft"),
+                },
+            ],
+            default: RuleAction::Complete,
+        })
+    }
+
+    #[test]
+    fn test_pointer_match_forwards_with_rendered_instruction() {
+        let router = create_router();
+        let output = json!({"needs_review": true, "title": "Rust", "words": 1200});
+
+        let decision = router.evaluate(&create_task(1, Some("writer-agent")), &output);
+
+        assert_eq!(
+            decision,
+            RoutingDecision::Forward {
+                next_agent: "reviewer-agent".to_string(),
+                next_instruction: "Review 'Rust' (1200 words)".to_string(),
+                forwarded_data: output,
+                sticky: true,
+            }
+        );
+    }
+
+    #[test]
+    fn test_first_matching_rule_wins() {
+        let router = create_router();
+        let output = json!({"needs_review": true, "draft": "text"});
+
+        // Iteration limit is listed first, so it beats the forwarding rules
+        let decision = router.evaluate(&create_task(3, Some("writer-agent")), &output);
+        assert!(decision.is_complete());
+
+        // Below the limit, needs-review precedes has-draft
+        let decision = router.evaluate(&create_task(1, Some("writer-agent")), &output);
+        assert_eq!(decision.next_agent(), Some("reviewer-agent"));
+
+        // Context conditions exclude needs-review after the reviewer ran
+        let decision = router.evaluate(&create_task(2, Some("reviewer-agent")), &output);
+        assert_eq!(decision.next_agent(), Some("editor-agent"));
+    }
+
+    #[test]
+    fn test_unmatched_output_uses_default() {
+        let mut router = create_router();
+        let output = json!({"unrelated": 1});
+
+        assert!(router
+            .evaluate(&create_task(0, None), &output)
+            .is_complete());
+
+        router.config.default = forward("fallback-agent", "Handle {{/output/missing}}");
+        assert_eq!(
+            router.evaluate(&create_task(0, None), &output),
+            RoutingDecision::Forward {
+                next_agent: "fallback-agent".to_string(),
+                next_instruction: "Handle ".to_string(),
+                forwarded_data: output,
+                sticky: true,
+            }
+        );
+    }
+
+    #[test]
+    fn test_condition_operators() {
+        let document = json!({
+            "output": {"score": 7, "tags": ["urgent", "draft"], "summary": "short text", "empty": null},
+        });
+        let matches = |c: RuleCondition| RuleRouter::condition_matches(&c, &document);
+
+        assert!(matches(condition("/output/score")));
+        assert!(!matches(condition("/output/missing")));
+        assert!(!matches(condition("/output/empty")));
+        assert!(matches(RuleCondition {
+            exists: Some(false),
+            ..condition("/output/missing")
+        }));
+        assert!(matches(RuleCondition {
+            equals: Some(json!(7.0)),
+            ..condition("/output/score")
+        }));
+        assert!(matches(RuleCondition {
+            gte: Some(5.0),
+            lte: Some(7.0),
+            ..condition("/output/score")
+        }));
+        assert!(!matches(RuleCondition {
+            gte: Some(8.0),
+            ..condition("/output/score")
+        }));
+        assert!(!matches(RuleCondition {
+            gte: Some(1.0),
+            ..condition("/output/summary")
+        }));
+        assert!(matches(RuleCondition {
+            contains: Some("urgent".to_string()),
+            ..condition("/output/tags")
+        }));
+        assert!(matches(RuleCondition {
+            contains: Some("short".to_string()),
+            ..condition("/output/summary")
+        }));
+        assert!(!matches(RuleCondition {
+            not_equals: Some(json!("short text")),
+            ..condition("/output/summary")
+        }));
+    }
+
+    #[tokio::test]
+    async fn test_router_trait_uses_rules() {
+        let router = create_router();
+        let registry = AgentRegistry::new();
+
+        let decision = router
+            .decide_next_step(&create_task(0, None), &json!({"draft": "text"}), &registry)
+            .await
+            .unwrap();
+
+        assert_eq!(decision.next_agent(), Some("editor-agent"));
+    }
+}
@@ -0,0 +1,261 @@
+//! Per-Conversation Sticky Routing
+//!
+//! Stateful agents (ones keeping conversation memory) misbehave when a
+//! conversation bounces between replicas of the same logical agent.
+//! `StickyRoutes` remembers which concrete agent served a conversation for a
+//! `capability:` target or a logical agent name (a prefix shared by replica
+//! ids, e.g. `writer` for `writer-1` and `writer-2`) and pins later forwards
+//! to it while it stays healthy.
+//!
+//! Entries are keyed on `(conversation_id, target)`, expire after `ttl_secs`
+//! without use, and the least recently used entry is evicted once
+//! `max_entries` is reached.
+
+use crate::agent::discovery::{AgentInfo, AgentRegistry};
+use crate::config::StickyRoutingConfig;
+use crate::observability::metrics::metrics;
+use crate::routing::agent_selector::CAPABILITY_TARGET_PREFIX;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+#[derive(Debug, Clone)]
+struct Pin {
+    agent_id: String,
+    last_used: Instant,
+}
+
+/// Thread-safe, bounded map of conversation → agent assignments
+#[derive(Debug)]
+pub struct StickyRoutes {
+    config: StickyRoutingConfig,
+    pins: Mutex<HashMap<(String, String), Pin>>,
+}
+
+impl StickyRoutes {
+    /// Create an empty map
+    pub fn new(config: StickyRoutingConfig) -> Self {
+        Self {
+            config,
+            pins: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Agent a conversation is pinned to for a target, if it can still serve it
+    ///
+    /// Expired pins and pins to agents that are no longer healthy or no longer
+    /// match the target are removed, so the caller fails over to a fresh
+    /// selection.
+    pub fn pinned(
+        &self,
+        conversation_id: &str,
+        target: &str,
+        registry: &AgentRegistry,
+    ) -> Option<AgentInfo> {
+        let key = Self::key(conversation_id, target);
+        let mut pins = self.pins.lock().unwrap();
+        let pin = pins.get_mut(&key)?;
+
+        if pin.last_used.elapsed() > self.ttl() {
+            debug!(
+                conversation_id = %conversation_id,
+                target = %target,
+                "Sticky route expired"
+            );
+            pins.remove(&key);
+            self.publish_size(pins.len());
+            return None;
+        }
+
+        let usable = registry.get_agent(&pin.agent_id).filter(|agent| {
+            agent.is_healthy() && !agent.is_expired() && Self::serves(agent, target)
+        });
+        match usable {
+            Some(agent) => {
+                pin.last_used = Instant::now();
+                Some(agent)
+            }
+            None => {
+                debug!(
+                    conversation_id = %conversation_id,
+                    agent_id = %pin.agent_id,
+                    "Sticky route target is no longer available"
+                );
+                pins.remove(&key);
+                self.publish_size(pins.len());
+                None
+            }
+        }
+    }
+
+    /// Pin a conversation's target to a concrete agent
+    pub fn pin(&self, conversation_id: &str, target: &str, agent_id: &str) {
+        if self.config.max_entries == 0 {
+            return;
+        }
+        let key = Self::key(conversation_id, target);
+        let ttl = self.ttl();
+        let mut pins = self.pins.lock().unwrap();
+
+        if !pins.contains_key(&key) && pins.len() >= self.config.max_entries {
+            pins.retain(|_, pin| pin.last_used.elapsed() <= ttl);
+            if pins.len() >= self.config.max_entries {
+                if let Some(least_recent) = pins
+                    .iter()
+                    .min_by_key(|(_, pin)| pin.last_used)
+                    .map(|(key, _)| key.clone())
+                {
+                    pins.remove(&least_recent);
+                }
+            }
+        }
+
+        pins.insert(
+            key,
+            Pin {
+                agent_id: agent_id.to_string(),
+                last_used: Instant::now(),
+            },
+        );
+        self.publish_size(pins.len());
+    }
+
+    /// Number of pins, including any not yet evicted as expired
+    pub fn len(&self) -> usize {
+        self.pins.lock().unwrap().len()
+    }
+
+    /// Whether no conversations are pinned
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Whether an agent still serves a `capability:` target or logical name
+    fn serves(agent: &AgentInfo, target: &str) -> bool {
+        match target.strip_prefix(CAPABILITY_TARGET_PREFIX) {
+            Some(capability) => agent.can_handle(capability.trim()),
+            None => agent
+                .agent_id
+                .to_lowercase()
+                .starts_with(&target.trim().to_lowercase()),
+        }
+    }
+
+    fn key(conversation_id: &str, target: &str) -> (String, String) {
+        (conversation_id.to_string(), target.trim().to_lowercase())
+    }
+
+    fn ttl(&self) -> Duration {
+        Duration::from_secs(self.config.ttl_secs)
+    }
+
+    fn publish_size(&self, size: usize) {
+        metrics().set_sticky_routes(size);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry(agents: &[(&str, &str)]) -> AgentRegistry {
+        let registry = AgentRegistry::new();
+        for (agent_id, health) in agents {
+            registry.register_agent(
+                AgentInfo::new(agent_id.to_string(), health.to_string(), 0.1)
+                    .with_capabilities(vec!["writing".to_string()]),
+            );
+        }
+        registry
+    }
+
+    fn pinned_id(routes: &StickyRoutes, registry: &AgentRegistry) -> Option<String> {
+        routes
+            .pinned("conv-1", "capability:writing", registry)
+            .map(|agent| agent.agent_id)
+    }
+
+    #[test]
+    fn test_pin_is_per_conversation_and_capability() {
+        let routes = StickyRoutes::new(StickyRoutingConfig::default());
+        let registry = registry(&[("writer-1", "ok"), ("writer-2", "ok")]);
+
+        assert_eq!(pinned_id(&routes, &registry), None);
+        routes.pin("conv-1", "capability:writing", "writer-2");
+        assert_eq!(pinned_id(&routes, &registry), Some("writer-2".to_string()));
+
+        assert!(routes
+            .pinned("conv-2", "capability:writing", &registry)
+            .is_none());
+        assert!(routes
+            .pinned("conv-1", "capability:editing", &registry)
+            .is_none());
+    }
+
+    #[test]
+    fn test_expired_pin_is_evicted() {
+        let routes = StickyRoutes::new(StickyRoutingConfig {
+            ttl_secs: 0,
+            ..Default::default()
+        });
+        let registry = registry(&[("writer-1", "ok")]);
+        routes.pin("conv-1", "capability:writing", "writer-1");
+
+        std::thread::sleep(Duration::from_millis(5));
+        assert_eq!(pinned_id(&routes, &registry), None);
+        assert!(routes.is_empty());
+    }
+
+    #[test]
+    fn test_unhealthy_pin_fails_over() {
+        let routes = StickyRoutes::new(StickyRoutingConfig::default());
+        let registry = registry(&[("writer-1", "error"), ("writer-2", "ok")]);
+        routes.pin("conv-1", "capability:writing", "writer-1");
+
+        assert_eq!(pinned_id(&routes, &registry), None);
+        assert!(routes.is_empty());
+    }
+
+    #[test]
+    fn test_logical_name_pin_requires_matching_replica() {
+        let routes = StickyRoutes::new(StickyRoutingConfig::default());
+        let registry = registry(&[("writer-1", "ok"), ("editor-1", "ok")]);
+
+        routes.pin("conv-1", "writer", "writer-1");
+        assert_eq!(
+            routes
+                .pinned("conv-1", "Writer", &registry)
+                .map(|agent| agent.agent_id),
+            Some("writer-1".to_string())
+        );
+
+        // A pin whose agent no longer matches the name is dropped
+        routes.pin("conv-1", "writer", "editor-1");
+        assert!(routes.pinned("conv-1", "writer", &registry).is_none());
+        assert!(routes.is_empty());
+    }
+
+    #[test]
+    fn test_max_entries_evicts_least_recently_used() {
+        let routes = StickyRoutes::new(StickyRoutingConfig {
+            max_entries: 2,
+            ..Default::default()
+        });
+        let registry = registry(&[("writer-1", "ok")]);
+
+        routes.pin("conv-1", "capability:writing", "writer-1");
+        std::thread::sleep(Duration::from_millis(2));
+        routes.pin("conv-2", "capability:writing", "writer-1");
+        std::thread::sleep(Duration::from_millis(2));
+        // Touch conv-1 so conv-2 becomes the least recently used
+        assert!(pinned_id(&routes, &registry).is_some());
+        routes.pin("conv-3", "capability:writing", "writer-1");
+
+        assert_eq!(routes.len(), 2);
+        assert!(pinned_id(&routes, &registry).is_some());
+        assert!(routes
+            .pinned("conv-2", "capability:writing", &registry)
+            .is_none());
+    }
+}
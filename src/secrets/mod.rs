@@ -0,0 +1,302 @@
+//! Credential references and their resolution
+//!
+//! Config fields that hold credentials (`api_key_env`, `username_env`,
+//! `hmac_key_env`, ...) take a `SecretRef` rather than a plain environment
+//! variable name:
+//!
+//! - `NAME` or `env:NAME` - the environment variable `NAME`
+//! - `file:/path` - the contents of a file, such as a mounted Kubernetes
+//!   secret, with trailing whitespace removed
+//! - `<scheme>:<reference>` - fetched from the `SecretProvider` registered
+//!   for `scheme`, such as `vault:secret/data/agent#api_key`
+//!
+//! `AgentConfig::resolve_secrets` resolves every configured reference once
+//! at startup and caches the values for the config accessors. Values are
+//! wrapped in `Secret`, whose `Debug` and `Display` never print them.
+
+#[cfg(feature = "vault")]
+pub mod vault;
+
+#[cfg(feature = "vault")]
+pub use vault::VaultSecretProvider;
+
+use crate::config::ConfigError;
+use async_trait::async_trait;
+use once_cell::sync::Lazy;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+
+/// Values resolved at startup, by reference
+static RESOLVED: Lazy<RwLock<HashMap<SecretRef, Secret>>> = Lazy::new(Default::default);
+
+/// A credential value that is never printed
+#[derive(Clone, PartialEq, Eq)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn new(value: impl Into<String>) -> Self {
+        Self(value.into())
+    }
+
+    /// The secret value itself; keep it out of logs and errors
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Secret([REDACTED])")
+    }
+}
+
+impl fmt::Display for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("[REDACTED]")
+    }
+}
+
+/// Where a credential comes from
+///
+/// Parsed from and serialized to a string, so existing `*_env` fields holding
+/// a bare environment variable name keep working.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(try_from = "String", into = "String")]
+pub enum SecretRef {
+    /// Environment variable name
+    Env(String),
+    /// File whose contents are the secret
+    File(PathBuf),
+    /// Reference resolved by the provider registered for `scheme`
+    External { scheme: String, reference: String },
+}
+
+impl SecretRef {
+    /// Reference to the environment variable `name`
+    pub fn env(name: impl Into<String>) -> Self {
+        Self::Env(name.into())
+    }
+
+    /// Value resolved at startup, or read from the environment or a file
+    ///
+    /// External references are only available once `SecretResolver` has
+    /// resolved them.
+    pub fn resolve(&self) -> Result<Secret, ConfigError> {
+        if let Some(secret) = RESOLVED.read().ok().and_then(|r| r.get(self).cloned()) {
+            return Ok(secret);
+        }
+        match self {
+            Self::Env(name) => std::env::var(name)
+                .map(Secret)
+                .map_err(|_| ConfigError::EnvVarNotFound(name.clone())),
+            Self::File(path) => std::fs::read_to_string(path)
+                .map(|contents| Secret(contents.trim_end().to_string()))
+                .map_err(|e| self.unavailable(e)),
+            Self::External { scheme, .. } => Err(self.unavailable(format!(
+                "not resolved at startup; no provider for scheme '{scheme}'"
+            ))),
+        }
+    }
+
+    fn unavailable(&self, reason: impl fmt::Display) -> ConfigError {
+        ConfigError::SecretUnavailable {
+            reference: self.to_string(),
+            reason: reason.to_string(),
+        }
+    }
+}
+
+impl FromStr for SecretRef {
+    type Err = ConfigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: &str| {
+            ConfigError::InvalidConfig(format!("Invalid secret reference '{s}': {reason}"))
+        };
+        let Some((scheme, rest)) = s.split_once(':') else {
+            if s.trim().is_empty() {
+                return Err(invalid("must not be empty"));
+            }
+            return Ok(Self::Env(s.to_string()));
+        };
+        if rest.is_empty() {
+            return Err(invalid("nothing after the scheme"));
+        }
+        match scheme {
+            "env" => Ok(Self::Env(rest.to_string())),
+            "file" => Ok(Self::File(PathBuf::from(rest))),
+            _ if !scheme.is_empty()
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') =>
+            {
+                Ok(Self::External {
+                    scheme: scheme.to_string(),
+                    reference: rest.to_string(),
+                })
+            }
+            _ => Err(invalid("scheme must be alphanumeric")),
+        }
+    }
+}
+
+impl TryFrom<String> for SecretRef {
+    type Error = ConfigError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<SecretRef> for String {
+    fn from(secret_ref: SecretRef) -> Self {
+        secret_ref.to_string()
+    }
+}
+
+impl fmt::Display for SecretRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            // Bare names round-trip to the format configs have always used
+            Self::Env(name) => f.write_str(name),
+            Self::File(path) => write!(f, "file:{}", path.display()),
+            Self::External { scheme, reference } => write!(f, "{scheme}:{reference}"),
+        }
+    }
+}
+
+/// Source of secrets for one `<scheme>:` prefix
+#[async_trait]
+pub trait SecretProvider: Send + Sync {
+    /// Scheme this provider resolves, without the colon
+    fn scheme(&self) -> &str;
+
+    /// Fetch the secret named by `reference`, the part after `<scheme>:`
+    async fn fetch(
+        &self,
+        reference: &str,
+    ) -> Result<Secret, Box<dyn std::error::Error + Send + Sync>>;
+}
+
+/// Resolves `SecretRef`s through the built-in sources and registered providers
+#[derive(Default, Clone)]
+pub struct SecretResolver {
+    providers: HashMap<String, Arc<dyn SecretProvider>>,
+}
+
+impl SecretResolver {
+    /// Resolver for environment variables and files only
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Resolver with the providers configured through the environment
+    ///
+    /// With the `vault` feature, registers `VaultSecretProvider` when
+    /// `VAULT_ADDR` is set.
+    pub fn from_environment() -> Result<Self, ConfigError> {
+        #[allow(unused_mut)]
+        let mut resolver = Self::new();
+        #[cfg(feature = "vault")]
+        if let Some(vault) = VaultSecretProvider::from_env()? {
+            resolver = resolver.with_provider(Arc::new(vault));
+        }
+        Ok(resolver)
+    }
+
+    /// Register `provider` for its scheme, replacing any earlier one
+    pub fn with_provider(mut self, provider: Arc<dyn SecretProvider>) -> Self {
+        self.providers
+            .insert(provider.scheme().to_string(), provider);
+        self
+    }
+
+    /// Resolve `secret_ref` and cache the value for `SecretRef::resolve`
+    pub async fn resolve(&self, secret_ref: &SecretRef) -> Result<Secret, ConfigError> {
+        let secret = match secret_ref {
+            SecretRef::External { scheme, reference } => {
+                let provider = self.providers.get(scheme).ok_or_else(|| {
+                    secret_ref.unavailable(format!("no provider for scheme '{scheme}'"))
+                })?;
+                provider
+                    .fetch(reference)
+                    .await
+                    .map_err(|e| secret_ref.unavailable(e))?
+            }
+            _ => secret_ref.resolve()?,
+        };
+        if let Ok(mut resolved) = RESOLVED.write() {
+            resolved.insert(secret_ref.clone(), secret.clone());
+        }
+        Ok(secret)
+    }
+}
+
+impl fmt::Debug for SecretResolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut schemes: Vec<_> = self.providers.keys().collect();
+        schemes.sort();
+        f.debug_struct("SecretResolver")
+            .field("providers", &schemes)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_secret_refs() {
+        assert_eq!(
+            "OPENAI_API_KEY".parse::<SecretRef>().unwrap(),
+            SecretRef::env("OPENAI_API_KEY")
+        );
+        assert_eq!(
+            "env:OPENAI_API_KEY".parse::<SecretRef>().unwrap(),
+            SecretRef::env("OPENAI_API_KEY")
+        );
+        assert_eq!(
+            "file:/run/secrets/api_key".parse::<SecretRef>().unwrap(),
+            SecretRef::File(PathBuf::from("/run/secrets/api_key"))
+        );
+        assert_eq!(
+            "vault:secret/data/agent#api_key"
+                .parse::<SecretRef>()
+                .unwrap(),
+            SecretRef::External {
+                scheme: "vault".to_string(),
+                reference: "secret/data/agent#api_key".to_string(),
+            }
+        );
+        for invalid in ["", "file:", "bad scheme:x", ":x"] {
+            assert!(invalid.parse::<SecretRef>().is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn test_secret_ref_display_round_trips() {
+        for text in ["API_KEY", "file:/run/secrets/key", "vault:kv/agent#key"] {
+            let secret_ref: SecretRef = text.parse().unwrap();
+            assert_eq!(secret_ref.to_string(), text);
+        }
+    }
+
+    #[test]
+    fn test_secret_is_redacted() {
+        let secret = Secret::new("hunter2");
+
+        assert_eq!(format!("{secret}"), "[REDACTED]");
+        assert_eq!(format!("{secret:?}"), "Secret([REDACTED])");
+        assert_eq!(
+            format!("{:?}", Some(secret.clone())),
+            "Some(Secret([REDACTED]))"
+        );
+        assert_eq!(secret.expose(), "hunter2");
+    }
+}
@@ -0,0 +1,85 @@
+//! HashiCorp Vault secret provider (`vault` feature)
+//!
+//! Resolves `vault:<path>#<field>` through Vault's HTTP API with a token:
+//! `GET {VAULT_ADDR}/v1/<path>` with an `X-Vault-Token` header, then reads
+//! `<field>` from the response's `data.data` (KV v2) or `data` (KV v1).
+
+use super::{Secret, SecretProvider};
+use crate::config::ConfigError;
+use async_trait::async_trait;
+use serde_json::Value;
+
+/// Reads secrets from a Vault server's KV engines
+pub struct VaultSecretProvider {
+    address: String,
+    token: Secret,
+    client: reqwest::Client,
+}
+
+impl VaultSecretProvider {
+    pub fn new(address: impl Into<String>, token: Secret) -> Self {
+        Self {
+            address: address.into().trim_end_matches('/').to_string(),
+            token,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Provider for `VAULT_ADDR` and `VAULT_TOKEN`, or `None` without `VAULT_ADDR`
+    pub fn from_env() -> Result<Option<Self>, ConfigError> {
+        let Ok(address) = std::env::var("VAULT_ADDR") else {
+            return Ok(None);
+        };
+        let token = std::env::var("VAULT_TOKEN")
+            .map_err(|_| ConfigError::EnvVarNotFound("VAULT_TOKEN".to_string()))?;
+        Ok(Some(Self::new(address, Secret::new(token))))
+    }
+}
+
+#[async_trait]
+impl SecretProvider for VaultSecretProvider {
+    fn scheme(&self) -> &str {
+        "vault"
+    }
+
+    async fn fetch(
+        &self,
+        reference: &str,
+    ) -> Result<Secret, Box<dyn std::error::Error + Send + Sync>> {
+        let (path, field) = reference.split_once('#').ok_or("expected <path>#<field>")?;
+        let response = self
+            .client
+            .get(format!(
+                "{}/v1/{}",
+                self.address,
+                path.trim_start_matches('/')
+            ))
+            .header("X-Vault-Token", self.token.expose())
+            .send()
+            .await?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(format!("Vault returned {status}").into());
+        }
+        let body: Value = response.json().await?;
+        let data = &body["data"];
+        let fields = if data["data"].is_object() {
+            &data["data"]
+        } else {
+            data
+        };
+        fields[field]
+            .as_str()
+            .map(Secret::new)
+            .ok_or_else(|| format!("no string field '{field}' at {path}").into())
+    }
+}
+
+impl std::fmt::Debug for VaultSecretProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("VaultSecretProvider")
+            .field("address", &self.address)
+            .field("token", &self.token)
+            .finish()
+    }
+}
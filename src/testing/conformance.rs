@@ -0,0 +1,1072 @@
+//! Protocol conformance fixtures and checks
+//!
+//! Golden JSON fixtures for every protocol message, both valid and invalid,
+//! together with the checks this crate applies to them. Implementations in
+//! other languages can run their serializers against the same fixtures; this
+//! crate runs its own serializers against them in its unit tests, so an
+//! accidental field rename or serde attribute change breaks a test instead of
+//! breaking interoperability.
+//!
+//! The rules the fixtures pin down:
+//! - field names are snake_case and enum values are lowercase/snake_case
+//! - an explicit `null` is equivalent to a missing optional field
+//! - topics start with `/` and contain no MQTT wildcards
+//!
+//! # Examples
+//! ```
+//! use agent2389::testing::conformance::{assert_envelope_roundtrip, canonical_json, MessageKind};
+//!
+//! let canonical = canonical_json(MessageKind::TaskEnvelopeV1);
+//! let envelope = assert_envelope_roundtrip(&canonical);
+//! assert_eq!(envelope.conversation_id(), "conv-123");
+//! ```
+
+use crate::protocol::messages::{
+    AckStatus, AgentManifest, AgentStatus, AgentStatusType, BatchItemResult, BatchItemStatus,
+    BatchSummary, CancelMessage, ErrorCode, ErrorDetails, ErrorMessage, NextTask, ResponseMessage,
+    RoutingExplanation, RoutingStep, TaskAck, TaskBatchEnvelope, TaskEnvelope, TaskEnvelopeV2,
+    TaskEnvelopeWrapper, WorkflowContext, WorkflowStep,
+};
+use crate::protocol::validation::{
+    validate_agent_status, validate_error_message, validate_response_message, ValidationErrors,
+};
+use crate::transport::mqtt::MessageHandler;
+use chrono::{DateTime, FixedOffset, TimeZone, Utc};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::fmt;
+use uuid::Uuid;
+
+/// Schema check applied before deserialization
+type SchemaValidator = fn(&Value) -> Result<(), ValidationErrors>;
+
+const TASK_ID: &str = "6f1c2a3b-4d5e-4f60-8a7b-9c0d1e2f3a4b";
+const PARENT_TASK_ID: &str = "0a1b2c3d-4e5f-4a6b-8c7d-9e0f1a2b3c4d";
+const BATCH_ID: &str = "9b8a7c6d-5e4f-4a3b-9c2d-1e0f9a8b7c6d";
+const ITEM_0_TASK_ID: &str = "1c2d3e4f-5a6b-4c7d-8e9f-0a1b2c3d4e5f";
+const ITEM_1_TASK_ID: &str = "2d3e4f5a-6b7c-4d8e-9f0a-1b2c3d4e5f6a";
+
+/// Protocol message types covered by the conformance suite
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MessageKind {
+    TaskEnvelopeV1,
+    TaskEnvelopeV2,
+    AgentStatus,
+    AgentManifest,
+    ErrorMessage,
+    ResponseMessage,
+    TaskAck,
+    CancelMessage,
+    TaskBatchEnvelope,
+    BatchSummary,
+    RoutingExplanation,
+}
+
+impl MessageKind {
+    /// Every message kind, in protocol order
+    pub const ALL: [MessageKind; 11] = [
+        MessageKind::TaskEnvelopeV1,
+        MessageKind::TaskEnvelopeV2,
+        MessageKind::AgentStatus,
+        MessageKind::AgentManifest,
+        MessageKind::ErrorMessage,
+        MessageKind::ResponseMessage,
+        MessageKind::TaskAck,
+        MessageKind::CancelMessage,
+        MessageKind::TaskBatchEnvelope,
+        MessageKind::BatchSummary,
+        MessageKind::RoutingExplanation,
+    ];
+}
+
+impl fmt::Display for MessageKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+/// A golden protocol message
+#[derive(Debug, Clone, Copy)]
+pub struct Fixture {
+    /// Unique fixture name; every kind has one named `canonical`
+    pub name: &'static str,
+    pub kind: MessageKind,
+    pub json: &'static str,
+    /// Why the message must be rejected, or `None` for valid fixtures
+    pub rejection: Option<&'static str>,
+}
+
+impl Fixture {
+    /// Whether the fixture is a message conforming implementations must accept
+    pub fn is_valid(&self) -> bool {
+        self.rejection.is_none()
+    }
+}
+
+/// Messages every conforming implementation must accept
+///
+/// Fixtures named `canonical` are exactly what this crate serializes; see
+/// [`canonical_json`].
+pub const VALID_FIXTURES: &[Fixture] = &[
+    Fixture {
+        name: "canonical",
+        kind: MessageKind::TaskEnvelopeV1,
+        json: r#"{
+  "task_id": "6f1c2a3b-4d5e-4f60-8a7b-9c0d1e2f3a4b",
+  "conversation_id": "conv-123",
+  "topic": "/control/agents/summarizer/input",
+  "instruction": "Summarize the document",
+  "input": {"url": "https://example.com/report"},
+  "next": {
+    "topic": "/control/agents/reviewer/input",
+    "instruction": "Review the summary",
+    "input": null,
+    "next": null
+  },
+  "deadline": "2024-06-01T12:30:00Z",
+  "correlation_id": "workflow-42",
+  "parent_task_id": "0a1b2c3d-4e5f-4a6b-8c7d-9e0f1a2b3c4d"
+}"#,
+        rejection: None,
+    },
+    Fixture {
+        name: "minimal",
+        kind: MessageKind::TaskEnvelopeV1,
+        json: r#"{
+  "task_id": "6f1c2a3b-4d5e-4f60-8a7b-9c0d1e2f3a4b",
+  "conversation_id": "conv-123",
+  "topic": "/control/agents/summarizer/input",
+  "input": {}
+}"#,
+        rejection: None,
+    },
+    Fixture {
+        name: "explicit_nulls",
+        kind: MessageKind::TaskEnvelopeV1,
+        json: r#"{
+  "task_id": "6f1c2a3b-4d5e-4f60-8a7b-9c0d1e2f3a4b",
+  "conversation_id": "conv-123",
+  "topic": "/control/agents/summarizer/input",
+  "instruction": null,
+  "input": null,
+  "next": null,
+  "deadline": null,
+  "correlation_id": null,
+  "parent_task_id": null
+}"#,
+        rejection: None,
+    },
+    Fixture {
+        name: "deadline_with_offset",
+        kind: MessageKind::TaskEnvelopeV1,
+        json: r#"{
+  "task_id": "6f1c2a3b-4d5e-4f60-8a7b-9c0d1e2f3a4b",
+  "conversation_id": "conv-123",
+  "topic": "/control/agents/summarizer/input",
+  "input": {},
+  "deadline": "2024-06-01T14:30:00.250+02:00"
+}"#,
+        rejection: None,
+    },
+    Fixture {
+        name: "canonical",
+        kind: MessageKind::TaskEnvelopeV2,
+        json: r#"{
+  "task_id": "6f1c2a3b-4d5e-4f60-8a7b-9c0d1e2f3a4b",
+  "conversation_id": "conv-123",
+  "topic": "/control/agents/editor/input",
+  "instruction": "Polish the draft",
+  "input": {"draft": "Quarterly results were strong."},
+  "next": null,
+  "version": "2.0",
+  "context": {
+    "original_query": "Write a quarterly update",
+    "steps_completed": [
+      {"agent_id": "writer", "action": "Drafted update", "timestamp": "2024-06-01T12:00:00Z"}
+    ],
+    "iteration_count": 1
+  },
+  "routing_trace": [
+    {
+      "from_agent": "writer",
+      "to_agent": "editor",
+      "reason": "Draft needs editing",
+      "timestamp": "2024-06-01T12:00:00Z",
+      "step_number": 1
+    }
+  ]
+}"#,
+        rejection: None,
+    },
+    Fixture {
+        name: "minimal",
+        kind: MessageKind::TaskEnvelopeV2,
+        json: r#"{
+  "task_id": "6f1c2a3b-4d5e-4f60-8a7b-9c0d1e2f3a4b",
+  "conversation_id": "conv-123",
+  "topic": "/control/agents/editor/input",
+  "input": {},
+  "version": "2.0"
+}"#,
+        rejection: None,
+    },
+    Fixture {
+        name: "canonical",
+        kind: MessageKind::AgentStatus,
+        json: r#"{
+  "agent_id": "summarizer",
+  "status": "available",
+  "timestamp": "2024-06-01T12:00:00Z",
+  "capabilities": ["summarization", "translation"],
+  "description": "Summarizes documents"
+}"#,
+        rejection: None,
+    },
+    Fixture {
+        name: "minimal",
+        kind: MessageKind::AgentStatus,
+        json: r#"{"agent_id": "summarizer", "status": "unavailable", "timestamp": "2024-06-01T12:00:00Z"}"#,
+        rejection: None,
+    },
+    Fixture {
+        name: "canonical",
+        kind: MessageKind::AgentManifest,
+        json: r#"{
+  "agent_id": "summarizer",
+  "description": "Summarizes documents",
+  "capabilities": ["summarization"],
+  "envelope_versions": ["1.0", "2.0"],
+  "tools": ["http_request", "web_search"],
+  "model": "gpt-4o",
+  "max_input_bytes": 65536,
+  "timestamp": "2024-06-01T12:00:00Z"
+}"#,
+        rejection: None,
+    },
+    Fixture {
+        name: "canonical",
+        kind: MessageKind::ErrorMessage,
+        json: r#"{
+  "error": {
+    "code": "rate_limited",
+    "message": "Provider rate limit reached",
+    "retryable": true,
+    "retry_after_ms": 2000
+  },
+  "task_id": "6f1c2a3b-4d5e-4f60-8a7b-9c0d1e2f3a4b",
+  "correlation_id": "workflow-42",
+  "parent_task_id": "0a1b2c3d-4e5f-4a6b-8c7d-9e0f1a2b3c4d"
+}"#,
+        rejection: None,
+    },
+    Fixture {
+        name: "unknown_code",
+        kind: MessageKind::ErrorMessage,
+        json: r#"{
+  "error": {"code": "quota_exhausted", "message": "Monthly quota used up"},
+  "task_id": "6f1c2a3b-4d5e-4f60-8a7b-9c0d1e2f3a4b"
+}"#,
+        rejection: None,
+    },
+    Fixture {
+        name: "canonical",
+        kind: MessageKind::ResponseMessage,
+        json: r#"{
+  "response": "The report covers Q2 revenue growth.",
+  "task_id": "6f1c2a3b-4d5e-4f60-8a7b-9c0d1e2f3a4b",
+  "routing_trace": [
+    {
+      "from_agent": "writer",
+      "to_agent": "editor",
+      "reason": "Draft needs editing",
+      "timestamp": "2024-06-01T12:00:00Z",
+      "step_number": 1
+    }
+  ],
+  "correlation_id": "workflow-42"
+}"#,
+        rejection: None,
+    },
+    Fixture {
+        name: "canonical",
+        kind: MessageKind::TaskAck,
+        json: r#"{
+  "task_id": "6f1c2a3b-4d5e-4f60-8a7b-9c0d1e2f3a4b",
+  "agent_id": "summarizer",
+  "status": "accepted",
+  "accepted_at": "2024-06-01T12:00:00Z",
+  "queue_position": 0,
+  "correlation_id": "workflow-42"
+}"#,
+        rejection: None,
+    },
+    Fixture {
+        name: "rejected",
+        kind: MessageKind::TaskAck,
+        json: r#"{
+  "task_id": "6f1c2a3b-4d5e-4f60-8a7b-9c0d1e2f3a4b",
+  "agent_id": "summarizer",
+  "status": "rejected",
+  "accepted_at": "2024-06-01T12:00:00Z",
+  "queue_position": 0,
+  "error": {"code": "invalid_input", "message": "Retained message", "retryable": false}
+}"#,
+        rejection: None,
+    },
+    Fixture {
+        name: "canonical",
+        kind: MessageKind::CancelMessage,
+        json: r#"{
+  "task_id": "6f1c2a3b-4d5e-4f60-8a7b-9c0d1e2f3a4b",
+  "conversation_id": "conv-123",
+  "reason": "User aborted workflow"
+}"#,
+        rejection: None,
+    },
+    Fixture {
+        name: "canonical",
+        kind: MessageKind::TaskBatchEnvelope,
+        json: r#"{
+  "batch_id": "9b8a7c6d-5e4f-4a3b-9c2d-1e0f9a8b7c6d",
+  "conversation_id": "conv-123",
+  "shared_instruction": "Summarize this document",
+  "items": [{"doc": "a.pdf"}, {"doc": "b.pdf"}]
+}"#,
+        rejection: None,
+    },
+    Fixture {
+        name: "canonical",
+        kind: MessageKind::BatchSummary,
+        json: r#"{
+  "batch_id": "9b8a7c6d-5e4f-4a3b-9c2d-1e0f9a8b7c6d",
+  "conversation_id": "conv-123",
+  "total": 2,
+  "succeeded": 1,
+  "failed": 1,
+  "duplicates": 0,
+  "items": [
+    {"index": 0, "task_id": "1c2d3e4f-5a6b-4c7d-8e9f-0a1b2c3d4e5f", "status": "succeeded"},
+    {
+      "index": 1,
+      "task_id": "2d3e4f5a-6b7c-4d8e-9f0a-1b2c3d4e5f6a",
+      "status": "failed",
+      "error": "Document could not be fetched"
+    }
+  ]
+}"#,
+        rejection: None,
+    },
+    Fixture {
+        name: "canonical",
+        kind: MessageKind::RoutingExplanation,
+        json: r#"{
+  "task_id": "6f1c2a3b-4d5e-4f60-8a7b-9c0d1e2f3a4b",
+  "conversation_id": "conv-123",
+  "agent_id": "writer",
+  "workflow_complete": false,
+  "next_agent": "editor",
+  "next_instruction": "Polish the draft",
+  "reasoning": "Draft needs editing",
+  "iteration_count": 1,
+  "timestamp": "2024-06-01T12:00:00Z"
+}"#,
+        rejection: None,
+    },
+    Fixture {
+        name: "complete_without_reasoning",
+        kind: MessageKind::RoutingExplanation,
+        json: r#"{
+  "task_id": "6f1c2a3b-4d5e-4f60-8a7b-9c0d1e2f3a4b",
+  "conversation_id": "conv-123",
+  "agent_id": "editor",
+  "workflow_complete": true,
+  "next_agent": null,
+  "iteration_count": 2,
+  "timestamp": "2024-06-01T12:00:00Z"
+}"#,
+        rejection: None,
+    },
+];
+
+/// Messages every conforming implementation must reject
+pub const INVALID_FIXTURES: &[Fixture] = &[
+    Fixture {
+        name: "camel_case_fields",
+        kind: MessageKind::TaskEnvelopeV1,
+        json: r#"{
+  "taskId": "6f1c2a3b-4d5e-4f60-8a7b-9c0d1e2f3a4b",
+  "conversationId": "conv-123",
+  "topic": "/control/agents/summarizer/input",
+  "input": {}
+}"#,
+        rejection: Some("field names are snake_case"),
+    },
+    Fixture {
+        name: "null_conversation_id",
+        kind: MessageKind::TaskEnvelopeV1,
+        json: r#"{
+  "task_id": "6f1c2a3b-4d5e-4f60-8a7b-9c0d1e2f3a4b",
+  "conversation_id": null,
+  "topic": "/control/agents/summarizer/input",
+  "input": {}
+}"#,
+        rejection: Some("required fields may not be null"),
+    },
+    Fixture {
+        name: "empty_conversation_id",
+        kind: MessageKind::TaskEnvelopeV1,
+        json: r#"{
+  "task_id": "6f1c2a3b-4d5e-4f60-8a7b-9c0d1e2f3a4b",
+  "conversation_id": "",
+  "topic": "/control/agents/summarizer/input",
+  "input": {}
+}"#,
+        rejection: Some("conversation_id must be non-empty"),
+    },
+    Fixture {
+        name: "missing_input",
+        kind: MessageKind::TaskEnvelopeV1,
+        json: r#"{
+  "task_id": "6f1c2a3b-4d5e-4f60-8a7b-9c0d1e2f3a4b",
+  "conversation_id": "conv-123",
+  "topic": "/control/agents/summarizer/input"
+}"#,
+        rejection: Some("input is required, although it may be null"),
+    },
+    Fixture {
+        name: "task_id_not_uuid",
+        kind: MessageKind::TaskEnvelopeV1,
+        json: r#"{
+  "task_id": "task-1",
+  "conversation_id": "conv-123",
+  "topic": "/control/agents/summarizer/input",
+  "input": {}
+}"#,
+        rejection: Some("task_id must be a UUID"),
+    },
+    Fixture {
+        name: "topic_without_leading_slash",
+        kind: MessageKind::TaskEnvelopeV1,
+        json: r#"{
+  "task_id": "6f1c2a3b-4d5e-4f60-8a7b-9c0d1e2f3a4b",
+  "conversation_id": "conv-123",
+  "topic": "control/agents/summarizer/input",
+  "input": {}
+}"#,
+        rejection: Some("topics start with '/'"),
+    },
+    Fixture {
+        name: "topic_with_wildcard",
+        kind: MessageKind::TaskEnvelopeV1,
+        json: r#"{
+  "task_id": "6f1c2a3b-4d5e-4f60-8a7b-9c0d1e2f3a4b",
+  "conversation_id": "conv-123",
+  "topic": "/control/agents/+/input",
+  "input": {}
+}"#,
+        rejection: Some("topics may not contain MQTT wildcards"),
+    },
+    Fixture {
+        name: "unsupported_version",
+        kind: MessageKind::TaskEnvelopeV2,
+        json: r#"{
+  "task_id": "6f1c2a3b-4d5e-4f60-8a7b-9c0d1e2f3a4b",
+  "conversation_id": "conv-123",
+  "topic": "/control/agents/editor/input",
+  "input": {},
+  "version": "3.0"
+}"#,
+        rejection: Some("only 2.x versions use the v2.0 envelope"),
+    },
+    Fixture {
+        name: "numeric_version",
+        kind: MessageKind::TaskEnvelopeV2,
+        json: r#"{
+  "task_id": "6f1c2a3b-4d5e-4f60-8a7b-9c0d1e2f3a4b",
+  "conversation_id": "conv-123",
+  "topic": "/control/agents/editor/input",
+  "input": {},
+  "version": 2.0
+}"#,
+        rejection: Some("version is a string"),
+    },
+    Fixture {
+        name: "negative_iteration_count",
+        kind: MessageKind::TaskEnvelopeV2,
+        json: r#"{
+  "task_id": "6f1c2a3b-4d5e-4f60-8a7b-9c0d1e2f3a4b",
+  "conversation_id": "conv-123",
+  "topic": "/control/agents/editor/input",
+  "input": {},
+  "version": "2.0",
+  "context": {"original_query": "q", "steps_completed": [], "iteration_count": -1}
+}"#,
+        rejection: Some("iteration_count is non-negative"),
+    },
+    Fixture {
+        name: "capitalized_status",
+        kind: MessageKind::AgentStatus,
+        json: r#"{"agent_id": "summarizer", "status": "Available", "timestamp": "2024-06-01T12:00:00Z"}"#,
+        rejection: Some("status values are lowercase"),
+    },
+    Fixture {
+        name: "missing_timestamp",
+        kind: MessageKind::AgentStatus,
+        json: r#"{"agent_id": "summarizer", "status": "available"}"#,
+        rejection: Some("timestamp is required"),
+    },
+    Fixture {
+        name: "missing_envelope_versions",
+        kind: MessageKind::AgentManifest,
+        json: r#"{"agent_id": "summarizer", "model": "gpt-4o", "timestamp": "2024-06-01T12:00:00Z"}"#,
+        rejection: Some("envelope_versions is required"),
+    },
+    Fixture {
+        name: "uppercase_error_code",
+        kind: MessageKind::ErrorMessage,
+        json: r#"{
+  "error": {"code": "RATE_LIMITED", "message": "Slow down"},
+  "task_id": "6f1c2a3b-4d5e-4f60-8a7b-9c0d1e2f3a4b"
+}"#,
+        rejection: Some("error codes are snake_case"),
+    },
+    Fixture {
+        name: "missing_error_message",
+        kind: MessageKind::ErrorMessage,
+        json: r#"{"error": {"code": "llm_error"}, "task_id": "6f1c2a3b-4d5e-4f60-8a7b-9c0d1e2f3a4b"}"#,
+        rejection: Some("error.message is required"),
+    },
+    Fixture {
+        name: "null_response",
+        kind: MessageKind::ResponseMessage,
+        json: r#"{"response": null, "task_id": "6f1c2a3b-4d5e-4f60-8a7b-9c0d1e2f3a4b"}"#,
+        rejection: Some("response may not be null"),
+    },
+    Fixture {
+        name: "uppercase_status",
+        kind: MessageKind::TaskAck,
+        json: r#"{
+  "task_id": "6f1c2a3b-4d5e-4f60-8a7b-9c0d1e2f3a4b",
+  "agent_id": "summarizer",
+  "status": "ACCEPTED",
+  "accepted_at": "2024-06-01T12:00:00Z",
+  "queue_position": 0
+}"#,
+        rejection: Some("ack status values are snake_case"),
+    },
+    Fixture {
+        name: "negative_queue_position",
+        kind: MessageKind::TaskAck,
+        json: r#"{
+  "task_id": "6f1c2a3b-4d5e-4f60-8a7b-9c0d1e2f3a4b",
+  "agent_id": "summarizer",
+  "status": "accepted",
+  "accepted_at": "2024-06-01T12:00:00Z",
+  "queue_position": -1
+}"#,
+        rejection: Some("queue_position is non-negative"),
+    },
+    Fixture {
+        name: "missing_conversation_id",
+        kind: MessageKind::CancelMessage,
+        json: r#"{"task_id": "6f1c2a3b-4d5e-4f60-8a7b-9c0d1e2f3a4b"}"#,
+        rejection: Some("conversation_id is required"),
+    },
+    Fixture {
+        name: "items_not_array",
+        kind: MessageKind::TaskBatchEnvelope,
+        json: r#"{
+  "batch_id": "9b8a7c6d-5e4f-4a3b-9c2d-1e0f9a8b7c6d",
+  "conversation_id": "conv-123",
+  "items": {"doc": "a.pdf"}
+}"#,
+        rejection: Some("items is an array"),
+    },
+    Fixture {
+        name: "unknown_item_status",
+        kind: MessageKind::BatchSummary,
+        json: r#"{
+  "batch_id": "9b8a7c6d-5e4f-4a3b-9c2d-1e0f9a8b7c6d",
+  "conversation_id": "conv-123",
+  "total": 1,
+  "succeeded": 1,
+  "failed": 0,
+  "duplicates": 0,
+  "items": [{"index": 0, "task_id": "1c2d3e4f-5a6b-4c7d-8e9f-0a1b2c3d4e5f", "status": "ok"}]
+}"#,
+        rejection: Some("item status is succeeded, failed or duplicate"),
+    },
+    Fixture {
+        name: "missing_workflow_complete",
+        kind: MessageKind::RoutingExplanation,
+        json: r#"{
+  "task_id": "6f1c2a3b-4d5e-4f60-8a7b-9c0d1e2f3a4b",
+  "conversation_id": "conv-123",
+  "agent_id": "writer",
+  "next_agent": "editor",
+  "iteration_count": 1,
+  "timestamp": "2024-06-01T12:00:00Z"
+}"#,
+        rejection: Some("workflow_complete is required"),
+    },
+];
+
+/// Topic canonicalization cases: (received topic, canonical form)
+pub const TOPIC_FIXTURES: &[(&str, &str)] = &[
+    (
+        "/control/agents/summarizer/input",
+        "/control/agents/summarizer/input",
+    ),
+    (
+        "control/agents/summarizer/input",
+        "/control/agents/summarizer/input",
+    ),
+    (
+        "/control/agents/summarizer/input/",
+        "/control/agents/summarizer/input",
+    ),
+    (
+        "//control//agents///summarizer/input",
+        "/control/agents/summarizer/input",
+    ),
+    ("", "/"),
+    ("/", "/"),
+];
+
+/// All fixtures, valid and invalid, for one message kind
+pub fn fixtures(kind: MessageKind) -> impl Iterator<Item = &'static Fixture> {
+    VALID_FIXTURES
+        .iter()
+        .chain(INVALID_FIXTURES)
+        .filter(move |fixture| fixture.kind == kind)
+}
+
+/// Parse a message the way this crate does on receipt, returning its canonical serialization
+///
+/// Task envelopes go through the same schema validation and parsing as the
+/// MQTT transport; other messages are schema-validated where a schema exists.
+pub fn parse_message(kind: MessageKind, json: &str) -> Result<Value, String> {
+    match kind {
+        MessageKind::TaskEnvelopeV1 | MessageKind::TaskEnvelopeV2 => {
+            let wrapper = MessageHandler::parse_task_envelope(json.as_bytes())?;
+            let parsed_kind = match wrapper {
+                TaskEnvelopeWrapper::V1(_) => MessageKind::TaskEnvelopeV1,
+                TaskEnvelopeWrapper::V2(_) => MessageKind::TaskEnvelopeV2,
+            };
+            if parsed_kind != kind {
+                return Err(format!("Expected {kind} but parsed as {parsed_kind}"));
+            }
+            to_value(&wrapper)
+        }
+        MessageKind::AgentStatus => decode::<AgentStatus>(json, Some(validate_agent_status)),
+        MessageKind::AgentManifest => decode::<AgentManifest>(json, None),
+        MessageKind::ErrorMessage => decode::<ErrorMessage>(json, Some(validate_error_message)),
+        MessageKind::ResponseMessage => {
+            decode::<ResponseMessage>(json, Some(validate_response_message))
+        }
+        MessageKind::TaskAck => decode::<TaskAck>(json, None),
+        MessageKind::CancelMessage => decode::<CancelMessage>(json, None),
+        MessageKind::TaskBatchEnvelope => decode::<TaskBatchEnvelope>(json, None),
+        MessageKind::BatchSummary => decode::<BatchSummary>(json, None),
+        MessageKind::RoutingExplanation => decode::<RoutingExplanation>(json, None),
+    }
+}
+
+/// Parse a message and check that nothing is lost when it is serialized again
+///
+/// Every non-null field of the input must survive with the same value (an
+/// explicit `null` counts as missing, and timestamps compare as instants), and
+/// the canonical serialization must itself parse back unchanged.
+pub fn check_roundtrip(kind: MessageKind, json: &str) -> Result<Value, String> {
+    let canonical = parse_message(kind, json)?;
+    let input: Value = serde_json::from_str(json).map_err(|e| format!("Invalid JSON: {e}"))?;
+    if let Some(path) = find_lost_field(&input, &canonical, "") {
+        return Err(format!(
+            "Field '{path}' did not survive the round trip; canonical form is {canonical}"
+        ));
+    }
+
+    let reparsed = parse_message(kind, &canonical.to_string())?;
+    if reparsed != canonical {
+        return Err(format!(
+            "Canonical serialization is not stable: {canonical} re-serialized as {reparsed}"
+        ));
+    }
+    Ok(canonical)
+}
+
+/// Check a fixture: valid ones must round-trip, invalid ones must be rejected
+pub fn check_fixture(fixture: &Fixture) -> Result<(), String> {
+    let result = check_roundtrip(fixture.kind, fixture.json);
+    match (fixture.rejection, result) {
+        (None, Ok(_)) | (Some(_), Err(_)) => Ok(()),
+        (None, Err(e)) => Err(format!(
+            "{} fixture '{}' was rejected: {e}",
+            fixture.kind, fixture.name
+        )),
+        (Some(rule), Ok(_)) => Err(format!(
+            "{} fixture '{}' was accepted, but {rule}",
+            fixture.kind, fixture.name
+        )),
+    }
+}
+
+/// Assert that a task envelope is accepted and round-trips without losing fields
+///
+/// # Panics
+/// Panics with the reason when the envelope is rejected or a field is lost.
+pub fn assert_envelope_roundtrip(json: &str) -> TaskEnvelopeWrapper {
+    let kind = match serde_json::from_str::<Value>(json) {
+        Ok(value) if value.get("version").is_some() => MessageKind::TaskEnvelopeV2,
+        _ => MessageKind::TaskEnvelopeV1,
+    };
+    let canonical = assert_message_roundtrip(kind, json);
+    serde_json::from_value(canonical).expect("canonical envelope parses")
+}
+
+/// Assert that a protocol message is accepted and round-trips without losing fields
+///
+/// # Panics
+/// Panics with the reason when the message is rejected or a field is lost.
+pub fn assert_message_roundtrip(kind: MessageKind, json: &str) -> Value {
+    check_roundtrip(kind, json).unwrap_or_else(|e| panic!("{kind} round trip failed: {e}"))
+}
+
+/// Canonical serialization of a sample message of each kind
+///
+/// Generated from this crate's types, so it always reflects the current serde
+/// attributes; the `canonical` fixtures must match it exactly.
+pub fn canonical_message(kind: MessageKind) -> Value {
+    let task_id = uuid(TASK_ID);
+    let timestamp = Utc.with_ymd_and_hms(2024, 6, 1, 12, 0, 0).unwrap();
+    let routing_trace = vec![RoutingStep {
+        from_agent: "writer".to_string(),
+        to_agent: "editor".to_string(),
+        reason: "Draft needs editing".to_string(),
+        timestamp: "2024-06-01T12:00:00Z".to_string(),
+        step_number: 1,
+    }];
+
+    let message = match kind {
+        MessageKind::TaskEnvelopeV1 => to_value(&TaskEnvelope {
+            task_id,
+            conversation_id: "conv-123".to_string(),
+            topic: "/control/agents/summarizer/input".to_string(),
+            instruction: Some("Summarize the document".to_string()),
+            input: json!({"url": "https://example.com/report"}),
+            next: Some(Box::new(NextTask {
+                topic: "/control/agents/reviewer/input".to_string(),
+                instruction: Some("Review the summary".to_string()),
+                input: None,
+                next: None,
+            })),
+            deadline: Some(Utc.with_ymd_and_hms(2024, 6, 1, 12, 30, 0).unwrap()),
+            correlation_id: Some("workflow-42".to_string()),
+            parent_task_id: Some(uuid(PARENT_TASK_ID)),
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
+        }),
+        MessageKind::TaskEnvelopeV2 => to_value(&TaskEnvelopeV2 {
+            task_id,
+            conversation_id: "conv-123".to_string(),
+            topic: "/control/agents/editor/input".to_string(),
+            instruction: Some("Polish the draft".to_string()),
+            input: json!({"draft": "Quarterly results were strong."}),
+            next: None,
+            version: "2.0".to_string(),
+            context: Some(WorkflowContext {
+                original_query: "Write a quarterly update".to_string(),
+                steps_completed: vec![WorkflowStep {
+                    agent_id: "writer".to_string(),
+                    action: "Drafted update".to_string(),
+                    timestamp: "2024-06-01T12:00:00Z".to_string(),
+                }],
+                iteration_count: 1,
+                started_at: None,
+                budget_secs: None,
+                state: None,
+                state_order: Vec::new(),
+            }),
+            routing_trace: Some(routing_trace),
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
+        }),
+        MessageKind::AgentStatus => to_value(&AgentStatus {
+            agent_id: "summarizer".to_string(),
+            status: AgentStatusType::Available,
+            timestamp,
+            capabilities: Some(vec!["summarization".to_string(), "translation".to_string()]),
+            description: Some("Summarizes documents".to_string()),
+        }),
+        MessageKind::AgentManifest => to_value(&AgentManifest {
+            agent_id: "summarizer".to_string(),
+            description: Some("Summarizes documents".to_string()),
+            capabilities: vec!["summarization".to_string()],
+            envelope_versions: vec!["1.0".to_string(), "2.0".to_string()],
+            tools: vec!["http_request".to_string(), "web_search".to_string()],
+            model: "gpt-4o".to_string(),
+            max_input_bytes: Some(65536),
+            timestamp,
+        }),
+        MessageKind::ErrorMessage => to_value(&ErrorMessage {
+            error: ErrorDetails {
+                retry_after_ms: Some(2000),
+                ..ErrorDetails::new(ErrorCode::RateLimited, "Provider rate limit reached")
+            },
+            task_id,
+            correlation_id: Some("workflow-42".to_string()),
+            parent_task_id: Some(uuid(PARENT_TASK_ID)),
+        }),
+        MessageKind::ResponseMessage => to_value(&ResponseMessage {
+            response: "The report covers Q2 revenue growth.".to_string(),
+            task_id,
+            routing_trace: Some(routing_trace),
+            correlation_id: Some("workflow-42".to_string()),
+            parent_task_id: None,
+            conversation_id: None,
+            agent_id: None,
+            completed_at: None,
+            oversized: None,
+            artifacts: Vec::new(),
+        }),
+        MessageKind::TaskAck => to_value(&TaskAck {
+            task_id,
+            agent_id: "summarizer".to_string(),
+            status: AckStatus::Accepted,
+            accepted_at: timestamp,
+            queue_position: 0,
+            error: None,
+            correlation_id: Some("workflow-42".to_string()),
+        }),
+        MessageKind::CancelMessage => to_value(&CancelMessage {
+            task_id,
+            conversation_id: "conv-123".to_string(),
+            reason: Some("User aborted workflow".to_string()),
+        }),
+        MessageKind::TaskBatchEnvelope => to_value(&TaskBatchEnvelope {
+            batch_id: uuid(BATCH_ID),
+            conversation_id: "conv-123".to_string(),
+            shared_instruction: Some("Summarize this document".to_string()),
+            items: vec![json!({"doc": "a.pdf"}), json!({"doc": "b.pdf"})],
+            next: None,
+        }),
+        MessageKind::BatchSummary => to_value(&BatchSummary {
+            batch_id: uuid(BATCH_ID),
+            conversation_id: "conv-123".to_string(),
+            total: 2,
+            succeeded: 1,
+            failed: 1,
+            duplicates: 0,
+            items: vec![
+                BatchItemResult {
+                    index: 0,
+                    task_id: uuid(ITEM_0_TASK_ID),
+                    status: BatchItemStatus::Succeeded,
+                    error: None,
+                },
+                BatchItemResult {
+                    index: 1,
+                    task_id: uuid(ITEM_1_TASK_ID),
+                    status: BatchItemStatus::Failed,
+                    error: Some("Document could not be fetched".to_string()),
+                },
+            ],
+        }),
+        MessageKind::RoutingExplanation => to_value(&RoutingExplanation {
+            task_id,
+            conversation_id: "conv-123".to_string(),
+            agent_id: "writer".to_string(),
+            workflow_complete: false,
+            next_agent: Some("editor".to_string()),
+            next_instruction: Some("Polish the draft".to_string()),
+            reasoning: Some("Draft needs editing".to_string()),
+            iteration_count: 1,
+            timestamp,
+        }),
+    };
+    message.expect("sample messages serialize")
+}
+
+/// Canonical serialization of a sample message as pretty-printed JSON
+pub fn canonical_json(kind: MessageKind) -> String {
+    serde_json::to_string_pretty(&canonical_message(kind)).expect("JSON values serialize")
+}
+
+fn uuid(id: &str) -> Uuid {
+    Uuid::parse_str(id).expect("fixture UUIDs are valid")
+}
+
+fn to_value<T: Serialize>(message: &T) -> Result<Value, String> {
+    serde_json::to_value(message).map_err(|e| format!("Serialization failed: {e}"))
+}
+
+fn decode<T: DeserializeOwned + Serialize>(
+    json: &str,
+    validate: Option<SchemaValidator>,
+) -> Result<Value, String> {
+    let value: Value = serde_json::from_str(json).map_err(|e| format!("Invalid JSON: {e}"))?;
+    if let Some(validate) = validate {
+        validate(&value).map_err(|e| format!("Schema violation: {e}"))?;
+    }
+    let message: T =
+        serde_json::from_value(value).map_err(|e| format!("Deserialization failed: {e}"))?;
+    to_value(&message)
+}
+
+/// Find the first input field missing or changed in the output (pure function)
+fn find_lost_field(input: &Value, output: &Value, path: &str) -> Option<String> {
+    match (input, output) {
+        (Value::Null, _) => None,
+        (Value::Object(fields), Value::Object(output_fields)) => {
+            fields.iter().find_map(|(key, value)| {
+                find_lost_field(
+                    value,
+                    output_fields.get(key).unwrap_or(&Value::Null),
+                    &format!("{path}/{key}"),
+                )
+            })
+        }
+        (Value::Array(items), Value::Array(output_items)) if items.len() == output_items.len() => {
+            items
+                .iter()
+                .zip(output_items)
+                .enumerate()
+                .find_map(|(index, (item, output_item))| {
+                    find_lost_field(item, output_item, &format!("{path}/{index}"))
+                })
+        }
+        (Value::String(a), Value::String(b)) if a == b || same_instant(a, b) => None,
+        _ if input == output => None,
+        _ => Some(if path.is_empty() { "/" } else { path }.to_string()),
+    }
+}
+
+fn same_instant(a: &str, b: &str) -> bool {
+    match (
+        DateTime::<FixedOffset>::parse_from_rfc3339(a),
+        DateTime::<FixedOffset>::parse_from_rfc3339(b),
+    ) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::topics::canonicalize_topic;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_valid_fixtures_round_trip() {
+        for fixture in VALID_FIXTURES {
+            assert!(fixture.is_valid());
+            if let Err(e) = check_fixture(fixture) {
+                panic!("{e}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_invalid_fixtures_are_rejected() {
+        for fixture in INVALID_FIXTURES {
+            assert!(!fixture.is_valid());
+            if let Err(e) = check_fixture(fixture) {
+                panic!("{e}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_canonical_fixtures_match_serializers() {
+        // A field rename or serde attribute change shows up here first
+        for kind in MessageKind::ALL {
+            let canonical: Vec<_> = fixtures(kind)
+                .filter(|fixture| fixture.name == "canonical")
+                .collect();
+            assert_eq!(canonical.len(), 1, "{kind} needs one canonical fixture");
+
+            let golden: Value = serde_json::from_str(canonical[0].json).unwrap();
+            assert_eq!(
+                canonical_message(kind),
+                golden,
+                "{kind} serialization drifted from its golden fixture"
+            );
+            assert_eq!(parse_message(kind, canonical[0].json).unwrap(), golden);
+        }
+    }
+
+    #[test]
+    fn test_fixture_names_are_unique_per_kind() {
+        let mut seen = HashSet::new();
+        for fixture in VALID_FIXTURES.iter().chain(INVALID_FIXTURES) {
+            assert!(
+                seen.insert((fixture.kind, fixture.name)),
+                "duplicate fixture {} {}",
+                fixture.kind,
+                fixture.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_topic_fixtures_match_canonicalization() {
+        for (topic, canonical) in TOPIC_FIXTURES {
+            assert_eq!(canonicalize_topic(topic), *canonical, "topic {topic:?}");
+        }
+    }
+
+    #[test]
+    fn test_assert_envelope_roundtrip_detects_version() {
+        let v1 = assert_envelope_roundtrip(&canonical_json(MessageKind::TaskEnvelopeV1));
+        assert!(matches!(v1, TaskEnvelopeWrapper::V1(_)));
+
+        let v2 = assert_envelope_roundtrip(&canonical_json(MessageKind::TaskEnvelopeV2));
+        assert!(matches!(v2, TaskEnvelopeWrapper::V2(_)));
+    }
+
+    #[test]
+    #[should_panic(expected = "Field '/parentTaskId' did not survive the round trip")]
+    fn test_assert_envelope_roundtrip_reports_dropped_fields() {
+        // Misnamed optional fields are silently ignored by serde, so they must be caught here
+        assert_envelope_roundtrip(
+            r#"{
+  "task_id": "6f1c2a3b-4d5e-4f60-8a7b-9c0d1e2f3a4b",
+  "conversation_id": "conv-123",
+  "topic": "/control/agents/summarizer/input",
+  "input": {},
+  "parentTaskId": "0a1b2c3d-4e5f-4a6b-8c7d-9e0f1a2b3c4d"
+}"#,
+        );
+    }
+
+    #[test]
+    fn test_find_lost_field() {
+        let output = json!({"a": 1, "b": {"c": "2024-06-01T12:00:00Z"}, "d": [1, 2]});
+
+        assert_eq!(find_lost_field(&json!({"a": 1}), &output, ""), None);
+        assert_eq!(find_lost_field(&json!({"x": null}), &output, ""), None);
+        assert_eq!(
+            find_lost_field(
+                &json!({"b": {"c": "2024-06-01T14:00:00+02:00"}}),
+                &output,
+                ""
+            ),
+            None
+        );
+        assert_eq!(
+            find_lost_field(&json!({"a": 2}), &output, ""),
+            Some("/a".to_string())
+        );
+        assert_eq!(
+            find_lost_field(&json!({"d": [1]}), &output, ""),
+            Some("/d".to_string())
+        );
+    }
+}
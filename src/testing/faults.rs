@@ -0,0 +1,656 @@
+//! Fault injection for chaos testing
+//!
+//! `FaultInjectingTransport` wraps any `Transport` and misbehaves according
+//! to a `FaultPolicy`: publishes fail or are delayed, the reported connection
+//! state flaps or goes permanently down, and tasks on the way to the pipeline
+//! are delivered twice.
+//!
+//! Faults are either scheduled by attempt number or drawn from a seeded RNG,
+//! so a failing run can be reproduced from its seed. Every call of a publish
+//! method counts as one publish attempt, numbered from 0 in call order; tasks
+//! are numbered from 0 in arrival order. Progress is published from
+//! background tasks, so schedules meant to hit a particular message should be
+//! narrowed with `FaultPolicy::only` to keep the numbering deterministic.
+
+use crate::processing::TaskJournal;
+use crate::protocol::{
+    AdminMessage, AgentStatus, BatchSummary, CancelMessage, ErrorMessage, ResponseMessage, TaskAck,
+    TaskBatchEnvelope, TaskEnvelopeWrapper,
+};
+use crate::transport::mqtt::{ConnectionState, HealthMetrics};
+use crate::transport::{TopicMessage, Transport};
+use async_trait::async_trait;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use thiserror::Error;
+use tokio::sync::{mpsc, watch};
+use tracing::debug;
+use uuid::Uuid;
+
+/// Errors from a fault-injecting transport
+#[derive(Debug, Error)]
+pub enum FaultError<E> {
+    #[error("Injected publish failure on attempt {attempt}")]
+    PublishFailed { attempt: usize },
+    #[error("Not connected - injected state: {state:?}")]
+    NotConnected { state: ConnectionState },
+    #[error(transparent)]
+    Transport(E),
+}
+
+/// Kind of publish, for narrowing a `FaultPolicy`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PublishKind {
+    Status,
+    Task,
+    Error,
+    Response,
+    BatchSummary,
+    Ack,
+    /// `Transport::publish`, used for progress, manifests and telemetry
+    Raw,
+}
+
+/// Which faults to inject, and when
+///
+/// Rates are probabilities between 0 and 1, drawn from an RNG seeded with
+/// `seed`. Scheduled faults always happen, whatever the rates.
+#[derive(Debug, Clone, Default)]
+pub struct FaultPolicy {
+    seed: u64,
+    publish_failure_rate: f64,
+    failing_publishes: BTreeSet<usize>,
+    latency: Option<(Duration, Duration)>,
+    duplicate_task_rate: f64,
+    duplicated_tasks: BTreeSet<usize>,
+    connection_states: BTreeMap<usize, ConnectionState>,
+    only: Option<BTreeSet<PublishKind>>,
+}
+
+impl FaultPolicy {
+    /// Policy injecting nothing until configured
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            ..Self::default()
+        }
+    }
+
+    /// Fail this fraction of publish attempts
+    pub fn fail_publishes(mut self, rate: f64) -> Self {
+        self.publish_failure_rate = rate;
+        self
+    }
+
+    /// Fail the given publish attempts
+    pub fn fail_publish_attempts(mut self, attempts: impl IntoIterator<Item = usize>) -> Self {
+        self.failing_publishes.extend(attempts);
+        self
+    }
+
+    /// Delay every publish by a random duration between `min` and `max`
+    pub fn with_latency(mut self, min: Duration, max: Duration) -> Self {
+        self.latency = Some((min, max.max(min)));
+        self
+    }
+
+    /// Deliver this fraction of received tasks twice
+    pub fn duplicate_tasks(mut self, rate: f64) -> Self {
+        self.duplicate_task_rate = rate;
+        self
+    }
+
+    /// Deliver the given received tasks twice
+    pub fn duplicate_task_numbers(mut self, tasks: impl IntoIterator<Item = usize>) -> Self {
+        self.duplicated_tasks.extend(tasks);
+        self
+    }
+
+    /// Report `state` from the given publish attempt on
+    ///
+    /// Publishes fail while the reported state is anything but `Connected`.
+    /// A flap is two entries: a disconnected state and `Connected` again.
+    pub fn connection_state_at(mut self, attempt: usize, state: ConnectionState) -> Self {
+        self.connection_states.insert(attempt, state);
+        self
+    }
+
+    /// Only inject publish faults into, and only count, these kinds of publish
+    ///
+    /// Other publishes pass through untouched.
+    pub fn only(mut self, kinds: impl IntoIterator<Item = PublishKind>) -> Self {
+        self.only = Some(kinds.into_iter().collect());
+        self
+    }
+
+    /// Go permanently down from the given publish attempt on
+    pub fn permanently_disconnect_at(self, attempt: usize) -> Self {
+        self.connection_state_at(
+            attempt,
+            ConnectionState::PermanentlyDisconnected("Injected permanent disconnect".to_string()),
+        )
+    }
+}
+
+/// Faults injected so far
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FaultLog {
+    /// Publish attempts counted by the policy, including failed ones
+    pub publish_attempts: usize,
+    /// Publish attempts that failed, injected failures and injected disconnects alike
+    pub failed_publishes: Vec<usize>,
+    /// Tasks delivered twice
+    pub duplicated_tasks: Vec<Uuid>,
+}
+
+/// SplitMix64, so schedules don't depend on an RNG crate's algorithm
+#[derive(Debug)]
+struct SeededRng(u64);
+
+impl SeededRng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in [0, 1)
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[derive(Debug)]
+struct FaultState {
+    rng: SeededRng,
+    state: Option<ConnectionState>,
+    tasks_received: usize,
+    log: FaultLog,
+}
+
+/// What happens to one publish attempt
+struct PublishFault {
+    attempt: usize,
+    delay: Option<Duration>,
+    state: Option<ConnectionState>,
+    fail: bool,
+}
+
+/// Transport wrapper injecting faults according to a `FaultPolicy`
+pub struct FaultInjectingTransport<T: Transport> {
+    inner: T,
+    policy: Arc<FaultPolicy>,
+    faults: Arc<Mutex<FaultState>>,
+    /// Injected states, for `connection_events` once the policy injects any
+    state_events: watch::Sender<ConnectionState>,
+}
+
+impl<T: Transport> FaultInjectingTransport<T> {
+    pub fn new(inner: T, policy: FaultPolicy) -> Self {
+        let faults = FaultState {
+            rng: SeededRng(policy.seed),
+            state: None,
+            tasks_received: 0,
+            log: FaultLog::default(),
+        };
+        let state_events = watch::channel(
+            inner
+                .connection_state()
+                .unwrap_or(ConnectionState::Disconnected("Not connected".to_string())),
+        )
+        .0;
+        Self {
+            inner,
+            policy: Arc::new(policy),
+            faults: Arc::new(Mutex::new(faults)),
+            state_events,
+        }
+    }
+
+    /// The wrapped transport
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    /// Faults injected so far
+    pub fn fault_log(&self) -> FaultLog {
+        self.faults.lock().unwrap().log.clone()
+    }
+
+    /// Draw the faults for the next publish attempt, if `kind` is targeted
+    ///
+    /// Random draws happen on every attempt, so one fault never shifts the
+    /// schedule of the others.
+    fn next_publish(&self, kind: PublishKind) -> Option<PublishFault> {
+        if let Some(only) = &self.policy.only {
+            if !only.contains(&kind) {
+                return None;
+            }
+        }
+        let mut faults = self.faults.lock().unwrap();
+        let attempt = faults.log.publish_attempts;
+        faults.log.publish_attempts += 1;
+
+        if let Some(state) = self.policy.connection_states.get(&attempt) {
+            faults.state = Some(state.clone());
+            self.state_events.send_replace(state.clone());
+        }
+        let latency_draw = faults.rng.next_f64();
+        let failure_draw = faults.rng.next_f64();
+        let delay = self
+            .policy
+            .latency
+            .map(|(min, max)| min + (max - min).mul_f64(latency_draw));
+        let state = faults
+            .state
+            .clone()
+            .filter(|state| *state != ConnectionState::Connected);
+        let fail = self.policy.failing_publishes.contains(&attempt)
+            || failure_draw < self.policy.publish_failure_rate;
+        if state.is_some() || fail {
+            faults.log.failed_publishes.push(attempt);
+        }
+        Some(PublishFault {
+            attempt,
+            delay,
+            state,
+            fail,
+        })
+    }
+
+    /// Apply the faults of the next publish attempt before delegating it
+    async fn check_publish(&self, kind: PublishKind) -> Result<(), FaultError<T::Error>> {
+        let Some(fault) = self.next_publish(kind) else {
+            return Ok(());
+        };
+        if let Some(delay) = fault.delay {
+            tokio::time::sleep(delay).await;
+        }
+        if let Some(state) = fault.state {
+            debug!(attempt = fault.attempt, ?state, "Injecting disconnect");
+            return Err(FaultError::NotConnected { state });
+        }
+        if fault.fail {
+            debug!(attempt = fault.attempt, "Injecting publish failure");
+            return Err(FaultError::PublishFailed {
+                attempt: fault.attempt,
+            });
+        }
+        Ok(())
+    }
+
+    fn injected_state(&self) -> Option<ConnectionState> {
+        self.faults.lock().unwrap().state.clone()
+    }
+}
+
+#[async_trait]
+impl<T: Transport> Transport for FaultInjectingTransport<T> {
+    type Error = FaultError<T::Error>;
+
+    async fn connect(&mut self) -> Result<(), Self::Error> {
+        self.inner.connect().await.map_err(FaultError::Transport)
+    }
+
+    async fn disconnect(&mut self) -> Result<(), Self::Error> {
+        self.inner.disconnect().await.map_err(FaultError::Transport)
+    }
+
+    async fn publish_status(&self, status: &AgentStatus) -> Result<(), Self::Error> {
+        self.check_publish(PublishKind::Status).await?;
+        self.inner
+            .publish_status(status)
+            .await
+            .map_err(FaultError::Transport)
+    }
+
+    async fn publish_task(
+        &self,
+        target_agent: &str,
+        envelope: &TaskEnvelopeWrapper,
+    ) -> Result<(), Self::Error> {
+        self.check_publish(PublishKind::Task).await?;
+        self.inner
+            .publish_task(target_agent, envelope)
+            .await
+            .map_err(FaultError::Transport)
+    }
+
+    async fn publish_error(
+        &self,
+        conversation_id: &str,
+        error: &ErrorMessage,
+    ) -> Result<(), Self::Error> {
+        self.check_publish(PublishKind::Error).await?;
+        self.inner
+            .publish_error(conversation_id, error)
+            .await
+            .map_err(FaultError::Transport)
+    }
+
+    async fn publish_response(
+        &self,
+        conversation_id: &str,
+        response: &ResponseMessage,
+    ) -> Result<(), Self::Error> {
+        self.check_publish(PublishKind::Response).await?;
+        self.inner
+            .publish_response(conversation_id, response)
+            .await
+            .map_err(FaultError::Transport)
+    }
+
+    async fn publish_batch_summary(
+        &self,
+        conversation_id: &str,
+        summary: &BatchSummary,
+    ) -> Result<(), Self::Error> {
+        self.check_publish(PublishKind::BatchSummary).await?;
+        self.inner
+            .publish_batch_summary(conversation_id, summary)
+            .await
+            .map_err(FaultError::Transport)
+    }
+
+    async fn publish_ack(&self, conversation_id: &str, ack: &TaskAck) -> Result<(), Self::Error> {
+        self.check_publish(PublishKind::Ack).await?;
+        self.inner
+            .publish_ack(conversation_id, ack)
+            .await
+            .map_err(FaultError::Transport)
+    }
+
+    async fn subscribe_to_tasks(&mut self) -> Result<(), Self::Error> {
+        self.inner
+            .subscribe_to_tasks()
+            .await
+            .map_err(FaultError::Transport)
+    }
+
+    async fn publish(
+        &self,
+        topic: &str,
+        payload: Vec<u8>,
+        retain: bool,
+    ) -> Result<(), Self::Error> {
+        self.check_publish(PublishKind::Raw).await?;
+        self.inner
+            .publish(topic, payload, retain)
+            .await
+            .map_err(FaultError::Transport)
+    }
+
+    async fn subscribe_topic(
+        &self,
+        topic: &str,
+    ) -> Result<mpsc::Receiver<TopicMessage>, Self::Error> {
+        self.inner
+            .subscribe_topic(topic)
+            .await
+            .map_err(FaultError::Transport)
+    }
+
+    async fn unsubscribe(&self, topic: &str) -> Result<(), Self::Error> {
+        self.inner
+            .unsubscribe(topic)
+            .await
+            .map_err(FaultError::Transport)
+    }
+
+    fn is_connected(&self) -> bool {
+        match self.injected_state() {
+            Some(state) => state == ConnectionState::Connected,
+            None => self.inner.is_connected(),
+        }
+    }
+
+    fn connection_state(&self) -> Option<ConnectionState> {
+        self.injected_state()
+            .or_else(|| self.inner.connection_state())
+    }
+
+    /// Follows the wrapped transport unless the policy injects connection
+    /// states, in which case only the injected ones are reported
+    fn connection_events(&self) -> watch::Receiver<ConnectionState> {
+        if self.policy.connection_states.is_empty() {
+            self.inner.connection_events()
+        } else {
+            self.state_events.subscribe()
+        }
+    }
+
+    fn is_permanently_disconnected(&self) -> bool {
+        match self.injected_state() {
+            Some(state) => matches!(state, ConnectionState::PermanentlyDisconnected(_)),
+            None => self.inner.is_permanently_disconnected(),
+        }
+    }
+
+    fn health_metrics(&self) -> HealthMetrics {
+        let connected = self.is_connected();
+        let metrics = self.inner.health_metrics();
+        HealthMetrics {
+            uptime: metrics.uptime.filter(|_| connected),
+            is_healthy: metrics.is_healthy && connected,
+            ..metrics
+        }
+    }
+
+    /// Received tasks pass through a relay that delivers scheduled ones twice
+    fn set_task_sender(&self, sender: mpsc::Sender<TaskEnvelopeWrapper>) {
+        let (relay_sender, mut relay_receiver) =
+            mpsc::channel::<TaskEnvelopeWrapper>(sender.max_capacity());
+        let policy = self.policy.clone();
+        let faults = self.faults.clone();
+        tokio::spawn(async move {
+            while let Some(task) = relay_receiver.recv().await {
+                let duplicate = {
+                    let mut faults = faults.lock().unwrap();
+                    let number = faults.tasks_received;
+                    faults.tasks_received += 1;
+                    let draw = faults.rng.next_f64();
+                    let duplicate = policy.duplicated_tasks.contains(&number)
+                        || draw < policy.duplicate_task_rate;
+                    if duplicate {
+                        faults.log.duplicated_tasks.push(task.task_id());
+                    }
+                    duplicate
+                };
+                if duplicate {
+                    debug!(task_id = %task.task_id(), "Injecting duplicate task");
+                    if sender.send(task.clone()).await.is_err() {
+                        return;
+                    }
+                }
+                if sender.send(task).await.is_err() {
+                    return;
+                }
+            }
+        });
+        self.inner.set_task_sender(relay_sender);
+    }
+
+    fn set_cancel_sender(&self, sender: mpsc::Sender<CancelMessage>) {
+        self.inner.set_cancel_sender(sender);
+    }
+
+    fn set_batch_sender(&self, sender: mpsc::Sender<TaskBatchEnvelope>) {
+        self.inner.set_batch_sender(sender);
+    }
+
+    fn set_admin_sender(&self, sender: mpsc::Sender<AdminMessage>) {
+        self.inner.set_admin_sender(sender);
+    }
+
+    fn set_task_journal(&self, journal: Arc<TaskJournal>) {
+        self.inner.set_task_journal(journal);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::mocks::MockTransport;
+
+    fn response() -> ResponseMessage {
+        ResponseMessage {
+            response: "done".to_string(),
+            task_id: Uuid::new_v4(),
+            routing_trace: None,
+            correlation_id: None,
+            parent_task_id: None,
+            conversation_id: None,
+            agent_id: None,
+            completed_at: None,
+            oversized: None,
+            artifacts: Vec::new(),
+        }
+    }
+
+    /// Which of `count` publishes fail
+    async fn failing_publishes(policy: FaultPolicy, count: usize) -> Vec<usize> {
+        let transport = FaultInjectingTransport::new(MockTransport::new(), policy);
+        for _ in 0..count {
+            let _ = transport.publish_response("conv", &response()).await;
+        }
+        transport.fault_log().failed_publishes
+    }
+
+    #[tokio::test]
+    async fn test_scheduled_publish_failures() {
+        let transport = FaultInjectingTransport::new(
+            MockTransport::new(),
+            FaultPolicy::new(0).fail_publish_attempts([1]),
+        );
+
+        assert!(transport
+            .publish_response("conv", &response())
+            .await
+            .is_ok());
+        let failed = transport.publish_response("conv", &response()).await;
+        assert!(matches!(
+            failed,
+            Err(FaultError::PublishFailed { attempt: 1 })
+        ));
+        assert!(transport
+            .publish_response("conv", &response())
+            .await
+            .is_ok());
+
+        assert_eq!(transport.inner().published_responses().len(), 2);
+        assert_eq!(transport.fault_log().publish_attempts, 3);
+    }
+
+    #[tokio::test]
+    async fn test_only_targeted_publishes_are_counted() {
+        let transport = FaultInjectingTransport::new(
+            MockTransport::new(),
+            FaultPolicy::new(0)
+                .only([PublishKind::Response])
+                .fail_publish_attempts([0]),
+        );
+
+        transport
+            .publish("/progress", b"{}".to_vec(), false)
+            .await
+            .unwrap();
+        assert!(transport
+            .publish_response("conv", &response())
+            .await
+            .is_err());
+        assert!(transport
+            .publish_response("conv", &response())
+            .await
+            .is_ok());
+
+        assert_eq!(transport.fault_log().publish_attempts, 2);
+        assert_eq!(transport.fault_log().failed_publishes, vec![0]);
+    }
+
+    #[tokio::test]
+    async fn test_random_failures_are_reproducible_from_the_seed() {
+        let policy = || FaultPolicy::new(42).fail_publishes(0.3);
+
+        let first = failing_publishes(policy(), 100).await;
+        let second = failing_publishes(policy(), 100).await;
+        let other_seed = failing_publishes(FaultPolicy::new(7).fail_publishes(0.3), 100).await;
+
+        assert_eq!(first, second);
+        assert_ne!(first, other_seed);
+        assert!((15..45).contains(&first.len()), "{first:?}");
+    }
+
+    #[tokio::test]
+    async fn test_connection_state_flap() {
+        let transport = FaultInjectingTransport::new(
+            MockTransport::new(),
+            FaultPolicy::new(0)
+                .connection_state_at(1, ConnectionState::Reconnecting(1))
+                .connection_state_at(2, ConnectionState::Connected),
+        );
+
+        assert!(transport
+            .publish_response("conv", &response())
+            .await
+            .is_ok());
+        assert!(matches!(
+            transport.publish_response("conv", &response()).await,
+            Err(FaultError::NotConnected {
+                state: ConnectionState::Reconnecting(1)
+            })
+        ));
+        assert!(!transport.is_connected());
+        assert!(!transport.health_metrics().is_healthy);
+
+        assert!(transport
+            .publish_response("conv", &response())
+            .await
+            .is_ok());
+        assert!(transport.is_connected());
+    }
+
+    #[tokio::test]
+    async fn test_permanent_disconnect() {
+        let transport = FaultInjectingTransport::new(
+            MockTransport::new(),
+            FaultPolicy::new(0).permanently_disconnect_at(0),
+        );
+        let mut events = transport.connection_events();
+
+        assert!(!transport.is_permanently_disconnected());
+        assert_eq!(*events.borrow_and_update(), ConnectionState::Connected);
+        for _ in 0..3 {
+            assert!(transport
+                .publish_response("conv", &response())
+                .await
+                .is_err());
+        }
+        assert!(transport.is_permanently_disconnected());
+        assert!(events.has_changed().unwrap());
+        assert!(matches!(
+            *events.borrow(),
+            ConnectionState::PermanentlyDisconnected(_)
+        ));
+        assert_eq!(transport.fault_log().failed_publishes, vec![0, 1, 2]);
+        assert!(transport.inner().published_responses().is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_latency_delays_publishes() {
+        let transport = FaultInjectingTransport::new(
+            MockTransport::new(),
+            FaultPolicy::new(0).with_latency(Duration::from_secs(1), Duration::from_secs(2)),
+        );
+
+        let started = tokio::time::Instant::now();
+        transport
+            .publish_response("conv", &response())
+            .await
+            .unwrap();
+        let elapsed = started.elapsed();
+        assert!(elapsed >= Duration::from_secs(1) && elapsed <= Duration::from_secs(2));
+    }
+}
@@ -0,0 +1,606 @@
+//! Proptest strategies for protocol messages
+//!
+//! Generators for every message that goes over the wire, for property tests
+//! here and in downstream crates. Strings are arbitrary unicode, `next`
+//! chains run up to `MAX_NEXT_DEPTH` deep and JSON values nest with large
+//! strings and numbers.
+//!
+//! Generated values only use what survives a JSON round trip unchanged.
+//! `Some(Value::Null)` encodes the same as `None`, and `ErrorCode::Other`
+//! never carries a known code, since those decode as the known variant.
+//!
+//! Available to this crate's tests and, with the `proptest` feature, to
+//! other crates.
+
+use crate::progress::{ProgressCategory, ProgressEventType, ProgressMessage};
+use crate::protocol::messages::{
+    AgentStatus, AgentStatusType, Artifact, ErrorCode, ErrorDetails, ErrorMessage, NextTask,
+    OversizedResponse, ResponseMessage, RoutingStep, TaskEnvelope, TaskEnvelopeV2, WorkflowContext,
+    WorkflowStep,
+};
+use chrono::{DateTime, Utc};
+use proptest::prelude::*;
+use serde_json::Value;
+use uuid::Uuid;
+
+/// Deepest `next` chain generated
+pub const MAX_NEXT_DEPTH: usize = 32;
+
+/// Arbitrary unicode string of up to `max_chars` characters
+pub fn unicode_string(max_chars: usize) -> impl Strategy<Value = String> {
+    prop::collection::vec(any::<char>(), 0..=max_chars).prop_map(String::from_iter)
+}
+
+/// Short identifier-like string, such as an agent id
+pub fn identifier() -> impl Strategy<Value = String> {
+    "[a-zA-Z0-9._-]{1,32}"
+}
+
+pub fn uuid() -> impl Strategy<Value = Uuid> {
+    any::<u128>().prop_map(Uuid::from_u128)
+}
+
+/// Timestamp between 1970 and 2100 with nanosecond precision
+pub fn timestamp() -> impl Strategy<Value = DateTime<Utc>> {
+    (0i64..4_102_444_800, 0u32..1_000_000_000).prop_map(|(secs, nanos)| {
+        DateTime::from_timestamp(secs, nanos).expect("timestamp is in range")
+    })
+}
+
+/// JSON value nesting arrays and objects, with large strings and numbers
+pub fn json_value() -> impl Strategy<Value = Value> {
+    let leaf = prop_oneof![
+        2 => Just(Value::Null),
+        2 => any::<bool>().prop_map(Value::from),
+        2 => any::<i64>().prop_map(Value::from),
+        2 => any::<u64>().prop_map(Value::from),
+        2 => any::<f64>()
+            .prop_filter("JSON has no NaN or infinity", |number| number.is_finite())
+            .prop_map(Value::from),
+        2 => unicode_string(16).prop_map(Value::from),
+        1 => unicode_string(4096).prop_map(Value::from),
+    ];
+    leaf.prop_recursive(4, 64, 8, |inner| {
+        prop_oneof![
+            prop::collection::vec(inner.clone(), 0..8).prop_map(Value::Array),
+            prop::collection::btree_map(unicode_string(16), inner, 0..8)
+                .prop_map(|map| Value::Object(map.into_iter().collect())),
+        ]
+    })
+}
+
+/// JSON value for an `Option<Value>` field, never `Some(Value::Null)`
+pub fn optional_json_value() -> impl Strategy<Value = Option<Value>> {
+    prop::option::of(json_value().prop_filter("null decodes as None", |value| !value.is_null()))
+}
+
+pub fn next_task() -> impl Strategy<Value = NextTask> {
+    let link = (
+        unicode_string(32),
+        prop::option::of(unicode_string(64)),
+        optional_json_value(),
+    );
+    prop::collection::vec(link, 1..=MAX_NEXT_DEPTH).prop_map(|links| {
+        links
+            .into_iter()
+            .rev()
+            .fold(None, |next, (topic, instruction, input)| {
+                Some(Box::new(NextTask {
+                    topic,
+                    instruction,
+                    input,
+                    next,
+                }))
+            })
+            .map(|task| *task)
+            .expect("chain has at least one link")
+    })
+}
+
+pub fn routing_step() -> impl Strategy<Value = RoutingStep> {
+    (
+        identifier(),
+        identifier(),
+        unicode_string(64),
+        unicode_string(32),
+        any::<u32>(),
+    )
+        .prop_map(
+            |(from_agent, to_agent, reason, timestamp, step_number)| RoutingStep {
+                from_agent,
+                to_agent,
+                reason,
+                timestamp,
+                step_number,
+            },
+        )
+}
+
+pub fn workflow_step() -> impl Strategy<Value = WorkflowStep> {
+    (identifier(), unicode_string(64), unicode_string(32)).prop_map(
+        |(agent_id, action, timestamp)| WorkflowStep {
+            agent_id,
+            action,
+            timestamp,
+        },
+    )
+}
+
+pub fn workflow_context() -> impl Strategy<Value = WorkflowContext> {
+    (
+        unicode_string(128),
+        prop::collection::vec(workflow_step(), 0..8),
+        any::<u32>(),
+        prop::option::of(timestamp()),
+        prop::option::of(any::<u64>()),
+    )
+        .prop_map(
+            |(original_query, steps_completed, iteration_count, started_at, budget_secs)| {
+                WorkflowContext {
+                    original_query,
+                    steps_completed,
+                    iteration_count,
+                    started_at,
+                    budget_secs,
+                    state: None,
+                    state_order: Vec::new(),
+                }
+            },
+        )
+}
+
+/// Fields shared by v1 and v2 envelopes
+#[allow(clippy::type_complexity)]
+fn envelope_fields() -> impl Strategy<
+    Value = (
+        (
+            Uuid,
+            String,
+            String,
+            Option<String>,
+            Value,
+            Option<NextTask>,
+        ),
+        (
+            Option<DateTime<Utc>>,
+            Option<String>,
+            Option<Uuid>,
+            Option<DateTime<Utc>>,
+            Option<String>,
+            Option<String>,
+        ),
+    ),
+> {
+    (
+        (
+            uuid(),
+            unicode_string(32),
+            unicode_string(64),
+            prop::option::of(unicode_string(256)),
+            json_value(),
+            prop::option::of(next_task()),
+        ),
+        (
+            prop::option::of(timestamp()),
+            prop::option::of(unicode_string(32)),
+            prop::option::of(uuid()),
+            prop::option::of(timestamp()),
+            prop::option::of(unicode_string(64)),
+            prop::option::of(unicode_string(32)),
+        ),
+    )
+}
+
+pub fn task_envelope() -> impl Strategy<Value = TaskEnvelope> {
+    envelope_fields().prop_map(
+        |(
+            (task_id, conversation_id, topic, instruction, input, next),
+            (deadline, correlation_id, parent_task_id, published_at, traceparent, sender_id),
+        )| TaskEnvelope {
+            task_id,
+            conversation_id,
+            topic,
+            instruction,
+            input,
+            next: next.map(Box::new),
+            deadline,
+            correlation_id,
+            parent_task_id,
+            published_at,
+            traceparent,
+            sender_id,
+        },
+    )
+}
+
+pub fn task_envelope_v2() -> impl Strategy<Value = TaskEnvelopeV2> {
+    (
+        envelope_fields(),
+        unicode_string(8),
+        prop::option::of(workflow_context()),
+        prop::option::of(prop::collection::vec(routing_step(), 0..8)),
+    )
+        .prop_map(
+            |(
+                (
+                    (task_id, conversation_id, topic, instruction, input, next),
+                    (
+                        deadline,
+                        correlation_id,
+                        parent_task_id,
+                        published_at,
+                        traceparent,
+                        sender_id,
+                    ),
+                ),
+                version,
+                context,
+                routing_trace,
+            )| TaskEnvelopeV2 {
+                task_id,
+                conversation_id,
+                topic,
+                instruction,
+                input,
+                next: next.map(Box::new),
+                version,
+                context,
+                routing_trace,
+                deadline,
+                correlation_id,
+                parent_task_id,
+                published_at,
+                traceparent,
+                sender_id,
+            },
+        )
+}
+
+pub fn agent_status() -> impl Strategy<Value = AgentStatus> {
+    (
+        identifier(),
+        prop_oneof![
+            Just(AgentStatusType::Available),
+            Just(AgentStatusType::Unavailable),
+            Just(AgentStatusType::Paused),
+        ],
+        timestamp(),
+        prop::option::of(prop::collection::vec(unicode_string(32), 0..8)),
+        prop::option::of(unicode_string(256)),
+    )
+        .prop_map(
+            |(agent_id, status, timestamp, capabilities, description)| AgentStatus {
+                agent_id,
+                status,
+                timestamp,
+                capabilities,
+                description,
+            },
+        )
+}
+
+pub fn response_message() -> impl Strategy<Value = ResponseMessage> {
+    (
+        (
+            unicode_string(4096),
+            uuid(),
+            prop::option::of(prop::collection::vec(routing_step(), 0..8)),
+            prop::option::of(unicode_string(32)),
+            prop::option::of(uuid()),
+        ),
+        (
+            prop::option::of(unicode_string(32)),
+            prop::option::of(identifier()),
+            prop::option::of(timestamp()),
+            prop::option::of(oversized_response()),
+            prop::collection::vec(artifact(), 0..3),
+        ),
+    )
+        .prop_map(
+            |(
+                (response, task_id, routing_trace, correlation_id, parent_task_id),
+                (conversation_id, agent_id, completed_at, oversized, artifacts),
+            )| ResponseMessage {
+                response,
+                task_id,
+                routing_trace,
+                correlation_id,
+                parent_task_id,
+                conversation_id,
+                agent_id,
+                completed_at,
+                oversized,
+                artifacts,
+            },
+        )
+}
+
+pub fn artifact() -> impl Strategy<Value = Artifact> {
+    (identifier(), identifier(), json_value()).prop_map(|(name, content_type, data)| Artifact {
+        name,
+        content_type,
+        data,
+    })
+}
+
+pub fn oversized_response() -> impl Strategy<Value = OversizedResponse> {
+    (any::<usize>(), prop::option::of(unicode_string(64))).prop_map(
+        |(original_bytes, reference)| OversizedResponse {
+            original_bytes,
+            reference,
+        },
+    )
+}
+
+pub fn error_code() -> impl Strategy<Value = ErrorCode> {
+    prop_oneof![
+        Just(ErrorCode::ToolExecutionFailed),
+        Just(ErrorCode::LlmError),
+        Just(ErrorCode::InvalidInput),
+        Just(ErrorCode::PipelineDepthExceeded),
+        Just(ErrorCode::InternalError),
+        Just(ErrorCode::Cancelled),
+        Just(ErrorCode::DeadlineExceeded),
+        Just(ErrorCode::StaleTask),
+        Just(ErrorCode::RateLimited),
+        Just(ErrorCode::Timeout),
+        Just(ErrorCode::Overloaded),
+        Just(ErrorCode::PolicyViolation),
+        Just(ErrorCode::Unauthorized),
+        unicode_string(32)
+            .prop_map(ErrorCode::Other)
+            .prop_filter("known codes decode as their variant", |code| {
+                ErrorCode::from(code.as_str()) == *code
+            }),
+    ]
+}
+
+pub fn error_message() -> impl Strategy<Value = ErrorMessage> {
+    (
+        error_code(),
+        unicode_string(256),
+        any::<bool>(),
+        prop::option::of(any::<u64>()),
+        uuid(),
+        prop::option::of(unicode_string(32)),
+        prop::option::of(uuid()),
+    )
+        .prop_map(
+            |(
+                code,
+                message,
+                retryable,
+                retry_after_ms,
+                task_id,
+                correlation_id,
+                parent_task_id,
+            )| {
+                ErrorMessage {
+                    error: ErrorDetails {
+                        code,
+                        message,
+                        retryable,
+                        retry_after_ms,
+                    },
+                    task_id,
+                    correlation_id,
+                    parent_task_id,
+                }
+            },
+        )
+}
+
+pub fn progress_category() -> impl Strategy<Value = ProgressCategory> {
+    prop_oneof![
+        Just(ProgressCategory::General),
+        Just(ProgressCategory::Tool),
+        Just(ProgressCategory::LLM),
+    ]
+}
+
+pub fn progress_event_type() -> impl Strategy<Value = ProgressEventType> {
+    prop_oneof![
+        Just(ProgressEventType::TaskStart),
+        Just(ProgressEventType::TaskComplete),
+        Just(ProgressEventType::TaskError),
+        Just(ProgressEventType::StepStart),
+        Just(ProgressEventType::StepComplete),
+        Just(ProgressEventType::ToolCall),
+        Just(ProgressEventType::ToolComplete),
+        Just(ProgressEventType::ToolError),
+        Just(ProgressEventType::LlmRequest),
+        Just(ProgressEventType::LlmResponse),
+        Just(ProgressEventType::LlmError),
+        Just(ProgressEventType::ValidationStart),
+        Just(ProgressEventType::ValidationComplete),
+        Just(ProgressEventType::ValidationError),
+        Just(ProgressEventType::Processing),
+        Just(ProgressEventType::Warning),
+        Just(ProgressEventType::PercentComplete),
+        Just(ProgressEventType::Custom),
+    ]
+}
+
+pub fn progress_message() -> impl Strategy<Value = ProgressMessage> {
+    (
+        (
+            identifier(),
+            prop::option::of(unicode_string(36)),
+            prop::option::of(unicode_string(32)),
+            timestamp(),
+            progress_category(),
+            progress_event_type(),
+        ),
+        (
+            unicode_string(256),
+            optional_json_value(),
+            prop::option::of(unicode_string(32)),
+            prop::option::of(unicode_string(36)),
+            prop::option::of(0f32..=100.0),
+            prop::option::of(any::<u64>()),
+        ),
+    )
+        .prop_map(
+            |(
+                (agent_id, task_id, conversation_id, timestamp, category, event_type),
+                (message, metadata, correlation_id, parent_task_id, percent, eta_seconds),
+            )| ProgressMessage {
+                agent_id,
+                task_id,
+                conversation_id,
+                timestamp,
+                category,
+                event_type,
+                message,
+                metadata,
+                correlation_id,
+                parent_task_id,
+                percent,
+                eta_seconds,
+            },
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::messages::TaskEnvelopeWrapper;
+    use serde::de::DeserializeOwned;
+    use serde::Serialize;
+    use std::fmt::Debug;
+
+    fn round_trip<T: Serialize + DeserializeOwned>(message: &T) -> T {
+        let json = serde_json::to_string(message).unwrap();
+        serde_json::from_str(&json).unwrap()
+    }
+
+    /// Decode `message` with an extra field the type does not know
+    fn with_extra_field<T: Serialize + DeserializeOwned>(
+        message: &T,
+        name: &str,
+        value: Value,
+    ) -> T {
+        let mut json = serde_json::to_value(message).unwrap();
+        json.as_object_mut()
+            .unwrap()
+            .insert(format!("x-unknown-{name}"), value);
+        serde_json::from_value(json).unwrap()
+    }
+
+    fn assert_wire_compatible<T>(message: T, name: &str, extra: Value) -> Result<(), TestCaseError>
+    where
+        T: Serialize + DeserializeOwned + PartialEq + Debug,
+    {
+        prop_assert_eq!(&round_trip(&message), &message);
+        prop_assert_eq!(&with_extra_field(&message, name, extra), &message);
+        Ok(())
+    }
+
+    // ========== Shrunk Regressions ==========
+
+    #[test]
+    fn test_float_in_next_input_round_trips_exactly() {
+        // Lost its last digit before serde_json's float_roundtrip was enabled
+        let next = NextTask {
+            topic: String::new(),
+            instruction: None,
+            input: Some(serde_json::json!([-1.2377325307852735e243])),
+            next: None,
+        };
+        assert_eq!(round_trip(&next), next);
+    }
+
+    #[test]
+    fn test_null_metadata_decodes_as_none() {
+        // Both encode as `"metadata": null`, so generators never produce Some(Null)
+        let mut progress = ProgressMessage::new(
+            "_".to_string(),
+            ProgressCategory::General,
+            ProgressEventType::TaskStart,
+            String::new(),
+        );
+        progress.metadata = Some(Value::Null);
+        assert_eq!(round_trip(&progress).metadata, None);
+    }
+
+    // ========== Properties ==========
+
+    proptest! {
+        #[test]
+        fn task_envelope_is_wire_compatible(
+            task in task_envelope(),
+            name in identifier(),
+            extra in json_value(),
+        ) {
+            assert_wire_compatible(task, &name, extra)?;
+        }
+
+        #[test]
+        fn task_envelope_v2_is_wire_compatible(
+            task in task_envelope_v2(),
+            name in identifier(),
+            extra in json_value(),
+        ) {
+            assert_wire_compatible(task, &name, extra)?;
+        }
+
+        #[test]
+        fn workflow_context_is_wire_compatible(
+            context in workflow_context(),
+            name in identifier(),
+            extra in json_value(),
+        ) {
+            assert_wire_compatible(context, &name, extra)?;
+        }
+
+        #[test]
+        fn agent_status_is_wire_compatible(
+            status in agent_status(),
+            name in identifier(),
+            extra in json_value(),
+        ) {
+            assert_wire_compatible(status, &name, extra)?;
+        }
+
+        #[test]
+        fn response_message_is_wire_compatible(
+            response in response_message(),
+            name in identifier(),
+            extra in json_value(),
+        ) {
+            assert_wire_compatible(response, &name, extra)?;
+        }
+
+        #[test]
+        fn error_message_is_wire_compatible(
+            error in error_message(),
+            name in identifier(),
+            extra in json_value(),
+        ) {
+            assert_wire_compatible(error, &name, extra)?;
+        }
+
+        #[test]
+        fn progress_message_is_wire_compatible(
+            progress in progress_message(),
+            name in identifier(),
+            extra in json_value(),
+        ) {
+            assert_wire_compatible(progress, &name, extra)?;
+        }
+
+        #[test]
+        fn v2_envelope_decodes_as_v2_variant(task in task_envelope_v2()) {
+            let json = serde_json::to_string(&task).unwrap();
+            let wrapper: TaskEnvelopeWrapper = serde_json::from_str(&json).unwrap();
+            prop_assert_eq!(wrapper, TaskEnvelopeWrapper::V2(task));
+        }
+
+        #[test]
+        fn v1_envelope_decodes_as_v1_variant(task in task_envelope()) {
+            let json = serde_json::to_string(&task).unwrap();
+            let wrapper: TaskEnvelopeWrapper = serde_json::from_str(&json).unwrap();
+            prop_assert_eq!(wrapper, TaskEnvelopeWrapper::V1(task));
+        }
+    }
+}
@@ -0,0 +1,323 @@
+//! Integration test harness running agents against an embedded MQTT broker
+//!
+//! `TestHarness` starts a `rumqttd` broker on an ephemeral local port, runs
+//! any number of agents against it with injected LLM providers, and talks to
+//! them through its own MQTT client: tasks are published to an agent's input
+//! topic and conversation responses are awaited with a timeout. Nothing
+//! outside the test process is needed.
+//!
+//! Dropping the harness stops its agents and its client, also when the test
+//! panics. The broker thread has no shutdown API and lives until the test
+//! process exits; each harness gets its own port, so brokers never share
+//! state between tests.
+//!
+//! Only available with the `test-harness` feature.
+
+use crate::agent::lifecycle::{AgentLifecycle, LifecycleError};
+use crate::config::{AgentConfig, ConfigError, MqttSection};
+use crate::llm::provider::LlmProvider;
+use crate::protocol::messages::{ErrorMessage, ResponseMessage, TaskEnvelopeWrapper};
+use crate::transport::mqtt::{MqttClient, MqttError, PayloadCodec};
+use crate::transport::TopicMessage;
+use std::collections::HashMap;
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tokio::sync::mpsc;
+use tracing::debug;
+
+/// How long `EmbeddedBroker::start` waits for the broker to accept connections
+const BROKER_STARTUP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Errors from the test harness
+#[derive(Debug, Error)]
+pub enum HarnessError {
+    #[error("Embedded broker failed to start: {0}")]
+    Broker(String),
+    #[error("Agent configuration error")]
+    Config(#[source] ConfigError),
+    #[error("Agent {agent_id} failed to start")]
+    Agent {
+        agent_id: String,
+        #[source]
+        source: LifecycleError,
+    },
+    #[error("Harness MQTT client error")]
+    Transport(#[source] MqttError),
+    #[error("Conversation {conversation_id} was never submitted through the harness")]
+    UnknownConversation { conversation_id: String },
+    #[error("No response from {agent_id} in conversation {conversation_id} within {waited:?}")]
+    Timeout {
+        conversation_id: String,
+        agent_id: String,
+        waited: Duration,
+    },
+    #[error("Agent {agent_id} published an error: {}", .error.error.message)]
+    AgentError {
+        agent_id: String,
+        error: Box<ErrorMessage>,
+    },
+}
+
+/// `rumqttd` broker serving MQTT 3.1.1 and 5 on a local ephemeral port
+#[derive(Debug)]
+pub struct EmbeddedBroker {
+    address: SocketAddr,
+}
+
+impl EmbeddedBroker {
+    /// Start a broker and wait until it accepts connections
+    pub fn start() -> Result<Self, HarnessError> {
+        let v4_address = free_local_address()?;
+        let address = free_local_address()?;
+        let config = rumqttd::Config {
+            id: 0,
+            router: rumqttd::RouterConfig {
+                max_connections: 64,
+                max_outgoing_packet_count: 200,
+                max_segment_size: 1024 * 1024,
+                max_segment_count: 10,
+                ..Default::default()
+            },
+            v4: Some(HashMap::from([(
+                "v4".to_string(),
+                server_settings("v4", v4_address),
+            )])),
+            v5: Some(HashMap::from([(
+                "v5".to_string(),
+                server_settings("v5", address),
+            )])),
+            ..Default::default()
+        };
+
+        std::thread::Builder::new()
+            .name(format!("embedded-broker-{}", address.port()))
+            .spawn(move || {
+                let mut broker = rumqttd::Broker::new(config);
+                if let Err(e) = broker.start() {
+                    tracing::error!("Embedded broker stopped: {}", e);
+                }
+            })
+            .map_err(|e| HarnessError::Broker(e.to_string()))?;
+
+        let started = Instant::now();
+        while TcpStream::connect(address).is_err() {
+            if started.elapsed() > BROKER_STARTUP_TIMEOUT {
+                return Err(HarnessError::Broker(format!(
+                    "not listening on {address} after {BROKER_STARTUP_TIMEOUT:?}"
+                )));
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        debug!("Embedded broker listening on {}", address);
+        Ok(Self { address })
+    }
+
+    /// Broker URL for `[mqtt] broker_url`
+    pub fn url(&self) -> String {
+        format!("mqtt://{}", self.address)
+    }
+
+    /// MQTT configuration pointing at this broker
+    pub fn mqtt_section(&self) -> MqttSection {
+        MqttSection {
+            broker_url: self.url(),
+            username_env: None,
+            password_env: None,
+            heartbeat_interval_secs: 900,
+            payload_format: Default::default(),
+        }
+    }
+}
+
+/// Reserve a free port by binding to it and letting it go again
+fn free_local_address() -> Result<SocketAddr, HarnessError> {
+    TcpListener::bind("127.0.0.1:0")
+        .and_then(|listener| listener.local_addr())
+        .map_err(|e| HarnessError::Broker(format!("no free local port: {e}")))
+}
+
+fn server_settings(name: &str, listen: SocketAddr) -> rumqttd::ServerSettings {
+    rumqttd::ServerSettings {
+        name: name.to_string(),
+        listen,
+        tls: None,
+        next_connection_delay_ms: 1,
+        connections: rumqttd::ConnectionSettings {
+            connection_timeout_ms: 5000,
+            max_payload_size: 1024 * 1024,
+            max_inflight_count: 100,
+            auth: None,
+            external_auth: None,
+            dynamic_filters: true,
+        },
+    }
+}
+
+/// Embedded broker, running agents and a client submitting tasks to them
+pub struct TestHarness {
+    broker: EmbeddedBroker,
+    client: MqttClient,
+    agents: Vec<AgentLifecycle<MqttClient>>,
+    conversations: HashMap<String, mpsc::Receiver<TopicMessage>>,
+}
+
+impl TestHarness {
+    /// Start a broker and connect the harness client to it
+    pub async fn start() -> Result<Self, HarnessError> {
+        let broker = EmbeddedBroker::start()?;
+        let mut client = MqttClient::new("test-harness", broker.mqtt_section())
+            .await
+            .map_err(HarnessError::Transport)?;
+        client.connect().await.map_err(HarnessError::Transport)?;
+        Ok(Self {
+            broker,
+            client,
+            agents: Vec::new(),
+            conversations: HashMap::new(),
+        })
+    }
+
+    /// The broker the agents are connected to
+    pub fn broker(&self) -> &EmbeddedBroker {
+        &self.broker
+    }
+
+    /// Minimal configuration for an agent connected to the embedded broker
+    ///
+    /// Adjust it before passing it to `start_agent_with_config`.
+    pub fn agent_config(&self, agent_id: &str) -> Result<AgentConfig, HarnessError> {
+        let mut config: AgentConfig = toml::from_str(&format!(
+            r#"
+[agent]
+id = "{agent_id}"
+description = "Agent started by the test harness"
+
+[mqtt]
+broker_url = "{}"
+
+[llm]
+provider = "mock"
+model = "mock-model"
+api_key_env = "HARNESS_UNUSED_API_KEY"
+system_prompt = "You are a helpful AI agent."
+"#,
+            self.broker.url()
+        ))
+        .map_err(|e| HarnessError::Config(ConfigError::TomlParse(e)))?;
+        config.mqtt = self.broker.mqtt_section();
+        Ok(config)
+    }
+
+    /// Start an agent with the default harness configuration
+    pub async fn start_agent(
+        &mut self,
+        agent_id: &str,
+        llm_provider: impl LlmProvider + 'static,
+    ) -> Result<(), HarnessError> {
+        let config = self.agent_config(agent_id)?;
+        self.start_agent_with_config(config, Box::new(llm_provider))
+            .await
+    }
+
+    /// Start an agent and wait until it is subscribed to its input topic
+    pub async fn start_agent_with_config(
+        &mut self,
+        config: AgentConfig,
+        llm_provider: Box<dyn LlmProvider>,
+    ) -> Result<(), HarnessError> {
+        let agent_id = config.agent.id.clone();
+        let failed = |source| HarnessError::Agent {
+            agent_id: agent_id.clone(),
+            source,
+        };
+        let transport = MqttClient::new(&agent_id, config.mqtt.clone())
+            .await
+            .map_err(|e| failed(LifecycleError::TransportError(Box::new(e))))?;
+        let mut lifecycle = AgentLifecycle::new(config, transport, llm_provider);
+        lifecycle.initialize().await.map_err(failed)?;
+        lifecycle.start().await.map_err(failed)?;
+        self.agents.push(lifecycle);
+        Ok(())
+    }
+
+    /// Publish a task to an agent's input topic
+    ///
+    /// Subscribes to the task's conversation first, so no response published
+    /// before `await_response` is called gets lost.
+    pub async fn submit_task(
+        &mut self,
+        agent_id: &str,
+        task: TaskEnvelopeWrapper,
+    ) -> Result<(), HarnessError> {
+        let conversation_id = task.conversation_id().to_string();
+        if !self.conversations.contains_key(&conversation_id) {
+            let receiver = self
+                .client
+                .subscribe_topic(&format!("/conversations/{conversation_id}/+"))
+                .await
+                .map_err(HarnessError::Transport)?;
+            self.conversations.insert(conversation_id, receiver);
+        }
+        self.client
+            .publish_task(agent_id, &task)
+            .await
+            .map_err(HarnessError::Transport)
+    }
+
+    /// Wait for `agent_id` to publish a response in a submitted conversation
+    ///
+    /// Messages from other agents in the conversation are skipped. An error
+    /// published by `agent_id` fails with `HarnessError::AgentError`.
+    pub async fn await_response(
+        &mut self,
+        conversation_id: &str,
+        agent_id: &str,
+        timeout: Duration,
+    ) -> Result<ResponseMessage, HarnessError> {
+        let receiver = self.conversations.get_mut(conversation_id).ok_or_else(|| {
+            HarnessError::UnknownConversation {
+                conversation_id: conversation_id.to_string(),
+            }
+        })?;
+        let topic = format!("/conversations/{conversation_id}/{agent_id}");
+        let timed_out = || HarnessError::Timeout {
+            conversation_id: conversation_id.to_string(),
+            agent_id: agent_id.to_string(),
+            waited: timeout,
+        };
+
+        tokio::time::timeout(timeout, async {
+            while let Some((received_topic, payload)) = receiver.recv().await {
+                if received_topic != topic {
+                    continue;
+                }
+                let Ok(value) = PayloadCodec::decode_value(&payload, None) else {
+                    continue;
+                };
+                if let Ok(error) = serde_json::from_value::<ErrorMessage>(value.clone()) {
+                    return Err(HarnessError::AgentError {
+                        agent_id: agent_id.to_string(),
+                        error: Box::new(error),
+                    });
+                }
+                if let Ok(response) = serde_json::from_value::<ResponseMessage>(value) {
+                    return Ok(response);
+                }
+            }
+            Err(timed_out())
+        })
+        .await
+        .map_err(|_| timed_out())?
+    }
+
+    /// Stop every agent and disconnect the harness client
+    ///
+    /// Dropping the harness does the same without waiting for the agents.
+    pub async fn shutdown(mut self) {
+        for agent in &mut self.agents {
+            let _ = agent.shutdown().await;
+        }
+        let _ = self.client.disconnect().await;
+    }
+}
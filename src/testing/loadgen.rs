@@ -0,0 +1,498 @@
+//! Load generation for pipeline throughput baselines
+//!
+//! `run_load` feeds synthetic tasks through a real `AgentPipeline` built on
+//! `MockTransport` and `MockLlmProvider`, so the numbers measure the
+//! pipeline itself: channel hand-off, the nine-step algorithm, publishing.
+//! Each task gets its own conversation and, when a tool latency is set, one
+//! round through a tool that sleeps for that long.
+//!
+//! Timing comes from the processor's progress stream: a task's channel wait
+//! runs from just before it is sent to the pipeline until its `TaskStart`
+//! event, and its end-to-end latency until its `TaskComplete` event.
+//!
+//! Allocation counts need [`CountingAllocator`] installed as the global
+//! allocator, which only a binary can do:
+//!
+//! ```ignore
+//! #[global_allocator]
+//! static ALLOCATOR: CountingAllocator = CountingAllocator;
+//! ```
+//!
+//! Without it reports show allocations as n/a. Counts cover the whole
+//! process while the load runs, mock bookkeeping included.
+
+use crate::agent::pipeline::{AgentPipeline, PipelineError};
+use crate::agent::processor::AgentProcessor;
+use crate::config::AgentConfig;
+use crate::progress::sink::{ProgressSink, SinkProgress};
+use crate::progress::{ProgressEventType, ProgressMessage};
+use crate::protocol::messages::{TaskEnvelope, TaskEnvelopeWrapper};
+use crate::testing::mocks::{MockLlmProvider, MockTransport};
+use crate::tools::{Tool, ToolDescription, ToolError, ToolSystem};
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+const AGENT_ID: &str = "loadgen-agent";
+const TOOL_NAME: &str = "wait";
+const MAX_PIPELINE_DEPTH: usize = 16;
+
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+static ALLOCATED_BYTES: AtomicU64 = AtomicU64::new(0);
+
+/// Global allocator counting allocations on top of the system allocator
+pub struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(layout.size() as u64, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(new_size as u64, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+/// Allocations made by the process, as counted by [`CountingAllocator`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AllocationCount {
+    pub allocations: u64,
+    pub bytes: u64,
+}
+
+impl AllocationCount {
+    /// Counts so far, or `None` when `CountingAllocator` isn't installed
+    pub fn now() -> Option<Self> {
+        let before = ALLOCATIONS.load(Ordering::Relaxed);
+        drop(std::hint::black_box(Box::new(0u64)));
+        let after = ALLOCATIONS.load(Ordering::Relaxed);
+        (after > before).then_some(Self {
+            allocations: after,
+            bytes: ALLOCATED_BYTES.load(Ordering::Relaxed),
+        })
+    }
+
+    fn since(self, start: Self) -> Self {
+        Self {
+            allocations: self.allocations.saturating_sub(start.allocations),
+            bytes: self.bytes.saturating_sub(start.bytes),
+        }
+    }
+}
+
+/// Shape of one load run
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoadConfig {
+    /// Tasks sent through the pipeline
+    pub tasks: usize,
+    /// Capacity of the channel feeding the pipeline
+    pub queue_size: usize,
+    /// Tasks the pipeline may process at once (`max_concurrent_tasks`)
+    pub workers: usize,
+    /// Time the tool called by every task takes; no tool round when `None`
+    pub tool_latency: Option<Duration>,
+}
+
+impl LoadConfig {
+    /// `tasks` tasks through a 64-slot queue, one worker, no tool round
+    pub fn new(tasks: usize) -> Self {
+        Self {
+            tasks,
+            queue_size: 64,
+            workers: 1,
+            tool_latency: None,
+        }
+    }
+
+    pub fn queue_size(mut self, queue_size: usize) -> Self {
+        self.queue_size = queue_size.max(1);
+        self
+    }
+
+    pub fn workers(mut self, workers: usize) -> Self {
+        self.workers = workers.max(1);
+        self
+    }
+
+    pub fn tool_latency(mut self, tool_latency: Duration) -> Self {
+        self.tool_latency = Some(tool_latency);
+        self
+    }
+
+    /// Short description for benchmark ids and report rows
+    pub fn label(&self) -> String {
+        let tool = self.tool_latency.map_or("none".to_string(), |latency| {
+            format!("{}ms", latency.as_millis())
+        });
+        format!(
+            "tasks={}/queue={}/workers={}/tool={tool}",
+            self.tasks, self.queue_size, self.workers
+        )
+    }
+}
+
+/// Distribution of a set of durations
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct LatencyStats {
+    pub p50: Duration,
+    pub p90: Duration,
+    pub p99: Duration,
+    pub max: Duration,
+    pub mean: Duration,
+}
+
+impl LatencyStats {
+    /// Nearest-rank percentiles; all zero for no samples
+    pub fn from_samples(mut samples: Vec<Duration>) -> Self {
+        if samples.is_empty() {
+            return Self::default();
+        }
+        samples.sort_unstable();
+        let percentile = |p: usize| {
+            let rank = (samples.len() * p).div_ceil(100).max(1);
+            samples[rank - 1]
+        };
+        let total: Duration = samples.iter().sum();
+        Self {
+            p50: percentile(50),
+            p90: percentile(90),
+            p99: percentile(99),
+            max: samples[samples.len() - 1],
+            mean: total / samples.len() as u32,
+        }
+    }
+}
+
+/// Outcome of one load run
+#[derive(Debug, Clone)]
+pub struct LoadReport {
+    pub config: LoadConfig,
+    /// Tasks that published a result
+    pub completed: usize,
+    /// Tasks sent that never completed
+    pub failed: usize,
+    /// From the first send until the pipeline stopped
+    pub elapsed: Duration,
+    pub end_to_end: LatencyStats,
+    pub channel_wait: LatencyStats,
+    /// `None` when `CountingAllocator` isn't the global allocator
+    pub allocations: Option<AllocationCount>,
+}
+
+impl LoadReport {
+    /// Completed tasks per second
+    pub fn throughput(&self) -> f64 {
+        let secs = self.elapsed.as_secs_f64();
+        if secs > 0.0 {
+            self.completed as f64 / secs
+        } else {
+            0.0
+        }
+    }
+
+    /// Allocations per sent task
+    pub fn allocations_per_task(&self) -> Option<f64> {
+        let allocations = self.allocations?;
+        (self.config.tasks > 0).then_some(allocations.allocations as f64 / self.config.tasks as f64)
+    }
+}
+
+/// Markdown table with one row per report, for pasting into PRs
+pub fn markdown_table(reports: &[LoadReport]) -> String {
+    let mut table = String::from(
+        "| tasks | queue | workers | tool latency | tasks/s | p50 | p90 | p99 | max | wait p50 | wait p99 | allocs/task | failed |\n\
+         |------:|------:|--------:|-------------:|--------:|----:|----:|----:|----:|---------:|---------:|------------:|-------:|\n",
+    );
+    for report in reports {
+        let config = &report.config;
+        let tool = config.tool_latency.map_or("-".to_string(), |latency| {
+            format!("{}ms", latency.as_millis())
+        });
+        let allocations = report
+            .allocations_per_task()
+            .map_or("n/a".to_string(), |per_task| format!("{per_task:.0}"));
+        let _ = writeln!(
+            table,
+            "| {} | {} | {} | {tool} | {:.1} | {} | {} | {} | {} | {} | {} | {allocations} | {} |",
+            config.tasks,
+            config.queue_size,
+            config.workers,
+            report.throughput(),
+            format_duration(report.end_to_end.p50),
+            format_duration(report.end_to_end.p90),
+            format_duration(report.end_to_end.p99),
+            format_duration(report.end_to_end.max),
+            format_duration(report.channel_wait.p50),
+            format_duration(report.channel_wait.p99),
+            report.failed,
+        );
+    }
+    table
+}
+
+fn format_duration(duration: Duration) -> String {
+    if duration >= Duration::from_millis(1) {
+        format!("{:.2}ms", duration.as_secs_f64() * 1000.0)
+    } else {
+        format!("{}µs", duration.as_micros())
+    }
+}
+
+/// When each task was sent, started and completed
+#[derive(Debug, Default)]
+struct Timings {
+    sent: HashMap<String, Instant>,
+    started: HashMap<String, Instant>,
+    completed: HashMap<String, Instant>,
+}
+
+/// Progress sink recording task start and completion times
+#[derive(Clone, Default)]
+struct TimingSink(Arc<Mutex<Timings>>);
+
+impl ProgressSink for TimingSink {
+    fn send(&self, message: ProgressMessage) {
+        let Some(task_id) = message.task_id else {
+            return;
+        };
+        let now = Instant::now();
+        let mut timings = self.0.lock().unwrap();
+        match message.event_type {
+            ProgressEventType::TaskStart => {
+                timings.started.entry(task_id).or_insert(now);
+            }
+            ProgressEventType::TaskComplete => {
+                timings.completed.insert(task_id, now);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Tool that sleeps for a fixed time
+struct WaitTool(Duration);
+
+#[async_trait]
+impl Tool for WaitTool {
+    fn describe(&self) -> ToolDescription {
+        ToolDescription {
+            name: TOOL_NAME.to_string(),
+            description: "Waits before returning".to_string(),
+            parameters: json!({"type": "object"}),
+        }
+    }
+
+    async fn initialize(&mut self, _config: Option<&Value>) -> Result<(), ToolError> {
+        Ok(())
+    }
+
+    async fn execute(&self, _parameters: &Value) -> Result<Value, ToolError> {
+        tokio::time::sleep(self.0).await;
+        Ok(json!({"waited_ms": self.0.as_millis() as u64}))
+    }
+}
+
+fn agent_config() -> AgentConfig {
+    toml::from_str(&format!(
+        r#"
+[agent]
+id = "{AGENT_ID}"
+description = "Agent driven by the load generator"
+
+[mqtt]
+broker_url = "mqtt://localhost:1883"
+
+[llm]
+provider = "mock"
+model = "mock-model"
+api_key_env = "LOADGEN_UNUSED_API_KEY"
+system_prompt = "You are a helpful AI agent."
+"#
+    ))
+    .expect("load generator config is valid")
+}
+
+fn synthetic_task(index: usize) -> TaskEnvelopeWrapper {
+    TaskEnvelopeWrapper::V1(TaskEnvelope {
+        task_id: Uuid::new_v4(),
+        conversation_id: format!("load-{index}"),
+        topic: format!("/control/agents/{AGENT_ID}/input"),
+        instruction: Some(format!("Synthetic task {index}")),
+        input: json!({"index": index}),
+        next: None,
+        deadline: None,
+        correlation_id: None,
+        parent_task_id: None,
+        published_at: None,
+        traceparent: None,
+        sender_id: None,
+    })
+}
+
+/// Send `config.tasks` tasks through a fresh pipeline and time them
+///
+/// Returns once every task has been processed, or with the pipeline's
+/// error if it stopped early.
+pub async fn run_load(config: &LoadConfig) -> Result<LoadReport, PipelineError> {
+    let mut llm = MockLlmProvider::single_response("Load task done");
+    let mut tool_system = ToolSystem::new();
+    if let Some(latency) = config.tool_latency {
+        tool_system.register_tool(TOOL_NAME, Box::new(WaitTool(latency)));
+        // One tool round per task: the follow-up prompt carries the result
+        llm = llm
+            .with_tool_call(TOOL_NAME)
+            .tool_call_unless("Tool results:");
+    }
+
+    let timings = TimingSink::default();
+    let processor = AgentProcessor::with_progress(
+        agent_config(),
+        Arc::new(llm),
+        Arc::new(tool_system),
+        Arc::new(MockTransport::new()),
+        Arc::new(SinkProgress::new(AGENT_ID.to_string(), timings.clone())),
+    );
+    let (sender, receiver) = mpsc::channel(config.queue_size.max(1));
+    let mut pipeline = AgentPipeline::new(processor, receiver, MAX_PIPELINE_DEPTH);
+    pipeline.set_max_concurrent_tasks(config.workers.max(1));
+
+    let tasks: Vec<_> = (0..config.tasks).map(synthetic_task).collect();
+    let allocations_before = AllocationCount::now();
+    let started = Instant::now();
+    let feed = async {
+        for task in tasks {
+            let task_id = task.task_id().to_string();
+            timings
+                .0
+                .lock()
+                .unwrap()
+                .sent
+                .insert(task_id, Instant::now());
+            if sender.send(task).await.is_err() {
+                break;
+            }
+        }
+        // Closing the channel lets the pipeline stop once it has drained
+        drop(sender);
+    };
+    let (result, ()) = tokio::join!(pipeline.run(), feed);
+    let elapsed = started.elapsed();
+    let allocations = AllocationCount::now()
+        .zip(allocations_before)
+        .map(|(after, before)| after.since(before));
+    result?;
+
+    let timings = timings.0.lock().unwrap();
+    let mut end_to_end = Vec::with_capacity(timings.completed.len());
+    let mut channel_wait = Vec::with_capacity(timings.started.len());
+    for (task_id, sent) in &timings.sent {
+        if let Some(started) = timings.started.get(task_id) {
+            channel_wait.push(started.saturating_duration_since(*sent));
+        }
+        if let Some(completed) = timings.completed.get(task_id) {
+            end_to_end.push(completed.saturating_duration_since(*sent));
+        }
+    }
+    let completed = end_to_end.len();
+    Ok(LoadReport {
+        config: config.clone(),
+        completed,
+        failed: config.tasks.saturating_sub(completed),
+        elapsed,
+        end_to_end: LatencyStats::from_samples(end_to_end),
+        channel_wait: LatencyStats::from_samples(channel_wait),
+        allocations,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_every_task_completes() {
+        let report = run_load(&LoadConfig::new(20).queue_size(4)).await.unwrap();
+
+        assert_eq!(report.completed, 20);
+        assert_eq!(report.failed, 0);
+        assert!(report.throughput() > 0.0);
+        assert!(report.end_to_end.p50 <= report.end_to_end.max);
+        assert!(report.channel_wait.max <= report.end_to_end.max);
+    }
+
+    #[tokio::test]
+    async fn test_tool_latency_is_part_of_every_task() {
+        let latency = Duration::from_millis(20);
+        let report = run_load(&LoadConfig::new(4).workers(4).tool_latency(latency))
+            .await
+            .unwrap();
+
+        assert_eq!(report.completed, 4);
+        assert!(report.end_to_end.p50 >= latency, "{report:?}");
+        // Four workers overlap the tool waits rather than queueing them
+        assert!(report.elapsed < latency * 4, "{report:?}");
+    }
+
+    #[test]
+    fn test_latency_percentiles() {
+        let samples = (1..=100).map(Duration::from_millis).collect();
+        let stats = LatencyStats::from_samples(samples);
+
+        assert_eq!(stats.p50, Duration::from_millis(50));
+        assert_eq!(stats.p90, Duration::from_millis(90));
+        assert_eq!(stats.p99, Duration::from_millis(99));
+        assert_eq!(stats.max, Duration::from_millis(100));
+        assert_eq!(LatencyStats::from_samples(vec![]), LatencyStats::default());
+    }
+
+    #[test]
+    fn test_counting_allocator_counts() {
+        let layout = Layout::from_size_align(64, 8).unwrap();
+        let before = ALLOCATIONS.load(Ordering::Relaxed);
+        unsafe {
+            let ptr = CountingAllocator.alloc(layout);
+            CountingAllocator.dealloc(ptr, layout);
+        }
+        assert!(ALLOCATIONS.load(Ordering::Relaxed) > before);
+    }
+
+    #[test]
+    fn test_markdown_table_has_a_row_per_report() {
+        let report = LoadReport {
+            config: LoadConfig::new(10).tool_latency(Duration::from_millis(5)),
+            completed: 10,
+            failed: 0,
+            elapsed: Duration::from_secs(1),
+            end_to_end: LatencyStats::default(),
+            channel_wait: LatencyStats::default(),
+            allocations: Some(AllocationCount {
+                allocations: 1000,
+                bytes: 64_000,
+            }),
+        };
+
+        let table = markdown_table(&[report.clone(), report]);
+
+        let lines: Vec<_> = table.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].starts_with("| tasks |"));
+        assert_eq!(lines[2], lines[3]);
+        assert!(lines[2].contains("| 5ms | 10.0 |"), "{}", lines[2]);
+        assert!(lines[2].contains("| 100 | 0 |"), "{}", lines[2]);
+    }
+}
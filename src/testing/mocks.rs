@@ -4,33 +4,130 @@
 //! to enable comprehensive testing without external dependencies.
 
 use crate::agent::discovery::{AgentInfo, AgentRegistry};
+use crate::clock::Clock;
 use crate::error::AgentError;
 use crate::llm::provider::{
-    CompletionRequest, CompletionResponse, FinishReason, LlmError, LlmProvider, TokenUsage,
+    CompletionRequest, CompletionResponse, FinishReason, LlmError, LlmProvider, MessageRole,
+    TokenUsage, ToolCall,
 };
+use crate::processing::TaskJournal;
 use crate::protocol::messages::{
-    AgentStatus, ErrorMessage, ResponseMessage, TaskEnvelope, TaskEnvelopeWrapper,
+    AdminMessage, AgentStatus, BatchSummary, CancelMessage, ErrorMessage, ResponseMessage, TaskAck,
+    TaskBatchEnvelope, TaskEnvelope, TaskEnvelopeWrapper,
 };
 use crate::tools::ToolError;
-use crate::transport::{mqtt::ConnectionState, Transport};
+use crate::transport::{
+    mqtt::{ConnectionState, HealthMetrics, MqttError},
+    topic_matches, TopicMessage, Transport, TOPIC_SUBSCRIPTION_CAPACITY,
+};
 use async_trait::async_trait;
 use serde_json::{json, Value};
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
-use tokio::sync::{mpsc, Mutex};
+use std::time::Duration;
+use tokio::sync::{mpsc, watch, Mutex};
 
 pub type PublishedMessage = (String, Vec<u8>);
+/// A `subscribe_topic` subscription: its topic and receiver
+pub type TopicSubscriber = (String, mpsc::Sender<TopicMessage>);
 
-/// Mock transport for testing
+/// One successful call on a [`MockTransport`]
+#[derive(Debug, Clone)]
+pub enum TransportCall {
+    PublishStatus(AgentStatus),
+    /// A task as sent, stamped with `published_at`, and the input topic it went to
+    PublishTask {
+        topic: String,
+        envelope: Box<TaskEnvelopeWrapper>,
+    },
+    PublishResponse {
+        conversation_id: String,
+        response: ResponseMessage,
+    },
+    PublishError {
+        conversation_id: String,
+        error: ErrorMessage,
+    },
+    PublishBatchSummary {
+        conversation_id: String,
+        summary: BatchSummary,
+    },
+    PublishAck {
+        conversation_id: String,
+        ack: TaskAck,
+    },
+    Publish(RawPublish),
+    SubscribeTopic(String),
+    Unsubscribe(String),
+}
+
+/// A message sent through [`Transport::publish`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct RawPublish {
+    pub topic: String,
+    pub payload: Vec<u8>,
+    pub retain: bool,
+}
+
+/// Publish failures scripted on a [`MockTransport`]
 #[derive(Debug, Default)]
+pub struct PublishFailures {
+    /// Publish attempts so far, of every kind
+    attempts: usize,
+    next: VecDeque<MqttError>,
+    nth: HashMap<usize, MqttError>,
+}
+
+impl PublishFailures {
+    /// Count one attempt and take the failure scripted for it, if any
+    fn attempt(&mut self) -> Option<MqttError> {
+        self.attempts += 1;
+        self.nth
+            .remove(&self.attempts)
+            .or_else(|| self.next.pop_front())
+    }
+}
+
+/// Mock transport for testing
+///
+/// Clones share every record and setting, so a test can keep a clone to
+/// inspect, script or feed a transport moved into the agent.
+#[derive(Debug, Default, Clone)]
 pub struct MockTransport {
+    /// Published tasks as v1.0 envelopes (v2.0-only fields stripped)
     pub published_tasks: Arc<Mutex<Vec<(String, TaskEnvelope)>>>,
+    /// Published tasks exactly as sent, including v2.0 fields
+    pub published_task_envelopes: Arc<Mutex<Vec<(String, TaskEnvelopeWrapper)>>>,
     pub published_responses: Arc<Mutex<Vec<(String, ResponseMessage)>>>,
     pub published_statuses: Arc<Mutex<Vec<AgentStatus>>>,
     pub published_errors: Arc<Mutex<Vec<(String, ErrorMessage)>>>,
+    pub published_batch_summaries: Arc<Mutex<Vec<(String, BatchSummary)>>>,
+    pub published_acks: Arc<Mutex<Vec<(String, TaskAck)>>>,
     pub published_messages: Arc<Mutex<Vec<PublishedMessage>>>,
+    /// Subset of published messages sent with the retain flag set
+    pub published_retained: Arc<Mutex<Vec<PublishedMessage>>>,
     pub should_fail: bool,
     pub task_sender: Arc<Mutex<Option<mpsc::Sender<TaskEnvelopeWrapper>>>>,
+    pub cancel_sender: Arc<Mutex<Option<mpsc::Sender<CancelMessage>>>>,
+    pub batch_sender: Arc<Mutex<Option<mpsc::Sender<TaskBatchEnvelope>>>>,
+    pub admin_sender: Arc<Mutex<Option<mpsc::Sender<AdminMessage>>>>,
+    pub task_journal: Arc<Mutex<Option<Arc<TaskJournal>>>>,
+    /// Subscribers receiving what is published on topics matching their filter,
+    /// plus the latest retained message per matching topic, like a broker would
+    pub topic_subscribers: Arc<Mutex<Vec<TopicSubscriber>>>,
+    /// Set by `disconnect_permanently` to simulate exhausted reconnects
+    pub permanently_disconnected: Arc<AtomicBool>,
+    /// Every successful call, in order
+    pub call_log: Arc<std::sync::Mutex<Vec<TransportCall>>>,
+    /// Failures scripted with `fail_next_publish` and `fail_nth_publish`
+    pub publish_failures: Arc<std::sync::Mutex<PublishFailures>>,
+    /// State set with `set_connection_state`, reported instead of the default
+    pub induced_state: Arc<std::sync::Mutex<Option<ConnectionState>>>,
+    /// Sender behind `connection_events`, created by its first call
+    pub connection_watch: Arc<std::sync::Mutex<Option<watch::Sender<ConnectionState>>>>,
+    /// Clock publish times are stamped from, the system clock when unset
+    pub clock: Option<Arc<dyn Clock>>,
 }
 
 impl MockTransport {
@@ -45,10 +142,216 @@ impl MockTransport {
         }
     }
 
+    /// Stamp publish times on tasks from `clock`
+    pub fn with_clock(clock: Arc<dyn Clock>) -> Self {
+        Self {
+            clock: Some(clock),
+            ..Default::default()
+        }
+    }
+
+    /// Report the connection as permanently lost from now on
+    pub fn disconnect_permanently(&self) {
+        self.permanently_disconnected.store(true, Ordering::Relaxed);
+        self.notify_connection_state();
+    }
+
+    /// Fail the next publish attempt that has no failure of its own with `error`
+    ///
+    /// Scripted failures queue up: each call fails one more attempt. Every
+    /// `publish_*` method and [`Transport::publish`] counts as an attempt.
+    pub fn fail_next_publish(&self, error: MqttError) {
+        self.publish_failures.lock().unwrap().next.push_back(error);
+    }
+
+    /// Fail the `n`th publish attempt, counting from 1 since the transport was created
+    pub fn fail_nth_publish(&self, n: usize, error: MqttError) {
+        self.publish_failures.lock().unwrap().nth.insert(n, error);
+    }
+
+    /// Publish attempts so far, failed or not
+    pub fn publish_attempts(&self) -> usize {
+        self.publish_failures.lock().unwrap().attempts
+    }
+
+    /// Report `state` from now on; the transport is connected only in `Connected`
+    pub fn set_connection_state(&self, state: ConnectionState) {
+        *self.induced_state.lock().unwrap() = Some(state);
+        self.notify_connection_state();
+    }
+
+    /// State reported on `connection_events`
+    fn event_state(&self) -> ConnectionState {
+        if self.permanently_disconnected.load(Ordering::Relaxed) {
+            return ConnectionState::PermanentlyDisconnected(
+                "Mock reconnection attempts exhausted".to_string(),
+            );
+        }
+        self.connection_state()
+            .unwrap_or(ConnectionState::Disconnected("Not connected".to_string()))
+    }
+
+    /// Tell `connection_events` receivers about a change of state
+    fn notify_connection_state(&self) {
+        if let Some(sender) = self.connection_watch.lock().unwrap().as_ref() {
+            let state = self.event_state();
+            sender.send_if_modified(|current| {
+                let changed = *current != state;
+                *current = state;
+                changed
+            });
+        }
+    }
+
+    /// Deliver `envelope` to the agent as if it arrived on its input topic
+    ///
+    /// Fails when no task sender is registered or its receiver is gone.
+    pub async fn deliver_task(&self, envelope: TaskEnvelopeWrapper) -> Result<(), AgentError> {
+        let sender = self
+            .task_sender
+            .lock()
+            .await
+            .clone()
+            .ok_or_else(|| AgentError::internal_error("No task sender registered"))?;
+        sender
+            .send(envelope)
+            .await
+            .map_err(|_| AgentError::internal_error("Task receiver dropped"))
+    }
+
+    /// Every successful call, in order
+    pub fn calls(&self) -> Vec<TransportCall> {
+        self.call_log.lock().unwrap().clone()
+    }
+
+    /// Published tasks as v1.0 envelopes, with the input topic each went to
+    pub fn published_tasks(&self) -> Vec<(String, TaskEnvelope)> {
+        self.calls()
+            .into_iter()
+            .filter_map(|call| match call {
+                TransportCall::PublishTask { topic, envelope } => {
+                    Some((topic, envelope.into_parts().0))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Published responses, with the conversation each went to
+    pub fn published_responses(&self) -> Vec<(String, ResponseMessage)> {
+        self.calls()
+            .into_iter()
+            .filter_map(|call| match call {
+                TransportCall::PublishResponse {
+                    conversation_id,
+                    response,
+                } => Some((conversation_id, response)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Published errors, with the conversation each went to
+    pub fn published_errors(&self) -> Vec<(String, ErrorMessage)> {
+        self.calls()
+            .into_iter()
+            .filter_map(|call| match call {
+                TransportCall::PublishError {
+                    conversation_id,
+                    error,
+                } => Some((conversation_id, error)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Messages sent through [`Transport::publish`], with their retain flag
+    pub fn raw_publishes(&self) -> Vec<RawPublish> {
+        self.calls()
+            .into_iter()
+            .filter_map(|call| match call {
+                TransportCall::Publish(publish) => Some(publish),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Assert a task was forwarded to `agent_id` and return the last one
+    ///
+    /// # Panics
+    /// When no task went to the agent's input topic, listing where tasks did go.
+    pub fn assert_task_forwarded_to(&self, agent_id: &str) -> TaskEnvelope {
+        let topic = format!("/control/agents/{agent_id}/input");
+        let tasks = self.published_tasks();
+        match tasks.iter().rev().find(|(sent_to, _)| *sent_to == topic) {
+            Some((_, task)) => task.clone(),
+            None => panic!(
+                "no task was forwarded to {agent_id}; tasks went to {:?}",
+                tasks.iter().map(|(sent_to, _)| sent_to).collect::<Vec<_>>()
+            ),
+        }
+    }
+
+    /// Assert no task was forwarded to any agent
+    ///
+    /// # Panics
+    /// When a task was published, naming where it went.
+    pub fn assert_nothing_forwarded(&self) {
+        let tasks = self.published_tasks();
+        assert!(
+            tasks.is_empty(),
+            "expected no forwarded tasks; tasks went to {:?}",
+            tasks.iter().map(|(sent_to, _)| sent_to).collect::<Vec<_>>()
+        );
+    }
+
+    /// Assert a response was published to `conversation_id` and return the last one
+    ///
+    /// # Panics
+    /// When the conversation got no response, listing any errors published to it.
+    pub fn assert_response_published(&self, conversation_id: &str) -> ResponseMessage {
+        match self
+            .published_responses()
+            .into_iter()
+            .rev()
+            .find(|(sent_to, _)| sent_to == conversation_id)
+        {
+            Some((_, response)) => response,
+            None => panic!(
+                "no response was published to {conversation_id}; errors: {:?}",
+                self.published_errors()
+                    .iter()
+                    .filter(|(sent_to, _)| sent_to == conversation_id)
+                    .map(|(_, error)| &error.error.message)
+                    .collect::<Vec<_>>()
+            ),
+        }
+    }
+
+    /// Fail with the configured failure, or the one scripted for this attempt
+    fn check_publish(&self) -> Result<(), AgentError> {
+        let scripted = self.publish_failures.lock().unwrap().attempt();
+        if self.should_fail {
+            return Err(AgentError::internal_error("Mock publish failure"));
+        }
+        match scripted {
+            Some(error) => Err(AgentError::TransportError(Box::new(error))),
+            None => Ok(()),
+        }
+    }
+
+    fn log(&self, call: TransportCall) {
+        self.call_log.lock().unwrap().push(call);
+    }
+
     pub async fn get_published_tasks(&self) -> Vec<(String, TaskEnvelope)> {
         self.published_tasks.lock().await.clone()
     }
 
+    pub async fn get_published_task_envelopes(&self) -> Vec<(String, TaskEnvelopeWrapper)> {
+        self.published_task_envelopes.lock().await.clone()
+    }
+
     pub async fn get_published_responses(&self) -> Vec<(String, ResponseMessage)> {
         self.published_responses.lock().await.clone()
     }
@@ -61,16 +364,33 @@ impl MockTransport {
         self.published_errors.lock().await.clone()
     }
 
+    pub async fn get_published_batch_summaries(&self) -> Vec<(String, BatchSummary)> {
+        self.published_batch_summaries.lock().await.clone()
+    }
+
+    pub async fn get_published_acks(&self) -> Vec<(String, TaskAck)> {
+        self.published_acks.lock().await.clone()
+    }
+
     pub async fn get_published_messages(&self) -> Vec<(String, Vec<u8>)> {
         self.published_messages.lock().await.clone()
     }
 
+    pub async fn get_published_retained(&self) -> Vec<(String, Vec<u8>)> {
+        self.published_retained.lock().await.clone()
+    }
+
     pub async fn clear_history(&self) {
         self.published_tasks.lock().await.clear();
+        self.published_task_envelopes.lock().await.clear();
         self.published_responses.lock().await.clear();
         self.published_statuses.lock().await.clear();
         self.published_errors.lock().await.clear();
+        self.published_batch_summaries.lock().await.clear();
+        self.published_acks.lock().await.clear();
         self.published_messages.lock().await.clear();
+        self.published_retained.lock().await.clear();
+        self.call_log.lock().unwrap().clear();
     }
 }
 
@@ -91,33 +411,49 @@ impl Transport for MockTransport {
     }
 
     async fn publish_status(&self, status: &AgentStatus) -> Result<(), Self::Error> {
-        if self.should_fail {
-            return Err(AgentError::internal_error("Mock publish failure"));
-        }
+        self.check_publish()?;
 
         let mut statuses = self.published_statuses.lock().await;
         statuses.push(status.clone());
+        self.log(TransportCall::PublishStatus(status.clone()));
         Ok(())
     }
 
     async fn publish_task(
         &self,
         target_agent: &str,
-        envelope: &TaskEnvelope,
+        envelope: &TaskEnvelopeWrapper,
     ) -> Result<(), Self::Error> {
-        if self.should_fail {
-            return Err(AgentError::internal_error("Mock publish failure"));
-        }
+        // Same contract as the MQTT transport, so tests catch a topic passed
+        // where an agent id belongs
+        debug_assert!(
+            !target_agent.contains('/'),
+            "publish_task expects an agent id, not a topic: {target_agent}"
+        );
+        self.check_publish()?;
 
         // Build full topic path like real MQTT transport does
-        // If target_agent already looks like a full topic path (starts with /), use it as-is
-        let topic = if target_agent.starts_with('/') {
-            target_agent.to_string()
-        } else {
-            format!("/control/agents/{target_agent}/input")
-        };
-        let mut tasks = self.published_tasks.lock().await;
-        tasks.push((topic, envelope.clone()));
+        let topic = format!("/control/agents/{target_agent}/input");
+        let mut envelope = envelope.clone();
+        envelope.set_published_at(
+            self.clock
+                .as_ref()
+                .map_or_else(chrono::Utc::now, |clock| clock.now()),
+        );
+        // Continue the publishing span's trace in the receiving agent
+        if let Some(traceparent) = crate::observability::otel::current_traceparent() {
+            envelope.set_traceparent(traceparent);
+        }
+        self.published_task_envelopes
+            .lock()
+            .await
+            .push((topic.clone(), envelope.clone()));
+        self.log(TransportCall::PublishTask {
+            topic: topic.clone(),
+            envelope: Box::new(envelope.clone()),
+        });
+        let (task, _) = envelope.into_parts();
+        self.published_tasks.lock().await.push((topic, task));
         Ok(())
     }
 
@@ -126,12 +462,14 @@ impl Transport for MockTransport {
         conversation_id: &str,
         error: &ErrorMessage,
     ) -> Result<(), Self::Error> {
-        if self.should_fail {
-            return Err(AgentError::internal_error("Mock publish failure"));
-        }
+        self.check_publish()?;
 
         let mut errors = self.published_errors.lock().await;
         errors.push((conversation_id.to_string(), error.clone()));
+        self.log(TransportCall::PublishError {
+            conversation_id: conversation_id.to_string(),
+            error: error.clone(),
+        });
         Ok(())
     }
 
@@ -140,12 +478,42 @@ impl Transport for MockTransport {
         conversation_id: &str,
         response: &ResponseMessage,
     ) -> Result<(), Self::Error> {
-        if self.should_fail {
-            return Err(AgentError::internal_error("Mock publish failure"));
-        }
+        self.check_publish()?;
 
         let mut responses = self.published_responses.lock().await;
         responses.push((conversation_id.to_string(), response.clone()));
+        self.log(TransportCall::PublishResponse {
+            conversation_id: conversation_id.to_string(),
+            response: response.clone(),
+        });
+        Ok(())
+    }
+
+    async fn publish_batch_summary(
+        &self,
+        conversation_id: &str,
+        summary: &BatchSummary,
+    ) -> Result<(), Self::Error> {
+        self.check_publish()?;
+
+        let mut summaries = self.published_batch_summaries.lock().await;
+        summaries.push((conversation_id.to_string(), summary.clone()));
+        self.log(TransportCall::PublishBatchSummary {
+            conversation_id: conversation_id.to_string(),
+            summary: summary.clone(),
+        });
+        Ok(())
+    }
+
+    async fn publish_ack(&self, conversation_id: &str, ack: &TaskAck) -> Result<(), Self::Error> {
+        self.check_publish()?;
+
+        let mut acks = self.published_acks.lock().await;
+        acks.push((conversation_id.to_string(), ack.clone()));
+        self.log(TransportCall::PublishAck {
+            conversation_id: conversation_id.to_string(),
+            ack: ack.clone(),
+        });
         Ok(())
     }
 
@@ -153,12 +521,54 @@ impl Transport for MockTransport {
         Ok(())
     }
 
+    async fn subscribe_topic(
+        &self,
+        topic: &str,
+    ) -> Result<mpsc::Receiver<TopicMessage>, Self::Error> {
+        if self.should_fail {
+            return Err(AgentError::internal_error("Mock subscribe failure"));
+        }
+
+        let (sender, receiver) = mpsc::channel(TOPIC_SUBSCRIPTION_CAPACITY);
+        let mut latest_retained: Vec<PublishedMessage> = Vec::new();
+        for (retained_topic, payload) in self.published_retained.lock().await.iter() {
+            latest_retained.retain(|(seen_topic, _)| seen_topic != retained_topic);
+            if topic_matches(topic, retained_topic) && !payload.is_empty() {
+                latest_retained.push((retained_topic.clone(), payload.clone()));
+            }
+        }
+        for message in latest_retained {
+            let _ = sender.try_send(message);
+        }
+        self.topic_subscribers
+            .lock()
+            .await
+            .push((topic.to_string(), sender));
+        self.log(TransportCall::SubscribeTopic(topic.to_string()));
+        Ok(receiver)
+    }
+
+    async fn unsubscribe(&self, topic: &str) -> Result<(), Self::Error> {
+        if self.should_fail {
+            return Err(AgentError::internal_error("Mock unsubscribe failure"));
+        }
+
+        self.topic_subscribers
+            .lock()
+            .await
+            .retain(|(subscribed, _)| subscribed != topic);
+        self.log(TransportCall::Unsubscribe(topic.to_string()));
+        Ok(())
+    }
+
     fn is_connected(&self) -> bool {
-        !self.should_fail
+        self.connection_state() == Some(ConnectionState::Connected)
     }
 
     fn connection_state(&self) -> Option<ConnectionState> {
-        if self.should_fail {
+        if let Some(state) = self.induced_state.lock().unwrap().clone() {
+            Some(state)
+        } else if self.should_fail {
             Some(ConnectionState::Disconnected(
                 "Mock disconnection".to_string(),
             ))
@@ -167,20 +577,59 @@ impl Transport for MockTransport {
         }
     }
 
+    fn connection_events(&self) -> watch::Receiver<ConnectionState> {
+        self.connection_watch
+            .lock()
+            .unwrap()
+            .get_or_insert_with(|| watch::channel(self.event_state()).0)
+            .subscribe()
+    }
+
     fn is_permanently_disconnected(&self) -> bool {
-        false
+        self.permanently_disconnected.load(Ordering::Relaxed)
+            || matches!(
+                *self.induced_state.lock().unwrap(),
+                Some(ConnectionState::PermanentlyDisconnected(_))
+            )
+    }
+
+    fn health_metrics(&self) -> HealthMetrics {
+        HealthMetrics {
+            uptime: self.is_connected().then_some(Duration::ZERO),
+            time_since_last_message: None,
+            reconnect_count: 0,
+            is_healthy: self.is_connected(),
+        }
     }
 
     async fn publish(
         &self,
         topic: &str,
         payload: Vec<u8>,
-        _retain: bool,
+        retain: bool,
     ) -> Result<(), Self::Error> {
-        if self.should_fail {
-            return Err(AgentError::internal_error("Mock publish failure"));
-        }
+        self.check_publish()?;
 
+        if retain {
+            if let Ok(mut retained) = self.published_retained.try_lock() {
+                retained.push((topic.to_string(), payload.clone()));
+            }
+        }
+        self.topic_subscribers
+            .lock()
+            .await
+            .retain(|(subscribed_topic, sender)| {
+                !topic_matches(subscribed_topic, topic)
+                    || !matches!(
+                        sender.try_send((topic.to_string(), payload.clone())),
+                        Err(mpsc::error::TrySendError::Closed(_))
+                    )
+            });
+        self.log(TransportCall::Publish(RawPublish {
+            topic: topic.to_string(),
+            payload: payload.clone(),
+            retain,
+        }));
         if let Ok(mut published) = self.published_messages.try_lock() {
             published.push((topic.to_string(), payload));
         }
@@ -192,14 +641,185 @@ impl Transport for MockTransport {
             *task_sender = Some(sender);
         }
     }
+
+    fn set_cancel_sender(&self, sender: mpsc::Sender<CancelMessage>) {
+        if let Ok(mut cancel_sender) = self.cancel_sender.try_lock() {
+            *cancel_sender = Some(sender);
+        }
+    }
+
+    fn set_batch_sender(&self, sender: mpsc::Sender<TaskBatchEnvelope>) {
+        if let Ok(mut batch_sender) = self.batch_sender.try_lock() {
+            *batch_sender = Some(sender);
+        }
+    }
+
+    fn set_admin_sender(&self, sender: mpsc::Sender<AdminMessage>) {
+        if let Ok(mut admin_sender) = self.admin_sender.try_lock() {
+            *admin_sender = Some(sender);
+        }
+    }
+
+    fn set_task_journal(&self, journal: Arc<TaskJournal>) {
+        if let Ok(mut task_journal) = self.task_journal.try_lock() {
+            *task_journal = Some(journal);
+        }
+    }
+}
+
+/// One completion of a [`MockLlmProvider::scripted`] conversation
+///
+/// A turn answers with content and tool calls, or fails with `error`. It can
+/// also check the request it answers: a request that doesn't match panics.
+#[derive(Debug, Clone)]
+pub struct ScriptedTurn {
+    pub content: Option<String>,
+    pub tool_calls: Vec<ToolCall>,
+    pub finish_reason: FinishReason,
+    /// Usage reported for this turn instead of the provider's
+    pub usage: Option<TokenUsage>,
+    /// Returned instead of a response
+    pub error: Option<LlmError>,
+    /// Texts that some message of the request must each contain
+    pub expected_messages: Vec<String>,
+    /// Tools the request must offer
+    pub expected_tools: Vec<String>,
+}
+
+impl ScriptedTurn {
+    /// A final answer
+    pub fn answer(content: impl Into<String>) -> Self {
+        Self {
+            content: Some(content.into()),
+            tool_calls: Vec::new(),
+            finish_reason: FinishReason::Stop,
+            usage: None,
+            error: None,
+            expected_messages: Vec::new(),
+            expected_tools: Vec::new(),
+        }
+    }
+
+    /// A turn without content that calls tool `name` with `arguments`
+    pub fn tool_call(name: impl Into<String>, arguments: Value) -> Self {
+        Self {
+            content: None,
+            ..Self::answer("")
+        }
+        .and_tool_call(name, arguments)
+    }
+
+    /// A turn failing with `error`
+    pub fn failure(error: LlmError) -> Self {
+        Self {
+            error: Some(error),
+            ..Self::answer("")
+        }
+    }
+
+    /// Also call tool `name` with `arguments`
+    pub fn and_tool_call(mut self, name: impl Into<String>, arguments: Value) -> Self {
+        self.tool_calls.push(ToolCall {
+            id: String::new(),
+            name: name.into(),
+            arguments,
+        });
+        self
+    }
+
+    pub fn with_finish_reason(mut self, finish_reason: FinishReason) -> Self {
+        self.finish_reason = finish_reason;
+        self
+    }
+
+    pub fn with_usage(mut self, prompt_tokens: u32, completion_tokens: u32) -> Self {
+        self.usage = Some(TokenUsage {
+            prompt_tokens,
+            completion_tokens,
+            total_tokens: prompt_tokens + completion_tokens,
+            cache_read_tokens: None,
+            cache_creation_tokens: None,
+        });
+        self
+    }
+
+    /// Panic unless some message of the request contains `text`
+    pub fn expecting_message(mut self, text: impl Into<String>) -> Self {
+        self.expected_messages.push(text.into());
+        self
+    }
+
+    /// Panic unless the request offers every tool in `tools`
+    pub fn expecting_tools(mut self, tools: &[&str]) -> Self {
+        self.expected_tools = tools.iter().map(|tool| tool.to_string()).collect();
+        self
+    }
+
+    /// Check `request` against the turn's expectations (pure function)
+    fn mismatch(&self, request: &CompletionRequest) -> Option<String> {
+        for text in &self.expected_messages {
+            if !request
+                .messages
+                .iter()
+                .any(|message| message.content.contains(text.as_str()))
+            {
+                return Some(format!("no message contains {text:?}"));
+            }
+        }
+        let offered: Vec<&str> = request
+            .tools
+            .iter()
+            .flatten()
+            .map(|tool| tool.name.as_str())
+            .collect();
+        let missing: Vec<&String> = self
+            .expected_tools
+            .iter()
+            .filter(|tool| !offered.contains(&tool.as_str()))
+            .collect();
+        (!missing.is_empty())
+            .then(|| format!("tools {missing:?} were not offered; offered {offered:?}"))
+    }
 }
 
 /// Mock LLM provider for testing
+///
+/// Replies with `responses` in rotation, or with the turns of a script.
+/// Builder methods add latency, scripted failures or a tool call, and every
+/// completion is instrumented so tests can check how many ran, how many
+/// overlapped, in what order and with which requests.
 #[derive(Debug)]
 pub struct MockLlmProvider {
     pub responses: Vec<String>,
     pub current_response: Arc<Mutex<usize>>,
     pub should_fail: bool,
+    /// Time each completion takes
+    pub delay: Duration,
+    /// Number of initial completions that fail with `failure`
+    pub failures: usize,
+    pub failure: fn() -> LlmError,
+    /// Fail completions whose prompt contains this text
+    pub fail_on: Option<String>,
+    /// Tool requested by every completion
+    pub tool_call: Option<String>,
+    /// Only request `tool_call` from completions whose prompt contains this text
+    pub tool_call_on: Option<String>,
+    /// Never request `tool_call` from completions whose prompt contains this text
+    pub tool_call_unless: Option<String>,
+    /// Only request `tool_call` from this many initial completions
+    pub tool_rounds: Option<usize>,
+    /// Model reported in every completion
+    pub model: String,
+    /// Token usage reported in every completion
+    pub usage: TokenUsage,
+    calls: AtomicUsize,
+    active: AtomicUsize,
+    max_active: AtomicUsize,
+    releases: AtomicUsize,
+    events: std::sync::Mutex<Vec<String>>,
+    requests: std::sync::Mutex<Vec<CompletionRequest>>,
+    /// Turns not served yet, when scripted
+    script: Option<std::sync::Mutex<VecDeque<ScriptedTurn>>>,
 }
 
 impl MockLlmProvider {
@@ -208,20 +828,203 @@ impl MockLlmProvider {
             responses,
             current_response: Arc::new(Mutex::new(0)),
             should_fail: false,
+            delay: Duration::ZERO,
+            failures: 0,
+            failure: || LlmError::RequestFailed("Mock LLM failure".to_string()),
+            fail_on: None,
+            tool_call: None,
+            tool_call_on: None,
+            tool_call_unless: None,
+            tool_rounds: None,
+            model: "mock-model".to_string(),
+            usage: TokenUsage {
+                prompt_tokens: 10,
+                completion_tokens: 5,
+                total_tokens: 15,
+                cache_read_tokens: None,
+                cache_creation_tokens: None,
+            },
+            calls: AtomicUsize::new(0),
+            active: AtomicUsize::new(0),
+            max_active: AtomicUsize::new(0),
+            releases: AtomicUsize::new(0),
+            events: std::sync::Mutex::new(Vec::new()),
+            requests: std::sync::Mutex::new(Vec::new()),
+            script: None,
+        }
+    }
+
+    /// Serve `turns` in order, one per completion
+    ///
+    /// Tool calls get the id `call-<completion>-<index>`. A completion after
+    /// the last turn panics.
+    pub fn scripted(turns: Vec<ScriptedTurn>) -> Self {
+        Self {
+            script: Some(std::sync::Mutex::new(turns.into())),
+            ..Self::new(vec![])
         }
     }
 
     pub fn with_failure() -> Self {
         Self {
-            responses: vec![],
-            current_response: Arc::new(Mutex::new(0)),
             should_fail: true,
+            ..Self::new(vec![])
         }
     }
 
     pub fn single_response(response: impl Into<String>) -> Self {
         Self::new(vec![response.into()])
     }
+
+    /// Make each completion take `delay`
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Fail the first `failures` completions with the error from `failure`
+    pub fn failing_first(mut self, failures: usize, failure: fn() -> LlmError) -> Self {
+        self.failures = failures;
+        self.failure = failure;
+        self
+    }
+
+    /// Fail completions whose prompt contains `marker`
+    pub fn failing_on(mut self, marker: impl Into<String>) -> Self {
+        self.fail_on = Some(marker.into());
+        self
+    }
+
+    /// Request the tool `name` from every completion
+    pub fn with_tool_call(mut self, name: impl Into<String>) -> Self {
+        self.tool_call = Some(name.into());
+        self
+    }
+
+    /// Only request the tool from completions whose prompt contains `marker`
+    pub fn tool_call_on(mut self, marker: impl Into<String>) -> Self {
+        self.tool_call_on = Some(marker.into());
+        self
+    }
+
+    /// Never request the tool from completions whose prompt contains `marker`
+    ///
+    /// With `"Tool results:"` every task gets exactly one tool round, however
+    /// many tasks share the provider.
+    pub fn tool_call_unless(mut self, marker: impl Into<String>) -> Self {
+        self.tool_call_unless = Some(marker.into());
+        self
+    }
+
+    /// Only request the tool from the first `rounds` completions
+    pub fn tool_rounds(mut self, rounds: usize) -> Self {
+        self.tool_rounds = Some(rounds);
+        self
+    }
+
+    /// Report `model` in every completion
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = model.into();
+        self
+    }
+
+    /// Report the given token usage in every completion
+    pub fn with_usage(mut self, prompt_tokens: u32, completion_tokens: u32) -> Self {
+        self.usage = TokenUsage {
+            prompt_tokens,
+            completion_tokens,
+            total_tokens: prompt_tokens + completion_tokens,
+            cache_read_tokens: None,
+            cache_creation_tokens: None,
+        };
+        self
+    }
+
+    /// Report the given prompt cache reads and writes in every completion
+    pub fn with_cache_usage(mut self, cache_read_tokens: u32, cache_creation_tokens: u32) -> Self {
+        self.usage.cache_read_tokens = Some(cache_read_tokens);
+        self.usage.cache_creation_tokens = Some(cache_creation_tokens);
+        self
+    }
+
+    /// Completions started so far
+    pub fn calls(&self) -> usize {
+        self.calls.load(Ordering::SeqCst)
+    }
+
+    /// Most completions that ran at the same time
+    pub fn max_active(&self) -> usize {
+        self.max_active.load(Ordering::SeqCst)
+    }
+
+    /// `start:<instruction>` and `end:<instruction>` for each completion, in
+    /// order, where the instruction is the first user message
+    pub fn events(&self) -> Vec<String> {
+        self.events.lock().unwrap().clone()
+    }
+
+    /// Every request received so far, in order
+    pub fn requests(&self) -> Vec<CompletionRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// Scripted turns not served yet
+    pub fn remaining_turns(&self) -> usize {
+        self.script
+            .as_ref()
+            .map_or(0, |script| script.lock().unwrap().len())
+    }
+
+    /// Times the agent asked the provider to release its resources
+    pub fn releases(&self) -> usize {
+        self.releases.load(Ordering::SeqCst)
+    }
+
+    fn record(&self, event: String) {
+        self.events.lock().unwrap().push(event);
+    }
+
+    fn scripted_completion(
+        &self,
+        script: &std::sync::Mutex<VecDeque<ScriptedTurn>>,
+        call: usize,
+        request: &CompletionRequest,
+    ) -> Result<CompletionResponse, LlmError> {
+        let Some(turn) = script.lock().unwrap().pop_front() else {
+            panic!(
+                "MockLlmProvider script exhausted: completion {} was requested after all {call} \
+                 scripted turns were served; its last message was {:?}",
+                call + 1,
+                request.messages.last().map(|message| &message.content)
+            );
+        };
+        if let Some(mismatch) = turn.mismatch(request) {
+            panic!(
+                "MockLlmProvider turn {} did not match its request: {mismatch}",
+                call + 1
+            );
+        }
+        if let Some(error) = turn.error {
+            return Err(error);
+        }
+        let tool_calls = turn
+            .tool_calls
+            .into_iter()
+            .enumerate()
+            .map(|(index, tool_call)| ToolCall {
+                id: format!("call-{call}-{index}"),
+                ..tool_call
+            })
+            .collect::<Vec<_>>();
+        Ok(CompletionResponse {
+            content: turn.content,
+            model: self.model.clone(),
+            usage: turn.usage.unwrap_or_else(|| self.usage.clone()),
+            finish_reason: turn.finish_reason,
+            tool_calls: (!tool_calls.is_empty()).then_some(tool_calls),
+            metadata: HashMap::new(),
+        })
+    }
 }
 
 #[async_trait]
@@ -234,11 +1037,47 @@ impl LlmProvider for MockLlmProvider {
         vec!["mock-model".to_string()]
     }
 
-    async fn complete(&self, _request: CompletionRequest) -> Result<CompletionResponse, LlmError> {
+    async fn complete(&self, request: CompletionRequest) -> Result<CompletionResponse, LlmError> {
+        let call = self.calls.fetch_add(1, Ordering::SeqCst);
         if self.should_fail {
             return Err(LlmError::RequestFailed("Mock LLM failure".to_string()));
         }
 
+        let instruction = request
+            .messages
+            .iter()
+            .find(|message| matches!(message.role, MessageRole::User))
+            .map(|message| message.content.clone())
+            .unwrap_or_default();
+        self.record(format!("start:{instruction}"));
+        let active = self.active.fetch_add(1, Ordering::SeqCst) + 1;
+        self.max_active.fetch_max(active, Ordering::SeqCst);
+        if !self.delay.is_zero() {
+            tokio::time::sleep(self.delay).await;
+        }
+        self.active.fetch_sub(1, Ordering::SeqCst);
+        self.record(format!("end:{instruction}"));
+        self.requests.lock().unwrap().push(request.clone());
+
+        if let Some(script) = &self.script {
+            return self.scripted_completion(script, call, &request);
+        }
+
+        if call < self.failures {
+            return Err((self.failure)());
+        }
+        if let Some(marker) = &self.fail_on {
+            if request
+                .messages
+                .iter()
+                .any(|message| message.content.contains(marker.as_str()))
+            {
+                return Err(LlmError::RequestFailed(
+                    "Mock LLM rejected prompt".to_string(),
+                ));
+            }
+        }
+
         let mut current = self.current_response.lock().await;
         let response_idx = *current % self.responses.len().max(1);
         *current += 1;
@@ -248,21 +1087,41 @@ impl LlmProvider for MockLlmProvider {
         } else {
             self.responses[response_idx].clone()
         };
+        let wants_tool = self.tool_rounds.map_or(true, |rounds| call < rounds)
+            && self.tool_call_on.as_ref().map_or(true, |marker| {
+                request
+                    .messages
+                    .iter()
+                    .any(|message| message.content.contains(marker.as_str()))
+            })
+            && self.tool_call_unless.as_ref().map_or(true, |marker| {
+                !request
+                    .messages
+                    .iter()
+                    .any(|message| message.content.contains(marker.as_str()))
+            });
+        let tool_calls = self.tool_call.as_ref().filter(|_| wants_tool).map(|name| {
+            vec![ToolCall {
+                id: format!("call-{call}"),
+                name: name.clone(),
+                arguments: json!({}),
+            }]
+        });
 
         Ok(CompletionResponse {
             content: Some(content),
-            model: "mock-model".to_string(),
-            usage: TokenUsage {
-                prompt_tokens: 10,
-                completion_tokens: 5,
-                total_tokens: 15,
-            },
+            model: self.model.clone(),
+            usage: self.usage.clone(),
             finish_reason: FinishReason::Stop,
-            tool_calls: None,
+            tool_calls,
             metadata: HashMap::new(),
         })
     }
 
+    fn release_resources(&self) {
+        self.releases.fetch_add(1, Ordering::SeqCst);
+    }
+
     async fn health_check(&self) -> Result<(), LlmError> {
         if self.should_fail {
             Err(LlmError::RequestFailed(
@@ -522,16 +1381,266 @@ mod tests {
             instruction: Some("test instruction".to_string()),
             input: json!({}),
             next: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
         };
 
-        transport.publish_task("/test", &task).await.unwrap();
+        transport
+            .publish_task("test-agent", &TaskEnvelopeWrapper::V1(task.clone()))
+            .await
+            .unwrap();
 
         let published = transport.get_published_tasks().await;
         assert_eq!(published.len(), 1);
-        assert_eq!(published[0].0, "/test");
+        assert_eq!(published[0].0, "/control/agents/test-agent/input");
         assert_eq!(published[0].1.task_id, task.task_id);
     }
 
+    fn task(conversation_id: &str) -> TaskEnvelope {
+        TaskEnvelope {
+            task_id: Uuid::new_v4(),
+            conversation_id: conversation_id.to_string(),
+            topic: "/control/agents/writer/input".to_string(),
+            instruction: Some("Write it".to_string()),
+            input: json!({}),
+            next: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
+        }
+    }
+
+    fn response(task_id: Uuid) -> ResponseMessage {
+        ResponseMessage {
+            response: "done".to_string(),
+            task_id,
+            routing_trace: None,
+            correlation_id: None,
+            parent_task_id: None,
+            conversation_id: None,
+            agent_id: None,
+            completed_at: None,
+            oversized: None,
+            artifacts: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_call_log_records_calls_in_order() {
+        let transport = MockTransport::new();
+        let forwarded = task("c1");
+        transport
+            .publish_task("writer", &TaskEnvelopeWrapper::V1(forwarded.clone()))
+            .await
+            .unwrap();
+        transport
+            .publish_response("c1", &response(forwarded.task_id))
+            .await
+            .unwrap();
+        transport
+            .publish("/control/agents/me/status", b"{}".to_vec(), true)
+            .await
+            .unwrap();
+        transport
+            .subscribe_topic("/control/agents/+/status")
+            .await
+            .unwrap();
+
+        let calls = transport.calls();
+        assert_eq!(calls.len(), 4);
+        assert!(matches!(&calls[0], TransportCall::PublishTask { topic, .. }
+            if topic == "/control/agents/writer/input"));
+        assert!(
+            matches!(&calls[1], TransportCall::PublishResponse { conversation_id, .. }
+            if conversation_id == "c1")
+        );
+        assert!(matches!(&calls[3], TransportCall::SubscribeTopic(filter)
+            if filter == "/control/agents/+/status"));
+        assert_eq!(
+            transport.raw_publishes(),
+            vec![RawPublish {
+                topic: "/control/agents/me/status".to_string(),
+                payload: b"{}".to_vec(),
+                retain: true,
+            }]
+        );
+
+        let sent = transport.assert_task_forwarded_to("writer");
+        assert_eq!(sent.task_id, forwarded.task_id);
+        assert!(sent.published_at.is_some());
+        assert_eq!(transport.published_tasks().len(), 1);
+        assert_eq!(
+            transport.assert_response_published("c1").task_id,
+            forwarded.task_id
+        );
+        assert!(transport.published_errors().is_empty());
+
+        transport.clear_history().await;
+        assert!(transport.calls().is_empty());
+        transport.assert_nothing_forwarded();
+    }
+
+    #[tokio::test]
+    #[should_panic(
+        expected = "no task was forwarded to editor; tasks went to [\"/control/agents/writer/input\"]"
+    )]
+    async fn test_assert_task_forwarded_to_names_actual_targets() {
+        let transport = MockTransport::new();
+        transport
+            .publish_task("writer", &TaskEnvelopeWrapper::V1(task("c1")))
+            .await
+            .unwrap();
+        transport.assert_task_forwarded_to("editor");
+    }
+
+    #[tokio::test]
+    async fn test_scripted_publish_failures() {
+        let transport = MockTransport::new();
+        transport.fail_next_publish(MqttError::ConnectionFailedStr("broker gone".to_string()));
+        transport.fail_nth_publish(2, MqttError::InvalidBrokerUrl("nowhere".to_string()));
+        let envelope = TaskEnvelopeWrapper::V1(task("c1"));
+
+        let first = transport
+            .publish_task("writer", &envelope)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            first.to_string(),
+            "Transport error: Connection failed: broker gone"
+        );
+        let second = transport
+            .publish("/some/topic", Vec::new(), false)
+            .await
+            .unwrap_err();
+        assert!(second.to_string().contains("Invalid broker URL: nowhere"));
+        transport.publish_task("writer", &envelope).await.unwrap();
+
+        assert_eq!(transport.publish_attempts(), 3);
+        // Failed attempts publish nothing
+        assert_eq!(transport.calls().len(), 1);
+    }
+
+    #[test]
+    fn test_induced_connection_states() {
+        let transport = MockTransport::new();
+        assert!(transport.is_connected());
+
+        transport.set_connection_state(ConnectionState::Reconnecting(2));
+        assert!(!transport.is_connected());
+        assert!(!transport.health_metrics().is_healthy);
+        assert!(!transport.is_permanently_disconnected());
+
+        transport.set_connection_state(ConnectionState::Connected);
+        assert!(transport.is_connected());
+
+        transport.set_connection_state(ConnectionState::PermanentlyDisconnected(
+            "gave up".to_string(),
+        ));
+        assert!(transport.is_permanently_disconnected());
+        assert_eq!(
+            transport.connection_state(),
+            Some(ConnectionState::PermanentlyDisconnected(
+                "gave up".to_string()
+            ))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unsubscribe_stops_delivery() {
+        let transport = MockTransport::new();
+        let mut receiver = transport
+            .subscribe_topic("/control/agents/+/status")
+            .await
+            .unwrap();
+
+        transport
+            .unsubscribe("/control/agents/+/status")
+            .await
+            .unwrap();
+        transport
+            .publish("/control/agents/me/status", b"{}".to_vec(), true)
+            .await
+            .unwrap();
+
+        assert!(receiver.recv().await.is_none());
+        assert!(matches!(
+            transport.calls().last(),
+            Some(TransportCall::Publish(_))
+        ));
+        assert!(transport.calls().iter().any(|call| matches!(call,
+            TransportCall::Unsubscribe(filter) if filter == "/control/agents/+/status")));
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_topic_delivers_raw_payloads_on_matching_topics() {
+        let transport = MockTransport::new();
+        let mut receiver = transport
+            .subscribe_topic("/conversations/+/progress")
+            .await
+            .unwrap();
+
+        transport
+            .publish("/conversations/c1/progress", vec![0, 159, 255], false)
+            .await
+            .unwrap();
+        transport
+            .publish("/conversations/c1/responses", b"ignored".to_vec(), false)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            receiver.recv().await.unwrap(),
+            ("/conversations/c1/progress".to_string(), vec![0, 159, 255])
+        );
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_connection_events_report_permanent_disconnect() {
+        let transport = MockTransport::new();
+        let mut events = transport.connection_events();
+        assert_eq!(*events.borrow_and_update(), ConnectionState::Connected);
+
+        transport.set_connection_state(ConnectionState::Reconnecting(1));
+        events.changed().await.unwrap();
+        assert_eq!(
+            *events.borrow_and_update(),
+            ConnectionState::Reconnecting(1)
+        );
+
+        transport.disconnect_permanently();
+        events.changed().await.unwrap();
+        assert!(matches!(
+            *events.borrow(),
+            ConnectionState::PermanentlyDisconnected(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_deliver_task_through_registered_sender() {
+        let transport = MockTransport::new();
+        let envelope = TaskEnvelopeWrapper::V1(task("c1"));
+        assert!(transport.deliver_task(envelope.clone()).await.is_err());
+
+        // The agent registers its sender on the transport it owns; a clone shares it
+        let agent_side = transport.clone();
+        let (sender, mut receiver) = mpsc::channel(1);
+        agent_side.set_task_sender(sender);
+        transport.deliver_task(envelope.clone()).await.unwrap();
+
+        let delivered = receiver.recv().await.unwrap();
+        assert_eq!(delivered.task_id(), envelope.task_id());
+        drop(receiver);
+        assert!(transport.deliver_task(envelope).await.is_err());
+    }
+
     #[tokio::test]
     async fn test_mock_llm_provider() {
         let provider = MockLlmProvider::single_response("Test response");
@@ -542,6 +1651,7 @@ mod tests {
             max_tokens: Some(100),
             temperature: Some(0.7),
             top_p: None,
+            seed: None,
             stop_sequences: None,
             tools: None,
             tool_choice: None,
@@ -569,6 +1679,109 @@ mod tests {
         assert_eq!(executed[0].0, "test_tool");
     }
 
+    fn user_request(content: &str, tools: &[&str]) -> CompletionRequest {
+        CompletionRequest {
+            messages: vec![crate::llm::provider::Message {
+                role: MessageRole::User,
+                content: content.to_string(),
+            }],
+            model: "test".to_string(),
+            max_tokens: None,
+            temperature: None,
+            top_p: None,
+            seed: None,
+            stop_sequences: None,
+            tools: Some(
+                tools
+                    .iter()
+                    .map(|name| crate::tools::ToolDescription {
+                        name: name.to_string(),
+                        description: String::new(),
+                        parameters: json!({"type": "object"}),
+                    })
+                    .collect(),
+            ),
+            tool_choice: None,
+            response_format: None,
+            metadata: HashMap::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_scripted_turns_are_served_in_order() {
+        let provider = MockLlmProvider::scripted(vec![
+            ScriptedTurn::tool_call("web_search", json!({"query": "rust"}))
+                .and_tool_call("fetch", json!({"url": "https://rust-lang.org"}))
+                .expecting_tools(&["web_search", "fetch"]),
+            ScriptedTurn::failure(LlmError::RateLimitExceeded("slow down".to_string())),
+            ScriptedTurn::answer("Rust is a language")
+                .with_finish_reason(FinishReason::Length)
+                .with_usage(100, 20)
+                .expecting_message("Tool results"),
+        ]);
+
+        let first = provider
+            .complete(user_request("Research rust", &["web_search", "fetch"]))
+            .await
+            .unwrap();
+        assert_eq!(first.content, None);
+        let tool_calls = first.tool_calls.unwrap();
+        let ids: Vec<&str> = tool_calls.iter().map(|call| call.id.as_str()).collect();
+        assert_eq!(ids, vec!["call-0-0", "call-0-1"]);
+        assert_eq!(tool_calls[1].arguments["url"], "https://rust-lang.org");
+
+        let second = provider
+            .complete(user_request("Tool results: ...", &[]))
+            .await;
+        assert!(matches!(second, Err(LlmError::RateLimitExceeded(_))));
+
+        let third = provider
+            .complete(user_request("Tool results: ...", &[]))
+            .await
+            .unwrap();
+        assert_eq!(third.content.as_deref(), Some("Rust is a language"));
+        assert!(matches!(third.finish_reason, FinishReason::Length));
+        assert_eq!(third.usage.total_tokens, 120);
+        assert!(third.tool_calls.is_none());
+
+        assert_eq!(provider.remaining_turns(), 0);
+        let requests = provider.requests();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(requests[0].messages[0].content, "Research rust");
+    }
+
+    #[tokio::test]
+    #[should_panic(
+        expected = "turn 1 did not match its request: no message contains \"Tool results\""
+    )]
+    async fn test_scripted_turn_checks_request_messages() {
+        let provider = MockLlmProvider::scripted(vec![
+            ScriptedTurn::answer("done").expecting_message("Tool results")
+        ]);
+        let _ = provider.complete(user_request("Research rust", &[])).await;
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "tools [\"fetch\"] were not offered; offered [\"web_search\"]")]
+    async fn test_scripted_turn_checks_offered_tools() {
+        let provider = MockLlmProvider::scripted(vec![
+            ScriptedTurn::answer("done").expecting_tools(&["web_search", "fetch"])
+        ]);
+        let _ = provider
+            .complete(user_request("Research rust", &["web_search"]))
+            .await;
+    }
+
+    #[tokio::test]
+    #[should_panic(
+        expected = "script exhausted: completion 2 was requested after all 1 scripted turns"
+    )]
+    async fn test_exhausted_script_panics() {
+        let provider = MockLlmProvider::scripted(vec![ScriptedTurn::answer("done")]);
+        provider.complete(user_request("one", &[])).await.unwrap();
+        let _ = provider.complete(user_request("two", &[])).await;
+    }
+
     // ========== V2 Mock Tests ==========
 
     #[test]
@@ -672,6 +1885,7 @@ mod tests {
             max_tokens: Some(100),
             temperature: Some(0.7),
             top_p: None,
+            seed: None,
             stop_sequences: None,
             tools: None,
             tool_choice: None,
@@ -1,8 +1,22 @@
 //! Testing utilities and mock implementations
 //!
 //! This module provides mock implementations for testing the 2389 Agent Protocol
-//! without requiring external dependencies like MQTT brokers or LLM providers.
+//! without requiring external dependencies like MQTT brokers or LLM providers,
+//! golden protocol fixtures for checking other implementations against this one,
+//! proptest generators for protocol messages, a fault-injecting transport
+//! wrapper for chaos testing, recording and replay of LLM and tool calls for
+//! deterministic runs, load generation for pipeline throughput baselines,
+//! and, with the `test-harness` feature, agents running against an embedded
+//! broker.
 
+pub mod conformance;
+pub mod faults;
+#[cfg(any(test, feature = "proptest"))]
+pub mod generators;
+#[cfg(feature = "test-harness")]
+pub mod harness;
+pub mod loadgen;
 pub mod mocks;
+pub mod recording;
 
 pub use mocks::*;
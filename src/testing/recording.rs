@@ -0,0 +1,632 @@
+//! Recording and replay of LLM and tool calls
+//!
+//! With `[testing] record_dir` set, every LLM request/response pair and every
+//! tool call/result is written to the directory, one JSON file each, named by
+//! a hash of the request. With `[testing] replay_dir` set, the same directory
+//! answers them instead of the provider and the real tools, so a recorded task
+//! runs again without network access and produces the same output.
+//!
+//! Requests are hashed after masking the current date the processor appends
+//! to the system prompt. A request that was never recorded fails with a diff
+//! against the closest recorded one.
+
+use crate::config::{AgentConfig, TestingSection};
+use crate::llm::provider::{CompletionRequest, CompletionResponse, LlmError, LlmProvider};
+use crate::tools::{Tool, ToolDescription, ToolError, ToolSystem};
+use async_trait::async_trait;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use thiserror::Error;
+use tracing::warn;
+
+/// File holding the descriptions of the recorded tools
+pub const TOOLS_FILE: &str = "tools.json";
+
+const LLM_PREFIX: &str = "llm-";
+const TOOL_PREFIX: &str = "tool-";
+
+static CURRENT_DATE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"Current date and time: \d{4}-\d{2}-\d{2} \d{2}:\d{2}:\d{2} UTC").unwrap()
+});
+
+/// Recording and replay errors
+#[derive(Debug, Error)]
+pub enum RecordingError {
+    #[error("Cannot read recording {}: {message}", .path.display())]
+    Read { path: PathBuf, message: String },
+    #[error("Cannot write recording {}: {message}", .path.display())]
+    Write { path: PathBuf, message: String },
+    #[error("No recorded {kind} matches this one\n{diff}")]
+    Unseen { kind: &'static str, diff: String },
+}
+
+/// One LLM request and what the provider answered
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LlmExchange {
+    /// The request as hashed: keys sorted and the current date masked
+    pub request: Value,
+    pub response: Result<CompletionResponse, LlmError>,
+}
+
+/// One tool call and what the tool returned
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ToolExchange {
+    pub tool: String,
+    pub parameters: Value,
+    pub result: Result<Value, ToolError>,
+}
+
+/// A request as it is hashed (pure function)
+///
+/// Object keys are sorted and the date the processor appends to the system
+/// prompt is masked, so the same task hashes the same on every run.
+pub fn normalize_request(request: &CompletionRequest) -> Value {
+    normalize(serde_json::to_value(request).unwrap_or_default())
+}
+
+fn normalize(value: Value) -> Value {
+    match value {
+        Value::String(text) => Value::String(
+            CURRENT_DATE
+                .replace_all(&text, "Current date and time: <masked> UTC")
+                .into_owned(),
+        ),
+        Value::Array(items) => Value::Array(items.into_iter().map(normalize).collect()),
+        Value::Object(object) => {
+            let mut entries: Vec<(String, Value)> = object.into_iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key, normalize(value)))
+                    .collect(),
+            )
+        }
+        other => other,
+    }
+}
+
+/// Hex SHA-256 of a normalized value (pure function)
+fn content_hash(value: &Value) -> String {
+    hex::encode(Sha256::digest(value.to_string().as_bytes()))
+}
+
+/// Key an LLM request is recorded under (pure function)
+pub fn request_key(request: &CompletionRequest) -> String {
+    content_hash(&normalize_request(request))
+}
+
+/// Key a tool call is recorded under (pure function)
+pub fn tool_call_key(tool: &str, parameters: &Value) -> String {
+    content_hash(&tool_call_value(tool, parameters))
+}
+
+fn tool_call_value(tool: &str, parameters: &Value) -> Value {
+    normalize(serde_json::json!({"tool": tool, "parameters": parameters}))
+}
+
+/// Writes exchanges to a recording directory
+#[derive(Debug, Clone)]
+pub struct Recorder {
+    dir: PathBuf,
+}
+
+impl Recorder {
+    /// Record into `dir`, creating it if needed
+    pub fn create(dir: impl Into<PathBuf>) -> Result<Self, RecordingError> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir).map_err(|e| RecordingError::Write {
+            path: dir.clone(),
+            message: e.to_string(),
+        })?;
+        Ok(Self { dir })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Record what the provider answered to `request`
+    pub async fn record_completion(
+        &self,
+        request: &CompletionRequest,
+        response: &Result<CompletionResponse, LlmError>,
+    ) -> Result<(), RecordingError> {
+        let exchange = LlmExchange {
+            request: normalize_request(request),
+            response: response.clone(),
+        };
+        let name = format!("{LLM_PREFIX}{}.json", content_hash(&exchange.request));
+        self.write(&name, &exchange).await
+    }
+
+    /// Record what `tool` returned for `parameters`
+    pub async fn record_tool_call(
+        &self,
+        tool: &str,
+        parameters: &Value,
+        result: &Result<Value, ToolError>,
+    ) -> Result<(), RecordingError> {
+        let exchange = ToolExchange {
+            tool: tool.to_string(),
+            parameters: parameters.clone(),
+            result: result.clone(),
+        };
+        let name = format!("{TOOL_PREFIX}{}.json", tool_call_key(tool, parameters));
+        self.write(&name, &exchange).await
+    }
+
+    /// Record the descriptions of the tools offered to the LLM
+    pub async fn record_tools(&self, tools: &[ToolDescription]) -> Result<(), RecordingError> {
+        self.write(TOOLS_FILE, &tools).await
+    }
+
+    async fn write(&self, name: &str, content: &impl Serialize) -> Result<(), RecordingError> {
+        let path = self.dir.join(name);
+        let json = serde_json::to_vec_pretty(content).map_err(|e| RecordingError::Write {
+            path: path.clone(),
+            message: e.to_string(),
+        })?;
+        tokio::fs::write(&path, json)
+            .await
+            .map_err(|e| RecordingError::Write {
+                path,
+                message: e.to_string(),
+            })
+    }
+}
+
+/// A recording directory read back into memory
+#[derive(Debug, Default)]
+pub struct Recording {
+    completions: HashMap<String, LlmExchange>,
+    tool_calls: HashMap<String, ToolExchange>,
+    tools: Vec<ToolDescription>,
+}
+
+impl Recording {
+    /// Read every exchange recorded in `dir`
+    pub fn load(dir: &Path) -> Result<Self, RecordingError> {
+        let read_error = |path: &Path, message: String| RecordingError::Read {
+            path: path.to_path_buf(),
+            message,
+        };
+        let entries = std::fs::read_dir(dir).map_err(|e| read_error(dir, e.to_string()))?;
+        let mut recording = Self::default();
+        for entry in entries {
+            let path = entry.map_err(|e| read_error(dir, e.to_string()))?.path();
+            let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            let Some(stem) = name.strip_suffix(".json") else {
+                continue;
+            };
+            let content =
+                std::fs::read_to_string(&path).map_err(|e| read_error(&path, e.to_string()))?;
+            let parse_error = |e: serde_json::Error| read_error(&path, e.to_string());
+            if name == TOOLS_FILE {
+                recording.tools = serde_json::from_str(&content).map_err(parse_error)?;
+            } else if let Some(key) = stem.strip_prefix(LLM_PREFIX) {
+                let exchange = serde_json::from_str(&content).map_err(parse_error)?;
+                recording.completions.insert(key.to_string(), exchange);
+            } else if let Some(key) = stem.strip_prefix(TOOL_PREFIX) {
+                let exchange = serde_json::from_str(&content).map_err(parse_error)?;
+                recording.tool_calls.insert(key.to_string(), exchange);
+            }
+        }
+        Ok(recording)
+    }
+
+    /// Descriptions of the tools offered while recording, sorted by name
+    pub fn tools(&self) -> &[ToolDescription] {
+        &self.tools
+    }
+
+    /// What the provider answered to `request` when it was recorded
+    pub fn completion(
+        &self,
+        request: &CompletionRequest,
+    ) -> Result<Result<CompletionResponse, LlmError>, RecordingError> {
+        let normalized = normalize_request(request);
+        match self.completions.get(&content_hash(&normalized)) {
+            Some(exchange) => Ok(exchange.response.clone()),
+            None => Err(RecordingError::Unseen {
+                kind: "LLM request",
+                diff: closest_diff(
+                    &normalized,
+                    self.completions.values().map(|exchange| &exchange.request),
+                ),
+            }),
+        }
+    }
+
+    /// What `tool` returned for `parameters` when it was recorded
+    pub fn tool_result(
+        &self,
+        tool: &str,
+        parameters: &Value,
+    ) -> Result<Result<Value, ToolError>, RecordingError> {
+        let call = tool_call_value(tool, parameters);
+        match self.tool_calls.get(&content_hash(&call)) {
+            Some(exchange) => Ok(exchange.result.clone()),
+            None => {
+                let recorded: Vec<Value> = self
+                    .tool_calls
+                    .values()
+                    .map(|exchange| tool_call_value(&exchange.tool, &exchange.parameters))
+                    .collect();
+                Err(RecordingError::Unseen {
+                    kind: "tool call",
+                    diff: closest_diff(&call, recorded.iter()),
+                })
+            }
+        }
+    }
+}
+
+/// Line diff between `value` and the recorded value closest to it (pure function)
+///
+/// The closest value differs in the fewest lines, then the fewest characters.
+/// Lines only in the recorded value start with `-`, lines only in `value`
+/// with `+`.
+pub fn closest_diff<'a>(value: &Value, recorded: impl Iterator<Item = &'a Value>) -> String {
+    let pretty = |value: &Value| serde_json::to_string_pretty(value).unwrap_or_default();
+    let lines = pretty(value);
+    let lines: Vec<&str> = lines.lines().collect();
+    let closest = recorded
+        .map(|candidate| line_diff(&pretty(candidate).lines().collect::<Vec<_>>(), &lines))
+        .min_by(|a, b| (a.lines().count(), a.len(), a).cmp(&(b.lines().count(), b.len(), b)));
+    match closest {
+        Some(diff) => format!("Differences from the closest recorded one:\n{diff}"),
+        None => "Nothing of this kind was recorded".to_string(),
+    }
+}
+
+/// Lines removed from `old` and added in `new`, in order (pure function)
+fn line_diff(old: &[&str], new: &[&str]) -> String {
+    // Longest common subsequence table, filled from the end
+    let mut common = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i][j] = if old[i] == new[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let mut diff = String::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            i += 1;
+            j += 1;
+        } else if j == new.len() || (i < old.len() && common[i + 1][j] >= common[i][j + 1]) {
+            diff.push_str(&format!("- {}\n", old[i]));
+            i += 1;
+        } else {
+            diff.push_str(&format!("+ {}\n", new[j]));
+            j += 1;
+        }
+    }
+    diff
+}
+
+/// Provider that records every exchange with the provider it wraps
+pub struct RecordingLlmProvider {
+    inner: Arc<dyn LlmProvider>,
+    recorder: Recorder,
+}
+
+impl RecordingLlmProvider {
+    pub fn new(inner: Arc<dyn LlmProvider>, recorder: Recorder) -> Self {
+        Self { inner, recorder }
+    }
+}
+
+#[async_trait]
+impl LlmProvider for RecordingLlmProvider {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn available_models(&self) -> Vec<String> {
+        self.inner.available_models()
+    }
+
+    async fn complete(&self, request: CompletionRequest) -> Result<CompletionResponse, LlmError> {
+        let response = self.inner.complete(request.clone()).await;
+        if let Err(e) = self.recorder.record_completion(&request, &response).await {
+            warn!(error = %e, "Failed to record LLM exchange");
+        }
+        response
+    }
+
+    async fn health_check(&self) -> Result<(), LlmError> {
+        self.inner.health_check().await
+    }
+
+    fn release_resources(&self) {
+        self.inner.release_resources()
+    }
+}
+
+/// Provider answering from a recording instead of calling a model
+pub struct ReplayLlmProvider {
+    recording: Arc<Recording>,
+}
+
+impl ReplayLlmProvider {
+    pub fn new(recording: Arc<Recording>) -> Self {
+        Self { recording }
+    }
+}
+
+#[async_trait]
+impl LlmProvider for ReplayLlmProvider {
+    fn name(&self) -> &str {
+        "replay"
+    }
+
+    fn available_models(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// The recorded response, or an error with the diff against the closest
+    /// recorded request
+    async fn complete(&self, request: CompletionRequest) -> Result<CompletionResponse, LlmError> {
+        self.recording
+            .completion(&request)
+            .map_err(|e| LlmError::InvalidRequest(e.to_string()))?
+    }
+
+    async fn health_check(&self) -> Result<(), LlmError> {
+        Ok(())
+    }
+}
+
+/// Tool that records every call to the tool it wraps
+pub struct RecordingTool {
+    name: String,
+    inner: Box<dyn Tool>,
+    recorder: Recorder,
+}
+
+impl RecordingTool {
+    pub fn new(name: impl Into<String>, inner: Box<dyn Tool>, recorder: Recorder) -> Self {
+        Self {
+            name: name.into(),
+            inner,
+            recorder,
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for RecordingTool {
+    fn describe(&self) -> ToolDescription {
+        self.inner.describe()
+    }
+
+    async fn initialize(&mut self, config: Option<&Value>) -> Result<(), ToolError> {
+        self.inner.initialize(config).await
+    }
+
+    async fn execute(&self, parameters: &Value) -> Result<Value, ToolError> {
+        let result = self.inner.execute(parameters).await;
+        if let Err(e) = self
+            .recorder
+            .record_tool_call(&self.name, parameters, &result)
+            .await
+        {
+            warn!(tool = %self.name, error = %e, "Failed to record tool call");
+        }
+        result
+    }
+
+    async fn shutdown(&mut self) -> Result<(), ToolError> {
+        self.inner.shutdown().await
+    }
+
+    async fn on_idle(&self) -> Result<(), ToolError> {
+        self.inner.on_idle().await
+    }
+}
+
+/// Tool answering from a recording instead of running
+pub struct ReplayTool {
+    description: ToolDescription,
+    recording: Arc<Recording>,
+}
+
+#[async_trait]
+impl Tool for ReplayTool {
+    fn describe(&self) -> ToolDescription {
+        self.description.clone()
+    }
+
+    async fn initialize(&mut self, _config: Option<&Value>) -> Result<(), ToolError> {
+        Ok(())
+    }
+
+    async fn execute(&self, parameters: &Value) -> Result<Value, ToolError> {
+        self.recording
+            .tool_result(&self.description.name, parameters)
+            .map_err(|e| ToolError::ExecutionError(e.to_string()))?
+    }
+}
+
+/// Tool system offering the recorded tools, each answering from the recording
+pub struct ReplayToolSystem;
+
+impl ReplayToolSystem {
+    pub fn build(recording: Arc<Recording>) -> ToolSystem {
+        let mut tool_system = ToolSystem::new();
+        for description in recording.tools() {
+            tool_system.register_tool(
+                description.name.clone(),
+                Box::new(ReplayTool {
+                    description: description.clone(),
+                    recording: recording.clone(),
+                }),
+            );
+        }
+        tool_system
+    }
+}
+
+/// Tool system for `config`, honoring its `[testing]` section
+///
+/// Replays from `replay_dir` when set; otherwise initializes the configured
+/// tools and, when `record_dir` is set, records their descriptions and calls.
+pub async fn build_tool_system(config: &AgentConfig) -> Result<ToolSystem, ToolError> {
+    let TestingSection {
+        record_dir,
+        replay_dir,
+        ..
+    } = &config.testing;
+    let initialization_error = |e: RecordingError| ToolError::InitializationError(e.to_string());
+
+    if let Some(dir) = replay_dir {
+        let recording = Recording::load(dir).map_err(initialization_error)?;
+        return Ok(ReplayToolSystem::build(Arc::new(recording)));
+    }
+
+    let mut tool_system = ToolSystem::new();
+    tool_system.initialize(&config.tools).await?;
+    if let Some(dir) = record_dir {
+        let recorder = Recorder::create(dir).map_err(initialization_error)?;
+        recorder
+            .record_tools(&tool_system.describe_tools())
+            .await
+            .map_err(initialization_error)?;
+        tool_system
+            .wrap_tools(|name, tool| Box::new(RecordingTool::new(name, tool, recorder.clone())));
+    }
+    Ok(tool_system)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::provider::{Message, MessageRole};
+    use crate::testing::mocks::MockLlmProvider;
+    use serde_json::json;
+
+    fn request(system_prompt: &str, question: &str) -> CompletionRequest {
+        CompletionRequest {
+            messages: vec![
+                Message {
+                    role: MessageRole::System,
+                    content: system_prompt.to_string(),
+                },
+                Message {
+                    role: MessageRole::User,
+                    content: question.to_string(),
+                },
+            ],
+            model: "gpt-4o".to_string(),
+            max_tokens: None,
+            temperature: None,
+            top_p: None,
+            seed: None,
+            stop_sequences: None,
+            tools: None,
+            tool_choice: None,
+            response_format: None,
+            metadata: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_request_key_ignores_the_current_date() {
+        let monday = request(
+            "Be brief\n\nCurrent date and time: 2026-10-12 09:00:00 UTC",
+            "Hi",
+        );
+        let tuesday = request(
+            "Be brief\n\nCurrent date and time: 2026-10-13 17:30:12 UTC",
+            "Hi",
+        );
+        assert_eq!(request_key(&monday), request_key(&tuesday));
+        assert_ne!(
+            request_key(&monday),
+            request_key(&request("Be brief", "Hello"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_recorded_exchanges_are_replayed() {
+        let dir = tempfile::tempdir().unwrap();
+        let recorder = Recorder::create(dir.path()).unwrap();
+        let provider = RecordingLlmProvider::new(
+            Arc::new(MockLlmProvider::single_response("hello")),
+            recorder.clone(),
+        );
+        let recorded = provider.complete(request("Be brief", "Hi")).await.unwrap();
+        recorder
+            .record_tool_call("lookup", &json!({"q": "rust"}), &Ok(json!({"hits": 3})))
+            .await
+            .unwrap();
+
+        let recording = Arc::new(Recording::load(dir.path()).unwrap());
+        let replayed = ReplayLlmProvider::new(recording.clone())
+            .complete(request("Be brief", "Hi"))
+            .await
+            .unwrap();
+        assert_eq!(
+            serde_json::to_value(&replayed).unwrap(),
+            serde_json::to_value(&recorded).unwrap()
+        );
+        assert_eq!(
+            recording
+                .tool_result("lookup", &json!({"q": "rust"}))
+                .unwrap()
+                .unwrap(),
+            json!({"hits": 3})
+        );
+    }
+
+    #[test]
+    fn test_unseen_request_shows_diff_against_closest() {
+        let mut recording = Recording::default();
+        for question in ["What is Rust?", "Completely unrelated"] {
+            let request = request("Be brief", question);
+            recording.completions.insert(
+                request_key(&request),
+                LlmExchange {
+                    request: normalize_request(&request),
+                    response: Err(LlmError::RequestFailed("unused".to_string())),
+                },
+            );
+        }
+
+        let error = recording
+            .completion(&request("Be brief", "What is Go?"))
+            .unwrap_err()
+            .to_string();
+        assert!(error.starts_with("No recorded LLM request matches this one"));
+        assert!(
+            error.contains("-       \"content\": \"What is Rust?\""),
+            "{error}"
+        );
+        assert!(
+            error.contains("+       \"content\": \"What is Go?\""),
+            "{error}"
+        );
+        assert!(!error.contains("Completely unrelated"));
+    }
+
+    #[test]
+    fn test_line_diff() {
+        assert_eq!(line_diff(&["a", "b", "c"], &["a", "c", "d"]), "- b\n+ d\n");
+        assert_eq!(line_diff(&[], &[]), "");
+    }
+}
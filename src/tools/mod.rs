@@ -5,12 +5,17 @@
 
 use crate::config::ToolConfig;
 use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
 use thiserror::Error;
+use tracing::warn;
 
 pub mod builtin;
 
+/// Names accepted for `<name> = "builtin"` tool configurations
+pub const BUILTIN_TOOLS: &[&str] = &["http_request", "file_read", "file_write", "web_search"];
+
 /// RFC Section 8: Tool interface specification
 #[async_trait]
 pub trait Tool: Send + Sync {
@@ -33,49 +38,122 @@ pub trait Tool: Send + Sync {
     async fn shutdown(&mut self) -> Result<(), ToolError> {
         Ok(())
     }
+
+    /// Called when the agent goes idle \[OPTIONAL\]
+    /// Releases state that can be rebuilt lazily on the next execute()
+    async fn on_idle(&self) -> Result<(), ToolError> {
+        Ok(())
+    }
 }
 
 /// Tool description per RFC Section 8.1
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ToolDescription {
     pub name: String,
     pub description: String,
     pub parameters: Value,
 }
 
+/// An optional tool left out because it failed to initialize
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DegradedTool {
+    pub name: String,
+    pub error: String,
+}
+
 /// Tool system for managing and executing RFC-compliant tools
 pub struct ToolSystem {
     tools: HashMap<String, Box<dyn Tool>>,
+    /// Optional tools skipped by `initialize`, sorted by name
+    degraded: Vec<DegradedTool>,
 }
 
 impl ToolSystem {
     pub fn new() -> Self {
         Self {
             tools: HashMap::new(),
+            degraded: Vec::new(),
         }
     }
 
     /// Initialize tool system with configuration from agent.toml
+    ///
+    /// An optional tool that fails is logged and left out; see
+    /// [`Self::degraded_tools`]. Every required tool is attempted: a single
+    /// failure is returned as is, several as [`ToolError::RequiredToolsFailed`].
     pub async fn initialize(
         &mut self,
         tool_configs: &HashMap<String, ToolConfig>,
     ) -> Result<(), ToolError> {
-        for (tool_name, tool_config) in tool_configs {
-            let mut tool = self.create_tool(tool_name, tool_config)?;
+        let mut tool_names: Vec<&String> = tool_configs.keys().collect();
+        tool_names.sort();
+
+        let mut failures = Vec::new();
+        for tool_name in tool_names {
+            let tool_config = &tool_configs[tool_name];
+            match self.initialize_tool(tool_name, tool_config).await {
+                Ok(tool) => {
+                    self.tools.insert(tool_name.clone(), tool);
+                }
+                Err(e) if tool_config.is_optional() => {
+                    warn!(tool = %tool_name, error = %e, "Optional tool failed to initialize, continuing without it");
+                    self.degraded.push(DegradedTool {
+                        name: tool_name.clone(),
+                        error: e.to_string(),
+                    });
+                }
+                Err(e) => failures.push((tool_name.clone(), e)),
+            }
+        }
+
+        match failures.len() {
+            0 => Ok(()),
+            1 => Err(failures.remove(0).1),
+            _ => Err(ToolError::RequiredToolsFailed(failures)),
+        }
+    }
 
-            // Extract config for initialize() method
-            let config = match tool_config {
-                ToolConfig::Simple(_) => None,
-                ToolConfig::Complex { config, .. } => Some(serde_json::to_value(config).unwrap()),
-            };
+    /// Create and initialize one configured tool
+    async fn initialize_tool(
+        &self,
+        tool_name: &str,
+        tool_config: &ToolConfig,
+    ) -> Result<Box<dyn Tool>, ToolError> {
+        let mut tool = self.create_tool(tool_name, tool_config)?;
 
-            // RFC Section 8.2: initialize(config) method
-            tool.initialize(config.as_ref()).await?;
+        // Extract config for initialize() method
+        let config = match tool_config {
+            ToolConfig::Simple(_) => None,
+            ToolConfig::Complex { config, .. } => Some(serde_json::to_value(config).unwrap()),
+        };
 
-            self.tools.insert(tool_name.clone(), tool);
-        }
+        // RFC Section 8.2: initialize(config) method
+        tool.initialize(config.as_ref()).await?;
+        Ok(tool)
+    }
 
-        Ok(())
+    /// Optional tools left out because they failed to initialize
+    pub fn degraded_tools(&self) -> &[DegradedTool] {
+        &self.degraded
+    }
+
+    /// Describe configured tools without initializing them, sorted by name
+    ///
+    /// Lets the CLI show tools whose initialization needs credentials that
+    /// aren't set locally.
+    pub fn describe_configured(
+        tool_configs: &HashMap<String, ToolConfig>,
+    ) -> Result<Vec<(String, ToolDescription)>, ToolError> {
+        let tool_system = Self::new();
+        let mut descriptions = tool_configs
+            .iter()
+            .map(|(tool_name, tool_config)| {
+                let tool = tool_system.create_tool(tool_name, tool_config)?;
+                Ok((tool_name.clone(), tool.describe()))
+            })
+            .collect::<Result<Vec<_>, ToolError>>()?;
+        descriptions.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(descriptions)
     }
 
     /// Create tool instance based on configuration
@@ -106,6 +184,24 @@ impl ToolSystem {
         }
     }
 
+    /// Register an already-initialized tool instance under the given name
+    pub fn register_tool(&mut self, tool_name: impl Into<String>, tool: Box<dyn Tool>) {
+        self.tools.insert(tool_name.into(), tool);
+    }
+
+    /// Replace every registered tool with what `wrap` returns for it
+    ///
+    /// Lets a caller observe tool calls without knowing the tool types.
+    pub fn wrap_tools(&mut self, mut wrap: impl FnMut(&str, Box<dyn Tool>) -> Box<dyn Tool>) {
+        self.tools = std::mem::take(&mut self.tools)
+            .into_iter()
+            .map(|(name, tool)| {
+                let tool = wrap(&name, tool);
+                (name, tool)
+            })
+            .collect();
+    }
+
     /// Get tool description
     pub fn describe_tool(&self, tool_name: &str) -> Option<ToolDescription> {
         self.tools.get(tool_name).map(|tool| tool.describe())
@@ -129,7 +225,11 @@ impl ToolSystem {
     }
 
     /// Validate parameters against tool schema per RFC Section 8.3
-    fn validate_parameters(&self, tool_name: &str, parameters: &Value) -> Result<(), ToolError> {
+    pub fn validate_parameters(
+        &self,
+        tool_name: &str,
+        parameters: &Value,
+    ) -> Result<(), ToolError> {
         let tool = self
             .tools
             .get(tool_name)
@@ -152,6 +252,25 @@ impl ToolSystem {
         self.tools.keys().cloned().collect()
     }
 
+    /// Describe every registered tool, sorted by name
+    pub fn describe_tools(&self) -> Vec<ToolDescription> {
+        let mut descriptions: Vec<ToolDescription> =
+            self.tools.values().map(|tool| tool.describe()).collect();
+        descriptions.sort_by(|a, b| a.name.cmp(&b.name));
+        descriptions
+    }
+
+    /// Notify every tool that the agent went idle
+    ///
+    /// A tool failing its idle hook is logged; the others are still notified.
+    pub async fn on_idle(&self) {
+        for (tool_name, tool) in &self.tools {
+            if let Err(e) = tool.on_idle().await {
+                warn!(tool = %tool_name, error = %e, "Tool idle hook failed");
+            }
+        }
+    }
+
     /// Shutdown all tools
     pub async fn shutdown(&mut self) -> Result<(), ToolError> {
         for tool in self.tools.values_mut() {
@@ -168,7 +287,7 @@ impl Default for ToolSystem {
 }
 
 /// RFC-compliant tool system errors
-#[derive(Debug, Error)]
+#[derive(Debug, Clone, Error, Serialize, Deserialize)]
 pub enum ToolError {
     #[error("Unknown tool: {0}")]
     UnknownTool(String),
@@ -184,6 +303,16 @@ pub enum ToolError {
     ExecutionError(String),
     #[error("Tool shutdown failed: {0}")]
     ShutdownError(String),
+    #[error("{} required tools failed to initialize: {}", .0.len(), format_tool_failures(.0))]
+    RequiredToolsFailed(Vec<(String, ToolError)>),
+}
+
+fn format_tool_failures(failures: &[(String, ToolError)]) -> String {
+    failures
+        .iter()
+        .map(|(tool_name, e)| format!("{tool_name}: {e}"))
+        .collect::<Vec<_>>()
+        .join("; ")
 }
 
 #[cfg(test)]
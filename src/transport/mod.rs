@@ -3,12 +3,38 @@
 //! This module provides transport abstraction and MQTT implementation
 //! for agent-to-agent communication and control messaging.
 
+use crate::processing::TaskJournal;
 use crate::protocol::{
-    AgentStatus, ErrorMessage, ResponseMessage, TaskEnvelope, TaskEnvelopeWrapper,
+    AdminMessage, AgentStatus, BatchSummary, CancelMessage, ErrorMessage, ResponseMessage, TaskAck,
+    TaskBatchEnvelope, TaskEnvelopeWrapper,
 };
 
 pub mod mqtt;
 
+/// A message received on a subscribed topic: the topic and its raw payload
+pub type TopicMessage = (String, Vec<u8>);
+
+/// Messages a topic subscriber may fall behind by before new ones are dropped
+pub const TOPIC_SUBSCRIPTION_CAPACITY: usize = 256;
+
+/// Whether `topic` matches an MQTT topic filter (pure function)
+///
+/// `+` matches exactly one level and a trailing `#` matches any remaining
+/// levels, including none.
+pub fn topic_matches(filter: &str, topic: &str) -> bool {
+    let mut filter_levels = filter.split('/');
+    let mut topic_levels = topic.split('/');
+    loop {
+        match (filter_levels.next(), topic_levels.next()) {
+            (Some("#"), _) => return filter_levels.next().is_none(),
+            (Some("+"), Some(_)) => {}
+            (Some(expected), Some(level)) if expected == level => {}
+            (None, None) => return true,
+            _ => return false,
+        }
+    }
+}
+
 /// Transport trait for agent communication
 ///
 /// This trait provides an abstraction over different transport mechanisms
@@ -27,10 +53,13 @@ pub trait Transport: Send + Sync {
     async fn publish_status(&self, status: &AgentStatus) -> Result<(), Self::Error>;
 
     /// Publish task to another agent
+    ///
+    /// v2.0 envelopes are published as-is so workflow context and routing
+    /// trace reach the next agent.
     async fn publish_task(
         &self,
         target_agent: &str,
-        envelope: &TaskEnvelope,
+        envelope: &TaskEnvelopeWrapper,
     ) -> Result<(), Self::Error>;
 
     /// Publish error message to conversation topic
@@ -47,6 +76,16 @@ pub trait Transport: Send + Sync {
         response: &ResponseMessage,
     ) -> Result<(), Self::Error>;
 
+    /// Publish batch summary to conversation topic once every batch item finished
+    async fn publish_batch_summary(
+        &self,
+        conversation_id: &str,
+        summary: &BatchSummary,
+    ) -> Result<(), Self::Error>;
+
+    /// Publish task acknowledgement (or rejection) to the conversation ack topic
+    async fn publish_ack(&self, conversation_id: &str, ack: &TaskAck) -> Result<(), Self::Error>;
+
     /// Subscribe to task input messages for this agent
     async fn subscribe_to_tasks(&mut self) -> Result<(), Self::Error>;
 
@@ -54,19 +93,103 @@ pub trait Transport: Send + Sync {
     async fn publish(&self, topic: &str, payload: Vec<u8>, retain: bool)
         -> Result<(), Self::Error>;
 
+    /// Subscribe to a topic outside the agent's own inputs
+    ///
+    /// Every message received on a topic matching the `topic` filter (which may
+    /// use the `+` and `#` wildcards) is delivered to the returned receiver
+    /// until it is dropped. A receiver that falls more than
+    /// `TOPIC_SUBSCRIPTION_CAPACITY` messages behind misses the newest ones.
+    async fn subscribe_topic(
+        &self,
+        topic: &str,
+    ) -> Result<tokio::sync::mpsc::Receiver<TopicMessage>, Self::Error>;
+
+    /// Stop receiving messages on `topic`, subscribed by this transport
+    ///
+    /// The topic is no longer restored after reconnecting, and receivers
+    /// returned by `subscribe_topic` for the same filter stop receiving.
+    async fn unsubscribe(&self, topic: &str) -> Result<(), Self::Error>;
+
     /// Check if transport is currently connected
     fn is_connected(&self) -> bool;
 
     /// Get current connection state
+    ///
+    /// To wait for a particular state, follow `connection_events` rather than
+    /// polling this.
     fn connection_state(&self) -> Option<crate::transport::mqtt::ConnectionState>;
 
+    /// Receiver notified of every change of connection state
+    ///
+    /// It holds the current state, so a receiver taken at any time, including
+    /// before connecting, sees where the connection stands and each later
+    /// transition, such as the move to `PermanentlyDisconnected`.
+    fn connection_events(
+        &self,
+    ) -> tokio::sync::watch::Receiver<crate::transport::mqtt::ConnectionState>;
+
     /// Check if the connection is permanently disconnected
+    ///
+    /// A point-in-time check; await `connection_events` to be told when it happens.
     fn is_permanently_disconnected(&self) -> bool;
 
+    /// Get connection uptime, activity and health
+    fn health_metrics(&self) -> crate::transport::mqtt::HealthMetrics;
+
     /// Set the task sender for forwarding received tasks to the pipeline
     /// Supports both v1.0 and v2.0 TaskEnvelope formats via TaskEnvelopeWrapper
     fn set_task_sender(&self, sender: tokio::sync::mpsc::Sender<TaskEnvelopeWrapper>);
+
+    /// Set the cancel sender for forwarding received cancel requests to the pipeline
+    fn set_cancel_sender(&self, sender: tokio::sync::mpsc::Sender<CancelMessage>);
+
+    /// Set the batch sender for forwarding received task batches to the pipeline
+    fn set_batch_sender(&self, sender: tokio::sync::mpsc::Sender<TaskBatchEnvelope>);
+
+    /// Set the admin sender for forwarding received pause/resume requests to the pipeline
+    fn set_admin_sender(&self, sender: tokio::sync::mpsc::Sender<AdminMessage>);
+
+    /// Journal received tasks before forwarding them to the pipeline
+    fn set_task_journal(&self, journal: std::sync::Arc<TaskJournal>);
 }
 
 /// Type alias for MQTT transport
 pub type MqttTransport = mqtt::MqttClient;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_topic_matches_wildcards() {
+        assert!(topic_matches(
+            "/control/agents/a/status",
+            "/control/agents/a/status"
+        ));
+        assert!(topic_matches(
+            "/control/agents/+/status",
+            "/control/agents/a/status"
+        ));
+        assert!(!topic_matches(
+            "/control/agents/+/status",
+            "/control/agents/a/manifest"
+        ));
+        assert!(!topic_matches(
+            "/control/agents/+/status",
+            "/control/agents/a/b/status"
+        ));
+        assert!(topic_matches(
+            "/control/agents/#",
+            "/control/agents/a/status"
+        ));
+        assert!(topic_matches("/control/agents/#", "/control/agents"));
+        assert!(!topic_matches(
+            "/control/#/status",
+            "/control/agents/a/status"
+        ));
+        assert!(!topic_matches(
+            "/control/agents/a",
+            "/control/agents/a/status"
+        ));
+    }
+}
@@ -3,17 +3,25 @@
 //! This module handles all impure I/O operations including network communication,
 //! async coordination, and integration with the rumqttc client.
 
+use super::codec::PayloadCodec;
 use super::connection::{
     configure_mqtt_options, ConnectionState, MqttError, ReconnectConfig, TopicBuilder,
 };
+use super::encryption::PayloadEncryptor;
 use super::health_monitor::{ConnectionEvent, HealthMetrics, HealthMonitor, ReconnectionDecision};
 use super::message_handler::{EventRoute, MessageForwarder, MessageHandler};
+use super::signing::MessageSigner;
 use crate::agent::discovery_integration::DiscoveryMqttIntegration;
+use crate::clock::{Clock, SystemClock};
 use crate::config::MqttSection;
+use crate::observability::event_log::{event_log, Event, EventCategory, EventSeverity};
+use crate::observability::metrics::metrics;
+use crate::processing::TaskJournal;
 use crate::protocol::{
-    AgentStatus, ErrorMessage, ResponseMessage, TaskEnvelope, TaskEnvelopeWrapper,
+    AdminMessage, AgentStatus, BatchSummary, CancelMessage, ErrorMessage, ResponseMessage, TaskAck,
+    TaskBatchEnvelope, TaskEnvelopeWrapper,
 };
-use crate::transport::Transport;
+use crate::transport::{TopicMessage, Transport, TOPIC_SUBSCRIPTION_CAPACITY};
 use async_trait::async_trait;
 use rumqttc::v5::mqttbytes::v5::PublishProperties;
 use rumqttc::v5::{mqttbytes::QoS, AsyncClient, EventLoop};
@@ -21,7 +29,7 @@ use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::{mpsc, watch, Mutex};
 use tokio::task::JoinHandle;
-use tracing::{debug, error, info, warn};
+use tracing::{debug, error, info, warn, Instrument};
 
 /// RFC-compliant MQTT transport client for 2389 Agent Protocol
 pub struct MqttClient {
@@ -32,14 +40,19 @@ pub struct MqttClient {
     event_loop_handle: Option<JoinHandle<()>>,
     state_rx: Option<watch::Receiver<ConnectionState>>,
     state_tx: Option<watch::Sender<ConnectionState>>,
+    state_events: watch::Sender<ConnectionState>, // outlives connections, for `connection_events`
     shutdown_tx: Option<watch::Sender<bool>>,
     reconnect_config: ReconnectConfig,
-    subscribed_topics: Vec<String>, // Track subscriptions for re-subscription
+    subscribed_topics: Arc<Mutex<Vec<String>>>, // Shared with the supervisor for re-subscription
     message_forwarder: Arc<Mutex<MessageForwarder>>,
     connect_time: Option<Instant>,
     last_message_time: Option<Instant>,
     reconnect_count: u32,
     discovery_integration: Option<Arc<Mutex<DiscoveryMqttIntegration>>>, // v2.0 agent discovery
+    signer: Option<Arc<MessageSigner>>, // HMAC signing and verification (opt-in)
+    encryptor: Option<Arc<PayloadEncryptor>>, // end-to-end payload encryption (opt-in)
+    last_status: std::sync::Mutex<Option<AgentStatus>>, // withdrawn on disconnect unless Unavailable
+    clock: Arc<dyn Clock>,                              // publish times stamped on tasks
 }
 
 impl MqttClient {
@@ -57,17 +70,48 @@ impl MqttClient {
             event_loop_handle: None,
             state_rx: None,
             state_tx: None,
+            state_events: watch::channel(ConnectionState::Disconnected(
+                "Not connected".to_string(),
+            ))
+            .0,
             shutdown_tx: None,
             reconnect_config: ReconnectConfig::default(),
-            subscribed_topics: Vec::new(),
+            subscribed_topics: Arc::new(Mutex::new(Vec::new())),
             message_forwarder: Arc::new(Mutex::new(MessageForwarder::new())),
             connect_time: None,
             last_message_time: None,
             reconnect_count: 0,
             discovery_integration: None, // v2.0 discovery disabled by default
+            signer: None,                // message signing disabled by default
+            encryptor: None,             // payload encryption disabled by default
+            last_status: std::sync::Mutex::new(None),
+            clock: Arc::new(SystemClock),
         })
     }
 
+    /// Enable HMAC signing of outgoing tasks/responses and verification of incoming tasks
+    ///
+    /// Must be called before `connect()`. Once enabled, unsigned or invalid tasks are
+    /// rejected and published to the agent's dead-letter topic.
+    pub fn set_message_signer(&mut self, signer: MessageSigner) {
+        self.signer = Some(Arc::new(signer));
+        info!("Message signing enabled");
+    }
+
+    /// Enable encryption of outgoing tasks/responses and decryption of incoming tasks
+    ///
+    /// Must be called before `connect()`. Payloads are encrypted before signing, so
+    /// signatures cover the ciphertext. Unencrypted incoming tasks are still accepted.
+    pub fn set_payload_encryptor(&mut self, encryptor: PayloadEncryptor) {
+        info!(key_id = encryptor.key_id(), "Payload encryption enabled");
+        self.encryptor = Some(Arc::new(encryptor));
+    }
+
+    /// Stamp publish times on tasks from `clock` instead of the system clock
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+
     /// Enable v2.0 agent discovery (opt-in)
     pub async fn enable_discovery(
         &mut self,
@@ -115,16 +159,21 @@ impl MqttClient {
     }
 
     /// Create connection state and shutdown channels
-    /// Pure function for channel setup - easily testable
+    ///
+    /// The state is reported on `state_events`, reset to `Connecting`, so
+    /// receivers taken before connecting see every transition.
     #[allow(clippy::type_complexity)]
-    fn setup_connection_channels() -> (
+    fn setup_connection_channels(
+        state_events: &watch::Sender<ConnectionState>,
+    ) -> (
         (
             watch::Sender<ConnectionState>,
             watch::Receiver<ConnectionState>,
         ),
         (watch::Sender<bool>, watch::Receiver<bool>),
     ) {
-        let state_channels = watch::channel(ConnectionState::Connecting);
+        state_events.send_replace(ConnectionState::Connecting);
+        let state_channels = (state_events.clone(), state_events.subscribe());
         let shutdown_channels = watch::channel(false);
         (state_channels, shutdown_channels)
     }
@@ -180,7 +229,7 @@ impl MqttClient {
 
         // Setup channels using pure function
         let ((state_tx, state_rx), (shutdown_tx, mut shutdown_rx)) =
-            Self::setup_connection_channels();
+            Self::setup_connection_channels(&self.state_events);
         self.state_rx = Some(state_rx.clone());
         self.state_tx = Some(state_tx.clone());
         self.shutdown_tx = Some(shutdown_tx);
@@ -193,6 +242,8 @@ impl MqttClient {
         let subscribed_topics = self.subscribed_topics.clone();
         let message_forwarder = self.message_forwarder.clone();
         let discovery_integration = self.discovery_integration.clone(); // v2.0 discovery
+        let signer = self.signer.clone();
+        let encryptor = self.encryptor.clone();
 
         let handle = tokio::spawn(async move {
             info!(
@@ -240,6 +291,8 @@ impl MqttClient {
                                     shutdown_rx.clone(),
                                     &mut current_event_loop,
                                     &config,
+                                    signer.as_deref(),
+                                    encryptor.as_deref(),
                                 ).await {
                                     break;
                                 }
@@ -299,6 +352,16 @@ impl MqttClient {
             ConnectionEvent::NetworkError(error_str.clone()),
         );
         let _ = state_tx.send(new_state);
+        metrics().mqtt_connection_lost();
+        event_log().record(
+            Event::new(
+                EventSeverity::Warning,
+                EventCategory::Connection,
+                "MQTT connection lost",
+            )
+            .with_field("agent_id", agent_id)
+            .with_field("error", error_str),
+        );
 
         error!("MQTT event loop error for agent {}: {}", agent_id, error);
 
@@ -324,13 +387,15 @@ impl MqttClient {
         state_tx: &watch::Sender<ConnectionState>,
         reconnect_attempts: &mut u32,
         shared_client: &Arc<Mutex<AsyncClient>>,
-        subscribed_topics: &[String],
+        subscribed_topics: &Mutex<Vec<String>>,
         message_forwarder: &Arc<Mutex<MessageForwarder>>,
         agent_id: &str,
         reconnect_config: &ReconnectConfig,
         shutdown_rx: watch::Receiver<bool>,
         current_event_loop: &mut Arc<Mutex<EventLoop>>,
         config: &MqttSection,
+        signer: Option<&MessageSigner>,
+        encryptor: Option<&PayloadEncryptor>,
     ) -> bool {
         match route {
             EventRoute::ConnectionAcknowledged => {
@@ -339,22 +404,50 @@ impl MqttClient {
                     ConnectionEvent::ConnAckReceived,
                 );
                 let _ = state_tx.send(new_state);
+                event_log().record(
+                    Event::new(
+                        EventSeverity::Info,
+                        EventCategory::Connection,
+                        "MQTT connected",
+                    )
+                    .with_field("agent_id", agent_id)
+                    .with_field("reconnect_attempts", *reconnect_attempts),
+                );
                 *reconnect_attempts = 0;
-                Self::resubscribe_to_topics(shared_client, subscribed_topics).await;
+                metrics().mqtt_connection_established();
+                let tracked_topics = subscribed_topics.lock().await.clone();
+                let extra_topics = message_forwarder.lock().await.subscribed_topics();
+                let topics = Self::topics_to_restore(&tracked_topics, &extra_topics);
+                Self::resubscribe_to_topics(shared_client, &topics).await;
+                if !topics.is_empty() {
+                    info!(count = topics.len(), topics = ?topics, "Restored subscriptions");
+                }
                 true
             }
             EventRoute::MessageReceived {
                 topic,
                 payload,
                 retain,
+                signature,
+                content_type,
             } => {
                 Self::handle_message_received(
                     message_forwarder,
+                    shared_client,
                     agent_id,
                     &topic,
                     &payload,
                     retain,
+                    signature.as_deref(),
+                    content_type.as_deref(),
+                    signer,
+                    encryptor,
                 )
+                .instrument(crate::mqtt_span!(
+                    agent_id = agent_id,
+                    operation = "receive",
+                    topic = %topic
+                ))
                 .await;
                 true
             }
@@ -364,6 +457,15 @@ impl MqttClient {
                     ConnectionEvent::DisconnectedByBroker,
                 );
                 let _ = state_tx.send(new_state);
+                metrics().mqtt_connection_lost();
+                event_log().record(
+                    Event::new(
+                        EventSeverity::Warning,
+                        EventCategory::Connection,
+                        "Disconnected by broker",
+                    )
+                    .with_field("agent_id", agent_id),
+                );
 
                 Self::should_attempt_reconnection(
                     *reconnect_attempts,
@@ -394,23 +496,82 @@ impl MqttClient {
     }
 
     /// Helper to handle received messages
+    #[allow(clippy::too_many_arguments)]
     async fn handle_message_received(
         message_forwarder: &Arc<Mutex<MessageForwarder>>,
+        shared_client: &Arc<Mutex<AsyncClient>>,
         agent_id: &str,
         topic: &str,
         payload: &[u8],
         retain: bool,
+        signature: Option<&str>,
+        content_type: Option<&str>,
+        signer: Option<&MessageSigner>,
+        encryptor: Option<&PayloadEncryptor>,
     ) {
         tracing::debug!(target: "mqtt_transport", "Received MQTT message on topic: {}", topic);
 
+        // Topics subscribed through `subscribe_topic` go to their subscribers as-is
+        if message_forwarder
+            .lock()
+            .await
+            .forward_topic_message(topic, payload)
+        {
+            return;
+        }
+
+        // Pause and resume requests are signed too, so only trusted operators
+        // can stop task intake
+        let admin_topic = TopicBuilder::build_admin_topic(agent_id);
+        let is_admin = MessageHandler::should_process_message(topic, retain, &admin_topic);
+
+        // Batches arrive on their own topic but are signed like single tasks
+        let batch_topic = TopicBuilder::build_batch_topic(agent_id);
+        let is_batch = MessageHandler::should_process_message(topic, retain, &batch_topic);
+
+        // Cancel requests are signed too, so only trusted producers can stop a task
+        let cancel_topic = TopicBuilder::build_cancel_topic(agent_id);
+        let is_cancel = MessageHandler::should_process_message(topic, retain, &cancel_topic);
+
         let expected_topic = TopicBuilder::build_input_topic(agent_id);
-        if !MessageHandler::should_process_message(topic, retain, &expected_topic) {
+        if !is_admin
+            && !is_batch
+            && !is_cancel
+            && !MessageHandler::should_process_message(topic, retain, &expected_topic)
+        {
+            return;
+        }
+
+        // Reject unsigned or tampered messages when verification is enabled
+        if let Some(signer) = signer {
+            if let Err(e) = signer.verify(payload, signature) {
+                warn!("Rejecting message on {}: {}", topic, e);
+                metrics().mqtt_signature_failed();
+                Self::publish_dead_letter(shared_client, agent_id, topic, payload, &e.to_string())
+                    .await;
+                return;
+            }
+        }
+
+        if is_admin {
+            Self::handle_admin_received(message_forwarder, payload).await;
+            return;
+        }
+
+        if is_cancel {
+            Self::handle_cancel_received(message_forwarder, payload).await;
+            return;
+        }
+
+        if is_batch {
+            Self::handle_batch_received(message_forwarder, payload, content_type, encryptor).await;
             return;
         }
 
         // Parse and forward TaskEnvelope to pipeline
         let forwarder_guard = message_forwarder.lock().await;
-        match MessageHandler::parse_task_envelope(payload) {
+        match MessageHandler::parse_task_envelope_with_encryption(payload, content_type, encryptor)
+        {
             Ok(task_envelope) => {
                 if let Err(e) = forwarder_guard.forward_task(task_envelope).await {
                     error!("Failed to forward task: {}", e);
@@ -418,6 +579,123 @@ impl MqttClient {
             }
             Err(e) => {
                 error!("Failed to parse TaskEnvelope from MQTT message: {}", e);
+                drop(forwarder_guard);
+                Self::publish_rejected_task_error(
+                    shared_client,
+                    agent_id,
+                    payload,
+                    content_type,
+                    &e,
+                )
+                .await;
+            }
+        }
+    }
+
+    /// Report a rejected task back to its conversation when the payload allows it (best effort)
+    async fn publish_rejected_task_error(
+        shared_client: &Arc<Mutex<AsyncClient>>,
+        agent_id: &str,
+        payload: &[u8],
+        content_type: Option<&str>,
+        reason: &str,
+    ) {
+        let Some((conversation_id, error_message)) =
+            MessageHandler::build_rejected_task_error(payload, content_type, reason)
+        else {
+            return;
+        };
+
+        let topic = TopicBuilder::build_error_topic(&conversation_id, agent_id);
+        let error_payload = match MessageHandler::format_error_payload(&error_message) {
+            Ok(payload) => payload,
+            Err(e) => {
+                error!("Failed to format rejected task error: {}", e);
+                return;
+            }
+        };
+
+        let client = shared_client.lock().await;
+        if let Err(e) = client
+            .publish(&topic, QoS::AtLeastOnce, false, error_payload)
+            .await
+        {
+            error!("Failed to publish rejected task error to {}: {}", topic, e);
+        }
+    }
+
+    /// Publish a rejected message to the agent's dead-letter topic (best effort)
+    async fn publish_dead_letter(
+        shared_client: &Arc<Mutex<AsyncClient>>,
+        agent_id: &str,
+        topic: &str,
+        payload: &[u8],
+        reason: &str,
+    ) {
+        let dlq_topic = TopicBuilder::build_dlq_topic(agent_id);
+        let dead_letter = MessageHandler::format_dead_letter_payload(topic, payload, reason);
+
+        let client = shared_client.lock().await;
+        if let Err(e) = client
+            .publish(&dlq_topic, QoS::AtLeastOnce, false, dead_letter)
+            .await
+        {
+            error!("Failed to publish dead letter to {}: {}", dlq_topic, e);
+        }
+    }
+
+    /// Helper to handle received cancel requests
+    async fn handle_cancel_received(
+        message_forwarder: &Arc<Mutex<MessageForwarder>>,
+        payload: &[u8],
+    ) {
+        let forwarder_guard = message_forwarder.lock().await;
+        match MessageHandler::parse_cancel_message(payload) {
+            Ok(cancel) => {
+                if let Err(e) = forwarder_guard.forward_cancel(cancel).await {
+                    error!("Failed to forward cancel request: {}", e);
+                }
+            }
+            Err(e) => {
+                error!("Failed to parse CancelMessage from MQTT message: {}", e);
+            }
+        }
+    }
+
+    /// Helper to handle received pause/resume requests
+    async fn handle_admin_received(
+        message_forwarder: &Arc<Mutex<MessageForwarder>>,
+        payload: &[u8],
+    ) {
+        let forwarder_guard = message_forwarder.lock().await;
+        match MessageHandler::parse_admin_message(payload) {
+            Ok(admin) => {
+                if let Err(e) = forwarder_guard.forward_admin(admin).await {
+                    error!("Failed to forward admin request: {}", e);
+                }
+            }
+            Err(e) => {
+                error!("Failed to parse AdminMessage from MQTT message: {}", e);
+            }
+        }
+    }
+
+    /// Helper to handle received task batches
+    async fn handle_batch_received(
+        message_forwarder: &Arc<Mutex<MessageForwarder>>,
+        payload: &[u8],
+        content_type: Option<&str>,
+        encryptor: Option<&PayloadEncryptor>,
+    ) {
+        let forwarder_guard = message_forwarder.lock().await;
+        match MessageHandler::parse_batch_envelope(payload, content_type, encryptor) {
+            Ok(batch) => {
+                if let Err(e) = forwarder_guard.forward_batch(batch).await {
+                    error!("Failed to forward task batch: {}", e);
+                }
+            }
+            Err(e) => {
+                error!("Failed to parse TaskBatchEnvelope from MQTT message: {}", e);
             }
         }
     }
@@ -467,6 +745,20 @@ impl MqttClient {
         }
     }
 
+    /// Topics to subscribe to after (re)connecting, each once (pure function)
+    ///
+    /// The agent's own topics come first, then those of live `subscribe_topic`
+    /// receivers.
+    fn topics_to_restore(tracked: &[String], extra: &[String]) -> Vec<String> {
+        let mut topics: Vec<String> = Vec::with_capacity(tracked.len() + extra.len());
+        for topic in tracked.iter().chain(extra) {
+            if !topics.contains(topic) {
+                topics.push(topic.clone());
+            }
+        }
+        topics
+    }
+
     /// Helper to resubscribe to topics after reconnection
     async fn resubscribe_to_topics(client: &Arc<Mutex<AsyncClient>>, topics: &[String]) {
         let client_guard = client.lock().await;
@@ -514,6 +806,16 @@ impl MqttClient {
                     "Attempting reconnection {}/{} after {}ms delay",
                     attempt, max_display, delay_ms
                 );
+                event_log().record(
+                    Event::new(
+                        EventSeverity::Info,
+                        EventCategory::Connection,
+                        "Reconnecting to MQTT broker",
+                    )
+                    .with_field("agent_id", agent_id)
+                    .with_field("attempt", attempt)
+                    .with_field("delay_ms", delay_ms),
+                );
 
                 // Sleep with shutdown monitoring
                 if !Self::interruptible_sleep(shutdown_rx.clone(), delay_ms).await {
@@ -539,6 +841,15 @@ impl MqttClient {
                     .max_attempts
                     .expect("AbortMaxAttemptsExceeded should only occur when max_attempts is Some");
                 let reason = format!("Max reconnection attempts ({max_attempts}) exceeded");
+                event_log().record(
+                    Event::new(
+                        EventSeverity::Error,
+                        EventCategory::Connection,
+                        "MQTT connection permanently lost",
+                    )
+                    .with_field("agent_id", agent_id)
+                    .with_field("reason", reason.as_str()),
+                );
                 let new_state = HealthMonitor::determine_next_state(
                     &ConnectionState::Disconnected("".to_string()),
                     ConnectionEvent::PermanentFailure(reason),
@@ -552,17 +863,14 @@ impl MqttClient {
     /// Disconnect from MQTT broker per RFC Section 7.2 shutdown sequence
     /// FIXES Issue #5: Graceful shutdown coordination instead of abrupt abort
     pub async fn disconnect(&mut self) -> Result<(), MqttError> {
-        // RFC Section 7.2: Agent MUST publish unavailability status before disconnect
-        let status = AgentStatus {
-            agent_id: self.agent_id.clone(),
-            status: crate::protocol::AgentStatusType::Unavailable,
-            timestamp: chrono::Utc::now(),
-            capabilities: None,
-            description: None,
-        };
-
-        // Best effort to publish unavailable status
-        let _ = self.publish_status(&status).await;
+        // RFC Section 7.2: Agent MUST publish unavailability status before disconnect.
+        // The agent's status publisher normally sent it already.
+        let unavailable =
+            Self::unavailable_on_disconnect(self.last_status.lock().unwrap().as_ref());
+        if let Some(status) = unavailable {
+            // Best effort to publish unavailable status
+            let _ = self.publish_status(&status).await;
+        }
 
         // Signal the reconnection supervisor to stop
         if let Some(shutdown_tx) = &self.shutdown_tx {
@@ -614,6 +922,13 @@ impl MqttClient {
         self.state_rx.as_ref().map(|rx| rx.borrow().clone())
     }
 
+    /// Receiver notified of every change of connection state
+    ///
+    /// Before connecting the state is `Disconnected`.
+    pub fn connection_events(&self) -> watch::Receiver<ConnectionState> {
+        self.state_events.subscribe()
+    }
+
     /// Check if the connection is permanently disconnected
     pub fn is_permanently_disconnected(&self) -> bool {
         matches!(
@@ -649,6 +964,21 @@ impl MqttClient {
         Ok(())
     }
 
+    /// Status to publish when disconnecting after `last` was published (pure function)
+    ///
+    /// Nothing when no status was published, as for CLI clients, or when the
+    /// last one already said Unavailable. Otherwise the last status as
+    /// Unavailable, so the agent's details stay consistent.
+    fn unavailable_on_disconnect(last: Option<&AgentStatus>) -> Option<AgentStatus> {
+        let last =
+            last.filter(|last| last.status != crate::protocol::AgentStatusType::Unavailable)?;
+        Some(AgentStatus {
+            status: crate::protocol::AgentStatusType::Unavailable,
+            timestamp: chrono::Utc::now(),
+            ..last.clone()
+        })
+    }
+
     /// Publish agent status per RFC Section 6.2
     /// FIXES Issue #2: Guards against publishing when not connected
     ///
@@ -702,35 +1032,60 @@ impl MqttClient {
             retain,
             if retain { "3600" } else { "none" }
         );
+        *self.last_status.lock().unwrap() = Some(status.clone());
         Ok(())
     }
 
+    /// Encode a task or response in the configured wire format, encrypting it if enabled
+    fn encode_payload<T: serde::Serialize>(&self, message: &T) -> Result<Vec<u8>, MqttError> {
+        let format = self._config.payload_format;
+        PayloadCodec::encode(message, format)
+            .and_then(|payload| {
+                MessageHandler::encrypt_payload(payload, format, self.encryptor.as_deref())
+            })
+            .map_err(MqttError::ConnectionFailedStr)
+    }
+
+    /// Publish properties announcing the content type and, if enabled, the signature
+    fn build_payload_properties(&self, payload: &[u8]) -> PublishProperties {
+        PublishProperties {
+            content_type: Some(PayloadCodec::content_type(self._config.payload_format).to_string()),
+            ..MessageHandler::build_signed_properties(payload, self.signer.as_deref())
+        }
+    }
+
     /// Publish task to another agent per RFC Section 6.1
     /// FIXES Issue #2: Guards against publishing when not connected
     pub async fn publish_task(
         &self,
         target_agent: &str,
-        task: &TaskEnvelope,
+        task: &TaskEnvelopeWrapper,
     ) -> Result<(), MqttError> {
+        debug_assert!(
+            !target_agent.contains('/'),
+            "publish_task expects an agent id, not a topic: {target_agent}"
+        );
         self.check_connection_state()?;
 
         let topic = TopicBuilder::build_target_input_topic(target_agent);
-        let payload = serde_json::to_string(task).map_err(MqttError::SerializationError)?;
+        // Receivers use the publish time to drop stale redeliveries
+        let mut task = task.clone();
+        task.set_published_at(self.clock.now());
+        // Continue the publishing span's trace in the receiving agent
+        if let Some(traceparent) = crate::observability::otel::current_traceparent() {
+            task.set_traceparent(traceparent);
+        }
+        let payload = self.encode_payload(&task)?;
+        let props = self.build_payload_properties(&payload);
 
         // RFC Section 5.1: Task messages are QoS 1, NOT RETAINED
         let client = self.client.lock().await;
         client
-            .publish_with_properties(
-                &topic,
-                QoS::AtLeastOnce,
-                false,
-                payload,
-                PublishProperties::default(),
-            )
+            .publish_with_properties(&topic, QoS::AtLeastOnce, false, payload, props)
             .await
             .map_err(|e| MqttError::PublishFailed(Box::new(e)))?;
 
-        debug!("Published task to {}: {}", topic, task.task_id);
+        debug!("Published task to {}: {}", topic, task.task_id());
         Ok(())
     }
 
@@ -774,19 +1129,13 @@ impl MqttClient {
         self.check_connection_state()?;
 
         let topic = TopicBuilder::build_response_topic(conversation_id, &self.agent_id);
-        let payload = MessageHandler::format_response_payload(response)
-            .map_err(MqttError::ConnectionFailedStr)?;
+        let payload = self.encode_payload(response)?;
+        let props = self.build_payload_properties(&payload);
 
         // Response messages are QoS 1, NOT RETAINED (like errors)
         let client = self.client.lock().await;
         client
-            .publish_with_properties(
-                &topic,
-                QoS::AtLeastOnce,
-                false,
-                payload,
-                PublishProperties::default(),
-            )
+            .publish_with_properties(&topic, QoS::AtLeastOnce, false, payload, props)
             .await
             .map_err(|e| MqttError::PublishFailed(Box::new(e)))?;
 
@@ -794,6 +1143,56 @@ impl MqttClient {
         Ok(())
     }
 
+    /// Publish batch summary to conversation topic
+    /// Uses the same topic as responses so clients see it alongside item results
+    pub async fn publish_batch_summary(
+        &self,
+        conversation_id: &str,
+        summary: &BatchSummary,
+    ) -> Result<(), MqttError> {
+        self.check_connection_state()?;
+
+        let topic = TopicBuilder::build_response_topic(conversation_id, &self.agent_id);
+        let payload = self.encode_payload(summary)?;
+        let props = self.build_payload_properties(&payload);
+
+        // Batch summaries are QoS 1, NOT RETAINED (like responses)
+        let client = self.client.lock().await;
+        client
+            .publish_with_properties(&topic, QoS::AtLeastOnce, false, payload, props)
+            .await
+            .map_err(|e| MqttError::PublishFailed(Box::new(e)))?;
+
+        info!(
+            "Published batch summary to {}: batch {} ({} succeeded, {} failed)",
+            topic, summary.batch_id, summary.succeeded, summary.failed
+        );
+        Ok(())
+    }
+
+    /// Publish task acknowledgement to the conversation ack topic
+    /// Lets producers tell an unreceived task from one that is still being worked on
+    pub async fn publish_ack(&self, conversation_id: &str, ack: &TaskAck) -> Result<(), MqttError> {
+        self.check_connection_state()?;
+
+        let topic = TopicBuilder::build_ack_topic(conversation_id, &self.agent_id);
+        let payload = self.encode_payload(ack)?;
+        let props = self.build_payload_properties(&payload);
+
+        // Acks are QoS 1, NOT RETAINED (like responses)
+        let client = self.client.lock().await;
+        client
+            .publish_with_properties(&topic, QoS::AtLeastOnce, false, payload, props)
+            .await
+            .map_err(|e| MqttError::PublishFailed(Box::new(e)))?;
+
+        debug!(
+            "Published {:?} ack to {}: task {}",
+            ack.status, topic, ack.task_id
+        );
+        Ok(())
+    }
+
     /// Subscribe to task input topic per RFC Section 7.1
     /// FIXES Issue #4: Verifies subscription success with SubAck
     pub async fn subscribe_to_tasks(&mut self) -> Result<(), MqttError> {
@@ -807,28 +1206,120 @@ impl MqttClient {
             }
         }
 
-        // RFC Section 5.2: Subscribe to agent input topic
-        let topic = TopicBuilder::build_input_topic(&self.agent_id);
+        // RFC Section 5.2: Subscribe to agent input topic, plus the batch, cancel and admin topics
+        let topics = [
+            TopicBuilder::build_input_topic(&self.agent_id),
+            TopicBuilder::build_batch_topic(&self.agent_id),
+            TopicBuilder::build_cancel_topic(&self.agent_id),
+            TopicBuilder::build_admin_topic(&self.agent_id),
+        ];
 
-        info!("Subscribing to task input topic: {}", topic);
+        for topic in topics {
+            info!("Subscribing to agent topic: {}", topic);
 
-        // Subscribe with QoS 1 for reliability
-        let client = self.client.lock().await;
-        client
-            .subscribe(&topic, QoS::AtLeastOnce)
+            // Subscribe with QoS 1 for reliability
+            let client = self.client.lock().await;
+            client
+                .subscribe(&topic, QoS::AtLeastOnce)
+                .await
+                .map_err(|e| {
+                    MqttError::SubscriptionFailed(
+                        format!("Failed to subscribe to {topic}: {e}").into(),
+                    )
+                })?;
+            drop(client);
+
+            // Track subscription for potential re-subscription after reconnection
+            let mut subscribed_topics = self.subscribed_topics.lock().await;
+            if !subscribed_topics.contains(&topic) {
+                subscribed_topics.push(topic.clone());
+            }
+            drop(subscribed_topics);
+
+            info!("Successfully subscribed to: {}", topic);
+        }
+
+        Ok(())
+    }
+
+    /// Subscribe to a topic outside the agent's inputs, such as a conversation's progress
+    ///
+    /// The subscription is restored after reconnecting for as long as the
+    /// receiver is alive.
+    pub async fn subscribe_topic(
+        &self,
+        topic: &str,
+    ) -> Result<mpsc::Receiver<TopicMessage>, MqttError> {
+        if let Some(state_rx) = &self.state_rx {
+            let current_state = state_rx.borrow().clone();
+            if !HealthMonitor::can_subscribe(&current_state) {
+                return Err(MqttError::NotConnected {
+                    state: current_state,
+                });
+            }
+        }
+
+        // Register first so nothing received right after the SUBSCRIBE is lost
+        let (sender, receiver) = mpsc::channel(TOPIC_SUBSCRIPTION_CAPACITY);
+        self.message_forwarder
+            .lock()
+            .await
+            .add_topic_subscriber(topic.to_string(), sender);
+
+        self.client
+            .lock()
+            .await
+            .subscribe(topic, QoS::AtLeastOnce)
             .await
             .map_err(|e| {
                 MqttError::SubscriptionFailed(format!("Failed to subscribe to {topic}: {e}").into())
             })?;
+        info!("Subscribed to topic: {}", topic);
+        Ok(receiver)
+    }
+
+    /// Stop receiving messages on `topic`
+    ///
+    /// The topic is forgotten first, so it is not restored by a reconnect even
+    /// when the UNSUBSCRIBE itself cannot be sent. `subscribe_topic` receivers
+    /// for the same filter stop receiving too.
+    pub async fn unsubscribe(&self, topic: &str) -> Result<(), MqttError> {
+        self.subscribed_topics
+            .lock()
+            .await
+            .retain(|subscribed| subscribed != topic);
+        self.message_forwarder
+            .lock()
+            .await
+            .remove_topic_subscribers(topic);
 
-        // Track subscription for potential re-subscription after reconnection
-        if !self.subscribed_topics.contains(&topic) {
-            self.subscribed_topics.push(topic.clone());
+        // A disconnected client has nothing to unsubscribe from until it reconnects
+        if let Some(state_rx) = &self.state_rx {
+            let current_state = state_rx.borrow().clone();
+            if !HealthMonitor::can_subscribe(&current_state) {
+                debug!(topic, state = ?current_state, "Not connected, skipping UNSUBSCRIBE");
+                return Ok(());
+            }
         }
 
-        info!("Successfully subscribed to: {}", topic);
+        self.client
+            .lock()
+            .await
+            .unsubscribe(topic)
+            .await
+            .map_err(|e| {
+                MqttError::SubscriptionFailed(
+                    format!("Failed to unsubscribe from {topic}: {e}").into(),
+                )
+            })?;
+        info!("Unsubscribed from topic: {}", topic);
         Ok(())
     }
+
+    /// Topics restored after reconnecting, besides `subscribe_topic` ones
+    pub async fn subscribed_topics(&self) -> Vec<String> {
+        self.subscribed_topics.lock().await.clone()
+    }
 }
 
 /// Implementation of Transport trait for MqttClient
@@ -854,7 +1345,7 @@ impl Transport for MqttClient {
     async fn publish_task(
         &self,
         target_agent: &str,
-        task: &TaskEnvelope,
+        task: &TaskEnvelopeWrapper,
     ) -> Result<(), Self::Error> {
         // Delegate to existing publish_task method on self
         MqttClient::publish_task(self, target_agent, task).await
@@ -878,11 +1369,34 @@ impl Transport for MqttClient {
         MqttClient::publish_response(self, conversation_id, response).await
     }
 
+    async fn publish_batch_summary(
+        &self,
+        conversation_id: &str,
+        summary: &BatchSummary,
+    ) -> Result<(), Self::Error> {
+        MqttClient::publish_batch_summary(self, conversation_id, summary).await
+    }
+
+    async fn publish_ack(&self, conversation_id: &str, ack: &TaskAck) -> Result<(), Self::Error> {
+        MqttClient::publish_ack(self, conversation_id, ack).await
+    }
+
     async fn subscribe_to_tasks(&mut self) -> Result<(), Self::Error> {
         // Delegate to existing subscribe_to_tasks method on self
         MqttClient::subscribe_to_tasks(self).await
     }
 
+    async fn subscribe_topic(
+        &self,
+        topic: &str,
+    ) -> Result<mpsc::Receiver<TopicMessage>, Self::Error> {
+        MqttClient::subscribe_topic(self, topic).await
+    }
+
+    async fn unsubscribe(&self, topic: &str) -> Result<(), Self::Error> {
+        MqttClient::unsubscribe(self, topic).await
+    }
+
     fn is_connected(&self) -> bool {
         // Check if we have a connected state
         matches!(self.connection_state(), Some(ConnectionState::Connected))
@@ -893,11 +1407,19 @@ impl Transport for MqttClient {
         MqttClient::connection_state(self)
     }
 
+    fn connection_events(&self) -> watch::Receiver<crate::transport::mqtt::ConnectionState> {
+        MqttClient::connection_events(self)
+    }
+
     fn is_permanently_disconnected(&self) -> bool {
         // Delegate to existing is_permanently_disconnected method on self
         MqttClient::is_permanently_disconnected(self)
     }
 
+    fn health_metrics(&self) -> HealthMetrics {
+        self.get_health_metrics()
+    }
+
     async fn publish(
         &self,
         topic: &str,
@@ -907,9 +1429,10 @@ impl Transport for MqttClient {
         self.check_connection_state()?;
 
         let qos = MessageHandler::determine_qos_level(retain);
+        let props = MessageHandler::build_signed_properties(&payload, self.signer.as_deref());
         let client = self.client.lock().await;
         client
-            .publish_with_properties(topic, qos, retain, payload, PublishProperties::default())
+            .publish_with_properties(topic, qos, retain, payload, props)
             .await
             .map_err(|e| MqttError::PublishFailed(Box::new(e)))?;
 
@@ -924,6 +1447,38 @@ impl Transport for MqttClient {
             forwarder.set_task_sender(sender);
         });
     }
+
+    fn set_cancel_sender(&self, sender: mpsc::Sender<CancelMessage>) {
+        let message_forwarder = self.message_forwarder.clone();
+        tokio::spawn(async move {
+            let mut forwarder = message_forwarder.lock().await;
+            forwarder.set_cancel_sender(sender);
+        });
+    }
+
+    fn set_batch_sender(&self, sender: mpsc::Sender<TaskBatchEnvelope>) {
+        let message_forwarder = self.message_forwarder.clone();
+        tokio::spawn(async move {
+            let mut forwarder = message_forwarder.lock().await;
+            forwarder.set_batch_sender(sender);
+        });
+    }
+
+    fn set_admin_sender(&self, sender: mpsc::Sender<AdminMessage>) {
+        let message_forwarder = self.message_forwarder.clone();
+        tokio::spawn(async move {
+            let mut forwarder = message_forwarder.lock().await;
+            forwarder.set_admin_sender(sender);
+        });
+    }
+
+    fn set_task_journal(&self, journal: Arc<TaskJournal>) {
+        let message_forwarder = self.message_forwarder.clone();
+        tokio::spawn(async move {
+            let mut forwarder = message_forwarder.lock().await;
+            forwarder.set_task_journal(journal);
+        });
+    }
 }
 impl Drop for MqttClient {
     fn drop(&mut self) {
@@ -948,11 +1503,16 @@ mod tests {
     use super::*;
     use tokio::time::Duration;
 
+    /// State channel of a client that never connected
+    fn test_state_events() -> watch::Sender<ConnectionState> {
+        watch::channel(ConnectionState::Disconnected("Not connected".to_string())).0
+    }
+
     #[test]
     fn test_setup_connection_channels() {
         // Act: Create channels using pure function
         let ((state_tx, state_rx), (shutdown_tx, shutdown_rx)) =
-            MqttClient::setup_connection_channels();
+            MqttClient::setup_connection_channels(&test_state_events());
 
         // Assert: Verify initial states
         assert_eq!(*state_rx.borrow(), ConnectionState::Connecting);
@@ -966,10 +1526,107 @@ mod tests {
         assert!(*shutdown_rx.borrow());
     }
 
+    /// Deliver `payload` on `topic`, signed with `signing_key` if any, to an
+    /// agent verifying with `signer`
+    async fn deliver_signed(
+        forwarder: MessageForwarder,
+        topic: &str,
+        payload: &[u8],
+        signer: &MessageSigner,
+        signing_key: Option<&str>,
+    ) {
+        let (client, _event_loop) =
+            AsyncClient::new(rumqttc::v5::MqttOptions::new("test", "localhost", 1883), 10);
+        let signature = signing_key.map(|key| MessageSigner::new(key).sign(payload));
+
+        MqttClient::handle_message_received(
+            &Arc::new(Mutex::new(forwarder)),
+            &Arc::new(Mutex::new(client)),
+            "agent",
+            topic,
+            payload,
+            false,
+            signature.as_deref(),
+            None,
+            Some(signer),
+            None,
+        )
+        .await;
+    }
+
+    /// Deliver a cancel request; returns the request if it was forwarded
+    async fn receive_cancel(
+        signer: &MessageSigner,
+        signing_key: Option<&str>,
+    ) -> Option<CancelMessage> {
+        let (cancel_sender, mut cancel_receiver) = mpsc::channel(1);
+        let mut forwarder = MessageForwarder::new();
+        forwarder.set_cancel_sender(cancel_sender);
+        let payload = serde_json::to_vec(&CancelMessage {
+            task_id: uuid::Uuid::new_v4(),
+            conversation_id: "conversation".to_string(),
+            reason: None,
+        })
+        .unwrap();
+
+        deliver_signed(
+            forwarder,
+            "/control/agents/agent/cancel",
+            &payload,
+            signer,
+            signing_key,
+        )
+        .await;
+        cancel_receiver.try_recv().ok()
+    }
+
+    /// Deliver a resume request; returns the request if it was forwarded
+    async fn receive_admin(
+        signer: &MessageSigner,
+        signing_key: Option<&str>,
+    ) -> Option<AdminMessage> {
+        let (admin_sender, mut admin_receiver) = mpsc::channel(1);
+        let mut forwarder = MessageForwarder::new();
+        forwarder.set_admin_sender(admin_sender);
+        let payload = serde_json::to_vec(&AdminMessage::ResumeAgent).unwrap();
+
+        deliver_signed(
+            forwarder,
+            "/control/agents/agent/admin",
+            &payload,
+            signer,
+            signing_key,
+        )
+        .await;
+        admin_receiver.try_recv().ok()
+    }
+
+    #[tokio::test]
+    async fn test_cancel_requests_are_verified() {
+        let signer = MessageSigner::new("key");
+
+        assert!(receive_cancel(&signer, None).await.is_none());
+        assert!(receive_cancel(&signer, Some("other-key")).await.is_none());
+        assert!(receive_cancel(&signer, Some("key")).await.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_admin_requests_are_verified() {
+        let signer = MessageSigner::new("key");
+
+        assert!(receive_admin(&signer, None).await.is_none());
+        assert!(receive_admin(&signer, Some("other-key")).await.is_none());
+        assert_eq!(
+            receive_admin(&signer, Some("key")).await,
+            Some(AdminMessage::ResumeAgent)
+        );
+    }
+
     #[tokio::test]
     async fn test_wait_for_connection_confirmation_success() {
         // Arrange: Create channels and spawn task to signal connected
-        let ((state_tx, state_rx), (_, _)) = MqttClient::setup_connection_channels();
+        let ((state_tx, state_rx), (_, _)) =
+            MqttClient::setup_connection_channels(&test_state_events());
 
         // Spawn task to signal connection after delay
         let state_tx_clone = state_tx.clone();
@@ -991,7 +1648,8 @@ mod tests {
     async fn test_wait_for_connection_confirmation_timeout() {
         // Arrange: Create channels but don't signal connection
         // CRITICAL: Keep state_tx alive so channel doesn't close
-        let ((state_tx, state_rx), (_, _)) = MqttClient::setup_connection_channels();
+        let ((state_tx, state_rx), (_, _)) =
+            MqttClient::setup_connection_channels(&test_state_events());
 
         // Spawn task that keeps the channel open but never signals
         let _handle = tokio::spawn(async move {
@@ -1016,7 +1674,8 @@ mod tests {
     #[tokio::test]
     async fn test_wait_for_connection_confirmation_disconnected() {
         // Arrange: Create channels and signal disconnection
-        let ((state_tx, state_rx), (_, _)) = MqttClient::setup_connection_channels();
+        let ((state_tx, state_rx), (_, _)) =
+            MqttClient::setup_connection_channels(&test_state_events());
 
         tokio::spawn(async move {
             tokio::time::sleep(Duration::from_millis(10)).await;
@@ -1036,7 +1695,8 @@ mod tests {
     #[tokio::test]
     async fn test_interruptible_sleep_completes() {
         // Arrange: Create shutdown channel
-        let ((_, _), (_, shutdown_rx)) = MqttClient::setup_connection_channels();
+        let ((_, _), (_, shutdown_rx)) =
+            MqttClient::setup_connection_channels(&test_state_events());
 
         // Act: Sleep without interruption
         let result = MqttClient::interruptible_sleep(shutdown_rx, 10).await;
@@ -1048,7 +1708,8 @@ mod tests {
     #[tokio::test]
     async fn test_interruptible_sleep_interrupted() {
         // Arrange: Create shutdown channel and signal shutdown
-        let ((_, _), (shutdown_tx, shutdown_rx)) = MqttClient::setup_connection_channels();
+        let ((_, _), (shutdown_tx, shutdown_rx)) =
+            MqttClient::setup_connection_channels(&test_state_events());
 
         tokio::spawn(async move {
             tokio::time::sleep(Duration::from_millis(5)).await;
@@ -1070,6 +1731,7 @@ mod tests {
             username_env: None,
             password_env: None,
             heartbeat_interval_secs: 900,
+            payload_format: Default::default(),
         };
         let client = MqttClient::new("test-agent-state", config).await.unwrap();
 
@@ -1080,6 +1742,39 @@ mod tests {
         assert!(state.is_none(), "State should be None before connect()");
     }
 
+    #[tokio::test]
+    async fn test_connection_events_report_transitions_from_before_connect() {
+        let config = crate::config::MqttSection {
+            broker_url: "mqtt://localhost:1883".to_string(),
+            username_env: None,
+            password_env: None,
+            heartbeat_interval_secs: 900,
+            payload_format: Default::default(),
+        };
+        let client = MqttClient::new("test-agent-events", config).await.unwrap();
+        let mut events = client.connection_events();
+        assert_eq!(
+            *events.borrow_and_update(),
+            ConnectionState::Disconnected("Not connected".to_string())
+        );
+
+        // Connecting hands the supervisor the same channel
+        let ((state_tx, _), (_, _)) = MqttClient::setup_connection_channels(&client.state_events);
+        events.changed().await.unwrap();
+        assert_eq!(*events.borrow_and_update(), ConnectionState::Connecting);
+
+        state_tx
+            .send(ConnectionState::PermanentlyDisconnected(
+                "Max reconnection attempts exceeded".to_string(),
+            ))
+            .unwrap();
+        events.changed().await.unwrap();
+        assert!(matches!(
+            *events.borrow(),
+            ConnectionState::PermanentlyDisconnected(_)
+        ));
+    }
+
     #[tokio::test]
     async fn test_is_permanently_disconnected_initial_state() {
         // Arrange: Create disconnected client
@@ -1088,6 +1783,7 @@ mod tests {
             username_env: None,
             password_env: None,
             heartbeat_interval_secs: 900,
+            payload_format: Default::default(),
         };
         let client = MqttClient::new("test-agent-perm", config).await.unwrap();
 
@@ -1109,6 +1805,7 @@ mod tests {
             username_env: None,
             password_env: None,
             heartbeat_interval_secs: 900,
+            payload_format: Default::default(),
         };
         let client = MqttClient::new("test-agent-health", config).await.unwrap();
 
@@ -1135,6 +1832,7 @@ mod tests {
             username_env: None,
             password_env: None,
             heartbeat_interval_secs: 900,
+            payload_format: Default::default(),
         };
         let client = MqttClient::new("test-agent-publish-fail", config)
             .await
@@ -1156,6 +1854,12 @@ mod tests {
             instruction: Some("test".to_string()),
             input: serde_json::json!({}),
             next: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
         };
 
         let error_msg = crate::protocol::ErrorMessage {
@@ -1163,7 +1867,11 @@ mod tests {
             error: crate::protocol::ErrorDetails {
                 code: crate::protocol::ErrorCode::InternalError,
                 message: "test error".to_string(),
+                retryable: false,
+                retry_after_ms: None,
             },
+            correlation_id: None,
+            parent_task_id: None,
         };
 
         // Act & Assert: All publish operations should fail
@@ -1172,7 +1880,10 @@ mod tests {
             "publish_status should fail without connection"
         );
         assert!(
-            client.publish_task("target-agent", &task).await.is_err(),
+            client
+                .publish_task("target-agent", &TaskEnvelopeWrapper::V1(task))
+                .await
+                .is_err(),
             "publish_task should fail without connection"
         );
         assert!(
@@ -1184,6 +1895,79 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_topics_to_restore_merges_without_duplicates() {
+        let tracked = vec![
+            "/control/agents/a/input".to_string(),
+            "/control/agents/a/cancel".to_string(),
+        ];
+        let extra = vec![
+            "/control/agents/+/status".to_string(),
+            "/control/agents/a/input".to_string(),
+            "/control/agents/+/status".to_string(),
+        ];
+
+        assert_eq!(
+            MqttClient::topics_to_restore(&tracked, &extra),
+            vec![
+                "/control/agents/a/input".to_string(),
+                "/control/agents/a/cancel".to_string(),
+                "/control/agents/+/status".to_string(),
+            ]
+        );
+        assert!(MqttClient::topics_to_restore(&[], &[]).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_unsubscribe_forgets_topic() {
+        let config = crate::config::MqttSection {
+            broker_url: "mqtt://localhost:1883".to_string(),
+            username_env: None,
+            password_env: None,
+            heartbeat_interval_secs: 900,
+            payload_format: Default::default(),
+        };
+        let client = MqttClient::new("test-agent-unsub", config).await.unwrap();
+        client.subscribed_topics.lock().await.extend([
+            "/control/agents/test-agent-unsub/input".to_string(),
+            "/control/agents/test-agent-unsub/cancel".to_string(),
+        ]);
+
+        // Never connected, so the UNSUBSCRIBE itself may fail; the topic is
+        // forgotten either way and will not come back on reconnect
+        let _ = client
+            .unsubscribe("/control/agents/test-agent-unsub/input")
+            .await;
+
+        assert_eq!(
+            client.subscribed_topics().await,
+            vec!["/control/agents/test-agent-unsub/cancel".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_unavailable_on_disconnect_withdraws_last_status_once() {
+        let available = AgentStatus {
+            agent_id: "agent".to_string(),
+            status: crate::protocol::AgentStatusType::Available,
+            timestamp: chrono::Utc::now(),
+            capabilities: Some(vec!["research".to_string()]),
+            description: Some("Researches things".to_string()),
+        };
+
+        let unavailable = MqttClient::unavailable_on_disconnect(Some(&available)).unwrap();
+        assert_eq!(
+            unavailable.status,
+            crate::protocol::AgentStatusType::Unavailable
+        );
+        assert_eq!(unavailable.capabilities, available.capabilities);
+        assert_eq!(unavailable.description, available.description);
+
+        // Already withdrawn, or never announced: nothing to publish
+        assert!(MqttClient::unavailable_on_disconnect(Some(&unavailable)).is_none());
+        assert!(MqttClient::unavailable_on_disconnect(None).is_none());
+    }
+
     #[tokio::test]
     async fn test_disconnect_without_connection() {
         // Arrange: Create client that was never connected
@@ -1192,6 +1976,7 @@ mod tests {
             username_env: None,
             password_env: None,
             heartbeat_interval_secs: 900,
+            payload_format: Default::default(),
         };
         let mut client = MqttClient::new("test-agent-disc", config).await.unwrap();
 
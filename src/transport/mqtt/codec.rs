@@ -0,0 +1,217 @@
+//! Pure payload encoding and decoding for the configured wire format
+//!
+//! Messages are first converted to a JSON value and then encoded, so every
+//! format carries the same logical document (UUIDs and timestamps stay strings)
+//! and schema validation works identically regardless of encoding. The format
+//! is announced with the MQTT v5 content type property; payloads without one
+//! are sniffed from their first byte so mixed-format fleets interoperate.
+
+use crate::config::PayloadFormat;
+use serde::Serialize;
+use serde_json::Value;
+
+/// Content type for JSON payloads
+pub const CONTENT_TYPE_JSON: &str = "application/json";
+/// Content type for CBOR payloads
+pub const CONTENT_TYPE_CBOR: &str = "application/cbor";
+/// Content type for MessagePack payloads
+pub const CONTENT_TYPE_MSGPACK: &str = "application/msgpack";
+
+/// Pure payload codec
+pub struct PayloadCodec;
+
+impl PayloadCodec {
+    /// MQTT v5 content type announced for a format (pure function)
+    pub fn content_type(format: PayloadFormat) -> &'static str {
+        match format {
+            PayloadFormat::Json => CONTENT_TYPE_JSON,
+            PayloadFormat::Cbor => CONTENT_TYPE_CBOR,
+            PayloadFormat::Msgpack => CONTENT_TYPE_MSGPACK,
+        }
+    }
+
+    /// Map a content type back to a format, if recognised (pure function)
+    pub fn format_for_content_type(content_type: &str) -> Option<PayloadFormat> {
+        match content_type {
+            CONTENT_TYPE_JSON => Some(PayloadFormat::Json),
+            CONTENT_TYPE_CBOR => Some(PayloadFormat::Cbor),
+            CONTENT_TYPE_MSGPACK | "application/vnd.msgpack" => Some(PayloadFormat::Msgpack),
+            _ => None,
+        }
+    }
+
+    /// Guess the format of a payload from its leading byte (pure function)
+    ///
+    /// Protocol messages are always maps: JSON starts with `{`, CBOR maps use
+    /// major type 5 (`0xa0..=0xbb`, `0xbf`), MessagePack maps use `0x80..=0x8f`,
+    /// `0xde` or `0xdf`. Anything else is treated as JSON.
+    pub fn sniff(payload: &[u8]) -> PayloadFormat {
+        match payload.first() {
+            Some(0xa0..=0xbb) | Some(0xbf) => PayloadFormat::Cbor,
+            Some(0x80..=0x8f) | Some(0xde) | Some(0xdf) => PayloadFormat::Msgpack,
+            _ => PayloadFormat::Json,
+        }
+    }
+
+    /// Encode a message in the given format (pure function)
+    pub fn encode<T: Serialize>(message: &T, format: PayloadFormat) -> Result<Vec<u8>, String> {
+        match format {
+            PayloadFormat::Json => {
+                serde_json::to_vec(message).map_err(|e| format!("Serialization error: {e}"))
+            }
+            PayloadFormat::Cbor => {
+                let value = Self::to_value(message)?;
+                let mut buffer = Vec::new();
+                ciborium::into_writer(&value, &mut buffer)
+                    .map_err(|e| format!("CBOR serialization error: {e}"))?;
+                Ok(buffer)
+            }
+            PayloadFormat::Msgpack => {
+                let value = Self::to_value(message)?;
+                rmp_serde::to_vec_named(&value)
+                    .map_err(|e| format!("MessagePack serialization error: {e}"))
+            }
+        }
+    }
+
+    /// Decode a payload into a JSON value (pure function)
+    ///
+    /// Uses the announced content type when recognised, otherwise sniffs the payload.
+    pub fn decode_value(payload: &[u8], content_type: Option<&str>) -> Result<Value, String> {
+        let format = content_type
+            .and_then(Self::format_for_content_type)
+            .unwrap_or_else(|| Self::sniff(payload));
+
+        match format {
+            PayloadFormat::Json => serde_json::from_slice(payload).map_err(|e| e.to_string()),
+            PayloadFormat::Cbor => ciborium::from_reader(payload).map_err(|e| e.to_string()),
+            PayloadFormat::Msgpack => rmp_serde::from_slice(payload).map_err(|e| e.to_string()),
+        }
+    }
+
+    fn to_value<T: Serialize>(message: &T) -> Result<Value, String> {
+        serde_json::to_value(message).map_err(|e| format!("Serialization error: {e}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::{ResponseMessage, TaskEnvelope, TaskEnvelopeV2, TaskEnvelopeWrapper};
+    use serde_json::json;
+    use uuid::Uuid;
+
+    const FORMATS: [PayloadFormat; 3] = [
+        PayloadFormat::Json,
+        PayloadFormat::Cbor,
+        PayloadFormat::Msgpack,
+    ];
+
+    fn v1_task() -> TaskEnvelope {
+        TaskEnvelope {
+            task_id: Uuid::new_v4(),
+            conversation_id: "codec".to_string(),
+            topic: "/control/agents/codec/input".to_string(),
+            instruction: Some("Encode me".to_string()),
+            input: json!({"nested": {"list": [1, 2, 3], "flag": true}}),
+            next: None,
+            deadline: Some(chrono::Utc::now()),
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
+        }
+    }
+
+    fn v2_task() -> TaskEnvelopeV2 {
+        TaskEnvelopeV2 {
+            task_id: Uuid::new_v4(),
+            conversation_id: "codec".to_string(),
+            topic: "/control/agents/codec/input".to_string(),
+            instruction: None,
+            input: json!({"score": 0.5}),
+            next: None,
+            version: "2.0".to_string(),
+            context: None,
+            routing_trace: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
+        }
+    }
+
+    #[test]
+    fn test_round_trip_preserves_wrapper_version() {
+        for format in FORMATS {
+            let v1 = v1_task();
+            let payload = PayloadCodec::encode(&v1, format).unwrap();
+            assert_eq!(PayloadCodec::sniff(&payload), format);
+
+            let value = PayloadCodec::decode_value(&payload, None).unwrap();
+            let wrapper: TaskEnvelopeWrapper = serde_json::from_value(value).unwrap();
+            assert_eq!(wrapper, TaskEnvelopeWrapper::V1(v1));
+
+            let v2 = v2_task();
+            let payload = PayloadCodec::encode(&v2, format).unwrap();
+            let content_type = PayloadCodec::content_type(format);
+            let value = PayloadCodec::decode_value(&payload, Some(content_type)).unwrap();
+            let wrapper: TaskEnvelopeWrapper = serde_json::from_value(value).unwrap();
+            assert_eq!(wrapper, TaskEnvelopeWrapper::V2(v2));
+        }
+    }
+
+    #[test]
+    fn test_response_round_trip() {
+        let response = ResponseMessage {
+            task_id: Uuid::new_v4(),
+            response: "done".to_string(),
+            routing_trace: None,
+            correlation_id: None,
+            parent_task_id: None,
+            conversation_id: None,
+            agent_id: None,
+            completed_at: None,
+            oversized: None,
+            artifacts: Vec::new(),
+        };
+        for format in FORMATS {
+            let payload = PayloadCodec::encode(&response, format).unwrap();
+            let value = PayloadCodec::decode_value(&payload, None).unwrap();
+            let decoded: ResponseMessage = serde_json::from_value(value).unwrap();
+            assert_eq!(decoded.task_id, response.task_id);
+            assert_eq!(decoded.response, "done");
+        }
+    }
+
+    #[test]
+    fn test_binary_formats_are_smaller_than_json() {
+        let task = v1_task();
+        let json = PayloadCodec::encode(&task, PayloadFormat::Json).unwrap();
+        let cbor = PayloadCodec::encode(&task, PayloadFormat::Cbor).unwrap();
+        let msgpack = PayloadCodec::encode(&task, PayloadFormat::Msgpack).unwrap();
+        assert!(cbor.len() < json.len());
+        assert!(msgpack.len() < json.len());
+    }
+
+    #[test]
+    fn test_content_type_mapping() {
+        for format in FORMATS {
+            assert_eq!(
+                PayloadCodec::format_for_content_type(PayloadCodec::content_type(format)),
+                Some(format)
+            );
+        }
+        assert_eq!(PayloadCodec::format_for_content_type("text/plain"), None);
+    }
+
+    #[test]
+    fn test_content_type_overrides_sniffing() {
+        let payload = PayloadCodec::encode(&v1_task(), PayloadFormat::Cbor).unwrap();
+        assert!(PayloadCodec::decode_value(&payload, Some(CONTENT_TYPE_JSON)).is_err());
+        assert!(PayloadCodec::decode_value(&payload, Some("text/plain")).is_ok());
+    }
+}
@@ -5,6 +5,7 @@
 
 use crate::config::MqttSection;
 use crate::protocol::{canonicalize_topic, AgentStatus};
+use crate::secrets::SecretRef;
 use rumqttc::v5::mqttbytes::v5::LastWill;
 use rumqttc::v5::{mqttbytes::QoS, MqttOptions};
 use rumqttc::Transport as RumqttcTransport;
@@ -129,17 +130,16 @@ pub fn configure_mqtt_options(
         mqtt_options.set_transport(transport);
     }
 
-    // Set authentication from environment variables per RFC Section 9
-    // Use consistent environment variable handling pattern
-    if let Some(username_env) = &config.username_env {
-        if let Ok(username) = std::env::var(username_env) {
-            let password = config
-                .password_env
-                .as_ref()
-                .and_then(|env_name| std::env::var(env_name).ok())
-                .unwrap_or_default();
-            mqtt_options.set_credentials(&username, &password);
-        }
+    // Set authentication from the configured secret references per RFC Section 9
+    if let Some(Ok(username)) = config.username_env.as_ref().map(SecretRef::resolve) {
+        let password = config
+            .password_env
+            .as_ref()
+            .and_then(|password_ref| password_ref.resolve().ok());
+        mqtt_options.set_credentials(
+            username.expose(),
+            password.as_ref().map_or("", |password| password.expose()),
+        );
     }
 
     // RFC requires QoS 1 - set default keep alive
@@ -178,6 +178,11 @@ impl TopicBuilder {
         canonicalize_topic(&format!("/control/agents/{agent_id}/status"))
     }
 
+    /// Build agent manifest topic: `/control/agents/{agent_id}/manifest`
+    pub fn build_manifest_topic(agent_id: &str) -> String {
+        canonicalize_topic(&format!("/control/agents/{agent_id}/manifest"))
+    }
+
     /// Build target agent input topic: `/control/agents/{target}/input`
     pub fn build_target_input_topic(target_agent: &str) -> String {
         canonicalize_topic(&format!("/control/agents/{target_agent}/input"))
@@ -194,10 +199,50 @@ impl TopicBuilder {
         canonicalize_topic(&format!("/conversations/{conversation_id}/{agent_id}"))
     }
 
+    /// Build task acknowledgement topic: `/conversations/{conversation_id}/{agent_id}/ack`
+    pub fn build_ack_topic(conversation_id: &str, agent_id: &str) -> String {
+        canonicalize_topic(&format!("/conversations/{conversation_id}/{agent_id}/ack"))
+    }
+
+    /// Build conversation progress topic: `/conversations/{conversation_id}/progress`
+    pub fn build_conversation_progress_topic(conversation_id: &str) -> String {
+        canonicalize_topic(&format!("/conversations/{conversation_id}/progress"))
+    }
+
     /// Build agent input topic: `/control/agents/{agent_id}/input`
     pub fn build_input_topic(agent_id: &str) -> String {
         canonicalize_topic(&format!("/control/agents/{agent_id}/input"))
     }
+
+    /// Build agent batch input topic: `/control/agents/{agent_id}/input/batch`
+    pub fn build_batch_topic(agent_id: &str) -> String {
+        canonicalize_topic(&format!("/control/agents/{agent_id}/input/batch"))
+    }
+
+    /// Build agent cancel topic: `/control/agents/{agent_id}/cancel`
+    pub fn build_cancel_topic(agent_id: &str) -> String {
+        canonicalize_topic(&format!("/control/agents/{agent_id}/cancel"))
+    }
+
+    /// Build agent admin topic: `/control/agents/{agent_id}/admin`
+    pub fn build_admin_topic(agent_id: &str) -> String {
+        canonicalize_topic(&format!("/control/agents/{agent_id}/admin"))
+    }
+
+    /// Build agent dead-letter topic: `/control/agents/{agent_id}/dlq`
+    pub fn build_dlq_topic(agent_id: &str) -> String {
+        canonicalize_topic(&format!("/control/agents/{agent_id}/dlq"))
+    }
+
+    /// Build routing dry-run topic: `/control/agents/{agent_id}/routing/explain`
+    pub fn build_routing_explain_topic(agent_id: &str) -> String {
+        canonicalize_topic(&format!("/control/agents/{agent_id}/routing/explain"))
+    }
+
+    /// Build agent telemetry topic: `/control/agents/{agent_id}/telemetry`
+    pub fn build_telemetry_topic(agent_id: &str) -> String {
+        canonicalize_topic(&format!("/control/agents/{agent_id}/telemetry"))
+    }
 }
 
 #[cfg(test)]
@@ -260,6 +305,38 @@ mod tests {
             TopicBuilder::build_error_topic("conv-123", "my-agent"),
             "/conversations/conv-123/my-agent"
         );
+        assert_eq!(
+            TopicBuilder::build_ack_topic("conv-123", "my-agent"),
+            "/conversations/conv-123/my-agent/ack"
+        );
+        assert_eq!(
+            TopicBuilder::build_manifest_topic("my-agent"),
+            "/control/agents/my-agent/manifest"
+        );
+        assert_eq!(
+            TopicBuilder::build_batch_topic("my-agent"),
+            "/control/agents/my-agent/input/batch"
+        );
+        assert_eq!(
+            TopicBuilder::build_cancel_topic("my-agent"),
+            "/control/agents/my-agent/cancel"
+        );
+        assert_eq!(
+            TopicBuilder::build_admin_topic("my-agent"),
+            "/control/agents/my-agent/admin"
+        );
+        assert_eq!(
+            TopicBuilder::build_dlq_topic("my-agent"),
+            "/control/agents/my-agent/dlq"
+        );
+        assert_eq!(
+            TopicBuilder::build_telemetry_topic("my-agent"),
+            "/control/agents/my-agent/telemetry"
+        );
+        assert_eq!(
+            TopicBuilder::build_conversation_progress_topic("conv-123"),
+            "/conversations/conv-123/progress"
+        );
     }
 
     #[test]
@@ -295,6 +372,7 @@ mod tests {
             username_env: None,
             password_env: None,
             heartbeat_interval_secs: 900,
+            payload_format: Default::default(),
         }
     }
 
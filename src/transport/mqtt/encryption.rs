@@ -0,0 +1,257 @@
+//! ChaCha20-Poly1305 payload encryption and decryption
+//!
+//! Sensitive payloads are encrypted end to end so they stay confidential on a
+//! shared broker, independent of TLS. The serialized message is sealed into an
+//! [`EncryptedPayload`] wrapper that names the key it was sealed with; receivers
+//! look the key up by id, so keys can be rotated by adding the new key to every
+//! agent before switching the encryption key over.
+
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use thiserror::Error;
+
+/// Algorithm identifier carried in [`EncryptedPayload::alg`]
+pub const ALGORITHM_CHACHA20POLY1305: &str = "chacha20poly1305";
+
+/// Encryption key length in bytes
+pub const KEY_LENGTH: usize = 32;
+
+/// Encryption and decryption failures
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum EncryptionError {
+    #[error("Encryption key must be {KEY_LENGTH} bytes, got {0}")]
+    InvalidKeyLength(usize),
+    #[error("Unknown encryption key id: {0}")]
+    UnknownKey(String),
+    #[error("Unsupported encryption algorithm: {0}")]
+    UnsupportedAlgorithm(String),
+    #[error("Malformed encrypted payload: {0}")]
+    Malformed(String),
+    #[error("Payload decryption failed: ciphertext was tampered with or the key is wrong")]
+    DecryptionFailed,
+    #[error("Payload is encrypted but no decryption keys are configured")]
+    NotConfigured,
+}
+
+/// Wire wrapper for an encrypted message
+///
+/// `nonce` and `ciphertext` are hex encoded; the ciphertext includes the
+/// Poly1305 authentication tag.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EncryptedPayload {
+    pub alg: String,
+    pub nonce: String,
+    pub ciphertext: String,
+    pub key_id: String,
+}
+
+impl EncryptedPayload {
+    /// Recognise an encrypted wrapper in a decoded payload (pure function)
+    ///
+    /// Returns `None` for ordinary protocol messages.
+    pub fn detect(value: &Value) -> Option<Result<Self, EncryptionError>> {
+        let object = value.as_object()?;
+        if !["alg", "nonce", "ciphertext", "key_id"]
+            .iter()
+            .all(|field| object.contains_key(*field))
+        {
+            return None;
+        }
+        Some(
+            serde_json::from_value(value.clone())
+                .map_err(|e| EncryptionError::Malformed(e.to_string())),
+        )
+    }
+}
+
+/// Encrypts outgoing payloads and decrypts incoming ones
+#[derive(Clone)]
+pub struct PayloadEncryptor {
+    key_id: String,
+    keys: HashMap<String, [u8; KEY_LENGTH]>,
+}
+
+impl std::fmt::Debug for PayloadEncryptor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Never print key material
+        let mut key_ids: Vec<_> = self.keys.keys().collect();
+        key_ids.sort();
+        f.debug_struct("PayloadEncryptor")
+            .field("key_id", &self.key_id)
+            .field("key_ids", &key_ids)
+            .finish()
+    }
+}
+
+impl PayloadEncryptor {
+    /// Create an encryptor that seals payloads with the given key
+    pub fn new(key_id: impl Into<String>, key: &[u8]) -> Result<Self, EncryptionError> {
+        let key_id = key_id.into();
+        let mut keys = HashMap::new();
+        keys.insert(key_id.clone(), Self::check_key(key)?);
+        Ok(Self { key_id, keys })
+    }
+
+    /// Also decrypt payloads sealed with another key during rotation
+    pub fn with_accepted_key(
+        mut self,
+        key_id: impl Into<String>,
+        key: &[u8],
+    ) -> Result<Self, EncryptionError> {
+        self.keys.insert(key_id.into(), Self::check_key(key)?);
+        Ok(self)
+    }
+
+    /// Id of the key used for encryption
+    pub fn key_id(&self) -> &str {
+        &self.key_id
+    }
+
+    /// Seal a payload with the current key and a fresh random nonce
+    pub fn encrypt(&self, plaintext: &[u8]) -> Result<EncryptedPayload, EncryptionError> {
+        let cipher = ChaCha20Poly1305::new(Key::from_slice(&self.keys[&self.key_id]));
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = cipher
+            .encrypt(&nonce, plaintext)
+            .map_err(|_| EncryptionError::Malformed("encryption failed".to_string()))?;
+
+        Ok(EncryptedPayload {
+            alg: ALGORITHM_CHACHA20POLY1305.to_string(),
+            nonce: hex::encode(nonce),
+            ciphertext: hex::encode(ciphertext),
+            key_id: self.key_id.clone(),
+        })
+    }
+
+    /// Open a payload sealed with any known key
+    pub fn decrypt(&self, encrypted: &EncryptedPayload) -> Result<Vec<u8>, EncryptionError> {
+        if encrypted.alg != ALGORITHM_CHACHA20POLY1305 {
+            return Err(EncryptionError::UnsupportedAlgorithm(encrypted.alg.clone()));
+        }
+        let key = self
+            .keys
+            .get(&encrypted.key_id)
+            .ok_or_else(|| EncryptionError::UnknownKey(encrypted.key_id.clone()))?;
+
+        let nonce = hex::decode(&encrypted.nonce)
+            .map_err(|_| EncryptionError::Malformed("nonce is not valid hex".to_string()))?;
+        if nonce.len() != 12 {
+            return Err(EncryptionError::Malformed(format!(
+                "nonce must be 12 bytes, got {}",
+                nonce.len()
+            )));
+        }
+        let ciphertext = hex::decode(&encrypted.ciphertext)
+            .map_err(|_| EncryptionError::Malformed("ciphertext is not valid hex".to_string()))?;
+
+        ChaCha20Poly1305::new(Key::from_slice(key))
+            .decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+            .map_err(|_| EncryptionError::DecryptionFailed)
+    }
+
+    fn check_key(key: &[u8]) -> Result<[u8; KEY_LENGTH], EncryptionError> {
+        key.try_into()
+            .map_err(|_| EncryptionError::InvalidKeyLength(key.len()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const PAYLOAD: &[u8] = br#"{"task_id":"1","input":{"ssn":"123-45-6789"}}"#;
+    const KEY: [u8; KEY_LENGTH] = [7; KEY_LENGTH];
+    const OLD_KEY: [u8; KEY_LENGTH] = [3; KEY_LENGTH];
+
+    #[test]
+    fn test_round_trip() {
+        let encryptor = PayloadEncryptor::new("k1", &KEY).unwrap();
+        let encrypted = encryptor.encrypt(PAYLOAD).unwrap();
+
+        assert_eq!(encrypted.alg, ALGORITHM_CHACHA20POLY1305);
+        assert_eq!(encrypted.key_id, "k1");
+        assert!(!encrypted.ciphertext.contains(&hex::encode(b"123-45-6789")));
+        assert_eq!(encryptor.decrypt(&encrypted).unwrap(), PAYLOAD);
+
+        // Fresh nonce per message
+        assert_ne!(encryptor.encrypt(PAYLOAD).unwrap().nonce, encrypted.nonce);
+    }
+
+    #[test]
+    fn test_tampered_ciphertext_is_rejected() {
+        let encryptor = PayloadEncryptor::new("k1", &KEY).unwrap();
+        let mut encrypted = encryptor.encrypt(PAYLOAD).unwrap();
+
+        let mut bytes = hex::decode(&encrypted.ciphertext).unwrap();
+        bytes[0] ^= 0x01;
+        encrypted.ciphertext = hex::encode(bytes);
+        assert_eq!(
+            encryptor.decrypt(&encrypted),
+            Err(EncryptionError::DecryptionFailed)
+        );
+
+        encrypted.ciphertext = "not-hex".to_string();
+        assert!(matches!(
+            encryptor.decrypt(&encrypted),
+            Err(EncryptionError::Malformed(_))
+        ));
+    }
+
+    #[test]
+    fn test_key_rotation_and_unknown_keys() {
+        let old = PayloadEncryptor::new("old", &OLD_KEY).unwrap();
+        let sealed_with_old = old.encrypt(PAYLOAD).unwrap();
+
+        // New key encrypts, old key still decrypts
+        let rotated = PayloadEncryptor::new("new", &KEY)
+            .unwrap()
+            .with_accepted_key("old", &OLD_KEY)
+            .unwrap();
+        assert_eq!(rotated.decrypt(&sealed_with_old).unwrap(), PAYLOAD);
+        assert_eq!(rotated.encrypt(PAYLOAD).unwrap().key_id, "new");
+
+        // Once the old key is retired its payloads are rejected by id
+        let retired = PayloadEncryptor::new("new", &KEY).unwrap();
+        assert_eq!(
+            retired.decrypt(&sealed_with_old),
+            Err(EncryptionError::UnknownKey("old".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_invalid_key_and_algorithm() {
+        assert_eq!(
+            PayloadEncryptor::new("short", b"too-short").unwrap_err(),
+            EncryptionError::InvalidKeyLength(9)
+        );
+
+        let encryptor = PayloadEncryptor::new("k1", &KEY).unwrap();
+        let mut encrypted = encryptor.encrypt(PAYLOAD).unwrap();
+        encrypted.alg = "aes-256-gcm".to_string();
+        assert_eq!(
+            encryptor.decrypt(&encrypted),
+            Err(EncryptionError::UnsupportedAlgorithm(
+                "aes-256-gcm".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_detect_encrypted_payload() {
+        let encryptor = PayloadEncryptor::new("k1", &KEY).unwrap();
+        let value = serde_json::to_value(encryptor.encrypt(PAYLOAD).unwrap()).unwrap();
+        assert!(matches!(EncryptedPayload::detect(&value), Some(Ok(_))));
+
+        assert!(EncryptedPayload::detect(&json!({"task_id": "1", "input": {}})).is_none());
+        assert!(matches!(
+            EncryptedPayload::detect(
+                &json!({"alg": 1, "nonce": "", "ciphertext": "", "key_id": ""})
+            ),
+            Some(Err(EncryptionError::Malformed(_)))
+        ));
+    }
+}
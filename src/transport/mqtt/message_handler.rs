@@ -3,12 +3,23 @@
 //! This module contains pure functions for handling MQTT events,
 //! message parsing, and routing decisions.
 
+use super::codec::PayloadCodec;
+use super::encryption::{EncryptedPayload, EncryptionError, PayloadEncryptor};
+use super::signing::{MessageSigner, SIGNATURE_PROPERTY};
+use crate::processing::TaskJournal;
 #[cfg(test)]
 use crate::protocol::TaskEnvelope;
-use crate::protocol::{AgentStatus, ErrorMessage, ResponseMessage, TaskEnvelopeWrapper};
+use crate::protocol::{
+    validate_envelope, AdminMessage, AgentStatus, CancelMessage, ErrorCode, ErrorDetails,
+    ErrorMessage, ResponseMessage, TaskBatchEnvelope, TaskEnvelopeWrapper,
+};
+use crate::transport::{topic_matches, TopicMessage};
+use rumqttc::v5::mqttbytes::v5::PublishProperties;
 use rumqttc::v5::{mqttbytes::QoS, Event};
+use std::sync::Arc;
 use tokio::sync::mpsc;
-use tracing::{debug, info, warn};
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
 
 /// Pure message routing decisions based on MQTT events
 pub struct MessageHandler;
@@ -16,11 +27,178 @@ pub struct MessageHandler;
 impl MessageHandler {
     /// Extract task envelope from MQTT publish message (pure function)
     /// Supports both v1.0 and v2.0 TaskEnvelope formats via auto-detection
+    /// The payload is validated against the protocol JSON Schema before deserialization
+    /// Encrypted payloads are detected and rejected, since no key is available here
     pub fn parse_task_envelope(payload: &[u8]) -> Result<TaskEnvelopeWrapper, String> {
-        serde_json::from_slice::<TaskEnvelopeWrapper>(payload)
+        Self::parse_task_envelope_with_content_type(payload, None)
+    }
+
+    /// Extract task envelope encoded in JSON, CBOR, or MessagePack (pure function)
+    ///
+    /// The MQTT v5 content type selects the decoder; without one the format is sniffed.
+    pub fn parse_task_envelope_with_content_type(
+        payload: &[u8],
+        content_type: Option<&str>,
+    ) -> Result<TaskEnvelopeWrapper, String> {
+        Self::parse_task_envelope_with_encryption(payload, content_type, None)
+    }
+
+    /// Extract task envelope, decrypting it first if it was sent encrypted (pure function)
+    pub fn parse_task_envelope_with_encryption(
+        payload: &[u8],
+        content_type: Option<&str>,
+        encryptor: Option<&PayloadEncryptor>,
+    ) -> Result<TaskEnvelopeWrapper, String> {
+        let value = Self::decode_payload(payload, content_type, encryptor)
+            .map_err(|e| format!("Failed to parse TaskEnvelope: {e}"))?;
+        validate_envelope(&value).map_err(|e| format!("Invalid TaskEnvelope: {e}"))?;
+        serde_json::from_value::<TaskEnvelopeWrapper>(value)
             .map_err(|e| format!("Failed to parse TaskEnvelope: {e}"))
     }
 
+    /// Decode a payload, unwrapping an [`EncryptedPayload`] when present (pure function)
+    ///
+    /// The encrypted wrapper uses the announced content type; the sealed message
+    /// inside it is sniffed, so both layers may use any supported wire format.
+    pub fn decode_payload(
+        payload: &[u8],
+        content_type: Option<&str>,
+        encryptor: Option<&PayloadEncryptor>,
+    ) -> Result<serde_json::Value, String> {
+        let value = PayloadCodec::decode_value(payload, content_type)?;
+        let Some(encrypted) = EncryptedPayload::detect(&value) else {
+            return Ok(value);
+        };
+
+        let plaintext = encrypted
+            .and_then(|encrypted| {
+                encryptor
+                    .ok_or(EncryptionError::NotConfigured)?
+                    .decrypt(&encrypted)
+            })
+            .map_err(|e| e.to_string())?;
+        PayloadCodec::decode_value(&plaintext, None)
+    }
+
+    /// Seal an encoded payload into an [`EncryptedPayload`] wrapper (pure function)
+    ///
+    /// Returns the payload unchanged when encryption is disabled.
+    pub fn encrypt_payload(
+        payload: Vec<u8>,
+        format: crate::config::PayloadFormat,
+        encryptor: Option<&PayloadEncryptor>,
+    ) -> Result<Vec<u8>, String> {
+        let Some(encryptor) = encryptor else {
+            return Ok(payload);
+        };
+        let encrypted = encryptor.encrypt(&payload).map_err(|e| e.to_string())?;
+        PayloadCodec::encode(&encrypted, format)
+    }
+
+    /// Build an `invalid_input` error for a rejected task payload (pure function)
+    ///
+    /// Returns the conversation id and error message when the payload carries a
+    /// usable `task_id` and `conversation_id`; otherwise there is nowhere to reply.
+    pub fn build_rejected_task_error(
+        payload: &[u8],
+        content_type: Option<&str>,
+        reason: &str,
+    ) -> Option<(String, ErrorMessage)> {
+        let value = PayloadCodec::decode_value(payload, content_type).ok()?;
+        let task_id = value.get("task_id")?.as_str()?.parse::<Uuid>().ok()?;
+        let conversation_id = value.get("conversation_id")?.as_str()?;
+        if conversation_id.is_empty() {
+            return None;
+        }
+
+        let error = ErrorMessage {
+            error: ErrorDetails::new(ErrorCode::InvalidInput, reason),
+            task_id,
+            correlation_id: value
+                .get("correlation_id")
+                .and_then(serde_json::Value::as_str)
+                .map(str::to_string),
+            parent_task_id: value
+                .get("parent_task_id")
+                .and_then(serde_json::Value::as_str)
+                .and_then(|id| id.parse().ok()),
+        };
+        Some((conversation_id.to_string(), error))
+    }
+
+    /// Verify the payload signature, then extract the task envelope (pure function)
+    ///
+    /// When no signer is configured this is equivalent to [`Self::parse_task_envelope`].
+    pub fn parse_verified_task_envelope(
+        payload: &[u8],
+        signature: Option<&str>,
+        signer: Option<&MessageSigner>,
+    ) -> Result<TaskEnvelopeWrapper, String> {
+        if let Some(signer) = signer {
+            signer
+                .verify(payload, signature)
+                .map_err(|e| format!("Signature verification failed: {e}"))?;
+        }
+        Self::parse_task_envelope(payload)
+    }
+
+    /// Extract the payload signature from MQTT v5 user properties (pure function)
+    pub fn extract_signature(properties: Option<&PublishProperties>) -> Option<String> {
+        properties?
+            .user_properties
+            .iter()
+            .find(|(key, _)| key == SIGNATURE_PROPERTY)
+            .map(|(_, value)| value.clone())
+    }
+
+    /// Build publish properties carrying the payload signature, if signing is enabled (pure function)
+    pub fn build_signed_properties(
+        payload: &[u8],
+        signer: Option<&MessageSigner>,
+    ) -> PublishProperties {
+        match signer {
+            Some(signer) => PublishProperties {
+                user_properties: vec![(SIGNATURE_PROPERTY.to_string(), signer.sign(payload))],
+                ..Default::default()
+            },
+            None => PublishProperties::default(),
+        }
+    }
+
+    /// Format a rejected message into a dead-letter JSON payload (pure function)
+    pub fn format_dead_letter_payload(topic: &str, payload: &[u8], reason: &str) -> String {
+        serde_json::json!({
+            "topic": topic,
+            "reason": reason,
+            "payload": String::from_utf8_lossy(payload),
+        })
+        .to_string()
+    }
+
+    /// Extract cancel request from MQTT publish message (pure function)
+    pub fn parse_cancel_message(payload: &[u8]) -> Result<CancelMessage, String> {
+        serde_json::from_slice::<CancelMessage>(payload)
+            .map_err(|e| format!("Failed to parse CancelMessage: {e}"))
+    }
+
+    /// Extract pause/resume request from MQTT publish message (pure function)
+    pub fn parse_admin_message(payload: &[u8]) -> Result<AdminMessage, String> {
+        serde_json::from_slice::<AdminMessage>(payload)
+            .map_err(|e| format!("Failed to parse AdminMessage: {e}"))
+    }
+
+    /// Extract task batch encoded in JSON, CBOR, or MessagePack, decrypting if needed (pure function)
+    pub fn parse_batch_envelope(
+        payload: &[u8],
+        content_type: Option<&str>,
+        encryptor: Option<&PayloadEncryptor>,
+    ) -> Result<TaskBatchEnvelope, String> {
+        let value = Self::decode_payload(payload, content_type, encryptor)
+            .map_err(|e| format!("Failed to parse TaskBatchEnvelope: {e}"))?;
+        serde_json::from_value::<TaskBatchEnvelope>(value)
+            .map_err(|e| format!("Failed to parse TaskBatchEnvelope: {e}"))
+    }
+
     /// Determine if message should be processed based on topic and retain flag (pure function)
     pub fn should_process_message(topic: &str, retain: bool, expected_topic: &str) -> bool {
         // RFC requirement: Ignore retained messages to prevent reprocessing
@@ -50,6 +228,11 @@ impl MessageHandler {
                         topic: String::from_utf8_lossy(&publish.topic).to_string(),
                         payload: publish.payload.to_vec(),
                         retain: publish.retain,
+                        signature: Self::extract_signature(publish.properties.as_ref()),
+                        content_type: publish
+                            .properties
+                            .as_ref()
+                            .and_then(|p| p.content_type.clone()),
                     },
                     Packet::Disconnect(_) => EventRoute::Disconnected,
                     Packet::SubAck(suback) => EventRoute::SubscriptionConfirmed {
@@ -88,7 +271,12 @@ impl MessageHandler {
 
     /// Build subscription topics for agent (pure function)
     pub fn build_subscription_topics(agent_id: &str) -> Vec<String> {
-        vec![format!("/control/agents/{}/input", agent_id)]
+        vec![
+            format!("/control/agents/{}/input", agent_id),
+            format!("/control/agents/{}/input/batch", agent_id),
+            format!("/control/agents/{}/cancel", agent_id),
+            format!("/control/agents/{}/admin", agent_id),
+        ]
     }
 
     /// Validate subscription success from SubAck (pure function)
@@ -113,6 +301,10 @@ pub enum EventRoute {
         topic: String,
         payload: Vec<u8>,
         retain: bool,
+        /// Payload signature from MQTT v5 user properties, if present
+        signature: Option<String>,
+        /// MQTT v5 content type of the payload, if present
+        content_type: Option<String>,
     },
     /// MQTT broker disconnected
     Disconnected,
@@ -130,17 +322,92 @@ pub enum EventRoute {
 /// Message forwarding operations (impure I/O)
 pub struct MessageForwarder {
     task_sender: Option<mpsc::Sender<TaskEnvelopeWrapper>>,
+    cancel_sender: Option<mpsc::Sender<CancelMessage>>,
+    batch_sender: Option<mpsc::Sender<TaskBatchEnvelope>>,
+    admin_sender: Option<mpsc::Sender<AdminMessage>>,
+    task_journal: Option<Arc<TaskJournal>>,
+    /// Receivers of messages on topics subscribed through `subscribe_topic`
+    topic_subscribers: Vec<(String, mpsc::Sender<TopicMessage>)>,
 }
 
 impl MessageForwarder {
     pub fn new() -> Self {
-        Self { task_sender: None }
+        Self {
+            task_sender: None,
+            cancel_sender: None,
+            batch_sender: None,
+            admin_sender: None,
+            task_journal: None,
+            topic_subscribers: Vec::new(),
+        }
     }
 
     pub fn set_task_sender(&mut self, sender: mpsc::Sender<TaskEnvelopeWrapper>) {
         self.task_sender = Some(sender);
     }
 
+    pub fn set_cancel_sender(&mut self, sender: mpsc::Sender<CancelMessage>) {
+        self.cancel_sender = Some(sender);
+    }
+
+    pub fn set_batch_sender(&mut self, sender: mpsc::Sender<TaskBatchEnvelope>) {
+        self.batch_sender = Some(sender);
+    }
+
+    pub fn set_admin_sender(&mut self, sender: mpsc::Sender<AdminMessage>) {
+        self.admin_sender = Some(sender);
+    }
+
+    pub fn set_task_journal(&mut self, journal: Arc<TaskJournal>) {
+        self.task_journal = Some(journal);
+    }
+
+    pub fn add_topic_subscriber(&mut self, topic: String, sender: mpsc::Sender<TopicMessage>) {
+        self.topic_subscribers.push((topic, sender));
+    }
+
+    /// Drop every subscriber registered for exactly this `topic` filter
+    pub fn remove_topic_subscribers(&mut self, topic: &str) {
+        self.topic_subscribers
+            .retain(|(subscribed, _)| subscribed != topic);
+    }
+
+    /// Topics with at least one live subscriber, for re-subscription
+    pub fn subscribed_topics(&self) -> Vec<String> {
+        let mut topics: Vec<String> = self
+            .topic_subscribers
+            .iter()
+            .filter(|(_, sender)| !sender.is_closed())
+            .map(|(topic, _)| topic.clone())
+            .collect();
+        topics.dedup();
+        topics
+    }
+
+    /// Deliver a message to the subscribers whose filter matches its topic (impure I/O)
+    ///
+    /// Returns whether the topic is subscribed. Never waits: a subscriber
+    /// whose queue is full misses the message, and dropped receivers are
+    /// forgotten.
+    pub fn forward_topic_message(&mut self, topic: &str, payload: &[u8]) -> bool {
+        let mut subscribed = false;
+        self.topic_subscribers.retain(|(subscribed_topic, sender)| {
+            if !topic_matches(subscribed_topic, topic) {
+                return true;
+            }
+            subscribed = true;
+            match sender.try_send((topic.to_string(), payload.to_vec())) {
+                Ok(()) => true,
+                Err(mpsc::error::TrySendError::Full(_)) => {
+                    debug!("Topic subscriber for {} is full; message dropped", topic);
+                    true
+                }
+                Err(mpsc::error::TrySendError::Closed(_)) => false,
+            }
+        });
+        subscribed
+    }
+
     /// Forward parsed task envelope to pipeline (impure I/O)
     /// Accepts both v1.0 and v2.0 envelopes and forwards them as-is
     pub async fn forward_task(
@@ -151,6 +418,14 @@ impl MessageForwarder {
             let task_id = task_envelope_wrapper.task_id();
             info!("Forwarding task {} to pipeline", task_id);
 
+            // The broker already has its ack, so journal the task before it
+            // enters the in-memory channel; a journal failure only costs crash safety
+            if let Some(ref journal) = self.task_journal {
+                if let Err(e) = journal.append_async(&task_envelope_wrapper).await {
+                    error!("Failed to journal task {}: {}", task_id, e);
+                }
+            }
+
             sender
                 .send(task_envelope_wrapper)
                 .await
@@ -161,6 +436,61 @@ impl MessageForwarder {
             Err("No task sender configured".to_string())
         }
     }
+
+    /// Forward cancel request to pipeline (impure I/O)
+    pub async fn forward_cancel(&self, cancel: CancelMessage) -> Result<(), String> {
+        if let Some(ref sender) = self.cancel_sender {
+            info!(
+                "Forwarding cancel request for task {} to pipeline",
+                cancel.task_id
+            );
+
+            sender
+                .send(cancel)
+                .await
+                .map_err(|e| format!("Failed to forward cancel request to pipeline: {e}"))?;
+            Ok(())
+        } else {
+            warn!("Received cancel request but no cancel sender configured - message dropped");
+            Err("No cancel sender configured".to_string())
+        }
+    }
+
+    /// Forward task batch to pipeline (impure I/O)
+    pub async fn forward_batch(&self, batch: TaskBatchEnvelope) -> Result<(), String> {
+        if let Some(ref sender) = self.batch_sender {
+            info!(
+                "Forwarding batch {} with {} items to pipeline",
+                batch.batch_id,
+                batch.items.len()
+            );
+
+            sender
+                .send(batch)
+                .await
+                .map_err(|e| format!("Failed to forward batch to pipeline: {e}"))?;
+            Ok(())
+        } else {
+            warn!("Received task batch but no batch sender configured - message dropped");
+            Err("No batch sender configured".to_string())
+        }
+    }
+
+    /// Forward pause/resume request to pipeline (impure I/O)
+    pub async fn forward_admin(&self, admin: AdminMessage) -> Result<(), String> {
+        if let Some(ref sender) = self.admin_sender {
+            info!("Forwarding admin request {:?} to pipeline", admin);
+
+            sender
+                .send(admin)
+                .await
+                .map_err(|e| format!("Failed to forward admin request to pipeline: {e}"))?;
+            Ok(())
+        } else {
+            warn!("Received admin request but no admin sender configured - message dropped");
+            Err("No admin sender configured".to_string())
+        }
+    }
 }
 
 impl Default for MessageForwarder {
@@ -172,12 +502,11 @@ impl Default for MessageForwarder {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::protocol::{AgentStatusType, ErrorCode, ErrorDetails};
+    use crate::protocol::AgentStatusType;
     use bytes::Bytes;
     use chrono::Utc;
     use rumqttc::v5::mqttbytes::v5::Publish;
     use serde_json::Value;
-    use uuid::Uuid;
 
     #[test]
     fn test_parse_task_envelope() {
@@ -188,6 +517,12 @@ mod tests {
             instruction: Some("Test task".to_string()),
             input: serde_json::json!({"test": "data"}),
             next: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
         };
 
         let json = serde_json::to_vec(&task).unwrap();
@@ -206,6 +541,83 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_parse_schema_invalid_task_envelope() {
+        let task_id = Uuid::new_v4();
+        let payload = serde_json::to_vec(&serde_json::json!({
+            "task_id": task_id,
+            "conversation_id": "conv-1",
+            "topic": "no-leading-slash",
+            "input": {},
+            "version": "2.0",
+            "context": {"original_query": "q", "steps_completed": [], "iteration_count": -3}
+        }))
+        .unwrap();
+
+        let error = MessageHandler::parse_task_envelope(&payload).unwrap_err();
+        assert!(error.starts_with("Invalid TaskEnvelope"));
+        assert!(error.contains("'/topic'"));
+        assert!(error.contains("'/context/iteration_count'"));
+
+        let (conversation_id, message) =
+            MessageHandler::build_rejected_task_error(&payload, None, &error).unwrap();
+        assert_eq!(conversation_id, "conv-1");
+        assert_eq!(message.task_id, task_id);
+        assert_eq!(message.error.code, ErrorCode::InvalidInput);
+        assert!(message.error.message.contains("'/topic'"));
+    }
+
+    #[test]
+    fn test_parse_binary_task_envelope() {
+        use crate::config::PayloadFormat;
+
+        let task = TaskEnvelope {
+            task_id: Uuid::new_v4(),
+            conversation_id: "binary".to_string(),
+            topic: "/control/agents/target/input".to_string(),
+            instruction: None,
+            input: serde_json::json!({"k": "v"}),
+            next: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
+        };
+
+        for format in [PayloadFormat::Cbor, PayloadFormat::Msgpack] {
+            let payload = PayloadCodec::encode(&task, format).unwrap();
+
+            // Announced content type and sniffing both work
+            let content_type = PayloadCodec::content_type(format);
+            let parsed =
+                MessageHandler::parse_task_envelope_with_content_type(&payload, Some(content_type))
+                    .unwrap();
+            assert_eq!(parsed, TaskEnvelopeWrapper::V1(task.clone()));
+            assert_eq!(
+                MessageHandler::parse_task_envelope(&payload).unwrap(),
+                TaskEnvelopeWrapper::V1(task.clone())
+            );
+        }
+    }
+
+    #[test]
+    fn test_build_rejected_task_error_needs_reply_address() {
+        assert!(MessageHandler::build_rejected_task_error(b"not json", None, "bad").is_none());
+
+        let empty_conversation = serde_json::json!({
+            "task_id": Uuid::new_v4(),
+            "conversation_id": ""
+        });
+        assert!(MessageHandler::build_rejected_task_error(
+            &serde_json::to_vec(&empty_conversation).unwrap(),
+            None,
+            "bad"
+        )
+        .is_none());
+    }
+
     #[test]
     fn test_should_process_message() {
         let topic = "/control/agents/test/input";
@@ -264,11 +676,15 @@ mod tests {
             topic,
             payload,
             retain,
+            signature,
+            content_type,
         } = MessageHandler::route_mqtt_event(&publish)
         {
             assert_eq!(topic, "test/topic");
             assert_eq!(payload, b"test payload");
             assert!(!retain);
+            assert!(signature.is_none());
+            assert!(content_type.is_none());
         } else {
             panic!("Expected MessageReceived route");
         }
@@ -280,6 +696,14 @@ mod tests {
         let response = ResponseMessage {
             task_id: Uuid::new_v4(),
             response: serde_json::json!({"success": true}).to_string(),
+            routing_trace: None,
+            correlation_id: None,
+            parent_task_id: None,
+            conversation_id: None,
+            agent_id: None,
+            completed_at: None,
+            oversized: None,
+            artifacts: Vec::new(),
         };
         let payload = MessageHandler::format_response_payload(&response);
         assert!(payload.is_ok());
@@ -290,8 +714,12 @@ mod tests {
             error: ErrorDetails {
                 code: ErrorCode::InternalError,
                 message: "Test error".to_string(),
+                retryable: false,
+                retry_after_ms: None,
             },
             task_id: Uuid::new_v4(),
+            correlation_id: None,
+            parent_task_id: None,
         };
         let payload = MessageHandler::format_error_payload(&error);
         assert!(payload.is_ok());
@@ -324,7 +752,15 @@ mod tests {
     #[test]
     fn test_build_subscription_topics() {
         let topics = MessageHandler::build_subscription_topics("test-agent");
-        assert_eq!(topics, vec!["/control/agents/test-agent/input"]);
+        assert_eq!(
+            topics,
+            vec![
+                "/control/agents/test-agent/input",
+                "/control/agents/test-agent/input/batch",
+                "/control/agents/test-agent/cancel",
+                "/control/agents/test-agent/admin"
+            ]
+        );
     }
 
     #[test]
@@ -353,6 +789,12 @@ mod tests {
             instruction: None,
             input: Value::Null,
             next: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
         };
 
         // Should fail without sender
@@ -377,4 +819,283 @@ mod tests {
         let received_wrapper = received.unwrap();
         assert_eq!(received_wrapper.task_id(), task.task_id);
     }
+
+    #[test]
+    fn test_parse_cancel_message() {
+        let task_id = Uuid::new_v4();
+        let payload = serde_json::json!({
+            "task_id": task_id,
+            "conversation_id": "conv-1",
+            "reason": "user abort"
+        });
+
+        let parsed =
+            MessageHandler::parse_cancel_message(&serde_json::to_vec(&payload).unwrap()).unwrap();
+        assert_eq!(parsed.task_id, task_id);
+        assert_eq!(parsed.reason.as_deref(), Some("user abort"));
+
+        assert!(MessageHandler::parse_cancel_message(b"not json").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_message_forwarder_cancel() {
+        let mut forwarder = MessageForwarder::new();
+        let cancel = CancelMessage {
+            task_id: Uuid::new_v4(),
+            conversation_id: "test".to_string(),
+            reason: None,
+        };
+
+        // Should fail without sender
+        assert!(forwarder.forward_cancel(cancel.clone()).await.is_err());
+
+        let (tx, mut rx) = mpsc::channel(1);
+        forwarder.set_cancel_sender(tx);
+
+        assert!(forwarder.forward_cancel(cancel.clone()).await.is_ok());
+        assert_eq!(rx.recv().await, Some(cancel));
+    }
+
+    #[tokio::test]
+    async fn test_parse_and_forward_admin() {
+        let admin = MessageHandler::parse_admin_message(br#"{"type": "resume_agent"}"#).unwrap();
+        assert_eq!(admin, AdminMessage::ResumeAgent);
+        assert!(MessageHandler::parse_admin_message(br#"{"type": "reboot"}"#).is_err());
+
+        let mut forwarder = MessageForwarder::new();
+        assert!(forwarder.forward_admin(admin.clone()).await.is_err());
+
+        let (tx, mut rx) = mpsc::channel(1);
+        forwarder.set_admin_sender(tx);
+
+        assert!(forwarder.forward_admin(admin.clone()).await.is_ok());
+        assert_eq!(rx.recv().await, Some(admin));
+    }
+
+    #[tokio::test]
+    async fn test_parse_and_forward_batch() {
+        let batch = TaskBatchEnvelope {
+            batch_id: Uuid::new_v4(),
+            conversation_id: "test".to_string(),
+            shared_instruction: Some("Summarize".to_string()),
+            items: vec![serde_json::json!({"doc": 1}), serde_json::json!({"doc": 2})],
+            next: None,
+        };
+        let payload = PayloadCodec::encode(&batch, crate::config::PayloadFormat::Cbor).unwrap();
+        let parsed = MessageHandler::parse_batch_envelope(&payload, None, None).unwrap();
+        assert_eq!(parsed, batch);
+        assert!(MessageHandler::parse_batch_envelope(b"{}", None, None).is_err());
+
+        let mut forwarder = MessageForwarder::new();
+        assert!(forwarder.forward_batch(parsed.clone()).await.is_err());
+
+        let (tx, mut rx) = mpsc::channel(1);
+        forwarder.set_batch_sender(tx);
+        assert!(forwarder.forward_batch(parsed).await.is_ok());
+        assert_eq!(rx.recv().await, Some(batch));
+    }
+
+    #[tokio::test]
+    async fn test_message_forwarder_topic_subscribers() {
+        let mut forwarder = MessageForwarder::new();
+        assert!(!forwarder.forward_topic_message("/conversations/c/progress", b"{}"));
+
+        let (tx, mut rx) = mpsc::channel(1);
+        forwarder.add_topic_subscriber("/conversations/c/progress".to_string(), tx);
+        assert_eq!(
+            forwarder.subscribed_topics(),
+            vec!["/conversations/c/progress".to_string()]
+        );
+
+        assert!(forwarder.forward_topic_message("/conversations/c/progress", b"1"));
+        // A full subscriber misses the message instead of blocking
+        assert!(forwarder.forward_topic_message("/conversations/c/progress", b"2"));
+        assert!(!forwarder.forward_topic_message("/conversations/other/progress", b"3"));
+        let (wildcard_tx, mut wildcard_rx) = mpsc::channel(1);
+        forwarder.add_topic_subscriber("/conversations/+/progress".to_string(), wildcard_tx);
+        assert!(forwarder.forward_topic_message("/conversations/other/progress", b"3"));
+        assert_eq!(
+            wildcard_rx.recv().await,
+            Some(("/conversations/other/progress".to_string(), b"3".to_vec()))
+        );
+        drop(wildcard_rx);
+        assert_eq!(
+            rx.recv().await,
+            Some(("/conversations/c/progress".to_string(), b"1".to_vec()))
+        );
+
+        drop(rx);
+        forwarder.forward_topic_message("/conversations/c/progress", b"4");
+        assert!(forwarder.subscribed_topics().is_empty());
+    }
+
+    #[test]
+    fn test_parse_encrypted_task_envelope() {
+        let task = TaskEnvelope {
+            task_id: Uuid::new_v4(),
+            conversation_id: "encrypted".to_string(),
+            topic: "/control/agents/target/input".to_string(),
+            instruction: Some("Check the patient record".to_string()),
+            input: serde_json::json!({"patient": "Jane Doe"}),
+            next: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
+        };
+        let encryptor = PayloadEncryptor::new("k1", &[9; 32]).unwrap();
+
+        for format in [
+            crate::config::PayloadFormat::Json,
+            crate::config::PayloadFormat::Cbor,
+        ] {
+            let plaintext = PayloadCodec::encode(&task, format).unwrap();
+            let payload =
+                MessageHandler::encrypt_payload(plaintext, format, Some(&encryptor)).unwrap();
+            assert!(!String::from_utf8_lossy(&payload).contains("Jane Doe"));
+
+            // Round trip with the key
+            let parsed = MessageHandler::parse_task_envelope_with_encryption(
+                &payload,
+                Some(PayloadCodec::content_type(format)),
+                Some(&encryptor),
+            )
+            .unwrap();
+            assert_eq!(parsed, TaskEnvelopeWrapper::V1(task.clone()));
+
+            // Without a key the payload is recognised but not parsed
+            let error = MessageHandler::parse_task_envelope(&payload).unwrap_err();
+            assert!(error.contains("no decryption keys"));
+        }
+
+        // Unknown key ids produce a clear error
+        let payload = MessageHandler::encrypt_payload(
+            serde_json::to_vec(&task).unwrap(),
+            crate::config::PayloadFormat::Json,
+            Some(&PayloadEncryptor::new("retired", &[1; 32]).unwrap()),
+        )
+        .unwrap();
+        let error =
+            MessageHandler::parse_task_envelope_with_encryption(&payload, None, Some(&encryptor))
+                .unwrap_err();
+        assert!(error.contains("Unknown encryption key id: retired"));
+
+        // Tampered ciphertext is rejected
+        let mut wrapper: EncryptedPayload = serde_json::from_slice(&payload).unwrap();
+        let mut ciphertext = hex::decode(&wrapper.ciphertext).unwrap();
+        ciphertext[0] ^= 0xff;
+        wrapper.ciphertext = hex::encode(ciphertext);
+        let rotated = encryptor.with_accepted_key("retired", &[1; 32]).unwrap();
+        let error = MessageHandler::parse_task_envelope_with_encryption(
+            &serde_json::to_vec(&wrapper).unwrap(),
+            None,
+            Some(&rotated),
+        )
+        .unwrap_err();
+        assert!(error.contains("decryption failed"));
+
+        // Plain payloads still parse when encryption is enabled
+        assert!(MessageHandler::parse_task_envelope_with_encryption(
+            &serde_json::to_vec(&task).unwrap(),
+            None,
+            Some(&rotated)
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn test_parse_verified_task_envelope() {
+        let task = TaskEnvelope {
+            task_id: Uuid::new_v4(),
+            conversation_id: "signed".to_string(),
+            topic: "/control/agents/target/input".to_string(),
+            instruction: None,
+            input: serde_json::json!({"amount": 10}),
+            next: None,
+            deadline: None,
+            correlation_id: None,
+            parent_task_id: None,
+            published_at: None,
+            traceparent: None,
+            sender_id: None,
+        };
+        let payload = serde_json::to_vec(&task).unwrap();
+        let signer = MessageSigner::new("current").with_accepted_key("previous");
+
+        // Valid signature
+        let properties = MessageHandler::build_signed_properties(&payload, Some(&signer));
+        let signature = MessageHandler::extract_signature(Some(&properties));
+        assert!(MessageHandler::parse_verified_task_envelope(
+            &payload,
+            signature.as_deref(),
+            Some(&signer)
+        )
+        .is_ok());
+
+        // Missing signature
+        assert!(
+            MessageHandler::parse_verified_task_envelope(&payload, None, Some(&signer)).is_err()
+        );
+
+        // Tampered payload
+        let tampered = String::from_utf8(payload.clone())
+            .unwrap()
+            .replace("10", "10000");
+        assert!(MessageHandler::parse_verified_task_envelope(
+            tampered.as_bytes(),
+            signature.as_deref(),
+            Some(&signer)
+        )
+        .is_err());
+
+        // Signed with a rotated-out key that is still accepted
+        let rotated = MessageSigner::new("previous").sign(&payload);
+        assert!(MessageHandler::parse_verified_task_envelope(
+            &payload,
+            Some(&rotated),
+            Some(&signer)
+        )
+        .is_ok());
+
+        // Verification disabled accepts unsigned messages
+        assert!(MessageHandler::parse_verified_task_envelope(&payload, None, None).is_ok());
+    }
+
+    #[test]
+    fn test_route_signed_publish() {
+        use rumqttc::v5::mqttbytes::v5::{Packet, Publish};
+
+        let signer = MessageSigner::new("key");
+        let publish = Event::Incoming(Packet::Publish(Publish {
+            dup: false,
+            qos: QoS::AtLeastOnce,
+            retain: false,
+            topic: Bytes::from("test/topic"),
+            pkid: 1,
+            payload: Bytes::from("{}"),
+            properties: Some(MessageHandler::build_signed_properties(
+                b"{}",
+                Some(&signer),
+            )),
+        }));
+
+        match MessageHandler::route_mqtt_event(&publish) {
+            EventRoute::MessageReceived { signature, .. } => {
+                assert_eq!(signature, Some(signer.sign(b"{}")));
+            }
+            other => panic!("Expected MessageReceived route, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_format_dead_letter_payload() {
+        let payload =
+            MessageHandler::format_dead_letter_payload("/control/agents/a/input", b"{}", "bad");
+        let value: Value = serde_json::from_str(&payload).unwrap();
+        assert_eq!(value["topic"], "/control/agents/a/input");
+        assert_eq!(value["reason"], "bad");
+        assert_eq!(value["payload"], "{}");
+    }
 }
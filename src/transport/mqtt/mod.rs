@@ -5,11 +5,14 @@
 //!
 //! # Architecture
 //!
-//! The module is split into four focused sub-modules:
+//! The module is split into focused sub-modules:
 //!
+//! - [`codec`] - Pure payload encoding for the configured wire format
 //! - [`connection`] - Pure connection state management and configuration
 //! - [`message_handler`] - Pure message routing and processing logic
 //! - [`health_monitor`] - Pure health monitoring and reconnection logic
+//! - [`signing`] - Pure HMAC payload signing and verification
+//! - [`encryption`] - Pure ChaCha20-Poly1305 payload encryption
 //! - [`client`] - Impure I/O operations and coordination
 //!
 //! # Usage
@@ -24,6 +27,7 @@
 //!     username_env: None,
 //!     password_env: None,
 //!     heartbeat_interval_secs: 900,
+//!     payload_format: Default::default(),
 //! };
 //!
 //! let mut client = MqttClient::new("my-agent", config).await?;
@@ -34,17 +38,23 @@
 //! ```
 
 pub mod client;
+pub mod codec;
 pub mod connection;
+pub mod encryption;
 pub mod health_monitor;
 pub mod message_handler;
+pub mod signing;
 
 // Re-export public types for convenience
 pub use client::MqttClient;
+pub use codec::PayloadCodec;
 pub use connection::{ConnectionState, MqttError, ReconnectConfig, TopicBuilder};
+pub use encryption::{EncryptedPayload, EncryptionError, PayloadEncryptor};
 pub use health_monitor::{
     ConnectionEvent, ConnectionQuality, HealthMetrics, HealthMonitor, ReconnectionDecision,
 };
 pub use message_handler::{EventRoute, MessageHandler};
+pub use signing::{MessageSigner, SignatureError, SIGNATURE_PROPERTY};
 
 // Re-export for backwards compatibility
 pub use client::MqttClient as Client;
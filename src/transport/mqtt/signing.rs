@@ -0,0 +1,148 @@
+//! HMAC-SHA256 payload signing and verification
+//!
+//! Signatures are carried in an MQTT v5 user property so the JSON payload is
+//! left untouched. Outgoing messages are always signed with the current key;
+//! incoming messages are accepted if they verify against the current key or
+//! any of the previously accepted keys, which allows keys to be rotated
+//! without dropping in-flight tasks.
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use thiserror::Error;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// MQTT v5 user property carrying the hex-encoded payload signature
+pub const SIGNATURE_PROPERTY: &str = "x-2389-signature";
+
+/// Signature verification failures
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum SignatureError {
+    #[error("Message is not signed")]
+    Missing,
+    #[error("Signature is not valid hex")]
+    Malformed,
+    #[error("Signature does not match any accepted key")]
+    Invalid,
+}
+
+/// Signs outgoing payloads and verifies incoming ones
+#[derive(Clone)]
+pub struct MessageSigner {
+    signing_key: Vec<u8>,
+    accepted_keys: Vec<Vec<u8>>,
+}
+
+impl std::fmt::Debug for MessageSigner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Never print key material
+        f.debug_struct("MessageSigner")
+            .field("accepted_keys", &self.accepted_keys.len())
+            .finish()
+    }
+}
+
+impl MessageSigner {
+    /// Create a signer using a single key for signing and verification
+    pub fn new(signing_key: impl Into<Vec<u8>>) -> Self {
+        Self {
+            signing_key: signing_key.into(),
+            accepted_keys: Vec::new(),
+        }
+    }
+
+    /// Also accept signatures made with a previous key during rotation
+    pub fn with_accepted_key(mut self, key: impl Into<Vec<u8>>) -> Self {
+        self.accepted_keys.push(key.into());
+        self
+    }
+
+    /// Sign a payload with the current key, returning a hex-encoded signature
+    pub fn sign(&self, payload: &[u8]) -> String {
+        hex::encode(
+            Self::mac(&self.signing_key, payload)
+                .finalize()
+                .into_bytes(),
+        )
+    }
+
+    /// Verify a payload signature against the current and accepted keys
+    pub fn verify(&self, payload: &[u8], signature: Option<&str>) -> Result<(), SignatureError> {
+        let signature = signature.ok_or(SignatureError::Missing)?;
+        let signature = hex::decode(signature).map_err(|_| SignatureError::Malformed)?;
+
+        let verified = std::iter::once(&self.signing_key)
+            .chain(self.accepted_keys.iter())
+            .any(|key| Self::mac(key, payload).verify_slice(&signature).is_ok());
+
+        if verified {
+            Ok(())
+        } else {
+            Err(SignatureError::Invalid)
+        }
+    }
+
+    fn mac(key: &[u8], payload: &[u8]) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any length");
+        mac.update(payload);
+        mac
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAYLOAD: &[u8] = br#"{"task_id":"1","input":{}}"#;
+
+    #[test]
+    fn test_valid_signature_verifies() {
+        let signer = MessageSigner::new("secret");
+        let signature = signer.sign(PAYLOAD);
+
+        assert_eq!(signature.len(), 64);
+        assert!(signer.verify(PAYLOAD, Some(&signature)).is_ok());
+    }
+
+    #[test]
+    fn test_missing_signature_is_rejected() {
+        let signer = MessageSigner::new("secret");
+        assert_eq!(signer.verify(PAYLOAD, None), Err(SignatureError::Missing));
+    }
+
+    #[test]
+    fn test_tampered_payload_is_rejected() {
+        let signer = MessageSigner::new("secret");
+        let signature = signer.sign(PAYLOAD);
+
+        let tampered = br#"{"task_id":"1","input":{"evil":true}}"#;
+        assert_eq!(
+            signer.verify(tampered, Some(&signature)),
+            Err(SignatureError::Invalid)
+        );
+        assert_eq!(
+            signer.verify(PAYLOAD, Some("not-hex")),
+            Err(SignatureError::Malformed)
+        );
+    }
+
+    #[test]
+    fn test_rotated_key_is_accepted() {
+        let old_signer = MessageSigner::new("old-secret");
+        let old_signature = old_signer.sign(PAYLOAD);
+
+        // New key signs, old key still verifies
+        let rotated = MessageSigner::new("new-secret").with_accepted_key("old-secret");
+        assert!(rotated.verify(PAYLOAD, Some(&old_signature)).is_ok());
+        assert!(rotated
+            .verify(PAYLOAD, Some(&rotated.sign(PAYLOAD)))
+            .is_ok());
+
+        // Once the old key is retired its signatures are rejected
+        let retired = MessageSigner::new("new-secret");
+        assert_eq!(
+            retired.verify(PAYLOAD, Some(&old_signature)),
+            Err(SignatureError::Invalid)
+        );
+    }
+}
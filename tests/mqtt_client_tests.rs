@@ -11,6 +11,7 @@ use agent2389::config::MqttSection;
 use agent2389::protocol::{
     AgentStatus, AgentStatusType, ErrorCode, ErrorDetails, ErrorMessage, ResponseMessage,
 };
+use agent2389::secrets::SecretRef;
 use agent2389::transport::mqtt::{MqttClient, ReconnectConfig};
 use agent2389::transport::Transport;
 use chrono::Utc;
@@ -23,15 +24,17 @@ fn test_mqtt_config() -> MqttSection {
         username_env: None,
         password_env: None,
         heartbeat_interval_secs: 900,
+        payload_format: Default::default(),
     }
 }
 
 fn test_mqtt_config_with_auth() -> MqttSection {
     MqttSection {
         broker_url: "mqtt://localhost:1883".to_string(),
-        username_env: Some("MQTT_USER".to_string()),
-        password_env: Some("MQTT_PASS".to_string()),
+        username_env: Some(SecretRef::env("MQTT_USER")),
+        password_env: Some(SecretRef::env("MQTT_PASS")),
         heartbeat_interval_secs: 900,
+        payload_format: Default::default(),
     }
 }
 
@@ -41,6 +44,7 @@ fn test_mqtt_config_tls() -> MqttSection {
         username_env: None,
         password_env: None,
         heartbeat_interval_secs: 900,
+        payload_format: Default::default(),
     }
 }
 
@@ -77,8 +81,8 @@ async fn test_mqtt_client_creation_with_auth() {
     }
 
     let mut config = test_mqtt_config_with_auth();
-    config.username_env = Some("MQTT_USER_TEST".to_string());
-    config.password_env = Some("MQTT_PASS_TEST".to_string());
+    config.username_env = Some(SecretRef::env("MQTT_USER_TEST"));
+    config.password_env = Some(SecretRef::env("MQTT_PASS_TEST"));
 
     // Act: Create client with auth config
     let result = MqttClient::new("test-agent-auth", config).await;
@@ -175,14 +179,26 @@ async fn test_protocol_messages_serialize_correctly() {
     let response = ResponseMessage {
         task_id: Uuid::new_v4(),
         response: json!({"result": "success"}).to_string(),
+        routing_trace: None,
+        correlation_id: None,
+        parent_task_id: None,
+        conversation_id: None,
+        agent_id: None,
+        completed_at: None,
+        oversized: None,
+        artifacts: Vec::new(),
     };
 
     let error = ErrorMessage {
         error: ErrorDetails {
             code: ErrorCode::InternalError,
             message: "Test error".to_string(),
+            retry_after_ms: None,
+            retryable: false,
         },
         task_id: Uuid::new_v4(),
+        correlation_id: None,
+        parent_task_id: None,
     };
 
     let status = AgentStatus {
@@ -263,6 +279,14 @@ async fn test_publish_operations_require_connection() {
     let response = ResponseMessage {
         task_id: Uuid::new_v4(),
         response: json!({"result": "test"}).to_string(),
+        routing_trace: None,
+        correlation_id: None,
+        parent_task_id: None,
+        conversation_id: None,
+        agent_id: None,
+        completed_at: None,
+        oversized: None,
+        artifacts: Vec::new(),
     };
 
     let error = ErrorMessage {
@@ -270,7 +294,11 @@ async fn test_publish_operations_require_connection() {
         error: ErrorDetails {
             code: ErrorCode::InternalError,
             message: "test error".to_string(),
+            retry_after_ms: None,
+            retryable: false,
         },
+        correlation_id: None,
+        parent_task_id: None,
     };
 
     // All publish operations should fail without connection
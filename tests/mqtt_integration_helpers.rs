@@ -17,6 +17,7 @@ pub fn mqtt_config() -> MqttSection {
         username_env: None,
         password_env: None,
         heartbeat_interval_secs: 900,
+        payload_format: Default::default(),
     }
 }
 
@@ -27,6 +28,7 @@ pub fn mqtt_config_with_heartbeat(heartbeat_secs: u64) -> MqttSection {
         username_env: None,
         password_env: None,
         heartbeat_interval_secs: heartbeat_secs,
+        payload_format: Default::default(),
     }
 }
 
@@ -0,0 +1,126 @@
+//! Integration tests for hosting several agents in one process
+
+mod test_helpers;
+
+use agent2389::agent::host::{AgentHost, HostedAgentState};
+use agent2389::agent::lifecycle::AgentLifecycle;
+use agent2389::observability::health::HealthServer;
+use agent2389::protocol::messages::{NextTask, TaskEnvelopeWrapper};
+use agent2389::testing::mocks::{MockLlmProvider, MockTransport};
+use std::sync::Arc;
+use std::time::Duration;
+
+fn hosted_lifecycle(
+    agent_id: &str,
+    transport: MockTransport,
+    response: &str,
+) -> AgentLifecycle<MockTransport> {
+    let mut config = test_helpers::test_config();
+    config.agent.id = agent_id.to_string();
+    AgentLifecycle::new(
+        config,
+        transport,
+        Box::new(MockLlmProvider::single_response(response)),
+    )
+}
+
+#[tokio::test]
+async fn test_hosted_agents_pass_a_task_between_them() {
+    let writer_transport = MockTransport::new();
+    let writer_inbox = writer_transport.task_sender.clone();
+    let writer_outbox = writer_transport.published_task_envelopes.clone();
+    let editor_transport = MockTransport::new();
+    let editor_inbox = editor_transport.task_sender.clone();
+    let editor_responses = editor_transport.published_responses.clone();
+
+    let mut host = AgentHost::new(Arc::new(HealthServer::new("agent-host".to_string(), 0)));
+    host.add_agent(hosted_lifecycle("writer", writer_transport, "draft"));
+    host.add_agent(hosted_lifecycle("editor", editor_transport, "final"));
+    assert_eq!(host.start().await, 2);
+
+    // Stand in for the broker: deliver the writer's forwarded task to the editor
+    let relay = tokio::spawn(async move {
+        loop {
+            let forwarded = writer_outbox.lock().await.pop();
+            if let Some((topic, envelope)) = forwarded {
+                assert_eq!(topic, "/control/agents/editor/input");
+                let sender = editor_inbox.lock().await.clone().unwrap();
+                sender.send(envelope).await.unwrap();
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    });
+
+    let mut task = test_helpers::create_task("hosted", "Write a draft");
+    task.topic = "/control/agents/writer/input".to_string();
+    task.next = Some(Box::new(NextTask {
+        topic: "/control/agents/editor/input".to_string(),
+        instruction: Some("Edit the draft".to_string()),
+        input: None,
+        next: None,
+    }));
+    let sender = writer_inbox.lock().await.clone().unwrap();
+    sender.send(TaskEnvelopeWrapper::V1(task)).await.unwrap();
+
+    let response = tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            if let Some(response) = editor_responses.lock().await.first().cloned() {
+                return response;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("editor should answer the forwarded task");
+    relay.await.unwrap();
+
+    assert_eq!(response.0, "hosted");
+    assert_eq!(response.1.response, "final");
+
+    host.shutdown().await;
+    assert_eq!(host.running_count(), 0);
+}
+
+#[tokio::test]
+async fn test_failed_agent_does_not_stop_the_others() {
+    let mut host = AgentHost::new(Arc::new(HealthServer::new("agent-host".to_string(), 0)));
+    host.add_agent(hosted_lifecycle(
+        "broken",
+        MockTransport::with_failure(),
+        "x",
+    ));
+    host.add_agent(hosted_lifecycle("healthy", MockTransport::new(), "ok"));
+
+    assert_eq!(host.start().await, 1);
+
+    assert!(matches!(
+        host.state("broken"),
+        Some(HostedAgentState::Failed(_))
+    ));
+    assert_eq!(host.state("healthy"), Some(&HostedAgentState::Running));
+    assert_eq!(host.agent_ids(), vec!["broken", "healthy"]);
+    assert!(host.health_server().agent("healthy").is_some());
+    assert!(host.health_server().agent("missing").is_none());
+
+    host.shutdown().await;
+    assert_eq!(host.state("healthy"), Some(&HostedAgentState::Stopped));
+    assert!(matches!(
+        host.state("broken"),
+        Some(HostedAgentState::Failed(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_run_until_fails_when_no_agent_starts() {
+    let mut host = AgentHost::new(Arc::new(HealthServer::new("agent-host".to_string(), 0)));
+    host.add_agent(hosted_lifecycle(
+        "broken",
+        MockTransport::with_failure(),
+        "x",
+    ));
+
+    let result = host.run_until(std::future::ready(())).await;
+
+    assert!(result.is_err());
+}
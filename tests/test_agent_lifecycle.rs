@@ -13,6 +13,7 @@ mod test_helpers;
 
 use agent2389::agent::lifecycle::AgentLifecycle;
 use agent2389::observability::health::HealthServer;
+use agent2389::protocol::AgentManifest;
 use agent2389::testing::mocks::{MockLlmProvider, MockTransport};
 use std::sync::Arc;
 use std::time::Duration;
@@ -71,6 +72,53 @@ async fn test_lifecycle_start_success() {
     assert!(lifecycle.is_initialized());
 }
 
+#[tokio::test]
+async fn test_lifecycle_start_publishes_retained_manifest() {
+    let config = test_helpers::test_config();
+    let transport = MockTransport::new();
+    let retained = transport.published_retained.clone();
+    let mut lifecycle = AgentLifecycle::new(
+        config.clone(),
+        transport,
+        Box::new(MockLlmProvider::single_response("test response")),
+    );
+
+    // Refreshing is only possible once the agent is running
+    let tools = agent2389::tools::ToolSystem::new();
+    assert!(lifecycle.refresh_manifest(&tools).await.is_err());
+
+    lifecycle.start().await.expect("Start should succeed");
+
+    let manifests: Vec<_> = retained
+        .lock()
+        .await
+        .iter()
+        .filter(|(topic, _)| topic == "/control/agents/test-agent/manifest")
+        .map(|(_, payload)| serde_json::from_slice::<AgentManifest>(payload).unwrap())
+        .collect();
+    assert_eq!(manifests.len(), 1);
+    assert_eq!(manifests[0].agent_id, "test-agent");
+    assert_eq!(manifests[0].capabilities, config.agent.capabilities);
+    assert_eq!(manifests[0].model, config.llm.model);
+    assert_eq!(manifests[0].envelope_versions, vec!["1.0", "2.0"]);
+    assert!(manifests[0].tools.is_empty());
+
+    // A tool reload republishes the retained manifest
+    lifecycle
+        .refresh_manifest(&tools)
+        .await
+        .expect("Refresh should succeed after start");
+    let count = retained
+        .lock()
+        .await
+        .iter()
+        .filter(|(topic, _)| topic == "/control/agents/test-agent/manifest")
+        .count();
+    assert_eq!(count, 2);
+
+    lifecycle.shutdown().await.expect("Shutdown should succeed");
+}
+
 #[tokio::test]
 async fn test_lifecycle_start_without_init() {
     let mut lifecycle = create_test_lifecycle();
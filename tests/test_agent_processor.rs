@@ -46,6 +46,12 @@ fn create_test_task(instruction: &str) -> TaskEnvelope {
         instruction: Some(instruction.to_string()),
         input: json!({}),
         next: None,
+        deadline: None,
+        correlation_id: None,
+        parent_task_id: None,
+        published_at: None,
+        traceparent: None,
+        sender_id: None,
     }
 }
 
@@ -190,6 +196,8 @@ async fn test_processor_handles_llm_tool_calls() {
                     prompt_tokens: 10,
                     completion_tokens: 5,
                     total_tokens: 15,
+                    cache_read_tokens: None,
+                    cache_creation_tokens: None,
                 },
                 finish_reason: FinishReason::Stop,
                 tool_calls: Some(vec![ToolCall {
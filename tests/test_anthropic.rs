@@ -6,11 +6,13 @@
 //! - Token usage tracking
 //! - Message format conversions
 //! - Finish reason handling
+//! - Prompt cache breakpoints and cache token usage
 
 use agent2389::llm::provider::{
     CompletionRequest, FinishReason, LlmError, LlmProvider, Message, MessageRole,
 };
 use agent2389::llm::providers::anthropic::{AnthropicConfig, AnthropicProvider};
+use agent2389::tools::ToolDescription;
 use std::collections::HashMap;
 use std::time::Duration;
 use wiremock::matchers::{header, method, path};
@@ -22,6 +24,7 @@ fn test_config(base_url: &str) -> AnthropicConfig {
         base_url: base_url.to_string(),
         timeout: Duration::from_secs(5),
         version: "2023-06-01".to_string(),
+        prompt_caching: false,
     }
 }
 
@@ -35,6 +38,7 @@ fn test_request(model: &str) -> CompletionRequest {
         max_tokens: Some(100),
         temperature: Some(0.7),
         top_p: None,
+        seed: None,
         stop_sequences: None,
         tools: None,
         tool_choice: None,
@@ -418,6 +422,172 @@ async fn test_anthropic_provider_preserves_request_metadata() {
     );
 }
 
+/// Request with a system prompt and two tools
+fn request_with_tools(model: &str) -> CompletionRequest {
+    let mut request = test_request(model);
+    request.messages.insert(
+        0,
+        Message {
+            role: MessageRole::System,
+            content: "You are a research agent".to_string(),
+        },
+    );
+    request.tools = Some(
+        ["web_search", "http_request"]
+            .iter()
+            .map(|name| ToolDescription {
+                name: name.to_string(),
+                description: format!("The {name} tool"),
+                parameters: serde_json::json!({"type": "object", "properties": {}}),
+            })
+            .collect(),
+    );
+    request
+}
+
+/// Body of the only request the mock server received
+async fn sent_body(mock_server: &MockServer) -> serde_json::Value {
+    let requests = mock_server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 1);
+    serde_json::from_slice(&requests[0].body).unwrap()
+}
+
+#[tokio::test]
+async fn test_anthropic_prompt_caching_marks_system_prompt_and_tools() {
+    let mock_server = MockServer::start().await;
+
+    let response_body = serde_json::json!({
+        "content": [{"type": "text", "text": "Response"}],
+        "model": "claude-3-haiku-20240307",
+        "stop_reason": "end_turn",
+        "usage": {
+            "input_tokens": 12,
+            "output_tokens": 5,
+            "cache_creation_input_tokens": 0,
+            "cache_read_input_tokens": 6000
+        }
+    });
+
+    Mock::given(method("POST"))
+        .and(path("/messages"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(response_body))
+        .mount(&mock_server)
+        .await;
+
+    let config = AnthropicConfig {
+        prompt_caching: true,
+        ..test_config(&mock_server.uri())
+    };
+    let provider = AnthropicProvider::new(config).unwrap();
+
+    let response = provider
+        .complete(request_with_tools("claude-3-haiku-20240307"))
+        .await
+        .unwrap();
+
+    // The system prompt becomes a cacheable text block, and the last tool
+    // carries the breakpoint that caches every tool
+    let body = sent_body(&mock_server).await;
+    assert_eq!(
+        body["system"],
+        serde_json::json!([{
+            "type": "text",
+            "text": "You are a research agent",
+            "cache_control": {"type": "ephemeral"}
+        }])
+    );
+    assert_eq!(body["tools"][0]["name"], "web_search");
+    assert!(body["tools"][0].get("cache_control").is_none());
+    assert_eq!(
+        body["tools"][1]["cache_control"],
+        serde_json::json!({"type": "ephemeral"})
+    );
+    assert!(body["messages"][0].get("cache_control").is_none());
+
+    // Cached tokens are reported apart from the uncached prompt tokens
+    assert_eq!(response.usage.prompt_tokens, 12);
+    assert_eq!(response.usage.cache_read_tokens, Some(6000));
+    assert_eq!(response.usage.cache_creation_tokens, Some(0));
+}
+
+#[tokio::test]
+async fn test_anthropic_without_prompt_caching_sends_plain_system_prompt() {
+    let mock_server = MockServer::start().await;
+
+    let response_body = serde_json::json!({
+        "content": [{"type": "text", "text": "Response"}],
+        "model": "claude-3-haiku-20240307",
+        "stop_reason": "end_turn",
+        "usage": {"input_tokens": 10, "output_tokens": 5}
+    });
+
+    Mock::given(method("POST"))
+        .and(path("/messages"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(response_body))
+        .mount(&mock_server)
+        .await;
+
+    let config = test_config(&mock_server.uri());
+    let provider = AnthropicProvider::new(config).unwrap();
+
+    let response = provider
+        .complete(request_with_tools("claude-3-haiku-20240307"))
+        .await
+        .unwrap();
+
+    let body = sent_body(&mock_server).await;
+    assert_eq!(body["system"], "You are a research agent");
+    assert_eq!(body["tools"].as_array().unwrap().len(), 2);
+    assert!(!body.to_string().contains("cache_control"));
+    assert_eq!(response.usage.cache_read_tokens, None);
+    assert_eq!(response.usage.cache_creation_tokens, None);
+}
+
+#[tokio::test]
+async fn test_anthropic_provider_returns_tool_use_blocks_as_tool_calls() {
+    let mock_server = MockServer::start().await;
+
+    let response_body = serde_json::json!({
+        "content": [
+            {"type": "text", "text": "Searching first."},
+            {
+                "type": "tool_use",
+                "id": "toolu_01",
+                "name": "web_search",
+                "input": {"query": "herodotus"}
+            }
+        ],
+        "model": "claude-3-haiku-20240307",
+        "stop_reason": "tool_use",
+        "usage": {"input_tokens": 10, "output_tokens": 5}
+    });
+
+    Mock::given(method("POST"))
+        .and(path("/messages"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(response_body))
+        .mount(&mock_server)
+        .await;
+
+    let config = test_config(&mock_server.uri());
+    let provider = AnthropicProvider::new(config).unwrap();
+
+    let response = provider
+        .complete(request_with_tools("claude-3-haiku-20240307"))
+        .await
+        .unwrap();
+
+    assert_eq!(response.content.as_deref(), Some("Searching first."));
+    assert!(matches!(response.finish_reason, FinishReason::Stop));
+    let tool_calls = response.tool_calls.expect("tool calls");
+    assert_eq!(tool_calls.len(), 1);
+    assert_eq!(tool_calls[0].id, "toolu_01");
+    assert_eq!(tool_calls[0].name, "web_search");
+    assert_eq!(
+        tool_calls[0].arguments,
+        serde_json::json!({"query": "herodotus"})
+    );
+}
+
 #[test]
 fn test_anthropic_provider_creation_requires_api_key() {
     let config = AnthropicConfig::default();
@@ -0,0 +1,192 @@
+//! Integration tests for inbound task authorization
+//!
+//! Runs tasks through a processor configured with `[authorization]` rules,
+//! and verifies that denied tasks get an `unauthorized` ErrorMessage without
+//! the LLM being called, that the first matching rule decides before the
+//! default, that senders are matched on the envelope's `sender_id`, and that
+//! reloaded rules apply to the next task.
+
+mod test_helpers;
+
+use agent2389::agent::processor::AgentProcessor;
+use agent2389::config::{AgentConfig, AuthorizationSection};
+use agent2389::error::AgentError;
+use agent2389::observability::metrics::metrics;
+use agent2389::protocol::messages::{ErrorCode, TaskEnvelope, TaskEnvelopeWrapper};
+use agent2389::testing::mocks::{MockLlmProvider, MockTransport};
+use agent2389::tools::ToolSystem;
+use std::sync::Arc;
+use tokio::sync::watch;
+
+// ========== Test Helpers ==========
+
+const TOPIC: &str = "/control/agents/test-agent/input";
+
+fn config(authorization: &str) -> AgentConfig {
+    let mut config = test_helpers::test_config();
+    config.authorization = toml::from_str::<AuthorizationSection>(authorization).unwrap();
+    config
+}
+
+fn create_processor(
+    config: AgentConfig,
+) -> (
+    AgentProcessor<MockTransport>,
+    Arc<MockTransport>,
+    Arc<MockLlmProvider>,
+) {
+    let llm = Arc::new(MockLlmProvider::new(vec![
+        "First answer".to_string(),
+        "Second answer".to_string(),
+    ]));
+    let (processor, transport) =
+        test_helpers::create_processor(config, llm.clone(), ToolSystem::new());
+    (processor, transport, llm)
+}
+
+fn task_from(conversation_id: &str, sender_id: Option<&str>, instruction: &str) -> TaskEnvelope {
+    let mut task = test_helpers::create_task(conversation_id, instruction);
+    task.sender_id = sender_id.map(str::to_string);
+    task
+}
+
+async fn process(
+    processor: &AgentProcessor<MockTransport>,
+    task: TaskEnvelope,
+) -> Result<(), AgentError> {
+    processor
+        .process_task(TaskEnvelopeWrapper::V1(task), TOPIC, false)
+        .await
+        .map(|_| ())
+}
+
+// ========== Authorization Tests ==========
+
+#[tokio::test]
+async fn test_denied_task_publishes_unauthorized_error_without_calling_the_llm() {
+    let (processor, transport, llm) = create_processor(config(
+        r#"
+        [[rules]]
+        effect = "deny"
+        conversation_prefixes = ["public/"]
+        "#,
+    ));
+    let unauthorized_before = metrics().tasks_unauthorized();
+
+    let error = process(&processor, task_from("public/1", None, "Summarize"))
+        .await
+        .unwrap_err();
+
+    assert!(matches!(error, AgentError::Unauthorized { .. }));
+    assert!(llm.requests().is_empty());
+    assert!(transport.get_published_responses().await.is_empty());
+    let errors = transport.get_published_errors().await;
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].0, "public/1");
+    assert_eq!(errors[0].1.error.code, ErrorCode::Unauthorized);
+    assert_eq!(
+        errors[0].1.error.message,
+        "Task denied by authorization rule 1"
+    );
+    assert!(metrics().tasks_unauthorized() > unauthorized_before);
+}
+
+#[tokio::test]
+async fn test_first_matching_rule_takes_precedence() {
+    let (processor, transport, llm) = create_processor(config(
+        r#"
+        [[rules]]
+        effect = "allow"
+        sender_ids = ["planner"]
+
+        [[rules]]
+        effect = "deny"
+        instruction_pattern = "(?i)delete"
+        "#,
+    ));
+
+    process(
+        &processor,
+        task_from("conv", Some("planner"), "Delete drafts"),
+    )
+    .await
+    .unwrap();
+    let error = process(
+        &processor,
+        task_from("conv", Some("intern"), "Delete drafts"),
+    )
+    .await
+    .unwrap_err();
+
+    assert!(matches!(error, AgentError::Unauthorized { .. }));
+    assert_eq!(llm.requests().len(), 1);
+    assert_eq!(transport.get_published_responses().await.len(), 1);
+}
+
+#[tokio::test]
+async fn test_default_policy_applies_when_no_rule_matches() {
+    let authorization = r#"
+        default = "deny"
+
+        [[rules]]
+        effect = "allow"
+        conversation_prefixes = ["team-a/"]
+        sender_ids = ["planner"]
+        "#;
+    let (processor, transport, _llm) = create_processor(config(authorization));
+
+    process(&processor, task_from("team-a/1", Some("planner"), "Plan"))
+        .await
+        .unwrap();
+    // Every condition of a rule has to match: right sender, wrong conversation
+    let error = process(&processor, task_from("team-b/1", Some("planner"), "Plan"))
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Unauthorized: Task denied by the default authorization policy"
+    );
+    // Tasks without a sender never match a sender rule
+    assert!(process(&processor, task_from("team-a/2", None, "Plan"))
+        .await
+        .is_err());
+    assert_eq!(transport.get_published_errors().await.len(), 2);
+}
+
+#[tokio::test]
+async fn test_tasks_are_allowed_without_rules() {
+    let (processor, transport, _llm) = create_processor(test_helpers::test_config());
+
+    process(&processor, task_from("anything", None, "Summarize"))
+        .await
+        .unwrap();
+
+    assert_eq!(transport.get_published_responses().await.len(), 1);
+}
+
+#[tokio::test]
+async fn test_reloaded_rules_apply_to_the_next_task() {
+    let startup = test_helpers::test_config();
+    let (config_updates, config_receiver) = watch::channel(startup.clone());
+    let (processor, transport, _llm) = create_processor(startup.clone());
+    let processor = processor.with_config_updates(config_receiver);
+
+    process(&processor, task_from("public/1", None, "Summarize"))
+        .await
+        .unwrap();
+
+    let mut reloaded = startup;
+    reloaded.authorization = toml::from_str(
+        r#"
+        default = "deny"
+        "#,
+    )
+    .unwrap();
+    config_updates.send_replace(reloaded);
+    let error = process(&processor, task_from("public/2", None, "Summarize"))
+        .await
+        .unwrap_err();
+
+    assert!(matches!(error, AgentError::Unauthorized { .. }));
+    assert_eq!(transport.get_published_responses().await.len(), 1);
+}
@@ -19,6 +19,7 @@ async fn test_agent_retries_when_broker_unavailable_at_startup() {
         username_env: None,
         password_env: None,
         heartbeat_interval_secs: 900,
+        payload_format: Default::default(),
     };
 
     // Act: Create client (should succeed)
@@ -56,6 +57,7 @@ async fn test_agent_eventually_connects_when_broker_starts() {
         username_env: None,
         password_env: None,
         heartbeat_interval_secs: 900,
+        payload_format: Default::default(),
     };
 
     let mut client = MqttClient::new("eventual-connect-agent", config)
@@ -93,6 +95,7 @@ async fn test_reconnection_backoff_timing() {
         username_env: None,
         password_env: None,
         heartbeat_interval_secs: 900,
+        payload_format: Default::default(),
     };
 
     let mut client = MqttClient::new("backoff-timing-agent", config)
@@ -122,6 +125,7 @@ async fn test_agent_does_not_exit_on_broker_unavailable() {
         username_env: None,
         password_env: None,
         heartbeat_interval_secs: 900,
+        payload_format: Default::default(),
     };
 
     // Act: Create client and attempt connection
@@ -148,6 +152,7 @@ async fn test_unlimited_retry_configuration() {
         username_env: None,
         password_env: None,
         heartbeat_interval_secs: 900,
+        payload_format: Default::default(),
     };
 
     let client = MqttClient::new("unlimited-config-agent", config)
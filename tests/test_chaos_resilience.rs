@@ -70,6 +70,8 @@ impl LlmProvider for SlowLlmProvider {
                 prompt_tokens: 10,
                 completion_tokens: 5,
                 total_tokens: 15,
+                cache_read_tokens: None,
+                cache_creation_tokens: None,
             },
             finish_reason: FinishReason::Stop,
             tool_calls: None,
@@ -90,6 +92,12 @@ fn create_test_task(instruction: &str) -> TaskEnvelope {
         instruction: Some(instruction.to_string()),
         input: json!({}),
         next: None,
+        deadline: None,
+        correlation_id: None,
+        parent_task_id: None,
+        published_at: None,
+        traceparent: None,
+        sender_id: None,
     }
 }
 
@@ -207,6 +215,7 @@ async fn test_broker_unavailable_during_startup() {
         username_env: None,
         password_env: None,
         heartbeat_interval_secs: 900,
+        payload_format: Default::default(),
     };
 
     let mut client = MqttClient::new("chaos-startup-agent", config)
@@ -238,6 +247,7 @@ async fn test_rapid_connect_disconnect_cycles() {
         username_env: None,
         password_env: None,
         heartbeat_interval_secs: 900,
+        payload_format: Default::default(),
     };
 
     let mut client = MqttClient::new("rapid-cycle-agent", config)
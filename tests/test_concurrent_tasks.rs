@@ -0,0 +1,152 @@
+//! Integration tests for concurrent task processing
+//!
+//! Verifies that the pipeline processes up to `max_concurrent_tasks` tasks at
+//! once, that tasks in the same conversation never interleave, and that
+//! accepted tasks are drained when the task channel closes.
+
+mod test_helpers;
+
+use agent2389::agent::pipeline::AgentPipeline;
+use agent2389::protocol::messages::TaskEnvelopeWrapper;
+use agent2389::testing::mocks::MockLlmProvider;
+use agent2389::tools::ToolSystem;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+// ========== Test Helpers ==========
+
+/// A task whose instruction is `label`, so the LLM mock records it in its events
+fn create_task(conversation_id: &str, label: &str) -> TaskEnvelopeWrapper {
+    TaskEnvelopeWrapper::V1(test_helpers::create_task(conversation_id, label))
+}
+
+/// Run a pipeline over the given tasks until the channel is drained
+async fn run_pipeline(
+    llm: Arc<MockLlmProvider>,
+    max_concurrent_tasks: Option<usize>,
+    tasks: Vec<TaskEnvelopeWrapper>,
+) {
+    let (processor, _transport) =
+        test_helpers::create_processor(test_helpers::test_config(), llm, ToolSystem::new());
+    let (sender, receiver) = mpsc::channel(tasks.len());
+    let mut pipeline = AgentPipeline::new(processor, receiver, 16);
+    if let Some(max_concurrent_tasks) = max_concurrent_tasks {
+        pipeline.set_max_concurrent_tasks(max_concurrent_tasks);
+    }
+
+    for task in tasks {
+        sender.send(task).await.unwrap();
+    }
+    // Closing the channel while tasks are queued must still process all of them
+    drop(sender);
+
+    tokio::time::timeout(Duration::from_secs(10), pipeline.run())
+        .await
+        .expect("pipeline should drain accepted tasks and stop")
+        .expect("pipeline should not fail");
+}
+
+// ========== Concurrency Tests ==========
+
+#[tokio::test]
+async fn test_concurrent_tasks_are_bounded() {
+    let llm =
+        Arc::new(MockLlmProvider::single_response("Handled").with_delay(Duration::from_millis(50)));
+    let tasks = (0..6)
+        .map(|i| create_task(&format!("conversation-{i}"), &format!("task-{i}")))
+        .collect();
+
+    run_pipeline(llm.clone(), Some(2), tasks).await;
+
+    assert_eq!(llm.max_active(), 2);
+    let events = llm.events();
+    assert_eq!(events.iter().filter(|e| e.starts_with("end:")).count(), 6);
+}
+
+#[tokio::test]
+async fn test_tasks_are_sequential_by_default() {
+    let llm =
+        Arc::new(MockLlmProvider::single_response("Handled").with_delay(Duration::from_millis(50)));
+    let tasks = (0..3)
+        .map(|i| create_task(&format!("conversation-{i}"), &format!("task-{i}")))
+        .collect();
+
+    run_pipeline(llm.clone(), None, tasks).await;
+
+    assert_eq!(llm.max_active(), 1);
+    assert_eq!(
+        llm.events(),
+        vec![
+            "start:task-0",
+            "end:task-0",
+            "start:task-1",
+            "end:task-1",
+            "start:task-2",
+            "end:task-2",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_same_conversation_tasks_never_interleave() {
+    let llm =
+        Arc::new(MockLlmProvider::single_response("Handled").with_delay(Duration::from_millis(50)));
+    let tasks = vec![
+        create_task("conversation-a", "a-1"),
+        create_task("conversation-a", "a-2"),
+        create_task("conversation-b", "b-1"),
+        create_task("conversation-a", "a-3"),
+        create_task("conversation-b", "b-2"),
+    ];
+
+    run_pipeline(llm.clone(), Some(4), tasks).await;
+
+    // Different conversations overlap...
+    assert_eq!(llm.max_active(), 2);
+
+    // ...but each conversation runs its tasks one at a time, in arrival order
+    let events = llm.events();
+    let conversation_events = |prefix: &str| -> Vec<String> {
+        events
+            .iter()
+            .filter(|e| e.contains(prefix))
+            .cloned()
+            .collect()
+    };
+    assert_eq!(
+        conversation_events(":a-"),
+        vec![
+            "start:a-1",
+            "end:a-1",
+            "start:a-2",
+            "end:a-2",
+            "start:a-3",
+            "end:a-3"
+        ]
+    );
+    assert_eq!(
+        conversation_events(":b-"),
+        vec!["start:b-1", "end:b-1", "start:b-2", "end:b-2"]
+    );
+}
+
+#[tokio::test]
+async fn test_queued_tasks_do_not_block_other_conversations() {
+    let llm =
+        Arc::new(MockLlmProvider::single_response("Handled").with_delay(Duration::from_millis(50)));
+    let tasks = vec![
+        create_task("conversation-a", "a-1"),
+        create_task("conversation-a", "a-2"),
+        create_task("conversation-a", "a-3"),
+        create_task("conversation-b", "b-1"),
+    ];
+
+    run_pipeline(llm.clone(), Some(2), tasks).await;
+
+    // a-2 and a-3 wait behind a-1 without taking the second slot from b-1
+    let events = llm.events();
+    let position = |event: &str| events.iter().position(|e| e == event).unwrap();
+    assert!(position("start:b-1") < position("end:a-1"));
+    assert_eq!(llm.max_active(), 2);
+}
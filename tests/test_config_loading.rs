@@ -3,7 +3,8 @@
 //! Tests focus on BEHAVIOR of configuration loading, validation, and error handling.
 //! We test observable outcomes, not implementation details of TOML parsing.
 
-use agent2389::config::{AgentConfig, ConfigError};
+use agent2389::config::{AgentConfig, ConfigError, ConfigFormat, ToolConfig};
+use agent2389::secrets::SecretRef;
 use std::io::Write;
 use tempfile::NamedTempFile;
 
@@ -72,14 +73,44 @@ max_iterations = 10
 
     let config = AgentConfig::load_from_file(temp_file.path()).unwrap();
 
-    assert_eq!(config.mqtt.username_env, Some("MQTT_USER".to_string()));
-    assert_eq!(config.mqtt.password_env, Some("MQTT_PASS".to_string()));
+    assert_eq!(config.mqtt.username_env, Some(SecretRef::env("MQTT_USER")));
+    assert_eq!(config.mqtt.password_env, Some(SecretRef::env("MQTT_PASS")));
     assert_eq!(config.llm.temperature, Some(0.5));
     assert_eq!(config.llm.max_tokens, Some(2000));
     assert_eq!(config.budget.max_tool_calls, 20);
     assert_eq!(config.budget.max_iterations, 10);
 }
 
+#[test]
+fn test_config_accepts_prompt_caching_for_openai() {
+    // OpenAI caches prompts by itself; the flag loads and is left unused
+    let mut temp_file = NamedTempFile::new().unwrap();
+    writeln!(
+        temp_file,
+        r#"
+[agent]
+id = "test-agent"
+description = "A test agent"
+
+[mqtt]
+broker_url = "mqtt://localhost:1883"
+
+[llm]
+provider = "openai"
+model = "gpt-4o"
+api_key_env = "OPENAI_API_KEY"
+system_prompt = "You are helpful."
+prompt_caching = true
+"#
+    )
+    .unwrap();
+
+    let config = AgentConfig::load_from_file(temp_file.path()).unwrap();
+
+    assert!(config.llm.prompt_caching);
+    assert!(config.validate().is_ok());
+}
+
 #[test]
 fn test_config_applies_default_budget_when_not_specified() {
     let mut temp_file = NamedTempFile::new().unwrap();
@@ -395,10 +426,11 @@ system_prompt = "You are helpful."
 
     let result = AgentConfig::load_from_file(temp_file.path());
 
-    assert!(result.is_err());
     match result {
-        Err(ConfigError::InvalidAgentId(_)) => {}
-        _ => panic!("Expected InvalidAgentId error for invalid characters"),
+        Err(ConfigError::Validation(errors)) => {
+            assert!(errors.iter().any(|error| error.field == "agent.id"));
+        }
+        _ => panic!("Expected agent.id validation error for invalid characters"),
     }
 }
 
@@ -426,10 +458,11 @@ system_prompt = "You are helpful."
 
     let result = AgentConfig::load_from_file(temp_file.path());
 
-    assert!(result.is_err());
     match result {
-        Err(ConfigError::InvalidAgentId(_)) => {}
-        _ => panic!("Expected InvalidAgentId error for empty agent ID"),
+        Err(ConfigError::Validation(errors)) => {
+            assert!(errors.iter().any(|error| error.field == "agent.id"));
+        }
+        _ => panic!("Expected agent.id validation error for empty agent ID"),
     }
 }
 
@@ -556,7 +589,7 @@ system_prompt = "You are helpful."
 
     let config = AgentConfig::load_from_file(temp_file.path()).unwrap();
 
-    assert_eq!(config.get_mqtt_username(), Some("test_user".to_string()));
+    assert_eq!(config.get_mqtt_username().unwrap().expose(), "test_user");
 
     unsafe {
         std::env::remove_var("TEST_MQTT_USER");
@@ -592,7 +625,7 @@ system_prompt = "You are helpful."
 
     let config = AgentConfig::load_from_file(temp_file.path()).unwrap();
 
-    assert_eq!(config.get_mqtt_password(), Some("test_pass".to_string()));
+    assert_eq!(config.get_mqtt_password().unwrap().expose(), "test_pass");
 
     unsafe {
         std::env::remove_var("TEST_MQTT_PASS");
@@ -659,7 +692,7 @@ system_prompt = "You are helpful."
 
     let config = AgentConfig::load_from_file(temp_file.path()).unwrap();
 
-    assert_eq!(config.get_llm_api_key().unwrap(), "sk-test123");
+    assert_eq!(config.get_llm_api_key().unwrap().expose(), "sk-test123");
 
     unsafe {
         std::env::remove_var("TEST_API_KEY");
@@ -829,10 +862,8 @@ system_prompt = "You are helpful."
 }
 
 #[test]
-fn test_config_accepts_different_llm_providers() {
-    let providers = vec!["anthropic", "openai", "custom-provider"];
-
-    for provider in providers {
+fn test_config_accepts_supported_llm_providers() {
+    for provider in ["anthropic", "openai", "custom-provider"] {
         let mut temp_file = NamedTempFile::new().unwrap();
         writeln!(
             temp_file,
@@ -853,8 +884,15 @@ system_prompt = "You are helpful."
         )
         .unwrap();
 
-        let config = AgentConfig::load_from_file(temp_file.path()).unwrap();
-        assert_eq!(config.llm.provider, provider);
+        let result = AgentConfig::load_from_file(temp_file.path());
+        if provider == "custom-provider" {
+            let Err(ConfigError::Validation(errors)) = result else {
+                panic!("Expected llm.provider validation error");
+            };
+            assert_eq!(errors[0].field, "llm.provider");
+        } else {
+            assert_eq!(result.unwrap().llm.provider, provider);
+        }
     }
 }
 
@@ -890,3 +928,300 @@ Always be professional.
     assert!(config.llm.system_prompt.contains("clear and concise"));
     assert!(config.llm.system_prompt.contains("professional"));
 }
+
+fn fixture(name: &str) -> std::path::PathBuf {
+    std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/config")
+        .join(name)
+}
+
+#[test]
+fn test_toml_yaml_and_json_fixtures_load_identically() {
+    let from_toml = AgentConfig::load_from_file(&fixture("agent.toml")).unwrap();
+    let from_yaml = AgentConfig::load_from_file(&fixture("agent.yaml")).unwrap();
+    let from_json = AgentConfig::load_from_file(&fixture("agent.json")).unwrap();
+
+    assert_eq!(from_toml.agent.id, "fixture-agent");
+    assert_eq!(from_toml.tools.len(), 2);
+    assert_eq!(from_yaml, from_toml);
+    assert_eq!(from_json, from_toml);
+}
+
+#[test]
+fn test_config_format_is_detected_by_extension() {
+    for (name, format) in [
+        ("agent.toml", ConfigFormat::Toml),
+        ("agent.yaml", ConfigFormat::Yaml),
+        ("agent.YML", ConfigFormat::Yaml),
+        ("agent.json", ConfigFormat::Json),
+        ("agent", ConfigFormat::Toml),
+    ] {
+        assert_eq!(
+            ConfigFormat::from_path(std::path::Path::new(name)),
+            format,
+            "{name}"
+        );
+    }
+}
+
+#[test]
+fn test_config_round_trips_through_every_format() {
+    let config = AgentConfig::load_from_file(&fixture("agent.toml")).unwrap();
+
+    for format in [ConfigFormat::Toml, ConfigFormat::Yaml, ConfigFormat::Json] {
+        let rendered = format.render(&config).unwrap();
+        let parsed: AgentConfig = format.parse(&rendered).unwrap();
+        assert_eq!(parsed, config, "{format} round trip");
+    }
+}
+
+#[test]
+fn test_yaml_parse_errors_are_reported_as_yaml() {
+    let mut temp_file = tempfile::Builder::new().suffix(".yaml").tempfile().unwrap();
+    writeln!(temp_file, "agent: [unterminated").unwrap();
+
+    let result = AgentConfig::load_from_file(temp_file.path());
+
+    assert!(matches!(result, Err(ConfigError::YamlParse(_))));
+}
+
+#[test]
+fn test_example_configs_match_json_schema() {
+    let schema = serde_json::to_value(AgentConfig::json_schema()).unwrap();
+    let validator = jsonschema::validator_for(&schema).expect("config schema compiles");
+    let root = std::path::Path::new(env!("CARGO_MANIFEST_DIR"));
+
+    let mut checked = 0;
+    for dir in [
+        "config/dev-agents",
+        "examples/01-echo-agent",
+        "examples/v2_routing_workflow",
+        "docs/examples/complete_configs",
+        "tests/fixtures/config",
+    ] {
+        for entry in std::fs::read_dir(root.join(dir)).unwrap() {
+            let path = entry.unwrap().path();
+            let format = ConfigFormat::from_path(&path);
+            let is_config = matches!(
+                path.extension().and_then(|e| e.to_str()),
+                Some("toml" | "yaml" | "json")
+            );
+            if !is_config {
+                continue;
+            }
+            let content = std::fs::read_to_string(&path).unwrap();
+            let document: serde_json::Value = format.parse(&content).unwrap();
+            if let Err(errors) = validator.validate(&document) {
+                let errors: Vec<String> = errors
+                    .map(|e| format!("At '{}': {}", e.instance_path, e))
+                    .collect();
+                panic!("{} doesn't match the schema: {errors:#?}", path.display());
+            }
+            checked += 1;
+        }
+    }
+    assert!(checked >= 10, "only {checked} example configs found");
+}
+
+#[test]
+fn test_json_schema_rejects_unknown_provider_and_malformed_tool() {
+    let schema = serde_json::to_value(AgentConfig::json_schema()).unwrap();
+    let validator = jsonschema::validator_for(&schema).unwrap();
+    let config = |provider: &str, tool: serde_json::Value| {
+        serde_json::json!({
+            "agent": { "id": "schema-agent", "description": "Schema test" },
+            "mqtt": { "broker_url": "mqtt://localhost:1883" },
+            "llm": {
+                "provider": provider,
+                "model": "gpt-4o",
+                "api_key_env": "OPENAI_API_KEY",
+                "system_prompt": "You are helpful."
+            },
+            "tools": { "http_request": tool }
+        })
+    };
+
+    assert!(validator.is_valid(&config("openai", "builtin".into())));
+    assert!(validator.is_valid(&config(
+        "anthropic",
+        serde_json::json!({ "impl": "builtin", "config": { "timeout_secs": 5 } })
+    )));
+    assert!(!validator.is_valid(&config("mystery", "builtin".into())));
+    assert!(!validator.is_valid(&config("openai", serde_json::json!({ "config": {} }))));
+    assert!(!validator.is_valid(&config("openai", serde_json::json!(42))));
+}
+
+fn write_config(dir: &std::path::Path, name: &str, content: &str) -> std::path::PathBuf {
+    let path = dir.join(name);
+    std::fs::write(&path, content).unwrap();
+    path
+}
+
+const BASE_CONFIG: &str = r#"
+[agent]
+id = "base-agent"
+description = "Shared base"
+capabilities = ["research", "writing"]
+
+[mqtt]
+broker_url = "mqtt://localhost:1883"
+heartbeat_interval_secs = 60
+
+[llm]
+provider = "openai"
+model = "gpt-4o-mini"
+api_key_env = "OPENAI_API_KEY"
+system_prompt = "You are helpful."
+temperature = 0.2
+
+[tools]
+file_read = "builtin"
+
+[tools.http_request]
+impl = "builtin"
+config = { timeout_secs = 30, user_agent = "base" }
+"#;
+
+#[test]
+fn test_include_merges_nested_tables_and_replaces_lists() {
+    let dir = tempfile::tempdir().unwrap();
+    write_config(dir.path(), "base.toml", BASE_CONFIG);
+    let agent = write_config(
+        dir.path(),
+        "agent.toml",
+        r#"
+include = ["base.toml"]
+
+[agent]
+id = "staging-agent"
+capabilities = ["review"]
+
+[llm]
+model = "gpt-4o"
+
+[tools]
+web_search = "builtin"
+
+[tools.http_request.config]
+timeout_secs = 5
+"#,
+    );
+
+    let config = AgentConfig::load_from_file(&agent).unwrap();
+
+    assert_eq!(config.agent.id, "staging-agent");
+    assert_eq!(config.agent.description, "Shared base");
+    // Lists are replaced, not appended
+    assert_eq!(config.agent.capabilities, vec!["review"]);
+    assert_eq!(config.mqtt.heartbeat_interval_secs, 60);
+    assert_eq!(config.llm.model, "gpt-4o");
+    assert_eq!(config.llm.temperature, Some(0.2));
+
+    let mut tools: Vec<_> = config.tools.keys().cloned().collect();
+    tools.sort();
+    assert_eq!(tools, ["file_read", "http_request", "web_search"]);
+    let ToolConfig::Complex { config: http, .. } = &config.tools["http_request"] else {
+        panic!("http_request should keep its table form");
+    };
+    assert_eq!(http["timeout_secs"], 5);
+    assert_eq!(http["user_agent"], "base");
+}
+
+#[test]
+fn test_later_includes_win_and_relative_paths_follow_the_including_file() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir(dir.path().join("shared")).unwrap();
+    write_config(dir.path(), "shared/base.toml", BASE_CONFIG);
+    write_config(
+        dir.path(),
+        "shared/broker.yaml",
+        "mqtt:\n  broker_url: mqtts://broker.internal:8883\n",
+    );
+    write_config(
+        dir.path(),
+        "shared/all.toml",
+        "include = [\"base.toml\", \"broker.yaml\"]\n",
+    );
+    let agent = write_config(
+        dir.path(),
+        "agent.toml",
+        "include = [\"shared/all.toml\"]\n",
+    );
+
+    let config = AgentConfig::load_from_file(&agent).unwrap();
+
+    assert_eq!(config.agent.id, "base-agent");
+    assert_eq!(config.mqtt.broker_url, "mqtts://broker.internal:8883");
+    assert_eq!(config.mqtt.heartbeat_interval_secs, 60);
+}
+
+#[test]
+fn test_profile_overrides_file_and_includes() {
+    let dir = tempfile::tempdir().unwrap();
+    write_config(dir.path(), "base.toml", BASE_CONFIG);
+    let agent = write_config(
+        dir.path(),
+        "agent.toml",
+        r#"
+include = ["base.toml"]
+
+[llm]
+model = "gpt-4o"
+
+[profiles.prod.mqtt]
+broker_url = "mqtts://prod-broker:8883"
+
+[profiles.prod.llm]
+model = "gpt-4.1"
+
+[profiles.prod.tools.http_request]
+impl = "builtin"
+config = { user_agent = "prod" }
+"#,
+    );
+
+    let dev = AgentConfig::load_from_file(&agent).unwrap();
+    assert_eq!(dev.mqtt.broker_url, "mqtt://localhost:1883");
+    assert_eq!(dev.llm.model, "gpt-4o");
+
+    let (prod, warnings) = AgentConfig::load_with_warnings(&agent, Some("prod")).unwrap();
+    assert_eq!(prod.mqtt.broker_url, "mqtts://prod-broker:8883");
+    assert_eq!(prod.mqtt.heartbeat_interval_secs, 60);
+    assert_eq!(prod.llm.model, "gpt-4.1");
+    assert_eq!(prod.llm.temperature, Some(0.2));
+    let ToolConfig::Complex { config: http, .. } = &prod.tools["http_request"] else {
+        panic!("http_request should keep its table form");
+    };
+    assert_eq!(http["user_agent"], "prod");
+    assert_eq!(http["timeout_secs"], 30);
+    assert!(
+        warnings.iter().all(|w| !w.field.starts_with("profiles")),
+        "{warnings:?}"
+    );
+
+    let result = AgentConfig::load_with_warnings(&agent, Some("qa"));
+    assert!(matches!(
+        result,
+        Err(ConfigError::UnknownProfile { ref name, ref available })
+            if name == "qa" && available == "prod"
+    ));
+}
+
+#[test]
+fn test_include_cycle_is_reported() {
+    let dir = tempfile::tempdir().unwrap();
+    write_config(dir.path(), "a.toml", "include = [\"b.toml\"]\n");
+    write_config(dir.path(), "b.toml", "include = [\"a.toml\"]\n");
+    let agent = write_config(dir.path(), "agent.toml", "include = [\"a.toml\"]\n");
+
+    let error = AgentConfig::load_from_file(&agent).unwrap_err();
+
+    let ConfigError::IncludeCycle(cycle) = &error else {
+        panic!("Expected IncludeCycle, got {error}");
+    };
+    let files: Vec<_> = cycle
+        .split(" -> ")
+        .map(|file| std::path::Path::new(file).file_name().unwrap().to_owned())
+        .collect();
+    assert_eq!(files, ["a.toml", "b.toml", "a.toml"]);
+}
@@ -0,0 +1,166 @@
+//! Integration tests for workflow correlation ids
+//!
+//! Verifies that a correlation id is generated when a workflow starts, copied
+//! verbatim into forwarded tasks, responses and error messages, and that
+//! forwarded tasks record the task that caused them.
+
+mod test_helpers;
+
+use agent2389::agent::processor::AgentProcessor;
+use agent2389::protocol::messages::{NextTask, TaskEnvelope, TaskEnvelopeWrapper};
+use agent2389::testing::mocks::{MockLlmProvider, MockTransport};
+use agent2389::tools::ToolSystem;
+use serde_json::json;
+use std::sync::Arc;
+use uuid::Uuid;
+
+// ========== Test Helpers ==========
+
+fn create_processor(
+    agent_id: &str,
+    llm: MockLlmProvider,
+) -> (AgentProcessor<MockTransport>, Arc<MockTransport>) {
+    let mut config = test_helpers::test_config();
+    config.agent.id = agent_id.to_string();
+    let transport = Arc::new(MockTransport::new());
+    let processor = AgentProcessor::new(
+        config,
+        Arc::new(llm),
+        Arc::new(ToolSystem::new()),
+        transport.clone(),
+    );
+    (processor, transport)
+}
+
+fn create_task(agent_id: &str, correlation_id: Option<&str>) -> TaskEnvelope {
+    TaskEnvelope {
+        task_id: Uuid::new_v4(),
+        conversation_id: "correlation-conversation".to_string(),
+        topic: format!("/control/agents/{agent_id}/input"),
+        instruction: Some("Summarize the report".to_string()),
+        input: json!({}),
+        next: None,
+        deadline: None,
+        correlation_id: correlation_id.map(str::to_string),
+        parent_task_id: None,
+        published_at: None,
+        traceparent: None,
+        sender_id: None,
+    }
+}
+
+// ========== Correlation Tests ==========
+
+#[tokio::test]
+async fn test_correlation_id_propagates_across_forward() {
+    // Arrange: first agent forwards to a second agent via the static pipeline
+    let (first, first_transport) =
+        create_processor("first-agent", MockLlmProvider::single_response("draft"));
+    let mut task = create_task("first-agent", None);
+    task.next = Some(Box::new(NextTask {
+        topic: "/control/agents/second-agent/input".to_string(),
+        instruction: Some("Polish the draft".to_string()),
+        input: None,
+        next: None,
+    }));
+    let first_task_id = task.task_id;
+
+    // Act: run the first hop, then feed the forwarded task to the second agent
+    first
+        .process_task(
+            TaskEnvelopeWrapper::V1(task),
+            "/control/agents/first-agent/input",
+            false,
+        )
+        .await
+        .expect("first hop should succeed");
+
+    let forwarded = first_transport.get_published_tasks().await;
+    assert_eq!(forwarded.len(), 1);
+    let forwarded = forwarded[0].1.clone();
+
+    let (second, second_transport) =
+        create_processor("second-agent", MockLlmProvider::single_response("final"));
+    second
+        .process_task(
+            TaskEnvelopeWrapper::V1(forwarded.clone()),
+            "/control/agents/second-agent/input",
+            false,
+        )
+        .await
+        .expect("second hop should succeed");
+
+    // Assert: a correlation id was generated once and carried to the end
+    let correlation_id = forwarded
+        .correlation_id
+        .clone()
+        .expect("forwarded task should carry a generated correlation id");
+    assert_eq!(forwarded.parent_task_id, Some(first_task_id));
+
+    let responses = second_transport.get_published_responses().await;
+    assert_eq!(responses.len(), 1);
+    assert_eq!(responses[0].1.correlation_id, Some(correlation_id));
+    assert_eq!(responses[0].1.parent_task_id, Some(first_task_id));
+}
+
+#[tokio::test]
+async fn test_existing_correlation_id_is_kept_verbatim() {
+    // Arrange
+    let (processor, transport) =
+        create_processor("test-agent", MockLlmProvider::single_response("done"));
+    let task = create_task("test-agent", Some("upstream-trace-42"));
+
+    // Act
+    processor
+        .process_task(
+            TaskEnvelopeWrapper::V1(task),
+            "/control/agents/test-agent/input",
+            false,
+        )
+        .await
+        .expect("task should complete");
+
+    // Assert
+    let responses = transport.get_published_responses().await;
+    assert_eq!(responses.len(), 1);
+    assert_eq!(
+        responses[0].1.correlation_id.as_deref(),
+        Some("upstream-trace-42")
+    );
+    assert_eq!(responses[0].1.parent_task_id, None);
+    // Responses name their conversation and agent, whatever topic they arrive on
+    assert_eq!(
+        responses[0].1.conversation_id.as_deref(),
+        Some("correlation-conversation")
+    );
+    assert_eq!(responses[0].1.agent_id.as_deref(), Some("test-agent"));
+    assert!(responses[0].1.completed_at.is_some());
+}
+
+#[tokio::test]
+async fn test_correlation_id_reaches_error_messages() {
+    // Arrange: LLM failure forces the error path
+    let (processor, transport) = create_processor("test-agent", MockLlmProvider::with_failure());
+    let parent_task_id = Uuid::new_v4();
+    let mut task = create_task("test-agent", Some("failing-workflow"));
+    task.parent_task_id = Some(parent_task_id);
+
+    // Act
+    let result = processor
+        .process_task(
+            TaskEnvelopeWrapper::V1(task),
+            "/control/agents/test-agent/input",
+            false,
+        )
+        .await;
+
+    // Assert
+    assert!(result.is_err());
+    let errors = transport.get_published_errors().await;
+    assert_eq!(errors.len(), 1);
+    assert_eq!(
+        errors[0].1.correlation_id.as_deref(),
+        Some("failing-workflow")
+    );
+    assert_eq!(errors[0].1.parent_task_id, Some(parent_task_id));
+}
@@ -0,0 +1,204 @@
+//! Integration tests for running the pipeline with a custom task processor
+//!
+//! Verifies that tasks flow through an `AgentPipeline` built around a
+//! `TaskProcessor` that is not the built-in 9-step processor, including the
+//! pipeline's retry handling and the cancel channel it cannot act on.
+
+mod test_helpers;
+
+use agent2389::agent::pipeline::AgentPipeline;
+use agent2389::agent::task_processor::{ConfigAccess, TaskProcessor, TransportAccess};
+use agent2389::config::AgentConfig;
+use agent2389::error::{AgentError, AgentResult};
+use agent2389::processing::nine_step::ProcessingResult;
+use agent2389::protocol::messages::{
+    CancelMessage, ResponseMessage, TaskEnvelope, TaskEnvelopeWrapper,
+};
+use agent2389::testing::mocks::MockTransport;
+use agent2389::transport::Transport;
+use async_trait::async_trait;
+use serde_json::json;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+// ========== Test Helpers ==========
+
+/// Processor that upper-cases the instruction instead of calling an LLM,
+/// failing its first `failures` attempts with a retryable error
+struct ShoutingProcessor {
+    config: AgentConfig,
+    transport: Arc<MockTransport>,
+    failures: usize,
+    attempts: AtomicUsize,
+    seen: Mutex<Vec<Uuid>>,
+}
+
+impl ShoutingProcessor {
+    fn new(transport: Arc<MockTransport>, failures: usize) -> Self {
+        Self {
+            config: test_helpers::test_config(),
+            transport,
+            failures,
+            attempts: AtomicUsize::new(0),
+            seen: Mutex::new(Vec::new()),
+        }
+    }
+
+    fn attempts(&self) -> usize {
+        self.attempts.load(Ordering::SeqCst)
+    }
+}
+
+impl ConfigAccess for ShoutingProcessor {
+    fn config(&self) -> &AgentConfig {
+        &self.config
+    }
+}
+
+impl TransportAccess<MockTransport> for ShoutingProcessor {
+    fn transport(&self) -> &Arc<MockTransport> {
+        &self.transport
+    }
+}
+
+#[async_trait]
+impl TaskProcessor<MockTransport> for ShoutingProcessor {
+    async fn process_task(
+        &self,
+        wrapper: TaskEnvelopeWrapper,
+        _received_topic: &str,
+        _is_retained: bool,
+    ) -> AgentResult<ProcessingResult> {
+        if self.attempts.fetch_add(1, Ordering::SeqCst) < self.failures {
+            return Err(AgentError::Overloaded {
+                message: "temporarily unavailable".to_string(),
+            });
+        }
+
+        let task_id = wrapper.task_id();
+        self.seen.lock().unwrap().push(task_id);
+        let instruction = match &wrapper {
+            TaskEnvelopeWrapper::V1(task) => task.instruction.clone(),
+            TaskEnvelopeWrapper::V2(task) => task.instruction.clone(),
+        };
+        let response = instruction.unwrap_or_default().to_uppercase();
+        self.transport
+            .publish_response(
+                wrapper.conversation_id(),
+                &ResponseMessage {
+                    response: response.clone(),
+                    task_id,
+                    routing_trace: None,
+                    correlation_id: None,
+                    parent_task_id: None,
+                    conversation_id: None,
+                    agent_id: None,
+                    completed_at: None,
+                    oversized: None,
+                    artifacts: Vec::new(),
+                },
+            )
+            .await
+            .map_err(|e| AgentError::internal_error(e.to_string()))?;
+
+        Ok(ProcessingResult {
+            task_id,
+            response,
+            forwarded: false,
+        })
+    }
+}
+
+fn create_task(instruction: &str) -> TaskEnvelopeWrapper {
+    TaskEnvelopeWrapper::V1(TaskEnvelope {
+        task_id: Uuid::new_v4(),
+        conversation_id: "custom-conversation".to_string(),
+        topic: "/control/agents/test-agent/input".to_string(),
+        instruction: Some(instruction.to_string()),
+        input: json!({}),
+        next: None,
+        deadline: None,
+        correlation_id: None,
+        parent_task_id: None,
+        published_at: None,
+        traceparent: None,
+        sender_id: None,
+    })
+}
+
+// ========== Custom Processor Tests ==========
+
+#[tokio::test]
+async fn test_tasks_flow_through_custom_processor() {
+    let transport = Arc::new(MockTransport::new());
+    let processor = Arc::new(ShoutingProcessor::new(transport.clone(), 0));
+    let (task_sender, task_receiver) = mpsc::channel(16);
+    let (cancel_sender, cancel_receiver) = mpsc::channel(1);
+    let mut pipeline = AgentPipeline::with_processor(processor.clone(), task_receiver, 16);
+    pipeline.set_cancel_receiver(cancel_receiver);
+
+    let first = create_task("hello");
+    let second = create_task("again");
+    let task_ids = vec![first.task_id(), second.task_id()];
+    task_sender.send(first).await.unwrap();
+    task_sender.send(second).await.unwrap();
+    let handle = tokio::spawn(async move { pipeline.run().await });
+
+    // Cancel requests are drained even though this processor cannot cancel
+    tokio::time::timeout(Duration::from_secs(5), async {
+        for _ in 0..3 {
+            cancel_sender
+                .send(CancelMessage {
+                    task_id: Uuid::new_v4(),
+                    conversation_id: "custom-conversation".to_string(),
+                    reason: None,
+                })
+                .await
+                .unwrap();
+        }
+    })
+    .await
+    .expect("cancel channel should be drained");
+    drop(task_sender);
+    tokio::time::timeout(Duration::from_secs(5), handle)
+        .await
+        .expect("pipeline should drain and stop")
+        .unwrap()
+        .expect("pipeline should not fail");
+
+    let mut seen = processor.seen.lock().unwrap().clone();
+    seen.sort();
+    let mut expected = task_ids.clone();
+    expected.sort();
+    assert_eq!(seen, expected);
+
+    let mut responses: Vec<String> = transport
+        .get_published_responses()
+        .await
+        .into_iter()
+        .map(|(_, response)| response.response)
+        .collect();
+    responses.sort();
+    assert_eq!(responses, vec!["AGAIN", "HELLO"]);
+}
+
+#[tokio::test]
+async fn test_pipeline_retries_custom_processor() {
+    let transport = Arc::new(MockTransport::new());
+    let processor = Arc::new(ShoutingProcessor::new(transport.clone(), 1));
+    let (_task_sender, task_receiver) = mpsc::channel(1);
+    let mut pipeline = AgentPipeline::with_processor(processor.clone(), task_receiver, 16);
+    pipeline.set_max_task_retries(1);
+    pipeline.set_retry_base_delay(Duration::from_millis(5));
+
+    let result = pipeline.process_single_task(create_task("retry me")).await;
+
+    assert!(result.is_ok(), "retry should succeed: {result:?}");
+    assert_eq!(processor.attempts(), 2);
+    let responses = transport.get_published_responses().await;
+    assert_eq!(responses.len(), 1);
+    assert_eq!(responses[0].1.response, "RETRY ME");
+}
@@ -0,0 +1,221 @@
+//! Integration tests for `[testing] deterministic` mode
+//!
+//! Runs the same scripted two-hop workflow twice - an agent decision forward
+//! followed by a router forward - and verifies that deterministic runs publish
+//! identical envelopes, with task ids derived from their parent, times read
+//! from a stepping clock, and LLM requests sampled at temperature 0 with a
+//! fixed seed.
+
+mod test_helpers;
+
+use agent2389::agent::discovery::AgentRegistry;
+use agent2389::agent::pipeline::AgentPipeline;
+use agent2389::agent::processor::AgentProcessor;
+use agent2389::clock::{clock_for, derived_task_id, deterministic_epoch};
+use agent2389::config::{AgentConfig, DEFAULT_DETERMINISTIC_SEED};
+use agent2389::error::AgentError;
+use agent2389::processing::nine_step::NineStepProcessor;
+use agent2389::protocol::messages::{TaskEnvelopeV2, TaskEnvelopeWrapper};
+use agent2389::routing::agent_selector::RoutingHelper;
+use agent2389::routing::{Router, RoutingDecision};
+use agent2389::testing::mocks::{MockAgentRegistry, MockLlmProvider, MockTransport};
+use agent2389::tools::ToolSystem;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+// ========== Test Helpers ==========
+
+/// Task id the scripted workflow starts from, the same on every run
+const START_TASK_ID: Uuid = Uuid::from_u128(0x2389);
+
+fn agent_config(agent_id: &str, deterministic: bool) -> AgentConfig {
+    let mut config = test_helpers::test_config();
+    config.agent.id = agent_id.to_string();
+    config.llm.temperature = Some(0.7);
+    config.testing.deterministic = deterministic;
+    config
+}
+
+fn registry() -> AgentRegistry {
+    let registry = MockAgentRegistry::new();
+    for agent in ["agent-b", "agent-c"] {
+        registry.register_agent(agent, vec![agent.to_string()]);
+    }
+    registry.registry().clone()
+}
+
+/// Router that forwards every task to agent C
+struct ForwardRouter;
+
+#[async_trait::async_trait]
+impl Router for ForwardRouter {
+    async fn decide_next_step(
+        &self,
+        _task: &TaskEnvelopeV2,
+        work_output: &Value,
+        _registry: &AgentRegistry,
+    ) -> Result<RoutingDecision, AgentError> {
+        Ok(RoutingDecision::Forward {
+            next_agent: "agent-c".to_string(),
+            next_instruction: "Summarize the findings".to_string(),
+            forwarded_data: work_output.clone(),
+            sticky: false,
+        })
+    }
+}
+
+/// What one run of the workflow published and asked its LLMs
+struct Run {
+    /// Forwarded envelopes as published, serialized, in order
+    envelopes: Vec<String>,
+    to_b: TaskEnvelopeV2,
+    to_c: TaskEnvelopeV2,
+    agent_a_llm: Arc<MockLlmProvider>,
+}
+
+/// Agent A forwards to B by its own decision, then B's router forwards to C
+async fn run_workflow(deterministic: bool) -> Run {
+    // Agent A: the nine-step processor forwards on the LLM's decision
+    let config_a = agent_config("agent-a", deterministic);
+    let transport_a = Arc::new(MockTransport::with_clock(clock_for(&config_a.testing)));
+    let agent_a_llm = Arc::new(MockLlmProvider::single_response(
+        json!({
+            "schema_version": "1.0",
+            "result": "Found three sources",
+            "next_agent": "agent-b",
+            "next_instruction": "Check the sources",
+            "workflow_complete": false
+        })
+        .to_string(),
+    ));
+    let agent_a = NineStepProcessor::new_with_routing(
+        config_a,
+        agent_a_llm.clone(),
+        Arc::new(ToolSystem::new()),
+        transport_a.clone(),
+        RoutingHelper::new(),
+        registry(),
+    );
+    let start = TaskEnvelopeV2 {
+        task_id: START_TASK_ID,
+        conversation_id: "deterministic-run".to_string(),
+        topic: "/control/agents/agent-a/input".to_string(),
+        instruction: Some("Research the protocol".to_string()),
+        input: json!({"topic": "RFC 2389"}),
+        next: None,
+        version: "2.0".to_string(),
+        context: None,
+        routing_trace: Some(vec![]),
+        deadline: None,
+        correlation_id: None,
+        parent_task_id: None,
+        published_at: None,
+        traceparent: None,
+        sender_id: None,
+    };
+    agent_a
+        .process_task(TaskEnvelopeWrapper::V2(start.clone()), &start.topic, false)
+        .await
+        .unwrap();
+    let to_b = transport_a.get_published_task_envelopes().await[0]
+        .1
+        .clone()
+        .to_v2();
+
+    // Agent B: the pipeline's router forwards to C
+    let config_b = agent_config("agent-b", deterministic);
+    let transport_b = Arc::new(MockTransport::with_clock(clock_for(&config_b.testing)));
+    let processor_b = AgentProcessor::new(
+        config_b,
+        Arc::new(MockLlmProvider::single_response("unused")),
+        Arc::new(ToolSystem::new()),
+        transport_b.clone(),
+    );
+    let (_task_sender, task_receiver) = mpsc::channel(1);
+    let pipeline_b = AgentPipeline::with_router(
+        processor_b,
+        task_receiver,
+        16,
+        Arc::new(ForwardRouter),
+        Arc::new(registry()),
+        10,
+    );
+    pipeline_b
+        .process_with_routing(to_b.clone(), json!({"result": "Sources check out"}))
+        .await
+        .unwrap();
+    let to_c = transport_b.get_published_task_envelopes().await[0]
+        .1
+        .clone()
+        .to_v2();
+
+    let envelopes = [&to_b, &to_c]
+        .iter()
+        .map(|envelope| serde_json::to_string(envelope).unwrap())
+        .collect();
+    Run {
+        envelopes,
+        to_b,
+        to_c,
+        agent_a_llm,
+    }
+}
+
+// ========== Deterministic Mode Tests ==========
+
+#[tokio::test]
+async fn test_two_deterministic_runs_forward_identical_envelopes() {
+    let first = run_workflow(true).await;
+    let second = run_workflow(true).await;
+
+    assert_eq!(first.envelopes, second.envelopes);
+}
+
+#[tokio::test]
+async fn test_deterministic_run_derives_ids_and_reads_the_stepping_clock() {
+    let run = run_workflow(true).await;
+
+    // The agent decision forward keeps the task id; the router forward
+    // derives its id from the parent and the hop
+    assert_eq!(run.to_b.task_id, START_TASK_ID);
+    let hop = run.to_c.context.as_ref().unwrap().iteration_count;
+    assert_eq!(
+        run.to_c.task_id,
+        derived_task_id(run.to_b.task_id, &hop.to_string())
+    );
+    // The correlation id is derived from the starting task, and carried along
+    let correlation_id = run.to_b.correlation_id.clone().unwrap();
+    assert_eq!(
+        correlation_id,
+        Uuid::new_v5(&START_TASK_ID, b"correlation").to_string()
+    );
+    assert_eq!(run.to_c.correlation_id, Some(correlation_id));
+    // Times come from the stepping clock, not the wall clock
+    let context = run.to_c.context.as_ref().unwrap();
+    assert!(context.started_at.unwrap() >= deterministic_epoch());
+    assert!(run.to_c.published_at.unwrap() < deterministic_epoch() + chrono::Duration::hours(1));
+}
+
+#[tokio::test]
+async fn test_deterministic_run_samples_at_temperature_zero_with_a_seed() {
+    let deterministic = run_workflow(true).await;
+    let regular = run_workflow(false).await;
+
+    let request = &deterministic.agent_a_llm.requests()[0];
+    assert_eq!(request.temperature, Some(0.0));
+    assert_eq!(request.seed, Some(DEFAULT_DETERMINISTIC_SEED));
+    let request = &regular.agent_a_llm.requests()[0];
+    assert_eq!(request.temperature, Some(0.7));
+    assert_eq!(request.seed, None);
+}
+
+#[tokio::test]
+async fn test_regular_runs_forward_fresh_ids() {
+    let first = run_workflow(false).await;
+    let second = run_workflow(false).await;
+
+    assert_ne!(first.to_c.task_id, second.to_c.task_id);
+    assert_ne!(first.to_b.correlation_id, second.to_b.correlation_id);
+}
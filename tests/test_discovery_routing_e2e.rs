@@ -91,6 +91,12 @@ async fn test_full_discovery_and_routing_flow() {
                 instruction: Some("Process this email".to_string()),
                 input: json!({"email": "test@example.com"}),
                 next: None,
+                deadline: None,
+                correlation_id: None,
+                parent_task_id: None,
+                published_at: None,
+                traceparent: None,
+                sender_id: None,
             };
 
             // Publish task to Agent A's input topic
@@ -0,0 +1,147 @@
+//! Integration tests for concurrently delivered duplicate tasks
+//!
+//! Races two copies of one task through the pipeline at once and verifies
+//! that only one reaches the LLM, whether the second copy is rejected or
+//! waits for the first per `[processing] duplicate_in_flight`, and that a
+//! copy arriving after a failed attempt is processed.
+
+mod test_helpers;
+
+use agent2389::agent::pipeline::AgentPipeline;
+use agent2389::config::DuplicateInFlight;
+use agent2389::llm::provider::LlmError;
+use agent2389::protocol::messages::TaskEnvelopeWrapper;
+use agent2389::testing::mocks::{MockLlmProvider, MockTransport};
+use agent2389::tools::ToolSystem;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+// ========== Test Helpers ==========
+
+fn auth_error() -> LlmError {
+    LlmError::AuthenticationFailed("invalid api key".to_string())
+}
+
+fn create_pipeline(
+    llm: Arc<MockLlmProvider>,
+    duplicate_in_flight: DuplicateInFlight,
+) -> (AgentPipeline<MockTransport>, Arc<MockTransport>) {
+    let mut config = test_helpers::test_config();
+    config.processing.duplicate_in_flight = duplicate_in_flight;
+    let (processor, transport) = test_helpers::create_processor(config, llm, ToolSystem::new());
+    let (_task_sender, task_receiver) = mpsc::channel(1);
+    let mut pipeline = AgentPipeline::new(processor, task_receiver, 16);
+    pipeline.set_max_task_retries(0);
+    (pipeline, transport)
+}
+
+fn create_task() -> TaskEnvelopeWrapper {
+    TaskEnvelopeWrapper::V1(test_helpers::create_task(
+        "duplicate-conversation",
+        "Do some work",
+    ))
+}
+
+/// LLM slow enough for a second copy to arrive while the first is in flight
+fn slow_llm() -> MockLlmProvider {
+    MockLlmProvider::single_response("Done").with_delay(Duration::from_millis(100))
+}
+
+// ========== Duplicate Tests ==========
+
+#[tokio::test]
+async fn test_concurrent_copy_is_rejected_while_the_task_is_in_flight() {
+    // Arrange
+    let llm = Arc::new(slow_llm());
+    let (pipeline, transport) = create_pipeline(llm.clone(), DuplicateInFlight::Reject);
+    let task = create_task();
+
+    // Act
+    let (first, second) = tokio::join!(
+        pipeline.process_single_task(task.clone()),
+        pipeline.process_single_task(task.clone()),
+    );
+
+    // Assert: exactly one copy ran
+    assert_eq!(llm.calls(), 1);
+    assert!(first.is_ok() != second.is_ok(), "{first:?} / {second:?}");
+    assert_eq!(transport.get_published_responses().await.len(), 1);
+}
+
+#[tokio::test]
+async fn test_rejected_copy_reports_the_task_as_in_flight() {
+    let llm = Arc::new(slow_llm());
+    let (pipeline, _transport) = create_pipeline(llm.clone(), DuplicateInFlight::Reject);
+    let task = create_task();
+
+    let (first, second) = tokio::join!(pipeline.process_single_task(task.clone()), async {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        pipeline.process_single_task(task.clone()).await
+    });
+
+    assert!(first.is_ok(), "{first:?}");
+    let error = second.expect_err("copy should be rejected").to_string();
+    assert!(error.contains("already being processed"), "{error}");
+    assert_eq!(llm.calls(), 1);
+}
+
+#[tokio::test]
+async fn test_waiting_copy_is_rejected_once_the_task_completes() {
+    // Arrange
+    let llm = Arc::new(slow_llm());
+    let (pipeline, transport) = create_pipeline(llm.clone(), DuplicateInFlight::Wait);
+    let task = create_task();
+
+    // Act
+    let (first, second) = tokio::join!(pipeline.process_single_task(task.clone()), async {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        pipeline.process_single_task(task.clone()).await
+    });
+
+    // Assert: the copy waited for the first, then failed the store check
+    assert!(first.is_ok(), "{first:?}");
+    let error = second.expect_err("copy should be rejected").to_string();
+    assert!(error.contains("already processed"), "{error}");
+    assert_eq!(llm.calls(), 1);
+    assert_eq!(transport.get_published_responses().await.len(), 1);
+}
+
+#[tokio::test]
+async fn test_waiting_copy_runs_when_the_task_fails() {
+    // Arrange: the first copy's LLM call fails permanently
+    let llm = Arc::new(slow_llm().failing_first(1, auth_error));
+    let (pipeline, transport) = create_pipeline(llm.clone(), DuplicateInFlight::Wait);
+    let task = create_task();
+
+    // Act
+    let (first, second) = tokio::join!(pipeline.process_single_task(task.clone()), async {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        pipeline.process_single_task(task.clone()).await
+    });
+
+    // Assert: the copy ran after the failure was settled
+    assert!(first.is_err());
+    assert!(second.is_ok(), "{second:?}");
+    assert_eq!(llm.calls(), 2);
+    assert_eq!(transport.get_published_responses().await.len(), 1);
+}
+
+#[tokio::test]
+async fn test_failed_task_can_be_delivered_again() {
+    // Arrange
+    let llm = Arc::new(MockLlmProvider::single_response("Done").failing_first(1, auth_error));
+    let (pipeline, _transport) = create_pipeline(llm.clone(), DuplicateInFlight::Reject);
+    let task = create_task();
+
+    // Act
+    let failed = pipeline.process_single_task(task.clone()).await;
+    let redelivered = pipeline.process_single_task(task.clone()).await;
+    let duplicate = pipeline.process_single_task(task).await;
+
+    // Assert: only the completed delivery is remembered
+    assert!(failed.is_err());
+    assert!(redelivered.is_ok(), "{redelivered:?}");
+    assert!(duplicate.is_err());
+    assert_eq!(llm.calls(), 2);
+}
@@ -28,6 +28,12 @@ fn create_test_task(instruction: &str) -> TaskEnvelope {
         instruction: Some(instruction.to_string()),
         input: json!({}),
         next: None,
+        deadline: None,
+        correlation_id: None,
+        parent_task_id: None,
+        published_at: None,
+        traceparent: None,
+        sender_id: None,
     }
 }
 
@@ -0,0 +1,194 @@
+//! Integration tests for the event log and `/events`
+//!
+//! Records events into the global log, directly and by processing tasks
+//! that fail, and checks what `/events` serves for each filter. The log is
+//! shared by the tests in this binary, so each test looks for its own
+//! task or marker rather than counting events.
+
+mod test_helpers;
+
+use agent2389::agent::pipeline::AgentPipeline;
+use agent2389::observability::event_log::{event_log, Event, EventCategory, EventSeverity};
+use agent2389::observability::health::HealthServer;
+use agent2389::protocol::messages::TaskEnvelopeWrapper;
+use agent2389::testing::mocks::MockLlmProvider;
+use agent2389::tools::ToolSystem;
+use serde_json::Value;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+// ========== Test Helpers ==========
+
+fn serve() -> SocketAddr {
+    let server = Arc::new(HealthServer::new("events-agent".to_string(), 0));
+    let (addr, serving) = server.serve_on(([127, 0, 0, 1], 0));
+    tokio::spawn(serving);
+    addr
+}
+
+async fn get(addr: SocketAddr, path: &str) -> (u16, Value) {
+    let response = reqwest::get(format!("http://{addr}{path}")).await.unwrap();
+    let status = response.status().as_u16();
+    (status, response.json().await.unwrap())
+}
+
+/// Served events carrying `field` = `value`
+fn with_field<'a>(body: &'a Value, field: &str, value: &str) -> Vec<&'a Value> {
+    body["events"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|event| event["fields"][field] == value)
+        .collect()
+}
+
+/// Run a task through a pipeline with `llm` and no tools, returning its id
+async fn process(llm: MockLlmProvider, instruction: &str) -> Uuid {
+    let (processor, _transport) = test_helpers::create_processor(
+        test_helpers::test_config(),
+        Arc::new(llm),
+        ToolSystem::new(),
+    );
+    let (sender, receiver) = mpsc::channel(1);
+    let mut pipeline = AgentPipeline::new(processor, receiver, 16);
+    let handle = tokio::spawn(async move { pipeline.run().await });
+    let task = test_helpers::create_task("events-conversation", instruction);
+    let task_id = task.task_id;
+    sender.send(TaskEnvelopeWrapper::V1(task)).await.unwrap();
+    drop(sender);
+    let _ = tokio::time::timeout(Duration::from_secs(5), handle)
+        .await
+        .unwrap();
+    task_id
+}
+
+// ========== Endpoint Tests ==========
+
+#[tokio::test]
+async fn test_events_endpoint_serves_recorded_events_in_order() {
+    // Arrange
+    let marker = Uuid::new_v4().to_string();
+    for message in ["first", "second", "third"] {
+        event_log().record(
+            Event::new(EventSeverity::Info, EventCategory::Connection, message)
+                .with_field("marker", marker.as_str()),
+        );
+    }
+    let addr = serve();
+
+    // Act
+    let (status, body) = get(addr, "/events").await;
+
+    // Assert
+    assert_eq!(status, 200);
+    assert_eq!(body["agent_id"], "events-agent");
+    let events = with_field(&body, "marker", &marker);
+    let messages: Vec<&str> = events
+        .iter()
+        .map(|event| event["message"].as_str().unwrap())
+        .collect();
+    assert_eq!(messages, vec!["first", "second", "third"]);
+    assert!(events[0]["sequence"].as_u64() < events[1]["sequence"].as_u64());
+    assert_eq!(events[0]["severity"], "info");
+    assert_eq!(events[0]["category"], "connection");
+    assert!(events[0]["timestamp"].is_string());
+}
+
+#[tokio::test]
+async fn test_events_endpoint_filters_by_category_and_since() {
+    // Arrange
+    let marker = Uuid::new_v4().to_string();
+    let mut old = Event::new(EventSeverity::Error, EventCategory::Tool, "old tool error")
+        .with_field("marker", marker.as_str());
+    old.timestamp = chrono::Utc::now() - chrono::Duration::minutes(10);
+    event_log().record(old);
+    event_log().record(
+        Event::new(EventSeverity::Warning, EventCategory::Tool, "tool error")
+            .with_field("marker", marker.as_str()),
+    );
+    event_log().record(
+        Event::new(EventSeverity::Info, EventCategory::Routing, "forwarded")
+            .with_field("marker", marker.as_str()),
+    );
+    let since = (chrono::Utc::now() - chrono::Duration::minutes(5)).timestamp();
+    let addr = serve();
+
+    // Act
+    let (_, tools) = get(addr, "/events?category=tool").await;
+    let (_, recent) = get(addr, &format!("/events?since={since}")).await;
+    let (_, recent_tools) = get(addr, &format!("/events?category=tool&since={since}")).await;
+
+    // Assert
+    let messages = |body: &Value| -> Vec<String> {
+        with_field(body, "marker", &marker)
+            .iter()
+            .map(|event| event["message"].as_str().unwrap().to_string())
+            .collect()
+    };
+    assert_eq!(messages(&tools), vec!["old tool error", "tool error"]);
+    assert_eq!(messages(&recent), vec!["tool error", "forwarded"]);
+    assert_eq!(messages(&recent_tools), vec!["tool error"]);
+}
+
+#[tokio::test]
+async fn test_events_endpoint_rejects_bad_filters() {
+    let addr = serve();
+
+    let (category_status, category) = get(addr, "/events?category=weather").await;
+    let (since_status, since) = get(addr, "/events?since=yesterday").await;
+
+    assert_eq!(category_status, 400);
+    assert!(category["error"].as_str().unwrap().contains("weather"));
+    assert_eq!(since_status, 400);
+    assert!(since["error"].as_str().unwrap().contains("yesterday"));
+}
+
+// ========== Recording Tests ==========
+
+#[tokio::test]
+async fn test_failed_task_is_recorded() {
+    // Arrange
+    let addr = serve();
+
+    // Act
+    let task_id = process(MockLlmProvider::with_failure(), "fail").await;
+    let (_, body) = get(addr, "/events?category=task").await;
+
+    // Assert
+    let events = with_field(&body, "task_id", &task_id.to_string());
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0]["message"], "Task failed");
+    assert_eq!(events[0]["severity"], "error");
+    assert_eq!(
+        events[0]["fields"]["conversation_id"],
+        "events-conversation"
+    );
+    assert!(events[0]["fields"]["error"].is_string());
+}
+
+#[tokio::test]
+async fn test_tool_error_is_recorded() {
+    // Arrange: the model calls a tool the agent does not have
+    let addr = serve();
+
+    // Act
+    let task_id = process(
+        MockLlmProvider::single_response("done").with_tool_call("missing_tool"),
+        "use a tool",
+    )
+    .await;
+    let (_, body) = get(addr, "/events?category=tool").await;
+
+    // Assert
+    let events = with_field(&body, "task_id", &task_id.to_string());
+    assert!(!events.is_empty(), "{body}");
+    for event in events {
+        assert_eq!(event["message"], "Tool failed");
+        assert_eq!(event["severity"], "warning");
+        assert_eq!(event["fields"]["tool"], "missing_tool");
+        assert_eq!(event["fields"]["error"], "Unknown tool: missing_tool");
+    }
+}
@@ -0,0 +1,322 @@
+//! Integration tests for fan-out and fan-in routing
+//!
+//! Routes a task with a `FanOut` decision, plays the branch agents by
+//! publishing their responses and errors on the branch conversations, and
+//! verifies that the aggregator receives the joined outputs per the join
+//! policy, or that the workflow's conversation gets an error when too few
+//! branches succeed.
+
+use agent2389::agent::discovery::AgentRegistry;
+use agent2389::agent::pipeline::AgentPipeline;
+use agent2389::agent::processor::AgentProcessor;
+use agent2389::error::AgentError;
+use agent2389::protocol::messages::{TaskEnvelopeV2, TaskEnvelopeWrapper};
+use agent2389::routing::{ForwardBranch, JoinPolicy, Router, RoutingDecision};
+use agent2389::testing::mocks::{MockAgentRegistry, MockLlmProvider, MockTransport};
+use agent2389::tools::ToolSystem;
+use agent2389::transport::Transport;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+mod test_helpers;
+
+// ========== Test Helpers ==========
+
+/// Router that fans every task out to the legal and finance agents
+struct FanOutRouter {
+    join_policy: JoinPolicy,
+}
+
+#[async_trait::async_trait]
+impl Router for FanOutRouter {
+    async fn decide_next_step(
+        &self,
+        _task: &TaskEnvelopeV2,
+        _work_output: &Value,
+        _registry: &AgentRegistry,
+    ) -> Result<RoutingDecision, AgentError> {
+        Ok(RoutingDecision::FanOut {
+            branches: vec![
+                ForwardBranch {
+                    next_agent: "legal-agent".to_string(),
+                    next_instruction: "Review the terms".to_string(),
+                    forwarded_data: json!({"contract": "terms"}),
+                },
+                ForwardBranch {
+                    next_agent: "finance-agent".to_string(),
+                    next_instruction: "Check the numbers".to_string(),
+                    forwarded_data: json!({"contract": "numbers"}),
+                },
+            ],
+            aggregator_agent: "editor-agent".to_string(),
+            aggregator_instruction: "Merge the reviews".to_string(),
+            join_policy: self.join_policy.clone(),
+        })
+    }
+}
+
+fn create_pipeline(join_policy: JoinPolicy) -> (AgentPipeline<MockTransport>, Arc<MockTransport>) {
+    let registry = MockAgentRegistry::new();
+    for agent in ["legal-agent", "finance-agent", "editor-agent"] {
+        registry.register_agent(agent, vec![agent.to_string()]);
+    }
+    let transport = Arc::new(MockTransport::new());
+    let processor = AgentProcessor::new(
+        test_helpers::test_config(),
+        Arc::new(MockLlmProvider::single_response("unused")),
+        Arc::new(ToolSystem::new()),
+        transport.clone(),
+    );
+    let (_task_sender, task_receiver) = mpsc::channel(1);
+    let pipeline = AgentPipeline::with_router(
+        processor,
+        task_receiver,
+        16,
+        Arc::new(FanOutRouter { join_policy }),
+        Arc::new(registry.registry().clone()),
+        10,
+    );
+    (pipeline, transport)
+}
+
+fn create_task() -> TaskEnvelopeV2 {
+    TaskEnvelopeV2 {
+        task_id: Uuid::new_v4(),
+        conversation_id: "contract-review".to_string(),
+        topic: "/control/agents/test-agent/input".to_string(),
+        instruction: Some("Review the contract".to_string()),
+        input: json!({"contract": "..."}),
+        next: None,
+        version: "2.0".to_string(),
+        context: None,
+        routing_trace: None,
+        deadline: None,
+        correlation_id: Some("review-42".to_string()),
+        parent_task_id: None,
+        published_at: None,
+        traceparent: None,
+        sender_id: None,
+    }
+}
+
+/// Tasks published so far, as v2 envelopes with their topics
+async fn published_tasks(transport: &MockTransport) -> Vec<(String, TaskEnvelopeV2)> {
+    transport
+        .get_published_task_envelopes()
+        .await
+        .into_iter()
+        .filter_map(|(topic, task)| match task {
+            TaskEnvelopeWrapper::V2(task) => Some((topic, task)),
+            TaskEnvelopeWrapper::V1(_) => None,
+        })
+        .collect()
+}
+
+/// Fan the task out, returning the branch tasks
+async fn fan_out(
+    pipeline: &AgentPipeline<MockTransport>,
+    transport: &MockTransport,
+) -> Vec<TaskEnvelopeV2> {
+    pipeline
+        .process_with_routing(create_task(), json!({"summary": "Contract read"}))
+        .await
+        .unwrap();
+    let branches: Vec<TaskEnvelopeV2> = published_tasks(transport)
+        .await
+        .into_iter()
+        .map(|(_, task)| task)
+        .collect();
+    assert_eq!(branches.len(), 2);
+    branches
+}
+
+/// Play a branch's agent publishing its response
+async fn respond(transport: &MockTransport, branch: &TaskEnvelopeV2, response: &str) {
+    let payload = json!({
+        "response": response,
+        "task_id": branch.task_id,
+        "conversation_id": branch.conversation_id,
+    });
+    publish_on_branch(transport, branch, payload).await;
+}
+
+/// Play a branch's agent publishing an error
+async fn fail(transport: &MockTransport, branch: &TaskEnvelopeV2, message: &str) {
+    let payload = json!({
+        "error": {"code": "llm_error", "message": message},
+        "task_id": branch.task_id,
+    });
+    publish_on_branch(transport, branch, payload).await;
+}
+
+async fn publish_on_branch(transport: &MockTransport, branch: &TaskEnvelopeV2, payload: Value) {
+    let topic = format!("/conversations/{}/branch-agent", branch.conversation_id);
+    transport
+        .publish(&topic, serde_json::to_vec(&payload).unwrap(), false)
+        .await
+        .unwrap();
+}
+
+/// Wait for the join to settle
+async fn joined(pipeline: &AgentPipeline<MockTransport>) {
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while !pipeline.pending_fan_outs().is_empty() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("fan-out should be joined");
+    // The join publishes just after it settles
+    tokio::time::sleep(Duration::from_millis(50)).await;
+}
+
+/// The task sent to the aggregator, if any
+async fn aggregation_task(transport: &MockTransport) -> Option<TaskEnvelopeV2> {
+    published_tasks(transport)
+        .await
+        .into_iter()
+        .find(|(topic, _)| topic == "/control/agents/editor-agent/input")
+        .map(|(_, task)| task)
+}
+
+// ========== Fan-Out Tests ==========
+
+#[tokio::test]
+async fn test_branches_run_in_their_own_conversations() {
+    // Arrange
+    let (pipeline, transport) = create_pipeline(JoinPolicy::default());
+
+    // Act
+    let branches = fan_out(&pipeline, &transport).await;
+
+    // Assert: each branch has its own conversation, its own data, the
+    // workflow's correlation, and one workflow step between them
+    assert_ne!(branches[0].conversation_id, branches[1].conversation_id);
+    for branch in &branches {
+        assert!(branch
+            .conversation_id
+            .starts_with("contract-review.fanout."));
+        assert_eq!(branch.correlation_id.as_deref(), Some("review-42"));
+        assert_eq!(branch.context.as_ref().unwrap().iteration_count, 1);
+    }
+    assert_eq!(branches[0].input, json!({"contract": "terms"}));
+    assert_eq!(
+        branches[1].instruction.as_deref(),
+        Some("Check the numbers")
+    );
+    assert_eq!(pipeline.pending_fan_outs().len(), 1);
+    assert!(aggregation_task(&transport).await.is_none());
+}
+
+#[tokio::test]
+async fn test_aggregator_receives_every_branch_output() {
+    // Arrange
+    let (pipeline, transport) = create_pipeline(JoinPolicy::default());
+    let branches = fan_out(&pipeline, &transport).await;
+
+    // Act: the branches finish out of order
+    respond(&transport, &branches[1], r#"{"total": 1200}"#).await;
+    respond(&transport, &branches[0], "The terms are standard").await;
+    joined(&pipeline).await;
+
+    // Assert
+    let task = aggregation_task(&transport).await.expect("aggregator task");
+    assert_eq!(task.conversation_id, "contract-review");
+    assert_eq!(task.instruction.as_deref(), Some("Merge the reviews"));
+    assert_eq!(task.input["complete"], true);
+    assert_eq!(task.input["branches"][0]["agent_id"], "legal-agent");
+    assert_eq!(
+        task.input["branches"][0]["output"],
+        "The terms are standard"
+    );
+    assert_eq!(task.input["branches"][1]["output"], json!({"total": 1200}));
+    assert!(transport.get_published_errors().await.is_empty());
+}
+
+#[tokio::test]
+async fn test_timeout_aggregates_the_branches_that_finished() {
+    // Arrange
+    let policy = JoinPolicy {
+        timeout_secs: 1,
+        ..JoinPolicy::default()
+    };
+    let (pipeline, transport) = create_pipeline(policy);
+    let branches = fan_out(&pipeline, &transport).await;
+
+    // Act: only one branch ever finishes
+    respond(&transport, &branches[0], "The terms are standard").await;
+    joined(&pipeline).await;
+
+    // Assert
+    let task = aggregation_task(&transport).await.expect("aggregator task");
+    assert_eq!(task.input["complete"], false);
+    assert_eq!(task.input["branches"][0]["status"], "succeeded");
+    assert_eq!(task.input["branches"][1]["status"], "timed_out");
+}
+
+#[tokio::test]
+async fn test_quorum_joins_without_waiting_for_every_branch() {
+    // Arrange
+    let policy = JoinPolicy {
+        quorum: Some(1),
+        ..JoinPolicy::default()
+    };
+    let (pipeline, transport) = create_pipeline(policy);
+    let branches = fan_out(&pipeline, &transport).await;
+
+    // Act
+    respond(&transport, &branches[1], "Numbers check out").await;
+    joined(&pipeline).await;
+
+    // Assert
+    let task = aggregation_task(&transport).await.expect("aggregator task");
+    assert_eq!(task.input["complete"], true);
+    assert_eq!(task.input["branches"][0]["status"], "pending");
+}
+
+#[tokio::test]
+async fn test_failed_branch_is_reported_to_the_aggregator() {
+    // Arrange
+    let (pipeline, transport) = create_pipeline(JoinPolicy::default());
+    let branches = fan_out(&pipeline, &transport).await;
+
+    // Act
+    fail(&transport, &branches[0], "model unavailable").await;
+    respond(&transport, &branches[1], "Numbers check out").await;
+    joined(&pipeline).await;
+
+    // Assert: partial results are allowed by default
+    let task = aggregation_task(&transport).await.expect("aggregator task");
+    assert_eq!(task.input["complete"], false);
+    assert_eq!(task.input["branches"][0]["status"], "failed");
+    assert_eq!(
+        task.input["branches"][0]["error"]["message"],
+        "model unavailable"
+    );
+}
+
+#[tokio::test]
+async fn test_failed_join_publishes_an_error_to_the_workflow() {
+    // Arrange
+    let policy = JoinPolicy {
+        allow_partial: false,
+        ..JoinPolicy::default()
+    };
+    let (pipeline, transport) = create_pipeline(policy);
+    let branches = fan_out(&pipeline, &transport).await;
+
+    // Act: one failure makes the quorum of two unreachable
+    fail(&transport, &branches[0], "model unavailable").await;
+    joined(&pipeline).await;
+
+    // Assert
+    assert!(aggregation_task(&transport).await.is_none());
+    let errors = transport.get_published_errors().await;
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].0, "contract-review");
+    assert_eq!(errors[0].1.correlation_id.as_deref(), Some("review-42"));
+    assert!(errors[0].1.error.message.contains("model unavailable"));
+}
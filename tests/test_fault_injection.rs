@@ -0,0 +1,113 @@
+//! Pipeline behaviour under injected transport faults
+//!
+//! Runs a real pipeline behind a `FaultInjectingTransport` to check that a
+//! task delivered twice is only processed once, and that a task whose
+//! response publish fails transiently is retried until it goes through.
+
+mod test_helpers;
+
+use agent2389::agent::pipeline::AgentPipeline;
+use agent2389::agent::processor::AgentProcessor;
+use agent2389::protocol::messages::TaskEnvelopeWrapper;
+use agent2389::testing::faults::{FaultInjectingTransport, FaultPolicy, PublishKind};
+use agent2389::testing::mocks::{MockLlmProvider, MockTransport};
+use agent2389::tools::ToolSystem;
+use agent2389::transport::Transport;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+// ========== Test Helpers ==========
+
+type FaultyTransport = FaultInjectingTransport<MockTransport>;
+
+/// Pipeline behind a faulty transport, and the receiver of tasks the transport delivers
+fn create_pipeline(
+    llm: Arc<MockLlmProvider>,
+    policy: FaultPolicy,
+    max_task_retries: u32,
+) -> (
+    AgentPipeline<FaultyTransport>,
+    Arc<FaultyTransport>,
+    mpsc::Receiver<TaskEnvelopeWrapper>,
+) {
+    let transport = Arc::new(FaultInjectingTransport::new(MockTransport::new(), policy));
+    let (task_sender, task_receiver) = mpsc::channel(16);
+    transport.set_task_sender(task_sender);
+    let processor = AgentProcessor::new(
+        test_helpers::test_config(),
+        llm,
+        Arc::new(ToolSystem::new()),
+        transport.clone(),
+    );
+    // Tasks are fed to process_single_task by hand, one at a time
+    let (_unused_sender, unused_receiver) = mpsc::channel(1);
+    let mut pipeline = AgentPipeline::new(processor, unused_receiver, 16);
+    pipeline.set_max_task_retries(max_task_retries);
+    pipeline.set_retry_base_delay(Duration::from_millis(5));
+    (pipeline, transport, task_receiver)
+}
+
+fn create_task(conversation_id: &str) -> TaskEnvelopeWrapper {
+    TaskEnvelopeWrapper::V1(test_helpers::create_task(conversation_id, "Do some work"))
+}
+
+// ========== Fault Injection Tests ==========
+
+#[tokio::test]
+async fn test_duplicated_task_is_rejected_by_idempotency() {
+    let llm = Arc::new(MockLlmProvider::single_response("Done"));
+    let (pipeline, transport, mut tasks) = create_pipeline(
+        llm.clone(),
+        FaultPolicy::new(1).duplicate_task_numbers([0]),
+        0,
+    );
+    let task = create_task("duplicated");
+    let task_id = task.task_id();
+    transport.inner().deliver_task(task).await.unwrap();
+
+    let original = pipeline
+        .process_single_task(tasks.recv().await.unwrap())
+        .await;
+    let duplicate = pipeline
+        .process_single_task(tasks.recv().await.unwrap())
+        .await;
+
+    assert!(original.is_ok(), "original should succeed: {original:?}");
+    let error = duplicate
+        .expect_err("duplicate should be rejected")
+        .to_string();
+    assert!(error.contains("idempotency"), "{error}");
+    assert_eq!(transport.fault_log().duplicated_tasks, vec![task_id]);
+    assert_eq!(llm.calls(), 1);
+    let responses = transport.inner().published_responses();
+    assert_eq!(responses.len(), 1);
+    assert_eq!(responses[0].1.task_id, task_id);
+}
+
+#[tokio::test]
+async fn test_transient_publish_failure_is_retried() {
+    let llm = Arc::new(MockLlmProvider::single_response("Done"));
+    let (pipeline, transport, mut tasks) = create_pipeline(
+        llm.clone(),
+        FaultPolicy::new(2)
+            .only([PublishKind::Response])
+            .fail_publish_attempts([0]),
+        2,
+    );
+    let task = create_task("flaky-publish");
+    let task_id = task.task_id();
+    transport.inner().deliver_task(task).await.unwrap();
+
+    let result = pipeline
+        .process_single_task(tasks.recv().await.unwrap())
+        .await;
+
+    assert!(result.is_ok(), "retry should succeed: {result:?}");
+    assert_eq!(transport.fault_log().failed_publishes, vec![0]);
+    assert_eq!(llm.calls(), 2);
+    assert!(transport.inner().published_errors().is_empty());
+    let responses = transport.inner().published_responses();
+    assert_eq!(responses.len(), 1);
+    assert_eq!(responses[0].1.task_id, task_id);
+}
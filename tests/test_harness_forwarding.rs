@@ -0,0 +1,76 @@
+//! Integration tests running agents against the embedded-broker harness
+//!
+//! Unlike the mock transport tests, tasks here travel through a real MQTT
+//! broker, so topic names, subscriptions and payload encoding are exercised
+//! end to end. Requires the `test-harness` feature.
+
+#![cfg(feature = "test-harness")]
+
+mod test_helpers;
+
+use agent2389::protocol::messages::{NextTask, TaskEnvelopeWrapper};
+use agent2389::testing::harness::{HarnessError, TestHarness};
+use agent2389::testing::mocks::MockLlmProvider;
+use std::time::Duration;
+
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(15);
+
+// ========== Forwarding Tests ==========
+
+#[tokio::test]
+async fn test_task_forwarded_from_writer_to_editor() {
+    let mut harness = TestHarness::start().await.unwrap();
+    harness
+        .start_agent("writer", MockLlmProvider::single_response("draft"))
+        .await
+        .unwrap();
+    harness
+        .start_agent("editor", MockLlmProvider::single_response("final"))
+        .await
+        .unwrap();
+
+    let mut task = test_helpers::create_task("harness-forwarding", "Write a draft");
+    task.topic = "/control/agents/writer/input".to_string();
+    task.next = Some(Box::new(NextTask {
+        topic: "/control/agents/editor/input".to_string(),
+        instruction: Some("Edit the draft".to_string()),
+        input: None,
+        next: None,
+    }));
+    let task_id = task.task_id;
+    harness
+        .submit_task("writer", TaskEnvelopeWrapper::V1(task))
+        .await
+        .unwrap();
+
+    let response = harness
+        .await_response("harness-forwarding", "editor", RESPONSE_TIMEOUT)
+        .await
+        .unwrap();
+    assert_eq!(response.response, "final");
+    assert_eq!(response.parent_task_id, Some(task_id));
+
+    harness.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_await_response_times_out_without_an_answer() {
+    let mut harness = TestHarness::start().await.unwrap();
+    harness
+        .start_agent("writer", MockLlmProvider::single_response("draft"))
+        .await
+        .unwrap();
+
+    let mut task = test_helpers::create_task("harness-silence", "Write a draft");
+    task.topic = "/control/agents/writer/input".to_string();
+    harness
+        .submit_task("writer", TaskEnvelopeWrapper::V1(task))
+        .await
+        .unwrap();
+
+    // Nobody named editor is running, so only the writer ever answers
+    let result = harness
+        .await_response("harness-silence", "editor", Duration::from_millis(500))
+        .await;
+    assert!(matches!(result, Err(HarnessError::Timeout { .. })));
+}
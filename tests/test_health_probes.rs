@@ -0,0 +1,230 @@
+//! Integration tests for the `/livez` and `/readyz` probes
+//!
+//! Runs an agent lifecycle against mock dependencies and checks what its
+//! health server reports on each endpoint as the agent starts, loses its
+//! broker connection for good and shuts down.
+
+mod test_helpers;
+
+use agent2389::agent::lifecycle::AgentLifecycle;
+use agent2389::agent::AgentHost;
+use agent2389::observability::health::HealthServer;
+use agent2389::observability::probes::{PipelineState, StartupPhase};
+use agent2389::testing::mocks::{MockLlmProvider, MockTransport};
+use serde_json::Value;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+// ========== Test Helpers ==========
+
+/// Lifecycle reporting into a fresh health server, plus the server
+fn lifecycle_with(
+    llm: MockLlmProvider,
+) -> (
+    AgentLifecycle<MockTransport>,
+    MockTransport,
+    Arc<HealthServer>,
+) {
+    let transport = MockTransport::new();
+    // Shares the disconnect flag with the transport moved into the lifecycle
+    let handle = MockTransport {
+        permanently_disconnected: transport.permanently_disconnected.clone(),
+        ..MockTransport::default()
+    };
+    let mut lifecycle = AgentLifecycle::new(test_helpers::test_config(), transport, Box::new(llm));
+    let health_server = Arc::new(HealthServer::new("test-agent".to_string(), 0));
+    lifecycle.set_health_server(health_server.clone());
+    (lifecycle, handle, health_server)
+}
+
+fn serve(server: &Arc<HealthServer>) -> SocketAddr {
+    let (addr, serving) = server.clone().serve_on(([127, 0, 0, 1], 0));
+    tokio::spawn(serving);
+    addr
+}
+
+async fn get(addr: SocketAddr, path: &str) -> (u16, Value) {
+    let response = reqwest::get(format!("http://{addr}{path}")).await.unwrap();
+    let status = response.status().as_u16();
+    (status, response.json().await.unwrap())
+}
+
+// ========== Startup Tests ==========
+
+#[tokio::test]
+async fn test_not_ready_but_live_before_start() {
+    // Arrange
+    let (_lifecycle, _transport, health_server) =
+        lifecycle_with(MockLlmProvider::single_response("ok"));
+    let addr = serve(&health_server);
+
+    // Act
+    let (ready_status, ready) = get(addr, "/readyz").await;
+    let (live_status, live) = get(addr, "/livez").await;
+
+    // Assert
+    assert_eq!(ready_status, 503);
+    assert_eq!(ready["ready"], false);
+    assert_eq!(ready["phase"], "initializing");
+    assert_eq!(
+        ready["components"]["mqtt_subscription"]["status"],
+        "failing"
+    );
+    assert_eq!(live_status, 200);
+    assert_eq!(live["live"], true);
+    assert_eq!(live["pipeline"], "not_started");
+}
+
+#[tokio::test]
+async fn test_ready_once_started() {
+    // Arrange
+    let (mut lifecycle, _transport, health_server) =
+        lifecycle_with(MockLlmProvider::single_response("ok"));
+    let addr = serve(&health_server);
+    lifecycle.initialize().await.unwrap();
+
+    // Act
+    lifecycle.start().await.unwrap();
+    let (status, ready) = get(addr, "/readyz").await;
+    let (live_status, live) = get(addr, "/livez").await;
+
+    // Assert
+    assert_eq!(status, 200, "{ready}");
+    assert_eq!(ready["ready"], true);
+    assert_eq!(ready["phase"], "ready");
+    let components = ready["components"].as_object().unwrap();
+    for name in [
+        "mqtt_connection",
+        "mqtt_subscription",
+        "mqtt_transport",
+        "llm_provider",
+        "tools",
+        "pipeline",
+        "task_intake",
+    ] {
+        assert_eq!(components[name]["status"], "ok", "{name}");
+    }
+    assert_eq!(live_status, 200);
+    assert_eq!(live["pipeline"], "running");
+}
+
+#[tokio::test]
+async fn test_unreachable_llm_is_reported_on_readyz() {
+    // Arrange
+    let (mut lifecycle, _transport, health_server) =
+        lifecycle_with(MockLlmProvider::with_failure());
+    let addr = serve(&health_server);
+    lifecycle.initialize().await.unwrap();
+
+    // Act
+    let result = lifecycle.start().await;
+    let (status, ready) = get(addr, "/readyz").await;
+
+    // Assert: subscribed, but the failed check keeps the agent unready
+    assert!(result.is_err());
+    assert_eq!(status, 503);
+    assert_eq!(ready["phase"], "subscribed");
+    assert_eq!(ready["components"]["mqtt_subscription"]["status"], "ok");
+    assert_eq!(ready["components"]["llm_provider"]["status"], "failing");
+    assert!(ready["components"]["llm_provider"]["message"]
+        .as_str()
+        .unwrap()
+        .contains("Mock health check failure"));
+}
+
+// ========== Disconnection and Shutdown Tests ==========
+
+#[tokio::test]
+async fn test_permanent_disconnect_makes_agent_unready() {
+    // Arrange
+    let (mut lifecycle, transport, health_server) =
+        lifecycle_with(MockLlmProvider::single_response("ok"));
+    lifecycle.initialize().await.unwrap();
+    lifecycle.start().await.unwrap();
+    assert!(!lifecycle.is_permanently_disconnected());
+
+    // Act
+    transport.disconnect_permanently();
+    tokio::time::timeout(
+        Duration::from_secs(5),
+        lifecycle.wait_for_permanent_disconnect(),
+    )
+    .await
+    .expect("disconnect should be noticed");
+
+    // Assert: still live, as the process can be restarted cleanly
+    let probes = health_server.probes();
+    assert_eq!(probes.phase(), StartupPhase::Disconnected);
+    let addr = serve(&health_server);
+    let (status, ready) = get(addr, "/readyz").await;
+    assert_eq!(status, 503);
+    assert_eq!(ready["phase"], "disconnected");
+    assert_eq!(ready["components"]["mqtt_connection"]["status"], "failing");
+    let (live_status, _) = get(addr, "/livez").await;
+    assert_eq!(live_status, 200);
+}
+
+#[tokio::test]
+async fn test_shutdown_makes_agent_unready() {
+    // Arrange
+    let (mut lifecycle, _transport, health_server) =
+        lifecycle_with(MockLlmProvider::single_response("ok"));
+    lifecycle.initialize().await.unwrap();
+    lifecycle.start().await.unwrap();
+
+    // Act
+    lifecycle.shutdown().await.unwrap();
+
+    // Assert
+    let addr = serve(&health_server);
+    let (status, ready) = get(addr, "/readyz").await;
+    assert_eq!(status, 503);
+    assert_eq!(ready["phase"], "stopping");
+}
+
+// ========== Liveness Tests ==========
+
+#[tokio::test]
+async fn test_livez_fails_once_pipeline_stops() {
+    // Arrange
+    let health_server = Arc::new(HealthServer::new("wedged-agent".to_string(), 0));
+    health_server.probes().set_pipeline(PipelineState::Panicked);
+    let addr = serve(&health_server);
+
+    // Act
+    let (status, live) = get(addr, "/livez").await;
+
+    // Assert
+    assert_eq!(status, 503);
+    assert_eq!(live["live"], false);
+    assert_eq!(live["pipeline"], "panicked");
+}
+
+// ========== Hosted Agent Tests ==========
+
+#[tokio::test]
+async fn test_hosted_agents_serve_their_own_probes() {
+    // Arrange
+    let host_server = Arc::new(HealthServer::new("agent-host".to_string(), 0));
+    let mut host = AgentHost::new(host_server.clone());
+    host.add_agent(AgentLifecycle::new(
+        test_helpers::test_config(),
+        MockTransport::new(),
+        Box::new(MockLlmProvider::single_response("ok")),
+    ));
+    let addr = serve(&host_server);
+
+    // Act
+    host.start().await;
+    let (agent_status, agent_ready) = get(addr, "/agents/test-agent/readyz").await;
+    let (host_status, _) = get(addr, "/readyz").await;
+    host.shutdown().await;
+    let (stopped_status, _) = get(addr, "/readyz").await;
+
+    // Assert
+    assert_eq!(agent_status, 200, "{agent_ready}");
+    assert_eq!(agent_ready["phase"], "ready");
+    assert_eq!(host_status, 200);
+    assert_eq!(stopped_status, 503);
+}
@@ -1,7 +1,16 @@
 //! Test helpers and utilities for integration tests
 
+use agent2389::agent::processor::AgentProcessor;
 use agent2389::config::{AgentConfig, AgentSection, BudgetConfig, LlmSection, MqttSection};
+use agent2389::llm::provider::LlmProvider;
+use agent2389::protocol::messages::TaskEnvelope;
+use agent2389::secrets::SecretRef;
+use agent2389::testing::mocks::MockTransport;
+use agent2389::tools::ToolSystem;
+use serde_json::json;
 use std::collections::HashMap;
+use std::sync::Arc;
+use uuid::Uuid;
 
 /// Create a test configuration for integration tests
 #[allow(dead_code)]
@@ -11,23 +20,78 @@ pub fn test_config() -> AgentConfig {
             id: "test-agent".to_string(),
             description: "Test agent for integration tests".to_string(),
             capabilities: vec!["testing".to_string(), "mock-responses".to_string()],
+            max_input_bytes: None,
+            publish_acks: false,
+            max_concurrent_tasks: 1,
+            state_dir: None,
+            idempotency_ttl_secs: 86400,
+            max_task_retries: 0,
+            retry_base_delay_ms: 500,
+            pause_mode: Default::default(),
+            persistence: None,
+            max_task_age_secs: None,
+            idle_after_secs: None,
+            response_limit: None,
+            artifact_limits: Default::default(),
         },
         mqtt: MqttSection {
             broker_url: "mqtt://localhost:1883".to_string(),
             username_env: None,
             password_env: None,
             heartbeat_interval_secs: 900,
+            payload_format: Default::default(),
         },
         llm: LlmSection {
             provider: "anthropic".to_string(),
             model: "claude-sonnet-4-20250514".to_string(),
-            api_key_env: "ANTHROPIC_API_KEY".to_string(),
+            api_key_env: SecretRef::env("ANTHROPIC_API_KEY"),
             system_prompt: "You are a helpful AI agent.".to_string(),
             temperature: Some(0.7),
             max_tokens: Some(4000),
+            allowed_override_models: Vec::new(),
+            prices: Default::default(),
+            prompt_caching: false,
         },
         tools: HashMap::new(),
         budget: BudgetConfig::default(),
+        processing: Default::default(),
         routing: None, // V2 routing disabled by default in tests
+        security: Default::default(),
+        authorization: Default::default(),
+        progress: Default::default(),
+        observability: Default::default(),
+        testing: Default::default(),
+        protocol: Default::default(),
     }
 }
+
+/// Create a V1 task for the test agent
+#[allow(dead_code)]
+pub fn create_task(conversation_id: &str, instruction: &str) -> TaskEnvelope {
+    TaskEnvelope {
+        task_id: Uuid::new_v4(),
+        conversation_id: conversation_id.to_string(),
+        topic: "/control/agents/test-agent/input".to_string(),
+        instruction: Some(instruction.to_string()),
+        input: json!({}),
+        next: None,
+        deadline: None,
+        correlation_id: None,
+        parent_task_id: None,
+        published_at: None,
+        traceparent: None,
+        sender_id: None,
+    }
+}
+
+/// Create a processor publishing to a fresh mock transport
+#[allow(dead_code)]
+pub fn create_processor(
+    config: AgentConfig,
+    llm: Arc<dyn LlmProvider>,
+    tool_system: ToolSystem,
+) -> (AgentProcessor<MockTransport>, Arc<MockTransport>) {
+    let transport = Arc::new(MockTransport::new());
+    let processor = AgentProcessor::new(config, llm, Arc::new(tool_system), transport.clone());
+    (processor, transport)
+}
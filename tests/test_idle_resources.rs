@@ -0,0 +1,160 @@
+//! Integration tests for idle resource management
+//!
+//! Verifies that the pipeline releases the LLM provider's resources and
+//! notifies tools once no task has run for `idle_after_secs`, never while a
+//! task is in flight, and becomes active again when the next task arrives.
+//! Tokio time is paused, so the idle periods elapse instantly.
+
+mod test_helpers;
+
+use agent2389::agent::pipeline::AgentPipeline;
+use agent2389::observability::metrics::metrics;
+use agent2389::protocol::messages::TaskEnvelopeWrapper;
+use agent2389::testing::mocks::{MockLlmProvider, MockTransport};
+use agent2389::tools::{Tool, ToolDescription, ToolError, ToolSystem};
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+// ========== Test Helpers ==========
+
+/// Tool that counts how often it was told the agent went idle
+struct IdleAwareTool {
+    idle_calls: Arc<AtomicUsize>,
+}
+
+#[async_trait]
+impl Tool for IdleAwareTool {
+    fn describe(&self) -> ToolDescription {
+        ToolDescription {
+            name: "idle_aware_tool".to_string(),
+            description: "Counts idle notifications".to_string(),
+            parameters: json!({"type": "object"}),
+        }
+    }
+
+    async fn initialize(&mut self, _config: Option<&Value>) -> Result<(), ToolError> {
+        Ok(())
+    }
+
+    async fn execute(&self, _parameters: &Value) -> Result<Value, ToolError> {
+        Ok(json!({}))
+    }
+
+    async fn on_idle(&self) -> Result<(), ToolError> {
+        self.idle_calls.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    }
+}
+
+struct RunningPipeline {
+    sender: mpsc::Sender<TaskEnvelopeWrapper>,
+    transport: Arc<MockTransport>,
+    idle_calls: Arc<AtomicUsize>,
+    handle: JoinHandle<()>,
+}
+
+/// Run a pipeline that goes idle after 60 seconds without work
+fn start(llm: Arc<MockLlmProvider>) -> RunningPipeline {
+    let idle_calls = Arc::new(AtomicUsize::new(0));
+    let mut tool_system = ToolSystem::new();
+    tool_system.register_tool(
+        "idle_aware_tool",
+        Box::new(IdleAwareTool {
+            idle_calls: idle_calls.clone(),
+        }),
+    );
+    let mut config = test_helpers::test_config();
+    config.agent.idle_after_secs = Some(60);
+    let (processor, transport) = test_helpers::create_processor(config, llm, tool_system);
+    let (sender, receiver) = mpsc::channel(4);
+    let mut pipeline = AgentPipeline::new(processor, receiver, 16);
+    let handle = tokio::spawn(async move {
+        pipeline.run().await.expect("pipeline should not fail");
+    });
+
+    RunningPipeline {
+        sender,
+        transport,
+        idle_calls,
+        handle,
+    }
+}
+
+async fn send_task(pipeline: &RunningPipeline, label: &str) {
+    let task = test_helpers::create_task(&format!("idle-{label}"), label);
+    pipeline
+        .sender
+        .send(TaskEnvelopeWrapper::V1(task))
+        .await
+        .unwrap();
+}
+
+// ========== Idle Tests ==========
+
+#[tokio::test(start_paused = true)]
+async fn test_idle_period_releases_resources_until_next_task() {
+    // Arrange
+    let llm = Arc::new(MockLlmProvider::single_response("done"));
+    let pipeline = start(llm.clone());
+    send_task(&pipeline, "first").await;
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    assert_eq!(pipeline.transport.get_published_responses().await.len(), 1);
+    assert_eq!(llm.releases(), 0);
+
+    // Act: stay quiet past the idle period
+    tokio::time::sleep(Duration::from_secs(60)).await;
+
+    // Assert: resources released once, and only once while idle
+    assert_eq!(llm.releases(), 1);
+    assert_eq!(pipeline.idle_calls.load(Ordering::SeqCst), 1);
+    // Metrics are process-wide, but the other test here never leaves the idle state
+    assert!(metrics().is_idle());
+    tokio::time::sleep(Duration::from_secs(300)).await;
+    assert_eq!(llm.releases(), 1);
+
+    // The next task wakes the agent and is processed normally
+    send_task(&pipeline, "second").await;
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    assert_eq!(pipeline.transport.get_published_responses().await.len(), 2);
+    assert_eq!(llm.calls(), 2);
+
+    // A fresh idle period follows the wake-up
+    tokio::time::sleep(Duration::from_secs(60)).await;
+    assert_eq!(llm.releases(), 2);
+    assert_eq!(pipeline.idle_calls.load(Ordering::SeqCst), 2);
+
+    drop(pipeline.sender);
+    pipeline.handle.await.unwrap();
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_task_in_flight_keeps_agent_active() {
+    // A task taking longer than the idle period must not release its provider
+    let llm =
+        Arc::new(MockLlmProvider::single_response("done").with_delay(Duration::from_secs(120)));
+    let pipeline = start(llm.clone());
+    send_task(&pipeline, "slow").await;
+
+    tokio::time::sleep(Duration::from_secs(110)).await;
+    assert_eq!(llm.releases(), 0);
+    assert!(pipeline
+        .transport
+        .get_published_responses()
+        .await
+        .is_empty());
+
+    // The idle period counts from when the task finished
+    tokio::time::sleep(Duration::from_secs(50)).await;
+    assert_eq!(pipeline.transport.get_published_responses().await.len(), 1);
+    assert_eq!(llm.releases(), 0);
+    tokio::time::sleep(Duration::from_secs(30)).await;
+    assert_eq!(llm.releases(), 1);
+
+    drop(pipeline.sender);
+    pipeline.handle.await.unwrap();
+}
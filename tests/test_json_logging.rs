@@ -0,0 +1,186 @@
+//! Integration tests for the JSON log format
+//!
+//! Captures lines written by the JSON log layer and checks that each is a
+//! single JSON object carrying the ids of every enclosing task, tool and
+//! MQTT span, and that the layer can write to a rotating log file.
+
+use agent2389::observability::logging::json_layer;
+use agent2389::observability::rotating_file::RotatingFile;
+use agent2389::{mqtt_span, task_span, tool_span};
+use serde_json::Value;
+use std::io;
+use std::sync::{Arc, Mutex};
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::prelude::*;
+
+// ========== Test Helpers ==========
+
+/// In-memory log output shared with the layer
+#[derive(Clone, Default)]
+struct Captured(Arc<Mutex<Vec<u8>>>);
+
+impl io::Write for Captured {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Captured {
+    fn lines(&self) -> Vec<Value> {
+        String::from_utf8(self.0.lock().unwrap().clone())
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).expect("each line is one JSON object"))
+            .collect()
+    }
+}
+
+/// Run `f` with the JSON layer as the default subscriber and return its lines
+fn capture(span_events: FmtSpan, f: impl FnOnce()) -> Vec<Value> {
+    let captured = Captured::default();
+    let writer = captured.clone();
+    let subscriber =
+        tracing_subscriber::registry().with(json_layer(move || writer.clone(), span_events));
+    tracing::subscriber::with_default(subscriber, f);
+    captured.lines()
+}
+
+// ========== Field Enrichment Tests ==========
+
+#[test]
+fn test_log_line_inherits_task_and_tool_fields() {
+    // Arrange
+    let task_id = uuid::Uuid::new_v4();
+
+    // Act
+    let lines = capture(FmtSpan::NONE, || {
+        let task = task_span!(
+            task_id = task_id,
+            conversation_id = "conv-42",
+            agent_id = "researcher"
+        );
+        let _task = task.enter();
+        let tool = tool_span!(tool_name = "web_search");
+        let _tool = tool.enter();
+        tracing::info!(results = 5, cached = false, "Search finished");
+    });
+
+    // Assert
+    assert_eq!(lines.len(), 1);
+    let line = &lines[0];
+    assert_eq!(line["message"], "Search finished");
+    assert_eq!(line["level"], "INFO");
+    assert_eq!(line["target"], "test_json_logging");
+    assert!(line["timestamp"].as_str().unwrap().ends_with('Z'));
+    assert_eq!(line["task_id"], task_id.to_string());
+    assert_eq!(line["conversation_id"], "conv-42");
+    assert_eq!(line["agent_id"], "researcher");
+    assert_eq!(line["tool_name"], "web_search");
+    assert_eq!(line["results"], 5);
+    assert_eq!(line["cached"], false);
+    assert_eq!(
+        line["spans"],
+        serde_json::json!(["task_processing", "tool_execution"])
+    );
+}
+
+#[test]
+fn test_fields_recorded_later_and_extra_fields_are_included() {
+    // Act
+    let lines = capture(FmtSpan::NONE, || {
+        let span = mqtt_span!(
+            agent_id = "researcher",
+            operation = "receive",
+            topic = tracing::field::Empty
+        );
+        let _enter = span.enter();
+        tracing::debug!("before topic");
+        span.record("topic", "/control/agents/researcher/input");
+        tracing::warn!("after topic");
+    });
+
+    // Assert
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0]["agent_id"], "researcher");
+    assert_eq!(lines[0]["operation"], "receive");
+    assert!(lines[0].get("topic").is_none());
+    assert_eq!(lines[1]["topic"], "/control/agents/researcher/input");
+    assert_eq!(lines[1]["level"], "WARN");
+}
+
+#[test]
+fn test_line_outside_spans_has_no_span_fields() {
+    // Act
+    let lines = capture(FmtSpan::NONE, || tracing::info!("Agent started"));
+
+    // Assert
+    let line = lines[0].as_object().unwrap();
+    let mut keys: Vec<&str> = line.keys().map(String::as_str).collect();
+    keys.sort_unstable();
+    assert_eq!(keys, vec!["level", "message", "target", "timestamp"]);
+}
+
+#[test]
+fn test_span_close_events_carry_task_fields() {
+    // Act
+    let lines = capture(FmtSpan::CLOSE, || {
+        task_span!(
+            task_id = "task-1",
+            conversation_id = "conv-1",
+            agent_id = "a1"
+        )
+        .in_scope(|| {});
+    });
+
+    // Assert
+    assert_eq!(lines.len(), 1);
+    assert_eq!(lines[0]["message"], "close");
+    assert_eq!(lines[0]["task_id"], "task-1");
+    assert!(lines[0]["time.busy"].is_string());
+}
+
+// ========== Log File Tests ==========
+
+#[test]
+fn test_json_lines_rotate_in_log_file() {
+    // Arrange
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("agent.log");
+    let file = Arc::new(RotatingFile::open(&path, 300, 2).unwrap());
+    let subscriber = tracing_subscriber::registry().with(json_layer(file.clone(), FmtSpan::NONE));
+
+    // Act
+    tracing::subscriber::with_default(subscriber, || {
+        let span = task_span!(
+            task_id = "task-1",
+            conversation_id = "conv-1",
+            agent_id = "a1"
+        );
+        let _enter = span.enter();
+        for i in 0..10 {
+            tracing::info!(step = i, "Processing step");
+        }
+    });
+
+    // Assert
+    let mut steps = Vec::new();
+    for rotated in [file.rotated(2), file.rotated(1), path.clone()] {
+        for line in std::fs::read_to_string(rotated).unwrap().lines() {
+            let line: Value = serde_json::from_str(line).expect("lines are never split");
+            assert_eq!(line["task_id"], "task-1");
+            steps.push(line["step"].as_u64().unwrap());
+        }
+    }
+    assert!(!file.rotated(3).exists());
+    assert_eq!(
+        steps.last(),
+        Some(&9),
+        "newest lines are in the current file"
+    );
+    assert!(steps.windows(2).all(|w| w[0] < w[1]));
+}
@@ -68,6 +68,8 @@ impl LlmProvider for FailoverLlmProvider {
                     prompt_tokens: 10,
                     completion_tokens: 5,
                     total_tokens: 15,
+                    cache_read_tokens: None,
+                    cache_creation_tokens: None,
                 },
                 finish_reason: FinishReason::Stop,
                 tool_calls: None,
@@ -111,6 +113,12 @@ fn create_test_task(instruction: &str) -> TaskEnvelope {
         instruction: Some(instruction.to_string()),
         input: json!({}),
         next: None,
+        deadline: None,
+        correlation_id: None,
+        parent_task_id: None,
+        published_at: None,
+        traceparent: None,
+        sender_id: None,
     }
 }
 
@@ -249,6 +257,12 @@ async fn test_failover_preserves_context() {
         instruction: Some("Important instruction that must not be lost".to_string()),
         input: json!({"key": "value"}),
         next: None,
+        deadline: None,
+        correlation_id: None,
+        parent_task_id: None,
+        published_at: None,
+        traceparent: None,
+        sender_id: None,
     };
 
     // Act: Process task
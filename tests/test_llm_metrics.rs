@@ -0,0 +1,194 @@
+//! Integration tests for LLM request metrics
+//!
+//! Processes tasks against a mock provider and checks the request counts,
+//! token counters, prompt cache usage, latency histogram and estimated cost
+//! recorded in the global metrics snapshot. Each test uses its own model
+//! name, as the collector is shared by the tests in this binary.
+
+mod test_helpers;
+
+use agent2389::agent::pipeline::AgentPipeline;
+use agent2389::config::LlmPrice;
+use agent2389::observability::metrics::{metrics, LlmModelStats};
+use agent2389::protocol::messages::TaskEnvelopeWrapper;
+use agent2389::testing::mocks::MockLlmProvider;
+use agent2389::tools::ToolSystem;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+// ========== Test Helpers ==========
+
+/// Process one task per instruction with `llm` and the given price table
+async fn process_tasks(llm: MockLlmProvider, prices: &[(&str, LlmPrice)], instructions: &[&str]) {
+    let mut config = test_helpers::test_config();
+    config.llm.prices = prices
+        .iter()
+        .map(|(model, price)| (model.to_string(), *price))
+        .collect();
+    let (processor, _transport) =
+        test_helpers::create_processor(config, Arc::new(llm), ToolSystem::new());
+    let (_sender, receiver) = mpsc::channel(1);
+    let pipeline = AgentPipeline::new(processor, receiver, 16);
+    for instruction in instructions {
+        let task = test_helpers::create_task("llm-metrics", instruction);
+        let _ = pipeline
+            .process_single_task(TaskEnvelopeWrapper::V1(task))
+            .await;
+    }
+}
+
+fn model_stats(model: &str) -> LlmModelStats {
+    metrics().get_metrics().llm.models[model].clone()
+}
+
+// ========== Cost Tests ==========
+
+#[tokio::test]
+async fn test_cost_is_estimated_from_the_model_price() {
+    // Arrange: the provider reports a dated id of the priced model
+    let llm = MockLlmProvider::single_response("done")
+        .with_model("priced-model-2024-08-06")
+        .with_usage(1000, 200);
+    let prices = [
+        (
+            "priced-model",
+            LlmPrice {
+                prompt_per_million: 3.0,
+                completion_per_million: 15.0,
+            },
+        ),
+        (
+            "priced",
+            LlmPrice {
+                prompt_per_million: 100.0,
+                completion_per_million: 100.0,
+            },
+        ),
+    ];
+
+    // Act
+    process_tasks(llm, &prices, &["first", "second"]).await;
+
+    // Assert: (1000 * 3 + 200 * 15) / 1M per request, priced by the longest prefix
+    let stats = model_stats("priced-model-2024-08-06");
+    assert_eq!(stats.requests, 2);
+    assert_eq!(stats.errors, 0);
+    assert_eq!(stats.prompt_tokens, 2000);
+    assert_eq!(stats.completion_tokens, 400);
+    assert!(
+        (stats.estimated_cost_usd - 0.012).abs() < 1e-9,
+        "cost: {}",
+        stats.estimated_cost_usd
+    );
+    assert_eq!(stats.unpriced_requests, 0);
+    assert!(metrics().get_metrics().llm.total_estimated_cost_usd >= stats.estimated_cost_usd);
+}
+
+#[tokio::test]
+async fn test_unpriced_model_is_counted_without_cost() {
+    // Arrange
+    let llm = MockLlmProvider::single_response("done").with_model("unpriced-model");
+
+    // Act
+    process_tasks(llm, &[], &["only"]).await;
+
+    // Assert
+    let stats = model_stats("unpriced-model");
+    assert_eq!(stats.requests, 1);
+    assert_eq!(stats.estimated_cost_usd, 0.0);
+    assert_eq!(stats.unpriced_requests, 1);
+}
+
+// ========== Latency and Outcome Tests ==========
+
+#[tokio::test]
+async fn test_latency_lands_in_its_histogram_bucket() {
+    // Arrange
+    let llm = MockLlmProvider::single_response("done")
+        .with_model("slow-model")
+        .with_delay(Duration::from_millis(300));
+
+    // Act
+    process_tasks(llm, &[], &["slow"]).await;
+
+    // Assert
+    let stats = model_stats("slow-model");
+    assert!(stats.avg_latency_ms >= 300.0, "{}", stats.avg_latency_ms);
+    let bucket = stats
+        .latency_histogram
+        .iter()
+        .find(|bucket| bucket.count == 1)
+        .unwrap();
+    assert_eq!(bucket.le_ms, Some(500));
+    assert_eq!(
+        stats.latency_histogram.iter().map(|b| b.count).sum::<u64>(),
+        1
+    );
+}
+
+#[tokio::test]
+async fn test_failed_request_counts_as_error_for_requested_model() {
+    // Arrange: failures are recorded under the model that was requested
+    let llm = MockLlmProvider::with_failure();
+    let mut config = test_helpers::test_config();
+    config.llm.model = "failing-model".to_string();
+    let (processor, _transport) =
+        test_helpers::create_processor(config, Arc::new(llm), ToolSystem::new());
+    let (_sender, receiver) = mpsc::channel(1);
+    let pipeline = AgentPipeline::new(processor, receiver, 16);
+    let task = test_helpers::create_task("llm-metrics", "fail");
+
+    // Act
+    let result = pipeline
+        .process_single_task(TaskEnvelopeWrapper::V1(task))
+        .await;
+
+    // Assert
+    assert!(result.is_err());
+    let stats = model_stats("failing-model");
+    assert_eq!(stats.requests, 1);
+    assert_eq!(stats.errors, 1);
+    assert_eq!(stats.prompt_tokens, 0);
+    assert_eq!(stats.estimated_cost_usd, 0.0);
+    assert_eq!(
+        stats.latency_histogram.iter().map(|b| b.count).sum::<u64>(),
+        1
+    );
+}
+
+// ========== Prompt Cache Tests ==========
+
+#[tokio::test]
+async fn test_prompt_cache_usage_gives_a_cache_hit_ratio() {
+    // Arrange: each request reads 3000 prompt tokens from the cache
+    let llm = MockLlmProvider::single_response("done")
+        .with_model("cached-model")
+        .with_usage(1000, 200)
+        .with_cache_usage(3000, 0);
+
+    // Act
+    process_tasks(llm, &[], &["first", "second"]).await;
+
+    // Assert
+    let stats = model_stats("cached-model");
+    assert_eq!(stats.prompt_tokens, 2000);
+    assert_eq!(stats.cache_read_tokens, 6000);
+    assert_eq!(stats.cache_creation_tokens, 0);
+    assert_eq!(stats.cache_hit_ratio, Some(0.75));
+    assert!(metrics().get_metrics().llm.cache_hit_ratio.is_some());
+}
+
+#[tokio::test]
+async fn test_provider_without_cache_usage_has_no_cache_hit_ratio() {
+    // Arrange
+    let llm = MockLlmProvider::single_response("done").with_model("uncached-model");
+
+    // Act
+    process_tasks(llm, &[], &["only"]).await;
+
+    // Assert
+    let stats = model_stats("uncached-model");
+    assert_eq!(stats.cache_read_tokens, 0);
+    assert_eq!(stats.cache_hit_ratio, None);
+}
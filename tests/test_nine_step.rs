@@ -12,13 +12,17 @@
 mod test_helpers;
 
 use agent2389::agent::discovery::AgentRegistry;
+use agent2389::error::AgentError;
+use agent2389::llm::provider::{CompletionRequest, ResponseFormat};
 use agent2389::processing::nine_step::{NineStepProcessor, ProcessorConfig};
 use agent2389::protocol::messages::{NextTask, TaskEnvelope, TaskEnvelopeWrapper};
 use agent2389::routing::agent_selector::RoutingHelper;
-use agent2389::testing::mocks::{MockLlmProvider, MockTransport};
-use agent2389::tools::ToolSystem;
-use serde_json::json;
+use agent2389::testing::mocks::{MockLlmProvider, MockTransport, ScriptedTurn};
+use agent2389::tools::{Tool, ToolDescription, ToolError, ToolSystem};
+use async_trait::async_trait;
+use serde_json::{json, Value};
 use std::sync::Arc;
+use std::time::Duration;
 use uuid::Uuid;
 
 // ========== Test Helpers ==========
@@ -60,6 +64,12 @@ fn create_simple_task() -> TaskEnvelope {
         instruction: Some("Process this task".to_string()),
         input: json!({"test": "data"}),
         next: None,
+        deadline: None,
+        correlation_id: None,
+        parent_task_id: None,
+        published_at: None,
+        traceparent: None,
+        sender_id: None,
     }
 }
 
@@ -154,7 +164,7 @@ async fn test_nine_step_custom_max_depth_configuration() {
 
     let processor_config = ProcessorConfig {
         max_pipeline_depth: 5, // Custom lower limit
-        max_task_cache: 10000,
+        ..Default::default()
     };
 
     let processor = NineStepProcessor::with_config(
@@ -339,6 +349,12 @@ async fn test_nine_step_forwards_through_multiple_hops() {
                 next: None,
             })),
         })),
+        deadline: None,
+        correlation_id: None,
+        parent_task_id: None,
+        published_at: None,
+        traceparent: None,
+        sender_id: None,
     };
 
     let result = processor
@@ -487,6 +503,12 @@ async fn test_nine_step_rejects_duplicate_task_id_for_idempotency() {
         instruction: Some("First attempt".to_string()),
         input: json!({}),
         next: None,
+        deadline: None,
+        correlation_id: None,
+        parent_task_id: None,
+        published_at: None,
+        traceparent: None,
+        sender_id: None,
     };
 
     let task2 = TaskEnvelope {
@@ -496,6 +518,12 @@ async fn test_nine_step_rejects_duplicate_task_id_for_idempotency() {
         instruction: Some("Duplicate attempt".to_string()),
         input: json!({}),
         next: None,
+        deadline: None,
+        correlation_id: None,
+        parent_task_id: None,
+        published_at: None,
+        traceparent: None,
+        sender_id: None,
     };
 
     // First task should succeed
@@ -527,6 +555,48 @@ async fn test_nine_step_rejects_duplicate_task_id_for_idempotency() {
     );
 }
 
+#[tokio::test]
+async fn test_nine_step_idempotency_survives_restart_with_state_dir() {
+    let state_dir = tempfile::tempdir().unwrap();
+    let mut config = test_helpers::test_config();
+    config.agent.state_dir = Some(state_dir.path().to_path_buf());
+    let create_processor = || {
+        NineStepProcessor::new(
+            config.clone(),
+            Arc::new(MockLlmProvider::single_response("test response")),
+            Arc::new(ToolSystem::new()),
+            Arc::new(MockTransport::new()),
+        )
+    };
+    let task = create_simple_task();
+
+    let processor = create_processor();
+    let result = processor
+        .process_task(
+            TaskEnvelopeWrapper::V1(task.clone()),
+            "/control/agents/test-agent/input",
+            false,
+        )
+        .await;
+    assert!(result.is_ok(), "First delivery should process");
+    drop(processor);
+
+    // A restarted agent must reject the broker's redelivery
+    let restarted = create_processor();
+    assert!(restarted.has_processed(&task.task_id).await);
+    let result = restarted
+        .process_task(
+            TaskEnvelopeWrapper::V1(task),
+            "/control/agents/test-agent/input",
+            false,
+        )
+        .await;
+    assert!(
+        result.is_err(),
+        "Redelivered task should be rejected after restart"
+    );
+}
+
 #[tokio::test]
 async fn test_nine_step_rejects_topic_mismatch() {
     let processor = create_test_processor();
@@ -549,6 +619,67 @@ async fn test_nine_step_rejects_topic_mismatch() {
     );
 }
 
+#[tokio::test]
+async fn test_nine_step_topic_case_folding_is_opt_in() {
+    let mut task = create_simple_task();
+    task.topic = "/control/agents/Test-Agent/input".to_string();
+
+    let strict = create_test_processor()
+        .process_task(
+            TaskEnvelopeWrapper::V1(task.clone()),
+            "/control/agents/test-agent/input",
+            false,
+        )
+        .await;
+    let error = strict.unwrap_err().to_string();
+    assert!(
+        error.contains("the topics differ only in case"),
+        "Error should point at the case difference, got: {error}"
+    );
+
+    let mut config = test_helpers::test_config();
+    config.protocol.case_insensitive_topics = true;
+    let folding = NineStepProcessor::new(
+        config,
+        Arc::new(MockLlmProvider::single_response("test response")),
+        Arc::new(ToolSystem::new()),
+        Arc::new(MockTransport::new()),
+    );
+    let result = folding
+        .process_task(
+            TaskEnvelopeWrapper::V1(task),
+            "/control/agents/test-agent/input",
+            false,
+        )
+        .await;
+    assert!(result.is_ok(), "Case-insensitive topics should match");
+}
+
+#[tokio::test]
+async fn test_nine_step_refuses_to_forward_to_illegal_agent_topic() {
+    let processor = create_processor_with_routing();
+    let mut task = create_simple_task();
+    task.next = Some(Box::new(NextTask {
+        topic: "/control/agents/bad+target/input".to_string(),
+        instruction: Some("Static route".to_string()),
+        input: None,
+        next: None,
+    }));
+
+    let result = processor
+        .process_task(
+            TaskEnvelopeWrapper::V1(task),
+            "/control/agents/test-agent/input",
+            false,
+        )
+        .await;
+
+    let error = result.unwrap_err();
+    assert!(matches!(error, AgentError::InvalidInput { .. }));
+    assert!(error.to_string().contains("invalid character: '+'"));
+    assert!(processor.transport.get_published_tasks().await.is_empty());
+}
+
 // ========== Dynamic Routing Tests ==========
 
 #[tokio::test]
@@ -709,6 +840,243 @@ async fn test_nine_step_skips_publish_when_forwarding() {
     );
 }
 
+// ========== Tool Loop Tests ==========
+
+/// Tool answering with its name and the parameters it was called with
+struct EchoTool(&'static str);
+
+#[async_trait]
+impl Tool for EchoTool {
+    fn describe(&self) -> ToolDescription {
+        ToolDescription {
+            name: self.0.to_string(),
+            description: format!("Echoes {} parameters", self.0),
+            parameters: json!({"type": "object"}),
+        }
+    }
+
+    async fn initialize(&mut self, _config: Option<&Value>) -> Result<(), ToolError> {
+        Ok(())
+    }
+
+    async fn execute(&self, parameters: &Value) -> Result<Value, ToolError> {
+        Ok(json!({"tool": self.0, "echo": parameters}))
+    }
+}
+
+#[tokio::test]
+async fn test_nine_step_runs_both_scripted_tool_calls_before_answering() {
+    let llm = Arc::new(MockLlmProvider::scripted(vec![
+        ScriptedTurn::tool_call("web_search", json!({"query": "rust"}))
+            .and_tool_call("fetch", json!({"url": "https://rust-lang.org"}))
+            .expecting_message("Process this task")
+            .expecting_tools(&["fetch", "web_search"]),
+        ScriptedTurn::answer("Rust is a systems language")
+            .expecting_message(r#"Tool web_search returned: {"echo":{"query":"rust"}"#)
+            .expecting_message(r#"Tool fetch returned: {"echo":{"url":"https://rust-lang.org"}"#),
+    ]));
+    let mut tool_system = ToolSystem::new();
+    tool_system.register_tool("web_search", Box::new(EchoTool("web_search")));
+    tool_system.register_tool("fetch", Box::new(EchoTool("fetch")));
+    let transport = Arc::new(MockTransport::new());
+    let processor = NineStepProcessor::new(
+        test_helpers::test_config(),
+        llm.clone(),
+        Arc::new(tool_system),
+        transport.clone(),
+    );
+    let task = create_simple_task();
+
+    processor
+        .process_task(
+            TaskEnvelopeWrapper::V1(task.clone()),
+            "/control/agents/test-agent/input",
+            false,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(llm.remaining_turns(), 0);
+    let requests = llm.requests();
+    assert_eq!(requests.len(), 2);
+    let tool_results = &requests[1].messages.last().unwrap().content;
+    assert!(
+        tool_results.starts_with("Tool results:\n"),
+        "{tool_results}"
+    );
+    let response = transport.assert_response_published(&task.conversation_id);
+    assert_eq!(response.response, "Rust is a systems language");
+}
+
+/// Name of the JSON schema a request asks the answer to follow, if any
+fn response_schema(request: &CompletionRequest) -> Option<&str> {
+    match &request.response_format {
+        Some(ResponseFormat::JsonSchema { json_schema }) => Some(json_schema.name.as_str()),
+        _ => None,
+    }
+}
+
+fn processor_with_echo_tool(llm: Arc<MockLlmProvider>) -> NineStepProcessor<MockTransport> {
+    let mut tool_system = ToolSystem::new();
+    tool_system.register_tool("web_search", Box::new(EchoTool("web_search")));
+    NineStepProcessor::new(
+        test_helpers::test_config(),
+        llm,
+        Arc::new(tool_system),
+        Arc::new(MockTransport::new()),
+    )
+}
+
+#[tokio::test]
+async fn test_v2_task_with_tools_ends_with_route_decision_request() {
+    let decision = json!({
+        "schema_version": "1.0",
+        "result": "Rust is a systems language",
+        "workflow_complete": true
+    });
+    let llm = Arc::new(MockLlmProvider::scripted(vec![
+        ScriptedTurn::tool_call("web_search", json!({"query": "rust"}))
+            .expecting_tools(&["web_search"]),
+        ScriptedTurn::answer("Rust is a systems language").expecting_message("Tool results"),
+        ScriptedTurn::answer(decision.to_string()).expecting_message("RouteDecision"),
+    ]));
+    let processor = processor_with_echo_tool(llm.clone());
+    let task = create_simple_task();
+
+    processor
+        .process_task(
+            TaskEnvelopeWrapper::V2(task.clone().upgrade(None)),
+            "/control/agents/test-agent/input",
+            false,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(llm.remaining_turns(), 0);
+    let requests = llm.requests();
+    assert_eq!(requests.len(), 3);
+    // Tool rounds offer tools and leave the format open
+    assert_eq!(response_schema(&requests[0]), None);
+    assert_eq!(response_schema(&requests[1]), None);
+    // The closing turn is forced into the RouteDecision schema without tools
+    assert_eq!(response_schema(&requests[2]), Some("RouteDecision"));
+    assert!(requests[2].tools.is_none());
+    let response = processor
+        .transport
+        .assert_response_published(&task.conversation_id);
+    assert_eq!(response.response, "Rust is a systems language");
+}
+
+#[tokio::test]
+async fn test_v1_task_with_tools_skips_route_decision_request() {
+    let llm = Arc::new(MockLlmProvider::scripted(vec![
+        ScriptedTurn::tool_call("web_search", json!({"query": "rust"})),
+        ScriptedTurn::answer("Rust is a systems language"),
+    ]));
+    let processor = processor_with_echo_tool(llm.clone());
+
+    processor
+        .process_task(
+            TaskEnvelopeWrapper::V1(create_simple_task()),
+            "/control/agents/test-agent/input",
+            false,
+        )
+        .await
+        .unwrap();
+
+    let requests = llm.requests();
+    assert_eq!(requests.len(), 2);
+    assert!(requests
+        .iter()
+        .all(|request| response_schema(request).is_none()));
+}
+
+#[tokio::test]
+async fn test_v2_task_out_of_tool_rounds_still_requests_route_decision() {
+    // Every completion asks for another tool round
+    let llm = Arc::new(
+        MockLlmProvider::single_response(r#"{"result": "partial", "workflow_complete": true}"#)
+            .with_tool_call("web_search"),
+    );
+    let processor = processor_with_echo_tool(llm.clone());
+
+    let result = processor
+        .process_task(
+            TaskEnvelopeWrapper::V2(create_simple_task().upgrade(None)),
+            "/control/agents/test-agent/input",
+            false,
+        )
+        .await;
+
+    assert!(result.is_ok(), "{result:?}");
+    let requests = llm.requests();
+    assert_eq!(requests.len(), 11, "ten tool rounds and the closing turn");
+    assert_eq!(response_schema(&requests[10]), Some("RouteDecision"));
+    assert!(requests[..10]
+        .iter()
+        .all(|request| response_schema(request).is_none()));
+}
+
+#[tokio::test]
+async fn test_configured_tool_iteration_cap_stops_tool_loop() {
+    let llm = Arc::new(MockLlmProvider::scripted(vec![
+        ScriptedTurn::tool_call("web_search", json!({"query": "one"})),
+        ScriptedTurn::tool_call("web_search", json!({"query": "two"})),
+        ScriptedTurn::tool_call("web_search", json!({"query": "three"})),
+    ]));
+    let mut config = test_helpers::test_config();
+    config.processing.max_tool_iterations = 2;
+    let mut tool_system = ToolSystem::new();
+    tool_system.register_tool("web_search", Box::new(EchoTool("web_search")));
+    let processor = NineStepProcessor::new(
+        config,
+        llm.clone(),
+        Arc::new(tool_system),
+        Arc::new(MockTransport::new()),
+    );
+
+    let error = processor
+        .process_task(
+            TaskEnvelopeWrapper::V1(create_simple_task()),
+            "/control/agents/test-agent/input",
+            false,
+        )
+        .await
+        .unwrap_err();
+
+    assert!(
+        error.to_string().contains("maximum iterations (2)"),
+        "{error}"
+    );
+    assert_eq!(llm.requests().len(), 2);
+    assert_eq!(llm.remaining_turns(), 1);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_configured_task_timeout_fails_slow_task() {
+    let llm =
+        Arc::new(MockLlmProvider::single_response("late").with_delay(Duration::from_secs(30)));
+    let mut config = test_helpers::test_config();
+    config.processing.task_timeout_secs = Some(5);
+    let processor = NineStepProcessor::new(
+        config,
+        llm,
+        Arc::new(ToolSystem::new()),
+        Arc::new(MockTransport::new()),
+    );
+
+    let error = processor
+        .process_task(
+            TaskEnvelopeWrapper::V1(create_simple_task()),
+            "/control/agents/test-agent/input",
+            false,
+        )
+        .await
+        .unwrap_err();
+
+    assert!(matches!(error, AgentError::Timeout { .. }), "{error:?}");
+}
+
 // ========== Edge Cases and Boundary Conditions ==========
 
 #[tokio::test]
@@ -795,6 +1163,12 @@ async fn test_nine_step_preserves_task_id_through_processing() {
         instruction: Some("Test".to_string()),
         input: json!({}),
         next: None,
+        deadline: None,
+        correlation_id: None,
+        parent_task_id: None,
+        published_at: None,
+        traceparent: None,
+        sender_id: None,
     };
 
     let result = processor
@@ -845,8 +1219,8 @@ async fn test_nine_step_idempotency_cache_respects_max_cache_size() {
     let transport = Arc::new(MockTransport::new());
 
     let processor_config = ProcessorConfig {
-        max_pipeline_depth: 16,
         max_task_cache: 5, // Small cache for testing
+        ..Default::default()
     };
 
     let processor = NineStepProcessor::with_config(
@@ -35,6 +35,7 @@ fn test_request(model: &str) -> CompletionRequest {
         max_tokens: Some(100),
         temperature: Some(0.7),
         top_p: None,
+        seed: None,
         stop_sequences: None,
         tools: None,
         tool_choice: None,
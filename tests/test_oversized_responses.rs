@@ -0,0 +1,134 @@
+//! Integration tests for oversized response handling
+//!
+//! Runs tasks whose responses exceed `[agent.response_limit]` through the
+//! pipeline and checks that they are truncated with a marker, or written to
+//! a file the published response refers to, and that a failed write is
+//! reported as an ErrorMessage instead of losing the result silently.
+
+mod test_helpers;
+
+use agent2389::agent::pipeline::AgentPipeline;
+use agent2389::config::{AgentConfig, OversizedResponseStrategy, ResponseLimitConfig};
+use agent2389::protocol::messages::{ErrorCode, TaskEnvelopeWrapper};
+use agent2389::testing::mocks::{MockLlmProvider, MockTransport};
+use agent2389::tools::ToolSystem;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+// ========== Test Helpers ==========
+
+const LIMIT: usize = 1024;
+
+fn limited_config(
+    strategy: OversizedResponseStrategy,
+    artifact_dir: Option<PathBuf>,
+) -> AgentConfig {
+    let mut config = test_helpers::test_config();
+    config.agent.response_limit = Some(ResponseLimitConfig {
+        max_response_bytes: LIMIT,
+        strategy,
+        artifact_dir,
+    });
+    config
+}
+
+fn create_pipeline(
+    config: AgentConfig,
+    response: &str,
+) -> (AgentPipeline<MockTransport>, Arc<MockTransport>) {
+    let llm = Arc::new(MockLlmProvider::single_response(response));
+    let (processor, transport) = test_helpers::create_processor(config, llm, ToolSystem::new());
+    let (_task_sender, task_receiver) = mpsc::channel(1);
+    (AgentPipeline::new(processor, task_receiver, 16), transport)
+}
+
+async fn run_task(pipeline: &AgentPipeline<MockTransport>) {
+    let task = test_helpers::create_task("oversized-conversation", "Write a long report");
+    // The outcome is checked through what was published
+    let _ = pipeline
+        .process_single_task(TaskEnvelopeWrapper::V1(task))
+        .await;
+}
+
+// ========== Oversized Response Tests ==========
+
+#[tokio::test]
+async fn test_response_within_limit_is_published_unchanged() {
+    let config = limited_config(OversizedResponseStrategy::Truncate, None);
+    let (pipeline, transport) = create_pipeline(config, "A short report");
+
+    run_task(&pipeline).await;
+
+    let responses = transport.get_published_responses().await;
+    assert_eq!(responses.len(), 1);
+    assert_eq!(responses[0].1.response, "A short report");
+    assert_eq!(responses[0].1.oversized, None);
+}
+
+#[tokio::test]
+async fn test_truncate_publishes_start_of_response_with_marker() {
+    let report = "findings ".repeat(1000);
+    let config = limited_config(OversizedResponseStrategy::Truncate, None);
+    let (pipeline, transport) = create_pipeline(config, &report);
+
+    run_task(&pipeline).await;
+
+    let responses = transport.get_published_responses().await;
+    assert_eq!(responses.len(), 1);
+    let response = &responses[0].1;
+    assert!(response.response.len() <= LIMIT);
+    assert!(response.response.starts_with("findings findings"));
+    assert!(response
+        .response
+        .ends_with(&format!("[Response truncated from {} bytes]", report.len())));
+    let oversized = response.oversized.clone().expect("oversized note");
+    assert_eq!(oversized.original_bytes, report.len());
+    assert_eq!(oversized.reference, None);
+    assert!(transport.get_published_errors().await.is_empty());
+}
+
+#[tokio::test]
+async fn test_reference_writes_full_response_and_publishes_its_path() {
+    let artifacts = tempfile::tempdir().unwrap();
+    let report = "findings ".repeat(1000);
+    let config = limited_config(
+        OversizedResponseStrategy::Reference,
+        Some(artifacts.path().to_path_buf()),
+    );
+    let (pipeline, transport) = create_pipeline(config, &report);
+
+    run_task(&pipeline).await;
+
+    let responses = transport.get_published_responses().await;
+    assert_eq!(responses.len(), 1);
+    let response = &responses[0].1;
+    let oversized = response.oversized.clone().expect("oversized note");
+    assert_eq!(oversized.original_bytes, report.len());
+    let reference = oversized.reference.expect("reference to the full response");
+    assert_eq!(
+        PathBuf::from(&reference),
+        artifacts.path().join(format!("{}.txt", response.task_id))
+    );
+    assert_eq!(std::fs::read_to_string(&reference).unwrap(), report);
+    assert!(response.response.len() <= LIMIT);
+    assert!(response.response.contains(&reference));
+}
+
+#[tokio::test]
+async fn test_failed_reference_write_publishes_error() {
+    let dir = tempfile::tempdir().unwrap();
+    let not_a_directory = dir.path().join("artifacts");
+    std::fs::write(&not_a_directory, "in the way").unwrap();
+    let config = limited_config(OversizedResponseStrategy::Reference, Some(not_a_directory));
+    let (pipeline, transport) = create_pipeline(config, &"findings ".repeat(1000));
+
+    run_task(&pipeline).await;
+
+    assert!(transport.get_published_responses().await.is_empty());
+    let errors = transport.get_published_errors().await;
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].0, "oversized-conversation");
+    assert_eq!(errors[0].1.error.code, ErrorCode::InternalError);
+    assert!(errors[0].1.error.message.contains("response directory"));
+}
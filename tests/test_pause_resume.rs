@@ -0,0 +1,235 @@
+//! Integration tests for pausing and resuming task intake
+//!
+//! Verifies that no tasks are processed while the agent is paused, that
+//! buffered tasks run after resuming (explicitly or by auto-resume), that
+//! reject mode answers paused tasks with a retryable error, and that the
+//! paused status is announced.
+
+mod test_helpers;
+
+use agent2389::agent::pipeline::AgentPipeline;
+use agent2389::config::PauseMode;
+use agent2389::protocol::messages::{
+    AdminMessage, AgentStatusType, ErrorCode, PauseAgent, TaskEnvelopeWrapper,
+};
+use agent2389::testing::mocks::{MockLlmProvider, MockTransport};
+use agent2389::tools::ToolSystem;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+// ========== Test Helpers ==========
+
+/// A running pipeline and the channels that feed it
+struct Harness {
+    llm: Arc<MockLlmProvider>,
+    transport: Arc<MockTransport>,
+    tasks: mpsc::Sender<TaskEnvelopeWrapper>,
+    admin: mpsc::Sender<AdminMessage>,
+    paused: Arc<AtomicBool>,
+    handle: JoinHandle<()>,
+}
+
+impl Harness {
+    fn start(pause_mode: PauseMode, llm_delay: Duration) -> Self {
+        let llm = Arc::new(MockLlmProvider::single_response("Done").with_delay(llm_delay));
+        let (processor, transport) = test_helpers::create_processor(
+            test_helpers::test_config(),
+            llm.clone(),
+            ToolSystem::new(),
+        );
+        let (tasks, task_receiver) = mpsc::channel(16);
+        let (admin, admin_receiver) = mpsc::channel(4);
+        let mut pipeline = AgentPipeline::new(processor, task_receiver, 16);
+        pipeline.set_admin_receiver(admin_receiver);
+        pipeline.set_pause_mode(pause_mode);
+        let paused = pipeline.paused_flag();
+
+        let handle = tokio::spawn(async move {
+            pipeline.run().await.expect("pipeline should not fail");
+        });
+        Self {
+            llm,
+            transport,
+            tasks,
+            admin,
+            paused,
+            handle,
+        }
+    }
+
+    async fn pause(&self, pause: PauseAgent) {
+        self.admin
+            .send(AdminMessage::PauseAgent(pause))
+            .await
+            .unwrap();
+    }
+
+    async fn resume(&self) {
+        self.admin.send(AdminMessage::ResumeAgent).await.unwrap();
+    }
+
+    async fn send_task(&self) -> Uuid {
+        let conversation_id = format!("conversation-{}", Uuid::new_v4());
+        let task = test_helpers::create_task(&conversation_id, "Do some work");
+        let task_id = task.task_id;
+        self.tasks
+            .send(TaskEnvelopeWrapper::V1(task))
+            .await
+            .unwrap();
+        task_id
+    }
+
+    /// Whether the pipeline reports itself paused
+    fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+
+    async fn statuses(&self) -> Vec<AgentStatusType> {
+        self.transport
+            .get_published_statuses()
+            .await
+            .into_iter()
+            .map(|status| status.status)
+            .collect()
+    }
+
+    /// Wait until `count` responses have been published
+    async fn wait_for_responses(&self, count: usize) {
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while self.transport.get_published_responses().await.len() < count {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("responses should be published");
+    }
+
+    /// Close the task channel and wait for the pipeline to stop
+    async fn stop(self) {
+        drop(self.tasks);
+        tokio::time::timeout(Duration::from_secs(5), self.handle)
+            .await
+            .expect("pipeline should stop")
+            .unwrap();
+    }
+}
+
+/// Give the pipeline time to act on what was sent
+async fn settle() {
+    tokio::time::sleep(Duration::from_millis(100)).await;
+}
+
+// ========== Pause/Resume Tests ==========
+
+#[tokio::test]
+async fn test_buffered_tasks_run_after_resume() {
+    let harness = Harness::start(PauseMode::Buffer, Duration::ZERO);
+
+    harness.pause(PauseAgent::default()).await;
+    settle().await;
+    let first = harness.send_task().await;
+    let second = harness.send_task().await;
+    settle().await;
+
+    // Nothing is processed while paused
+    assert_eq!(harness.llm.calls(), 0);
+    assert!(harness.transport.get_published_responses().await.is_empty());
+    assert_eq!(harness.statuses().await, vec![AgentStatusType::Paused]);
+    assert!(harness.is_paused());
+
+    harness.resume().await;
+    harness.wait_for_responses(2).await;
+    assert!(!harness.is_paused());
+
+    let responses = harness.transport.get_published_responses().await;
+    let task_ids: Vec<Uuid> = responses.iter().map(|(_, r)| r.task_id).collect();
+    assert_eq!(task_ids, vec![first, second]);
+    assert_eq!(
+        harness.statuses().await,
+        vec![AgentStatusType::Paused, AgentStatusType::Available]
+    );
+    harness.stop().await;
+}
+
+#[tokio::test]
+async fn test_auto_resume_after_timeout() {
+    let harness = Harness::start(PauseMode::Buffer, Duration::ZERO);
+
+    harness
+        .pause(PauseAgent {
+            auto_resume_secs: Some(1),
+            ..Default::default()
+        })
+        .await;
+    settle().await;
+    harness.send_task().await;
+    settle().await;
+    assert_eq!(harness.llm.calls(), 0);
+
+    // No resume message: the auto-resume timeout releases the held task
+    harness.wait_for_responses(1).await;
+    assert_eq!(
+        harness.statuses().await,
+        vec![AgentStatusType::Paused, AgentStatusType::Available]
+    );
+    harness.stop().await;
+}
+
+#[tokio::test]
+async fn test_reject_mode_rejects_paused_tasks() {
+    let harness = Harness::start(PauseMode::Reject, Duration::ZERO);
+
+    harness.pause(PauseAgent::default()).await;
+    settle().await;
+    let task_id = harness.send_task().await;
+    settle().await;
+
+    assert_eq!(harness.llm.calls(), 0);
+    let errors = harness.transport.get_published_errors().await;
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].1.task_id, task_id);
+    assert_eq!(errors[0].1.error.code, ErrorCode::Overloaded);
+    assert!(errors[0].1.error.retryable);
+
+    // Rejected tasks are not replayed after resuming
+    harness.resume().await;
+    settle().await;
+    assert_eq!(harness.llm.calls(), 0);
+    harness.stop().await;
+}
+
+#[tokio::test]
+async fn test_drain_announces_pause_after_in_flight_work() {
+    let harness = Harness::start(PauseMode::Buffer, Duration::from_millis(200));
+
+    harness.send_task().await;
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while harness.llm.calls() == 0 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+    })
+    .await
+    .expect("task should start");
+
+    harness
+        .pause(PauseAgent {
+            drain: true,
+            ..Default::default()
+        })
+        .await;
+    settle().await;
+
+    // The task in progress keeps running and the pause is not yet announced
+    assert!(harness.statuses().await.is_empty());
+    assert!(!harness.is_paused());
+
+    harness.wait_for_responses(1).await;
+    settle().await;
+    assert_eq!(harness.statuses().await, vec![AgentStatusType::Paused]);
+    assert!(harness.is_paused());
+    harness.stop().await;
+}
@@ -53,6 +53,12 @@ fn create_test_task(instruction: &str) -> TaskEnvelope {
         instruction: Some(instruction.to_string()),
         input: json!({}),
         next: None,
+        deadline: None,
+        correlation_id: None,
+        parent_task_id: None,
+        published_at: None,
+        traceparent: None,
+        sender_id: None,
     }
 }
 
@@ -401,6 +407,8 @@ impl LlmProvider for CountingLlmProvider {
                 prompt_tokens: 10,
                 completion_tokens: 5,
                 total_tokens: 15,
+                cache_read_tokens: None,
+                cache_creation_tokens: None,
             },
             finish_reason: FinishReason::Stop,
             tool_calls: None,
@@ -0,0 +1,164 @@
+//! Integration tests for structured tool and LLM progress metadata
+//!
+//! Verifies that tool and LLM progress events carry parseable metadata
+//! with a bounded result preview instead of whole results in the message,
+//! and that `max_metadata_bytes` caps the published metadata.
+
+mod test_helpers;
+
+use agent2389::agent::pipeline::AgentPipeline;
+use agent2389::agent::processor::AgentProcessor;
+use agent2389::progress::metadata::RESULT_PREVIEW_CHARS;
+use agent2389::progress::subscriber::decode_progress_payload;
+use agent2389::progress::{BroadcastProgress, ProgressEventType, ProgressMessage};
+use agent2389::protocol::messages::TaskEnvelopeWrapper;
+use agent2389::testing::mocks::{MockLlmProvider, MockTransport};
+use agent2389::tools::{Tool, ToolDescription, ToolError, ToolSystem};
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::sync::{broadcast, mpsc};
+
+// ========== Test Helpers ==========
+
+/// Tool returning a result far larger than a progress event should carry
+struct BigResultTool;
+
+#[async_trait]
+impl Tool for BigResultTool {
+    fn describe(&self) -> ToolDescription {
+        ToolDescription {
+            name: "fetch".to_string(),
+            description: "Fetches a large document".to_string(),
+            parameters: json!({"type": "object"}),
+        }
+    }
+
+    async fn initialize(&mut self, _config: Option<&Value>) -> Result<(), ToolError> {
+        Ok(())
+    }
+
+    async fn execute(&self, _parameters: &Value) -> Result<Value, ToolError> {
+        Ok(json!({"document": "x".repeat(10_000)}))
+    }
+}
+
+fn tools() -> ToolSystem {
+    let mut tool_system = ToolSystem::new();
+    tool_system.register_tool("fetch", Box::new(BigResultTool));
+    tool_system
+}
+
+fn llm() -> Arc<MockLlmProvider> {
+    Arc::new(
+        MockLlmProvider::single_response("done")
+            .with_tool_call("fetch")
+            .tool_rounds(1),
+    )
+}
+
+async fn run(processor: AgentProcessor<MockTransport>) {
+    let (_task_sender, task_receiver) = mpsc::channel(1);
+    AgentPipeline::new(processor, task_receiver, 16)
+        .process_single_task(TaskEnvelopeWrapper::V1(test_helpers::create_task(
+            "metadata-conversation",
+            "Fetch it",
+        )))
+        .await
+        .unwrap();
+}
+
+fn event(events: &[ProgressMessage], event_type: ProgressEventType) -> &ProgressMessage {
+    events
+        .iter()
+        .find(|e| e.event_type == event_type)
+        .unwrap_or_else(|| panic!("no {event_type:?} event in {events:?}"))
+}
+
+// ========== Progress Metadata Tests ==========
+
+#[tokio::test]
+async fn test_tool_and_llm_events_carry_structured_metadata() {
+    // Arrange: observe every event, unfiltered and uncapped
+    let (sender, mut receiver) = broadcast::channel(256);
+    let processor = AgentProcessor::with_progress(
+        test_helpers::test_config(),
+        llm(),
+        Arc::new(tools()),
+        Arc::new(MockTransport::new()),
+        Arc::new(BroadcastProgress::new("test-agent".to_string(), sender)),
+    );
+
+    // Act
+    run(processor).await;
+
+    // Assert
+    let mut events = Vec::new();
+    while let Ok(event) = receiver.try_recv() {
+        events.push(event);
+    }
+    let complete = event(&events, ProgressEventType::ToolComplete);
+    assert!(complete.message.starts_with("Tool 'fetch' completed in "));
+    assert!(
+        complete.message.len() < 100,
+        "message: {}",
+        complete.message
+    );
+    let metadata = complete.metadata.as_ref().unwrap();
+    assert_eq!(metadata["tool_name"], "fetch");
+    assert!(metadata["duration_ms"].is_u64());
+    assert!(metadata["result_size_bytes"].as_u64().unwrap() > 10_000);
+    assert_eq!(
+        metadata["result_preview"].as_str().unwrap().chars().count(),
+        RESULT_PREVIEW_CHARS
+    );
+    assert_eq!(metadata["result_truncated"], true);
+
+    let call = event(&events, ProgressEventType::ToolCall);
+    assert_eq!(call.metadata.as_ref().unwrap()["tool_name"], "fetch");
+
+    let request = event(&events, ProgressEventType::LlmRequest);
+    assert!(request.metadata.as_ref().unwrap()["message_count"].is_u64());
+    let response = event(&events, ProgressEventType::LlmResponse);
+    let metadata = response.metadata.as_ref().unwrap();
+    for field in [
+        "model",
+        "duration_ms",
+        "finish_reason",
+        "prompt_tokens",
+        "completion_tokens",
+        "total_tokens",
+        "tool_calls",
+    ] {
+        assert!(metadata.get(field).is_some(), "missing {field}: {metadata}");
+    }
+    assert_eq!(metadata["tool_calls"], 1);
+}
+
+#[tokio::test]
+async fn test_published_metadata_is_capped_at_max_metadata_bytes() {
+    // Arrange
+    let mut config = test_helpers::test_config();
+    config.progress.max_metadata_bytes = 120;
+    let (processor, transport) = test_helpers::create_processor(config, llm(), tools());
+
+    // Act
+    run(processor).await;
+
+    // Assert
+    let events: Vec<ProgressMessage> = transport
+        .get_published_messages()
+        .await
+        .iter()
+        .filter(|(topic, _)| topic.starts_with("/control/agents/test-agent/progress"))
+        .flat_map(|(_, payload)| decode_progress_payload(payload).unwrap())
+        .collect();
+    let metadata = event(&events, ProgressEventType::ToolComplete)
+        .metadata
+        .as_ref()
+        .unwrap();
+    assert!(serde_json::to_vec(metadata).unwrap().len() <= 120);
+    assert_eq!(metadata["tool_name"], "fetch");
+    assert_eq!(metadata["metadata_truncated"], true);
+    assert!(metadata.get("result_preview").is_none());
+}
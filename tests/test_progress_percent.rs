@@ -0,0 +1,106 @@
+//! Integration tests for percent-complete progress reporting
+//!
+//! Verifies that processing a task publishes PercentComplete progress events
+//! that never go backwards, include sub-progress for each tool round, and
+//! end at 100%.
+
+mod test_helpers;
+
+use agent2389::agent::pipeline::AgentPipeline;
+use agent2389::progress::{ProgressEventType, ProgressMessage};
+use agent2389::protocol::messages::TaskEnvelopeWrapper;
+use agent2389::testing::mocks::MockLlmProvider;
+use agent2389::tools::{Tool, ToolDescription, ToolError, ToolSystem};
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+// ========== Test Helpers ==========
+
+/// Tool that always finds what it looks up
+struct LookupTool;
+
+#[async_trait]
+impl Tool for LookupTool {
+    fn describe(&self) -> ToolDescription {
+        ToolDescription {
+            name: "lookup".to_string(),
+            description: "Looks things up".to_string(),
+            parameters: json!({"type": "object"}),
+        }
+    }
+
+    async fn initialize(&mut self, _config: Option<&Value>) -> Result<(), ToolError> {
+        Ok(())
+    }
+
+    async fn execute(&self, _parameters: &Value) -> Result<Value, ToolError> {
+        Ok(json!({"found": true}))
+    }
+}
+
+/// Progress events in a published payload, which is an object or an array
+fn decode(payload: &[u8]) -> Vec<ProgressMessage> {
+    match serde_json::from_slice::<Value>(payload).unwrap() {
+        Value::Array(events) => events
+            .into_iter()
+            .map(|event| serde_json::from_value(event).unwrap())
+            .collect(),
+        event => vec![serde_json::from_value(event).unwrap()],
+    }
+}
+
+// ========== Percent Progress Tests ==========
+
+#[tokio::test]
+async fn test_percent_never_decreases_across_a_task() {
+    // Arrange: two tool rounds before the final answer
+    let mut tool_system = ToolSystem::new();
+    tool_system.register_tool("lookup", Box::new(LookupTool));
+    let llm = Arc::new(
+        MockLlmProvider::single_response("done")
+            .with_tool_call("lookup")
+            .tool_rounds(2),
+    );
+    let (processor, transport) =
+        test_helpers::create_processor(test_helpers::test_config(), llm.clone(), tool_system);
+    let (_task_sender, task_receiver) = mpsc::channel(1);
+    let pipeline = AgentPipeline::new(processor, task_receiver, 16);
+    let task = test_helpers::create_task("percent-conversation", "Look it up");
+    let task_id = task.task_id.to_string();
+
+    // Act
+    pipeline
+        .process_single_task(TaskEnvelopeWrapper::V1(task))
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(llm.calls(), 3);
+    let events: Vec<ProgressMessage> = transport
+        .get_published_messages()
+        .await
+        .iter()
+        .filter(|(topic, _)| topic == "/control/agents/test-agent/progress")
+        .flat_map(|(_, payload)| decode(payload))
+        .collect();
+    let percents: Vec<f32> = events
+        .iter()
+        .filter(|event| event.event_type == ProgressEventType::PercentComplete)
+        .inspect(|event| assert_eq!(event.task_id.as_deref(), Some(task_id.as_str())))
+        .map(|event| event.percent.expect("percent events carry a percent"))
+        .collect();
+    // Nine steps plus one update per tool round
+    assert_eq!(percents.len(), 11, "percents: {percents:?}");
+    assert!(
+        percents.windows(2).all(|pair| pair[0] <= pair[1]),
+        "percent went backwards: {percents:?}"
+    );
+    assert_eq!(percents.last(), Some(&100.0));
+    // Only percent events carry a percent
+    assert!(events
+        .iter()
+        .filter(|event| event.event_type != ProgressEventType::PercentComplete)
+        .all(|event| event.percent.is_none()));
+}
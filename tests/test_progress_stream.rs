@@ -0,0 +1,80 @@
+//! Integration tests for the health server's `/progress/stream` endpoint
+//!
+//! Connects an HTTP client to a running health server and verifies that
+//! progress reported through `BroadcastProgress` arrives as Server-Sent
+//! Events, filtered by the query parameters.
+
+use agent2389::observability::health::HealthServer;
+use agent2389::progress::{BroadcastProgress, Progress, ProgressMessage};
+use std::sync::Arc;
+use std::time::Duration;
+
+// ========== Test Helpers ==========
+
+/// Read SSE frames until one carries a progress message with `wanted` as its message
+async fn read_until(response: &mut reqwest::Response, wanted: &str) -> Vec<ProgressMessage> {
+    let mut buffer = String::new();
+    let mut events = Vec::new();
+    loop {
+        let chunk = tokio::time::timeout(Duration::from_secs(5), response.chunk())
+            .await
+            .expect("progress event should arrive")
+            .unwrap()
+            .expect("stream should stay open");
+        buffer.push_str(std::str::from_utf8(&chunk).unwrap());
+
+        while let Some(end) = buffer.find("\n\n") {
+            let frame: String = buffer.drain(..end + 2).collect();
+            for data in frame.lines().filter_map(|line| line.strip_prefix("data:")) {
+                events.push(serde_json::from_str::<ProgressMessage>(data.trim()).unwrap());
+            }
+        }
+        if events.iter().any(|event| event.message == wanted) {
+            return events;
+        }
+    }
+}
+
+// ========== Progress Stream Tests ==========
+
+#[tokio::test]
+async fn test_stream_delivers_only_matching_progress() {
+    // Arrange
+    let health_server = Arc::new(HealthServer::new("stream-agent".to_string(), 0));
+    let progress =
+        BroadcastProgress::new("stream-agent".to_string(), health_server.progress_sender());
+    let (addr, server) = health_server.serve_on(([127, 0, 0, 1], 0));
+    tokio::spawn(server);
+
+    let mut response = reqwest::get(format!(
+        "http://{addr}/progress/stream?conversation_id=watched"
+    ))
+    .await
+    .unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(
+        response.headers()["content-type"],
+        "text/event-stream",
+        "progress is served as Server-Sent Events"
+    );
+
+    // Act
+    progress
+        .report_task_start("task-1", "watched", "watched start")
+        .await;
+    progress
+        .report_task_start("task-2", "ignored", "ignored start")
+        .await;
+    progress
+        .report_progress_percent("task-1", "watched", 50.0, "watched halfway")
+        .await;
+
+    // Assert
+    let events = read_until(&mut response, "watched halfway").await;
+    let messages: Vec<&str> = events.iter().map(|e| e.message.as_str()).collect();
+    assert_eq!(messages, vec!["watched start", "watched halfway"]);
+    assert_eq!(events[1].percent, Some(50.0));
+    assert!(events
+        .iter()
+        .all(|event| event.conversation_id.as_deref() == Some("watched")));
+}
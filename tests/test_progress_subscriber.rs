@@ -0,0 +1,117 @@
+//! Integration tests for per-conversation progress topics
+//!
+//! Verifies that agents publish progress on
+//! `/conversations/{conversation_id}/progress` when enabled, and that
+//! `ProgressSubscriber` streams those events, filtered by task, through the
+//! mock transport.
+
+mod test_helpers;
+
+use agent2389::agent::pipeline::AgentPipeline;
+use agent2389::progress::{ProgressEventType, ProgressMessage, ProgressSubscriber};
+use agent2389::protocol::messages::TaskEnvelopeWrapper;
+use agent2389::testing::mocks::MockLlmProvider;
+use agent2389::tools::ToolSystem;
+use agent2389::transport::Transport;
+use futures::StreamExt;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+// ========== Test Helpers ==========
+
+/// Read the stream until the task completes
+async fn until_complete(
+    stream: &mut agent2389::progress::subscriber::ProgressStream,
+) -> Vec<ProgressMessage> {
+    let mut events = Vec::new();
+    loop {
+        let event = tokio::time::timeout(Duration::from_secs(5), stream.next())
+            .await
+            .expect("progress should arrive")
+            .expect("stream should stay open");
+        let done = event.event_type == ProgressEventType::TaskComplete;
+        events.push(event);
+        if done {
+            return events;
+        }
+    }
+}
+
+// ========== Conversation Progress Tests ==========
+
+#[tokio::test]
+async fn test_subscriber_follows_one_task_of_a_conversation() {
+    // Arrange
+    let mut config = test_helpers::test_config();
+    config.progress.conversation_topics = true;
+    let (processor, transport) = test_helpers::create_processor(
+        config,
+        Arc::new(MockLlmProvider::single_response("done")),
+        ToolSystem::new(),
+    );
+    let (_task_sender, task_receiver) = mpsc::channel(1);
+    let pipeline = AgentPipeline::new(processor, task_receiver, 16);
+    let watched = test_helpers::create_task("shared-conversation", "First");
+    let other = test_helpers::create_task("shared-conversation", "Second");
+    let watched_id = watched.task_id.to_string();
+
+    let mut stream = ProgressSubscriber::new("shared-conversation")
+        .with_task_id(watched_id.clone())
+        .subscribe(transport.as_ref())
+        .await
+        .unwrap();
+
+    // Act
+    for task in [other, watched] {
+        pipeline
+            .process_single_task(TaskEnvelopeWrapper::V1(task))
+            .await
+            .unwrap();
+    }
+
+    // Assert
+    let events = until_complete(&mut stream).await;
+    assert_eq!(events[0].event_type, ProgressEventType::TaskStart);
+    assert!(events
+        .iter()
+        .all(|event| event.task_id.as_deref() == Some(watched_id.as_str())));
+    // Per-agent topics still carry every event
+    let published = transport.get_published_messages().await;
+    assert!(published
+        .iter()
+        .any(|(topic, _)| topic == "/control/agents/test-agent/progress"));
+}
+
+#[tokio::test]
+async fn test_conversation_topics_are_off_by_default() {
+    // Arrange
+    let (processor, transport) = test_helpers::create_processor(
+        test_helpers::test_config(),
+        Arc::new(MockLlmProvider::single_response("done")),
+        ToolSystem::new(),
+    );
+    let (_task_sender, task_receiver) = mpsc::channel(1);
+    let pipeline = AgentPipeline::new(processor, task_receiver, 16);
+    let mut subscription = transport
+        .subscribe_topic("/conversations/quiet-conversation/progress")
+        .await
+        .unwrap();
+
+    // Act
+    pipeline
+        .process_single_task(TaskEnvelopeWrapper::V1(test_helpers::create_task(
+            "quiet-conversation",
+            "Hello",
+        )))
+        .await
+        .unwrap();
+
+    // Assert
+    assert!(subscription.try_recv().is_err());
+    assert!(!transport
+        .get_published_messages()
+        .await
+        .iter()
+        .any(|(topic, _)| topic.starts_with("/conversations/")));
+}
@@ -0,0 +1,268 @@
+//! Integration tests for the Prometheus exposition of agent metrics
+//!
+//! Renders a collector's metrics and parses them back with a Prometheus
+//! text parser, then checks that `/metrics` on a running health server
+//! serves the same format to scrapers while plain clients keep JSON.
+
+use agent2389::observability::health::HealthServer;
+use agent2389::observability::MetricsCollector;
+use prometheus_parse::{Sample, Scrape, Value};
+use std::io::BufRead;
+use std::sync::Arc;
+use std::time::Duration;
+
+// ========== Test Helpers ==========
+
+fn parse(text: &str) -> Scrape {
+    Scrape::parse(text.as_bytes().lines()).expect("exposition should parse")
+}
+
+/// The sample of `metric` whose labels include every pair in `labels`
+fn find<'a>(scrape: &'a Scrape, metric: &str, labels: &[(&str, &str)]) -> &'a Sample {
+    scrape
+        .samples
+        .iter()
+        .find(|sample| {
+            sample.metric == metric
+                && labels
+                    .iter()
+                    .all(|(key, value)| sample.labels.get(key) == Some(*value))
+        })
+        .unwrap_or_else(|| panic!("no sample {metric} with labels {labels:?}"))
+}
+
+fn populated_collector() -> MetricsCollector {
+    let collector = MetricsCollector::new();
+    collector.set_agent_state("running");
+    for _ in 0..3 {
+        collector.task_received();
+        collector.task_processing_started();
+    }
+    collector.task_processing_completed(Duration::from_millis(120));
+    collector.task_processing_completed(Duration::from_millis(80));
+    collector.task_processing_failed(Duration::from_millis(30));
+    collector.mqtt_connection_established();
+    collector.mqtt_connection_lost();
+    collector.mqtt_connection_established();
+    collector.llm_request_completed("gpt-4o", 100, 25, Duration::from_millis(900), Some(0.5));
+    collector.llm_request_failed("gpt-4o", Duration::from_millis(15000));
+    collector.tool_executed("web_search", Duration::from_millis(40), true);
+    collector.tool_executed("web_search", Duration::from_millis(700), false);
+    collector
+}
+
+// ========== Exposition Tests ==========
+
+#[test]
+fn test_exposition_parses_with_agent_id_on_every_sample() {
+    // Arrange
+    let collector = populated_collector();
+
+    // Act
+    let text = collector.render_prometheus("agent-7");
+    let scrape = parse(&text);
+
+    // Assert
+    assert!(!scrape.samples.is_empty());
+    for sample in &scrape.samples {
+        assert!(sample.metric.starts_with("agent2389_"), "{}", sample.metric);
+        assert_eq!(sample.labels.get("agent_id"), Some("agent-7"));
+    }
+    assert_eq!(
+        scrape.docs["agent2389_tasks_total"],
+        "Tasks that finished processing, by outcome"
+    );
+    for line in text.lines().filter(|line| line.starts_with("# TYPE")) {
+        let name = line.split_whitespace().nth(2).unwrap();
+        assert!(scrape.docs.contains_key(name), "{name} has no HELP line");
+    }
+}
+
+#[test]
+fn test_exposition_values() {
+    // Arrange
+    let collector = populated_collector();
+
+    // Act
+    let scrape = parse(&collector.render_prometheus("agent-7"));
+
+    // Assert
+    let value = |metric: &str, labels: &[(&str, &str)]| find(&scrape, metric, labels).value.clone();
+    assert_eq!(
+        value("agent2389_tasks_total", &[("outcome", "completed")]),
+        Value::Counter(2.0)
+    );
+    assert_eq!(
+        value("agent2389_tasks_total", &[("outcome", "failed")]),
+        Value::Counter(1.0)
+    );
+    assert_eq!(
+        value("agent2389_agent_state", &[("state", "running")]),
+        Value::Gauge(1.0)
+    );
+    assert_eq!(
+        value("agent2389_agent_state", &[("state", "stopped")]),
+        Value::Gauge(0.0)
+    );
+    assert_eq!(
+        value("agent2389_mqtt_reconnects_total", &[]),
+        Value::Counter(1.0)
+    );
+    assert_eq!(value("agent2389_mqtt_connected", &[]), Value::Gauge(1.0));
+    assert_eq!(
+        value(
+            "agent2389_llm_tokens_total",
+            &[("model", "gpt-4o"), ("kind", "prompt")]
+        ),
+        Value::Counter(100.0)
+    );
+    assert_eq!(
+        value(
+            "agent2389_llm_requests_total",
+            &[("model", "gpt-4o"), ("outcome", "error")]
+        ),
+        Value::Counter(1.0)
+    );
+    assert_eq!(
+        value(
+            "agent2389_llm_estimated_cost_usd_total",
+            &[("model", "gpt-4o")]
+        ),
+        Value::Counter(0.5)
+    );
+    assert_eq!(
+        value(
+            "agent2389_tool_executions_total",
+            &[("tool", "web_search"), ("outcome", "failure")]
+        ),
+        Value::Counter(1.0)
+    );
+}
+
+#[test]
+fn test_llm_latency_histogram_by_model() {
+    // Arrange
+    let collector = populated_collector();
+
+    // Act
+    let scrape = parse(&collector.render_prometheus("agent-7"));
+
+    // Assert
+    let Value::Histogram(buckets) = &find(
+        &scrape,
+        "agent2389_llm_request_duration_seconds",
+        &[("model", "gpt-4o")],
+    )
+    .value
+    else {
+        panic!("LLM latency should be a histogram");
+    };
+    let count_at = |le: f64| {
+        buckets
+            .iter()
+            .find(|bucket| bucket.less_than == le)
+            .map(|bucket| bucket.count)
+    };
+    assert_eq!(count_at(1.0), Some(1.0));
+    assert_eq!(count_at(10.0), Some(1.0));
+    assert_eq!(count_at(20.0), Some(2.0));
+    assert_eq!(count_at(f64::INFINITY), Some(2.0));
+}
+
+#[test]
+fn test_tool_latency_histogram_is_cumulative() {
+    // Arrange
+    let collector = populated_collector();
+
+    // Act
+    let scrape = parse(&collector.render_prometheus("agent-7"));
+
+    // Assert
+    let Value::Histogram(buckets) = &find(
+        &scrape,
+        "agent2389_tool_duration_seconds",
+        &[("tool", "web_search")],
+    )
+    .value
+    else {
+        panic!("tool latency should be a histogram");
+    };
+    let count_at = |le: f64| {
+        buckets
+            .iter()
+            .find(|bucket| bucket.less_than == le)
+            .map(|bucket| bucket.count)
+    };
+    assert_eq!(count_at(0.05), Some(1.0));
+    assert_eq!(count_at(0.5), Some(1.0));
+    assert_eq!(count_at(1.0), Some(2.0));
+    assert_eq!(count_at(f64::INFINITY), Some(2.0));
+    assert!(buckets.windows(2).all(|w| w[0].count <= w[1].count));
+
+    let sum = find(&scrape, "agent2389_tool_duration_seconds_sum", &[]);
+    assert_eq!(sum.value, Value::Untyped(0.74));
+}
+
+#[test]
+fn test_label_values_are_escaped() {
+    // Arrange
+    let collector = MetricsCollector::new();
+    collector.tool_executed("odd\"tool\\name\nx", Duration::from_millis(1), true);
+
+    // Act
+    let text = collector.render_prometheus("agent \"quoted\"");
+
+    // Assert
+    assert!(text.contains(r#"agent_id="agent \"quoted\"""#));
+    assert!(text.contains(r#"tool="odd\"tool\\name\nx""#));
+    assert!(
+        text.lines().all(|line| !line.is_empty()),
+        "a raw newline would split a sample"
+    );
+    parse(&text);
+}
+
+// ========== HTTP Endpoint Tests ==========
+
+#[tokio::test]
+async fn test_metrics_endpoint_negotiates_format() {
+    // Arrange
+    let health_server = Arc::new(HealthServer::new("scraped-agent".to_string(), 0));
+    let (addr, server) = health_server.serve_on(([127, 0, 0, 1], 0));
+    tokio::spawn(server);
+    let client = reqwest::Client::new();
+
+    // Act
+    let scraped = client
+        .get(format!("http://{addr}/metrics"))
+        .header(
+            "accept",
+            "application/openmetrics-text;version=1.0.0;q=0.75,text/plain;version=0.0.4;q=0.5,*/*;q=0.1",
+        )
+        .send()
+        .await
+        .unwrap();
+    let plain = client
+        .get(format!("http://{addr}/metrics"))
+        .header("accept", "*/*")
+        .send()
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(scraped.status(), 200);
+    assert!(scraped.headers()["content-type"]
+        .to_str()
+        .unwrap()
+        .starts_with("text/plain; version=0.0.4"));
+    let scrape = parse(&scraped.text().await.unwrap());
+    find(
+        &scrape,
+        "agent2389_tasks_total",
+        &[("agent_id", "scraped-agent")],
+    );
+
+    assert_eq!(plain.headers()["content-type"], "application/json");
+    let json: serde_json::Value = plain.json().await.unwrap();
+    assert!(json["tasks"]["tasks_received"].is_u64());
+}
@@ -14,6 +14,7 @@ use agent2389::config::{
 };
 use agent2389::llm::provider::LlmProvider;
 use agent2389::protocol::messages::{TaskEnvelopeV2, WorkflowContext};
+use agent2389::secrets::SecretRef;
 use agent2389::testing::mocks::{AgentDecision, MockLlmProvider};
 use agent2389::tools::ToolSystem;
 use agent2389::transport::MqttTransport;
@@ -39,33 +40,66 @@ fn create_test_agent_config(
             id: agent_id.to_string(),
             description: format!("{agent_id} agent for realistic workflow testing"),
             capabilities,
+            max_input_bytes: None,
+            publish_acks: false,
+            max_concurrent_tasks: 1,
+            state_dir: None,
+            idempotency_ttl_secs: 86400,
+            max_task_retries: 0,
+            retry_base_delay_ms: 500,
+            pause_mode: Default::default(),
+            persistence: None,
+            max_task_age_secs: None,
+            idle_after_secs: None,
+            response_limit: None,
+            artifact_limits: Default::default(),
         },
         mqtt: MqttSection {
             broker_url: MQTT_BROKER_URL.to_string(),
             username_env: None,
             password_env: None,
             heartbeat_interval_secs: 900,
+            payload_format: Default::default(),
         },
         llm: LlmSection {
             provider: "openai".to_string(),
             model: "gpt-4o-mini".to_string(),
-            api_key_env: "OPENAI_API_KEY".to_string(),
+            api_key_env: SecretRef::env("OPENAI_API_KEY"),
             system_prompt: system_prompt.to_string(),
             temperature: Some(0.7),
             max_tokens: Some(2000),
+            allowed_override_models: Vec::new(),
+            prices: Default::default(),
+            prompt_caching: false,
         },
         tools: HashMap::new(),
         budget: BudgetConfig::default(),
+        processing: Default::default(),
         routing: Some(RoutingConfig {
             strategy: RoutingStrategy::Llm,
             max_iterations: 10,
+            cycle_repeat_threshold: 2,
+            dry_run: false,
+            auto_correct_agent_ids: false,
+            workflow_budget_secs: None,
             llm: Some(LlmRouterConfig {
                 provider: "openai".to_string(),
                 model: "gpt-4o-mini".to_string(),
                 temperature: 0.1,
             }),
             gatekeeper: None,
+            rules: None,
+            cache: None,
+            selection: None,
+            sticky: None,
+            audit_log: None,
         }),
+        security: Default::default(),
+        authorization: Default::default(),
+        progress: Default::default(),
+        observability: Default::default(),
+        testing: Default::default(),
+        protocol: Default::default(),
     }
 }
 
@@ -304,8 +338,18 @@ async fn test_realistic_research_write_edit_workflow() {
             original_query: "Create an article on Rust async programming".to_string(),
             steps_completed: vec![],
             iteration_count: 0,
+            started_at: None,
+            budget_secs: None,
+            state: None,
+            state_order: Vec::new(),
         }),
         routing_trace: None,
+        deadline: None,
+        correlation_id: None,
+        parent_task_id: None,
+        published_at: None,
+        traceparent: None,
+        sender_id: None,
     };
 
     // Run the workflow with 30 second timeout
@@ -347,8 +391,18 @@ async fn test_realistic_iterative_refinement_workflow() {
             original_query: "Create a high-quality technical article".to_string(),
             steps_completed: vec![],
             iteration_count: 0,
+            started_at: None,
+            budget_secs: None,
+            state: None,
+            state_order: Vec::new(),
         }),
         routing_trace: None,
+        deadline: None,
+        correlation_id: None,
+        parent_task_id: None,
+        published_at: None,
+        traceparent: None,
+        sender_id: None,
     };
 
     let result = timeout(
@@ -448,8 +502,18 @@ async fn test_realistic_max_iterations_enforcement() {
             original_query: "Test max iterations".to_string(),
             steps_completed: vec![],
             iteration_count: 0,
+            started_at: None,
+            budget_secs: None,
+            state: None,
+            state_order: Vec::new(),
         }),
         routing_trace: None,
+        deadline: None,
+        correlation_id: None,
+        parent_task_id: None,
+        published_at: None,
+        traceparent: None,
+        sender_id: None,
     };
 
     // Should complete (forced by max_iterations) within 30 seconds
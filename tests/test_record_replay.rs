@@ -0,0 +1,190 @@
+//! Integration tests for recording and replaying LLM and tool calls
+//!
+//! Records a mock-driven task that calls a tool, then replays the recording
+//! with neither the provider nor the tool, and checks the agent publishes
+//! the same response byte for byte.
+
+mod test_helpers;
+
+use agent2389::agent::pipeline::AgentPipeline;
+use agent2389::agent::processor::AgentProcessor;
+use agent2389::llm::provider::LlmProvider;
+use agent2389::protocol::messages::{ResponseMessage, TaskEnvelope, TaskEnvelopeWrapper};
+use agent2389::testing::mocks::{MockLlmProvider, MockTransport};
+use agent2389::testing::recording::{
+    Recorder, Recording, RecordingLlmProvider, RecordingTool, ReplayLlmProvider, ReplayToolSystem,
+};
+use agent2389::tools::{Tool, ToolDescription, ToolError, ToolSystem};
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+// ========== Test Helpers ==========
+
+/// Tool counting how often it actually runs
+struct CountingTool {
+    calls: Arc<AtomicUsize>,
+}
+
+#[async_trait]
+impl Tool for CountingTool {
+    fn describe(&self) -> ToolDescription {
+        ToolDescription {
+            name: "fetch".to_string(),
+            description: "Fetches a page".to_string(),
+            parameters: json!({"type": "object"}),
+        }
+    }
+
+    async fn initialize(&mut self, _config: Option<&Value>) -> Result<(), ToolError> {
+        Ok(())
+    }
+
+    async fn execute(&self, _parameters: &Value) -> Result<Value, ToolError> {
+        let call = self.calls.fetch_add(1, Ordering::SeqCst);
+        Ok(json!({"page": "hello", "call": call}))
+    }
+}
+
+async fn run(
+    llm: Arc<dyn LlmProvider>,
+    tools: ToolSystem,
+    task: TaskEnvelope,
+) -> Arc<MockTransport> {
+    let transport = Arc::new(MockTransport::new());
+    let processor = AgentProcessor::new(
+        test_helpers::test_config(),
+        llm,
+        Arc::new(tools),
+        transport.clone(),
+    );
+    let (_task_sender, task_receiver) = mpsc::channel(1);
+    let _ = AgentPipeline::new(processor, task_receiver, 16)
+        .process_single_task(TaskEnvelopeWrapper::V1(task))
+        .await;
+    transport
+}
+
+async fn published_responses(transport: &MockTransport) -> Vec<Vec<u8>> {
+    transport
+        .published_responses
+        .lock()
+        .await
+        .iter()
+        .map(|(_, response)| {
+            // Only the completion time differs between runs
+            let response = ResponseMessage {
+                completed_at: None,
+                ..response.clone()
+            };
+            serde_json::to_vec(&response).unwrap()
+        })
+        .collect()
+}
+
+/// Record `task` into `dir`, returning what the agent published and how often the tool ran
+async fn record(dir: &std::path::Path, task: TaskEnvelope) -> (Vec<Vec<u8>>, usize) {
+    let recorder = Recorder::create(dir).unwrap();
+    let calls = Arc::new(AtomicUsize::new(0));
+    let mut tools = ToolSystem::new();
+    tools.register_tool(
+        "fetch",
+        Box::new(CountingTool {
+            calls: calls.clone(),
+        }),
+    );
+    recorder
+        .record_tools(&tools.describe_tools())
+        .await
+        .unwrap();
+    tools.wrap_tools(|name, tool| Box::new(RecordingTool::new(name, tool, recorder.clone())));
+    let llm = Arc::new(RecordingLlmProvider::new(
+        Arc::new(
+            MockLlmProvider::new(vec![
+                "Fetching".to_string(),
+                "The page says hello".to_string(),
+            ])
+            .with_tool_call("fetch")
+            .tool_rounds(1),
+        ),
+        recorder,
+    ));
+
+    let transport = run(llm, tools, task).await;
+    (
+        published_responses(&transport).await,
+        calls.load(Ordering::SeqCst),
+    )
+}
+
+async fn replay(dir: &std::path::Path, task: TaskEnvelope) -> Arc<MockTransport> {
+    let recording = Arc::new(Recording::load(dir).unwrap());
+    run(
+        Arc::new(ReplayLlmProvider::new(recording.clone())),
+        ReplayToolSystem::build(recording),
+        task,
+    )
+    .await
+}
+
+// ========== Record and Replay Tests ==========
+
+#[tokio::test]
+async fn test_replayed_task_publishes_identical_response() {
+    let dir = tempfile::tempdir().unwrap();
+    // Without one the agent mints a new correlation id on every run
+    let task = TaskEnvelope {
+        correlation_id: Some("recorded-workflow".to_string()),
+        ..test_helpers::create_task("recorded-conversation", "Fetch the page")
+    };
+
+    let (recorded, tool_runs) = record(dir.path(), task.clone()).await;
+    assert_eq!(recorded.len(), 1);
+    assert_eq!(tool_runs, 1);
+    let files = std::fs::read_dir(dir.path()).unwrap().count();
+    // Two completions, one tool call and the tool descriptions
+    assert_eq!(files, 4);
+
+    let transport = replay(dir.path(), task).await;
+    assert_eq!(published_responses(&transport).await, recorded);
+    assert!(transport.published_errors.lock().await.is_empty());
+}
+
+#[tokio::test]
+async fn test_unseen_request_fails_with_diff() {
+    let dir = tempfile::tempdir().unwrap();
+    record(
+        dir.path(),
+        test_helpers::create_task("recorded-conversation", "Fetch the page"),
+    )
+    .await;
+
+    let transport = replay(
+        dir.path(),
+        test_helpers::create_task("recorded-conversation", "Fetch another page"),
+    )
+    .await;
+
+    assert!(transport.published_responses.lock().await.is_empty());
+    let errors = transport.published_errors.lock().await;
+    let message = &errors
+        .first()
+        .expect("an error is published")
+        .1
+        .error
+        .message;
+    assert!(
+        message.contains("No recorded LLM request matches this one"),
+        "{message}"
+    );
+    assert!(
+        message.contains("-       \"content\": \"Fetch the page\""),
+        "{message}"
+    );
+    assert!(
+        message.contains("+       \"content\": \"Fetch another page\""),
+        "{message}"
+    );
+}
@@ -0,0 +1,235 @@
+//! Integration tests for secret redaction
+//!
+//! A tool that echoes its arguments stands in for the common leak: the LLM
+//! passes a configured API key to a tool. Verifies that the key never reaches
+//! progress events or log lines once `[observability.redaction]` is set, and
+//! that `redact_tool_io` keeps it out of the conversation sent back to the LLM.
+
+mod test_helpers;
+
+use agent2389::agent::pipeline::AgentPipeline;
+use agent2389::agent::processor::AgentProcessor;
+use agent2389::config::{AgentConfig, RedactionConfig};
+use agent2389::observability::logging::json_layer;
+use agent2389::observability::redaction::RedactingMakeWriter;
+use agent2389::observability::{install_log_redactor, Redactor};
+use agent2389::progress::{BroadcastProgress, ProgressMessage};
+use agent2389::protocol::messages::TaskEnvelopeWrapper;
+use agent2389::secrets::SecretRef;
+use agent2389::testing::mocks::{MockLlmProvider, MockTransport, ScriptedTurn};
+use agent2389::tools::{Tool, ToolDescription, ToolError, ToolSystem};
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::io;
+use std::sync::{Arc, Mutex};
+use tokio::sync::{broadcast, mpsc};
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::prelude::*;
+
+// ========== Test Helpers ==========
+
+const API_KEY_ENV: &str = "TEST_REDACTION_LLM_API_KEY";
+const API_KEY: &str = "sk-live-4f9a8b7c6d5e4f3a";
+
+/// Tool returning its arguments as its result
+struct EchoTool;
+
+#[async_trait]
+impl Tool for EchoTool {
+    fn describe(&self) -> ToolDescription {
+        ToolDescription {
+            name: "http_request".to_string(),
+            description: "Echoes the request it was given".to_string(),
+            parameters: json!({"type": "object"}),
+        }
+    }
+
+    async fn initialize(&mut self, _config: Option<&Value>) -> Result<(), ToolError> {
+        Ok(())
+    }
+
+    async fn execute(&self, parameters: &Value) -> Result<Value, ToolError> {
+        Ok(json!({"sent": parameters}))
+    }
+}
+
+/// Test config whose LLM API key is `API_KEY`, redacting with `redaction`
+fn config(redaction: Option<RedactionConfig>) -> AgentConfig {
+    std::env::set_var(API_KEY_ENV, API_KEY);
+    let mut config = test_helpers::test_config();
+    config.llm.api_key_env = SecretRef::env(API_KEY_ENV);
+    config.observability.redaction = redaction;
+    config
+}
+
+/// LLM that passes the API key to the echo tool, then answers
+fn llm() -> Arc<MockLlmProvider> {
+    Arc::new(MockLlmProvider::scripted(vec![
+        ScriptedTurn::tool_call(
+            "http_request",
+            json!({"headers": {"authorization": format!("Bearer {API_KEY}")}}),
+        ),
+        ScriptedTurn::answer("done"),
+    ]))
+}
+
+/// Process one task and return every progress event it reported
+async fn run(config: AgentConfig, llm: Arc<MockLlmProvider>) -> Vec<ProgressMessage> {
+    let (sender, mut receiver) = broadcast::channel(256);
+    let mut tools = ToolSystem::new();
+    tools.register_tool("http_request", Box::new(EchoTool));
+    let processor = AgentProcessor::with_progress(
+        config,
+        llm,
+        Arc::new(tools),
+        Arc::new(MockTransport::new()),
+        Arc::new(BroadcastProgress::new("test-agent".to_string(), sender)),
+    );
+
+    let (_task_sender, task_receiver) = mpsc::channel(1);
+    AgentPipeline::new(processor, task_receiver, 16)
+        .process_single_task(TaskEnvelopeWrapper::V1(test_helpers::create_task(
+            "redaction-conversation",
+            "Call the API",
+        )))
+        .await
+        .unwrap();
+
+    let mut events = Vec::new();
+    while let Ok(event) = receiver.try_recv() {
+        events.push(event);
+    }
+    events
+}
+
+/// Text of every message of the LLM's second request, which carries the
+/// tool results
+fn tool_results_sent_to_llm(llm: &MockLlmProvider) -> String {
+    let requests = llm.requests();
+    requests[1]
+        .messages
+        .iter()
+        .map(|message| message.content.as_str())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// In-memory log output shared with the layer
+#[derive(Clone, Default)]
+struct Captured(Arc<Mutex<Vec<u8>>>);
+
+impl io::Write for Captured {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// ========== Progress Tests ==========
+
+#[tokio::test]
+async fn test_config_secret_is_redacted_from_progress() {
+    // Arrange
+    let redaction = RedactionConfig::default();
+
+    // Act
+    let events = run(config(Some(redaction)), llm()).await;
+
+    // Assert: the echoed key is in the tool result preview, redacted
+    let text = serde_json::to_string(&events).unwrap();
+    assert!(!text.contains(API_KEY), "key leaked into progress: {text}");
+    assert!(text.contains("Bearer [REDACTED]"), "events: {text}");
+}
+
+#[tokio::test]
+async fn test_progress_is_unredacted_without_the_section() {
+    let events = run(config(None), llm()).await;
+
+    let text = serde_json::to_string(&events).unwrap();
+    assert!(text.contains(API_KEY));
+}
+
+#[tokio::test]
+async fn test_patterns_redact_values_that_are_not_configured_secrets() {
+    // Arrange: a token the config knows nothing about
+    let llm = Arc::new(MockLlmProvider::scripted(vec![
+        ScriptedTurn::tool_call("http_request", json!({"token": "ghp_abcdefghij0123456789"})),
+        ScriptedTurn::answer("done"),
+    ]));
+    let redaction = RedactionConfig {
+        patterns: vec!["ghp_[A-Za-z0-9]{20}".to_string()],
+        redact_secrets: false,
+        ..RedactionConfig::default()
+    };
+
+    // Act
+    let events = run(config(Some(redaction)), llm).await;
+
+    // Assert
+    let text = serde_json::to_string(&events).unwrap();
+    assert!(!text.contains("ghp_abcdefghij"), "token leaked: {text}");
+    assert!(text.contains("[REDACTED]"));
+}
+
+// ========== Tool I/O Tests ==========
+
+#[tokio::test]
+async fn test_redact_tool_io_keeps_secrets_out_of_the_llm_conversation() {
+    // Arrange
+    let llm = llm();
+    let redaction = RedactionConfig {
+        redact_tool_io: true,
+        ..RedactionConfig::default()
+    };
+
+    // Act
+    run(config(Some(redaction)), llm.clone()).await;
+
+    // Assert
+    let sent = tool_results_sent_to_llm(&llm);
+    assert!(sent.contains("Tool http_request returned"), "sent: {sent}");
+    assert!(!sent.contains(API_KEY), "key sent back to the LLM: {sent}");
+    assert!(sent.contains("Bearer [REDACTED]"));
+}
+
+#[tokio::test]
+async fn test_tool_results_reach_the_llm_unredacted_by_default() {
+    // Arrange: progress is redacted, but tool I/O redaction is off
+    let llm = llm();
+
+    // Act
+    run(config(Some(RedactionConfig::default())), llm.clone()).await;
+
+    // Assert
+    assert!(tool_results_sent_to_llm(&llm).contains(API_KEY));
+}
+
+// ========== Log Tests ==========
+
+#[test]
+fn test_installed_redactor_redacts_log_lines() {
+    // Arrange: the writer consults the redactor installed process-wide
+    let captured = Captured::default();
+    let writer = captured.clone();
+    let subscriber = tracing_subscriber::registry().with(json_layer(
+        RedactingMakeWriter::new(move || writer.clone()),
+        FmtSpan::NONE,
+    ));
+    let config = config(Some(RedactionConfig::default()));
+    install_log_redactor(&Redactor::from_config(&config).unwrap());
+
+    // Act
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info!(args = %json!({"key": API_KEY}), "Executing tool with key {API_KEY}");
+    });
+
+    // Assert
+    let output = String::from_utf8(captured.0.lock().unwrap().clone()).unwrap();
+    let line: Value = serde_json::from_str(output.trim()).expect("still one JSON object");
+    assert!(!output.contains(API_KEY), "key leaked into log: {output}");
+    assert_eq!(line["message"], "Executing tool with key [REDACTED]");
+}
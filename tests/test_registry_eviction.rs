@@ -0,0 +1,190 @@
+//! Discovery registry eviction as seen by routers
+//!
+//! Status messages are fed through `DiscoveryMqttIntegration` as MQTT events,
+//! then a Gatekeeper router backed by a mock HTTP server is asked for a
+//! routing decision. Agents whose status went stale, or that announced they
+//! are unavailable, must not be offered in `available_agents`.
+
+use agent2389::agent::discovery::AgentRegistry;
+use agent2389::agent::discovery_integration::DiscoveryMqttIntegration;
+use agent2389::protocol::{AgentStatus, AgentStatusType, TaskEnvelopeV2};
+use agent2389::routing::{GatekeeperRouter, Router};
+use rumqttc::v5::mqttbytes::v5::{Packet, Publish};
+use rumqttc::v5::mqttbytes::QoS;
+use rumqttc::v5::Event;
+use serde_json::{json, Value};
+use std::time::Duration;
+use uuid::Uuid;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+// ========== Test Helpers ==========
+
+fn status_event(agent_id: &str, payload: Vec<u8>) -> Event {
+    Event::Incoming(Packet::Publish(Publish::new(
+        format!("/control/agents/{agent_id}/status"),
+        QoS::AtLeastOnce,
+        payload,
+        None,
+    )))
+}
+
+fn healthy_status(agent_id: &str) -> Event {
+    let status = json!({
+        "health": "ok",
+        "load": 0.2,
+        "last_updated": chrono::Utc::now().to_rfc3339(),
+        "capabilities": ["writing"],
+    });
+    status_event(agent_id, serde_json::to_vec(&status).unwrap())
+}
+
+/// The status a broker publishes for an agent that disconnected uncleanly
+fn last_will(agent_id: &str) -> Event {
+    let status = AgentStatus {
+        agent_id: agent_id.to_string(),
+        status: AgentStatusType::Unavailable,
+        timestamp: chrono::Utc::now(),
+        capabilities: None,
+        description: None,
+    };
+    status_event(agent_id, serde_json::to_vec(&status).unwrap())
+}
+
+/// Make an agent's last status older than the registry TTL
+fn make_stale(registry: &AgentRegistry, agent_id: &str) {
+    let mut agent = registry.get_agent(agent_id).unwrap();
+    agent.last_updated = (chrono::Utc::now() - chrono::Duration::seconds(20)).to_rfc3339();
+    registry.register_agent_without_refresh(agent);
+}
+
+fn create_task() -> TaskEnvelopeV2 {
+    TaskEnvelopeV2 {
+        task_id: Uuid::new_v4(),
+        conversation_id: "eviction-conv".to_string(),
+        topic: "/control/agents/router-agent/input".to_string(),
+        instruction: Some("Write something".to_string()),
+        input: json!({}),
+        next: None,
+        version: "2.0".to_string(),
+        context: None,
+        routing_trace: None,
+        deadline: None,
+        correlation_id: None,
+        parent_task_id: None,
+        published_at: None,
+        traceparent: None,
+        sender_id: None,
+    }
+}
+
+/// Agent ids the Gatekeeper was offered, in the order requests were made
+async fn offered_agents(server: &MockServer) -> Vec<Vec<String>> {
+    server
+        .received_requests()
+        .await
+        .unwrap()
+        .iter()
+        .map(|request| {
+            let body: Value = serde_json::from_slice(&request.body).unwrap();
+            let mut ids: Vec<String> = body["available_agents"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|agent| agent["agent_id"].as_str().unwrap().to_string())
+                .collect();
+            ids.sort();
+            ids
+        })
+        .collect()
+}
+
+async fn gatekeeper() -> (GatekeeperRouter, MockServer) {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/route"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "workflow_complete": true,
+            "reasoning": "Done"
+        })))
+        .mount(&server)
+        .await;
+    let router = GatekeeperRouter::from_url(format!("{}/route", server.uri()), 5000, 1);
+    (router, server)
+}
+
+// ========== Eviction Tests ==========
+
+#[tokio::test]
+async fn test_stale_agent_not_offered_to_gatekeeper() {
+    let registry = AgentRegistry::new();
+    let mut discovery = DiscoveryMqttIntegration::new(registry.clone());
+    for agent_id in ["writer", "editor"] {
+        discovery
+            .process_mqtt_event(&healthy_status(agent_id))
+            .await
+            .unwrap();
+    }
+    let (router, server) = gatekeeper().await;
+    let task = create_task();
+
+    router
+        .decide_next_step(&task, &json!({}), &registry)
+        .await
+        .unwrap();
+    make_stale(&registry, "editor");
+    // Stale entries are hidden from routers even before they are pruned
+    router
+        .decide_next_step(&task, &json!({}), &registry)
+        .await
+        .unwrap();
+
+    discovery.start_pruning(Duration::from_millis(10));
+    for _ in 0..100 {
+        if registry.agent_count() == 1 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert!(registry.get_agent("editor").is_none(), "stale agent pruned");
+
+    let offered = offered_agents(&server).await;
+    assert_eq!(offered[0], vec!["editor", "writer"]);
+    assert_eq!(offered[1], vec!["writer"]);
+    discovery.cleanup().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_last_will_evicts_agent_immediately() {
+    let registry = AgentRegistry::new();
+    let discovery = DiscoveryMqttIntegration::new(registry.clone());
+    for agent_id in ["writer", "editor"] {
+        discovery
+            .process_mqtt_event(&healthy_status(agent_id))
+            .await
+            .unwrap();
+    }
+
+    discovery
+        .process_mqtt_event(&last_will("editor"))
+        .await
+        .unwrap();
+    let (router, server) = gatekeeper().await;
+    router
+        .decide_next_step(&create_task(), &json!({}), &registry)
+        .await
+        .unwrap();
+
+    assert!(registry.get_agent("editor").is_none());
+    assert_eq!(offered_agents(&server).await, vec![vec!["writer"]]);
+
+    // A later healthy status brings the agent back
+    discovery
+        .process_mqtt_event(&healthy_status("editor"))
+        .await
+        .unwrap();
+    assert_eq!(
+        registry.get_agent("editor").map(|agent| agent.health),
+        Some("ok".to_string())
+    );
+}
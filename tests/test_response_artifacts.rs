@@ -0,0 +1,120 @@
+//! Integration tests for response artifacts
+//!
+//! Runs tasks whose decisions carry an `artifacts` array through the pipeline
+//! and checks that the artifacts are published in the response message's
+//! `artifacts` field rather than its text, within `[agent.artifact_limits]`,
+//! and that responses without artifacts look as they did before.
+
+mod test_helpers;
+
+use agent2389::agent::pipeline::AgentPipeline;
+use agent2389::config::{AgentConfig, ArtifactLimitsConfig};
+use agent2389::protocol::messages::{ResponseMessage, TaskEnvelopeWrapper};
+use agent2389::testing::mocks::{MockLlmProvider, MockTransport};
+use agent2389::tools::ToolSystem;
+use serde_json::json;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+// ========== Test Helpers ==========
+
+fn create_pipeline(
+    config: AgentConfig,
+    response: &str,
+) -> (AgentPipeline<MockTransport>, Arc<MockTransport>) {
+    let llm = Arc::new(MockLlmProvider::single_response(response));
+    let (processor, transport) = test_helpers::create_processor(config, llm, ToolSystem::new());
+    let (_task_sender, task_receiver) = mpsc::channel(1);
+    (AgentPipeline::new(processor, task_receiver, 16), transport)
+}
+
+async fn run_task(config: AgentConfig, llm_response: &str) -> ResponseMessage {
+    let (pipeline, transport) = create_pipeline(config, llm_response);
+    let task = test_helpers::create_task("artifacts-conversation", "Find the people");
+    pipeline
+        .process_single_task(TaskEnvelopeWrapper::V1(task))
+        .await
+        .unwrap();
+
+    let responses = transport.get_published_responses().await;
+    assert_eq!(responses.len(), 1);
+    responses[0].1.clone()
+}
+
+// ========== Artifact Tests ==========
+
+#[tokio::test]
+async fn test_decision_artifacts_are_published_beside_the_response() {
+    // Arrange
+    let decision = json!({
+        "result": "I found two people.",
+        "workflow_complete": true,
+        "artifacts": [
+            {"name": "people", "data": [{"name": "Ada"}, {"name": "Grace"}]},
+            {"name": "table", "content_type": "text/csv", "data": "name\nAda\nGrace"}
+        ]
+    });
+
+    // Act
+    let response = run_task(test_helpers::test_config(), &decision.to_string()).await;
+
+    // Assert
+    assert_eq!(response.response, "I found two people.");
+    assert_eq!(response.artifacts.len(), 2);
+    assert_eq!(response.artifacts[0].name, "people");
+    assert_eq!(response.artifacts[0].content_type, "application/json");
+    assert_eq!(
+        response.artifacts[0].data,
+        json!([{"name": "Ada"}, {"name": "Grace"}])
+    );
+    assert_eq!(response.artifacts[1].content_type, "text/csv");
+}
+
+#[tokio::test]
+async fn test_artifacts_over_the_caps_are_dropped() {
+    // Arrange: the second artifact is over its cap, the fourth over the total
+    let mut config = test_helpers::test_config();
+    config.agent.artifact_limits = ArtifactLimitsConfig {
+        max_artifact_bytes: 100,
+        max_total_bytes: 150,
+    };
+    let decision = json!({
+        "result": "Done",
+        "artifacts": [
+            {"name": "first", "data": "a".repeat(60)},
+            {"name": "huge", "data": "b".repeat(500)},
+            {"name": "second", "data": "c".repeat(60)},
+            {"name": "third", "data": "d".repeat(60)}
+        ]
+    });
+
+    // Act
+    let response = run_task(config, &decision.to_string()).await;
+
+    // Assert
+    let names: Vec<&str> = response.artifacts.iter().map(|a| a.name.as_str()).collect();
+    assert_eq!(names, vec!["first", "second"]);
+    assert_eq!(response.response, "Done");
+}
+
+#[tokio::test]
+async fn test_response_without_artifacts_serializes_as_before() {
+    // Act
+    let response = run_task(test_helpers::test_config(), "Plain answer").await;
+
+    // Assert: no artifacts key for consumers that predate it
+    assert!(response.artifacts.is_empty());
+    let serialized = serde_json::to_value(&response).unwrap();
+    assert!(serialized.get("artifacts").is_none());
+}
+
+#[test]
+fn test_response_from_an_older_agent_deserializes() {
+    let response: ResponseMessage = serde_json::from_value(json!({
+        "response": "done",
+        "task_id": "4b9b3d6e-1f0e-4a57-9a8e-5f8c2c1d0e7a"
+    }))
+    .unwrap();
+
+    assert!(response.artifacts.is_empty());
+}
@@ -0,0 +1,338 @@
+//! Integration tests for secret references
+//!
+//! Loads configs whose credential fields use each kind of `SecretRef` and
+//! checks what the accessors return after `resolve_secrets`, the errors for
+//! references that can't be resolved, and that secret values never appear
+//! in `Debug` or `Display` output. Resolved values are cached process-wide,
+//! so each test uses its own variable names and files.
+
+use agent2389::config::{AgentConfig, ConfigError};
+use agent2389::secrets::{Secret, SecretProvider, SecretRef, SecretResolver};
+use async_trait::async_trait;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tempfile::NamedTempFile;
+
+// ========== Test Helpers ==========
+
+fn config_with_llm_key(api_key_env: &str, extra: &str) -> AgentConfig {
+    toml::from_str(&format!(
+        r#"
+[agent]
+id = "secrets-agent"
+description = "Secret reference tests"
+
+[mqtt]
+broker_url = "mqtt://localhost:1883"
+
+[llm]
+provider = "openai"
+model = "gpt-4o"
+api_key_env = "{api_key_env}"
+system_prompt = "You are helpful."
+{extra}
+"#
+    ))
+    .unwrap()
+}
+
+fn secret_file(contents: &str) -> NamedTempFile {
+    let mut file = NamedTempFile::new().unwrap();
+    write!(file, "{contents}").unwrap();
+    file
+}
+
+/// Provider for the `memory:` scheme serving fixed values
+struct MemoryProvider;
+
+#[async_trait]
+impl SecretProvider for MemoryProvider {
+    fn scheme(&self) -> &str {
+        "memory"
+    }
+
+    async fn fetch(
+        &self,
+        reference: &str,
+    ) -> Result<Secret, Box<dyn std::error::Error + Send + Sync>> {
+        match reference {
+            "llm/api-key" => Ok(Secret::new("sk-from-memory")),
+            other => Err(format!("no secret at {other}").into()),
+        }
+    }
+}
+
+// ========== Source Tests ==========
+
+#[tokio::test]
+async fn test_plain_env_name_keeps_working() {
+    // Arrange
+    std::env::set_var("SECRETS_TEST_PLAIN_KEY", "sk-plain");
+    let config = config_with_llm_key("SECRETS_TEST_PLAIN_KEY", "");
+
+    // Act
+    config
+        .resolve_secrets(&SecretResolver::new())
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(
+        config.llm.api_key_env,
+        SecretRef::env("SECRETS_TEST_PLAIN_KEY")
+    );
+    assert_eq!(config.get_llm_api_key().unwrap().expose(), "sk-plain");
+    let serialized = toml::to_string(&config).unwrap();
+    assert!(serialized.contains(r#"api_key_env = "SECRETS_TEST_PLAIN_KEY""#));
+}
+
+#[tokio::test]
+async fn test_env_prefix() {
+    std::env::set_var("SECRETS_TEST_PREFIXED_KEY", "sk-prefixed");
+    let config = config_with_llm_key("env:SECRETS_TEST_PREFIXED_KEY", "");
+
+    config
+        .resolve_secrets(&SecretResolver::new())
+        .await
+        .unwrap();
+
+    assert_eq!(config.get_llm_api_key().unwrap().expose(), "sk-prefixed");
+}
+
+#[tokio::test]
+async fn test_file_reference_trims_trailing_newline() {
+    // Arrange: mounted secrets usually end with a newline
+    let key_file = secret_file("sk-from-file\n");
+    let user_file = secret_file("mqtt-user");
+    let mut config = config_with_llm_key(&format!("file:{}", key_file.path().display()), "");
+    config.mqtt.username_env = Some(SecretRef::File(user_file.path().to_path_buf()));
+
+    // Act
+    config
+        .resolve_secrets(&SecretResolver::new())
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(config.get_llm_api_key().unwrap().expose(), "sk-from-file");
+    assert_eq!(config.get_mqtt_username().unwrap().expose(), "mqtt-user");
+}
+
+#[tokio::test]
+async fn test_custom_provider() {
+    // Arrange
+    let config = config_with_llm_key("memory:llm/api-key", "");
+    let resolver = SecretResolver::new().with_provider(Arc::new(MemoryProvider));
+
+    // Act
+    config.resolve_secrets(&resolver).await.unwrap();
+
+    // Assert: cached for the synchronous accessor
+    assert_eq!(config.get_llm_api_key().unwrap().expose(), "sk-from-memory");
+}
+
+#[tokio::test]
+async fn test_security_keys_resolve_from_files() {
+    // Arrange
+    let hmac_file = secret_file("hmac-key\n");
+    let encryption_file = secret_file(&"ab".repeat(32));
+    std::env::set_var("SECRETS_TEST_SECURITY_LLM_KEY", "sk");
+    let config = config_with_llm_key(
+        "SECRETS_TEST_SECURITY_LLM_KEY",
+        &format!(
+            r#"
+[security]
+hmac_key_env = "file:{}"
+
+[security.encryption]
+enabled = true
+key_env = "file:{}"
+"#,
+            hmac_file.path().display(),
+            encryption_file.path().display()
+        ),
+    );
+
+    // Act
+    config
+        .resolve_secrets(&SecretResolver::new())
+        .await
+        .unwrap();
+
+    // Assert
+    assert!(config.get_message_signer().unwrap().is_some());
+    assert!(config.get_payload_encryptor().unwrap().is_some());
+}
+
+// ========== Error Tests ==========
+
+#[tokio::test]
+async fn test_missing_file_names_the_reference() {
+    let config = config_with_llm_key("file:/nonexistent/secrets-test/api_key", "");
+
+    let result = config.resolve_secrets(&SecretResolver::new()).await;
+
+    match result {
+        Err(error @ ConfigError::SecretUnavailable { .. }) => {
+            assert!(error
+                .to_string()
+                .contains("file:/nonexistent/secrets-test/api_key"));
+        }
+        other => panic!("Expected SecretUnavailable, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn test_unknown_scheme_and_provider_errors_name_the_reference() {
+    // Arrange
+    let unregistered = config_with_llm_key("memory:llm/api-key", "");
+    let failing = config_with_llm_key("memory:llm/missing", "");
+    let resolver = SecretResolver::new().with_provider(Arc::new(MemoryProvider));
+
+    // Act
+    let no_provider = unregistered
+        .resolve_secrets(&SecretResolver::new())
+        .await
+        .unwrap_err();
+    let fetch_failed = failing.resolve_secrets(&resolver).await.unwrap_err();
+
+    // Assert
+    assert!(no_provider.to_string().contains("memory:llm/api-key"));
+    assert!(no_provider
+        .to_string()
+        .contains("no provider for scheme 'memory'"));
+    assert!(fetch_failed.to_string().contains("memory:llm/missing"));
+    assert!(fetch_failed
+        .to_string()
+        .contains("no secret at llm/missing"));
+}
+
+#[tokio::test]
+async fn test_missing_mqtt_credential_env_is_not_an_error() {
+    std::env::remove_var("SECRETS_TEST_MISSING_MQTT_USER");
+    std::env::set_var("SECRETS_TEST_MQTT_LLM_KEY", "sk");
+    let mut config = config_with_llm_key("SECRETS_TEST_MQTT_LLM_KEY", "");
+    config.mqtt.username_env = Some(SecretRef::env("SECRETS_TEST_MISSING_MQTT_USER"));
+
+    config
+        .resolve_secrets(&SecretResolver::new())
+        .await
+        .unwrap();
+
+    assert_eq!(config.get_mqtt_username(), None);
+}
+
+#[test]
+fn test_invalid_reference_is_rejected_at_load() {
+    let result: Result<AgentConfig, _> = toml::from_str(
+        r#"
+[agent]
+id = "secrets-agent"
+description = "Secret reference tests"
+
+[mqtt]
+broker_url = "mqtt://localhost:1883"
+
+[llm]
+provider = "openai"
+model = "gpt-4o"
+api_key_env = "file:"
+system_prompt = "You are helpful."
+"#,
+    );
+
+    let error = result.unwrap_err().to_string();
+    assert!(
+        error.contains("Invalid secret reference 'file:'"),
+        "{error}"
+    );
+}
+
+// ========== Redaction Tests ==========
+
+#[tokio::test]
+async fn test_resolved_secrets_are_redacted() {
+    // Arrange
+    let key_file = secret_file("sk-never-printed");
+    let config = config_with_llm_key(&format!("file:{}", key_file.path().display()), "");
+    config
+        .resolve_secrets(&SecretResolver::new())
+        .await
+        .unwrap();
+
+    // Act
+    let secret = config.get_llm_api_key().unwrap();
+
+    // Assert
+    assert_eq!(secret.to_string(), "[REDACTED]");
+    assert!(!format!("{secret:?}").contains("sk-never-printed"));
+    assert!(!format!("{config:?}").contains("sk-never-printed"));
+    assert_eq!(
+        config.llm.api_key_env,
+        SecretRef::File(PathBuf::from(key_file.path()))
+    );
+}
+
+// ========== Vault Tests ==========
+
+#[cfg(feature = "vault")]
+mod vault {
+    use super::*;
+    use agent2389::secrets::VaultSecretProvider;
+    use serde_json::json;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_vault_kv2_field() {
+        // Arrange
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/secret/data/agent"))
+            .and(header("X-Vault-Token", "vault-token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "data": {"data": {"api_key": "sk-from-vault"}, "metadata": {"version": 3}}
+            })))
+            .mount(&server)
+            .await;
+        let provider = VaultSecretProvider::new(server.uri(), Secret::new("vault-token"));
+        let config = config_with_llm_key("vault:secret/data/agent#api_key", "");
+
+        // Act
+        config
+            .resolve_secrets(&SecretResolver::new().with_provider(Arc::new(provider)))
+            .await
+            .unwrap();
+
+        // Assert
+        assert_eq!(config.get_llm_api_key().unwrap().expose(), "sk-from-vault");
+    }
+
+    #[tokio::test]
+    async fn test_vault_errors_name_the_reference() {
+        // Arrange
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(403))
+            .mount(&server)
+            .await;
+        let provider = VaultSecretProvider::new(server.uri(), Secret::new("bad-token"));
+        let config = config_with_llm_key("vault:secret/data/denied#api_key", "");
+
+        // Act
+        let error = config
+            .resolve_secrets(&SecretResolver::new().with_provider(Arc::new(provider)))
+            .await
+            .unwrap_err()
+            .to_string();
+
+        // Assert
+        assert!(
+            error.contains("vault:secret/data/denied#api_key"),
+            "{error}"
+        );
+        assert!(error.contains("403"), "{error}");
+        assert!(!error.contains("bad-token"));
+    }
+}
@@ -0,0 +1,123 @@
+//! Integration tests for stale task rejection
+//!
+//! Verifies that the pipeline rejects tasks published longer ago than
+//! `max_task_age_secs` with a `stale_task` ErrorMessage, processes fresh
+//! tasks and tasks without a publish time, and that forwarded tasks are
+//! stamped with a fresh publish time.
+
+mod test_helpers;
+
+use agent2389::agent::pipeline::{AgentPipeline, PipelineError};
+use agent2389::observability::metrics::metrics;
+use agent2389::protocol::messages::{ErrorCode, NextTask, TaskEnvelope, TaskEnvelopeWrapper};
+use agent2389::testing::mocks::{MockLlmProvider, MockTransport};
+use agent2389::tools::ToolSystem;
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+// ========== Test Helpers ==========
+
+fn create_pipeline(
+    llm: Arc<MockLlmProvider>,
+) -> (AgentPipeline<MockTransport>, Arc<MockTransport>) {
+    let mut config = test_helpers::test_config();
+    config.agent.max_task_age_secs = Some(300);
+    let (processor, transport) = test_helpers::create_processor(config, llm, ToolSystem::new());
+    let (_task_sender, task_receiver) = mpsc::channel(1);
+    (AgentPipeline::new(processor, task_receiver, 16), transport)
+}
+
+fn create_task(published_at: Option<DateTime<Utc>>) -> TaskEnvelope {
+    let mut task = test_helpers::create_task("stale-conversation", "Summarize the queue");
+    task.published_at = published_at;
+    task
+}
+
+// ========== Stale Task Tests ==========
+
+#[tokio::test]
+async fn test_stale_task_rejected_without_processing() {
+    // Arrange: a task queued by the broker for an hour while we were offline
+    let llm = Arc::new(MockLlmProvider::single_response("done"));
+    let (pipeline, transport) = create_pipeline(llm.clone());
+    let task = create_task(Some(Utc::now() - ChronoDuration::hours(1)));
+    let task_id = task.task_id;
+    let stale_before = metrics().tasks_stale();
+
+    // Act
+    let result = pipeline
+        .process_single_task(TaskEnvelopeWrapper::V1(task))
+        .await;
+
+    // Assert: no LLM work happened and a stale task error was published
+    assert!(matches!(result, Err(PipelineError::StaleTask(_))));
+    assert_eq!(llm.calls(), 0);
+    let errors = transport.get_published_errors().await;
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].0, "stale-conversation");
+    assert_eq!(errors[0].1.task_id, task_id);
+    assert_eq!(errors[0].1.error.code, ErrorCode::StaleTask);
+    assert!(!errors[0].1.error.retryable);
+    assert!(transport.get_published_responses().await.is_empty());
+    assert!(metrics().tasks_stale() > stale_before);
+}
+
+#[tokio::test]
+async fn test_fresh_task_is_processed() {
+    let llm = Arc::new(MockLlmProvider::single_response("done"));
+    let (pipeline, transport) = create_pipeline(llm.clone());
+    let task = create_task(Some(Utc::now() - ChronoDuration::seconds(5)));
+
+    let result = pipeline
+        .process_single_task(TaskEnvelopeWrapper::V1(task))
+        .await;
+
+    assert!(result.is_ok(), "fresh task should be processed: {result:?}");
+    assert_eq!(llm.calls(), 1);
+    assert!(transport.get_published_errors().await.is_empty());
+    assert_eq!(transport.get_published_responses().await.len(), 1);
+}
+
+#[tokio::test]
+async fn test_task_without_publish_time_is_processed() {
+    // Producers predating `published_at` must keep working
+    let llm = Arc::new(MockLlmProvider::single_response("done"));
+    let (pipeline, transport) = create_pipeline(llm.clone());
+
+    let result = pipeline
+        .process_single_task(TaskEnvelopeWrapper::V1(create_task(None)))
+        .await;
+
+    assert!(
+        result.is_ok(),
+        "legacy task should be processed: {result:?}"
+    );
+    assert!(transport.get_published_errors().await.is_empty());
+    assert_eq!(transport.get_published_responses().await.len(), 1);
+}
+
+#[tokio::test]
+async fn test_forwarded_task_is_stamped_with_publish_time() {
+    let llm = Arc::new(MockLlmProvider::single_response("draft"));
+    let (pipeline, transport) = create_pipeline(llm);
+    let mut task = create_task(Some(Utc::now() - ChronoDuration::seconds(60)));
+    task.next = Some(Box::new(NextTask {
+        topic: "/control/agents/editor/input".to_string(),
+        instruction: Some("Edit the draft".to_string()),
+        input: None,
+        next: None,
+    }));
+    let before = Utc::now();
+
+    pipeline
+        .process_single_task(TaskEnvelopeWrapper::V1(task))
+        .await
+        .unwrap();
+
+    // Each hop restarts the clock, so a long workflow isn't rejected midway
+    let forwarded = transport.published_tasks.lock().await;
+    assert_eq!(forwarded.len(), 1);
+    let published_at = forwarded[0].1.published_at.expect("publish time stamped");
+    assert!(published_at >= before);
+}
@@ -0,0 +1,120 @@
+//! Integration tests for nine-step algorithm step latency
+//!
+//! Processes tasks against mock dependencies and checks the per-step
+//! latency recorded in the global metrics snapshot and the step timings
+//! attached to the task-complete progress event. The collector is shared
+//! by the tests in this binary, so assertions are lower bounds.
+
+mod test_helpers;
+
+use agent2389::agent::pipeline::AgentPipeline;
+use agent2389::agent::processor::AgentProcessor;
+use agent2389::observability::metrics::{metrics, StepLatencyStats};
+use agent2389::progress::{BroadcastProgress, ProgressEventType};
+use agent2389::protocol::messages::TaskEnvelopeWrapper;
+use agent2389::testing::mocks::{MockLlmProvider, MockTransport};
+use agent2389::tools::ToolSystem;
+use std::sync::Arc;
+use tokio::sync::{broadcast, mpsc};
+
+// ========== Test Helpers ==========
+
+async fn run(processor: AgentProcessor<MockTransport>) -> Result<(), String> {
+    let (_task_sender, task_receiver) = mpsc::channel(1);
+    AgentPipeline::new(processor, task_receiver, 16)
+        .process_single_task(TaskEnvelopeWrapper::V1(test_helpers::create_task(
+            "step-metrics",
+            "Do it",
+        )))
+        .await
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+fn step_stats(step: u8, outcome: &str) -> Option<StepLatencyStats> {
+    metrics()
+        .get_metrics()
+        .steps
+        .into_iter()
+        .find(|stats| stats.step == step && stats.outcome == outcome)
+}
+
+// ========== Step Metrics Tests ==========
+
+#[tokio::test]
+async fn test_processed_task_records_all_nine_steps() {
+    // Arrange
+    let (processor, _transport) = test_helpers::create_processor(
+        test_helpers::test_config(),
+        Arc::new(MockLlmProvider::single_response("done")),
+        ToolSystem::new(),
+    );
+
+    // Act
+    run(processor).await.unwrap();
+
+    // Assert
+    for step in 1..=9 {
+        let stats = step_stats(step, "success").unwrap_or_else(|| panic!("no step {step}"));
+        assert!(stats.count >= 1, "step {step}");
+        assert!(stats.p99_ms >= stats.p50_ms, "step {step}");
+        let bucketed: u64 = stats.latency_histogram.iter().map(|b| b.count).sum();
+        assert_eq!(bucketed, stats.count, "step {step}");
+    }
+}
+
+#[tokio::test]
+async fn test_failed_llm_call_records_step_7_failure() {
+    // Arrange
+    let (processor, _transport) = test_helpers::create_processor(
+        test_helpers::test_config(),
+        Arc::new(MockLlmProvider::with_failure()),
+        ToolSystem::new(),
+    );
+
+    // Act
+    let result = run(processor).await;
+
+    // Assert
+    assert!(result.is_err());
+    let stats = step_stats(7, "failure").expect("step 7 failure recorded");
+    assert!(stats.count >= 1);
+}
+
+// ========== Progress Metadata Tests ==========
+
+#[tokio::test]
+async fn test_task_complete_carries_step_timings() {
+    // Arrange
+    let (sender, mut receiver) = broadcast::channel(256);
+    let processor = AgentProcessor::with_progress(
+        test_helpers::test_config(),
+        Arc::new(MockLlmProvider::single_response("done")),
+        Arc::new(ToolSystem::new()),
+        Arc::new(MockTransport::new()),
+        Arc::new(BroadcastProgress::new("test-agent".to_string(), sender)),
+    );
+
+    // Act
+    run(processor).await.unwrap();
+
+    // Assert
+    let mut complete = None;
+    while let Ok(event) = receiver.try_recv() {
+        if event.event_type == ProgressEventType::TaskComplete {
+            complete = Some(event);
+        }
+    }
+    let metadata = complete.expect("task complete event").metadata.unwrap();
+    assert_eq!(metadata["forwarded"], false);
+    let steps: Vec<u64> = metadata["steps"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|step| {
+            assert!(step["duration_ms"].is_u64(), "{step}");
+            step["step"].as_u64().unwrap()
+        })
+        .collect();
+    assert_eq!(steps, (1..=9).collect::<Vec<_>>());
+}
@@ -0,0 +1,169 @@
+//! Integration tests for task acknowledgements
+//!
+//! Verifies that accepted tasks are acked on the conversation ack topic once
+//! validation passes, that rejected tasks are nacked with the matching error
+//! code, and that nothing is published when acks are disabled.
+
+mod test_helpers;
+
+use agent2389::agent::processor::AgentProcessor;
+use agent2389::protocol::messages::{AckStatus, ErrorCode, TaskEnvelope, TaskEnvelopeWrapper};
+use agent2389::testing::mocks::{MockLlmProvider, MockTransport};
+use agent2389::tools::ToolSystem;
+use chrono::{Duration as ChronoDuration, Utc};
+use serde_json::json;
+use std::sync::Arc;
+use uuid::Uuid;
+
+// ========== Test Helpers ==========
+
+fn create_processor(publish_acks: bool) -> (AgentProcessor<MockTransport>, Arc<MockTransport>) {
+    let mut config = test_helpers::test_config();
+    config.agent.publish_acks = publish_acks;
+
+    let transport = Arc::new(MockTransport::new());
+    let processor = AgentProcessor::new(
+        config,
+        Arc::new(MockLlmProvider::single_response("done")),
+        Arc::new(ToolSystem::new()),
+        transport.clone(),
+    );
+    (processor, transport)
+}
+
+fn create_task() -> TaskEnvelope {
+    TaskEnvelope {
+        task_id: Uuid::new_v4(),
+        conversation_id: "ack-conversation".to_string(),
+        topic: "/control/agents/test-agent/input".to_string(),
+        instruction: Some("Acknowledge me".to_string()),
+        input: json!({}),
+        next: None,
+        deadline: None,
+        correlation_id: Some("workflow-1".to_string()),
+        parent_task_id: None,
+        published_at: None,
+        traceparent: None,
+        sender_id: None,
+    }
+}
+
+// ========== Ack Tests ==========
+
+#[tokio::test]
+async fn test_accepted_task_is_acked_before_response() {
+    // Arrange
+    let (processor, transport) = create_processor(true);
+    let task = create_task();
+    let task_id = task.task_id;
+    let before = Utc::now();
+
+    // Act
+    processor
+        .process_task(
+            TaskEnvelopeWrapper::V1(task),
+            "/control/agents/test-agent/input",
+            false,
+        )
+        .await
+        .expect("task should complete");
+
+    // Assert: one ack for the task, published to its conversation
+    let acks = transport.get_published_acks().await;
+    assert_eq!(acks.len(), 1);
+    let (conversation_id, ack) = &acks[0];
+    assert_eq!(conversation_id, "ack-conversation");
+    assert_eq!(ack.task_id, task_id);
+    assert_eq!(ack.agent_id, "test-agent");
+    assert_eq!(ack.status, AckStatus::Accepted);
+    assert_eq!(ack.queue_position, 0);
+    assert!(ack.accepted_at >= before);
+    assert!(ack.error.is_none());
+    assert_eq!(ack.correlation_id.as_deref(), Some("workflow-1"));
+    assert_eq!(transport.get_published_responses().await.len(), 1);
+}
+
+#[tokio::test]
+async fn test_retained_task_is_nacked_with_invalid_input() {
+    // Arrange
+    let (processor, transport) = create_processor(true);
+    let task = create_task();
+    let task_id = task.task_id;
+
+    // Act: retained messages are rejected at step 2
+    let result = processor
+        .process_task(
+            TaskEnvelopeWrapper::V1(task),
+            "/control/agents/test-agent/input",
+            true,
+        )
+        .await;
+
+    // Assert: nack carries the same error code as the published error
+    assert!(result.is_err());
+    let acks = transport.get_published_acks().await;
+    assert_eq!(acks.len(), 1);
+    let ack = &acks[0].1;
+    assert_eq!(ack.task_id, task_id);
+    assert_eq!(ack.status, AckStatus::Rejected);
+    let error = ack.error.as_ref().expect("nack should carry an error");
+    assert_eq!(error.code, ErrorCode::InvalidInput);
+
+    let errors = transport.get_published_errors().await;
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].1.error.code, ErrorCode::InvalidInput);
+    assert!(transport.get_published_responses().await.is_empty());
+}
+
+#[tokio::test]
+async fn test_expired_task_is_nacked_with_deadline_exceeded() {
+    // Arrange
+    let (processor, transport) = create_processor(true);
+    let mut task = create_task();
+    task.deadline = Some(Utc::now() - ChronoDuration::minutes(5));
+
+    // Act
+    let result = processor
+        .process_task(
+            TaskEnvelopeWrapper::V1(task),
+            "/control/agents/test-agent/input",
+            false,
+        )
+        .await;
+
+    // Assert
+    assert!(result.is_err());
+    let acks = transport.get_published_acks().await;
+    assert_eq!(acks.len(), 1);
+    assert_eq!(acks[0].1.status, AckStatus::Rejected);
+    assert_eq!(
+        acks[0].1.error.as_ref().map(|error| error.code.clone()),
+        Some(ErrorCode::DeadlineExceeded)
+    );
+}
+
+#[tokio::test]
+async fn test_no_acks_when_disabled() {
+    // Arrange
+    let (processor, transport) = create_processor(false);
+
+    // Act: one accepted and one rejected task
+    processor
+        .process_task(
+            TaskEnvelopeWrapper::V1(create_task()),
+            "/control/agents/test-agent/input",
+            false,
+        )
+        .await
+        .expect("task should complete");
+    let _ = processor
+        .process_task(
+            TaskEnvelopeWrapper::V1(create_task()),
+            "/control/agents/test-agent/input",
+            true,
+        )
+        .await;
+
+    // Assert
+    assert!(transport.get_published_acks().await.is_empty());
+}
@@ -0,0 +1,215 @@
+//! Integration tests for task batches
+//!
+//! Verifies that a TaskBatchEnvelope is expanded into one task per item,
+//! processed with bounded concurrency, summarised with per-item results, and
+//! that re-delivering the same batch does not reprocess finished items.
+
+mod test_helpers;
+
+use agent2389::agent::pipeline::AgentPipeline;
+use agent2389::protocol::messages::{BatchItemStatus, TaskBatchEnvelope, TaskEnvelopeWrapper};
+use agent2389::testing::mocks::{MockLlmProvider, MockTransport};
+use agent2389::tools::ToolSystem;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+// ========== Test Helpers ==========
+
+/// LLM mock that rejects items marked `"fail": true`
+fn batch_llm() -> Arc<MockLlmProvider> {
+    Arc::new(
+        MockLlmProvider::single_response("summarised")
+            .with_delay(Duration::from_millis(20))
+            .failing_on("\"fail\":true"),
+    )
+}
+
+fn create_pipeline(
+    llm: Arc<MockLlmProvider>,
+) -> (
+    AgentPipeline<MockTransport>,
+    Arc<MockTransport>,
+    mpsc::Sender<TaskEnvelopeWrapper>,
+) {
+    let (processor, transport) =
+        test_helpers::create_processor(test_helpers::test_config(), llm, ToolSystem::new());
+    let (task_sender, task_receiver) = mpsc::channel(10);
+    let pipeline = AgentPipeline::new(processor, task_receiver, 16);
+    (pipeline, transport, task_sender)
+}
+
+fn create_batch(items: Vec<Value>) -> TaskBatchEnvelope {
+    TaskBatchEnvelope {
+        batch_id: Uuid::new_v4(),
+        conversation_id: "batch-conversation".to_string(),
+        shared_instruction: Some("Summarize this document".to_string()),
+        items,
+        next: None,
+    }
+}
+
+// ========== Batch Tests ==========
+
+#[tokio::test]
+async fn test_batch_expands_into_one_task_per_item() {
+    // Arrange
+    let llm = batch_llm();
+    let (mut pipeline, transport, task_sender) = create_pipeline(llm.clone());
+    pipeline.set_batch_concurrency(2);
+    let batch = create_batch((0..6).map(|doc| json!({ "doc": doc })).collect());
+
+    // Act
+    let summary = pipeline.process_batch(batch.clone()).await;
+    drop(task_sender);
+
+    // Assert: every item produced a response under its derived task id
+    assert_eq!(summary.total, 6);
+    assert_eq!(summary.succeeded, 6);
+    assert_eq!(llm.calls(), 6);
+    assert!(llm.max_active() <= 2);
+
+    let responses = transport.get_published_responses().await;
+    assert_eq!(responses.len(), 6);
+    for index in 0..6 {
+        let task_id = batch.item_task_id(index);
+        let response = responses
+            .iter()
+            .find(|(_, response)| response.task_id == task_id)
+            .expect("each item should publish a response");
+        assert_eq!(response.0, "batch-conversation");
+        assert_eq!(response.1.correlation_id, Some(batch.batch_id.to_string()));
+    }
+
+    // Summary is published once, to the batch conversation
+    let summaries = transport.get_published_batch_summaries().await;
+    assert_eq!(summaries.len(), 1);
+    assert_eq!(summaries[0].0, "batch-conversation");
+    assert_eq!(summaries[0].1, summary);
+}
+
+#[tokio::test]
+async fn test_partial_failures_do_not_abort_batch() {
+    // Arrange: items 1 and 3 fail in the LLM
+    let llm = batch_llm();
+    let (pipeline, transport, _task_sender) = create_pipeline(llm);
+    let batch = create_batch(vec![
+        json!({"doc": 0}),
+        json!({"doc": 1, "fail": true}),
+        json!({"doc": 2}),
+        json!({"doc": 3, "fail": true}),
+    ]);
+
+    // Act
+    let summary = pipeline.process_batch(batch.clone()).await;
+
+    // Assert: counts and per-item status, ordered by index
+    assert_eq!(summary.total, 4);
+    assert_eq!(summary.succeeded, 2);
+    assert_eq!(summary.failed, 2);
+    assert_eq!(summary.duplicates, 0);
+    let statuses: Vec<_> = summary.items.iter().map(|item| item.status).collect();
+    assert_eq!(
+        statuses,
+        vec![
+            BatchItemStatus::Succeeded,
+            BatchItemStatus::Failed,
+            BatchItemStatus::Succeeded,
+            BatchItemStatus::Failed,
+        ]
+    );
+    assert!(summary.items[1].error.is_some());
+    assert!(summary.items[0].error.is_none());
+
+    // Failed items are still reported to the conversation individually
+    let errors = transport.get_published_errors().await;
+    assert_eq!(errors.len(), 2);
+    assert!(errors
+        .iter()
+        .any(|(_, error)| error.task_id == batch.item_task_id(1)));
+    assert_eq!(transport.get_published_responses().await.len(), 2);
+}
+
+#[tokio::test]
+async fn test_redelivered_batch_only_retries_failed_items() {
+    // Arrange
+    let llm = batch_llm();
+    let (pipeline, transport, _task_sender) = create_pipeline(llm.clone());
+    let batch = create_batch(vec![json!({"doc": 0}), json!({"doc": 1, "fail": true})]);
+
+    // Act: deliver the same batch twice
+    let first = pipeline.process_batch(batch.clone()).await;
+    let second = pipeline.process_batch(batch.clone()).await;
+
+    // Assert: the completed item is a duplicate, the failed one runs again
+    assert_eq!(first.succeeded, 1);
+    assert_eq!(first.failed, 1);
+    assert_eq!(second.total, 2);
+    assert_eq!(second.duplicates, 1);
+    assert_eq!(second.failed, 1);
+    assert_eq!(second.succeeded, 0);
+    assert_eq!(llm.calls(), 3);
+    assert_eq!(transport.get_published_responses().await.len(), 1);
+    assert_eq!(transport.get_published_batch_summaries().await.len(), 2);
+}
+
+#[tokio::test]
+async fn test_batch_routed_through_pipeline() {
+    // Arrange: pipeline with a batch channel attached
+    let llm = batch_llm();
+    let (mut pipeline, transport, task_sender) = create_pipeline(llm);
+    let (batch_sender, batch_receiver) = mpsc::channel(10);
+    pipeline.set_batch_receiver(batch_receiver);
+
+    let batch = create_batch(vec![json!({"doc": 0}), json!({"doc": 1, "fail": true})]);
+    batch_sender.send(batch.clone()).await.unwrap();
+    drop(batch_sender);
+    drop(task_sender);
+
+    // Act
+    let result = tokio::time::timeout(Duration::from_secs(5), pipeline.run())
+        .await
+        .expect("pipeline should finish");
+
+    // Assert: the failed item did not stop the pipeline and a summary went out
+    assert!(result.is_ok());
+    let summaries = transport.get_published_batch_summaries().await;
+    assert_eq!(summaries.len(), 1);
+    assert_eq!(summaries[0].1.batch_id, batch.batch_id);
+    assert_eq!(summaries[0].1.succeeded, 1);
+    assert_eq!(summaries[0].1.failed, 1);
+}
+
+#[tokio::test]
+async fn test_batch_items_share_the_task_limit() {
+    // Arrange: batches may run 4 items at once, the agent only 1 task
+    let llm = batch_llm();
+    let (mut pipeline, _transport, task_sender) = create_pipeline(llm.clone());
+    pipeline.set_max_concurrent_tasks(1);
+    let (batch_sender, batch_receiver) = mpsc::channel(10);
+    pipeline.set_batch_receiver(batch_receiver);
+
+    let items = (0..3).map(|doc| json!({"doc": doc})).collect();
+    batch_sender.send(create_batch(items)).await.unwrap();
+    task_sender
+        .send(TaskEnvelopeWrapper::V1(test_helpers::create_task(
+            "other-conversation",
+            "Do some work",
+        )))
+        .await
+        .unwrap();
+    drop(batch_sender);
+    drop(task_sender);
+
+    // Act
+    tokio::time::timeout(Duration::from_secs(5), pipeline.run())
+        .await
+        .expect("pipeline should finish")
+        .expect("pipeline should not fail");
+
+    // Assert: every item and the task ran, one at a time
+    assert_eq!(llm.calls(), 4);
+    assert_eq!(llm.max_active(), 1);
+}
@@ -0,0 +1,318 @@
+//! Integration tests for task cancellation
+//!
+//! Verifies that cancel requests abort in-flight tasks between tool-loop
+//! iterations, publish a `cancelled` ErrorMessage, and that cancelling
+//! unknown or completed tasks is a harmless no-op.
+
+mod test_helpers;
+
+use agent2389::agent::pipeline::AgentPipeline;
+use agent2389::agent::processor::AgentProcessor;
+use agent2389::processing::CancelOutcome;
+use agent2389::protocol::messages::{CancelMessage, ErrorCode, TaskEnvelope, TaskEnvelopeWrapper};
+use agent2389::testing::mocks::{MockLlmProvider, MockTransport};
+use agent2389::tools::{Tool, ToolDescription, ToolError, ToolSystem};
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+// ========== Test Helpers ==========
+
+/// Tool that sleeps before returning, so tasks spend time inside the tool loop
+struct SlowTool {
+    calls: Arc<AtomicUsize>,
+}
+
+#[async_trait]
+impl Tool for SlowTool {
+    fn describe(&self) -> ToolDescription {
+        ToolDescription {
+            name: "slow_tool".to_string(),
+            description: "Sleeps before returning".to_string(),
+            parameters: json!({"type": "object"}),
+        }
+    }
+
+    async fn initialize(&mut self, _config: Option<&Value>) -> Result<(), ToolError> {
+        Ok(())
+    }
+
+    async fn execute(&self, _parameters: &Value) -> Result<Value, ToolError> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        Ok(json!({"done": true}))
+    }
+}
+
+fn create_slow_processor(
+    calls: Arc<AtomicUsize>,
+) -> (AgentProcessor<MockTransport>, Arc<MockTransport>) {
+    let mut tool_system = ToolSystem::new();
+    tool_system.register_tool("slow_tool", Box::new(SlowTool { calls }));
+
+    // The LLM keeps requesting the slow tool
+    test_helpers::create_processor(
+        test_helpers::test_config(),
+        Arc::new(MockLlmProvider::single_response("Calling slow tool").with_tool_call("slow_tool")),
+        tool_system,
+    )
+}
+
+fn create_task() -> TaskEnvelope {
+    test_helpers::create_task("cancel-conversation", "Keep calling tools")
+}
+
+async fn wait_for_calls(calls: &AtomicUsize, count: usize) {
+    while calls.load(Ordering::SeqCst) < count {
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+}
+
+// ========== Cancellation Tests ==========
+
+#[tokio::test]
+async fn test_cancel_mid_tool_loop_aborts_and_publishes_error() {
+    // Arrange: processor whose LLM keeps requesting a slow tool
+    let calls = Arc::new(AtomicUsize::new(0));
+    let (processor, transport) = create_slow_processor(calls.clone());
+    let processor = Arc::new(processor);
+    let task = create_task();
+    let task_id = task.task_id;
+
+    let handle = {
+        let processor = processor.clone();
+        tokio::spawn(async move {
+            processor
+                .process_task(
+                    TaskEnvelopeWrapper::V1(task),
+                    "/control/agents/test-agent/input",
+                    false,
+                )
+                .await
+        })
+    };
+
+    // Act: cancel once the first tool call is running
+    wait_for_calls(&calls, 1).await;
+    let registry = processor.nine_step_processor().cancellation();
+    assert_eq!(
+        registry.cancel(
+            task_id,
+            "cancel-conversation",
+            Some("user abort".to_string())
+        ),
+        CancelOutcome::Flagged
+    );
+
+    let result = tokio::time::timeout(Duration::from_secs(5), handle)
+        .await
+        .expect("cancelled task should finish promptly")
+        .unwrap();
+
+    // Assert: task aborted before exhausting the tool loop
+    assert!(result.is_err());
+    assert!(calls.load(Ordering::SeqCst) < 10);
+    assert!(!registry.is_active(&task_id));
+
+    let errors = transport.get_published_errors().await;
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].0, "cancel-conversation");
+    assert_eq!(errors[0].1.task_id, task_id);
+    assert_eq!(errors[0].1.error.code, ErrorCode::Cancelled);
+    assert!(errors[0].1.error.message.contains("user abort"));
+    assert!(transport.get_published_responses().await.is_empty());
+}
+
+#[tokio::test]
+async fn test_cancel_routed_through_pipeline() {
+    // Arrange: pipeline with a cancel channel attached
+    let calls = Arc::new(AtomicUsize::new(0));
+    let (processor, transport) = create_slow_processor(calls.clone());
+    let (task_sender, task_receiver) = mpsc::channel(10);
+    let (cancel_sender, cancel_receiver) = mpsc::channel(10);
+    let mut pipeline = AgentPipeline::new(processor, task_receiver, 16);
+    pipeline.set_cancel_receiver(cancel_receiver);
+
+    let task = create_task();
+    let task_id = task.task_id;
+    task_sender
+        .send(TaskEnvelopeWrapper::V1(task))
+        .await
+        .unwrap();
+    drop(task_sender);
+
+    // Act: run the pipeline and cancel while the tool loop is running
+    let run = tokio::spawn(async move { pipeline.run().await });
+    wait_for_calls(&calls, 1).await;
+    cancel_sender
+        .send(CancelMessage {
+            task_id,
+            conversation_id: "cancel-conversation".to_string(),
+            reason: None,
+        })
+        .await
+        .unwrap();
+
+    let result = tokio::time::timeout(Duration::from_secs(5), run)
+        .await
+        .expect("pipeline should finish after cancellation")
+        .unwrap();
+
+    // Assert: pipeline survives the cancelled task and the error was published
+    assert!(result.is_ok());
+    let errors = transport.get_published_errors().await;
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].1.error.code, ErrorCode::Cancelled);
+}
+
+#[tokio::test]
+async fn test_cancel_completed_task_is_noop() {
+    // Arrange
+    let (processor, transport) = test_helpers::create_processor(
+        test_helpers::test_config(),
+        Arc::new(MockLlmProvider::single_response("done")),
+        ToolSystem::new(),
+    );
+    let registry = processor.nine_step_processor().cancellation().clone();
+
+    // Act: complete a task and cancel it afterwards
+    let task = create_task();
+    let task_id = task.task_id;
+    processor
+        .process_task(
+            TaskEnvelopeWrapper::V1(task),
+            "/control/agents/test-agent/input",
+            false,
+        )
+        .await
+        .expect("task should complete");
+
+    // Assert: late cancellation is ignored and nothing is published
+    assert_eq!(
+        registry.cancel(task_id, "cancel-conversation", Some("too late".to_string())),
+        CancelOutcome::Ignored
+    );
+    assert_eq!(
+        registry.cancel(task_id, "cancel-conversation", Some("too late".to_string())),
+        CancelOutcome::Ignored
+    );
+    assert!(transport.get_published_errors().await.is_empty());
+    assert_eq!(transport.get_published_responses().await.len(), 1);
+}
+
+#[tokio::test]
+async fn test_cancel_before_task_is_dequeued() {
+    // Arrange: task queued in the pipeline channel but not yet processed
+    let (processor, transport) = test_helpers::create_processor(
+        test_helpers::test_config(),
+        Arc::new(MockLlmProvider::single_response("done")),
+        ToolSystem::new(),
+    );
+    let (task_sender, task_receiver) = mpsc::channel(10);
+    let (cancel_sender, cancel_receiver) = mpsc::channel(10);
+    let mut pipeline = AgentPipeline::new(processor, task_receiver, 16);
+    pipeline.set_cancel_receiver(cancel_receiver);
+
+    let queued = create_task();
+    let queued_id = queued.task_id;
+    let follow_up = create_task();
+    let follow_up_id = follow_up.task_id;
+
+    // Act: cancel arrives before the pipeline starts consuming tasks
+    cancel_sender
+        .send(CancelMessage {
+            task_id: queued_id,
+            conversation_id: "cancel-conversation".to_string(),
+            reason: Some("changed my mind".to_string()),
+        })
+        .await
+        .unwrap();
+
+    let run = tokio::spawn(async move { pipeline.run().await });
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    task_sender
+        .send(TaskEnvelopeWrapper::V1(queued))
+        .await
+        .unwrap();
+    task_sender
+        .send(TaskEnvelopeWrapper::V1(follow_up))
+        .await
+        .unwrap();
+    drop(task_sender);
+
+    let result = tokio::time::timeout(Duration::from_secs(5), run)
+        .await
+        .expect("pipeline should finish")
+        .unwrap();
+
+    // Assert: queued task was cancelled, the next task still ran
+    assert!(result.is_ok());
+    let errors = transport.get_published_errors().await;
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].1.task_id, queued_id);
+    assert_eq!(errors[0].1.error.code, ErrorCode::Cancelled);
+
+    let responses = transport.get_published_responses().await;
+    assert_eq!(responses.len(), 1);
+    assert_eq!(responses[0].1.task_id, follow_up_id);
+}
+
+#[tokio::test]
+async fn test_cancel_with_mismatched_conversation_is_ignored() {
+    // Arrange
+    let calls = Arc::new(AtomicUsize::new(0));
+    let (processor, _transport) = create_slow_processor(calls.clone());
+    let processor = Arc::new(processor);
+    let task = create_task();
+    let task_id = task.task_id;
+
+    let handle = {
+        let processor = processor.clone();
+        tokio::spawn(async move {
+            processor
+                .process_task(
+                    TaskEnvelopeWrapper::V1(task),
+                    "/control/agents/test-agent/input",
+                    false,
+                )
+                .await
+        })
+    };
+
+    // Act: cancel the running task using another conversation's id
+    wait_for_calls(&calls, 1).await;
+    let registry = processor.nine_step_processor().cancellation();
+    let outcome = registry.cancel(task_id, "other-conversation", None);
+
+    // Assert: the task is not flagged
+    assert_eq!(outcome, CancelOutcome::Ignored);
+    assert!(!registry.is_cancelled(&task_id));
+    handle.abort();
+}
+
+#[tokio::test]
+async fn test_non_cancel_failure_still_stops_pipeline() {
+    // Arrange: LLM failures are not cancellations
+    let (processor, _transport) = test_helpers::create_processor(
+        test_helpers::test_config(),
+        Arc::new(MockLlmProvider::with_failure()),
+        ToolSystem::new(),
+    );
+    let (task_sender, task_receiver) = mpsc::channel(10);
+    let mut pipeline = AgentPipeline::new(processor, task_receiver, 16);
+    task_sender
+        .send(TaskEnvelopeWrapper::V1(create_task()))
+        .await
+        .unwrap();
+
+    // Act
+    let result = tokio::time::timeout(Duration::from_secs(5), pipeline.run())
+        .await
+        .expect("pipeline should stop on failure");
+
+    // Assert: non-cancel failures keep the existing fail-fast behaviour
+    assert!(result.is_err());
+}
@@ -0,0 +1,147 @@
+//! Integration tests for task deadlines
+//!
+//! Verifies that expired tasks are rejected with a `deadline_exceeded`
+//! ErrorMessage at intake and after waiting in the pipeline queue, and that
+//! forwarded tasks carry the original deadline.
+
+mod test_helpers;
+
+use agent2389::agent::pipeline::AgentPipeline;
+use agent2389::agent::processor::AgentProcessor;
+use agent2389::protocol::messages::{ErrorCode, NextTask, TaskEnvelope, TaskEnvelopeWrapper};
+use agent2389::testing::mocks::{MockLlmProvider, MockTransport};
+use agent2389::tools::ToolSystem;
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+// ========== Test Helpers ==========
+
+fn create_processor() -> (AgentProcessor<MockTransport>, Arc<MockTransport>) {
+    let transport = Arc::new(MockTransport::new());
+    let processor = AgentProcessor::new(
+        test_helpers::test_config(),
+        Arc::new(MockLlmProvider::single_response("done")),
+        Arc::new(ToolSystem::new()),
+        transport.clone(),
+    );
+    (processor, transport)
+}
+
+fn create_task(deadline: Option<DateTime<Utc>>) -> TaskEnvelope {
+    TaskEnvelope {
+        task_id: Uuid::new_v4(),
+        conversation_id: "deadline-conversation".to_string(),
+        topic: "/control/agents/test-agent/input".to_string(),
+        instruction: Some("Summarize the last 5 minutes of logs".to_string()),
+        input: json!({}),
+        next: None,
+        deadline,
+        correlation_id: None,
+        parent_task_id: None,
+        published_at: None,
+        traceparent: None,
+        sender_id: None,
+    }
+}
+
+// ========== Deadline Tests ==========
+
+#[tokio::test]
+async fn test_expired_task_rejected_at_intake() {
+    // Arrange
+    let (processor, transport) = create_processor();
+    let task = create_task(Some(Utc::now() - ChronoDuration::hours(2)));
+    let task_id = task.task_id;
+
+    // Act
+    let result = processor
+        .process_task(
+            TaskEnvelopeWrapper::V1(task),
+            "/control/agents/test-agent/input",
+            false,
+        )
+        .await;
+
+    // Assert: no LLM work happened and a deadline error was published
+    assert!(result.is_err());
+    let errors = transport.get_published_errors().await;
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].0, "deadline-conversation");
+    assert_eq!(errors[0].1.task_id, task_id);
+    assert_eq!(errors[0].1.error.code, ErrorCode::DeadlineExceeded);
+    assert!(transport.get_published_responses().await.is_empty());
+}
+
+#[tokio::test]
+async fn test_task_expiring_in_queue_is_rejected_on_dequeue() {
+    // Arrange: a task that expires while it waits in the channel
+    let (processor, transport) = create_processor();
+    let (task_sender, task_receiver) = mpsc::channel(10);
+    let mut pipeline = AgentPipeline::new(processor, task_receiver, 16);
+
+    let expiring = create_task(Some(Utc::now() + ChronoDuration::milliseconds(20)));
+    let expiring_id = expiring.task_id;
+    let follow_up = create_task(None);
+    let follow_up_id = follow_up.task_id;
+
+    task_sender
+        .send(TaskEnvelopeWrapper::V1(expiring))
+        .await
+        .unwrap();
+    task_sender
+        .send(TaskEnvelopeWrapper::V1(follow_up))
+        .await
+        .unwrap();
+    drop(task_sender);
+
+    // Act: start consuming only after the deadline has passed
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    let result = tokio::time::timeout(Duration::from_secs(5), pipeline.run())
+        .await
+        .expect("pipeline should finish");
+
+    // Assert: expired task skipped with an error, the next task still ran
+    assert!(result.is_ok());
+    let errors = transport.get_published_errors().await;
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].1.task_id, expiring_id);
+    assert_eq!(errors[0].1.error.code, ErrorCode::DeadlineExceeded);
+
+    let responses = transport.get_published_responses().await;
+    assert_eq!(responses.len(), 1);
+    assert_eq!(responses[0].1.task_id, follow_up_id);
+}
+
+#[tokio::test]
+async fn test_forwarded_task_keeps_original_deadline() {
+    // Arrange: a pipelined task with a deadline well in the future
+    let (processor, transport) = create_processor();
+    let deadline = Utc::now() + ChronoDuration::hours(1);
+    let mut task = create_task(Some(deadline));
+    task.next = Some(Box::new(NextTask {
+        topic: "/control/agents/next-agent/input".to_string(),
+        instruction: Some("Next step".to_string()),
+        input: None,
+        next: None,
+    }));
+
+    // Act
+    processor
+        .process_task(
+            TaskEnvelopeWrapper::V1(task),
+            "/control/agents/test-agent/input",
+            false,
+        )
+        .await
+        .expect("task should complete");
+
+    // Assert: forwarded task carries the same deadline
+    let forwarded = transport.get_published_tasks().await;
+    assert_eq!(forwarded.len(), 1);
+    assert_eq!(forwarded[0].0, "/control/agents/next-agent/input");
+    assert_eq!(forwarded[0].1.deadline, Some(deadline));
+}
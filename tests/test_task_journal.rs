@@ -0,0 +1,224 @@
+//! Crash-simulation tests for the write-ahead task journal
+//!
+//! Each test journals tasks through the transport's message forwarder, kills
+//! or skips part of a pipeline run, then reopens the journal the way a
+//! restarted agent would and checks which tasks are replayed.
+
+mod test_helpers;
+
+use agent2389::agent::pipeline::AgentPipeline;
+use agent2389::config::AgentConfig;
+use agent2389::processing::task_journal::TASK_JOURNAL_FILE;
+use agent2389::processing::TaskJournal;
+use agent2389::protocol::messages::TaskEnvelopeWrapper;
+use agent2389::testing::mocks::{MockLlmProvider, MockTransport};
+use agent2389::tools::ToolSystem;
+use agent2389::transport::mqtt::message_handler::MessageForwarder;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+// ========== Test Helpers ==========
+
+fn open_journal(dir: &Path) -> Arc<TaskJournal> {
+    Arc::new(TaskJournal::open(&dir.join(TASK_JOURNAL_FILE), 100, true).unwrap())
+}
+
+/// A pipeline wired to a journal, with the forwarder that feeds it
+fn create_pipeline(
+    config: AgentConfig,
+    llm: Arc<MockLlmProvider>,
+    journal: Arc<TaskJournal>,
+) -> (
+    AgentPipeline<MockTransport>,
+    Arc<MockTransport>,
+    MessageForwarder,
+) {
+    let (processor, transport) = test_helpers::create_processor(config, llm, ToolSystem::new());
+    let (task_sender, task_receiver) = mpsc::channel(16);
+    let mut pipeline = AgentPipeline::new(processor, task_receiver, 16);
+    pipeline.set_task_journal(journal.clone());
+
+    let mut forwarder = MessageForwarder::new();
+    forwarder.set_task_sender(task_sender);
+    forwarder.set_task_journal(journal);
+    (pipeline, transport, forwarder)
+}
+
+/// Run a pipeline until its task channel is closed and its work is drained
+async fn run_to_completion(
+    mut pipeline: AgentPipeline<MockTransport>,
+    forwarder: MessageForwarder,
+) {
+    drop(forwarder);
+    tokio::time::timeout(Duration::from_secs(5), pipeline.run())
+        .await
+        .expect("pipeline should drain and stop")
+        .expect("pipeline should not fail");
+}
+
+async fn response_task_ids(transport: &MockTransport) -> Vec<Uuid> {
+    transport
+        .get_published_responses()
+        .await
+        .into_iter()
+        .map(|(_, response)| response.task_id)
+        .collect()
+}
+
+// ========== Crash Simulation Tests ==========
+
+#[tokio::test]
+async fn test_task_killed_mid_processing_is_replayed_on_restart() {
+    let dir = tempfile::tempdir().unwrap();
+    let task = TaskEnvelopeWrapper::V1(test_helpers::create_task(
+        "journal-conversation",
+        "Do some work",
+    ));
+    let task_id = task.task_id();
+
+    // First run: the task is journaled, then the process dies during step 7
+    let llm =
+        Arc::new(MockLlmProvider::single_response("Done").with_delay(Duration::from_secs(60)));
+    let (mut pipeline, transport, forwarder) = create_pipeline(
+        test_helpers::test_config(),
+        llm.clone(),
+        open_journal(dir.path()),
+    );
+    forwarder.forward_task(task).await.unwrap();
+    let handle = tokio::spawn(async move {
+        let _ = pipeline.run().await;
+    });
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while llm.calls() == 0 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+    })
+    .await
+    .expect("task should reach the LLM");
+    handle.abort();
+    assert!(handle.await.unwrap_err().is_cancelled());
+    assert!(transport.get_published_responses().await.is_empty());
+
+    // Restart: the journal replays the task and the new pipeline finishes it
+    let journal = open_journal(dir.path());
+    assert_eq!(journal.pending_len(), 1);
+    let llm = Arc::new(MockLlmProvider::single_response("Done"));
+    let (pipeline, transport, forwarder) =
+        create_pipeline(test_helpers::test_config(), llm.clone(), journal.clone());
+    run_to_completion(pipeline, forwarder).await;
+
+    assert_eq!(llm.calls(), 1);
+    assert_eq!(response_task_ids(&transport).await, vec![task_id]);
+    assert_eq!(journal.pending_len(), 0);
+    drop(journal);
+    assert_eq!(open_journal(dir.path()).pending_len(), 0);
+}
+
+#[tokio::test]
+async fn test_task_killed_mid_processing_is_replayed_with_persistent_idempotency() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut config = test_helpers::test_config();
+    config.agent.state_dir = Some(dir.path().to_path_buf());
+    let task = TaskEnvelopeWrapper::V1(test_helpers::create_task(
+        "journal-conversation",
+        "Do some work",
+    ));
+    let task_id = task.task_id();
+
+    // First run: the task passes step 4, then the process dies during step 7
+    let llm =
+        Arc::new(MockLlmProvider::single_response("Done").with_delay(Duration::from_secs(60)));
+    let (mut pipeline, _transport, forwarder) =
+        create_pipeline(config.clone(), llm.clone(), open_journal(dir.path()));
+    forwarder.forward_task(task).await.unwrap();
+    let handle = tokio::spawn(async move {
+        let _ = pipeline.run().await;
+    });
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while llm.calls() == 0 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+    })
+    .await
+    .expect("task should reach the LLM");
+    handle.abort();
+    assert!(handle.await.unwrap_err().is_cancelled());
+
+    // Restart: the unfinished task is not in the idempotency database, so it is replayed
+    let journal = open_journal(dir.path());
+    let llm = Arc::new(MockLlmProvider::single_response("Done"));
+    let (pipeline, transport, forwarder) = create_pipeline(config, llm.clone(), journal.clone());
+    run_to_completion(pipeline, forwarder).await;
+
+    assert_eq!(llm.calls(), 1);
+    assert_eq!(response_task_ids(&transport).await, vec![task_id]);
+    assert_eq!(journal.pending_len(), 0);
+}
+
+#[tokio::test]
+async fn test_recovered_tasks_run_ahead_of_new_work() {
+    let dir = tempfile::tempdir().unwrap();
+    let recovered =
+        TaskEnvelopeWrapper::V1(test_helpers::create_task("conversation-a", "Do some work"));
+    let recovered_id = recovered.task_id();
+    {
+        // A previous run journaled the task and crashed before processing it
+        let journal = open_journal(dir.path());
+        journal.append(&recovered).unwrap();
+    }
+
+    let llm = Arc::new(MockLlmProvider::single_response("Done"));
+    let (pipeline, transport, forwarder) =
+        create_pipeline(test_helpers::test_config(), llm, open_journal(dir.path()));
+    let new_task =
+        TaskEnvelopeWrapper::V1(test_helpers::create_task("conversation-b", "Do some work"));
+    let new_id = new_task.task_id();
+    forwarder.forward_task(new_task).await.unwrap();
+    run_to_completion(pipeline, forwarder).await;
+
+    // The new task was journaled on arrival but is not replayed a second time
+    assert_eq!(
+        response_task_ids(&transport).await,
+        vec![recovered_id, new_id]
+    );
+    assert_eq!(open_journal(dir.path()).pending_len(), 0);
+}
+
+#[tokio::test]
+async fn test_finished_task_is_not_replayed_with_persistent_idempotency() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut config = test_helpers::test_config();
+    config.agent.state_dir = Some(dir.path().to_path_buf());
+    let task = TaskEnvelopeWrapper::V1(test_helpers::create_task(
+        "journal-conversation",
+        "Do some work",
+    ));
+
+    // First run finishes the task but dies before marking it complete
+    {
+        let journal = open_journal(dir.path());
+        journal.append(&task).unwrap();
+        let (processor, _transport) = test_helpers::create_processor(
+            config.clone(),
+            Arc::new(MockLlmProvider::single_response("Done")),
+            ToolSystem::new(),
+        );
+        processor
+            .process_task(task.clone(), "/control/agents/test-agent/input", false)
+            .await
+            .unwrap();
+    }
+
+    // Restart: the idempotency database shows the task already ran
+    let llm = Arc::new(MockLlmProvider::single_response("Done"));
+    let journal = open_journal(dir.path());
+    let (pipeline, transport, forwarder) = create_pipeline(config, llm.clone(), journal.clone());
+    run_to_completion(pipeline, forwarder).await;
+
+    assert_eq!(llm.calls(), 0);
+    assert!(transport.get_published_responses().await.is_empty());
+    assert_eq!(journal.pending_len(), 0);
+}
@@ -0,0 +1,89 @@
+//! Integration tests for panic isolation in task processing
+//!
+//! Verifies that a panic while processing one task publishes an internal
+//! error for that task and leaves the pipeline serving the next one.
+
+mod test_helpers;
+
+use agent2389::agent::pipeline::AgentPipeline;
+use agent2389::observability::metrics::metrics;
+use agent2389::protocol::messages::{ErrorCode, TaskEnvelopeWrapper};
+use agent2389::testing::mocks::MockLlmProvider;
+use agent2389::tools::{Tool, ToolDescription, ToolError, ToolSystem};
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+// ========== Test Helpers ==========
+
+/// Tool that panics when executed
+struct PanickingTool;
+
+#[async_trait]
+impl Tool for PanickingTool {
+    fn describe(&self) -> ToolDescription {
+        ToolDescription {
+            name: "panicking_tool".to_string(),
+            description: "Panics when executed".to_string(),
+            parameters: json!({"type": "object"}),
+        }
+    }
+
+    async fn initialize(&mut self, _config: Option<&Value>) -> Result<(), ToolError> {
+        Ok(())
+    }
+
+    async fn execute(&self, _parameters: &Value) -> Result<Value, ToolError> {
+        panic!("boom");
+    }
+}
+
+// ========== Panic Isolation Tests ==========
+
+#[tokio::test]
+async fn test_panicking_task_does_not_stop_the_pipeline() {
+    // Arrange: only prompts mentioning PANIC call the panicking tool
+    let mut tool_system = ToolSystem::new();
+    tool_system.register_tool("panicking_tool", Box::new(PanickingTool));
+    let llm = Arc::new(
+        MockLlmProvider::single_response("Handled")
+            .with_tool_call("panicking_tool")
+            .tool_call_on("PANIC"),
+    );
+    let (processor, transport) =
+        test_helpers::create_processor(test_helpers::test_config(), llm, tool_system);
+    let (sender, receiver) = mpsc::channel(2);
+    let mut pipeline = AgentPipeline::new(processor, receiver, 16);
+    let panics_before = metrics().task_panics();
+
+    let panicking = test_helpers::create_task("panic-conversation", "PANIC please");
+    let healthy = test_helpers::create_task("healthy-conversation", "Do some work");
+    let (panicking_id, healthy_id) = (panicking.task_id, healthy.task_id);
+    sender
+        .send(TaskEnvelopeWrapper::V1(panicking))
+        .await
+        .unwrap();
+    sender.send(TaskEnvelopeWrapper::V1(healthy)).await.unwrap();
+    drop(sender);
+
+    // Act
+    tokio::time::timeout(Duration::from_secs(10), pipeline.run())
+        .await
+        .expect("pipeline should drain accepted tasks and stop")
+        .expect("a panicking task must not fail the pipeline");
+
+    // Assert: the panic became an internal error for its own task only
+    let errors = transport.get_published_errors().await;
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].0, "panic-conversation");
+    assert_eq!(errors[0].1.task_id, panicking_id);
+    assert_eq!(errors[0].1.error.code, ErrorCode::InternalError);
+    assert!(errors[0].1.error.message.contains("boom"));
+
+    let responses = transport.get_published_responses().await;
+    assert_eq!(responses.len(), 1);
+    assert_eq!(responses[0].1.task_id, healthy_id);
+    assert!(metrics().task_panics() > panics_before);
+}
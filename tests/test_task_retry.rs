@@ -0,0 +1,120 @@
+//! Integration tests for automatic task retries
+//!
+//! Verifies that the pipeline retries tasks failing with a retryable error,
+//! publishes the error only after the final attempt, and never retries
+//! permanent failures.
+
+mod test_helpers;
+
+use agent2389::agent::pipeline::AgentPipeline;
+use agent2389::llm::provider::LlmError;
+use agent2389::protocol::messages::TaskEnvelopeWrapper;
+use agent2389::testing::mocks::{MockLlmProvider, MockTransport};
+use agent2389::tools::ToolSystem;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+// ========== Test Helpers ==========
+
+fn server_error() -> LlmError {
+    LlmError::ApiError("server error: 500 Internal Server Error".to_string())
+}
+
+fn auth_error() -> LlmError {
+    LlmError::AuthenticationFailed("invalid api key".to_string())
+}
+
+fn create_pipeline(
+    llm: Arc<MockLlmProvider>,
+    max_task_retries: u32,
+) -> (AgentPipeline<MockTransport>, Arc<MockTransport>) {
+    let (processor, transport) =
+        test_helpers::create_processor(test_helpers::test_config(), llm, ToolSystem::new());
+    let (_task_sender, task_receiver) = mpsc::channel(1);
+    let mut pipeline = AgentPipeline::new(processor, task_receiver, 16);
+    pipeline.set_max_task_retries(max_task_retries);
+    pipeline.set_retry_base_delay(Duration::from_millis(5));
+    (pipeline, transport)
+}
+
+fn create_task() -> TaskEnvelopeWrapper {
+    TaskEnvelopeWrapper::V1(test_helpers::create_task(
+        "retry-conversation",
+        "Do some work",
+    ))
+}
+
+// ========== Retry Tests ==========
+
+#[tokio::test]
+async fn test_task_succeeds_on_second_attempt() {
+    // Arrange: the first LLM call fails with a transient server error
+    let llm =
+        Arc::new(MockLlmProvider::single_response("Recovered").failing_first(1, server_error));
+    let (pipeline, transport) = create_pipeline(llm.clone(), 2);
+    let task = create_task();
+    let task_id = task.task_id();
+
+    // Act
+    let result = pipeline.process_single_task(task).await;
+
+    // Assert: retried past step 4 without publishing the transient error
+    assert!(result.is_ok(), "retry should succeed: {result:?}");
+    assert_eq!(llm.calls(), 2);
+    assert!(transport.get_published_errors().await.is_empty());
+    let responses = transport.get_published_responses().await;
+    assert_eq!(responses.len(), 1);
+    assert_eq!(responses[0].1.task_id, task_id);
+}
+
+#[tokio::test]
+async fn test_retries_exhausted_publishes_error_once() {
+    // Arrange: every LLM call fails with a transient server error
+    let llm = Arc::new(
+        MockLlmProvider::single_response("Recovered").failing_first(usize::MAX, server_error),
+    );
+    let (pipeline, transport) = create_pipeline(llm.clone(), 2);
+    let task = create_task();
+    let task_id = task.task_id();
+
+    // Act
+    let result = pipeline.process_single_task(task).await;
+
+    // Assert: one initial attempt plus two retries, then a single error
+    assert!(result.is_err());
+    assert_eq!(llm.calls(), 3);
+    let errors = transport.get_published_errors().await;
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].0, "retry-conversation");
+    assert_eq!(errors[0].1.task_id, task_id);
+    assert!(errors[0].1.error.retryable);
+    assert!(transport.get_published_responses().await.is_empty());
+}
+
+#[tokio::test]
+async fn test_permanent_failure_is_not_retried() {
+    let llm = Arc::new(MockLlmProvider::single_response("Recovered").failing_first(1, auth_error));
+    let (pipeline, transport) = create_pipeline(llm.clone(), 2);
+
+    let result = pipeline.process_single_task(create_task()).await;
+
+    assert!(result.is_err());
+    assert_eq!(llm.calls(), 1);
+    let errors = transport.get_published_errors().await;
+    assert_eq!(errors.len(), 1);
+    assert!(!errors[0].1.error.retryable);
+}
+
+#[tokio::test]
+async fn test_zero_retries_fails_on_first_attempt() {
+    let llm =
+        Arc::new(MockLlmProvider::single_response("Recovered").failing_first(1, server_error));
+    let (pipeline, transport) = create_pipeline(llm.clone(), 0);
+
+    let result = pipeline.process_single_task(create_task()).await;
+
+    assert!(result.is_err());
+    assert_eq!(llm.calls(), 1);
+    assert_eq!(transport.get_published_errors().await.len(), 1);
+}
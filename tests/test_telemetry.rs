@@ -0,0 +1,127 @@
+//! Integration tests for telemetry publication
+//!
+//! Runs an agent lifecycle against a mock transport with
+//! `[observability.telemetry]` set and checks the snapshots published on
+//! `/control/agents/{id}/telemetry`. Time is paused, so intervals elapse as
+//! soon as the agent is idle. Metrics are shared by the tests in this
+//! binary, so task and token assertions are lower bounds.
+
+mod test_helpers;
+
+use agent2389::agent::lifecycle::AgentLifecycle;
+use agent2389::config::{AgentConfig, TelemetryConfig};
+use agent2389::observability::TelemetrySnapshot;
+use agent2389::protocol::messages::TaskEnvelopeWrapper;
+use agent2389::testing::mocks::{MockLlmProvider, MockTransport};
+use std::time::Duration;
+
+const TELEMETRY_TOPIC: &str = "/control/agents/test-agent/telemetry";
+
+// ========== Test Helpers ==========
+
+fn config_with_telemetry(interval_secs: u64) -> AgentConfig {
+    let mut config = test_helpers::test_config();
+    config.observability.telemetry = Some(TelemetryConfig { interval_secs });
+    config
+}
+
+/// Started lifecycle, plus a handle sharing its transport's recordings
+async fn start(
+    config: AgentConfig,
+    llm: MockLlmProvider,
+) -> (AgentLifecycle<MockTransport>, MockTransport) {
+    let transport = MockTransport::new();
+    let handle = MockTransport {
+        published_messages: transport.published_messages.clone(),
+        published_retained: transport.published_retained.clone(),
+        task_sender: transport.task_sender.clone(),
+        ..MockTransport::default()
+    };
+    let mut lifecycle = AgentLifecycle::new(config, transport, Box::new(llm));
+    lifecycle.initialize().await.unwrap();
+    lifecycle.start().await.unwrap();
+    (lifecycle, handle)
+}
+
+async fn telemetry(transport: &MockTransport) -> Vec<TelemetrySnapshot> {
+    transport
+        .get_published_messages()
+        .await
+        .iter()
+        .filter(|(topic, _)| topic == TELEMETRY_TOPIC)
+        .map(|(_, payload)| serde_json::from_slice(payload).unwrap())
+        .collect()
+}
+
+// ========== Publication Tests ==========
+
+#[tokio::test(start_paused = true)]
+async fn test_telemetry_is_published_every_interval() {
+    // Arrange
+    let (mut lifecycle, transport) = start(
+        config_with_telemetry(5),
+        MockLlmProvider::single_response("ok"),
+    )
+    .await;
+
+    // Act
+    tokio::time::sleep(Duration::from_secs(11)).await;
+    lifecycle.shutdown().await.unwrap();
+
+    // Assert: two intervals, never retained
+    let snapshots = telemetry(&transport).await;
+    assert_eq!(snapshots.len(), 2);
+    let snapshot = &snapshots[0];
+    assert_eq!(snapshot.agent_id, "test-agent");
+    assert!(snapshot.mqtt_healthy);
+    if cfg!(target_os = "linux") {
+        assert!(snapshot.memory_rss_bytes.unwrap() > 0);
+    }
+    assert!(transport
+        .get_published_retained()
+        .await
+        .iter()
+        .all(|(topic, _)| topic != TELEMETRY_TOPIC));
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_telemetry_reports_tasks_and_tokens_of_the_interval() {
+    // Arrange
+    let (mut lifecycle, transport) = start(
+        config_with_telemetry(5),
+        MockLlmProvider::single_response("done").with_usage(1000, 200),
+    )
+    .await;
+    let sender = transport.task_sender.lock().await.clone().unwrap();
+
+    // Act: one task in the first interval, none in the second
+    let task = test_helpers::create_task("telemetry-conversation", "Do it");
+    sender.send(TaskEnvelopeWrapper::V1(task)).await.unwrap();
+    tokio::time::sleep(Duration::from_secs(11)).await;
+    lifecycle.shutdown().await.unwrap();
+
+    // Assert
+    let snapshots = telemetry(&transport).await;
+    assert_eq!(snapshots.len(), 2);
+    assert!(snapshots[0].tasks_processed >= 1);
+    assert!(snapshots[0].llm_tokens_last_interval >= 1200);
+    assert!(snapshots[1].tasks_processed >= snapshots[0].tasks_processed);
+    assert!(snapshots[1].uptime_seconds >= snapshots[0].uptime_seconds);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_no_telemetry_without_config() {
+    // Arrange
+    let (mut lifecycle, transport) = start(
+        test_helpers::test_config(),
+        MockLlmProvider::single_response("ok"),
+    )
+    .await;
+
+    // Act
+    tokio::time::sleep(Duration::from_secs(600)).await;
+    lifecycle.shutdown().await.unwrap();
+
+    // Assert
+    assert!(telemetry(&transport).await.is_empty());
+}
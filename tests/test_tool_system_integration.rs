@@ -65,6 +65,80 @@ async fn test_tool_initialization_with_missing_builtin() {
     assert!(matches!(result, Err(ToolError::UnknownTool(_))));
 }
 
+fn optional_tool(implementation: &str) -> ToolConfig {
+    ToolConfig::Complex {
+        implementation: implementation.to_string(),
+        config: HashMap::new(),
+        optional: true,
+    }
+}
+
+#[tokio::test]
+async fn test_optional_tool_failure_is_skipped() {
+    let mut tool_system = ToolSystem::new();
+    let mut tool_configs = HashMap::new();
+
+    tool_configs.insert(
+        "file_read".to_string(),
+        ToolConfig::Simple("builtin".to_string()),
+    );
+    tool_configs.insert("nonexistent_builtin".to_string(), optional_tool("builtin"));
+
+    tool_system.initialize(&tool_configs).await.unwrap();
+
+    assert_eq!(tool_system.list_tools(), vec!["file_read".to_string()]);
+    let degraded = tool_system.degraded_tools();
+    assert_eq!(degraded.len(), 1);
+    assert_eq!(degraded[0].name, "nonexistent_builtin");
+    assert!(degraded[0].error.contains("nonexistent_builtin"));
+}
+
+#[tokio::test]
+async fn test_required_tool_failure_still_fails_next_to_optional_one() {
+    let mut tool_system = ToolSystem::new();
+    let mut tool_configs = HashMap::new();
+
+    tool_configs.insert("optional_tool".to_string(), optional_tool("nonexistent"));
+    tool_configs.insert(
+        "required_tool".to_string(),
+        ToolConfig::Simple("nonexistent".to_string()),
+    );
+
+    let result = tool_system.initialize(&tool_configs).await;
+
+    assert!(matches!(result, Err(ToolError::UnknownImplementation(_))));
+}
+
+#[tokio::test]
+async fn test_every_required_tool_failure_is_reported() {
+    let mut tool_system = ToolSystem::new();
+    let mut tool_configs = HashMap::new();
+
+    tool_configs.insert(
+        "first_missing".to_string(),
+        ToolConfig::Simple("builtin".to_string()),
+    );
+    tool_configs.insert(
+        "second_missing".to_string(),
+        ToolConfig::Simple("nonexistent".to_string()),
+    );
+
+    let error = tool_system.initialize(&tool_configs).await.unwrap_err();
+
+    match &error {
+        ToolError::RequiredToolsFailed(failures) => {
+            let names: Vec<&str> = failures.iter().map(|(name, _)| name.as_str()).collect();
+            assert_eq!(names, vec!["first_missing", "second_missing"]);
+            assert!(matches!(failures[0].1, ToolError::UnknownTool(_)));
+            assert!(matches!(failures[1].1, ToolError::UnknownImplementation(_)));
+        }
+        other => panic!("expected RequiredToolsFailed, got {other:?}"),
+    }
+    let message = error.to_string();
+    assert!(message.starts_with("2 required tools failed to initialize"));
+    assert!(message.contains("first_missing") && message.contains("second_missing"));
+}
+
 #[tokio::test]
 async fn test_tool_initialization_with_complex_config() {
     let mut tool_system = ToolSystem::new();
@@ -78,6 +152,7 @@ async fn test_tool_initialization_with_complex_config() {
         ToolConfig::Complex {
             implementation: "builtin".to_string(),
             config: config_map,
+            optional: false,
         },
     );
 
@@ -652,6 +727,7 @@ async fn test_file_read_validates_file_size_limit() {
         ToolConfig::Complex {
             implementation: "builtin".to_string(),
             config: config_map,
+            optional: false,
         },
     );
 
@@ -684,6 +760,7 @@ async fn test_file_write_validates_content_size_limit() {
         ToolConfig::Complex {
             implementation: "builtin".to_string(),
             config: config_map,
+            optional: false,
         },
     );
 
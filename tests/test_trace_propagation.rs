@@ -0,0 +1,126 @@
+//! Integration tests for W3C trace context propagation between agents
+//!
+//! Runs tasks through the pipeline under an OpenTelemetry tracing layer and
+//! checks that a task's `traceparent` is continued into the envelopes it
+//! forwards, so spans of every agent in a workflow share one trace id.
+
+mod test_helpers;
+
+use agent2389::agent::pipeline::AgentPipeline;
+use agent2389::protocol::messages::{NextTask, TaskEnvelope, TaskEnvelopeWrapper};
+use agent2389::testing::mocks::{MockLlmProvider, MockTransport};
+use agent2389::tools::ToolSystem;
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry_sdk::trace::TracerProvider;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tracing_subscriber::prelude::*;
+
+const UPSTREAM_TRACEPARENT: &str = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+
+// ========== Test Helpers ==========
+
+fn create_pipeline() -> (AgentPipeline<MockTransport>, Arc<MockTransport>) {
+    let llm = Arc::new(MockLlmProvider::single_response("draft"));
+    let (processor, transport) =
+        test_helpers::create_processor(test_helpers::test_config(), llm, ToolSystem::new());
+    let (_task_sender, task_receiver) = mpsc::channel(1);
+    (AgentPipeline::new(processor, task_receiver, 16), transport)
+}
+
+fn create_forwarding_task(traceparent: Option<&str>) -> TaskEnvelope {
+    let mut task = test_helpers::create_task("traced-conversation", "Write a draft");
+    task.traceparent = traceparent.map(str::to_string);
+    task.next = Some(Box::new(NextTask {
+        topic: "/control/agents/editor/input".to_string(),
+        instruction: Some("Edit the draft".to_string()),
+        input: None,
+        next: None,
+    }));
+    task
+}
+
+/// Install an OpenTelemetry layer for the current thread
+fn trace_with_otel() -> tracing::subscriber::DefaultGuard {
+    let tracer = TracerProvider::builder().build().tracer("test");
+    tracing::subscriber::set_default(
+        tracing_subscriber::registry().with(tracing_opentelemetry::layer().with_tracer(tracer)),
+    )
+}
+
+fn trace_id(traceparent: &str) -> &str {
+    traceparent.split('-').nth(1).unwrap()
+}
+
+async fn forwarded_traceparent(task: TaskEnvelope) -> Option<String> {
+    let (pipeline, transport) = create_pipeline();
+    pipeline
+        .process_single_task(TaskEnvelopeWrapper::V1(task))
+        .await
+        .unwrap();
+    let forwarded = transport.published_tasks.lock().await;
+    assert_eq!(forwarded.len(), 1);
+    forwarded[0].1.traceparent.clone()
+}
+
+// ========== Propagation Tests ==========
+
+#[tokio::test]
+async fn test_forwarded_task_continues_incoming_trace() {
+    // Arrange
+    let _guard = trace_with_otel();
+    let task = create_forwarding_task(Some(UPSTREAM_TRACEPARENT));
+
+    // Act
+    let traceparent = forwarded_traceparent(task)
+        .await
+        .expect("forwarded task carries trace context");
+
+    // Assert
+    assert_eq!(trace_id(&traceparent), trace_id(UPSTREAM_TRACEPARENT));
+    assert_ne!(
+        traceparent, UPSTREAM_TRACEPARENT,
+        "the next agent's parent is this agent's span"
+    );
+}
+
+#[tokio::test]
+async fn test_untraced_task_starts_trace() {
+    // Arrange
+    let _guard = trace_with_otel();
+    let task = create_forwarding_task(None);
+
+    // Act
+    let traceparent = forwarded_traceparent(task).await;
+
+    // Assert
+    assert!(traceparent.is_some_and(|tp| trace_id(&tp) != trace_id(UPSTREAM_TRACEPARENT)));
+}
+
+#[tokio::test]
+async fn test_no_trace_context_without_export() {
+    // Arrange
+    let task = create_forwarding_task(Some(UPSTREAM_TRACEPARENT));
+
+    // Act
+    let traceparent = forwarded_traceparent(task).await;
+
+    // Assert
+    assert_eq!(traceparent, None);
+}
+
+#[test]
+fn test_traceparent_is_optional_on_the_wire() {
+    // Arrange
+    let mut task = create_forwarding_task(None);
+
+    // Act
+    let untraced = serde_json::to_value(&task).unwrap();
+    task.traceparent = Some(UPSTREAM_TRACEPARENT.to_string());
+    let traced: TaskEnvelope =
+        serde_json::from_value(serde_json::to_value(&task).unwrap()).unwrap();
+
+    // Assert
+    assert!(untraced.get("traceparent").is_none());
+    assert_eq!(traced.traceparent.as_deref(), Some(UPSTREAM_TRACEPARENT));
+}
@@ -12,11 +12,15 @@
 use agent2389::agent::discovery::AgentRegistry;
 use agent2389::agent::pipeline::pipeline_orchestrator::AgentPipeline;
 use agent2389::agent::processor::AgentProcessor;
-use agent2389::config::{AgentConfig, AgentSection, BudgetConfig, LlmSection, MqttSection};
+use agent2389::config::{
+    AgentConfig, AgentSection, BudgetConfig, LlmSection, MqttSection, OversizedResponseStrategy,
+    ResponseLimitConfig,
+};
 use agent2389::llm::provider::LlmProvider;
 use agent2389::protocol::messages::{TaskEnvelopeV2, WorkflowContext};
 use agent2389::routing::llm_router::LlmRouter;
 use agent2389::routing::Router;
+use agent2389::secrets::SecretRef;
 use agent2389::testing::mocks::{AgentDecision, MockAgentRegistry, MockLlmProvider, MockTransport};
 use agent2389::tools::ToolSystem;
 use serde_json::json;
@@ -34,24 +38,48 @@ fn create_agent_config(agent_id: &str, system_prompt: &str) -> AgentConfig {
             id: agent_id.to_string(),
             description: format!("{agent_id} agent for testing"),
             capabilities: vec![agent_id.to_string()],
+            max_input_bytes: None,
+            publish_acks: false,
+            max_concurrent_tasks: 1,
+            state_dir: None,
+            idempotency_ttl_secs: 86400,
+            max_task_retries: 0,
+            retry_base_delay_ms: 500,
+            pause_mode: Default::default(),
+            persistence: None,
+            max_task_age_secs: None,
+            idle_after_secs: None,
+            response_limit: None,
+            artifact_limits: Default::default(),
         },
         mqtt: MqttSection {
             broker_url: "mqtt://localhost:1883".to_string(),
             username_env: None,
             password_env: None,
             heartbeat_interval_secs: 900,
+            payload_format: Default::default(),
         },
         llm: LlmSection {
             provider: "openai".to_string(),
             model: "gpt-4o-mini".to_string(),
-            api_key_env: "OPENAI_API_KEY".to_string(),
+            api_key_env: SecretRef::env("OPENAI_API_KEY"),
             system_prompt: system_prompt.to_string(),
             temperature: Some(0.7),
             max_tokens: Some(2000),
+            allowed_override_models: Vec::new(),
+            prices: Default::default(),
+            prompt_caching: false,
         },
         tools: HashMap::new(),
         budget: BudgetConfig::default(),
+        processing: Default::default(),
         routing: None,
+        security: Default::default(),
+        authorization: Default::default(),
+        progress: Default::default(),
+        observability: Default::default(),
+        testing: Default::default(),
+        protocol: Default::default(),
     }
 }
 
@@ -146,8 +174,18 @@ async fn test_research_write_edit_workflow() {
             original_query: "Create article on Rust async programming".to_string(),
             steps_completed: vec![],
             iteration_count: 0,
+            started_at: None,
+            budget_secs: None,
+            state: None,
+            state_order: Vec::new(),
         }),
         routing_trace: None,
+        deadline: None,
+        correlation_id: None,
+        parent_task_id: None,
+        published_at: None,
+        traceparent: None,
+        sender_id: None,
     };
 
     let work_output = json!({"research": "Rust async traits stabilized in 1.75"});
@@ -160,7 +198,7 @@ async fn test_research_write_edit_workflow() {
     assert!(result.is_ok(), "Workflow should complete successfully");
 
     // Assert: Check that forwarding happened
-    let published = transport.get_published_messages().await;
+    let published = transport.get_published_task_envelopes().await;
     assert!(!published.is_empty(), "Should have published messages");
 
     // Assert: Verify forwarding to writer agent
@@ -249,8 +287,18 @@ async fn test_iterative_quality_refinement() {
             original_query: "Create high-quality article on Rust async".to_string(),
             steps_completed: vec![],
             iteration_count: 0,
+            started_at: None,
+            budget_secs: None,
+            state: None,
+            state_order: Vec::new(),
         }),
         routing_trace: None,
+        deadline: None,
+        correlation_id: None,
+        parent_task_id: None,
+        published_at: None,
+        traceparent: None,
+        sender_id: None,
     };
 
     let work_output = json!({"article": "Basic article about Rust"});
@@ -259,7 +307,7 @@ async fn test_iterative_quality_refinement() {
     assert!(result.is_ok(), "Iterative workflow should complete");
 
     // Verify that multiple iterations occurred
-    let published = transport.get_published_messages().await;
+    let published = transport.get_published_task_envelopes().await;
 
     let writer_tasks: Vec<_> = published
         .iter()
@@ -357,8 +405,18 @@ async fn test_max_iterations_prevents_infinite_loop() {
             original_query: "Process data".to_string(),
             steps_completed: vec![],
             iteration_count: 0,
+            started_at: None,
+            budget_secs: None,
+            state: None,
+            state_order: Vec::new(),
         }),
         routing_trace: None,
+        deadline: None,
+        correlation_id: None,
+        parent_task_id: None,
+        published_at: None,
+        traceparent: None,
+        sender_id: None,
     };
 
     let work_output = json!({"result": "iteration 1"});
@@ -371,9 +429,10 @@ async fn test_max_iterations_prevents_infinite_loop() {
 
     // Verify that some routing happened - we should have at least attempted
     // to forward to agent-b initially
-    let published = transport.get_published_messages().await;
+    let forwarded = transport.get_published_task_envelopes().await;
+    let responses = transport.get_published_responses().await;
     assert!(
-        !published.is_empty(),
+        !forwarded.is_empty() || !responses.is_empty(),
         "Should have published some messages during processing"
     );
 
@@ -426,8 +485,18 @@ async fn test_workflow_history_tracks_iterations() {
             original_query: "Multi-step workflow".to_string(),
             steps_completed: vec![],
             iteration_count: 0,
+            started_at: None,
+            budget_secs: None,
+            state: None,
+            state_order: Vec::new(),
         }),
         routing_trace: None,
+        deadline: None,
+        correlation_id: None,
+        parent_task_id: None,
+        published_at: None,
+        traceparent: None,
+        sender_id: None,
     };
 
     let work_output = json!({"step": 1});
@@ -438,12 +507,191 @@ async fn test_workflow_history_tracks_iterations() {
     // In a single-pipeline test with MockTransport, we can't verify actual
     // multi-agent forwarding because there's no real MQTT message passing.
     // What we CAN verify is that routing decisions were made.
-    let published = transport.get_published_messages().await;
+    let forwarded = transport.get_published_task_envelopes().await;
+    let responses = transport.get_published_responses().await;
 
-    // Verify that SOME messages were published (conversation messages, etc)
-    assert!(!published.is_empty(), "Should have published some messages");
+    // Verify that SOME messages were published (forwarded tasks or results)
+    assert!(
+        !forwarded.is_empty() || !responses.is_empty(),
+        "Should have published some messages"
+    );
 
     // Note: To truly test multi-agent workflows with history tracking,
     // use the v2_workflow_demo with real MQTT and multiple agent pipelines.
     // This unit test verifies that routing completes without errors.
 }
+
+#[tokio::test]
+async fn test_final_result_identifies_conversation_and_agent() {
+    let mock_llm = Arc::new(MockLlmProvider::with_agent_decisions(vec![
+        AgentDecision::complete(json!({"answer": 42})),
+    ]));
+    let registry = MockAgentRegistry::new();
+    registry.register_agent("agent-a", vec!["answer".to_string()]);
+    let router = Arc::new(LlmRouter::new(mock_llm.clone(), "gpt-4o-mini".to_string()));
+    let (pipeline, transport) = create_test_pipeline_with_router(
+        create_agent_config("agent-a", "Agent A"),
+        mock_llm,
+        router,
+        Arc::new(registry.registry().clone()),
+        10,
+    );
+
+    let task = TaskEnvelopeV2 {
+        task_id: Uuid::new_v4(),
+        conversation_id: "conv-self-describing".to_string(),
+        topic: "/control/agents/agent-a/input".to_string(),
+        instruction: Some("Answer".to_string()),
+        input: json!({}),
+        next: None,
+        version: "2.0".to_string(),
+        context: None,
+        routing_trace: None,
+        deadline: None,
+        correlation_id: None,
+        parent_task_id: None,
+        published_at: None,
+        traceparent: None,
+        sender_id: None,
+    };
+    let task_id = task.task_id;
+    let before = chrono::Utc::now();
+
+    pipeline
+        .process_with_routing(task, json!({"answer": 42}))
+        .await
+        .expect("workflow should complete");
+
+    let responses = transport.get_published_responses().await;
+    assert_eq!(responses.len(), 1);
+    let (topic_conversation, response) = &responses[0];
+    assert_eq!(topic_conversation, "conv-self-describing");
+    assert_eq!(response.task_id, task_id);
+    assert_eq!(
+        response.conversation_id.as_deref(),
+        Some("conv-self-describing")
+    );
+    assert_eq!(response.agent_id.as_deref(), Some("agent-a"));
+    let completed_at = response
+        .completed_at
+        .expect("completion time should be set");
+    assert!(completed_at >= before && completed_at <= chrono::Utc::now());
+}
+
+#[tokio::test]
+async fn test_oversized_final_result_is_truncated() {
+    let answer = "forty-two ".repeat(500);
+    let mock_llm = Arc::new(MockLlmProvider::with_agent_decisions(vec![
+        AgentDecision::complete(json!(answer.clone())),
+    ]));
+    let registry = MockAgentRegistry::new();
+    registry.register_agent("agent-a", vec!["answer".to_string()]);
+    let router = Arc::new(LlmRouter::new(mock_llm.clone(), "gpt-4o-mini".to_string()));
+    let mut config = create_agent_config("agent-a", "Agent A");
+    config.agent.response_limit = Some(ResponseLimitConfig {
+        max_response_bytes: 1024,
+        strategy: OversizedResponseStrategy::Truncate,
+        artifact_dir: None,
+    });
+    let (pipeline, transport) = create_test_pipeline_with_router(
+        config,
+        mock_llm,
+        router,
+        Arc::new(registry.registry().clone()),
+        10,
+    );
+
+    let task = TaskEnvelopeV2 {
+        task_id: Uuid::new_v4(),
+        conversation_id: "conv-oversized".to_string(),
+        topic: "/control/agents/agent-a/input".to_string(),
+        instruction: Some("Answer at length".to_string()),
+        input: json!({}),
+        next: None,
+        version: "2.0".to_string(),
+        context: None,
+        routing_trace: None,
+        deadline: None,
+        correlation_id: None,
+        parent_task_id: None,
+        published_at: None,
+        traceparent: None,
+        sender_id: None,
+    };
+
+    pipeline
+        .process_with_routing(task, json!(answer.clone()))
+        .await
+        .expect("workflow should complete");
+
+    let responses = transport.get_published_responses().await;
+    assert_eq!(responses.len(), 1);
+    let response = &responses[0].1;
+    assert!(response.response.len() <= 1024);
+    assert!(response.response.starts_with("forty-two forty-two"));
+    assert_eq!(
+        response
+            .oversized
+            .as_ref()
+            .map(|oversized| oversized.original_bytes),
+        Some(answer.len())
+    );
+}
+
+#[tokio::test]
+async fn test_final_output_artifacts_are_published_separately() {
+    let final_output = json!({
+        "summary": "Two people found",
+        "artifacts": [
+            {"name": "people", "data": [{"name": "Ada"}, {"name": "Grace"}]}
+        ]
+    });
+    let mock_llm = Arc::new(MockLlmProvider::with_agent_decisions(vec![
+        AgentDecision::complete(final_output.clone()),
+    ]));
+    let registry = MockAgentRegistry::new();
+    registry.register_agent("agent-a", vec!["search".to_string()]);
+    let router = Arc::new(LlmRouter::new(mock_llm.clone(), "gpt-4o-mini".to_string()));
+    let (pipeline, transport) = create_test_pipeline_with_router(
+        create_agent_config("agent-a", "Agent A"),
+        mock_llm,
+        router,
+        Arc::new(registry.registry().clone()),
+        10,
+    );
+
+    let task = TaskEnvelopeV2 {
+        task_id: Uuid::new_v4(),
+        conversation_id: "conv-artifacts".to_string(),
+        topic: "/control/agents/agent-a/input".to_string(),
+        instruction: Some("Find the people".to_string()),
+        input: json!({}),
+        next: None,
+        version: "2.0".to_string(),
+        context: None,
+        routing_trace: None,
+        deadline: None,
+        correlation_id: None,
+        parent_task_id: None,
+        published_at: None,
+        traceparent: None,
+        sender_id: None,
+    };
+
+    pipeline
+        .process_with_routing(task, final_output)
+        .await
+        .expect("workflow should complete");
+
+    let responses = transport.get_published_responses().await;
+    assert_eq!(responses.len(), 1);
+    let response = &responses[0].1;
+    assert!(!response.response.contains("artifacts"));
+    assert!(response.response.contains("Two people found"));
+    assert_eq!(response.artifacts.len(), 1);
+    assert_eq!(response.artifacts[0].name, "people");
+    assert_eq!(
+        response.artifacts[0].data,
+        json!([{"name": "Ada"}, {"name": "Grace"}])
+    );
+}
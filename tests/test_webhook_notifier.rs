@@ -0,0 +1,235 @@
+//! Integration tests for webhook notifications
+//!
+//! Points a webhook progress sink at a mocked HTTP endpoint and verifies
+//! that failed tasks are notified from the pipeline's error path, that 5xx
+//! answers are retried and 4xx answers are not, that the rate limit drops
+//! excess notifications, and that an unreachable webhook never affects task
+//! processing.
+
+mod test_helpers;
+
+use agent2389::agent::pipeline::AgentPipeline;
+use agent2389::agent::processor::AgentProcessor;
+use agent2389::config::WebhookSinkConfig;
+use agent2389::progress::{NotificationSeverity, Progress, ProgressEventType, WebhookProgress};
+use agent2389::protocol::messages::{TaskEnvelope, TaskEnvelopeWrapper};
+use agent2389::testing::mocks::{MockLlmProvider, MockTransport};
+use agent2389::tools::ToolSystem;
+use serde_json::Value;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+// ========== Test Helpers ==========
+
+fn webhook_config() -> WebhookSinkConfig {
+    let mut config: WebhookSinkConfig =
+        toml::from_str(r#"url_env = "UNUSED_WEBHOOK_URL""#).unwrap();
+    config.retry_delay_ms = 10;
+    config
+}
+
+async fn mount(server: &MockServer, status: u16) {
+    Mock::given(method("POST"))
+        .and(path("/hook"))
+        .respond_with(ResponseTemplate::new(status))
+        .mount(server)
+        .await;
+}
+
+fn webhook(server: &MockServer, config: &WebhookSinkConfig) -> Arc<WebhookProgress> {
+    Arc::new(WebhookProgress::spawn(
+        "test-agent".to_string(),
+        format!("{}/hook", server.uri()),
+        config,
+    ))
+}
+
+/// Payloads the server received once `count` have arrived
+async fn wait_for_payloads(server: &MockServer, count: usize) -> Vec<Value> {
+    for _ in 0..200 {
+        let requests = server.received_requests().await.unwrap();
+        if requests.len() >= count {
+            return requests
+                .iter()
+                .map(|request| serde_json::from_slice(&request.body).unwrap())
+                .collect();
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("webhook received fewer than {count} requests");
+}
+
+/// Run `tasks` through the pipeline's run loop, whose error path reports failures
+async fn run_tasks(processor: AgentProcessor<MockTransport>, tasks: Vec<TaskEnvelope>) {
+    let (task_sender, task_receiver) = mpsc::channel(tasks.len().max(1));
+    let mut pipeline = AgentPipeline::new(processor, task_receiver, 16);
+    pipeline.start().await.unwrap();
+    for task in tasks {
+        task_sender
+            .send(TaskEnvelopeWrapper::V1(task))
+            .await
+            .unwrap();
+    }
+    drop(task_sender);
+    // Failed tasks don't stop the pipeline; it ends when the channel closes
+    let _ = tokio::time::timeout(Duration::from_secs(5), pipeline.run())
+        .await
+        .unwrap();
+}
+
+async fn report_errors(progress: &WebhookProgress, count: usize) {
+    for i in 0..count {
+        progress
+            .report_task_error(Some(&format!("task-{i}")), Some("conv"), "LLM unavailable")
+            .await;
+    }
+}
+
+// ========== Pipeline Error Path Tests ==========
+
+#[tokio::test]
+async fn test_failed_task_is_notified_from_the_pipeline() {
+    let server = MockServer::start().await;
+    mount(&server, 200).await;
+    // The failed LLM call is an error event too; only the task's failure is asked for
+    let mut config = webhook_config();
+    config.event_types = vec![ProgressEventType::TaskError];
+    let processor = AgentProcessor::with_progress(
+        test_helpers::test_config(),
+        Arc::new(MockLlmProvider::with_failure()),
+        Arc::new(ToolSystem::new()),
+        Arc::new(MockTransport::new()),
+        webhook(&server, &config),
+    );
+    let task = test_helpers::create_task("webhook-conversation", "Summarize the report");
+    let task_id = task.task_id;
+
+    run_tasks(processor, vec![task]).await;
+
+    let payloads = wait_for_payloads(&server, 1).await;
+    assert_eq!(payloads.len(), 1);
+    let text = payloads[0]["text"].as_str().unwrap();
+    assert!(text.starts_with("[error] test-agent TaskError: "));
+    assert!(text.contains("conversation webhook-conversation"));
+    assert!(text.contains(&format!("task {task_id}")));
+}
+
+#[tokio::test]
+async fn test_completed_task_is_notified_with_a_custom_template() {
+    let server = MockServer::start().await;
+    mount(&server, 200).await;
+    let mut config = webhook_config();
+    config.event_types = vec![ProgressEventType::TaskComplete];
+    config.min_severity = NotificationSeverity::Info;
+    config.template = Some(r#"{"done": "{{conversation_id}}", "by": "{{agent_id}}"}"#.to_string());
+    let processor = AgentProcessor::with_progress(
+        test_helpers::test_config(),
+        Arc::new(MockLlmProvider::single_response("All done")),
+        Arc::new(ToolSystem::new()),
+        Arc::new(MockTransport::new()),
+        webhook(&server, &config),
+    );
+
+    run_tasks(
+        processor,
+        vec![test_helpers::create_task(
+            "priority-conversation",
+            "Ship it",
+        )],
+    )
+    .await;
+
+    let payloads = wait_for_payloads(&server, 1).await;
+    assert_eq!(
+        payloads,
+        vec![serde_json::json!({"done": "priority-conversation", "by": "test-agent"})]
+    );
+}
+
+// ========== Delivery Tests ==========
+
+#[tokio::test]
+async fn test_server_errors_are_retried() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/hook"))
+        .respond_with(ResponseTemplate::new(503))
+        .up_to_n_times(2)
+        .mount(&server)
+        .await;
+    mount(&server, 200).await;
+    let progress = webhook(&server, &webhook_config());
+
+    report_errors(&progress, 1).await;
+
+    // Two failed attempts, then the delivered one
+    let payloads = wait_for_payloads(&server, 3).await;
+    assert_eq!(payloads[0], payloads[2]);
+}
+
+#[tokio::test]
+async fn test_client_errors_are_not_retried() {
+    let server = MockServer::start().await;
+    mount(&server, 400).await;
+    let progress = webhook(&server, &webhook_config());
+
+    report_errors(&progress, 1).await;
+    wait_for_payloads(&server, 1).await;
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    assert_eq!(server.received_requests().await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_rate_limit_drops_excess_notifications() {
+    let server = MockServer::start().await;
+    mount(&server, 200).await;
+    let mut config = webhook_config();
+    config.max_per_minute = 3;
+    let progress = webhook(&server, &config);
+
+    report_errors(&progress, 5).await;
+    let payloads = wait_for_payloads(&server, 3).await;
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    assert_eq!(server.received_requests().await.unwrap().len(), 3);
+    let texts: Vec<&str> = payloads
+        .iter()
+        .map(|payload| payload["text"].as_str().unwrap())
+        .collect();
+    for (i, text) in texts.iter().enumerate() {
+        assert!(text.ends_with(&format!("task task-{i})")));
+    }
+}
+
+#[tokio::test]
+async fn test_unreachable_webhook_does_not_affect_tasks() {
+    let mut config = webhook_config();
+    config.max_retries = 5;
+    config.retry_delay_ms = 1000;
+    // Nothing listens on the discard port
+    let progress = Arc::new(WebhookProgress::spawn(
+        "test-agent".to_string(),
+        "http://127.0.0.1:9/hook".to_string(),
+        &config,
+    ));
+    let processor = AgentProcessor::with_progress(
+        test_helpers::test_config(),
+        Arc::new(MockLlmProvider::with_failure()),
+        Arc::new(ToolSystem::new()),
+        Arc::new(MockTransport::new()),
+        progress,
+    );
+    let tasks = (0..3)
+        .map(|i| test_helpers::create_task(&format!("conv-{i}"), "Summarize"))
+        .collect();
+
+    let started = std::time::Instant::now();
+    run_tasks(processor, tasks).await;
+
+    // Deliveries retry in the background without holding up the tasks
+    assert!(started.elapsed() < Duration::from_secs(1));
+}
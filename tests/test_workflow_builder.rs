@@ -0,0 +1,109 @@
+//! Integration tests for starting v2.0 workflows with `WorkflowBuilder`
+//!
+//! Verifies that a submitted workflow reaches the target agent's input topic
+//! as a v2.0 envelope with a workflow context, that an agent processes it
+//! like any other task, and that transport failures are surfaced.
+
+mod test_helpers;
+
+use agent2389::agent::pipeline::AgentPipeline;
+use agent2389::agent::workflow::{WorkflowBuilder, WorkflowError};
+use agent2389::protocol::messages::TaskEnvelopeWrapper;
+use agent2389::testing::mocks::{MockLlmProvider, MockTransport};
+use agent2389::tools::ToolSystem;
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+// ========== Workflow Submission Tests ==========
+
+#[tokio::test]
+async fn test_submit_publishes_v2_task_to_agent_input() {
+    // Arrange
+    let transport = MockTransport::new();
+
+    // Act
+    let task = WorkflowBuilder::new("Compare the three vendor proposals")
+        .for_agent("analyst")
+        .with_input(json!({"vendors": ["a", "b", "c"]}))
+        .with_budget(Duration::from_secs(900))
+        .with_correlation_id("procurement-42")
+        .submit(&transport)
+        .await
+        .unwrap();
+
+    // Assert
+    let published = transport.get_published_task_envelopes().await;
+    assert_eq!(published.len(), 1);
+    let (topic, envelope) = &published[0];
+    assert_eq!(topic, "/control/agents/analyst/input");
+    let TaskEnvelopeWrapper::V2(envelope) = envelope else {
+        panic!("workflow should start with a v2.0 envelope: {envelope:?}");
+    };
+    assert_eq!(envelope.task_id, task.task_id);
+    assert_eq!(envelope.conversation_id, task.conversation_id);
+    assert_eq!(envelope.correlation_id.as_deref(), Some("procurement-42"));
+    assert!(envelope.published_at.is_some());
+    let context = envelope.context.as_ref().unwrap();
+    assert_eq!(context.original_query, "Compare the three vendor proposals");
+    assert_eq!(context.budget_secs, Some(900));
+}
+
+#[tokio::test]
+async fn test_submitted_workflow_is_processed_by_target_agent() {
+    // Arrange: submit, then hand the published envelope to an agent
+    let submitter = MockTransport::new();
+    let task = WorkflowBuilder::new("Say hello")
+        .for_agent("test-agent")
+        .with_conversation_id("workflow-conversation")
+        .submit(&submitter)
+        .await
+        .unwrap();
+    let (_, envelope) = submitter.get_published_task_envelopes().await.remove(0);
+
+    let llm = Arc::new(MockLlmProvider::single_response("hello"));
+    let (processor, transport) =
+        test_helpers::create_processor(test_helpers::test_config(), llm, ToolSystem::new());
+    let (_task_sender, task_receiver) = mpsc::channel(1);
+    let pipeline = AgentPipeline::new(processor, task_receiver, 16);
+
+    // Act
+    let result = pipeline.process_single_task(envelope).await;
+
+    // Assert
+    assert!(
+        result.is_ok(),
+        "workflow task should be processed: {result:?}"
+    );
+    assert!(transport.get_published_errors().await.is_empty());
+    let responses = transport.get_published_responses().await;
+    assert_eq!(responses.len(), 1);
+    assert_eq!(responses[0].0, "workflow-conversation");
+    assert_eq!(responses[0].1.task_id, task.task_id);
+}
+
+#[tokio::test]
+async fn test_submit_reports_publish_failure() {
+    let transport = MockTransport::with_failure();
+
+    let result = WorkflowBuilder::new("Anything")
+        .to_topic("/control/agents/analyst/input")
+        .submit(&transport)
+        .await;
+
+    assert!(matches!(result, Err(WorkflowError::PublishFailed(_))));
+}
+
+#[tokio::test]
+async fn test_invalid_workflow_is_not_published() {
+    let transport = MockTransport::new();
+
+    let result = WorkflowBuilder::new("Anything")
+        .to_topic("/control/agents/analyst/status")
+        .submit(&transport)
+        .await;
+
+    assert!(matches!(result, Err(WorkflowError::MalformedTopic(_))));
+    assert!(transport.get_published_task_envelopes().await.is_empty());
+}
@@ -0,0 +1,279 @@
+//! Integration tests for workflow state shared across hops
+//!
+//! Forwards a v2 task through two agents whose decisions carry
+//! `state_updates`, and verifies that the updates are merged into the
+//! forwarded context, shown to the next agent's LLM, capped in size with the
+//! oldest keys evicted, and carried by router-driven forwards too.
+
+mod test_helpers;
+
+use agent2389::agent::discovery::AgentRegistry;
+use agent2389::agent::pipeline::AgentPipeline;
+use agent2389::agent::processor::AgentProcessor;
+use agent2389::error::AgentError;
+use agent2389::processing::nine_step::NineStepProcessor;
+use agent2389::protocol::messages::{TaskEnvelopeV2, TaskEnvelopeWrapper, WorkflowContext};
+use agent2389::protocol::MAX_WORKFLOW_STATE_BYTES;
+use agent2389::routing::agent_selector::RoutingHelper;
+use agent2389::routing::{Router, RoutingDecision};
+use agent2389::testing::mocks::{MockAgentRegistry, MockLlmProvider, MockTransport};
+use agent2389::tools::ToolSystem;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+// ========== Test Helpers ==========
+
+fn registry() -> MockAgentRegistry {
+    let registry = MockAgentRegistry::new();
+    for agent in ["agent-b", "agent-c"] {
+        registry.register_agent(agent, vec![agent.to_string()]);
+    }
+    registry
+}
+
+/// An agent whose LLM answers with `decision`
+fn create_agent(
+    agent_id: &str,
+    decision: Value,
+) -> (NineStepProcessor<MockTransport>, Arc<MockLlmProvider>) {
+    let mut config = test_helpers::test_config();
+    config.agent.id = agent_id.to_string();
+    let llm = Arc::new(MockLlmProvider::single_response(decision.to_string()));
+    let processor = NineStepProcessor::new_with_routing(
+        config,
+        llm.clone(),
+        Arc::new(ToolSystem::new()),
+        Arc::new(MockTransport::new()),
+        RoutingHelper::new(),
+        registry().registry().clone(),
+    );
+    (processor, llm)
+}
+
+/// Decision forwarding to `next_agent` with `state_updates`
+fn forward_decision(next_agent: &str, state_updates: Value) -> Value {
+    json!({
+        "schema_version": "1.0",
+        "result": "Done",
+        "next_agent": next_agent,
+        "next_instruction": "Carry on",
+        "workflow_complete": false,
+        "state_updates": state_updates
+    })
+}
+
+fn create_task(context: WorkflowContext) -> TaskEnvelopeV2 {
+    TaskEnvelopeV2 {
+        task_id: Uuid::new_v4(),
+        conversation_id: "state-conversation".to_string(),
+        topic: "/control/agents/agent-a/input".to_string(),
+        instruction: Some("Research the topic".to_string()),
+        input: json!({"topic": "protocols"}),
+        next: None,
+        version: "2.0".to_string(),
+        context: Some(context),
+        routing_trace: Some(vec![]),
+        deadline: None,
+        correlation_id: None,
+        parent_task_id: None,
+        published_at: None,
+        traceparent: None,
+        sender_id: None,
+    }
+}
+
+fn empty_context() -> WorkflowContext {
+    WorkflowContext {
+        original_query: "Research the topic".to_string(),
+        steps_completed: vec![],
+        iteration_count: 0,
+        started_at: None,
+        budget_secs: None,
+        state: None,
+        state_order: Vec::new(),
+    }
+}
+
+/// Run `task` through `processor`, returning the task it forwarded
+async fn forward(
+    processor: &NineStepProcessor<MockTransport>,
+    task: TaskEnvelopeV2,
+) -> TaskEnvelopeV2 {
+    let topic = task.topic.clone();
+    let result = processor
+        .process_task(TaskEnvelopeWrapper::V2(task), &topic, false)
+        .await
+        .unwrap();
+    assert!(result.forwarded);
+    let mut published = processor.transport.get_published_task_envelopes().await;
+    assert_eq!(published.len(), 1);
+    published.pop().unwrap().1.to_v2()
+}
+
+fn state_of(task: &TaskEnvelopeV2) -> (Value, Vec<String>) {
+    let context = task.context.as_ref().expect("context forwarded");
+    (
+        context.state.clone().map_or(Value::Null, Value::Object),
+        context.state_order.clone(),
+    )
+}
+
+// ========== Workflow State Tests ==========
+
+#[tokio::test]
+async fn test_state_propagates_and_merges_across_two_hops() {
+    // Arrange
+    let (agent_a, _) = create_agent(
+        "agent-a",
+        forward_decision(
+            "agent-b",
+            json!({"language": "fr", "citations": ["RFC 2389"]}),
+        ),
+    );
+    let (agent_b, _) = create_agent(
+        "agent-b",
+        forward_decision(
+            "agent-c",
+            json!({"citations": ["RFC 2389", "RFC 7230"], "language": null, "tone": "formal"}),
+        ),
+    );
+
+    // Act
+    let to_b = forward(&agent_a, create_task(empty_context())).await;
+    let to_c = forward(&agent_b, to_b.clone()).await;
+
+    // Assert: the first hop carries agent A's state
+    let (state, _) = state_of(&to_b);
+    assert_eq!(state, json!({"language": "fr", "citations": ["RFC 2389"]}));
+
+    // the second hop merges agent B's updates over it
+    let (state, order) = state_of(&to_c);
+    assert_eq!(
+        state,
+        json!({"citations": ["RFC 2389", "RFC 7230"], "tone": "formal"})
+    );
+    assert_eq!(order, vec!["citations", "tone"]);
+    assert_eq!(to_c.context.as_ref().unwrap().iteration_count, 2);
+}
+
+#[tokio::test]
+async fn test_next_agent_sees_the_state_in_its_system_prompt() {
+    // Arrange
+    let (agent_a, _) = create_agent(
+        "agent-a",
+        forward_decision("agent-b", json!({"language": "fr"})),
+    );
+    let (agent_b, agent_b_llm) = create_agent("agent-b", forward_decision("agent-c", json!({})));
+    let (stateless_agent, stateless_llm) =
+        create_agent("agent-a", forward_decision("agent-b", json!({})));
+
+    // Act
+    let to_b = forward(&agent_a, create_task(empty_context())).await;
+    forward(&agent_b, to_b).await;
+    forward(&stateless_agent, create_task(empty_context())).await;
+
+    // Assert
+    let system = &agent_b_llm.requests()[0].messages[0].content;
+    assert!(system.contains("Workflow state"), "{system}");
+    assert!(system.contains(r#""language": "fr""#), "{system}");
+    assert!(system.contains("state_updates"), "{system}");
+    // Without state there is no section
+    let system = &stateless_llm.requests()[0].messages[0].content;
+    assert!(!system.contains("Workflow state"), "{system}");
+}
+
+#[tokio::test]
+async fn test_state_over_the_cap_evicts_the_oldest_keys() {
+    // Arrange: the state is near the cap, oldest key first
+    let mut context = empty_context();
+    context.merge_state(
+        json!({"oldest": "a".repeat(MAX_WORKFLOW_STATE_BYTES / 2)})
+            .as_object()
+            .unwrap(),
+    );
+    context.merge_state(
+        json!({"older": "b".repeat(MAX_WORKFLOW_STATE_BYTES / 3)})
+            .as_object()
+            .unwrap(),
+    );
+    let (agent_a, _) = create_agent(
+        "agent-a",
+        forward_decision(
+            "agent-b",
+            json!({"newest": "c".repeat(MAX_WORKFLOW_STATE_BYTES / 3)}),
+        ),
+    );
+
+    // Act
+    let to_b = forward(&agent_a, create_task(context)).await;
+
+    // Assert
+    let (state, order) = state_of(&to_b);
+    assert_eq!(order, vec!["older", "newest"]);
+    assert!(state.get("oldest").is_none());
+    assert!(serde_json::to_vec(&state).unwrap().len() <= MAX_WORKFLOW_STATE_BYTES);
+}
+
+/// Router that forwards every task to agent B
+struct ForwardRouter;
+
+#[async_trait::async_trait]
+impl Router for ForwardRouter {
+    async fn decide_next_step(
+        &self,
+        _task: &TaskEnvelopeV2,
+        work_output: &Value,
+        _registry: &AgentRegistry,
+    ) -> Result<RoutingDecision, AgentError> {
+        Ok(RoutingDecision::Forward {
+            next_agent: "agent-b".to_string(),
+            next_instruction: "Carry on".to_string(),
+            forwarded_data: work_output["result"].clone(),
+            sticky: false,
+        })
+    }
+}
+
+#[tokio::test]
+async fn test_router_forward_merges_the_agents_state_updates() {
+    // Arrange: the incoming context already holds state from an earlier hop
+    let mut context = empty_context();
+    context.merge_state(json!({"language": "fr"}).as_object().unwrap());
+    let transport = Arc::new(MockTransport::new());
+    let processor = AgentProcessor::new(
+        test_helpers::test_config(),
+        Arc::new(MockLlmProvider::single_response("unused")),
+        Arc::new(ToolSystem::new()),
+        transport.clone(),
+    );
+    let (_task_sender, task_receiver) = mpsc::channel(1);
+    let pipeline = AgentPipeline::with_router(
+        processor,
+        task_receiver,
+        16,
+        Arc::new(ForwardRouter),
+        Arc::new(registry().registry().clone()),
+        10,
+    );
+    let work_output = json!({
+        "schema_version": "1.0",
+        "result": "Done",
+        "workflow_complete": false,
+        "state_updates": {"citations": ["RFC 2389"]}
+    });
+
+    // Act
+    pipeline
+        .process_with_routing(create_task(context), work_output)
+        .await
+        .unwrap();
+
+    // Assert
+    let mut published = transport.get_published_task_envelopes().await;
+    assert_eq!(published.len(), 1);
+    let (state, order) = state_of(&published.pop().unwrap().1.to_v2());
+    assert_eq!(state, json!({"language": "fr", "citations": ["RFC 2389"]}));
+    assert_eq!(order, vec!["language", "citations"]);
+}